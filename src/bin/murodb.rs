@@ -8,11 +8,13 @@ use murodb::{
     Database, DatabaseEncryption, ExecResult, MuroError, QueryCancelHandle, RecoveryMode,
     SqlStatementClass, Value,
 };
+use zeroize::Zeroizing;
 
 #[derive(Clone, Debug, ValueEnum)]
 enum RecoveryModeArg {
     Strict,
     Permissive,
+    Salvage,
 }
 
 #[derive(Clone, Debug, ValueEnum)]
@@ -21,6 +23,12 @@ enum OutputFormatArg {
     Json,
 }
 
+#[derive(Clone, Copy, Debug, ValueEnum, PartialEq, Eq)]
+enum UuidFormatArg {
+    Text,
+    Raw,
+}
+
 #[derive(Clone, Debug, ValueEnum, PartialEq, Eq)]
 enum EncryptionModeArg {
     Aes256GcmSiv,
@@ -32,6 +40,7 @@ impl From<RecoveryModeArg> for RecoveryMode {
         match value {
             RecoveryModeArg::Strict => RecoveryMode::Strict,
             RecoveryModeArg::Permissive => RecoveryMode::Permissive,
+            RecoveryModeArg::Salvage => RecoveryMode::Salvage,
         }
     }
 }
@@ -77,6 +86,7 @@ struct Cli {
     ///
     /// `strict` aborts on malformed WAL records.
     /// `permissive` skips malformed records where recovery can continue.
+    /// `salvage` also reads past damaged frames to the next readable one.
     #[arg(long, value_enum, default_value = "strict")]
     recovery_mode: RecoveryModeArg,
 
@@ -86,6 +96,13 @@ struct Cli {
     #[arg(long, value_enum, default_value = "text")]
     format: OutputFormatArg,
 
+    /// How UUID values are rendered.
+    ///
+    /// `text` prints the lowercase hyphenated form; `raw` prints the 16 bytes
+    /// like VARBINARY (hex in text output, base64 in JSON).
+    #[arg(long, value_enum, default_value = "text")]
+    uuid_format: UuidFormatArg,
+
     /// Lock wait timeout in milliseconds.
     ///
     /// `0` means wait indefinitely.
@@ -99,17 +116,21 @@ struct Cli {
     statement_timeout_ms: u64,
 }
 
-fn get_password(cli_password: &Option<String>) -> String {
+/// The password, zeroed when dropped. `--password` is moved out of the
+/// parsed arguments rather than copied.
+fn get_password(cli_password: Option<String>) -> Zeroizing<String> {
     if let Some(pw) = cli_password {
         eprintln!(
             "WARNING: Passing passwords via --password can expose secrets in shell history and process lists. Prefer interactive prompt when possible."
         );
-        return pw.clone();
+        return Zeroizing::new(pw);
     }
-    rpassword::read_password_from_tty(Some("Password: ")).unwrap_or_else(|e| {
-        eprintln!("ERROR: Failed to read password: {}", e);
-        process::exit(1);
-    })
+    Zeroizing::new(
+        rpassword::read_password_from_tty(Some("Password: ")).unwrap_or_else(|e| {
+            eprintln!("ERROR: Failed to read password: {}", e);
+            process::exit(1);
+        }),
+    )
 }
 
 fn format_rows(result: &ExecResult) -> String {
@@ -203,9 +224,9 @@ fn format_value_json(val: &Value) -> String {
         Value::Integer(n) => n.to_string(),
         Value::Float(n) => {
             if n.is_finite() {
-                n.to_string()
+                murodb::format_float(*n)
             } else {
-                format!("\"{}\"", json_escape(&n.to_string()))
+                format!("\"{}\"", json_escape(&murodb::format_float(*n)))
             }
         }
         Value::Date(n) => format!("\"{}\"", json_escape(&murodb::format_date(*n))),
@@ -278,10 +299,26 @@ fn format_error_json(msg: &str) -> String {
     )
 }
 
+fn apply_uuid_format(result: ExecResult, uuid_format: UuidFormatArg) -> ExecResult {
+    match (result, uuid_format) {
+        (ExecResult::Rows(mut rows), UuidFormatArg::Raw) => {
+            for row in &mut rows {
+                for (_, val) in &mut row.values {
+                    if let Value::Uuid(b) = val {
+                        *val = Value::Varbinary(b.to_vec());
+                    }
+                }
+            }
+            ExecResult::Rows(rows)
+        }
+        (result, _) => result,
+    }
+}
+
 fn format_value(val: &Value) -> String {
     match val {
         Value::Integer(n) => n.to_string(),
-        Value::Float(n) => n.to_string(),
+        Value::Float(n) => murodb::format_float(*n),
         Value::Date(n) => murodb::format_date(*n),
         Value::DateTime(n) => murodb::format_datetime(*n),
         Value::Timestamp(n) => murodb::format_datetime(*n),
@@ -339,6 +376,7 @@ fn execute_sql(
     db: &mut Database,
     sql: &str,
     format: &OutputFormatArg,
+    uuid_format: UuidFormatArg,
     in_explicit_tx: &mut bool,
     interrupts: &InterruptController,
 ) {
@@ -373,7 +411,7 @@ fn execute_sql(
     };
     interrupts.end_statement();
 
-    match result {
+    match result.map(|result| apply_uuid_format(result, uuid_format)) {
         Ok(result) => match format {
             OutputFormatArg::Text => {
                 if matches!(class, SqlStatementClass::Begin) {
@@ -405,7 +443,12 @@ fn execute_sql(
     }
 }
 
-fn run_repl(db: &mut Database, format: &OutputFormatArg, interrupts: &InterruptController) {
+fn run_repl(
+    db: &mut Database,
+    format: &OutputFormatArg,
+    uuid_format: UuidFormatArg,
+    interrupts: &InterruptController,
+) {
     let mut rl = rustyline::DefaultEditor::new().unwrap_or_else(|e| {
         eprintln!("ERROR: Failed to initialize REPL: {}", e);
         process::exit(1);
@@ -438,7 +481,14 @@ fn run_repl(db: &mut Database, format: &OutputFormatArg, interrupts: &InterruptC
                 if buffer.trim_end().ends_with(';') {
                     let sql = buffer.trim().to_string();
                     let _ = rl.add_history_entry(&sql);
-                    execute_sql(db, &sql, format, &mut in_explicit_tx, interrupts);
+                    execute_sql(
+                        db,
+                        &sql,
+                        format,
+                        uuid_format,
+                        &mut in_explicit_tx,
+                        interrupts,
+                    );
                     buffer.clear();
                 }
             }
@@ -464,7 +514,7 @@ fn run_repl(db: &mut Database, format: &OutputFormatArg, interrupts: &InterruptC
 }
 
 fn main() {
-    let cli = Cli::parse();
+    let mut cli = Cli::parse();
 
     let recovery_mode: RecoveryMode = cli.recovery_mode.clone().into();
 
@@ -479,7 +529,7 @@ fn main() {
         }
         match cli.encryption {
             EncryptionModeArg::Aes256GcmSiv => {
-                let password = get_password(&cli.password);
+                let password = get_password(cli.password.take());
                 Database::create_with_password(db_path, &password).unwrap_or_else(|e| {
                     eprintln!("ERROR: Failed to create database: {}", e);
                     process::exit(1);
@@ -524,7 +574,7 @@ fn main() {
         }
         let (db, report) = match detected_mode {
             DatabaseEncryption::Encrypted => {
-                let password = get_password(&cli.password);
+                let password = get_password(cli.password.take());
                 Database::open_with_password_and_recovery_mode_and_report(
                     db_path,
                     &password,
@@ -539,12 +589,14 @@ fn main() {
             eprintln!("ERROR: Failed to open database: {}", e);
             process::exit(1);
         });
-        if recovery_mode == RecoveryMode::Permissive {
+        if recovery_mode != RecoveryMode::Strict {
             if let Some(report) = &report {
-                if !report.skipped.is_empty() {
+                if report.lost_committed_data() {
                     eprintln!(
-                        "WARNING: permissive recovery skipped {} malformed transaction(s)",
-                        report.skipped.len()
+                        "WARNING: {} recovery skipped {} malformed and {} incomplete transaction(s)",
+                        recovery_mode.as_str(),
+                        report.skipped.len(),
+                        report.abandoned_txids.len()
                     );
                     if let Some(path) = &report.wal_quarantine_path {
                         eprintln!("  - quarantined WAL: {}", path);
@@ -552,6 +604,12 @@ fn main() {
                     for skipped in &report.skipped {
                         eprintln!("  - txid {}: {}", skipped.txid, skipped.reason);
                     }
+                    for txid in &report.abandoned_txids {
+                        eprintln!("  - txid {}: frames unreadable, not replayed", txid);
+                    }
+                    if let Some(offset) = report.first_corruption_offset {
+                        eprintln!("  - first unreadable frame at WAL offset {}", offset);
+                    }
                 }
             }
         }
@@ -566,13 +624,20 @@ fn main() {
 
     if let Some(sql) = &cli.execute {
         let mut in_explicit_tx = false;
-        execute_sql(&mut db, sql, &cli.format, &mut in_explicit_tx, &interrupts);
+        execute_sql(
+            &mut db,
+            sql,
+            &cli.format,
+            cli.uuid_format,
+            &mut in_explicit_tx,
+            &interrupts,
+        );
         if let Err(e) = db.flush() {
             eprintln!("ERROR: Failed to flush database: {}", e);
             process::exit(1);
         }
     } else {
-        run_repl(&mut db, &cli.format, &interrupts);
+        run_repl(&mut db, &cli.format, cli.uuid_format, &interrupts);
     }
 }
 
@@ -641,4 +706,19 @@ mod tests {
             "2024-02-03 11:22:33"
         );
     }
+
+    #[test]
+    fn uuid_format_raw_renders_bytes() {
+        let uuid = [0xabu8; 16];
+        let rows = || {
+            ExecResult::Rows(vec![Row {
+                values: vec![("id".to_string(), Value::Uuid(uuid))],
+            }])
+        };
+        let text = format_rows_json(&apply_uuid_format(rows(), UuidFormatArg::Text));
+        assert!(text.contains(&format!("\"{}\"", murodb::format_uuid(&uuid))));
+        let raw = apply_uuid_format(rows(), UuidFormatArg::Raw);
+        assert!(format_rows(&raw).contains(&format!("0x{}", "ab".repeat(16))));
+        assert!(format_rows_json(&raw).contains(&format!("\"{}\"", base64_encode(&uuid))));
+    }
 }
@@ -1,8 +1,8 @@
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
 
 use clap::{value_parser, Parser};
-use murodb::{Database, MasterKey};
+use murodb::{Database, MasterKey, Value};
 use rand::rngs::StdRng;
 use rand::{Rng, SeedableRng};
 
@@ -10,7 +10,7 @@ use rand::{Rng, SeedableRng};
 #[command(
     name = "murodb-bench",
     about = "Embedded DB benchmark for typical OLTP-style workloads",
-    long_about = "Run deterministic micro-benchmarks against a temporary MuroDB database.\n\nThe benchmark currently covers:\n- point selects and updates on a primary-key table (`kv`)\n- batched inserts\n- range scans\n- mixed read/write workloads\n- full-text search (FTS) point-select/update/mixed workloads\n\nResults include throughput and latency percentiles (p50/p95/p99) per scenario.\n\nThis is intended for local performance profiling and regression checks.",
+    long_about = "Run deterministic micro-benchmarks against a temporary MuroDB database.\n\nThe benchmark currently covers:\n- point selects and updates on a primary-key table (`kv`)\n- batched inserts\n- row-by-row vs bulk loading into an empty table\n- multi-row inserts into a table with three unique indexes\n- range scans\n- mixed read/write workloads\n- full-scan filters with and without predicate reordering\n- full-scan filters on a computed expression, with and without projecting it\n- a selective COUNT(*) over a wide table, against one that reads every column\n- full-text search (FTS) point-select/update/mixed workloads\n- multi-page commits with WAL frames written one by one vs batched\n- same-key backups vs backups re-encrypted under a new password\n\nResults include throughput and latency percentiles (p50/p95/p99) per scenario.\n\nThis is intended for local performance profiling and regression checks.",
     after_long_help = "Examples:\n  murodb_bench\n  murodb_bench --initial-rows 50000 --batch-size 1000\n  murodb_bench --select-ops 100000 --mixed-ops 50000\n\nDocumentation:\n  https://tokuhirom.github.io/murodb/"
 )]
 struct Cli {
@@ -54,6 +54,54 @@ struct Cli {
     #[arg(long, default_value_t = 5_000)]
     fts_mixed_ops: u64,
 
+    /// Number of full-scan filter operations (`LIKE ... AND v1 = ?`), run
+    /// once with predicate reordering off and once with it on.
+    #[arg(long, default_value_t = 50)]
+    filter_ops: u64,
+
+    /// Number of full scans filtering on a computed expression, run once
+    /// selecting only `id` and once also selecting the expression.
+    #[arg(long, default_value_t = 50)]
+    expr_ops: u64,
+
+    /// Number of rows in `wide`, a table of 24 VARCHAR columns and a status.
+    #[arg(long, default_value_t = 5_000, value_parser = value_parser!(u64).range(1..))]
+    wide_rows: u64,
+
+    /// Number of `COUNT(*) ... WHERE status = ?` scans of `wide`, run once
+    /// as is and once also reading every VARCHAR column.
+    #[arg(long, default_value_t = 50)]
+    wide_ops: u64,
+
+    /// Number of rows loaded into a fresh table row by row and again with
+    /// `Database::bulk_insert`, to compare the two load paths.
+    #[arg(long, default_value_t = 20_000, value_parser = value_parser!(u64).range(1..))]
+    load_rows: u64,
+
+    /// Number of rows inserted, `batch_size` per multi-row INSERT, into a
+    /// table with three unique indexes that already holds a row.
+    #[arg(long, default_value_t = 100_000, value_parser = value_parser!(u64).range(1..))]
+    unique_insert_rows: u64,
+
+    /// Number of transactions that each rewrite `commit_pages` rows of about
+    /// a page each, run once writing WAL frames one by one and once batched.
+    #[arg(long, default_value_t = 200)]
+    commit_ops: u64,
+
+    /// Rows (and so pages) each of those transactions rewrites.
+    #[arg(long, default_value_t = 40, value_parser = value_parser!(u64).range(1..))]
+    commit_pages: u64,
+
+    /// Directory for the multi-page commit database. Point it at a slow
+    /// filesystem (a network or FUSE mount) to see what each WAL write costs.
+    #[arg(long, default_value = "/tmp")]
+    commit_dir: PathBuf,
+
+    /// Number of backups taken with `Database::backup` and again with
+    /// `Database::backup_with_new_password`, after the other workloads.
+    #[arg(long, default_value_t = 5)]
+    backup_ops: u64,
+
     /// Number of warmup point-select operations before measurements.
     #[arg(long, default_value_t = 200)]
     warmup_ops: u64,
@@ -185,6 +233,222 @@ fn populate_fts_docs(db: &mut Database, initial_rows: u64, batch_size: u64) {
     }
 }
 
+/// Number of VARCHAR columns in `wide`.
+const WIDE_COLUMNS: usize = 24;
+
+/// Create `wide` with `rows` rows; every tenth has status 'x'.
+fn populate_wide(db: &mut Database, rows: u64, batch_size: u64) {
+    let columns: Vec<String> = (0..WIDE_COLUMNS)
+        .map(|i| format!("c{} VARCHAR", i))
+        .collect();
+    db.execute(&format!(
+        "CREATE TABLE wide (id BIGINT PRIMARY KEY, {}, status VARCHAR)",
+        columns.join(", ")
+    ))
+    .expect("create wide table failed");
+    let mut id = 1;
+    while id <= rows {
+        let end = (id + batch_size - 1).min(rows);
+        let values: Vec<String> = (id..=end)
+            .map(|id| {
+                let text: Vec<String> = (0..WIDE_COLUMNS as u64)
+                    .map(|i| format!("'{}'", payload(id, i)))
+                    .collect();
+                let status = if id % 10 == 0 { "x" } else { "y" };
+                format!("({}, {}, '{}')", id, text.join(", "), status)
+            })
+            .collect();
+        db.execute(&format!("INSERT INTO wide VALUES {}", values.join(", ")))
+            .expect("populate wide failed");
+        id = end + 1;
+    }
+}
+
+/// Load `rows` rows into an empty indexed table twice: one INSERT per row
+/// (committed every `batch_size` rows), then a single `bulk_insert`.
+/// Returns the elapsed time of each.
+fn compare_load_paths(db: &mut Database, rows: u64, batch_size: u64) -> (Duration, Duration) {
+    for table in ["load_rowwise", "load_bulk"] {
+        db.execute(&format!(
+            "CREATE TABLE {} (id BIGINT PRIMARY KEY, v1 BIGINT, v2 VARCHAR)",
+            table
+        ))
+        .expect("create load table failed");
+        db.execute(&format!("CREATE INDEX {0}_v1 ON {0} (v1)", table))
+            .expect("create load index failed");
+    }
+
+    let start = Instant::now();
+    for id in 1..=rows {
+        if (id - 1) % batch_size == 0 {
+            db.execute("BEGIN").expect("BEGIN failed while loading");
+        }
+        let sql = format!(
+            "INSERT INTO load_rowwise VALUES ({}, {}, '{}')",
+            id,
+            id % 1000,
+            payload(id, 0)
+        );
+        db.execute(&sql).expect("row-by-row load failed");
+        if id % batch_size == 0 || id == rows {
+            db.execute("COMMIT").expect("COMMIT failed while loading");
+        }
+    }
+    let rowwise = start.elapsed();
+
+    let values: Vec<Vec<Value>> = (1..=rows)
+        .map(|id| {
+            vec![
+                Value::Integer(id as i64),
+                Value::Integer((id % 1000) as i64),
+                Value::Varchar(payload(id, 0)),
+            ]
+        })
+        .collect();
+    let start = Instant::now();
+    db.bulk_insert("load_bulk", &values)
+        .expect("bulk load failed");
+    (rowwise, start.elapsed())
+}
+
+/// Run `ops` transactions that each rewrite `pages` page-sized rows, once
+/// with the WAL write buffer off (a write per frame) and once with the
+/// default buffer (a write per commit). Returns the two measurements and
+/// the WAL writes per commit of each.
+fn compare_commit_writes(dir: &Path, ops: u64, pages: u64) -> [(Stat, f64); 2] {
+    ["commit_frame_by_frame", "commit_batched"].map(|name| {
+        let path = dir.join(format!(
+            "murodb_bench_commit_{}_{}.db",
+            std::process::id(),
+            name
+        ));
+        let mut db =
+            Database::create(&path, &MasterKey::new([0x42; 32])).expect("create commit db failed");
+        if name == "commit_frame_by_frame" {
+            db.set_wal_write_buffer_bytes(0);
+        }
+        db.execute("CREATE TABLE pages (id BIGINT PRIMARY KEY, body VARCHAR)")
+            .expect("create pages table failed");
+        db.execute("BEGIN").expect("BEGIN failed");
+        for id in 0..pages {
+            db.execute(&format!(
+                "INSERT INTO pages VALUES ({}, '{}')",
+                id,
+                "x".repeat(3000)
+            ))
+            .expect("insert page row failed");
+        }
+        db.execute("COMMIT").expect("COMMIT failed");
+
+        let before = db.database_stats().clone();
+        let mut round = 0u64;
+        let stat = measure(name, ops, || {
+            round += 1;
+            db.execute("BEGIN").expect("BEGIN failed");
+            let body = format!("{:03000}", round);
+            for id in 0..pages {
+                db.execute(&format!(
+                    "UPDATE pages SET body = '{}' WHERE id = {}",
+                    body, id
+                ))
+                .expect("update page row failed");
+            }
+            db.execute("COMMIT").expect("COMMIT failed");
+            pages as usize
+        });
+        let after = db.database_stats();
+        let writes_per_commit = (after.wal_commit_write_calls - before.wal_commit_write_calls)
+            as f64
+            / (after.wal_commits - before.wal_commits).max(1) as f64;
+
+        drop(db);
+        let _ = std::fs::remove_file(&path);
+        let mut wal_os = path.into_os_string();
+        wal_os.push(".wal");
+        let _ = std::fs::remove_file(PathBuf::from(wal_os));
+        (stat, writes_per_commit)
+    })
+}
+
+/// Insert `rows` rows, `batch_size` per statement, into a table with three
+/// unique indexes. A row is inserted first so the statements do not bulk
+/// load. Returns the elapsed time.
+fn load_unique_indexed(db: &mut Database, rows: u64, batch_size: u64) -> Duration {
+    db.execute(
+        "CREATE TABLE load_unique (id BIGINT PRIMARY KEY, email VARCHAR, code BIGINT, name VARCHAR)",
+    )
+    .expect("create load_unique table failed");
+    for column in ["email", "code", "name"] {
+        db.execute(&format!(
+            "CREATE UNIQUE INDEX load_unique_{0} ON load_unique ({0})",
+            column
+        ))
+        .expect("create unique index failed");
+    }
+    db.execute("INSERT INTO load_unique VALUES (0, 'seed', -1, 'seed')")
+        .expect("seed insert failed");
+
+    let start = Instant::now();
+    let mut id = 1;
+    while id <= rows {
+        let end = (id + batch_size - 1).min(rows);
+        let values: Vec<String> = (id..=end)
+            .map(|id| {
+                format!(
+                    "({0}, 'user{0}@example.com', {1}, '{2}')",
+                    id,
+                    id * 7,
+                    payload(id, 0)
+                )
+            })
+            .collect();
+        db.execute(&format!(
+            "INSERT INTO load_unique VALUES {}",
+            values.join(", ")
+        ))
+        .expect("unique-indexed insert failed");
+        id = end + 1;
+    }
+    start.elapsed()
+}
+
+/// Back up `db` into `dir` `ops` times as a byte copy, then `ops` times
+/// re-encrypted under a new password. The re-encrypting backup of an empty
+/// database is timed as well: it is mostly the key derivation the second
+/// path pays once per backup. Returns the three measurements.
+fn compare_backups(db: &mut Database, dir: &Path, ops: u64) -> [Stat; 3] {
+    let dest = dir.join(format!("murodb_bench_backup_{}.db", std::process::id()));
+    let same_key = measure("backup_same_key", ops, || {
+        db.backup(&dest).expect("backup failed");
+        1
+    });
+    let new_password = measure("backup_new_password", ops, || {
+        db.backup_with_new_password(&dest, "bench-backup-pw")
+            .expect("backup with new password failed");
+        1
+    });
+    let empty_path = dir.join(format!(
+        "murodb_bench_backup_empty_{}.db",
+        std::process::id()
+    ));
+    let mut empty =
+        Database::create(&empty_path, &MasterKey::new([0x42; 32])).expect("create empty db failed");
+    let fixed = measure("backup_new_password_empty_db", ops, || {
+        empty
+            .backup_with_new_password(&dest, "bench-backup-pw")
+            .expect("backup with new password failed");
+        1
+    });
+    drop(empty);
+    for path in [dest, empty_path] {
+        let _ = std::fs::remove_file(&path);
+        let mut wal_os = path.into_os_string();
+        wal_os.push(".wal");
+        let _ = std::fs::remove_file(PathBuf::from(wal_os));
+    }
+    [same_key, new_password, fixed]
+}
+
 fn main() {
     let cli = Cli::parse();
     if cli.initial_rows == 0 {
@@ -228,9 +492,11 @@ fn main() {
     println!("== MuroDB Embedded Benchmark ==");
     println!("db_path={}", db_path.display());
     println!(
-        "config: initial_rows={}, fts_initial_rows={}, select_ops={}, update_ops={}, insert_ops={}, scan_ops={}, mixed_ops={}, fts_select_ops={}, fts_update_ops={}, fts_mixed_ops={}, warmup_ops={}, batch_size={}, fts_batch_size={}, rng_seed={}",
+        "config: initial_rows={}, fts_initial_rows={}, load_rows={}, unique_insert_rows={}, select_ops={}, update_ops={}, insert_ops={}, scan_ops={}, mixed_ops={}, fts_select_ops={}, fts_update_ops={}, fts_mixed_ops={}, filter_ops={}, expr_ops={}, wide_rows={}, wide_ops={}, commit_ops={}, commit_pages={}, backup_ops={}, warmup_ops={}, batch_size={}, fts_batch_size={}, rng_seed={}",
         cli.initial_rows,
         cli.fts_initial_rows,
+        cli.load_rows,
+        cli.unique_insert_rows,
         cli.select_ops,
         cli.update_ops,
         cli.insert_ops,
@@ -239,6 +505,13 @@ fn main() {
         cli.fts_select_ops,
         cli.fts_update_ops,
         cli.fts_mixed_ops,
+        cli.filter_ops,
+        cli.expr_ops,
+        cli.wide_rows,
+        cli.wide_ops,
+        cli.commit_ops,
+        cli.commit_pages,
+        cli.backup_ops,
         cli.warmup_ops,
         cli.batch_size,
         fts_batch_size,
@@ -248,12 +521,37 @@ fn main() {
     let setup_start = Instant::now();
     populate(&mut db, cli.initial_rows, cli.batch_size);
     populate_fts_docs(&mut db, cli.fts_initial_rows, fts_batch_size);
+    populate_wide(&mut db, cli.wide_rows, cli.batch_size);
     let setup_elapsed = setup_start.elapsed();
     println!(
         "setup_elapsed_ms={:.3}",
         setup_elapsed.as_secs_f64() * 1000.0
     );
 
+    let (rowwise_load, bulk_load) = compare_load_paths(&mut db, cli.load_rows, cli.batch_size);
+    println!(
+        "load_rows={}, row_by_row_ms={:.3}, bulk_insert_ms={:.3}, speedup={:.1}x",
+        cli.load_rows,
+        rowwise_load.as_secs_f64() * 1000.0,
+        bulk_load.as_secs_f64() * 1000.0,
+        rowwise_load.as_secs_f64() / bulk_load.as_secs_f64().max(f64::EPSILON)
+    );
+
+    let unique_load = load_unique_indexed(&mut db, cli.unique_insert_rows, cli.batch_size);
+    println!(
+        "unique_insert_rows={}, unique_insert_ms={:.3}, rows_per_sec={:.0}",
+        cli.unique_insert_rows,
+        unique_load.as_secs_f64() * 1000.0,
+        cli.unique_insert_rows as f64 / unique_load.as_secs_f64().max(f64::EPSILON)
+    );
+
+    let [(frame_commit_stat, frame_writes), (batched_commit_stat, batched_writes)] =
+        compare_commit_writes(&cli.commit_dir, cli.commit_ops, cli.commit_pages);
+    println!(
+        "commit_pages={}, wal_writes_per_commit: frame_by_frame={:.1}, batched={:.1}",
+        cli.commit_pages, frame_writes, batched_writes
+    );
+
     for _ in 0..cli.warmup_ops {
         let id = rng.gen_range(1..=cli.initial_rows);
         let sql = format!("SELECT * FROM kv WHERE id = {}", id);
@@ -375,6 +673,67 @@ fn main() {
         }
     });
 
+    // Heavy LIKE written before a selective integer equality: the same
+    // query stream with reordering off, then on.
+    let filter_op = |rng: &mut StdRng, db: &mut Database| {
+        let v1 = rng.gen_range(1..=cli.initial_rows);
+        let sql = format!(
+            "SELECT id FROM kv WHERE v2 LIKE '%a%b%c%d%' AND v1 = {}",
+            v1
+        );
+        db.query(&sql).expect("filter select failed").len()
+    };
+    let mut filter_rng = StdRng::seed_from_u64(seed ^ 0xF11E);
+    db.set_predicate_reorder(false);
+    let filter_written_stat = measure("filter_like_eq_written_order", cli.filter_ops, || {
+        filter_op(&mut filter_rng, &mut db)
+    });
+    let mut filter_rng = StdRng::seed_from_u64(seed ^ 0xF11E);
+    db.set_predicate_reorder(true);
+    let filter_reordered_stat = measure("filter_like_eq_reordered", cli.filter_ops, || {
+        filter_op(&mut filter_rng, &mut db)
+    });
+
+    // A computed expression in WHERE, then also in the select list. The
+    // projection reuses each row's WHERE result, so the second scan should
+    // cost about the same as the first.
+    let expr = "LENGTH(UPPER(CONCAT(v2, v2))) + v1 % 7";
+    let expr_filter_stat = measure("expr_filter_only", cli.expr_ops, || {
+        let sql = format!("SELECT id FROM kv WHERE {} > 0", expr);
+        db.query(&sql).expect("expr filter failed").len()
+    });
+    let expr_project_stat = measure("expr_filter_and_project", cli.expr_ops, || {
+        let sql = format!("SELECT id, {0} AS e FROM kv WHERE {0} > 0", expr);
+        db.query(&sql).expect("expr filter failed").len()
+    });
+
+    // A selective count reads only `status`; the same count with a
+    // predicate over every VARCHAR column decodes whole rows.
+    let wide_count_stat = measure("wide_count_selective", cli.wide_ops, || {
+        let sql = "SELECT COUNT(*) FROM wide WHERE status = 'x'";
+        db.query(sql).expect("wide count failed").len()
+    });
+    let all_columns: Vec<String> = (0..WIDE_COLUMNS).map(|i| format!("c{}", i)).collect();
+    let wide_full_sql = format!(
+        "SELECT COUNT(*) FROM wide WHERE status = 'x' AND CONCAT({}) IS NOT NULL",
+        all_columns.join(", ")
+    );
+    let wide_full_stat = measure("wide_count_reading_all_columns", cli.wide_ops, || {
+        db.query(&wide_full_sql).expect("wide count failed").len()
+    });
+
+    // Re-encryption decrypts and encrypts every page where the same-key
+    // backup copies bytes; compare medians with the fixed cost taken out.
+    let [backup_same_stat, backup_rekey_stat, backup_fixed_stat] =
+        compare_backups(&mut db, &cli.commit_dir, cli.backup_ops);
+    println!(
+        "backup_pages_ms: same_key={:.3}, re_encrypting={:.3}, ratio={:.1}x",
+        backup_same_stat.p50_ms,
+        backup_rekey_stat.p50_ms - backup_fixed_stat.p50_ms,
+        (backup_rekey_stat.p50_ms - backup_fixed_stat.p50_ms)
+            / backup_same_stat.p50_ms.max(f64::EPSILON)
+    );
+
     println!();
     println!("name,ops,total_sec,ops_per_sec,p50_ms,p95_ms,p99_ms");
     for stat in [
@@ -386,6 +745,17 @@ fn main() {
         fts_select_stat,
         fts_update_stat,
         fts_mixed_stat,
+        filter_written_stat,
+        filter_reordered_stat,
+        expr_filter_stat,
+        expr_project_stat,
+        wide_count_stat,
+        wide_full_stat,
+        frame_commit_stat,
+        batched_commit_stat,
+        backup_same_stat,
+        backup_rekey_stat,
+        backup_fixed_stat,
     ] {
         let total_sec = stat.elapsed.as_secs_f64();
         let ops_per_sec = if total_sec > 0.0 {
@@ -3,11 +3,13 @@ use std::process;
 
 use clap::{Parser, ValueEnum};
 use murodb::{Database, RecoveryMode};
+use zeroize::Zeroizing;
 
 #[derive(Clone, Debug, ValueEnum)]
 enum RecoveryModeArg {
     Strict,
     Permissive,
+    Salvage,
 }
 
 impl From<RecoveryModeArg> for RecoveryMode {
@@ -15,6 +17,7 @@ impl From<RecoveryModeArg> for RecoveryMode {
         match value {
             RecoveryModeArg::Strict => RecoveryMode::Strict,
             RecoveryModeArg::Permissive => RecoveryMode::Permissive,
+            RecoveryModeArg::Salvage => RecoveryMode::Salvage,
         }
     }
 }
@@ -35,11 +38,14 @@ struct Cli {
     recovery_mode: RecoveryModeArg,
 }
 
-fn prompt_password(prompt: &str) -> String {
-    rpassword::read_password_from_tty(Some(prompt)).unwrap_or_else(|e| {
-        eprintln!("ERROR: Failed to read password: {}", e);
-        process::exit(1);
-    })
+/// The password typed at the prompt, zeroed when dropped.
+fn prompt_password(prompt: &str) -> Zeroizing<String> {
+    Zeroizing::new(
+        rpassword::read_password_from_tty(Some(prompt)).unwrap_or_else(|e| {
+            eprintln!("ERROR: Failed to read password: {}", e);
+            process::exit(1);
+        }),
+    )
 }
 
 fn main() {
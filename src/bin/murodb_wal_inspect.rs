@@ -4,6 +4,7 @@ use std::time::{SystemTime, UNIX_EPOCH};
 
 use clap::{Parser, ValueEnum};
 use murodb::{Database, DatabaseEncryption, MuroError, RecoveryMode, RecoveryResult};
+use zeroize::Zeroizing;
 
 const EXIT_OK: i32 = 0;
 const EXIT_MALFORMED_DETECTED: i32 = 10;
@@ -13,6 +14,7 @@ const EXIT_FATAL_ERROR: i32 = 20;
 enum RecoveryModeArg {
     Strict,
     Permissive,
+    Salvage,
 }
 
 #[derive(Clone, Debug, ValueEnum)]
@@ -26,6 +28,7 @@ impl From<RecoveryModeArg> for RecoveryMode {
         match value {
             RecoveryModeArg::Strict => RecoveryMode::Strict,
             RecoveryModeArg::Permissive => RecoveryMode::Permissive,
+            RecoveryModeArg::Salvage => RecoveryMode::Salvage,
         }
     }
 }
@@ -34,8 +37,8 @@ impl From<RecoveryModeArg> for RecoveryMode {
 #[command(
     name = "murodb-wal-inspect",
     about = "Inspect MuroDB WAL consistency",
-    long_about = "Inspect a MuroDB WAL file (or quarantine WAL file) without opening the database for normal SQL operations.\n\nThis command validates transaction boundaries and replay eligibility under a selected recovery policy.\n\nExit codes:\n- 0: inspection succeeded and no malformed transaction was skipped.\n- 10: inspection succeeded but malformed transaction(s) were skipped.\n- 20: fatal error (cannot inspect).",
-    after_long_help = "Examples:\n  murodb-wal-inspect my.db --wal my.db.wal\n  murodb-wal-inspect my.db --wal quarantine.wal --recovery-mode permissive\n  murodb-wal-inspect my.db --wal my.db.wal --format json\n\nDocumentation:\n  https://tokuhirom.github.io/murodb/"
+    long_about = "Inspect a MuroDB WAL file (or quarantine WAL file) without opening the database for normal SQL operations.\n\nThis command validates transaction boundaries and replay eligibility under a selected recovery policy.\n\nExit codes:\n- 0: inspection succeeded and nothing was skipped.\n- 10: inspection succeeded but malformed or incomplete transaction(s), or unreadable frames, were skipped.\n- 20: fatal error (cannot inspect).",
+    after_long_help = "Examples:\n  murodb-wal-inspect my.db --wal my.db.wal\n  murodb-wal-inspect my.db --wal quarantine.wal --recovery-mode permissive\n  murodb-wal-inspect my.db --wal quarantine.wal --recovery-mode salvage\n  murodb-wal-inspect my.db --wal my.db.wal --format json\n\nDocumentation:\n  https://tokuhirom.github.io/murodb/"
 )]
 struct Cli {
     /// Path to the database file whose encryption metadata is used.
@@ -55,6 +58,7 @@ struct Cli {
     ///
     /// `strict` aborts on malformed data.
     /// `permissive` reports and skips malformed transactions when possible.
+    /// `salvage` also reads past damaged frames to the next readable one.
     #[arg(long, value_enum, default_value = "strict")]
     recovery_mode: RecoveryModeArg,
 
@@ -65,14 +69,17 @@ struct Cli {
     format: OutputFormatArg,
 }
 
-fn get_password(cli_password: &Option<String>) -> String {
+/// The password, zeroed when dropped.
+fn get_password(cli_password: Option<String>) -> Zeroizing<String> {
     if let Some(pw) = cli_password {
-        return pw.clone();
+        return Zeroizing::new(pw);
     }
-    rpassword::read_password_from_tty(Some("Password: ")).unwrap_or_else(|e| {
-        eprintln!("ERROR: Failed to read password: {}", e);
-        process::exit(1);
-    })
+    Zeroizing::new(
+        rpassword::read_password_from_tty(Some("Password: ")).unwrap_or_else(|e| {
+            eprintln!("ERROR: Failed to read password: {}", e);
+            process::exit(1);
+        }),
+    )
 }
 
 fn json_escape(s: &str) -> String {
@@ -91,15 +98,8 @@ fn json_escape(s: &str) -> String {
     out
 }
 
-fn json_mode_str(mode: RecoveryMode) -> &'static str {
-    match mode {
-        RecoveryMode::Strict => "strict",
-        RecoveryMode::Permissive => "permissive",
-    }
-}
-
 fn inspect_success_exit_code(report: &RecoveryResult) -> i32 {
-    if report.skipped.is_empty() {
+    if !report.lost_committed_data() {
         EXIT_OK
     } else {
         EXIT_MALFORMED_DETECTED
@@ -161,6 +161,28 @@ fn build_inspect_json_success(
         })
         .collect::<Vec<_>>()
         .join(",");
+    let incomplete = report
+        .incomplete_txids
+        .iter()
+        .map(|txid| txid.to_string())
+        .collect::<Vec<_>>()
+        .join(",");
+    let abandoned = report
+        .abandoned_txids
+        .iter()
+        .map(|txid| txid.to_string())
+        .collect::<Vec<_>>()
+        .join(",");
+    let unreadable = report
+        .unreadable_frames
+        .iter()
+        .map(|f| format!("{{\"lsn\":{},\"offset\":{}}}", f.lsn, f.offset))
+        .collect::<Vec<_>>()
+        .join(",");
+    let first_corruption_offset = report
+        .first_corruption_offset
+        .map(|offset| offset.to_string())
+        .unwrap_or_else(|| "null".to_string());
     let quarantine = report
         .wal_quarantine_path
         .as_ref()
@@ -168,16 +190,21 @@ fn build_inspect_json_success(
         .unwrap_or_else(|| "null".to_string());
 
     format!(
-        "{{\"schema_version\":1,\"mode\":\"{}\",\"wal_path\":\"{}\",\"generated_at\":{},\"committed_txids\":[{}],\"aborted_txids\":[{}],\"pages_replayed\":{},\"skipped\":[{}],\"wal_quarantine_path\":{},\"status\":\"{}\",\"fatal_error\":null,\"fatal_error_code\":null,\"exit_code\":{}}}",
-        json_mode_str(mode),
+        "{{\"schema_version\":1,\"mode\":\"{}\",\"wal_path\":\"{}\",\"generated_at\":{},\"committed_txids\":[{}],\"aborted_txids\":[{}],\"pages_replayed\":{},\"skipped\":[{}],\"incomplete_txids\":[{}],\"abandoned_txids\":[{}],\"unreadable_frames\":[{}],\"first_corruption_offset\":{},\"wal_tail\":\"{}\",\"wal_quarantine_path\":{},\"status\":\"{}\",\"fatal_error\":null,\"fatal_error_code\":null,\"exit_code\":{}}}",
+        mode.as_str(),
         json_escape(&wal_path.display().to_string()),
         generated_at,
         committed,
         aborted,
         report.pages_replayed,
         skipped,
+        incomplete,
+        abandoned,
+        unreadable,
+        first_corruption_offset,
+        report.wal_tail.as_str(),
         quarantine,
-        if report.skipped.is_empty() { "ok" } else { "warning" },
+        if report.lost_committed_data() { "warning" } else { "ok" },
         inspect_success_exit_code(report)
     )
 }
@@ -197,8 +224,8 @@ fn build_inspect_json_fatal(
         .unwrap_or_default()
         .as_secs();
     format!(
-        "{{\"schema_version\":1,\"mode\":\"{}\",\"wal_path\":\"{}\",\"generated_at\":{},\"committed_txids\":[],\"aborted_txids\":[],\"pages_replayed\":0,\"skipped\":[],\"wal_quarantine_path\":null,\"status\":\"fatal\",\"fatal_error\":\"{}\",\"fatal_error_code\":\"{}\",\"exit_code\":{}}}",
-        json_mode_str(mode),
+        "{{\"schema_version\":1,\"mode\":\"{}\",\"wal_path\":\"{}\",\"generated_at\":{},\"committed_txids\":[],\"aborted_txids\":[],\"pages_replayed\":0,\"skipped\":[],\"incomplete_txids\":[],\"abandoned_txids\":[],\"unreadable_frames\":[],\"first_corruption_offset\":null,\"wal_tail\":null,\"wal_quarantine_path\":null,\"status\":\"fatal\",\"fatal_error\":\"{}\",\"fatal_error_code\":\"{}\",\"exit_code\":{}}}",
+        mode.as_str(),
         json_escape(&wal_path.display().to_string()),
         generated_at,
         json_escape(msg),
@@ -222,7 +249,7 @@ fn inspect_fatal_and_exit(
 }
 
 fn main() {
-    let cli = Cli::parse();
+    let mut cli = Cli::parse();
 
     let recovery_mode: RecoveryMode = cli.recovery_mode.clone().into();
 
@@ -236,23 +263,28 @@ fn main() {
         );
     });
     let password = match mode {
-        DatabaseEncryption::Encrypted => Some(get_password(&cli.password)),
+        DatabaseEncryption::Encrypted => Some(get_password(cli.password.take())),
         DatabaseEncryption::Plaintext => None,
     };
-    let report = Database::inspect_wal(&cli.db_path, &cli.wal, password.as_deref(), recovery_mode)
-        .unwrap_or_else(|e| {
-            let kind = match &e {
-                MuroError::Kdf(_) => InspectFatalKind::DeriveKey,
-                _ => InspectFatalKind::InspectFailed,
-            };
-            inspect_fatal_and_exit(
-                &cli.format,
-                recovery_mode,
-                &cli.wal,
-                kind,
-                &format!("WAL inspection failed: {}", e),
-            );
-        });
+    let report = Database::inspect_wal(
+        &cli.db_path,
+        &cli.wal,
+        password.as_ref().map(|pw| pw.as_bytes()),
+        recovery_mode,
+    )
+    .unwrap_or_else(|e| {
+        let kind = match &e {
+            MuroError::Kdf(_) => InspectFatalKind::DeriveKey,
+            _ => InspectFatalKind::InspectFailed,
+        };
+        inspect_fatal_and_exit(
+            &cli.format,
+            recovery_mode,
+            &cli.wal,
+            kind,
+            &format!("WAL inspection failed: {}", e),
+        );
+    });
 
     match cli.format {
         OutputFormatArg::Text => {
@@ -269,6 +301,23 @@ fn main() {
                     skipped.reason
                 );
             }
+            println!("  incomplete txs: {}", report.incomplete_txids.len());
+            for txid in &report.incomplete_txids {
+                println!("  - txid {}", txid);
+            }
+            println!("  abandoned txs: {}", report.abandoned_txids.len());
+            for txid in &report.abandoned_txids {
+                println!("  - txid {}", txid);
+            }
+            println!("  unreadable frames: {}", report.unreadable_frames.len());
+            for frame in &report.unreadable_frames {
+                println!("  - LSN {} at offset {}", frame.lsn, frame.offset);
+            }
+            match report.first_corruption_offset {
+                Some(offset) => println!("  first corruption at offset: {}", offset),
+                None => println!("  first corruption at offset: none"),
+            }
+            println!("  WAL tail: {}", report.wal_tail.as_str());
         }
         OutputFormatArg::Json => {
             emit_inspect_json_success(recovery_mode, &cli.wal, &report);
@@ -281,7 +330,7 @@ fn main() {
 #[cfg(test)]
 mod tests {
     use super::*;
-    use murodb::{RecoverySkipCode, RecoverySkippedTx};
+    use murodb::{RecoverySkipCode, RecoverySkippedTx, UnreadableFrame, WalTail};
 
     #[test]
     fn inspect_json_success_has_null_fatal_error() {
@@ -295,6 +344,11 @@ mod tests {
                 code: RecoverySkipCode::CommitWithoutMetaUpdate,
                 reason: "missing meta".to_string(),
             }],
+            incomplete_txids: vec![],
+            abandoned_txids: vec![],
+            unreadable_frames: vec![],
+            first_corruption_offset: None,
+            wal_tail: WalTail::Clean,
             wal_quarantine_path: Some("/tmp/test.wal.quarantine".to_string()),
         };
 
@@ -334,6 +388,11 @@ mod tests {
             aborted_txids: vec![],
             pages_replayed: 1,
             skipped: vec![],
+            incomplete_txids: vec![],
+            abandoned_txids: vec![],
+            unreadable_frames: vec![],
+            first_corruption_offset: None,
+            wal_tail: WalTail::Clean,
             wal_quarantine_path: None,
         };
         assert_eq!(inspect_success_exit_code(&report), 0);
@@ -438,6 +497,11 @@ mod tests {
             aborted_txids: vec![],
             pages_replayed: 1,
             skipped: vec![],
+            incomplete_txids: vec![],
+            abandoned_txids: vec![],
+            unreadable_frames: vec![],
+            first_corruption_offset: None,
+            wal_tail: WalTail::Clean,
             wal_quarantine_path: None,
         };
 
@@ -447,4 +511,33 @@ mod tests {
         assert!(json.contains("\"fatal_error\":null"));
         assert!(json.contains("\"fatal_error_code\":null"));
     }
+
+    #[test]
+    fn inspect_json_reports_lost_frames_and_tail() {
+        let wal_path = Path::new("/tmp/test.wal");
+        let report = RecoveryResult {
+            committed_txids: vec![1, 3],
+            aborted_txids: vec![],
+            pages_replayed: 2,
+            skipped: vec![],
+            incomplete_txids: vec![2],
+            abandoned_txids: vec![2, 3],
+            unreadable_frames: vec![UnreadableFrame {
+                lsn: 5,
+                offset: 812,
+            }],
+            first_corruption_offset: Some(812),
+            wal_tail: WalTail::TornWrite,
+            wal_quarantine_path: None,
+        };
+
+        let json = build_inspect_json_success(RecoveryMode::Permissive, wal_path, &report);
+        assert!(json.contains("\"incomplete_txids\":[2]"));
+        assert!(json.contains("\"abandoned_txids\":[2,3]"));
+        assert!(json.contains("\"unreadable_frames\":[{\"lsn\":5,\"offset\":812}]"));
+        assert!(json.contains("\"first_corruption_offset\":812"));
+        assert!(json.contains("\"wal_tail\":\"torn_write\""));
+        assert!(json.contains("\"status\":\"warning\""));
+        assert_eq!(inspect_success_exit_code(&report), EXIT_MALFORMED_DETECTED);
+    }
 }
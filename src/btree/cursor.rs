@@ -2,6 +2,14 @@
 ///
 /// The cursor collects entries from the current scan position.
 /// For MVP, this uses the scan method internally.
+///
+/// Statement stability: every entry is materialized when the cursor is
+/// created, so the cursor yields exactly the tree contents as of that moment.
+/// Later writes through the same `PageStore` — including a transaction's own
+/// inserts, updates, and deletes on keys before or after the cursor position —
+/// are never observed by an existing cursor, and splits/merges cannot
+/// invalidate it. Any future lazy cursor must keep this guarantee (e.g. by
+/// copying pages it has yielded from before they are modified).
 use crate::btree::ops::BTree;
 use crate::error::Result;
 use crate::storage::page_store::PageStore;
@@ -111,4 +119,60 @@ mod tests {
 
         std::fs::remove_file(&path).ok();
     }
+
+    #[test]
+    fn test_cursor_is_stable_under_same_tx_writes() {
+        use crate::btree::key_encoding::encode_i64;
+        use crate::tx::page_store::TxPageStore;
+        use crate::tx::transaction::Transaction;
+
+        let tmp = NamedTempFile::new().unwrap();
+        let path = tmp.path().to_path_buf();
+        drop(tmp);
+        std::fs::remove_file(&path).ok();
+
+        let key = MasterKey::new([0x42u8; 32]);
+        let mut pager = Pager::create(&path, &key).unwrap();
+        let mut btree = BTree::create(&mut pager).unwrap();
+        for i in 0..400i64 {
+            btree
+                .insert(&mut pager, &encode_i64(i * 2), b"old")
+                .unwrap();
+        }
+
+        let mut store = TxPageStore::new(Transaction::begin(1, 0), &mut pager);
+        let mut cursor = BTreeCursor::new(&btree, &mut store).unwrap();
+        let mut seen = Vec::new();
+        for _ in 0..200 {
+            let (k, v) = cursor.next().unwrap();
+            assert_eq!(v, b"old");
+            seen.push(k.to_vec());
+        }
+
+        // Mutate behind and ahead of the cursor position within the same transaction.
+        for i in 0..400i64 {
+            let k = encode_i64(i * 2);
+            if i % 3 == 0 {
+                btree.delete(&mut store, &k).unwrap();
+            } else {
+                btree.insert(&mut store, &k, &[b'n'; 300]).unwrap();
+            }
+            btree
+                .insert(&mut store, &encode_i64(i * 2 + 1), b"new")
+                .unwrap();
+        }
+
+        while let Some((k, v)) = cursor.next() {
+            assert_eq!(v, b"old");
+            seen.push(k.to_vec());
+        }
+        let expected: Vec<Vec<u8>> = (0..400i64).map(|i| encode_i64(i * 2).to_vec()).collect();
+        assert_eq!(seen, expected);
+
+        // A fresh cursor sees the transaction's writes.
+        let fresh = BTreeCursor::new(&btree, &mut store).unwrap();
+        assert_eq!(fresh.len(), 400 - 134 + 400);
+
+        std::fs::remove_file(&path).ok();
+    }
 }
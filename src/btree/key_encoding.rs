@@ -69,6 +69,11 @@ pub fn decode_f32(bytes: &[u8; 4]) -> f32 {
 }
 
 /// Encode f64 into 8 bytes that preserve sort order under byte comparison.
+///
+/// Byte order is numeric order: `-inf` < negative normals < negative
+/// subnormals < zero < positive subnormals < positive normals < `+inf`.
+/// `-0.0` encodes as `0.0`. NaN has no place in this order; FLOAT/DOUBLE
+/// columns never store it.
 pub fn encode_f64(val: f64) -> [u8; 8] {
     let val = if val == 0.0 { 0.0 } else { val };
     let bits = val.to_bits();
@@ -112,6 +117,7 @@ pub fn compare_keys(a: &[u8], b: &[u8]) -> std::cmp::Ordering {
 ///   - Fixed-length integers: sign-bit-flipped big-endian (existing encode_iN)
 ///   - Variable-length (VARCHAR/TEXT/VARBINARY): byte-stuffing
 ///     (`0x00` → `0x00 0x01`, terminated by `0x00 0x00`)
+#[cfg(feature = "sql")]
 pub fn encode_composite_key(
     values: &[&crate::types::Value],
     data_types: &[&crate::types::DataType],
@@ -246,11 +252,107 @@ pub fn encode_composite_key(
     buf
 }
 
+/// Width of a non-NULL fixed-length key part of type `data_type`, or `None`
+/// for variable-length types.
+#[cfg(feature = "sql")]
+fn fixed_key_width(data_type: &crate::types::DataType) -> Option<usize> {
+    use crate::types::DataType;
+    match data_type {
+        DataType::TinyInt => Some(1),
+        DataType::SmallInt => Some(2),
+        DataType::Int | DataType::Float | DataType::Date => Some(4),
+        DataType::BigInt | DataType::Double | DataType::DateTime | DataType::Timestamp => Some(8),
+        DataType::Decimal(..) | DataType::Uuid => Some(16),
+        DataType::Varchar(_) | DataType::Text | DataType::Jsonb | DataType::Varbinary(_) => None,
+    }
+}
+
+/// Decode a single-column key that `encode_value` wrote for a value of the
+/// column's own type. `None` if the bytes do not fit the type.
+///
+/// FLOAT and DOUBLE keys fold `-0.0` into `0.0`; every other type decodes to
+/// the value that was encoded.
+#[cfg(feature = "sql")]
+pub fn decode_key_value(
+    bytes: &[u8],
+    data_type: &crate::types::DataType,
+) -> Option<crate::types::Value> {
+    use crate::types::{DataType, Value};
+    if let Some(width) = fixed_key_width(data_type) {
+        if bytes.len() != width {
+            return None;
+        }
+    }
+    Some(match data_type {
+        DataType::TinyInt => Value::Integer(decode_i8(bytes.try_into().ok()?) as i64),
+        DataType::SmallInt => Value::Integer(decode_i16(bytes.try_into().ok()?) as i64),
+        DataType::Int => Value::Integer(decode_i32(bytes.try_into().ok()?) as i64),
+        DataType::BigInt => Value::Integer(decode_i64(bytes.try_into().ok()?)),
+        DataType::Float => Value::Float(decode_f32(bytes.try_into().ok()?) as f64),
+        DataType::Double => Value::Float(decode_f64(bytes.try_into().ok()?)),
+        DataType::Date => Value::Date(decode_i32(bytes.try_into().ok()?)),
+        DataType::DateTime => Value::DateTime(decode_i64(bytes.try_into().ok()?)),
+        DataType::Timestamp => Value::Timestamp(decode_i64(bytes.try_into().ok()?)),
+        DataType::Decimal(_, scale) => {
+            let unsigned = u128::from_be_bytes(bytes.try_into().ok()?);
+            let mantissa = (unsigned ^ (1u128 << 127)) as i128;
+            Value::Decimal(rust_decimal::Decimal::try_from_i128_with_scale(mantissa, *scale).ok()?)
+        }
+        DataType::Uuid => Value::Uuid(bytes.try_into().ok()?),
+        DataType::Varchar(_) | DataType::Text | DataType::Jsonb => {
+            Value::Varchar(String::from_utf8(bytes.to_vec()).ok()?)
+        }
+        DataType::Varbinary(_) => Value::Varbinary(bytes.to_vec()),
+    })
+}
+
+/// Decode a key written by [`encode_composite_key`] for values of the
+/// columns' own types. `None` if the bytes do not fit the types or are left
+/// over at the end.
+#[cfg(feature = "sql")]
+pub fn decode_composite_key(
+    bytes: &[u8],
+    data_types: &[&crate::types::DataType],
+) -> Option<Vec<crate::types::Value>> {
+    use crate::types::Value;
+    let mut values = Vec::with_capacity(data_types.len());
+    let mut rest = bytes;
+    for dt in data_types {
+        let (&marker, tail) = rest.split_first()?;
+        rest = tail;
+        match marker {
+            0x00 => values.push(Value::Null),
+            0x01 => match fixed_key_width(dt) {
+                Some(width) if rest.len() >= width => {
+                    values.push(decode_key_value(&rest[..width], dt)?);
+                    rest = &rest[width..];
+                }
+                Some(_) => return None,
+                None => {
+                    let (data, read) = decode_byte_stuffed(rest)?;
+                    values.push(decode_key_value(&data, dt)?);
+                    rest = &rest[read..];
+                }
+            },
+            _ => return None,
+        }
+    }
+    rest.is_empty().then_some(values)
+}
+
 /// Byte-stuffing encoding for variable-length data.
 /// Each `0x00` byte in the input is replaced with `0x00 0x01`.
 /// The sequence is terminated with `0x00 0x00`.
 /// This preserves lexicographic order.
-fn encode_byte_stuffed(buf: &mut Vec<u8>, data: &[u8]) {
+pub fn encode_byte_stuffed(buf: &mut Vec<u8>, data: &[u8]) {
+    push_byte_stuffed(buf, data);
+    buf.push(0x00);
+    buf.push(0x00);
+}
+
+/// Byte-stuff `data` into `buf` without the terminator, for a prefix that
+/// must match the start of longer stuffed sequences.
+pub fn push_byte_stuffed(buf: &mut Vec<u8>, data: &[u8]) {
     for &b in data {
         if b == 0x00 {
             buf.push(0x00);
@@ -259,8 +361,30 @@ fn encode_byte_stuffed(buf: &mut Vec<u8>, data: &[u8]) {
             buf.push(b);
         }
     }
-    buf.push(0x00);
-    buf.push(0x00);
+}
+
+/// Decode a byte-stuffed sequence at the start of `bytes`. Returns the data
+/// and the number of bytes read, terminator included, or `None` if the
+/// terminator is missing or an escape is invalid.
+pub fn decode_byte_stuffed(bytes: &[u8]) -> Option<(Vec<u8>, usize)> {
+    let mut data = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        if bytes[i] != 0x00 {
+            data.push(bytes[i]);
+            i += 1;
+            continue;
+        }
+        match bytes.get(i + 1) {
+            Some(0x00) => return Some((data, i + 2)),
+            Some(0x01) => {
+                data.push(0x00);
+                i += 2;
+            }
+            _ => return None,
+        }
+    }
+    None
 }
 
 #[cfg(test)]
@@ -337,6 +461,52 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_f64_encode_order_matches_numeric_order() {
+        use rand::{Rng, SeedableRng};
+
+        let mut rng = rand::rngs::StdRng::seed_from_u64(0x5eed_f10a7);
+        let mut vals = vec![
+            0.0,
+            -0.0,
+            f64::MIN_POSITIVE,
+            -f64::MIN_POSITIVE,
+            f64::from_bits(1),
+            -f64::from_bits(1),
+            f64::from_bits(0x000f_ffff_ffff_ffff),
+            f64::MAX,
+            f64::MIN,
+            f64::INFINITY,
+            f64::NEG_INFINITY,
+            1.0,
+            -1.0,
+        ];
+        while vals.len() < 2000 {
+            // Random bit patterns cover every exponent, subnormals included.
+            let v = f64::from_bits(rng.gen::<u64>());
+            if !v.is_nan() {
+                vals.push(v);
+            }
+        }
+        for a in &vals {
+            assert_eq!(
+                decode_f64(&encode_f64(*a)),
+                if *a == 0.0 { 0.0 } else { *a }
+            );
+        }
+        for _ in 0..20_000 {
+            let a = vals[rng.gen_range(0..vals.len())];
+            let b = vals[rng.gen_range(0..vals.len())];
+            assert_eq!(
+                encode_f64(a).cmp(&encode_f64(b)),
+                a.partial_cmp(&b).unwrap(),
+                "{:e} vs {:e}",
+                a,
+                b
+            );
+        }
+    }
+
     #[test]
     fn test_float_zero_canonicalization() {
         assert_eq!(encode_f32(-0.0), encode_f32(0.0));
@@ -344,6 +514,7 @@ mod tests {
     }
 
     #[test]
+    #[cfg(feature = "sql")]
     fn test_composite_key_integer_literal_for_float_column() {
         use crate::types::{DataType, Value};
 
@@ -356,6 +527,34 @@ mod tests {
         assert_eq!(k1, k2);
     }
 
+    #[test]
+    fn test_byte_stuffed_roundtrip_and_order() {
+        let values: [&[u8]; 7] = [b"", b"\0", b"\0\0", b"\0\x01", b"a", b"a\0", b"ab"];
+        let encoded: Vec<Vec<u8>> = values
+            .iter()
+            .map(|v| {
+                let mut buf = Vec::new();
+                encode_byte_stuffed(&mut buf, v);
+                buf
+            })
+            .collect();
+        for (v, e) in values.iter().zip(&encoded) {
+            let mut tail = e.clone();
+            tail.extend_from_slice(b"\xffrest");
+            assert_eq!(decode_byte_stuffed(&tail), Some((v.to_vec(), e.len())));
+        }
+        for i in 0..encoded.len() - 1 {
+            assert!(
+                encoded[i] < encoded[i + 1],
+                "{:?} vs {:?}",
+                values[i],
+                values[i + 1]
+            );
+        }
+        assert_eq!(decode_byte_stuffed(b"ab"), None);
+        assert_eq!(decode_byte_stuffed(b"a\0\x02\0\0"), None);
+    }
+
     #[test]
     fn test_varchar_byte_comparison() {
         // UTF-8 byte comparison
@@ -365,6 +564,7 @@ mod tests {
     }
 
     #[test]
+    #[cfg(feature = "sql")]
     fn test_composite_key_order_int_string() {
         use crate::types::{DataType, Value};
 
@@ -381,6 +581,7 @@ mod tests {
     }
 
     #[test]
+    #[cfg(feature = "sql")]
     fn test_composite_key_null_less_than_non_null() {
         use crate::types::{DataType, Value};
 
@@ -397,6 +598,7 @@ mod tests {
     }
 
     #[test]
+    #[cfg(feature = "sql")]
     fn test_composite_key_with_nul_byte_in_varchar() {
         use crate::types::{DataType, Value};
 
@@ -411,6 +613,7 @@ mod tests {
     }
 
     #[test]
+    #[cfg(feature = "sql")]
     fn test_composite_key_equality() {
         use crate::types::{DataType, Value};
 
@@ -426,6 +629,7 @@ mod tests {
     }
 
     #[test]
+    #[cfg(feature = "sql")]
     fn test_composite_key_date_literal_for_datetime_column() {
         use crate::types::{DataType, Value};
 
@@ -440,6 +644,7 @@ mod tests {
     }
 
     #[test]
+    #[cfg(feature = "sql")]
     fn test_composite_key_datetime_literal_for_date_column() {
         use crate::types::{DataType, Value};
 
@@ -452,4 +657,56 @@ mod tests {
             encode_composite_key(&vals_date, &dts)
         );
     }
+
+    #[cfg(feature = "sql")]
+    #[test]
+    fn test_composite_key_decodes_to_the_encoded_values() {
+        use crate::types::{DataType, Value};
+        let types = [
+            DataType::TinyInt,
+            DataType::Int,
+            DataType::BigInt,
+            DataType::Double,
+            DataType::Decimal(10, 2),
+            DataType::Date,
+            DataType::DateTime,
+            DataType::Varchar(None),
+            DataType::Varbinary(None),
+            DataType::Uuid,
+        ];
+        let values = [
+            Value::Integer(-5),
+            Value::Integer(i32::MIN as i64),
+            Value::Integer(i64::MAX),
+            Value::Float(-2.5),
+            Value::Decimal(rust_decimal::Decimal::new(-12345, 2)),
+            Value::Date(19000),
+            Value::DateTime(1_700_000_000_000_000),
+            Value::Varchar("a\0b".into()),
+            Value::Varbinary(vec![0, 0, 1, 0xff]),
+            Value::Uuid([7; 16]),
+        ];
+        let type_refs: Vec<&DataType> = types.iter().collect();
+        let value_refs: Vec<&Value> = values.iter().collect();
+        let key = encode_composite_key(&value_refs, &type_refs);
+        assert_eq!(
+            decode_composite_key(&key, &type_refs).as_deref(),
+            Some(&values[..])
+        );
+
+        let mut with_null = value_refs.clone();
+        with_null[7] = &Value::Null;
+        let key = encode_composite_key(&with_null, &type_refs);
+        assert_eq!(
+            decode_composite_key(&key, &type_refs).unwrap()[7],
+            Value::Null
+        );
+
+        // Truncated or extended keys are rejected.
+        assert!(decode_composite_key(&key[..key.len() - 1], &type_refs).is_none());
+        let mut longer = key.clone();
+        longer.push(0);
+        assert!(decode_composite_key(&longer, &type_refs).is_none());
+        assert!(decode_key_value(&[1, 2, 3], &DataType::Int).is_none());
+    }
 }
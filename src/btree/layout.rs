@@ -0,0 +1,319 @@
+/// Physical page layout of a B-tree: bottom-up bulk loading into contiguous
+/// page runs, and the leaf clustering metric reported by SHOW TABLE STATUS.
+use crate::btree::node::*;
+use crate::btree::ops::{BTree, MAX_BTREE_DEPTH};
+use crate::error::{MuroError, Result};
+use crate::storage::page::{Page, PageId};
+use crate::storage::page_store::PageStore;
+
+/// Node pages of a B-tree, with leaves in key order.
+#[derive(Debug, Default)]
+pub struct TreeLayout {
+    pub leaf_pages: Vec<PageId>,
+    pub internal_pages: u64,
+}
+
+impl TreeLayout {
+    /// Geometric mean of the page distance between consecutive leaves: 1.0
+    /// means the leaves are laid out sequentially. Unlike the arithmetic
+    /// mean, a few long jumps between otherwise sequential runs barely move
+    /// it, while leaves scattered every few pages do. `None` with fewer than
+    /// two leaves.
+    pub fn mean_leaf_gap(&self) -> Option<f64> {
+        if self.leaf_pages.len() < 2 {
+            return None;
+        }
+        let log_sum: f64 = self
+            .leaf_pages
+            .windows(2)
+            .map(|w| (w[0].abs_diff(w[1]).max(1) as f64).ln())
+            .sum();
+        Some((log_sum / (self.leaf_pages.len() - 1) as f64).exp())
+    }
+}
+
+/// A finished node of the level being built: its page and key range.
+struct BuiltNode {
+    page_id: PageId,
+    first_key: Vec<u8>,
+    last_key: Vec<u8>,
+}
+
+impl BTree {
+    /// Collect the tree's node pages. Only internal pages are read: all leaves
+    /// sit at the same depth, found by descending the leftmost path.
+    pub fn layout(&self, pager: &mut impl PageStore) -> Result<TreeLayout> {
+        let mut height = 0usize;
+        let mut page_id = self.root_page_id();
+        loop {
+            if height > MAX_BTREE_DEPTH {
+                return Err(MuroError::Corruption(
+                    "B-tree depth exceeds maximum (possible cycle)".into(),
+                ));
+            }
+            let page = pager.read_page(page_id)?;
+            match node_type(&page) {
+                Some(NodeType::Leaf) => break,
+                Some(NodeType::Internal) => {
+                    page_id = if num_entries(&page) > 0 {
+                        internal_left_child(&page, 0)
+                    } else {
+                        right_child(&page)
+                    }
+                    .ok_or(MuroError::InvalidPage)?;
+                    height += 1;
+                }
+                None => return Err(MuroError::InvalidPage),
+            }
+        }
+
+        let mut layout = TreeLayout::default();
+        collect_leaves(pager, self.root_page_id(), height, &mut layout)?;
+        Ok(layout)
+    }
+
+    /// Build a tree from entries in ascending key order, filling leaves to
+    /// `fill_factor` percent and packing internal nodes full. Overflow chains,
+    /// the leaves, and then each internal level are allocated as contiguous
+    /// runs near `hint`.
+    pub fn bulk_load(
+        pager: &mut impl PageStore,
+        entries: &[(Vec<u8>, Vec<u8>)],
+        hint: PageId,
+        fill_factor: u8,
+    ) -> Result<BTree> {
+        if entries.is_empty() {
+            return Ok(BTree::create(pager)?.with_fill_factor(fill_factor));
+        }
+        let builder = BTree::open(hint);
+        let mut cells = Vec::with_capacity(entries.len());
+        for (key, value) in entries {
+            cells.push(builder.encode_cell_with_overflow(pager, key, value, hint)?);
+        }
+
+        let groups = pack_leaf_cells(&cells, fill_factor)?;
+        let pages = pager.allocate_pages_near(groups.len(), hint)?;
+        let mut level = Vec::with_capacity(groups.len());
+        for (mut page, range) in pages.into_iter().zip(groups) {
+            init_leaf(&mut page);
+            for cell in &cells[range.clone()] {
+                page.insert_cell(cell)?;
+            }
+            pager.write_page(&page)?;
+            level.push(BuiltNode {
+                page_id: page.page_id(),
+                first_key: entries[range.start].0.clone(),
+                last_key: entries[range.end - 1].0.clone(),
+            });
+        }
+
+        while level.len() > 1 {
+            level = build_internal_level(pager, level, hint)?;
+        }
+        Ok(BTree::open(level[0].page_id).with_fill_factor(fill_factor))
+    }
+}
+
+fn collect_leaves(
+    pager: &mut impl PageStore,
+    page_id: PageId,
+    height: usize,
+    layout: &mut TreeLayout,
+) -> Result<()> {
+    if height == 0 {
+        layout.leaf_pages.push(page_id);
+        return Ok(());
+    }
+    let page = pager.read_page(page_id)?;
+    if node_type(&page) != Some(NodeType::Internal) {
+        return Err(MuroError::Corruption(format!(
+            "B-tree page {} is not internal at height {}",
+            page_id, height
+        )));
+    }
+    layout.internal_pages += 1;
+    for i in 0..num_entries(&page) {
+        let child = internal_left_child(&page, i).ok_or(MuroError::InvalidPage)?;
+        collect_leaves(pager, child, height - 1, layout)?;
+    }
+    let last = right_child(&page).ok_or(MuroError::InvalidPage)?;
+    collect_leaves(pager, last, height - 1, layout)
+}
+
+/// Split leaf `cells` into consecutive ranges that each fill one fresh leaf
+/// to `fill_factor` percent; a cell larger than that still gets a leaf.
+fn pack_leaf_cells(cells: &[Vec<u8>], fill_factor: u8) -> Result<Vec<std::ops::Range<usize>>> {
+    let target = leaf_fill_target(fill_factor);
+    let capacity = leaf_fill_target(100);
+    let mut groups = Vec::new();
+    let mut start = 0;
+    let mut used = 0;
+    for (i, cell) in cells.iter().enumerate() {
+        let size = leaf_cell_footprint(cell);
+        if size > capacity {
+            return Err(MuroError::PageOverflow);
+        }
+        if i > start && used + size > target {
+            groups.push(start..i);
+            start = i;
+            used = 0;
+        }
+        used += size;
+    }
+    groups.push(start..cells.len());
+    Ok(groups)
+}
+
+/// Build the parents of `children`. Each internal node takes as many children
+/// as fit; its last child becomes the right-most pointer.
+fn build_internal_level(
+    pager: &mut impl PageStore,
+    children: Vec<BuiltNode>,
+    hint: PageId,
+) -> Result<Vec<BuiltNode>> {
+    // Cell i points at child i, separated from child i + 1 by its first key.
+    let cells: Vec<Vec<u8>> = children
+        .windows(2)
+        .map(|w| {
+            encode_internal_cell_with_fence(w[0].page_id, &w[1].first_key, Some(&w[0].last_key))
+        })
+        .collect();
+    // A node with children a..=b holds cells a..b; the cell of its right-most
+    // child b is dropped, the separator moving up to the parent level.
+    let mut groups = Vec::new();
+    let mut start = 0;
+    while start < children.len() {
+        let mut scratch = Page::new(0);
+        init_internal(&mut scratch, 0);
+        let mut right = start;
+        while right + 1 < children.len() && scratch.insert_cell(&cells[right]).is_ok() {
+            right += 1;
+        }
+        groups.push(start..right);
+        start = right + 1;
+    }
+    if groups.len() == children.len() {
+        // Not a single separator fits: the level would never shrink.
+        return Err(MuroError::PageOverflow);
+    }
+
+    let pages = pager.allocate_pages_near(groups.len(), hint)?;
+    let mut parents = Vec::with_capacity(groups.len());
+    for (mut page, range) in pages.into_iter().zip(groups) {
+        let right = &children[range.end];
+        init_internal(&mut page, right.page_id);
+        for cell in &cells[range.start..range.end] {
+            page.insert_cell(cell)?;
+        }
+        pager.write_page(&page)?;
+        parents.push(BuiltNode {
+            page_id: page.page_id(),
+            first_key: children[range.start].first_key.clone(),
+            last_key: right.last_key.clone(),
+        });
+    }
+    Ok(parents)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::btree::key_encoding::encode_i64;
+    use crate::btree::ops::DEFAULT_FILL_FACTOR;
+    use crate::crypto::aead::MasterKey;
+    use crate::storage::pager::Pager;
+    use tempfile::NamedTempFile;
+
+    fn setup() -> (Pager, std::path::PathBuf) {
+        let tmp = NamedTempFile::new().unwrap();
+        let path = tmp.path().to_path_buf();
+        drop(tmp);
+        std::fs::remove_file(&path).ok();
+        let key = MasterKey::new([0x42u8; 32]);
+        let pager = Pager::create(&path, &key).unwrap();
+        (pager, path)
+    }
+
+    #[test]
+    fn test_bulk_load_matches_inserted_tree() {
+        let (mut pager, path) = setup();
+        let entries: Vec<(Vec<u8>, Vec<u8>)> = (0..3000i64)
+            .map(|i| {
+                let len = if i % 500 == 0 {
+                    9000
+                } else {
+                    (i as usize * 31) % 200
+                };
+                (encode_i64(i).to_vec(), vec![b'v'; len])
+            })
+            .collect();
+        let btree = BTree::bulk_load(&mut pager, &entries, 0, DEFAULT_FILL_FACTOR).unwrap();
+
+        let check = btree.verify(&mut pager, |_, _| {});
+        assert!(check.is_ok(), "{:?}", check.problems);
+        assert_eq!(check.entries, 3000);
+        let mut scanned = Vec::new();
+        btree
+            .scan(&mut pager, |k, v| {
+                scanned.push((k.to_vec(), v.to_vec()));
+                Ok(true)
+            })
+            .unwrap();
+        assert_eq!(scanned, entries);
+        assert_eq!(
+            btree.search(&mut pager, &encode_i64(1500)).unwrap(),
+            Some(entries[1500].1.clone())
+        );
+
+        // Leaves were allocated as one run.
+        let layout = btree.layout(&mut pager).unwrap();
+        assert!(layout.leaf_pages.len() > 10);
+        assert!(layout.internal_pages >= 1);
+        assert_eq!(layout.mean_leaf_gap(), Some(1.0));
+        let scattered = TreeLayout {
+            leaf_pages: vec![10, 12, 14, 16],
+            internal_pages: 1,
+        };
+        assert!((scattered.mean_leaf_gap().unwrap() - 2.0).abs() < 1e-9);
+
+        // The tree stays writable.
+        let mut btree = btree;
+        btree.insert(&mut pager, &encode_i64(-1), b"first").unwrap();
+        btree.delete(&mut pager, &encode_i64(10)).unwrap();
+        assert!(btree.verify(&mut pager, |_, _| {}).is_ok());
+        std::fs::remove_file(&path).ok();
+    }
+
+    #[test]
+    fn test_bulk_load_honors_fill_factor() {
+        let (mut pager, path) = setup();
+        let entries: Vec<(Vec<u8>, Vec<u8>)> = (0..2000i64)
+            .map(|i| (encode_i64(i).to_vec(), vec![b'v'; 100]))
+            .collect();
+        let full = BTree::bulk_load(&mut pager, &entries, 0, DEFAULT_FILL_FACTOR).unwrap();
+        let half = BTree::bulk_load(&mut pager, &entries, 0, 50).unwrap();
+        assert_eq!(half.fill_factor(), 50);
+        assert!(half.verify(&mut pager, |_, _| {}).is_ok());
+
+        let full_leaves = full.layout(&mut pager).unwrap().leaf_pages.len();
+        let half_leaves = half.layout(&mut pager).unwrap().leaf_pages.len();
+        assert!(
+            half_leaves >= full_leaves * 19 / 10 && half_leaves <= full_leaves * 21 / 10 + 1,
+            "{} vs {}",
+            half_leaves,
+            full_leaves
+        );
+        std::fs::remove_file(&path).ok();
+    }
+
+    #[test]
+    fn test_layout_of_single_leaf_tree() {
+        let (mut pager, path) = setup();
+        let btree = BTree::bulk_load(&mut pager, &[], 0, DEFAULT_FILL_FACTOR).unwrap();
+        let layout = btree.layout(&mut pager).unwrap();
+        assert_eq!(layout.leaf_pages, vec![btree.root_page_id()]);
+        assert_eq!(layout.internal_pages, 0);
+        assert_eq!(layout.mean_leaf_gap(), None);
+        std::fs::remove_file(&path).ok();
+    }
+}
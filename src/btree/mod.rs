@@ -1,4 +1,7 @@
 pub mod cursor;
 pub mod key_encoding;
+pub mod layout;
 pub mod node;
 pub mod ops;
+pub mod salvage;
+pub mod verify;
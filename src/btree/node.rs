@@ -14,9 +14,15 @@
 ///   All value data is stored in overflow pages; no inline prefix.
 ///
 /// Internal cell layout:
-///   [left_child: u64] [key_len: u16] [key bytes]
+///   [left_child: u64] [key_len: u16] [key bytes] ([fence_len: u16] [fence bytes])
+///
+/// The optional fence is an upper bound (inclusive) on the keys in the left
+/// child's subtree, normally its highest key. It lets scans and lookups skip a
+/// child without reading it. Cells written before fences existed, or whose
+/// fence would exceed `MAX_FENCE_LEN`, carry none and are always descended.
 ///
 /// For internal nodes, the right-most child pointer is stored in the node header.
+/// The right-most child has no fence: the parent's own bound already covers it.
 use crate::storage::page::{
     Page, PageId, CELL_HEADER_SIZE, CELL_POINTER_SIZE, PAGE_HEADER_SIZE, PAGE_SIZE,
 };
@@ -30,14 +36,39 @@ pub const OVERFLOW_FLAG: u16 = 0x8000;
 /// Overhead of overflow metadata in a leaf cell: total_value_len(4) + first_overflow_page(8) = 12.
 const OVERFLOW_META_SIZE: usize = 4 + 8;
 
+/// Longest key stored as a fence; longer fences are omitted to keep fan-out.
+pub const MAX_FENCE_LEN: usize = 256;
+
 /// Maximum cell payload that fits in a fresh leaf page (with header cell already inserted).
 /// = PAGE_SIZE - PAGE_HEADER_SIZE - (header cell: pointer + header + 1 byte payload) - (this cell: pointer + header)
 /// = 4096 - 14 - (2 + 2 + 1) - (2 + 2) = 4073
-const MAX_LEAF_CELL_PAYLOAD: usize = PAGE_SIZE
+pub const MAX_LEAF_CELL_PAYLOAD: usize = PAGE_SIZE
     - PAGE_HEADER_SIZE
     - (CELL_POINTER_SIZE + CELL_HEADER_SIZE + 1)
     - (CELL_POINTER_SIZE + CELL_HEADER_SIZE);
 
+/// Longest key a B-tree accepts: an internal page (header cell: type +
+/// right child) must hold two cells of this key length with a full fence,
+/// so every split leaves a key on each side.
+/// = (4096 - 14 - (2 + 2 + 9)) / 2 - (2 + 2 + 8 + 2 + 2 + 256) = 1762
+pub const MAX_KEY_LEN: usize =
+    (PAGE_SIZE - PAGE_HEADER_SIZE - (CELL_POINTER_SIZE + CELL_HEADER_SIZE + 9)) / 2
+        - (CELL_POINTER_SIZE + CELL_HEADER_SIZE + 8 + 2 + 2 + MAX_FENCE_LEN);
+
+/// Space for cells in a fresh leaf page, after the page and node headers.
+const LEAF_CELL_SPACE: usize =
+    PAGE_SIZE - PAGE_HEADER_SIZE - (CELL_POINTER_SIZE + CELL_HEADER_SIZE + 1);
+
+/// Leaf cell space filled at `fill_factor` percent.
+pub fn leaf_fill_target(fill_factor: u8) -> usize {
+    LEAF_CELL_SPACE * fill_factor as usize / 100
+}
+
+/// Leaf space a cell takes: its payload, length prefix, and cell pointer.
+pub fn leaf_cell_footprint(payload: &[u8]) -> usize {
+    CELL_POINTER_SIZE + CELL_HEADER_SIZE + payload.len()
+}
+
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum NodeType {
     Leaf,
@@ -66,7 +97,7 @@ pub fn init_internal(page: &mut Page, right_child: PageId) {
 /// Get the node type from a page.
 pub fn node_type(page: &Page) -> Option<NodeType> {
     let header = page.cell(0)?;
-    match header[0] {
+    match *header.first()? {
         NODE_TYPE_LEAF => Some(NodeType::Leaf),
         NODE_TYPE_INTERNAL => Some(NodeType::Internal),
         _ => None,
@@ -260,6 +291,35 @@ pub fn encode_internal_cell(left_child: PageId, key: &[u8]) -> Vec<u8> {
     buf
 }
 
+/// Encode an internal cell with an optional fence for its left child.
+/// Fences longer than `MAX_FENCE_LEN` are dropped.
+pub fn encode_internal_cell_with_fence(
+    left_child: PageId,
+    key: &[u8],
+    fence: Option<&[u8]>,
+) -> Vec<u8> {
+    let mut buf = encode_internal_cell(left_child, key);
+    if let Some(fence) = fence.filter(|f| f.len() <= MAX_FENCE_LEN) {
+        buf.extend_from_slice(&(fence.len() as u16).to_le_bytes());
+        buf.extend_from_slice(fence);
+    }
+    buf
+}
+
+/// Fence of an internal cell, or None if it has none.
+pub fn internal_cell_fence(cell: &[u8]) -> Option<&[u8]> {
+    if cell.len() < 10 {
+        return None;
+    }
+    let key_end = 10 + u16::from_le_bytes([cell[8], cell[9]]) as usize;
+    let fence_start = key_end + 2;
+    if fence_start > cell.len() {
+        return None;
+    }
+    let fence_len = u16::from_le_bytes([cell[key_end], cell[key_end + 1]]) as usize;
+    cell.get(fence_start..fence_start + fence_len)
+}
+
 /// Decode an internal cell into (left_child, key).
 pub fn decode_internal_cell(cell: &[u8]) -> Option<(PageId, &[u8])> {
     if cell.len() < 10 {
@@ -290,6 +350,24 @@ pub fn internal_left_child(page: &Page, entry_idx: u16) -> Option<PageId> {
     Some(left_child)
 }
 
+/// Get the fence of the i-th entry's left child in an internal node.
+pub fn internal_fence(page: &Page, entry_idx: u16) -> Option<&[u8]> {
+    internal_cell_fence(page.cell(entry_idx + 1)?)
+}
+
+/// Like `find_child`, but also returns the child's fence, if any.
+pub fn find_child_with_fence<'a>(page: &'a Page, key: &[u8]) -> Option<(PageId, Option<&'a [u8]>)> {
+    let n = num_entries(page);
+    for i in 0..n {
+        let cell = page.cell(i + 1)?;
+        let (left_child, entry_key) = decode_internal_cell(cell)?;
+        if key < entry_key {
+            return Some((left_child, internal_cell_fence(cell)));
+        }
+    }
+    right_child(page).map(|right| (right, None))
+}
+
 /// Find the child page to follow for a given key in an internal node.
 /// Returns the child page_id.
 pub fn find_child(page: &Page, key: &[u8]) -> Option<PageId> {
@@ -339,6 +417,30 @@ mod tests {
         assert_eq!(internal_left_child(&page, 0), Some(10));
     }
 
+    #[test]
+    fn test_internal_cell_fence_round_trip() {
+        let cell = encode_internal_cell_with_fence(10, b"m", Some(b"k"));
+        assert_eq!(decode_internal_cell(&cell), Some((10, b"m".as_slice())));
+        assert_eq!(internal_cell_fence(&cell), Some(b"k".as_slice()));
+
+        // Legacy cells and over-long fences carry no fence.
+        assert_eq!(internal_cell_fence(&encode_internal_cell(10, b"m")), None);
+        let long = vec![b'k'; MAX_FENCE_LEN + 1];
+        let cell = encode_internal_cell_with_fence(10, b"m", Some(&long));
+        assert_eq!(internal_cell_fence(&cell), None);
+
+        let mut page = Page::new(3);
+        init_internal(&mut page, 99);
+        page.insert_cell(&encode_internal_cell_with_fence(10, b"m", Some(b"f")))
+            .unwrap();
+        assert_eq!(internal_fence(&page, 0), Some(b"f".as_slice()));
+        assert_eq!(
+            find_child_with_fence(&page, b"a"),
+            Some((10, Some(b"f".as_slice())))
+        );
+        assert_eq!(find_child_with_fence(&page, b"z"), Some((99, None)));
+    }
+
     #[test]
     fn test_find_child() {
         let mut page = Page::new(3);
@@ -5,6 +5,7 @@
 use crate::btree::key_encoding::compare_keys;
 use crate::btree::node::*;
 use crate::error::{MuroError, Result};
+use crate::limits::Limit;
 use crate::storage::overflow;
 use crate::storage::page::{Page, PageId};
 use crate::storage::page_store::PageStore;
@@ -15,11 +16,43 @@ const MIN_ENTRIES: u16 = 2;
 /// Maximum B-tree depth to prevent stack overflow on corrupted trees.
 /// A 4096-byte page B-tree with 2 entries per internal node reaches depth 64
 /// at 2^64 pages, which is far beyond practical limits.
-const MAX_BTREE_DEPTH: usize = 64;
+pub(crate) const MAX_BTREE_DEPTH: usize = 64;
+
+/// Percent of a leaf filled when keys arrive in ascending order (bulk load
+/// and appends past the rightmost key) before a new leaf is started.
+pub const DEFAULT_FILL_FACTOR: u8 = 100;
+
+/// Lowest fill factor accepted by `WITH (fill_factor = N)`.
+pub const MIN_FILL_FACTOR: u8 = 10;
+
+/// Raw entry cells of an internal page, in key order.
+fn internal_cells(page: &Page) -> Result<Vec<Vec<u8>>> {
+    (0..num_entries(page))
+        .map(|i| {
+            page.cell(i + 1)
+                .map(<[u8]>::to_vec)
+                .ok_or(MuroError::InvalidPage)
+        })
+        .collect()
+}
+
+/// Build internal page `page_id` from `cells`, or None if they do not fit.
+fn build_internal(page_id: PageId, right: PageId, cells: &[Vec<u8>]) -> Option<Page> {
+    let mut page = Page::new(page_id);
+    init_internal(&mut page, right);
+    for cell in cells {
+        page.insert_cell(cell).ok()?;
+    }
+    Some(page)
+}
 
-/// B-tree handle. Tracks the root page.
+/// B-tree handle. Tracks the root page and the fill factor of ordered inserts.
 pub struct BTree {
     root_page_id: PageId,
+    fill_factor: u8,
+    /// Patch leaf pages in place when a cell fits; tests turn it off to
+    /// compare against always rebuilding the page.
+    leaf_fast_path: bool,
 }
 
 impl BTree {
@@ -29,14 +62,35 @@ impl BTree {
         let root_id = root.page_id();
         init_leaf(&mut root);
         pager.write_page(&root)?;
-        Ok(BTree {
-            root_page_id: root_id,
-        })
+        Ok(BTree::open(root_id))
     }
 
     /// Open an existing B-tree given the root page id.
     pub fn open(root_page_id: PageId) -> Self {
-        BTree { root_page_id }
+        BTree {
+            root_page_id,
+            fill_factor: DEFAULT_FILL_FACTOR,
+            leaf_fast_path: true,
+        }
+    }
+
+    /// Always rebuild a leaf page on insert and update, as before the
+    /// in-place fast path existed.
+    #[cfg(test)]
+    fn without_leaf_fast_path(mut self) -> Self {
+        self.leaf_fast_path = false;
+        self
+    }
+
+    /// Fill leaves to `fill_factor` percent when a key is appended past the
+    /// rightmost key, leaving the rest free for later updates.
+    pub fn with_fill_factor(mut self, fill_factor: u8) -> Self {
+        self.fill_factor = fill_factor;
+        self
+    }
+
+    pub fn fill_factor(&self) -> u8 {
+        self.fill_factor
     }
 
     pub fn root_page_id(&self) -> PageId {
@@ -71,22 +125,7 @@ impl BTree {
                     })?;
                     match compare_keys(key, k) {
                         std::cmp::Ordering::Equal => {
-                            // Check for overflow
-                            if is_overflow_cell(cell) {
-                                let (total_len, first_page) = decode_overflow_metadata(cell)
-                                    .ok_or_else(|| {
-                                        MuroError::Corruption(
-                                            "invalid overflow metadata in leaf cell".into(),
-                                        )
-                                    })?;
-                                let value =
-                                    overflow::read_overflow_chain(pager, first_page, total_len)?;
-                                return Ok(Some(value));
-                            }
-                            let (_, v) = decode_leaf_cell(cell).ok_or_else(|| {
-                                MuroError::Corruption("invalid leaf cell encoding".into())
-                            })?;
-                            return Ok(Some(v.to_vec()));
+                            return Ok(Some(leaf_cell_value(pager, cell)?));
                         }
                         std::cmp::Ordering::Less => return Ok(None),
                         std::cmp::Ordering::Greater => continue,
@@ -95,24 +134,174 @@ impl BTree {
                 Ok(None)
             }
             Some(NodeType::Internal) => {
-                let child_id = find_child(&page, key).ok_or(MuroError::InvalidPage)?;
+                let (child_id, fence) =
+                    find_child_with_fence(&page, key).ok_or(MuroError::InvalidPage)?;
+                if fence.is_some_and(|f| compare_keys(key, f) == std::cmp::Ordering::Greater) {
+                    // Above the child's highest key: not present.
+                    return Ok(None);
+                }
                 self.search_in_page(pager, child_id, key, depth + 1)
             }
             None => Err(MuroError::InvalidPage),
         }
     }
 
+    /// Search for many keys in one pass. The keys are visited in sorted
+    /// order, so every page on the way is read once however many of them
+    /// lead through it. Values come back in the order of `keys`; duplicate
+    /// keys each get the value.
+    pub fn get_many(
+        &self,
+        pager: &mut impl PageStore,
+        keys: &[Vec<u8>],
+    ) -> Result<Vec<Option<Vec<u8>>>> {
+        let mut order: Vec<usize> = (0..keys.len()).collect();
+        order.sort_by(|&a, &b| compare_keys(&keys[a], &keys[b]));
+        let mut values = vec![None; keys.len()];
+        if !order.is_empty() {
+            self.get_many_in_page(pager, self.root_page_id, keys, &order, &mut values, 0)?;
+        }
+        Ok(values)
+    }
+
+    /// Fill `values` for `keys[order[..]]`, which all route to `page_id`;
+    /// `order` sorts them.
+    fn get_many_in_page(
+        &self,
+        pager: &mut impl PageStore,
+        page_id: PageId,
+        keys: &[Vec<u8>],
+        order: &[usize],
+        values: &mut [Option<Vec<u8>>],
+        depth: usize,
+    ) -> Result<()> {
+        if depth > MAX_BTREE_DEPTH {
+            return Err(MuroError::Corruption(
+                "B-tree depth exceeds maximum (possible cycle)".into(),
+            ));
+        }
+        let page = pager.read_page(page_id)?;
+        match node_type(&page) {
+            Some(NodeType::Leaf) => {
+                // Merge the sorted keys with the sorted cells.
+                let mut next = 0;
+                for i in 0..num_entries(&page) {
+                    if next == order.len() {
+                        break;
+                    }
+                    let cell = page.cell(i + 1).ok_or(MuroError::InvalidPage)?;
+                    let (k, _) = decode_leaf_cell(cell).ok_or_else(|| {
+                        MuroError::Corruption("invalid leaf cell encoding".into())
+                    })?;
+                    while next < order.len()
+                        && compare_keys(&keys[order[next]], k) == std::cmp::Ordering::Less
+                    {
+                        next += 1;
+                    }
+                    let matched = order[next..]
+                        .iter()
+                        .take_while(|&&j| keys[j].as_slice() == k)
+                        .count();
+                    if matched > 0 {
+                        let value = leaf_cell_value(pager, cell)?;
+                        for &j in &order[next..next + matched] {
+                            values[j] = Some(value.clone());
+                        }
+                        next += matched;
+                    }
+                }
+                Ok(())
+            }
+            Some(NodeType::Internal) => {
+                let mut rest = order;
+                for i in 0..num_entries(&page) {
+                    if rest.is_empty() {
+                        return Ok(());
+                    }
+                    let cell = page.cell(i + 1).ok_or(MuroError::InvalidPage)?;
+                    let (left, entry_key) = decode_internal_cell(cell).ok_or_else(|| {
+                        MuroError::Corruption("invalid internal cell encoding".into())
+                    })?;
+                    let split = rest.partition_point(|&j| {
+                        compare_keys(&keys[j], entry_key) == std::cmp::Ordering::Less
+                    });
+                    let (mut here, after) = rest.split_at(split);
+                    if let Some(fence) = internal_cell_fence(cell) {
+                        // Keys above the child's highest key are not present.
+                        let within = here.partition_point(|&j| {
+                            compare_keys(&keys[j], fence) != std::cmp::Ordering::Greater
+                        });
+                        here = &here[..within];
+                    }
+                    if !here.is_empty() {
+                        self.get_many_in_page(pager, left, keys, here, values, depth + 1)?;
+                    }
+                    rest = after;
+                }
+                if !rest.is_empty() {
+                    let right = right_child(&page).ok_or(MuroError::InvalidPage)?;
+                    self.get_many_in_page(pager, right, keys, rest, values, depth + 1)?;
+                }
+                Ok(())
+            }
+            None => Err(MuroError::InvalidPage),
+        }
+    }
+
     /// Insert a key-value pair. If key exists, update the value.
     pub fn insert(&mut self, pager: &mut impl PageStore, key: &[u8], value: &[u8]) -> Result<()> {
-        let result = self.insert_into_page(pager, self.root_page_id, key, value, 0)?;
+        self.insert_from_root(pager, key, value, false)?;
+        Ok(())
+    }
+
+    /// Insert a key-value pair unless the key is already present, in one
+    /// descent. Returns false, leaving the existing entry untouched, if it was.
+    pub fn insert_if_absent(
+        &mut self,
+        pager: &mut impl PageStore,
+        key: &[u8],
+        value: &[u8],
+    ) -> Result<bool> {
+        self.insert_from_root(pager, key, value, true)
+    }
 
-        if let Some(split) = result {
+    /// Insert from the root, growing the tree if the root splits. With
+    /// `keep_existing` an existing key is left as is. Returns false if it
+    /// was.
+    fn insert_from_root(
+        &mut self,
+        pager: &mut impl PageStore,
+        key: &[u8],
+        value: &[u8],
+        keep_existing: bool,
+    ) -> Result<bool> {
+        let result = self.insert_into_page(
+            pager,
+            self.root_page_id,
+            key,
+            value,
+            0,
+            InsertMode {
+                rightmost: true,
+                keep_existing,
+            },
+        )?;
+
+        let split = match result {
+            Inserted::Existing => return Ok(false),
+            Inserted::Written(split) => split,
+        };
+        if let Some(split) = split {
             // Root was split; create a new root
-            let mut new_root = pager.allocate_page()?;
+            let mut new_root = pager.allocate_page_near(self.root_page_id)?;
             let new_root_id = new_root.page_id();
             init_internal(&mut new_root, split.right_page_id);
 
-            let cell = encode_internal_cell(self.root_page_id, &split.median_key);
+            let cell = encode_internal_cell_with_fence(
+                self.root_page_id,
+                &split.median_key,
+                split.left_max.as_deref(),
+            );
             new_root
                 .insert_cell(&cell)
                 .map_err(|_| MuroError::PageOverflow)?;
@@ -120,10 +309,10 @@ impl BTree {
             self.root_page_id = new_root_id;
         }
 
-        Ok(())
+        Ok(true)
     }
 
-    /// Result of inserting into a node that caused a split.
+    /// Insert into the subtree at `page_id`.
     fn insert_into_page(
         &mut self,
         pager: &mut impl PageStore,
@@ -131,7 +320,8 @@ impl BTree {
         key: &[u8],
         value: &[u8],
         depth: usize,
-    ) -> Result<Option<SplitResult>> {
+        mode: InsertMode,
+    ) -> Result<Inserted> {
         if depth > MAX_BTREE_DEPTH {
             return Err(MuroError::Corruption(
                 "B-tree depth exceeds maximum (possible cycle)".into(),
@@ -140,19 +330,22 @@ impl BTree {
         let page = pager.read_page(page_id)?;
 
         match node_type(&page) {
-            Some(NodeType::Leaf) => self.insert_into_leaf(pager, page, key, value),
-            Some(NodeType::Internal) => self.insert_into_internal(pager, page, key, value, depth),
+            Some(NodeType::Leaf) => self.insert_into_leaf(pager, page, key, value, mode),
+            Some(NodeType::Internal) => {
+                self.insert_into_internal(pager, page, key, value, depth, mode)
+            }
             None => Err(MuroError::InvalidPage),
         }
     }
 
     fn insert_into_leaf(
-        &self,
+        &mut self,
         pager: &mut impl PageStore,
-        page: Page,
+        mut page: Page,
         key: &[u8],
         value: &[u8],
-    ) -> Result<Option<SplitResult>> {
+        mode: InsertMode,
+    ) -> Result<Inserted> {
         let page_id = page.page_id();
         let n = num_entries(&page);
 
@@ -162,6 +355,9 @@ impl BTree {
             let (k, _) = decode_leaf_cell(old_cell)
                 .ok_or_else(|| MuroError::Corruption("invalid leaf cell encoding".into()))?;
             if compare_keys(key, k) == std::cmp::Ordering::Equal {
+                if mode.keep_existing {
+                    return Ok(Inserted::Existing);
+                }
                 // Free old overflow chain if the existing cell is overflow
                 if is_overflow_cell(old_cell) {
                     if let Some((_, first_page)) = decode_overflow_metadata(old_cell) {
@@ -170,24 +366,51 @@ impl BTree {
                 }
 
                 // Encode new cell (possibly with overflow)
-                let new_cell_bytes = self.encode_cell_with_overflow(pager, key, value)?;
+                let new_cell_bytes = self.encode_cell_with_overflow(pager, key, value, page_id)?;
+
+                // A cell of the same size, or one that fits in the free gap,
+                // is patched in place instead of rebuilding every cell.
+                if self.leaf_fast_path && page.replace_cell(i + 1, &new_cell_bytes).is_ok() {
+                    pager.write_page(&page)?;
+                    return Ok(Inserted::Written(None));
+                }
 
                 // Rebuild the page with updated value
                 let mut new_page = Page::new(page_id);
                 init_leaf(&mut new_page);
+                let mut fits = true;
                 for j in 0..n {
-                    if j == i {
-                        new_page
-                            .insert_cell(&new_cell_bytes)
-                            .map_err(|_| MuroError::PageOverflow)?;
-                    } else if let Some(cell_data) = page.cell(j + 1) {
-                        new_page
+                    let cell_data = if j == i {
+                        Some(new_cell_bytes.as_slice())
+                    } else {
+                        page.cell(j + 1)
+                    };
+                    if let Some(cell_data) = cell_data {
+                        if new_page.insert_cell(cell_data).is_err() {
+                            fits = false;
+                            break;
+                        }
+                    }
+                }
+                if fits {
+                    pager.write_page(&new_page)?;
+                    return Ok(Inserted::Written(None));
+                }
+
+                // The grown value no longer fits: drop the old cell and split
+                // as if the new cell were being inserted at its position.
+                let mut without_old = Page::new(page_id);
+                init_leaf(&mut without_old);
+                for j in (0..n).filter(|&j| j != i) {
+                    if let Some(cell_data) = page.cell(j + 1) {
+                        without_old
                             .insert_cell(cell_data)
                             .map_err(|_| MuroError::PageOverflow)?;
                     }
                 }
-                pager.write_page(&new_page)?;
-                return Ok(None);
+                return self
+                    .split_leaf_raw(pager, &without_old, &new_cell_bytes, i, false)
+                    .map(Inserted::Written);
             }
         }
 
@@ -204,9 +427,17 @@ impl BTree {
         }
 
         // Encode cell (possibly with overflow)
-        let cell = self.encode_cell_with_overflow(pager, key, value)?;
+        let cell = self.encode_cell_with_overflow(pager, key, value, page_id)?;
+        let append = mode.rightmost && pos == n;
+
+        if self.leaf_fast_path && page.insert_cell_at(pos + 1, &cell).is_ok() {
+            pager.write_page(&page)?;
+            return Ok(Inserted::Written(None));
+        }
 
-        // Rebuild page with the new entry at the correct position
+        // Not enough contiguous room: rebuild the page with the new entry at
+        // the correct position, which reclaims the space of replaced cells,
+        // and split if it still does not fit.
         let mut new_page = Page::new(page_id);
         init_leaf(&mut new_page);
 
@@ -215,31 +446,41 @@ impl BTree {
             if i == pos && !inserted {
                 if new_page.insert_cell(&cell).is_err() {
                     // Need to split — work with raw cells to preserve overflow pointers
-                    return self.split_leaf_raw(pager, &page, &cell, pos);
+                    return self
+                        .split_leaf_raw(pager, &page, &cell, pos, append)
+                        .map(Inserted::Written);
                 }
                 inserted = true;
             }
             if let Some(cell_data) = page.cell(i + 1) {
                 if new_page.insert_cell(cell_data).is_err() {
-                    return self.split_leaf_raw(pager, &page, &cell, pos);
+                    return self
+                        .split_leaf_raw(pager, &page, &cell, pos, append)
+                        .map(Inserted::Written);
                 }
             }
         }
         if !inserted && new_page.insert_cell(&cell).is_err() {
-            return self.split_leaf_raw(pager, &page, &cell, pos);
+            return self
+                .split_leaf_raw(pager, &page, &cell, pos, append)
+                .map(Inserted::Written);
         }
 
         pager.write_page(&new_page)?;
-        Ok(None)
+        Ok(Inserted::Written(None))
     }
 
-    /// Encode a key+value as a leaf cell, using overflow if needed.
-    fn encode_cell_with_overflow(
+    /// Encode a key+value as a leaf cell, using overflow if needed. The
+    /// overflow chain is allocated near `leaf_id`. Keys longer than
+    /// [`MAX_KEY_LEN`] are rejected.
+    pub(crate) fn encode_cell_with_overflow(
         &self,
         pager: &mut impl PageStore,
         key: &[u8],
         value: &[u8],
+        leaf_id: PageId,
     ) -> Result<Vec<u8>> {
+        Limit::KeyBytes.check(key.len())?;
         if needs_overflow(key, value) {
             let total_value_len = u32::try_from(value.len()).map_err(|_| {
                 MuroError::Execution(format!(
@@ -249,7 +490,7 @@ impl BTree {
                 ))
             })?;
             let mut cell = encode_overflow_leaf_cell(key, total_value_len);
-            let first_page = overflow::write_overflow_chain(pager, value)?;
+            let first_page = overflow::write_overflow_chain(pager, value, leaf_id)?;
             set_overflow_page_id(&mut cell, first_page);
             Ok(cell)
         } else {
@@ -258,12 +499,15 @@ impl BTree {
     }
 
     /// Split a leaf node, working with raw cell bytes to preserve overflow pointers.
+    /// `append` marks a key past the end of the rightmost leaf, which splits
+    /// at the fill factor instead of the midpoint.
     fn split_leaf_raw(
         &self,
         pager: &mut impl PageStore,
         old_page: &Page,
         new_cell: &[u8],
         insert_pos: u16,
+        append: bool,
     ) -> Result<Option<SplitResult>> {
         let old_id = old_page.page_id();
         let n = num_entries(old_page);
@@ -282,10 +526,17 @@ impl BTree {
             cells.push(new_cell.to_vec());
         }
 
-        let mid = cells.len() / 2;
+        let mid = if append {
+            self.append_split_point(&cells)
+        } else {
+            cells.len() / 2
+        };
         let (median_key, _) = decode_leaf_cell(&cells[mid])
             .ok_or_else(|| MuroError::Corruption("invalid leaf cell encoding".into()))?;
         let median_key = median_key.to_vec();
+        let cell_key = |cell: &[u8]| decode_leaf_cell(cell).map(|(k, _)| k.to_vec());
+        let left_max = cell_key(&cells[mid - 1]);
+        let right_max = cells.last().and_then(|cell| cell_key(cell));
 
         // Left page (reuse old page id)
         let mut left = Page::new(old_id);
@@ -296,7 +547,7 @@ impl BTree {
         }
 
         // Right page (new page)
-        let mut right = pager.allocate_page()?;
+        let mut right = pager.allocate_page_near(old_id)?;
         let right_id = right.page_id();
         init_leaf(&mut right);
         for cell in &cells[mid..] {
@@ -311,9 +562,34 @@ impl BTree {
         Ok(Some(SplitResult {
             median_key,
             right_page_id: right_id,
+            left_max,
+            right_max,
         }))
     }
 
+    /// Split point for a key appended to the rightmost leaf: the left page
+    /// keeps its cells up to the fill factor, so ascending inserts leave full
+    /// pages behind instead of half-empty ones. Falls back to the midpoint
+    /// when the remainder would not fit the right page.
+    fn append_split_point(&self, cells: &[Vec<u8>]) -> usize {
+        let target = leaf_fill_target(self.fill_factor);
+        let mut used = 0;
+        let mut mid = 0;
+        for cell in &cells[..cells.len() - 1] {
+            used += leaf_cell_footprint(cell);
+            if mid > 0 && used > target {
+                break;
+            }
+            mid += 1;
+        }
+        let right: usize = cells[mid..].iter().map(|c| leaf_cell_footprint(c)).sum();
+        if right > leaf_fill_target(100) {
+            cells.len() / 2
+        } else {
+            mid
+        }
+    }
+
     fn insert_into_internal(
         &mut self,
         pager: &mut impl PageStore,
@@ -321,7 +597,8 @@ impl BTree {
         key: &[u8],
         value: &[u8],
         depth: usize,
-    ) -> Result<Option<SplitResult>> {
+        mode: InsertMode,
+    ) -> Result<Inserted> {
         let page_id = page.page_id();
 
         // Find child to recurse into
@@ -340,67 +617,141 @@ impl BTree {
             }
         }
 
-        let split = self.insert_into_page(pager, child_page_id, key, value, depth + 1)?;
-
-        if let Some(split) = split {
-            // Child was split. Insert median key + right child into this internal node.
-            let page = pager.read_page(page_id)?;
+        let split = self.insert_into_page(
+            pager,
+            child_page_id,
+            key,
+            value,
+            depth + 1,
+            InsertMode {
+                rightmost: mode.rightmost && child_idx.is_none(),
+                ..mode
+            },
+        )?;
+
+        let split = match split {
+            Inserted::Existing => return Ok(Inserted::Existing),
+            Inserted::Written(split) => split,
+        };
+        let Some(split) = split else {
+            if let Some(i) = child_idx {
+                self.raise_fence(pager, &page, i, key)?;
+            }
+            return Ok(Inserted::Written(None));
+        };
+        // Child was split. Insert median key + right child into this internal node.
+        let page = pager.read_page(page_id)?;
 
-            // Find insertion position in internal node
-            let n = num_entries(&page);
-            let pos = child_idx.unwrap_or(n);
+        // Find insertion position in internal node
+        let n = num_entries(&page);
+        let pos = child_idx.unwrap_or(n);
 
-            // Rebuild page with new entry
-            let new_cell = encode_internal_cell(child_page_id, &split.median_key);
+        // Rebuild page with new entry
+        let new_cell = encode_internal_cell_with_fence(
+            child_page_id,
+            &split.median_key,
+            split.left_max.as_deref(),
+        );
 
-            // Collect all entries
-            let mut entries: Vec<Vec<u8>> = Vec::with_capacity(n as usize + 1);
-            for i in 0..n {
-                if let Some(cell_data) = page.cell(i + 1) {
-                    entries.push(cell_data.to_vec());
-                }
+        // Collect all entries
+        let mut entries: Vec<Vec<u8>> = Vec::with_capacity(n as usize + 1);
+        for i in 0..n {
+            if let Some(cell_data) = page.cell(i + 1) {
+                entries.push(cell_data.to_vec());
             }
+        }
+
+        let old_right = right_child(&page).ok_or(MuroError::InvalidPage)?;
 
-            let old_right = right_child(&page).ok_or(MuroError::InvalidPage)?;
+        entries.insert(pos as usize, new_cell);
+
+        if (pos as usize + 1) < entries.len() {
+            let old_entry = &entries[pos as usize + 1];
+            let (_, old_key) = decode_internal_cell(old_entry)
+                .ok_or_else(|| MuroError::Corruption("invalid internal cell encoding".into()))?;
+            // The right half keeps the old child's upper range.
+            let right_fence = split
+                .right_max
+                .clone()
+                .or_else(|| internal_cell_fence(old_entry).map(|f| f.max(key).to_vec()));
+            let new_entry = encode_internal_cell_with_fence(
+                split.right_page_id,
+                old_key,
+                right_fence.as_deref(),
+            );
+            entries[pos as usize + 1] = new_entry;
+        }
 
-            entries.insert(pos as usize, new_cell);
+        let new_right = if child_idx.is_none() {
+            split.right_page_id
+        } else {
+            old_right
+        };
 
-            if (pos as usize + 1) < entries.len() {
-                let old_entry = &entries[pos as usize + 1];
-                let (_, old_key) = decode_internal_cell(old_entry).ok_or_else(|| {
-                    MuroError::Corruption("invalid internal cell encoding".into())
-                })?;
-                let new_entry = encode_internal_cell(split.right_page_id, old_key);
-                entries[pos as usize + 1] = new_entry;
+        // Try to rebuild the page
+        let mut new_page = Page::new(page_id);
+        init_internal(&mut new_page, new_right);
+        let mut overflow = false;
+        for entry in &entries {
+            if new_page.insert_cell(entry).is_err() {
+                overflow = true;
+                break;
             }
+        }
 
-            let new_right = if child_idx.is_none() {
-                split.right_page_id
-            } else {
-                old_right
-            };
+        if overflow {
+            // Split this internal node
+            return self
+                .split_internal(pager, page_id, &entries, new_right)
+                .map(Inserted::Written);
+        }
 
-            // Try to rebuild the page
-            let mut new_page = Page::new(page_id);
-            init_internal(&mut new_page, new_right);
-            let mut overflow = false;
-            for entry in &entries {
-                if new_page.insert_cell(entry).is_err() {
-                    overflow = true;
-                    break;
+        pager.write_page(&new_page)?;
+        Ok(Inserted::Written(None))
+    }
+
+    /// After inserting `key` below entry `entry_idx` without a split, raise
+    /// that entry's fence if the key is above it. Entries without a fence stay
+    /// without one. If the longer fence no longer fits, it is dropped.
+    fn raise_fence(
+        &self,
+        pager: &mut impl PageStore,
+        page: &Page,
+        entry_idx: u16,
+        key: &[u8],
+    ) -> Result<()> {
+        let cell = page.cell(entry_idx + 1).ok_or(MuroError::InvalidPage)?;
+        match internal_cell_fence(cell) {
+            Some(fence) if compare_keys(key, fence) == std::cmp::Ordering::Greater => {}
+            _ => return Ok(()),
+        }
+        let (left_child, entry_key) = decode_internal_cell(cell)
+            .ok_or_else(|| MuroError::Corruption("invalid internal cell encoding".into()))?;
+        let right = right_child(page).ok_or(MuroError::InvalidPage)?;
+        let raised = encode_internal_cell_with_fence(left_child, entry_key, Some(key));
+        let dropped = encode_internal_cell(left_child, entry_key);
+        for replacement in [raised, dropped] {
+            let mut new_page = Page::new(page.page_id());
+            init_internal(&mut new_page, right);
+            let mut fits = true;
+            for i in 0..num_entries(page) {
+                let cell_data = if i == entry_idx {
+                    Some(replacement.as_slice())
+                } else {
+                    page.cell(i + 1)
+                };
+                if let Some(cell_data) = cell_data {
+                    if new_page.insert_cell(cell_data).is_err() {
+                        fits = false;
+                        break;
+                    }
                 }
             }
-
-            if overflow {
-                // Split this internal node
-                return self.split_internal(pager, page_id, &entries, new_right);
+            if fits {
+                return pager.write_page(&new_page);
             }
-
-            pager.write_page(&new_page)?;
-            return Ok(None);
         }
-
-        Ok(None)
+        Err(MuroError::PageOverflow)
     }
 
     fn split_internal(
@@ -416,6 +767,8 @@ impl BTree {
         let (median_left_child, median_key_bytes) = decode_internal_cell(&entries[mid])
             .ok_or_else(|| MuroError::Corruption("invalid internal cell encoding".into()))?;
         let median_key = median_key_bytes.to_vec();
+        // The left page's highest keys live under median_left_child.
+        let left_max = internal_cell_fence(&entries[mid]).map(<[u8]>::to_vec);
 
         // Left page: entries[0..mid], right child = median_left_child
         let mut left = Page::new(old_id);
@@ -426,7 +779,7 @@ impl BTree {
         }
 
         // Right page: entries[mid+1..], right child = current_right
-        let mut right = pager.allocate_page()?;
+        let mut right = pager.allocate_page_near(old_id)?;
         let right_id = right.page_id();
         init_internal(&mut right, current_right);
         for entry in &entries[mid + 1..] {
@@ -441,6 +794,8 @@ impl BTree {
         Ok(Some(SplitResult {
             median_key,
             right_page_id: right_id,
+            left_max,
+            right_max: None,
         }))
     }
 
@@ -449,15 +804,17 @@ impl BTree {
         let (deleted, _) = self.delete_from_page(pager, self.root_page_id, key, 0)?;
 
         if deleted {
-            // Check if root is an internal node with 0 entries
-            let root = pager.read_page(self.root_page_id)?;
-            if node_type(&root) == Some(NodeType::Internal) && num_entries(&root) == 0 {
-                // Collapse root: the single child becomes the new root
-                if let Some(child) = right_child(&root) {
-                    let old_root = self.root_page_id;
-                    self.root_page_id = child;
-                    pager.free_page(old_root);
+            // While the root is an internal node with 0 entries, its single
+            // child becomes the new root.
+            loop {
+                let root = pager.read_page(self.root_page_id)?;
+                if node_type(&root) != Some(NodeType::Internal) || num_entries(&root) != 0 {
+                    break;
                 }
+                let child = right_child(&root).ok_or(MuroError::InvalidPage)?;
+                let old_root = self.root_page_id;
+                self.root_page_id = child;
+                pager.free_page(old_root);
             }
         }
 
@@ -566,16 +923,18 @@ impl BTree {
     where
         F: FnMut(&[u8], &[u8]) -> Result<bool>,
     {
-        self.scan_page(pager, self.root_page_id, &mut callback, 0)
+        self.scan_page(pager, self.root_page_id, &mut callback, 0)?;
+        Ok(())
     }
 
+    /// Returns false once the callback has requested the scan to stop.
     fn scan_page<F>(
         &self,
         pager: &mut impl PageStore,
         page_id: PageId,
         callback: &mut F,
         depth: usize,
-    ) -> Result<()>
+    ) -> Result<bool>
     where
         F: FnMut(&[u8], &[u8]) -> Result<bool>,
     {
@@ -591,36 +950,22 @@ impl BTree {
                 let n = num_entries(&page);
                 for i in 0..n {
                     let cell = page.cell(i + 1).ok_or(MuroError::InvalidPage)?;
-                    let (k, v) = decode_leaf_cell(cell).ok_or_else(|| {
-                        MuroError::Corruption("invalid leaf cell encoding".into())
-                    })?;
-                    if is_overflow_cell(cell) {
-                        let (total_len, first_page) =
-                            decode_overflow_metadata(cell).ok_or_else(|| {
-                                MuroError::Corruption(
-                                    "invalid overflow metadata in leaf cell".into(),
-                                )
-                            })?;
-                        let full_value =
-                            overflow::read_overflow_chain(pager, first_page, total_len)?;
-                        if !callback(k, &full_value)? {
-                            return Ok(());
-                        }
-                    } else if !callback(k, v)? {
-                        return Ok(());
+                    if !visit_leaf_cell(pager, cell, callback)? {
+                        return Ok(false);
                     }
                 }
-                Ok(())
+                Ok(true)
             }
             Some(NodeType::Internal) => {
                 let n = num_entries(&page);
                 for i in 0..n {
                     let left = internal_left_child(&page, i).ok_or(MuroError::InvalidPage)?;
-                    self.scan_page(pager, left, callback, depth + 1)?;
+                    if !self.scan_page(pager, left, callback, depth + 1)? {
+                        return Ok(false);
+                    }
                 }
                 let right = right_child(&page).ok_or(MuroError::InvalidPage)?;
-                self.scan_page(pager, right, callback, depth + 1)?;
-                Ok(())
+                self.scan_page(pager, right, callback, depth + 1)
             }
             None => Err(MuroError::InvalidPage),
         }
@@ -636,7 +981,8 @@ impl BTree {
     where
         F: FnMut(&[u8], &[u8]) -> Result<bool>,
     {
-        self.scan_from_page(pager, self.root_page_id, start_key, &mut callback, 0)
+        self.scan_from_page(pager, self.root_page_id, start_key, &mut callback, 0)?;
+        Ok(())
     }
 
     fn scan_from_page<F>(
@@ -646,7 +992,7 @@ impl BTree {
         start_key: &[u8],
         callback: &mut F,
         depth: usize,
-    ) -> Result<()>
+    ) -> Result<bool>
     where
         F: FnMut(&[u8], &[u8]) -> Result<bool>,
     {
@@ -662,28 +1008,16 @@ impl BTree {
                 let n = num_entries(&page);
                 for i in 0..n {
                     let cell = page.cell(i + 1).ok_or(MuroError::InvalidPage)?;
-                    let (k, v) = decode_leaf_cell(cell).ok_or_else(|| {
+                    let (k, _) = decode_leaf_cell(cell).ok_or_else(|| {
                         MuroError::Corruption("invalid leaf cell encoding".into())
                     })?;
-                    if compare_keys(k, start_key) != std::cmp::Ordering::Less {
-                        if is_overflow_cell(cell) {
-                            let (total_len, first_page) = decode_overflow_metadata(cell)
-                                .ok_or_else(|| {
-                                    MuroError::Corruption(
-                                        "invalid overflow metadata in leaf cell".into(),
-                                    )
-                                })?;
-                            let full_value =
-                                overflow::read_overflow_chain(pager, first_page, total_len)?;
-                            if !callback(k, &full_value)? {
-                                return Ok(());
-                            }
-                        } else if !callback(k, v)? {
-                            return Ok(());
-                        }
+                    if compare_keys(k, start_key) != std::cmp::Ordering::Less
+                        && !visit_leaf_cell(pager, cell, callback)?
+                    {
+                        return Ok(false);
                     }
                 }
-                Ok(())
+                Ok(true)
             }
             Some(NodeType::Internal) => {
                 let n = num_entries(&page);
@@ -694,30 +1028,182 @@ impl BTree {
                         MuroError::Corruption("invalid internal cell encoding".into())
                     })?;
                     if !started && compare_keys(start_key, entry_key) == std::cmp::Ordering::Less {
+                        // A start key above the child's fence falls in the gap
+                        // before the next child: skip reading this one.
+                        let skip = internal_cell_fence(cell).is_some_and(|f| {
+                            compare_keys(start_key, f) == std::cmp::Ordering::Greater
+                        });
                         let left = internal_left_child(&page, i).ok_or(MuroError::InvalidPage)?;
-                        self.scan_from_page(pager, left, start_key, callback, depth + 1)?;
+                        if !skip
+                            && !self.scan_from_page(pager, left, start_key, callback, depth + 1)?
+                        {
+                            return Ok(false);
+                        }
                         started = true;
                     } else if started {
                         let left = internal_left_child(&page, i).ok_or(MuroError::InvalidPage)?;
-                        self.scan_page(pager, left, callback, depth + 1)?;
+                        if !self.scan_page(pager, left, callback, depth + 1)? {
+                            return Ok(false);
+                        }
                     }
                 }
-                if !started {
-                    // start_key >= all keys, scan from rightmost child
-                }
+                // If start_key >= all separator keys, scan from the rightmost child.
                 let right = right_child(&page).ok_or(MuroError::InvalidPage)?;
                 if started {
-                    self.scan_page(pager, right, callback, depth + 1)?;
+                    self.scan_page(pager, right, callback, depth + 1)
                 } else {
-                    self.scan_from_page(pager, right, start_key, callback, depth + 1)?;
+                    self.scan_from_page(pager, right, start_key, callback, depth + 1)
                 }
-                Ok(())
             }
             None => Err(MuroError::InvalidPage),
         }
     }
 
-    /// Try to rebalance an underfull child by merging with a sibling.
+    /// Iterate over all key-value pairs in descending key order.
+    /// Internal nodes are visited right-child-first, so returning `false`
+    /// from the callback after the first entry yields the maximum key
+    /// without touching any other leaf.
+    pub fn scan_rev<F>(&self, pager: &mut impl PageStore, mut callback: F) -> Result<()>
+    where
+        F: FnMut(&[u8], &[u8]) -> Result<bool>,
+    {
+        self.scan_page_rev(pager, self.root_page_id, &mut callback, 0)?;
+        Ok(())
+    }
+
+    fn scan_page_rev<F>(
+        &self,
+        pager: &mut impl PageStore,
+        page_id: PageId,
+        callback: &mut F,
+        depth: usize,
+    ) -> Result<bool>
+    where
+        F: FnMut(&[u8], &[u8]) -> Result<bool>,
+    {
+        if depth > MAX_BTREE_DEPTH {
+            return Err(MuroError::Corruption(
+                "B-tree depth exceeds maximum (possible cycle)".into(),
+            ));
+        }
+        let page = pager.read_page(page_id)?;
+
+        match node_type(&page) {
+            Some(NodeType::Leaf) => {
+                let n = num_entries(&page);
+                for i in (0..n).rev() {
+                    let cell = page.cell(i + 1).ok_or(MuroError::InvalidPage)?;
+                    if !visit_leaf_cell(pager, cell, callback)? {
+                        return Ok(false);
+                    }
+                }
+                Ok(true)
+            }
+            Some(NodeType::Internal) => {
+                let n = num_entries(&page);
+                let right = right_child(&page).ok_or(MuroError::InvalidPage)?;
+                if !self.scan_page_rev(pager, right, callback, depth + 1)? {
+                    return Ok(false);
+                }
+                for i in (0..n).rev() {
+                    let left = internal_left_child(&page, i).ok_or(MuroError::InvalidPage)?;
+                    if !self.scan_page_rev(pager, left, callback, depth + 1)? {
+                        return Ok(false);
+                    }
+                }
+                Ok(true)
+            }
+            None => Err(MuroError::InvalidPage),
+        }
+    }
+
+    /// Reverse range scan: iterate over entries where key <= start_key,
+    /// in descending key order.
+    pub fn scan_from_rev<F>(
+        &self,
+        pager: &mut impl PageStore,
+        start_key: &[u8],
+        mut callback: F,
+    ) -> Result<()>
+    where
+        F: FnMut(&[u8], &[u8]) -> Result<bool>,
+    {
+        self.scan_from_page_rev(pager, self.root_page_id, start_key, &mut callback, 0)?;
+        Ok(())
+    }
+
+    fn scan_from_page_rev<F>(
+        &self,
+        pager: &mut impl PageStore,
+        page_id: PageId,
+        start_key: &[u8],
+        callback: &mut F,
+        depth: usize,
+    ) -> Result<bool>
+    where
+        F: FnMut(&[u8], &[u8]) -> Result<bool>,
+    {
+        if depth > MAX_BTREE_DEPTH {
+            return Err(MuroError::Corruption(
+                "B-tree depth exceeds maximum (possible cycle)".into(),
+            ));
+        }
+        let page = pager.read_page(page_id)?;
+
+        match node_type(&page) {
+            Some(NodeType::Leaf) => {
+                let n = num_entries(&page);
+                for i in (0..n).rev() {
+                    let cell = page.cell(i + 1).ok_or(MuroError::InvalidPage)?;
+                    let (k, _) = decode_leaf_cell(cell).ok_or_else(|| {
+                        MuroError::Corruption("invalid leaf cell encoding".into())
+                    })?;
+                    if compare_keys(k, start_key) != std::cmp::Ordering::Greater
+                        && !visit_leaf_cell(pager, cell, callback)?
+                    {
+                        return Ok(false);
+                    }
+                }
+                Ok(true)
+            }
+            Some(NodeType::Internal) => {
+                // Locate the child that would contain start_key (same rule as
+                // search), scan it partially, then every child to its left in full.
+                let n = num_entries(&page);
+                let mut child_idx = n;
+                for i in 0..n {
+                    let cell = page.cell(i + 1).ok_or(MuroError::InvalidPage)?;
+                    let (_, entry_key) = decode_internal_cell(cell).ok_or_else(|| {
+                        MuroError::Corruption("invalid internal cell encoding".into())
+                    })?;
+                    if compare_keys(start_key, entry_key) == std::cmp::Ordering::Less {
+                        child_idx = i;
+                        break;
+                    }
+                }
+                let child = if child_idx == n {
+                    right_child(&page).ok_or(MuroError::InvalidPage)?
+                } else {
+                    internal_left_child(&page, child_idx).ok_or(MuroError::InvalidPage)?
+                };
+                if !self.scan_from_page_rev(pager, child, start_key, callback, depth + 1)? {
+                    return Ok(false);
+                }
+                for i in (0..child_idx).rev() {
+                    let left = internal_left_child(&page, i).ok_or(MuroError::InvalidPage)?;
+                    if !self.scan_page_rev(pager, left, callback, depth + 1)? {
+                        return Ok(false);
+                    }
+                }
+                Ok(true)
+            }
+            None => Err(MuroError::InvalidPage),
+        }
+    }
+
+    /// Try to rebalance an underfull child with a sibling: merge the two
+    /// into one page when they fit, otherwise (internal nodes only) move
+    /// entries across through the parent separator.
     /// `child_idx` is Some(i) if the child was found via entry i's left_child,
     /// or None if the child is the rightmost child.
     fn try_rebalance(
@@ -744,11 +1230,7 @@ impl BTree {
                 (left, right, 0u16)
             }
             Some(i) => {
-                let left = if i == 1 {
-                    internal_left_child(&parent, 0).ok_or(MuroError::InvalidPage)?
-                } else {
-                    internal_left_child(&parent, i - 1).ok_or(MuroError::InvalidPage)?
-                };
+                let left = internal_left_child(&parent, i - 1).ok_or(MuroError::InvalidPage)?;
                 let right = internal_left_child(&parent, i).ok_or(MuroError::InvalidPage)?;
                 (left, right, i - 1)
             }
@@ -762,16 +1244,28 @@ impl BTree {
         let left_page = pager.read_page(left_child_id)?;
         let right_page = pager.read_page(right_child_id)?;
 
-        let left_type = node_type(&left_page);
-        let right_type = node_type(&right_page);
-
-        // Only merge leaf nodes for now (simpler and most common case)
-        if left_type != Some(NodeType::Leaf) || right_type != Some(NodeType::Leaf) {
-            return Ok(());
+        match (node_type(&left_page), node_type(&right_page)) {
+            (Some(NodeType::Leaf), Some(NodeType::Leaf)) => {
+                self.merge_leaves(pager, &parent, separator_idx, &left_page, &right_page)
+            }
+            (Some(NodeType::Internal), Some(NodeType::Internal)) => {
+                self.rebalance_internal(pager, &parent, separator_idx, &left_page, &right_page)
+            }
+            _ => Ok(()),
         }
+    }
 
-        let left_entries = num_entries(&left_page);
-        let right_entries = num_entries(&right_page);
+    /// Merge two sibling leaves into the left one if all their cells fit.
+    fn merge_leaves(
+        &self,
+        pager: &mut impl PageStore,
+        parent: &Page,
+        separator_idx: u16,
+        left_page: &Page,
+        right_page: &Page,
+    ) -> Result<()> {
+        let left_entries = num_entries(left_page);
+        let right_entries = num_entries(right_page);
 
         // Collect all raw cells from both leaves (preserves overflow pointers)
         let mut all_cells: Vec<Vec<u8>> =
@@ -788,62 +1282,198 @@ impl BTree {
         }
 
         // Try to fit all cells into a single page
-        let mut merged = Page::new(left_child_id);
+        let mut merged = Page::new(left_page.page_id());
         init_leaf(&mut merged);
-        let mut fits = true;
         for cell in &all_cells {
             if merged.insert_cell(cell).is_err() {
-                fits = false;
-                break;
+                return Ok(());
             }
         }
 
+        // All entries fit in one page - merge successful
+        pager.write_page(&merged)?;
+        pager.free_page(right_page.page_id());
+        self.remove_separator(pager, parent, separator_idx, left_page.page_id())
+    }
+
+    /// Rebalance two sibling internal nodes, one of them underfull.
+    ///
+    /// If the entries of both plus the parent separator fit in one page, they
+    /// are merged into the left page and the right one is freed. Otherwise
+    /// entries rotate through the parent separator until the underfull side
+    /// has `MIN_ENTRIES` or the other side would drop below it. A rotation
+    /// that leaves any of the three pages too full is not applied.
+    fn rebalance_internal(
+        &self,
+        pager: &mut impl PageStore,
+        parent: &Page,
+        separator_idx: u16,
+        left_page: &Page,
+        right_page: &Page,
+    ) -> Result<()> {
+        let sep_cell = parent
+            .cell(separator_idx + 1)
+            .ok_or(MuroError::InvalidPage)?;
+        let (_, sep_key) = decode_internal_cell(sep_cell)
+            .ok_or_else(|| MuroError::Corruption("invalid internal cell encoding".into()))?;
+        // Bounds every key under the left page, so its rightmost child too.
+        let sep_fence = internal_cell_fence(sep_cell);
+
+        let mut left_cells = internal_cells(left_page)?;
+        let mut left_right = right_child(left_page).ok_or(MuroError::InvalidPage)?;
+        let mut right_cells = internal_cells(right_page)?;
+        let right_right = right_child(right_page).ok_or(MuroError::InvalidPage)?;
+
+        // Merge: the separator comes down over the left page's rightmost child.
+        let pulled_down = encode_internal_cell_with_fence(left_right, sep_key, sep_fence);
+        let mut merged = Page::new(left_page.page_id());
+        init_internal(&mut merged, right_right);
+        let fits = left_cells
+            .iter()
+            .chain(std::iter::once(&pulled_down))
+            .chain(&right_cells)
+            .all(|cell| merged.insert_cell(cell).is_ok());
         if fits {
-            // All entries fit in one page - merge successful
             pager.write_page(&merged)?;
-            pager.free_page(right_child_id);
-
-            // Remove the separator entry from the parent and update pointers
-            let parent = pager.read_page(parent_page_id)?;
-            let old_right = right_child(&parent).ok_or(MuroError::InvalidPage)?;
-            let mut new_parent = Page::new(parent_page_id);
-
-            let new_right = if separator_idx == n - 1 && child_idx.is_none() {
-                left_child_id
-            } else {
-                old_right
-            };
-
-            init_internal(&mut new_parent, new_right);
-            for i in 0..n {
-                if i == separator_idx {
-                    continue;
-                }
-                if let Some(cell_data) = parent.cell(i + 1) {
-                    if i == separator_idx + 1 {
-                        let (_, entry_key) = decode_internal_cell(cell_data).ok_or_else(|| {
-                            MuroError::Corruption("invalid internal cell encoding".into())
-                        })?;
-                        let new_cell = encode_internal_cell(left_child_id, entry_key);
-                        new_parent
-                            .insert_cell(&new_cell)
-                            .map_err(|_| MuroError::PageOverflow)?;
-                    } else {
-                        new_parent
-                            .insert_cell(cell_data)
-                            .map_err(|_| MuroError::PageOverflow)?;
-                    }
+            pager.free_page(right_page.page_id());
+            return self.remove_separator(pager, parent, separator_idx, left_page.page_id());
+        }
+
+        let mut sep_key = sep_key.to_vec();
+        let mut sep_fence = sep_fence.map(<[u8]>::to_vec);
+        let mut moved = false;
+        while left_cells.len() < MIN_ENTRIES as usize && right_cells.len() > MIN_ENTRIES as usize {
+            // Right to left: the separator comes down, R's first key goes up.
+            let first = right_cells.remove(0);
+            let (child, key) = decode_internal_cell(&first)
+                .ok_or_else(|| MuroError::Corruption("invalid internal cell encoding".into()))?;
+            left_cells.push(encode_internal_cell_with_fence(
+                left_right,
+                &sep_key,
+                sep_fence.as_deref(),
+            ));
+            left_right = child;
+            sep_key = key.to_vec();
+            sep_fence = internal_cell_fence(&first).map(<[u8]>::to_vec);
+            moved = true;
+        }
+        while right_cells.len() < MIN_ENTRIES as usize && left_cells.len() > MIN_ENTRIES as usize {
+            // Left to right: L's last key goes up, the separator comes down.
+            let last = left_cells.pop().ok_or(MuroError::InvalidPage)?;
+            let (child, key) = decode_internal_cell(&last)
+                .ok_or_else(|| MuroError::Corruption("invalid internal cell encoding".into()))?;
+            right_cells.insert(
+                0,
+                encode_internal_cell_with_fence(left_right, &sep_key, sep_fence.as_deref()),
+            );
+            left_right = child;
+            sep_key = key.to_vec();
+            sep_fence = internal_cell_fence(&last).map(<[u8]>::to_vec);
+            moved = true;
+        }
+        if !moved {
+            return Ok(());
+        }
+
+        let Some(new_left) = build_internal(left_page.page_id(), left_right, &left_cells) else {
+            return Ok(());
+        };
+        let Some(new_right) = build_internal(right_page.page_id(), right_right, &right_cells)
+        else {
+            return Ok(());
+        };
+        let mut parent_cells = internal_cells(parent)?;
+        parent_cells[separator_idx as usize] =
+            encode_internal_cell_with_fence(left_page.page_id(), &sep_key, sep_fence.as_deref());
+        let parent_right = right_child(parent).ok_or(MuroError::InvalidPage)?;
+        let Some(new_parent) = build_internal(parent.page_id(), parent_right, &parent_cells) else {
+            return Ok(());
+        };
+        pager.write_page(&new_left)?;
+        pager.write_page(&new_right)?;
+        pager.write_page(&new_parent)
+    }
+
+    /// Remove entry `separator_idx` from `parent` after the two children on
+    /// either side of it were merged into `merged_id`. The slot that pointed
+    /// at the right child, an entry or the rightmost pointer, now points at
+    /// the merged page.
+    fn remove_separator(
+        &self,
+        pager: &mut impl PageStore,
+        parent: &Page,
+        separator_idx: u16,
+        merged_id: PageId,
+    ) -> Result<()> {
+        let n = num_entries(parent);
+        let new_right = if separator_idx + 1 == n {
+            merged_id
+        } else {
+            right_child(parent).ok_or(MuroError::InvalidPage)?
+        };
+        let mut new_parent = Page::new(parent.page_id());
+        init_internal(&mut new_parent, new_right);
+        for i in 0..n {
+            if i == separator_idx {
+                continue;
+            }
+            if let Some(cell_data) = parent.cell(i + 1) {
+                if i == separator_idx + 1 {
+                    let (_, entry_key) = decode_internal_cell(cell_data).ok_or_else(|| {
+                        MuroError::Corruption("invalid internal cell encoding".into())
+                    })?;
+                    // The merged page ends where the right sibling did.
+                    let new_cell = encode_internal_cell_with_fence(
+                        merged_id,
+                        entry_key,
+                        internal_cell_fence(cell_data),
+                    );
+                    new_parent
+                        .insert_cell(&new_cell)
+                        .map_err(|_| MuroError::PageOverflow)?;
+                } else {
+                    new_parent
+                        .insert_cell(cell_data)
+                        .map_err(|_| MuroError::PageOverflow)?;
                 }
             }
+        }
+        pager.write_page(&new_parent)
+    }
 
-            if child_idx.is_none() {
-                set_right_child(&mut new_parent, left_child_id);
-            }
+    /// Whether the tree holds no entries, i.e. its root is an empty leaf.
+    pub fn is_empty(&self, pager: &mut impl PageStore) -> Result<bool> {
+        let root = pager.read_page(self.root_page_id)?;
+        Ok(node_type(&root) == Some(NodeType::Leaf) && num_entries(&root) == 0)
+    }
 
-            pager.write_page(&new_parent)?;
+    /// Approximate entry count from a single root-to-leaf descent: the
+    /// fanout of each internal page on the middle path times the entry count
+    /// of the leaf it reaches. Exact for a single-leaf tree; otherwise good
+    /// to within a small factor for trees filled by ordinary inserts.
+    pub fn estimate_entries(&self, pager: &mut impl PageStore) -> Result<u64> {
+        let mut page_id = self.root_page_id;
+        let mut multiplier: u64 = 1;
+        for _ in 0..=MAX_BTREE_DEPTH {
+            let page = pager.read_page(page_id)?;
+            let n = num_entries(&page);
+            match node_type(&page) {
+                Some(NodeType::Leaf) => return Ok(multiplier.saturating_mul(n as u64)),
+                Some(NodeType::Internal) => {
+                    multiplier = multiplier.saturating_mul(n as u64 + 1);
+                    let mid = n / 2;
+                    page_id = if mid < n {
+                        internal_left_child(&page, mid).ok_or(MuroError::InvalidPage)?
+                    } else {
+                        right_child(&page).ok_or(MuroError::InvalidPage)?
+                    };
+                }
+                None => return Err(MuroError::InvalidPage),
+            }
         }
-
-        Ok(())
+        Err(MuroError::Corruption(
+            "B-tree depth exceeds maximum (possible cycle)".into(),
+        ))
     }
 
     /// Collect all page IDs in this B-tree (for freeing), including overflow pages.
@@ -911,9 +1541,62 @@ impl BTree {
     }
 }
 
+/// The value of a leaf cell, read from its overflow chain if it has one.
+fn leaf_cell_value(pager: &mut impl PageStore, cell: &[u8]) -> Result<Vec<u8>> {
+    if is_overflow_cell(cell) {
+        let (total_len, first_page) = decode_overflow_metadata(cell).ok_or_else(|| {
+            MuroError::Corruption("invalid overflow metadata in leaf cell".into())
+        })?;
+        return overflow::read_overflow_chain(pager, first_page, total_len);
+    }
+    let (_, v) = decode_leaf_cell(cell)
+        .ok_or_else(|| MuroError::Corruption("invalid leaf cell encoding".into()))?;
+    Ok(v.to_vec())
+}
+
+/// Decode one leaf cell (reconstructing overflow values) and pass it to the callback.
+fn visit_leaf_cell<F>(pager: &mut impl PageStore, cell: &[u8], callback: &mut F) -> Result<bool>
+where
+    F: FnMut(&[u8], &[u8]) -> Result<bool>,
+{
+    let (k, v) = decode_leaf_cell(cell)
+        .ok_or_else(|| MuroError::Corruption("invalid leaf cell encoding".into()))?;
+    if is_overflow_cell(cell) {
+        let (total_len, first_page) = decode_overflow_metadata(cell).ok_or_else(|| {
+            MuroError::Corruption("invalid overflow metadata in leaf cell".into())
+        })?;
+        let full_value = overflow::read_overflow_chain(pager, first_page, total_len)?;
+        callback(k, &full_value)
+    } else {
+        callback(k, v)
+    }
+}
+
+/// How an insert descends the tree.
+#[derive(Clone, Copy)]
+struct InsertMode {
+    /// Set while descending the tree's right edge.
+    rightmost: bool,
+    /// Leave an existing key as is instead of overwriting its value.
+    keep_existing: bool,
+}
+
+/// Outcome of inserting into a subtree.
+enum Inserted {
+    /// The entry was written; `Some` if the subtree's root split.
+    Written(Option<SplitResult>),
+    /// The key was already present and `keep_existing` left it as is.
+    Existing,
+}
+
+/// Result of inserting into a node that caused a split.
 struct SplitResult {
     median_key: Vec<u8>,
     right_page_id: PageId,
+    /// Highest key left in the split page's subtree, if known.
+    left_max: Option<Vec<u8>>,
+    /// Highest key in the new right page's subtree, if known.
+    right_max: Option<Vec<u8>>,
 }
 
 #[cfg(test)]
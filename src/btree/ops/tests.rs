@@ -1,5 +1,5 @@
 use super::*;
-use crate::btree::key_encoding::encode_i64;
+use crate::btree::key_encoding::{decode_i64, encode_i64};
 use crate::crypto::aead::MasterKey;
 use crate::storage::pager::Pager;
 use tempfile::NamedTempFile;
@@ -60,6 +60,41 @@ fn test_update_existing_key() {
     std::fs::remove_file(&path).ok();
 }
 
+#[test]
+fn test_insert_if_absent_keeps_existing_value() {
+    let (mut pager, path) = setup();
+    let mut btree = BTree::create(&mut pager).unwrap();
+
+    // Enough keys to split, so some lookups go through internal pages.
+    for i in 0..2000i64 {
+        let inserted = btree
+            .insert_if_absent(&mut pager, &encode_i64(i * 2), b"first")
+            .unwrap();
+        assert!(inserted);
+    }
+    for i in 0..4000i64 {
+        let inserted = btree
+            .insert_if_absent(&mut pager, &encode_i64(i), b"second")
+            .unwrap();
+        assert_eq!(inserted, i % 2 == 1, "key {}", i);
+    }
+    for i in 0..4000i64 {
+        let expected: &[u8] = if i % 2 == 0 { b"first" } else { b"second" };
+        assert_eq!(
+            btree.search(&mut pager, &encode_i64(i)).unwrap().as_deref(),
+            Some(expected)
+        );
+    }
+    // A plain insert still overwrites.
+    btree.insert(&mut pager, &encode_i64(0), b"third").unwrap();
+    assert_eq!(
+        btree.search(&mut pager, &encode_i64(0)).unwrap(),
+        Some(b"third".to_vec())
+    );
+
+    std::fs::remove_file(&path).ok();
+}
+
 #[test]
 fn test_delete() {
     let (mut pager, path) = setup();
@@ -345,3 +380,778 @@ fn test_insert_delete_many() {
 
     std::fs::remove_file(&path).ok();
 }
+
+#[test]
+fn test_scan_rev_empty_and_single_leaf() {
+    let (mut pager, path) = setup();
+    let mut btree = BTree::create(&mut pager).unwrap();
+
+    let mut results = Vec::new();
+    btree
+        .scan_rev(&mut pager, |k, _| {
+            results.push(k.to_vec());
+            Ok(true)
+        })
+        .unwrap();
+    assert!(results.is_empty());
+
+    btree.insert(&mut pager, b"b", b"2").unwrap();
+    btree.insert(&mut pager, b"a", b"1").unwrap();
+    btree.insert(&mut pager, b"c", b"3").unwrap();
+
+    btree
+        .scan_rev(&mut pager, |k, v| {
+            results.push(k.to_vec());
+            assert_eq!(v.len(), 1);
+            Ok(true)
+        })
+        .unwrap();
+    assert_eq!(results, vec![b"c".to_vec(), b"b".to_vec(), b"a".to_vec()]);
+
+    std::fs::remove_file(&path).ok();
+}
+
+#[test]
+fn test_scan_rev_many_with_splits() {
+    let (mut pager, path) = setup();
+    let mut btree = BTree::create(&mut pager).unwrap();
+
+    let count = 500;
+    for i in 0..count {
+        let key = encode_i64(i);
+        btree
+            .insert(&mut pager, &key, format!("value_{}", i).as_bytes())
+            .unwrap();
+    }
+
+    let mut forward = Vec::new();
+    btree
+        .scan(&mut pager, |k, _| {
+            forward.push(k.to_vec());
+            Ok(true)
+        })
+        .unwrap();
+    let mut reverse = Vec::new();
+    btree
+        .scan_rev(&mut pager, |k, _| {
+            reverse.push(k.to_vec());
+            Ok(true)
+        })
+        .unwrap();
+    reverse.reverse();
+    assert_eq!(forward, reverse);
+    assert_eq!(forward.len(), count as usize);
+
+    std::fs::remove_file(&path).ok();
+}
+
+#[test]
+fn test_scan_early_stop_spans_leaves() {
+    let (mut pager, path) = setup();
+    let mut btree = BTree::create(&mut pager).unwrap();
+
+    for i in 0..500 {
+        btree.insert(&mut pager, &encode_i64(i), b"v").unwrap();
+    }
+
+    // A callback returning false must stop the whole traversal, not just the current leaf.
+    let mut calls = 0;
+    btree
+        .scan(&mut pager, |_, _| {
+            calls += 1;
+            Ok(false)
+        })
+        .unwrap();
+    assert_eq!(calls, 1);
+
+    let mut max_key = None;
+    let mut calls = 0;
+    btree
+        .scan_rev(&mut pager, |k, _| {
+            calls += 1;
+            max_key = Some(k.to_vec());
+            Ok(false)
+        })
+        .unwrap();
+    assert_eq!(calls, 1);
+    assert_eq!(max_key, Some(encode_i64(499).to_vec()));
+
+    std::fs::remove_file(&path).ok();
+}
+
+#[test]
+fn test_scan_from_rev() {
+    let (mut pager, path) = setup();
+    let mut btree = BTree::create(&mut pager).unwrap();
+
+    // Even keys only, so start keys can fall between entries.
+    for i in (0..600).step_by(2) {
+        btree.insert(&mut pager, &encode_i64(i), b"v").unwrap();
+    }
+
+    let collect_from = |pager: &mut Pager, start: i64| {
+        let mut keys = Vec::new();
+        btree
+            .scan_from_rev(pager, &encode_i64(start), |k, _| {
+                keys.push(decode_i64(k.try_into().unwrap()));
+                Ok(true)
+            })
+            .unwrap();
+        keys
+    };
+
+    // Exact match is inclusive.
+    let keys = collect_from(&mut pager, 300);
+    assert_eq!(keys.len(), 151);
+    assert_eq!(keys[0], 300);
+    assert_eq!(*keys.last().unwrap(), 0);
+    assert!(keys.windows(2).all(|w| w[0] > w[1]));
+
+    // Between two keys starts at the lower neighbour.
+    let keys = collect_from(&mut pager, 301);
+    assert_eq!(keys[0], 300);
+    assert_eq!(keys.len(), 151);
+
+    // Beyond the maximum key returns everything.
+    let keys = collect_from(&mut pager, 10_000);
+    assert_eq!(keys.len(), 300);
+    assert_eq!(keys[0], 598);
+
+    // Smaller than every key returns nothing.
+    let keys = collect_from(&mut pager, -1);
+    assert!(keys.is_empty());
+
+    std::fs::remove_file(&path).ok();
+}
+
+#[test]
+fn test_update_growing_value_splits_full_leaf() {
+    let (mut pager, path) = setup();
+    let mut btree = BTree::create(&mut pager).unwrap();
+
+    // Fill the leaves with small values, then grow each one in place.
+    for i in 0..200i64 {
+        btree.insert(&mut pager, &encode_i64(i), b"v").unwrap();
+    }
+    for i in 0..200i64 {
+        let big = vec![(i % 251) as u8; 300];
+        btree.insert(&mut pager, &encode_i64(i), &big).unwrap();
+    }
+
+    for i in 0..200i64 {
+        let got = btree.search(&mut pager, &encode_i64(i)).unwrap().unwrap();
+        assert_eq!(got, vec![(i % 251) as u8; 300]);
+    }
+    let mut count = 0;
+    btree
+        .scan(&mut pager, |_, _| {
+            count += 1;
+            Ok(true)
+        })
+        .unwrap();
+    assert_eq!(count, 200);
+
+    std::fs::remove_file(&path).ok();
+}
+
+/// Highest key in the subtree rooted at `page_id`.
+fn subtree_max(pager: &mut Pager, page_id: PageId) -> Vec<u8> {
+    let mut max = None;
+    BTree::open(page_id)
+        .scan_rev(pager, |k, _| {
+            max = Some(k.to_vec());
+            Ok(false)
+        })
+        .unwrap();
+    max.unwrap()
+}
+
+fn reads(pager: &Pager) -> u64 {
+    pager.cache_hits() + pager.cache_misses()
+}
+
+#[test]
+fn test_fences_track_child_maximum_on_insert() {
+    let (mut pager, path) = setup();
+    let mut btree = BTree::create(&mut pager).unwrap();
+    // Sequential bulk load, then fill gaps in random order.
+    for i in 0..3000i64 {
+        btree
+            .insert(&mut pager, &encode_i64(i * 2), &[0u8; 40])
+            .unwrap();
+    }
+    for i in 0..3000i64 {
+        btree
+            .insert(
+                &mut pager,
+                &encode_i64((i * 7919 % 3000) * 2 + 1),
+                &[1u8; 40],
+            )
+            .unwrap();
+    }
+    assert!(btree.verify(&mut pager, |_, _| {}).is_ok());
+
+    // With inserts only, every non-rightmost fence is exact, at every level.
+    let mut pending = vec![btree.root_page_id()];
+    let mut checked = 0;
+    while let Some(page_id) = pending.pop() {
+        let page = pager.read_page(page_id).unwrap();
+        if node_type(&page) != Some(NodeType::Internal) {
+            continue;
+        }
+        for i in 0..num_entries(&page) {
+            let child = internal_left_child(&page, i).unwrap();
+            let fence = internal_fence(&page, i).expect("fence").to_vec();
+            assert_eq!(fence, subtree_max(&mut pager, child));
+            pending.push(child);
+            checked += 1;
+        }
+        pending.push(right_child(&page).unwrap());
+    }
+    assert!(checked > 20);
+    std::fs::remove_file(&path).ok();
+}
+
+#[test]
+fn test_fences_stay_valid_after_deletes_and_merges() {
+    let (mut pager, path) = setup();
+    let mut btree = BTree::create(&mut pager).unwrap();
+    for i in 0..2000i64 {
+        btree
+            .insert(&mut pager, &encode_i64(i), &[0u8; 100])
+            .unwrap();
+    }
+    // Delete runs from the top of each leaf range, then re-insert some.
+    for i in (0..2000i64).filter(|i| i % 50 >= 20) {
+        assert!(btree.delete(&mut pager, &encode_i64(i)).unwrap());
+    }
+    assert!(btree.verify(&mut pager, |_, _| {}).is_ok());
+    for i in (0..2000i64).filter(|i| i % 50 == 45) {
+        btree
+            .insert(&mut pager, &encode_i64(i), &[2u8; 100])
+            .unwrap();
+    }
+    let check = btree.verify(&mut pager, |_, _| {});
+    assert!(check.is_ok(), "{:?}", check.problems);
+    assert_eq!(check.entries, 800 + 40);
+    std::fs::remove_file(&path).ok();
+}
+
+#[test]
+fn test_fence_skipping_never_misses_matches() {
+    let (mut pager, path) = setup();
+    let mut btree = BTree::create(&mut pager).unwrap();
+    let keys: Vec<i64> = (0..600i64).map(|i| i * 10).collect();
+    // Mixed order so splits happen all over the tree.
+    for n in 0..keys.len() {
+        let k = keys[(n * 389) % keys.len()];
+        btree
+            .insert(&mut pager, &encode_i64(k), &[0u8; 60])
+            .unwrap();
+    }
+    for start in -5..6010i64 {
+        let mut first = None;
+        btree
+            .scan_from(&mut pager, &encode_i64(start), |k, _| {
+                first = Some(decode_i64(k.try_into().unwrap()));
+                Ok(false)
+            })
+            .unwrap();
+        let expected = keys.iter().copied().find(|&k| k >= start);
+        assert_eq!(first, expected, "scan_from({})", start);
+        let found = btree.search(&mut pager, &encode_i64(start)).unwrap();
+        assert_eq!(found.is_some(), keys.contains(&start), "search({})", start);
+    }
+    std::fs::remove_file(&path).ok();
+}
+
+#[test]
+fn test_fence_skips_child_reads_and_legacy_cells_fall_back() {
+    let (mut pager, path) = setup();
+    let mut btree = BTree::create(&mut pager).unwrap();
+    for i in 0..300i64 {
+        btree
+            .insert(&mut pager, &encode_i64(i * 1000), &[0u8; 100])
+            .unwrap();
+    }
+    let root_id = btree.root_page_id();
+    let root = pager.read_page(root_id).unwrap();
+    assert_eq!(
+        node_type(
+            &pager
+                .read_page(internal_left_child(&root, 0).unwrap())
+                .unwrap()
+        ),
+        Some(NodeType::Leaf)
+    );
+    let fence = decode_i64(internal_fence(&root, 0).unwrap().try_into().unwrap());
+    let (_, separator) = decode_internal_cell(root.cell(1).unwrap()).unwrap();
+    let separator = decode_i64(separator.try_into().unwrap());
+    let gap = encode_i64(fence + 500);
+    assert!(fence + 500 < separator);
+
+    let first_from = |btree: &BTree, pager: &mut Pager| {
+        let mut first = None;
+        btree
+            .scan_from(pager, &gap, |k, _| {
+                first = Some(decode_i64(k.try_into().unwrap()));
+                Ok(false)
+            })
+            .unwrap();
+        first
+    };
+
+    // Root and the next leaf only; the leaf below the fence is never read.
+    let before = reads(&pager);
+    assert_eq!(first_from(&btree, &mut pager), Some(separator));
+    assert_eq!(reads(&pager) - before, 2);
+    let before = reads(&pager);
+    assert_eq!(btree.search(&mut pager, &gap).unwrap(), None);
+    assert_eq!(reads(&pager) - before, 1);
+
+    // Strip the fences, as in a tree written before they existed.
+    let mut legacy = Page::new(root_id);
+    init_internal(&mut legacy, right_child(&root).unwrap());
+    for i in 0..num_entries(&root) {
+        let (child, key) = decode_internal_cell(root.cell(i + 1).unwrap()).unwrap();
+        legacy
+            .insert_cell(&encode_internal_cell(child, key))
+            .unwrap();
+    }
+    pager.write_page(&legacy).unwrap();
+    let before = reads(&pager);
+    assert_eq!(first_from(&btree, &mut pager), Some(separator));
+    assert_eq!(reads(&pager) - before, 3);
+    assert_eq!(btree.search(&mut pager, &gap).unwrap(), None);
+    assert!(btree.verify(&mut pager, |_, _| {}).is_ok());
+
+    // New inserts below a legacy cell do not add a fence to it.
+    btree
+        .insert(&mut pager, &encode_i64(fence + 1), &[0u8; 10])
+        .unwrap();
+    let root = pager.read_page(btree.root_page_id()).unwrap();
+    assert_eq!(internal_fence(&root, 0), None);
+    std::fs::remove_file(&path).ok();
+}
+
+#[test]
+fn test_estimate_entries_tracks_tree_size() {
+    let (mut pager, path) = setup();
+    let mut btree = BTree::create(&mut pager).unwrap();
+    for i in 0..20i64 {
+        btree.insert(&mut pager, &encode_i64(i), b"v").unwrap();
+    }
+    // A single leaf is counted exactly.
+    assert_eq!(btree.estimate_entries(&mut pager).unwrap(), 20);
+
+    for i in 20..5000i64 {
+        btree
+            .insert(&mut pager, &encode_i64(i), &[0u8; 40])
+            .unwrap();
+    }
+    let est = btree.estimate_entries(&mut pager).unwrap();
+    assert!((2_500..=10_000).contains(&est), "estimate {}", est);
+    std::fs::remove_file(&path).ok();
+}
+
+#[test]
+fn test_is_empty() {
+    let (mut pager, path) = setup();
+    let mut btree = BTree::create(&mut pager).unwrap();
+    assert!(btree.is_empty(&mut pager).unwrap());
+    btree.insert(&mut pager, b"k", b"v").unwrap();
+    assert!(!btree.is_empty(&mut pager).unwrap());
+    btree.delete(&mut pager, b"k").unwrap();
+    assert!(btree.is_empty(&mut pager).unwrap());
+    std::fs::remove_file(&path).ok();
+}
+
+/// Leaf count of a tree built by inserting `keys` one at a time.
+fn leaves_after_inserts(fill_factor: u8, keys: &[i64]) -> usize {
+    let (mut pager, path) = setup();
+    let mut btree = BTree::create(&mut pager)
+        .unwrap()
+        .with_fill_factor(fill_factor);
+    for &i in keys {
+        btree
+            .insert(&mut pager, &encode_i64(i), &[b'v'; 100])
+            .unwrap();
+    }
+    let check = btree.verify(&mut pager, |_, _| {});
+    assert!(check.is_ok(), "{:?}", check.problems);
+    assert_eq!(check.entries, keys.len() as u64);
+    for &i in keys.iter().step_by(97) {
+        assert!(btree.search(&mut pager, &encode_i64(i)).unwrap().is_some());
+    }
+    let leaves = btree.layout(&mut pager).unwrap().leaf_pages.len();
+    std::fs::remove_file(&path).ok();
+    leaves
+}
+
+#[test]
+fn test_ascending_inserts_split_at_fill_factor() {
+    let ascending: Vec<i64> = (0..3000).collect();
+    let descending: Vec<i64> = (0..3000).rev().collect();
+    // 35 cells of 114 bytes fit a leaf: 3000 of them fill 86 leaves.
+    let packed = leaves_after_inserts(DEFAULT_FILL_FACTOR, &ascending);
+    assert_eq!(packed, 86);
+    // Splits below the rightmost leaf still go down the middle.
+    let halved = leaves_after_inserts(DEFAULT_FILL_FACTOR, &descending);
+    assert!(halved >= packed * 18 / 10, "{} vs {}", halved, packed);
+
+    let spaced = leaves_after_inserts(80, &ascending);
+    assert!(
+        spaced > packed && spaced <= packed * 13 / 10,
+        "{} vs {}",
+        spaced,
+        packed
+    );
+}
+
+#[test]
+fn test_random_inserts_keep_midpoint_splits() {
+    use rand::seq::SliceRandom;
+    use rand::{rngs::StdRng, SeedableRng};
+
+    let mut keys: Vec<i64> = (0..3000).collect();
+    keys.shuffle(&mut StdRng::seed_from_u64(7));
+    let leaves = leaves_after_inserts(DEFAULT_FILL_FACTOR, &keys);
+    // Random splits leave leaves between half and fully used.
+    assert!((86..=172).contains(&leaves), "{}", leaves);
+}
+
+#[test]
+fn test_get_many_matches_search_in_caller_order() {
+    use rand::{rngs::StdRng, Rng, SeedableRng};
+
+    let (mut pager, path) = setup();
+    let mut btree = BTree::create(&mut pager).unwrap();
+    for i in 0..2000i64 {
+        // Every 97th value spills to an overflow chain.
+        let len = if i % 97 == 0 { 6000 } else { 30 };
+        btree
+            .insert(&mut pager, &encode_i64(i * 3), &vec![(i % 251) as u8; len])
+            .unwrap();
+    }
+    assert_eq!(btree.get_many(&mut pager, &[]).unwrap(), vec![]);
+
+    let mut rng = StdRng::seed_from_u64(11);
+    for round in 0..40 {
+        let n = rng.gen_range(1..300);
+        // Hits (multiples of 3), misses, keys past both ends, duplicates.
+        let mut keys: Vec<Vec<u8>> = (0..n)
+            .map(|_| encode_i64(rng.gen_range(-20..6020)).to_vec())
+            .collect();
+        keys.push(keys[0].clone());
+        keys.push(b"".to_vec());
+        let expected: Vec<Option<Vec<u8>>> = keys
+            .iter()
+            .map(|k| btree.search(&mut pager, k).unwrap())
+            .collect();
+        assert!(expected.iter().any(Option::is_some), "round {}", round);
+        assert_eq!(btree.get_many(&mut pager, &keys).unwrap(), expected);
+    }
+    std::fs::remove_file(&path).ok();
+}
+
+#[test]
+fn test_get_many_reads_each_page_once() {
+    let (mut pager, path) = setup();
+    let mut btree = BTree::create(&mut pager).unwrap();
+    for i in 0..2500i64 {
+        btree
+            .insert(&mut pager, &encode_i64(i), &[0u8; 1000])
+            .unwrap();
+    }
+    let pages = btree.collect_all_pages(&mut pager).unwrap().len() as u64;
+
+    // Every key, in reverse: each page once, against a full descent per key.
+    let keys: Vec<Vec<u8>> = (0..2500i64).rev().map(|i| encode_i64(i).to_vec()).collect();
+    let before = reads(&pager);
+    let values = btree.get_many(&mut pager, &keys).unwrap();
+    assert_eq!(reads(&pager) - before, pages);
+    assert!(values.iter().all(Option::is_some));
+    let before = reads(&pager);
+    for key in keys.iter().take(100) {
+        btree.search(&mut pager, key).unwrap();
+    }
+    assert!(reads(&pager) - before >= 300);
+
+    // Keys of one leaf, with a duplicate: one descent.
+    let before = reads(&pager);
+    btree.search(&mut pager, &encode_i64(0)).unwrap();
+    let descent = reads(&pager) - before;
+    assert_eq!(descent, 3);
+    let keys: Vec<Vec<u8>> = [1i64, 0, 1]
+        .iter()
+        .map(|&i| encode_i64(i).to_vec())
+        .collect();
+    let before = reads(&pager);
+    btree.get_many(&mut pager, &keys).unwrap();
+    assert_eq!(reads(&pager) - before, descent);
+    std::fs::remove_file(&path).ok();
+}
+
+/// Long keys keep internal fan-out low, so a few thousand rows build a deep tree.
+fn wide_key(i: i64) -> Vec<u8> {
+    let mut key = encode_i64(i).to_vec();
+    key.resize(400, b'k');
+    key
+}
+
+/// Tree depth and the entry count of every internal page other than the root.
+fn shape(pager: &mut Pager, btree: &BTree) -> (usize, Vec<u16>) {
+    fn walk(pager: &mut Pager, page_id: PageId, level: usize, out: &mut (usize, Vec<u16>)) {
+        let page = pager.read_page(page_id).unwrap();
+        out.0 = out.0.max(level + 1);
+        if node_type(&page) != Some(NodeType::Internal) {
+            return;
+        }
+        let n = num_entries(&page);
+        if level > 0 {
+            out.1.push(n);
+        }
+        for i in 0..n {
+            walk(
+                pager,
+                internal_left_child(&page, i).unwrap(),
+                level + 1,
+                out,
+            );
+        }
+        walk(pager, right_child(&page).unwrap(), level + 1, out);
+    }
+    let mut out = (0, Vec::new());
+    walk(pager, btree.root_page_id(), 0, &mut out);
+    out
+}
+
+fn assert_keys(pager: &mut Pager, btree: &BTree, expected: &[i64]) {
+    let check = btree.verify(pager, |_, _| {});
+    assert!(check.is_ok(), "{:?}", check.problems);
+    let mut scanned = Vec::new();
+    btree
+        .scan(pager, |k, _| {
+            scanned.push(k.to_vec());
+            Ok(true)
+        })
+        .unwrap();
+    let wanted: Vec<Vec<u8>> = expected.iter().map(|&i| wide_key(i)).collect();
+    assert_eq!(scanned, wanted);
+    for key in &wanted {
+        assert_eq!(btree.search(pager, key).unwrap(), Some(b"v".to_vec()));
+    }
+}
+
+#[test]
+fn test_deletes_rebalance_internal_nodes_at_every_level() {
+    let (mut pager, path) = setup();
+    let mut btree = BTree::create(&mut pager).unwrap();
+    let count = 2000i64;
+    for i in 0..count {
+        btree.insert(&mut pager, &wide_key(i), b"v").unwrap();
+    }
+    let (depth_before, _) = shape(&mut pager, &btree);
+    let pages_before = btree.collect_all_pages(&mut pager).unwrap().len();
+    assert!(depth_before >= 4, "tree only {} levels deep", depth_before);
+
+    // Drop a wide middle range, then thin out what is left, so whole
+    // subtrees empty out and their parents merge or borrow level by level.
+    for i in 400..1600 {
+        assert!(btree.delete(&mut pager, &wide_key(i)).unwrap());
+    }
+    let (_, internal_entries) = shape(&mut pager, &btree);
+    assert!(
+        internal_entries.iter().all(|&n| n > 0),
+        "single-child internal pages left: {:?}",
+        internal_entries
+    );
+    for i in (0..400).chain(1600..count).filter(|i| i % 2 == 1) {
+        assert!(btree.delete(&mut pager, &wide_key(i)).unwrap());
+    }
+    let remaining: Vec<i64> = (0..400).chain(1600..count).filter(|i| i % 2 == 0).collect();
+    assert_keys(&mut pager, &btree, &remaining);
+
+    // Then shrink to the first 50 survivors: the tree loses a level.
+    for &i in &remaining[50..] {
+        assert!(btree.delete(&mut pager, &wide_key(i)).unwrap());
+    }
+    assert_keys(&mut pager, &btree, &remaining[..50]);
+    let (depth_after, internal_entries) = shape(&mut pager, &btree);
+    assert!(
+        internal_entries.iter().all(|&n| n > 0),
+        "single-child internal pages left: {:?}",
+        internal_entries
+    );
+    assert!(
+        depth_after < depth_before,
+        "{} -> {}",
+        depth_before,
+        depth_after
+    );
+
+    // Close to a tree built from the survivors alone. Leaves only merge
+    // once they drop below `MIN_ENTRIES`, so thinned ones stay sparse.
+    let mut fresh = BTree::create(&mut pager).unwrap();
+    for &i in &remaining[..50] {
+        fresh.insert(&mut pager, &wide_key(i), b"v").unwrap();
+    }
+    let fresh_pages = fresh.collect_all_pages(&mut pager).unwrap().len();
+    let pages_after = btree.collect_all_pages(&mut pager).unwrap().len();
+    assert!(
+        pages_after <= 3 * fresh_pages,
+        "{} pages, fresh tree {}",
+        pages_after,
+        fresh_pages
+    );
+    assert!(
+        pages_after < pages_before / 10,
+        "{} -> {}",
+        pages_before,
+        pages_after
+    );
+
+    std::fs::remove_file(&path).ok();
+}
+
+#[test]
+fn test_deleting_all_but_one_key_collapses_to_a_leaf_root() {
+    let (mut pager, path) = setup();
+    let mut btree = BTree::create(&mut pager).unwrap();
+    for i in 0..1200i64 {
+        btree.insert(&mut pager, &wide_key(i), b"v").unwrap();
+    }
+    assert!(shape(&mut pager, &btree).0 >= 3);
+
+    // Delete from both ends towards a survivor in the middle.
+    for i in (0..600).chain(601..1200).rev() {
+        assert!(btree.delete(&mut pager, &wide_key(i)).unwrap());
+    }
+    assert_keys(&mut pager, &btree, &[600]);
+    assert_eq!(shape(&mut pager, &btree).0, 1);
+    assert_eq!(btree.collect_all_pages(&mut pager).unwrap().len(), 1);
+
+    // The collapsed tree keeps growing normally.
+    for i in 0..1200i64 {
+        if i != 600 {
+            btree.insert(&mut pager, &wide_key(i), b"v").unwrap();
+        }
+    }
+    assert_keys(&mut pager, &btree, &(0..1200).collect::<Vec<_>>());
+
+    std::fs::remove_file(&path).ok();
+}
+
+/// Every page of `btree`: the cells of B-tree nodes (their logical contents,
+/// independent of where on the page they sit) and the raw bytes of others.
+fn logical_pages(btree: &BTree, pager: &mut Pager) -> Vec<(PageId, Vec<Vec<u8>>)> {
+    let mut pages = btree.collect_all_pages(pager).unwrap();
+    pages.sort_unstable();
+    pages
+        .into_iter()
+        .map(|page_id| {
+            let page = pager.read_page(page_id).unwrap();
+            let cells = if node_type(&page).is_some() {
+                (0..page.cell_count())
+                    .map(|i| page.cell(i).unwrap().to_vec())
+                    .collect()
+            } else {
+                vec![page.as_bytes().to_vec()]
+            };
+            (page_id, cells)
+        })
+        .collect()
+}
+
+#[test]
+fn test_leaf_fast_path_matches_rebuilding_pages() {
+    use rand::{rngs::StdRng, Rng, SeedableRng};
+
+    let (mut pager, path) = setup();
+    let (mut ref_pager, ref_path) = setup();
+    let mut btree = BTree::create(&mut pager).unwrap();
+    let mut reference = BTree::create(&mut ref_pager)
+        .unwrap()
+        .without_leaf_fast_path();
+    let mut model = std::collections::BTreeMap::new();
+
+    let mut rng = StdRng::seed_from_u64(23);
+    for step in 0..3000 {
+        let key = encode_i64(rng.gen_range(0..400)).to_vec();
+        if rng.gen_bool(0.2) {
+            assert_eq!(
+                btree.delete(&mut pager, &key).unwrap(),
+                reference.delete(&mut ref_pager, &key).unwrap()
+            );
+            model.remove(&key);
+        } else {
+            // Mostly small values, some the same length as the old one,
+            // occasionally one that spills to an overflow chain.
+            let len = match rng.gen_range(0..20) {
+                0 => 5000,
+                1..=8 => model.get(&key).map_or(16, |v: &Vec<u8>| v.len()),
+                _ => rng.gen_range(0..120),
+            };
+            let value = vec![(step % 251) as u8; len];
+            btree.insert(&mut pager, &key, &value).unwrap();
+            reference.insert(&mut ref_pager, &key, &value).unwrap();
+            model.insert(key, value);
+        }
+        assert_eq!(btree.root_page_id(), reference.root_page_id());
+        if step % 50 == 0 {
+            assert_eq!(
+                logical_pages(&btree, &mut pager),
+                logical_pages(&reference, &mut ref_pager),
+                "step {}",
+                step
+            );
+        }
+    }
+    assert_eq!(
+        logical_pages(&btree, &mut pager),
+        logical_pages(&reference, &mut ref_pager)
+    );
+
+    let mut entries = Vec::new();
+    btree
+        .scan(&mut pager, |k, v| {
+            entries.push((k.to_vec(), v.to_vec()));
+            Ok(true)
+        })
+        .unwrap();
+    assert_eq!(entries, model.into_iter().collect::<Vec<_>>());
+    std::fs::remove_file(&path).ok();
+    std::fs::remove_file(&ref_path).ok();
+}
+
+#[test]
+fn test_same_size_update_patches_leaf_in_place() {
+    let (mut pager, path) = setup();
+    let mut btree = BTree::create(&mut pager).unwrap();
+    for i in 0..20i64 {
+        btree.insert(&mut pager, &encode_i64(i), &[1u8; 8]).unwrap();
+    }
+    let before = pager.read_page(btree.root_page_id()).unwrap();
+
+    btree.insert(&mut pager, &encode_i64(7), &[2u8; 8]).unwrap();
+    let after = pager.read_page(btree.root_page_id()).unwrap();
+    assert_eq!(after.free_end(), before.free_end());
+    assert_eq!(after.cell_pointer(8), before.cell_pointer(8));
+    assert_eq!(
+        btree.search(&mut pager, &encode_i64(7)).unwrap(),
+        Some(vec![2u8; 8])
+    );
+
+    // A longer value moves into the free gap; the other cells stay put.
+    btree
+        .insert(&mut pager, &encode_i64(7), &[3u8; 12])
+        .unwrap();
+    let after = pager.read_page(btree.root_page_id()).unwrap();
+    assert!(after.free_end() < before.free_end());
+    assert_eq!(after.cell_pointer(1), before.cell_pointer(1));
+    assert_eq!(
+        btree.search(&mut pager, &encode_i64(7)).unwrap(),
+        Some(vec![3u8; 12])
+    );
+    std::fs::remove_file(&path).ok();
+}
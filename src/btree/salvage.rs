@@ -0,0 +1,270 @@
+/// Corruption-tolerant full scans.
+///
+/// The regular scans abort on the first unreadable page. A salvage scan
+/// instead skips the subtree (or single entry) it cannot read, reports the
+/// key range that may be missing, and continues with its siblings, so the
+/// readable part of a damaged tree can still be exported.
+use crate::btree::node::*;
+use crate::btree::ops::BTree;
+use crate::btree::verify::ChildRange;
+use crate::error::{MuroError, Result};
+use crate::storage::overflow;
+use crate::storage::page::PageId;
+use crate::storage::page_store::PageStore;
+
+/// Same bound as the regular traversal paths.
+const MAX_SALVAGE_DEPTH: usize = 64;
+
+/// Part of the tree a salvage scan could not read.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SkippedRange {
+    /// The page that failed to read or decode.
+    pub page_id: PageId,
+    /// Inclusive lower bound of the keys that may be missing; `None` if
+    /// unbounded. For a single skipped entry, both bounds hold its key.
+    pub lower: Option<Vec<u8>>,
+    /// Exclusive upper bound of the keys that may be missing; `None` if
+    /// unbounded.
+    pub upper: Option<Vec<u8>>,
+    pub error: String,
+}
+
+struct Salvager<'a, F> {
+    reverse: bool,
+    skipped: Vec<SkippedRange>,
+    callback: &'a mut F,
+}
+
+impl BTree {
+    /// Scan every readable entry, skipping unreadable pages and entries.
+    ///
+    /// Visits entries in ascending key order, or descending when `reverse`,
+    /// just like [`BTree::scan`] / [`BTree::scan_rev`]. Errors returned by
+    /// `callback` are not treated as corruption and abort the scan.
+    pub fn scan_salvage<F>(
+        &self,
+        pager: &mut impl PageStore,
+        reverse: bool,
+        mut callback: F,
+    ) -> Result<Vec<SkippedRange>>
+    where
+        F: FnMut(&[u8], &[u8]) -> Result<bool>,
+    {
+        let mut salvager = Salvager {
+            reverse,
+            skipped: Vec::new(),
+            callback: &mut callback,
+        };
+        salvager.scan_page(pager, self.root_page_id(), None, None, 0)?;
+        Ok(salvager.skipped)
+    }
+}
+
+impl<F> Salvager<'_, F>
+where
+    F: FnMut(&[u8], &[u8]) -> Result<bool>,
+{
+    fn skip(&mut self, page_id: PageId, lower: Option<&[u8]>, upper: Option<&[u8]>, error: String) {
+        self.skipped.push(SkippedRange {
+            page_id,
+            lower: lower.map(<[u8]>::to_vec),
+            upper: upper.map(<[u8]>::to_vec),
+            error,
+        });
+    }
+
+    /// Returns false once the callback has requested the scan to stop.
+    fn scan_page(
+        &mut self,
+        pager: &mut impl PageStore,
+        page_id: PageId,
+        lower: Option<&[u8]>,
+        upper: Option<&[u8]>,
+        depth: usize,
+    ) -> Result<bool> {
+        if depth > MAX_SALVAGE_DEPTH {
+            self.skip(
+                page_id,
+                lower,
+                upper,
+                "B-tree depth exceeds maximum (possible cycle)".into(),
+            );
+            return Ok(true);
+        }
+        let page = match pager.read_page(page_id) {
+            Ok(page) => page,
+            Err(e) => {
+                self.skip(page_id, lower, upper, e.to_string());
+                return Ok(true);
+            }
+        };
+
+        match node_type(&page) {
+            Some(NodeType::Leaf) => {
+                let n = num_entries(&page);
+                let mut order: Vec<u16> = (0..n).collect();
+                if self.reverse {
+                    order.reverse();
+                }
+                for i in order {
+                    let Some(cell) = page.cell(i + 1) else {
+                        self.skip(page_id, lower, upper, MuroError::InvalidPage.to_string());
+                        continue;
+                    };
+                    if !self.visit_cell(pager, page_id, cell, lower, upper)? {
+                        return Ok(false);
+                    }
+                }
+                Ok(true)
+            }
+            Some(NodeType::Internal) => {
+                let n = num_entries(&page);
+                let mut children: Vec<ChildRange> = Vec::with_capacity(n as usize + 1);
+                let mut prev: Option<Vec<u8>> = lower.map(<[u8]>::to_vec);
+                for i in 0..n {
+                    match page.cell(i + 1).and_then(decode_internal_cell) {
+                        Some((child, key)) => {
+                            children.push((child, prev.take(), Some(key.to_vec())));
+                            prev = Some(key.to_vec());
+                        }
+                        None => self.skip(
+                            page_id,
+                            prev.as_deref(),
+                            upper,
+                            "invalid internal cell encoding".into(),
+                        ),
+                    }
+                }
+                match right_child(&page) {
+                    Some(right) => children.push((right, prev, upper.map(<[u8]>::to_vec))),
+                    None => self.skip(
+                        page_id,
+                        prev.as_deref(),
+                        upper,
+                        "missing right child pointer".into(),
+                    ),
+                }
+                if self.reverse {
+                    children.reverse();
+                }
+                for (child, lo, hi) in children {
+                    if !self.scan_page(pager, child, lo.as_deref(), hi.as_deref(), depth + 1)? {
+                        return Ok(false);
+                    }
+                }
+                Ok(true)
+            }
+            None => {
+                self.skip(page_id, lower, upper, "invalid B-tree node type".into());
+                Ok(true)
+            }
+        }
+    }
+
+    fn visit_cell(
+        &mut self,
+        pager: &mut impl PageStore,
+        page_id: PageId,
+        cell: &[u8],
+        lower: Option<&[u8]>,
+        upper: Option<&[u8]>,
+    ) -> Result<bool> {
+        let Some((k, v)) = decode_leaf_cell(cell) else {
+            self.skip(page_id, lower, upper, "invalid leaf cell encoding".into());
+            return Ok(true);
+        };
+        if !is_overflow_cell(cell) {
+            return (self.callback)(k, v);
+        }
+        let value = decode_overflow_metadata(cell)
+            .ok_or_else(|| MuroError::Corruption("invalid overflow metadata in leaf cell".into()))
+            .and_then(|(total_len, first_page)| {
+                overflow::read_overflow_chain(pager, first_page, total_len)
+            });
+        match value {
+            Ok(value) => (self.callback)(k, &value),
+            Err(e) => {
+                self.skip(page_id, Some(k), Some(k), e.to_string());
+                Ok(true)
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::btree::key_encoding::encode_i64;
+    use crate::crypto::aead::MasterKey;
+    use crate::storage::page::Page;
+    use crate::storage::pager::Pager;
+    use tempfile::NamedTempFile;
+
+    fn setup() -> (Pager, std::path::PathBuf) {
+        let tmp = NamedTempFile::new().unwrap();
+        let path = tmp.path().to_path_buf();
+        drop(tmp);
+        std::fs::remove_file(&path).ok();
+        let key = MasterKey::new([0x42u8; 32]);
+        let pager = Pager::create(&path, &key).unwrap();
+        (pager, path)
+    }
+
+    #[test]
+    fn test_scan_salvage_skips_broken_leaf_and_reports_range() {
+        let (mut pager, path) = setup();
+        let mut btree = BTree::create(&mut pager).unwrap();
+        for i in 0..500i64 {
+            btree
+                .insert(&mut pager, &encode_i64(i), &[0u8; 100])
+                .unwrap();
+        }
+        let root = pager.read_page(btree.root_page_id()).unwrap();
+        let second_leaf = internal_left_child(&root, 1).unwrap();
+        let (_, lower) = decode_internal_cell(root.cell(1).unwrap()).unwrap();
+        let (_, upper) = decode_internal_cell(root.cell(2).unwrap()).unwrap();
+        let (lower, upper) = (lower.to_vec(), upper.to_vec());
+        let lost = num_entries(&pager.read_page(second_leaf).unwrap()) as u64;
+
+        // A page with no node header.
+        pager.write_page(&Page::new(second_leaf)).unwrap();
+        assert!(btree.scan(&mut pager, |_, _| Ok(true)).is_err());
+
+        for reverse in [false, true] {
+            let mut keys = Vec::new();
+            let skipped = btree
+                .scan_salvage(&mut pager, reverse, |k, _| {
+                    keys.push(k.to_vec());
+                    Ok(true)
+                })
+                .unwrap();
+            assert_eq!(keys.len() as u64, 500 - lost);
+            let mut sorted = keys.clone();
+            sorted.sort();
+            if reverse {
+                sorted.reverse();
+            }
+            assert_eq!(keys, sorted);
+            assert_eq!(
+                skipped,
+                vec![SkippedRange {
+                    page_id: second_leaf,
+                    lower: Some(lower.clone()),
+                    upper: Some(upper.clone()),
+                    error: "invalid B-tree node type".into(),
+                }]
+            );
+        }
+        std::fs::remove_file(&path).ok();
+    }
+
+    #[test]
+    fn test_scan_salvage_propagates_callback_errors() {
+        let (mut pager, path) = setup();
+        let mut btree = BTree::create(&mut pager).unwrap();
+        btree.insert(&mut pager, &encode_i64(1), b"v").unwrap();
+        let result = btree.scan_salvage(&mut pager, false, |_, _| Err(MuroError::Cancelled));
+        assert!(matches!(result, Err(MuroError::Cancelled)));
+        std::fs::remove_file(&path).ok();
+    }
+}
@@ -0,0 +1,346 @@
+/// B-tree structural verification for integrity checks.
+///
+/// Unlike the regular read paths, verification does not stop at the first
+/// problem: unreadable pages and malformed cells are recorded and the walk
+/// continues with the remaining subtrees, so callers get a full damage report.
+use std::collections::HashSet;
+
+use crate::btree::key_encoding::compare_keys;
+use crate::btree::node::*;
+use crate::btree::ops::BTree;
+use crate::error::MuroError;
+use crate::storage::overflow;
+use crate::storage::page::{Page, PageId};
+use crate::storage::page_store::PageStore;
+
+/// Same bound as the regular traversal paths.
+const MAX_VERIFY_DEPTH: usize = 64;
+
+/// Result of [`BTree::verify`].
+#[derive(Debug, Default)]
+pub struct BTreeCheck {
+    /// Every page reachable from the root, including value overflow pages.
+    pub pages: Vec<PageId>,
+    /// Number of leaf entries visited.
+    pub entries: u64,
+    /// Description of each problem found, in traversal order.
+    pub problems: Vec<String>,
+}
+
+impl BTreeCheck {
+    pub fn is_ok(&self) -> bool {
+        self.problems.is_empty()
+    }
+}
+
+/// A child page with the lower (inclusive) and upper (exclusive) key bounds
+/// implied by its parent's separators.
+pub(crate) type ChildRange = (PageId, Option<Vec<u8>>, Option<Vec<u8>>);
+
+struct Walker<'a, F> {
+    check: BTreeCheck,
+    visited: HashSet<PageId>,
+    last_key: Option<Vec<u8>>,
+    visit: &'a mut F,
+}
+
+impl BTree {
+    /// Walk the whole tree and verify its structure.
+    ///
+    /// Checks that every page is readable and has a valid node type, that
+    /// keys ascend strictly within and across leaves and respect the
+    /// separator keys and fences of their parents, that no page is referenced twice, and
+    /// that value overflow chains terminate with the recorded length.
+    /// `visit` is called for every leaf entry that could be decoded, with
+    /// overflow values reconstructed.
+    pub fn verify<F>(&self, pager: &mut impl PageStore, mut visit: F) -> BTreeCheck
+    where
+        F: FnMut(&[u8], &[u8]),
+    {
+        let mut walker = Walker {
+            check: BTreeCheck::default(),
+            visited: HashSet::new(),
+            last_key: None,
+            visit: &mut visit,
+        };
+        walker.walk(pager, self.root_page_id(), None, None, None, 0);
+        walker.check
+    }
+}
+
+impl<F> Walker<'_, F>
+where
+    F: FnMut(&[u8], &[u8]),
+{
+    fn problem(&mut self, detail: String) {
+        self.check.problems.push(detail);
+    }
+
+    fn claim(&mut self, page_id: PageId) -> bool {
+        if !self.visited.insert(page_id) {
+            self.problem(format!("page {} is referenced more than once", page_id));
+            return false;
+        }
+        self.check.pages.push(page_id);
+        true
+    }
+
+    fn walk(
+        &mut self,
+        pager: &mut impl PageStore,
+        page_id: PageId,
+        lower: Option<&[u8]>,
+        upper: Option<&[u8]>,
+        fence: Option<&[u8]>,
+        depth: usize,
+    ) {
+        if depth > MAX_VERIFY_DEPTH {
+            self.problem(format!(
+                "page {}: depth exceeds {} (possible cycle)",
+                page_id, MAX_VERIFY_DEPTH
+            ));
+            return;
+        }
+        if !self.claim(page_id) {
+            return;
+        }
+        let page = match pager.read_page(page_id) {
+            Ok(page) => page,
+            Err(e) => {
+                self.problem(format!("page {}: unreadable: {}", page_id, e));
+                return;
+            }
+        };
+        match node_type(&page) {
+            Some(NodeType::Leaf) => self.walk_leaf(pager, &page, lower, upper, fence),
+            Some(NodeType::Internal) => {
+                self.walk_internal(pager, &page, lower, upper, fence, depth)
+            }
+            None => self.problem(format!("page {}: invalid B-tree node type", page_id)),
+        }
+    }
+
+    fn walk_leaf(
+        &mut self,
+        pager: &mut impl PageStore,
+        page: &Page,
+        lower: Option<&[u8]>,
+        upper: Option<&[u8]>,
+        fence: Option<&[u8]>,
+    ) {
+        let page_id = page.page_id();
+        for i in 0..num_entries(page) {
+            let Some((key, inline_value)) = page.cell(i + 1).and_then(decode_leaf_cell) else {
+                self.problem(format!("page {}: leaf cell {} is malformed", page_id, i));
+                continue;
+            };
+            if let Some(last) = &self.last_key {
+                if compare_keys(key, last) != std::cmp::Ordering::Greater {
+                    self.problem(format!(
+                        "page {}: leaf key {} is not greater than the previous key",
+                        page_id, i
+                    ));
+                }
+            }
+            if !within_bounds(key, lower, upper) {
+                self.problem(format!(
+                    "page {}: leaf key {} lies outside its parent's separator range",
+                    page_id, i
+                ));
+            }
+            if fence.is_some_and(|f| compare_keys(key, f) == std::cmp::Ordering::Greater) {
+                self.problem(format!(
+                    "page {}: leaf key {} lies above its parent's fence",
+                    page_id, i
+                ));
+            }
+            self.last_key = Some(key.to_vec());
+            self.check.entries += 1;
+
+            if !leaf_is_overflow(page, i) {
+                (self.visit)(key, inline_value);
+                continue;
+            }
+            let Some((total_len, first_page)) = page.cell(i + 1).and_then(decode_overflow_metadata)
+            else {
+                self.problem(format!(
+                    "page {}: leaf cell {} has malformed overflow metadata",
+                    page_id, i
+                ));
+                continue;
+            };
+            match overflow::collect_overflow_pages(pager, first_page) {
+                Ok(chain) => {
+                    if !chain.into_iter().all(|p| self.claim(p)) {
+                        continue;
+                    }
+                }
+                Err(e) => {
+                    // An unreadable chain page still belongs to this tree.
+                    if let MuroError::PageDecrypt { page_id: bad, .. } = e {
+                        self.claim(bad);
+                    }
+                    self.problem(format!("page {}: leaf cell {}: {}", page_id, i, e));
+                    continue;
+                }
+            }
+            match overflow::read_overflow_chain(pager, first_page, total_len) {
+                Ok(value) => (self.visit)(key, &value),
+                Err(e) => self.problem(format!("page {}: leaf cell {}: {}", page_id, i, e)),
+            }
+        }
+    }
+
+    fn walk_internal(
+        &mut self,
+        pager: &mut impl PageStore,
+        page: &Page,
+        lower: Option<&[u8]>,
+        upper: Option<&[u8]>,
+        fence: Option<&[u8]>,
+        depth: usize,
+    ) {
+        let page_id = page.page_id();
+        let mut children: Vec<ChildRange> = Vec::new();
+        let mut fences: Vec<Option<Vec<u8>>> = Vec::new();
+        let mut prev: Option<Vec<u8>> = lower.map(<[u8]>::to_vec);
+        for i in 0..num_entries(page) {
+            let cell = page.cell(i + 1);
+            let Some((child, key)) = cell.and_then(decode_internal_cell) else {
+                self.problem(format!(
+                    "page {}: internal cell {} is malformed",
+                    page_id, i
+                ));
+                continue;
+            };
+            if let Some(p) = &prev {
+                if compare_keys(key, p) == std::cmp::Ordering::Less {
+                    self.problem(format!(
+                        "page {}: separator key {} is out of order",
+                        page_id, i
+                    ));
+                }
+            }
+            if !within_bounds(key, lower, upper) {
+                self.problem(format!(
+                    "page {}: separator key {} lies outside its parent's range",
+                    page_id, i
+                ));
+            }
+            children.push((child, prev.take(), Some(key.to_vec())));
+            // Keys below must satisfy both this fence and any inherited one.
+            let own = cell.and_then(internal_cell_fence);
+            let tightest = match (own, fence) {
+                (Some(a), Some(b)) => Some(std::cmp::min_by(a, b, |x, y| compare_keys(x, y))),
+                (a, b) => a.or(b),
+            };
+            fences.push(tightest.map(<[u8]>::to_vec));
+            prev = Some(key.to_vec());
+        }
+        match right_child(page) {
+            Some(right) => {
+                children.push((right, prev, upper.map(<[u8]>::to_vec)));
+                fences.push(fence.map(<[u8]>::to_vec));
+            }
+            None => self.problem(format!("page {}: missing right child pointer", page_id)),
+        }
+
+        for ((child, lo, hi), fence) in children.into_iter().zip(fences) {
+            self.walk(
+                pager,
+                child,
+                lo.as_deref(),
+                hi.as_deref(),
+                fence.as_deref(),
+                depth + 1,
+            );
+        }
+    }
+}
+
+/// Keys in a child subtree satisfy `lower <= key < upper` (see `find_child`).
+fn within_bounds(key: &[u8], lower: Option<&[u8]>, upper: Option<&[u8]>) -> bool {
+    lower.is_none_or(|lo| compare_keys(key, lo) != std::cmp::Ordering::Less)
+        && upper.is_none_or(|hi| compare_keys(key, hi) == std::cmp::Ordering::Less)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::btree::key_encoding::encode_i64;
+    use crate::crypto::aead::MasterKey;
+    use crate::storage::pager::Pager;
+    use tempfile::NamedTempFile;
+
+    fn setup() -> (Pager, std::path::PathBuf) {
+        let tmp = NamedTempFile::new().unwrap();
+        let path = tmp.path().to_path_buf();
+        drop(tmp);
+        std::fs::remove_file(&path).ok();
+        let key = MasterKey::new([0x42u8; 32]);
+        let pager = Pager::create(&path, &key).unwrap();
+        (pager, path)
+    }
+
+    #[test]
+    fn test_verify_clean_after_splits_and_merges() {
+        let (mut pager, path) = setup();
+        let mut btree = BTree::create(&mut pager).unwrap();
+        for i in 0..2000i64 {
+            let value = vec![b'v'; (i as usize * 37) % 300];
+            btree
+                .insert(&mut pager, &encode_i64(i * 7919 % 2000), &value)
+                .unwrap();
+        }
+        btree
+            .insert(&mut pager, &encode_i64(5000), &vec![b'o'; 10_000])
+            .unwrap();
+        for i in (0..2000i64).filter(|i| i % 3 != 0) {
+            btree.delete(&mut pager, &encode_i64(i)).unwrap();
+        }
+
+        let mut visited = 0u64;
+        let check = btree.verify(&mut pager, |_, _| visited += 1);
+        assert!(check.is_ok(), "{:?}", check.problems);
+        assert_eq!(check.entries, 668);
+        assert_eq!(visited, 668);
+        let mut all = btree.collect_all_pages(&mut pager).unwrap();
+        let mut pages = check.pages.clone();
+        all.sort_unstable();
+        pages.sort_unstable();
+        assert_eq!(pages, all);
+        std::fs::remove_file(&path).ok();
+    }
+
+    #[test]
+    fn test_verify_reports_out_of_order_leaf_and_continues() {
+        let (mut pager, path) = setup();
+        let mut btree = BTree::create(&mut pager).unwrap();
+        for i in 0..500i64 {
+            btree
+                .insert(&mut pager, &encode_i64(i), &[0u8; 100])
+                .unwrap();
+        }
+        let root = pager.read_page(btree.root_page_id()).unwrap();
+        let first_leaf = internal_left_child(&root, 0).unwrap();
+
+        // Rewrite the first leaf with its keys in descending order.
+        let old = pager.read_page(first_leaf).unwrap();
+        let mut cells: Vec<Vec<u8>> = (0..num_entries(&old))
+            .map(|i| old.cell(i + 1).unwrap().to_vec())
+            .collect();
+        cells.reverse();
+        let mut page = Page::new(first_leaf);
+        init_leaf(&mut page);
+        for cell in &cells {
+            page.insert_cell(cell).unwrap();
+        }
+        pager.write_page(&page).unwrap();
+
+        let check = btree.verify(&mut pager, |_, _| {});
+        assert!(!check.is_ok());
+        assert!(check.problems[0].contains("not greater than the previous key"));
+        assert_eq!(check.entries, 500);
+        std::fs::remove_file(&path).ok();
+    }
+}
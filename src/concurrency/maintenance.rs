@@ -0,0 +1,334 @@
+/// Maintenance mode: exclusive use of a database by one handle, shared by
+/// every `Database` / `DatabaseReader` of the same file in this process.
+///
+/// Handles register with a per-file registry and enter it around every
+/// statement. While maintenance is pending, new statements from other handles
+/// are rejected, except on handles that still have an explicit transaction
+/// open, so that transaction can finish. Once active, only the owner runs.
+///
+/// A handle opened with `OpenOptions::exclusive_in_process` claims the file:
+/// it fails if another handle is registered, and later registrations fail
+/// until it is dropped. Readers it opens itself are still admitted.
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, OnceLock, Weak};
+use std::time::{Duration, Instant};
+
+use parking_lot::{Condvar, Mutex};
+
+use crate::error::{MuroError, Result};
+use crate::sql::session::TransactionInfo;
+
+type RegistryMap = Mutex<HashMap<PathBuf, Weak<HandleRegistry>>>;
+
+static REGISTRIES: OnceLock<RegistryMap> = OnceLock::new();
+
+struct HandleRegistry {
+    path: PathBuf,
+    state: Mutex<RegistryState>,
+    changed: Condvar,
+    next_id: AtomicU64,
+}
+
+#[derive(Default)]
+struct RegistryState {
+    maintenance: Option<Maintenance>,
+    handles: HashMap<u64, HandleActivity>,
+    /// The handle that opened the file with `exclusive_in_process`.
+    exclusive_owner: Option<u64>,
+}
+
+struct Maintenance {
+    owner: u64,
+    /// `false` while waiting for other handles to drain.
+    active: bool,
+}
+
+#[derive(Default)]
+struct HandleActivity {
+    in_flight: usize,
+    open_tx: Option<TransactionInfo>,
+}
+
+/// One handle's membership in its file's registry; dropped with the handle.
+pub(crate) struct HandleRegistration {
+    id: u64,
+    registry: Arc<HandleRegistry>,
+}
+
+/// Held while a statement runs on a registered handle.
+pub(crate) struct StatementTicket {
+    id: u64,
+    registry: Arc<HandleRegistry>,
+}
+
+impl HandleRegistration {
+    /// Register a new handle of `db_path`, or fail with
+    /// [`MuroError::AlreadyOpen`] while an exclusive handle holds it.
+    pub(crate) fn register(db_path: &Path) -> Result<Self> {
+        Self::register_with(db_path, false)
+    }
+
+    /// Register a handle that no other handle of `db_path` may join, or fail
+    /// with [`MuroError::AlreadyOpen`] if one is registered already.
+    pub(crate) fn register_exclusive(db_path: &Path) -> Result<Self> {
+        Self::register_with(db_path, true)
+    }
+
+    fn register_with(db_path: &Path, exclusive: bool) -> Result<Self> {
+        let path = canonical_db_path(db_path);
+        let registry = {
+            let mut map = REGISTRIES.get_or_init(Default::default).lock();
+            match map.get(&path).and_then(Weak::upgrade) {
+                Some(registry) => registry,
+                None => {
+                    let registry = Arc::new(HandleRegistry {
+                        path: path.clone(),
+                        state: Mutex::new(RegistryState::default()),
+                        changed: Condvar::new(),
+                        next_id: AtomicU64::new(1),
+                    });
+                    map.insert(path, Arc::downgrade(&registry));
+                    registry
+                }
+            }
+        };
+        // On failure the registry may drop here, which takes the map lock.
+        let mut state = registry.state.lock();
+        if state.exclusive_owner.is_some() || (exclusive && !state.handles.is_empty()) {
+            return Err(MuroError::AlreadyOpen(registry.path.display().to_string()));
+        }
+        let id = registry.next_id.fetch_add(1, Ordering::Relaxed);
+        state.handles.insert(id, HandleActivity::default());
+        if exclusive {
+            state.exclusive_owner = Some(id);
+        }
+        drop(state);
+        Ok(HandleRegistration { id, registry })
+    }
+
+    /// Register another handle of the same file, such as a reader opened by
+    /// this handle; an exclusive claim does not turn it away.
+    pub(crate) fn sibling(&self) -> Self {
+        let id = self.registry.next_id.fetch_add(1, Ordering::Relaxed);
+        self.registry
+            .state
+            .lock()
+            .handles
+            .insert(id, HandleActivity::default());
+        HandleRegistration {
+            id,
+            registry: Arc::clone(&self.registry),
+        }
+    }
+
+    /// Admit a statement, or fail with [`MuroError::MaintenanceInProgress`].
+    pub(crate) fn enter(&self) -> Result<StatementTicket> {
+        let mut state = self.registry.state.lock();
+        if let Some(m) = &state.maintenance {
+            let finishing_tx = !m.active
+                && state
+                    .handles
+                    .get(&self.id)
+                    .is_some_and(|h| h.open_tx.is_some());
+            if m.owner != self.id && !finishing_tx {
+                return Err(MuroError::MaintenanceInProgress);
+            }
+        }
+        state.handles.entry(self.id).or_default().in_flight += 1;
+        Ok(StatementTicket {
+            id: self.id,
+            registry: Arc::clone(&self.registry),
+        })
+    }
+
+    /// Switch to maintenance mode once every other handle is idle with no
+    /// open transaction. On timeout, normal service resumes and
+    /// [`MuroError::Busy`] lists the blockers.
+    pub(crate) fn begin_maintenance(
+        &self,
+        timeout: Duration,
+        own_tx: Option<TransactionInfo>,
+    ) -> Result<()> {
+        let deadline = Instant::now() + timeout;
+        let mut state = self.registry.state.lock();
+        if state.maintenance.is_some() {
+            return Err(MuroError::MaintenanceInProgress);
+        }
+        if let Some(tx) = own_tx {
+            return Err(MuroError::Transaction(format!(
+                "cannot begin maintenance while transaction {} is open on this handle",
+                tx.txid
+            )));
+        }
+        state.maintenance = Some(Maintenance {
+            owner: self.id,
+            active: false,
+        });
+        loop {
+            let blockers = describe_blockers(&state, self.id);
+            if blockers.is_empty() {
+                if let Some(m) = state.maintenance.as_mut() {
+                    m.active = true;
+                }
+                eprintln!(
+                    "INFO: maintenance_begin path={}",
+                    self.registry.path.display()
+                );
+                return Ok(());
+            }
+            if Instant::now() >= deadline {
+                state.maintenance = None;
+                self.registry.changed.notify_all();
+                return Err(MuroError::Busy(blockers.join("; ")));
+            }
+            self.registry.changed.wait_until(&mut state, deadline);
+        }
+    }
+
+    pub(crate) fn end_maintenance(&self, held: Duration) {
+        let mut state = self.registry.state.lock();
+        if state
+            .maintenance
+            .as_ref()
+            .is_some_and(|m| m.owner == self.id)
+        {
+            state.maintenance = None;
+            self.registry.changed.notify_all();
+            eprintln!(
+                "INFO: maintenance_end path={} held_ms={}",
+                self.registry.path.display(),
+                held.as_millis()
+            );
+        }
+    }
+}
+
+impl Drop for HandleRegistration {
+    fn drop(&mut self) {
+        let mut state = self.registry.state.lock();
+        state.handles.remove(&self.id);
+        if state.exclusive_owner == Some(self.id) {
+            state.exclusive_owner = None;
+        }
+        if state
+            .maintenance
+            .as_ref()
+            .is_some_and(|m| m.owner == self.id)
+        {
+            state.maintenance = None;
+        }
+        self.registry.changed.notify_all();
+    }
+}
+
+impl Drop for HandleRegistry {
+    fn drop(&mut self) {
+        // A handle of the same file may have registered a new registry since
+        // the last reference to this one went away.
+        let mut map = REGISTRIES.get_or_init(Default::default).lock();
+        if map
+            .get(&self.path)
+            .is_some_and(|registry| registry.strong_count() == 0)
+        {
+            map.remove(&self.path);
+        }
+    }
+}
+
+impl StatementTicket {
+    /// Finish the statement, recording whether it left a transaction open.
+    pub(crate) fn finish(self, open_tx: Option<TransactionInfo>) {
+        let mut state = self.registry.state.lock();
+        if let Some(handle) = state.handles.get_mut(&self.id) {
+            handle.open_tx = open_tx;
+        }
+    }
+}
+
+impl Drop for StatementTicket {
+    fn drop(&mut self) {
+        let mut state = self.registry.state.lock();
+        if let Some(handle) = state.handles.get_mut(&self.id) {
+            handle.in_flight = handle.in_flight.saturating_sub(1);
+        }
+        self.registry.changed.notify_all();
+    }
+}
+
+/// The registry key for `db_path`: the resolved path of the file, or, for a
+/// file not created yet, the resolved directory joined with its name.
+fn canonical_db_path(db_path: &Path) -> PathBuf {
+    if let Ok(path) = std::fs::canonicalize(db_path) {
+        return path;
+    }
+    let (Some(parent), Some(name)) = (db_path.parent(), db_path.file_name()) else {
+        return db_path.to_path_buf();
+    };
+    let parent = if parent.as_os_str().is_empty() {
+        Path::new(".")
+    } else {
+        parent
+    };
+    std::fs::canonicalize(parent)
+        .map(|dir| dir.join(name))
+        .unwrap_or_else(|_| db_path.to_path_buf())
+}
+
+fn describe_blockers(state: &RegistryState, owner: u64) -> Vec<String> {
+    let mut ids: Vec<&u64> = state.handles.keys().filter(|id| **id != owner).collect();
+    ids.sort();
+    let mut blockers = Vec::new();
+    for id in ids {
+        let handle = &state.handles[id];
+        if let Some(tx) = &handle.open_tx {
+            blockers.push(format!(
+                "handle {}: transaction {} open for {}ms",
+                id,
+                tx.txid,
+                tx.age().as_millis()
+            ));
+        } else if handle.in_flight > 0 {
+            blockers.push(format!(
+                "handle {}: {} statement(s) in flight",
+                id, handle.in_flight
+            ));
+        }
+    }
+    blockers
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn registered(path: &Path) -> bool {
+        REGISTRIES
+            .get_or_init(Default::default)
+            .lock()
+            .contains_key(&canonical_db_path(path))
+    }
+
+    #[test]
+    fn test_last_registration_removes_the_entry() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let path = dir.path().join("test.db");
+        let first = HandleRegistration::register(&path).unwrap();
+        let second = first.sibling();
+        assert!(registered(&path));
+        drop(first);
+        assert!(registered(&path));
+        drop(second);
+        assert!(!registered(&path));
+
+        let exclusive = HandleRegistration::register_exclusive(&path).unwrap();
+        assert!(matches!(
+            HandleRegistration::register(&path),
+            Err(MuroError::AlreadyOpen(_))
+        ));
+        drop(exclusive);
+        assert!(!registered(&path));
+        drop(HandleRegistration::register_exclusive(&path).unwrap());
+    }
+}
@@ -3,8 +3,14 @@
 /// Multiple readers, single writer model.
 /// Thread-level: parking_lot::RwLock
 /// Process-level: fs4 file lock
+///
+/// Every guard opens its own handle on the lock file. `flock`-style locks
+/// belong to the open file description, so sharing one handle would let a
+/// guard dropped on one thread release the lock another thread still holds.
 use std::fs::{File, OpenOptions};
 use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
 use std::time::{Duration, Instant};
 
 use fs4::fs_std::FileExt;
@@ -12,12 +18,74 @@ use parking_lot::RwLock;
 
 use crate::error::{MuroError, Result};
 
+#[cfg(feature = "sql")]
+mod maintenance;
+
+#[cfg(feature = "sql")]
+pub(crate) use maintenance::HandleRegistration;
+
 /// Database lock manager combining thread-level and process-level locks.
 pub struct LockManager {
-    /// Thread-level RwLock for concurrent access within a single process.
-    rw_lock: RwLock<()>,
-    /// File used for process-level locking.
-    lock_file: File,
+    /// Thread-level RwLock for concurrent access within a single process,
+    /// shared with the managers made by [`LockManager::sibling`].
+    rw_lock: Arc<RwLock<()>>,
+    /// Path of the file used for process-level locking.
+    lock_path: PathBuf,
+    /// Guards of this manager that hold the process-level lock.
+    hold: Arc<LockHold>,
+}
+
+/// Process-level lock held by one handle, as reported by `SHOW PROCESSLIST`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LockState {
+    Unlocked,
+    Shared,
+    Exclusive,
+}
+
+impl LockState {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            LockState::Unlocked => "none",
+            LockState::Shared => "shared",
+            LockState::Exclusive => "exclusive",
+        }
+    }
+}
+
+/// Count of a manager's guards holding the file lock, readable without
+/// taking the lock.
+#[derive(Debug, Default)]
+pub struct LockHold {
+    shared: AtomicUsize,
+    exclusive: AtomicUsize,
+}
+
+impl LockHold {
+    pub fn state(&self) -> LockState {
+        if self.exclusive.load(Ordering::Acquire) > 0 {
+            LockState::Exclusive
+        } else if self.shared.load(Ordering::Acquire) > 0 {
+            LockState::Shared
+        } else {
+            LockState::Unlocked
+        }
+    }
+}
+
+#[derive(Clone, Copy)]
+enum LockMode {
+    Shared,
+    Exclusive,
+}
+
+impl LockMode {
+    fn name(self) -> &'static str {
+        match self {
+            LockMode::Shared => "shared",
+            LockMode::Exclusive => "exclusive",
+        }
+    }
 }
 
 impl LockManager {
@@ -25,17 +93,40 @@ impl LockManager {
         let mut lock_os = db_path.as_os_str().to_os_string();
         lock_os.push(".lock");
         let lock_path = PathBuf::from(lock_os);
-        let lock_file = OpenOptions::new()
+        // Create the lock file up front so permission problems surface at open.
+        Self::open_lock_file(&lock_path)?;
+
+        Ok(LockManager {
+            rw_lock: Arc::new(RwLock::new(())),
+            lock_path,
+            hold: Arc::new(LockHold::default()),
+        })
+    }
+
+    /// A manager for another handle on the same file that shares this one's
+    /// thread-level lock. A writer waiting on it blocks new readers, so a
+    /// steady stream of overlapping queries on sibling handles cannot keep
+    /// the writer out of the file lock.
+    pub fn sibling(&self) -> Self {
+        LockManager {
+            rw_lock: Arc::clone(&self.rw_lock),
+            lock_path: self.lock_path.clone(),
+            hold: Arc::new(LockHold::default()),
+        }
+    }
+
+    /// Which file lock this manager's guards hold right now.
+    pub fn hold(&self) -> Arc<LockHold> {
+        Arc::clone(&self.hold)
+    }
+
+    fn open_lock_file(lock_path: &Path) -> Result<File> {
+        Ok(OpenOptions::new()
             .create(true)
             .truncate(false)
             .read(true)
             .write(true)
-            .open(&lock_path)?;
-
-        Ok(LockManager {
-            rw_lock: RwLock::new(()),
-            lock_file,
-        })
+            .open(lock_path)?)
     }
 
     /// Acquire a shared (read) lock.
@@ -45,53 +136,24 @@ impl LockManager {
 
     /// Acquire a shared (read) lock with timeout.
     ///
-    /// If `timeout` is `None`, this blocks until acquired.
+    /// If `timeout` is `None`, this blocks until acquired. The timeout
+    /// covers both the in-process wait and the wait for other processes.
     pub fn read_lock_with_timeout(&self, timeout: Option<Duration>) -> Result<ReadGuard<'_>> {
-        let timeout_ms = timeout.map(|d| d.as_millis() as u64).unwrap_or(0);
-        let thread_guard = if let Some(timeout) = timeout {
-            self.rw_lock
-                .try_read_for(timeout)
-                .ok_or(MuroError::LockTimeout {
-                    mode: "shared",
-                    timeout_ms,
-                })?
-        } else {
-            self.rw_lock.read()
+        let deadline = timeout.map(|t| Instant::now() + t);
+        let thread_guard = match deadline {
+            Some(deadline) => self
+                .rw_lock
+                .try_read_until(deadline)
+                .ok_or_else(|| lock_timeout(LockMode::Shared, timeout))?,
+            None => self.rw_lock.read(),
         };
-
-        if let Some(timeout) = timeout {
-            let deadline = Instant::now() + timeout;
-            loop {
-                match self.lock_file.try_lock_shared() {
-                    Ok(()) => break,
-                    Err(std::fs::TryLockError::WouldBlock) => {
-                        let now = Instant::now();
-                        if now >= deadline {
-                            return Err(MuroError::LockTimeout {
-                                mode: "shared",
-                                timeout_ms,
-                            });
-                        }
-                        let remaining = deadline.saturating_duration_since(now);
-                        std::thread::sleep(std::cmp::min(Duration::from_millis(1), remaining));
-                    }
-                    Err(std::fs::TryLockError::Error(e)) => {
-                        return Err(MuroError::Lock(format!(
-                            "Failed to acquire shared file lock: {}",
-                            e
-                        )))
-                    }
-                }
-            }
-        } else {
-            self.lock_file.lock_shared().map_err(|e| {
-                MuroError::Lock(format!("Failed to acquire shared file lock: {}", e))
-            })?;
-        }
+        let lock_file = self.lock_process(LockMode::Shared, timeout, deadline)?;
+        self.hold.shared.fetch_add(1, Ordering::AcqRel);
 
         Ok(ReadGuard {
             _thread_guard: thread_guard,
-            lock_file: &self.lock_file,
+            lock_file,
+            hold: &self.hold,
         })
     }
 
@@ -102,75 +164,102 @@ impl LockManager {
 
     /// Acquire an exclusive (write) lock with timeout.
     ///
-    /// If `timeout` is `None`, this blocks until acquired.
+    /// If `timeout` is `None`, this blocks until acquired. The timeout
+    /// covers both the in-process wait and the wait for other processes.
     pub fn write_lock_with_timeout(&self, timeout: Option<Duration>) -> Result<WriteGuard<'_>> {
-        let timeout_ms = timeout.map(|d| d.as_millis() as u64).unwrap_or(0);
-        let thread_guard = if let Some(timeout) = timeout {
-            self.rw_lock
-                .try_write_for(timeout)
-                .ok_or(MuroError::LockTimeout {
-                    mode: "exclusive",
-                    timeout_ms,
-                })?
-        } else {
-            self.rw_lock.write()
+        let deadline = timeout.map(|t| Instant::now() + t);
+        let thread_guard = match deadline {
+            Some(deadline) => self
+                .rw_lock
+                .try_write_until(deadline)
+                .ok_or_else(|| lock_timeout(LockMode::Exclusive, timeout))?,
+            None => self.rw_lock.write(),
         };
+        let lock_file = self.lock_process(LockMode::Exclusive, timeout, deadline)?;
+        self.hold.exclusive.fetch_add(1, Ordering::AcqRel);
 
-        if let Some(timeout) = timeout {
-            let deadline = Instant::now() + timeout;
-            loop {
-                match self.lock_file.try_lock_exclusive() {
-                    Ok(()) => break,
-                    Err(e) if e.kind() == std::io::ErrorKind::WouldBlock => {
-                        let now = Instant::now();
-                        if now >= deadline {
-                            return Err(MuroError::LockTimeout {
-                                mode: "exclusive",
-                                timeout_ms,
-                            });
-                        }
-                        let remaining = deadline.saturating_duration_since(now);
-                        std::thread::sleep(std::cmp::min(Duration::from_millis(1), remaining));
-                    }
-                    Err(e) => {
-                        return Err(MuroError::Lock(format!(
-                            "Failed to acquire exclusive file lock: {}",
-                            e
-                        )))
+        Ok(WriteGuard {
+            _thread_guard: thread_guard,
+            lock_file,
+            hold: &self.hold,
+        })
+    }
+
+    /// Take the process-level lock on a fresh handle owned by the caller.
+    fn lock_process(
+        &self,
+        mode: LockMode,
+        timeout: Option<Duration>,
+        deadline: Option<Instant>,
+    ) -> Result<File> {
+        let file = Self::open_lock_file(&self.lock_path)?;
+        let lock_error = |e: std::io::Error| {
+            MuroError::Lock(format!(
+                "Failed to acquire {} file lock: {}",
+                mode.name(),
+                e
+            ))
+        };
+
+        let Some(deadline) = deadline else {
+            match mode {
+                LockMode::Shared => file.lock_shared(),
+                LockMode::Exclusive => file.lock_exclusive(),
+            }
+            .map_err(lock_error)?;
+            return Ok(file);
+        };
+
+        loop {
+            let attempt = match mode {
+                LockMode::Shared => file.try_lock_shared(),
+                LockMode::Exclusive => file.try_lock(),
+            };
+            match attempt {
+                Ok(()) => return Ok(file),
+                Err(std::fs::TryLockError::WouldBlock) => {
+                    let now = Instant::now();
+                    if now >= deadline {
+                        return Err(lock_timeout(mode, timeout));
                     }
+                    let remaining = deadline.saturating_duration_since(now);
+                    std::thread::sleep(std::cmp::min(Duration::from_millis(1), remaining));
                 }
+                Err(std::fs::TryLockError::Error(e)) => return Err(lock_error(e)),
             }
-        } else {
-            self.lock_file.lock_exclusive().map_err(|e| {
-                MuroError::Lock(format!("Failed to acquire exclusive file lock: {}", e))
-            })?;
         }
+    }
+}
 
-        Ok(WriteGuard {
-            _thread_guard: thread_guard,
-            lock_file: &self.lock_file,
-        })
+fn lock_timeout(mode: LockMode, timeout: Option<Duration>) -> MuroError {
+    MuroError::LockTimeout {
+        mode: mode.name(),
+        timeout_ms: timeout.map(|d| d.as_millis() as u64).unwrap_or(0),
     }
 }
 
 pub struct ReadGuard<'a> {
     _thread_guard: parking_lot::RwLockReadGuard<'a, ()>,
-    lock_file: &'a File,
+    lock_file: File,
+    hold: &'a LockHold,
 }
 
 impl<'a> Drop for ReadGuard<'a> {
     fn drop(&mut self) {
+        self.hold.shared.fetch_sub(1, Ordering::AcqRel);
         let _ = self.lock_file.unlock();
     }
 }
 
 pub struct WriteGuard<'a> {
     _thread_guard: parking_lot::RwLockWriteGuard<'a, ()>,
-    lock_file: &'a File,
+    lock_file: File,
+    hold: &'a LockHold,
 }
 
 impl<'a> Drop for WriteGuard<'a> {
     fn drop(&mut self) {
+        self.hold.exclusive.fetch_sub(1, Ordering::AcqRel);
         let _ = self.lock_file.unlock();
     }
 }
@@ -258,4 +347,65 @@ mod tests {
         };
         assert!(matches!(err, MuroError::LockTimeout { mode: "shared", .. }));
     }
+
+    #[test]
+    fn test_dropping_one_read_guard_keeps_other_threads_file_lock() {
+        let dir = TempDir::new().unwrap();
+        let db_path = dir.path().join("test.db");
+        File::create(&db_path).unwrap();
+
+        let lock_mgr = Arc::new(LockManager::new(&db_path).unwrap());
+        // Stands in for another process: a separate manager only meets this
+        // one at the file lock.
+        let other = LockManager::new(&db_path).unwrap();
+
+        let held = lock_mgr.read_lock().unwrap();
+        let lm = lock_mgr.clone();
+        thread::spawn(move || {
+            let _guard = lm.read_lock().unwrap();
+        })
+        .join()
+        .unwrap();
+
+        let err = match other.write_lock_with_timeout(Some(std::time::Duration::from_millis(20))) {
+            Err(err) => err,
+            Ok(_) => panic!("file lock was released while a read guard is still held"),
+        };
+        assert!(matches!(
+            err,
+            MuroError::LockTimeout {
+                mode: "exclusive",
+                ..
+            }
+        ));
+
+        drop(held);
+        other
+            .write_lock_with_timeout(Some(std::time::Duration::from_secs(5)))
+            .unwrap();
+    }
+
+    #[test]
+    fn test_lock_hold_tracks_guards() {
+        let dir = TempDir::new().unwrap();
+        let db_path = dir.path().join("test.db");
+        File::create(&db_path).unwrap();
+
+        let lock_mgr = LockManager::new(&db_path).unwrap();
+        let hold = lock_mgr.hold();
+        assert_eq!(hold.state(), LockState::Unlocked);
+        {
+            let _first = lock_mgr.read_lock().unwrap();
+            let second = lock_mgr.read_lock().unwrap();
+            assert_eq!(hold.state(), LockState::Shared);
+            drop(second);
+            assert_eq!(hold.state(), LockState::Shared);
+        }
+        assert_eq!(hold.state(), LockState::Unlocked);
+        {
+            let _guard = lock_mgr.write_lock().unwrap();
+            assert_eq!(hold.state(), LockState::Exclusive);
+        }
+        assert_eq!(hold.state(), LockState::Unlocked);
+    }
 }
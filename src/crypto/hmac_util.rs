@@ -1,38 +1,66 @@
 use crate::crypto::aead::MasterKey;
 use hmac::{Hmac, Mac};
 use sha2::Sha256;
+use zeroize::{Zeroize, ZeroizeOnDrop};
 
 type HmacSha256 = Hmac<Sha256>;
 const FTS_TERM_KEY_LABEL: &[u8] = b"murodb/fts/term-key/v1";
 const FTS_TERM_KEY_PLAINTEXT_LABEL: &[u8] = b"murodb/fts/term-key/plaintext/v1";
+const WAL_KEY_CHECK_LABEL: &[u8] = b"murodb/wal/key-check/v1";
+
+/// 256-bit key that blinds FULLTEXT terms.
+/// Key material is zeroed on drop, like [`MasterKey`].
+#[derive(Clone, Zeroize, ZeroizeOnDrop)]
+pub struct TermKey([u8; 32]);
+
+impl TermKey {
+    pub fn new(key: [u8; 32]) -> Self {
+        TermKey(key)
+    }
+
+    pub fn as_bytes(&self) -> &[u8; 32] {
+        &self.0
+    }
+}
 
 /// Compute HMAC-SHA256 for FTS term blinding.
 /// term_id = HMAC-SHA256(term_key, bigram_bytes)
 /// This ensures no plaintext tokens appear on disk.
-pub fn hmac_term_id(term_key: &[u8; 32], data: &[u8]) -> [u8; 32] {
-    let mut mac = HmacSha256::new_from_slice(term_key).expect("HMAC can take key of any size");
+pub fn hmac_term_id(term_key: &TermKey, data: &[u8]) -> [u8; 32] {
+    let mut mac =
+        HmacSha256::new_from_slice(term_key.as_bytes()).expect("HMAC can take key of any size");
     mac.update(data);
     let result = mac.finalize();
     result.into_bytes().into()
 }
 
 /// Derive a database-scoped FTS term key from the master key and DB salt.
-pub fn derive_fts_term_key(master_key: &MasterKey, salt: &[u8; 16]) -> [u8; 32] {
+pub fn derive_fts_term_key(master_key: &MasterKey, salt: &[u8; 16]) -> TermKey {
     let mut mac =
         HmacSha256::new_from_slice(master_key.as_bytes()).expect("HMAC can take key of any size");
     mac.update(FTS_TERM_KEY_LABEL);
     mac.update(salt);
-    let result = mac.finalize();
-    result.into_bytes().into()
+    TermKey::new(mac.finalize().into_bytes().into())
 }
 
 /// Derive a plaintext-mode FTS term key using DB salt only.
-pub fn derive_fts_term_key_plaintext(salt: &[u8; 16]) -> [u8; 32] {
+pub fn derive_fts_term_key_plaintext(salt: &[u8; 16]) -> TermKey {
     let mut mac = HmacSha256::new_from_slice(FTS_TERM_KEY_PLAINTEXT_LABEL)
         .expect("HMAC can take key of any size");
     mac.update(salt);
-    let result = mac.finalize();
-    result.into_bytes().into()
+    TermKey::new(mac.finalize().into_bytes().into())
+}
+
+/// Short fingerprint of the master key, recorded in the WAL header so that a
+/// WAL written under another key is recognized before any frame is decrypted.
+pub fn derive_wal_key_check(master_key: &MasterKey) -> [u8; 8] {
+    let mut mac =
+        HmacSha256::new_from_slice(master_key.as_bytes()).expect("HMAC can take key of any size");
+    mac.update(WAL_KEY_CHECK_LABEL);
+    let result = mac.finalize().into_bytes();
+    let mut check = [0u8; 8];
+    check.copy_from_slice(&result[..8]);
+    check
 }
 
 #[cfg(test)]
@@ -41,7 +69,7 @@ mod tests {
 
     #[test]
     fn test_hmac_deterministic() {
-        let key = [0x42u8; 32];
+        let key = TermKey::new([0x42u8; 32]);
         let h1 = hmac_term_id(&key, "東京".as_bytes());
         let h2 = hmac_term_id(&key, "東京".as_bytes());
         assert_eq!(h1, h2);
@@ -49,7 +77,7 @@ mod tests {
 
     #[test]
     fn test_hmac_different_input() {
-        let key = [0x42u8; 32];
+        let key = TermKey::new([0x42u8; 32]);
         let h1 = hmac_term_id(&key, "東京".as_bytes());
         let h2 = hmac_term_id(&key, "京都".as_bytes());
         assert_ne!(h1, h2);
@@ -57,8 +85,8 @@ mod tests {
 
     #[test]
     fn test_hmac_different_key() {
-        let h1 = hmac_term_id(&[0x01u8; 32], "test".as_bytes());
-        let h2 = hmac_term_id(&[0x02u8; 32], "test".as_bytes());
+        let h1 = hmac_term_id(&TermKey::new([0x01u8; 32]), "test".as_bytes());
+        let h2 = hmac_term_id(&TermKey::new([0x02u8; 32]), "test".as_bytes());
         assert_ne!(h1, h2);
     }
 
@@ -68,7 +96,7 @@ mod tests {
         let salt = [0x11u8; 16];
         let k1 = derive_fts_term_key(&master, &salt);
         let k2 = derive_fts_term_key(&master, &salt);
-        assert_eq!(k1, k2);
+        assert_eq!(k1.as_bytes(), k2.as_bytes());
     }
 
     #[test]
@@ -76,7 +104,7 @@ mod tests {
         let master = MasterKey::new([0x42u8; 32]);
         let k1 = derive_fts_term_key(&master, &[0x11u8; 16]);
         let k2 = derive_fts_term_key(&master, &[0x22u8; 16]);
-        assert_ne!(k1, k2);
+        assert_ne!(k1.as_bytes(), k2.as_bytes());
     }
 
     #[test]
@@ -84,13 +112,13 @@ mod tests {
         let salt = [0x11u8; 16];
         let k1 = derive_fts_term_key(&MasterKey::new([0x01u8; 32]), &salt);
         let k2 = derive_fts_term_key(&MasterKey::new([0x02u8; 32]), &salt);
-        assert_ne!(k1, k2);
+        assert_ne!(k1.as_bytes(), k2.as_bytes());
     }
 
     #[test]
     fn test_derive_fts_term_key_plaintext_changes_with_salt() {
         let k1 = derive_fts_term_key_plaintext(&[0x11u8; 16]);
         let k2 = derive_fts_term_key_plaintext(&[0x22u8; 16]);
-        assert_ne!(k1, k2);
+        assert_ne!(k1.as_bytes(), k2.as_bytes());
     }
 }
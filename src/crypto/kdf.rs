@@ -1,14 +1,16 @@
 use crate::crypto::aead::MasterKey;
 use crate::error::{MuroError, Result};
 use argon2::Argon2;
+use zeroize::Zeroizing;
 
 /// Derive a 256-bit master key from a passphrase using Argon2id.
+/// The derivation buffer is zeroed once the key is built.
 pub fn derive_key(passphrase: &[u8], salt: &[u8]) -> Result<MasterKey> {
-    let mut output = [0u8; 32];
+    let mut output = Zeroizing::new([0u8; 32]);
     Argon2::default()
-        .hash_password_into(passphrase, salt, &mut output)
+        .hash_password_into(passphrase, salt, output.as_mut())
         .map_err(|e| MuroError::Kdf(e.to_string()))?;
-    Ok(MasterKey::new(output))
+    Ok(MasterKey::new(*output))
 }
 
 /// Generate a random 16-byte salt.
@@ -0,0 +1,2365 @@
+//! The SQL database handle: [`Database`], [`DatabaseReader`] and the
+//! transaction and maintenance guards built on a [`Session`].
+
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use crate::btree::ops::BTree;
+use crate::concurrency::{HandleRegistration, LockManager};
+use crate::crypto::aead::MasterKey;
+use crate::crypto::hmac_util::TermKey;
+use crate::crypto::kdf;
+use crate::crypto::suite::EncryptionSuite;
+use crate::error::{MuroError, Result};
+use crate::fts::index::FtsIndex;
+use crate::fts::tokenizer::tokenize_bigram;
+use crate::schema::catalog::SystemCatalog;
+use crate::schema::index::IndexType;
+use crate::sql::ast::Statement;
+use crate::sql::executor::deserialize_row_versioned;
+use crate::sql::session::{import_script, DatabaseStats, MaintenanceRun, RuntimeConfig};
+use crate::storage::pager::{read_rekey_marker, rekey_marker_path, unwrap_rekey_old_key, Pager};
+use crate::tx::commit_outcome::CommitOutcomeLog;
+use crate::wal::header::WalIdentity;
+use crate::wal::writer::WalWriter;
+use crate::{
+    migrate_legacy_sidecar_paths, quarantine_wal_durably, sync_dir, truncate_wal_durably, wal_path,
+    ArchiveRestoreResult, BackupCursor, CancellationToken, CommitOutcome, CommitRef,
+    CorruptionReport, DatabaseEncryption, DbEncryptionInfo, DumpOptions, EngineStatus, ExecResult,
+    IncrementalManifest, Limits, MaintenanceBudget, MaintenanceReport, OpenOptions, PlanBaseline,
+    PreparedStatement, QueryCancelHandle, RecoveryMode, RecoveryResult, RetryPolicy, Row,
+    ScanCorruptionPolicy, SchemaDiff, SchemaExpectation, Session, SessionStatus, StatementMetrics,
+    Value, WalDurability,
+};
+
+const LEGACY_SQL_FTS_TERM_KEY: [u8; 32] = [0x55u8; 32];
+
+/// Main database handle.
+pub struct Database {
+    session: Session,
+    lock_manager: LockManager,
+    master_key: Option<MasterKey>,
+    db_path: PathBuf,
+    encryption_suite: EncryptionSuite,
+    /// Opened with [`Database::open_read_only`]: statements run under the
+    /// shared lock and writes fail with [`MuroError::ReadOnly`].
+    read_only: bool,
+    registration: HandleRegistration,
+}
+
+/// Read-only database handle for concurrent query workloads.
+pub struct DatabaseReader {
+    session: Session,
+    lock_manager: LockManager,
+    registration: HandleRegistration,
+}
+
+/// Exclusive maintenance access returned by [`Database::begin_maintenance`].
+///
+/// Dereferences to the [`Database`]; dropping it ends maintenance mode.
+pub struct MaintenanceGuard<'a> {
+    db: &'a mut Database,
+    started_at: Instant,
+}
+
+impl std::ops::Deref for MaintenanceGuard<'_> {
+    type Target = Database;
+
+    fn deref(&self) -> &Database {
+        self.db
+    }
+}
+
+impl std::ops::DerefMut for MaintenanceGuard<'_> {
+    fn deref_mut(&mut self) -> &mut Database {
+        self.db
+    }
+}
+
+impl Drop for MaintenanceGuard<'_> {
+    fn drop(&mut self) {
+        self.db
+            .registration
+            .end_maintenance(self.started_at.elapsed());
+    }
+}
+
+/// Statements of a transaction run by [`Database::transaction`].
+///
+/// Every statement joins the open transaction. `BEGIN`, `COMMIT` and
+/// `ROLLBACK` are rejected: the closure's result decides the outcome.
+pub struct TxHandle<'a> {
+    db: &'a mut Database,
+}
+
+impl TxHandle<'_> {
+    /// Execute a SQL statement inside the transaction.
+    pub fn execute(&mut self, sql: &str) -> Result<ExecResult> {
+        match Database::classify_sql(sql)? {
+            SqlStatementClass::Begin | SqlStatementClass::Commit | SqlStatementClass::Rollback => {
+                Err(MuroError::Transaction(
+                    "transaction control is not allowed inside Database::transaction; \
+                     return Ok to commit or Err to roll back"
+                        .into(),
+                ))
+            }
+            _ => self.db.execute(sql),
+        }
+    }
+
+    /// Convenience API: prepare+execute in one call using bound values.
+    pub fn execute_params(&mut self, sql: &str, params: &[Value]) -> Result<ExecResult> {
+        self.db.execute_params(sql, params)
+    }
+
+    /// Run a read-only query; it sees the transaction's own writes.
+    pub fn query(&mut self, sql: &str) -> Result<Vec<Row>> {
+        self.db.query(sql)
+    }
+
+    /// Convenience API: prepare+query in one call using bound values.
+    pub fn query_params(&mut self, sql: &str, params: &[Value]) -> Result<Vec<Row>> {
+        self.db.query_params(sql, params)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SqlStatementClass {
+    ReadOnly,
+    Begin,
+    Commit,
+    Rollback,
+    Write,
+}
+
+fn fts_value_to_text(value: &Value) -> Option<&str> {
+    match value {
+        Value::Varchar(s) => Some(s.as_str()),
+        _ => None,
+    }
+}
+
+fn resolve_missing_fts_term_key(
+    pager: &mut Pager,
+    catalog: &mut SystemCatalog,
+    bootstrap_key: TermKey,
+) -> Result<TermKey> {
+    if bootstrap_key.as_bytes() == &LEGACY_SQL_FTS_TERM_KEY {
+        return Ok(bootstrap_key);
+    }
+
+    let mut legacy_hits = 0u64;
+    let mut bootstrap_hits = 0u64;
+
+    let table_names = catalog.list_tables(pager)?;
+    for table_name in table_names {
+        let Some(table_def) = catalog.get_table(pager, &table_name)? else {
+            continue;
+        };
+        let indexes = catalog.get_indexes_for_table(pager, &table_name)?;
+        for idx in indexes {
+            if idx.index_type != IndexType::Fulltext {
+                continue;
+            }
+            let Some(col_name) = idx.column_names.first() else {
+                continue;
+            };
+            let Some(col_idx) = table_def.column_index(col_name) else {
+                continue;
+            };
+
+            let data_btree = BTree::open(table_def.data_btree_root);
+            let mut tokens_to_probe: Vec<String> = Vec::new();
+            data_btree.scan(pager, |_pk, row| {
+                if tokens_to_probe.len() >= 64 {
+                    return Ok(false);
+                }
+                let values = deserialize_row_versioned(
+                    row,
+                    &table_def.columns,
+                    table_def.row_format_version,
+                )?;
+                let Some(text) = values.get(col_idx).and_then(fts_value_to_text) else {
+                    return Ok(true);
+                };
+                let tokens = tokenize_bigram(text);
+                for token in tokens {
+                    if token.text.is_empty() {
+                        continue;
+                    }
+                    tokens_to_probe.push(token.text);
+                    if tokens_to_probe.len() >= 64 {
+                        break;
+                    }
+                }
+                Ok(tokens_to_probe.len() < 64)
+            })?;
+
+            let fts_legacy = FtsIndex::open(idx.btree_root, TermKey::new(LEGACY_SQL_FTS_TERM_KEY));
+            let fts_bootstrap = FtsIndex::open(idx.btree_root, bootstrap_key.clone());
+            for token in tokens_to_probe {
+                if fts_legacy.get_postings(pager, &token)?.df() > 0 {
+                    legacy_hits = legacy_hits.saturating_add(1);
+                    break;
+                }
+                if fts_bootstrap.get_postings(pager, &token)?.df() > 0 {
+                    bootstrap_hits = bootstrap_hits.saturating_add(1);
+                    break;
+                }
+            }
+        }
+    }
+
+    if legacy_hits > 0 && bootstrap_hits == 0 {
+        Ok(TermKey::new(LEGACY_SQL_FTS_TERM_KEY))
+    } else if bootstrap_hits > 0 && legacy_hits == 0 {
+        Ok(bootstrap_key)
+    } else if legacy_hits > bootstrap_hits {
+        Ok(TermKey::new(LEGACY_SQL_FTS_TERM_KEY))
+    } else {
+        Ok(bootstrap_key)
+    }
+}
+
+fn initialize_fts_term_key(
+    pager: &mut Pager,
+    catalog: Option<&mut SystemCatalog>,
+    missing_meta_key: TermKey,
+    persist_missing_meta: bool,
+) -> Result<bool> {
+    let Some(catalog) = catalog else {
+        pager.set_fts_term_key(pager.derive_bootstrap_fts_term_key());
+        return Ok(false);
+    };
+
+    match catalog.get_fts_term_key(pager) {
+        Ok(Some(existing)) => {
+            pager.set_fts_term_key(existing);
+            Ok(false)
+        }
+        Ok(None) => {
+            // Missing metadata means legacy DB; call site decides which key to backfill.
+            let generated = if missing_meta_key.as_bytes() == &LEGACY_SQL_FTS_TERM_KEY {
+                resolve_missing_fts_term_key(pager, catalog, pager.derive_bootstrap_fts_term_key())?
+            } else {
+                missing_meta_key
+            };
+            pager.set_fts_term_key(generated.clone());
+            if persist_missing_meta {
+                catalog.set_fts_term_key(pager, &generated)?;
+                Ok(true)
+            } else {
+                Ok(false)
+            }
+        }
+        // Some tests build DB files through Pager-only flows where catalog_root is unset (0).
+        // For those files, keep FTS usable via in-memory bootstrap without catalog writes.
+        Err(MuroError::InvalidPage) if pager.catalog_root() == 0 => {
+            pager.set_fts_term_key(pager.derive_bootstrap_fts_term_key());
+            Ok(false)
+        }
+        Err(e) => Err(e),
+    }
+}
+
+/// Build a session that can only read: the data file is opened without write
+/// access and the WAL is never opened for writing.
+///
+/// With `check_wal`, refuse while the WAL holds committed transactions the
+/// data file does not reflect yet (their txids are not below the header's
+/// `next_txid`): only a read-write open can recover them.
+fn open_read_only_session(
+    path: &Path,
+    suite: EncryptionSuite,
+    master_key: Option<&MasterKey>,
+    check_wal: bool,
+) -> Result<Session> {
+    let wp = wal_path(path);
+    let mut pager = Pager::open_read_only_with_suite(path, Some(suite), master_key)?;
+    if check_wal && wp.exists() {
+        crate::wal::recovery::check_wal_belongs_to_db(path, &wp, master_key)?;
+        let report = crate::wal::recovery::inspect_wal_with_suite(
+            &wp,
+            suite,
+            master_key,
+            RecoveryMode::Strict,
+        )?;
+        let next_txid = pager.next_txid();
+        if let Some(txid) = report.committed_txids.iter().find(|t| **t >= next_txid) {
+            return Err(MuroError::Wal(format!(
+                "WAL holds committed transaction {} not yet applied to the data file; open the database read-write to recover it",
+                txid
+            )));
+        }
+    }
+    let catalog_root = pager.catalog_root();
+    let mut catalog = SystemCatalog::open(catalog_root);
+    let has_uninitialized_catalog = catalog_root == 0 && pager.page_count() == 0;
+    initialize_fts_term_key(
+        &mut pager,
+        if has_uninitialized_catalog {
+            None
+        } else {
+            Some(&mut catalog)
+        },
+        TermKey::new(LEGACY_SQL_FTS_TERM_KEY),
+        false,
+    )?;
+    let wal = WalWriter::read_only(&wp, suite, master_key)?;
+    let mut session = Session::new(pager, catalog, wal);
+    // An older catalog stays readable; only a read-write open migrates it.
+    session.check_catalog_version()?;
+    Ok(session)
+}
+
+impl Database {
+    /// Parse and classify SQL for routing between read-only and write paths.
+    pub fn classify_sql(sql: &str) -> Result<SqlStatementClass> {
+        fn classify(stmt: &Statement) -> SqlStatementClass {
+            match stmt {
+                Statement::Select(_)
+                | Statement::SetQuery(_)
+                | Statement::ShowTables
+                | Statement::ShowCreateTable(_)
+                | Statement::ShowIndexes(_)
+                | Statement::Describe(_)
+                | Statement::ShowCheckpointStats
+                | Statement::ShowDatabaseStats
+                | Statement::ShowWarnings
+                | Statement::ShowProcesslist
+                | Statement::ShowVariables(_)
+                | Statement::ShowTableStatus
+                | Statement::CheckTable(_)
+                | Statement::Explain(_) => SqlStatementClass::ReadOnly,
+                Statement::ExplainAnalyze(inner) => classify(inner),
+                Statement::Begin => SqlStatementClass::Begin,
+                Statement::Commit => SqlStatementClass::Commit,
+                Statement::Rollback => SqlStatementClass::Rollback,
+                Statement::Savepoint(_)
+                | Statement::RollbackToSavepoint(_)
+                | Statement::ReleaseSavepoint(_)
+                | Statement::SetVariable(_) => SqlStatementClass::Write,
+                Statement::CreateTable(_)
+                | Statement::CreateIndex(_)
+                | Statement::CreateFulltextIndex(_)
+                | Statement::AnalyzeTable(_)
+                | Statement::OptimizeTable(_)
+                | Statement::Optimize
+                | Statement::DropTable(_)
+                | Statement::DropIndex(_)
+                | Statement::AlterTable(_)
+                | Statement::RenameTable(_)
+                | Statement::Insert(_)
+                | Statement::Update(_)
+                | Statement::Delete(_)
+                | Statement::InstallPlanBaseline(_)
+                | Statement::RemovePlanBaseline(_) => SqlStatementClass::Write,
+            }
+        }
+
+        let stmt = crate::sql::parser::parse_statement(sql)?;
+        Ok(classify(&stmt))
+    }
+
+    /// Read database file encryption metadata from header.
+    pub fn read_encryption_info(path: &Path) -> Result<DbEncryptionInfo> {
+        Pager::read_encryption_info_from_file(path)
+    }
+
+    /// Detect whether a database file is encrypted.
+    pub fn read_encryption_mode(path: &Path) -> Result<DatabaseEncryption> {
+        let info = Self::read_encryption_info(path)?;
+        Ok(match info.suite {
+            EncryptionSuite::Aes256GcmSiv => DatabaseEncryption::Encrypted,
+            EncryptionSuite::Plaintext => DatabaseEncryption::Plaintext,
+        })
+    }
+
+    /// Inspect WAL consistency without opening a writable SQL session.
+    pub fn inspect_wal(
+        db_path: &Path,
+        wal_path: &Path,
+        password: Option<&[u8]>,
+        mode: RecoveryMode,
+    ) -> Result<RecoveryResult> {
+        let info = Self::read_encryption_info(db_path)?;
+        match info.suite {
+            EncryptionSuite::Aes256GcmSiv => {
+                let password = password.ok_or_else(|| {
+                    MuroError::Encryption(
+                        "password is required for WAL inspection of encrypted database".to_string(),
+                    )
+                })?;
+                let key = kdf::derive_key(password, &info.salt)?;
+                crate::wal::recovery::inspect_wal(wal_path, &key, mode)
+            }
+            EncryptionSuite::Plaintext => crate::wal::recovery::inspect_wal_with_suite(
+                wal_path,
+                EncryptionSuite::Plaintext,
+                None,
+                mode,
+            ),
+        }
+    }
+
+    /// Create a new database at the given path.
+    pub fn create(path: &Path, master_key: &MasterKey) -> Result<Self> {
+        let registration = HandleRegistration::register(path)?;
+        // Held until the WAL exists, so a concurrent open does not recover
+        // and recreate it underneath this one.
+        let lock_manager = LockManager::new(path)?;
+        let create_guard = lock_manager.write_lock()?;
+        let mut pager = Pager::create(path, master_key)?;
+        let mut catalog = SystemCatalog::create(&mut pager)?;
+        let bootstrap_fts_key = pager.derive_bootstrap_fts_term_key();
+        initialize_fts_term_key(&mut pager, Some(&mut catalog), bootstrap_fts_key, true)?;
+        pager.set_catalog_root(catalog.root_page_id());
+        pager.flush_meta()?;
+
+        // Directory fsync to persist the newly created DB file metadata
+        sync_dir(path);
+
+        let wal = WalWriter::create_for_instance(
+            &wal_path(path),
+            EncryptionSuite::Aes256GcmSiv,
+            Some(master_key),
+            *pager.salt(),
+        )?;
+        let session = Session::new(pager, catalog, wal);
+        drop(create_guard);
+        session.track_lock(&lock_manager);
+
+        Ok(Database {
+            session,
+            lock_manager,
+            master_key: Some(master_key.clone()),
+            db_path: path.to_path_buf(),
+            encryption_suite: EncryptionSuite::Aes256GcmSiv,
+            read_only: false,
+            registration,
+        })
+    }
+
+    pub fn create_plaintext(path: &Path) -> Result<Self> {
+        let registration = HandleRegistration::register(path)?;
+        let lock_manager = LockManager::new(path)?;
+        let create_guard = lock_manager.write_lock()?;
+        let mut pager = Pager::create_plaintext(path)?;
+        let mut catalog = SystemCatalog::create(&mut pager)?;
+        let bootstrap_fts_key = pager.derive_bootstrap_fts_term_key();
+        initialize_fts_term_key(&mut pager, Some(&mut catalog), bootstrap_fts_key, true)?;
+        pager.set_catalog_root(catalog.root_page_id());
+        pager.flush_meta()?;
+
+        sync_dir(path);
+
+        let wal = WalWriter::create_for_instance(
+            &wal_path(path),
+            EncryptionSuite::Plaintext,
+            None,
+            *pager.salt(),
+        )?;
+        let session = Session::new(pager, catalog, wal);
+        drop(create_guard);
+        session.track_lock(&lock_manager);
+
+        Ok(Database {
+            session,
+            lock_manager,
+            master_key: None,
+            db_path: path.to_path_buf(),
+            encryption_suite: EncryptionSuite::Plaintext,
+            read_only: false,
+            registration,
+        })
+    }
+
+    /// Open an existing database.
+    pub fn open(path: &Path, master_key: &MasterKey) -> Result<Self> {
+        Ok(Self::open_with_recovery_mode_and_report(path, master_key, RecoveryMode::Strict)?.0)
+    }
+
+    /// Open an existing database with explicit open options.
+    pub fn open_with_options(
+        path: &Path,
+        master_key: &MasterKey,
+        options: OpenOptions,
+    ) -> Result<Self> {
+        let registration = Self::register_for_options(path, options)?;
+        let (mut db, _) = Self::open_encrypted_inner(
+            path,
+            master_key,
+            options.recovery_mode,
+            options.busy_timeout,
+            registration,
+        )?;
+        db.apply_open_options(options)?;
+        Ok(db)
+    }
+
+    pub fn open_plaintext_with_options(path: &Path, options: OpenOptions) -> Result<Self> {
+        let registration = Self::register_for_options(path, options)?;
+        let (mut db, _) = Self::open_plaintext_inner(
+            path,
+            options.recovery_mode,
+            options.busy_timeout,
+            registration,
+        )?;
+        db.apply_open_options(options)?;
+        Ok(db)
+    }
+
+    fn register_for_options(path: &Path, options: OpenOptions) -> Result<HandleRegistration> {
+        if options.exclusive_in_process {
+            HandleRegistration::register_exclusive(path)
+        } else {
+            HandleRegistration::register(path)
+        }
+    }
+
+    fn apply_open_options(&mut self, options: OpenOptions) -> Result<()> {
+        self.session
+            .set_busy_timeout_ms(options.busy_timeout.as_millis() as u64);
+        self.session
+            .pager_mut()
+            .set_cache_capacity(options.page_cache_pages);
+        self.session
+            .set_wal_write_buffer_bytes(options.wal_write_buffer_bytes);
+        self.session.set_wal_durability(options.wal_durability)
+    }
+
+    pub fn open_plaintext(path: &Path) -> Result<Self> {
+        Ok(Self::open_plaintext_with_recovery_mode_and_report(path, RecoveryMode::Strict)?.0)
+    }
+
+    pub fn open_plaintext_with_recovery_mode(
+        path: &Path,
+        recovery_mode: RecoveryMode,
+    ) -> Result<Self> {
+        Ok(Self::open_plaintext_with_recovery_mode_and_report(path, recovery_mode)?.0)
+    }
+
+    /// Open an existing database with configurable WAL recovery behavior.
+    pub fn open_with_recovery_mode(
+        path: &Path,
+        master_key: &MasterKey,
+        recovery_mode: RecoveryMode,
+    ) -> Result<Self> {
+        Ok(Self::open_with_recovery_mode_and_report(path, master_key, recovery_mode)?.0)
+    }
+
+    /// Open an existing database with configurable WAL recovery behavior and return recovery report.
+    pub fn open_with_recovery_mode_and_report(
+        path: &Path,
+        master_key: &MasterKey,
+        recovery_mode: RecoveryMode,
+    ) -> Result<(Self, Option<RecoveryResult>)> {
+        let registration = HandleRegistration::register(path)?;
+        Self::open_encrypted_inner(
+            path,
+            master_key,
+            recovery_mode,
+            Duration::ZERO,
+            registration,
+        )
+    }
+
+    fn open_encrypted_inner(
+        path: &Path,
+        master_key: &MasterKey,
+        recovery_mode: RecoveryMode,
+        busy_timeout: Duration,
+        registration: HandleRegistration,
+    ) -> Result<(Self, Option<RecoveryResult>)> {
+        migrate_legacy_sidecar_paths(path);
+        let wp = wal_path(path);
+        let mut recovery_report = None;
+        let mut commit_outcomes = None;
+        // Recovery rewrites the WAL and the writer is created over it, so
+        // both run under the exclusive lock: they never overlap a commit or
+        // the open of another handle, in this process or another.
+        let lock_manager = LockManager::new(path)?;
+        let open_guard = lock_manager
+            .write_lock_with_timeout((!busy_timeout.is_zero()).then_some(busy_timeout))?;
+
+        // Run WAL recovery before opening
+        if wp.exists() {
+            let mut report = crate::wal::recovery::recover_with_mode_and_suite(
+                path,
+                &wp,
+                EncryptionSuite::Aes256GcmSiv,
+                Some(master_key),
+                recovery_mode,
+            )?;
+            // Resolve in-doubt commits while the WAL still holds them.
+            commit_outcomes = Some(CommitOutcomeLog::resolve_at_open(
+                path,
+                &report.committed_txids,
+            )?);
+            if recovery_mode != RecoveryMode::Strict && report.lost_committed_data() {
+                let quarantine = quarantine_wal_durably(&wp)?;
+                report.wal_quarantine_path = Some(quarantine.display().to_string());
+            } else {
+                // Truncate WAL after successful recovery
+                let salt = Pager::read_encryption_info_from_file(path)?.salt;
+                truncate_wal_durably(
+                    &wp,
+                    &WalIdentity::new(salt, EncryptionSuite::Aes256GcmSiv, Some(master_key)),
+                )?;
+            }
+            recovery_report = Some(report);
+        }
+
+        let mut pager = Pager::open(path, master_key)?;
+        let catalog_root = pager.catalog_root();
+        let mut catalog = SystemCatalog::open(catalog_root);
+        let has_uninitialized_catalog = catalog_root == 0 && pager.page_count() == 0;
+        initialize_fts_term_key(
+            &mut pager,
+            if has_uninitialized_catalog {
+                None
+            } else {
+                Some(&mut catalog)
+            },
+            TermKey::new(LEGACY_SQL_FTS_TERM_KEY),
+            false,
+        )?;
+        let wal = WalWriter::create_for_instance(
+            &wp,
+            EncryptionSuite::Aes256GcmSiv,
+            Some(master_key),
+            *pager.salt(),
+        )?;
+        let mut session = Session::new(pager, catalog, wal);
+        session.set_commit_outcomes(match commit_outcomes {
+            Some(outcomes) => outcomes,
+            None => CommitOutcomeLog::resolve_at_open(path, &[])?,
+        });
+        // Still under the exclusive open lock: no other handle sees the
+        // catalog mid-migration, an index whose build is being resumed, or
+        // a crashed session's temporary tables being reclaimed.
+        session.migrate_catalog()?;
+        session.resume_index_builds()?;
+        session.reclaim_orphaned_temp_tables()?;
+        drop(open_guard);
+        session.track_lock(&lock_manager);
+
+        Ok((
+            Database {
+                session,
+                lock_manager,
+                master_key: Some(master_key.clone()),
+                db_path: path.to_path_buf(),
+                encryption_suite: EncryptionSuite::Aes256GcmSiv,
+                read_only: false,
+                registration,
+            },
+            recovery_report,
+        ))
+    }
+
+    pub fn open_plaintext_with_recovery_mode_and_report(
+        path: &Path,
+        recovery_mode: RecoveryMode,
+    ) -> Result<(Self, Option<RecoveryResult>)> {
+        let registration = HandleRegistration::register(path)?;
+        Self::open_plaintext_inner(path, recovery_mode, Duration::ZERO, registration)
+    }
+
+    fn open_plaintext_inner(
+        path: &Path,
+        recovery_mode: RecoveryMode,
+        busy_timeout: Duration,
+        registration: HandleRegistration,
+    ) -> Result<(Self, Option<RecoveryResult>)> {
+        migrate_legacy_sidecar_paths(path);
+        let wp = wal_path(path);
+        let mut recovery_report = None;
+        let mut commit_outcomes = None;
+        // Recovery rewrites the WAL and the writer is created over it, so
+        // both run under the exclusive lock: they never overlap a commit or
+        // the open of another handle, in this process or another.
+        let lock_manager = LockManager::new(path)?;
+        let open_guard = lock_manager
+            .write_lock_with_timeout((!busy_timeout.is_zero()).then_some(busy_timeout))?;
+
+        if wp.exists() {
+            let mut report = crate::wal::recovery::recover_with_mode_and_suite(
+                path,
+                &wp,
+                EncryptionSuite::Plaintext,
+                None,
+                recovery_mode,
+            )?;
+            // Resolve in-doubt commits while the WAL still holds them.
+            commit_outcomes = Some(CommitOutcomeLog::resolve_at_open(
+                path,
+                &report.committed_txids,
+            )?);
+            if recovery_mode != RecoveryMode::Strict && report.lost_committed_data() {
+                let quarantine = quarantine_wal_durably(&wp)?;
+                report.wal_quarantine_path = Some(quarantine.display().to_string());
+            } else {
+                let salt = Pager::read_encryption_info_from_file(path)?.salt;
+                truncate_wal_durably(
+                    &wp,
+                    &WalIdentity::new(salt, EncryptionSuite::Plaintext, None),
+                )?;
+            }
+            recovery_report = Some(report);
+        }
+
+        let mut pager = Pager::open_plaintext(path)?;
+        let catalog_root = pager.catalog_root();
+        let mut catalog = SystemCatalog::open(catalog_root);
+        let has_uninitialized_catalog = catalog_root == 0 && pager.page_count() == 0;
+        initialize_fts_term_key(
+            &mut pager,
+            if has_uninitialized_catalog {
+                None
+            } else {
+                Some(&mut catalog)
+            },
+            TermKey::new(LEGACY_SQL_FTS_TERM_KEY),
+            false,
+        )?;
+        let wal =
+            WalWriter::create_for_instance(&wp, EncryptionSuite::Plaintext, None, *pager.salt())?;
+        let mut session = Session::new(pager, catalog, wal);
+        session.set_commit_outcomes(match commit_outcomes {
+            Some(outcomes) => outcomes,
+            None => CommitOutcomeLog::resolve_at_open(path, &[])?,
+        });
+        // Still under the exclusive open lock: no other handle sees the
+        // catalog mid-migration, an index whose build is being resumed, or
+        // a crashed session's temporary tables being reclaimed.
+        session.migrate_catalog()?;
+        session.resume_index_builds()?;
+        session.reclaim_orphaned_temp_tables()?;
+        drop(open_guard);
+        session.track_lock(&lock_manager);
+
+        Ok((
+            Database {
+                session,
+                lock_manager,
+                master_key: None,
+                db_path: path.to_path_buf(),
+                encryption_suite: EncryptionSuite::Plaintext,
+                read_only: false,
+                registration,
+            },
+            recovery_report,
+        ))
+    }
+
+    /// Open an existing database for reading only.
+    ///
+    /// Any number of read-only handles, in any number of processes, can run
+    /// next to one read-write handle. The handle never writes the data file
+    /// or the WAL: statements run under the shared lock, and
+    /// [`Database::execute`] returns [`MuroError::ReadOnly`] for anything but
+    /// a read-only statement. Opening fails while the WAL holds committed
+    /// transactions that are not applied to the data file yet (after a crash,
+    /// or while a writer under relaxed [`WalDurability`] has unsynced
+    /// commits); a read-write open recovers them.
+    pub fn open_read_only(path: &Path, master_key: &MasterKey) -> Result<Self> {
+        Self::open_read_only_with_suite(path, EncryptionSuite::Aes256GcmSiv, Some(master_key))
+    }
+
+    /// Plaintext counterpart of [`Database::open_read_only`].
+    pub fn open_plaintext_read_only(path: &Path) -> Result<Self> {
+        Self::open_read_only_with_suite(path, EncryptionSuite::Plaintext, None)
+    }
+
+    fn open_read_only_with_suite(
+        path: &Path,
+        suite: EncryptionSuite,
+        master_key: Option<&MasterKey>,
+    ) -> Result<Self> {
+        let registration = HandleRegistration::register(path)?;
+        let lock_manager = LockManager::new(path)?;
+        // A writer must not commit or checkpoint while the header, freelist
+        // and WAL are read.
+        let session = {
+            let _guard = lock_manager.read_lock()?;
+            open_read_only_session(path, suite, master_key, true)?
+        };
+        session.track_lock(&lock_manager);
+        Ok(Database {
+            session,
+            lock_manager,
+            master_key: master_key.cloned(),
+            db_path: path.to_path_buf(),
+            encryption_suite: suite,
+            read_only: true,
+            registration,
+        })
+    }
+
+    /// Whether this handle was opened with [`Database::open_read_only`].
+    pub fn is_read_only(&self) -> bool {
+        self.read_only
+    }
+
+    /// Size limits of this database, for validating input before sending
+    /// it. Statements and writes past them fail with
+    /// [`MuroError::LimitExceeded`].
+    pub fn limits(&self) -> Limits {
+        Limits::current()
+    }
+
+    /// Create a new database with a password.
+    ///
+    /// The password is only borrowed; a caller holding it in a
+    /// `zeroize::Zeroizing<String>` keeps it out of freed memory.
+    pub fn create_with_password(path: &Path, password: impl AsRef<[u8]>) -> Result<Self> {
+        let registration = HandleRegistration::register(path)?;
+        let salt = kdf::generate_salt();
+        let master_key = kdf::derive_key(password.as_ref(), &salt)?;
+        let lock_manager = LockManager::new(path)?;
+        let create_guard = lock_manager.write_lock()?;
+        let mut pager = Pager::create_with_salt(path, &master_key, salt)?;
+        let mut catalog = SystemCatalog::create(&mut pager)?;
+        let bootstrap_fts_key = pager.derive_bootstrap_fts_term_key();
+        initialize_fts_term_key(&mut pager, Some(&mut catalog), bootstrap_fts_key, true)?;
+        pager.set_catalog_root(catalog.root_page_id());
+        pager.flush_meta()?;
+
+        // Directory fsync to persist the newly created DB file metadata
+        sync_dir(path);
+
+        let wal = WalWriter::create_for_instance(
+            &wal_path(path),
+            EncryptionSuite::Aes256GcmSiv,
+            Some(&master_key),
+            salt,
+        )?;
+        let session = Session::new(pager, catalog, wal);
+        drop(create_guard);
+        session.track_lock(&lock_manager);
+
+        Ok(Database {
+            session,
+            lock_manager,
+            master_key: Some(master_key),
+            db_path: path.to_path_buf(),
+            encryption_suite: EncryptionSuite::Aes256GcmSiv,
+            read_only: false,
+            registration,
+        })
+    }
+
+    /// Open an existing database with a password.
+    ///
+    /// If a `.rekey` marker file exists (from a crashed rekey operation), this
+    /// will attempt to complete the recovery before opening normally.
+    pub fn open_with_password(path: &Path, password: impl AsRef<[u8]>) -> Result<Self> {
+        let password = password.as_ref();
+        Self::recover_interrupted_rekey(path, password)?;
+        let info = Pager::read_encryption_info_from_file(path)?;
+        if info.suite != EncryptionSuite::Aes256GcmSiv {
+            return Err(crate::error::MuroError::Encryption(format!(
+                "database uses {}; open with plaintext mode",
+                info.suite.as_str()
+            )));
+        }
+        let salt = info.salt;
+        let master_key = kdf::derive_key(password, &salt)?;
+        Self::open(path, &master_key)
+    }
+
+    /// Open an existing database with a password and configurable recovery behavior.
+    pub fn open_with_password_and_recovery_mode(
+        path: &Path,
+        password: impl AsRef<[u8]>,
+        recovery_mode: RecoveryMode,
+    ) -> Result<Self> {
+        Ok(Self::open_with_password_and_recovery_mode_and_report(path, password, recovery_mode)?.0)
+    }
+
+    /// Open an existing database with a password, configurable recovery mode, and return recovery report.
+    pub fn open_with_password_and_recovery_mode_and_report(
+        path: &Path,
+        password: impl AsRef<[u8]>,
+        recovery_mode: RecoveryMode,
+    ) -> Result<(Self, Option<RecoveryResult>)> {
+        let password = password.as_ref();
+        Self::recover_interrupted_rekey(path, password)?;
+        let info = Pager::read_encryption_info_from_file(path)?;
+        if info.suite != EncryptionSuite::Aes256GcmSiv {
+            return Err(crate::error::MuroError::Encryption(format!(
+                "database uses {}; open with plaintext mode",
+                info.suite.as_str()
+            )));
+        }
+        let salt = info.salt;
+        let master_key = kdf::derive_key(password, &salt)?;
+        Self::open_with_recovery_mode_and_report(path, &master_key, recovery_mode)
+    }
+
+    /// Recover from a crashed rekey operation.
+    ///
+    /// If a `.rekey` marker file exists, this checks whether the rekey completed
+    /// (header salt matches marker salt) or needs to be re-run.
+    fn recover_interrupted_rekey(path: &Path, password: &[u8]) -> Result<()> {
+        let marker = rekey_marker_path(path);
+        if !marker.exists() {
+            return Ok(());
+        }
+        // Pages are rewritten in place; keep other handles out meanwhile.
+        let lock_manager = LockManager::new(path)?;
+        let _guard = lock_manager.write_lock()?;
+
+        let marker_info = read_rekey_marker(&marker)?;
+        let new_salt = marker_info.new_salt;
+        let new_epoch = marker_info.new_epoch;
+
+        // Read current DB header to check if rekey already completed
+        let info = Pager::read_encryption_info_from_file(path)?;
+        if info.salt == new_salt {
+            // Rekey completed successfully, just remove stale marker
+            let _ = std::fs::remove_file(&marker);
+            return Ok(());
+        }
+
+        // Rekey was interrupted mid-way. We need to complete it.
+        // Derive new key from password + marker's new salt.
+        let new_key = kdf::derive_key(password, &new_salt)?;
+
+        // Derive old key from wrapped marker payload.
+        let wrapped_old_key = marker_info.wrapped_old_key.ok_or_else(|| {
+            crate::error::MuroError::Execution(
+                "rekey recovery marker is missing wrapped old key; automatic recovery is unavailable".to_string(),
+            )
+        })?;
+        let old_key = unwrap_rekey_old_key(&new_key, new_epoch, &wrapped_old_key)?;
+        let old_epoch = new_epoch.saturating_sub(1);
+
+        // Open the file directly for recovery
+        let old_crypto =
+            crate::crypto::suite::PageCipher::new(EncryptionSuite::Aes256GcmSiv, Some(&old_key))?;
+        let new_crypto =
+            crate::crypto::suite::PageCipher::new(EncryptionSuite::Aes256GcmSiv, Some(&new_key))?;
+
+        let mut file = std::fs::OpenOptions::new()
+            .read(true)
+            .write(true)
+            .open(path)?;
+
+        // Re-read header for page_count
+        use std::io::{Read, Seek, SeekFrom, Write};
+        let mut header = [0u8; 76];
+        file.seek(SeekFrom::Start(0))?;
+        file.read_exact(&mut header)?;
+        let page_count = u64::from_le_bytes(header[36..44].try_into().unwrap());
+
+        let page_size_on_disk =
+            crate::storage::page::PAGE_SIZE + crate::crypto::aead::PageCrypto::overhead();
+
+        for page_id in 0..page_count {
+            let offset = 76 + page_id * page_size_on_disk as u64;
+            file.seek(SeekFrom::Start(offset))?;
+            let mut encrypted = vec![0u8; page_size_on_disk];
+            file.read_exact(&mut encrypted)?;
+
+            // Try decrypting with new key/epoch first (page already re-encrypted)
+            let mut plaintext = [0u8; crate::storage::page::PAGE_SIZE];
+            let decrypt_result =
+                new_crypto.decrypt_into(page_id, new_epoch, &encrypted, &mut plaintext);
+
+            if decrypt_result.is_err() {
+                // Page was not yet re-encrypted; decrypt with old key/epoch
+                let len =
+                    old_crypto.decrypt_into(page_id, old_epoch, &encrypted, &mut plaintext)?;
+                if len != crate::storage::page::PAGE_SIZE {
+                    return Err(crate::error::MuroError::InvalidPage);
+                }
+
+                // Re-encrypt with new key/epoch
+                let mut new_encrypted = vec![0u8; page_size_on_disk];
+                new_crypto.encrypt_into(page_id, new_epoch, &plaintext, &mut new_encrypted)?;
+                file.seek(SeekFrom::Start(offset))?;
+                file.write_all(&new_encrypted)?;
+            }
+        }
+
+        file.sync_data()?;
+
+        // Update header with new salt and epoch
+        header[12..28].copy_from_slice(&new_salt);
+        header[44..52].copy_from_slice(&new_epoch.to_le_bytes());
+        let checksum = crate::wal::record::crc32(&header[0..72]);
+        header[72..76].copy_from_slice(&checksum.to_le_bytes());
+        file.seek(SeekFrom::Start(0))?;
+        file.write_all(&header)?;
+        file.sync_all()?;
+
+        // Remove marker
+        let _ = std::fs::remove_file(&marker);
+
+        Ok(())
+    }
+
+    /// Execute a SQL statement. Returns the result.
+    pub fn execute(&mut self, sql: &str) -> Result<ExecResult> {
+        if self.read_only {
+            if Self::classify_sql(sql)? != SqlStatementClass::ReadOnly {
+                return Err(MuroError::ReadOnly);
+            }
+            return self.query(sql).map(ExecResult::Rows);
+        }
+        let ticket = self.registration.enter()?;
+        let timeout_ms = self.session.busy_timeout_ms();
+        let _guard = if timeout_ms == 0 {
+            self.lock_manager.write_lock()?
+        } else {
+            self.lock_manager
+                .write_lock_with_timeout(Some(Duration::from_millis(timeout_ms)))?
+        };
+        let result = self.session.execute(sql);
+        ticket.finish(self.session.transaction_info());
+        result
+    }
+
+    /// Execute a SQL statement that stops with [`MuroError::Cancelled`] once
+    /// `token` is cancelled, e.g. from another thread.
+    ///
+    /// Cancellation is checked while scanning, joining, aggregating, and
+    /// applying rows. A cancelled statement is rolled back like any failed
+    /// statement, so it leaves no partial writes and the handle stays usable.
+    pub fn execute_with_cancel(
+        &mut self,
+        sql: &str,
+        token: &CancellationToken,
+    ) -> Result<ExecResult> {
+        let previous = self.session.set_cancel_token(Some(token.clone()));
+        let result = self.execute(sql);
+        self.session.set_cancel_token(previous);
+        result
+    }
+
+    /// Execute a script of semicolon-separated statements, such as a schema
+    /// migration, and return one result per statement.
+    ///
+    /// The script runs under a single write lock. Unless it contains its own
+    /// `BEGIN`/`COMMIT`, it runs in one implicit transaction, so either every
+    /// statement takes effect or none does. A failing statement is reported
+    /// as [`MuroError::Script`] with its 0-based index and character offset.
+    pub fn execute_batch(&mut self, sql: &str) -> Result<Vec<ExecResult>> {
+        if self.read_only {
+            return Err(MuroError::ReadOnly);
+        }
+        let ticket = self.registration.enter()?;
+        let timeout_ms = self.session.busy_timeout_ms();
+        let _guard = if timeout_ms == 0 {
+            self.lock_manager.write_lock()?
+        } else {
+            self.lock_manager
+                .write_lock_with_timeout(Some(Duration::from_millis(timeout_ms)))?
+        };
+        let result = self.session.execute_batch(sql);
+        ticket.finish(self.session.transaction_info());
+        result
+    }
+
+    /// Run idle-time maintenance within `budget`: vacuum stale full-text
+    /// posting segments and release free pages at the end of the file.
+    ///
+    /// Each batch takes the write lock and commits on its own, so other
+    /// handles only wait for one batch, and a crash loses at most the batch
+    /// in flight. Call again while [`MaintenanceReport::is_complete`] is
+    /// false to finish the remaining work.
+    pub fn run_maintenance(&mut self, budget: MaintenanceBudget) -> Result<MaintenanceReport> {
+        if self.read_only {
+            return Err(MuroError::ReadOnly);
+        }
+        let mut run = MaintenanceRun::new(budget);
+        while run.wants_batch() {
+            let ticket = self.registration.enter()?;
+            let timeout_ms = self.session.busy_timeout_ms();
+            let _guard = if timeout_ms == 0 {
+                self.lock_manager.write_lock()?
+            } else {
+                self.lock_manager
+                    .write_lock_with_timeout(Some(Duration::from_millis(timeout_ms)))?
+            };
+            let result = self.session.run_maintenance_batch(&mut run);
+            ticket.finish(self.session.transaction_info());
+            result?;
+        }
+        Ok(run.finish())
+    }
+
+    /// Write the schema and rows as a SQL script for [`Database::import`].
+    ///
+    /// The whole dump is read under one shared lock, so it is a consistent
+    /// snapshot; writers on other handles wait until it is written. See
+    /// [`Session::dump`].
+    pub fn dump(&mut self, out: &mut impl std::io::Write, opts: DumpOptions) -> Result<()> {
+        let _ticket = self.registration.enter()?;
+        let timeout_ms = self.session.busy_timeout_ms();
+        let _guard = if timeout_ms == 0 {
+            self.lock_manager.read_lock()?
+        } else {
+            self.lock_manager
+                .read_lock_with_timeout(Some(Duration::from_millis(timeout_ms)))?
+        };
+        self.session.dump(out, opts)
+    }
+
+    /// Replay a script written by [`Database::dump`] and return the number
+    /// of statements run.
+    ///
+    /// The script is read as it is executed and committed every hundred or
+    /// so statements, each batch through [`Database::execute_batch`], so a
+    /// large dump neither sits in memory nor becomes one transaction. A
+    /// failure leaves the batches committed before it in place.
+    pub fn import(&mut self, reader: impl std::io::Read) -> Result<u64> {
+        import_script(reader, |sql| self.execute_batch(sql))
+    }
+
+    /// Get a handle that can request cancellation of in-flight statements.
+    pub fn cancel_handle(&self) -> QueryCancelHandle {
+        self.session.cancel_handle()
+    }
+
+    /// What this handle is running: statement text, phase, elapsed time,
+    /// open transaction, and the file lock it holds.
+    pub fn status(&self) -> SessionStatus {
+        self.session.status()
+    }
+
+    /// Live status of this handle, readable from another thread while a
+    /// statement runs; see [`Session::status_handle`].
+    pub fn status_handle(&self) -> Arc<EngineStatus> {
+        self.session.status_handle()
+    }
+
+    /// Configure per-statement execution timeout in milliseconds.
+    ///
+    /// `0` means no timeout.
+    pub fn set_statement_timeout_ms(&mut self, timeout_ms: u64) {
+        self.session.set_statement_timeout_ms(timeout_ms);
+    }
+
+    /// Current per-statement execution timeout in milliseconds.
+    ///
+    /// `0` means no timeout.
+    pub fn statement_timeout_ms(&self) -> u64 {
+        self.session.statement_timeout_ms()
+    }
+
+    /// Configure how full table scans handle unreadable pages and rows.
+    ///
+    /// See [`Session::set_scan_corruption_policy`].
+    pub fn set_scan_corruption_policy(&mut self, policy: ScanCorruptionPolicy) {
+        self.session.set_scan_corruption_policy(policy);
+    }
+
+    /// Current scan corruption policy.
+    pub fn scan_corruption_policy(&self) -> ScanCorruptionPolicy {
+        self.session.scan_corruption_policy()
+    }
+
+    /// Enable or disable cost-based ordering of WHERE conjuncts.
+    ///
+    /// See [`Session::set_predicate_reorder`].
+    pub fn set_predicate_reorder(&mut self, enabled: bool) {
+        self.session.set_predicate_reorder(enabled);
+    }
+
+    /// Whether WHERE conjuncts are reordered by estimated cost.
+    pub fn predicate_reorder(&self) -> bool {
+        self.session.predicate_reorder()
+    }
+
+    /// Abort an explicit transaction when one of its statements fails.
+    ///
+    /// See [`Session::set_abort_on_statement_error`].
+    pub fn set_abort_on_statement_error(&mut self, enabled: bool) {
+        self.session.set_abort_on_statement_error(enabled);
+    }
+
+    /// Whether a failed statement aborts its transaction.
+    pub fn abort_on_statement_error(&self) -> bool {
+        self.session.abort_on_statement_error()
+    }
+
+    /// Bytes GROUP BY may hold in memory before spilling to temp files.
+    ///
+    /// See [`Session::set_aggregation_memory_budget`].
+    pub fn set_aggregation_memory_budget(&mut self, bytes: usize) {
+        self.session.set_aggregation_memory_budget(bytes);
+    }
+
+    /// Memory budget of GROUP BY, in bytes.
+    pub fn aggregation_memory_budget(&self) -> usize {
+        self.session.aggregation_memory_budget()
+    }
+
+    /// Longest `column IN (...)` list planned as one seek per value.
+    ///
+    /// See [`Session::set_in_list_seek_max_items`].
+    pub fn set_in_list_seek_max_items(&mut self, items: usize) {
+        self.session.set_in_list_seek_max_items(items);
+    }
+
+    /// Longest `IN` list planned as one seek per value.
+    pub fn in_list_seek_max_items(&self) -> usize {
+        self.session.in_list_seek_max_items()
+    }
+
+    /// Cache up to `capacity` SELECT plans; `0` disables the cache.
+    ///
+    /// See [`Session::set_plan_cache_capacity`].
+    pub fn set_plan_cache_capacity(&mut self, capacity: usize) {
+        self.session.set_plan_cache_capacity(capacity);
+    }
+
+    /// Configured plan cache capacity; `0` when disabled.
+    pub fn plan_cache_capacity(&self) -> usize {
+        self.session.plan_cache_capacity()
+    }
+
+    /// Enable or disable installed plan baselines for this handle.
+    ///
+    /// See [`Session::set_plan_baselines_enabled`].
+    pub fn set_plan_baselines_enabled(&mut self, enabled: bool) {
+        self.session.set_plan_baselines_enabled(enabled);
+    }
+
+    /// Whether installed plan baselines are used.
+    pub fn plan_baselines_enabled(&self) -> bool {
+        self.session.plan_baselines_enabled()
+    }
+
+    /// Render this handle's statistics in the Prometheus text format.
+    ///
+    /// See [`Session::metrics_prometheus`]. Takes no lock and performs no page I/O.
+    pub fn metrics_prometheus(&self) -> String {
+        self.session.metrics_prometheus()
+    }
+
+    /// Counters behind `SHOW DATABASE STATS` for this handle.
+    pub fn database_stats(&self) -> &DatabaseStats {
+        self.session.database_stats()
+    }
+
+    /// Whether the last statement on this handle wrote pages or WAL frames.
+    ///
+    /// See [`StatementMetrics`].
+    pub fn last_statement_metrics(&self) -> StatementMetrics {
+        self.session.last_statement_metrics()
+    }
+
+    /// Tag the next commit so its verdict survives a `CommitInDoubt`.
+    ///
+    /// See [`Session::set_commit_tag`].
+    pub fn set_commit_tag(&mut self, tag: &str) -> Result<()> {
+        self.session.set_commit_tag(tag)
+    }
+
+    /// How recovery at open resolved an in-doubt commit, by tag or txid.
+    ///
+    /// See [`Session::commit_outcome`].
+    pub fn commit_outcome<'a>(&self, key: impl Into<CommitRef<'a>>) -> Option<CommitOutcome> {
+        self.session.commit_outcome(key)
+    }
+
+    /// Get current session runtime configuration.
+    pub fn runtime_config(&self) -> Result<RuntimeConfig> {
+        let timeout_ms = self.session.busy_timeout_ms();
+        let _guard = if timeout_ms == 0 {
+            self.lock_manager.read_lock()?
+        } else {
+            self.lock_manager
+                .read_lock_with_timeout(Some(Duration::from_millis(timeout_ms)))?
+        };
+        Ok(self.session.runtime_config())
+    }
+
+    /// Update session runtime configuration.
+    pub fn set_runtime_config(&mut self, config: RuntimeConfig) -> Result<()> {
+        let timeout_ms = self.session.busy_timeout_ms();
+        let _guard = if timeout_ms == 0 {
+            self.lock_manager.write_lock()?
+        } else {
+            self.lock_manager
+                .write_lock_with_timeout(Some(Duration::from_millis(timeout_ms)))?
+        };
+        self.session.set_runtime_config(config)
+    }
+
+    /// Configure lock wait timeout in milliseconds.
+    ///
+    /// `0` means wait indefinitely (default).
+    pub fn set_busy_timeout_ms(&mut self, timeout_ms: u64) {
+        self.session.set_busy_timeout_ms(timeout_ms);
+    }
+
+    /// Current lock wait timeout in milliseconds.
+    ///
+    /// `0` means wait indefinitely.
+    pub fn busy_timeout_ms(&self) -> u64 {
+        self.session.busy_timeout_ms()
+    }
+
+    /// Parse SQL into a reusable prepared statement template.
+    pub fn prepare(&self, sql: &str) -> Result<PreparedStatement> {
+        self.session.prepare(sql)
+    }
+
+    /// Execute a prepared statement with bound values.
+    pub fn execute_prepared(
+        &mut self,
+        prepared: &PreparedStatement,
+        params: &[Value],
+    ) -> Result<ExecResult> {
+        if self.read_only {
+            if Self::classify_sql(prepared.sql())? != SqlStatementClass::ReadOnly {
+                return Err(MuroError::ReadOnly);
+            }
+            return self.query_prepared(prepared, params).map(ExecResult::Rows);
+        }
+        let ticket = self.registration.enter()?;
+        let timeout_ms = self.session.busy_timeout_ms();
+        let _guard = if timeout_ms == 0 {
+            self.lock_manager.write_lock()?
+        } else {
+            self.lock_manager
+                .write_lock_with_timeout(Some(Duration::from_millis(timeout_ms)))?
+        };
+        let result = self.session.execute_prepared(prepared, params);
+        ticket.finish(self.session.transaction_info());
+        result
+    }
+
+    /// Convenience API: prepare+execute in one call using bound values.
+    pub fn execute_params(&mut self, sql: &str, params: &[Value]) -> Result<ExecResult> {
+        let prepared = self.prepare(sql)?;
+        self.execute_prepared(&prepared, params)
+    }
+
+    /// Insert many rows into `table` in one statement, without building SQL
+    /// text. Each row holds values for the visible columns in table order,
+    /// as in `INSERT INTO t VALUES (...)`. When the table is empty, the data
+    /// tree and its indexes are built bottom-up from the sorted rows, which
+    /// is much faster than inserting them one by one. Constraints are
+    /// enforced as for INSERT and the whole batch fails on any violation.
+    pub fn bulk_insert(&mut self, table: &str, rows: &[Vec<Value>]) -> Result<u64> {
+        if self.read_only {
+            return Err(MuroError::ReadOnly);
+        }
+        let ticket = self.registration.enter()?;
+        let timeout_ms = self.session.busy_timeout_ms();
+        let _guard = if timeout_ms == 0 {
+            self.lock_manager.write_lock()?
+        } else {
+            self.lock_manager
+                .write_lock_with_timeout(Some(Duration::from_millis(timeout_ms)))?
+        };
+        let result = self.session.bulk_insert(table, rows);
+        ticket.finish(self.session.transaction_info());
+        result
+    }
+
+    /// Execute a read-only SQL query and return rows.
+    ///
+    /// This takes only the shared lock and writes nothing to the WAL, so it
+    /// runs alongside queries on other handles; writes wait for it.
+    ///
+    /// Note: this method takes `&mut self` because the session may refresh
+    /// pager/catalog state from disk before executing the read, so one handle
+    /// runs one statement at a time. For parallel reads, give each thread its
+    /// own handle from [`Database::open_reader`] rather than sharing this one
+    /// behind a mutex. Non-read-only SQL returns an execution error.
+    pub fn query(&mut self, sql: &str) -> Result<Vec<Row>> {
+        let ticket = self.registration.enter()?;
+        let timeout_ms = self.session.busy_timeout_ms();
+        let _guard = if timeout_ms == 0 {
+            self.lock_manager.read_lock()?
+        } else {
+            self.lock_manager
+                .read_lock_with_timeout(Some(Duration::from_millis(timeout_ms)))?
+        };
+        let result = self.session.execute_read_only_query(sql);
+        ticket.finish(self.session.transaction_info());
+        result
+    }
+
+    /// [`Self::query`] that stops with [`MuroError::Cancelled`] once `token`
+    /// is cancelled; see [`Self::execute_with_cancel`].
+    pub fn query_with_cancel(&mut self, sql: &str, token: &CancellationToken) -> Result<Vec<Row>> {
+        let previous = self.session.set_cancel_token(Some(token.clone()));
+        let result = self.query(sql);
+        self.session.set_cancel_token(previous);
+        result
+    }
+
+    /// Execute a prepared read-only query and return rows.
+    pub fn query_prepared(
+        &mut self,
+        prepared: &PreparedStatement,
+        params: &[Value],
+    ) -> Result<Vec<Row>> {
+        let ticket = self.registration.enter()?;
+        let timeout_ms = self.session.busy_timeout_ms();
+        let _guard = if timeout_ms == 0 {
+            self.lock_manager.read_lock()?
+        } else {
+            self.lock_manager
+                .read_lock_with_timeout(Some(Duration::from_millis(timeout_ms)))?
+        };
+        let result = self
+            .session
+            .execute_read_only_prepared_query(prepared, params);
+        ticket.finish(self.session.transaction_info());
+        result
+    }
+
+    /// Convenience API: prepare+query in one call using bound values.
+    pub fn query_params(&mut self, sql: &str, params: &[Value]) -> Result<Vec<Row>> {
+        let prepared = self.prepare(sql)?;
+        self.query_prepared(&prepared, params)
+    }
+
+    /// Record the plan currently chosen for a single-table SELECT.
+    ///
+    /// See [`Session::capture_baseline`].
+    pub fn capture_baseline(&mut self, sql: &str) -> Result<PlanBaseline> {
+        let ticket = self.registration.enter()?;
+        let timeout_ms = self.session.busy_timeout_ms();
+        let _guard = if timeout_ms == 0 {
+            self.lock_manager.read_lock()?
+        } else {
+            self.lock_manager
+                .read_lock_with_timeout(Some(Duration::from_millis(timeout_ms)))?
+        };
+        let result = self.session.capture_baseline(sql);
+        ticket.finish(self.session.transaction_info());
+        result
+    }
+
+    /// Pin `baseline`'s plan for every statement of its shape.
+    ///
+    /// See [`Session::install_baseline`].
+    pub fn install_baseline(&mut self, baseline: &PlanBaseline) -> Result<()> {
+        if self.read_only {
+            return Err(MuroError::ReadOnly);
+        }
+        let ticket = self.registration.enter()?;
+        let timeout_ms = self.session.busy_timeout_ms();
+        let _guard = if timeout_ms == 0 {
+            self.lock_manager.write_lock()?
+        } else {
+            self.lock_manager
+                .write_lock_with_timeout(Some(Duration::from_millis(timeout_ms)))?
+        };
+        let result = self.session.install_baseline(baseline);
+        ticket.finish(self.session.transaction_info());
+        result
+    }
+
+    /// Remove the baseline installed for `shape`; returns whether there was one.
+    pub fn remove_baseline(&mut self, shape: &str) -> Result<bool> {
+        if self.read_only {
+            return Err(MuroError::ReadOnly);
+        }
+        let ticket = self.registration.enter()?;
+        let timeout_ms = self.session.busy_timeout_ms();
+        let _guard = if timeout_ms == 0 {
+            self.lock_manager.write_lock()?
+        } else {
+            self.lock_manager
+                .write_lock_with_timeout(Some(Duration::from_millis(timeout_ms)))?
+        };
+        let result = self.session.remove_baseline(shape);
+        ticket.finish(self.session.transaction_info());
+        result
+    }
+
+    /// Open an additional database handle for read-oriented workloads.
+    ///
+    /// This is useful when you want concurrent readers without manually
+    /// re-opening the same path and re-supplying key material.
+    ///
+    /// The returned handle is read-only and does not expose write APIs. It
+    /// shares this handle's in-process lock, so a write waiting for the file
+    /// lock holds back new queries on the readers until it has run.
+    pub fn open_reader(&self) -> Result<DatabaseReader> {
+        let master_key = match self.encryption_suite {
+            EncryptionSuite::Plaintext => None,
+            EncryptionSuite::Aes256GcmSiv => Some(self.master_key.as_ref().ok_or_else(|| {
+                MuroError::Encryption("missing master key for encrypted reader open".into())
+            })?),
+        };
+        // WAL records not yet in the data file are this handle's own
+        // deferred commits; the reader sees them once they are synced.
+        let mut session =
+            open_read_only_session(&self.db_path, self.encryption_suite, master_key, false)?;
+        session.set_statement_timeout_ms(self.session.statement_timeout_ms());
+        session.set_busy_timeout_ms(self.session.busy_timeout_ms());
+        session
+            .pager_mut()
+            .set_cache_capacity(self.session.pager().cache_capacity());
+        let lock_manager = self.lock_manager.sibling();
+        session.track_lock(&lock_manager);
+        Ok(DatabaseReader {
+            session,
+            lock_manager,
+            registration: self.registration.sibling(),
+        })
+    }
+
+    /// Re-encrypt the database with a new password-derived key.
+    pub fn rekey_with_password(&mut self, new_password: impl AsRef<[u8]>) -> Result<()> {
+        let new_password = new_password.as_ref();
+        if self.read_only {
+            return Err(MuroError::ReadOnly);
+        }
+        let _ticket = self.registration.enter()?;
+        let timeout_ms = self.session.busy_timeout_ms();
+        let _guard = if timeout_ms == 0 {
+            self.lock_manager.write_lock()?
+        } else {
+            self.lock_manager
+                .write_lock_with_timeout(Some(Duration::from_millis(timeout_ms)))?
+        };
+        self.session.rekey_with_password(new_password)?;
+        let info = Pager::read_encryption_info_from_file(&self.db_path)?;
+        if info.suite == EncryptionSuite::Aes256GcmSiv {
+            let master_key = kdf::derive_key(new_password, &info.salt)?;
+            self.master_key = Some(master_key);
+        } else {
+            self.master_key = None;
+        }
+        self.encryption_suite = info.suite;
+        Ok(())
+    }
+
+    /// Verify the catalog, every table and index, and the freelist.
+    ///
+    /// Returns `(object, status, detail)` rows like `CHECK TABLE`; problems are
+    /// reported as `error` rows instead of failing on the first one.
+    pub fn verify_integrity(&mut self) -> Result<Vec<Row>> {
+        let _ticket = self.registration.enter()?;
+        let timeout_ms = self.session.busy_timeout_ms();
+        let _guard = if timeout_ms == 0 {
+            self.lock_manager.read_lock()?
+        } else {
+            self.lock_manager
+                .read_lock_with_timeout(Some(Duration::from_millis(timeout_ms)))?
+        };
+        self.session.verify_integrity()
+    }
+
+    /// Check the schema against what the application expects, typically at
+    /// startup. See [`Session::assert_schema`].
+    pub fn assert_schema(&mut self, expected: &SchemaExpectation) -> Result<SchemaDiff> {
+        let _ticket = self.registration.enter()?;
+        let timeout_ms = self.session.busy_timeout_ms();
+        let _guard = if timeout_ms == 0 {
+            self.lock_manager.read_lock()?
+        } else {
+            self.lock_manager
+                .read_lock_with_timeout(Some(Duration::from_millis(timeout_ms)))?
+        };
+        self.session.assert_schema(expected)
+    }
+
+    /// Read every page and report the unreadable ones with the table, index,
+    /// or other structure each belongs to. See [`Session::corruption_report`].
+    pub fn corruption_report(&mut self) -> Result<CorruptionReport> {
+        let _ticket = self.registration.enter()?;
+        let timeout_ms = self.session.busy_timeout_ms();
+        let _guard = if timeout_ms == 0 {
+            self.lock_manager.read_lock()?
+        } else {
+            self.lock_manager
+                .read_lock_with_timeout(Some(Duration::from_millis(timeout_ms)))?
+        };
+        self.session.corruption_report()
+    }
+
+    /// Get the catalog root page ID (needed for internal reopen flows).
+    pub(crate) fn catalog_root(&self) -> u64 {
+        self.session.catalog().root_page_id()
+    }
+
+    /// Flush all data to disk. A read-only handle has nothing to flush.
+    pub fn flush(&mut self) -> Result<()> {
+        if self.read_only {
+            return Ok(());
+        }
+        let _ticket = self.registration.enter()?;
+        let timeout_ms = self.session.busy_timeout_ms();
+        let _guard = if timeout_ms == 0 {
+            self.lock_manager.write_lock()?
+        } else {
+            self.lock_manager
+                .write_lock_with_timeout(Some(Duration::from_millis(timeout_ms)))?
+        };
+        self.session.flush_commit_batch()?;
+        let catalog_root = self.session.catalog().root_page_id();
+        let pager = self.session.pager_mut();
+        pager.set_catalog_root(catalog_root);
+        pager.flush_meta()
+    }
+
+    /// Configure when commits are fsynced.
+    ///
+    /// `GroupCommit` and `Async` trade the durability of the most recent
+    /// commits for throughput; they never leave the database corrupt. They
+    /// assume this handle is the only writer: other processes see deferred
+    /// commits only once their batch is synced.
+    pub fn set_wal_durability(&mut self, durability: WalDurability) -> Result<()> {
+        let _ticket = self.registration.enter()?;
+        let timeout_ms = self.session.busy_timeout_ms();
+        let _guard = if timeout_ms == 0 {
+            self.lock_manager.write_lock()?
+        } else {
+            self.lock_manager
+                .write_lock_with_timeout(Some(Duration::from_millis(timeout_ms)))?
+        };
+        self.session.set_wal_durability(durability)
+    }
+
+    /// Current WAL durability mode.
+    pub fn wal_durability(&self) -> WalDurability {
+        self.session.wal_durability()
+    }
+
+    /// Bytes of encrypted WAL frames a commit buffers before writing them to
+    /// the WAL file. Larger commits write the buffer out (without fsync) each
+    /// time it fills, so the memory a commit uses for frames does not grow
+    /// with its size; the commit record is still written and fsynced last.
+    /// `0` writes every frame as it is built.
+    pub fn set_wal_write_buffer_bytes(&mut self, bytes: usize) {
+        self.session.set_wal_write_buffer_bytes(bytes);
+    }
+
+    /// Current WAL write buffer size in bytes.
+    pub fn wal_write_buffer_bytes(&self) -> usize {
+        self.session.wal_write_buffer_bytes()
+    }
+
+    /// Archive the WAL at every checkpoint into `dir` instead of discarding
+    /// it; `None` stops archiving.
+    ///
+    /// Before the WAL is truncated, its contents are copied (still encrypted)
+    /// to the segment `<db>.wal.NNNNNN` in `dir` and recorded in
+    /// `<db>.wal.index`; both are fsynced first. A [`Database::backup`] plus
+    /// the segments archived after it restore with
+    /// [`Database::restore_from_archive`]. Archiving applies to this handle
+    /// only, and a WAL replayed by crash recovery when the database is opened
+    /// is not archived: take a new base backup after such an open.
+    pub fn set_wal_archive_dir(&mut self, dir: Option<&Path>) -> Result<()> {
+        if self.read_only {
+            return Err(MuroError::ReadOnly);
+        }
+        let _ticket = self.registration.enter()?;
+        let timeout_ms = self.session.busy_timeout_ms();
+        let _guard = if timeout_ms == 0 {
+            self.lock_manager.write_lock()?
+        } else {
+            self.lock_manager
+                .write_lock_with_timeout(Some(Duration::from_millis(timeout_ms)))?
+        };
+        self.session.set_wal_archive_dir(dir)
+    }
+
+    /// Directory WAL segments are archived into, if archiving is on.
+    pub fn wal_archive_dir(&self) -> Option<&Path> {
+        self.session.wal_archive_dir()
+    }
+
+    /// Rebuild a database at `target` from the base backup `base_backup`
+    /// and the WAL segments archived in `archive_dir`.
+    ///
+    /// `target` must not exist. Segments are replayed in order through WAL
+    /// recovery; those already contained in the base backup are skipped.
+    /// The result opens with the same key as the archived database.
+    pub fn restore_from_archive(
+        base_backup: &Path,
+        archive_dir: &Path,
+        target: &Path,
+        master_key: &MasterKey,
+    ) -> Result<ArchiveRestoreResult> {
+        crate::wal::archive::restore_from_archive(
+            base_backup,
+            archive_dir,
+            target,
+            EncryptionSuite::Aes256GcmSiv,
+            Some(master_key),
+        )
+    }
+
+    /// Plaintext counterpart of [`Database::restore_from_archive`].
+    pub fn restore_plaintext_from_archive(
+        base_backup: &Path,
+        archive_dir: &Path,
+        target: &Path,
+    ) -> Result<ArchiveRestoreResult> {
+        crate::wal::archive::restore_from_archive(
+            base_backup,
+            archive_dir,
+            target,
+            EncryptionSuite::Plaintext,
+            None,
+        )
+    }
+
+    /// Create a consistent backup of the database to `dest`.
+    ///
+    /// Acquires a write lock, checkpoints the WAL (flushing all committed
+    /// data to the main file), then performs a byte-level copy of the
+    /// database file. The backup file is a valid MuroDB database that can
+    /// be opened directly with the same key/password.
+    ///
+    /// The returned cursor starts a chain of
+    /// [`Database::backup_incremental`] calls on top of this backup.
+    pub fn backup<P: AsRef<Path>>(&mut self, dest: P) -> Result<BackupCursor> {
+        if self.read_only {
+            return Err(MuroError::ReadOnly);
+        }
+        let _ticket = self.registration.enter()?;
+        let timeout_ms = self.session.busy_timeout_ms();
+        let _guard = if timeout_ms == 0 {
+            self.lock_manager.write_lock()?
+        } else {
+            self.lock_manager
+                .write_lock_with_timeout(Some(Duration::from_millis(timeout_ms)))?
+        };
+        // Checkpoint WAL so all committed data is in the data file.
+        self.session.try_checkpoint_truncate_once()?;
+        let pager = self.session.pager_mut();
+        let cursor = pager.close_backup_generation()?;
+        // Copy the data file bytes.
+        pager.backup_to_file(dest.as_ref())?;
+        Ok(cursor)
+    }
+
+    /// Back up only the pages written since the backup that returned
+    /// `since`, into the directory `dest_dir`.
+    ///
+    /// Like [`Database::backup`], this holds the write lock and checkpoints
+    /// the WAL first. `dest_dir` is created if needed and must not already
+    /// hold a backup. It receives the changed pages exactly as stored on
+    /// disk and a manifest listing them with their write generations and
+    /// checksums. The returned cursor continues the chain;
+    /// [`Database::restore_from_incrementals`] applies the chain onto the
+    /// full backup it started from.
+    ///
+    /// Fails if `since` came from another database, from before the key was
+    /// rotated, or from before the page generation file (`<db>.pagegen`)
+    /// was lost: take a new full backup then.
+    pub fn backup_incremental<P: AsRef<Path>>(
+        &mut self,
+        dest_dir: P,
+        since: BackupCursor,
+    ) -> Result<(IncrementalManifest, BackupCursor)> {
+        if self.read_only {
+            return Err(MuroError::ReadOnly);
+        }
+        let _ticket = self.registration.enter()?;
+        let timeout_ms = self.session.busy_timeout_ms();
+        let _guard = if timeout_ms == 0 {
+            self.lock_manager.write_lock()?
+        } else {
+            self.lock_manager
+                .write_lock_with_timeout(Some(Duration::from_millis(timeout_ms)))?
+        };
+        self.session.try_checkpoint_truncate_once()?;
+        crate::storage::incremental_backup::backup_incremental(
+            self.session.pager_mut(),
+            dest_dir.as_ref(),
+            &since,
+        )
+    }
+
+    /// Rebuild a database at `target` from the full backup `base_backup`
+    /// and the incremental backup directories `manifests_in_order`, oldest
+    /// first.
+    ///
+    /// `target` must not exist. Each incremental backup must continue the
+    /// one before it (the first one, the base backup): a missing or
+    /// reordered link is refused, as is a page or manifest that does not
+    /// match its checksum. Nothing is left at `target` on failure. The
+    /// result opens with the same key as the backed-up database.
+    pub fn restore_from_incrementals(
+        base_backup: &Path,
+        manifests_in_order: &[&Path],
+        target: &Path,
+    ) -> Result<()> {
+        crate::storage::incremental_backup::restore_from_incrementals(
+            base_backup,
+            manifests_in_order,
+            target,
+        )
+    }
+
+    /// Create a consistent backup of the database to `dest`, encrypted under
+    /// a key derived from `backup_password` and a fresh salt.
+    ///
+    /// Unlike [`Database::backup`], the copy shares no key material with the
+    /// source: it opens with `open_with_password(dest, backup_password)` and
+    /// not with the source's password. Pages are re-encrypted as they are
+    /// streamed; the header is written last, so an interrupted backup leaves
+    /// a file that fails to open instead of a truncated database.
+    pub fn backup_with_new_password<P: AsRef<Path>>(
+        &mut self,
+        dest: P,
+        backup_password: impl AsRef<[u8]>,
+    ) -> Result<()> {
+        if self.read_only {
+            return Err(MuroError::ReadOnly);
+        }
+        let _ticket = self.registration.enter()?;
+        let timeout_ms = self.session.busy_timeout_ms();
+        let _guard = if timeout_ms == 0 {
+            self.lock_manager.write_lock()?
+        } else {
+            self.lock_manager
+                .write_lock_with_timeout(Some(Duration::from_millis(timeout_ms)))?
+        };
+        if self.encryption_suite == EncryptionSuite::Plaintext {
+            return Err(MuroError::Execution(
+                "backup with a new password is not supported for plaintext databases".into(),
+            ));
+        }
+        let salt = kdf::generate_salt();
+        let key = kdf::derive_key(backup_password.as_ref(), &salt)?;
+        self.session.try_checkpoint_truncate_once()?;
+        self.session
+            .pager_mut()
+            .backup_to_file_with_key(dest.as_ref(), &key, salt)
+    }
+
+    /// Take exclusive use of the database for maintenance work.
+    ///
+    /// Waits up to `timeout` for every other `Database` and `DatabaseReader`
+    /// on the same file in this process to finish its in-flight statement and
+    /// any open explicit transaction. Meanwhile, and until the guard is
+    /// dropped, their new statements fail with
+    /// [`MuroError::MaintenanceInProgress`]; handles that still have a
+    /// transaction open may keep running until it ends. On timeout the
+    /// database returns to normal service and [`MuroError::Busy`] names the
+    /// blocking handles and transactions.
+    ///
+    /// Other processes are not covered: they are still arbitrated by the
+    /// file lock alone.
+    pub fn begin_maintenance(&mut self, timeout: Duration) -> Result<MaintenanceGuard<'_>> {
+        self.registration
+            .begin_maintenance(timeout, self.session.transaction_info())?;
+        Ok(MaintenanceGuard {
+            db: self,
+            started_at: Instant::now(),
+        })
+    }
+
+    /// Run `f` in a transaction: commit when it returns `Ok`, roll back when
+    /// it returns `Err` or panics. A panic is re-raised after the rollback.
+    ///
+    /// Fails with [`MuroError::Transaction`] if this handle already has a
+    /// transaction open, rather than joining it.
+    ///
+    /// ```
+    /// use murodb::{Database, MuroError};
+    ///
+    /// let dir = tempfile::TempDir::new().unwrap();
+    /// let mut db = Database::create_plaintext(&dir.path().join("app.db")).unwrap();
+    /// db.execute("CREATE TABLE t (id BIGINT PRIMARY KEY)").unwrap();
+    ///
+    /// db.transaction(|tx| {
+    ///     tx.execute("INSERT INTO t VALUES (1)")?;
+    ///     tx.execute("INSERT INTO t VALUES (2)")?;
+    ///     Ok(())
+    /// })
+    /// .unwrap();
+    ///
+    /// let result: murodb::Result<()> = db.transaction(|tx| {
+    ///     tx.execute("INSERT INTO t VALUES (3)")?;
+    ///     Err(MuroError::Execution("changed my mind".into()))
+    /// });
+    /// assert!(result.is_err());
+    /// assert_eq!(db.query("SELECT * FROM t").unwrap().len(), 2);
+    /// ```
+    pub fn transaction<T, F>(&mut self, f: F) -> Result<T>
+    where
+        F: FnOnce(&mut TxHandle<'_>) -> Result<T>,
+    {
+        if self.session.transaction_info().is_some() {
+            return Err(MuroError::Transaction(
+                "Database::transaction cannot run inside an open transaction".into(),
+            ));
+        }
+        self.execute("BEGIN")?;
+        let outcome = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| {
+            f(&mut TxHandle { db: &mut *self })
+        }));
+        match outcome {
+            Ok(Ok(value)) => match self.execute("COMMIT") {
+                Ok(_) => Ok(value),
+                Err(e) => {
+                    // A COMMIT that never got the lock leaves the transaction open.
+                    self.rollback_open_transaction();
+                    Err(e)
+                }
+            },
+            Ok(Err(e)) => {
+                self.rollback_open_transaction();
+                Err(e)
+            }
+            Err(panic) => {
+                self.rollback_open_transaction();
+                std::panic::resume_unwind(panic)
+            }
+        }
+    }
+
+    /// Like [`Database::transaction`], but run the whole transaction again
+    /// when an attempt fails with a lock error, as `policy` allows. The last
+    /// error is returned once retries run out.
+    ///
+    /// `f` may therefore run several times, each attempt starting from a
+    /// rolled-back state. Keep it free of side effects outside the database,
+    /// or make them safe to repeat.
+    pub fn transaction_with_retry<T, F>(&mut self, policy: RetryPolicy, mut f: F) -> Result<T>
+    where
+        F: FnMut(&mut TxHandle<'_>) -> Result<T>,
+    {
+        let mut retries = 0;
+        loop {
+            match self.transaction(&mut f) {
+                Err(e) if policy.should_retry(&e, retries) => {
+                    std::thread::sleep(policy.backoff(retries));
+                    retries += 1;
+                }
+                result => return result,
+            }
+        }
+    }
+
+    /// Roll back the transaction [`Database::transaction`] left open. Waits
+    /// for the lock without the busy timeout, since giving up would leave
+    /// the transaction open on this handle.
+    pub(crate) fn rollback_open_transaction(&mut self) {
+        if self.session.transaction_info().is_none() {
+            return;
+        }
+        let Ok(ticket) = self.registration.enter() else {
+            return;
+        };
+        let Ok(_guard) = self.lock_manager.write_lock() else {
+            return;
+        };
+        let _ = self.session.execute("ROLLBACK");
+        ticket.finish(self.session.transaction_info());
+    }
+
+    /// Route the writes and fsyncs of the database file and the WAL through
+    /// `fault`, or stop doing so with `None`. See [`crate::fault`].
+    #[cfg(any(test, feature = "test-utils"))]
+    pub fn set_fault_injector(&mut self, fault: Option<crate::fault::FaultInjector>) {
+        self.session.set_fault_injector(fault);
+    }
+
+    /// Create a `Session` that supports BEGIN/COMMIT/ROLLBACK.
+    ///
+    /// This consumes the Database and returns a Session. The Session owns the
+    /// pager, catalog, and WAL writer, and manages explicit transaction state.
+    /// It also takes over the lock manager and busy timeout, so each statement
+    /// still runs under the file lock and cannot interleave with writers in
+    /// other handles or processes. The Session leaves the in-process handle
+    /// registry, so [`Database::begin_maintenance`] does not wait for it.
+    ///
+    /// ```
+    /// use murodb::{Database, MuroError};
+    ///
+    /// let dir = tempfile::TempDir::new().unwrap();
+    /// let path = dir.path().join("app.db");
+    /// let mut db = Database::create_plaintext(&path).unwrap();
+    /// db.set_busy_timeout_ms(50);
+    /// let mut session = db.into_session();
+    /// session.execute("CREATE TABLE t (id BIGINT PRIMARY KEY)").unwrap();
+    ///
+    /// session.execute("BEGIN").unwrap();
+    /// session.execute("INSERT INTO t VALUES (1)").unwrap();
+    /// {
+    ///     // Another process takes the exclusive lock on `app.db.lock`: the
+    ///     // COMMIT waits for it and gives up after the busy timeout.
+    ///     let other = std::fs::File::open(dir.path().join("app.db.lock")).unwrap();
+    ///     other.lock().unwrap();
+    ///     assert!(matches!(
+    ///         session.execute("COMMIT"),
+    ///         Err(MuroError::LockTimeout { .. })
+    ///     ));
+    /// }
+    /// session.execute("COMMIT").unwrap();
+    /// assert_eq!(session.execute_read_only_query("SELECT * FROM t").unwrap().len(), 1);
+    /// ```
+    pub fn into_session(self) -> Session {
+        let mut session = self.session;
+        session.attach_lock_manager(self.lock_manager, self.registration);
+        session
+    }
+}
+
+impl DatabaseReader {
+    /// Get a handle that can request cancellation of in-flight statements.
+    pub fn cancel_handle(&self) -> QueryCancelHandle {
+        self.session.cancel_handle()
+    }
+
+    /// What this handle is running: statement text, phase, elapsed time,
+    /// open transaction, and the file lock it holds.
+    pub fn status(&self) -> SessionStatus {
+        self.session.status()
+    }
+
+    /// Live status of this handle, readable from another thread while a
+    /// statement runs; see [`Session::status_handle`].
+    pub fn status_handle(&self) -> Arc<EngineStatus> {
+        self.session.status_handle()
+    }
+
+    /// Configure lock wait timeout in milliseconds.
+    ///
+    /// `0` means wait indefinitely (default).
+    pub fn set_busy_timeout_ms(&mut self, timeout_ms: u64) {
+        self.session.set_busy_timeout_ms(timeout_ms);
+    }
+
+    /// Current lock wait timeout in milliseconds.
+    ///
+    /// `0` means wait indefinitely.
+    pub fn busy_timeout_ms(&self) -> u64 {
+        self.session.busy_timeout_ms()
+    }
+
+    /// Configure per-statement execution timeout in milliseconds.
+    ///
+    /// `0` means no timeout.
+    pub fn set_statement_timeout_ms(&mut self, timeout_ms: u64) {
+        self.session.set_statement_timeout_ms(timeout_ms);
+    }
+
+    /// Current per-statement execution timeout in milliseconds.
+    ///
+    /// `0` means no timeout.
+    pub fn statement_timeout_ms(&self) -> u64 {
+        self.session.statement_timeout_ms()
+    }
+
+    /// Configure how full table scans handle unreadable pages and rows.
+    ///
+    /// With [`ScanCorruptionPolicy::Skip`], queries return every readable row
+    /// and report what was skipped via `SHOW WARNINGS`.
+    pub fn set_scan_corruption_policy(&mut self, policy: ScanCorruptionPolicy) {
+        self.session.set_scan_corruption_policy(policy);
+    }
+
+    /// Current scan corruption policy.
+    pub fn scan_corruption_policy(&self) -> ScanCorruptionPolicy {
+        self.session.scan_corruption_policy()
+    }
+
+    /// Enable or disable cost-based ordering of WHERE conjuncts.
+    ///
+    /// See [`Session::set_predicate_reorder`].
+    pub fn set_predicate_reorder(&mut self, enabled: bool) {
+        self.session.set_predicate_reorder(enabled);
+    }
+
+    /// Whether WHERE conjuncts are reordered by estimated cost.
+    pub fn predicate_reorder(&self) -> bool {
+        self.session.predicate_reorder()
+    }
+
+    /// Bytes GROUP BY may hold in memory before spilling to temp files.
+    ///
+    /// See [`Session::set_aggregation_memory_budget`].
+    pub fn set_aggregation_memory_budget(&mut self, bytes: usize) {
+        self.session.set_aggregation_memory_budget(bytes);
+    }
+
+    /// Memory budget of GROUP BY, in bytes.
+    pub fn aggregation_memory_budget(&self) -> usize {
+        self.session.aggregation_memory_budget()
+    }
+
+    /// Longest `column IN (...)` list planned as one seek per value.
+    ///
+    /// See [`Session::set_in_list_seek_max_items`].
+    pub fn set_in_list_seek_max_items(&mut self, items: usize) {
+        self.session.set_in_list_seek_max_items(items);
+    }
+
+    /// Longest `IN` list planned as one seek per value.
+    pub fn in_list_seek_max_items(&self) -> usize {
+        self.session.in_list_seek_max_items()
+    }
+
+    /// Cache up to `capacity` SELECT plans; `0` disables the cache.
+    ///
+    /// See [`Session::set_plan_cache_capacity`].
+    pub fn set_plan_cache_capacity(&mut self, capacity: usize) {
+        self.session.set_plan_cache_capacity(capacity);
+    }
+
+    /// Configured plan cache capacity; `0` when disabled.
+    pub fn plan_cache_capacity(&self) -> usize {
+        self.session.plan_cache_capacity()
+    }
+
+    /// Enable or disable installed plan baselines for this handle.
+    ///
+    /// See [`Session::set_plan_baselines_enabled`].
+    pub fn set_plan_baselines_enabled(&mut self, enabled: bool) {
+        self.session.set_plan_baselines_enabled(enabled);
+    }
+
+    /// Whether installed plan baselines are used.
+    pub fn plan_baselines_enabled(&self) -> bool {
+        self.session.plan_baselines_enabled()
+    }
+
+    /// Render this reader's statistics in the Prometheus text format.
+    pub fn metrics_prometheus(&self) -> String {
+        self.session.metrics_prometheus()
+    }
+
+    /// Writes of the last query on this reader; always zero.
+    pub fn last_statement_metrics(&self) -> StatementMetrics {
+        self.session.last_statement_metrics()
+    }
+
+    /// Parse SQL into a reusable prepared statement template.
+    pub fn prepare(&self, sql: &str) -> Result<PreparedStatement> {
+        self.session.prepare(sql)
+    }
+
+    /// Execute a read-only SQL query and return rows.
+    pub fn query(&mut self, sql: &str) -> Result<Vec<Row>> {
+        let ticket = self.registration.enter()?;
+        let timeout_ms = self.session.busy_timeout_ms();
+        let _guard = if timeout_ms == 0 {
+            self.lock_manager.read_lock()?
+        } else {
+            self.lock_manager
+                .read_lock_with_timeout(Some(Duration::from_millis(timeout_ms)))?
+        };
+        let result = self.session.execute_read_only_query(sql);
+        ticket.finish(None);
+        result
+    }
+
+    /// Execute a prepared read-only query and return rows.
+    pub fn query_prepared(
+        &mut self,
+        prepared: &PreparedStatement,
+        params: &[Value],
+    ) -> Result<Vec<Row>> {
+        let ticket = self.registration.enter()?;
+        let timeout_ms = self.session.busy_timeout_ms();
+        let _guard = if timeout_ms == 0 {
+            self.lock_manager.read_lock()?
+        } else {
+            self.lock_manager
+                .read_lock_with_timeout(Some(Duration::from_millis(timeout_ms)))?
+        };
+        let result = self
+            .session
+            .execute_read_only_prepared_query(prepared, params);
+        ticket.finish(None);
+        result
+    }
+
+    /// Convenience API: prepare+query in one call using bound values.
+    pub fn query_params(&mut self, sql: &str, params: &[Value]) -> Result<Vec<Row>> {
+        let prepared = self.prepare(sql)?;
+        self.query_prepared(&prepared, params)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::wal_path;
+    use super::Database;
+    use crate::wal::reader::WalReader;
+    use crate::wal::record::WalRecord;
+    use crate::wal::writer::WalWriter;
+    use std::sync::mpsc;
+    use std::thread;
+    use std::time::Duration;
+    use tempfile::TempDir;
+
+    #[test]
+    fn flush_waits_while_read_lock_is_held() {
+        let dir = TempDir::new().unwrap();
+        let db_path = dir.path().join("flush_lock.db");
+
+        let mut db1 = Database::create_plaintext(&db_path).unwrap();
+        db1.execute("CREATE TABLE t (id BIGINT PRIMARY KEY)")
+            .unwrap();
+        db1.execute("INSERT INTO t VALUES (1)").unwrap();
+
+        let db2 = Database::open_plaintext(&db_path).unwrap();
+        let read_guard = db2.lock_manager.read_lock().unwrap();
+
+        let (tx, rx) = mpsc::channel();
+        let handle = thread::spawn(move || {
+            let mut db = db1;
+            db.flush().unwrap();
+            tx.send(()).unwrap();
+        });
+
+        thread::sleep(Duration::from_millis(100));
+        assert!(
+            rx.try_recv().is_err(),
+            "flush completed even though another handle held a read lock"
+        );
+
+        drop(read_guard);
+        rx.recv_timeout(Duration::from_secs(2))
+            .expect("flush should complete after read lock is released");
+        handle.join().unwrap();
+    }
+
+    #[test]
+    fn flush_respects_busy_timeout() {
+        let dir = TempDir::new().unwrap();
+        let db_path = dir.path().join("flush_timeout.db");
+
+        let mut db1 = Database::create_plaintext(&db_path).unwrap();
+        db1.execute("CREATE TABLE t (id BIGINT PRIMARY KEY)")
+            .unwrap();
+        db1.execute("INSERT INTO t VALUES (1)").unwrap();
+        db1.set_busy_timeout_ms(20);
+
+        let db2 = Database::open_plaintext(&db_path).unwrap();
+        let read_guard = db2.lock_manager.read_lock().unwrap();
+
+        let err = db1
+            .flush()
+            .expect_err("flush should time out while read lock is held");
+        assert!(matches!(
+            err,
+            crate::MuroError::LockTimeout {
+                mode: "exclusive",
+                ..
+            }
+        ));
+
+        drop(read_guard);
+    }
+
+    #[test]
+    fn open_reader_plaintext_can_query_same_db() {
+        let dir = TempDir::new().unwrap();
+        let db_path = dir.path().join("reader_plaintext.db");
+
+        let mut db = Database::create_plaintext(&db_path).unwrap();
+        db.execute("CREATE TABLE t (id BIGINT PRIMARY KEY, name VARCHAR)")
+            .unwrap();
+        db.execute("INSERT INTO t VALUES (1, 'alice')").unwrap();
+
+        let mut reader = db.open_reader().unwrap();
+        let rows = reader
+            .query("SELECT name FROM t WHERE id = 1")
+            .expect("reader query should succeed");
+        assert_eq!(rows.len(), 1);
+        assert_eq!(
+            rows[0].get("name"),
+            Some(&crate::types::Value::Varchar("alice".to_string()))
+        );
+    }
+
+    #[test]
+    fn open_reader_works_after_rekey_with_password() {
+        let dir = TempDir::new().unwrap();
+        let db_path = dir.path().join("reader_rekey.db");
+
+        let mut db = Database::create_with_password(&db_path, "old-pass").unwrap();
+        db.execute("CREATE TABLE t (id BIGINT PRIMARY KEY)")
+            .unwrap();
+        db.execute("INSERT INTO t VALUES (1)").unwrap();
+        db.rekey_with_password("new-pass").unwrap();
+
+        let mut reader = db.open_reader().unwrap();
+        let rows = reader
+            .query("SELECT id FROM t")
+            .expect("reader query after rekey should succeed");
+        assert_eq!(rows.len(), 1);
+        assert_eq!(rows[0].get("id"), Some(&crate::types::Value::Integer(1)));
+    }
+
+    #[test]
+    fn open_reader_does_not_truncate_existing_wal() {
+        let dir = TempDir::new().unwrap();
+        let db_path = dir.path().join("reader_wal_preserve.db");
+
+        let db = Database::create_plaintext(&db_path).unwrap();
+        let wp = wal_path(&db_path);
+
+        {
+            let mut wal = WalWriter::open_plaintext(&wp, 0).unwrap();
+            wal.append(&WalRecord::Begin { txid: 42 }).unwrap();
+            wal.sync().unwrap();
+        }
+        let size_before = std::fs::metadata(&wp).unwrap().len();
+
+        let _reader = db.open_reader().unwrap();
+
+        let size_after = std::fs::metadata(&wp).unwrap().len();
+        assert_eq!(size_after, size_before, "open_reader must not truncate WAL");
+
+        let mut wal_reader = WalReader::open_plaintext(&wp).unwrap();
+        let records = wal_reader.read_all().unwrap();
+        assert_eq!(records.len(), 1);
+    }
+
+    #[test]
+    fn runtime_config_api_updates_checkpoint_policy() {
+        let dir = TempDir::new().unwrap();
+        let db_path = dir.path().join("runtime_config_api.db");
+
+        let mut db = Database::create_plaintext(&db_path).unwrap();
+        db.set_runtime_config(crate::sql::session::RuntimeConfig {
+            checkpoint_tx_threshold: 7,
+            checkpoint_wal_bytes_threshold: 4096,
+            checkpoint_interval_ms: 500,
+        })
+        .unwrap();
+
+        let cfg = db.runtime_config().unwrap();
+        assert_eq!(cfg.checkpoint_tx_threshold, 7);
+        assert_eq!(cfg.checkpoint_wal_bytes_threshold, 4096);
+        assert_eq!(cfg.checkpoint_interval_ms, 500);
+    }
+
+    #[test]
+    fn statement_timeout_api_roundtrip() {
+        let dir = TempDir::new().unwrap();
+        let db_path = dir.path().join("statement_timeout_api.db");
+
+        let mut db = Database::create_plaintext(&db_path).unwrap();
+        assert_eq!(db.statement_timeout_ms(), 0);
+        db.set_statement_timeout_ms(123);
+        assert_eq!(db.statement_timeout_ms(), 123);
+    }
+
+    #[test]
+    fn open_reader_inherits_statement_timeout() {
+        let dir = TempDir::new().unwrap();
+        let db_path = dir.path().join("reader_statement_timeout.db");
+
+        let mut db = Database::create_plaintext(&db_path).unwrap();
+        db.set_statement_timeout_ms(77);
+
+        let reader = db.open_reader().unwrap();
+        assert_eq!(reader.statement_timeout_ms(), 77);
+    }
+}
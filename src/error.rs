@@ -11,6 +11,18 @@ pub enum MuroError {
     #[error("Decryption error: page may be corrupted or tampered")]
     Decryption,
 
+    /// A page read from disk failed authentication (damage, tampering, or a
+    /// wrong key).
+    #[error(
+        "Decryption error: page {page_id} failed authentication; it may be corrupted or tampered"
+    )]
+    PageDecrypt {
+        page_id: u64,
+        /// LSN recorded for the page, when known. Page images carry no LSN in
+        /// the current format, so the pager reports `None`.
+        page_lsn: Option<u64>,
+    },
+
     #[error("Page overflow: data exceeds page capacity")]
     PageOverflow,
 
@@ -23,6 +35,17 @@ pub enum MuroError {
     #[error("WAL error: {0}")]
     Wal(String),
 
+    /// The WAL was written for a different database or configuration than
+    /// the data file beside it; recovery refuses to replay it in any mode.
+    #[error(
+        "WAL does not belong to this database: {field} is {wal_value} in the WAL but {db_value} in the data file; if this pairing is intentional, move the WAL file aside before opening"
+    )]
+    WalMismatch {
+        field: &'static str,
+        wal_value: String,
+        db_value: String,
+    },
+
     #[error("Transaction error: {0}")]
     Transaction(String),
 
@@ -44,6 +67,18 @@ pub enum MuroError {
     #[error("Unique constraint violation: {0}")]
     UniqueViolation(String),
 
+    /// A write broke a NOT NULL, CHECK or FOREIGN KEY constraint.
+    #[error("Constraint violation: {0}")]
+    ConstraintViolation(String),
+
+    /// A statement or write went past one of the [`Limits`](crate::Limits).
+    #[error("Limit exceeded: {limit} is {max}, got {value}")]
+    LimitExceeded {
+        limit: crate::limits::Limit,
+        value: u64,
+        max: u64,
+    },
+
     #[error("Type error: {0}")]
     Type(String),
 
@@ -68,8 +103,357 @@ pub enum MuroError {
     #[error("Session poisoned: {0}. Database must be reopened to trigger WAL recovery.")]
     SessionPoisoned(String),
 
+    #[error("Database is open read-only")]
+    ReadOnly,
+
+    #[error("Database is in maintenance mode")]
+    MaintenanceInProgress,
+
+    /// The file is already open in this process and one of the two opens
+    /// asked for it exclusively; see `OpenOptions::exclusive_in_process`.
+    #[error("Database is already open in this process: {0}")]
+    AlreadyOpen(String),
+
+    #[error("Database busy: {0}")]
+    Busy(String),
+
     #[error("Internal error: {0}")]
     Internal(String),
+
+    /// The catalog was written by a newer library version whose format this
+    /// one cannot read.
+    #[error(
+        "Incompatible catalog version {found}: this library reads catalog versions up to {supported}; upgrade murodb to open this database"
+    )]
+    IncompatibleVersion { found: u32, supported: u32 },
+
+    /// The live schema lacks required elements of a
+    /// [`SchemaExpectation`](crate::schema::expectation::SchemaExpectation).
+    #[cfg(feature = "sql")]
+    #[error("Schema mismatch: {0}")]
+    SchemaMismatch(Box<crate::schema::expectation::SchemaDiff>),
+
+    /// A statement of a script run by `execute_batch` failed. `index` is the
+    /// 0-based statement number and `offset` the character offset where the
+    /// statement starts in the script.
+    #[error("Statement {index} at offset {offset} failed: {source}")]
+    Script {
+        index: usize,
+        offset: usize,
+        source: Box<MuroError>,
+    },
+}
+
+/// Coarse fault category of a [`MuroError`], for deciding how a caller should react.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ErrorClass {
+    /// The statement or its input is wrong (syntax, unknown table, bad type, misuse of the API).
+    UserError,
+    /// The statement is well-formed but conflicts with a declared constraint.
+    ConstraintViolation,
+    /// A temporary condition such as lock contention or an I/O hiccup; retrying may succeed.
+    Transient,
+    /// Disk, memory, or a configured budget ran out.
+    ResourceExhausted,
+    /// On-disk data failed validation or authentication (a wrong key is indistinguishable
+    /// from tampering).
+    Corruption,
+    /// A bug or an unrecoverable engine state; the handle may need to be reopened.
+    Internal,
+}
+
+impl MuroError {
+    /// Classify this error. Every variant maps to exactly one class.
+    pub fn error_class(&self) -> ErrorClass {
+        match self {
+            MuroError::Io(e) => classify_io_error(e),
+            MuroError::Encryption(_) => ErrorClass::Internal,
+            MuroError::Decryption => ErrorClass::Corruption,
+            MuroError::PageDecrypt { .. } => ErrorClass::Corruption,
+            MuroError::PageOverflow => ErrorClass::UserError,
+            MuroError::PageNotFound(_) => ErrorClass::Corruption,
+            MuroError::InvalidPage => ErrorClass::Corruption,
+            MuroError::Wal(_) => ErrorClass::Corruption,
+            MuroError::WalMismatch { .. } => ErrorClass::UserError,
+            MuroError::Transaction(_) => ErrorClass::UserError,
+            MuroError::Schema(_) => ErrorClass::UserError,
+            MuroError::Parse(_) => ErrorClass::UserError,
+            MuroError::Execution(_) => ErrorClass::UserError,
+            MuroError::Cancelled => ErrorClass::UserError,
+            MuroError::StatementTimeout { .. } => ErrorClass::ResourceExhausted,
+            MuroError::UniqueViolation(_) => ErrorClass::ConstraintViolation,
+            MuroError::ConstraintViolation(_) => ErrorClass::ConstraintViolation,
+            MuroError::LimitExceeded { .. } => ErrorClass::UserError,
+            MuroError::Type(_) => ErrorClass::UserError,
+            MuroError::Lock(_) => ErrorClass::Transient,
+            MuroError::LockTimeout { .. } => ErrorClass::Transient,
+            MuroError::Fts(_) => ErrorClass::UserError,
+            MuroError::Kdf(_) => ErrorClass::Internal,
+            MuroError::Corruption(_) => ErrorClass::Corruption,
+            // Retrying would risk applying the transaction twice; reopen to recover.
+            MuroError::CommitInDoubt(_) => ErrorClass::Internal,
+            MuroError::SessionPoisoned(_) => ErrorClass::Internal,
+            MuroError::ReadOnly => ErrorClass::UserError,
+            MuroError::MaintenanceInProgress => ErrorClass::Transient,
+            MuroError::AlreadyOpen(_) => ErrorClass::UserError,
+            MuroError::Busy(_) => ErrorClass::Transient,
+            MuroError::Internal(_) => ErrorClass::Internal,
+            MuroError::IncompatibleVersion { .. } => ErrorClass::UserError,
+            #[cfg(feature = "sql")]
+            MuroError::SchemaMismatch(_) => ErrorClass::UserError,
+            MuroError::Script { source, .. } => source.error_class(),
+        }
+    }
+
+    /// Whether the same operation may succeed if retried unchanged.
+    pub fn is_retryable(&self) -> bool {
+        self.error_class() == ErrorClass::Transient
+    }
+
+    /// Whether the error indicates damaged or unauthenticated on-disk data.
+    pub fn is_data_corruption(&self) -> bool {
+        self.error_class() == ErrorClass::Corruption
+    }
+}
+
+fn classify_io_error(e: &std::io::Error) -> ErrorClass {
+    use std::io::ErrorKind;
+    match e.kind() {
+        ErrorKind::StorageFull
+        | ErrorKind::QuotaExceeded
+        | ErrorKind::FileTooLarge
+        | ErrorKind::OutOfMemory => ErrorClass::ResourceExhausted,
+        // A short read or undecodable bytes mean the file itself is damaged.
+        ErrorKind::UnexpectedEof | ErrorKind::InvalidData => ErrorClass::Corruption,
+        ErrorKind::NotFound
+        | ErrorKind::PermissionDenied
+        | ErrorKind::AlreadyExists
+        | ErrorKind::InvalidInput
+        | ErrorKind::ReadOnlyFilesystem
+        | ErrorKind::IsADirectory
+        | ErrorKind::NotADirectory
+        | ErrorKind::Unsupported => ErrorClass::UserError,
+        // EIO and other uncategorized failures: the device may recover, and data that
+        // is actually damaged surfaces as Decryption/Corruption once it is read back.
+        _ => ErrorClass::Transient,
+    }
 }
 
 pub type Result<T> = std::result::Result<T, MuroError>;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Exhaustive on purpose: a new variant does not compile until it is
+    /// named here, and it then needs a row in `SPEC` and a sample.
+    fn variant_name(e: &MuroError) -> &'static str {
+        match e {
+            MuroError::Io(_) => "Io",
+            MuroError::Encryption(_) => "Encryption",
+            MuroError::Decryption => "Decryption",
+            MuroError::PageDecrypt { .. } => "PageDecrypt",
+            MuroError::PageOverflow => "PageOverflow",
+            MuroError::PageNotFound(_) => "PageNotFound",
+            MuroError::InvalidPage => "InvalidPage",
+            MuroError::Wal(_) => "Wal",
+            MuroError::WalMismatch { .. } => "WalMismatch",
+            MuroError::Transaction(_) => "Transaction",
+            MuroError::Schema(_) => "Schema",
+            MuroError::Parse(_) => "Parse",
+            MuroError::Execution(_) => "Execution",
+            MuroError::Cancelled => "Cancelled",
+            MuroError::StatementTimeout { .. } => "StatementTimeout",
+            MuroError::UniqueViolation(_) => "UniqueViolation",
+            MuroError::ConstraintViolation(_) => "ConstraintViolation",
+            MuroError::LimitExceeded { .. } => "LimitExceeded",
+            MuroError::Type(_) => "Type",
+            MuroError::Lock(_) => "Lock",
+            MuroError::LockTimeout { .. } => "LockTimeout",
+            MuroError::Fts(_) => "Fts",
+            MuroError::Kdf(_) => "Kdf",
+            MuroError::Corruption(_) => "Corruption",
+            MuroError::CommitInDoubt(_) => "CommitInDoubt",
+            MuroError::SessionPoisoned(_) => "SessionPoisoned",
+            MuroError::ReadOnly => "ReadOnly",
+            MuroError::MaintenanceInProgress => "MaintenanceInProgress",
+            MuroError::AlreadyOpen(_) => "AlreadyOpen",
+            MuroError::Busy(_) => "Busy",
+            MuroError::Internal(_) => "Internal",
+            MuroError::IncompatibleVersion { .. } => "IncompatibleVersion",
+            #[cfg(feature = "sql")]
+            MuroError::SchemaMismatch(_) => "SchemaMismatch",
+            MuroError::Script { .. } => "Script",
+        }
+    }
+
+    /// The class each variant is documented to have. `Io` depends on the
+    /// error kind (see `test_io_error_kinds`) and `Script` on the wrapped
+    /// error, so their samples below are an uncategorized I/O error and a
+    /// script wrapping `Busy`.
+    const SPEC: &[(&str, ErrorClass)] = &[
+        ("Io", ErrorClass::Transient),
+        ("Encryption", ErrorClass::Internal),
+        ("Decryption", ErrorClass::Corruption),
+        ("PageDecrypt", ErrorClass::Corruption),
+        ("PageOverflow", ErrorClass::UserError),
+        ("PageNotFound", ErrorClass::Corruption),
+        ("InvalidPage", ErrorClass::Corruption),
+        ("Wal", ErrorClass::Corruption),
+        ("WalMismatch", ErrorClass::UserError),
+        ("Transaction", ErrorClass::UserError),
+        ("Schema", ErrorClass::UserError),
+        ("Parse", ErrorClass::UserError),
+        ("Execution", ErrorClass::UserError),
+        ("Cancelled", ErrorClass::UserError),
+        ("StatementTimeout", ErrorClass::ResourceExhausted),
+        ("UniqueViolation", ErrorClass::ConstraintViolation),
+        ("ConstraintViolation", ErrorClass::ConstraintViolation),
+        ("LimitExceeded", ErrorClass::UserError),
+        ("Type", ErrorClass::UserError),
+        ("Lock", ErrorClass::Transient),
+        ("LockTimeout", ErrorClass::Transient),
+        ("Fts", ErrorClass::UserError),
+        ("Kdf", ErrorClass::Internal),
+        ("Corruption", ErrorClass::Corruption),
+        ("CommitInDoubt", ErrorClass::Internal),
+        ("SessionPoisoned", ErrorClass::Internal),
+        ("ReadOnly", ErrorClass::UserError),
+        ("MaintenanceInProgress", ErrorClass::Transient),
+        ("AlreadyOpen", ErrorClass::UserError),
+        ("Busy", ErrorClass::Transient),
+        ("Internal", ErrorClass::Internal),
+        ("IncompatibleVersion", ErrorClass::UserError),
+        #[cfg(feature = "sql")]
+        ("SchemaMismatch", ErrorClass::UserError),
+        ("Script", ErrorClass::Transient),
+    ];
+
+    fn samples() -> Vec<MuroError> {
+        vec![
+            MuroError::Io(std::io::Error::other("x")),
+            MuroError::Encryption("x".into()),
+            MuroError::Decryption,
+            MuroError::PageDecrypt {
+                page_id: 3,
+                page_lsn: None,
+            },
+            MuroError::PageOverflow,
+            MuroError::PageNotFound(7),
+            MuroError::InvalidPage,
+            MuroError::Wal("x".into()),
+            MuroError::WalMismatch {
+                field: "page_size",
+                wal_value: "8192".into(),
+                db_value: "4096".into(),
+            },
+            MuroError::Transaction("x".into()),
+            MuroError::Schema("x".into()),
+            MuroError::Parse("x".into()),
+            MuroError::Execution("x".into()),
+            MuroError::Cancelled,
+            MuroError::StatementTimeout { timeout_ms: 1 },
+            MuroError::UniqueViolation("x".into()),
+            MuroError::ConstraintViolation("x".into()),
+            MuroError::LimitExceeded {
+                limit: crate::limits::Limit::SqlBytes,
+                value: 2,
+                max: 1,
+            },
+            MuroError::Type("x".into()),
+            MuroError::Lock("x".into()),
+            MuroError::LockTimeout {
+                mode: "shared",
+                timeout_ms: 1,
+            },
+            MuroError::Fts("x".into()),
+            MuroError::Kdf("x".into()),
+            MuroError::Corruption("x".into()),
+            MuroError::CommitInDoubt("x".into()),
+            MuroError::SessionPoisoned("x".into()),
+            MuroError::ReadOnly,
+            MuroError::MaintenanceInProgress,
+            MuroError::AlreadyOpen("x".into()),
+            MuroError::Busy("x".into()),
+            MuroError::Internal("x".into()),
+            MuroError::IncompatibleVersion {
+                found: 3,
+                supported: 2,
+            },
+            #[cfg(feature = "sql")]
+            MuroError::SchemaMismatch(Box::default()),
+            MuroError::Script {
+                index: 2,
+                offset: 40,
+                source: Box::new(MuroError::Busy("x".into())),
+            },
+        ]
+    }
+
+    #[test]
+    fn test_every_variant_is_classified() {
+        let samples = samples();
+        for e in &samples {
+            let name = variant_name(e);
+            let expected = SPEC
+                .iter()
+                .find(|(n, _)| *n == name)
+                .unwrap_or_else(|| panic!("{name} has no row in SPEC"))
+                .1;
+            assert_eq!(e.error_class(), expected, "{e:?}");
+        }
+        let mut sampled: Vec<&str> = samples.iter().map(variant_name).collect();
+        let mut specified: Vec<&str> = SPEC.iter().map(|(n, _)| *n).collect();
+        sampled.sort_unstable();
+        specified.sort_unstable();
+        assert_eq!(sampled, specified);
+    }
+
+    #[test]
+    fn test_script_takes_the_class_of_its_source() {
+        let script = |source| MuroError::Script {
+            index: 0,
+            offset: 0,
+            source: Box::new(source),
+        };
+        assert_eq!(
+            script(MuroError::UniqueViolation("x".into())).error_class(),
+            ErrorClass::ConstraintViolation
+        );
+        assert_eq!(
+            script(MuroError::Decryption).error_class(),
+            ErrorClass::Corruption
+        );
+    }
+
+    #[test]
+    fn test_io_error_kinds() {
+        use std::io::{Error, ErrorKind};
+        let class = |kind| MuroError::Io(Error::new(kind, "x")).error_class();
+        assert_eq!(class(ErrorKind::StorageFull), ErrorClass::ResourceExhausted);
+        assert_eq!(class(ErrorKind::UnexpectedEof), ErrorClass::Corruption);
+        assert_eq!(class(ErrorKind::PermissionDenied), ErrorClass::UserError);
+        assert_eq!(class(ErrorKind::Interrupted), ErrorClass::Transient);
+        #[cfg(target_os = "linux")]
+        {
+            // ENOSPC / EIO as the OS reports them.
+            let enospc = MuroError::Io(Error::from_raw_os_error(28));
+            assert_eq!(enospc.error_class(), ErrorClass::ResourceExhausted);
+            let eio = MuroError::Io(Error::from_raw_os_error(5));
+            assert!(eio.is_retryable());
+            assert!(!eio.is_data_corruption());
+        }
+    }
+
+    #[test]
+    fn test_convenience_predicates() {
+        assert!(MuroError::LockTimeout {
+            mode: "exclusive",
+            timeout_ms: 10
+        }
+        .is_retryable());
+        assert!(!MuroError::UniqueViolation("x".into()).is_retryable());
+        assert!(MuroError::Decryption.is_data_corruption());
+        assert!(!MuroError::Parse("x".into()).is_data_corruption());
+    }
+}
@@ -0,0 +1,252 @@
+//! Crash-point sweeps over scripted workloads.
+//!
+//! [`CrashHarness::prepare`] runs a [`CrashWorkload`] once without faults,
+//! recording the database contents after every step and the number of writes
+//! each file received. [`CrashHarness::run`] then replays the workload on a
+//! fresh database that crashes at one [`FaultPoint`], reopens copies of the
+//! files in both [`RecoveryMode`]s, and checks the invariants recovery
+//! promises:
+//!
+//! - every step acknowledged before the crash is present, and the contents
+//!   equal the state after the last acknowledged step or, when the crash hit
+//!   the commit of the next one, after that step; no partial step is visible
+//! - `verify_integrity` reports no errors: indexes match their tables and
+//!   the freelist is sane (leaked pages are only a warning)
+//!
+//! Workloads must be deterministic (no `NOW()`, `UUID_V4()`, ...) so the
+//! crash run makes the same writes as the reference run.
+
+use std::path::{Path, PathBuf};
+
+use super::{FaultCounts, FaultFile, FaultInjector, FaultKind, FaultPoint};
+use crate::crypto::aead::MasterKey;
+use crate::error::{MuroError, Result};
+use crate::types::Value;
+use crate::wal::recovery::RecoveryMode;
+use crate::Database;
+
+const DB_FILE: &str = "crash.db";
+
+/// SQL scripts run one after another. Each step goes through
+/// [`Database::execute_batch`], so it commits as one transaction unless it
+/// has its own `BEGIN`/`COMMIT`.
+#[derive(Debug, Clone, Default)]
+pub struct CrashWorkload {
+    pub steps: Vec<String>,
+}
+
+impl CrashWorkload {
+    pub fn new<I, S>(steps: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        CrashWorkload {
+            steps: steps.into_iter().map(Into::into).collect(),
+        }
+    }
+}
+
+/// What one crash run found. `run` returns an error instead when an
+/// invariant does not hold.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CrashReport {
+    pub point: FaultPoint,
+    /// Whether the workload reached the fault point.
+    pub crashed: bool,
+    /// Steps that returned success before the crash.
+    pub acknowledged_steps: usize,
+    /// Steps whose effects the recovered database holds, in strict and in
+    /// permissive recovery.
+    pub strict_steps: usize,
+    pub permissive_steps: usize,
+}
+
+/// A workload with its reference states, ready to be crashed at any write.
+pub struct CrashHarness {
+    dir: PathBuf,
+    master_key: MasterKey,
+    workload: CrashWorkload,
+    /// Contents after each step; `states[0]` is the empty database.
+    states: Vec<Vec<String>>,
+    counts: FaultCounts,
+}
+
+impl CrashHarness {
+    /// Run `workload` without faults in a subdirectory of `dir`, which also
+    /// holds the files of later crash runs.
+    pub fn prepare(dir: &Path, master_key: &MasterKey, workload: CrashWorkload) -> Result<Self> {
+        let reference = fresh_dir(&dir.join("reference"))?;
+        let path = reference.join(DB_FILE);
+
+        // Count writes in a run of its own: reading the states in between
+        // may write too (refreshes, checkpoints).
+        let mut db = Database::create(&path, master_key)?;
+        let injector = FaultInjector::counting();
+        db.set_fault_injector(Some(injector.clone()));
+        for (i, step) in workload.steps.iter().enumerate() {
+            db.execute_batch(step)
+                .map_err(|e| MuroError::Execution(format!("workload step {} failed: {}", i, e)))?;
+        }
+        drop(db);
+        let counts = injector.counts();
+
+        fresh_dir(&reference)?;
+        let mut db = Database::create(&path, master_key)?;
+        let mut states = vec![dump(&mut db)?];
+        for step in &workload.steps {
+            db.execute_batch(step)?;
+            states.push(dump(&mut db)?);
+        }
+        drop(db);
+        std::fs::remove_dir_all(&reference)?;
+        Ok(CrashHarness {
+            dir: dir.to_path_buf(),
+            master_key: master_key.clone(),
+            workload,
+            states,
+            counts,
+        })
+    }
+
+    /// Writes the workload makes to each file.
+    pub fn counts(&self) -> FaultCounts {
+        self.counts
+    }
+
+    /// Every write of both files, as `kind` faults.
+    pub fn points(&self, kind: FaultKind) -> Vec<FaultPoint> {
+        let wal = (1..=self.counts.wal_writes).map(|write| FaultPoint {
+            file: FaultFile::Wal,
+            write,
+            kind,
+        });
+        let data = (1..=self.counts.data_writes).map(|write| FaultPoint {
+            file: FaultFile::Data,
+            write,
+            kind,
+        });
+        wal.chain(data).collect()
+    }
+
+    /// Replay the workload, crash at `point`, and check both recoveries.
+    pub fn run(&self, point: FaultPoint) -> Result<CrashReport> {
+        let case = fresh_dir(&self.dir.join("crash"))?;
+        let path = case.join(DB_FILE);
+        let mut db = Database::create(&path, &self.master_key)?;
+        let injector = FaultInjector::at(point);
+        db.set_fault_injector(Some(injector.clone()));
+        let mut acknowledged = 0;
+        for (i, step) in self.workload.steps.iter().enumerate() {
+            match db.execute_batch(step) {
+                Ok(_) => acknowledged += 1,
+                Err(_) if injector.has_crashed() => break,
+                Err(e) => {
+                    return Err(MuroError::Execution(format!(
+                        "workload step {} failed before the crash at {:?}: {}",
+                        i, point, e
+                    )))
+                }
+            }
+        }
+        let crashed = injector.has_crashed();
+        drop(db);
+
+        let mut recovered = [0; 2];
+        for (slot, mode) in [RecoveryMode::Strict, RecoveryMode::Permissive]
+            .into_iter()
+            .enumerate()
+        {
+            let copy = copy_files(&case, &case.join(format!("{:?}", mode)))?;
+            recovered[slot] = self.check_recovery(&copy, mode, acknowledged, point)?;
+        }
+        std::fs::remove_dir_all(&case)?;
+        Ok(CrashReport {
+            point,
+            crashed,
+            acknowledged_steps: acknowledged,
+            strict_steps: recovered[0],
+            permissive_steps: recovered[1],
+        })
+    }
+
+    /// Open `path` in `mode` and return how many steps it holds.
+    fn check_recovery(
+        &self,
+        path: &Path,
+        mode: RecoveryMode,
+        acknowledged: usize,
+        point: FaultPoint,
+    ) -> Result<usize> {
+        let violation = |what: String| {
+            MuroError::Corruption(format!(
+                "crash at {:?}, {:?} recovery: {}",
+                point, mode, what
+            ))
+        };
+        let (mut db, _) =
+            Database::open_with_recovery_mode_and_report(path, &self.master_key, mode)
+                .map_err(|e| violation(format!("reopen failed: {}", e)))?;
+        let state = dump(&mut db)?;
+        let last = (acknowledged + 1).min(self.workload.steps.len());
+        let steps = (acknowledged..=last)
+            .find(|&steps| self.states[steps] == state)
+            .ok_or_else(|| {
+                violation(format!(
+                    "contents match neither {} nor {} steps",
+                    acknowledged, last
+                ))
+            })?;
+        for row in db.verify_integrity()? {
+            if matches!(row.get("status"), Some(Value::Varchar(s)) if s == "error") {
+                return Err(violation(format!("integrity error: {:?}", row.values)));
+            }
+        }
+        Ok(steps)
+    }
+}
+
+/// Every table's definition and rows, as sorted text.
+fn dump(db: &mut Database) -> Result<Vec<String>> {
+    let mut out = Vec::new();
+    for table in db.query("SHOW TABLES")? {
+        let Some(Value::Varchar(name)) = table.get_at(0) else {
+            continue;
+        };
+        let name = name.clone();
+        for row in db.query(&format!("SHOW CREATE TABLE {}", name))? {
+            out.push(format!("{:?}", row.values));
+        }
+        let mut rows: Vec<String> = db
+            .query(&format!("SELECT * FROM {}", name))?
+            .iter()
+            .map(|row| format!("{}: {:?}", name, row.values))
+            .collect();
+        rows.sort();
+        out.extend(rows);
+    }
+    Ok(out)
+}
+
+fn fresh_dir(dir: &Path) -> Result<PathBuf> {
+    if dir.exists() {
+        std::fs::remove_dir_all(dir)?;
+    }
+    std::fs::create_dir_all(dir)?;
+    Ok(dir.to_path_buf())
+}
+
+/// Copy the database file and its sidecars from `from` into a new `to`, as
+/// the files a restarted process would find. Returns the database path.
+fn copy_files(from: &Path, to: &Path) -> Result<PathBuf> {
+    fresh_dir(to)?;
+    for entry in std::fs::read_dir(from)? {
+        let entry = entry?;
+        let name = entry.file_name();
+        let is_db_file = name.to_str().is_some_and(|n| n.starts_with(DB_FILE));
+        if entry.file_type()?.is_file() && is_db_file {
+            std::fs::copy(entry.path(), to.join(&name))?;
+        }
+    }
+    Ok(to.join(DB_FILE))
+}
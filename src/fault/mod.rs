@@ -0,0 +1,321 @@
+//! Fault injection for crash-consistency tests (`test-utils` feature).
+//!
+//! A [`FaultInjector`] counts the writes a [`Pager`] and a [`WalWriter`] make
+//! to their files, and can be programmed to fail or tear the Nth write of
+//! either file. From that write on, every write, truncation and fsync of both
+//! files fails, as if the process had been killed there: what reached the
+//! files stays, nothing more is added. Reopening the files then runs recovery
+//! exactly as after a crash.
+//!
+//! [`FaultyPager`] and [`FaultyWalWriter`] wrap a pager and a WAL writer for
+//! tests that drive transactions directly; `Database::set_fault_injector`
+//! installs an injector into a whole database, and [`harness`] runs scripted
+//! workloads against it and checks what recovery makes of every crash point.
+
+#[cfg(feature = "sql")]
+pub mod harness;
+
+use std::io::Write;
+use std::ops::{Deref, DerefMut};
+use std::sync::Arc;
+
+use parking_lot::Mutex;
+
+use crate::crypto::hmac_util::TermKey;
+use crate::error::{MuroError, Result};
+use crate::storage::page::{Page, PageId};
+use crate::storage::page_store::PageStore;
+use crate::storage::pager::Pager;
+use crate::wal::writer::WalWriter;
+
+/// The file a write goes to.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum FaultFile {
+    /// The `.wal` file: frame writes and checkpoint truncations.
+    Wal,
+    /// The database file: page flushes (one per batch of pages, whatever
+    /// runs it is split into) and header updates.
+    Data,
+}
+
+/// What happens to the write a fault hits.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum FaultKind {
+    /// Nothing of the write reaches the file.
+    Fail,
+    /// The first half of the write reaches the file. Header updates and
+    /// truncations are treated as atomic, so a torn one fails instead.
+    TornWrite,
+}
+
+/// Where an injector crashes.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct FaultPoint {
+    pub file: FaultFile,
+    /// 1-based position of the write among the writes to `file`.
+    pub write: u64,
+    pub kind: FaultKind,
+}
+
+/// Writes seen by an injector, per file.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct FaultCounts {
+    pub wal_writes: u64,
+    pub data_writes: u64,
+}
+
+#[derive(Debug, Default)]
+struct FaultState {
+    point: Option<FaultPoint>,
+    counts: FaultCounts,
+    crashed: bool,
+}
+
+/// Shared write counter and crash switch. Clones share one state, so the
+/// pager and the WAL writer of a database count against the same point.
+#[derive(Debug, Clone, Default)]
+pub struct FaultInjector {
+    state: Arc<Mutex<FaultState>>,
+}
+
+impl FaultInjector {
+    /// An injector that only counts writes.
+    pub fn counting() -> Self {
+        Self::default()
+    }
+
+    /// An injector that crashes at `point`.
+    pub fn at(point: FaultPoint) -> Self {
+        let injector = Self::default();
+        injector.state.lock().point = Some(point);
+        injector
+    }
+
+    /// Writes counted so far, including the one that crashed.
+    pub fn counts(&self) -> FaultCounts {
+        self.state.lock().counts
+    }
+
+    /// Whether the fault point has been reached.
+    pub fn has_crashed(&self) -> bool {
+        self.state.lock().crashed
+    }
+
+    /// Count a write of `bytes` to `file` and perform it on `out`, or the
+    /// part of it the fault lets through.
+    pub(crate) fn write_all(
+        &self,
+        file: FaultFile,
+        out: &mut impl Write,
+        bytes: &[u8],
+        atomic: bool,
+    ) -> Result<()> {
+        let keep = self.admit(file, bytes.len(), atomic)?;
+        if keep < bytes.len() {
+            out.write_all(&bytes[..keep])?;
+            return Err(crash_error());
+        }
+        out.write_all(bytes)?;
+        Ok(())
+    }
+
+    /// Count an operation that either happens whole or not at all.
+    pub(crate) fn admit_atomic(&self, file: FaultFile) -> Result<()> {
+        self.admit(file, 0, true).map(|_| ())
+    }
+
+    /// Fail once crashed; used before fsyncs, which are not counted.
+    pub(crate) fn check(&self) -> Result<()> {
+        if self.state.lock().crashed {
+            return Err(crash_error());
+        }
+        Ok(())
+    }
+
+    /// Count a write of `len` bytes to `file` and return how many of them
+    /// reach it: all, or the prefix of a torn write, after which the caller
+    /// reports the crash.
+    pub(crate) fn admit(&self, file: FaultFile, len: usize, atomic: bool) -> Result<usize> {
+        let mut state = self.state.lock();
+        if state.crashed {
+            return Err(crash_error());
+        }
+        let count = match file {
+            FaultFile::Wal => &mut state.counts.wal_writes,
+            FaultFile::Data => &mut state.counts.data_writes,
+        };
+        *count += 1;
+        let write = *count;
+        match state.point {
+            Some(point) if point.file == file && point.write == write => {
+                state.crashed = true;
+                match point.kind {
+                    FaultKind::TornWrite if !atomic && len > 1 => Ok(len / 2),
+                    _ => Err(crash_error()),
+                }
+            }
+            _ => Ok(len),
+        }
+    }
+}
+
+fn crash_error() -> MuroError {
+    MuroError::Io(std::io::Error::other("injected crash"))
+}
+
+/// A pager whose file writes go through a [`FaultInjector`].
+///
+/// Page I/O through [`PageStore`] and writes the pager makes on its own
+/// (commit flushes, checkpoints, header updates) are all counted.
+pub struct FaultyPager {
+    pager: Pager,
+}
+
+impl FaultyPager {
+    pub fn new(mut pager: Pager, injector: &FaultInjector) -> Self {
+        pager.set_fault_injector(Some(injector.clone()));
+        FaultyPager { pager }
+    }
+
+    /// The pager, with the injector removed.
+    pub fn into_inner(mut self) -> Pager {
+        self.pager.set_fault_injector(None);
+        self.pager
+    }
+}
+
+impl Deref for FaultyPager {
+    type Target = Pager;
+
+    fn deref(&self) -> &Pager {
+        &self.pager
+    }
+}
+
+impl DerefMut for FaultyPager {
+    fn deref_mut(&mut self) -> &mut Pager {
+        &mut self.pager
+    }
+}
+
+impl PageStore for FaultyPager {
+    fn read_page(&mut self, page_id: PageId) -> Result<Page> {
+        self.pager.read_page(page_id)
+    }
+
+    fn write_page(&mut self, page: &Page) -> Result<()> {
+        self.pager.write_page(page)
+    }
+
+    fn allocate_page(&mut self) -> Result<Page> {
+        self.pager.allocate_page()
+    }
+
+    fn allocate_page_near(&mut self, hint: PageId) -> Result<Page> {
+        self.pager.allocate_page_near(hint)
+    }
+
+    fn allocate_pages_near(&mut self, count: usize, hint: PageId) -> Result<Vec<Page>> {
+        self.pager.allocate_pages_near(count, hint)
+    }
+
+    fn free_page(&mut self, page_id: PageId) {
+        self.pager.free_page(page_id)
+    }
+
+    fn fts_term_key(&self) -> Result<TermKey> {
+        self.pager.fts_term_key()
+    }
+
+    fn cache_counters(&self) -> (u64, u64) {
+        PageStore::cache_counters(&self.pager)
+    }
+}
+
+/// A WAL writer whose frame writes, truncations and fsyncs go through a
+/// [`FaultInjector`].
+pub struct FaultyWalWriter {
+    wal: WalWriter,
+}
+
+impl FaultyWalWriter {
+    pub fn new(mut wal: WalWriter, injector: &FaultInjector) -> Self {
+        wal.set_fault_injector(Some(injector.clone()));
+        FaultyWalWriter { wal }
+    }
+
+    /// The writer, with the injector removed.
+    pub fn into_inner(mut self) -> WalWriter {
+        self.wal.set_fault_injector(None);
+        self.wal
+    }
+}
+
+impl Deref for FaultyWalWriter {
+    type Target = WalWriter;
+
+    fn deref(&self) -> &WalWriter {
+        &self.wal
+    }
+}
+
+impl DerefMut for FaultyWalWriter {
+    fn deref_mut(&mut self) -> &mut WalWriter {
+        &mut self.wal
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn point(file: FaultFile, write: u64, kind: FaultKind) -> FaultPoint {
+        FaultPoint { file, write, kind }
+    }
+
+    #[test]
+    fn test_injector_counts_until_its_point_then_fails_everything() {
+        let injector = FaultInjector::at(point(FaultFile::Wal, 2, FaultKind::TornWrite));
+        let mut out = Vec::new();
+        injector
+            .write_all(FaultFile::Data, &mut out, b"data", false)
+            .unwrap();
+        injector
+            .write_all(FaultFile::Wal, &mut out, b"wal1", false)
+            .unwrap();
+        assert!(!injector.has_crashed());
+
+        assert!(injector
+            .write_all(FaultFile::Wal, &mut out, b"wal2", false)
+            .is_err());
+        assert_eq!(out, b"datawal1wa");
+        assert!(injector.has_crashed());
+        assert!(injector.check().is_err());
+        assert!(injector.admit_atomic(FaultFile::Data).is_err());
+        assert!(injector
+            .write_all(FaultFile::Data, &mut out, b"more", false)
+            .is_err());
+        assert_eq!(
+            injector.counts(),
+            FaultCounts {
+                wal_writes: 2,
+                data_writes: 1
+            }
+        );
+    }
+
+    #[test]
+    fn test_atomic_writes_fail_instead_of_tearing() {
+        let injector = FaultInjector::at(point(FaultFile::Data, 1, FaultKind::TornWrite));
+        let mut out = Vec::new();
+        assert!(injector
+            .write_all(FaultFile::Data, &mut out, b"header", true)
+            .is_err());
+        assert!(out.is_empty());
+
+        let counting = FaultInjector::counting();
+        counting.admit_atomic(FaultFile::Wal).unwrap();
+        counting.check().unwrap();
+        assert_eq!(counting.counts().wal_writes, 1);
+    }
+}
@@ -9,7 +9,8 @@
 ///   key = b"__stats__"
 ///   value = FtsStats serialized
 use crate::btree::ops::BTree;
-use crate::crypto::hmac_util::hmac_term_id;
+use crate::btree::verify::BTreeCheck;
+use crate::crypto::hmac_util::{hmac_term_id, TermKey};
 use crate::error::Result;
 use crate::fts::postings::{Posting, PostingList};
 use crate::fts::tokenizer::tokenize_bigram;
@@ -19,7 +20,7 @@ use crate::storage::page_store::PageStore;
 /// FTS index handle.
 pub struct FtsIndex {
     btree: BTree,
-    term_key: [u8; 32],
+    term_key: TermKey,
 }
 
 /// FTS statistics for BM25 scoring.
@@ -66,6 +67,7 @@ const SEG_META_V2_VERSION: u8 = 2;
 const SEG_GC_HEAD_KEY: &[u8] = b"__seggc_head__";
 const SEG_GC_TAIL_KEY: &[u8] = b"__seggc_tail__";
 const SEG_GC_TASK_PREFIX: &[u8] = b"__seggc__";
+const SEG_GC_ORPHANS_KEY: &[u8] = b"__seggc_orphans__";
 // Keep inline payloads comfortably below page-cell limits even with key/cell overhead.
 const MAX_SEGMENT_INLINE_BYTES: usize = 3000;
 // Logical segment size target before falling back to overflow pages.
@@ -106,7 +108,12 @@ struct SegmentGcTask {
     delete_legacy_single: bool,
 }
 
-/// Pending FTS operation (accumulated during a transaction).
+/// Pending FTS operation.
+///
+/// The executor applies these to the transaction's `TxPageStore` as each row
+/// changes, so the resulting posting-page writes live in the transaction's
+/// dirty pages: ROLLBACK (and ROLLBACK TO SAVEPOINT) discard them, and COMMIT
+/// logs them in the same WAL frames as the row writes.
 #[derive(Debug, Clone)]
 pub enum FtsPendingOp {
     Add { doc_id: u64, text: String },
@@ -115,7 +122,7 @@ pub enum FtsPendingOp {
 
 impl FtsIndex {
     /// Create a new FTS index.
-    pub fn create(pager: &mut impl PageStore, term_key: [u8; 32]) -> Result<Self> {
+    pub fn create(pager: &mut impl PageStore, term_key: TermKey) -> Result<Self> {
         let btree = BTree::create(pager)?;
         let mut index = FtsIndex { btree, term_key };
 
@@ -127,7 +134,7 @@ impl FtsIndex {
     }
 
     /// Open an existing FTS index.
-    pub fn open(root_page_id: PageId, term_key: [u8; 32]) -> Self {
+    pub fn open(root_page_id: PageId, term_key: TermKey) -> Self {
         FtsIndex {
             btree: BTree::open(root_page_id),
             term_key,
@@ -157,7 +164,7 @@ impl FtsIndex {
         }
     }
 
-    /// Apply pending operations at commit time.
+    /// Apply pending operations through `pager`.
     pub fn apply_pending(
         &mut self,
         pager: &mut impl PageStore,
@@ -242,6 +249,10 @@ impl FtsIndex {
 
     /// Vacuum stale segmented payloads left behind by generation switching.
     /// Returns the number of GC tasks processed.
+    ///
+    /// Once the `[head, tail)` window is drained, task records left outside
+    /// it (by counters that lost track of them) are processed too, within
+    /// the same `max_tasks` budget, and added to [`Self::gc_orphans_recovered`].
     pub fn vacuum_stale_segments(
         &mut self,
         pager: &mut impl PageStore,
@@ -257,18 +268,26 @@ impl FtsIndex {
         while head < tail && processed < max_tasks {
             let task_key = seg_gc_task_key(head);
             if let Some(raw) = self.btree.search(pager, &task_key)? {
-                let task = decode_segment_gc_task(&raw)?;
-                self.delete_postings_from_meta(pager, &task.tid, task.old_meta)?;
-                if task.delete_legacy_single && self.btree.search(pager, &task.tid)?.is_some() {
-                    self.btree.delete(pager, &task.tid)?;
-                }
-                self.btree.delete(pager, &task_key)?;
+                self.run_segment_gc_task(pager, &task_key, &raw)?;
             }
             head = head.saturating_add(1);
             processed = processed.saturating_add(1);
         }
         self.store_gc_counter(pager, SEG_GC_HEAD_KEY, head)?;
         if head >= tail {
+            let orphans = self.orphan_gc_tasks(pager, max_tasks - processed)?;
+            for (task_key, raw) in &orphans {
+                self.run_segment_gc_task(pager, task_key, raw)?;
+            }
+            if !orphans.is_empty() {
+                let recovered = self.load_gc_counter(pager, SEG_GC_ORPHANS_KEY)?;
+                self.store_gc_counter(
+                    pager,
+                    SEG_GC_ORPHANS_KEY,
+                    recovered.saturating_add(orphans.len() as u64),
+                )?;
+                processed = processed.saturating_add(orphans.len());
+            }
             // Keep counters bounded once queue is drained.
             self.store_gc_counter(pager, SEG_GC_HEAD_KEY, 0)?;
             self.store_gc_counter(pager, SEG_GC_TAIL_KEY, 0)?;
@@ -276,6 +295,107 @@ impl FtsIndex {
         Ok(processed)
     }
 
+    /// Free every page the index owns: each segment overflow chain
+    /// (including ones only referenced by queued GC tasks, whose stale
+    /// segments keep their overflow records until vacuumed) and then the
+    /// B-tree itself. Pending GC tasks are discarded with the tree.
+    pub fn destroy(self, pager: &mut impl PageStore) -> Result<()> {
+        let mut overflow_refs = Vec::new();
+        self.btree
+            .scan_from(pager, SEG_OVERFLOW_V2_PREFIX, |k, v| {
+                if !k.starts_with(SEG_OVERFLOW_V2_PREFIX) {
+                    return Ok(false);
+                }
+                overflow_refs.push(decode_overflow_ref(v)?);
+                Ok(true)
+            })?;
+        for overflow_ref in overflow_refs {
+            free_overflow_chain(pager, overflow_ref)?;
+        }
+        for page_id in self.btree.collect_all_pages(pager)? {
+            pager.free_page(page_id);
+        }
+        Ok(())
+    }
+
+    /// Number of GC task records found outside the queue window and
+    /// processed by [`Self::vacuum_stale_segments`] over the index's life.
+    pub fn gc_orphans_recovered(&self, pager: &mut impl PageStore) -> Result<u64> {
+        self.load_gc_counter(pager, SEG_GC_ORPHANS_KEY)
+    }
+
+    /// Number of GC tasks queued for [`Self::vacuum_stale_segments`], not
+    /// counting orphaned task records.
+    pub fn pending_gc_tasks(&self, pager: &mut impl PageStore) -> Result<u64> {
+        let head = self.load_gc_counter(pager, SEG_GC_HEAD_KEY)?;
+        let tail = self.load_gc_counter(pager, SEG_GC_TAIL_KEY)?;
+        Ok(tail.saturating_sub(head))
+    }
+
+    /// Verify the index B-tree and its segmented postings.
+    ///
+    /// In addition to the structural B-tree checks, every segment referenced
+    /// by a `__segmeta__` record must exist and decode, and every segment
+    /// overflow chain (including ones still queued for GC) must terminate with
+    /// its recorded page count. The returned page list includes those
+    /// overflow pages. `visit` sees every entry of the underlying B-tree.
+    pub fn verify<F>(&self, pager: &mut impl PageStore, mut visit: F) -> BTreeCheck
+    where
+        F: FnMut(&[u8], &[u8]),
+    {
+        let mut metas: Vec<([u8; 32], Vec<u8>)> = Vec::new();
+        let mut overflow_refs: Vec<Vec<u8>> = Vec::new();
+        let mut check = self.btree.verify(pager, |k, v| {
+            if let Some(tid) = k.strip_prefix(SEG_META_PREFIX) {
+                if let Ok(tid) = <[u8; 32]>::try_from(tid) {
+                    metas.push((tid, v.to_vec()));
+                }
+            } else if k.starts_with(SEG_OVERFLOW_V2_PREFIX) {
+                overflow_refs.push(v.to_vec());
+            }
+            visit(k, v);
+        });
+
+        for (tid, raw_meta) in metas {
+            let term = hex_prefix(&tid);
+            let meta = match decode_segment_meta(&raw_meta) {
+                Ok(meta) => meta,
+                Err(e) => {
+                    check.problems.push(format!("term {}: {}", term, e));
+                    continue;
+                }
+            };
+            let seg_count = match meta {
+                SegmentMeta::V1 { seg_count, .. } => seg_count,
+                SegmentMeta::V2 { seg_count, .. } => seg_count,
+            };
+            for seg_idx in 0..seg_count {
+                match self.load_segment_payload(pager, &tid, meta, seg_idx) {
+                    Ok(data) if PostingList::deserialize(&data).is_some() => {}
+                    Ok(_) => check.problems.push(format!(
+                        "term {}: segment {} does not decode as a posting list",
+                        term, seg_idx
+                    )),
+                    Err(e) => check
+                        .problems
+                        .push(format!("term {}: segment {}: {}", term, seg_idx, e)),
+                }
+            }
+        }
+
+        for raw_ref in overflow_refs {
+            let chain = decode_overflow_ref(&raw_ref)
+                .and_then(|overflow_ref| collect_overflow_chain_pages(pager, overflow_ref));
+            match chain {
+                Ok(pages) => check.pages.extend(pages),
+                Err(e) => check
+                    .problems
+                    .push(format!("segment overflow chain: {}", e)),
+            }
+        }
+        check
+    }
+
     fn load_postings_by_tid(
         &self,
         pager: &mut impl PageStore,
@@ -437,7 +557,7 @@ impl FtsIndex {
             return Ok(());
         }
 
-        let overflow_ref = write_overflow_chain(pager, payload)?;
+        let overflow_ref = write_overflow_chain(pager, payload, self.btree.root_page_id())?;
         self.btree
             .insert(pager, &overflow_key, &encode_overflow_ref(overflow_ref))?;
         Ok(())
@@ -466,18 +586,64 @@ impl FtsIndex {
         Ok(())
     }
 
+    /// Queue `task` at the tail. The record and the advanced tail counter are
+    /// written through the same `pager`, so they commit or roll back
+    /// together; a slot still holding an orphaned record is skipped rather
+    /// than overwritten.
     fn enqueue_segment_gc_task(
         &mut self,
         pager: &mut impl PageStore,
         task: SegmentGcTask,
     ) -> Result<()> {
-        let tail = self.load_gc_counter(pager, SEG_GC_TAIL_KEY)?;
+        let mut tail = self.load_gc_counter(pager, SEG_GC_TAIL_KEY)?;
+        while self.btree.search(pager, &seg_gc_task_key(tail))?.is_some() {
+            tail = tail.saturating_add(1);
+        }
         self.btree
             .insert(pager, &seg_gc_task_key(tail), &encode_segment_gc_task(task))?;
         self.store_gc_counter(pager, SEG_GC_TAIL_KEY, tail.saturating_add(1))?;
         Ok(())
     }
 
+    /// Free what the GC task stored under `task_key` refers to, then delete it.
+    fn run_segment_gc_task(
+        &mut self,
+        pager: &mut impl PageStore,
+        task_key: &[u8],
+        raw: &[u8],
+    ) -> Result<()> {
+        let task = decode_segment_gc_task(raw)?;
+        self.delete_postings_from_meta(pager, &task.tid, task.old_meta)?;
+        if task.delete_legacy_single && self.btree.search(pager, &task.tid)?.is_some() {
+            self.btree.delete(pager, &task.tid)?;
+        }
+        self.btree.delete(pager, task_key)?;
+        Ok(())
+    }
+
+    /// Up to `limit` GC task records still in the B-tree, in key order. Called
+    /// once the queue window is empty, so every one found is an orphan.
+    fn orphan_gc_tasks(
+        &self,
+        pager: &mut impl PageStore,
+        limit: usize,
+    ) -> Result<Vec<(Vec<u8>, Vec<u8>)>> {
+        let mut tasks = Vec::new();
+        if limit == 0 {
+            return Ok(tasks);
+        }
+        self.btree.scan_from(pager, SEG_GC_TASK_PREFIX, |k, v| {
+            if !k.starts_with(SEG_GC_TASK_PREFIX) {
+                return Ok(false);
+            }
+            if k.len() == SEG_GC_TASK_PREFIX.len() + 8 {
+                tasks.push((k.to_vec(), v.to_vec()));
+            }
+            Ok(tasks.len() < limit)
+        })?;
+        Ok(tasks)
+    }
+
     fn load_gc_counter(&self, pager: &mut impl PageStore, key: &[u8]) -> Result<u64> {
         match self.btree.search(pager, key)? {
             Some(raw) if raw.len() == 8 => Ok(u64::from_le_bytes([
@@ -742,7 +908,11 @@ fn decode_overflow_ref(raw: &[u8]) -> Result<SegmentOverflowRef> {
     })
 }
 
-fn write_overflow_chain(pager: &mut impl PageStore, payload: &[u8]) -> Result<SegmentOverflowRef> {
+fn write_overflow_chain(
+    pager: &mut impl PageStore,
+    payload: &[u8],
+    hint: PageId,
+) -> Result<SegmentOverflowRef> {
     if payload.is_empty() {
         return Err(crate::error::MuroError::Execution(
             "overflow payload cannot be empty".into(),
@@ -754,11 +924,11 @@ fn write_overflow_chain(pager: &mut impl PageStore, payload: &[u8]) -> Result<Se
     let total_len = u32::try_from(payload.len())
         .map_err(|_| crate::error::MuroError::Execution("overflow payload too large".into()))?;
 
-    let mut page_ids = Vec::with_capacity(page_count_usize);
-    for _ in 0..page_count_usize {
-        let page = pager.allocate_page()?;
-        page_ids.push(page.page_id());
-    }
+    let page_ids: Vec<PageId> = pager
+        .allocate_pages_near(page_count_usize, hint)?
+        .iter()
+        .map(Page::page_id)
+        .collect();
 
     for (i, &page_id) in page_ids.iter().enumerate() {
         let next_page_id = page_ids.get(i + 1).copied().unwrap_or(0);
@@ -835,6 +1005,41 @@ fn read_overflow_chain(
     Ok(out)
 }
 
+fn collect_overflow_chain_pages(
+    pager: &mut impl PageStore,
+    overflow_ref: SegmentOverflowRef,
+) -> Result<Vec<PageId>> {
+    let mut pages = Vec::new();
+    let mut current = overflow_ref.first_page_id;
+    while current != 0 {
+        if pages.contains(&current) || pages.len() >= overflow_ref.page_count as usize {
+            return Err(crate::error::MuroError::Corruption(
+                "overflow chain does not terminate at its recorded page_count".into(),
+            ));
+        }
+        let page = pager.read_page(current)?;
+        let base = PAGE_HEADER_SIZE;
+        if &page.data[base..base + 4] != OVERFLOW_PAGE_MAGIC {
+            return Err(crate::error::MuroError::Corruption(format!(
+                "invalid overflow page magic on page {}",
+                current
+            )));
+        }
+        pages.push(current);
+        current = u64::from_le_bytes(page.data[base + 4..base + 12].try_into().unwrap());
+    }
+    if pages.len() != overflow_ref.page_count as usize {
+        return Err(crate::error::MuroError::Corruption(
+            "overflow chain length mismatch".into(),
+        ));
+    }
+    Ok(pages)
+}
+
+fn hex_prefix(tid: &[u8; 32]) -> String {
+    tid[..8].iter().map(|b| format!("{:02x}", b)).collect()
+}
+
 fn free_overflow_chain(pager: &mut impl PageStore, overflow_ref: SegmentOverflowRef) -> Result<()> {
     let mut visited = std::collections::HashSet::new();
     let mut current = overflow_ref.first_page_id;
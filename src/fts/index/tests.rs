@@ -1,5 +1,6 @@
 use super::*;
 use crate::crypto::aead::MasterKey;
+use crate::crypto::hmac_util::TermKey;
 use crate::storage::pager::Pager;
 use tempfile::TempDir;
 
@@ -7,8 +8,8 @@ fn test_key() -> MasterKey {
     MasterKey::new([0x42u8; 32])
 }
 
-fn term_key() -> [u8; 32] {
-    [0x55u8; 32]
+fn term_key() -> TermKey {
+    TermKey::new([0x55u8; 32])
 }
 
 fn segment_payload_exists(
@@ -377,3 +378,159 @@ fn test_generation_does_not_reuse_after_delete_then_readd() {
     assert_eq!(loaded.df(), 1);
     assert_eq!(loaded.get(3).unwrap().positions, vec![2]);
 }
+
+/// Sequence numbers of every GC task record in the index.
+fn gc_task_seqs(idx: &FtsIndex, pager: &mut impl PageStore) -> Vec<u64> {
+    let mut seqs = Vec::new();
+    idx.btree
+        .scan_from(pager, SEG_GC_TASK_PREFIX, |k, _| {
+            let Some(seq) = k.strip_prefix(SEG_GC_TASK_PREFIX) else {
+                return Ok(false);
+            };
+            if let Ok(seq) = <[u8; 8]>::try_from(seq) {
+                seqs.push(u64::from_be_bytes(seq));
+            }
+            Ok(true)
+        })
+        .unwrap();
+    seqs
+}
+
+/// Give each of `terms` a superseded generation, which queues one GC task per
+/// term, and return their term ids.
+fn queue_stale_generations(
+    idx: &mut FtsIndex,
+    pager: &mut impl PageStore,
+    terms: &[&str],
+) -> Vec<[u8; 32]> {
+    let mut tids = Vec::new();
+    for (i, term) in terms.iter().enumerate() {
+        let tid = idx.term_id(term);
+        for doc_id in [1, 2] {
+            let mut pl = PostingList::new();
+            pl.add(doc_id + i as u64 * 10, vec![0]);
+            idx.store_postings_by_tid(pager, &tid, &pl).unwrap();
+        }
+        tids.push(tid);
+    }
+    tids
+}
+
+#[test]
+fn test_vacuum_recovers_gc_tasks_outside_queue_window() {
+    let dir = TempDir::new().unwrap();
+    let mut pager = Pager::create(&dir.path().join("test.db"), &test_key()).unwrap();
+    let mut idx = FtsIndex::create(&mut pager, term_key()).unwrap();
+    let tids = queue_stale_generations(&mut idx, &mut pager, &["東京", "大阪", "京都"]);
+    assert_eq!(gc_task_seqs(&idx, &mut pager), [0, 1, 2]);
+
+    // Counters that lost track of the queue: the window is empty, and one
+    // record sits far past the tail.
+    let raw = idx
+        .btree
+        .search(&mut pager, &seg_gc_task_key(2))
+        .unwrap()
+        .unwrap();
+    idx.btree.delete(&mut pager, &seg_gc_task_key(2)).unwrap();
+    idx.btree
+        .insert(&mut pager, &seg_gc_task_key(900), &raw)
+        .unwrap();
+    idx.store_gc_counter(&mut pager, SEG_GC_HEAD_KEY, 1)
+        .unwrap();
+    idx.store_gc_counter(&mut pager, SEG_GC_TAIL_KEY, 1)
+        .unwrap();
+
+    // Orphans share the per-call budget.
+    assert_eq!(idx.vacuum_stale_segments(&mut pager, 2).unwrap(), 2);
+    assert_eq!(gc_task_seqs(&idx, &mut pager), [900]);
+    assert_eq!(idx.vacuum_stale_segments(&mut pager, 2).unwrap(), 1);
+    assert!(gc_task_seqs(&idx, &mut pager).is_empty());
+    assert_eq!(idx.gc_orphans_recovered(&mut pager).unwrap(), 3);
+
+    for (i, tid) in tids.iter().enumerate() {
+        assert!(!segment_payload_exists(&mut idx, &mut pager, tid, 1, 0));
+        assert!(segment_payload_exists(&mut idx, &mut pager, tid, 2, 0));
+        let loaded = idx.load_postings_by_tid(&mut pager, tid).unwrap();
+        assert!(loaded.get(2 + i as u64 * 10).is_some());
+    }
+    assert_eq!(idx.vacuum_stale_segments(&mut pager, 2).unwrap(), 0);
+    assert_eq!(idx.gc_orphans_recovered(&mut pager).unwrap(), 3);
+}
+
+#[test]
+fn test_enqueue_does_not_overwrite_orphaned_task() {
+    let dir = TempDir::new().unwrap();
+    let mut pager = Pager::create(&dir.path().join("test.db"), &test_key()).unwrap();
+    let mut idx = FtsIndex::create(&mut pager, term_key()).unwrap();
+    let first = queue_stale_generations(&mut idx, &mut pager, &["東京"]);
+    // A reset tail would hand out slot 0 again.
+    idx.store_gc_counter(&mut pager, SEG_GC_TAIL_KEY, 0)
+        .unwrap();
+
+    let second = queue_stale_generations(&mut idx, &mut pager, &["大阪"]);
+    assert_eq!(gc_task_seqs(&idx, &mut pager), [0, 1]);
+    assert_eq!(idx.load_gc_counter(&mut pager, SEG_GC_TAIL_KEY).unwrap(), 2);
+
+    assert_eq!(idx.vacuum_stale_segments(&mut pager, 16).unwrap(), 2);
+    assert_eq!(idx.gc_orphans_recovered(&mut pager).unwrap(), 0);
+    for tid in first.iter().chain(&second) {
+        assert!(!segment_payload_exists(&mut idx, &mut pager, tid, 1, 0));
+    }
+}
+
+#[test]
+fn test_crash_around_enqueue_keeps_queue_in_sync() {
+    use crate::tx::page_store::TxPageStore;
+    use crate::tx::transaction::Transaction;
+    use crate::wal::recovery::recover;
+    use crate::wal::writer::WalWriter;
+
+    let dir = TempDir::new().unwrap();
+    let db_path = dir.path().join("test.db");
+    let wal_path = dir.path().join("test.db.wal");
+    let mut pager = Pager::create(&db_path, &test_key()).unwrap();
+    let mut wal = WalWriter::create(&wal_path, &test_key()).unwrap();
+
+    let mut tx = Transaction::begin(1, wal.current_lsn());
+    let root = {
+        let mut store = TxPageStore::new(tx, &mut pager);
+        let mut idx = FtsIndex::create(&mut store, term_key()).unwrap();
+        queue_stale_generations(&mut idx, &mut store, &["東京"]);
+        tx = store.into_tx();
+        idx.root_page_id()
+    };
+    tx.commit(&mut pager, &mut wal, 0).unwrap();
+
+    // Enqueued, then the data-file write fails after the WAL is durable.
+    let mut tx = Transaction::begin(2, wal.current_lsn());
+    {
+        let mut store = TxPageStore::new(tx, &mut pager);
+        let mut idx = FtsIndex::open(root, term_key());
+        queue_stale_generations(&mut idx, &mut store, &["大阪", "京都"]);
+        tx = store.into_tx();
+    }
+    pager.set_inject_write_page_failure(Some(std::io::ErrorKind::Other));
+    assert!(tx.commit(&mut pager, &mut wal, 0).is_err());
+    pager.set_inject_write_page_failure(None);
+
+    // Enqueued, then the process dies before committing.
+    let tx = Transaction::begin(3, wal.current_lsn());
+    {
+        let mut store = TxPageStore::new(tx, &mut pager);
+        let mut idx = FtsIndex::open(root, term_key());
+        queue_stale_generations(&mut idx, &mut store, &["名古屋"]);
+    }
+    drop(pager);
+    drop(wal);
+
+    recover(&db_path, &wal_path, &test_key()).unwrap();
+    let mut pager = Pager::open(&db_path, &test_key()).unwrap();
+    let mut idx = FtsIndex::open(root, term_key());
+    let head = idx.load_gc_counter(&mut pager, SEG_GC_HEAD_KEY).unwrap();
+    let tail = idx.load_gc_counter(&mut pager, SEG_GC_TAIL_KEY).unwrap();
+    assert_eq!((head, tail), (0, 3));
+    assert_eq!(gc_task_seqs(&idx, &mut pager), [0, 1, 2]);
+    assert_eq!(idx.vacuum_stale_segments(&mut pager, 16).unwrap(), 3);
+    assert_eq!(idx.gc_orphans_recovered(&mut pager).unwrap(), 0);
+    assert!(gc_task_seqs(&idx, &mut pager).is_empty());
+}
@@ -1,10 +1,11 @@
 /// FTS query evaluation: NATURAL and BOOLEAN mode.
 ///
-/// NATURAL: bigram tokenize query → look up postings → BM25 score
+/// NATURAL: bigram tokenize query → look up postings → BM25 score;
+///          a "quoted phrase" must occur with its bigrams adjacent
 /// BOOLEAN: parse +term, -term, "phrase" → evaluate constraints
-use std::collections::HashSet;
+use std::collections::{HashMap, HashSet};
 
-use crate::error::Result;
+use crate::error::{MuroError, Result};
 use crate::fts::index::FtsIndex;
 use crate::fts::postings::PostingList;
 use crate::fts::scoring::bm25_score;
@@ -18,10 +19,56 @@ pub struct FtsResult {
     pub score: f64,
 }
 
+/// Default cap on documents scored by a stop-filter fallback rescan.
+pub const DEFAULT_STOP_FALLBACK_MAX_DOCS: u32 = 10_000;
+
+/// What a NATURAL query does when the stop filter removes every query ngram.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum FtsStopFallback {
+    /// Score again with the filter off, bounded by `stop_fallback_max_docs`.
+    #[default]
+    Rescan,
+    /// Return no documents and report it through `SHOW WARNINGS`.
+    EmptyWithWarning,
+}
+
+impl FtsStopFallback {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            FtsStopFallback::Rescan => "rescan",
+            FtsStopFallback::EmptyWithWarning => "empty_with_warning",
+        }
+    }
+
+    pub fn parse(s: &str) -> Option<Self> {
+        match s.to_ascii_lowercase().as_str() {
+            "rescan" => Some(FtsStopFallback::Rescan),
+            "empty_with_warning" => Some(FtsStopFallback::EmptyWithWarning),
+            _ => None,
+        }
+    }
+}
+
 #[derive(Debug, Clone, Copy, Default)]
 pub struct FtsQueryConfig {
     pub stop_filter: bool,
     pub stop_df_ratio_ppm: u32,
+    pub stop_fallback: FtsStopFallback,
+    /// Candidate-document cap for the fallback rescan (0 = unlimited).
+    pub stop_fallback_max_docs: u32,
+    /// Called periodically during a fallback rescan so statement timeout and
+    /// cancellation can abort it.
+    pub checkpoint: Option<fn() -> Result<()>>,
+}
+
+/// NATURAL query results plus how the stop filter affected them.
+#[derive(Debug, Default)]
+pub struct FtsNaturalOutcome {
+    pub results: Vec<FtsResult>,
+    /// Every query ngram was stop-filtered and the fallback returned nothing.
+    pub all_stop_filtered: bool,
+    /// Every query ngram was stop-filtered and the query was rescanned unfiltered.
+    pub rescanned: bool,
 }
 
 /// Execute a NATURAL LANGUAGE MODE query.
@@ -40,9 +87,25 @@ pub fn query_natural_with_config(
     query: &str,
     config: FtsQueryConfig,
 ) -> Result<Vec<FtsResult>> {
-    let query_tokens = tokenize_bigram(query);
+    Ok(query_natural_outcome(fts_index, pager, query, config)?.results)
+}
+
+/// Like [`query_natural_with_config`], also reporting whether the stop-filter
+/// fallback kicked in.
+pub fn query_natural_outcome(
+    fts_index: &FtsIndex,
+    pager: &mut impl PageStore,
+    query: &str,
+    config: FtsQueryConfig,
+) -> Result<FtsNaturalOutcome> {
+    let mut outcome = FtsNaturalOutcome::default();
+    let (phrases, rest) = split_quoted_phrases(query);
+    let mut query_tokens = tokenize_bigram(&rest);
+    for phrase in &phrases {
+        query_tokens.extend(tokenize_bigram(phrase));
+    }
     if query_tokens.is_empty() {
-        return Ok(Vec::new());
+        return Ok(outcome);
     }
 
     let stats = fts_index.get_stats(pager)?;
@@ -50,45 +113,99 @@ pub fn query_natural_with_config(
 
     // Get posting lists for each query term
     let mut term_postings: Vec<(String, PostingList)> = Vec::new();
+    let mut stopped_postings: Vec<(String, PostingList)> = Vec::new();
     let mut seen_terms: HashSet<String> = HashSet::new();
 
     for token in &query_tokens {
         if seen_terms.insert(token.text.clone()) {
             let pl = fts_index.get_postings(pager, &token.text)?;
             if should_skip_stop_ngram(&stats, &pl, config) {
+                stopped_postings.push((token.text.clone(), pl));
                 continue;
             }
             term_postings.push((token.text.clone(), pl));
         }
     }
-    if term_postings.is_empty() {
-        return Ok(Vec::new());
+    // Phrases select the candidates themselves, so the fallback is moot.
+    if term_postings.is_empty() && phrases.is_empty() {
+        if stopped_postings.is_empty() {
+            return Ok(outcome);
+        }
+        match config.stop_fallback {
+            FtsStopFallback::EmptyWithWarning => {
+                outcome.all_stop_filtered = true;
+                return Ok(outcome);
+            }
+            FtsStopFallback::Rescan => {
+                outcome.rescanned = true;
+                term_postings = std::mem::take(&mut stopped_postings);
+            }
+        }
     }
+    // Stop ngrams do not select candidates, but still rank the documents the
+    // other ngrams selected. The decision uses the current df, so a term that
+    // crosses the threshold changes no stored posting.
+    let rank_postings: Vec<&PostingList> = term_postings
+        .iter()
+        .chain(&stopped_postings)
+        .map(|(_, pl)| pl)
+        .collect();
+    let checkpoint = || match config.checkpoint {
+        Some(check) if outcome.rescanned => check(),
+        _ => Ok(()),
+    };
 
-    // Collect all matching doc_ids
+    // Collect all matching doc_ids: those containing every quoted phrase,
+    // else those containing any selecting ngram.
     let mut doc_ids: HashSet<u64> = HashSet::new();
-    for (_, pl) in &term_postings {
+    for (i, phrase) in phrases.iter().enumerate() {
+        let matching = find_phrase_matches(fts_index, pager, phrase)?;
+        if i == 0 {
+            doc_ids.extend(matching);
+        } else {
+            let matching: HashSet<u64> = matching.into_iter().collect();
+            doc_ids.retain(|doc_id| matching.contains(doc_id));
+        }
+    }
+    let selecting_postings = if phrases.is_empty() {
+        &term_postings[..]
+    } else {
+        &[]
+    };
+    for (_, pl) in selecting_postings {
+        checkpoint()?;
         for posting in &pl.postings {
             doc_ids.insert(posting.doc_id);
         }
+        let cap = config.stop_fallback_max_docs;
+        if outcome.rescanned && cap > 0 && doc_ids.len() > cap as usize {
+            return Err(MuroError::Fts(format!(
+                "stop-filter fallback rescan would score more than stop_fallback_max_docs={} documents",
+                cap
+            )));
+        }
     }
 
     // Score each document
     let mut results: Vec<FtsResult> = Vec::new();
-    let doc_freqs: Vec<u64> = term_postings.iter().map(|(_, pl)| pl.df() as u64).collect();
+    let doc_freqs: Vec<u64> = rank_postings.iter().map(|pl| pl.df() as u64).collect();
 
-    for doc_id in &doc_ids {
-        let term_freqs: Vec<u32> = term_postings
+    for (i, doc_id) in doc_ids.iter().enumerate() {
+        if i % RESCAN_CHECKPOINT_INTERVAL == 0 {
+            checkpoint()?;
+        }
+        let term_freqs: Vec<u32> = rank_postings
             .iter()
-            .map(|(_, pl)| {
+            .map(|pl| {
                 pl.get(*doc_id)
                     .map(|p| p.positions.len() as u32)
                     .unwrap_or(0)
             })
             .collect();
 
-        // Approximate doc_len as sum of all term frequencies
-        let doc_len: u32 = term_freqs.iter().sum::<u32>().max(1);
+        // Approximate doc_len as sum of the selecting terms' frequencies, so
+        // a stop ngram only ever adds to a score.
+        let doc_len: u32 = term_freqs[..term_postings.len()].iter().sum::<u32>().max(1);
 
         let score = bm25_score(
             &term_freqs,
@@ -113,7 +230,34 @@ pub fn query_natural_with_config(
             .unwrap_or(std::cmp::Ordering::Equal)
     });
 
-    Ok(results)
+    outcome.results = results;
+    Ok(outcome)
+}
+
+/// Documents scored between checkpoint calls during a fallback rescan.
+const RESCAN_CHECKPOINT_INTERVAL: usize = 1024;
+
+/// Split a NATURAL query into its `"quoted phrases"` and the text outside
+/// them. An unterminated quote runs to the end of the query.
+fn split_quoted_phrases(query: &str) -> (Vec<String>, String) {
+    let mut phrases = Vec::new();
+    let mut rest = String::new();
+    let mut parts = query.split('"');
+    if let Some(outside) = parts.next() {
+        rest.push_str(outside);
+    }
+    for (i, part) in parts.enumerate() {
+        if i % 2 == 0 {
+            if !part.is_empty() {
+                phrases.push(part.to_string());
+            }
+        } else {
+            // Keep the phrase's neighbours from joining into a bigram.
+            rest.push(' ');
+            rest.push_str(part);
+        }
+    }
+    (phrases, rest)
 }
 
 fn should_skip_stop_ngram(
@@ -275,7 +419,9 @@ fn parse_boolean_query(query: &str) -> Vec<BooleanTerm> {
     terms
 }
 
-/// Find documents matching a phrase (consecutive bigrams).
+/// Find documents matching a phrase: bigram `i` of the phrase at position
+/// `p` and bigram `i + 1` at `p + 1`, for every bigram. Returns doc ids in
+/// ascending order.
 fn find_phrase_matches(
     fts_index: &FtsIndex,
     pager: &mut impl PageStore,
@@ -286,80 +432,74 @@ fn find_phrase_matches(
         return Ok(Vec::new());
     }
 
-    // Get posting lists for all bigrams
-    let mut postings: Vec<PostingList> = Vec::new();
+    // One posting list per distinct bigram; a phrase may repeat one.
+    let mut lists: HashMap<&str, PostingList> = HashMap::new();
     for bg in &bigrams {
-        let pl = fts_index.get_postings(pager, &bg.text)?;
-        postings.push(pl);
+        if !lists.contains_key(bg.text.as_str()) {
+            lists.insert(&bg.text, fts_index.get_postings(pager, &bg.text)?);
+        }
     }
+    let postings: Vec<&PostingList> = bigrams.iter().map(|bg| &lists[bg.text.as_str()]).collect();
 
-    // Find documents present in all posting lists
-    if postings.is_empty() {
+    // Walk the rarest bigram's documents and look the others up in theirs.
+    let Some(rarest) = postings.iter().min_by_key(|pl| pl.df()) else {
         return Ok(Vec::new());
-    }
-
-    let first_docs: HashSet<u64> = postings[0].postings.iter().map(|p| p.doc_id).collect();
-
-    let mut candidate_docs: HashSet<u64> = first_docs;
-    for pl in &postings[1..] {
-        let docs: HashSet<u64> = pl.postings.iter().map(|p| p.doc_id).collect();
-        candidate_docs = candidate_docs.intersection(&docs).cloned().collect();
-    }
-
-    // For each candidate, verify phrase by checking consecutive positions
+    };
     let mut matching = Vec::new();
-
-    for doc_id in &candidate_docs {
-        // Get positions for each bigram in this document
-        let positions: Vec<Vec<u32>> = postings
-            .iter()
-            .map(|pl| {
-                pl.get(*doc_id)
-                    .map(|p| p.positions.clone())
-                    .unwrap_or_default()
-            })
-            .collect();
-
-        // Check if there exist positions where bigram_i appears at pos, bigram_{i+1} at pos+1, etc.
-        if check_consecutive_positions(&positions) {
-            matching.push(*doc_id);
+    let mut positions: Vec<&[u32]> = Vec::with_capacity(postings.len());
+    'docs: for posting in &rarest.postings {
+        positions.clear();
+        for pl in &postings {
+            match pl.get(posting.doc_id) {
+                Some(p) => positions.push(&p.positions),
+                None => continue 'docs,
+            }
+        }
+        if has_consecutive_positions(&positions) {
+            matching.push(posting.doc_id);
         }
     }
-
     Ok(matching)
 }
 
-/// Check if there's a sequence of consecutive positions across the position arrays.
-fn check_consecutive_positions(positions: &[Vec<u32>]) -> bool {
-    if positions.is_empty() {
+/// Whether some `p` has `p` in `positions[0]`, `p + 1` in `positions[1]`,
+/// and so on. Each list must be sorted; every pass is a linear merge of the
+/// surviving start positions with the next list.
+fn has_consecutive_positions(positions: &[&[u32]]) -> bool {
+    let Some((first, rest)) = positions.split_first() else {
         return false;
-    }
-    if positions.len() == 1 {
-        return !positions[0].is_empty();
-    }
-
-    // For each position in the first bigram, check if consecutive positions exist
-    for &start_pos in &positions[0] {
-        let mut found = true;
-        for (i, pos_list) in positions.iter().enumerate().skip(1) {
-            let expected = start_pos + i as u32;
-            if !pos_list.contains(&expected) {
-                found = false;
+    };
+    let mut starts: Vec<u32> = first.to_vec();
+    for (i, list) in rest.iter().enumerate() {
+        let offset = i as u32 + 1;
+        let mut kept = 0;
+        let mut j = 0;
+        for k in 0..starts.len() {
+            let wanted = starts[k] + offset;
+            while j < list.len() && list[j] < wanted {
+                j += 1;
+            }
+            if j == list.len() {
                 break;
             }
+            if list[j] == wanted {
+                starts[kept] = starts[k];
+                kept += 1;
+            }
         }
-        if found {
-            return true;
+        starts.truncate(kept);
+        if starts.is_empty() {
+            return false;
         }
     }
-
-    false
+    !starts.is_empty()
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
     use crate::crypto::aead::MasterKey;
+    use crate::crypto::hmac_util::TermKey;
     use crate::fts::index::FtsPendingOp;
     use crate::storage::pager::Pager;
     use tempfile::TempDir;
@@ -368,8 +508,8 @@ mod tests {
         MasterKey::new([0x42u8; 32])
     }
 
-    fn term_key() -> [u8; 32] {
-        [0x55u8; 32]
+    fn term_key() -> TermKey {
+        TermKey::new([0x55u8; 32])
     }
 
     fn setup_index(docs: &[(u64, &str)]) -> (Pager, FtsIndex, TempDir) {
@@ -433,10 +573,159 @@ mod tests {
         // Doc 2 has the bigrams but not in consecutive order for "東京タワー"
     }
 
+    #[test]
+    fn test_natural_phrase_requires_adjacent_bigrams() {
+        let (mut pager, idx, _dir) = setup_index(&[
+            (1, "東京都庁の展望室"),
+            (2, "東京から京都まで"),
+            (3, "京都と東京"),
+            (4, "大阪と東京都"),
+        ]);
+
+        let ids = |results: Vec<FtsResult>| {
+            let mut ids: Vec<u64> = results.iter().map(|r| r.doc_id).collect();
+            ids.sort();
+            ids
+        };
+        let results = query_natural(&idx, &mut pager, "\"東京都\"").unwrap();
+        assert_eq!(ids(results), vec![1, 4]);
+        // Unquoted, any bigram selects.
+        let results = query_natural(&idx, &mut pager, "東京都").unwrap();
+        assert_eq!(ids(results), vec![1, 2, 3, 4]);
+
+        // Terms outside the phrase rank the phrase matches but select nothing.
+        let results = query_natural(&idx, &mut pager, "大阪 \"東京都\"").unwrap();
+        assert_eq!(results.iter().map(|r| r.doc_id).collect::<Vec<_>>(), [4, 1]);
+        // Every phrase must match.
+        let results = query_natural(&idx, &mut pager, "\"東京都\" \"大阪\"").unwrap();
+        assert_eq!(ids(results), vec![4]);
+        let results = query_natural(&idx, &mut pager, "\"都庁の展望\"").unwrap();
+        assert_eq!(ids(results), vec![1]);
+        let results = query_natural(&idx, &mut pager, "\"京都と東京都\"").unwrap();
+        assert!(results.is_empty());
+    }
+
+    #[test]
+    fn test_phrase_with_repeated_bigram() {
+        let (mut pager, idx, _dir) =
+            setup_index(&[(1, "ああいあ"), (2, "あああ"), (3, "ああ ああ")]);
+        let results = query_boolean(&idx, &mut pager, "\"あああ\"").unwrap();
+        assert_eq!(results.iter().map(|r| r.doc_id).collect::<Vec<_>>(), [2]);
+    }
+
+    #[test]
+    fn test_phrase_positions_split_across_segments() {
+        // 東京 occurs often enough in doc 1 that its positions there are
+        // stored in several segments; the phrase ends in the last one.
+        let long_doc = format!("{}東京都", "東京 ".repeat(70_000));
+        let (mut pager, idx, _dir) =
+            setup_index(&[(1, long_doc.as_str()), (2, "東京 京都"), (3, "東京都")]);
+        let postings = idx.get_postings(&mut pager, "東京").unwrap();
+        assert_eq!(postings.get(1).unwrap().positions.len(), 70_001);
+
+        let results = query_natural(&idx, &mut pager, "\"東京都\"").unwrap();
+        let mut ids: Vec<u64> = results.iter().map(|r| r.doc_id).collect();
+        ids.sort();
+        assert_eq!(ids, vec![1, 3]);
+        let results = query_natural(&idx, &mut pager, "\"東京 東京 東京都\"").unwrap();
+        assert_eq!(results.iter().map(|r| r.doc_id).collect::<Vec<_>>(), [1]);
+    }
+
+    #[test]
+    fn test_has_consecutive_positions() {
+        assert!(!has_consecutive_positions(&[]));
+        assert!(has_consecutive_positions(&[&[4]]));
+        assert!(!has_consecutive_positions(&[&[]]));
+        assert!(has_consecutive_positions(&[&[1, 5, 9], &[3, 10], &[11]]));
+        assert!(!has_consecutive_positions(&[
+            &[1, 5, 9],
+            &[3, 10],
+            &[4, 12]
+        ]));
+        assert!(!has_consecutive_positions(&[&[1, 5], &[2, 6], &[]]));
+        // The same list at every step, as for a repeated bigram.
+        let run: &[u32] = &[0, 1, 3];
+        assert!(has_consecutive_positions(&[run, run]));
+        assert!(!has_consecutive_positions(&[run, run, run]));
+    }
+
+    #[test]
+    fn test_split_quoted_phrases() {
+        assert_eq!(
+            split_quoted_phrases("a \"b c\" d \"\" \"e"),
+            (
+                vec!["b c".to_string(), "e".to_string()],
+                "a   d   ".to_string()
+            )
+        );
+        assert_eq!(split_quoted_phrases("plain"), (vec![], "plain".to_string()));
+    }
+
     #[test]
     fn test_empty_query() {
         let (mut pager, idx, _dir) = setup_index(&[(1, "test")]);
         let results = query_natural(&idx, &mut pager, "").unwrap();
         assert!(results.is_empty());
     }
+
+    #[test]
+    fn test_all_stop_filtered_query_falls_back() {
+        let (mut pager, idx, _dir) =
+            setup_index(&[(1, "東京タワー"), (2, "東京駅"), (3, "東京湾")]);
+        let mut config = FtsQueryConfig {
+            stop_filter: true,
+            stop_df_ratio_ppm: 500_000,
+            ..FtsQueryConfig::default()
+        };
+
+        let outcome = query_natural_outcome(&idx, &mut pager, "東京", config).unwrap();
+        assert!(outcome.rescanned);
+        assert_eq!(outcome.results.len(), 3);
+
+        config.stop_fallback_max_docs = 2;
+        let err = query_natural_outcome(&idx, &mut pager, "東京", config).unwrap_err();
+        assert!(
+            err.to_string().contains("stop_fallback_max_docs=2"),
+            "{}",
+            err
+        );
+
+        config.stop_fallback = FtsStopFallback::EmptyWithWarning;
+        let outcome = query_natural_outcome(&idx, &mut pager, "東京", config).unwrap();
+        assert!(outcome.all_stop_filtered);
+        assert!(outcome.results.is_empty());
+
+        // A partly filtered query selects by the surviving ngrams only.
+        let outcome = query_natural_outcome(&idx, &mut pager, "東京タワー", config).unwrap();
+        assert!(!outcome.rescanned && !outcome.all_stop_filtered);
+        assert_eq!(outcome.results[0].doc_id, 1);
+    }
+
+    #[test]
+    fn test_stop_ngram_ranks_but_does_not_select() {
+        // 東京 is in 18 of 20 documents.
+        let mut docs = vec![(1, "東京タワー".to_string()), (2, "タワー東京".to_string())];
+        docs.push((3, "タワー大阪".to_string()));
+        docs.push((4, "大阪城".to_string()));
+        for id in 5..=20 {
+            docs.push((id, format!("東京駅{}", id)));
+        }
+        let docs: Vec<(u64, &str)> = docs.iter().map(|(id, t)| (*id, t.as_str())).collect();
+        let (mut pager, idx, _dir) = setup_index(&docs);
+        let config = FtsQueryConfig {
+            stop_filter: true,
+            stop_df_ratio_ppm: 500_000,
+            ..FtsQueryConfig::default()
+        };
+
+        let outcome = query_natural_outcome(&idx, &mut pager, "東京タワー", config).unwrap();
+        assert!(!outcome.rescanned);
+        let ids: Vec<u64> = outcome.results.iter().map(|r| r.doc_id).collect();
+        assert_eq!(ids, vec![1, 2, 3]);
+        // 2 and 3 share タワー; only 2 also has the stop ngram.
+        assert!(outcome.results[1].score > outcome.results[2].score);
+
+        let unfiltered = query_natural(&idx, &mut pager, "東京タワー").unwrap();
+        assert_eq!(unfiltered.len(), 19);
+    }
 }
@@ -0,0 +1,641 @@
+//! Transactional key-value access without the SQL layer.
+//!
+//! [`KvDatabase`] stores byte keys and values in one B-tree registered in the
+//! system catalog under `kv:default`. It runs on the same pager, WAL,
+//! recovery and file locks as the SQL [`Database`](crate::Database): every
+//! write is a WAL transaction, opening replays the WAL after a crash, and
+//! the file may be encrypted. It is the whole public API when the crate is
+//! built without the default `sql` feature.
+//!
+//! The catalog entry maps `kv:<namespace>` to the namespace's B-tree root
+//! (u64 LE). The B-tree is created by the first write, so a database created
+//! through SQL can be opened here too, and integrity checks of the SQL layer
+//! account for its pages.
+
+use std::ops::Bound;
+use std::path::Path;
+use std::time::Duration;
+
+use crate::btree::ops::BTree;
+use crate::concurrency::LockManager;
+use crate::crypto::aead::MasterKey;
+use crate::crypto::suite::EncryptionSuite;
+use crate::error::{MuroError, Result};
+use crate::storage::page::PageId;
+use crate::storage::page_store::PageStore;
+use crate::storage::pager::Pager;
+use crate::tx::page_store::{PagerAllocState, TxPageStore};
+use crate::tx::transaction::Transaction;
+use crate::wal::header::WalIdentity;
+use crate::wal::record::TxId;
+use crate::wal::recovery::RecoveryMode;
+use crate::wal::writer::WalWriter;
+use crate::{
+    migrate_legacy_sidecar_paths, quarantine_wal_durably, sync_dir, truncate_wal_durably, wal_path,
+    RecoveryResult, RetryPolicy,
+};
+
+/// Catalog key prefix of key-value namespaces.
+pub(crate) const KV_CATALOG_PREFIX: &[u8] = b"kv:";
+
+/// The namespace [`KvDatabase`] reads and writes.
+const DEFAULT_NAMESPACE: &str = "default";
+
+/// Catalog entry of the FTS term key, written at creation so the SQL layer
+/// finds the same metadata as in a database it created itself.
+const META_FTS_TERM_KEY: &[u8] = b"meta:fts_term_key";
+
+fn namespace_catalog_key(namespace: &str) -> Vec<u8> {
+    let mut key = KV_CATALOG_PREFIX.to_vec();
+    key.extend_from_slice(namespace.as_bytes());
+    key
+}
+
+fn decode_namespace_root(value: &[u8]) -> Result<PageId> {
+    let bytes: [u8; 8] = value.try_into().map_err(|_| {
+        MuroError::Corruption("catalog kv namespace entry has invalid length".to_string())
+    })?;
+    Ok(PageId::from_le_bytes(bytes))
+}
+
+/// Every key-value namespace registered in the catalog at `catalog_root`,
+/// with its B-tree root, in name order.
+pub(crate) fn catalog_namespaces(
+    pager: &mut impl PageStore,
+    catalog_root: PageId,
+) -> Result<Vec<(String, PageId)>> {
+    let mut entries = Vec::new();
+    BTree::open(catalog_root).scan_from(pager, KV_CATALOG_PREFIX, |k, v| {
+        let Some(name) = k.strip_prefix(KV_CATALOG_PREFIX) else {
+            return Ok(false);
+        };
+        entries.push((String::from_utf8_lossy(name).into_owned(), v.to_vec()));
+        Ok(true)
+    })?;
+    entries
+        .into_iter()
+        .map(|(name, v)| Ok((name, decode_namespace_root(&v)?)))
+        .collect()
+}
+
+fn namespace_root(pager: &mut impl PageStore, catalog: &BTree) -> Result<Option<PageId>> {
+    catalog
+        .search(pager, &namespace_catalog_key(DEFAULT_NAMESPACE))?
+        .map(|v| decode_namespace_root(&v))
+        .transpose()
+}
+
+fn in_range(key: &[u8], end: Bound<&[u8]>) -> bool {
+    match end {
+        Bound::Included(end) => key <= end,
+        Bound::Excluded(end) => key < end,
+        Bound::Unbounded => true,
+    }
+}
+
+fn scan_tree(
+    pager: &mut impl PageStore,
+    tree: &BTree,
+    start: Bound<&[u8]>,
+    end: Bound<&[u8]>,
+) -> Result<Vec<(Vec<u8>, Vec<u8>)>> {
+    let mut entries = Vec::new();
+    let first = match start {
+        Bound::Included(key) | Bound::Excluded(key) => key,
+        Bound::Unbounded => &[],
+    };
+    tree.scan_from(pager, first, |k, v| {
+        if matches!(start, Bound::Excluded(s) if k == s) {
+            return Ok(true);
+        }
+        if !in_range(k, end) {
+            return Ok(false);
+        }
+        entries.push((k.to_vec(), v.to_vec()));
+        Ok(true)
+    })?;
+    Ok(entries)
+}
+
+/// Key-value database handle.
+///
+/// Single operations run in their own transaction;
+/// [`KvDatabase::transaction`] groups several into one.
+pub struct KvDatabase {
+    pager: Pager,
+    wal: WalWriter,
+    lock_manager: LockManager,
+    next_txid: TxId,
+    busy_timeout_ms: u64,
+    /// Set when a commit failed after its WAL frames were synced: the
+    /// in-memory pager may disagree with the file until reopened.
+    poisoned: Option<String>,
+}
+
+/// Reads and writes of a transaction run by [`KvDatabase::transaction`].
+///
+/// Reads see the transaction's own writes. Nothing reaches the file unless
+/// the closure returns `Ok`. An operation that fails may leave part of its
+/// writes behind, so return its error to roll the transaction back.
+pub struct KvTransaction<'a> {
+    store: TxPageStore<'a>,
+    catalog: BTree,
+    data: Option<BTree>,
+}
+
+impl KvTransaction<'_> {
+    /// Value stored under `key`, if any.
+    pub fn get(&mut self, key: &[u8]) -> Result<Option<Vec<u8>>> {
+        match &self.data {
+            Some(tree) => tree.search(&mut self.store, key),
+            None => Ok(None),
+        }
+    }
+
+    /// Store `value` under `key`, replacing any previous value.
+    pub fn put(&mut self, key: &[u8], value: &[u8]) -> Result<()> {
+        let root_before = self.data.as_ref().map(BTree::root_page_id);
+        if self.data.is_none() {
+            self.data = Some(BTree::create(&mut self.store)?);
+        }
+        let tree = self.data.as_mut().expect("created above");
+        tree.insert(&mut self.store, key, value)?;
+        self.register_data_root(root_before)
+    }
+
+    /// Remove `key`. Returns whether it was present.
+    pub fn delete(&mut self, key: &[u8]) -> Result<bool> {
+        let Some(tree) = self.data.as_mut() else {
+            return Ok(false);
+        };
+        let root_before = Some(tree.root_page_id());
+        let deleted = tree.delete(&mut self.store, key)?;
+        self.register_data_root(root_before)?;
+        Ok(deleted)
+    }
+
+    /// Entries with keys between `start` and `end`, in key order.
+    pub fn scan_range(
+        &mut self,
+        start: Bound<&[u8]>,
+        end: Bound<&[u8]>,
+    ) -> Result<Vec<(Vec<u8>, Vec<u8>)>> {
+        match &self.data {
+            Some(tree) => scan_tree(&mut self.store, tree, start, end),
+            None => Ok(Vec::new()),
+        }
+    }
+
+    /// Point the catalog entry at the data B-tree's root if a write moved it.
+    fn register_data_root(&mut self, root_before: Option<PageId>) -> Result<()> {
+        match self.data.as_ref().map(BTree::root_page_id) {
+            Some(root) if Some(root) != root_before => self.catalog.insert(
+                &mut self.store,
+                &namespace_catalog_key(DEFAULT_NAMESPACE),
+                &root.to_le_bytes(),
+            ),
+            _ => Ok(()),
+        }
+    }
+}
+
+impl KvDatabase {
+    /// Create a new encrypted database at `path`.
+    pub fn create(path: &Path, master_key: &MasterKey) -> Result<Self> {
+        Self::create_inner(path, Some(master_key))
+    }
+
+    /// Create a new plaintext database at `path`.
+    pub fn create_plaintext(path: &Path) -> Result<Self> {
+        Self::create_inner(path, None)
+    }
+
+    /// Open an existing encrypted database, replaying its WAL if the last
+    /// handle crashed.
+    pub fn open(path: &Path, master_key: &MasterKey) -> Result<Self> {
+        Self::open_inner(path, Some(master_key), RecoveryMode::Strict).map(|(db, _)| db)
+    }
+
+    /// Open an existing plaintext database, replaying its WAL if the last
+    /// handle crashed.
+    pub fn open_plaintext(path: &Path) -> Result<Self> {
+        Self::open_inner(path, None, RecoveryMode::Strict).map(|(db, _)| db)
+    }
+
+    /// Open with an explicit recovery mode (`None` master key for
+    /// plaintext), returning the recovery report when a WAL was replayed.
+    pub fn open_with_recovery_mode_and_report(
+        path: &Path,
+        master_key: Option<&MasterKey>,
+        recovery_mode: RecoveryMode,
+    ) -> Result<(Self, Option<RecoveryResult>)> {
+        Self::open_inner(path, master_key, recovery_mode)
+    }
+
+    fn suite(master_key: Option<&MasterKey>) -> EncryptionSuite {
+        match master_key {
+            Some(_) => EncryptionSuite::Aes256GcmSiv,
+            None => EncryptionSuite::Plaintext,
+        }
+    }
+
+    fn create_inner(path: &Path, master_key: Option<&MasterKey>) -> Result<Self> {
+        let lock_manager = LockManager::new(path)?;
+        let create_guard = lock_manager.write_lock()?;
+        let mut pager = match master_key {
+            Some(key) => Pager::create(path, key)?,
+            None => Pager::create_plaintext(path)?,
+        };
+        let mut catalog = BTree::create(&mut pager)?;
+        let fts_term_key = pager.derive_bootstrap_fts_term_key();
+        catalog.insert(&mut pager, META_FTS_TERM_KEY, fts_term_key.as_bytes())?;
+        pager.set_catalog_root(catalog.root_page_id());
+        pager.flush_meta()?;
+
+        sync_dir(path);
+
+        let wal = WalWriter::create_for_instance(
+            &wal_path(path),
+            Self::suite(master_key),
+            master_key,
+            *pager.salt(),
+        )?;
+        drop(create_guard);
+        Ok(Self::from_parts(pager, wal, lock_manager))
+    }
+
+    fn open_inner(
+        path: &Path,
+        master_key: Option<&MasterKey>,
+        recovery_mode: RecoveryMode,
+    ) -> Result<(Self, Option<RecoveryResult>)> {
+        migrate_legacy_sidecar_paths(path);
+        let suite = Self::suite(master_key);
+        let wp = wal_path(path);
+        let mut recovery_report = None;
+        // As for `Database`: recovery and the writer's creation never
+        // overlap a commit or the open of another handle.
+        let lock_manager = LockManager::new(path)?;
+        let open_guard = lock_manager.write_lock()?;
+
+        if wp.exists() {
+            let mut report = crate::wal::recovery::recover_with_mode_and_suite(
+                path,
+                &wp,
+                suite,
+                master_key,
+                recovery_mode,
+            )?;
+            if recovery_mode != RecoveryMode::Strict && report.lost_committed_data() {
+                let quarantine = quarantine_wal_durably(&wp)?;
+                report.wal_quarantine_path = Some(quarantine.display().to_string());
+            } else {
+                let salt = Pager::read_encryption_info_from_file(path)?.salt;
+                truncate_wal_durably(&wp, &WalIdentity::new(salt, suite, master_key))?;
+            }
+            recovery_report = Some(report);
+        }
+
+        let pager = Pager::open_with_suite(path, Some(suite), master_key)?;
+        if pager.catalog_root() == 0 && pager.page_count() == 0 {
+            return Err(MuroError::Corruption(
+                "database has no system catalog".to_string(),
+            ));
+        }
+        let wal = WalWriter::create_for_instance(&wp, suite, master_key, *pager.salt())?;
+        drop(open_guard);
+        Ok((Self::from_parts(pager, wal, lock_manager), recovery_report))
+    }
+
+    fn from_parts(pager: Pager, wal: WalWriter, lock_manager: LockManager) -> Self {
+        KvDatabase {
+            next_txid: pager.next_txid(),
+            pager,
+            wal,
+            lock_manager,
+            busy_timeout_ms: 0,
+            poisoned: None,
+        }
+    }
+
+    /// Configure lock wait timeout in milliseconds.
+    ///
+    /// `0` means wait indefinitely (default).
+    pub fn set_busy_timeout_ms(&mut self, timeout_ms: u64) {
+        self.busy_timeout_ms = timeout_ms;
+    }
+
+    /// Current lock wait timeout in milliseconds.
+    ///
+    /// `0` means wait indefinitely.
+    pub fn busy_timeout_ms(&self) -> u64 {
+        self.busy_timeout_ms
+    }
+
+    fn lock_timeout(&self) -> Option<Duration> {
+        (self.busy_timeout_ms > 0).then(|| Duration::from_millis(self.busy_timeout_ms))
+    }
+
+    fn check_poisoned(&self) -> Result<()> {
+        match &self.poisoned {
+            Some(msg) => Err(MuroError::SessionPoisoned(msg.clone())),
+            None => Ok(()),
+        }
+    }
+
+    /// Pick up commits other handles made since this one last looked.
+    fn refresh_from_disk(pager: &mut Pager, next_txid: &mut TxId) -> Result<()> {
+        if pager.refresh_from_disk_if_changed()? {
+            *next_txid = (*next_txid).max(pager.next_txid());
+        }
+        Ok(())
+    }
+
+    /// Value stored under `key`, if any.
+    pub fn get(&mut self, key: &[u8]) -> Result<Option<Vec<u8>>> {
+        self.check_poisoned()?;
+        let _guard = self
+            .lock_manager
+            .read_lock_with_timeout(self.lock_timeout())?;
+        Self::refresh_from_disk(&mut self.pager, &mut self.next_txid)?;
+        let catalog = BTree::open(self.pager.catalog_root());
+        match namespace_root(&mut self.pager, &catalog)? {
+            Some(root) => BTree::open(root).search(&mut self.pager, key),
+            None => Ok(None),
+        }
+    }
+
+    /// Entries with keys between `start` and `end`, in key order.
+    pub fn scan_range(
+        &mut self,
+        start: Bound<&[u8]>,
+        end: Bound<&[u8]>,
+    ) -> Result<Vec<(Vec<u8>, Vec<u8>)>> {
+        self.check_poisoned()?;
+        let _guard = self
+            .lock_manager
+            .read_lock_with_timeout(self.lock_timeout())?;
+        Self::refresh_from_disk(&mut self.pager, &mut self.next_txid)?;
+        let catalog = BTree::open(self.pager.catalog_root());
+        match namespace_root(&mut self.pager, &catalog)? {
+            Some(root) => scan_tree(&mut self.pager, &BTree::open(root), start, end),
+            None => Ok(Vec::new()),
+        }
+    }
+
+    /// Store `value` under `key` in a transaction of its own.
+    pub fn put(&mut self, key: &[u8], value: &[u8]) -> Result<()> {
+        self.transaction(|tx| tx.put(key, value))
+    }
+
+    /// Remove `key` in a transaction of its own. Returns whether it was
+    /// present.
+    pub fn delete(&mut self, key: &[u8]) -> Result<bool> {
+        self.transaction(|tx| tx.delete(key))
+    }
+
+    /// Run `f` in a write transaction: committed when it returns `Ok`,
+    /// rolled back when it returns `Err` or panics (the panic then
+    /// continues). The exclusive lock is held throughout.
+    pub fn transaction<T, F>(&mut self, f: F) -> Result<T>
+    where
+        F: FnOnce(&mut KvTransaction<'_>) -> Result<T>,
+    {
+        self.check_poisoned()?;
+        let _guard = self
+            .lock_manager
+            .write_lock_with_timeout(self.lock_timeout())?;
+        Self::refresh_from_disk(&mut self.pager, &mut self.next_txid)?;
+
+        let txid = self.next_txid;
+        self.next_txid += 1;
+        let alloc_before = PagerAllocState::capture(&mut self.pager);
+        let tx = Transaction::begin(txid, self.wal.current_lsn());
+        let catalog = BTree::open(self.pager.catalog_root());
+        let mut store = TxPageStore::new(tx, &mut self.pager);
+        let outcome = namespace_root(&mut store, &catalog).map(|root| {
+            let mut kv_tx = KvTransaction {
+                store,
+                catalog,
+                data: root.map(BTree::open),
+            };
+            let result = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| f(&mut kv_tx)));
+            (result, kv_tx.store.into_tx(), kv_tx.catalog.root_page_id())
+        });
+        let (result, mut tx, catalog_root) = match outcome {
+            Ok(parts) => parts,
+            Err(e) => {
+                alloc_before.restore(&mut self.pager);
+                return Err(e);
+            }
+        };
+
+        let value = match result {
+            Ok(Ok(value)) => value,
+            Ok(Err(e)) => {
+                tx.rollback_no_wal();
+                alloc_before.restore(&mut self.pager);
+                return Err(e);
+            }
+            Err(panic) => {
+                tx.rollback_no_wal();
+                alloc_before.restore(&mut self.pager);
+                std::panic::resume_unwind(panic)
+            }
+        };
+
+        self.pager.set_next_txid(self.next_txid);
+        match tx.commit(&mut self.pager, &mut self.wal, catalog_root) {
+            Ok(_) => {}
+            Err(e @ MuroError::CommitInDoubt(_)) => {
+                self.poisoned = Some(e.to_string());
+                return Err(e);
+            }
+            Err(e) => {
+                alloc_before.restore(&mut self.pager);
+                return Err(e);
+            }
+        }
+        // Best-effort: the commit is durable in the WAL either way, and the
+        // next open replays whatever a failed truncation left behind.
+        let _ = self.wal.checkpoint_truncate();
+        Ok(value)
+    }
+
+    /// Like [`KvDatabase::transaction`], but run the whole transaction
+    /// again when an attempt fails with a lock error, as `policy` allows.
+    /// The last error is returned once retries run out.
+    pub fn transaction_with_retry<T, F>(&mut self, policy: RetryPolicy, mut f: F) -> Result<T>
+    where
+        F: FnMut(&mut KvTransaction<'_>) -> Result<T>,
+    {
+        let mut retries = 0;
+        loop {
+            match self.transaction(&mut f) {
+                Err(e) if policy.should_retry(&e, retries) => {
+                    std::thread::sleep(policy.backoff(retries));
+                    retries += 1;
+                }
+                result => return result,
+            }
+        }
+    }
+
+    /// Route the writes and fsyncs of the database file and the WAL through
+    /// `fault`, or stop doing so with `None`. See [`crate::fault`].
+    #[cfg(any(test, feature = "test-utils"))]
+    pub fn set_fault_injector(&mut self, fault: Option<crate::fault::FaultInjector>) {
+        self.pager.set_fault_injector(fault.clone());
+        self.wal.set_fault_injector(fault);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fault::{FaultFile, FaultInjector, FaultKind, FaultPoint};
+    use tempfile::TempDir;
+
+    fn entries(db: &mut KvDatabase) -> Vec<(Vec<u8>, Vec<u8>)> {
+        db.scan_range(Bound::Unbounded, Bound::Unbounded).unwrap()
+    }
+
+    fn kv(pairs: &[(&str, &str)]) -> Vec<(Vec<u8>, Vec<u8>)> {
+        pairs
+            .iter()
+            .map(|(k, v)| (k.as_bytes().to_vec(), v.as_bytes().to_vec()))
+            .collect()
+    }
+
+    #[test]
+    fn test_namespace_is_registered_in_catalog_on_first_write() {
+        let dir = TempDir::new().unwrap();
+        let mut db = KvDatabase::create_plaintext(&dir.path().join("kv.db")).unwrap();
+        let catalog_root = db.pager.catalog_root();
+        assert!(catalog_namespaces(&mut db.pager, catalog_root)
+            .unwrap()
+            .is_empty());
+        assert_eq!(db.get(b"a").unwrap(), None);
+
+        db.put(b"a", b"1").unwrap();
+        let catalog_root = db.pager.catalog_root();
+        let namespaces = catalog_namespaces(&mut db.pager, catalog_root).unwrap();
+        assert_eq!(namespaces.len(), 1);
+        assert_eq!(namespaces[0].0, DEFAULT_NAMESPACE);
+    }
+
+    #[test]
+    fn test_catalog_follows_data_root_through_splits() {
+        let dir = TempDir::new().unwrap();
+        let path = dir.path().join("kv.db");
+        let mut db = KvDatabase::create_plaintext(&path).unwrap();
+        db.transaction(|tx| {
+            for i in 0..2000u32 {
+                tx.put(format!("key{:05}", i).as_bytes(), &[7u8; 64])?;
+            }
+            Ok(())
+        })
+        .unwrap();
+        drop(db);
+
+        let mut db = KvDatabase::open_plaintext(&path).unwrap();
+        assert_eq!(entries(&mut db).len(), 2000);
+        assert_eq!(db.get(b"key01999").unwrap(), Some(vec![7u8; 64]));
+    }
+
+    /// Every crash point of a transaction leaves either all of it or none
+    /// of it after recovery, and all of it once commit returned `Ok`.
+    #[test]
+    fn test_crash_at_every_write_recovers_all_or_nothing() {
+        let before = kv(&[("a", "1"), ("b", "2"), ("c", "3")]);
+        let after = kv(&[("a", "1"), ("b", "two"), ("d", "4")]);
+        let workload = |db: &mut KvDatabase| {
+            db.transaction(|tx| {
+                tx.put(b"b", b"two")?;
+                tx.delete(b"c")?;
+                tx.put(b"d", b"4")?;
+                Ok(())
+            })
+        };
+        let setup = |dir: &TempDir| {
+            let path = dir.path().join("crash.db");
+            let mut db = KvDatabase::create(&path, &MasterKey::new([9u8; 32])).unwrap();
+            for (k, v) in &before {
+                db.put(k, v).unwrap();
+            }
+            db
+        };
+
+        let dir = TempDir::new().unwrap();
+        let mut db = setup(&dir);
+        let counter = FaultInjector::counting();
+        db.set_fault_injector(Some(counter.clone()));
+        workload(&mut db).unwrap();
+        let counts = counter.counts();
+        assert!(counts.wal_writes > 0 && counts.data_writes > 0);
+
+        let mut points = Vec::new();
+        for kind in [FaultKind::Fail, FaultKind::TornWrite] {
+            for write in 1..=counts.wal_writes {
+                points.push(FaultPoint {
+                    file: FaultFile::Wal,
+                    write,
+                    kind,
+                });
+            }
+            for write in 1..=counts.data_writes {
+                points.push(FaultPoint {
+                    file: FaultFile::Data,
+                    write,
+                    kind,
+                });
+            }
+        }
+        for point in points {
+            let dir = TempDir::new().unwrap();
+            let mut db = setup(&dir);
+            db.set_fault_injector(Some(FaultInjector::at(point)));
+            let committed = workload(&mut db).is_ok();
+            drop(db);
+
+            let mut db = KvDatabase::open(&dir.path().join("crash.db"), &MasterKey::new([9u8; 32]))
+                .unwrap_or_else(|e| panic!("reopen after {:?}: {}", point, e));
+            let got = entries(&mut db);
+            if committed {
+                assert_eq!(got, after, "lost a committed transaction at {:?}", point);
+            } else {
+                assert!(
+                    got == before || got == after,
+                    "partial transaction after {:?}: {:?}",
+                    point,
+                    got
+                );
+            }
+            // The recovered database takes new writes.
+            db.put(b"z", b"26").unwrap();
+            assert_eq!(db.get(b"z").unwrap(), Some(b"26".to_vec()));
+        }
+    }
+
+    #[test]
+    fn test_failed_commit_leaves_handle_usable() {
+        let dir = TempDir::new().unwrap();
+        let path = dir.path().join("kv.db");
+        let mut db = KvDatabase::create_plaintext(&path).unwrap();
+        db.put(b"a", b"1").unwrap();
+
+        // The first WAL write of the commit fails before anything is durable.
+        db.set_fault_injector(Some(FaultInjector::at(FaultPoint {
+            file: FaultFile::Wal,
+            write: 1,
+            kind: FaultKind::Fail,
+        })));
+        assert!(db.put(b"b", b"2").is_err());
+        drop(db);
+
+        let mut db = KvDatabase::open_plaintext(&path).unwrap();
+        assert_eq!(entries(&mut db), kv(&[("a", "1")]));
+        db.put(b"b", b"2").unwrap();
+        assert_eq!(entries(&mut db), kv(&[("a", "1"), ("b", "2")]));
+    }
+}
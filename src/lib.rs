@@ -8,6 +8,10 @@
 //! - Full-text search with bigram tokenization
 //! - WAL-based crash recovery
 //! - Multiple readers / single writer concurrency
+//!
+//! The SQL layer ([`Database`]) is behind the default `sql` feature. Without
+//! it the crate is a transactional key-value store, [`KvDatabase`], on the
+//! same pager, WAL, recovery, locking and encryption.
 
 #[cfg(feature = "test-utils")]
 pub mod btree;
@@ -24,24 +28,37 @@ pub mod crypto;
 #[cfg(not(feature = "test-utils"))]
 pub(crate) mod crypto;
 
+#[cfg(feature = "sql")]
+mod database;
+
 #[cfg(feature = "test-utils")]
 pub mod error;
 #[cfg(not(feature = "test-utils"))]
 pub(crate) mod error;
 
-#[cfg(feature = "test-utils")]
+#[cfg(any(test, feature = "test-utils"))]
+pub mod fault;
+
+#[cfg(all(feature = "sql", feature = "test-utils"))]
 pub mod fts;
-#[cfg(not(feature = "test-utils"))]
+#[cfg(all(feature = "sql", not(feature = "test-utils")))]
 pub(crate) mod fts;
 
-#[cfg(feature = "test-utils")]
+mod kv;
+
+pub mod limits;
+
+#[cfg(feature = "sql")]
+mod pool;
+
+#[cfg(all(feature = "sql", feature = "test-utils"))]
 pub mod schema;
-#[cfg(not(feature = "test-utils"))]
+#[cfg(all(feature = "sql", not(feature = "test-utils")))]
 pub(crate) mod schema;
 
-#[cfg(feature = "test-utils")]
+#[cfg(all(feature = "sql", feature = "test-utils"))]
 pub mod sql;
-#[cfg(not(feature = "test-utils"))]
+#[cfg(all(feature = "sql", not(feature = "test-utils")))]
 pub(crate) mod sql;
 
 #[cfg(feature = "test-utils")]
@@ -54,9 +71,9 @@ pub mod tx;
 #[cfg(not(feature = "test-utils"))]
 pub(crate) mod tx;
 
-#[cfg(feature = "test-utils")]
+#[cfg(all(feature = "sql", feature = "test-utils"))]
 pub mod types;
-#[cfg(not(feature = "test-utils"))]
+#[cfg(all(feature = "sql", not(feature = "test-utils")))]
 pub(crate) mod types;
 
 #[cfg(feature = "test-utils")]
@@ -64,67 +81,141 @@ pub mod wal;
 #[cfg(not(feature = "test-utils"))]
 pub(crate) mod wal;
 
+pub use crate::concurrency::LockState;
 pub use crate::crypto::aead::MasterKey;
-pub use crate::error::{MuroError, Result};
+#[cfg(feature = "sql")]
+pub use crate::database::{
+    Database, DatabaseReader, MaintenanceGuard, SqlStatementClass, TxHandle,
+};
+pub use crate::error::{ErrorClass, MuroError, Result};
+#[cfg(feature = "sql")]
 pub use crate::fts::snippet::fts_snippet;
-pub use crate::sql::executor::{ExecResult, Row};
+pub use crate::kv::{KvDatabase, KvTransaction};
+pub use crate::limits::{Limit, Limits};
+#[cfg(feature = "sql")]
+pub use crate::pool::{DatabasePool, PoolConfig, ReadHandle, WriteHandle};
+#[cfg(feature = "sql")]
+pub use crate::schema::expectation::{
+    ExpectedColumn, ExpectedIndex, ExpectedTable, SchemaDiff, SchemaDifference, SchemaExpectation,
+};
+#[cfg(feature = "sql")]
+pub use crate::schema::plan_baseline::{PlanAccess, PlanBaseline, PlanDescription};
+#[cfg(feature = "sql")]
+pub use crate::sql::ast::ScanCorruptionPolicy;
+#[cfg(feature = "sql")]
+pub use crate::sql::executor::{ExecResult, FromRow, Row};
+#[cfg(feature = "sql")]
 pub use crate::sql::prepared::PreparedStatement;
-pub use crate::sql::session::{QueryCancelHandle, Session};
+#[cfg(feature = "sql")]
+pub use crate::sql::session::{
+    CancellationToken, CorruptPage, CorruptionReport, DatabaseStats, DumpOptions, EngineStatus,
+    MaintenanceBudget, MaintenanceReport, PageOwner, QueryCancelHandle, Session, SessionStatus,
+    StatementMetrics, StatementPhase, TransactionInfo,
+};
+pub use crate::storage::incremental_backup::{BackupCursor, IncrementalManifest, ManifestPage};
 pub use crate::storage::pager::DbEncryptionInfo;
-pub use crate::types::{format_date, format_datetime, format_uuid, parse_uuid_string, Value};
+pub use crate::tx::commit_outcome::{CommitOutcome, CommitRef};
+#[cfg(feature = "sql")]
+pub use crate::types::{
+    format_date, format_datetime, format_float, format_uuid, parse_uuid_string, Value,
+};
+pub use crate::wal::archive::ArchiveRestoreResult;
+pub use crate::wal::reader::{UnreadableFrame, WalTail};
 pub use crate::wal::recovery::{RecoveryMode, RecoveryResult, RecoverySkipCode, RecoverySkippedTx};
+pub use crate::wal::writer::WalDurability;
 
+#[cfg(feature = "sql")]
 pub type QueryResult = Vec<Row>;
 
 use std::path::{Path, PathBuf};
 use std::time::{Duration, SystemTime, UNIX_EPOCH};
 
-use crate::btree::ops::BTree;
-use crate::concurrency::LockManager;
-use crate::crypto::kdf;
-use crate::crypto::suite::EncryptionSuite;
-use crate::fts::index::FtsIndex;
-use crate::fts::tokenizer::tokenize_bigram;
-use crate::schema::catalog::SystemCatalog;
-use crate::schema::index::IndexType;
-use crate::sql::ast::Statement;
-use crate::sql::executor::deserialize_row_versioned;
-use crate::sql::session::RuntimeConfig;
-use crate::storage::pager::{read_rekey_marker, rekey_marker_path, unwrap_rekey_old_key, Pager};
-use crate::wal::writer::WalWriter;
+use crate::wal::header::WalIdentity;
 
-const LEGACY_SQL_FTS_TERM_KEY: [u8; 32] = [0x55u8; 32];
+/// When [`Database::transaction_with_retry`] runs a transaction again.
+///
+/// A failed attempt is retried when its error is [`MuroError::Busy`] or
+/// [`MuroError::LockTimeout`]. The wait before retry `n` (1-based) is
+/// `initial_backoff * 2^(n-1)`, capped at `max_backoff`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RetryPolicy {
+    /// Attempts after the first one; `0` never retries.
+    pub max_retries: u32,
+    /// Wait before the first retry.
+    pub initial_backoff: Duration,
+    /// Longest wait between two attempts.
+    pub max_backoff: Duration,
+}
 
-/// Main database handle.
-pub struct Database {
-    session: Session,
-    lock_manager: LockManager,
-    busy_timeout_ms: u64,
-    master_key: Option<MasterKey>,
-    db_path: PathBuf,
-    encryption_suite: EncryptionSuite,
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        RetryPolicy {
+            max_retries: 3,
+            initial_backoff: Duration::from_millis(10),
+            max_backoff: Duration::from_secs(1),
+        }
+    }
 }
 
-/// Read-only database handle for concurrent query workloads.
-pub struct DatabaseReader {
-    session: Session,
-    lock_manager: LockManager,
-    busy_timeout_ms: u64,
+impl RetryPolicy {
+    fn should_retry(&self, error: &MuroError, retries_done: u32) -> bool {
+        retries_done < self.max_retries
+            && matches!(error, MuroError::Busy(_) | MuroError::LockTimeout { .. })
+    }
+
+    fn backoff(&self, retry: u32) -> Duration {
+        self.initial_backoff
+            .saturating_mul(1u32 << retry.min(31))
+            .min(self.max_backoff)
+    }
 }
 
+/// Options for [`Database::open_with_options`].
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
-pub enum DatabaseEncryption {
-    Encrypted,
-    Plaintext,
+pub struct OpenOptions {
+    /// WAL recovery behavior applied before the database is opened.
+    pub recovery_mode: RecoveryMode,
+    /// Page cache capacity in pages (clamped to at least 1).
+    ///
+    /// The cache holds only clean page images; uncommitted transaction pages are
+    /// buffered separately and never evicted.
+    pub page_cache_pages: usize,
+    /// When commits are fsynced; see [`WalDurability`].
+    pub wal_durability: WalDurability,
+    /// Bytes of encrypted WAL frames a commit buffers before writing them
+    /// out; see [`Database::set_wal_write_buffer_bytes`].
+    pub wal_write_buffer_bytes: usize,
+    /// How long opening and every later statement wait for a lock held by
+    /// another handle or process before failing with
+    /// [`MuroError::LockTimeout`]. Zero waits indefinitely; see
+    /// [`Database::set_busy_timeout_ms`].
+    pub busy_timeout: Duration,
+    /// Fail with [`MuroError::AlreadyOpen`] if another handle of the file is
+    /// open in this process, and turn away later opens of it in this process
+    /// until this handle (and a `Session` from
+    /// [`Database::into_session`]) is dropped. Readers from
+    /// [`Database::open_reader`] are still allowed. Off by default, since
+    /// several handles of one file in a process are safe.
+    pub exclusive_in_process: bool,
+}
+
+impl Default for OpenOptions {
+    fn default() -> Self {
+        OpenOptions {
+            recovery_mode: RecoveryMode::Strict,
+            page_cache_pages: crate::storage::pager::DEFAULT_CACHE_CAPACITY,
+            wal_durability: WalDurability::Full,
+            wal_write_buffer_bytes: crate::wal::writer::DEFAULT_WAL_WRITE_BUFFER_BYTES,
+            busy_timeout: Duration::ZERO,
+            exclusive_in_process: false,
+        }
+    }
 }
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
-pub enum SqlStatementClass {
-    ReadOnly,
-    Begin,
-    Commit,
-    Rollback,
-    Write,
+pub enum DatabaseEncryption {
+    Encrypted,
+    Plaintext,
 }
 
 fn wal_path(db_path: &Path) -> PathBuf {
@@ -182,8 +273,7 @@ fn sync_dir(file_path: &Path) {
 /// - After `sync_all()` but before directory fsync: The WAL file contents are
 ///   durable. On most filesystems the directory entry is already updated;
 ///   the directory fsync is a belt-and-suspenders measure.
-fn truncate_wal_durably(wal_path: &Path) -> Result<()> {
-    use crate::wal::{WAL_HEADER_SIZE, WAL_MAGIC, WAL_VERSION};
+fn truncate_wal_durably(wal_path: &Path, identity: &WalIdentity) -> Result<()> {
     use std::io::Write;
 
     let mut wal_file = std::fs::OpenOptions::new()
@@ -191,10 +281,7 @@ fn truncate_wal_durably(wal_path: &Path) -> Result<()> {
         .truncate(true)
         .create(true)
         .open(wal_path)?;
-    let mut header = [0u8; WAL_HEADER_SIZE];
-    header[0..8].copy_from_slice(WAL_MAGIC);
-    header[8..12].copy_from_slice(&WAL_VERSION.to_le_bytes());
-    wal_file.write_all(&header)?;
+    wal_file.write_all(&identity.encode_header())?;
     wal_file.sync_all()?;
 
     // Best-effort directory fsync to persist metadata updates (size/truncate).
@@ -227,1107 +314,3 @@ fn quarantine_wal_durably(wal_path: &Path) -> Result<PathBuf> {
 
     Ok(dest)
 }
-
-fn fts_value_to_text(value: &Value) -> Option<&str> {
-    match value {
-        Value::Varchar(s) => Some(s.as_str()),
-        _ => None,
-    }
-}
-
-fn resolve_missing_fts_term_key(
-    pager: &mut Pager,
-    catalog: &mut SystemCatalog,
-    bootstrap_key: [u8; 32],
-) -> Result<[u8; 32]> {
-    if bootstrap_key == LEGACY_SQL_FTS_TERM_KEY {
-        return Ok(bootstrap_key);
-    }
-
-    let mut legacy_hits = 0u64;
-    let mut bootstrap_hits = 0u64;
-
-    let table_names = catalog.list_tables(pager)?;
-    for table_name in table_names {
-        let Some(table_def) = catalog.get_table(pager, &table_name)? else {
-            continue;
-        };
-        let indexes = catalog.get_indexes_for_table(pager, &table_name)?;
-        for idx in indexes {
-            if idx.index_type != IndexType::Fulltext {
-                continue;
-            }
-            let Some(col_name) = idx.column_names.first() else {
-                continue;
-            };
-            let Some(col_idx) = table_def.column_index(col_name) else {
-                continue;
-            };
-
-            let data_btree = BTree::open(table_def.data_btree_root);
-            let mut tokens_to_probe: Vec<String> = Vec::new();
-            data_btree.scan(pager, |_pk, row| {
-                if tokens_to_probe.len() >= 64 {
-                    return Ok(false);
-                }
-                let values = deserialize_row_versioned(
-                    row,
-                    &table_def.columns,
-                    table_def.row_format_version,
-                )?;
-                let Some(text) = values.get(col_idx).and_then(fts_value_to_text) else {
-                    return Ok(true);
-                };
-                let tokens = tokenize_bigram(text);
-                for token in tokens {
-                    if token.text.is_empty() {
-                        continue;
-                    }
-                    tokens_to_probe.push(token.text);
-                    if tokens_to_probe.len() >= 64 {
-                        break;
-                    }
-                }
-                Ok(tokens_to_probe.len() < 64)
-            })?;
-
-            let fts_legacy = FtsIndex::open(idx.btree_root, LEGACY_SQL_FTS_TERM_KEY);
-            let fts_bootstrap = FtsIndex::open(idx.btree_root, bootstrap_key);
-            for token in tokens_to_probe {
-                if fts_legacy.get_postings(pager, &token)?.df() > 0 {
-                    legacy_hits = legacy_hits.saturating_add(1);
-                    break;
-                }
-                if fts_bootstrap.get_postings(pager, &token)?.df() > 0 {
-                    bootstrap_hits = bootstrap_hits.saturating_add(1);
-                    break;
-                }
-            }
-        }
-    }
-
-    if legacy_hits > 0 && bootstrap_hits == 0 {
-        Ok(LEGACY_SQL_FTS_TERM_KEY)
-    } else if bootstrap_hits > 0 && legacy_hits == 0 {
-        Ok(bootstrap_key)
-    } else if legacy_hits > bootstrap_hits {
-        Ok(LEGACY_SQL_FTS_TERM_KEY)
-    } else {
-        Ok(bootstrap_key)
-    }
-}
-
-fn initialize_fts_term_key(
-    pager: &mut Pager,
-    catalog: Option<&mut SystemCatalog>,
-    missing_meta_key: [u8; 32],
-    persist_missing_meta: bool,
-) -> Result<bool> {
-    let Some(catalog) = catalog else {
-        pager.set_fts_term_key(pager.derive_bootstrap_fts_term_key());
-        return Ok(false);
-    };
-
-    match catalog.get_fts_term_key(pager) {
-        Ok(Some(existing)) => {
-            pager.set_fts_term_key(existing);
-            Ok(false)
-        }
-        Ok(None) => {
-            // Missing metadata means legacy DB; call site decides which key to backfill.
-            let generated = if missing_meta_key == LEGACY_SQL_FTS_TERM_KEY {
-                resolve_missing_fts_term_key(pager, catalog, pager.derive_bootstrap_fts_term_key())?
-            } else {
-                missing_meta_key
-            };
-            pager.set_fts_term_key(generated);
-            if persist_missing_meta {
-                catalog.set_fts_term_key(pager, generated)?;
-                Ok(true)
-            } else {
-                Ok(false)
-            }
-        }
-        // Some tests build DB files through Pager-only flows where catalog_root is unset (0).
-        // For those files, keep FTS usable via in-memory bootstrap without catalog writes.
-        Err(MuroError::InvalidPage) if pager.catalog_root() == 0 => {
-            pager.set_fts_term_key(pager.derive_bootstrap_fts_term_key());
-            Ok(false)
-        }
-        Err(e) => Err(e),
-    }
-}
-
-impl Database {
-    /// Parse and classify SQL for routing between read-only and write paths.
-    pub fn classify_sql(sql: &str) -> Result<SqlStatementClass> {
-        fn classify(stmt: &Statement) -> SqlStatementClass {
-            match stmt {
-                Statement::Select(_)
-                | Statement::SetQuery(_)
-                | Statement::ShowTables
-                | Statement::ShowCreateTable(_)
-                | Statement::Describe(_)
-                | Statement::ShowCheckpointStats
-                | Statement::ShowDatabaseStats => SqlStatementClass::ReadOnly,
-                Statement::Explain(inner) => classify(inner),
-                Statement::Begin => SqlStatementClass::Begin,
-                Statement::Commit => SqlStatementClass::Commit,
-                Statement::Rollback => SqlStatementClass::Rollback,
-                Statement::Savepoint(_)
-                | Statement::RollbackToSavepoint(_)
-                | Statement::ReleaseSavepoint(_)
-                | Statement::SetRuntimeOption(_) => SqlStatementClass::Write,
-                Statement::CreateTable(_)
-                | Statement::CreateIndex(_)
-                | Statement::CreateFulltextIndex(_)
-                | Statement::AnalyzeTable(_)
-                | Statement::DropTable(_)
-                | Statement::DropIndex(_)
-                | Statement::AlterTable(_)
-                | Statement::RenameTable(_)
-                | Statement::Insert(_)
-                | Statement::Update(_)
-                | Statement::Delete(_) => SqlStatementClass::Write,
-            }
-        }
-
-        let stmt = crate::sql::parser::parse_sql(sql).map_err(MuroError::Parse)?;
-        Ok(classify(&stmt))
-    }
-
-    /// Read database file encryption metadata from header.
-    pub fn read_encryption_info(path: &Path) -> Result<DbEncryptionInfo> {
-        Pager::read_encryption_info_from_file(path)
-    }
-
-    /// Detect whether a database file is encrypted.
-    pub fn read_encryption_mode(path: &Path) -> Result<DatabaseEncryption> {
-        let info = Self::read_encryption_info(path)?;
-        Ok(match info.suite {
-            EncryptionSuite::Aes256GcmSiv => DatabaseEncryption::Encrypted,
-            EncryptionSuite::Plaintext => DatabaseEncryption::Plaintext,
-        })
-    }
-
-    /// Inspect WAL consistency without opening a writable SQL session.
-    pub fn inspect_wal(
-        db_path: &Path,
-        wal_path: &Path,
-        password: Option<&str>,
-        mode: RecoveryMode,
-    ) -> Result<RecoveryResult> {
-        let info = Self::read_encryption_info(db_path)?;
-        match info.suite {
-            EncryptionSuite::Aes256GcmSiv => {
-                let password = password.ok_or_else(|| {
-                    MuroError::Encryption(
-                        "password is required for WAL inspection of encrypted database".to_string(),
-                    )
-                })?;
-                let key = kdf::derive_key(password.as_bytes(), &info.salt)?;
-                crate::wal::recovery::inspect_wal(wal_path, &key, mode)
-            }
-            EncryptionSuite::Plaintext => crate::wal::recovery::inspect_wal_with_suite(
-                wal_path,
-                EncryptionSuite::Plaintext,
-                None,
-                mode,
-            ),
-        }
-    }
-
-    /// Create a new database at the given path.
-    pub fn create(path: &Path, master_key: &MasterKey) -> Result<Self> {
-        let mut pager = Pager::create(path, master_key)?;
-        let mut catalog = SystemCatalog::create(&mut pager)?;
-        let bootstrap_fts_key = pager.derive_bootstrap_fts_term_key();
-        initialize_fts_term_key(&mut pager, Some(&mut catalog), bootstrap_fts_key, true)?;
-        pager.set_catalog_root(catalog.root_page_id());
-        pager.flush_meta()?;
-
-        // Directory fsync to persist the newly created DB file metadata
-        sync_dir(path);
-
-        let wal = WalWriter::create(&wal_path(path), master_key)?;
-        let lock_manager = LockManager::new(path)?;
-        let session = Session::new(pager, catalog, wal);
-
-        Ok(Database {
-            session,
-            lock_manager,
-            busy_timeout_ms: 0,
-            master_key: Some(master_key.clone()),
-            db_path: path.to_path_buf(),
-            encryption_suite: EncryptionSuite::Aes256GcmSiv,
-        })
-    }
-
-    pub fn create_plaintext(path: &Path) -> Result<Self> {
-        let mut pager = Pager::create_plaintext(path)?;
-        let mut catalog = SystemCatalog::create(&mut pager)?;
-        let bootstrap_fts_key = pager.derive_bootstrap_fts_term_key();
-        initialize_fts_term_key(&mut pager, Some(&mut catalog), bootstrap_fts_key, true)?;
-        pager.set_catalog_root(catalog.root_page_id());
-        pager.flush_meta()?;
-
-        sync_dir(path);
-
-        let wal = WalWriter::create_plaintext(&wal_path(path))?;
-        let lock_manager = LockManager::new(path)?;
-        let session = Session::new(pager, catalog, wal);
-
-        Ok(Database {
-            session,
-            lock_manager,
-            busy_timeout_ms: 0,
-            master_key: None,
-            db_path: path.to_path_buf(),
-            encryption_suite: EncryptionSuite::Plaintext,
-        })
-    }
-
-    /// Open an existing database.
-    pub fn open(path: &Path, master_key: &MasterKey) -> Result<Self> {
-        Ok(Self::open_with_recovery_mode_and_report(path, master_key, RecoveryMode::Strict)?.0)
-    }
-
-    pub fn open_plaintext(path: &Path) -> Result<Self> {
-        Ok(Self::open_plaintext_with_recovery_mode_and_report(path, RecoveryMode::Strict)?.0)
-    }
-
-    pub fn open_plaintext_with_recovery_mode(
-        path: &Path,
-        recovery_mode: RecoveryMode,
-    ) -> Result<Self> {
-        Ok(Self::open_plaintext_with_recovery_mode_and_report(path, recovery_mode)?.0)
-    }
-
-    /// Open an existing database with configurable WAL recovery behavior.
-    pub fn open_with_recovery_mode(
-        path: &Path,
-        master_key: &MasterKey,
-        recovery_mode: RecoveryMode,
-    ) -> Result<Self> {
-        Ok(Self::open_with_recovery_mode_and_report(path, master_key, recovery_mode)?.0)
-    }
-
-    /// Open an existing database with configurable WAL recovery behavior and return recovery report.
-    pub fn open_with_recovery_mode_and_report(
-        path: &Path,
-        master_key: &MasterKey,
-        recovery_mode: RecoveryMode,
-    ) -> Result<(Self, Option<RecoveryResult>)> {
-        migrate_legacy_sidecar_paths(path);
-        let wp = wal_path(path);
-        let mut recovery_report = None;
-
-        // Run WAL recovery before opening
-        if wp.exists() {
-            let mut report = crate::wal::recovery::recover_with_mode_and_suite(
-                path,
-                &wp,
-                EncryptionSuite::Aes256GcmSiv,
-                Some(master_key),
-                recovery_mode,
-            )?;
-            if recovery_mode == RecoveryMode::Permissive && !report.skipped.is_empty() {
-                let quarantine = quarantine_wal_durably(&wp)?;
-                report.wal_quarantine_path = Some(quarantine.display().to_string());
-            } else {
-                // Truncate WAL after successful recovery
-                truncate_wal_durably(&wp)?;
-            }
-            recovery_report = Some(report);
-        }
-
-        let mut pager = Pager::open(path, master_key)?;
-        let catalog_root = pager.catalog_root();
-        let mut catalog = SystemCatalog::open(catalog_root);
-        let has_uninitialized_catalog = catalog_root == 0 && pager.page_count() == 0;
-        initialize_fts_term_key(
-            &mut pager,
-            if has_uninitialized_catalog {
-                None
-            } else {
-                Some(&mut catalog)
-            },
-            LEGACY_SQL_FTS_TERM_KEY,
-            false,
-        )?;
-        let wal = WalWriter::create(&wp, master_key)?;
-        let lock_manager = LockManager::new(path)?;
-        let session = Session::new(pager, catalog, wal);
-
-        Ok((
-            Database {
-                session,
-                lock_manager,
-                busy_timeout_ms: 0,
-                master_key: Some(master_key.clone()),
-                db_path: path.to_path_buf(),
-                encryption_suite: EncryptionSuite::Aes256GcmSiv,
-            },
-            recovery_report,
-        ))
-    }
-
-    pub fn open_plaintext_with_recovery_mode_and_report(
-        path: &Path,
-        recovery_mode: RecoveryMode,
-    ) -> Result<(Self, Option<RecoveryResult>)> {
-        migrate_legacy_sidecar_paths(path);
-        let wp = wal_path(path);
-        let mut recovery_report = None;
-
-        if wp.exists() {
-            let mut report = crate::wal::recovery::recover_with_mode_and_suite(
-                path,
-                &wp,
-                EncryptionSuite::Plaintext,
-                None,
-                recovery_mode,
-            )?;
-            if recovery_mode == RecoveryMode::Permissive && !report.skipped.is_empty() {
-                let quarantine = quarantine_wal_durably(&wp)?;
-                report.wal_quarantine_path = Some(quarantine.display().to_string());
-            } else {
-                truncate_wal_durably(&wp)?;
-            }
-            recovery_report = Some(report);
-        }
-
-        let mut pager = Pager::open_plaintext(path)?;
-        let catalog_root = pager.catalog_root();
-        let mut catalog = SystemCatalog::open(catalog_root);
-        let has_uninitialized_catalog = catalog_root == 0 && pager.page_count() == 0;
-        initialize_fts_term_key(
-            &mut pager,
-            if has_uninitialized_catalog {
-                None
-            } else {
-                Some(&mut catalog)
-            },
-            LEGACY_SQL_FTS_TERM_KEY,
-            false,
-        )?;
-        let wal = WalWriter::create_plaintext(&wp)?;
-        let lock_manager = LockManager::new(path)?;
-        let session = Session::new(pager, catalog, wal);
-
-        Ok((
-            Database {
-                session,
-                lock_manager,
-                busy_timeout_ms: 0,
-                master_key: None,
-                db_path: path.to_path_buf(),
-                encryption_suite: EncryptionSuite::Plaintext,
-            },
-            recovery_report,
-        ))
-    }
-
-    /// Create a new database with a password.
-    pub fn create_with_password(path: &Path, password: &str) -> Result<Self> {
-        let salt = kdf::generate_salt();
-        let master_key = kdf::derive_key(password.as_bytes(), &salt)?;
-        let mut pager = Pager::create_with_salt(path, &master_key, salt)?;
-        let mut catalog = SystemCatalog::create(&mut pager)?;
-        let bootstrap_fts_key = pager.derive_bootstrap_fts_term_key();
-        initialize_fts_term_key(&mut pager, Some(&mut catalog), bootstrap_fts_key, true)?;
-        pager.set_catalog_root(catalog.root_page_id());
-        pager.flush_meta()?;
-
-        // Directory fsync to persist the newly created DB file metadata
-        sync_dir(path);
-
-        let wal = WalWriter::create(&wal_path(path), &master_key)?;
-        let lock_manager = LockManager::new(path)?;
-        let session = Session::new(pager, catalog, wal);
-
-        Ok(Database {
-            session,
-            lock_manager,
-            busy_timeout_ms: 0,
-            master_key: Some(master_key),
-            db_path: path.to_path_buf(),
-            encryption_suite: EncryptionSuite::Aes256GcmSiv,
-        })
-    }
-
-    /// Open an existing database with a password.
-    ///
-    /// If a `.rekey` marker file exists (from a crashed rekey operation), this
-    /// will attempt to complete the recovery before opening normally.
-    pub fn open_with_password(path: &Path, password: &str) -> Result<Self> {
-        Self::recover_interrupted_rekey(path, password)?;
-        let info = Pager::read_encryption_info_from_file(path)?;
-        if info.suite != EncryptionSuite::Aes256GcmSiv {
-            return Err(crate::error::MuroError::Encryption(format!(
-                "database uses {}; open with plaintext mode",
-                info.suite.as_str()
-            )));
-        }
-        let salt = info.salt;
-        let master_key = kdf::derive_key(password.as_bytes(), &salt)?;
-        Self::open(path, &master_key)
-    }
-
-    /// Open an existing database with a password and configurable recovery behavior.
-    pub fn open_with_password_and_recovery_mode(
-        path: &Path,
-        password: &str,
-        recovery_mode: RecoveryMode,
-    ) -> Result<Self> {
-        Ok(Self::open_with_password_and_recovery_mode_and_report(path, password, recovery_mode)?.0)
-    }
-
-    /// Open an existing database with a password, configurable recovery mode, and return recovery report.
-    pub fn open_with_password_and_recovery_mode_and_report(
-        path: &Path,
-        password: &str,
-        recovery_mode: RecoveryMode,
-    ) -> Result<(Self, Option<RecoveryResult>)> {
-        Self::recover_interrupted_rekey(path, password)?;
-        let info = Pager::read_encryption_info_from_file(path)?;
-        if info.suite != EncryptionSuite::Aes256GcmSiv {
-            return Err(crate::error::MuroError::Encryption(format!(
-                "database uses {}; open with plaintext mode",
-                info.suite.as_str()
-            )));
-        }
-        let salt = info.salt;
-        let master_key = kdf::derive_key(password.as_bytes(), &salt)?;
-        Self::open_with_recovery_mode_and_report(path, &master_key, recovery_mode)
-    }
-
-    /// Recover from a crashed rekey operation.
-    ///
-    /// If a `.rekey` marker file exists, this checks whether the rekey completed
-    /// (header salt matches marker salt) or needs to be re-run.
-    fn recover_interrupted_rekey(path: &Path, password: &str) -> Result<()> {
-        let marker = rekey_marker_path(path);
-        if !marker.exists() {
-            return Ok(());
-        }
-
-        let marker_info = read_rekey_marker(&marker)?;
-        let new_salt = marker_info.new_salt;
-        let new_epoch = marker_info.new_epoch;
-
-        // Read current DB header to check if rekey already completed
-        let info = Pager::read_encryption_info_from_file(path)?;
-        if info.salt == new_salt {
-            // Rekey completed successfully, just remove stale marker
-            let _ = std::fs::remove_file(&marker);
-            return Ok(());
-        }
-
-        // Rekey was interrupted mid-way. We need to complete it.
-        // Derive new key from password + marker's new salt.
-        let new_key = kdf::derive_key(password.as_bytes(), &new_salt)?;
-
-        // Derive old key from wrapped marker payload.
-        let wrapped_old_key = marker_info.wrapped_old_key.ok_or_else(|| {
-            crate::error::MuroError::Execution(
-                "rekey recovery marker is missing wrapped old key; automatic recovery is unavailable".to_string(),
-            )
-        })?;
-        let old_key = unwrap_rekey_old_key(&new_key, new_epoch, &wrapped_old_key)?;
-        let old_epoch = new_epoch.saturating_sub(1);
-
-        // Open the file directly for recovery
-        let old_crypto =
-            crate::crypto::suite::PageCipher::new(EncryptionSuite::Aes256GcmSiv, Some(&old_key))?;
-        let new_crypto =
-            crate::crypto::suite::PageCipher::new(EncryptionSuite::Aes256GcmSiv, Some(&new_key))?;
-
-        let mut file = std::fs::OpenOptions::new()
-            .read(true)
-            .write(true)
-            .open(path)?;
-
-        // Re-read header for page_count
-        use std::io::{Read, Seek, SeekFrom, Write};
-        let mut header = [0u8; 76];
-        file.seek(SeekFrom::Start(0))?;
-        file.read_exact(&mut header)?;
-        let page_count = u64::from_le_bytes(header[36..44].try_into().unwrap());
-
-        let page_size_on_disk =
-            crate::storage::page::PAGE_SIZE + crate::crypto::aead::PageCrypto::overhead();
-
-        for page_id in 0..page_count {
-            let offset = 76 + page_id * page_size_on_disk as u64;
-            file.seek(SeekFrom::Start(offset))?;
-            let mut encrypted = vec![0u8; page_size_on_disk];
-            file.read_exact(&mut encrypted)?;
-
-            // Try decrypting with new key/epoch first (page already re-encrypted)
-            let mut plaintext = [0u8; crate::storage::page::PAGE_SIZE];
-            let decrypt_result =
-                new_crypto.decrypt_into(page_id, new_epoch, &encrypted, &mut plaintext);
-
-            if decrypt_result.is_err() {
-                // Page was not yet re-encrypted; decrypt with old key/epoch
-                let len =
-                    old_crypto.decrypt_into(page_id, old_epoch, &encrypted, &mut plaintext)?;
-                if len != crate::storage::page::PAGE_SIZE {
-                    return Err(crate::error::MuroError::InvalidPage);
-                }
-
-                // Re-encrypt with new key/epoch
-                let mut new_encrypted = vec![0u8; page_size_on_disk];
-                new_crypto.encrypt_into(page_id, new_epoch, &plaintext, &mut new_encrypted)?;
-                file.seek(SeekFrom::Start(offset))?;
-                file.write_all(&new_encrypted)?;
-            }
-        }
-
-        file.sync_data()?;
-
-        // Update header with new salt and epoch
-        header[12..28].copy_from_slice(&new_salt);
-        header[44..52].copy_from_slice(&new_epoch.to_le_bytes());
-        let checksum = crate::wal::record::crc32(&header[0..72]);
-        header[72..76].copy_from_slice(&checksum.to_le_bytes());
-        file.seek(SeekFrom::Start(0))?;
-        file.write_all(&header)?;
-        file.sync_all()?;
-
-        // Remove marker
-        let _ = std::fs::remove_file(&marker);
-
-        Ok(())
-    }
-
-    /// Execute a SQL statement. Returns the result.
-    pub fn execute(&mut self, sql: &str) -> Result<ExecResult> {
-        let timeout_ms = self.busy_timeout_ms;
-        let _guard = if timeout_ms == 0 {
-            self.lock_manager.write_lock()?
-        } else {
-            self.lock_manager
-                .write_lock_with_timeout(Some(Duration::from_millis(timeout_ms)))?
-        };
-        self.session.execute(sql)
-    }
-
-    /// Get a handle that can request cancellation of in-flight statements.
-    pub fn cancel_handle(&self) -> QueryCancelHandle {
-        self.session.cancel_handle()
-    }
-
-    /// Configure per-statement execution timeout in milliseconds.
-    ///
-    /// `0` means no timeout.
-    pub fn set_statement_timeout_ms(&mut self, timeout_ms: u64) {
-        self.session.set_statement_timeout_ms(timeout_ms);
-    }
-
-    /// Current per-statement execution timeout in milliseconds.
-    ///
-    /// `0` means no timeout.
-    pub fn statement_timeout_ms(&self) -> u64 {
-        self.session.statement_timeout_ms()
-    }
-
-    /// Get current session runtime configuration.
-    pub fn runtime_config(&self) -> Result<RuntimeConfig> {
-        let timeout_ms = self.busy_timeout_ms;
-        let _guard = if timeout_ms == 0 {
-            self.lock_manager.read_lock()?
-        } else {
-            self.lock_manager
-                .read_lock_with_timeout(Some(Duration::from_millis(timeout_ms)))?
-        };
-        Ok(self.session.runtime_config())
-    }
-
-    /// Update session runtime configuration.
-    pub fn set_runtime_config(&mut self, config: RuntimeConfig) -> Result<()> {
-        let timeout_ms = self.busy_timeout_ms;
-        let _guard = if timeout_ms == 0 {
-            self.lock_manager.write_lock()?
-        } else {
-            self.lock_manager
-                .write_lock_with_timeout(Some(Duration::from_millis(timeout_ms)))?
-        };
-        self.session.set_runtime_config(config)
-    }
-
-    /// Configure lock wait timeout in milliseconds.
-    ///
-    /// `0` means wait indefinitely (default).
-    pub fn set_busy_timeout_ms(&mut self, timeout_ms: u64) {
-        self.busy_timeout_ms = timeout_ms;
-    }
-
-    /// Current lock wait timeout in milliseconds.
-    ///
-    /// `0` means wait indefinitely.
-    pub fn busy_timeout_ms(&self) -> u64 {
-        self.busy_timeout_ms
-    }
-
-    /// Parse SQL into a reusable prepared statement template.
-    pub fn prepare(&self, sql: &str) -> Result<PreparedStatement> {
-        self.session.prepare(sql)
-    }
-
-    /// Execute a prepared statement with bound values.
-    pub fn execute_prepared(
-        &mut self,
-        prepared: &PreparedStatement,
-        params: &[Value],
-    ) -> Result<ExecResult> {
-        let timeout_ms = self.busy_timeout_ms;
-        let _guard = if timeout_ms == 0 {
-            self.lock_manager.write_lock()?
-        } else {
-            self.lock_manager
-                .write_lock_with_timeout(Some(Duration::from_millis(timeout_ms)))?
-        };
-        self.session.execute_prepared(prepared, params)
-    }
-
-    /// Convenience API: prepare+execute in one call using bound values.
-    pub fn execute_params(&mut self, sql: &str, params: &[Value]) -> Result<ExecResult> {
-        let prepared = self.prepare(sql)?;
-        self.execute_prepared(&prepared, params)
-    }
-
-    /// Execute a read-only SQL query and return rows.
-    ///
-    /// This uses a shared lock so multiple readers can run concurrently.
-    ///
-    /// Note: this method takes `&mut self` because the session may refresh
-    /// pager/catalog state from disk before executing the read.
-    /// Non-read-only SQL returns an execution error.
-    pub fn query(&mut self, sql: &str) -> Result<Vec<Row>> {
-        let timeout_ms = self.busy_timeout_ms;
-        let _guard = if timeout_ms == 0 {
-            self.lock_manager.read_lock()?
-        } else {
-            self.lock_manager
-                .read_lock_with_timeout(Some(Duration::from_millis(timeout_ms)))?
-        };
-        self.session.execute_read_only_query(sql)
-    }
-
-    /// Execute a prepared read-only query and return rows.
-    pub fn query_prepared(
-        &mut self,
-        prepared: &PreparedStatement,
-        params: &[Value],
-    ) -> Result<Vec<Row>> {
-        let timeout_ms = self.busy_timeout_ms;
-        let _guard = if timeout_ms == 0 {
-            self.lock_manager.read_lock()?
-        } else {
-            self.lock_manager
-                .read_lock_with_timeout(Some(Duration::from_millis(timeout_ms)))?
-        };
-        self.session
-            .execute_read_only_prepared_query(prepared, params)
-    }
-
-    /// Convenience API: prepare+query in one call using bound values.
-    pub fn query_params(&mut self, sql: &str, params: &[Value]) -> Result<Vec<Row>> {
-        let prepared = self.prepare(sql)?;
-        self.query_prepared(&prepared, params)
-    }
-
-    /// Open an additional database handle for read-oriented workloads.
-    ///
-    /// This is useful when you want concurrent readers without manually
-    /// re-opening the same path and re-supplying key material.
-    ///
-    /// The returned handle is read-only and does not expose write APIs.
-    pub fn open_reader(&self) -> Result<DatabaseReader> {
-        let path = self.db_path.as_path();
-        let wp = wal_path(path);
-        match self.encryption_suite {
-            EncryptionSuite::Plaintext => {
-                let mut pager = Pager::open_plaintext(path)?;
-                let catalog_root = pager.catalog_root();
-                let mut catalog = SystemCatalog::open(catalog_root);
-                let has_uninitialized_catalog = catalog_root == 0 && pager.page_count() == 0;
-                initialize_fts_term_key(
-                    &mut pager,
-                    if has_uninitialized_catalog {
-                        None
-                    } else {
-                        Some(&mut catalog)
-                    },
-                    LEGACY_SQL_FTS_TERM_KEY,
-                    false,
-                )?;
-                // Reader handles never write WAL; fixed LSN is acceptable.
-                let wal = WalWriter::open_plaintext(&wp, 0)?;
-                let lock_manager = LockManager::new(path)?;
-                let mut session = Session::new(pager, catalog, wal);
-                session.set_statement_timeout_ms(self.session.statement_timeout_ms());
-                Ok(DatabaseReader {
-                    session,
-                    lock_manager,
-                    busy_timeout_ms: self.busy_timeout_ms,
-                })
-            }
-            EncryptionSuite::Aes256GcmSiv => {
-                let master_key = self.master_key.as_ref().ok_or_else(|| {
-                    MuroError::Encryption("missing master key for encrypted reader open".into())
-                })?;
-                let mut pager = Pager::open(path, master_key)?;
-                let catalog_root = pager.catalog_root();
-                let mut catalog = SystemCatalog::open(catalog_root);
-                let has_uninitialized_catalog = catalog_root == 0 && pager.page_count() == 0;
-                initialize_fts_term_key(
-                    &mut pager,
-                    if has_uninitialized_catalog {
-                        None
-                    } else {
-                        Some(&mut catalog)
-                    },
-                    LEGACY_SQL_FTS_TERM_KEY,
-                    false,
-                )?;
-                // Reader handles never write WAL; fixed LSN is acceptable.
-                let wal = WalWriter::open(&wp, master_key, 0)?;
-                let lock_manager = LockManager::new(path)?;
-                let mut session = Session::new(pager, catalog, wal);
-                session.set_statement_timeout_ms(self.session.statement_timeout_ms());
-                Ok(DatabaseReader {
-                    session,
-                    lock_manager,
-                    busy_timeout_ms: self.busy_timeout_ms,
-                })
-            }
-        }
-    }
-
-    /// Re-encrypt the database with a new password-derived key.
-    pub fn rekey_with_password(&mut self, new_password: &str) -> Result<()> {
-        let timeout_ms = self.busy_timeout_ms;
-        let _guard = if timeout_ms == 0 {
-            self.lock_manager.write_lock()?
-        } else {
-            self.lock_manager
-                .write_lock_with_timeout(Some(Duration::from_millis(timeout_ms)))?
-        };
-        self.session.rekey_with_password(new_password)?;
-        let info = Pager::read_encryption_info_from_file(&self.db_path)?;
-        if info.suite == EncryptionSuite::Aes256GcmSiv {
-            let master_key = kdf::derive_key(new_password.as_bytes(), &info.salt)?;
-            self.master_key = Some(master_key);
-        } else {
-            self.master_key = None;
-        }
-        self.encryption_suite = info.suite;
-        Ok(())
-    }
-
-    /// Get the catalog root page ID (needed for internal reopen flows).
-    pub(crate) fn catalog_root(&self) -> u64 {
-        self.session.catalog().root_page_id()
-    }
-
-    /// Flush all data to disk.
-    pub fn flush(&mut self) -> Result<()> {
-        let timeout_ms = self.busy_timeout_ms;
-        let _guard = if timeout_ms == 0 {
-            self.lock_manager.write_lock()?
-        } else {
-            self.lock_manager
-                .write_lock_with_timeout(Some(Duration::from_millis(timeout_ms)))?
-        };
-        let catalog_root = self.session.catalog().root_page_id();
-        let pager = self.session.pager_mut();
-        pager.set_catalog_root(catalog_root);
-        pager.flush_meta()
-    }
-
-    /// Create a consistent backup of the database to `dest`.
-    ///
-    /// Acquires a write lock, checkpoints the WAL (flushing all committed
-    /// data to the main file), then performs a byte-level copy of the
-    /// database file. The backup file is a valid MuroDB database that can
-    /// be opened directly with the same key/password.
-    pub fn backup<P: AsRef<Path>>(&mut self, dest: P) -> Result<()> {
-        let timeout_ms = self.busy_timeout_ms;
-        let _guard = if timeout_ms == 0 {
-            self.lock_manager.write_lock()?
-        } else {
-            self.lock_manager
-                .write_lock_with_timeout(Some(Duration::from_millis(timeout_ms)))?
-        };
-        // Checkpoint WAL so all committed data is in the data file.
-        self.session.try_checkpoint_truncate_once()?;
-        // Copy the data file bytes.
-        self.session.pager_mut().backup_to_file(dest.as_ref())
-    }
-
-    /// Create a `Session` that supports BEGIN/COMMIT/ROLLBACK.
-    ///
-    /// This consumes the Database and returns a Session. The Session owns the
-    /// pager, catalog, and WAL writer, and manages explicit transaction state.
-    pub fn into_session(self) -> Session {
-        self.session
-    }
-}
-
-impl DatabaseReader {
-    /// Get a handle that can request cancellation of in-flight statements.
-    pub fn cancel_handle(&self) -> QueryCancelHandle {
-        self.session.cancel_handle()
-    }
-
-    /// Configure lock wait timeout in milliseconds.
-    ///
-    /// `0` means wait indefinitely (default).
-    pub fn set_busy_timeout_ms(&mut self, timeout_ms: u64) {
-        self.busy_timeout_ms = timeout_ms;
-    }
-
-    /// Current lock wait timeout in milliseconds.
-    ///
-    /// `0` means wait indefinitely.
-    pub fn busy_timeout_ms(&self) -> u64 {
-        self.busy_timeout_ms
-    }
-
-    /// Configure per-statement execution timeout in milliseconds.
-    ///
-    /// `0` means no timeout.
-    pub fn set_statement_timeout_ms(&mut self, timeout_ms: u64) {
-        self.session.set_statement_timeout_ms(timeout_ms);
-    }
-
-    /// Current per-statement execution timeout in milliseconds.
-    ///
-    /// `0` means no timeout.
-    pub fn statement_timeout_ms(&self) -> u64 {
-        self.session.statement_timeout_ms()
-    }
-
-    /// Parse SQL into a reusable prepared statement template.
-    pub fn prepare(&self, sql: &str) -> Result<PreparedStatement> {
-        self.session.prepare(sql)
-    }
-
-    /// Execute a read-only SQL query and return rows.
-    pub fn query(&mut self, sql: &str) -> Result<Vec<Row>> {
-        let timeout_ms = self.busy_timeout_ms;
-        let _guard = if timeout_ms == 0 {
-            self.lock_manager.read_lock()?
-        } else {
-            self.lock_manager
-                .read_lock_with_timeout(Some(Duration::from_millis(timeout_ms)))?
-        };
-        self.session.execute_read_only_query(sql)
-    }
-
-    /// Execute a prepared read-only query and return rows.
-    pub fn query_prepared(
-        &mut self,
-        prepared: &PreparedStatement,
-        params: &[Value],
-    ) -> Result<Vec<Row>> {
-        let timeout_ms = self.busy_timeout_ms;
-        let _guard = if timeout_ms == 0 {
-            self.lock_manager.read_lock()?
-        } else {
-            self.lock_manager
-                .read_lock_with_timeout(Some(Duration::from_millis(timeout_ms)))?
-        };
-        self.session
-            .execute_read_only_prepared_query(prepared, params)
-    }
-
-    /// Convenience API: prepare+query in one call using bound values.
-    pub fn query_params(&mut self, sql: &str, params: &[Value]) -> Result<Vec<Row>> {
-        let prepared = self.prepare(sql)?;
-        self.query_prepared(&prepared, params)
-    }
-}
-
-#[cfg(test)]
-mod tests {
-    use super::wal_path;
-    use super::Database;
-    use crate::wal::reader::WalReader;
-    use crate::wal::record::WalRecord;
-    use crate::wal::writer::WalWriter;
-    use std::sync::mpsc;
-    use std::thread;
-    use std::time::Duration;
-    use tempfile::TempDir;
-
-    #[test]
-    fn flush_waits_while_read_lock_is_held() {
-        let dir = TempDir::new().unwrap();
-        let db_path = dir.path().join("flush_lock.db");
-
-        let mut db1 = Database::create_plaintext(&db_path).unwrap();
-        db1.execute("CREATE TABLE t (id BIGINT PRIMARY KEY)")
-            .unwrap();
-        db1.execute("INSERT INTO t VALUES (1)").unwrap();
-
-        let db2 = Database::open_plaintext(&db_path).unwrap();
-        let read_guard = db2.lock_manager.read_lock().unwrap();
-
-        let (tx, rx) = mpsc::channel();
-        let handle = thread::spawn(move || {
-            let mut db = db1;
-            db.flush().unwrap();
-            tx.send(()).unwrap();
-        });
-
-        thread::sleep(Duration::from_millis(100));
-        assert!(
-            rx.try_recv().is_err(),
-            "flush completed even though another handle held a read lock"
-        );
-
-        drop(read_guard);
-        rx.recv_timeout(Duration::from_secs(2))
-            .expect("flush should complete after read lock is released");
-        handle.join().unwrap();
-    }
-
-    #[test]
-    fn flush_respects_busy_timeout() {
-        let dir = TempDir::new().unwrap();
-        let db_path = dir.path().join("flush_timeout.db");
-
-        let mut db1 = Database::create_plaintext(&db_path).unwrap();
-        db1.execute("CREATE TABLE t (id BIGINT PRIMARY KEY)")
-            .unwrap();
-        db1.execute("INSERT INTO t VALUES (1)").unwrap();
-        db1.set_busy_timeout_ms(20);
-
-        let db2 = Database::open_plaintext(&db_path).unwrap();
-        let read_guard = db2.lock_manager.read_lock().unwrap();
-
-        let err = db1
-            .flush()
-            .expect_err("flush should time out while read lock is held");
-        assert!(matches!(
-            err,
-            crate::MuroError::LockTimeout {
-                mode: "exclusive",
-                ..
-            }
-        ));
-
-        drop(read_guard);
-    }
-
-    #[test]
-    fn open_reader_plaintext_can_query_same_db() {
-        let dir = TempDir::new().unwrap();
-        let db_path = dir.path().join("reader_plaintext.db");
-
-        let mut db = Database::create_plaintext(&db_path).unwrap();
-        db.execute("CREATE TABLE t (id BIGINT PRIMARY KEY, name VARCHAR)")
-            .unwrap();
-        db.execute("INSERT INTO t VALUES (1, 'alice')").unwrap();
-
-        let mut reader = db.open_reader().unwrap();
-        let rows = reader
-            .query("SELECT name FROM t WHERE id = 1")
-            .expect("reader query should succeed");
-        assert_eq!(rows.len(), 1);
-        assert_eq!(
-            rows[0].get("name"),
-            Some(&crate::types::Value::Varchar("alice".to_string()))
-        );
-    }
-
-    #[test]
-    fn open_reader_works_after_rekey_with_password() {
-        let dir = TempDir::new().unwrap();
-        let db_path = dir.path().join("reader_rekey.db");
-
-        let mut db = Database::create_with_password(&db_path, "old-pass").unwrap();
-        db.execute("CREATE TABLE t (id BIGINT PRIMARY KEY)")
-            .unwrap();
-        db.execute("INSERT INTO t VALUES (1)").unwrap();
-        db.rekey_with_password("new-pass").unwrap();
-
-        let mut reader = db.open_reader().unwrap();
-        let rows = reader
-            .query("SELECT id FROM t")
-            .expect("reader query after rekey should succeed");
-        assert_eq!(rows.len(), 1);
-        assert_eq!(rows[0].get("id"), Some(&crate::types::Value::Integer(1)));
-    }
-
-    #[test]
-    fn open_reader_does_not_truncate_existing_wal() {
-        let dir = TempDir::new().unwrap();
-        let db_path = dir.path().join("reader_wal_preserve.db");
-
-        let db = Database::create_plaintext(&db_path).unwrap();
-        let wp = wal_path(&db_path);
-
-        {
-            let mut wal = WalWriter::open_plaintext(&wp, 0).unwrap();
-            wal.append(&WalRecord::Begin { txid: 42 }).unwrap();
-            wal.sync().unwrap();
-        }
-        let size_before = std::fs::metadata(&wp).unwrap().len();
-
-        let _reader = db.open_reader().unwrap();
-
-        let size_after = std::fs::metadata(&wp).unwrap().len();
-        assert_eq!(size_after, size_before, "open_reader must not truncate WAL");
-
-        let mut wal_reader = WalReader::open_plaintext(&wp).unwrap();
-        let records = wal_reader.read_all().unwrap();
-        assert_eq!(records.len(), 1);
-    }
-
-    #[test]
-    fn runtime_config_api_updates_checkpoint_policy() {
-        let dir = TempDir::new().unwrap();
-        let db_path = dir.path().join("runtime_config_api.db");
-
-        let mut db = Database::create_plaintext(&db_path).unwrap();
-        db.set_runtime_config(crate::sql::session::RuntimeConfig {
-            checkpoint_tx_threshold: 7,
-            checkpoint_wal_bytes_threshold: 4096,
-            checkpoint_interval_ms: 500,
-        })
-        .unwrap();
-
-        let cfg = db.runtime_config().unwrap();
-        assert_eq!(cfg.checkpoint_tx_threshold, 7);
-        assert_eq!(cfg.checkpoint_wal_bytes_threshold, 4096);
-        assert_eq!(cfg.checkpoint_interval_ms, 500);
-    }
-
-    #[test]
-    fn statement_timeout_api_roundtrip() {
-        let dir = TempDir::new().unwrap();
-        let db_path = dir.path().join("statement_timeout_api.db");
-
-        let mut db = Database::create_plaintext(&db_path).unwrap();
-        assert_eq!(db.statement_timeout_ms(), 0);
-        db.set_statement_timeout_ms(123);
-        assert_eq!(db.statement_timeout_ms(), 123);
-    }
-
-    #[test]
-    fn open_reader_inherits_statement_timeout() {
-        let dir = TempDir::new().unwrap();
-        let db_path = dir.path().join("reader_statement_timeout.db");
-
-        let mut db = Database::create_plaintext(&db_path).unwrap();
-        db.set_statement_timeout_ms(77);
-
-        let reader = db.open_reader().unwrap();
-        assert_eq!(reader.statement_timeout_ms(), 77);
-    }
-}
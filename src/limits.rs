@@ -0,0 +1,152 @@
+//! Size limits of a database, and the error raised when a statement or a
+//! write goes past one of them.
+use std::fmt;
+
+use crate::btree::node::{MAX_KEY_LEN, MAX_LEAF_CELL_PAYLOAD};
+use crate::error::MuroError;
+use crate::storage::page::PAGE_SIZE;
+
+/// Longest table, column or index name, in UTF-8 bytes.
+pub const MAX_IDENTIFIER_BYTES: usize = 255;
+
+/// Most values in a literal `IN (...)` list.
+pub const MAX_IN_LIST_ITEMS: usize = 100_000;
+
+/// Longest SQL text accepted by `execute`, `query`, `prepare` or
+/// `execute_batch`, in bytes.
+pub const MAX_SQL_BYTES: usize = 16 * 1024 * 1024;
+
+/// Bytes of a row before its column data: the u16 column count.
+const ROW_HEADER_BYTES: usize = 2;
+
+/// Most columns in a table, including a hidden `_rowid`: the row header and
+/// NULL bitmap of a row whose columns are all NULL must fit in a leaf cell
+/// beside the longest key.
+pub const MAX_COLUMNS_PER_TABLE: usize =
+    (MAX_LEAF_CELL_PAYLOAD - 2 - MAX_KEY_LEN - ROW_HEADER_BYTES) * 8;
+
+/// Limits of a database; see [`Database::limits`](crate::Database::limits).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Limits {
+    pub page_size: usize,
+    /// Most columns in a table. `CREATE TABLE` and `ALTER TABLE ... ADD
+    /// COLUMN` fail past it.
+    pub max_columns_per_table: usize,
+    /// Longest primary key or index key, in encoded bytes. An index key is
+    /// the indexed values followed by the primary key. Writes fail past it.
+    pub max_key_bytes: usize,
+    /// Longest row, key included, kept inline in a leaf page. Longer rows
+    /// move their column data to overflow pages; this is not an error.
+    pub max_row_inline_bytes: usize,
+    /// Longest table, column or index name, in UTF-8 bytes. DDL fails past it.
+    pub max_identifier_bytes: usize,
+    /// Most values in a literal `IN (...)` list. Parsing fails past it.
+    pub max_in_list_items: usize,
+    /// Longest SQL text, in bytes. Parsing fails past it.
+    pub max_sql_bytes: usize,
+}
+
+impl Limits {
+    /// The limits of databases created by this build.
+    pub const fn current() -> Self {
+        Limits {
+            page_size: PAGE_SIZE,
+            max_columns_per_table: MAX_COLUMNS_PER_TABLE,
+            max_key_bytes: MAX_KEY_LEN,
+            // The cell stores a u16 key length, the key and the row.
+            max_row_inline_bytes: MAX_LEAF_CELL_PAYLOAD - 2,
+            max_identifier_bytes: MAX_IDENTIFIER_BYTES,
+            max_in_list_items: MAX_IN_LIST_ITEMS,
+            max_sql_bytes: MAX_SQL_BYTES,
+        }
+    }
+}
+
+/// A limit enforced with [`MuroError::LimitExceeded`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Limit {
+    ColumnsPerTable,
+    KeyBytes,
+    IdentifierBytes,
+    InListItems,
+    SqlBytes,
+}
+
+impl Limit {
+    /// The largest allowed value.
+    pub const fn max(self) -> usize {
+        match self {
+            Limit::ColumnsPerTable => MAX_COLUMNS_PER_TABLE,
+            Limit::KeyBytes => MAX_KEY_LEN,
+            Limit::IdentifierBytes => MAX_IDENTIFIER_BYTES,
+            Limit::InListItems => MAX_IN_LIST_ITEMS,
+            Limit::SqlBytes => MAX_SQL_BYTES,
+        }
+    }
+
+    /// The name of the [`Limits`] field holding this limit.
+    pub const fn name(self) -> &'static str {
+        match self {
+            Limit::ColumnsPerTable => "max_columns_per_table",
+            Limit::KeyBytes => "max_key_bytes",
+            Limit::IdentifierBytes => "max_identifier_bytes",
+            Limit::InListItems => "max_in_list_items",
+            Limit::SqlBytes => "max_sql_bytes",
+        }
+    }
+
+    /// `Err(LimitExceeded)` when `value` is past this limit.
+    pub(crate) fn check(self, value: usize) -> Result<(), MuroError> {
+        if value > self.max() {
+            return Err(self.exceeded(value));
+        }
+        Ok(())
+    }
+
+    pub(crate) fn exceeded(self, value: usize) -> MuroError {
+        MuroError::LimitExceeded {
+            limit: self,
+            value: value as u64,
+            max: self.max() as u64,
+        }
+    }
+}
+
+impl fmt::Display for Limit {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::btree::node::needs_overflow;
+
+    #[test]
+    fn test_limit_values() {
+        let limits = Limits::current();
+        assert_eq!(limits.page_size, 4096);
+        assert_eq!(limits.max_key_bytes, 1762);
+        assert_eq!(limits.max_columns_per_table, 18456);
+        assert_eq!(limits.max_row_inline_bytes, 4071);
+    }
+
+    #[test]
+    fn test_row_inline_boundary() {
+        let max = Limits::current().max_row_inline_bytes;
+        let key = [7u8; 8];
+        assert!(!needs_overflow(&key, &vec![0u8; max - key.len()]));
+        assert!(needs_overflow(&key, &vec![0u8; max - key.len() + 1]));
+    }
+
+    #[test]
+    fn test_check() {
+        assert!(Limit::InListItems.check(MAX_IN_LIST_ITEMS).is_ok());
+        let err = Limit::InListItems.check(MAX_IN_LIST_ITEMS + 1).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "Limit exceeded: max_in_list_items is 100000, got 100001"
+        );
+    }
+}
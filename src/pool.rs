@@ -0,0 +1,260 @@
+//! [`DatabasePool`]: one writer [`Database`] and a fixed set of
+//! [`DatabaseReader`]s over the same file, handed out as owned guards.
+//!
+//! Every handle has its own pager, page cache, catalog and statistics; they
+//! share only the file lock, so readers run their queries side by side while
+//! the writer waits for them, as between handles opened separately. Each
+//! statement on a reader first picks up what the writer committed, so a
+//! reader sees every commit that finished before its statement began.
+//! Commits a deferred [`WalDurability`](crate::WalDurability) still holds
+//! back become visible once they are synced.
+
+use std::ops::{Deref, DerefMut};
+use std::path::Path;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use parking_lot::{Condvar, Mutex};
+
+use crate::crypto::aead::MasterKey;
+use crate::error::{MuroError, Result};
+use crate::{Database, DatabaseReader, OpenOptions};
+
+/// Shape of a [`DatabasePool`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PoolConfig {
+    /// Read-only handles opened next to the writer (at least one).
+    pub readers: usize,
+    /// How long [`DatabasePool::read`] and [`DatabasePool::write`] wait for a
+    /// free handle before failing with [`MuroError::LockTimeout`]. `None`
+    /// waits indefinitely.
+    pub checkout_timeout: Option<Duration>,
+    /// Options the writer is opened with. Readers take its page cache size,
+    /// busy timeout and statement timeout.
+    pub open_options: OpenOptions,
+}
+
+impl Default for PoolConfig {
+    fn default() -> Self {
+        Self {
+            readers: 4,
+            checkout_timeout: None,
+            open_options: OpenOptions::default(),
+        }
+    }
+}
+
+/// Handles waiting to be checked out.
+struct Idle<T> {
+    handles: Mutex<Vec<T>>,
+    returned: Condvar,
+}
+
+impl<T> Idle<T> {
+    fn new(handles: Vec<T>) -> Self {
+        Self {
+            handles: Mutex::new(handles),
+            returned: Condvar::new(),
+        }
+    }
+
+    fn take(&self, timeout: Option<Duration>, mode: &'static str) -> Result<T> {
+        let deadline = timeout.map(|t| Instant::now() + t);
+        let mut handles = self.handles.lock();
+        loop {
+            if let Some(handle) = handles.pop() {
+                return Ok(handle);
+            }
+            match deadline {
+                None => self.returned.wait(&mut handles),
+                Some(deadline) => {
+                    if self.returned.wait_until(&mut handles, deadline).timed_out()
+                        && handles.is_empty()
+                    {
+                        return Err(MuroError::LockTimeout {
+                            mode,
+                            timeout_ms: timeout.map_or(0, |t| t.as_millis() as u64),
+                        });
+                    }
+                }
+            }
+        }
+    }
+
+    fn put(&self, handle: T) {
+        self.handles.lock().push(handle);
+        self.returned.notify_one();
+    }
+}
+
+struct PoolShared {
+    writer: Idle<Database>,
+    readers: Idle<DatabaseReader>,
+    checkout_timeout: Option<Duration>,
+}
+
+/// One writer and a fixed number of readers over a database file, shared
+/// by cloning.
+///
+/// [`read`](Self::read) and [`write`](Self::write) check out a handle for
+/// as long as the returned guard lives. Guards own their handle and are
+/// `Send`, so they can move to another thread or across an `.await`.
+///
+/// ```
+/// use murodb::{DatabasePool, PoolConfig};
+///
+/// let dir = tempfile::TempDir::new().unwrap();
+/// let pool = DatabasePool::create_plaintext(&dir.path().join("app.db"), PoolConfig::default())
+///     .unwrap();
+/// pool.write()
+///     .unwrap()
+///     .execute("CREATE TABLE t (id BIGINT PRIMARY KEY)")
+///     .unwrap();
+/// pool.write().unwrap().execute("INSERT INTO t VALUES (1)").unwrap();
+///
+/// let worker = {
+///     let pool = pool.clone();
+///     std::thread::spawn(move || pool.read().unwrap().query("SELECT * FROM t").unwrap().len())
+/// };
+/// assert_eq!(worker.join().unwrap(), 1);
+/// ```
+#[derive(Clone)]
+pub struct DatabasePool {
+    shared: Arc<PoolShared>,
+}
+
+impl DatabasePool {
+    /// Open an encrypted database as a pool.
+    pub fn open(path: &Path, master_key: &MasterKey, config: PoolConfig) -> Result<Self> {
+        Self::with_writer(
+            Database::open_with_options(path, master_key, config.open_options)?,
+            config,
+        )
+    }
+
+    /// Open a plaintext database as a pool.
+    pub fn open_plaintext(path: &Path, config: PoolConfig) -> Result<Self> {
+        Self::with_writer(
+            Database::open_plaintext_with_options(path, config.open_options)?,
+            config,
+        )
+    }
+
+    /// Create an encrypted database and open it as a pool.
+    pub fn create(path: &Path, master_key: &MasterKey, config: PoolConfig) -> Result<Self> {
+        Database::create(path, master_key)?;
+        Self::open(path, master_key, config)
+    }
+
+    /// Create a plaintext database and open it as a pool.
+    pub fn create_plaintext(path: &Path, config: PoolConfig) -> Result<Self> {
+        Database::create_plaintext(path)?;
+        Self::open_plaintext(path, config)
+    }
+
+    /// Build a pool around `writer`, opening the readers from it.
+    pub fn with_writer(writer: Database, config: PoolConfig) -> Result<Self> {
+        if writer.is_read_only() {
+            return Err(MuroError::ReadOnly);
+        }
+        let readers = (0..config.readers.max(1))
+            .map(|_| writer.open_reader())
+            .collect::<Result<Vec<_>>>()?;
+        Ok(Self {
+            shared: Arc::new(PoolShared {
+                writer: Idle::new(vec![writer]),
+                readers: Idle::new(readers),
+                checkout_timeout: config.checkout_timeout,
+            }),
+        })
+    }
+
+    /// Check out a reader, waiting while all of them are in use.
+    pub fn read(&self) -> Result<ReadHandle> {
+        let reader = self
+            .shared
+            .readers
+            .take(self.shared.checkout_timeout, "pool reader")?;
+        Ok(ReadHandle {
+            reader: Some(reader),
+            shared: Arc::clone(&self.shared),
+        })
+    }
+
+    /// Check out the writer, waiting while it is in use.
+    pub fn write(&self) -> Result<WriteHandle> {
+        let db = self
+            .shared
+            .writer
+            .take(self.shared.checkout_timeout, "pool writer")?;
+        Ok(WriteHandle {
+            db: Some(db),
+            shared: Arc::clone(&self.shared),
+        })
+    }
+
+    /// Readers not checked out at the moment.
+    pub fn idle_readers(&self) -> usize {
+        self.shared.readers.handles.lock().len()
+    }
+}
+
+/// A reader checked out of a [`DatabasePool`]; dereferences to the
+/// [`DatabaseReader`] and goes back to the pool when dropped.
+pub struct ReadHandle {
+    reader: Option<DatabaseReader>,
+    shared: Arc<PoolShared>,
+}
+
+impl Deref for ReadHandle {
+    type Target = DatabaseReader;
+
+    fn deref(&self) -> &DatabaseReader {
+        self.reader.as_ref().expect("reader present until drop")
+    }
+}
+
+impl DerefMut for ReadHandle {
+    fn deref_mut(&mut self) -> &mut DatabaseReader {
+        self.reader.as_mut().expect("reader present until drop")
+    }
+}
+
+impl Drop for ReadHandle {
+    fn drop(&mut self) {
+        if let Some(reader) = self.reader.take() {
+            self.shared.readers.put(reader);
+        }
+    }
+}
+
+/// The writer checked out of a [`DatabasePool`]; dereferences to the
+/// [`Database`] and goes back to the pool when dropped. A transaction left
+/// open is rolled back first, so the next holder starts clean.
+pub struct WriteHandle {
+    db: Option<Database>,
+    shared: Arc<PoolShared>,
+}
+
+impl Deref for WriteHandle {
+    type Target = Database;
+
+    fn deref(&self) -> &Database {
+        self.db.as_ref().expect("writer present until drop")
+    }
+}
+
+impl DerefMut for WriteHandle {
+    fn deref_mut(&mut self) -> &mut Database {
+        self.db.as_mut().expect("writer present until drop")
+    }
+}
+
+impl Drop for WriteHandle {
+    fn drop(&mut self) {
+        if let Some(mut db) = self.db.take() {
+            db.rollback_open_transaction();
+            self.shared.writer.put(db);
+        }
+    }
+}
@@ -3,16 +3,34 @@
 /// The catalog is stored as a B-tree with well-known keys:
 ///   "table:<name>" -> serialized TableDef
 ///   "index:<name>" -> serialized IndexDef
+///   "plan_baseline:<hash>" -> serialized PlanBaseline
+///   "meta:catalog_version" -> u32 LE catalog format version (see `schema::migrate`)
+///   "temp:<owner>:table:<name>", "temp:<owner>:index:<name>" -> temporary
+///     tables and their indexes, seen only through a catalog whose temporary
+///     owner is `<owner>` (see [`SystemCatalog::set_temp_owner`])
 ///
 /// The catalog B-tree root is stored at a well-known page.
-use crate::btree::ops::BTree;
+use crate::btree::ops::{BTree, DEFAULT_FILL_FACTOR};
+use crate::crypto::hmac_util::TermKey;
 use crate::error::{MuroError, Result};
+use crate::limits::Limit;
 use crate::schema::column::ColumnDef;
-use crate::schema::index::IndexDef;
+use crate::schema::identifier::{collision_error, fold_identifier, folded_collision};
+use crate::schema::index::{IndexBuildProgress, IndexDef};
+use crate::schema::migrate::{CATALOG_VERSION, UNVERSIONED_CATALOG_VERSION};
+use crate::schema::plan_baseline::{fnv1a64, PlanBaseline};
 use crate::storage::page::PageId;
 use crate::storage::page_store::PageStore;
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicU64, Ordering};
 const META_FTS_TERM_KEY: &[u8] = b"meta:fts_term_key";
+const META_CATALOG_VERSION: &[u8] = b"meta:catalog_version";
 const FK_LAYOUT_V2_TAG: u8 = 0xF1;
+const COLUMN_STATS_TAG: u8 = 0xC1;
+const ROW_COUNT_TAG: u8 = 0xA1;
+const FILL_FACTOR_TAG: u8 = 0xA2;
+const INDEX_KEY_FORMAT_TAG: u8 = 0xA3;
+const TEMP_PREFIX: &str = "temp:";
 
 fn serialize_fk_action(action: &ForeignKeyAction) -> u8 {
     match action {
@@ -47,6 +65,27 @@ pub struct ForeignKeyDef {
     pub on_update: ForeignKeyAction,
 }
 
+/// Per-column statistics captured by ANALYZE TABLE.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ColumnStats {
+    pub column: String,
+    /// Distinct non-NULL values: exact for small tables, a HyperLogLog
+    /// estimate otherwise.
+    pub distinct_count: u64,
+    pub null_count: u64,
+    /// Smallest non-NULL value of an integer or temporal column.
+    pub min: Option<i64>,
+    /// Largest non-NULL value of an integer or temporal column.
+    pub max: Option<i64>,
+}
+
+/// [`TableDef::index_key_format`] of tables from before catalog version 3:
+/// a non-unique index entry is the index key followed by the primary key.
+pub const INDEX_KEY_FORMAT_CONCAT: u8 = 0;
+/// [`TableDef::index_key_format`] of current tables: a non-unique index
+/// entry is the index key byte-stuffed and terminated, then the primary key.
+pub const INDEX_KEY_FORMAT_TERMINATED: u8 = 1;
+
 /// Table definition.
 #[derive(Debug, Clone)]
 pub struct TableDef {
@@ -61,6 +100,22 @@ pub struct TableDef {
     /// Last analyzed approximate row count (0 means unknown / not analyzed).
     pub stats_row_count: u64,
     pub foreign_keys: Vec<ForeignKeyDef>,
+    /// Per-column statistics from the last ANALYZE TABLE (empty = not analyzed).
+    pub column_stats: Vec<ColumnStats>,
+    /// Exact row count, kept current by every statement that adds or removes
+    /// rows. `None` for tables created before it was tracked, until ANALYZE
+    /// TABLE counts them.
+    pub row_count: Option<u64>,
+    /// Percent of each data leaf filled by bulk loads and ascending inserts
+    /// (`WITH (fill_factor = N)`).
+    pub fill_factor: u8,
+    /// Layout of the entries of the table's non-unique indexes:
+    /// [`INDEX_KEY_FORMAT_CONCAT`] or [`INDEX_KEY_FORMAT_TERMINATED`].
+    pub index_key_format: u8,
+    /// Encoded fields past the ones above, written by a newer version.
+    /// Kept verbatim and written back after the known fields, so rewriting
+    /// the definition does not drop them.
+    pub trailing_fields: Vec<u8>,
 }
 
 impl TableDef {
@@ -128,6 +183,42 @@ impl TableDef {
             buf.push(serialize_fk_action(&fk.on_delete));
             buf.push(serialize_fk_action(&fk.on_update));
         }
+        // column_stats (optional tail, backward compatible)
+        if !self.column_stats.is_empty() {
+            buf.push(COLUMN_STATS_TAG);
+            buf.extend_from_slice(&(self.column_stats.len() as u16).to_le_bytes());
+            for cs in &self.column_stats {
+                let b = cs.column.as_bytes();
+                buf.extend_from_slice(&(b.len() as u16).to_le_bytes());
+                buf.extend_from_slice(b);
+                buf.extend_from_slice(&cs.distinct_count.to_le_bytes());
+                buf.extend_from_slice(&cs.null_count.to_le_bytes());
+                match (cs.min, cs.max) {
+                    (Some(min), Some(max)) => {
+                        buf.push(1);
+                        buf.extend_from_slice(&min.to_le_bytes());
+                        buf.extend_from_slice(&max.to_le_bytes());
+                    }
+                    _ => buf.push(0),
+                }
+            }
+        }
+        // row_count (optional tail, backward compatible)
+        if let Some(row_count) = self.row_count {
+            buf.push(ROW_COUNT_TAG);
+            buf.extend_from_slice(&row_count.to_le_bytes());
+        }
+        // fill_factor (optional tail, backward compatible)
+        if self.fill_factor != DEFAULT_FILL_FACTOR {
+            buf.push(FILL_FACTOR_TAG);
+            buf.push(self.fill_factor);
+        }
+        // index_key_format (optional tail, absent for the concatenated layout)
+        if self.index_key_format != INDEX_KEY_FORMAT_CONCAT {
+            buf.push(INDEX_KEY_FORMAT_TAG);
+            buf.push(self.index_key_format);
+        }
+        buf.extend_from_slice(&self.trailing_fields);
         buf
     }
 
@@ -351,6 +442,88 @@ impl TableDef {
             Vec::new()
         };
 
+        // column_stats (optional tail)
+        let mut column_stats = Vec::new();
+        if data.len() > offset && data[offset] == COLUMN_STATS_TAG {
+            offset += 1;
+            if data.len() < offset + 2 {
+                return None;
+            }
+            let count = u16::from_le_bytes(data[offset..offset + 2].try_into().unwrap()) as usize;
+            offset += 2;
+            for _ in 0..count {
+                if data.len() < offset + 2 {
+                    return None;
+                }
+                let len = u16::from_le_bytes(data[offset..offset + 2].try_into().unwrap()) as usize;
+                offset += 2;
+                if data.len() < offset + len + 17 {
+                    return None;
+                }
+                let column = String::from_utf8(data[offset..offset + len].to_vec()).ok()?;
+                offset += len;
+                let distinct_count =
+                    u64::from_le_bytes(data[offset..offset + 8].try_into().unwrap());
+                offset += 8;
+                let null_count = u64::from_le_bytes(data[offset..offset + 8].try_into().unwrap());
+                offset += 8;
+                let has_bounds = data[offset] != 0;
+                offset += 1;
+                let (min, max) = if has_bounds {
+                    if data.len() < offset + 16 {
+                        return None;
+                    }
+                    let min = i64::from_le_bytes(data[offset..offset + 8].try_into().unwrap());
+                    let max = i64::from_le_bytes(data[offset + 8..offset + 16].try_into().unwrap());
+                    offset += 16;
+                    (Some(min), Some(max))
+                } else {
+                    (None, None)
+                };
+                column_stats.push(ColumnStats {
+                    column,
+                    distinct_count,
+                    null_count,
+                    min,
+                    max,
+                });
+            }
+        }
+
+        // row_count (optional tail)
+        let row_count = if data.len() > offset && data[offset] == ROW_COUNT_TAG {
+            offset += 1;
+            if data.len() < offset + 8 {
+                return None;
+            }
+            let row_count = u64::from_le_bytes(data[offset..offset + 8].try_into().unwrap());
+            offset += 8;
+            Some(row_count)
+        } else {
+            None
+        };
+
+        // fill_factor (optional tail)
+        let fill_factor = if data.len() > offset && data[offset] == FILL_FACTOR_TAG {
+            let fill_factor = *data.get(offset + 1)?;
+            offset += 2;
+            fill_factor
+        } else {
+            DEFAULT_FILL_FACTOR
+        };
+
+        // index_key_format (optional tail)
+        let index_key_format = if data.len() > offset && data[offset] == INDEX_KEY_FORMAT_TAG {
+            let format = *data.get(offset + 1)?;
+            offset += 2;
+            format
+        } else {
+            INDEX_KEY_FORMAT_CONCAT
+        };
+
+        // Anything left was appended by a newer version.
+        let trailing_fields = data[offset..].to_vec();
+
         Some(TableDef {
             name,
             columns,
@@ -360,9 +533,38 @@ impl TableDef {
             row_format_version,
             stats_row_count,
             foreign_keys,
+            column_stats,
+            row_count,
+            fill_factor,
+            index_key_format,
+            trailing_fields,
         })
     }
 
+    /// Account for `delta` rows added (positive) or removed (negative).
+    /// Tables without a tracked count keep none.
+    pub fn adjust_row_count(&mut self, delta: i64) {
+        if let Some(count) = self.row_count.as_mut() {
+            *count = count.saturating_add_signed(delta);
+        }
+    }
+
+    /// The tracked row count, or the last ANALYZE TABLE estimate for tables
+    /// that predate tracking.
+    pub fn approximate_row_count(&self) -> u64 {
+        self.row_count.unwrap_or(self.stats_row_count)
+    }
+
+    /// Statistics of a column from the last ANALYZE TABLE.
+    pub fn column_stats(&self, name: &str) -> Option<&ColumnStats> {
+        self.column_stats.iter().find(|cs| cs.column == name)
+    }
+
+    /// Drop the ANALYZE stats of a column whose definition changed.
+    pub fn forget_column_stats(&mut self, name: &str) {
+        self.column_stats.retain(|cs| cs.column != name);
+    }
+
     /// Find column index by name.
     pub fn column_index(&self, name: &str) -> Option<usize> {
         self.columns.iter().position(|c| c.name == name)
@@ -389,32 +591,171 @@ impl TableDef {
     pub fn is_composite_pk(&self) -> bool {
         self.pk_columns.len() > 1
     }
+
+    /// Fingerprint of what the table's rows mean: column definitions, primary
+    /// key, and row format. Unchanged by data, statistics, and index changes.
+    pub fn semantics_version(&self) -> u64 {
+        let mut bytes = vec![self.row_format_version];
+        for col in &self.columns {
+            let col_bytes = col.serialize();
+            bytes.extend_from_slice(&(col_bytes.len() as u16).to_le_bytes());
+            bytes.extend_from_slice(&col_bytes);
+        }
+        for pk in &self.pk_columns {
+            bytes.extend_from_slice(&(pk.len() as u16).to_le_bytes());
+            bytes.extend_from_slice(pk.as_bytes());
+        }
+        fnv1a64(&bytes)
+    }
+}
+
+/// Source of catalog generations, shared by every catalog in the process so
+/// a reopened catalog never reuses a generation seen before.
+static NEXT_CATALOG_GENERATION: AtomicU64 = AtomicU64::new(1);
+
+fn next_catalog_generation() -> u64 {
+    NEXT_CATALOG_GENERATION.fetch_add(1, Ordering::Relaxed)
+}
+
+/// Outcome of creating a catalog entry whose name may already be taken.
+///
+/// The lookup and the insert happen in one catalog call, so `IF NOT EXISTS`
+/// is decided by the same check that guards the creation.
+#[derive(Debug)]
+pub enum CreateOutcome<T> {
+    Created(T),
+    /// An entry of this name exists; the payload is its name, which differs
+    /// from the requested one when the two only fold to the same identifier.
+    AlreadyExists(String),
 }
 
 /// System catalog managing table and index definitions.
 pub struct SystemCatalog {
     catalog_btree: BTree,
+    generation: u64,
+    /// Namespace of the session's temporary tables, resolved before the
+    /// permanent ones.
+    temp_owner: Option<String>,
 }
 
 impl SystemCatalog {
-    /// Create a new system catalog with a fresh B-tree.
+    /// Create a new system catalog with a fresh B-tree, stamped with the
+    /// current catalog format version.
     pub fn create(pager: &mut impl PageStore) -> Result<Self> {
         let catalog_btree = BTree::create(pager)?;
-        Ok(SystemCatalog { catalog_btree })
+        let mut catalog = SystemCatalog {
+            catalog_btree,
+            generation: next_catalog_generation(),
+            temp_owner: None,
+        };
+        catalog.set_catalog_version(pager, CATALOG_VERSION)?;
+        Ok(catalog)
     }
 
     /// Open an existing system catalog.
     pub fn open(catalog_root: PageId) -> Self {
         SystemCatalog {
             catalog_btree: BTree::open(catalog_root),
+            generation: next_catalog_generation(),
+            temp_owner: None,
+        }
+    }
+
+    /// Resolve table and index names against the temporary tables of
+    /// `owner` before the permanent ones, and create temporary tables
+    /// there. With `None` only permanent tables are visible.
+    pub fn set_temp_owner(&mut self, owner: Option<String>) {
+        self.temp_owner = owner;
+    }
+
+    pub fn temp_owner(&self) -> Option<&str> {
+        self.temp_owner.as_deref()
+    }
+
+    /// Key prefixes of tables and indexes: the temporary namespace's when
+    /// `temp` and there is one, the permanent ones otherwise.
+    fn prefixes(&self, temp: bool) -> (String, String) {
+        match &self.temp_owner {
+            Some(owner) if temp => (
+                format!("{}{}:table:", TEMP_PREFIX, owner),
+                format!("{}{}:index:", TEMP_PREFIX, owner),
+            ),
+            _ => ("table:".to_string(), "index:".to_string()),
         }
     }
 
+    /// The temporary table or index (`kind`) `name`, if the namespace has one.
+    fn search_temp(
+        &self,
+        pager: &mut impl PageStore,
+        kind: &str,
+        name: &str,
+    ) -> Result<Option<Vec<u8>>> {
+        let Some(owner) = &self.temp_owner else {
+            return Ok(None);
+        };
+        let key = format!("{}{}:{}:{}", TEMP_PREFIX, owner, kind, name);
+        self.catalog_btree.search(pager, key.as_bytes())
+    }
+
+    /// Whether `name` is a temporary table of this catalog's owner.
+    pub fn is_temp_table(&self, pager: &mut impl PageStore, name: &str) -> Result<bool> {
+        Ok(self.search_temp(pager, "table", name)?.is_some())
+    }
+
+    /// Names of the temporary tables of this catalog's owner.
+    pub fn list_temp_tables(&self, pager: &mut impl PageStore) -> Result<Vec<String>> {
+        if self.temp_owner.is_none() {
+            return Ok(Vec::new());
+        }
+        let (prefix, _) = self.prefixes(true);
+        let mut tables = Vec::new();
+        self.catalog_btree
+            .scan_from(pager, prefix.as_bytes(), |k, _v| {
+                let Some(name) = k.strip_prefix(prefix.as_bytes()) else {
+                    return Ok(false);
+                };
+                tables.push(String::from_utf8_lossy(name).into_owned());
+                Ok(true)
+            })?;
+        Ok(tables)
+    }
+
+    /// Every owner with temporary tables or indexes in the catalog, live
+    /// sessions and crashed ones alike.
+    pub fn temp_owners(&self, pager: &mut impl PageStore) -> Result<Vec<String>> {
+        let mut owners: Vec<String> = Vec::new();
+        self.catalog_btree
+            .scan_from(pager, TEMP_PREFIX.as_bytes(), |k, _v| {
+                let Some(rest) = k.strip_prefix(TEMP_PREFIX.as_bytes()) else {
+                    return Ok(false);
+                };
+                let owner = rest.split(|&b| b == b':').next().unwrap_or_default();
+                let owner = String::from_utf8_lossy(owner);
+                if owners.last().map(String::as_str) != Some(&*owner) {
+                    owners.push(owner.into_owned());
+                }
+                Ok(true)
+            })?;
+        Ok(owners)
+    }
+
+    /// In-memory counter that changes on every DDL and every (re)open, so
+    /// anything derived from the schema can tell it is stale. Not persisted.
+    pub fn generation(&self) -> u64 {
+        self.generation
+    }
+
+    /// Mark the schema as changed.
+    pub fn bump_generation(&mut self) {
+        self.generation = next_catalog_generation();
+    }
+
     pub fn root_page_id(&self) -> PageId {
         self.catalog_btree.root_page_id()
     }
 
-    pub fn get_fts_term_key(&self, pager: &mut impl PageStore) -> Result<Option<[u8; 32]>> {
+    pub fn get_fts_term_key(&self, pager: &mut impl PageStore) -> Result<Option<TermKey>> {
         match self.catalog_btree.search(pager, META_FTS_TERM_KEY)? {
             Some(v) => {
                 if v.len() != 32 {
@@ -424,14 +765,44 @@ impl SystemCatalog {
                 }
                 let mut key = [0u8; 32];
                 key.copy_from_slice(&v);
-                Ok(Some(key))
+                Ok(Some(TermKey::new(key)))
             }
             None => Ok(None),
         }
     }
 
-    pub fn set_fts_term_key(&mut self, pager: &mut impl PageStore, key: [u8; 32]) -> Result<()> {
-        self.catalog_btree.insert(pager, META_FTS_TERM_KEY, &key)?;
+    pub fn set_fts_term_key(&mut self, pager: &mut impl PageStore, key: &TermKey) -> Result<()> {
+        self.catalog_btree
+            .insert(pager, META_FTS_TERM_KEY, key.as_bytes())?;
+        Ok(())
+    }
+
+    /// Catalog format version; catalogs written before versioning are
+    /// [`UNVERSIONED_CATALOG_VERSION`].
+    pub fn catalog_version(&self, pager: &mut impl PageStore) -> Result<u32> {
+        match self.catalog_btree.search(pager, META_CATALOG_VERSION)? {
+            Some(v) => {
+                let bytes: [u8; 4] = v.as_slice().try_into().map_err(|_| {
+                    MuroError::Corruption(
+                        "catalog meta:catalog_version has invalid length".to_string(),
+                    )
+                })?;
+                Ok(u32::from_le_bytes(bytes))
+            }
+            None => Ok(UNVERSIONED_CATALOG_VERSION),
+        }
+    }
+
+    pub fn set_catalog_version(&mut self, pager: &mut impl PageStore, version: u32) -> Result<()> {
+        self.catalog_btree
+            .insert(pager, META_CATALOG_VERSION, &version.to_le_bytes())
+    }
+
+    /// Drop the version key, making the catalog look like one written
+    /// before versioning. For building old-format fixtures in tests.
+    #[cfg(any(test, feature = "test-utils"))]
+    pub fn clear_catalog_version(&mut self, pager: &mut impl PageStore) -> Result<()> {
+        self.catalog_btree.delete(pager, META_CATALOG_VERSION)?;
         Ok(())
     }
 
@@ -447,13 +818,66 @@ impl SystemCatalog {
         name: &str,
         columns: Vec<ColumnDef>,
     ) -> Result<TableDef> {
-        // Check if table already exists
-        let key = format!("table:{}", name);
-        if self.catalog_btree.search(pager, key.as_bytes())?.is_some() {
-            return Err(MuroError::Schema(format!(
-                "Table '{}' already exists",
-                name
-            )));
+        match self.try_create_table(pager, name, columns)? {
+            CreateOutcome::Created(table_def) => Ok(table_def),
+            CreateOutcome::AlreadyExists(other) => Err(collision_error("Table", name, &other)),
+        }
+    }
+
+    /// Create a table unless one of the same name, also under identifier
+    /// folding, exists. Nothing is allocated when it does. A temporary
+    /// table of the owner takes its name as well.
+    pub fn try_create_table(
+        &mut self,
+        pager: &mut impl PageStore,
+        name: &str,
+        columns: Vec<ColumnDef>,
+    ) -> Result<CreateOutcome<TableDef>> {
+        if self.temp_owner.is_some() {
+            let (temp_prefix, _) = self.prefixes(true);
+            if let Some(other) = self.folded_name_collision(pager, &temp_prefix, name, None)? {
+                return Ok(CreateOutcome::AlreadyExists(other));
+            }
+        }
+        self.create_table_entry(pager, false, name, columns)
+    }
+
+    /// Create a temporary table in the owner's namespace unless it has one
+    /// of the same name. It may shadow a permanent table.
+    pub fn try_create_temp_table(
+        &mut self,
+        pager: &mut impl PageStore,
+        name: &str,
+        columns: Vec<ColumnDef>,
+    ) -> Result<CreateOutcome<TableDef>> {
+        if self.temp_owner.is_none() {
+            return Err(MuroError::Internal(
+                "temporary table created without a temporary namespace".into(),
+            ));
+        }
+        self.create_table_entry(pager, true, name, columns)
+    }
+
+    fn create_table_entry(
+        &mut self,
+        pager: &mut impl PageStore,
+        temp: bool,
+        name: &str,
+        columns: Vec<ColumnDef>,
+    ) -> Result<CreateOutcome<TableDef>> {
+        let (prefix, _) = self.prefixes(temp);
+        let key = format!("{}{}", prefix, name);
+        Limit::IdentifierBytes.check(name.len())?;
+        if let Some(other) = self.folded_name_collision(pager, &prefix, name, None)? {
+            return Ok(CreateOutcome::AlreadyExists(other));
+        }
+        let mut folded_names = HashMap::with_capacity(columns.len());
+        for col in &columns {
+            Limit::IdentifierBytes.check(col.name.len())?;
+            if let Some(other) = folded_names.insert(fold_identifier(&col.name), col.name.as_str())
+            {
+                return Err(collision_error("Column", &col.name, other));
+            }
         }
 
         // Find PK columns; if none, inject a hidden _rowid column
@@ -473,6 +897,7 @@ impl SystemCatalog {
             cols.extend(columns);
             (cols, vec!["_rowid".to_string()])
         };
+        Limit::ColumnsPerTable.check(columns.len())?;
 
         // Allocate a B-tree for the table data
         let data_btree = BTree::create(pager)?;
@@ -487,6 +912,11 @@ impl SystemCatalog {
             row_format_version: 1,
             stats_row_count: 0,
             foreign_keys: Vec::new(),
+            column_stats: Vec::new(),
+            row_count: Some(0),
+            fill_factor: DEFAULT_FILL_FACTOR,
+            index_key_format: INDEX_KEY_FORMAT_TERMINATED,
+            trailing_fields: Vec::new(),
         };
 
         // Store in catalog
@@ -494,11 +924,14 @@ impl SystemCatalog {
         self.catalog_btree
             .insert(pager, key.as_bytes(), &serialized)?;
 
-        Ok(table_def)
+        Ok(CreateOutcome::Created(table_def))
     }
 
-    /// Get a table definition by name.
+    /// Get a table definition by name, the owner's temporary table first.
     pub fn get_table(&self, pager: &mut impl PageStore, name: &str) -> Result<Option<TableDef>> {
+        if let Some(data) = self.search_temp(pager, "table", name)? {
+            return Ok(TableDef::deserialize(&data));
+        }
         let key = format!("table:{}", name);
         match self.catalog_btree.search(pager, key.as_bytes())? {
             Some(data) => Ok(TableDef::deserialize(&data)),
@@ -508,7 +941,8 @@ impl SystemCatalog {
 
     /// Update a table definition.
     pub fn update_table(&mut self, pager: &mut impl PageStore, table_def: &TableDef) -> Result<()> {
-        let key = format!("table:{}", table_def.name);
+        let (prefix, _) = self.prefixes(self.is_temp_table(pager, &table_def.name)?);
+        let key = format!("{}{}", prefix, table_def.name);
         let serialized = table_def.serialize();
         self.catalog_btree
             .insert(pager, key.as_bytes(), &serialized)?;
@@ -521,23 +955,40 @@ impl SystemCatalog {
         pager: &mut impl PageStore,
         index_def: IndexDef,
     ) -> Result<IndexDef> {
-        let key = format!("index:{}", index_def.name);
-        if self.catalog_btree.search(pager, key.as_bytes())?.is_some() {
-            return Err(MuroError::Schema(format!(
-                "Index '{}' already exists",
-                index_def.name
-            )));
+        let name = index_def.name.clone();
+        match self.try_create_index(pager, index_def)? {
+            CreateOutcome::Created(index_def) => Ok(index_def),
+            CreateOutcome::AlreadyExists(other) => Err(collision_error("Index", &name, &other)),
+        }
+    }
+
+    /// Store an index definition unless an index of the same name, also
+    /// under identifier folding, exists. Indexes of a temporary table live
+    /// in its namespace, whose names are separate from the permanent ones.
+    pub fn try_create_index(
+        &mut self,
+        pager: &mut impl PageStore,
+        index_def: IndexDef,
+    ) -> Result<CreateOutcome<IndexDef>> {
+        let (_, prefix) = self.prefixes(self.is_temp_table(pager, &index_def.table_name)?);
+        let key = format!("{}{}", prefix, index_def.name);
+        if let Some(other) = self.folded_name_collision(pager, &prefix, &index_def.name, None)? {
+            return Ok(CreateOutcome::AlreadyExists(other));
         }
         let serialized = index_def.serialize();
         self.catalog_btree
             .insert(pager, key.as_bytes(), &serialized)?;
-        Ok(index_def)
+        Ok(CreateOutcome::Created(index_def))
     }
 
-    /// Get an index definition by name.
+    /// Get an index definition by name, the owner's temporary index first.
     pub fn get_index(&self, pager: &mut impl PageStore, name: &str) -> Result<Option<IndexDef>> {
         let key = format!("index:{}", name);
-        match self.catalog_btree.search(pager, key.as_bytes())? {
+        let data = match self.search_temp(pager, "index", name)? {
+            Some(data) => Some(data),
+            None => self.catalog_btree.search(pager, key.as_bytes())?,
+        };
+        match data {
             Some(data) => Ok(IndexDef::deserialize(&data).map(|(idx, _)| idx)),
             None => Ok(None),
         }
@@ -545,32 +996,83 @@ impl SystemCatalog {
 
     /// Update an existing index definition.
     pub fn update_index(&mut self, pager: &mut impl PageStore, index_def: &IndexDef) -> Result<()> {
-        let key = format!("index:{}", index_def.name);
+        let (_, prefix) = self.prefixes(self.is_temp_table(pager, &index_def.table_name)?);
+        let key = format!("{}{}", prefix, index_def.name);
         let serialized = index_def.serialize();
         self.catalog_btree
             .insert(pager, key.as_bytes(), &serialized)?;
         Ok(())
     }
 
+    /// Progress of the batched build of index `name`, if one is running or
+    /// was interrupted.
+    pub fn get_index_build(
+        &self,
+        pager: &mut impl PageStore,
+        name: &str,
+    ) -> Result<Option<IndexBuildProgress>> {
+        let key = format!("index_build:{}", name);
+        match self.catalog_btree.search(pager, key.as_bytes())? {
+            Some(data) => IndexBuildProgress::deserialize(&data)
+                .map(Some)
+                .ok_or_else(|| {
+                    MuroError::Corruption(format!("invalid build progress of index '{}'", name))
+                }),
+            None => Ok(None),
+        }
+    }
+
+    pub fn put_index_build(
+        &mut self,
+        pager: &mut impl PageStore,
+        name: &str,
+        progress: &IndexBuildProgress,
+    ) -> Result<()> {
+        let key = format!("index_build:{}", name);
+        self.catalog_btree
+            .insert(pager, key.as_bytes(), &progress.serialize())
+    }
+
+    pub fn delete_index_build(&mut self, pager: &mut impl PageStore, name: &str) -> Result<()> {
+        let key = format!("index_build:{}", name);
+        self.catalog_btree.delete(pager, key.as_bytes())?;
+        Ok(())
+    }
+
+    /// Names of the indexes whose build has not finished.
+    pub fn list_index_builds(&self, pager: &mut impl PageStore) -> Result<Vec<String>> {
+        let mut names = Vec::new();
+        self.catalog_btree
+            .scan_from(pager, b"index_build:", |k, _v| {
+                let Some(name) = k.strip_prefix(b"index_build:") else {
+                    return Ok(false);
+                };
+                names.push(String::from_utf8_lossy(name).into_owned());
+                Ok(true)
+            })?;
+        Ok(names)
+    }
+
     /// Get all indexes for a table.
     pub fn get_indexes_for_table(
         &self,
         pager: &mut impl PageStore,
         table_name: &str,
     ) -> Result<Vec<IndexDef>> {
+        let (_, prefix) = self.prefixes(self.is_temp_table(pager, table_name)?);
         let mut indexes = Vec::new();
-        self.catalog_btree.scan(pager, |k, v| {
-            if let Ok(key_str) = std::str::from_utf8(k) {
-                if key_str.starts_with("index:") {
-                    if let Some((idx, _)) = IndexDef::deserialize(v) {
-                        if idx.table_name == table_name {
-                            indexes.push(idx);
-                        }
+        self.catalog_btree
+            .scan_from(pager, prefix.as_bytes(), |k, v| {
+                if !k.starts_with(prefix.as_bytes()) {
+                    return Ok(false);
+                }
+                if let Some((idx, _)) = IndexDef::deserialize(v) {
+                    if idx.table_name == table_name {
+                        indexes.push(idx);
                     }
                 }
-            }
-            Ok(true)
-        })?;
+                Ok(true)
+            })?;
         Ok(indexes)
     }
 
@@ -582,34 +1084,38 @@ impl SystemCatalog {
         old_name: &str,
         new_name: &str,
     ) -> Result<()> {
+        Limit::IdentifierBytes.check(new_name.len())?;
         // Check old table exists
         let mut table_def = self
             .get_table(pager, old_name)?
             .ok_or_else(|| MuroError::Schema(format!("Table '{}' does not exist", old_name)))?;
+        // A temporary table is renamed within its namespace
+        let temp = self.is_temp_table(pager, old_name)?;
+        let (table_prefix, index_prefix) = self.prefixes(temp);
 
-        // Check new name doesn't exist
-        if self.get_table(pager, new_name)?.is_some() {
-            return Err(MuroError::Schema(format!(
-                "Table '{}' already exists",
-                new_name
-            )));
+        // Check new name doesn't exist; the table may change its own spelling
+        if let Some(other) =
+            self.folded_name_collision(pager, &table_prefix, new_name, Some(old_name))?
+        {
+            return Err(collision_error("Table", new_name, &other));
         }
 
+        // Update all indexes for this table while the old name still resolves
+        let indexes = self.get_indexes_for_table(pager, old_name)?;
+
         // Delete old key
-        let old_key = format!("table:{}", old_name);
+        let old_key = format!("{}{}", table_prefix, old_name);
         self.catalog_btree.delete(pager, old_key.as_bytes())?;
 
         // Update name and insert with new key
         table_def.name = new_name.to_string();
-        let new_key = format!("table:{}", new_name);
+        let new_key = format!("{}{}", table_prefix, new_name);
         let serialized = table_def.serialize();
         self.catalog_btree
             .insert(pager, new_key.as_bytes(), &serialized)?;
 
-        // Update all indexes for this table
-        let indexes = self.get_indexes_for_table(pager, old_name)?;
         for mut idx in indexes {
-            let idx_key = format!("index:{}", idx.name);
+            let idx_key = format!("{}{}", index_prefix, idx.name);
             idx.table_name = new_name.to_string();
             let idx_serialized = idx.serialize();
             self.catalog_btree
@@ -621,27 +1127,94 @@ impl SystemCatalog {
 
     /// Delete a table from the catalog.
     pub fn delete_table(&mut self, pager: &mut impl PageStore, name: &str) -> Result<()> {
-        let key = format!("table:{}", name);
-        if self.catalog_btree.search(pager, key.as_bytes())?.is_none() {
-            return Err(MuroError::Schema(format!(
+        match self.try_delete_table(pager, name)? {
+            Some(_) => Ok(()),
+            None => Err(MuroError::Schema(format!(
                 "Table '{}' does not exist",
                 name
-            )));
+            ))),
         }
+    }
+
+    /// Delete a table's entry, the owner's temporary table first, and
+    /// return its definition, or `None` when there is no such table. Its
+    /// indexes are left to the caller.
+    pub fn try_delete_table(
+        &mut self,
+        pager: &mut impl PageStore,
+        name: &str,
+    ) -> Result<Option<TableDef>> {
+        let (prefix, _) = self.prefixes(self.is_temp_table(pager, name)?);
+        let Some(table_def) = self.get_table(pager, name)? else {
+            return Ok(None);
+        };
+        let key = format!("{}{}", prefix, name);
         self.catalog_btree.delete(pager, key.as_bytes())?;
-        Ok(())
+        Ok(Some(table_def))
     }
 
     /// Delete an index from the catalog.
     pub fn delete_index(&mut self, pager: &mut impl PageStore, name: &str) -> Result<()> {
-        let key = format!("index:{}", name);
-        if self.catalog_btree.search(pager, key.as_bytes())?.is_none() {
-            return Err(MuroError::Schema(format!(
+        match self.try_delete_index(pager, name)? {
+            Some(_) => Ok(()),
+            None => Err(MuroError::Schema(format!(
                 "Index '{}' does not exist",
                 name
-            )));
+            ))),
         }
+    }
+
+    /// Delete an index's entry, the owner's temporary index first, and
+    /// return its definition, or `None` when there is no such index.
+    pub fn try_delete_index(
+        &mut self,
+        pager: &mut impl PageStore,
+        name: &str,
+    ) -> Result<Option<IndexDef>> {
+        let (_, prefix) = self.prefixes(self.search_temp(pager, "index", name)?.is_some());
+        let Some(index_def) = self.get_index(pager, name)? else {
+            return Ok(None);
+        };
+        let key = format!("{}{}", prefix, name);
         self.catalog_btree.delete(pager, key.as_bytes())?;
+        if index_def.building {
+            self.delete_index_build(pager, name)?;
+        }
+        Ok(Some(index_def))
+    }
+
+    /// Rename an index: delete the old key and store the definition, with
+    /// its new name, under the new one. Its tree is untouched. An index
+    /// still being built keeps its name until the build finishes.
+    pub fn rename_index(
+        &mut self,
+        pager: &mut impl PageStore,
+        old_name: &str,
+        new_name: &str,
+    ) -> Result<()> {
+        Limit::IdentifierBytes.check(new_name.len())?;
+        let mut index_def = self
+            .get_index(pager, old_name)?
+            .ok_or_else(|| MuroError::Schema(format!("Index '{}' does not exist", old_name)))?;
+        if index_def.building {
+            return Err(MuroError::Schema(format!(
+                "Cannot rename index '{}' while it is being built",
+                old_name
+            )));
+        }
+        let (_, prefix) = self.prefixes(self.is_temp_table(pager, &index_def.table_name)?);
+        // The index may change its own spelling
+        if let Some(other) = self.folded_name_collision(pager, &prefix, new_name, Some(old_name))? {
+            return Err(collision_error("Index", new_name, &other));
+        }
+
+        let old_key = format!("{}{}", prefix, index_def.name);
+        self.catalog_btree.delete(pager, old_key.as_bytes())?;
+        index_def.name = new_name.to_string();
+        let new_key = format!("{}{}", prefix, new_name);
+        self.catalog_btree
+            .insert(pager, new_key.as_bytes(), &index_def.serialize())?;
+        self.bump_generation();
         Ok(())
     }
 
@@ -651,14 +1224,89 @@ impl SystemCatalog {
         pager: &mut impl PageStore,
         table_name: &str,
     ) -> Result<()> {
+        let (_, prefix) = self.prefixes(self.is_temp_table(pager, table_name)?);
         let indexes = self.get_indexes_for_table(pager, table_name)?;
         for idx in indexes {
-            let key = format!("index:{}", idx.name);
+            let key = format!("{}{}", prefix, idx.name);
             self.catalog_btree.delete(pager, key.as_bytes())?;
+            if idx.building {
+                self.delete_index_build(pager, &idx.name)?;
+            }
         }
         Ok(())
     }
 
+    /// Store `baseline`, replacing any baseline of the same shape.
+    pub fn put_plan_baseline(
+        &mut self,
+        pager: &mut impl PageStore,
+        baseline: &PlanBaseline,
+    ) -> Result<()> {
+        let key = PlanBaseline::catalog_key(&baseline.shape);
+        self.catalog_btree
+            .insert(pager, key.as_bytes(), &baseline.serialize())?;
+        Ok(())
+    }
+
+    /// Remove the baseline of `shape`. Returns whether one existed.
+    pub fn delete_plan_baseline(
+        &mut self,
+        pager: &mut impl PageStore,
+        shape: &str,
+    ) -> Result<bool> {
+        let key = PlanBaseline::catalog_key(shape);
+        match self.catalog_btree.search(pager, key.as_bytes())? {
+            Some(data) if PlanBaseline::deserialize(&data).is_some_and(|b| b.shape == shape) => {
+                self.catalog_btree.delete(pager, key.as_bytes())?;
+                Ok(true)
+            }
+            _ => Ok(false),
+        }
+    }
+
+    /// All stored plan baselines.
+    pub fn list_plan_baselines(&self, pager: &mut impl PageStore) -> Result<Vec<PlanBaseline>> {
+        let mut baselines = Vec::new();
+        self.catalog_btree.scan(pager, |k, v| {
+            if k.starts_with(b"plan_baseline:") {
+                if let Some(baseline) = PlanBaseline::deserialize(v) {
+                    baselines.push(baseline);
+                }
+            }
+            Ok(true)
+        })?;
+        Ok(baselines)
+    }
+
+    /// An existing catalog name under `prefix` (`"table:"`, `"index:"`) that
+    /// folds to the same identifier as `name`, ignoring `skip`. Exact matches
+    /// are found by key; folded ones need a scan of the prefix.
+    fn folded_name_collision(
+        &self,
+        pager: &mut impl PageStore,
+        prefix: &str,
+        name: &str,
+        skip: Option<&str>,
+    ) -> Result<Option<String>> {
+        let key = format!("{}{}", prefix, name);
+        if skip != Some(name) && self.catalog_btree.search(pager, key.as_bytes())?.is_some() {
+            return Ok(Some(name.to_string()));
+        }
+        let mut names = Vec::new();
+        self.catalog_btree.scan(pager, |k, _v| {
+            if let Some(existing) = std::str::from_utf8(k)
+                .ok()
+                .and_then(|key| key.strip_prefix(prefix))
+            {
+                if skip != Some(existing) {
+                    names.push(existing.to_string());
+                }
+            }
+            Ok(true)
+        })?;
+        Ok(folded_collision(name, names.iter().map(|n| n.as_str())).map(str::to_string))
+    }
+
     /// List all table names.
     pub fn list_tables(&self, pager: &mut impl PageStore) -> Result<Vec<String>> {
         let mut tables = Vec::new();
@@ -678,6 +1326,7 @@ impl SystemCatalog {
 mod tests {
     use super::*;
     use crate::crypto::aead::MasterKey;
+    use crate::fts::query::FtsStopFallback;
     use crate::schema::index::IndexType;
     use crate::storage::pager::Pager;
     use crate::types::DataType;
@@ -702,6 +1351,11 @@ mod tests {
             row_format_version: 1,
             stats_row_count: 0,
             foreign_keys: Vec::new(),
+            column_stats: Vec::new(),
+            row_count: None,
+            fill_factor: DEFAULT_FILL_FACTOR,
+            index_key_format: INDEX_KEY_FORMAT_TERMINATED,
+            trailing_fields: Vec::new(),
         };
 
         let bytes = table.serialize();
@@ -713,6 +1367,168 @@ mod tests {
         assert_eq!(table2.row_format_version, 1);
     }
 
+    #[test]
+    fn test_table_def_column_stats_roundtrip_and_legacy_tail() {
+        let mut table = TableDef {
+            name: "t".to_string(),
+            columns: vec![
+                ColumnDef::new("id", DataType::BigInt).primary_key(),
+                ColumnDef::new("s", DataType::Varchar(None)),
+            ],
+            pk_columns: vec!["id".to_string()],
+            data_btree_root: 7,
+            next_rowid: 0,
+            row_format_version: 1,
+            stats_row_count: 10,
+            foreign_keys: Vec::new(),
+            column_stats: vec![
+                ColumnStats {
+                    column: "id".to_string(),
+                    distinct_count: 10,
+                    null_count: 0,
+                    min: Some(1),
+                    max: Some(10),
+                },
+                ColumnStats {
+                    column: "s".to_string(),
+                    distinct_count: 3,
+                    null_count: 4,
+                    min: None,
+                    max: None,
+                },
+            ],
+            row_count: None,
+            fill_factor: DEFAULT_FILL_FACTOR,
+            index_key_format: INDEX_KEY_FORMAT_TERMINATED,
+            trailing_fields: Vec::new(),
+        };
+        let decoded = TableDef::deserialize(&table.serialize()).unwrap();
+        assert_eq!(decoded.column_stats, table.column_stats);
+        assert_eq!(decoded.column_stats("s").unwrap().null_count, 4);
+
+        // Definitions written before the extension have no column stats.
+        let stats = std::mem::take(&mut table.column_stats);
+        let decoded = TableDef::deserialize(&table.serialize()).unwrap();
+        assert!(decoded.column_stats.is_empty());
+        assert_eq!(decoded.stats_row_count, 10);
+        table.column_stats = stats;
+        let mut bytes = table.serialize();
+        bytes.truncate(bytes.len() - 3);
+        assert!(TableDef::deserialize(&bytes).is_none());
+
+        // The row count follows the column stats, with or without them.
+        table.row_count = Some(12);
+        assert_eq!(
+            TableDef::deserialize(&table.serialize()).unwrap().row_count,
+            Some(12)
+        );
+        table.column_stats.clear();
+        let decoded = TableDef::deserialize(&table.serialize()).unwrap();
+        assert_eq!(decoded.row_count, Some(12));
+        assert!(decoded.column_stats.is_empty());
+        assert_eq!(decoded.fill_factor, DEFAULT_FILL_FACTOR);
+
+        // A non-default fill factor comes last.
+        table.fill_factor = 70;
+        let decoded = TableDef::deserialize(&table.serialize()).unwrap();
+        assert_eq!((decoded.fill_factor, decoded.row_count), (70, Some(12)));
+        table.row_count = None;
+        assert_eq!(
+            TableDef::deserialize(&table.serialize())
+                .unwrap()
+                .fill_factor,
+            70
+        );
+
+        // The index key format follows; definitions without it are on the
+        // concatenated layout.
+        let decoded = TableDef::deserialize(&table.serialize()).unwrap();
+        assert_eq!(decoded.index_key_format, INDEX_KEY_FORMAT_TERMINATED);
+        assert!(decoded.trailing_fields.is_empty());
+        table.index_key_format = INDEX_KEY_FORMAT_CONCAT;
+        let decoded = TableDef::deserialize(&table.serialize()).unwrap();
+        assert_eq!(
+            (decoded.index_key_format, decoded.fill_factor),
+            (INDEX_KEY_FORMAT_CONCAT, 70)
+        );
+    }
+
+    #[test]
+    fn test_table_def_keeps_trailing_fields_of_newer_versions() {
+        // A newer column field, then a newer table field after the known
+        // tail, with and without the optional known fields written.
+        let mut column = ColumnDef::new("s", DataType::Varchar(None));
+        column.trailing_fields = vec![0x07, 0xAB, 0xCD];
+        let mut table = TableDef {
+            name: "t".to_string(),
+            columns: vec![ColumnDef::new("id", DataType::BigInt).primary_key(), column],
+            pk_columns: vec!["id".to_string()],
+            data_btree_root: 7,
+            next_rowid: 3,
+            row_format_version: 1,
+            stats_row_count: 0,
+            foreign_keys: Vec::new(),
+            column_stats: Vec::new(),
+            row_count: None,
+            fill_factor: DEFAULT_FILL_FACTOR,
+            index_key_format: INDEX_KEY_FORMAT_TERMINATED,
+            trailing_fields: Vec::new(),
+        };
+        let future = [0xB7, 0x04, 0x00, b'h', b'i', b's', b't'];
+        for (row_count, fill_factor) in [(None, DEFAULT_FILL_FACTOR), (Some(5), 80)] {
+            table.row_count = row_count;
+            table.fill_factor = fill_factor;
+            let mut bytes = table.serialize();
+            bytes.extend_from_slice(&future);
+
+            let decoded = TableDef::deserialize(&bytes).unwrap();
+            assert_eq!(decoded.trailing_fields, future);
+            assert_eq!(decoded.columns[1].trailing_fields, [0x07, 0xAB, 0xCD]);
+            assert_eq!(
+                (decoded.row_count, decoded.fill_factor),
+                (row_count, fill_factor)
+            );
+            assert_eq!(decoded.serialize(), bytes);
+        }
+    }
+
+    #[test]
+    fn test_update_table_preserves_fields_it_does_not_know() {
+        let dir = TempDir::new().unwrap();
+        let mut pager = Pager::create(&dir.path().join("test.db"), &test_key()).unwrap();
+        let mut catalog = SystemCatalog::create(&mut pager).unwrap();
+        let table = catalog
+            .create_table(
+                &mut pager,
+                "t",
+                vec![ColumnDef::new("id", DataType::BigInt).primary_key()],
+            )
+            .unwrap();
+
+        // As written by a newer version.
+        let future = [0xB7, 0x01, 0x02, 0x03];
+        let mut bytes = table.serialize();
+        bytes.extend_from_slice(&future);
+        catalog
+            .catalog_btree
+            .insert(&mut pager, b"table:t", &bytes)
+            .unwrap();
+
+        let mut table = catalog.get_table(&mut pager, "t").unwrap().unwrap();
+        table.next_rowid += 1;
+        catalog.update_table(&mut pager, &table).unwrap();
+
+        let stored = catalog
+            .catalog_btree
+            .search(&mut pager, b"table:t")
+            .unwrap()
+            .unwrap();
+        assert!(stored.ends_with(&future));
+        let reread = TableDef::deserialize(&stored).unwrap();
+        assert_eq!(reread.next_rowid, table.next_rowid);
+        assert_eq!(reread.trailing_fields, future);
+    }
+
     #[test]
     fn test_catalog_create_and_get_table() {
         let dir = TempDir::new().unwrap();
@@ -735,6 +1551,49 @@ mod tests {
         assert_eq!(retrieved.columns.len(), 2);
     }
 
+    #[test]
+    fn test_try_create_and_delete_report_existing_names() {
+        let dir = TempDir::new().unwrap();
+        let db_path = dir.path().join("test.db");
+
+        let mut pager = Pager::create(&db_path, &test_key()).unwrap();
+        let mut catalog = SystemCatalog::create(&mut pager).unwrap();
+        let columns = vec![ColumnDef::new("id", DataType::BigInt).primary_key()];
+
+        let created = catalog
+            .try_create_table(&mut pager, "posts", columns.clone())
+            .unwrap();
+        let CreateOutcome::Created(first) = created else {
+            panic!("expected Created, got {:?}", created);
+        };
+        let pages = pager.page_count();
+        for name in ["posts", "Posts"] {
+            match catalog
+                .try_create_table(&mut pager, name, columns.clone())
+                .unwrap()
+            {
+                CreateOutcome::AlreadyExists(other) => assert_eq!(other, "posts"),
+                other => panic!("expected AlreadyExists, got {:?}", other),
+            }
+        }
+        // Nothing is allocated for a name that is taken.
+        assert_eq!(pager.page_count(), pages);
+
+        let removed = catalog.try_delete_table(&mut pager, "posts").unwrap();
+        assert_eq!(
+            removed.map(|t| t.data_btree_root),
+            Some(first.data_btree_root)
+        );
+        assert!(catalog
+            .try_delete_table(&mut pager, "posts")
+            .unwrap()
+            .is_none());
+        assert!(catalog
+            .try_delete_index(&mut pager, "idx_missing")
+            .unwrap()
+            .is_none());
+    }
+
     #[test]
     fn test_catalog_duplicate_table() {
         let dir = TempDir::new().unwrap();
@@ -772,6 +1631,12 @@ mod tests {
             stats_num_hist_bins: Vec::new(),
             fts_stop_filter: false,
             fts_stop_df_ratio_ppm: 0,
+            fts_stop_fallback: FtsStopFallback::Rescan,
+            fts_stop_fallback_max_docs: 0,
+            expressions: Vec::new(),
+            fill_factor: DEFAULT_FILL_FACTOR,
+            building: false,
+            trailing_fields: Vec::new(),
         };
 
         catalog.create_index(&mut pager, idx).unwrap();
@@ -819,10 +1684,11 @@ mod tests {
         let db_path = dir.path().join("test.db");
         let mut pager = Pager::create(&db_path, &test_key()).unwrap();
         let mut catalog = SystemCatalog::create(&mut pager).unwrap();
-        let key = [0xA5u8; 32];
+        let key = TermKey::new([0xA5u8; 32]);
 
-        assert_eq!(catalog.get_fts_term_key(&mut pager).unwrap(), None);
-        catalog.set_fts_term_key(&mut pager, key).unwrap();
-        assert_eq!(catalog.get_fts_term_key(&mut pager).unwrap(), Some(key));
+        assert!(catalog.get_fts_term_key(&mut pager).unwrap().is_none());
+        catalog.set_fts_term_key(&mut pager, &key).unwrap();
+        let stored = catalog.get_fts_term_key(&mut pager).unwrap().unwrap();
+        assert_eq!(stored.as_bytes(), key.as_bytes());
     }
 }
@@ -1,4 +1,4 @@
-use crate::types::DataType;
+use crate::types::{Collation, DataType};
 
 #[derive(Debug, Clone)]
 pub struct ColumnDef {
@@ -9,20 +9,29 @@ pub struct ColumnDef {
     pub is_nullable: bool,
     pub is_hidden: bool,
     pub auto_increment: bool,
-    /// Default value as a simple literal (integer or string).
+    /// Default value: a literal, or an expression evaluated per inserted row.
     /// Stored as serialized bytes in the column definition.
     pub default_value: Option<DefaultValue>,
     /// CHECK constraint expression text (stored as string, re-parsed at runtime).
     pub check_expr: Option<String>,
+    /// How the column's strings compare; also applied to its index keys.
+    pub collation: Collation,
+    /// Encoded fields past the collation, written by a newer version. Kept
+    /// verbatim and written back after the known fields, so rewriting the
+    /// column does not drop them.
+    pub trailing_fields: Vec<u8>,
 }
 
-/// Simple default values that can be serialized.
+/// Column default values that can be serialized.
 #[derive(Debug, Clone, PartialEq)]
 pub enum DefaultValue {
     Integer(i64),
     Float(f64),
     String(String),
     Null,
+    /// `DEFAULT CURRENT_TIMESTAMP` / `DEFAULT (expr)`: canonical expression
+    /// text, re-parsed and evaluated for every row that takes the default.
+    Expr(String),
 }
 
 impl ColumnDef {
@@ -37,6 +46,8 @@ impl ColumnDef {
             auto_increment: false,
             default_value: None,
             check_expr: None,
+            collation: Collation::Binary,
+            trailing_fields: Vec::new(),
         }
     }
 
@@ -76,9 +87,19 @@ impl ColumnDef {
         self
     }
 
+    pub fn with_collation(mut self, collation: Collation) -> Self {
+        self.collation = collation;
+        self
+    }
+
     /// Serialize column definition to bytes.
     /// Format: [name_len(u16)][name][type_byte][flags][optional_size(u32)]
     ///         [default_tag(u8)][default_data...][check_len(u16)][check_str...]
+    ///         [default_expr_len(u16)][default_expr...][collation_len(u8)][collation...]
+    /// The trailing fields are written only when needed, and the default
+    /// expression is written empty when only a collation follows it. Fields
+    /// of a newer version, if any, follow the collation, which is then
+    /// written even when binary.
     pub fn serialize(&self) -> Vec<u8> {
         let mut buf = Vec::new();
         // name length + name
@@ -132,9 +153,9 @@ impl ColumnDef {
             }
             _ => {}
         }
-        // default value
+        // default value (expression defaults go in the trailing field below)
         match &self.default_value {
-            None => buf.push(0), // no default
+            None | Some(DefaultValue::Expr(_)) => buf.push(0), // no literal default
             Some(DefaultValue::Null) => buf.push(1),
             Some(DefaultValue::Integer(n)) => {
                 buf.push(2);
@@ -160,6 +181,25 @@ impl ColumnDef {
                 buf.extend_from_slice(expr_bytes);
             }
         }
+        // default expression: trailing and only present when set, so readers
+        // that predate it see a column without a default.
+        let default_expr = match &self.default_value {
+            Some(DefaultValue::Expr(expr)) => Some(expr.as_bytes()),
+            _ => None,
+        };
+        let collation = self.collation != Collation::Binary || !self.trailing_fields.is_empty();
+        if default_expr.is_some() || collation {
+            let expr_bytes = default_expr.unwrap_or_default();
+            buf.extend_from_slice(&(expr_bytes.len() as u16).to_le_bytes());
+            buf.extend_from_slice(expr_bytes);
+        }
+        // collation: by name, only when not binary
+        if collation {
+            let name = self.collation.name().as_bytes();
+            buf.push(name.len() as u8);
+            buf.extend_from_slice(name);
+        }
+        buf.extend_from_slice(&self.trailing_fields);
         buf
     }
 
@@ -298,6 +338,43 @@ impl ColumnDef {
             None
         };
 
+        // default expression
+        let default_value = if data.len() >= consumed + 2 {
+            let expr_len =
+                u16::from_le_bytes(data[consumed..consumed + 2].try_into().unwrap()) as usize;
+            consumed += 2;
+            if data.len() < consumed + expr_len {
+                return None;
+            }
+            let s = String::from_utf8(data[consumed..consumed + expr_len].to_vec()).ok()?;
+            consumed += expr_len;
+            if s.is_empty() {
+                default_value
+            } else {
+                Some(DefaultValue::Expr(s))
+            }
+        } else {
+            default_value
+        };
+
+        // collation
+        let collation = if data.len() > consumed {
+            let name_len = data[consumed] as usize;
+            consumed += 1;
+            if data.len() < consumed + name_len {
+                return None;
+            }
+            let name = std::str::from_utf8(&data[consumed..consumed + name_len]).ok()?;
+            consumed += name_len;
+            Collation::from_name(name)?
+        } else {
+            Collation::Binary
+        };
+
+        // Anything left was appended by a newer version.
+        let trailing_fields = data[consumed..].to_vec();
+        consumed = data.len();
+
         let col = ColumnDef {
             name,
             data_type,
@@ -308,6 +385,8 @@ impl ColumnDef {
             auto_increment,
             default_value,
             check_expr,
+            collation,
+            trailing_fields,
         };
         Some((col, consumed))
     }
@@ -412,4 +491,74 @@ mod tests {
         let (col2, _) = ColumnDef::deserialize(&bytes).unwrap();
         assert_eq!(col2.check_expr, Some("age > 0".into()));
     }
+
+    #[test]
+    fn test_column_roundtrip_default_expr() {
+        let col = ColumnDef::new("created", DataType::DateTime)
+            .with_default(DefaultValue::Expr("CURRENT_TIMESTAMP".into()))
+            .with_check("created > 0");
+        let bytes = col.serialize();
+        let (col2, consumed) = ColumnDef::deserialize(&bytes).unwrap();
+        assert_eq!(consumed, bytes.len());
+        assert_eq!(
+            col2.default_value,
+            Some(DefaultValue::Expr("CURRENT_TIMESTAMP".into()))
+        );
+        assert_eq!(col2.check_expr, Some("created > 0".into()));
+
+        // Without the trailing field the column reads as having no default.
+        let legacy_len = bytes.len() - 2 - "CURRENT_TIMESTAMP".len();
+        let (col3, _) = ColumnDef::deserialize(&bytes[..legacy_len]).unwrap();
+        assert_eq!(col3.default_value, None);
+        assert_eq!(col3.check_expr, Some("created > 0".into()));
+    }
+
+    #[test]
+    fn test_column_roundtrip_collation() {
+        for default in [
+            None,
+            Some(DefaultValue::String("x".into())),
+            Some(DefaultValue::Expr("UUID_V4()".into())),
+        ] {
+            let mut col = ColumnDef::new("email", DataType::Varchar(Some(100)))
+                .with_collation(Collation::Nocase);
+            col.default_value = default.clone();
+            let bytes = col.serialize();
+            let (col2, consumed) = ColumnDef::deserialize(&bytes).unwrap();
+            assert_eq!(consumed, bytes.len());
+            assert_eq!(col2.collation, Collation::Nocase);
+            assert_eq!(col2.default_value, default);
+        }
+
+        // Binary columns serialize exactly as before collations existed.
+        let col = ColumnDef::new("email", DataType::Varchar(Some(100)));
+        let bytes = col.serialize();
+        let (col2, _) = ColumnDef::deserialize(&bytes).unwrap();
+        assert_eq!(col2.collation, Collation::Binary);
+        assert_eq!(bytes.len(), 2 + 5 + 2 + 4 + 1 + 2);
+    }
+
+    #[test]
+    fn test_column_keeps_trailing_fields_of_newer_versions() {
+        let future = [0x02, 0x10, 0x20];
+        for col in [
+            ColumnDef::new("email", DataType::Varchar(Some(100))),
+            ColumnDef::new("email", DataType::Varchar(Some(100)))
+                .with_default(DefaultValue::String("x".into()))
+                .with_collation(Collation::Nocase),
+        ] {
+            // A newer version writes every field before its own.
+            let mut newer = col.clone();
+            newer.trailing_fields = future.to_vec();
+            let bytes = newer.serialize();
+            assert!(bytes.ends_with(&future));
+
+            let (decoded, consumed) = ColumnDef::deserialize(&bytes).unwrap();
+            assert_eq!(consumed, bytes.len());
+            assert_eq!(decoded.trailing_fields, future);
+            assert_eq!(decoded.collation, col.collation);
+            assert_eq!(decoded.default_value, col.default_value);
+            assert_eq!(decoded.serialize(), bytes);
+        }
+    }
 }
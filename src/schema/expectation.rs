@@ -0,0 +1,514 @@
+/// Expected-schema assertions: the tables, columns, and indexes an
+/// application needs, compared structurally against the live catalog.
+///
+/// A live column satisfies an expected one when the expected definition could
+/// be turned into the live one by a metadata-only MODIFY COLUMN: the same type
+/// or a wider VARCHAR/VARBINARY, and NOT NULL only where the expectation has
+/// it too. An expected index is present when a live index covers the same
+/// columns in the same order (and is UNIQUE or FULLTEXT when required), under
+/// any name.
+use std::fmt;
+
+use crate::error::{MuroError, Result};
+use crate::schema::catalog::{SystemCatalog, TableDef};
+use crate::schema::index::{IndexDef, IndexType};
+use crate::sql::ast::{Statement, TableConstraint};
+use crate::storage::page_store::PageStore;
+use crate::types::DataType;
+
+/// The schema an application expects. Extra tables, columns, and indexes in
+/// the live database are tolerated unless disallowed.
+#[derive(Debug, Clone)]
+pub struct SchemaExpectation {
+    pub tables: Vec<ExpectedTable>,
+    pub allow_extra_tables: bool,
+    pub allow_extra_columns: bool,
+    pub allow_extra_indexes: bool,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ExpectedTable {
+    pub name: String,
+    pub columns: Vec<ExpectedColumn>,
+    /// Primary key columns; empty means the primary key is not checked.
+    pub primary_key: Vec<String>,
+    pub indexes: Vec<ExpectedIndex>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ExpectedColumn {
+    pub name: String,
+    pub data_type: DataType,
+    pub nullable: bool,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ExpectedIndex {
+    pub columns: Vec<String>,
+    pub unique: bool,
+    pub fulltext: bool,
+}
+
+impl Default for SchemaExpectation {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl SchemaExpectation {
+    pub fn new() -> Self {
+        SchemaExpectation {
+            tables: Vec::new(),
+            allow_extra_tables: true,
+            allow_extra_columns: true,
+            allow_extra_indexes: true,
+        }
+    }
+
+    /// Build an expectation from `CREATE TABLE`, `CREATE INDEX`, and
+    /// `CREATE FULLTEXT INDEX` statements, such as the schema file a
+    /// migration produces.
+    pub fn from_sql(sql: &str) -> Result<Self> {
+        let statements = crate::sql::parser::parse_script(sql).map_err(MuroError::Parse)?;
+        let mut expectation = SchemaExpectation::new();
+        for stmt in statements {
+            match stmt {
+                Statement::CreateTable(ct) => {
+                    let mut table = ExpectedTable::new(&ct.table_name);
+                    let mut primary_key: Vec<String> = ct
+                        .columns
+                        .iter()
+                        .filter(|c| c.is_primary_key)
+                        .map(|c| c.name.clone())
+                        .collect();
+                    for constraint in &ct.constraints {
+                        match constraint {
+                            TableConstraint::PrimaryKey(cols) => primary_key = cols.clone(),
+                            TableConstraint::Unique(_, cols) => {
+                                table.indexes.push(ExpectedIndex::unique(cols))
+                            }
+                            TableConstraint::Index(ci) => table.indexes.push(expected_btree_index(
+                                &ci.index_name,
+                                &ci.column_names,
+                                &ci.expressions,
+                                ci.is_unique,
+                            )?),
+                            TableConstraint::ForeignKey { .. } => {}
+                        }
+                    }
+                    for col in &ct.columns {
+                        let nullable = col.is_nullable && !primary_key.contains(&col.name);
+                        table = table.column(&col.name, col.data_type, nullable);
+                        if col.is_unique && !col.is_primary_key {
+                            table
+                                .indexes
+                                .push(ExpectedIndex::unique(&[col.name.as_str()]));
+                        }
+                    }
+                    table.primary_key = primary_key;
+                    expectation.tables.push(table);
+                }
+                Statement::CreateIndex(ci) => {
+                    let index = expected_btree_index(
+                        &ci.index_name,
+                        &ci.column_names,
+                        &ci.expressions,
+                        ci.is_unique,
+                    )?;
+                    expectation.table_mut(&ci.table_name)?.indexes.push(index);
+                }
+                Statement::CreateFulltextIndex(fi) => {
+                    expectation
+                        .table_mut(&fi.table_name)?
+                        .indexes
+                        .push(ExpectedIndex::fulltext(&fi.column_name));
+                }
+                _ => {
+                    return Err(MuroError::Schema(
+                        "schema expectation accepts only CREATE TABLE and CREATE INDEX statements"
+                            .into(),
+                    ))
+                }
+            }
+        }
+        Ok(expectation)
+    }
+
+    pub fn table(mut self, table: ExpectedTable) -> Self {
+        self.tables.push(table);
+        self
+    }
+
+    pub fn allow_extra_tables(mut self, allow: bool) -> Self {
+        self.allow_extra_tables = allow;
+        self
+    }
+
+    pub fn allow_extra_columns(mut self, allow: bool) -> Self {
+        self.allow_extra_columns = allow;
+        self
+    }
+
+    pub fn allow_extra_indexes(mut self, allow: bool) -> Self {
+        self.allow_extra_indexes = allow;
+        self
+    }
+
+    fn table_mut(&mut self, name: &str) -> Result<&mut ExpectedTable> {
+        self.tables
+            .iter_mut()
+            .find(|t| t.name == name)
+            .ok_or_else(|| {
+                MuroError::Schema(format!(
+                    "index on table '{}' must follow its CREATE TABLE",
+                    name
+                ))
+            })
+    }
+
+    /// Compare against the catalog. Differences this expectation does not
+    /// tolerate go to [`SchemaDiff::required`], tolerated extras to
+    /// [`SchemaDiff::tolerated`].
+    pub fn compare(
+        &self,
+        pager: &mut impl PageStore,
+        catalog: &SystemCatalog,
+    ) -> Result<SchemaDiff> {
+        let mut diff = SchemaDiff::default();
+        let live_tables = catalog.list_tables(pager)?;
+
+        for expected in &self.tables {
+            let Some(live) = catalog.get_table(pager, &expected.name)? else {
+                diff.required
+                    .push(SchemaDifference::MissingTable(expected.name.clone()));
+                continue;
+            };
+            let live_indexes = catalog.get_indexes_for_table(pager, &expected.name)?;
+            self.compare_table(expected, &live, &live_indexes, &mut diff);
+        }
+
+        for name in live_tables {
+            if !self.tables.iter().any(|t| t.name == name) {
+                diff.extra(self.allow_extra_tables, SchemaDifference::ExtraTable(name));
+            }
+        }
+        Ok(diff)
+    }
+
+    /// Compare one expected table against a live table definition and its
+    /// indexes, which need not have the expected table's name. Differences
+    /// are reported under the expected name.
+    pub(crate) fn compare_table(
+        &self,
+        expected: &ExpectedTable,
+        live: &TableDef,
+        live_indexes: &[IndexDef],
+        diff: &mut SchemaDiff,
+    ) {
+        let table = &expected.name;
+
+        for column in &expected.columns {
+            let Some(live_col) = live.columns.iter().find(|c| c.name == column.name) else {
+                diff.required.push(SchemaDifference::MissingColumn {
+                    table: table.clone(),
+                    column: column.name.clone(),
+                });
+                continue;
+            };
+            let type_ok = column
+                .data_type
+                .is_metadata_only_change_to(live_col.data_type);
+            let null_ok = !column.nullable || live_col.is_nullable;
+            if !type_ok || !null_ok {
+                diff.required.push(SchemaDifference::IncompatibleColumn {
+                    table: table.clone(),
+                    column: column.name.clone(),
+                    expected: describe_column(column.data_type, column.nullable),
+                    actual: describe_column(live_col.data_type, live_col.is_nullable),
+                });
+            }
+        }
+        for live_col in live.columns.iter().filter(|c| !c.is_hidden) {
+            if !expected.columns.iter().any(|c| c.name == live_col.name) {
+                diff.extra(
+                    self.allow_extra_columns,
+                    SchemaDifference::ExtraColumn {
+                        table: table.clone(),
+                        column: live_col.name.clone(),
+                    },
+                );
+            }
+        }
+
+        if !expected.primary_key.is_empty() && expected.primary_key != live.pk_columns {
+            diff.required.push(SchemaDifference::PrimaryKeyMismatch {
+                table: table.clone(),
+                expected: expected.primary_key.clone(),
+                actual: live.pk_columns.clone(),
+            });
+        }
+
+        let mut matched = vec![false; live_indexes.len()];
+        for index in &expected.indexes {
+            let found = live_indexes.iter().enumerate().position(|(i, live)| {
+                !matched[i]
+                    && live.column_names == index.columns
+                    && (live.is_unique || !index.unique)
+                    && (live.index_type == IndexType::Fulltext) == index.fulltext
+            });
+            match found {
+                Some(i) => matched[i] = true,
+                None => diff.required.push(SchemaDifference::MissingIndex {
+                    table: table.clone(),
+                    index: index.clone(),
+                }),
+            }
+        }
+        for (live, _) in live_indexes.iter().zip(&matched).filter(|(_, m)| !**m) {
+            diff.extra(
+                self.allow_extra_indexes,
+                SchemaDifference::ExtraIndex {
+                    table: table.clone(),
+                    index: live.name.clone(),
+                },
+            );
+        }
+    }
+}
+
+fn expected_btree_index(
+    name: &str,
+    columns: &[String],
+    expressions: &[Option<crate::sql::ast::Expr>],
+    unique: bool,
+) -> Result<ExpectedIndex> {
+    if expressions.iter().any(Option::is_some) {
+        return Err(MuroError::Schema(format!(
+            "index '{}': expression indexes are not supported in schema expectations",
+            name
+        )));
+    }
+    Ok(ExpectedIndex {
+        columns: columns.to_vec(),
+        unique,
+        fulltext: false,
+    })
+}
+
+fn describe_column(data_type: DataType, nullable: bool) -> String {
+    if nullable {
+        data_type.to_string()
+    } else {
+        format!("{} NOT NULL", data_type)
+    }
+}
+
+impl ExpectedTable {
+    pub fn new(name: &str) -> Self {
+        ExpectedTable {
+            name: name.to_string(),
+            columns: Vec::new(),
+            primary_key: Vec::new(),
+            indexes: Vec::new(),
+        }
+    }
+
+    pub fn column(mut self, name: &str, data_type: DataType, nullable: bool) -> Self {
+        self.columns.push(ExpectedColumn {
+            name: name.to_string(),
+            data_type,
+            nullable,
+        });
+        self
+    }
+
+    pub fn primary_key(mut self, columns: &[&str]) -> Self {
+        self.primary_key = columns.iter().map(|c| c.to_string()).collect();
+        self
+    }
+
+    pub fn index(mut self, index: ExpectedIndex) -> Self {
+        self.indexes.push(index);
+        self
+    }
+
+    /// The expectation a live table satisfies exactly: its visible columns,
+    /// primary key, and indexes.
+    pub fn from_live(table: &TableDef, indexes: &[IndexDef]) -> Self {
+        ExpectedTable {
+            name: table.name.clone(),
+            columns: table
+                .columns
+                .iter()
+                .filter(|c| !c.is_hidden)
+                .map(|c| ExpectedColumn {
+                    name: c.name.clone(),
+                    data_type: c.data_type,
+                    nullable: c.is_nullable,
+                })
+                .collect(),
+            primary_key: table.pk_columns.clone(),
+            indexes: indexes
+                .iter()
+                .map(|idx| ExpectedIndex {
+                    columns: idx.column_names.clone(),
+                    unique: idx.is_unique,
+                    fulltext: idx.index_type == IndexType::Fulltext,
+                })
+                .collect(),
+        }
+    }
+}
+
+impl ExpectedIndex {
+    pub fn new(columns: &[&str]) -> Self {
+        ExpectedIndex {
+            columns: columns.iter().map(|c| c.to_string()).collect(),
+            unique: false,
+            fulltext: false,
+        }
+    }
+
+    pub fn unique<S: AsRef<str>>(columns: &[S]) -> Self {
+        ExpectedIndex {
+            columns: columns.iter().map(|c| c.as_ref().to_string()).collect(),
+            unique: true,
+            fulltext: false,
+        }
+    }
+
+    pub fn fulltext(column: &str) -> Self {
+        ExpectedIndex {
+            columns: vec![column.to_string()],
+            unique: false,
+            fulltext: true,
+        }
+    }
+}
+
+impl fmt::Display for ExpectedIndex {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let kind = if self.fulltext {
+            "FULLTEXT INDEX"
+        } else if self.unique {
+            "UNIQUE INDEX"
+        } else {
+            "INDEX"
+        };
+        write!(f, "{} ({})", kind, self.columns.join(", "))
+    }
+}
+
+/// One way the live schema differs from the expectation.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SchemaDifference {
+    MissingTable(String),
+    MissingColumn {
+        table: String,
+        column: String,
+    },
+    /// The live column cannot hold every value the expected one can.
+    IncompatibleColumn {
+        table: String,
+        column: String,
+        expected: String,
+        actual: String,
+    },
+    PrimaryKeyMismatch {
+        table: String,
+        expected: Vec<String>,
+        actual: Vec<String>,
+    },
+    MissingIndex {
+        table: String,
+        index: ExpectedIndex,
+    },
+    ExtraTable(String),
+    ExtraColumn {
+        table: String,
+        column: String,
+    },
+    ExtraIndex {
+        table: String,
+        index: String,
+    },
+}
+
+impl fmt::Display for SchemaDifference {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SchemaDifference::MissingTable(t) => write!(f, "missing table {}", t),
+            SchemaDifference::MissingColumn { table, column } => {
+                write!(f, "missing column {}.{}", table, column)
+            }
+            SchemaDifference::IncompatibleColumn {
+                table,
+                column,
+                expected,
+                actual,
+            } => write!(
+                f,
+                "column {}.{} is {}, expected {}",
+                table, column, actual, expected
+            ),
+            SchemaDifference::PrimaryKeyMismatch {
+                table,
+                expected,
+                actual,
+            } => write!(
+                f,
+                "primary key of {} is ({}), expected ({})",
+                table,
+                actual.join(", "),
+                expected.join(", ")
+            ),
+            SchemaDifference::MissingIndex { table, index } => {
+                write!(f, "missing {} on {}", index, table)
+            }
+            SchemaDifference::ExtraTable(t) => write!(f, "extra table {}", t),
+            SchemaDifference::ExtraColumn { table, column } => {
+                write!(f, "extra column {}.{}", table, column)
+            }
+            SchemaDifference::ExtraIndex { table, index } => {
+                write!(f, "extra index {} on {}", index, table)
+            }
+        }
+    }
+}
+
+/// Result of comparing a [`SchemaExpectation`] with the live catalog.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct SchemaDiff {
+    /// Missing or incompatible elements, and extras the expectation does not
+    /// tolerate.
+    pub required: Vec<SchemaDifference>,
+    /// Extra tables, columns, and indexes the expectation tolerates.
+    pub tolerated: Vec<SchemaDifference>,
+}
+
+impl SchemaDiff {
+    /// No differences at all.
+    pub fn is_empty(&self) -> bool {
+        self.required.is_empty() && self.tolerated.is_empty()
+    }
+
+    /// Whether the live schema satisfies the expectation.
+    pub fn is_compatible(&self) -> bool {
+        self.required.is_empty()
+    }
+
+    fn extra(&mut self, allowed: bool, difference: SchemaDifference) {
+        if allowed {
+            self.tolerated.push(difference);
+        } else {
+            self.required.push(difference);
+        }
+    }
+}
+
+impl fmt::Display for SchemaDiff {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let parts: Vec<String> = self.required.iter().map(|d| d.to_string()).collect();
+        write!(f, "{}", parts.join("; "))
+    }
+}
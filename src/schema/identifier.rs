@@ -0,0 +1,81 @@
+/// Identifier folding: when two table, column, or index names count as the same.
+///
+/// Names are stored and resolved exactly as written, but two siblings must
+/// not differ only by letter case or Unicode normalization form ("Email" and
+/// "email", or a precomposed "é" and "e" + combining acute): such pairs look
+/// identical to users and to anything that folds identifiers. DDL rejects a
+/// name whose folded form matches an existing sibling's; databases created
+/// before the rule may still hold such pairs, which stay reachable by their
+/// exact names and are flagged by `verify_integrity`.
+use crate::error::MuroError;
+use unicode_normalization::UnicodeNormalization;
+
+/// The form two identifiers are compared in: Unicode lowercase, then NFC.
+pub fn fold_identifier(name: &str) -> String {
+    name.to_lowercase().nfc().collect()
+}
+
+/// The first of `existing` that folds to the same identifier as `name`.
+pub fn folded_collision<'a>(
+    name: &str,
+    existing: impl IntoIterator<Item = &'a str>,
+) -> Option<&'a str> {
+    let folded = fold_identifier(name);
+    existing
+        .into_iter()
+        .find(|other| fold_identifier(other) == folded)
+}
+
+/// Error for `name` colliding with the existing `other` of the same `kind`
+/// ("Table", "Column", "Index").
+pub fn collision_error(kind: &str, name: &str, other: &str) -> MuroError {
+    if name == other {
+        return MuroError::Schema(format!("{} '{}' already exists", kind, name));
+    }
+    MuroError::Schema(format!(
+        "{} '{}' collides with existing {} '{}': names must differ by more than letter case or Unicode normalization",
+        kind,
+        name,
+        kind.to_lowercase(),
+        other
+    ))
+}
+
+/// Pairs of `names` that fold to the same identifier, each pair once.
+pub fn folded_collision_pairs<'a>(names: &[&'a str]) -> Vec<(&'a str, &'a str)> {
+    let folded: Vec<String> = names.iter().map(|n| fold_identifier(n)).collect();
+    let mut pairs = Vec::new();
+    for i in 0..names.len() {
+        for j in i + 1..names.len() {
+            if folded[i] == folded[j] {
+                pairs.push((names[i], names[j]));
+            }
+        }
+    }
+    pairs
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_fold_identifier() {
+        assert_eq!(fold_identifier("Email"), fold_identifier("email"));
+        assert_eq!(fold_identifier("caf\u{e9}"), fold_identifier("cafe\u{301}"));
+        assert_eq!(fold_identifier("CAF\u{c9}"), fold_identifier("cafe\u{301}"));
+        assert_ne!(fold_identifier("email"), fold_identifier("e_mail"));
+
+        assert_eq!(folded_collision("EMAIL", ["id", "Email"]), Some("Email"));
+        assert_eq!(folded_collision("mail", ["id", "Email"]), None);
+        assert_eq!(
+            folded_collision_pairs(&["a", "B", "A", "b", "c"]),
+            vec![("a", "A"), ("B", "b")]
+        );
+        assert_eq!(
+            collision_error("Column", "email", "Email").to_string(),
+            "Schema error: Column 'email' collides with existing column 'Email': \
+             names must differ by more than letter case or Unicode normalization"
+        );
+    }
+}
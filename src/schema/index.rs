@@ -1,3 +1,5 @@
+use crate::btree::ops::DEFAULT_FILL_FACTOR;
+use crate::fts::query::{FtsStopFallback, DEFAULT_STOP_FALLBACK_MAX_DOCS};
 use crate::storage::page::PageId;
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
@@ -28,6 +30,24 @@ pub struct IndexDef {
     pub fts_stop_filter: bool,
     /// FULLTEXT-only: df/total_docs threshold in ppm (0..=1_000_000).
     pub fts_stop_df_ratio_ppm: u32,
+    /// FULLTEXT-only: behavior when every NATURAL query ngram is stop-filtered.
+    pub fts_stop_fallback: FtsStopFallback,
+    /// FULLTEXT-only: candidate-document cap for the fallback rescan (0 = unlimited).
+    pub fts_stop_fallback_max_docs: u32,
+    /// Expression key parts as canonical SQL text, aligned with
+    /// `column_names` (which holds the same text for those parts). Empty
+    /// when every key part is a plain column.
+    pub expressions: Vec<Option<String>>,
+    /// Percent of each leaf filled by bulk loads and ascending inserts
+    /// (`WITH (fill_factor = N)`).
+    pub fill_factor: u8,
+    /// Still being filled by a batched `CREATE INDEX`; the planner does not
+    /// use it. Its progress is an [`IndexBuildProgress`] in the catalog.
+    pub building: bool,
+    /// Encoded fields past `fill_factor`, written by a newer version. Kept
+    /// verbatim and written back after the known fields, so rewriting the
+    /// definition does not drop them.
+    pub trailing_fields: Vec<u8>,
 }
 
 impl IndexDef {
@@ -84,9 +104,37 @@ impl IndexDef {
         for c in &self.stats_num_hist_bins {
             buf.extend_from_slice(&c.to_le_bytes());
         }
+        // FULLTEXT stop-filter fallback (optional extension)
+        buf.push(match self.fts_stop_fallback {
+            FtsStopFallback::Rescan => 0,
+            FtsStopFallback::EmptyWithWarning => 1,
+        });
+        buf.extend_from_slice(&self.fts_stop_fallback_max_docs.to_le_bytes());
+        // expression key parts (optional extension)
+        buf.extend_from_slice(&(self.expressions.len() as u16).to_le_bytes());
+        for expr in &self.expressions {
+            match expr {
+                Some(text) => {
+                    buf.push(1);
+                    buf.extend_from_slice(&(text.len() as u16).to_le_bytes());
+                    buf.extend_from_slice(text.as_bytes());
+                }
+                None => buf.push(0),
+            }
+        }
+        // fill_factor (optional extension)
+        buf.push(self.fill_factor);
+        // building (optional extension)
+        buf.push(if self.building { 1 } else { 0 });
+        buf.extend_from_slice(&self.trailing_fields);
         buf
     }
 
+    /// Whether any key part is an expression rather than a column.
+    pub fn has_expressions(&self) -> bool {
+        self.expressions.iter().any(Option::is_some)
+    }
+
     /// Deserialize index definition from bytes.
     /// Backward-compatible: reads first column from legacy position,
     /// then reads additional columns if present after btree_root.
@@ -219,6 +267,7 @@ impl IndexDef {
 
         // numeric histogram bins (optional extension)
         let mut stats_num_hist_bins = Vec::new();
+        let mut hist_complete = false;
         if data.len() >= offset + 2 {
             let bin_count = u16::from_le_bytes(data[offset..offset + 2].try_into().unwrap());
             offset += 2;
@@ -229,12 +278,78 @@ impl IndexDef {
                     offset += 4;
                     stats_num_hist_bins.push(n);
                 }
+                hist_complete = true;
             } else {
                 // Corrupt/incomplete tail; ignore histogram extension.
                 stats_num_hist_bins.clear();
             }
         }
 
+        // FULLTEXT stop-filter fallback (optional extension)
+        let mut fts_stop_fallback = FtsStopFallback::Rescan;
+        let mut fts_stop_fallback_max_docs = DEFAULT_STOP_FALLBACK_MAX_DOCS;
+        let mut fallback_complete = false;
+        if hist_complete && data.len() >= offset + 5 {
+            fts_stop_fallback = match data[offset] {
+                1 => FtsStopFallback::EmptyWithWarning,
+                _ => FtsStopFallback::Rescan,
+            };
+            offset += 1;
+            fts_stop_fallback_max_docs =
+                u32::from_le_bytes(data[offset..offset + 4].try_into().unwrap());
+            offset += 4;
+            fallback_complete = true;
+        }
+
+        // expression key parts (optional extension)
+        let mut expressions = Vec::new();
+        let mut expressions_complete = false;
+        if fallback_complete && data.len() >= offset + 2 {
+            let count = u16::from_le_bytes(data[offset..offset + 2].try_into().unwrap());
+            offset += 2;
+            for _ in 0..count {
+                match *data.get(offset)? {
+                    0 => {
+                        offset += 1;
+                        expressions.push(None);
+                    }
+                    1 => {
+                        offset += 1;
+                        if data.len() < offset + 2 {
+                            return None;
+                        }
+                        let len = u16::from_le_bytes(data[offset..offset + 2].try_into().unwrap())
+                            as usize;
+                        offset += 2;
+                        if data.len() < offset + len {
+                            return None;
+                        }
+                        let text = String::from_utf8(data[offset..offset + len].to_vec()).ok()?;
+                        offset += len;
+                        expressions.push(Some(text));
+                    }
+                    _ => return None,
+                }
+            }
+            expressions_complete = true;
+        }
+
+        // fill_factor and building (optional extensions)
+        let mut fill_factor = DEFAULT_FILL_FACTOR;
+        let mut building = false;
+        let mut trailing_fields = Vec::new();
+        if expressions_complete && data.len() > offset {
+            fill_factor = data[offset];
+            offset += 1;
+            if data.len() > offset {
+                building = data[offset] != 0;
+                offset += 1;
+            }
+            // Anything left was appended by a newer version.
+            trailing_fields = data[offset..].to_vec();
+            offset = data.len();
+        }
+
         Some((
             IndexDef {
                 name,
@@ -250,12 +365,60 @@ impl IndexDef {
                 stats_num_hist_bins,
                 fts_stop_filter,
                 fts_stop_df_ratio_ppm,
+                fts_stop_fallback,
+                fts_stop_fallback_max_docs,
+                expressions,
+                fill_factor,
+                building,
+                trailing_fields,
             },
             offset,
         ))
     }
 }
 
+/// Progress of a batched `CREATE INDEX`, stored in the catalog until the
+/// index is complete so an interrupted build can resume.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct IndexBuildProgress {
+    /// Primary key of the last row indexed; `None` before the first batch.
+    pub last_key: Option<Vec<u8>>,
+    /// Rows read so far, including rows whose key is NULL.
+    pub rows_done: u64,
+}
+
+impl IndexBuildProgress {
+    pub fn serialize(&self) -> Vec<u8> {
+        let mut buf = Vec::with_capacity(11 + self.last_key.as_ref().map_or(0, Vec::len));
+        buf.extend_from_slice(&self.rows_done.to_le_bytes());
+        match &self.last_key {
+            Some(key) => {
+                buf.push(1);
+                buf.extend_from_slice(&(key.len() as u16).to_le_bytes());
+                buf.extend_from_slice(key);
+            }
+            None => buf.push(0),
+        }
+        buf
+    }
+
+    pub fn deserialize(data: &[u8]) -> Option<Self> {
+        let rows_done = u64::from_le_bytes(data.get(0..8)?.try_into().ok()?);
+        let last_key = match *data.get(8)? {
+            0 => None,
+            1 => {
+                let len = u16::from_le_bytes(data.get(9..11)?.try_into().ok()?) as usize;
+                Some(data.get(11..11 + len)?.to_vec())
+            }
+            _ => return None,
+        };
+        Some(IndexBuildProgress {
+            last_key,
+            rows_done,
+        })
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -307,6 +470,12 @@ mod tests {
             stats_num_hist_bins: vec![1, 2, 3],
             fts_stop_filter: false,
             fts_stop_df_ratio_ppm: 0,
+            fts_stop_fallback: FtsStopFallback::Rescan,
+            fts_stop_fallback_max_docs: 0,
+            expressions: Vec::new(),
+            fill_factor: DEFAULT_FILL_FACTOR,
+            building: false,
+            trailing_fields: Vec::new(),
         };
         let bytes = idx.serialize();
         let (idx2, _) = IndexDef::deserialize(&bytes).unwrap();
@@ -335,6 +504,12 @@ mod tests {
             stats_num_hist_bins: Vec::new(),
             fts_stop_filter: false,
             fts_stop_df_ratio_ppm: 0,
+            fts_stop_fallback: FtsStopFallback::Rescan,
+            fts_stop_fallback_max_docs: 0,
+            expressions: Vec::new(),
+            fill_factor: DEFAULT_FILL_FACTOR,
+            building: false,
+            trailing_fields: Vec::new(),
         };
         let bytes = idx.serialize();
         let (idx2, _) = IndexDef::deserialize(&bytes).unwrap();
@@ -344,6 +519,179 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_expression_index_roundtrip() {
+        let idx = IndexDef {
+            name: "idx_email_ci".to_string(),
+            table_name: "users".to_string(),
+            column_names: vec!["id".to_string(), "LOWER(email)".to_string()],
+            index_type: IndexType::BTree,
+            is_unique: true,
+            btree_root: 7,
+            stats_distinct_keys: 0,
+            stats_num_min: 0,
+            stats_num_max: 0,
+            stats_num_bounds_known: false,
+            stats_num_hist_bins: Vec::new(),
+            fts_stop_filter: false,
+            fts_stop_df_ratio_ppm: 0,
+            fts_stop_fallback: FtsStopFallback::Rescan,
+            fts_stop_fallback_max_docs: 0,
+            expressions: vec![None, Some("LOWER(email)".to_string())],
+            fill_factor: DEFAULT_FILL_FACTOR,
+            building: false,
+            trailing_fields: Vec::new(),
+        };
+        let bytes = idx.serialize();
+        let (decoded, used) = IndexDef::deserialize(&bytes).unwrap();
+        assert_eq!(used, bytes.len());
+        assert_eq!(decoded.column_names, idx.column_names);
+        assert_eq!(decoded.expressions, idx.expressions);
+        assert!(decoded.has_expressions());
+
+        // Records written before the extension have no expressions.
+        let mut plain = idx.clone();
+        plain.expressions.clear();
+        let mut bytes = plain.serialize();
+        bytes.truncate(bytes.len() - 3);
+        let (decoded, _) = IndexDef::deserialize(&bytes).unwrap();
+        assert!(decoded.expressions.is_empty());
+    }
+
+    #[test]
+    fn test_fill_factor_roundtrip_and_legacy_default() {
+        let mut idx = IndexDef {
+            name: "idx_k".to_string(),
+            table_name: "t".to_string(),
+            column_names: vec!["k".to_string()],
+            index_type: IndexType::BTree,
+            is_unique: false,
+            btree_root: 9,
+            stats_distinct_keys: 0,
+            stats_num_min: 0,
+            stats_num_max: 0,
+            stats_num_bounds_known: false,
+            stats_num_hist_bins: Vec::new(),
+            fts_stop_filter: false,
+            fts_stop_df_ratio_ppm: 0,
+            fts_stop_fallback: FtsStopFallback::Rescan,
+            fts_stop_fallback_max_docs: 0,
+            expressions: vec![Some("LOWER(k)".to_string())],
+            fill_factor: 60,
+            building: false,
+            trailing_fields: Vec::new(),
+        };
+        let bytes = idx.serialize();
+        let (decoded, used) = IndexDef::deserialize(&bytes).unwrap();
+        assert_eq!(used, bytes.len());
+        assert_eq!(decoded.fill_factor, 60);
+        assert_eq!(decoded.expressions, idx.expressions);
+
+        // Records written before the extension fill leaves completely
+        // (fill factor and the later building flag are the last two bytes).
+        idx.expressions.clear();
+        let mut bytes = idx.serialize();
+        bytes.truncate(bytes.len() - 2);
+        let (decoded, _) = IndexDef::deserialize(&bytes).unwrap();
+        assert_eq!(decoded.fill_factor, DEFAULT_FILL_FACTOR);
+    }
+
+    #[test]
+    fn test_building_flag_and_build_progress_roundtrip() {
+        let mut idx = IndexDef {
+            name: "idx_k".to_string(),
+            table_name: "t".to_string(),
+            column_names: vec!["k".to_string()],
+            index_type: IndexType::BTree,
+            is_unique: true,
+            btree_root: 9,
+            stats_distinct_keys: 0,
+            stats_num_min: 0,
+            stats_num_max: 0,
+            stats_num_bounds_known: false,
+            stats_num_hist_bins: Vec::new(),
+            fts_stop_filter: false,
+            fts_stop_df_ratio_ppm: 0,
+            fts_stop_fallback: FtsStopFallback::Rescan,
+            fts_stop_fallback_max_docs: 0,
+            expressions: Vec::new(),
+            fill_factor: 80,
+            building: true,
+            trailing_fields: Vec::new(),
+        };
+        let bytes = idx.serialize();
+        let (decoded, used) = IndexDef::deserialize(&bytes).unwrap();
+        assert_eq!(used, bytes.len());
+        assert!(decoded.building);
+        assert_eq!(decoded.fill_factor, 80);
+
+        // Records written before the flag are complete indexes.
+        idx.building = false;
+        let mut bytes = idx.serialize();
+        bytes.pop();
+        let (decoded, _) = IndexDef::deserialize(&bytes).unwrap();
+        assert!(!decoded.building);
+        assert_eq!(decoded.fill_factor, 80);
+
+        for progress in [
+            IndexBuildProgress::default(),
+            IndexBuildProgress {
+                last_key: Some(vec![0x80, 0, 0, 0, 0, 0, 0, 7]),
+                rows_done: 4096,
+            },
+        ] {
+            let bytes = progress.serialize();
+            assert_eq!(IndexBuildProgress::deserialize(&bytes), Some(progress));
+            assert_eq!(
+                IndexBuildProgress::deserialize(&bytes[..bytes.len() - 1]),
+                None
+            );
+        }
+    }
+
+    #[test]
+    fn test_trailing_fields_of_newer_versions_roundtrip() {
+        let idx = IndexDef {
+            name: "idx_k".to_string(),
+            table_name: "t".to_string(),
+            column_names: vec!["k".to_string()],
+            index_type: IndexType::BTree,
+            is_unique: false,
+            btree_root: 9,
+            stats_distinct_keys: 4,
+            stats_num_min: 0,
+            stats_num_max: 0,
+            stats_num_bounds_known: false,
+            stats_num_hist_bins: vec![1, 2],
+            fts_stop_filter: false,
+            fts_stop_df_ratio_ppm: 0,
+            fts_stop_fallback: FtsStopFallback::Rescan,
+            fts_stop_fallback_max_docs: 0,
+            expressions: Vec::new(),
+            fill_factor: 90,
+            building: false,
+            trailing_fields: Vec::new(),
+        };
+        let mut bytes = idx.serialize();
+        bytes.extend_from_slice(b"\x01\x05k > 0");
+
+        let (mut decoded, used) = IndexDef::deserialize(&bytes).unwrap();
+        assert_eq!(used, bytes.len());
+        assert_eq!(decoded.fill_factor, 90);
+        assert_eq!(decoded.trailing_fields, b"\x01\x05k > 0");
+        decoded.stats_distinct_keys = 5;
+        let rewritten = decoded.serialize();
+        assert!(rewritten.ends_with(b"\x01\x05k > 0"));
+        assert_eq!(rewritten.len(), bytes.len());
+        assert_eq!(
+            IndexDef::deserialize(&rewritten)
+                .unwrap()
+                .0
+                .stats_distinct_keys,
+            5
+        );
+    }
+
     #[test]
     fn test_deserialize_old_layout_keeps_fts_settings() {
         let idx = IndexDef {
@@ -360,6 +708,12 @@ mod tests {
             stats_num_hist_bins: Vec::new(),
             fts_stop_filter: true,
             fts_stop_df_ratio_ppm: 250_000,
+            fts_stop_fallback: FtsStopFallback::Rescan,
+            fts_stop_fallback_max_docs: 0,
+            expressions: Vec::new(),
+            fill_factor: DEFAULT_FILL_FACTOR,
+            building: false,
+            trailing_fields: Vec::new(),
         };
         let old = serialize_old_layout(&idx);
         let (decoded, _used) = IndexDef::deserialize(&old).unwrap();
@@ -371,6 +725,47 @@ mod tests {
         assert!(!decoded.stats_num_bounds_known);
     }
 
+    #[test]
+    fn test_stop_fallback_roundtrip_and_legacy_default() {
+        let mut idx = IndexDef {
+            name: "fts_idx".to_string(),
+            table_name: "docs".to_string(),
+            column_names: vec!["body".to_string()],
+            index_type: IndexType::Fulltext,
+            is_unique: false,
+            btree_root: 88,
+            stats_distinct_keys: 0,
+            stats_num_min: 0,
+            stats_num_max: 0,
+            stats_num_bounds_known: false,
+            stats_num_hist_bins: Vec::new(),
+            fts_stop_filter: true,
+            fts_stop_df_ratio_ppm: 250_000,
+            fts_stop_fallback: FtsStopFallback::EmptyWithWarning,
+            fts_stop_fallback_max_docs: 500,
+            expressions: Vec::new(),
+            fill_factor: DEFAULT_FILL_FACTOR,
+            building: false,
+            trailing_fields: Vec::new(),
+        };
+        let (decoded, used) = IndexDef::deserialize(&idx.serialize()).unwrap();
+        assert_eq!(used, idx.serialize().len());
+        assert_eq!(decoded.fts_stop_fallback, FtsStopFallback::EmptyWithWarning);
+        assert_eq!(decoded.fts_stop_fallback_max_docs, 500);
+
+        // Records written before the extension default to a capped rescan.
+        idx.fts_stop_fallback = FtsStopFallback::Rescan;
+        let mut bytes = idx.serialize();
+        bytes.truncate(bytes.len() - 5);
+        let (decoded, _) = IndexDef::deserialize(&bytes).unwrap();
+        assert_eq!(decoded.fts_stop_fallback, FtsStopFallback::Rescan);
+        assert_eq!(
+            decoded.fts_stop_fallback_max_docs,
+            DEFAULT_STOP_FALLBACK_MAX_DOCS
+        );
+        assert!(decoded.fts_stop_filter);
+    }
+
     #[test]
     fn test_deserialize_truncated_index_returns_none() {
         let idx = IndexDef {
@@ -387,6 +782,12 @@ mod tests {
             stats_num_hist_bins: Vec::new(),
             fts_stop_filter: false,
             fts_stop_df_ratio_ppm: 0,
+            fts_stop_fallback: FtsStopFallback::Rescan,
+            fts_stop_fallback_max_docs: 0,
+            expressions: Vec::new(),
+            fill_factor: DEFAULT_FILL_FACTOR,
+            building: false,
+            trailing_fields: Vec::new(),
         };
         let mut bytes = idx.serialize();
         bytes.truncate(4);
@@ -0,0 +1,205 @@
+//! Catalog format versions and the migrations between them.
+//!
+//! The catalog stores its format version under `meta:catalog_version`; a
+//! catalog without the key predates versioning and is version 1. Opening a
+//! database read-write runs every migration between the stored version and
+//! [`CATALOG_VERSION`] in one WAL-protected transaction, which also writes
+//! the new version, so a crash or error leaves the database at the old
+//! version with nothing half-rewritten. A stored version newer than
+//! [`CATALOG_VERSION`] fails with [`MuroError::IncompatibleVersion`] instead
+//! of misreading records.
+//!
+//! To add a migration, bump [`CATALOG_VERSION`], add an entry to
+//! [`MIGRATIONS`], and handle the new version in [`apply_migration`].
+
+use crate::btree::ops::BTree;
+use crate::error::{MuroError, Result};
+use crate::schema::catalog::{SystemCatalog, INDEX_KEY_FORMAT_TERMINATED};
+use crate::schema::index::IndexType;
+use crate::sql::executor::{deserialize_row_versioned, rebuild_index, serialize_row};
+use crate::storage::page_store::PageStore;
+
+/// Catalog format version written by this library.
+pub const CATALOG_VERSION: u32 = 3;
+
+/// Version of a catalog that has no `meta:catalog_version` key.
+pub const UNVERSIONED_CATALOG_VERSION: u32 = 1;
+
+/// Each migration: the version it upgrades to and what it does.
+pub const MIGRATIONS: &[(u32, &str)] = &[
+    (2, "rewrite legacy rows with a column count prefix"),
+    (3, "rebuild non-unique indexes with terminated index keys"),
+];
+
+/// Rows rewritten per scan when upgrading a table's row format.
+const ROW_REWRITE_BATCH: usize = 1024;
+
+/// Fail if `stored` was written by a newer library.
+pub fn check_supported(stored: u32) -> Result<()> {
+    if stored > CATALOG_VERSION {
+        return Err(MuroError::IncompatibleVersion {
+            found: stored,
+            supported: CATALOG_VERSION,
+        });
+    }
+    Ok(())
+}
+
+/// Whether the catalog needs migrating, failing if it is too new to read.
+pub fn needs_migration(pager: &mut impl PageStore, catalog: &SystemCatalog) -> Result<bool> {
+    let stored = catalog.catalog_version(pager)?;
+    check_supported(stored)?;
+    Ok(stored < CATALOG_VERSION)
+}
+
+/// Run every migration from the stored version up to [`CATALOG_VERSION`]
+/// and record the new version. Returns the version migrated from.
+///
+/// The caller runs this inside one transaction: a failure partway must
+/// discard every write, including the ones of migrations that finished.
+pub fn migrate(store: &mut impl PageStore, catalog: &mut SystemCatalog) -> Result<u32> {
+    let from = catalog.catalog_version(store)?;
+    check_supported(from)?;
+    for (to, _) in MIGRATIONS.iter().filter(|(to, _)| *to > from) {
+        apply_migration(*to, store, catalog)?;
+    }
+    if from < CATALOG_VERSION {
+        catalog.set_catalog_version(store, CATALOG_VERSION)?;
+    }
+    Ok(from)
+}
+
+fn apply_migration(to: u32, store: &mut impl PageStore, catalog: &mut SystemCatalog) -> Result<()> {
+    match to {
+        2 => upgrade_legacy_row_format(store, catalog),
+        3 => rebuild_non_unique_indexes(store, catalog),
+        _ => Err(MuroError::Internal(format!(
+            "no catalog migration to version {}",
+            to
+        ))),
+    }
+}
+
+/// Version 2: tables still on row format 0 (rows without a column count
+/// prefix, from before `ADD COLUMN` could leave short rows) are rewritten in
+/// format 1, so readers no longer have to assume every row holds every
+/// column. Keys are unchanged, so secondary indexes stay valid.
+fn upgrade_legacy_row_format(
+    store: &mut impl PageStore,
+    catalog: &mut SystemCatalog,
+) -> Result<()> {
+    for table_name in catalog.list_tables(store)? {
+        let Some(mut table_def) = catalog.get_table(store, &table_name)? else {
+            continue;
+        };
+        if table_def.row_format_version >= 1 {
+            continue;
+        }
+        let mut data_btree = BTree::open(table_def.data_btree_root);
+        let mut last_key: Option<Vec<u8>> = None;
+        loop {
+            let mut batch = Vec::new();
+            let start = last_key.clone().unwrap_or_default();
+            data_btree.scan_from(store, &start, |k, v| {
+                if last_key.as_deref() == Some(k) {
+                    return Ok(true);
+                }
+                let values = deserialize_row_versioned(v, &table_def.columns, 0)?;
+                batch.push((k.to_vec(), values));
+                Ok(batch.len() < ROW_REWRITE_BATCH)
+            })?;
+            let Some((key, _)) = batch.last() else {
+                break;
+            };
+            last_key = Some(key.clone());
+            for (key, values) in &batch {
+                data_btree.insert(store, key, &serialize_row(values, &table_def.columns))?;
+            }
+        }
+        table_def.data_btree_root = data_btree.root_page_id();
+        table_def.row_format_version = 1;
+        catalog.update_table(store, &table_def)?;
+    }
+    Ok(())
+}
+
+/// Version 3: non-unique index entries were the index key followed by the
+/// primary key, so the entries of a variable-length key ran into those of
+/// keys it is a prefix of, and two rows could share an entry key. Tables
+/// move to [`INDEX_KEY_FORMAT_TERMINATED`], where the index key is
+/// byte-stuffed and terminated, and their non-unique B-tree indexes are
+/// rebuilt from the rows in that layout.
+fn rebuild_non_unique_indexes(
+    store: &mut impl PageStore,
+    catalog: &mut SystemCatalog,
+) -> Result<()> {
+    for table_name in catalog.list_tables(store)? {
+        let Some(mut table_def) = catalog.get_table(store, &table_name)? else {
+            continue;
+        };
+        if table_def.index_key_format == INDEX_KEY_FORMAT_TERMINATED {
+            continue;
+        }
+        table_def.index_key_format = INDEX_KEY_FORMAT_TERMINATED;
+        for mut idx in catalog.get_indexes_for_table(store, &table_name)? {
+            if idx.is_unique || idx.index_type != IndexType::BTree {
+                continue;
+            }
+            rebuild_index(&table_def, &mut idx, store, catalog)?;
+        }
+        catalog.update_table(store, &table_def)?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::crypto::aead::MasterKey;
+    use crate::storage::pager::Pager;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_new_catalog_is_current_and_newer_is_rejected() {
+        let dir = TempDir::new().unwrap();
+        let mut pager = Pager::create(&dir.path().join("t.db"), &MasterKey::new([7; 32])).unwrap();
+        let mut catalog = SystemCatalog::create(&mut pager).unwrap();
+        assert_eq!(
+            catalog.catalog_version(&mut pager).unwrap(),
+            CATALOG_VERSION
+        );
+        assert!(!needs_migration(&mut pager, &catalog).unwrap());
+
+        catalog.clear_catalog_version(&mut pager).unwrap();
+        assert_eq!(
+            catalog.catalog_version(&mut pager).unwrap(),
+            UNVERSIONED_CATALOG_VERSION
+        );
+        assert!(needs_migration(&mut pager, &catalog).unwrap());
+        assert_eq!(
+            migrate(&mut pager, &mut catalog).unwrap(),
+            UNVERSIONED_CATALOG_VERSION
+        );
+        assert_eq!(
+            catalog.catalog_version(&mut pager).unwrap(),
+            CATALOG_VERSION
+        );
+
+        catalog
+            .set_catalog_version(&mut pager, CATALOG_VERSION + 1)
+            .unwrap();
+        assert!(matches!(
+            needs_migration(&mut pager, &catalog),
+            Err(MuroError::IncompatibleVersion { found, supported })
+                if found == CATALOG_VERSION + 1 && supported == CATALOG_VERSION
+        ));
+        assert!(migrate(&mut pager, &mut catalog).is_err());
+    }
+
+    #[test]
+    fn test_migrations_cover_every_version() {
+        let versions: Vec<u32> = MIGRATIONS.iter().map(|(to, _)| *to).collect();
+        let expected: Vec<u32> = (UNVERSIONED_CATALOG_VERSION + 1..=CATALOG_VERSION).collect();
+        assert_eq!(versions, expected);
+    }
+}
@@ -1,3 +1,7 @@
 pub mod catalog;
 pub mod column;
+pub mod expectation;
+pub mod identifier;
 pub mod index;
+pub mod migrate;
+pub mod plan_baseline;
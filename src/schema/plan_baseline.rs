@@ -0,0 +1,188 @@
+/// Plan baselines: access paths pinned for a statement shape.
+///
+/// A baseline is stored in the catalog under `plan_baseline:<hash of shape>`
+/// and records the plan the planner chose when it was captured, plus the
+/// semantics version of the table it reads. The executor reuses the pinned
+/// access path for statements of the same shape until an index it names is
+/// dropped or the table definition changes.
+use std::fmt;
+
+const PLAN_BASELINE_FORMAT: u8 = 1;
+
+/// Access type of a single-table plan, named as in EXPLAIN's `type` column.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PlanAccess {
+    /// Primary key equality.
+    Const,
+    /// Secondary index equality.
+    Ref,
+    /// Secondary index range.
+    Range,
+    /// Full table scan.
+    All,
+    /// FULLTEXT index scan.
+    Fulltext,
+}
+
+impl PlanAccess {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            PlanAccess::Const => "const",
+            PlanAccess::Ref => "ref",
+            PlanAccess::Range => "range",
+            PlanAccess::All => "ALL",
+            PlanAccess::Fulltext => "fulltext",
+        }
+    }
+
+    fn from_tag(tag: u8) -> Option<Self> {
+        Some(match tag {
+            1 => PlanAccess::Const,
+            2 => PlanAccess::Ref,
+            3 => PlanAccess::Range,
+            4 => PlanAccess::All,
+            5 => PlanAccess::Fulltext,
+            _ => return None,
+        })
+    }
+
+    fn tag(self) -> u8 {
+        match self {
+            PlanAccess::Const => 1,
+            PlanAccess::Ref => 2,
+            PlanAccess::Range => 3,
+            PlanAccess::All => 4,
+            PlanAccess::Fulltext => 5,
+        }
+    }
+}
+
+impl fmt::Display for PlanAccess {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+/// What a single-table plan does: the EXPLAIN `table`, `type`, and `key`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PlanDescription {
+    pub table: String,
+    pub access: PlanAccess,
+    /// Secondary or FULLTEXT index the plan reads; `None` for primary key
+    /// seeks and full scans.
+    pub index: Option<String>,
+}
+
+impl fmt::Display for PlanDescription {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} on {}", self.access, self.table)?;
+        if let Some(index) = &self.index {
+            write!(f, " using {}", index)?;
+        }
+        Ok(())
+    }
+}
+
+/// A plan pinned for every statement of one shape.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PlanBaseline {
+    /// Normalized statement: table, index hints, and WHERE clause with
+    /// literals replaced by `?`.
+    pub shape: String,
+    pub plan: PlanDescription,
+    /// [`TableDef::semantics_version`](crate::schema::catalog::TableDef::semantics_version)
+    /// of the table when the plan was captured.
+    pub table_version: u64,
+}
+
+impl PlanBaseline {
+    /// Catalog key of the baseline for `shape`. Shapes can be long, so the
+    /// key holds a hash and the value repeats the full shape.
+    pub fn catalog_key(shape: &str) -> String {
+        format!("plan_baseline:{:016x}", fnv1a64(shape.as_bytes()))
+    }
+
+    pub fn serialize(&self) -> Vec<u8> {
+        let mut buf = vec![PLAN_BASELINE_FORMAT];
+        put_str(&mut buf, &self.shape);
+        put_str(&mut buf, &self.plan.table);
+        buf.push(self.plan.access.tag());
+        put_str(&mut buf, self.plan.index.as_deref().unwrap_or(""));
+        buf.extend_from_slice(&self.table_version.to_le_bytes());
+        buf
+    }
+
+    pub fn deserialize(data: &[u8]) -> Option<Self> {
+        let (&format, mut rest) = data.split_first()?;
+        if format != PLAN_BASELINE_FORMAT {
+            return None;
+        }
+        let shape = take_str(&mut rest)?;
+        let table = take_str(&mut rest)?;
+        let (&access, tail) = rest.split_first()?;
+        rest = tail;
+        let access = PlanAccess::from_tag(access)?;
+        let index = take_str(&mut rest)?;
+        let table_version = u64::from_le_bytes(rest.get(..8)?.try_into().ok()?);
+        Some(PlanBaseline {
+            shape,
+            plan: PlanDescription {
+                table,
+                access,
+                index: (!index.is_empty()).then_some(index),
+            },
+            table_version,
+        })
+    }
+}
+
+fn put_str(buf: &mut Vec<u8>, s: &str) {
+    buf.extend_from_slice(&(s.len() as u32).to_le_bytes());
+    buf.extend_from_slice(s.as_bytes());
+}
+
+fn take_str(data: &mut &[u8]) -> Option<String> {
+    let len = u32::from_le_bytes(data.get(..4)?.try_into().ok()?) as usize;
+    let bytes = data.get(4..4 + len)?;
+    let s = String::from_utf8(bytes.to_vec()).ok()?;
+    *data = &data[4 + len..];
+    Some(s)
+}
+
+/// 64-bit FNV-1a. Persisted, so it must not change between releases (unlike
+/// `std`'s default hasher).
+pub(crate) fn fnv1a64(bytes: &[u8]) -> u64 {
+    let mut hash: u64 = 0xcbf2_9ce4_8422_2325;
+    for &b in bytes {
+        hash ^= b as u64;
+        hash = hash.wrapping_mul(0x0100_0000_01b3);
+    }
+    hash
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_plan_baseline_roundtrip() {
+        for index in [Some("idx_a".to_string()), None] {
+            let baseline = PlanBaseline {
+                shape: "t WHERE a = ?".into(),
+                plan: PlanDescription {
+                    table: "t".into(),
+                    access: PlanAccess::Range,
+                    index,
+                },
+                table_version: 0xDEAD_BEEF,
+            };
+            let bytes = baseline.serialize();
+            assert_eq!(PlanBaseline::deserialize(&bytes), Some(baseline));
+            assert_eq!(PlanBaseline::deserialize(&bytes[..bytes.len() - 1]), None);
+        }
+        assert_ne!(
+            PlanBaseline::catalog_key("t WHERE a = ?"),
+            PlanBaseline::catalog_key("t WHERE b = ?")
+        );
+    }
+}
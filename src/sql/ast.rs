@@ -1,4 +1,6 @@
-use crate::types::DataType;
+use crate::fts::query::FtsStopFallback;
+use crate::schema::plan_baseline::PlanBaseline;
+use crate::types::{Collation, DataType};
 
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub enum IndexHintType {
@@ -28,6 +30,8 @@ pub enum Statement {
     Delete(Delete),
     ShowTables,
     ShowCreateTable(String),
+    /// `SHOW INDEXES FROM t` (alias `SHOW INDEX FROM t`).
+    ShowIndexes(String),
     Describe(String),
     Begin,
     Commit,
@@ -37,36 +41,73 @@ pub enum Statement {
     ReleaseSavepoint(String),
     SetQuery(Box<SetQuery>),
     Explain(Box<Statement>),
+    /// `EXPLAIN ANALYZE <stmt>`: execute and report per-stage actuals.
+    ExplainAnalyze(Box<Statement>),
     ShowCheckpointStats,
     ShowDatabaseStats,
-    SetRuntimeOption(SetRuntimeOption),
+    /// `SET name = value`: change a session setting. The name and value are
+    /// checked against the session's settings registry when it runs.
+    SetVariable(SetVariable),
+    /// `SHOW VARIABLES [LIKE 'pattern']`: current session settings.
+    ShowVariables(Option<String>),
+    /// Store a plan baseline; built by `Session::install_baseline`, no SQL syntax.
+    InstallPlanBaseline(Box<PlanBaseline>),
+    /// Remove the plan baseline of a shape; built by `Session::remove_baseline`.
+    RemovePlanBaseline(String),
+    ShowWarnings,
+    /// `SHOW PROCESSLIST`: what each session on the database file in this
+    /// process is running.
+    ShowProcesslist,
     AnalyzeTable(String),
+    CheckTable(String),
+    /// `SHOW TABLE STATUS`: row and page counts per table, and leaf
+    /// clustering per B-tree.
+    ShowTableStatus,
+    /// `OPTIMIZE TABLE t`: rebuild the table's B-trees into contiguous pages.
+    OptimizeTable(String),
+    /// `OPTIMIZE`: one pass of idle-time maintenance over the whole database.
+    Optimize,
 }
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
-#[allow(clippy::enum_variant_names)]
-pub enum RuntimeOption {
-    CheckpointTxThreshold,
-    CheckpointWalBytesThreshold,
-    CheckpointIntervalMs,
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SetVariable {
+    /// Setting name, lowercased.
+    pub name: String,
+    pub value: SetValue,
 }
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
-pub struct SetRuntimeOption {
-    pub option: RuntimeOption,
-    pub value: u64,
+/// Right-hand side of `SET name = value`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SetValue {
+    Integer(i64),
+    /// A quoted string or bare word (`ON`, `off`, `'skip'`), lowercased.
+    Word(String),
+}
+
+/// How full table scans react to pages or rows that cannot be read.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ScanCorruptionPolicy {
+    /// Fail the statement (default).
+    #[default]
+    Error,
+    /// Skip the unreadable part, record a warning, and keep scanning.
+    Skip,
 }
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum SetOp {
+    /// `UNION` / `UNION DISTINCT`: duplicate rows are removed.
     Union,
+    /// `UNION ALL`: every row is kept.
     UnionAll,
 }
 
+/// SELECTs joined by UNION operators, applied left to right.
 #[derive(Debug, Clone)]
 pub struct SetQuery {
     pub left: Select,
     pub ops: Vec<(SetOp, Select)>,
+    /// ORDER BY, LIMIT and OFFSET of the combined result.
     pub order_by: Option<Vec<OrderByItem>>,
     pub limit: Option<u64>,
     pub offset: Option<u64>,
@@ -80,12 +121,20 @@ pub enum AlterTableOp {
     ChangeColumn(String, ColumnSpec), // (old_name, new_spec)
     AddForeignKey(ForeignKeySpec),
     DropForeignKey(Vec<String>), // child column list
+    ExchangeWith(String),        // table whose contents are swapped in
+    /// `ADD [UNIQUE] INDEX|KEY [name] (...)`, run as the CREATE INDEX it
+    /// spells; an omitted name is generated by the parser.
+    AddIndex(CreateIndex),
+    /// `DROP INDEX|KEY name`; the index must belong to the altered table.
+    DropIndex(String),
+    RenameIndex(String, String), // (old_name, new_name)
 }
 
 #[derive(Debug, Clone)]
 pub struct AlterTable {
     pub table_name: String,
-    pub operation: AlterTableOp,
+    /// Comma-separated operations, applied in order within the statement.
+    pub operations: Vec<AlterTableOp>,
 }
 
 #[derive(Debug, Clone)]
@@ -98,6 +147,9 @@ pub struct RenameTable {
 pub enum TableConstraint {
     PrimaryKey(Vec<String>),
     Unique(Option<String>, Vec<String>), // (optional name, columns)
+    /// `KEY name (...)` / `UNIQUE KEY name (...)`: a named index created
+    /// along with the table, as if by a following CREATE INDEX.
+    Index(CreateIndex),
     ForeignKey {
         columns: Vec<String>,
         ref_table: String,
@@ -129,6 +181,13 @@ pub struct CreateTable {
     pub columns: Vec<ColumnSpec>,
     pub constraints: Vec<TableConstraint>,
     pub if_not_exists: bool,
+    /// `WITH (fill_factor = N)`, already range-checked.
+    pub fill_factor: Option<u8>,
+    /// `AUTO_INCREMENT = N`: the first value the counter hands out.
+    pub auto_increment: Option<i64>,
+    /// `CREATE TEMPORARY TABLE`: visible to this session only and dropped
+    /// when it ends.
+    pub temporary: bool,
 }
 
 #[derive(Debug, Clone)]
@@ -141,6 +200,8 @@ pub struct ColumnSpec {
     pub default_value: Option<Expr>,
     pub auto_increment: bool,
     pub check_expr: Option<Expr>,
+    /// `COLLATE <name>`; `None` means the default, binary.
+    pub collation: Option<Collation>,
     /// Parse order of column constraint expressions (DEFAULT / CHECK).
     pub constraint_expr_order: Vec<ColumnConstraintExprKind>,
 }
@@ -155,9 +216,15 @@ pub enum ColumnConstraintExprKind {
 pub struct CreateIndex {
     pub index_name: String,
     pub table_name: String,
+    /// Key part names; empty for expression parts.
     pub column_names: Vec<String>,
+    /// Expression key parts, aligned with `column_names`. Empty when every
+    /// key part is a plain column.
+    pub expressions: Vec<Option<Expr>>,
     pub is_unique: bool,
     pub if_not_exists: bool,
+    /// `WITH (fill_factor = N)`, already range-checked.
+    pub fill_factor: Option<u8>,
 }
 
 #[derive(Debug, Clone)]
@@ -172,12 +239,18 @@ pub struct CreateFulltextIndex {
     pub stop_filter: bool,
     /// Document-frequency threshold in parts-per-million (0..=1_000_000).
     pub stop_df_ratio_ppm: u32,
+    /// What to do when every query ngram is stop-filtered.
+    pub stop_fallback: FtsStopFallback,
+    /// Candidate-document cap for the fallback rescan (0 = unlimited).
+    pub stop_fallback_max_docs: u32,
 }
 
 #[derive(Debug, Clone)]
 pub struct DropTable {
     pub table_name: String,
     pub if_exists: bool,
+    /// `DROP TEMPORARY TABLE`: never drops a permanent table.
+    pub temporary: bool,
 }
 
 #[derive(Debug, Clone)]
@@ -245,6 +318,8 @@ pub struct Update {
     pub index_hints: Vec<IndexHint>,
     pub assignments: Vec<(String, Expr)>,
     pub where_clause: Option<Expr>,
+    pub order_by: Option<Vec<OrderByItem>>,
+    pub limit: Option<u64>,
 }
 
 #[derive(Debug, Clone)]
@@ -252,6 +327,8 @@ pub struct Delete {
     pub table_name: String,
     pub index_hints: Vec<IndexHint>,
     pub where_clause: Option<Expr>,
+    pub order_by: Option<Vec<OrderByItem>>,
+    pub limit: Option<u64>,
 }
 
 #[derive(Debug, Clone)]
@@ -277,6 +354,8 @@ pub enum Expr {
     Like {
         expr: Box<Expr>,
         pattern: Box<Expr>,
+        /// `ESCAPE` character expression; without one, `%` and `_` are always wildcards.
+        escape: Option<Box<Expr>>,
         negated: bool,
     },
     InList {
@@ -319,6 +398,13 @@ pub enum Expr {
         expr: Box<Expr>,
         target_type: DataType,
     },
+    /// `expr COLLATE name`: the value of `expr`, compared and sorted under
+    /// `collation`. Column references to collated columns are wrapped in
+    /// this before a statement's filters and sort keys are evaluated.
+    Collate {
+        expr: Box<Expr>,
+        collation: Collation,
+    },
     AggregateFunc {
         name: String,           // COUNT, SUM, AVG, MIN, MAX
         arg: Option<Box<Expr>>, // None for COUNT(*)
@@ -1,24 +1,163 @@
 /// Expression evaluator for WHERE clauses.
 use crate::error::{MuroError, Result};
-use crate::sql::ast::Expr;
-use crate::types::Value;
+use crate::sql::ast::{BinaryOp, Expr};
+use crate::types::{Collation, Value};
+use std::borrow::Cow;
 
 mod cast;
 mod compare;
 mod functions;
+mod memo;
 mod ops;
 mod pattern;
 
 use cast::eval_cast;
+pub(crate) use cast::{convert_value, validate_value};
 pub use compare::is_truthy;
 use compare::value_cmp;
 use functions::{eval_case_when, eval_function_call};
+pub use memo::ExprMemo;
 use ops::{eval_binary_op, eval_unary_op};
-use pattern::like_match;
+pub(crate) use pattern::like_match;
+use pattern::like_match_tokens;
+pub use pattern::{like_escape_char, like_tokens, LikeToken};
+
+/// `CAST(val AS target_type)`, with the engine's conversion rules.
+pub fn cast_value(val: &Value, target_type: &crate::types::DataType) -> Result<Value> {
+    eval_cast(val, target_type)
+}
 
 /// Evaluate an expression given a row's column values.
 /// `columns` maps column name -> Value.
 pub fn eval_expr(expr: &Expr, columns: &dyn Fn(&str) -> Option<Value>) -> Result<Value> {
+    eval_in(
+        expr,
+        &mut EvalEnv {
+            columns: Columns::Owned(columns),
+            memo: None,
+            fts: None,
+        },
+    )
+}
+
+/// Like [`eval_expr`], but `columns` borrows values from the row, so column
+/// references are only cloned where an expression returns them as is.
+pub fn eval_expr_ref<'r>(
+    expr: &Expr,
+    columns: &'r dyn Fn(&str) -> Option<&'r Value>,
+) -> Result<Value> {
+    eval_in(
+        expr,
+        &mut EvalEnv {
+            columns: Columns::Borrowed(columns),
+            memo: None,
+            fts: None,
+        },
+    )
+}
+
+/// Where column values come from.
+#[derive(Clone, Copy)]
+enum Columns<'r> {
+    /// Values cloned out by the caller's lookup.
+    Owned(&'r dyn Fn(&str) -> Option<Value>),
+    /// Values borrowed from the row.
+    Borrowed(&'r dyn Fn(&str) -> Option<&'r Value>),
+}
+
+/// State threaded through the evaluation of one expression tree.
+struct EvalEnv<'a, 'q, 'r> {
+    columns: Columns<'r>,
+    /// Per-row results of shared sub-expressions.
+    memo: Option<&'a mut ExprMemo<'q>>,
+    /// Value of MATCH ... AGAINST and fts_snippet() for the current row,
+    /// supplied by the executor.
+    fts: Option<&'a dyn Fn(&Expr) -> Value>,
+}
+
+impl<'r> EvalEnv<'_, '_, 'r> {
+    fn column(&self, name: &str) -> Result<Cow<'r, Value>> {
+        let value = match self.columns {
+            Columns::Owned(lookup) => lookup(name).map(Cow::Owned),
+            Columns::Borrowed(lookup) => lookup(name).map(Cow::Borrowed),
+        };
+        value.ok_or_else(|| MuroError::Execution(format!("Unknown column: {}", name)))
+    }
+}
+
+fn eval_in(expr: &Expr, env: &mut EvalEnv<'_, '_, '_>) -> Result<Value> {
+    if let Expr::InList { .. } = expr {
+        if let Some(value) = eval_in_set(expr, env)? {
+            return Ok(value);
+        }
+    }
+    let Some(id) = env.memo.as_ref().and_then(|memo| memo.id_of(expr)) else {
+        return eval_node(expr, env);
+    };
+    if let Some(value) = env.memo.as_ref().and_then(|memo| memo.cached(id)) {
+        return Ok(value.clone());
+    }
+    let value = eval_node(expr, env)?;
+    if let Some(memo) = env.memo.as_mut() {
+        memo.store(id, value.clone());
+    }
+    Ok(value)
+}
+
+/// `column IN (<literals>)` answered by the memo's hash set of the list;
+/// `None` when it has none or the column value is not of the set's type.
+fn eval_in_set(node: &Expr, env: &mut EvalEnv<'_, '_, '_>) -> Result<Option<Value>> {
+    let Expr::InList { expr, negated, .. } = node else {
+        return Ok(None);
+    };
+    if !env.memo.as_ref().is_some_and(|memo| memo.has_in_set(node)) {
+        return Ok(None);
+    }
+    let value = eval_operand(expr, env)?;
+    if value.is_null() {
+        return Ok(Some(Value::Null));
+    }
+    Ok(env
+        .memo
+        .as_ref()
+        .and_then(|memo| memo.in_set_contains(node, &value))
+        .map(|found| Value::Integer((found != *negated) as i64)))
+}
+
+/// Evaluate an operand, borrowing a column reference's value from the row.
+fn eval_operand<'r>(expr: &Expr, env: &mut EvalEnv<'_, '_, 'r>) -> Result<Cow<'r, Value>> {
+    match expr {
+        Expr::ColumnRef(name) => env.column(name),
+        Expr::Collate { expr, .. } => eval_operand(expr, env),
+        _ => eval_in(expr, env).map(Cow::Owned),
+    }
+}
+
+/// Collation a comparison of `operands` runs under: the first operand's
+/// `COLLATE`, explicit or from its column. `None` for byte-wise comparison.
+fn comparison_collation(operands: &[&Expr]) -> Option<Collation> {
+    operands
+        .iter()
+        .find_map(|e| match e {
+            Expr::Collate { collation, .. } => Some(*collation),
+            _ => None,
+        })
+        .filter(|c| *c != Collation::Binary)
+}
+
+/// `value` as compared under `collation`.
+fn collated<'v>(value: Cow<'v, Value>, collation: Option<Collation>) -> Cow<'v, Value> {
+    let Some(collation) = collation else {
+        return value;
+    };
+    let folded = match collation.key_value(&value) {
+        Cow::Owned(folded) => Some(folded),
+        Cow::Borrowed(_) => None,
+    };
+    folded.map_or(value, Cow::Owned)
+}
+
+fn eval_node(expr: &Expr, env: &mut EvalEnv<'_, '_, '_>) -> Result<Value> {
     match expr {
         Expr::BindParam => Err(MuroError::Execution(
             "Unbound parameter in expression; use prepared statement binding".into(),
@@ -30,32 +169,48 @@ pub fn eval_expr(expr: &Expr, columns: &dyn Fn(&str) -> Option<Value>) -> Result
         Expr::Null => Ok(Value::Null),
         Expr::DefaultValue => Ok(Value::Null), // handled by executor before eval
 
-        Expr::ColumnRef(name) => {
-            columns(name).ok_or_else(|| MuroError::Execution(format!("Unknown column: {}", name)))
-        }
+        Expr::ColumnRef(name) => env.column(name).map(Cow::into_owned),
 
         Expr::BinaryOp { left, op, right } => {
-            let lval = eval_expr(left, columns)?;
-            let rval = eval_expr(right, columns)?;
-            eval_binary_op(&lval, *op, &rval)
+            let lval = eval_operand(left, env)?;
+            let rval = eval_operand(right, env)?;
+            let collation = match op {
+                BinaryOp::Eq
+                | BinaryOp::Ne
+                | BinaryOp::Lt
+                | BinaryOp::Gt
+                | BinaryOp::Le
+                | BinaryOp::Ge => comparison_collation(&[left, right]),
+                _ => None,
+            };
+            eval_binary_op(&collated(lval, collation), *op, &collated(rval, collation))
         }
 
         Expr::UnaryOp { op, operand } => {
-            let val = eval_expr(operand, columns)?;
+            let val = eval_operand(operand, env)?;
             eval_unary_op(*op, &val)
         }
 
         Expr::Like {
             expr,
             pattern,
+            escape,
             negated,
         } => {
-            let val = eval_expr(expr, columns)?;
-            let pat = eval_expr(pattern, columns)?;
-            match (&val, &pat) {
+            let collation = comparison_collation(&[expr, pattern]);
+            let val = collated(eval_operand(expr, env)?, collation);
+            let pat = collated(eval_operand(pattern, env)?, collation);
+            let escape = match escape {
+                Some(e) => match eval_in(e, env)? {
+                    Value::Null => return Ok(Value::Null),
+                    v => like_escape_char(&v)?,
+                },
+                None => None,
+            };
+            match (&*val, &*pat) {
                 (Value::Null, _) | (_, Value::Null) => Ok(Value::Null),
                 (Value::Varchar(s), Value::Varchar(p)) => {
-                    let matches = like_match(s, p);
+                    let matches = like_match_tokens(s, &like_tokens(p, escape));
                     let result = if *negated { !matches } else { matches };
                     Ok(Value::Integer(if result { 1 } else { 0 }))
                 }
@@ -68,14 +223,15 @@ pub fn eval_expr(expr: &Expr, columns: &dyn Fn(&str) -> Option<Value>) -> Result
             list,
             negated,
         } => {
-            let val = eval_expr(expr, columns)?;
+            let collation = comparison_collation(&[expr]);
+            let val = collated(eval_operand(expr, env)?, collation);
             if val.is_null() {
                 return Ok(Value::Null);
             }
             let mut found = false;
             let mut has_null = false;
             for item in list {
-                let item_val = eval_expr(item, columns)?;
+                let item_val = collated(eval_operand(item, env)?, collation);
                 if item_val.is_null() {
                     has_null = true;
                     continue;
@@ -104,9 +260,10 @@ pub fn eval_expr(expr: &Expr, columns: &dyn Fn(&str) -> Option<Value>) -> Result
             high,
             negated,
         } => {
-            let val = eval_expr(expr, columns)?;
-            let low_val = eval_expr(low, columns)?;
-            let high_val = eval_expr(high, columns)?;
+            let collation = comparison_collation(&[expr, low, high]);
+            let val = collated(eval_operand(expr, env)?, collation);
+            let low_val = collated(eval_operand(low, env)?, collation);
+            let high_val = collated(eval_operand(high, env)?, collation);
             if val.is_null() || low_val.is_null() || high_val.is_null() {
                 return Ok(Value::Null);
             }
@@ -124,25 +281,27 @@ pub fn eval_expr(expr: &Expr, columns: &dyn Fn(&str) -> Option<Value>) -> Result
         }
 
         Expr::IsNull { expr, negated } => {
-            let val = eval_expr(expr, columns)?;
+            let val = eval_operand(expr, env)?;
             let is_null = val.is_null();
             let result = if *negated { !is_null } else { is_null };
             Ok(Value::Integer(if result { 1 } else { 0 }))
         }
 
-        Expr::FunctionCall { name, args } => eval_function_call(name, args, columns),
+        Expr::FunctionCall { name, args } => eval_function_call(name, args, env),
 
         Expr::CaseWhen {
             operand,
             when_clauses,
             else_clause,
-        } => eval_case_when(operand, when_clauses, else_clause, columns),
+        } => eval_case_when(operand, when_clauses, else_clause, env),
 
         Expr::Cast { expr, target_type } => {
-            let val = eval_expr(expr, columns)?;
+            let val = eval_operand(expr, env)?;
             eval_cast(&val, target_type)
         }
 
+        Expr::Collate { expr, .. } => eval_operand(expr, env).map(Cow::into_owned),
+
         Expr::AggregateFunc { .. } => {
             // Aggregate functions are evaluated by the executor's aggregation pipeline,
             // not by eval_expr. If we reach here, the aggregate value should have been
@@ -154,17 +313,19 @@ pub fn eval_expr(expr: &Expr, columns: &dyn Fn(&str) -> Option<Value>) -> Result
 
         Expr::MatchAgainst { .. } => {
             // FTS scoring - actual evaluation happens in the executor
-            Ok(Value::Integer(0))
+            Ok(env.fts.map_or(Value::Integer(0), |fts| fts(expr)))
         }
 
         Expr::FtsSnippet { .. } => {
             // FTS snippet - handled in executor
-            Ok(Value::Varchar(String::new()))
+            Ok(env
+                .fts
+                .map_or_else(|| Value::Varchar(String::new()), |fts| fts(expr)))
         }
 
         Expr::GreaterThanZero(inner) => {
-            let val = eval_expr(inner, columns)?;
-            match val {
+            let val = eval_operand(inner, env)?;
+            match *val {
                 Value::Integer(n) => Ok(Value::Integer(if n > 0 { 1 } else { 0 })),
                 Value::Float(n) => Ok(Value::Integer(if n > 0.0 { 1 } else { 0 })),
                 _ => Ok(Value::Integer(0)),
@@ -177,10 +338,11 @@ pub fn eval_expr(expr: &Expr, columns: &dyn Fn(&str) -> Option<Value>) -> Result
         ),
     }
 }
+
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::sql::ast::{BinaryOp, UnaryOp};
+    use crate::sql::ast::UnaryOp;
     use crate::types::DataType;
 
     #[test]
@@ -251,6 +413,7 @@ mod tests {
         let expr = Expr::Like {
             expr: Box::new(Expr::StringLiteral("hello world".into())),
             pattern: Box::new(Expr::StringLiteral("%world".into())),
+            escape: None,
             negated: false,
         };
         assert_eq!(eval_expr(&expr, &lookup).unwrap(), Value::Integer(1));
@@ -258,6 +421,7 @@ mod tests {
         let expr = Expr::Like {
             expr: Box::new(Expr::StringLiteral("hello".into())),
             pattern: Box::new(Expr::StringLiteral("h_llo".into())),
+            escape: None,
             negated: false,
         };
         assert_eq!(eval_expr(&expr, &lookup).unwrap(), Value::Integer(1));
@@ -265,11 +429,39 @@ mod tests {
         let expr = Expr::Like {
             expr: Box::new(Expr::StringLiteral("hello".into())),
             pattern: Box::new(Expr::StringLiteral("world".into())),
+            escape: None,
             negated: false,
         };
         assert_eq!(eval_expr(&expr, &lookup).unwrap(), Value::Integer(0));
     }
 
+    #[test]
+    fn test_eval_like_escape() {
+        let lookup = |_: &str| -> Option<Value> { None };
+        let like = |s: &str, p: &str, e: Expr| {
+            eval_expr(
+                &Expr::Like {
+                    expr: Box::new(Expr::StringLiteral(s.into())),
+                    pattern: Box::new(Expr::StringLiteral(p.into())),
+                    escape: Some(Box::new(e)),
+                    negated: false,
+                },
+                &lookup,
+            )
+        };
+        let esc = |e: &str| Expr::StringLiteral(e.into());
+        assert_eq!(like("50%", "50!%", esc("!")).unwrap(), Value::Integer(1));
+        assert_eq!(like("500", "50!%", esc("!")).unwrap(), Value::Integer(0));
+        assert_eq!(like("a_b", "a!_b", esc("!")).unwrap(), Value::Integer(1));
+        assert_eq!(like("a!", "a!!", esc("!")).unwrap(), Value::Integer(1));
+        // A trailing escape matches itself; an empty escape disables escaping.
+        assert_eq!(like("a!", "a!", esc("!")).unwrap(), Value::Integer(1));
+        assert_eq!(like("a!x", "a!%", esc("")).unwrap(), Value::Integer(1));
+        assert_eq!(like("a", "a", Expr::Null).unwrap(), Value::Null);
+        assert!(like("a", "a", esc("ab")).is_err());
+        assert!(like("a", "a", Expr::IntLiteral(1)).is_err());
+    }
+
     #[test]
     fn test_eval_in_list() {
         let lookup = |_: &str| -> Option<Value> { None };
@@ -1,228 +1,323 @@
 use crate::error::{MuroError, Result};
 use crate::types::{
-    format_date, format_datetime, format_uuid, parse_date_string, parse_datetime_string,
-    parse_timestamp_string, parse_uuid_string, DataType, Value,
+    format_date, format_datetime, format_float, format_uuid, parse_date_string,
+    parse_datetime_string, parse_timestamp_string, parse_uuid_string, DataType, Value,
 };
 use rust_decimal::prelude::ToPrimitive;
+use rust_decimal::Decimal;
 use serde_json::Value as JsonValue;
+use std::str::FromStr;
 
+/// `CAST(val AS target_type)`: [`convert_value`], then a check that the
+/// result fits the target, so a narrowing cast fails instead of keeping a
+/// value the type cannot hold.
 pub(super) fn eval_cast(val: &Value, target_type: &DataType) -> Result<Value> {
-    const I64_MIN_F64: f64 = -9_223_372_036_854_775_808.0; // -2^63
-    const I64_UPPER_EXCLUSIVE_F64: f64 = 9_223_372_036_854_775_808.0; // 2^63
-
-    fn float_to_i64_checked(n: f64) -> Result<i64> {
-        if !n.is_finite() {
-            return Err(MuroError::Execution(format!(
-                "Cannot cast non-finite float '{}' to integer",
-                n
-            )));
-        }
-        if !(I64_MIN_F64..I64_UPPER_EXCLUSIVE_F64).contains(&n) {
-            return Err(MuroError::Execution(format!(
-                "Float '{}' out of range for integer cast",
-                n
-            )));
-        }
-        Ok(n as i64)
-    }
+    let converted = convert_value(val, *target_type)?;
+    validate_value(&converted, target_type).map_err(|e| match e {
+        MuroError::Execution(msg) => MuroError::Execution(format!(
+            "Cannot cast {} to {}: {}",
+            describe(val),
+            target_type,
+            msg
+        )),
+        other => other,
+    })?;
+    Ok(converted)
+}
 
-    fn float_checked(n: f64, target_type: &DataType) -> Result<f64> {
-        if !n.is_finite() {
-            return Err(MuroError::Execution(format!(
-                "Cannot cast non-finite float '{}' to {}",
-                n, target_type
-            )));
-        }
-        if *target_type == DataType::Float && (n < f32::MIN as f64 || n > f32::MAX as f64) {
-            return Err(MuroError::Execution(format!(
-                "Float '{}' out of range for FLOAT",
-                n
-            )));
-        }
-        Ok(n)
-    }
+/// Convert `value` to `target_type`. CAST and the coercion of values stored
+/// into a column (INSERT, UPDATE, ALTER TABLE ... MODIFY) both use this, so
+/// they always agree:
+///
+/// - Strings convert to numbers, dates, times and UUIDs only when the whole
+///   string, with surrounding whitespace trimmed, is one; `'12abc'` is an
+///   error, not `12`.
+/// - FLOAT, DOUBLE and DECIMAL convert to integers by truncating toward
+///   zero; DECIMAL targets round to their scale.
+/// - VARBINARY converts to VARCHAR, TEXT and JSONB only when it is valid
+///   UTF-8, and to UUID only when it is 16 bytes long.
+///
+/// Range and length limits of the target (TINYINT, VARCHAR(n), ...) are
+/// checked by [`validate_value`], not here.
+pub(crate) fn convert_value(value: &Value, target_type: DataType) -> Result<Value> {
+    match (value, target_type) {
+        (Value::Null, _) => Ok(Value::Null),
 
-    if val.is_null() {
-        return Ok(Value::Null);
-    }
-    match target_type {
-        DataType::TinyInt | DataType::SmallInt | DataType::Int | DataType::BigInt => match val {
-            Value::Integer(n) => Ok(Value::Integer(*n)),
-            Value::Float(n) => Ok(Value::Integer(float_to_i64_checked(*n)?)),
-            Value::Decimal(d) => {
-                let truncated = d.trunc();
-                truncated.to_i64().map(Value::Integer).ok_or_else(|| {
-                    MuroError::Execution(format!("Decimal '{}' out of range for integer cast", d))
-                })
-            }
-            Value::Varchar(s) => {
-                let n: i64 = s
+        (_, DataType::TinyInt | DataType::SmallInt | DataType::Int | DataType::BigInt) => {
+            match value {
+                Value::Integer(n) => Ok(Value::Integer(*n)),
+                Value::Float(n) => Ok(Value::Integer(float_to_i64(*n)?)),
+                Value::Decimal(d) => d.trunc().to_i64().map(Value::Integer).ok_or_else(|| {
+                    MuroError::Execution(format!("Decimal '{}' out of range for integer", d))
+                }),
+                Value::Varchar(s) => s
                     .trim()
                     .parse()
-                    .map_err(|_| MuroError::Execution(format!("Cannot cast '{}' to integer", s)))?;
-                Ok(Value::Integer(n))
+                    .map(Value::Integer)
+                    .map_err(|_| cannot_convert(value, target_type)),
+                _ => Err(cannot_convert(value, target_type)),
             }
-            _ => Err(MuroError::Execution(format!(
-                "Cannot cast {:?} to integer",
-                val
-            ))),
-        },
-        DataType::Float | DataType::Double => match val {
-            Value::Integer(n) => Ok(Value::Float(float_checked(*n as f64, target_type)?)),
-            Value::Float(n) => Ok(Value::Float(float_checked(*n, target_type)?)),
-            Value::Decimal(d) => {
-                let n = d.to_f64().ok_or_else(|| {
-                    MuroError::Execution(format!("Cannot cast Decimal '{}' to float", d))
-                })?;
-                Ok(Value::Float(float_checked(n, target_type)?))
-            }
-            Value::Varchar(s) => {
-                let n: f64 = s
+        }
+
+        (_, DataType::Float | DataType::Double) => {
+            let n = match value {
+                Value::Integer(n) => *n as f64,
+                Value::Float(n) => *n,
+                Value::Decimal(d) => d
+                    .to_f64()
+                    .ok_or_else(|| cannot_convert(value, target_type))?,
+                Value::Varchar(s) => s
                     .trim()
                     .parse()
-                    .map_err(|_| MuroError::Execution(format!("Cannot cast '{}' to float", s)))?;
-                Ok(Value::Float(float_checked(n, target_type)?))
-            }
-            _ => Err(MuroError::Execution(format!(
-                "Cannot cast {:?} to float",
-                val
-            ))),
-        },
-        DataType::Decimal(p, s) => {
-            let d = match val {
-                Value::Integer(n) => rust_decimal::Decimal::from(*n),
-                Value::Float(n) => {
-                    use std::str::FromStr;
-                    rust_decimal::Decimal::from_str(&n.to_string()).map_err(|_| {
-                        MuroError::Execution(format!("Cannot cast float '{}' to DECIMAL", n))
-                    })?
-                }
+                    .map_err(|_| cannot_convert(value, target_type))?,
+                _ => return Err(cannot_convert(value, target_type)),
+            };
+            Ok(Value::Float(checked_float(n, target_type)?))
+        }
+
+        (_, DataType::Decimal(p, s)) => {
+            let d = match value {
+                Value::Integer(n) => Decimal::from(*n),
+                // Through the shortest text, to avoid binary artifacts.
+                Value::Float(n) => Decimal::from_str(&n.to_string())
+                    .map_err(|_| cannot_convert(value, target_type))?,
                 Value::Decimal(d) => *d,
                 Value::Varchar(sv) => {
-                    use std::str::FromStr;
-                    rust_decimal::Decimal::from_str(sv.trim()).map_err(|_| {
-                        MuroError::Execution(format!("Cannot cast '{}' to DECIMAL", sv))
-                    })?
-                }
-                _ => {
-                    return Err(MuroError::Execution(format!(
-                        "Cannot cast {:?} to DECIMAL",
-                        val
-                    )))
+                    Decimal::from_str(sv.trim()).map_err(|_| cannot_convert(value, target_type))?
                 }
+                _ => return Err(cannot_convert(value, target_type)),
             };
-            // Round to declared scale, set exact scale, and validate precision
-            let mut rounded = d.round_dp(*s);
-            rounded.rescale(*s);
-            let max_int_digits = p - s;
-            let int_part = rounded.trunc().abs();
-            let int_digits = if int_part.is_zero() {
-                0u32
-            } else {
-                int_part.to_string().len() as u32
-            };
-            if int_digits > max_int_digits {
-                return Err(MuroError::Execution(format!(
-                    "Value '{}' out of range for DECIMAL({},{})",
-                    d, p, s
-                )));
-            }
-            Ok(Value::Decimal(rounded))
+            Ok(Value::Decimal(fit_decimal(d, p, s)?))
         }
-        DataType::Date => match val {
+
+        (_, DataType::Date) => match value {
             Value::Date(d) => Ok(Value::Date(*d)),
-            Value::DateTime(dt) => Ok(Value::Date((*dt / 1_000_000) as i32)),
-            Value::Timestamp(ts) => Ok(Value::Date((*ts / 1_000_000) as i32)),
-            Value::Varchar(s) => parse_date_string(s)
+            Value::DateTime(t) | Value::Timestamp(t) => Ok(Value::Date((*t / 1_000_000) as i32)),
+            Value::Varchar(s) => parse_date_string(s.trim())
                 .map(Value::Date)
-                .ok_or_else(|| MuroError::Execution(format!("Cannot cast '{}' to DATE", s))),
-            _ => Err(MuroError::Execution(format!(
-                "Cannot cast {:?} to DATE",
-                val
-            ))),
+                .ok_or_else(|| cannot_convert(value, target_type)),
+            _ => Err(cannot_convert(value, target_type)),
         },
-        DataType::DateTime => match val {
+        (_, DataType::DateTime) => match value {
             Value::Date(d) => Ok(Value::DateTime((*d as i64) * 1_000_000)),
-            Value::DateTime(dt) => Ok(Value::DateTime(*dt)),
-            Value::Timestamp(ts) => Ok(Value::DateTime(*ts)),
-            Value::Varchar(s) => parse_datetime_string(s)
+            Value::DateTime(t) | Value::Timestamp(t) => Ok(Value::DateTime(*t)),
+            Value::Varchar(s) => parse_datetime_string(s.trim())
                 .map(Value::DateTime)
-                .ok_or_else(|| MuroError::Execution(format!("Cannot cast '{}' to DATETIME", s))),
-            _ => Err(MuroError::Execution(format!(
-                "Cannot cast {:?} to DATETIME",
-                val
-            ))),
+                .ok_or_else(|| cannot_convert(value, target_type)),
+            _ => Err(cannot_convert(value, target_type)),
         },
-        DataType::Timestamp => match val {
+        (_, DataType::Timestamp) => match value {
             Value::Date(d) => Ok(Value::Timestamp((*d as i64) * 1_000_000)),
-            Value::DateTime(dt) => Ok(Value::Timestamp(*dt)),
-            Value::Timestamp(ts) => Ok(Value::Timestamp(*ts)),
-            Value::Varchar(s) => parse_timestamp_string(s)
+            Value::DateTime(t) | Value::Timestamp(t) => Ok(Value::Timestamp(*t)),
+            Value::Varchar(s) => parse_timestamp_string(s.trim())
                 .map(Value::Timestamp)
-                .ok_or_else(|| MuroError::Execution(format!("Cannot cast '{}' to TIMESTAMP", s))),
-            _ => Err(MuroError::Execution(format!(
-                "Cannot cast {:?} to TIMESTAMP",
-                val
-            ))),
+                .ok_or_else(|| cannot_convert(value, target_type)),
+            _ => Err(cannot_convert(value, target_type)),
         },
-        DataType::Varchar(_) | DataType::Text => {
-            let s = match val {
-                Value::Date(d) => format_date(*d),
-                Value::DateTime(dt) => format_datetime(*dt),
-                Value::Timestamp(ts) => format_datetime(*ts),
-                Value::Uuid(b) => format_uuid(b),
-                _ => val.to_string(),
-            };
-            Ok(Value::Varchar(s))
+
+        (Value::Varbinary(b), DataType::Varchar(_) | DataType::Text) => {
+            String::from_utf8(b.clone())
+                .map(Value::Varchar)
+                .map_err(|_| {
+                    MuroError::Execution(format!("{} is not valid UTF-8", describe(value)))
+                })
         }
-        DataType::Jsonb => match val {
+        (_, DataType::Varchar(_) | DataType::Text) => Ok(Value::Varchar(text_of(value))),
+
+        (_, DataType::Jsonb) => match value {
             Value::Varchar(s) => Ok(Value::Varchar(canonicalize_json_text(s)?)),
             Value::Varbinary(b) => {
                 let s = std::str::from_utf8(b).map_err(|_| {
-                    MuroError::Execution("Cannot cast non-UTF8 VARBINARY to JSONB".into())
+                    MuroError::Execution(format!("{} is not valid UTF-8", describe(value)))
                 })?;
                 Ok(Value::Varchar(canonicalize_json_text(s)?))
             }
             Value::Date(_) | Value::DateTime(_) | Value::Timestamp(_) | Value::Uuid(_) => {
-                Ok(Value::Varchar(json_string_literal(&val.to_string())?))
+                Ok(Value::Varchar(json_string_literal(&text_of(value))?))
             }
-            _ => Ok(Value::Varchar(canonicalize_json_text(&val.to_string())?)),
+            _ => Ok(Value::Varchar(canonicalize_json_text(&text_of(value))?)),
         },
-        DataType::Varbinary(_) => match val {
+
+        (_, DataType::Varbinary(_)) => match value {
             Value::Varbinary(b) => Ok(Value::Varbinary(b.clone())),
             Value::Varchar(s) => Ok(Value::Varbinary(s.as_bytes().to_vec())),
             Value::Uuid(b) => Ok(Value::Varbinary(b.to_vec())),
-            _ => Err(MuroError::Execution(format!(
-                "Cannot cast {:?} to varbinary",
-                val
-            ))),
+            _ => Err(cannot_convert(value, target_type)),
         },
-        DataType::Uuid => match val {
+
+        (_, DataType::Uuid) => match value {
             Value::Uuid(b) => Ok(Value::Uuid(*b)),
-            Value::Varchar(s) => {
-                let bytes = parse_uuid_string(s)
-                    .ok_or_else(|| MuroError::Execution(format!("Cannot cast '{}' to UUID", s)))?;
-                Ok(Value::Uuid(bytes))
-            }
+            Value::Varchar(s) => parse_uuid_string(s.trim())
+                .map(Value::Uuid)
+                .ok_or_else(|| cannot_convert(value, target_type)),
             Value::Varbinary(b) => {
-                if b.len() != 16 {
-                    return Err(MuroError::Execution(format!(
-                        "VARBINARY must be 16 bytes to cast to UUID, got {}",
+                let bytes: [u8; 16] = b.as_slice().try_into().map_err(|_| {
+                    MuroError::Execution(format!(
+                        "VARBINARY must be 16 bytes to convert to UUID, got {}",
                         b.len()
-                    )));
-                }
-                let mut bytes = [0u8; 16];
-                bytes.copy_from_slice(b);
+                    ))
+                })?;
                 Ok(Value::Uuid(bytes))
             }
-            _ => Err(MuroError::Execution(format!(
-                "Cannot cast {:?} to UUID",
-                val
-            ))),
+            _ => Err(cannot_convert(value, target_type)),
         },
     }
 }
 
+/// Check that a value of `data_type` fits its range, length and format
+/// limits: integer widths, FLOAT range, VARCHAR(n) / VARBINARY(n) lengths,
+/// DECIMAL precision and JSON syntax.
+pub(crate) fn validate_value(value: &Value, data_type: &DataType) -> Result<()> {
+    match (value, data_type) {
+        (Value::Integer(n), DataType::TinyInt) if *n < -128 || *n > 127 => {
+            Err(MuroError::Execution(format!(
+                "Value {} out of range for TINYINT (-128 to 127)",
+                n
+            )))
+        }
+        (Value::Integer(n), DataType::SmallInt) if *n < -32768 || *n > 32767 => {
+            Err(MuroError::Execution(format!(
+                "Value {} out of range for SMALLINT (-32768 to 32767)",
+                n
+            )))
+        }
+        (Value::Integer(n), DataType::Int) if *n < i32::MIN as i64 || *n > i32::MAX as i64 => {
+            Err(MuroError::Execution(format!(
+                "Value {} out of range for INT ({} to {})",
+                n,
+                i32::MIN,
+                i32::MAX
+            )))
+        }
+        (Value::Float(n), DataType::Float) if !n.is_finite() => {
+            Err(MuroError::Execution("FLOAT must be a finite value".into()))
+        }
+        (Value::Float(n), DataType::Double) if !n.is_finite() => {
+            Err(MuroError::Execution("DOUBLE must be a finite value".into()))
+        }
+        (Value::Float(n), DataType::Float) if *n < f32::MIN as f64 || *n > f32::MAX as f64 => Err(
+            MuroError::Execution(format!("Value {} out of range for FLOAT", n)),
+        ),
+        (Value::Varchar(s), DataType::Varchar(Some(max))) if s.chars().count() as u32 > *max => {
+            Err(MuroError::Execution(format!(
+                "String length {} exceeds VARCHAR({})",
+                s.chars().count(),
+                max
+            )))
+        }
+        (Value::Varbinary(b), DataType::Varbinary(Some(max))) if b.len() as u32 > *max => {
+            Err(MuroError::Execution(format!(
+                "Binary length {} exceeds VARBINARY({})",
+                b.len(),
+                max
+            )))
+        }
+        (Value::Decimal(d), DataType::Decimal(p, s)) => {
+            if integer_digits(d) > p - s {
+                Err(MuroError::Execution(format!(
+                    "Value '{}' out of range for DECIMAL({},{})",
+                    d, p, s
+                )))
+            } else {
+                Ok(())
+            }
+        }
+        (Value::Varchar(s), DataType::Jsonb) => {
+            serde_json::from_str::<serde_json::Value>(s)
+                .map_err(|e| MuroError::Execution(format!("Invalid JSON: {}", e)))?;
+            Ok(())
+        }
+        _ => Ok(()),
+    }
+}
+
+/// Round `d` to `scale` and check that its integer digits fit
+/// `precision - scale`.
+fn fit_decimal(d: Decimal, precision: u32, scale: u32) -> Result<Decimal> {
+    let mut rounded = d.round_dp(scale);
+    // Exact scale, so that 42 becomes 42.00 for DECIMAL(10,2).
+    rounded.rescale(scale);
+    if integer_digits(&rounded) > precision - scale {
+        return Err(MuroError::Execution(format!(
+            "Value '{}' out of range for DECIMAL({},{})",
+            d, precision, scale
+        )));
+    }
+    Ok(rounded)
+}
+
+fn integer_digits(d: &Decimal) -> u32 {
+    let int_part = d.trunc().abs();
+    if int_part.is_zero() {
+        0
+    } else {
+        int_part.to_string().len() as u32
+    }
+}
+
+fn float_to_i64(n: f64) -> Result<i64> {
+    const I64_MIN_F64: f64 = -9_223_372_036_854_775_808.0; // -2^63
+    const I64_UPPER_EXCLUSIVE_F64: f64 = 9_223_372_036_854_775_808.0; // 2^63
+    if !n.is_finite() {
+        return Err(MuroError::Execution(format!(
+            "Cannot convert non-finite float '{}' to integer",
+            n
+        )));
+    }
+    if !(I64_MIN_F64..I64_UPPER_EXCLUSIVE_F64).contains(&n) {
+        return Err(MuroError::Execution(format!(
+            "Float '{}' out of range for integer",
+            n
+        )));
+    }
+    Ok(n as i64)
+}
+
+fn checked_float(n: f64, target_type: DataType) -> Result<f64> {
+    if !n.is_finite() {
+        return Err(MuroError::Execution(format!(
+            "Cannot convert non-finite float '{}' to {}",
+            n, target_type
+        )));
+    }
+    if target_type == DataType::Float && (n < f32::MIN as f64 || n > f32::MAX as f64) {
+        return Err(MuroError::Execution(format!(
+            "Float '{}' out of range for FLOAT",
+            n
+        )));
+    }
+    // -0.0 is stored as 0.0, matching its key encoding and equality.
+    Ok(if n == 0.0 { 0.0 } else { n })
+}
+
+/// Text form of a value converted to VARCHAR.
+fn text_of(value: &Value) -> String {
+    match value {
+        Value::Float(n) => format_float(*n),
+        Value::Date(d) => format_date(*d),
+        Value::DateTime(t) | Value::Timestamp(t) => format_datetime(*t),
+        Value::Uuid(b) => format_uuid(b),
+        _ => value.to_string(),
+    }
+}
+
+/// A value as it appears in an error message.
+fn describe(value: &Value) -> String {
+    match value {
+        Value::Varchar(s) => format!("'{}'", s),
+        Value::Varbinary(b) => format!("VARBINARY of {} bytes", b.len()),
+        _ => text_of(value),
+    }
+}
+
+fn cannot_convert(value: &Value, target_type: DataType) -> MuroError {
+    MuroError::Execution(format!(
+        "Cannot cast {} to {}",
+        describe(value),
+        target_type
+    ))
+}
+
 fn canonicalize_json_text(s: &str) -> Result<String> {
     let parsed: JsonValue = serde_json::from_str(s)
         .map_err(|e| MuroError::Execution(format!("Invalid JSON: {}", e)))?;
@@ -1,16 +1,18 @@
 use crate::error::{MuroError, Result};
 use crate::sql::ast::Expr;
 use crate::types::{parse_date_string, parse_timestamp_string, Value};
+use rust_decimal::prelude::ToPrimitive;
 use serde_json::Value as JsonValue;
 use std::time::{SystemTime, UNIX_EPOCH};
 
 use super::compare::{is_truthy, value_cmp};
-use super::eval_expr;
+use super::ops::finite_float;
+use super::{eval_in, EvalEnv};
 
 pub(super) fn eval_function_call(
     name: &str,
     args: &[Expr],
-    columns: &dyn Fn(&str) -> Option<Value>,
+    env: &mut EvalEnv<'_, '_, '_>,
 ) -> Result<Value> {
     match name {
         // Date/time functions
@@ -20,7 +22,7 @@ pub(super) fn eval_function_call(
         }
         "DATE_FORMAT" => {
             check_args(name, args, 2)?;
-            let vals = eval_args_null_check(args, columns)?;
+            let vals = eval_args_null_check(args, env)?;
             let vals = match vals {
                 Some(v) => v,
                 None => return Ok(Value::Null),
@@ -35,7 +37,7 @@ pub(super) fn eval_function_call(
         // NULL handling & conditional (these have special NULL semantics)
         "COALESCE" => {
             for arg in args {
-                let val = eval_expr(arg, columns)?;
+                let val = eval_in(arg, env)?;
                 if !val.is_null() {
                     return Ok(val);
                 }
@@ -44,17 +46,17 @@ pub(super) fn eval_function_call(
         }
         "IFNULL" => {
             check_args(name, args, 2)?;
-            let a = eval_expr(&args[0], columns)?;
+            let a = eval_in(&args[0], env)?;
             if !a.is_null() {
                 Ok(a)
             } else {
-                eval_expr(&args[1], columns)
+                eval_in(&args[1], env)
             }
         }
         "NULLIF" => {
             check_args(name, args, 2)?;
-            let a = eval_expr(&args[0], columns)?;
-            let b = eval_expr(&args[1], columns)?;
+            let a = eval_in(&args[0], env)?;
+            let b = eval_in(&args[1], env)?;
             if !a.is_null() && !b.is_null() && value_cmp(&a, &b) == Some(std::cmp::Ordering::Equal)
             {
                 Ok(Value::Null)
@@ -64,36 +66,40 @@ pub(super) fn eval_function_call(
         }
         "IF" => {
             check_args(name, args, 3)?;
-            let cond = eval_expr(&args[0], columns)?;
+            let cond = eval_in(&args[0], env)?;
             if is_truthy(&cond) {
-                eval_expr(&args[1], columns)
+                eval_in(&args[1], env)
             } else {
-                eval_expr(&args[2], columns)
+                eval_in(&args[2], env)
             }
         }
 
         // String functions (basic)
         "LENGTH" => {
             check_args(name, args, 1)?;
-            let val = eval_expr(&args[0], columns)?;
+            let val = eval_in(&args[0], env)?;
             if val.is_null() {
                 return Ok(Value::Null);
             }
+            // Non-string arguments are measured in their string form, as in MySQL.
             match &val {
-                Value::Varchar(s) => Ok(Value::Integer(s.len() as i64)),
                 Value::Varbinary(b) => Ok(Value::Integer(b.len() as i64)),
-                _ => Ok(Value::Null),
+                Value::Uuid(b) => Ok(Value::Integer(b.len() as i64)),
+                other => Ok(Value::Integer(other.to_string().len() as i64)),
             }
         }
         "CHAR_LENGTH" | "CHARACTER_LENGTH" => {
             check_args(name, args, 1)?;
-            let val = eval_expr(&args[0], columns)?;
+            let val = eval_in(&args[0], env)?;
             if val.is_null() {
                 return Ok(Value::Null);
             }
+            // Binary strings count bytes, everything else counts characters of
+            // its string form.
             match &val {
-                Value::Varchar(s) => Ok(Value::Integer(s.chars().count() as i64)),
-                _ => Ok(Value::Null),
+                Value::Varbinary(b) => Ok(Value::Integer(b.len() as i64)),
+                Value::Uuid(b) => Ok(Value::Integer(b.len() as i64)),
+                other => Ok(Value::Integer(other.to_string().chars().count() as i64)),
             }
         }
         "CONCAT" => {
@@ -104,7 +110,7 @@ pub(super) fn eval_function_call(
             }
             let mut result = String::new();
             for arg in args {
-                let val = eval_expr(arg, columns)?;
+                let val = eval_in(arg, env)?;
                 if val.is_null() {
                     return Ok(Value::Null);
                 }
@@ -112,6 +118,27 @@ pub(super) fn eval_function_call(
             }
             Ok(Value::Varchar(result))
         }
+        "CONCAT_WS" => {
+            if args.len() < 2 {
+                return Err(MuroError::Execution(
+                    "CONCAT_WS requires at least 2 arguments".into(),
+                ));
+            }
+            // A NULL separator yields NULL; NULL values after it are skipped.
+            let sep = eval_in(&args[0], env)?;
+            if sep.is_null() {
+                return Ok(Value::Null);
+            }
+            let sep = sep.to_string();
+            let mut parts = Vec::with_capacity(args.len() - 1);
+            for arg in &args[1..] {
+                let val = eval_in(arg, env)?;
+                if !val.is_null() {
+                    parts.push(val.to_string());
+                }
+            }
+            Ok(Value::Varchar(parts.join(&sep)))
+        }
         "SUBSTRING" | "SUBSTR" => {
             if args.len() < 2 || args.len() > 3 {
                 return Err(MuroError::Execution(format!(
@@ -119,7 +146,7 @@ pub(super) fn eval_function_call(
                     name
                 )));
             }
-            let vals = eval_args_null_check(args, columns)?;
+            let vals = eval_args_null_check(args, env)?;
             let vals = match vals {
                 Some(v) => v,
                 None => return Ok(Value::Null),
@@ -133,9 +160,10 @@ pub(super) fn eval_function_call(
             let start = if pos > 0 {
                 (pos - 1) as usize
             } else if pos < 0 {
-                let from_end = (-pos) as usize;
+                let from_end = pos.unsigned_abs() as usize;
                 if from_end > chars.len() {
-                    0
+                    // Reaching back past the start yields '' in MySQL.
+                    return Ok(Value::Varchar(String::new()));
                 } else {
                     chars.len() - from_end
                 }
@@ -146,10 +174,9 @@ pub(super) fn eval_function_call(
                 return Ok(Value::Varchar(String::new()));
             }
             let len = if args.len() == 3 {
-                vals[2]
+                let l = vals[2]
                     .as_i64()
                     .ok_or_else(|| MuroError::Execution("SUBSTRING len must be integer".into()))?;
-                let l = vals[2].as_i64().unwrap();
                 if l < 0 {
                     return Ok(Value::Varchar(String::new()));
                 }
@@ -157,12 +184,12 @@ pub(super) fn eval_function_call(
             } else {
                 chars.len() - start
             };
-            let end = (start + len).min(chars.len());
+            let end = start.saturating_add(len).min(chars.len());
             Ok(Value::Varchar(chars[start..end].iter().collect()))
         }
         "UPPER" => {
             check_args(name, args, 1)?;
-            let val = eval_expr(&args[0], columns)?;
+            let val = eval_in(&args[0], env)?;
             if val.is_null() {
                 return Ok(Value::Null);
             }
@@ -170,7 +197,7 @@ pub(super) fn eval_function_call(
         }
         "LOWER" => {
             check_args(name, args, 1)?;
-            let val = eval_expr(&args[0], columns)?;
+            let val = eval_in(&args[0], env)?;
             if val.is_null() {
                 return Ok(Value::Null);
             }
@@ -180,7 +207,7 @@ pub(super) fn eval_function_call(
         // String functions (extended)
         "TRIM" => {
             check_args(name, args, 1)?;
-            let val = eval_expr(&args[0], columns)?;
+            let val = eval_in(&args[0], env)?;
             if val.is_null() {
                 return Ok(Value::Null);
             }
@@ -188,7 +215,7 @@ pub(super) fn eval_function_call(
         }
         "LTRIM" => {
             check_args(name, args, 1)?;
-            let val = eval_expr(&args[0], columns)?;
+            let val = eval_in(&args[0], env)?;
             if val.is_null() {
                 return Ok(Value::Null);
             }
@@ -196,7 +223,7 @@ pub(super) fn eval_function_call(
         }
         "RTRIM" => {
             check_args(name, args, 1)?;
-            let val = eval_expr(&args[0], columns)?;
+            let val = eval_in(&args[0], env)?;
             if val.is_null() {
                 return Ok(Value::Null);
             }
@@ -204,7 +231,7 @@ pub(super) fn eval_function_call(
         }
         "REPLACE" => {
             check_args(name, args, 3)?;
-            let vals = eval_args_null_check(args, columns)?;
+            let vals = eval_args_null_check(args, env)?;
             let vals = match vals {
                 Some(v) => v,
                 None => return Ok(Value::Null),
@@ -212,11 +239,44 @@ pub(super) fn eval_function_call(
             let s = vals[0].to_string();
             let from = vals[1].to_string();
             let to = vals[2].to_string();
+            // An empty search string matches nothing, as in MySQL.
+            if from.is_empty() {
+                return Ok(Value::Varchar(s));
+            }
             Ok(Value::Varchar(s.replace(&from, &to)))
         }
+        "HEX" => {
+            check_args(name, args, 1)?;
+            let val = eval_in(&args[0], env)?;
+            let bytes = match &val {
+                Value::Null => return Ok(Value::Null),
+                // Numbers are rounded to an integer and printed as an unsigned
+                // 64-bit value, so HEX(-1) is FFFFFFFFFFFFFFFF.
+                Value::Integer(n) => return Ok(Value::Varchar(format!("{:X}", *n as u64))),
+                Value::Float(n) => {
+                    return Ok(Value::Varchar(format!("{:X}", n.round() as i64 as u64)))
+                }
+                Value::Decimal(d) => {
+                    let n = d.round().to_i64().unwrap_or(if d.is_sign_negative() {
+                        i64::MIN
+                    } else {
+                        i64::MAX
+                    });
+                    return Ok(Value::Varchar(format!("{:X}", n as u64)));
+                }
+                Value::Varbinary(b) => b.clone(),
+                Value::Uuid(b) => b.to_vec(),
+                other => other.to_string().into_bytes(),
+            };
+            let mut out = String::with_capacity(bytes.len() * 2);
+            for b in bytes {
+                out.push_str(&format!("{:02X}", b));
+            }
+            Ok(Value::Varchar(out))
+        }
         "REVERSE" => {
             check_args(name, args, 1)?;
-            let val = eval_expr(&args[0], columns)?;
+            let val = eval_in(&args[0], env)?;
             if val.is_null() {
                 return Ok(Value::Null);
             }
@@ -224,7 +284,7 @@ pub(super) fn eval_function_call(
         }
         "REPEAT" => {
             check_args(name, args, 2)?;
-            let vals = eval_args_null_check(args, columns)?;
+            let vals = eval_args_null_check(args, env)?;
             let vals = match vals {
                 Some(v) => v,
                 None => return Ok(Value::Null),
@@ -240,7 +300,7 @@ pub(super) fn eval_function_call(
         }
         "LEFT" => {
             check_args(name, args, 2)?;
-            let vals = eval_args_null_check(args, columns)?;
+            let vals = eval_args_null_check(args, env)?;
             let vals = match vals {
                 Some(v) => v,
                 None => return Ok(Value::Null),
@@ -256,7 +316,7 @@ pub(super) fn eval_function_call(
         }
         "RIGHT" => {
             check_args(name, args, 2)?;
-            let vals = eval_args_null_check(args, columns)?;
+            let vals = eval_args_null_check(args, env)?;
             let vals = match vals {
                 Some(v) => v,
                 None => return Ok(Value::Null),
@@ -274,7 +334,7 @@ pub(super) fn eval_function_call(
         }
         "LPAD" => {
             check_args(name, args, 3)?;
-            let vals = eval_args_null_check(args, columns)?;
+            let vals = eval_args_null_check(args, env)?;
             let vals = match vals {
                 Some(v) => v,
                 None => return Ok(Value::Null),
@@ -302,7 +362,7 @@ pub(super) fn eval_function_call(
         }
         "RPAD" => {
             check_args(name, args, 3)?;
-            let vals = eval_args_null_check(args, columns)?;
+            let vals = eval_args_null_check(args, env)?;
             let vals = match vals {
                 Some(v) => v,
                 None => return Ok(Value::Null),
@@ -329,7 +389,7 @@ pub(super) fn eval_function_call(
         }
         "INSTR" => {
             check_args(name, args, 2)?;
-            let vals = eval_args_null_check(args, columns)?;
+            let vals = eval_args_null_check(args, env)?;
             let vals = match vals {
                 Some(v) => v,
                 None => return Ok(Value::Null),
@@ -350,7 +410,7 @@ pub(super) fn eval_function_call(
                     "LOCATE requires 2 or 3 arguments".into(),
                 ));
             }
-            let vals = eval_args_null_check(args, columns)?;
+            let vals = eval_args_null_check(args, env)?;
             let vals = match vals {
                 Some(v) => v,
                 None => return Ok(Value::Null),
@@ -383,7 +443,7 @@ pub(super) fn eval_function_call(
         // REGEXP
         "REGEXP" | "REGEXP_LIKE" => {
             check_args(name, args, 2)?;
-            let vals = eval_args_null_check(args, columns)?;
+            let vals = eval_args_null_check(args, env)?;
             let vals = match vals {
                 Some(v) => v,
                 None => return Ok(Value::Null),
@@ -398,12 +458,15 @@ pub(super) fn eval_function_call(
         // Numeric functions
         "ABS" => {
             check_args(name, args, 1)?;
-            let val = eval_expr(&args[0], columns)?;
+            let val = eval_in(&args[0], env)?;
             if val.is_null() {
                 return Ok(Value::Null);
             }
             match val {
-                Value::Integer(n) => Ok(Value::Integer(n.abs())),
+                Value::Integer(n) => n
+                    .checked_abs()
+                    .map(Value::Integer)
+                    .ok_or_else(|| MuroError::Execution("Integer overflow in ABS".into())),
                 Value::Float(n) => Ok(Value::Float(n.abs())),
                 Value::Decimal(d) => Ok(Value::Decimal(d.abs())),
                 _ => Err(MuroError::Execution("ABS requires numeric argument".into())),
@@ -411,7 +474,7 @@ pub(super) fn eval_function_call(
         }
         "CEIL" | "CEILING" => {
             check_args(name, args, 1)?;
-            let val = eval_expr(&args[0], columns)?;
+            let val = eval_in(&args[0], env)?;
             if val.is_null() {
                 return Ok(Value::Null);
             }
@@ -427,7 +490,7 @@ pub(super) fn eval_function_call(
         }
         "FLOOR" => {
             check_args(name, args, 1)?;
-            let val = eval_expr(&args[0], columns)?;
+            let val = eval_in(&args[0], env)?;
             if val.is_null() {
                 return Ok(Value::Null);
             }
@@ -446,33 +509,69 @@ pub(super) fn eval_function_call(
                     "ROUND requires 1 or 2 arguments".into(),
                 ));
             }
-            let val = eval_expr(&args[0], columns)?;
-            if val.is_null() {
-                return Ok(Value::Null);
-            }
-            // Integer-only: ROUND is identity
-            match val {
-                Value::Integer(n) => Ok(Value::Integer(n)),
-                Value::Float(n) => {
-                    let scale = if args.len() == 2 {
-                        eval_expr(&args[1], columns)?.as_i64().ok_or_else(|| {
-                            MuroError::Execution("ROUND scale must be integer".into())
-                        })?
+            let vals = match eval_args_null_check(args, env)? {
+                Some(v) => v,
+                None => return Ok(Value::Null),
+            };
+            let scale = match vals.get(1) {
+                Some(v) => v
+                    .as_i64()
+                    .ok_or_else(|| MuroError::Execution("ROUND scale must be integer".into()))?,
+                None => 0,
+            };
+            match &vals[0] {
+                // A non-negative scale leaves an integer as is; a negative one
+                // rounds to tens, hundreds, ... half away from zero.
+                Value::Integer(n) => {
+                    if scale >= 0 {
+                        return Ok(Value::Integer(*n));
+                    }
+                    let Some(factor) = 10i128.checked_pow(scale.unsigned_abs().min(38) as u32)
+                    else {
+                        return Ok(Value::Integer(0));
+                    };
+                    let n = *n as i128;
+                    let half = factor / 2;
+                    let rounded = if n >= 0 {
+                        (n + half) / factor * factor
                     } else {
-                        0
+                        (n - half) / factor * factor
                     };
-                    let factor = 10f64.powi(scale as i32);
-                    Ok(Value::Float((n * factor).round() / factor))
+                    i64::try_from(rounded)
+                        .map(Value::Integer)
+                        .map_err(|_| MuroError::Execution("Integer overflow in ROUND".into()))
                 }
-                Value::Decimal(d) => {
-                    let scale = if args.len() == 2 {
-                        eval_expr(&args[1], columns)?.as_i64().ok_or_else(|| {
-                            MuroError::Execution("ROUND scale must be integer".into())
-                        })? as u32
+                Value::Float(n) => {
+                    let factor = 10f64.powi(scale.clamp(-400, 400) as i32);
+                    let rounded = if factor == 0.0 {
+                        0.0
                     } else {
-                        0
+                        (n * factor).round() / factor
                     };
-                    Ok(Value::Decimal(d.round_dp(scale)))
+                    // Past the precision of f64 rounding is the identity.
+                    Ok(Value::Float(if rounded.is_finite() { rounded } else { *n }))
+                }
+                Value::Decimal(d) => {
+                    if scale >= 0 {
+                        return Ok(Value::Decimal(d.round_dp_with_strategy(
+                            scale.min(28) as u32,
+                            rust_decimal::RoundingStrategy::MidpointAwayFromZero,
+                        )));
+                    }
+                    let exp = scale.unsigned_abs();
+                    if exp > 28 {
+                        return Ok(Value::Decimal(rust_decimal::Decimal::ZERO));
+                    }
+                    let factor =
+                        rust_decimal::Decimal::from_i128_with_scale(10i128.pow(exp as u32), 0);
+                    let rounded = (d / factor)
+                        .round_dp_with_strategy(
+                            0,
+                            rust_decimal::RoundingStrategy::MidpointAwayFromZero,
+                        )
+                        .checked_mul(factor)
+                        .ok_or_else(|| MuroError::Execution("Decimal overflow in ROUND".into()))?;
+                    Ok(Value::Decimal(rounded))
                 }
                 _ => Err(MuroError::Execution(
                     "ROUND requires numeric argument".into(),
@@ -481,7 +580,7 @@ pub(super) fn eval_function_call(
         }
         "MOD" => {
             check_args(name, args, 2)?;
-            let vals = eval_args_null_check(args, columns)?;
+            let vals = eval_args_null_check(args, env)?;
             let vals = match vals {
                 Some(v) => v,
                 None => return Ok(Value::Null),
@@ -491,7 +590,8 @@ pub(super) fn eval_function_call(
                     if *b == 0 {
                         return Err(MuroError::Execution("Division by zero".into()));
                     }
-                    Ok(Value::Integer(a % b))
+                    // i64::MIN % -1 overflows; the mathematical result is 0.
+                    Ok(Value::Integer(a.wrapping_rem(*b)))
                 }
                 (Value::Decimal(a), Value::Decimal(b)) => {
                     if b.is_zero() {
@@ -514,7 +614,7 @@ pub(super) fn eval_function_call(
         }
         "POWER" | "POW" => {
             check_args(name, args, 2)?;
-            let vals = eval_args_null_check(args, columns)?;
+            let vals = eval_args_null_check(args, env)?;
             let vals = match vals {
                 Some(v) => v,
                 None => return Ok(Value::Null),
@@ -531,9 +631,9 @@ pub(super) fn eval_function_call(
                         Ok(Value::Integer(result))
                     }
                 }
-                (base, exp) if base.as_f64().is_some() && exp.as_f64().is_some() => Ok(
-                    Value::Float(base.as_f64().unwrap().powf(exp.as_f64().unwrap())),
-                ),
+                (base, exp) if base.as_f64().is_some() && exp.as_f64().is_some() => {
+                    finite_float(base.as_f64().unwrap().powf(exp.as_f64().unwrap()))
+                }
                 _ => Err(MuroError::Execution(
                     "POWER requires numeric arguments".into(),
                 )),
@@ -541,7 +641,7 @@ pub(super) fn eval_function_call(
         }
         "JSON_EXTRACT" => {
             check_args(name, args, 2)?;
-            let vals = eval_args_null_check(args, columns)?;
+            let vals = eval_args_null_check(args, env)?;
             let vals = match vals {
                 Some(v) => v,
                 None => return Ok(Value::Null),
@@ -562,7 +662,7 @@ pub(super) fn eval_function_call(
         }
         "JSON_SET" => {
             check_args(name, args, 3)?;
-            let vals = eval_args_null_check(args, columns)?;
+            let vals = eval_args_null_check(args, env)?;
             let vals = match vals {
                 Some(v) => v,
                 None => return Ok(Value::Null),
@@ -576,7 +676,7 @@ pub(super) fn eval_function_call(
         }
         "JSON_REMOVE" => {
             check_args(name, args, 2)?;
-            let vals = eval_args_null_check(args, columns)?;
+            let vals = eval_args_null_check(args, env)?;
             let vals = match vals {
                 Some(v) => v,
                 None => return Ok(Value::Null),
@@ -589,7 +689,7 @@ pub(super) fn eval_function_call(
         }
         "JSON_TYPE" => {
             check_args(name, args, 1)?;
-            let val = eval_expr(&args[0], columns)?;
+            let val = eval_in(&args[0], env)?;
             if val.is_null() {
                 return Ok(Value::Null);
             }
@@ -607,7 +707,7 @@ pub(super) fn eval_function_call(
         }
         "JSON_CONTAINS" => {
             check_args(name, args, 2)?;
-            let vals = eval_args_null_check(args, columns)?;
+            let vals = eval_args_null_check(args, env)?;
             let vals = match vals {
                 Some(v) => v,
                 None => return Ok(Value::Null),
@@ -638,6 +738,12 @@ pub(super) fn eval_function_call(
             Ok(Value::Uuid(*uuid::Uuid::now_v7().as_bytes()))
         }
 
+        // Replaced by the attempted value before ON DUPLICATE KEY UPDATE
+        // assignments are evaluated; anywhere else it has no row to read.
+        "VALUES" => Err(MuroError::Execution(
+            "VALUES() is only allowed in ON DUPLICATE KEY UPDATE".into(),
+        )),
+
         _ => Err(MuroError::Execution(format!("Unknown function: {}", name))),
     }
 }
@@ -1052,11 +1158,11 @@ fn check_args(name: &str, args: &[Expr], expected: usize) -> Result<()> {
 /// Evaluate all args and return None if any is NULL.
 fn eval_args_null_check(
     args: &[Expr],
-    columns: &dyn Fn(&str) -> Option<Value>,
+    env: &mut EvalEnv<'_, '_, '_>,
 ) -> Result<Option<Vec<Value>>> {
     let mut vals = Vec::with_capacity(args.len());
     for arg in args {
-        let val = eval_expr(arg, columns)?;
+        let val = eval_in(arg, env)?;
         if val.is_null() {
             return Ok(None);
         }
@@ -1069,34 +1175,34 @@ pub(super) fn eval_case_when(
     operand: &Option<Box<Expr>>,
     when_clauses: &[(Expr, Expr)],
     else_clause: &Option<Box<Expr>>,
-    columns: &dyn Fn(&str) -> Option<Value>,
+    env: &mut EvalEnv<'_, '_, '_>,
 ) -> Result<Value> {
     match operand {
         Some(op_expr) => {
             // Simple CASE: CASE expr WHEN val THEN result ...
-            let op_val = eval_expr(op_expr, columns)?;
+            let op_val = eval_in(op_expr, env)?;
             for (when_expr, then_expr) in when_clauses {
-                let when_val = eval_expr(when_expr, columns)?;
+                let when_val = eval_in(when_expr, env)?;
                 if !op_val.is_null()
                     && !when_val.is_null()
                     && value_cmp(&op_val, &when_val) == Some(std::cmp::Ordering::Equal)
                 {
-                    return eval_expr(then_expr, columns);
+                    return eval_in(then_expr, env);
                 }
             }
         }
         None => {
             // Searched CASE: CASE WHEN condition THEN result ...
             for (cond_expr, then_expr) in when_clauses {
-                let cond_val = eval_expr(cond_expr, columns)?;
+                let cond_val = eval_in(cond_expr, env)?;
                 if is_truthy(&cond_val) {
-                    return eval_expr(then_expr, columns);
+                    return eval_in(then_expr, env);
                 }
             }
         }
     }
     match else_clause {
-        Some(else_expr) => eval_expr(else_expr, columns),
+        Some(else_expr) => eval_in(else_expr, env),
         None => Ok(Value::Null),
     }
 }
@@ -0,0 +1,437 @@
+//! Per-row results of sub-expressions shared within one query block.
+//!
+//! `SELECT price * qty AS total FROM t WHERE price * qty > 100` computes
+//! `price * qty` once for the WHERE check and again for the projection of
+//! every matching row. `ExprMemo` numbers the sub-expressions that occur more
+//! than once in a pre-pass over the block's expressions, and caches each
+//! one's value the first time a row computes it.
+//!
+//! Occurrences are matched on a key in which every column reference is
+//! replaced by the row slot it resolves to in the block's scope. In a join,
+//! `a.v * 2` and `b.v * 2` therefore never share a result, while `v * 2` and
+//! `a.v * 2` do when `v` is only a column of `a`.
+//!
+//! The memo also keeps a hash set of every long `column IN (...)` list of
+//! integer or string literals, so the test costs one lookup per row instead
+//! of a comparison per list item.
+
+use std::collections::{HashMap, HashSet};
+use std::fmt::Write;
+use std::marker::PhantomData;
+
+use super::{eval_in, Columns, EvalEnv};
+use crate::error::Result;
+use crate::sql::ast::{Expr, UnaryOp};
+use crate::sql::index_expr::NON_DETERMINISTIC_FUNCTIONS;
+use crate::types::Value;
+
+pub struct ExprMemo<'q> {
+    /// Address of each shared node -> its id. Nodes of the same sub-expression
+    /// share an id.
+    ids: HashMap<usize, usize>,
+    /// Result per id for the current row.
+    values: Vec<Option<Value>>,
+    /// Address of each long literal `IN` list node -> its values.
+    in_sets: HashMap<usize, InSet>,
+    /// The registered nodes must not move while their addresses are keys.
+    _exprs: PhantomData<&'q Expr>,
+}
+
+impl<'q> ExprMemo<'q> {
+    /// Assign ids to the sub-expressions of `exprs` that occur more than once.
+    /// `resolve` maps a column reference to its slot in the row, or `None`
+    /// if the name does not resolve (evaluation reports the error).
+    pub fn new<I>(exprs: I, resolve: &dyn Fn(&str) -> Option<usize>) -> Self
+    where
+        I: IntoIterator<Item = &'q Expr>,
+    {
+        let mut occurrences: HashMap<String, Vec<usize>> = HashMap::new();
+        let mut in_sets = HashMap::new();
+        for expr in exprs {
+            expr_key(expr, resolve, &mut occurrences, &mut in_sets);
+        }
+        let mut ids = HashMap::new();
+        let mut shared = 0;
+        for nodes in occurrences.into_values() {
+            if nodes.len() < 2 {
+                continue;
+            }
+            for node in nodes {
+                ids.insert(node, shared);
+            }
+            shared += 1;
+        }
+        ExprMemo {
+            ids,
+            values: vec![None; shared],
+            in_sets,
+            _exprs: PhantomData,
+        }
+    }
+
+    /// Number of distinct shared sub-expressions.
+    pub fn shared_count(&self) -> usize {
+        self.values.len()
+    }
+
+    /// Forget the previous row's results.
+    pub fn start_row(&mut self) {
+        self.values.fill(None);
+    }
+
+    /// Evaluate `expr` against the current row, reusing the results of
+    /// shared sub-expressions already computed for it. `fts` supplies the
+    /// row's MATCH ... AGAINST scores and snippets.
+    pub fn eval<'r>(
+        &mut self,
+        expr: &Expr,
+        columns: &'r dyn Fn(&str) -> Option<&'r Value>,
+        fts: Option<&dyn Fn(&Expr) -> Value>,
+    ) -> Result<Value> {
+        eval_in(
+            expr,
+            &mut EvalEnv {
+                columns: Columns::Borrowed(columns),
+                memo: Some(self),
+                fts,
+            },
+        )
+    }
+
+    pub(super) fn id_of(&self, expr: &Expr) -> Option<usize> {
+        if self.ids.is_empty() {
+            return None;
+        }
+        self.ids.get(&(expr as *const Expr as usize)).copied()
+    }
+
+    pub(super) fn cached(&self, id: usize) -> Option<&Value> {
+        self.values[id].as_ref()
+    }
+
+    pub(super) fn store(&mut self, id: usize, value: Value) {
+        self.values[id] = Some(value);
+    }
+
+    pub(super) fn has_in_set(&self, expr: &Expr) -> bool {
+        !self.in_sets.is_empty() && self.in_sets.contains_key(&(expr as *const Expr as usize))
+    }
+
+    /// Whether the hash set of the `IN` list node `expr` holds `value`;
+    /// `None` if there is no set or `value` is not of the set's type.
+    pub(super) fn in_set_contains(&self, expr: &Expr, value: &Value) -> Option<bool> {
+        match (self.in_sets.get(&(expr as *const Expr as usize))?, value) {
+            (InSet::Integers(set), Value::Integer(n)) => Some(set.contains(n)),
+            (InSet::Strings(set), Value::Varchar(s)) => Some(set.contains(s)),
+            _ => None,
+        }
+    }
+}
+
+/// `IN` lists shorter than this are compared item by item.
+const IN_SET_MIN_ITEMS: usize = 16;
+
+/// The distinct values of a literal `IN` list. Integers and strings compare
+/// equal exactly when they are equal, so membership is a hash lookup.
+enum InSet {
+    Integers(HashSet<i64>),
+    Strings(HashSet<String>),
+}
+
+impl InSet {
+    /// The set of `list`, if it is long enough and all integer or all string
+    /// literals (a NULL item makes a miss UNKNOWN, so it has no set).
+    fn of(list: &[Expr]) -> Option<Self> {
+        if list.len() < IN_SET_MIN_ITEMS {
+            return None;
+        }
+        let integer = |item: &Expr| match item {
+            Expr::IntLiteral(n) => Some(*n),
+            Expr::UnaryOp {
+                op: UnaryOp::Neg,
+                operand,
+            } => match **operand {
+                Expr::IntLiteral(n) => n.checked_neg(),
+                _ => None,
+            },
+            _ => None,
+        };
+        match list.first()? {
+            Expr::StringLiteral(_) => list
+                .iter()
+                .map(|item| match item {
+                    Expr::StringLiteral(s) => Some(s.clone()),
+                    _ => None,
+                })
+                .collect::<Option<_>>()
+                .map(InSet::Strings),
+            _ => list
+                .iter()
+                .map(integer)
+                .collect::<Option<_>>()
+                .map(InSet::Integers),
+        }
+    }
+}
+
+/// Scope-resolved key of `expr`, recording every cacheable composite node
+/// under its key. `None` if `expr` cannot be cached: it contains a bind
+/// parameter, an aggregate, a subquery, or a non-deterministic function.
+fn expr_key(
+    expr: &Expr,
+    resolve: &dyn Fn(&str) -> Option<usize>,
+    occurrences: &mut HashMap<String, Vec<usize>>,
+    in_sets: &mut HashMap<usize, InSet>,
+) -> Option<String> {
+    let node = expr as *const Expr as usize;
+    let mut key = |e: &Expr| expr_key(e, resolve, occurrences, in_sets);
+    let text = match expr {
+        Expr::IntLiteral(_)
+        | Expr::FloatLiteral(_)
+        | Expr::StringLiteral(_)
+        | Expr::BlobLiteral(_)
+        | Expr::Null
+        | Expr::MatchAgainst { .. }
+        | Expr::FtsSnippet { .. } => return Some(format!("{:?}", expr)),
+        Expr::ColumnRef(name) => {
+            return Some(match resolve(name) {
+                Some(slot) => format!("#{}", slot),
+                None => format!("?{}", name),
+            })
+        }
+        Expr::BindParam
+        | Expr::DefaultValue
+        | Expr::AggregateFunc { .. }
+        | Expr::InSubquery { .. }
+        | Expr::Exists { .. }
+        | Expr::ScalarSubquery(_) => return None,
+        Expr::BinaryOp { left, op, right } => {
+            let (left, right) = (key(left), key(right));
+            format!("({} {:?} {})", left?, op, right?)
+        }
+        Expr::UnaryOp { op, operand } => format!("{:?}({})", op, key(operand)?),
+        Expr::Like {
+            expr,
+            pattern,
+            escape,
+            negated,
+        } => {
+            let parts = [
+                Some(key(expr)),
+                Some(key(pattern)),
+                escape.as_deref().map(&mut key),
+            ];
+            format!(
+                "Like[{}]({})",
+                negated,
+                join_keys(parts.into_iter().flatten())?
+            )
+        }
+        Expr::InList {
+            expr: operand,
+            list,
+            negated,
+        } => {
+            let parts: Vec<_> = std::iter::once(key(operand))
+                .chain(list.iter().map(&mut key))
+                .collect();
+            // A bare column compares byte-wise; collated ones are wrapped in
+            // COLLATE by the executor.
+            if matches!(**operand, Expr::ColumnRef(_)) {
+                if let Some(set) = InSet::of(list) {
+                    in_sets.insert(node, set);
+                }
+            }
+            format!("In[{}]({})", negated, join_keys(parts.into_iter())?)
+        }
+        Expr::Between {
+            expr,
+            low,
+            high,
+            negated,
+        } => {
+            let parts = [key(expr), key(low), key(high)];
+            format!("Between[{}]({})", negated, join_keys(parts.into_iter())?)
+        }
+        Expr::IsNull { expr, negated } => format!("IsNull[{}]({})", negated, key(expr)?),
+        Expr::FunctionCall { name, args } => {
+            let parts: Vec<_> = args.iter().map(&mut key).collect();
+            if NON_DETERMINISTIC_FUNCTIONS.contains(&name.as_str()) {
+                return None;
+            }
+            format!("{}({})", name, join_keys(parts.into_iter())?)
+        }
+        Expr::CaseWhen {
+            operand,
+            when_clauses,
+            else_clause,
+        } => {
+            let mut parts = vec![operand.as_deref().map_or(Some(String::new()), &mut key)];
+            for (when, then) in when_clauses {
+                parts.push(key(when));
+                parts.push(key(then));
+            }
+            parts.push(else_clause.as_deref().map_or(Some(String::new()), &mut key));
+            format!("Case({})", join_keys(parts.into_iter())?)
+        }
+        Expr::Cast { expr, target_type } => format!("Cast[{:?}]({})", target_type, key(expr)?),
+        Expr::Collate { expr, collation } => format!("Collate[{}]({})", collation, key(expr)?),
+        Expr::GreaterThanZero(inner) => format!("Gt0({})", key(inner)?),
+    };
+    occurrences.entry(text.clone()).or_default().push(node);
+    Some(text)
+}
+
+/// Comma-join child keys, `None` if any child is uncacheable.
+fn join_keys(parts: impl Iterator<Item = Option<String>>) -> Option<String> {
+    let mut joined = String::new();
+    for (i, part) in parts.enumerate() {
+        if i > 0 {
+            joined.push_str(", ");
+        }
+        write!(joined, "{}", part?).unwrap();
+    }
+    Some(joined)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::sql::ast::{SelectColumn, Statement};
+    use crate::sql::parser::parse_sql;
+
+    /// WHERE and select expressions of `sql`.
+    fn block(sql: &str) -> (Expr, Vec<Expr>) {
+        let Ok(Statement::Select(sel)) = parse_sql(sql) else {
+            panic!("not a select: {}", sql);
+        };
+        let columns = sel
+            .columns
+            .into_iter()
+            .filter_map(|c| match c {
+                SelectColumn::Expr(e, _) => Some(e),
+                SelectColumn::Star => None,
+            })
+            .collect();
+        (sel.where_clause.unwrap(), columns)
+    }
+
+    fn slot(names: &'static [&'static str]) -> impl Fn(&str) -> Option<usize> {
+        move |name| names.iter().position(|n| *n == name)
+    }
+
+    #[test]
+    fn test_shared_subexpression_computed_once_per_row() {
+        let (filter, columns) =
+            block("SELECT price * qty, price * qty + 1 FROM t WHERE price * qty > 100");
+        let resolve = slot(&["price", "qty"]);
+        let mut memo = ExprMemo::new(std::iter::once(&filter).chain(&columns), &resolve);
+        assert_eq!(memo.shared_count(), 1);
+
+        let lookups = std::cell::Cell::new(0);
+        let row = [Value::Integer(20), Value::Integer(7)];
+        let lookup = |name: &str| {
+            lookups.set(lookups.get() + 1);
+            resolve(name).map(|i| &row[i])
+        };
+        memo.start_row();
+        assert_eq!(
+            memo.eval(&filter, &lookup, None).unwrap(),
+            Value::Integer(1)
+        );
+        assert_eq!(
+            memo.eval(&columns[0], &lookup, None).unwrap(),
+            Value::Integer(140)
+        );
+        assert_eq!(
+            memo.eval(&columns[1], &lookup, None).unwrap(),
+            Value::Integer(141)
+        );
+        assert_eq!(lookups.get(), 2);
+
+        // The next row starts empty.
+        let row2 = [Value::Integer(1), Value::Integer(2)];
+        let lookup2 = |name: &str| resolve(name).map(|i| &row2[i]);
+        memo.start_row();
+        assert_eq!(
+            memo.eval(&columns[0], &lookup2, None).unwrap(),
+            Value::Integer(2)
+        );
+    }
+
+    #[test]
+    fn test_ids_follow_resolved_columns() {
+        let (filter, columns) = block("SELECT a.v * 2, b.v * 2, v * 2 FROM a WHERE 1 = 1");
+        let resolve = |name: &str| match name {
+            "a.v" | "v" => Some(0),
+            "b.v" => Some(1),
+            _ => None,
+        };
+        let memo = ExprMemo::new(std::iter::once(&filter).chain(&columns), &resolve);
+        assert_eq!(memo.shared_count(), 1);
+        assert_eq!(memo.id_of(&columns[0]), memo.id_of(&columns[2]));
+        assert!(memo.id_of(&columns[0]).is_some());
+        assert_eq!(memo.id_of(&columns[1]), None);
+
+        let row = [Value::Integer(3), Value::Integer(5)];
+        let lookup = |name: &str| resolve(name).map(|i| &row[i]);
+        let mut memo = memo;
+        memo.start_row();
+        let got: Vec<Value> = columns
+            .iter()
+            .map(|e| memo.eval(e, &lookup, None).unwrap())
+            .collect();
+        assert_eq!(
+            got,
+            vec![Value::Integer(6), Value::Integer(10), Value::Integer(6)]
+        );
+    }
+
+    #[test]
+    fn test_uncacheable_expressions_get_no_id() {
+        let (filter, columns) =
+            block("SELECT UUID_V4(), LENGTH(UUID_V4()), ? + 1 FROM t WHERE LENGTH(UUID_V4()) > 0 AND ? + 1 > 0");
+        let memo = ExprMemo::new(std::iter::once(&filter).chain(&columns), &slot(&[]));
+        assert_eq!(memo.shared_count(), 0);
+    }
+
+    #[test]
+    fn test_long_literal_in_list_uses_hash_set() {
+        let items: Vec<String> = (0..20).map(|i| (i * 3 - 9).to_string()).collect();
+        let (filter, columns) = block(&format!(
+            "SELECT v NOT IN ({0}) FROM t WHERE v IN ({0})",
+            items.join(", ")
+        ));
+        let resolve = slot(&["v"]);
+        let mut memo = ExprMemo::new(std::iter::once(&filter).chain(&columns), &resolve);
+        assert!(memo.has_in_set(&filter));
+        assert!(memo.has_in_set(&columns[0]));
+
+        let mut check = |v: Value, expect_in: Value, expect_not_in: Value| {
+            let row = [v];
+            let lookup = |name: &str| resolve(name).map(|i| &row[i]);
+            memo.start_row();
+            assert_eq!(memo.eval(&filter, &lookup, None).unwrap(), expect_in);
+            assert_eq!(
+                memo.eval(&columns[0], &lookup, None).unwrap(),
+                expect_not_in
+            );
+        };
+        check(Value::Integer(-9), Value::Integer(1), Value::Integer(0));
+        check(Value::Integer(48), Value::Integer(1), Value::Integer(0));
+        check(Value::Integer(4), Value::Integer(0), Value::Integer(1));
+        check(Value::Null, Value::Null, Value::Null);
+        // Other types fall back to comparing item by item.
+        check(Value::Float(6.0), Value::Integer(1), Value::Integer(0));
+
+        // Short lists and lists with non-literal items keep the plain path.
+        let (short, _) = block("SELECT 1 FROM t WHERE v IN (1, 2, 3)");
+        let (mixed, _) = block(&format!(
+            "SELECT 1 FROM t WHERE v IN ({}, v + 1)",
+            items.join(", ")
+        ));
+        let memo = ExprMemo::new([&short, &mixed], &resolve);
+        assert!(!memo.has_in_set(&short));
+        assert!(!memo.has_in_set(&mixed));
+    }
+}
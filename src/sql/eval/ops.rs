@@ -122,6 +122,18 @@ pub(super) fn eval_binary_op(left: &Value, op: BinaryOp, right: &Value) -> Resul
     }
 }
 
+/// Float results must stay finite: overflow is an error rather than an
+/// infinity (or NaN) that no FLOAT/DOUBLE column could store or order.
+pub(super) fn finite_float(n: f64) -> Result<Value> {
+    if n.is_finite() {
+        Ok(Value::Float(n))
+    } else {
+        Err(MuroError::Execution(
+            "Floating-point value is out of range".into(),
+        ))
+    }
+}
+
 fn eval_arithmetic(left: &Value, op: BinaryOp, right: &Value) -> Result<Value> {
     if left.is_null() || right.is_null() {
         return Ok(Value::Null);
@@ -151,7 +163,7 @@ fn eval_arithmetic(left: &Value, op: BinaryOp, right: &Value) -> Result<Value> {
             }
             _ => unreachable!(),
         };
-        return Ok(Value::Float(result));
+        return finite_float(result);
     }
 
     // Decimal arithmetic
@@ -210,7 +222,7 @@ fn eval_arithmetic(left: &Value, op: BinaryOp, right: &Value) -> Result<Value> {
                 }
                 _ => unreachable!(),
             };
-            return Ok(Value::Float(result));
+            return finite_float(result);
         }
     }
 
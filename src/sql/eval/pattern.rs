@@ -1,16 +1,67 @@
-pub(super) fn like_match(s: &str, pattern: &str) -> bool {
+use crate::error::{MuroError, Result};
+use crate::types::Value;
+
+/// One element of a LIKE pattern after escapes are resolved.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LikeToken {
+    /// `%`: zero or more characters.
+    AnyRun,
+    /// `_`: exactly one character.
+    AnyOne,
+    Literal(char),
+}
+
+/// Split a LIKE pattern into tokens. With an escape character, the character
+/// following it is always literal; a trailing lone escape matches itself.
+pub fn like_tokens(pattern: &str, escape: Option<char>) -> Vec<LikeToken> {
+    let mut tokens = Vec::with_capacity(pattern.len());
+    let mut chars = pattern.chars();
+    while let Some(c) = chars.next() {
+        let token = match c {
+            c if Some(c) == escape => LikeToken::Literal(chars.next().unwrap_or(c)),
+            '%' => LikeToken::AnyRun,
+            '_' => LikeToken::AnyOne,
+            c => LikeToken::Literal(c),
+        };
+        tokens.push(token);
+    }
+    tokens
+}
+
+/// Resolve the value of an `ESCAPE` clause: a single character, or none for
+/// the empty string.
+pub fn like_escape_char(value: &Value) -> Result<Option<char>> {
+    let Value::Varchar(s) = value else {
+        return Err(MuroError::Execution(
+            "ESCAPE must be a single character string".into(),
+        ));
+    };
+    let mut chars = s.chars();
+    match (chars.next(), chars.next()) {
+        (None, _) => Ok(None),
+        (Some(c), None) => Ok(Some(c)),
+        _ => Err(MuroError::Execution(
+            "ESCAPE must be a single character string".into(),
+        )),
+    }
+}
+
+pub(crate) fn like_match(s: &str, pattern: &str) -> bool {
+    like_match_tokens(s, &like_tokens(pattern, None))
+}
+
+pub(super) fn like_match_tokens(s: &str, tokens: &[LikeToken]) -> bool {
     let s_chars: Vec<char> = s.chars().collect();
-    let p_chars: Vec<char> = pattern.chars().collect();
-    like_match_inner(&s_chars, &p_chars)
+    like_match_inner(&s_chars, tokens)
 }
 
-fn like_match_inner(s: &[char], p: &[char]) -> bool {
+fn like_match_inner(s: &[char], p: &[LikeToken]) -> bool {
     if p.is_empty() {
         return s.is_empty();
     }
 
     match p[0] {
-        '%' => {
+        LikeToken::AnyRun => {
             // % matches zero or more characters
             // Try matching the rest of the pattern at every position
             for i in 0..=s.len() {
@@ -20,7 +71,7 @@ fn like_match_inner(s: &[char], p: &[char]) -> bool {
             }
             false
         }
-        '_' => {
+        LikeToken::AnyOne => {
             // _ matches exactly one character
             if s.is_empty() {
                 false
@@ -28,7 +79,7 @@ fn like_match_inner(s: &[char], p: &[char]) -> bool {
                 like_match_inner(&s[1..], &p[1..])
             }
         }
-        c => {
+        LikeToken::Literal(c) => {
             if s.is_empty() {
                 false
             } else if s[0] == c {
@@ -3,75 +3,135 @@ use std::collections::HashMap;
 use std::collections::HashSet;
 
 use crate::btree::key_encoding::{
-    encode_composite_key, encode_f32, encode_f64, encode_i16, encode_i32, encode_i64, encode_i8,
+    decode_byte_stuffed, encode_byte_stuffed, encode_composite_key, encode_f32, encode_f64,
+    encode_i16, encode_i32, encode_i64, encode_i8, push_byte_stuffed,
 };
-use crate::btree::ops::BTree;
+use crate::btree::ops::{BTree, DEFAULT_FILL_FACTOR};
 use crate::error::{MuroError, Result};
 use crate::fts::index::{FtsIndex, FtsPendingOp};
-use crate::fts::query::{query_boolean, query_natural_with_config, FtsQueryConfig, FtsResult};
+use crate::fts::query::{
+    query_boolean, query_natural_outcome, FtsQueryConfig, FtsResult, FtsStopFallback,
+};
 use crate::fts::snippet::fts_snippet;
-use crate::schema::catalog::{ForeignKeyDef, SystemCatalog, TableDef};
+use crate::schema::catalog::{
+    CreateOutcome, ForeignKeyDef, SystemCatalog, TableDef, INDEX_KEY_FORMAT_CONCAT,
+    INDEX_KEY_FORMAT_TERMINATED,
+};
 use crate::schema::column::{ColumnDef, DefaultValue};
-use crate::schema::index::{IndexDef, IndexType};
+use crate::schema::identifier::{collision_error, folded_collision, folded_collision_pairs};
+use crate::schema::index::{IndexBuildProgress, IndexDef, IndexType};
 use crate::sql::ast::*;
-use crate::sql::eval::{eval_expr, is_truthy};
-use crate::sql::parser::parse_sql;
+use crate::sql::eval::{
+    convert_value, eval_expr, eval_expr_ref, is_truthy, validate_value, ExprMemo,
+};
+use crate::sql::index_expr::{
+    default_expr_text, index_expr_columns, index_expr_text, parse_index_expr,
+    rename_index_expr_column,
+};
+use crate::sql::parser::parse_statement;
 use crate::sql::planner::{
     choose_nested_loop_order, estimate_plan_rows_hint, plan_cost_hint_with_stats,
-    plan_select_with_hints, IndexPlanStat, JoinLoopOrder, Plan, PlannerStats,
+    plan_select_with_estimates, plan_select_with_hints, ColumnPlanStat, IndexPlanStat,
+    JoinLoopOrder, Plan, PlanEstimate, PlannerStats,
 };
 use crate::storage::page::PageId;
 use crate::storage::page_store::PageStore;
 use crate::types::{
-    format_date, format_datetime, parse_date_string, parse_datetime_string, parse_timestamp_string,
-    parse_uuid_string, DataType, Value, ValueKey,
+    format_date, format_datetime, format_float, format_float_literal, parse_uuid_string, Collation,
+    DataType, Value, ValueKey,
 };
 
 mod aggregation;
 mod alter;
+mod check;
 mod codec;
+mod collation;
+mod column_stats;
+mod compare_types;
+mod covering;
 mod ddl;
 mod foreign_key;
 mod fts;
 mod indexing;
 mod insert;
+mod like_rewrite;
 mod mutation;
+mod optimize;
+mod plan_baseline;
+mod predicate_order;
+mod profile;
+mod row;
 mod row_format;
+mod scan;
 mod select_join;
 mod select_meta;
 mod select_query;
+mod set_query;
 mod show;
+mod spill;
 mod subquery;
 
-pub use codec::{deserialize_row_versioned, encode_value, serialize_row};
+pub use codec::{deserialize_columns, deserialize_row_versioned, encode_value, serialize_row};
+pub use row::FromRow;
 
-use aggregation::{cmp_values, execute_aggregation, execute_aggregation_join, has_aggregates};
+use aggregation::{
+    cmp_values, execute_aggregation, execute_aggregation_join, has_aggregates, GroupAggregator,
+};
 use alter::*;
-use codec::default_value_for_column;
+pub(crate) use check::check_database;
+use check::exec_check_table;
+use codec::{default_value_for_column, eval_column_default};
+use collation::{
+    collated_cmp, collated_key, collated_order_by, column_collation_of, expr_collation,
+    join_column_collation, order_by_column, output_collations, retain_distinct,
+    with_column_collations, with_join_collations,
+};
+use column_stats::{value_as_i64_for_stats, ColumnStatsCollector};
+use compare_types::{check_join_literal_types, check_where_literal_types};
+use covering::{covering_index, index_only_rows};
 use ddl::*;
+pub(crate) use ddl::{abort_index_build, begin_index_build, continue_index_build};
 use foreign_key::{
     enforce_child_foreign_keys, enforce_parent_restrict_on_delete,
     enforce_parent_restrict_on_update,
 };
 use fts::{
     build_fts_eval_context, execute_fts_scan_rows, free_btree_pages, fts_allocate_doc_id,
-    fts_delete_doc_mapping, fts_get_doc_id, fts_put_doc_mapping, fts_set_next_doc_id,
-    materialize_fts_expr, populate_fts_row_doc_ids, validate_fulltext_parser, validate_value,
-    value_to_fts_text, FtsEvalContext,
+    fts_delete_doc_mapping, fts_expr_value, fts_get_doc_id, fts_put_doc_mapping,
+    fts_set_next_doc_id, populate_fts_row_doc_ids, validate_fulltext_parser, value_to_fts_text,
+    FtsEvalContext,
 };
+pub(crate) use indexing::rebuild_index;
 use indexing::{
-    check_unique_index_constraints, check_unique_index_constraints_excluding,
-    delete_from_secondary_indexes, encode_index_key_from_row, encode_pk_key, eval_index_seek_key,
-    eval_pk_seek_key, find_unique_index_conflict, index_seek_pk_keys, index_seek_pk_keys_range,
-    insert_into_secondary_indexes, persist_indexes,
+    build_index_from_rows, build_index_rows, check_unique_index_constraints_excluding,
+    delete_from_secondary_indexes, encode_index_key, encode_pk_key, ensure_no_expression_index_on,
+    eval_index_range_bound, eval_index_seek_key, eval_pk_seek_key, find_unique_index_conflict,
+    in_list_seek_entries, in_list_seek_pk_keys, index_key_for_row, index_key_parts,
+    index_plan_stats, index_range_entries, index_referenced_columns, index_seek_entries,
+    index_seek_pk_keys, index_seek_pk_keys_range, insert_into_secondary_indexes,
+    non_unique_entry_index_key, non_unique_entry_key, persist_indexes, rename_index_column,
+    table_planner_stats, unique_violation, IndexKeyPart,
 };
 use insert::*;
+use like_rewrite::{like_simplified_where, simplify_like_predicates};
 use mutation::*;
+use optimize::exec_optimize_table;
+pub(crate) use plan_baseline::capture_plan_baseline;
+use plan_baseline::{
+    baseline_plan, describe_plan, exec_install_plan_baseline, exec_remove_plan_baseline,
+};
+use predicate_order::{describe_conjunct, filter_matches, ordered_conjuncts, residual_filter};
+use profile::{
+    exec_explain_analyze, finish_stage, plan_node_name, start_stage, stats_rows_hint, Stage,
+};
 use row_format::*;
+use scan::{scan_table_columns, scan_table_rows};
 use select_join::*;
 use select_meta::*;
 use select_query::*;
+use set_query::exec_set_query;
 use show::*;
+pub(crate) use show::{create_table_sql, foreign_key_sql, fulltext_index_sql};
 use subquery::*;
 
 /// A result row.
@@ -80,12 +140,6 @@ pub struct Row {
     pub values: Vec<(String, Value)>,
 }
 
-impl Row {
-    pub fn get(&self, name: &str) -> Option<&Value> {
-        self.values.iter().find(|(n, _)| n == name).map(|(_, v)| v)
-    }
-}
-
 /// Execution result.
 #[derive(Debug)]
 pub enum ExecResult {
@@ -103,7 +157,7 @@ pub fn execute(
     pager: &mut impl PageStore,
     catalog: &mut SystemCatalog,
 ) -> Result<ExecResult> {
-    let stmt = parse_sql(sql).map_err(MuroError::Parse)?;
+    let stmt = parse_statement(sql)?;
     execute_statement(&stmt, pager, catalog)
 }
 
@@ -112,11 +166,29 @@ pub fn execute_statement(
     pager: &mut impl PageStore,
     catalog: &mut SystemCatalog,
 ) -> Result<ExecResult> {
+    if matches!(
+        stmt,
+        Statement::CreateTable(_)
+            | Statement::CreateIndex(_)
+            | Statement::CreateFulltextIndex(_)
+            | Statement::AnalyzeTable(_)
+            | Statement::OptimizeTable(_)
+            | Statement::DropTable(_)
+            | Statement::DropIndex(_)
+            | Statement::AlterTable(_)
+            | Statement::RenameTable(_)
+            | Statement::InstallPlanBaseline(_)
+            | Statement::RemovePlanBaseline(_)
+    ) {
+        catalog.bump_generation();
+    }
     match stmt {
         Statement::CreateTable(ct) => exec_create_table(ct, pager, catalog),
         Statement::CreateIndex(ci) => exec_create_index(ci, pager, catalog),
         Statement::CreateFulltextIndex(fi) => exec_create_fulltext_index(fi, pager, catalog),
         Statement::AnalyzeTable(table_name) => exec_analyze_table(table_name, pager, catalog),
+        Statement::CheckTable(table_name) => exec_check_table(table_name, pager, catalog),
+        Statement::OptimizeTable(table_name) => exec_optimize_table(table_name, pager, catalog),
         Statement::DropTable(dt) => exec_drop_table(dt, pager, catalog),
         Statement::DropIndex(di) => exec_drop_index(di, pager, catalog),
         Statement::AlterTable(at) => exec_alter_table(at, pager, catalog),
@@ -124,12 +196,19 @@ pub fn execute_statement(
         Statement::Insert(ins) => exec_insert(ins, pager, catalog),
         Statement::Select(sel) => exec_select(sel, pager, catalog),
         Statement::Explain(inner) => exec_explain(inner, pager, catalog),
+        Statement::ExplainAnalyze(inner) => exec_explain_analyze(inner, pager, catalog),
         Statement::SetQuery(sq) => exec_set_query(sq, pager, catalog),
         Statement::Update(upd) => exec_update(upd, pager, catalog),
         Statement::Delete(del) => exec_delete(del, pager, catalog),
         Statement::ShowTables => exec_show_tables(pager, catalog),
+        Statement::ShowTableStatus => exec_show_table_status(pager, catalog),
         Statement::ShowCreateTable(name) => exec_show_create_table(name, pager, catalog),
+        Statement::ShowIndexes(name) => exec_show_indexes(name, pager, catalog),
         Statement::Describe(name) => exec_describe(name, pager, catalog),
+        Statement::InstallPlanBaseline(baseline) => {
+            exec_install_plan_baseline(baseline, pager, catalog)
+        }
+        Statement::RemovePlanBaseline(shape) => exec_remove_plan_baseline(shape, pager, catalog),
         Statement::Begin
         | Statement::Commit
         | Statement::Rollback
@@ -138,8 +217,12 @@ pub fn execute_statement(
         | Statement::ReleaseSavepoint(_)
         | Statement::ShowCheckpointStats
         | Statement::ShowDatabaseStats
-        | Statement::SetRuntimeOption(_) => Err(MuroError::Execution(
-            "BEGIN/COMMIT/ROLLBACK/SAVEPOINT/ROLLBACK TO/RELEASE SAVEPOINT/SHOW CHECKPOINT STATS/SHOW DATABASE STATS/SET runtime option must be handled by Session".into(),
+        | Statement::SetVariable(_)
+        | Statement::ShowVariables(_)
+        | Statement::ShowWarnings
+        | Statement::ShowProcesslist
+        | Statement::Optimize => Err(MuroError::Execution(
+            "BEGIN/COMMIT/ROLLBACK/SAVEPOINT/ROLLBACK TO/RELEASE SAVEPOINT/SHOW CHECKPOINT STATS/SHOW DATABASE STATS/SHOW WARNINGS/SHOW PROCESSLIST/SHOW VARIABLES/SET/OPTIMIZE must be handled by Session".into(),
         )),
     }
 }
@@ -1,4 +1,7 @@
+use super::codec::deserialize_row;
+use super::spill::SpillFile;
 use super::*;
+use crate::sql::session::aggregation_memory_budget_current;
 
 pub(super) fn has_aggregates(columns: &[SelectColumn], having: &Option<Expr>) -> bool {
     for col in columns {
@@ -23,8 +26,15 @@ fn expr_contains_aggregate(expr: &Expr) -> bool {
             expr_contains_aggregate(left) || expr_contains_aggregate(right)
         }
         Expr::UnaryOp { operand, .. } => expr_contains_aggregate(operand),
-        Expr::Like { expr, pattern, .. } => {
-            expr_contains_aggregate(expr) || expr_contains_aggregate(pattern)
+        Expr::Like {
+            expr,
+            pattern,
+            escape,
+            ..
+        } => {
+            expr_contains_aggregate(expr)
+                || expr_contains_aggregate(pattern)
+                || escape.as_deref().is_some_and(expr_contains_aggregate)
         }
         Expr::IsNull { expr, .. } => expr_contains_aggregate(expr),
         Expr::FunctionCall { args, .. } => args.iter().any(expr_contains_aggregate),
@@ -50,7 +60,7 @@ fn expr_contains_aggregate(expr: &Expr) -> bool {
             }
             false
         }
-        Expr::Cast { expr, .. } => expr_contains_aggregate(expr),
+        Expr::Cast { expr, .. } | Expr::Collate { expr, .. } => expr_contains_aggregate(expr),
         Expr::InList { expr, list, .. } => {
             expr_contains_aggregate(expr) || list.iter().any(expr_contains_aggregate)
         }
@@ -77,15 +87,18 @@ enum Accumulator {
     },
     CountDistinct {
         values: HashSet<ValueKey>,
+        collation: Collation,
     },
     Sum {
         total: Option<Value>,
     },
     Min {
         val: Option<Value>,
+        collation: Collation,
     },
     Max {
         val: Option<Value>,
+        collation: Collation,
     },
     Avg {
         int_sum: i128,
@@ -98,15 +111,23 @@ enum Accumulator {
 }
 
 impl Accumulator {
-    fn new(name: &str, distinct: bool) -> Self {
-        match name {
-            "COUNT" if distinct => Accumulator::CountDistinct {
+    fn new(info: &AggregateInfo) -> Self {
+        let collation = info.collation;
+        match info.name.as_str() {
+            "COUNT" if info.distinct => Accumulator::CountDistinct {
                 values: HashSet::new(),
+                collation,
             },
             "COUNT" => Accumulator::Count { count: 0 },
             "SUM" => Accumulator::Sum { total: None },
-            "MIN" => Accumulator::Min { val: None },
-            "MAX" => Accumulator::Max { val: None },
+            "MIN" => Accumulator::Min {
+                val: None,
+                collation,
+            },
+            "MAX" => Accumulator::Max {
+                val: None,
+                collation,
+            },
             "AVG" => Accumulator::Avg {
                 int_sum: 0,
                 float_sum: 0.0,
@@ -127,9 +148,9 @@ impl Accumulator {
                     *count += 1;
                 }
             }
-            Accumulator::CountDistinct { values } => {
+            Accumulator::CountDistinct { values, collation } => {
                 if !val.is_null() {
-                    values.insert(ValueKey(val.clone()));
+                    values.insert(collated_key(*collation, val));
                 }
             }
             Accumulator::Sum { total } => match val {
@@ -164,27 +185,33 @@ impl Accumulator {
                 }
                 _ => {}
             },
-            Accumulator::Min { val: current } => {
+            Accumulator::Min {
+                val: current,
+                collation,
+            } => {
                 if val.is_null() {
                     return;
                 }
                 match current {
                     None => *current = Some(val.clone()),
                     Some(cur) => {
-                        if cmp_values(Some(val), Some(cur)) == std::cmp::Ordering::Less {
+                        if collated_cmp(*collation, val, cur) == std::cmp::Ordering::Less {
                             *current = Some(val.clone());
                         }
                     }
                 }
             }
-            Accumulator::Max { val: current } => {
+            Accumulator::Max {
+                val: current,
+                collation,
+            } => {
                 if val.is_null() {
                     return;
                 }
                 match current {
                     None => *current = Some(val.clone()),
                     Some(cur) => {
-                        if cmp_values(Some(val), Some(cur)) == std::cmp::Ordering::Greater {
+                        if collated_cmp(*collation, val, cur) == std::cmp::Ordering::Greater {
                             *current = Some(val.clone());
                         }
                     }
@@ -222,6 +249,14 @@ impl Accumulator {
         }
     }
 
+    /// Number of distinct values a COUNT(DISTINCT) has collected; 0 otherwise.
+    fn distinct_len(&self) -> usize {
+        match self {
+            Accumulator::CountDistinct { values, .. } => values.len(),
+            _ => 0,
+        }
+    }
+
     fn feed_count_star(&mut self) {
         if let Accumulator::Count { count } = self {
             *count += 1;
@@ -231,10 +266,11 @@ impl Accumulator {
     fn finalize(&self) -> Value {
         match self {
             Accumulator::Count { count } => Value::Integer(*count),
-            Accumulator::CountDistinct { values } => Value::Integer(values.len() as i64),
+            Accumulator::CountDistinct { values, .. } => Value::Integer(values.len() as i64),
             Accumulator::Sum { total } => total.clone().unwrap_or(Value::Null),
-            Accumulator::Min { val } => val.clone().unwrap_or(Value::Null),
-            Accumulator::Max { val } => val.clone().unwrap_or(Value::Null),
+            Accumulator::Min { val, .. } | Accumulator::Max { val, .. } => {
+                val.clone().unwrap_or(Value::Null)
+            }
             Accumulator::Avg {
                 int_sum,
                 float_sum,
@@ -265,9 +301,15 @@ struct AggregateInfo {
     name: String,
     arg: Option<Expr>,
     distinct: bool,
+    /// Collation of the argument, for COUNT(DISTINCT), MIN and MAX.
+    collation: Collation,
 }
 
-fn collect_aggregates(columns: &[SelectColumn], having: &Option<Expr>) -> Vec<AggregateInfo> {
+fn collect_aggregates(
+    columns: &[SelectColumn],
+    having: &Option<Expr>,
+    collation_of: &dyn Fn(&str) -> Option<Collation>,
+) -> Vec<AggregateInfo> {
     let mut aggs = Vec::new();
     for col in columns {
         if let SelectColumn::Expr(expr, _) = col {
@@ -277,6 +319,11 @@ fn collect_aggregates(columns: &[SelectColumn], having: &Option<Expr>) -> Vec<Ag
     if let Some(h) = having {
         collect_aggregates_from_expr(h, &mut aggs);
     }
+    for agg in &mut aggs {
+        if let Some(arg) = &agg.arg {
+            agg.collation = expr_collation(arg, collation_of);
+        }
+    }
     aggs
 }
 
@@ -298,6 +345,7 @@ fn collect_aggregates_from_expr(expr: &Expr, aggs: &mut Vec<AggregateInfo>) {
                     name: name.clone(),
                     arg: arg.as_deref().cloned(),
                     distinct: *distinct,
+                    collation: Collation::Binary,
                 });
             }
         }
@@ -329,10 +377,20 @@ fn collect_aggregates_from_expr(expr: &Expr, aggs: &mut Vec<AggregateInfo>) {
                 collect_aggregates_from_expr(e, aggs);
             }
         }
-        Expr::Cast { expr, .. } => collect_aggregates_from_expr(expr, aggs),
-        Expr::Like { expr, pattern, .. } => {
+        Expr::Cast { expr, .. } | Expr::Collate { expr, .. } => {
+            collect_aggregates_from_expr(expr, aggs)
+        }
+        Expr::Like {
+            expr,
+            pattern,
+            escape,
+            ..
+        } => {
             collect_aggregates_from_expr(expr, aggs);
             collect_aggregates_from_expr(pattern, aggs);
+            if let Some(e) = escape {
+                collect_aggregates_from_expr(e, aggs);
+            }
         }
         Expr::IsNull { expr, .. } => collect_aggregates_from_expr(expr, aggs),
         Expr::InList { expr, list, .. } => {
@@ -395,90 +453,215 @@ fn substitute_aggregates(expr: &Expr, aggs: &[AggregateInfo], agg_values: &[Valu
     }
 }
 
-/// Execute the aggregation pipeline for non-join queries.
-/// Takes raw rows (Vec<Vec<Value>>), groups them, computes aggregates,
-/// applies HAVING, and returns projected Rows.
-pub(super) fn execute_aggregation(
-    raw_rows: Vec<Vec<Value>>,
-    table_def: &TableDef,
-    sel: &Select,
-) -> Result<Vec<Row>> {
-    let aggs = collect_aggregates(&sel.columns, &sel.having);
-    let has_group_by = sel.group_by.is_some();
+/// Partitions rows of groups that did not fit in memory are spread over.
+const SPILL_PARTITIONS: u64 = 16;
+/// Rounds of re-partitioning before a partition is aggregated in memory
+/// regardless of the budget.
+const MAX_SPILL_DEPTH: u32 = 3;
+
+/// Running state of one group.
+struct Group {
+    /// Position of the group's first row in the input, which fixes the
+    /// order groups are returned in.
+    first_seq: u64,
+    /// The group's first row, used for column references outside aggregates.
+    rep_row: Vec<Value>,
+    accumulators: Vec<Accumulator>,
+}
 
-    // Build groups: group_key -> list of raw rows
-    let mut groups: Vec<(Vec<ValueKey>, Vec<Vec<Value>>)> = Vec::new();
-    let mut group_index: HashMap<Vec<ValueKey>, usize> = HashMap::new();
+/// Hash aggregation for non-join queries.
+///
+/// Rows are fed one at a time and folded into per-group accumulators, so
+/// memory grows with the number of groups rather than the number of rows.
+/// Once the estimated size of the groups exceeds the session's
+/// `aggregation_memory_budget`, groups already in memory keep accumulating
+/// while rows of new groups are hash-partitioned into encrypted temp files;
+/// [`GroupAggregator::finish`] aggregates each partition on its own and
+/// returns all groups in order of first appearance, as if nothing spilled.
+pub(super) struct GroupAggregator<'a> {
+    table_def: &'a TableDef,
+    sel: &'a Select,
+    aggs: Vec<AggregateInfo>,
+    /// Collation of each GROUP BY expression.
+    group_collations: Vec<Collation>,
+    groups: Vec<Group>,
+    group_index: HashMap<Vec<ValueKey>, usize>,
+    budget: usize,
+    memory_used: usize,
+    depth: u32,
+    rows_fed: u64,
+    partitions: Vec<Option<SpillFile>>,
+}
 
-    for raw_row in &raw_rows {
+impl<'a> GroupAggregator<'a> {
+    pub(super) fn new(table_def: &'a TableDef, sel: &'a Select) -> Self {
+        Self::with_depth(table_def, sel, aggregation_memory_budget_current(), 0)
+    }
+
+    fn with_depth(table_def: &'a TableDef, sel: &'a Select, budget: usize, depth: u32) -> Self {
+        let collation_of = |name: &str| column_collation_of(table_def, name);
+        GroupAggregator {
+            table_def,
+            sel,
+            aggs: collect_aggregates(&sel.columns, &sel.having, &collation_of),
+            group_collations: sel
+                .group_by
+                .iter()
+                .flatten()
+                .map(|e| expr_collation(e, &collation_of))
+                .collect(),
+            groups: Vec::new(),
+            group_index: HashMap::new(),
+            budget,
+            memory_used: 0,
+            depth,
+            rows_fed: 0,
+            partitions: Vec::new(),
+        }
+    }
+
+    /// Number of rows fed so far.
+    pub(super) fn rows_fed(&self) -> u64 {
+        self.rows_fed
+    }
+
+    /// Fold one input row into its group.
+    pub(super) fn feed(&mut self, row: Vec<Value>) -> Result<()> {
+        self.feed_with_seq(self.rows_fed, row)
+    }
+
+    fn feed_with_seq(&mut self, seq: u64, row: Vec<Value>) -> Result<()> {
         cancellation_point()?;
-        let group_key = if let Some(group_exprs) = &sel.group_by {
-            let mut key = Vec::with_capacity(group_exprs.len());
-            for gexpr in group_exprs {
-                cancellation_point()?;
-                let val = eval_expr(gexpr, &|name| {
-                    table_def
-                        .column_index(name)
-                        .and_then(|i| raw_row.get(i).cloned())
-                })?;
-                key.push(ValueKey(val));
-            }
-            key
-        } else {
+        self.rows_fed += 1;
+        let key = self.group_key(&row)?;
+        if let Some(&idx) = self.group_index.get(&key) {
+            let grown = accumulate(
+                &self.aggs,
+                self.table_def,
+                &mut self.groups[idx].accumulators,
+                &row,
+            )?;
+            self.memory_used += grown;
+            return Ok(());
+        }
+        if self.memory_used >= self.budget && self.depth < MAX_SPILL_DEPTH {
+            return self.spill(seq, &key, &row);
+        }
+
+        let mut accumulators: Vec<Accumulator> = self.aggs.iter().map(Accumulator::new).collect();
+        let grown = accumulate(&self.aggs, self.table_def, &mut accumulators, &row)?;
+        self.memory_used += grown
+            + GROUP_OVERHEAD_BYTES
+            + key.iter().map(|k| value_bytes(&k.0)).sum::<usize>()
+            + row.iter().map(value_bytes).sum::<usize>()
+            + accumulators.len() * std::mem::size_of::<Accumulator>();
+        self.group_index.insert(key, self.groups.len());
+        self.groups.push(Group {
+            first_seq: seq,
+            rep_row: row,
+            accumulators,
+        });
+        Ok(())
+    }
+
+    fn group_key(&self, row: &[Value]) -> Result<Vec<ValueKey>> {
+        let Some(group_exprs) = &self.sel.group_by else {
             // No GROUP BY: all rows in one group
-            vec![]
+            return Ok(vec![]);
         };
+        let mut key = Vec::with_capacity(group_exprs.len());
+        for (gexpr, collation) in group_exprs.iter().zip(&self.group_collations) {
+            cancellation_point()?;
+            let val = eval_expr(gexpr, &|name| {
+                self.table_def
+                    .column_index(name)
+                    .and_then(|i| row.get(i).cloned())
+            })?;
+            key.push(collated_key(*collation, &val));
+        }
+        Ok(key)
+    }
 
-        if let Some(&idx) = group_index.get(&group_key) {
-            groups[idx].1.push(raw_row.clone());
-        } else {
-            let idx = groups.len();
-            group_index.insert(group_key.clone(), idx);
-            groups.push((group_key, vec![raw_row.clone()]));
+    fn spill(&mut self, seq: u64, key: &[ValueKey], row: &[Value]) -> Result<()> {
+        use std::hash::{Hash, Hasher};
+
+        if self.partitions.is_empty() {
+            self.partitions = (0..SPILL_PARTITIONS).map(|_| None).collect();
+        }
+        // Salt with the depth so a partition that spills again is split
+        // differently from the round that produced it.
+        let mut hasher = std::collections::hash_map::DefaultHasher::new();
+        self.depth.hash(&mut hasher);
+        key.hash(&mut hasher);
+        let slot = &mut self.partitions[(hasher.finish() % SPILL_PARTITIONS) as usize];
+        if slot.is_none() {
+            *slot = Some(SpillFile::create()?);
         }
+        let file = slot.as_mut().expect("spill partition created above");
+        file.push(seq, &serialize_row(row, &self.table_def.columns))
     }
 
-    // If no rows and no GROUP BY, produce a single group (for SELECT COUNT(*) FROM empty_table)
-    if groups.is_empty() && !has_group_by {
-        groups.push((vec![], vec![]));
+    /// Finalize every group, apply HAVING, and project the SELECT list.
+    pub(super) fn finish(self) -> Result<Vec<Row>> {
+        let spilled = !self.partitions.is_empty();
+        let mut rows = self.finish_groups()?;
+        if spilled {
+            rows.sort_by_key(|(seq, _)| *seq);
+        }
+        Ok(rows.into_iter().map(|(_, row)| row).collect())
     }
 
-    let mut result_rows = Vec::new();
+    fn finish_groups(mut self) -> Result<Vec<(u64, Row)>> {
+        let mut result_rows = Vec::new();
 
-    for (_group_key, group_rows) in &groups {
-        cancellation_point()?;
-        // Create accumulators for each aggregate
-        let mut accumulators: Vec<Accumulator> = aggs
-            .iter()
-            .map(|a| Accumulator::new(&a.name, a.distinct))
-            .collect();
-
-        // Feed rows into accumulators
-        for raw_row in group_rows {
+        // If no rows and no GROUP BY, produce a single group (for SELECT COUNT(*) FROM empty_table)
+        if self.rows_fed == 0 && self.sel.group_by.is_none() {
+            let accumulators: Vec<Accumulator> = self.aggs.iter().map(Accumulator::new).collect();
+            if let Some(row) = self.output_row(None, &accumulators)? {
+                result_rows.push((0, row));
+            }
+            return Ok(result_rows);
+        }
+
+        self.group_index = HashMap::new();
+        for group in std::mem::take(&mut self.groups) {
             cancellation_point()?;
-            for (i, agg_info) in aggs.iter().enumerate() {
-                if let Some(arg_expr) = &agg_info.arg {
-                    let val = eval_expr(arg_expr, &|name| {
-                        table_def
-                            .column_index(name)
-                            .and_then(|j| raw_row.get(j).cloned())
-                    })?;
-                    accumulators[i].feed(&val);
-                } else {
-                    // COUNT(*)
-                    accumulators[i].feed_count_star();
-                }
+            if let Some(row) = self.output_row(Some(&group.rep_row), &group.accumulators)? {
+                result_rows.push((group.first_seq, row));
             }
         }
 
+        for file in std::mem::take(&mut self.partitions).into_iter().flatten() {
+            let mut partition =
+                GroupAggregator::with_depth(self.table_def, self.sel, self.budget, self.depth + 1);
+            let mut reader = file.into_reader()?;
+            while let Some((seq, data)) = reader.next_record()? {
+                partition.feed_with_seq(seq, deserialize_row(&data, &self.table_def.columns)?)?;
+            }
+            drop(reader);
+            result_rows.extend(partition.finish_groups()?);
+        }
+
+        Ok(result_rows)
+    }
+
+    /// The output row of a group, or `None` when HAVING rejects it.
+    fn output_row(
+        &self,
+        rep_row: Option<&[Value]>,
+        accumulators: &[Accumulator],
+    ) -> Result<Option<Row>> {
+        let table_def = self.table_def;
+        let sel = self.sel;
+        let aggs = &self.aggs;
+
         // Finalize aggregates
         let agg_values: Vec<Value> = accumulators.iter().map(|a| a.finalize()).collect();
 
         // Apply HAVING filter
         if let Some(having_expr) = &sel.having {
-            let substituted = substitute_aggregates(having_expr, &aggs, &agg_values);
+            let substituted = substitute_aggregates(having_expr, aggs, &agg_values);
             // Use a representative row from the group for column references
-            let rep_row = group_rows.first();
             let result = eval_expr(&substituted, &|name| {
                 if let Some(row) = rep_row {
                     table_def
@@ -489,12 +672,11 @@ pub(super) fn execute_aggregation(
                 }
             })?;
             if !is_truthy(&result) {
-                continue;
+                return Ok(None);
             }
         }
 
         // Project SELECT columns
-        let rep_row = group_rows.first();
         let mut row_values = Vec::new();
 
         for sel_col in &sel.columns {
@@ -512,7 +694,7 @@ pub(super) fn execute_aggregation(
                     }
                 }
                 SelectColumn::Expr(expr, alias) => {
-                    let substituted = substitute_aggregates(expr, &aggs, &agg_values);
+                    let substituted = substitute_aggregates(expr, aggs, &agg_values);
                     let val = eval_expr(&substituted, &|name| {
                         if let Some(row) = rep_row {
                             table_def
@@ -548,10 +730,65 @@ pub(super) fn execute_aggregation(
             }
         }
 
-        result_rows.push(Row { values: row_values });
+        Ok(Some(Row { values: row_values }))
     }
+}
 
-    Ok(result_rows)
+/// Estimated bookkeeping per group beyond its key, row, and accumulators:
+/// the hash map entry and the vectors' headers.
+const GROUP_OVERHEAD_BYTES: usize = 96;
+
+/// Estimated in-memory size of a value.
+fn value_bytes(value: &Value) -> usize {
+    std::mem::size_of::<Value>()
+        + match value {
+            Value::Varchar(s) => s.len(),
+            Value::Varbinary(b) => b.len(),
+            _ => 0,
+        }
+}
+
+/// Feed `row` to a group's accumulators. Returns the estimated bytes the
+/// accumulators grew by, which only DISTINCT aggregates do.
+fn accumulate(
+    aggs: &[AggregateInfo],
+    table_def: &TableDef,
+    accumulators: &mut [Accumulator],
+    row: &[Value],
+) -> Result<usize> {
+    let mut grown = 0;
+    for (acc, agg_info) in accumulators.iter_mut().zip(aggs) {
+        if let Some(arg_expr) = &agg_info.arg {
+            let val = eval_expr(arg_expr, &|name| {
+                table_def
+                    .column_index(name)
+                    .and_then(|j| row.get(j).cloned())
+            })?;
+            let before = acc.distinct_len();
+            acc.feed(&val);
+            if acc.distinct_len() > before {
+                grown += value_bytes(&val);
+            }
+        } else {
+            // COUNT(*)
+            acc.feed_count_star();
+        }
+    }
+    Ok(grown)
+}
+
+/// Execute the aggregation pipeline for non-join queries over rows that are
+/// already in memory. See [`GroupAggregator`] for the streaming form.
+pub(super) fn execute_aggregation(
+    raw_rows: Vec<Vec<Value>>,
+    table_def: &TableDef,
+    sel: &Select,
+) -> Result<Vec<Row>> {
+    let mut aggregator = GroupAggregator::new(table_def, sel);
+    for raw_row in raw_rows {
+        aggregator.feed(raw_row)?;
+    }
+    aggregator.finish()
 }
 
 /// Execute the aggregation pipeline for join queries.
@@ -559,8 +796,16 @@ pub(super) fn execute_aggregation_join(
     joined_rows: &[Vec<(String, Value)>],
     sel: &Select,
     hidden_columns: &[String],
+    tables: &[(String, TableDef)],
 ) -> Result<Vec<Row>> {
-    let aggs = collect_aggregates(&sel.columns, &sel.having);
+    let collation_of = |name: &str| join_column_collation(tables, name);
+    let aggs = collect_aggregates(&sel.columns, &sel.having, &collation_of);
+    let group_collations: Vec<Collation> = sel
+        .group_by
+        .iter()
+        .flatten()
+        .map(|e| expr_collation(e, &collation_of))
+        .collect();
     let has_group_by = sel.group_by.is_some();
 
     // Build groups
@@ -572,10 +817,10 @@ pub(super) fn execute_aggregation_join(
         cancellation_point()?;
         let group_key = if let Some(group_exprs) = &sel.group_by {
             let mut key = Vec::with_capacity(group_exprs.len());
-            for gexpr in group_exprs {
+            for (gexpr, collation) in group_exprs.iter().zip(&group_collations) {
                 cancellation_point()?;
                 let val = eval_join_expr(gexpr, jrow)?;
-                key.push(ValueKey(val));
+                key.push(collated_key(*collation, &val));
             }
             key
         } else {
@@ -599,10 +844,7 @@ pub(super) fn execute_aggregation_join(
 
     for (_group_key, group_rows) in &groups {
         cancellation_point()?;
-        let mut accumulators: Vec<Accumulator> = aggs
-            .iter()
-            .map(|a| Accumulator::new(&a.name, a.distinct))
-            .collect();
+        let mut accumulators: Vec<Accumulator> = aggs.iter().map(Accumulator::new).collect();
 
         for jrow in group_rows {
             cancellation_point()?;
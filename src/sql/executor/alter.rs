@@ -1,40 +1,222 @@
 use super::*;
+use crate::limits::Limit;
+use crate::schema::expectation::{ExpectedTable, SchemaDiff, SchemaExpectation};
+use crate::sql::session::forget_auto_increment_current;
 use serde_json::Value as JsonValue;
 
+/// Apply the operations of an ALTER TABLE in order. Each sees the table as
+/// the previous one left it; a failing operation fails the statement, whose
+/// rollback undoes the ones before it.
 pub(super) fn exec_alter_table(
     at: &AlterTable,
     pager: &mut impl PageStore,
     catalog: &mut SystemCatalog,
+) -> Result<ExecResult> {
+    for operation in &at.operations {
+        exec_alter_op(&at.table_name, operation, pager, catalog)?;
+    }
+    Ok(ExecResult::Ok)
+}
+
+fn exec_alter_op(
+    table_name: &str,
+    operation: &AlterTableOp,
+    pager: &mut impl PageStore,
+    catalog: &mut SystemCatalog,
 ) -> Result<ExecResult> {
     let table_def = catalog
-        .get_table(pager, &at.table_name)?
-        .ok_or_else(|| MuroError::Schema(format!("Table '{}' not found", at.table_name)))?;
+        .get_table(pager, table_name)?
+        .ok_or_else(|| MuroError::Schema(format!("Table '{}' not found", table_name)))?;
 
-    match &at.operation {
+    match operation {
         AlterTableOp::AddColumn(col_spec) => {
             exec_alter_add_column(table_def, col_spec, pager, catalog)
         }
         AlterTableOp::DropColumn(col_name) => {
-            exec_alter_drop_column(table_def, col_name, &at.table_name, pager, catalog)
+            exec_alter_drop_column(table_def, col_name, table_name, pager, catalog)
         }
         AlterTableOp::ModifyColumn(col_spec) => {
-            exec_alter_modify_column(table_def, col_spec, &at.table_name, pager, catalog)
+            exec_alter_modify_column(table_def, col_spec, table_name, pager, catalog)
+        }
+        AlterTableOp::ChangeColumn(old_name, col_spec) => {
+            exec_alter_change_column(table_def, old_name, col_spec, table_name, pager, catalog)
         }
-        AlterTableOp::ChangeColumn(old_name, col_spec) => exec_alter_change_column(
-            table_def,
-            old_name,
-            col_spec,
-            &at.table_name,
-            pager,
-            catalog,
-        ),
         AlterTableOp::AddForeignKey(fk) => {
-            exec_alter_add_foreign_key(table_def, fk, &at.table_name, pager, catalog)
+            exec_alter_add_foreign_key(table_def, fk, table_name, pager, catalog)
         }
         AlterTableOp::DropForeignKey(columns) => {
-            exec_alter_drop_foreign_key(table_def, columns, &at.table_name, pager, catalog)
+            exec_alter_drop_foreign_key(table_def, columns, table_name, pager, catalog)
+        }
+        AlterTableOp::ExchangeWith(other) => exec_alter_exchange(table_def, other, pager, catalog),
+        AlterTableOp::AddIndex(ci) => exec_create_index(ci, pager, catalog),
+        AlterTableOp::DropIndex(index_name) => {
+            table_index(&table_def, index_name, pager, catalog)?;
+            exec_drop_index(
+                &DropIndex {
+                    index_name: index_name.clone(),
+                    if_exists: false,
+                },
+                pager,
+                catalog,
+            )
         }
+        AlterTableOp::RenameIndex(old_name, new_name) => {
+            table_index(&table_def, old_name, pager, catalog)?;
+            catalog.rename_index(pager, old_name, new_name)?;
+            Ok(ExecResult::Ok)
+        }
+    }
+}
+
+/// The index `index_name`, which must be one of `table_def`'s.
+fn table_index(
+    table_def: &TableDef,
+    index_name: &str,
+    pager: &mut impl PageStore,
+    catalog: &mut SystemCatalog,
+) -> Result<IndexDef> {
+    match catalog.get_index(pager, index_name)? {
+        Some(idx) if idx.table_name == table_def.name => Ok(idx),
+        Some(idx) => Err(MuroError::Schema(format!(
+            "Index '{}' belongs to table '{}', not '{}'",
+            index_name, idx.table_name, table_def.name
+        ))),
+        None => Err(MuroError::Schema(format!(
+            "Index '{}' does not exist on table '{}'",
+            index_name, table_def.name
+        ))),
+    }
+}
+
+/// Swap the contents of two tables with identical schemas: rows, indexes,
+/// statistics, and counters trade places while table and index names stay
+/// put. Both catalog entries change in the same statement,
+/// so readers see either the old mapping or the new one.
+fn exec_alter_exchange(
+    mut table_def: TableDef,
+    other_name: &str,
+    pager: &mut impl PageStore,
+    catalog: &mut SystemCatalog,
+) -> Result<ExecResult> {
+    let mut other_def = catalog
+        .get_table(pager, other_name)?
+        .ok_or_else(|| MuroError::Schema(format!("Table '{}' not found", other_name)))?;
+    if table_def.name == other_def.name {
+        return Err(MuroError::Schema(format!(
+            "Cannot exchange table '{}' with itself",
+            table_def.name
+        )));
+    }
+    if !table_def.foreign_keys.is_empty() || !other_def.foreign_keys.is_empty() {
+        return Err(MuroError::Schema(
+            "Cannot exchange tables that have FOREIGN KEY constraints".into(),
+        ));
+    }
+    for name in catalog.list_tables(pager)? {
+        let Some(child) = catalog.get_table(pager, &name)? else {
+            continue;
+        };
+        if let Some(fk) = child
+            .foreign_keys
+            .iter()
+            .find(|fk| fk.ref_table == table_def.name || fk.ref_table == other_def.name)
+        {
+            return Err(MuroError::Schema(format!(
+                "Cannot exchange '{}' while table '{}' references it",
+                fk.ref_table, child.name
+            )));
+        }
+    }
+
+    // Each table must satisfy the other's exact schema, checked both ways so
+    // that widened columns or extra indexes on either side are rejected.
+    let mut indexes = catalog.get_indexes_for_table(pager, &table_def.name)?;
+    let mut other_indexes = catalog.get_indexes_for_table(pager, &other_def.name)?;
+    let exact = SchemaExpectation::new()
+        .allow_extra_columns(false)
+        .allow_extra_indexes(false);
+    let mut diff = SchemaDiff::default();
+    for (shape, shape_indexes, target, target_indexes) in [
+        (&table_def, &indexes, &other_def, &other_indexes),
+        (&other_def, &other_indexes, &table_def, &indexes),
+    ] {
+        let expected = ExpectedTable {
+            name: target.name.clone(),
+            ..ExpectedTable::from_live(shape, shape_indexes)
+        };
+        exact.compare_table(&expected, target, target_indexes, &mut diff);
+    }
+    let visible = |def: &TableDef| -> Vec<String> {
+        def.columns
+            .iter()
+            .filter(|c| !c.is_hidden)
+            .map(|c| c.name.clone())
+            .collect()
+    };
+    if !diff.is_compatible() {
+        return Err(MuroError::Schema(format!(
+            "Cannot exchange '{}' with '{}': schemas differ: {}",
+            table_def.name, other_def.name, diff
+        )));
+    }
+    if visible(&table_def) != visible(&other_def) {
+        return Err(MuroError::Schema(format!(
+            "Cannot exchange '{}' with '{}': column order differs",
+            table_def.name, other_def.name
+        )));
     }
+    // Index keys of collated columns hold collation keys.
+    if let Some(col) = table_def
+        .columns
+        .iter()
+        .zip(&other_def.columns)
+        .find(|(a, b)| a.collation != b.collation)
+        .map(|(a, _)| a)
+    {
+        return Err(MuroError::Schema(format!(
+            "Cannot exchange '{}' with '{}': column collation differs on '{}'",
+            table_def.name, other_def.name, col.name
+        )));
+    }
+
+    // Pair each index with its structural twin on the other table; the
+    // physical state moves while the name stays with its table.
+    let mut paired = Vec::with_capacity(indexes.len());
+    let mut taken = vec![false; other_indexes.len()];
+    for idx in &indexes {
+        let twin = other_indexes.iter().enumerate().position(|(i, o)| {
+            !taken[i]
+                && o.column_names == idx.column_names
+                && o.is_unique == idx.is_unique
+                && o.index_type == idx.index_type
+                && o.expressions == idx.expressions
+        });
+        let Some(i) = twin else {
+            return Err(MuroError::Schema(format!(
+                "Cannot exchange '{}' with '{}': index '{}' has no counterpart",
+                table_def.name, other_def.name, idx.name
+            )));
+        };
+        taken[i] = true;
+        paired.push(i);
+    }
+    for (idx, i) in indexes.iter_mut().zip(paired) {
+        let other_idx = &mut other_indexes[i];
+        std::mem::swap(idx, other_idx);
+        std::mem::swap(&mut idx.name, &mut other_idx.name);
+        std::mem::swap(&mut idx.table_name, &mut other_idx.table_name);
+    }
+
+    std::mem::swap(&mut table_def, &mut other_def);
+    std::mem::swap(&mut table_def.name, &mut other_def.name);
+    catalog.update_table(pager, &table_def)?;
+    catalog.update_table(pager, &other_def)?;
+    for idx in indexes.iter().chain(&other_indexes) {
+        catalog.update_index(pager, idx)?;
+    }
+    forget_auto_increment_current(&table_def.name);
+    forget_auto_increment_current(&other_def.name);
+    Ok(ExecResult::Ok)
 }
 
 fn exec_alter_add_foreign_key(
@@ -61,6 +243,7 @@ fn exec_alter_add_foreign_key(
     }) {
         return Err(MuroError::Schema("Duplicate FOREIGN KEY constraint".into()));
     }
+    reject_temp_foreign_key(catalog, pager, table_name, &fk.ref_table)?;
 
     let mut child_types = Vec::with_capacity(fk.columns.len());
     for col_name in &fk.columns {
@@ -86,7 +269,7 @@ fn exec_alter_add_foreign_key(
         }
         let mut unique_sets = vec![table_def.pk_columns.clone()];
         for idx in catalog.get_indexes_for_table(pager, table_name)? {
-            if idx.is_unique {
+            if idx.is_unique && !idx.has_expressions() {
                 unique_sets.push(idx.column_names);
             }
         }
@@ -110,7 +293,7 @@ fn exec_alter_add_foreign_key(
         }
         let mut unique_sets = vec![parent_def.pk_columns.clone()];
         for idx in catalog.get_indexes_for_table(pager, &fk.ref_table)? {
-            if idx.is_unique {
+            if idx.is_unique && !idx.has_expressions() {
                 unique_sets.push(idx.column_names);
             }
         }
@@ -214,12 +397,18 @@ pub(super) fn exec_alter_add_column(
     pager: &mut impl PageStore,
     catalog: &mut SystemCatalog,
 ) -> Result<ExecResult> {
-    // Validate: column doesn't already exist
-    if table_def.column_index(&col_spec.name).is_some() {
-        return Err(MuroError::Schema(format!(
-            "Column '{}' already exists in table '{}'",
-            col_spec.name, table_def.name
-        )));
+    Limit::IdentifierBytes.check(col_spec.name.len())?;
+    Limit::ColumnsPerTable.check(table_def.columns.len() + 1)?;
+    // Validate: column doesn't already exist, also under identifier folding
+    let existing = table_def.columns.iter().map(|c| c.name.as_str());
+    if let Some(other) = folded_collision(&col_spec.name, existing) {
+        if other == col_spec.name {
+            return Err(MuroError::Schema(format!(
+                "Column '{}' already exists in table '{}'",
+                col_spec.name, table_def.name
+            )));
+        }
+        return Err(collision_error("Column", &col_spec.name, other));
     }
     // Don't allow adding PK column
     if col_spec.is_primary_key {
@@ -259,19 +448,34 @@ pub(super) fn exec_alter_add_column(
         col = col.with_auto_increment();
     }
     if let Some(default_expr) = &col_spec.default_value {
-        col.default_value = ast_expr_to_default(default_expr);
+        col.default_value = Some(ast_expr_to_default(default_expr, col_spec.data_type)?);
     }
     if let Some(check) = &col_spec.check_expr {
         col.check_expr = Some(expr_to_string(check));
     }
-
-    table_def.columns.push(col);
+    col.collation = column_collation(col_spec)?;
+
+    // Existing rows take an expression default evaluated once, now, and
+    // stored; literal defaults are read from the column definition instead.
+    let default_val = match &col.default_value {
+        Some(DefaultValue::Expr(_)) => {
+            let value = coerce_value(&eval_column_default(&col)?, col.data_type)?;
+            let old_columns = table_def.columns.clone();
+            table_def.columns.push(col);
+            materialize_rows(&mut table_def, &old_columns, &value, pager)?;
+            value
+        }
+        _ => {
+            let value = default_value_for_column(&col);
+            table_def.columns.push(col);
+            value
+        }
+    };
     catalog.update_table(pager, &table_def)?;
 
     // Create unique index if UNIQUE was specified, and backfill existing rows
     if col_spec.is_unique && !col_spec.is_primary_key {
         let new_col = table_def.columns.last().unwrap();
-        let default_val = default_value_for_column(new_col);
 
         let idx_btree = BTree::create(pager)?;
         let mut idx_btree_mut = BTree::open(idx_btree.root_page_id());
@@ -279,7 +483,10 @@ pub(super) fn exec_alter_add_column(
         // Backfill: insert default value for all existing rows into the index.
         // For non-NULL defaults, duplicates are detected during backfill.
         if !default_val.is_null() {
-            let idx_key = encode_value(&default_val, &new_col.data_type);
+            let idx_key = encode_value(
+                &new_col.collation.key_value(&default_val),
+                &new_col.data_type,
+            );
             let data_btree = BTree::open(table_def.data_btree_root);
             let mut pk_keys: Vec<Vec<u8>> = Vec::new();
             data_btree.scan(pager, |k, _v| {
@@ -312,6 +519,12 @@ pub(super) fn exec_alter_add_column(
             stats_num_hist_bins: Vec::new(),
             fts_stop_filter: false,
             fts_stop_df_ratio_ppm: 0,
+            fts_stop_fallback: FtsStopFallback::Rescan,
+            fts_stop_fallback_max_docs: 0,
+            expressions: Vec::new(),
+            fill_factor: DEFAULT_FILL_FACTOR,
+            building: false,
+            trailing_fields: Vec::new(),
         };
         catalog.create_index(pager, idx_def)?;
     }
@@ -341,7 +554,7 @@ pub(super) fn exec_alter_drop_column(
     // Check if any index references this column
     let indexes = catalog.get_indexes_for_table(pager, table_name)?;
     for idx in &indexes {
-        if idx.column_names.contains(&col_name.to_string()) {
+        if index_referenced_columns(idx)?.iter().any(|c| c == col_name) {
             return Err(MuroError::Schema(format!(
                 "Cannot drop column '{}': index '{}' depends on it",
                 col_name, idx.name
@@ -385,6 +598,7 @@ pub(super) fn exec_alter_drop_column(
 
     // Create new column list without the dropped column
     table_def.columns.remove(col_idx);
+    table_def.forget_column_stats(col_name);
 
     // Rewrite all rows
     // Free old data pages and create a new B-tree
@@ -432,8 +646,11 @@ pub(super) fn exec_alter_modify_column(
     })?;
 
     let old_col = &table_def.columns[col_idx];
-    let type_changed = old_col.data_type != col_spec.data_type;
+    let type_changed = !old_col
+        .data_type
+        .is_metadata_only_change_to(col_spec.data_type);
     let adding_not_null = old_col.is_nullable && !col_spec.is_nullable;
+    let old_collation = old_col.collation;
 
     // If adding NOT NULL constraint, validate existing rows
     if adding_not_null {
@@ -441,6 +658,8 @@ pub(super) fn exec_alter_modify_column(
     }
 
     if type_changed {
+        let indexes = catalog.get_indexes_for_table(pager, table_name)?;
+        ensure_no_expression_index_on(&indexes, &col_spec.name)?;
         // Full table rewrite with type coercion
         let old_columns = table_def.columns.clone();
         let data_btree = BTree::open(table_def.data_btree_root);
@@ -454,7 +673,7 @@ pub(super) fn exec_alter_modify_column(
         })?;
 
         // Update column def
-        update_column_def(&mut table_def.columns[col_idx], col_spec);
+        update_column_def(&mut table_def.columns[col_idx], col_spec)?;
 
         // Rewrite with coerced values
         let old_pages = data_btree.collect_all_pages(pager)?;
@@ -466,6 +685,7 @@ pub(super) fn exec_alter_modify_column(
 
         for (key, mut row_values) in entries {
             row_values[col_idx] = coerce_value(&row_values[col_idx], col_spec.data_type)?;
+            validate_row(&table_def, &row_values)?;
             let new_data = serialize_row(&row_values, &table_def.columns);
             new_btree.insert(pager, &key, &new_data)?;
         }
@@ -474,10 +694,16 @@ pub(super) fn exec_alter_modify_column(
         table_def.row_format_version = 1; // rewritten rows are v1 format
     } else {
         // Metadata-only change
-        update_column_def(&mut table_def.columns[col_idx], col_spec);
+        let old_columns = table_def.columns.clone();
+        update_column_def(&mut table_def.columns[col_idx], col_spec)?;
+        materialize_if_default_became_expr(&mut table_def, &old_columns, col_idx, pager)?;
     }
+    table_def.forget_column_stats(&col_spec.name);
 
     catalog.update_table(pager, &table_def)?;
+    if table_def.columns[col_idx].collation != old_collation {
+        rekey_after_collation_change(&mut table_def, col_idx, pager, catalog)?;
+    }
 
     // Reconcile unique index: create or drop as needed
     reconcile_unique_index(&table_def, col_spec, &col_spec.name, pager, catalog)?;
@@ -509,9 +735,25 @@ pub(super) fn exec_alter_change_column(
         ))
     })?;
 
+    // The new name must not collide with another column; the column itself
+    // may change its spelling.
+    Limit::IdentifierBytes.check(col_spec.name.len())?;
+    let siblings = table_def
+        .columns
+        .iter()
+        .enumerate()
+        .filter(|(i, _)| *i != col_idx)
+        .map(|(_, c)| c.name.as_str());
+    if let Some(other) = folded_collision(&col_spec.name, siblings) {
+        return Err(collision_error("Column", &col_spec.name, other));
+    }
+
     let old_col = &table_def.columns[col_idx];
-    let type_changed = old_col.data_type != col_spec.data_type;
+    let type_changed = !old_col
+        .data_type
+        .is_metadata_only_change_to(col_spec.data_type);
     let adding_not_null = old_col.is_nullable && !col_spec.is_nullable;
+    let old_collation = old_col.collation;
 
     // If adding NOT NULL constraint, validate existing rows
     if adding_not_null {
@@ -520,20 +762,12 @@ pub(super) fn exec_alter_change_column(
 
     // Update any indexes referencing the old column name
     let indexes = catalog.get_indexes_for_table(pager, table_name)?;
+    if type_changed {
+        ensure_no_expression_index_on(&indexes, old_name)?;
+    }
     for mut idx in indexes {
-        let mut changed = false;
-        for cn in &mut idx.column_names {
-            if cn == old_name {
-                *cn = col_spec.name.clone();
-                changed = true;
-            }
-        }
-        if changed {
-            let idx_key = format!("index:{}", idx.name);
-            let idx_serialized = idx.serialize();
-            catalog
-                .catalog_btree_mut()
-                .insert(pager, idx_key.as_bytes(), &idx_serialized)?;
+        if rename_index_column(&mut idx, old_name, &col_spec.name)? {
+            catalog.update_index(pager, &idx)?;
         }
     }
 
@@ -550,7 +784,7 @@ pub(super) fn exec_alter_change_column(
         })?;
 
         // Update column def (including name change)
-        update_column_def(&mut table_def.columns[col_idx], col_spec);
+        update_column_def(&mut table_def.columns[col_idx], col_spec)?;
 
         let old_pages = data_btree.collect_all_pages(pager)?;
         for page_id in old_pages {
@@ -561,6 +795,7 @@ pub(super) fn exec_alter_change_column(
 
         for (key, mut row_values) in entries {
             row_values[col_idx] = coerce_value(&row_values[col_idx], col_spec.data_type)?;
+            validate_row(&table_def, &row_values)?;
             let new_data = serialize_row(&row_values, &table_def.columns);
             new_btree.insert(pager, &key, &new_data)?;
         }
@@ -568,10 +803,16 @@ pub(super) fn exec_alter_change_column(
         table_def.data_btree_root = new_btree.root_page_id();
         table_def.row_format_version = 1; // rewritten rows are v1 format
     } else {
-        update_column_def(&mut table_def.columns[col_idx], col_spec);
+        let old_columns = table_def.columns.clone();
+        update_column_def(&mut table_def.columns[col_idx], col_spec)?;
+        materialize_if_default_became_expr(&mut table_def, &old_columns, col_idx, pager)?;
     }
+    table_def.forget_column_stats(old_name);
 
     catalog.update_table(pager, &table_def)?;
+    if table_def.columns[col_idx].collation != old_collation {
+        rekey_after_collation_change(&mut table_def, col_idx, pager, catalog)?;
+    }
 
     // Reconcile unique index: create or drop as needed
     reconcile_unique_index(&table_def, col_spec, old_name, pager, catalog)?;
@@ -579,6 +820,90 @@ pub(super) fn exec_alter_change_column(
     Ok(ExecResult::Ok)
 }
 
+/// Re-encode the keys that contain column `col_idx` after its collation
+/// changed: the data B-tree when the column is part of the primary key (and
+/// with it every secondary index, whose entries point at primary keys), and
+/// otherwise the B-tree indexes over the column. Fails with a unique
+/// violation when two keys become equal under the new collation.
+fn rekey_after_collation_change(
+    table_def: &mut TableDef,
+    col_idx: usize,
+    pager: &mut impl PageStore,
+    catalog: &mut SystemCatalog,
+) -> Result<()> {
+    let col_name = table_def.columns[col_idx].name.clone();
+    let collation = table_def.columns[col_idx].collation;
+    let mut indexes = catalog.get_indexes_for_table(pager, &table_def.name)?;
+    let in_pk = table_def.pk_columns.contains(&col_name);
+
+    if in_pk {
+        // FULLTEXT doc-id mappings are keyed by primary key.
+        if indexes
+            .iter()
+            .any(|idx| idx.index_type == IndexType::Fulltext)
+        {
+            return Err(MuroError::Schema(format!(
+                "Cannot change the collation of primary key column '{}' while table '{}' has a FULLTEXT index",
+                col_name, table_def.name
+            )));
+        }
+        let old_btree = BTree::open(table_def.data_btree_root);
+        let mut rows: Vec<Vec<u8>> = Vec::new();
+        old_btree.scan(pager, |_k, v| {
+            rows.push(v.to_vec());
+            Ok(true)
+        })?;
+        let mut new_btree = BTree::create(pager)?.with_fill_factor(table_def.fill_factor);
+        for data in rows {
+            let values =
+                deserialize_row_versioned(&data, &table_def.columns, table_def.row_format_version)?;
+            let key = encode_pk_key(table_def, &values);
+            if new_btree.search(pager, &key)?.is_some() {
+                return Err(MuroError::UniqueViolation(format!(
+                    "Duplicate primary key in column '{}' under collation {}",
+                    col_name, collation
+                )));
+            }
+            new_btree.insert(pager, &key, &data)?;
+        }
+        for page_id in old_btree.collect_all_pages(pager)? {
+            pager.free_page(page_id);
+        }
+        table_def.data_btree_root = new_btree.root_page_id();
+        catalog.update_table(pager, table_def)?;
+    }
+
+    for idx in indexes.iter_mut() {
+        if idx.index_type != IndexType::BTree || !(in_pk || idx.column_names.contains(&col_name)) {
+            continue;
+        }
+        let Some(parts) = index_key_parts(table_def, idx)? else {
+            continue;
+        };
+        let mut idx_btree = BTree::create(pager)?.with_fill_factor(idx.fill_factor);
+        build_index_from_rows(
+            table_def,
+            &mut idx_btree,
+            idx.is_unique,
+            pager,
+            |row_values| encode_index_key(table_def, &parts, row_values),
+            || {
+                MuroError::UniqueViolation(format!(
+                    "Duplicate value in column(s) '{}' under collation {}",
+                    idx.column_names.join(", "),
+                    collation
+                ))
+            },
+        )?;
+        for page_id in BTree::open(idx.btree_root).collect_all_pages(pager)? {
+            pager.free_page(page_id);
+        }
+        idx.btree_root = idx_btree.root_page_id();
+        catalog.update_index(pager, idx)?;
+    }
+    Ok(())
+}
+
 /// Reconcile unique index for a column after ALTER TABLE MODIFY/CHANGE.
 /// Creates a new unique index if UNIQUE was added, or drops existing one if UNIQUE was removed.
 pub(super) fn reconcile_unique_index(
@@ -600,36 +925,27 @@ pub(super) fn reconcile_unique_index(
         let col_idx = table_def
             .column_index(&col_spec.name)
             .ok_or_else(|| MuroError::Schema(format!("Column '{}' not found", col_spec.name)))?;
-        let data_btree = BTree::open(table_def.data_btree_root);
         let col_data_type = table_def.columns[col_idx].data_type;
-
-        let mut seen_keys: Vec<Vec<u8>> = Vec::new();
-        let mut idx_entries: Vec<(Vec<u8>, Vec<u8>)> = Vec::new();
-        data_btree.scan(pager, |pk_key, v| {
-            let row_values =
-                deserialize_row_versioned(v, &table_def.columns, table_def.row_format_version)?;
-            if col_idx < row_values.len() {
-                let val = &row_values[col_idx];
-                if !val.is_null() {
-                    let encoded = encode_value(val, &col_data_type);
-                    if seen_keys.contains(&encoded) {
-                        return Err(MuroError::UniqueViolation(format!(
-                            "Duplicate value in column '{}'; cannot add UNIQUE constraint",
-                            col_spec.name
-                        )));
-                    }
-                    seen_keys.push(encoded.clone());
-                    idx_entries.push((encoded, pk_key.to_vec()));
-                }
-            }
-            Ok(true)
-        })?;
-
-        let idx_btree = BTree::create(pager)?;
-        let mut idx_btree_mut = BTree::open(idx_btree.root_page_id());
-        for (idx_key, pk_key) in &idx_entries {
-            idx_btree_mut.insert(pager, idx_key, pk_key)?;
-        }
+        let col_collation = table_def.columns[col_idx].collation;
+        let mut idx_btree = BTree::create(pager)?;
+        build_index_from_rows(
+            table_def,
+            &mut idx_btree,
+            true,
+            pager,
+            |row_values| {
+                Ok(row_values
+                    .get(col_idx)
+                    .filter(|val| !val.is_null())
+                    .map(|val| encode_value(&col_collation.key_value(val), &col_data_type)))
+            },
+            || {
+                MuroError::UniqueViolation(format!(
+                    "Duplicate value in column '{}'; cannot add UNIQUE constraint",
+                    col_spec.name
+                ))
+            },
+        )?;
 
         let idx_def = IndexDef {
             name: format!("auto_unique_{}_{}", table_def.name, col_spec.name),
@@ -637,7 +953,7 @@ pub(super) fn reconcile_unique_index(
             column_names: vec![col_spec.name.clone()],
             index_type: IndexType::BTree,
             is_unique: true,
-            btree_root: idx_btree_mut.root_page_id(),
+            btree_root: idx_btree.root_page_id(),
             stats_distinct_keys: 0,
             stats_num_min: 0,
             stats_num_max: 0,
@@ -645,6 +961,12 @@ pub(super) fn reconcile_unique_index(
             stats_num_hist_bins: Vec::new(),
             fts_stop_filter: false,
             fts_stop_df_ratio_ppm: 0,
+            fts_stop_fallback: FtsStopFallback::Rescan,
+            fts_stop_fallback_max_docs: 0,
+            expressions: Vec::new(),
+            fill_factor: DEFAULT_FILL_FACTOR,
+            building: false,
+            trailing_fields: Vec::new(),
         };
         catalog.create_index(pager, idx_def)?;
     } else if !col_spec.is_unique {
@@ -721,257 +1043,75 @@ fn ensure_column_not_fk_dependent(
 }
 
 /// Update a ColumnDef in place from a ColumnSpec.
-pub(super) fn update_column_def(col: &mut ColumnDef, spec: &ColumnSpec) {
+pub(super) fn update_column_def(col: &mut ColumnDef, spec: &ColumnSpec) -> Result<()> {
     col.name = spec.name.clone();
     col.data_type = spec.data_type;
     col.is_unique = spec.is_unique;
     col.is_nullable = spec.is_nullable;
     col.auto_increment = spec.auto_increment;
-    if let Some(default_expr) = &spec.default_value {
-        col.default_value = ast_expr_to_default(default_expr);
-    } else {
-        col.default_value = None;
-    }
+    col.default_value = match &spec.default_value {
+        Some(default_expr) => Some(ast_expr_to_default(default_expr, spec.data_type)?),
+        None => None,
+    };
     if let Some(check) = &spec.check_expr {
         col.check_expr = Some(expr_to_string(check));
     } else {
         col.check_expr = None;
     }
+    col.collation = column_collation(spec)?;
+    Ok(())
 }
 
-/// Coerce a value to a target data type.
-pub(super) fn coerce_value(value: &Value, target_type: DataType) -> Result<Value> {
-    const I64_MIN_F64: f64 = -9_223_372_036_854_775_808.0; // -2^63
-    const I64_UPPER_EXCLUSIVE_F64: f64 = 9_223_372_036_854_775_808.0; // 2^63
-
-    fn float_to_i64_checked(n: f64) -> Result<i64> {
-        if !n.is_finite() {
-            return Err(MuroError::Execution(format!(
-                "Cannot convert non-finite float '{}' to integer",
-                n
-            )));
-        }
-        if !(I64_MIN_F64..I64_UPPER_EXCLUSIVE_F64).contains(&n) {
-            return Err(MuroError::Execution(format!(
-                "Float '{}' out of range for integer conversion",
-                n
-            )));
-        }
-        Ok(n as i64)
+/// Rows written before a column was added read its literal default from the
+/// column definition. Before that default becomes an expression, store the
+/// value those rows currently read so they keep it.
+fn materialize_if_default_became_expr(
+    table_def: &mut TableDef,
+    old_columns: &[ColumnDef],
+    col_idx: usize,
+    pager: &mut impl PageStore,
+) -> Result<()> {
+    let became_expr = matches!(
+        table_def.columns[col_idx].default_value,
+        Some(DefaultValue::Expr(_))
+    ) && !matches!(
+        old_columns[col_idx].default_value,
+        Some(DefaultValue::Expr(_))
+    );
+    if became_expr {
+        materialize_rows(table_def, old_columns, &Value::Null, pager)?;
     }
+    Ok(())
+}
 
-    fn validate_float_for_target(n: f64, target_type: DataType) -> Result<f64> {
-        if !n.is_finite() {
-            return Err(MuroError::Execution(format!(
-                "Cannot convert non-finite float '{}' to {}",
-                n, target_type
-            )));
-        }
-        if target_type == DataType::Float && (n < f32::MIN as f64 || n > f32::MAX as f64) {
-            return Err(MuroError::Execution(format!(
-                "Float '{}' out of range for FLOAT",
-                n
-            )));
-        }
-        Ok(n)
+/// Rewrite every row with all of `table_def`'s columns stored. Rows are
+/// decoded with `old_columns`; columns past those take `added`.
+fn materialize_rows(
+    table_def: &mut TableDef,
+    old_columns: &[ColumnDef],
+    added: &Value,
+    pager: &mut impl PageStore,
+) -> Result<()> {
+    let mut data_btree = BTree::open(table_def.data_btree_root);
+    let mut entries: Vec<(Vec<u8>, Vec<Value>)> = Vec::new();
+    data_btree.scan(pager, |k, v| {
+        let row_values = deserialize_row_versioned(v, old_columns, table_def.row_format_version)?;
+        entries.push((k.to_vec(), row_values));
+        Ok(true)
+    })?;
+    for (key, mut row_values) in entries {
+        row_values.resize(table_def.columns.len(), added.clone());
+        data_btree.insert(pager, &key, &serialize_row(&row_values, &table_def.columns))?;
     }
+    table_def.data_btree_root = data_btree.root_page_id();
+    table_def.row_format_version = 1;
+    Ok(())
+}
 
-    match value {
-        Value::Null => Ok(Value::Null),
-        Value::Integer(n) => match target_type {
-            DataType::TinyInt | DataType::SmallInt | DataType::Int | DataType::BigInt => {
-                Ok(Value::Integer(*n))
-            }
-            DataType::Float | DataType::Double => Ok(Value::Float(validate_float_for_target(
-                *n as f64,
-                target_type,
-            )?)),
-            DataType::Decimal(p, s) => {
-                let d = rust_decimal::Decimal::from(*n);
-                Ok(Value::Decimal(super::codec::fit_decimal(d, p, s)?))
-            }
-            DataType::Date | DataType::DateTime | DataType::Timestamp => Err(MuroError::Execution(
-                "Cannot coerce integer to date/time type".into(),
-            )),
-            DataType::Varchar(_) | DataType::Text => Ok(Value::Varchar(n.to_string())),
-            DataType::Jsonb => Ok(Value::Varchar(canonicalize_json_text(&n.to_string())?)),
-            DataType::Varbinary(_) => Err(MuroError::Execution(
-                "Cannot coerce integer to VARBINARY".into(),
-            )),
-            DataType::Uuid => Err(MuroError::Execution("Cannot coerce integer to UUID".into())),
-        },
-        Value::Float(n) => match target_type {
-            DataType::Float | DataType::Double => {
-                Ok(Value::Float(validate_float_for_target(*n, target_type)?))
-            }
-            DataType::TinyInt | DataType::SmallInt | DataType::Int | DataType::BigInt => {
-                Ok(Value::Integer(float_to_i64_checked(*n)?))
-            }
-            DataType::Decimal(p, s) => {
-                // Use string representation to avoid floating-point artifacts
-                use std::str::FromStr;
-                let str_val = n.to_string();
-                let d = rust_decimal::Decimal::from_str(&str_val).map_err(|_| {
-                    MuroError::Execution(format!("Cannot coerce float '{}' to DECIMAL", n))
-                })?;
-                Ok(Value::Decimal(super::codec::fit_decimal(d, p, s)?))
-            }
-            DataType::Date | DataType::DateTime | DataType::Timestamp => Err(MuroError::Execution(
-                "Cannot coerce float to date/time type".into(),
-            )),
-            DataType::Varchar(_) | DataType::Text => Ok(Value::Varchar(n.to_string())),
-            DataType::Jsonb => Ok(Value::Varchar(canonicalize_json_text(&n.to_string())?)),
-            DataType::Varbinary(_) => Err(MuroError::Execution(
-                "Cannot coerce floating-point value to VARBINARY".into(),
-            )),
-            DataType::Uuid => Err(MuroError::Execution("Cannot coerce float to UUID".into())),
-        },
-        Value::Decimal(d) => match target_type {
-            DataType::Decimal(p, s) => Ok(Value::Decimal(super::codec::fit_decimal(*d, p, s)?)),
-            DataType::TinyInt | DataType::SmallInt | DataType::Int | DataType::BigInt => {
-                use rust_decimal::prelude::ToPrimitive;
-                let truncated = d.trunc();
-                truncated.to_i64().map(Value::Integer).ok_or_else(|| {
-                    MuroError::Execution(format!(
-                        "Decimal '{}' out of range for integer conversion",
-                        d
-                    ))
-                })
-            }
-            DataType::Float | DataType::Double => {
-                use rust_decimal::prelude::ToPrimitive;
-                let n = d.to_f64().ok_or_else(|| {
-                    MuroError::Execution(format!("Cannot convert Decimal '{}' to float", d))
-                })?;
-                Ok(Value::Float(n))
-            }
-            DataType::Varchar(_) | DataType::Text => Ok(Value::Varchar(d.to_string())),
-            DataType::Jsonb => Ok(Value::Varchar(canonicalize_json_text(&d.to_string())?)),
-            _ => Err(MuroError::Execution(
-                "Cannot coerce DECIMAL to target type".into(),
-            )),
-        },
-        Value::Varchar(s) => match target_type {
-            DataType::TinyInt | DataType::SmallInt | DataType::Int | DataType::BigInt => {
-                let n: i64 = s.parse().map_err(|_| {
-                    MuroError::Execution(format!("Cannot convert '{}' to integer", s))
-                })?;
-                Ok(Value::Integer(n))
-            }
-            DataType::Float | DataType::Double => {
-                let n: f64 = s.parse().map_err(|_| {
-                    MuroError::Execution(format!("Cannot convert '{}' to float", s))
-                })?;
-                Ok(Value::Float(validate_float_for_target(n, target_type)?))
-            }
-            DataType::Decimal(p, sc) => {
-                use std::str::FromStr;
-                let d = rust_decimal::Decimal::from_str(s).map_err(|_| {
-                    MuroError::Execution(format!("Cannot convert '{}' to DECIMAL", s))
-                })?;
-                Ok(Value::Decimal(super::codec::fit_decimal(d, p, sc)?))
-            }
-            DataType::Date => {
-                let d = parse_date_string(s).ok_or_else(|| {
-                    MuroError::Execution(format!("Cannot convert '{}' to DATE", s))
-                })?;
-                Ok(Value::Date(d))
-            }
-            DataType::DateTime => {
-                let dt = parse_datetime_string(s).ok_or_else(|| {
-                    MuroError::Execution(format!("Cannot convert '{}' to DATETIME", s))
-                })?;
-                Ok(Value::DateTime(dt))
-            }
-            DataType::Timestamp => {
-                let ts = parse_timestamp_string(s).ok_or_else(|| {
-                    MuroError::Execution(format!("Cannot convert '{}' to TIMESTAMP", s))
-                })?;
-                Ok(Value::Timestamp(ts))
-            }
-            DataType::Varchar(_) | DataType::Text => Ok(Value::Varchar(s.clone())),
-            DataType::Jsonb => Ok(Value::Varchar(canonicalize_json_text(s)?)),
-            DataType::Varbinary(_) => Ok(Value::Varbinary(s.as_bytes().to_vec())),
-            DataType::Uuid => {
-                let bytes = crate::types::parse_uuid_string(s).ok_or_else(|| {
-                    MuroError::Execution(format!("Cannot convert '{}' to UUID", s))
-                })?;
-                Ok(Value::Uuid(bytes))
-            }
-        },
-        Value::Date(d) => match target_type {
-            DataType::Date => Ok(Value::Date(*d)),
-            DataType::DateTime => Ok(Value::DateTime((*d as i64) * 1_000_000)),
-            DataType::Timestamp => Ok(Value::Timestamp((*d as i64) * 1_000_000)),
-            DataType::Varchar(_) | DataType::Text => Ok(Value::Varchar(format_date(*d))),
-            DataType::Jsonb => Ok(Value::Varchar(json_string_literal(&format_date(*d))?)),
-            _ => Err(MuroError::Execution(
-                "Cannot coerce DATE to target type".into(),
-            )),
-        },
-        Value::DateTime(dt) => match target_type {
-            DataType::DateTime => Ok(Value::DateTime(*dt)),
-            DataType::Timestamp => Ok(Value::Timestamp(*dt)),
-            DataType::Date => Ok(Value::Date((*dt / 1_000_000) as i32)),
-            DataType::Varchar(_) | DataType::Text => Ok(Value::Varchar(format_datetime(*dt))),
-            DataType::Jsonb => Ok(Value::Varchar(json_string_literal(&format_datetime(*dt))?)),
-            _ => Err(MuroError::Execution(
-                "Cannot coerce DATETIME to target type".into(),
-            )),
-        },
-        Value::Timestamp(ts) => match target_type {
-            DataType::Timestamp => Ok(Value::Timestamp(*ts)),
-            DataType::DateTime => Ok(Value::DateTime(*ts)),
-            DataType::Date => Ok(Value::Date((*ts / 1_000_000) as i32)),
-            DataType::Varchar(_) | DataType::Text => Ok(Value::Varchar(format_datetime(*ts))),
-            DataType::Jsonb => Ok(Value::Varchar(json_string_literal(&format_datetime(*ts))?)),
-            _ => Err(MuroError::Execution(
-                "Cannot coerce TIMESTAMP to target type".into(),
-            )),
-        },
-        Value::Varbinary(b) => match target_type {
-            DataType::Varchar(_) | DataType::Text => {
-                let s = String::from_utf8(b.clone()).map_err(|_| {
-                    MuroError::Execution("Cannot convert VARBINARY to VARCHAR".into())
-                })?;
-                Ok(Value::Varchar(s))
-            }
-            DataType::Varbinary(_) => Ok(Value::Varbinary(b.clone())),
-            DataType::Jsonb => {
-                let s = std::str::from_utf8(b).map_err(|_| {
-                    MuroError::Execution("Cannot convert non-UTF8 VARBINARY to JSONB".into())
-                })?;
-                Ok(Value::Varchar(canonicalize_json_text(s)?))
-            }
-            DataType::Uuid => {
-                if b.len() != 16 {
-                    return Err(MuroError::Execution(format!(
-                        "VARBINARY must be 16 bytes to convert to UUID, got {}",
-                        b.len()
-                    )));
-                }
-                let mut bytes = [0u8; 16];
-                bytes.copy_from_slice(b);
-                Ok(Value::Uuid(bytes))
-            }
-            _ => Err(MuroError::Execution(
-                "Cannot coerce VARBINARY to integer type".into(),
-            )),
-        },
-        Value::Uuid(b) => match target_type {
-            DataType::Uuid => Ok(Value::Uuid(*b)),
-            DataType::Varchar(_) | DataType::Text => {
-                Ok(Value::Varchar(crate::types::format_uuid(b)))
-            }
-            DataType::Jsonb => Ok(Value::Varchar(json_string_literal(
-                &crate::types::format_uuid(b),
-            )?)),
-            DataType::Varbinary(_) => Ok(Value::Varbinary(b.to_vec())),
-            _ => Err(MuroError::Execution(
-                "Cannot coerce UUID to target type".into(),
-            )),
-        },
-    }
+/// Coerce a value to a target data type, the same way `CAST` does; see
+/// [`convert_value`].
+pub(super) fn coerce_value(value: &Value, target_type: DataType) -> Result<Value> {
+    convert_value(value, target_type)
 }
 
 fn canonicalize_json_text(s: &str) -> Result<String> {
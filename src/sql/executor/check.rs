@@ -0,0 +1,603 @@
+use super::fts::{fts_pk_to_doc_key, SQL_FTS_DOC2PK_PREFIX, SQL_FTS_PK2DOC_PREFIX};
+use super::*;
+use crate::btree::verify::BTreeCheck;
+use crate::sql::session::PageOwner;
+
+/// Detail rows reported per object before the rest are summarized, so a
+/// wrecked index cannot flood the report.
+const MAX_PROBLEMS_PER_OBJECT: usize = 50;
+
+/// Accumulated `CHECK TABLE` / `verify_integrity` output.
+///
+/// Besides the `(object, status, detail)` rows, it remembers which object
+/// owns every reachable page so cross-object sharing and freelist overlap can
+/// be reported.
+#[derive(Default)]
+pub(crate) struct IntegrityReport {
+    pub(crate) rows: Vec<Row>,
+    pub(crate) reachable: HashMap<PageId, PageOwner>,
+}
+
+impl IntegrityReport {
+    fn push(&mut self, object: &str, status: &str, detail: String) {
+        self.rows.push(Row {
+            values: vec![
+                ("object".to_string(), Value::Varchar(object.to_string())),
+                ("status".to_string(), Value::Varchar(status.to_string())),
+                ("detail".to_string(), Value::Varchar(detail)),
+            ],
+        });
+    }
+
+    /// Emit one `ok` row with `summary`, or one `error` row per problem.
+    pub(crate) fn record(&mut self, object: &str, summary: String, problems: Vec<String>) {
+        if problems.is_empty() {
+            self.push(object, "ok", summary);
+            return;
+        }
+        let total = problems.len();
+        for detail in problems.into_iter().take(MAX_PROBLEMS_PER_OBJECT) {
+            self.push(object, "error", detail);
+        }
+        if total > MAX_PROBLEMS_PER_OBJECT {
+            self.push(
+                object,
+                "error",
+                format!(
+                    "{} more problems not shown",
+                    total - MAX_PROBLEMS_PER_OBJECT
+                ),
+            );
+        }
+    }
+
+    /// A `warning` row: legal, but worth a look.
+    pub(crate) fn warn(&mut self, object: &str, detail: String) {
+        self.push(object, "warning", detail);
+    }
+
+    fn claim_pages(&mut self, object: &PageOwner, pages: &[PageId], problems: &mut Vec<String>) {
+        for &page_id in pages {
+            if let Some(owner) = self.reachable.get(&page_id) {
+                problems.push(format!("page {} is also used by {}", page_id, owner));
+            } else {
+                self.reachable.insert(page_id, object.clone());
+            }
+        }
+    }
+}
+
+pub(super) fn exec_check_table(
+    table_name: &str,
+    pager: &mut impl PageStore,
+    catalog: &mut SystemCatalog,
+) -> Result<ExecResult> {
+    let table_def = catalog
+        .get_table(pager, table_name)?
+        .ok_or_else(|| MuroError::Schema(format!("Table '{}' not found", table_name)))?;
+    let mut report = IntegrityReport::default();
+    check_table(&table_def, pager, catalog, &mut report)?;
+    Ok(ExecResult::Rows(report.rows))
+}
+
+/// Check the catalog, every table with its indexes, the temporary tables of
+/// every session, and every key-value namespace.
+pub(crate) fn check_database(
+    pager: &mut impl PageStore,
+    catalog: &SystemCatalog,
+) -> Result<IntegrityReport> {
+    // Permanent names only: a temporary table must not stand in for the
+    // permanent table it shadows.
+    let catalog = &SystemCatalog::open(catalog.root_page_id());
+    let mut report = IntegrityReport::default();
+    let check = BTree::open(catalog.root_page_id()).verify(pager, |_, _| {});
+    let summary = format!("{} entries, {} pages", check.entries, check.pages.len());
+    record_tree(&mut report, PageOwner::Catalog, summary, check, Vec::new());
+
+    let tables = match catalog.list_tables(pager) {
+        Ok(tables) => tables,
+        Err(e) => {
+            report.record("catalog", String::new(), vec![e.to_string()]);
+            return Ok(report);
+        }
+    };
+    let table_names: Vec<&str> = tables.iter().map(|t| t.as_str()).collect();
+    for (a, b) in folded_collision_pairs(&table_names) {
+        report.warn("catalog", identifier_collision_detail("tables", a, b));
+    }
+    let mut index_names = Vec::new();
+    for name in &tables {
+        if let Ok(indexes) = catalog.get_indexes_for_table(pager, name) {
+            index_names.extend(indexes.into_iter().map(|idx| idx.name));
+        }
+    }
+    let index_names: Vec<&str> = index_names.iter().map(|n| n.as_str()).collect();
+    for (a, b) in folded_collision_pairs(&index_names) {
+        report.warn("catalog", identifier_collision_detail("indexes", a, b));
+    }
+
+    for name in tables {
+        match catalog.get_table(pager, &name) {
+            Ok(Some(table_def)) => check_table(&table_def, pager, catalog, &mut report)?,
+            Ok(None) => report.record(
+                &name,
+                String::new(),
+                vec!["listed in the catalog but its definition is missing".into()],
+            ),
+            Err(e) => report.record(&name, String::new(), vec![e.to_string()]),
+        }
+    }
+
+    match catalog.temp_owners(pager) {
+        Ok(owners) => {
+            for owner in owners {
+                let mut temp_catalog = SystemCatalog::open(catalog.root_page_id());
+                temp_catalog.set_temp_owner(Some(owner));
+                for name in temp_catalog.list_temp_tables(pager)? {
+                    if let Some(table_def) = temp_catalog.get_table(pager, &name)? {
+                        check_table(&table_def, pager, &temp_catalog, &mut report)?;
+                    }
+                }
+            }
+        }
+        Err(e) => report.record("catalog", String::new(), vec![e.to_string()]),
+    }
+
+    match crate::kv::catalog_namespaces(pager, catalog.root_page_id()) {
+        Ok(namespaces) => {
+            for (name, root) in namespaces {
+                cancellation_point()?;
+                let check = BTree::open(root).verify(pager, |_, _| {});
+                let summary = format!("{} entries, {} pages", check.entries, check.pages.len());
+                record_tree(
+                    &mut report,
+                    PageOwner::KvNamespace(name),
+                    summary,
+                    check,
+                    Vec::new(),
+                );
+            }
+        }
+        Err(e) => report.record("catalog", String::new(), vec![e.to_string()]),
+    }
+    Ok(report)
+}
+
+/// Names created before DDL rejected identifiers that differ only by case
+/// or normalization; they remain usable by their exact spelling.
+fn identifier_collision_detail(kind: &str, a: &str, b: &str) -> String {
+    format!(
+        "{} '{}' and '{}' differ only by letter case or Unicode normalization; \
+         refer to them by their exact names",
+        kind, a, b
+    )
+}
+
+fn record_tree(
+    report: &mut IntegrityReport,
+    owner: PageOwner,
+    summary: String,
+    check: BTreeCheck,
+    extra_problems: Vec<String>,
+) {
+    let mut problems = check.problems;
+    problems.extend(extra_problems);
+    report.claim_pages(&owner, &check.pages, &mut problems);
+    report.record(&owner.to_string(), summary, problems);
+}
+
+/// Entries read per chunk when cross-checking a table against its indexes;
+/// only one chunk is held in memory at a time.
+const CHECK_CHUNK: usize = 1024;
+
+/// How the entries of one index derive from the rows.
+enum IndexShape {
+    BTree(Vec<IndexKeyPart>),
+    /// Column holding the indexed text.
+    Fulltext(usize),
+}
+
+/// Cross-check state of one secondary index.
+struct IndexCheck<'a> {
+    def: &'a IndexDef,
+    shape: IndexShape,
+    check: BTreeCheck,
+    /// Problems found walking the index entries.
+    problems: Vec<String>,
+    /// Problems found walking the rows, reported after `problems`.
+    missing: Vec<String>,
+    /// FULLTEXT documents seen.
+    docs: u64,
+    /// A lookup or scan failed; the rest of the index is not cross-checked.
+    unreadable: bool,
+}
+
+impl IndexCheck<'_> {
+    fn fail(&mut self, e: MuroError) {
+        self.problems.push(e.to_string());
+        self.unreadable = true;
+    }
+}
+
+/// Visit the entries of `btree` whose key starts with `prefix` in key
+/// order, [`CHECK_CHUNK`] at a time. `visit` gets the pager back between
+/// chunks so it can look entries up elsewhere.
+fn for_each_chunk<P: PageStore>(
+    btree: &BTree,
+    pager: &mut P,
+    prefix: &[u8],
+    mut visit: impl FnMut(&mut P, Vec<(Vec<u8>, Vec<u8>)>),
+) -> Result<()> {
+    let mut start = prefix.to_vec();
+    loop {
+        cancellation_point()?;
+        let mut chunk = Vec::with_capacity(CHECK_CHUNK);
+        btree.scan_from(pager, &start, |k, v| {
+            if !k.starts_with(prefix) {
+                return Ok(false);
+            }
+            chunk.push((k.to_vec(), v.to_vec()));
+            Ok(chunk.len() < CHECK_CHUNK)
+        })?;
+        let Some((last, _)) = chunk.last() else {
+            return Ok(());
+        };
+        let more = chunk.len() == CHECK_CHUNK;
+        // The smallest key after `last`.
+        start = last.clone();
+        start.push(0);
+        visit(pager, chunk);
+        if !more {
+            return Ok(());
+        }
+    }
+}
+
+/// B-tree key of the entry `parts` derive from `row`. `None` for NULL keys
+/// and rows or key expressions that do not evaluate; those are reported
+/// while walking the rows.
+fn row_entry_key(
+    table_def: &TableDef,
+    idx: &IndexDef,
+    parts: &[IndexKeyPart],
+    pk: &[u8],
+    row: &[u8],
+) -> Option<Vec<u8>> {
+    let values =
+        deserialize_row_versioned(row, &table_def.columns, table_def.row_format_version).ok()?;
+    let idx_key = encode_index_key(table_def, parts, &values).ok()??;
+    if idx.is_unique {
+        Some(idx_key)
+    } else {
+        Some(non_unique_entry_key(table_def, &idx_key, pk))
+    }
+}
+
+fn check_table(
+    table_def: &TableDef,
+    pager: &mut impl PageStore,
+    catalog: &SystemCatalog,
+    report: &mut IntegrityReport,
+) -> Result<()> {
+    cancellation_point()?;
+    let name = table_def.name.as_str();
+    let column_names: Vec<&str> = table_def.columns.iter().map(|c| c.name.as_str()).collect();
+    for (a, b) in folded_collision_pairs(&column_names) {
+        report.warn(name, identifier_collision_detail("columns", a, b));
+    }
+    let indexes = match catalog.get_indexes_for_table(pager, name) {
+        Ok(indexes) => indexes,
+        Err(e) => {
+            report.record(
+                name,
+                String::new(),
+                vec![format!("index definitions: {}", e)],
+            );
+            Vec::new()
+        }
+    };
+
+    let mut shapes: Vec<(&IndexDef, IndexShape)> = Vec::new();
+    let mut index_problems: Vec<(String, Vec<String>)> = Vec::new();
+    for idx in &indexes {
+        let missing_column = || {
+            (
+                idx.name.clone(),
+                vec!["references a column that does not exist".to_string()],
+            )
+        };
+        match idx.index_type {
+            IndexType::BTree => match index_key_parts(table_def, idx) {
+                Ok(Some(parts)) if !parts.is_empty() => {
+                    shapes.push((idx, IndexShape::BTree(parts)))
+                }
+                Ok(_) => index_problems.push(missing_column()),
+                Err(e) => index_problems.push((idx.name.clone(), vec![e.to_string()])),
+            },
+            IndexType::Fulltext => {
+                match idx
+                    .column_names
+                    .first()
+                    .and_then(|cn| table_def.column_index(cn))
+                {
+                    Some(col_idx) => shapes.push((idx, IndexShape::Fulltext(col_idx))),
+                    None => index_problems.push(missing_column()),
+                }
+            }
+        }
+    }
+
+    // B-tree indexes report before FULLTEXT ones.
+    shapes.sort_by_key(|(_, shape)| matches!(shape, IndexShape::Fulltext(_)));
+
+    let data = BTree::open(table_def.data_btree_root);
+    let mut row_problems = Vec::new();
+    let data_check = data.verify(pager, |pk, row| {
+        if let Err(e) =
+            deserialize_row_versioned(row, &table_def.columns, table_def.row_format_version)
+        {
+            row_problems.push(format!("row {}: {}", short_hex(pk), e));
+        }
+    });
+
+    let term_key = if shapes
+        .iter()
+        .any(|(_, shape)| matches!(shape, IndexShape::Fulltext(_)))
+    {
+        Some(pager.fts_term_key()?)
+    } else {
+        None
+    };
+    let mut checks: Vec<IndexCheck> = Vec::with_capacity(shapes.len());
+    for (def, shape) in shapes {
+        let check = match (&shape, &term_key) {
+            (IndexShape::Fulltext(_), Some(term_key)) => {
+                FtsIndex::open(def.btree_root, term_key.clone()).verify(pager, |_, _| {})
+            }
+            _ => BTree::open(def.btree_root).verify(pager, |_, _| {}),
+        };
+        checks.push(IndexCheck {
+            def,
+            shape,
+            check,
+            problems: Vec::new(),
+            missing: Vec::new(),
+            docs: 0,
+            unreadable: false,
+        });
+    }
+
+    // Cross-checks compare against a table whose rows can all be reached.
+    if data_check.is_ok() {
+        for ic in checks.iter_mut() {
+            walk_index_entries(ic, table_def, &data, pager)?;
+        }
+        let walk = for_each_chunk(&data, pager, &[], |pager, rows| {
+            for (pk, row) in rows {
+                let Ok(values) = deserialize_row_versioned(
+                    &row,
+                    &table_def.columns,
+                    table_def.row_format_version,
+                ) else {
+                    continue;
+                };
+                for ic in checks.iter_mut().filter(|ic| !ic.unreadable) {
+                    check_row_entry(ic, table_def, &data, pager, &pk, &values);
+                }
+            }
+        });
+        match walk {
+            Ok(()) => {}
+            Err(MuroError::Cancelled) => return Err(MuroError::Cancelled),
+            Err(e) => row_problems.push(e.to_string()),
+        }
+    }
+
+    let summary = format!(
+        "{} rows, {} pages",
+        data_check.entries,
+        data_check.pages.len()
+    );
+    record_tree(
+        report,
+        PageOwner::Table(name.to_string()),
+        summary,
+        data_check,
+        row_problems,
+    );
+
+    for (idx_name, problems) in index_problems {
+        report.record(&format!("{}.{}", name, idx_name), String::new(), problems);
+    }
+    for ic in checks {
+        let owner = PageOwner::Index {
+            table: name.to_string(),
+            index: ic.def.name.clone(),
+        };
+        let mut problems = ic.problems;
+        problems.extend(ic.missing);
+        let summary = match ic.shape {
+            IndexShape::BTree(_) => {
+                let mut summary = format!(
+                    "{} entries, {} pages",
+                    ic.check.entries,
+                    ic.check.pages.len()
+                );
+                if ic.def.building {
+                    summary.push_str(", still building");
+                }
+                summary
+            }
+            IndexShape::Fulltext(_) => {
+                format!("{} documents, {} pages", ic.docs, ic.check.pages.len())
+            }
+        };
+        record_tree(report, owner, summary, ic.check, problems);
+    }
+    Ok(())
+}
+
+/// Walk the entries of one index in key order, looking up the row each
+/// one refers to.
+fn walk_index_entries(
+    ic: &mut IndexCheck,
+    table_def: &TableDef,
+    data: &BTree,
+    pager: &mut impl PageStore,
+) -> Result<()> {
+    let index = BTree::open(ic.def.btree_root);
+    let walked = match &ic.shape {
+        IndexShape::BTree(parts) => {
+            let (def, problems) = (ic.def, &mut ic.problems);
+            for_each_chunk(&index, pager, &[], |pager, entries| {
+                for (key, pk) in entries {
+                    match data.search(pager, &pk) {
+                        Ok(None) => problems.push(format!(
+                            "dangling entry {}: row {} does not exist",
+                            short_hex(&key),
+                            short_hex(&pk)
+                        )),
+                        Ok(Some(row)) => {
+                            if row_entry_key(table_def, def, parts, &pk, &row)
+                                .is_some_and(|expected| expected != key)
+                            {
+                                problems.push(format!(
+                                    "stale entry {} for row {}",
+                                    short_hex(&key),
+                                    short_hex(&pk)
+                                ));
+                            }
+                        }
+                        Err(e) => problems.push(e.to_string()),
+                    }
+                }
+            })
+        }
+        IndexShape::Fulltext(_) => {
+            let (docs, problems) = (&mut ic.docs, &mut ic.problems);
+            let mut dangling = |pager: &mut _, pk: &[u8]| match data.search(pager, pk) {
+                Ok(Some(_)) => {}
+                Ok(None) => problems.push(format!(
+                    "dangling document mapping: row {} does not exist",
+                    short_hex(pk)
+                )),
+                Err(e) => problems.push(e.to_string()),
+            };
+            for_each_chunk(&index, pager, SQL_FTS_DOC2PK_PREFIX, |pager, entries| {
+                for (_, pk) in entries {
+                    dangling(pager, &pk);
+                }
+            })
+            .and_then(|()| {
+                for_each_chunk(&index, pager, SQL_FTS_PK2DOC_PREFIX, |pager, entries| {
+                    for (key, _) in entries {
+                        *docs += 1;
+                        dangling(pager, &key[SQL_FTS_PK2DOC_PREFIX.len()..]);
+                    }
+                })
+            })
+        }
+    };
+    match walked {
+        Ok(()) => Ok(()),
+        Err(MuroError::Cancelled) => Err(MuroError::Cancelled),
+        Err(e) => {
+            ic.fail(e);
+            Ok(())
+        }
+    }
+}
+
+/// Look up the entry one row should have in one index.
+fn check_row_entry(
+    ic: &mut IndexCheck,
+    table_def: &TableDef,
+    data: &BTree,
+    pager: &mut impl PageStore,
+    pk: &[u8],
+    values: &[Value],
+) {
+    let index = BTree::open(ic.def.btree_root);
+    let parts = match &ic.shape {
+        IndexShape::BTree(parts) => parts,
+        IndexShape::Fulltext(col_idx) => {
+            if values.get(*col_idx).and_then(value_to_fts_text).is_none() {
+                return;
+            }
+            match index.search(pager, &fts_pk_to_doc_key(pk)) {
+                Ok(Some(_)) => {}
+                Ok(None) => ic.missing.push(format!(
+                    "missing document mapping for row {}",
+                    short_hex(pk)
+                )),
+                Err(e) => ic.fail(e),
+            }
+            return;
+        }
+    };
+    let idx_key = match encode_index_key(table_def, parts, values) {
+        Ok(Some(idx_key)) => idx_key,
+        Ok(None) => return,
+        Err(e) => {
+            ic.missing
+                .push(format!("row {}: key expression: {}", short_hex(pk), e));
+            return;
+        }
+    };
+    let entry_key = if ic.def.is_unique {
+        idx_key
+    } else {
+        non_unique_entry_key(table_def, &idx_key, pk)
+    };
+    let found = match index.search(pager, &entry_key) {
+        Ok(found) => found,
+        Err(e) => return ic.fail(e),
+    };
+    match found {
+        Some(entry_pk) if entry_pk == pk => {}
+        // An index still being built lacks the rows its build has not reached.
+        None if ic.def.building => {}
+        None => ic
+            .missing
+            .push(format!("missing entry for row {}", short_hex(pk))),
+        Some(other) => {
+            let other_key = match data.search(pager, &other) {
+                Ok(row) => {
+                    row.and_then(|row| row_entry_key(table_def, ic.def, parts, &other, &row))
+                }
+                Err(e) => return ic.fail(e),
+            };
+            ic.missing.push(
+                if ic.def.is_unique && other_key.as_deref() == Some(&entry_key[..]) {
+                    format!(
+                        "duplicate key {} in UNIQUE index (rows {} and {})",
+                        short_hex(&entry_key),
+                        short_hex(&other),
+                        short_hex(pk)
+                    )
+                } else {
+                    format!(
+                        "entry {} points to row {} instead of {}",
+                        short_hex(&entry_key),
+                        short_hex(&other),
+                        short_hex(pk)
+                    )
+                },
+            );
+        }
+    }
+}
+
+/// Hex rendering of a key for report details, truncated for long keys.
+pub(super) fn short_hex(bytes: &[u8]) -> String {
+    const MAX_BYTES: usize = 16;
+    let mut out: String = bytes
+        .iter()
+        .take(MAX_BYTES)
+        .map(|b| format!("{:02x}", b))
+        .collect();
+    if bytes.len() > MAX_BYTES {
+        out.push_str("...");
+    }
+    out
+}
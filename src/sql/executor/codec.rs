@@ -1,35 +1,5 @@
 use super::*;
 
-/// Fit a Decimal value to the declared DECIMAL(p,s) column constraints.
-/// Rounds fractional digits to scale `s`, sets exact scale, then rejects
-/// if the integer part exceeds `p - s` digits (MySQL-compatible behavior).
-pub(super) fn fit_decimal(
-    d: rust_decimal::Decimal,
-    precision: u32,
-    scale: u32,
-) -> Result<rust_decimal::Decimal> {
-    // Round to the declared scale
-    let mut rounded = d.round_dp(scale);
-    // Set exact scale so that e.g. 42 becomes 42.00 for DECIMAL(10,2)
-    rounded.rescale(scale);
-    // Check that integer digits fit within (precision - scale)
-    let max_int_digits = precision - scale;
-    let int_part = rounded.trunc().abs();
-    // Count integer digits: the number of digits in the integer part
-    let int_digits = if int_part.is_zero() {
-        0u32
-    } else {
-        int_part.to_string().len() as u32
-    };
-    if int_digits > max_int_digits {
-        return Err(MuroError::Execution(format!(
-            "Value '{}' out of range for DECIMAL({},{})",
-            d, precision, scale
-        )));
-    }
-    Ok(rounded)
-}
-
 pub fn serialize_row(values: &[Value], columns: &[ColumnDef]) -> Vec<u8> {
     let mut buf = Vec::new();
 
@@ -185,6 +155,31 @@ pub fn deserialize_row_versioned(
     data: &[u8],
     columns: &[ColumnDef],
     row_format_version: u8,
+) -> Result<Vec<Value>> {
+    decode_row(data, columns, row_format_version, None)
+}
+
+/// Deserialize only the columns at positions `wanted`; every other column
+/// reads as NULL. Fields before the last wanted one are stepped over by
+/// their width without being decoded, and fields after it are not read at
+/// all, so an empty `wanted` never looks at `data`.
+pub fn deserialize_columns(
+    data: &[u8],
+    columns: &[ColumnDef],
+    row_format_version: u8,
+    wanted: &[usize],
+) -> Result<Vec<Value>> {
+    if wanted.is_empty() {
+        return Ok(vec![Value::Null; columns.len()]);
+    }
+    decode_row(data, columns, row_format_version, Some(wanted))
+}
+
+fn decode_row(
+    data: &[u8],
+    columns: &[ColumnDef],
+    row_format_version: u8,
+    wanted: Option<&[usize]>,
 ) -> Result<Vec<Value>> {
     let (stored_col_count, data) = if row_format_version >= 1 {
         // New format: u16 column count prefix
@@ -206,11 +201,21 @@ pub fn deserialize_row_versioned(
     let bitmap = &data[..bitmap_bytes];
     let mut offset = bitmap_bytes;
     let mut values = Vec::with_capacity(columns.len());
+    let last_wanted = wanted.map(|w| w.iter().copied().max().unwrap_or(0));
 
     for (i, col) in columns.iter().enumerate() {
+        let is_wanted = wanted.is_none_or(|w| w.contains(&i));
+        if last_wanted.is_some_and(|last| i > last) {
+            values.push(Value::Null);
+            continue;
+        }
         // Columns beyond what was stored get default/NULL
         if i >= stored_col_count {
-            values.push(default_value_for_column(col));
+            values.push(if is_wanted {
+                default_value_for_column(col)
+            } else {
+                Value::Null
+            });
             continue;
         }
 
@@ -220,136 +225,93 @@ pub fn deserialize_row_versioned(
             continue;
         }
 
-        match col.data_type {
-            DataType::TinyInt => {
-                if offset + 1 > data.len() {
-                    return Err(MuroError::InvalidPage);
-                }
-                let n = data[offset] as i8;
-                values.push(Value::Integer(n as i64));
-                offset += 1;
-            }
-            DataType::SmallInt => {
-                if offset + 2 > data.len() {
-                    return Err(MuroError::InvalidPage);
-                }
-                let n = i16::from_le_bytes(data[offset..offset + 2].try_into().unwrap());
-                values.push(Value::Integer(n as i64));
-                offset += 2;
-            }
-            DataType::Int => {
-                if offset + 4 > data.len() {
-                    return Err(MuroError::InvalidPage);
-                }
-                let n = i32::from_le_bytes(data[offset..offset + 4].try_into().unwrap());
-                values.push(Value::Integer(n as i64));
-                offset += 4;
-            }
-            DataType::BigInt => {
-                if offset + 8 > data.len() {
-                    return Err(MuroError::InvalidPage);
-                }
-                let n = i64::from_le_bytes(data[offset..offset + 8].try_into().unwrap());
-                values.push(Value::Integer(n));
-                offset += 8;
-            }
-            DataType::Float => {
-                if offset + 4 > data.len() {
-                    return Err(MuroError::InvalidPage);
-                }
-                let n = f32::from_le_bytes(data[offset..offset + 4].try_into().unwrap());
-                values.push(Value::Float(n as f64));
-                offset += 4;
-            }
-            DataType::Double => {
-                if offset + 8 > data.len() {
-                    return Err(MuroError::InvalidPage);
-                }
-                let n = f64::from_le_bytes(data[offset..offset + 8].try_into().unwrap());
-                values.push(Value::Float(n));
-                offset += 8;
-            }
-            DataType::Date => {
-                if offset + 4 > data.len() {
-                    return Err(MuroError::InvalidPage);
-                }
-                let n = i32::from_le_bytes(data[offset..offset + 4].try_into().unwrap());
-                values.push(Value::Date(n));
-                offset += 4;
-            }
-            DataType::DateTime => {
-                if offset + 8 > data.len() {
-                    return Err(MuroError::InvalidPage);
-                }
-                let n = i64::from_le_bytes(data[offset..offset + 8].try_into().unwrap());
-                values.push(Value::DateTime(n));
-                offset += 8;
-            }
-            DataType::Timestamp => {
-                if offset + 8 > data.len() {
-                    return Err(MuroError::InvalidPage);
-                }
-                let n = i64::from_le_bytes(data[offset..offset + 8].try_into().unwrap());
-                values.push(Value::Timestamp(n));
-                offset += 8;
-            }
-            DataType::Varchar(_) | DataType::Text | DataType::Jsonb => {
-                if offset + 4 > data.len() {
-                    return Err(MuroError::InvalidPage);
-                }
-                let len = u32::from_le_bytes(data[offset..offset + 4].try_into().unwrap()) as usize;
-                offset += 4;
-                if offset + len > data.len() {
-                    return Err(MuroError::InvalidPage);
-                }
-                let s = String::from_utf8(data[offset..offset + len].to_vec())
-                    .map_err(|_| MuroError::InvalidPage)?;
-                values.push(Value::Varchar(s));
-                offset += len;
-            }
-            DataType::Varbinary(_) => {
-                if offset + 4 > data.len() {
-                    return Err(MuroError::InvalidPage);
-                }
-                let len = u32::from_le_bytes(data[offset..offset + 4].try_into().unwrap()) as usize;
-                offset += 4;
-                if offset + len > data.len() {
-                    return Err(MuroError::InvalidPage);
-                }
-                values.push(Value::Varbinary(data[offset..offset + len].to_vec()));
-                offset += len;
-            }
-            DataType::Decimal(_, _) => {
-                if offset + 16 > data.len() {
-                    return Err(MuroError::InvalidPage);
-                }
-                let mut bytes = [0u8; 16];
-                bytes.copy_from_slice(&data[offset..offset + 16]);
-                values.push(Value::Decimal(rust_decimal::Decimal::deserialize(bytes)));
-                offset += 16;
-            }
-            DataType::Uuid => {
-                if offset + 16 > data.len() {
-                    return Err(MuroError::InvalidPage);
-                }
-                let mut bytes = [0u8; 16];
-                bytes.copy_from_slice(&data[offset..offset + 16]);
-                values.push(Value::Uuid(bytes));
-                offset += 16;
-            }
-        }
+        let bytes = field_bytes(data, &mut offset, &col.data_type)?;
+        values.push(if is_wanted {
+            decode_field(bytes, &col.data_type)?
+        } else {
+            Value::Null
+        });
     }
 
     Ok(values)
 }
 
+/// Encoded width of a fixed-size type; `None` for length-prefixed ones.
+fn fixed_width(data_type: &DataType) -> Option<usize> {
+    match data_type {
+        DataType::TinyInt => Some(1),
+        DataType::SmallInt => Some(2),
+        DataType::Int | DataType::Float | DataType::Date => Some(4),
+        DataType::BigInt | DataType::Double | DataType::DateTime | DataType::Timestamp => Some(8),
+        DataType::Decimal(_, _) | DataType::Uuid => Some(16),
+        DataType::Varchar(_) | DataType::Text | DataType::Jsonb | DataType::Varbinary(_) => None,
+    }
+}
+
+/// The encoded bytes of the field at `offset`, without a length prefix, and
+/// advance `offset` past it.
+fn field_bytes<'a>(data: &'a [u8], offset: &mut usize, data_type: &DataType) -> Result<&'a [u8]> {
+    let len = match fixed_width(data_type) {
+        Some(width) => width,
+        None => {
+            let prefix = data
+                .get(*offset..*offset + 4)
+                .ok_or(MuroError::InvalidPage)?;
+            *offset += 4;
+            u32::from_le_bytes(prefix.try_into().unwrap()) as usize
+        }
+    };
+    let end = offset.checked_add(len).ok_or(MuroError::InvalidPage)?;
+    let bytes = data.get(*offset..end).ok_or(MuroError::InvalidPage)?;
+    *offset = end;
+    Ok(bytes)
+}
+
+/// Decode one field from the bytes [`field_bytes`] returned for it.
+fn decode_field(bytes: &[u8], data_type: &DataType) -> Result<Value> {
+    Ok(match data_type {
+        DataType::TinyInt => Value::Integer(bytes[0] as i8 as i64),
+        DataType::SmallInt => Value::Integer(i16::from_le_bytes(bytes.try_into().unwrap()) as i64),
+        DataType::Int => Value::Integer(i32::from_le_bytes(bytes.try_into().unwrap()) as i64),
+        DataType::BigInt => Value::Integer(i64::from_le_bytes(bytes.try_into().unwrap())),
+        DataType::Float => Value::Float(f32::from_le_bytes(bytes.try_into().unwrap()) as f64),
+        DataType::Double => Value::Float(f64::from_le_bytes(bytes.try_into().unwrap())),
+        DataType::Date => Value::Date(i32::from_le_bytes(bytes.try_into().unwrap())),
+        DataType::DateTime => Value::DateTime(i64::from_le_bytes(bytes.try_into().unwrap())),
+        DataType::Timestamp => Value::Timestamp(i64::from_le_bytes(bytes.try_into().unwrap())),
+        DataType::Varchar(_) | DataType::Text | DataType::Jsonb => {
+            Value::Varchar(String::from_utf8(bytes.to_vec()).map_err(|_| MuroError::InvalidPage)?)
+        }
+        DataType::Varbinary(_) => Value::Varbinary(bytes.to_vec()),
+        DataType::Decimal(_, _) => Value::Decimal(rust_decimal::Decimal::deserialize(
+            bytes.try_into().unwrap(),
+        )),
+        DataType::Uuid => Value::Uuid(bytes.try_into().unwrap()),
+    })
+}
+
 /// Get the default value for a newly-added column.
+///
+/// Expression defaults read as NULL here: ADD COLUMN materializes them into
+/// every existing row, so stored rows never rely on re-evaluating one.
 pub(super) fn default_value_for_column(col: &ColumnDef) -> Value {
     match &col.default_value {
         Some(DefaultValue::Integer(n)) => Value::Integer(*n),
         Some(DefaultValue::Float(n)) => Value::Float(*n),
         Some(DefaultValue::String(s)) => Value::Varchar(s.clone()),
-        Some(DefaultValue::Null) | None => Value::Null,
+        Some(DefaultValue::Null) | Some(DefaultValue::Expr(_)) | None => Value::Null,
+    }
+}
+
+/// The value a row takes for `col` when it gets the column default,
+/// evaluating expression defaults.
+pub(super) fn eval_column_default(col: &ColumnDef) -> Result<Value> {
+    match &col.default_value {
+        Some(DefaultValue::Expr(text)) => {
+            let expr = parse_index_expr(text)?;
+            eval_expr(&expr, &|_| None)
+        }
+        _ => Ok(default_value_for_column(col)),
     }
 }
 
@@ -0,0 +1,230 @@
+use super::*;
+use std::borrow::Cow;
+
+/// Whether any column of `table_def` has a non-binary collation.
+pub(super) fn has_collated_columns(table_def: &TableDef) -> bool {
+    table_def
+        .columns
+        .iter()
+        .any(|c| c.collation != Collation::Binary)
+}
+
+/// Collation of the column `name` of `table_def`, if it is not binary.
+pub(super) fn column_collation_of(table_def: &TableDef, name: &str) -> Option<Collation> {
+    let ci = table_def.column_index(name)?;
+    Some(table_def.columns[ci].collation).filter(|c| *c != Collation::Binary)
+}
+
+/// `expr` with every reference to a collated column of `table_def` wrapped
+/// in `Expr::Collate`, so comparisons on it run under the column's
+/// collation. `None` when the table has no collated column.
+pub(super) fn with_column_collations(expr: &Expr, table_def: &TableDef) -> Option<Expr> {
+    if !has_collated_columns(table_def) {
+        return None;
+    }
+    let mut expr = expr.clone();
+    wrap_collated_columns(&mut expr, &|name| column_collation_of(table_def, name));
+    Some(expr)
+}
+
+/// Collation of the column `name` refers to in a join of `tables` (keyed by
+/// qualifier), if it is not binary. `name` is `qualifier.column` or an
+/// unambiguous column name.
+pub(super) fn join_column_collation(
+    tables: &[(String, TableDef)],
+    name: &str,
+) -> Option<Collation> {
+    if let Some((qualifier, column)) = name.split_once('.') {
+        let (_, def) = tables.iter().find(|(q, _)| q == qualifier)?;
+        return column_collation_of(def, column);
+    }
+    let mut owners = tables
+        .iter()
+        .filter(|(_, def)| def.column_index(name).is_some());
+    match (owners.next(), owners.next()) {
+        (Some((_, def)), None) => column_collation_of(def, name),
+        _ => None,
+    }
+}
+
+/// [`with_column_collations`] for a join of `tables`.
+pub(super) fn with_join_collations(expr: &Expr, tables: &[(String, TableDef)]) -> Option<Expr> {
+    if !tables.iter().any(|(_, def)| has_collated_columns(def)) {
+        return None;
+    }
+    let mut expr = expr.clone();
+    wrap_collated_columns(&mut expr, &|name| join_column_collation(tables, name));
+    Some(expr)
+}
+
+/// Collation the value of `expr` compares under: an explicit `COLLATE`, or
+/// the collation `collation_of` gives the column it names.
+pub(super) fn expr_collation(
+    expr: &Expr,
+    collation_of: &dyn Fn(&str) -> Option<Collation>,
+) -> Collation {
+    match expr {
+        Expr::Collate { collation, .. } => *collation,
+        Expr::ColumnRef(name) => collation_of(name).unwrap_or_default(),
+        _ => Collation::Binary,
+    }
+}
+
+/// Collation of each output column of a SELECT list, `*` expanding to
+/// `star`.
+pub(super) fn output_collations(
+    columns: &[SelectColumn],
+    star: &[Collation],
+    collation_of: &dyn Fn(&str) -> Option<Collation>,
+) -> Vec<Collation> {
+    let mut collations = Vec::with_capacity(columns.len());
+    for col in columns {
+        match col {
+            SelectColumn::Star => collations.extend_from_slice(star),
+            SelectColumn::Expr(expr, _) => collations.push(expr_collation(expr, collation_of)),
+        }
+    }
+    collations
+}
+
+/// Hash key of `value` under `collation`: values the collation treats as
+/// equal get equal keys.
+pub(super) fn collated_key(collation: Collation, value: &Value) -> ValueKey {
+    ValueKey(collation.key_value(value).into_owned())
+}
+
+/// Remove rows equal to an earlier one under the collation of each column;
+/// columns past the end of `collations` compare binary.
+pub(super) fn retain_distinct(rows: &mut Vec<Row>, collations: &[Collation]) {
+    let mut seen = HashSet::new();
+    rows.retain(|row| {
+        let key: Vec<ValueKey> = row
+            .values
+            .iter()
+            .enumerate()
+            .map(|(i, (_, v))| collated_key(collations.get(i).copied().unwrap_or_default(), v))
+            .collect();
+        seen.insert(key)
+    });
+}
+
+/// Order of two values under `collation`, for MIN and MAX.
+pub(super) fn collated_cmp(collation: Collation, a: &Value, b: &Value) -> std::cmp::Ordering {
+    match (a, b) {
+        (Value::Varchar(a), Value::Varchar(b)) => collation.compare(a, b),
+        _ => cmp_values(Some(a), Some(b)),
+    }
+}
+
+/// ORDER BY items with collated columns of `table_def` wrapped as in
+/// [`with_column_collations`], for [`sort_rows`].
+pub(super) fn collated_order_by<'a>(
+    items: &'a [OrderByItem],
+    table_def: &TableDef,
+) -> Cow<'a, [OrderByItem]> {
+    if !has_collated_columns(table_def) {
+        return Cow::Borrowed(items);
+    }
+    Cow::Owned(
+        items
+            .iter()
+            .map(|item| {
+                let mut item = item.clone();
+                wrap_collated_columns(&mut item.expr, &|name| column_collation_of(table_def, name));
+                item
+            })
+            .collect(),
+    )
+}
+
+/// The column an ORDER BY item sorts by and the collation it sorts under.
+pub(super) fn order_by_column(expr: &Expr) -> Option<(&String, Collation)> {
+    match expr {
+        Expr::ColumnRef(name) => Some((name, Collation::Binary)),
+        Expr::Collate { expr, collation } => match expr.as_ref() {
+            Expr::ColumnRef(name) => Some((name, *collation)),
+            _ => None,
+        },
+        _ => None,
+    }
+}
+
+/// Wrap column references for which `collation_of` gives a collation. An
+/// explicit `COLLATE` is left as written: it takes precedence over the
+/// column's.
+fn wrap_collated_columns(expr: &mut Expr, collation_of: &dyn Fn(&str) -> Option<Collation>) {
+    let wrap = |e: &mut Expr| wrap_collated_columns(e, collation_of);
+    match expr {
+        Expr::ColumnRef(name) => {
+            if let Some(collation) = collation_of(name) {
+                let column = std::mem::replace(expr, Expr::Null);
+                *expr = Expr::Collate {
+                    expr: Box::new(column),
+                    collation,
+                };
+            }
+        }
+        Expr::BinaryOp { left, right, .. } => {
+            wrap(left);
+            wrap(right);
+        }
+        Expr::Like {
+            expr,
+            pattern,
+            escape,
+            ..
+        } => {
+            wrap(expr);
+            wrap(pattern);
+            if let Some(escape) = escape {
+                wrap(escape);
+            }
+        }
+        Expr::InList { expr, list, .. } => {
+            wrap(expr);
+            list.iter_mut().for_each(wrap);
+        }
+        Expr::Between {
+            expr, low, high, ..
+        } => {
+            wrap(expr);
+            wrap(low);
+            wrap(high);
+        }
+        Expr::UnaryOp { operand: expr, .. }
+        | Expr::IsNull { expr, .. }
+        | Expr::Cast { expr, .. }
+        | Expr::GreaterThanZero(expr)
+        | Expr::InSubquery { expr, .. } => wrap(expr),
+        Expr::FunctionCall { args, .. } => args.iter_mut().for_each(wrap),
+        Expr::CaseWhen {
+            operand,
+            when_clauses,
+            else_clause,
+        } => {
+            if let Some(operand) = operand {
+                wrap(operand);
+            }
+            for (when, then) in when_clauses {
+                wrap(when);
+                wrap(then);
+            }
+            if let Some(else_clause) = else_clause {
+                wrap(else_clause);
+            }
+        }
+        Expr::Collate { .. }
+        | Expr::AggregateFunc { .. }
+        | Expr::BindParam
+        | Expr::IntLiteral(_)
+        | Expr::FloatLiteral(_)
+        | Expr::StringLiteral(_)
+        | Expr::BlobLiteral(_)
+        | Expr::Null
+        | Expr::DefaultValue
+        | Expr::MatchAgainst { .. }
+        | Expr::FtsSnippet { .. }
+        | Expr::Exists { .. }
+        | Expr::ScalarSubquery(_) => {}
+    }
+}
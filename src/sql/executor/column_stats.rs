@@ -0,0 +1,185 @@
+use super::*;
+use crate::schema::catalog::ColumnStats;
+use std::hash::{Hash, Hasher};
+
+/// Distinct values tracked exactly before switching to HyperLogLog.
+const EXACT_DISTINCT_LIMIT: usize = 4096;
+/// HyperLogLog precision: 2^12 registers, about 1.6% standard error.
+const HLL_PRECISION: u32 = 12;
+
+/// Counts distinct values: exactly while the set is small, then with a
+/// HyperLogLog sketch so memory stays bounded on large tables.
+pub(super) enum DistinctCounter {
+    Exact(HashSet<u64>),
+    Sketch(Vec<u8>),
+}
+
+impl DistinctCounter {
+    pub(super) fn new() -> Self {
+        DistinctCounter::Exact(HashSet::new())
+    }
+
+    pub(super) fn insert(&mut self, bytes: &[u8]) {
+        let hash = stats_hash(bytes);
+        match self {
+            DistinctCounter::Exact(set) => {
+                set.insert(hash);
+                if set.len() > EXACT_DISTINCT_LIMIT {
+                    let mut registers = vec![0u8; 1 << HLL_PRECISION];
+                    for h in set.iter() {
+                        hll_add(&mut registers, *h);
+                    }
+                    *self = DistinctCounter::Sketch(registers);
+                }
+            }
+            DistinctCounter::Sketch(registers) => hll_add(registers, hash),
+        }
+    }
+
+    pub(super) fn count(&self) -> u64 {
+        match self {
+            DistinctCounter::Exact(set) => set.len() as u64,
+            DistinctCounter::Sketch(registers) => hll_estimate(registers),
+        }
+    }
+}
+
+fn stats_hash(bytes: &[u8]) -> u64 {
+    // Fixed keys, so the same data always yields the same estimate.
+    let mut hasher = std::collections::hash_map::DefaultHasher::new();
+    bytes.hash(&mut hasher);
+    hasher.finish()
+}
+
+fn hll_add(registers: &mut [u8], hash: u64) {
+    let idx = (hash >> (64 - HLL_PRECISION)) as usize;
+    let rest = hash << HLL_PRECISION;
+    let rank = (rest.leading_zeros().min(64 - HLL_PRECISION) + 1) as u8;
+    if rank > registers[idx] {
+        registers[idx] = rank;
+    }
+}
+
+fn hll_estimate(registers: &[u8]) -> u64 {
+    let m = registers.len() as f64;
+    let alpha = 0.7213 / (1.0 + 1.079 / m);
+    let sum: f64 = registers.iter().map(|r| 2f64.powi(-(*r as i32))).sum();
+    let raw = alpha * m * m / sum;
+    let zeros = registers.iter().filter(|r| **r == 0).count();
+    let est = if raw <= 2.5 * m && zeros > 0 {
+        // Linear counting is more accurate at low cardinalities.
+        m * (m / zeros as f64).ln()
+    } else {
+        raw
+    };
+    est.round() as u64
+}
+
+/// Accumulates one column's ANALYZE statistics row by row.
+pub(super) struct ColumnStatsCollector {
+    column: String,
+    data_type: DataType,
+    distinct: DistinctCounter,
+    null_count: u64,
+    bounds: Option<(i64, i64)>,
+}
+
+impl ColumnStatsCollector {
+    pub(super) fn new(column: &ColumnDef) -> Self {
+        ColumnStatsCollector {
+            column: column.name.clone(),
+            data_type: column.data_type,
+            distinct: DistinctCounter::new(),
+            null_count: 0,
+            bounds: None,
+        }
+    }
+
+    pub(super) fn add(&mut self, value: &Value) {
+        if value.is_null() {
+            self.null_count += 1;
+            return;
+        }
+        self.distinct.insert(&encode_value(value, &self.data_type));
+        if let Some(n) = value_as_i64_for_stats(value) {
+            self.bounds = Some(match self.bounds {
+                Some((min, max)) => (min.min(n), max.max(n)),
+                None => (n, n),
+            });
+        }
+    }
+
+    pub(super) fn finish(self) -> ColumnStats {
+        ColumnStats {
+            column: self.column,
+            distinct_count: self.distinct.count(),
+            null_count: self.null_count,
+            min: self.bounds.map(|(min, _)| min),
+            max: self.bounds.map(|(_, max)| max),
+        }
+    }
+}
+
+/// Integer ordering key of a value, for min/max and range estimates.
+pub(super) fn value_as_i64_for_stats(v: &Value) -> Option<i64> {
+    match v {
+        Value::Integer(n) => Some(*n),
+        Value::Date(n) => Some(*n as i64),
+        Value::DateTime(n) => Some(*n),
+        Value::Timestamp(n) => Some(*n),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_distinct_counter_is_exact_when_small() {
+        let mut counter = DistinctCounter::new();
+        for i in 0..1000u32 {
+            counter.insert(&(i % 300).to_le_bytes());
+        }
+        assert!(matches!(counter, DistinctCounter::Exact(_)));
+        assert_eq!(counter.count(), 300);
+    }
+
+    #[test]
+    fn test_distinct_counter_sketch_stays_close() {
+        for n in [10_000u64, 100_000] {
+            let mut counter = DistinctCounter::new();
+            for i in 0..n {
+                counter.insert(&i.to_le_bytes());
+                counter.insert(&i.to_le_bytes());
+            }
+            assert!(matches!(counter, DistinctCounter::Sketch(_)));
+            let est = counter.count() as f64;
+            let err = (est - n as f64).abs() / n as f64;
+            assert!(err < 0.05, "n={} est={} err={}", n, est, err);
+        }
+    }
+
+    #[test]
+    fn test_collector_tracks_nulls_and_bounds() {
+        let mut c = ColumnStatsCollector::new(&ColumnDef::new("a", DataType::Int));
+        for v in [
+            Value::Integer(5),
+            Value::Null,
+            Value::Integer(-3),
+            Value::Integer(5),
+        ] {
+            c.add(&v);
+        }
+        let stats = c.finish();
+        assert_eq!(stats.distinct_count, 2);
+        assert_eq!(stats.null_count, 1);
+        assert_eq!((stats.min, stats.max), (Some(-3), Some(5)));
+
+        let mut c = ColumnStatsCollector::new(&ColumnDef::new("s", DataType::Varchar(None)));
+        c.add(&Value::Varchar("x".into()));
+        let stats = c.finish();
+        assert_eq!(stats.distinct_count, 1);
+        assert_eq!((stats.min, stats.max), (None, None));
+    }
+}
@@ -0,0 +1,210 @@
+use super::*;
+
+/// Which side of the number/string divide a column type or literal is on.
+/// Values of the two kinds never compare equal, so a filter comparing a
+/// column with a literal of the other kind can match no row.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum CompareKind {
+    Number,
+    String,
+}
+
+fn column_kind(data_type: DataType) -> Option<CompareKind> {
+    match data_type {
+        DataType::TinyInt
+        | DataType::SmallInt
+        | DataType::Int
+        | DataType::BigInt
+        | DataType::Float
+        | DataType::Double
+        | DataType::Decimal(..) => Some(CompareKind::Number),
+        DataType::Varchar(_) | DataType::Text => Some(CompareKind::String),
+        _ => None,
+    }
+}
+
+fn literal_kind(expr: &Expr) -> Option<CompareKind> {
+    match expr {
+        Expr::IntLiteral(_) | Expr::FloatLiteral(_) => Some(CompareKind::Number),
+        Expr::UnaryOp {
+            op: UnaryOp::Neg,
+            operand,
+        } => literal_kind(operand).filter(|kind| *kind == CompareKind::Number),
+        Expr::StringLiteral(_) => Some(CompareKind::String),
+        _ => None,
+    }
+}
+
+fn compared_column(expr: &Expr) -> Option<&str> {
+    match expr {
+        Expr::ColumnRef(name) => Some(name),
+        Expr::Collate { expr, .. } => compared_column(expr),
+        _ => None,
+    }
+}
+
+fn literal_text(expr: &Expr) -> String {
+    match expr {
+        Expr::IntLiteral(n) => n.to_string(),
+        Expr::FloatLiteral(n) => n.to_string(),
+        Expr::UnaryOp { operand, .. } => format!("-{}", literal_text(operand)),
+        Expr::StringLiteral(s) => format!("'{}'", s),
+        _ => String::new(),
+    }
+}
+
+/// Fail when `column` (of the type `type_of` gives) is compared with a
+/// literal of the other kind.
+fn check_pair(
+    column: &Expr,
+    literal: &Expr,
+    type_of: &dyn Fn(&str) -> Option<DataType>,
+) -> Result<()> {
+    let Some(name) = compared_column(column) else {
+        return Ok(());
+    };
+    let (Some(data_type), Some(kind)) = (type_of(name), literal_kind(literal)) else {
+        return Ok(());
+    };
+    match column_kind(data_type) {
+        Some(column_kind) if column_kind != kind => {
+            let (literal_kind, wanted) = match column_kind {
+                CompareKind::Number => ("string", "number"),
+                CompareKind::String => ("numeric", "string"),
+            };
+            Err(MuroError::Type(format!(
+                "Cannot compare {} column '{}' with {} literal {}; write the literal as a {} or CAST one side",
+                data_type,
+                name,
+                literal_kind,
+                literal_text(literal),
+                wanted
+            )))
+        }
+        _ => Ok(()),
+    }
+}
+
+/// Reject comparisons (`=`, `<>`, `<`, `IN`, `BETWEEN`, ...) of a numeric
+/// column with a string literal, or of a VARCHAR/TEXT column with a numeric
+/// one. Strings and numbers are never converted into each other, so such a
+/// comparison would silently match nothing, with or without an index.
+/// Subqueries are checked when they run.
+pub(super) fn check_literal_comparisons(
+    expr: &Expr,
+    type_of: &dyn Fn(&str) -> Option<DataType>,
+) -> Result<()> {
+    let check = |e: &Expr| check_literal_comparisons(e, type_of);
+    match expr {
+        Expr::BinaryOp { left, op, right } => {
+            if matches!(
+                op,
+                BinaryOp::Eq
+                    | BinaryOp::Ne
+                    | BinaryOp::Lt
+                    | BinaryOp::Gt
+                    | BinaryOp::Le
+                    | BinaryOp::Ge
+            ) {
+                check_pair(left, right, type_of)?;
+                check_pair(right, left, type_of)?;
+            }
+            check(left)?;
+            check(right)
+        }
+        Expr::InList { expr, list, .. } => {
+            for item in list {
+                check_pair(expr, item, type_of)?;
+                check(item)?;
+            }
+            check(expr)
+        }
+        Expr::Between {
+            expr, low, high, ..
+        } => {
+            check_pair(expr, low, type_of)?;
+            check_pair(expr, high, type_of)?;
+            check(expr)?;
+            check(low)?;
+            check(high)
+        }
+        Expr::Like {
+            expr,
+            pattern,
+            escape,
+            ..
+        } => {
+            check(expr)?;
+            check(pattern)?;
+            escape.as_deref().map_or(Ok(()), check)
+        }
+        Expr::UnaryOp { operand: expr, .. }
+        | Expr::IsNull { expr, .. }
+        | Expr::Cast { expr, .. }
+        | Expr::Collate { expr, .. }
+        | Expr::GreaterThanZero(expr)
+        | Expr::InSubquery { expr, .. } => check(expr),
+        Expr::FunctionCall { args, .. } => args.iter().try_for_each(check),
+        Expr::CaseWhen {
+            operand,
+            when_clauses,
+            else_clause,
+        } => {
+            operand.as_deref().map_or(Ok(()), check)?;
+            for (when, then) in when_clauses {
+                check(when)?;
+                check(then)?;
+            }
+            else_clause.as_deref().map_or(Ok(()), check)
+        }
+        Expr::AggregateFunc { .. }
+        | Expr::BindParam
+        | Expr::IntLiteral(_)
+        | Expr::FloatLiteral(_)
+        | Expr::StringLiteral(_)
+        | Expr::BlobLiteral(_)
+        | Expr::Null
+        | Expr::DefaultValue
+        | Expr::ColumnRef(_)
+        | Expr::MatchAgainst { .. }
+        | Expr::FtsSnippet { .. }
+        | Expr::Exists { .. }
+        | Expr::ScalarSubquery(_) => Ok(()),
+    }
+}
+
+/// [`check_literal_comparisons`] for the WHERE of a single-table statement.
+pub(super) fn check_where_literal_types(
+    where_clause: &Option<Expr>,
+    table_def: &TableDef,
+) -> Result<()> {
+    let Some(expr) = where_clause else {
+        return Ok(());
+    };
+    check_literal_comparisons(expr, &|name| {
+        let ci = table_def.column_index(name)?;
+        Some(table_def.columns[ci].data_type)
+    })
+}
+
+/// [`check_literal_comparisons`] for a condition over a join of `tables`
+/// (keyed by qualifier). Ambiguous names are left to evaluation to report.
+pub(super) fn check_join_literal_types(expr: &Expr, tables: &[(&str, &TableDef)]) -> Result<()> {
+    check_literal_comparisons(expr, &|name| {
+        let column_type = |def: &TableDef, column: &str| {
+            let ci = def.column_index(column)?;
+            Some(def.columns[ci].data_type)
+        };
+        if let Some((qualifier, column)) = name.split_once('.') {
+            let (_, def) = tables.iter().find(|(q, _)| *q == qualifier)?;
+            return column_type(def, column);
+        }
+        let mut owners = tables
+            .iter()
+            .filter(|(_, def)| def.column_index(name).is_some());
+        match (owners.next(), owners.next()) {
+            (Some((_, def)), None) => column_type(def, name),
+            _ => None,
+        }
+    })
+}
@@ -0,0 +1,205 @@
+//! Index-only scans.
+//!
+//! When every column a query reads is part of the index an index seek uses,
+//! or of the primary key stored in its entries, the rows are decoded from the
+//! index entries and the data B-tree is never read.
+
+use super::*;
+use crate::btree::key_encoding::{decode_composite_key, decode_key_value};
+
+/// Where an index-only scan of `index` finds each column it decodes.
+pub(super) struct CoveringIndex<'a> {
+    index: &'a IndexDef,
+    /// Table positions of the index key parts, in key order.
+    key_columns: Vec<usize>,
+    /// Table positions of the primary key columns, in key order.
+    pk_columns: Vec<usize>,
+}
+
+/// The index `plan` seeks, if it covers every column `sel` reads.
+///
+/// NULLs are not indexed, so a WHERE that tests for NULL is left to the row
+/// path, as are columns whose key loses information: FLOAT and DOUBLE (`-0.0`
+/// is stored as `0.0`), JSONB, strings under a folding collation, and
+/// FULLTEXT columns, whose document ids come from the row.
+pub(super) fn covering_index<'a>(
+    sel: &Select,
+    table_def: &TableDef,
+    plan: &Plan,
+    indexes: &'a [IndexDef],
+) -> Option<CoveringIndex<'a>> {
+    let index_name = match plan {
+        Plan::IndexSeek { index_name, .. } | Plan::IndexRangeSeek { index_name, .. } => index_name,
+        Plan::InListSeek {
+            index_name: Some(index_name),
+            ..
+        } => index_name,
+        _ => return None,
+    };
+    let index = indexes.iter().find(|i| &i.name == index_name)?;
+    if index.index_type != IndexType::BTree || index.expressions.iter().any(Option::is_some) {
+        return None;
+    }
+    if sel.where_clause.as_ref().is_some_and(expr_tests_null) {
+        return None;
+    }
+    let key_columns = index
+        .column_names
+        .iter()
+        .map(|name| table_def.column_index(name))
+        .collect::<Option<Vec<_>>>()?;
+    let pk_columns = table_def.pk_column_indices();
+    for pos in scanned_column_positions(sel, table_def)? {
+        let col = &table_def.columns[pos];
+        let in_fulltext = indexes.iter().any(|i| {
+            i.index_type == IndexType::Fulltext && i.column_names.iter().any(|c| c == &col.name)
+        });
+        if !(key_columns.contains(&pos) || pk_columns.contains(&pos))
+            || !key_preserves_value(col)
+            || in_fulltext
+        {
+            return None;
+        }
+    }
+    Some(CoveringIndex {
+        index,
+        key_columns,
+        pk_columns,
+    })
+}
+
+/// The candidate rows of `plan` decoded from the entries of `covering`'s
+/// index. Columns outside the index and primary key are NULL; the caller
+/// still applies the WHERE clause.
+pub(super) fn index_only_rows(
+    covering: &CoveringIndex,
+    table_def: &TableDef,
+    plan: &Plan,
+    pager: &mut impl PageStore,
+) -> Result<Vec<Vec<Value>>> {
+    let idx = covering.index;
+    let entries = match plan {
+        Plan::IndexSeek {
+            column_names,
+            key_exprs,
+            ..
+        } => {
+            let idx_key = eval_index_seek_key(table_def, column_names, key_exprs)?;
+            index_seek_entries(table_def, idx, &idx_key, pager)?
+        }
+        Plan::IndexRangeSeek {
+            column_names,
+            prefix_key_exprs,
+            lower,
+            upper,
+            ..
+        } => {
+            let bound_columns = &column_names[..prefix_key_exprs.len() + 1];
+            let eval_bound = |bound: &(Box<Expr>, bool), is_upper: bool| {
+                eval_index_range_bound(table_def, bound_columns, prefix_key_exprs, bound, is_upper)
+            };
+            let lower_key = lower.as_ref().map(|b| eval_bound(b, false)).transpose()?;
+            let upper_key = upper.as_ref().map(|b| eval_bound(b, true)).transpose()?;
+            index_range_entries(table_def, idx, lower_key, upper_key, pager)?
+        }
+        Plan::InListSeek {
+            column_name,
+            values,
+            ..
+        } => in_list_seek_entries(table_def, idx, column_name, values, pager)?,
+        _ => {
+            return Err(MuroError::Internal(format!(
+                "index-only scan of '{}' on a plan without an index seek",
+                idx.name
+            )))
+        }
+    };
+    let mut rows = Vec::with_capacity(entries.len());
+    for (idx_key, pk_key) in &entries {
+        cancellation_point()?;
+        let mut values = vec![Value::Null; table_def.columns.len()];
+        decode_key_into(table_def, &covering.key_columns, idx_key, &mut values)
+            .and_then(|()| decode_key_into(table_def, &covering.pk_columns, pk_key, &mut values))
+            .ok_or_else(|| {
+                MuroError::Corruption(format!("undecodable entry in index '{}'", idx.name))
+            })?;
+        rows.push(values);
+    }
+    Ok(rows)
+}
+
+/// Decode `key`, encoded from the values of `columns`, into `values`.
+fn decode_key_into(
+    table_def: &TableDef,
+    columns: &[usize],
+    key: &[u8],
+    values: &mut [Value],
+) -> Option<()> {
+    let decoded = match columns {
+        [] => return Some(()),
+        [single] => vec![decode_key_value(
+            key,
+            &table_def.columns[*single].data_type,
+        )?],
+        _ => {
+            let types: Vec<&DataType> = columns
+                .iter()
+                .map(|&i| &table_def.columns[i].data_type)
+                .collect();
+            decode_composite_key(key, &types)?
+        }
+    };
+    for (&i, value) in columns.iter().zip(decoded) {
+        values[i] = value;
+    }
+    Some(())
+}
+
+/// Whether the key of a value of `col` decodes back to that value.
+fn key_preserves_value(col: &ColumnDef) -> bool {
+    match col.data_type {
+        DataType::Float | DataType::Double | DataType::Jsonb => false,
+        DataType::Varchar(_) | DataType::Text => col.collation == Collation::Binary,
+        _ => true,
+    }
+}
+
+/// Whether `expr` holds an `IS [NOT] NULL` test.
+fn expr_tests_null(expr: &Expr) -> bool {
+    match expr {
+        Expr::IsNull { .. } => true,
+        Expr::BinaryOp { left, right, .. } => expr_tests_null(left) || expr_tests_null(right),
+        Expr::UnaryOp { operand, .. } => expr_tests_null(operand),
+        Expr::Like {
+            expr,
+            pattern,
+            escape,
+            ..
+        } => {
+            expr_tests_null(expr)
+                || expr_tests_null(pattern)
+                || escape.as_deref().is_some_and(expr_tests_null)
+        }
+        Expr::InList { expr, list, .. } => {
+            expr_tests_null(expr) || list.iter().any(expr_tests_null)
+        }
+        Expr::Between {
+            expr, low, high, ..
+        } => expr_tests_null(expr) || expr_tests_null(low) || expr_tests_null(high),
+        Expr::CaseWhen {
+            operand,
+            when_clauses,
+            else_clause,
+        } => {
+            operand.as_deref().is_some_and(expr_tests_null)
+                || when_clauses
+                    .iter()
+                    .any(|(c, t)| expr_tests_null(c) || expr_tests_null(t))
+                || else_clause.as_deref().is_some_and(expr_tests_null)
+        }
+        Expr::Cast { expr, .. } | Expr::Collate { expr, .. } => expr_tests_null(expr),
+        Expr::FunctionCall { args, .. } => args.iter().any(expr_tests_null),
+        Expr::GreaterThanZero(inner) => expr_tests_null(inner),
+        _ => false,
+    }
+}
@@ -1,4 +1,6 @@
 use super::*;
+use crate::limits::Limit;
+use crate::sql::session::forget_auto_increment_current;
 use std::collections::{HashMap, HashSet};
 
 pub(super) fn exec_create_table(
@@ -6,11 +8,6 @@ pub(super) fn exec_create_table(
     pager: &mut impl PageStore,
     catalog: &mut SystemCatalog,
 ) -> Result<ExecResult> {
-    // Check IF NOT EXISTS
-    if ct.if_not_exists && catalog.get_table(pager, &ct.table_name)?.is_some() {
-        return Ok(ExecResult::Ok);
-    }
-
     let col_names: Vec<&str> = ct.columns.iter().map(|c| c.name.as_str()).collect();
 
     // --- Validate all constraints BEFORE creating any catalog entries ---
@@ -19,6 +16,7 @@ pub(super) fn exec_create_table(
     let mut table_level_pk: Option<Vec<String>> = None;
     let mut table_level_uniques: Vec<(String, Vec<String>)> = Vec::new();
     let mut table_level_fks: Vec<ForeignKeyDef> = Vec::new();
+    let mut table_level_indexes: Vec<&CreateIndex> = Vec::new();
 
     for constraint in &ct.constraints {
         match constraint {
@@ -47,6 +45,9 @@ pub(super) fn exec_create_table(
                         )));
                     }
                 }
+                if let Some(name) = name {
+                    Limit::IdentifierBytes.check(name.len())?;
+                }
                 let idx_name = name
                     .clone()
                     .unwrap_or_else(|| format!("auto_unique_{}_{}", ct.table_name, cols.join("_")));
@@ -58,6 +59,19 @@ pub(super) fn exec_create_table(
                 }
                 table_level_uniques.push((idx_name, cols.clone()));
             }
+            TableConstraint::Index(ci) => {
+                Limit::IdentifierBytes.check(ci.index_name.len())?;
+                if table_level_indexes
+                    .iter()
+                    .any(|other| other.index_name == ci.index_name)
+                {
+                    return Err(MuroError::Schema(format!(
+                        "Duplicate index name '{}'",
+                        ci.index_name
+                    )));
+                }
+                table_level_indexes.push(ci);
+            }
             TableConstraint::ForeignKey {
                 columns,
                 ref_table,
@@ -94,6 +108,12 @@ pub(super) fn exec_create_table(
         }
     }
 
+    for fk in &table_level_fks {
+        if ct.temporary {
+            return Err(temp_foreign_key_error(&ct.table_name));
+        }
+        reject_temp_foreign_key(catalog, pager, &ct.table_name, &fk.ref_table)?;
+    }
     validate_foreign_keys(
         ct,
         catalog,
@@ -141,14 +161,15 @@ pub(super) fn exec_create_table(
                 col = col.with_auto_increment();
             }
             if let Some(default_expr) = &cs.default_value {
-                col.default_value = ast_expr_to_default(default_expr);
+                col.default_value = Some(ast_expr_to_default(default_expr, cs.data_type)?);
             }
             if let Some(check) = &cs.check_expr {
                 col.check_expr = Some(expr_to_string(check));
             }
-            col
+            col.collation = column_collation(cs)?;
+            Ok(col)
         })
-        .collect();
+        .collect::<Result<_>>()?;
 
     // Apply table-level PK to columns before creating the table
     if let Some(ref pk_cols) = table_level_pk {
@@ -162,7 +183,24 @@ pub(super) fn exec_create_table(
 
     // --- Now create the table (all validation passed) ---
 
-    let _table_def = catalog.create_table(pager, &ct.table_name, columns)?;
+    // The catalog checks the name and inserts in one call, so IF NOT EXISTS
+    // is decided by the same lookup that guards the creation.
+    let created = if ct.temporary {
+        catalog.try_create_temp_table(pager, &ct.table_name, columns)?
+    } else {
+        catalog.try_create_table(pager, &ct.table_name, columns)?
+    };
+    match created {
+        CreateOutcome::Created(_) => {}
+        CreateOutcome::AlreadyExists(other) if ct.if_not_exists && other == ct.table_name => {
+            return Ok(ExecResult::Ok);
+        }
+        CreateOutcome::AlreadyExists(other) => {
+            return Err(collision_error("Table", &ct.table_name, &other));
+        }
+    }
+    // A dropped table of the same name may have left a high-water mark.
+    forget_auto_increment_current(&ct.table_name);
 
     // Apply table-level PK: update pk_columns and remove _rowid
     if let Some(pk_cols) = table_level_pk {
@@ -180,6 +218,16 @@ pub(super) fn exec_create_table(
         table_def.foreign_keys = table_level_fks.clone();
         catalog.update_table(pager, &table_def)?;
     }
+    if let Some(fill_factor) = ct.fill_factor {
+        let mut table_def = catalog.get_table(pager, &ct.table_name)?.unwrap();
+        table_def.fill_factor = fill_factor;
+        catalog.update_table(pager, &table_def)?;
+    }
+    if let Some(start) = ct.auto_increment {
+        let mut table_def = catalog.get_table(pager, &ct.table_name)?.unwrap();
+        table_def.next_rowid = start - 1;
+        catalog.update_table(pager, &table_def)?;
+    }
 
     // Create table-level UNIQUE indexes
     for (idx_name, cols) in table_level_uniques {
@@ -198,6 +246,12 @@ pub(super) fn exec_create_table(
             stats_num_hist_bins: Vec::new(),
             fts_stop_filter: false,
             fts_stop_df_ratio_ppm: 0,
+            fts_stop_fallback: FtsStopFallback::Rescan,
+            fts_stop_fallback_max_docs: 0,
+            expressions: Vec::new(),
+            fill_factor: DEFAULT_FILL_FACTOR,
+            building: false,
+            trailing_fields: Vec::new(),
         };
         catalog.create_index(pager, idx_def)?;
     }
@@ -220,14 +274,48 @@ pub(super) fn exec_create_table(
                 stats_num_hist_bins: Vec::new(),
                 fts_stop_filter: false,
                 fts_stop_df_ratio_ppm: 0,
+                fts_stop_fallback: FtsStopFallback::Rescan,
+                fts_stop_fallback_max_docs: 0,
+                expressions: Vec::new(),
+                fill_factor: DEFAULT_FILL_FACTOR,
+                building: false,
+                trailing_fields: Vec::new(),
             };
             catalog.create_index(pager, idx_def)?;
         }
     }
 
+    // Named KEY / UNIQUE KEY definitions, validated like CREATE INDEX
+    for ci in table_level_indexes {
+        exec_create_index(ci, pager, catalog)?;
+    }
+
     Ok(ExecResult::Ok)
 }
 
+/// Temporary tables take no part in foreign keys: their rows vanish with
+/// the session, which would leave references to or from them dangling.
+pub(super) fn reject_temp_foreign_key(
+    catalog: &SystemCatalog,
+    pager: &mut impl PageStore,
+    child: &str,
+    parent: &str,
+) -> Result<()> {
+    for name in [child, parent] {
+        if catalog.is_temp_table(pager, name)? {
+            return Err(temp_foreign_key_error(name));
+        }
+    }
+    Ok(())
+}
+
+fn temp_foreign_key_error(name: &str) -> MuroError {
+    MuroError::Schema(format!(
+        "Temporary table '{}' cannot have or be referenced by a FOREIGN KEY",
+        name
+    ))
+}
+
 fn map_fk_action(
     action: crate::sql::ast::ForeignKeyAction,
 ) -> crate::schema::catalog::ForeignKeyAction {
@@ -319,7 +407,7 @@ fn validate_foreign_keys(
             let mut unique_sets = Vec::new();
             unique_sets.push(parent_def.pk_columns.clone());
             for idx in catalog.get_indexes_for_table(pager, &fk.ref_table)? {
-                if idx.is_unique {
+                if idx.is_unique && !idx.has_expressions() {
                     unique_sets.push(idx.column_names.clone());
                 }
             }
@@ -347,13 +435,50 @@ fn validate_foreign_keys(
 }
 
 /// Convert an AST expression (from DEFAULT clause) to a DefaultValue for storage.
-pub(super) fn ast_expr_to_default(expr: &Expr) -> Option<DefaultValue> {
+///
+/// Literals keep their compact encoding; anything else is stored as an
+/// expression, evaluated once here so a default that cannot produce a
+/// `data_type` value is rejected at DDL time rather than on first insert.
+pub(super) fn ast_expr_to_default(expr: &Expr, data_type: DataType) -> Result<DefaultValue> {
+    if let Some(literal) = literal_default(expr) {
+        return Ok(literal);
+    }
+    let text = default_expr_text(expr).ok_or_else(|| {
+        MuroError::Schema(
+            "DEFAULT expression must be built from literals and functions; \
+             it cannot reference columns, parameters, or subqueries"
+                .into(),
+        )
+    })?;
+    let value = eval_expr(expr, &|_| None)?;
+    if !value.is_null() {
+        coerce_value(&value, data_type)?;
+    }
+    Ok(DefaultValue::Expr(text))
+}
+
+/// Collation of a column definition. COLLATE applies to VARCHAR and TEXT
+/// columns only.
+pub(super) fn column_collation(spec: &ColumnSpec) -> Result<Collation> {
+    match spec.collation {
+        None => Ok(Collation::Binary),
+        Some(collation) if matches!(spec.data_type, DataType::Varchar(_) | DataType::Text) => {
+            Ok(collation)
+        }
+        Some(collation) => Err(MuroError::Schema(format!(
+            "COLLATE {} is not allowed on column '{}' of type {}; only VARCHAR and TEXT columns have a collation",
+            collation, spec.name, spec.data_type
+        ))),
+    }
+}
+
+fn literal_default(expr: &Expr) -> Option<DefaultValue> {
     match expr {
         Expr::IntLiteral(n) => Some(DefaultValue::Integer(*n)),
         Expr::FloatLiteral(n) => Some(DefaultValue::Float(*n)),
         Expr::StringLiteral(s) => Some(DefaultValue::String(s.clone())),
         Expr::Null => Some(DefaultValue::Null),
-        Expr::Cast { expr, .. } => ast_expr_to_default(expr),
+        Expr::Cast { expr, .. } => literal_default(expr),
         _ => None,
     }
 }
@@ -362,8 +487,8 @@ pub(super) fn ast_expr_to_default(expr: &Expr) -> Option<DefaultValue> {
 pub(super) fn expr_to_string(expr: &Expr) -> String {
     match expr {
         Expr::IntLiteral(n) => n.to_string(),
-        Expr::FloatLiteral(n) => n.to_string(),
-        Expr::StringLiteral(s) => format!("'{}'", s),
+        Expr::FloatLiteral(n) => format_float_literal(*n),
+        Expr::StringLiteral(s) => format!("'{}'", s.replace('\'', "''")),
         Expr::Null => "NULL".to_string(),
         Expr::ColumnRef(name) => name.clone(),
         Expr::BinaryOp { left, op, right } => {
@@ -396,6 +521,16 @@ pub(super) fn expr_to_string(expr: &Expr) -> String {
             };
             format!("{}{}", op_str, expr_to_string(operand))
         }
+        Expr::Collate { expr, collation } => {
+            format!("{} COLLATE {}", expr_to_string(expr), collation)
+        }
+        Expr::FunctionCall { name, args } => {
+            let args: Vec<String> = args.iter().map(expr_to_string).collect();
+            format!("{}({})", name, args.join(", "))
+        }
+        Expr::Cast { expr, target_type } => {
+            format!("CAST({} AS {})", expr_to_string(expr), target_type)
+        }
         _ => "?".to_string(),
     }
 }
@@ -405,92 +540,86 @@ pub(super) fn exec_create_index(
     pager: &mut impl PageStore,
     catalog: &mut SystemCatalog,
 ) -> Result<ExecResult> {
-    // Check IF NOT EXISTS
-    if ci.if_not_exists && catalog.get_index(pager, &ci.index_name)?.is_some() {
+    let Some(name) = begin_index_build(ci, pager, catalog)? else {
         return Ok(ExecResult::Ok);
+    };
+    // Release the claim on failure; callers without a statement rollback
+    // would otherwise keep an index with no complete tree.
+    match continue_index_build(&name, u64::MAX, pager, catalog) {
+        Ok(_) => Ok(ExecResult::Ok),
+        Err(e) => {
+            abort_index_build(&name, pager, catalog)?;
+            Err(e)
+        }
     }
+}
 
+/// Check a `CREATE INDEX` and store its definition, marked as building,
+/// with an empty tree and no rows indexed yet. Returns the index name, or
+/// `None` when `IF NOT EXISTS` found it already there.
+pub(crate) fn begin_index_build(
+    ci: &CreateIndex,
+    pager: &mut impl PageStore,
+    catalog: &mut SystemCatalog,
+) -> Result<Option<String>> {
+    catalog.bump_generation();
+    Limit::IdentifierBytes.check(ci.index_name.len())?;
     let table_def = catalog
         .get_table(pager, &ci.table_name)?
         .ok_or_else(|| MuroError::Schema(format!("Table '{}' not found", ci.table_name)))?;
 
-    // Verify all columns exist
-    let mut col_indices = Vec::new();
-    for col_name in &ci.column_names {
-        let col_idx = table_def.column_index(col_name).ok_or_else(|| {
-            MuroError::Schema(format!(
-                "Column '{}' not found in table '{}'",
-                col_name, ci.table_name
-            ))
-        })?;
-        col_indices.push(col_idx);
-    }
-
-    let is_composite = ci.column_names.len() > 1;
-
-    let idx_btree = BTree::create(pager)?;
-
-    // If unique, scan existing data for duplicates
-    if ci.is_unique {
-        let data_btree = BTree::open(table_def.data_btree_root);
-        let mut seen_keys: Vec<Vec<u8>> = Vec::new();
-        data_btree.scan(pager, |_k, v| {
-            let row_values =
-                deserialize_row_versioned(v, &table_def.columns, table_def.row_format_version)?;
-            let encoded = encode_index_key_from_row(
-                &row_values,
-                &col_indices,
-                &table_def.columns,
-                is_composite,
-            );
-            if let Some(key) = encoded {
-                if seen_keys.contains(&key) {
-                    return Err(MuroError::UniqueViolation(format!(
-                        "Duplicate value in column(s) '{}'",
-                        ci.column_names.join(", ")
+    // Resolve key parts: plain columns must exist, expressions must be
+    // indexable and read only existing columns.
+    let mut column_names = Vec::with_capacity(ci.column_names.len());
+    let mut expressions = Vec::new();
+    for (i, col_name) in ci.column_names.iter().enumerate() {
+        if let Some(Some(expr)) = ci.expressions.get(i) {
+            let text = index_expr_text(expr).ok_or_else(|| {
+                MuroError::Schema(format!(
+                    "Expression in index '{}' cannot be indexed",
+                    ci.index_name
+                ))
+            })?;
+            let mut referenced = Vec::new();
+            index_expr_columns(expr, &mut referenced);
+            if referenced.is_empty() {
+                return Err(MuroError::Schema(format!(
+                    "Expression in index '{}' must reference a column",
+                    ci.index_name
+                )));
+            }
+            for col in &referenced {
+                if table_def.column_index(col).is_none() {
+                    return Err(MuroError::Schema(format!(
+                        "Column '{}' not found in table '{}'",
+                        col, ci.table_name
                     )));
                 }
-                seen_keys.push(key);
             }
-            Ok(true)
-        })?;
-    }
-
-    // Collect existing data for index building
-    let data_btree = BTree::open(table_def.data_btree_root);
-    let mut entries: Vec<(Vec<u8>, Vec<u8>)> = Vec::new();
-
-    data_btree.scan(pager, |pk_key, v| {
-        let row_values =
-            deserialize_row_versioned(v, &table_def.columns, table_def.row_format_version)?;
-        let encoded =
-            encode_index_key_from_row(&row_values, &col_indices, &table_def.columns, is_composite);
-        if let Some(idx_key) = encoded {
-            if ci.is_unique {
-                entries.push((idx_key, pk_key.to_vec()));
-            } else {
-                // For non-unique indexes, append PK to make B-tree key unique
-                let mut full_key = idx_key;
-                full_key.extend_from_slice(pk_key);
-                entries.push((full_key, pk_key.to_vec()));
+            expressions.resize(i, None);
+            expressions.push(Some(text.clone()));
+            column_names.push(text);
+        } else {
+            if table_def.column_index(col_name).is_none() {
+                return Err(MuroError::Schema(format!(
+                    "Column '{}' not found in table '{}'",
+                    col_name, ci.table_name
+                )));
             }
+            column_names.push(col_name.clone());
         }
-        Ok(true)
-    })?;
-
-    // Build index from collected entries
-    let mut idx_btree_mut = BTree::open(idx_btree.root_page_id());
-    for (idx_key, pk_key) in &entries {
-        idx_btree_mut.insert(pager, idx_key, pk_key)?;
+    }
+    if !expressions.is_empty() {
+        expressions.resize(column_names.len(), None);
     }
 
-    let idx_def = IndexDef {
+    let mut idx_def = IndexDef {
         name: ci.index_name.clone(),
         table_name: ci.table_name.clone(),
-        column_names: ci.column_names.clone(),
+        column_names,
         index_type: IndexType::BTree,
         is_unique: ci.is_unique,
-        btree_root: idx_btree_mut.root_page_id(),
+        btree_root: 0,
         stats_distinct_keys: 0,
         stats_num_min: 0,
         stats_num_max: 0,
@@ -498,10 +627,101 @@ pub(super) fn exec_create_index(
         stats_num_hist_bins: Vec::new(),
         fts_stop_filter: false,
         fts_stop_df_ratio_ppm: 0,
+        fts_stop_fallback: FtsStopFallback::Rescan,
+        fts_stop_fallback_max_docs: 0,
+        expressions,
+        fill_factor: ci.fill_factor.unwrap_or(DEFAULT_FILL_FACTOR),
+        building: true,
+        trailing_fields: Vec::new(),
     };
-    catalog.create_index(pager, idx_def)?;
+    if index_key_parts(&table_def, &idx_def)?.is_none() {
+        return Err(MuroError::Schema(format!(
+            "Index '{}' references a missing column",
+            ci.index_name
+        )));
+    }
 
-    Ok(ExecResult::Ok)
+    // Claim the name before building, in the same catalog call that checks
+    // it, so IF NOT EXISTS never builds an index it then throws away.
+    match catalog.try_create_index(pager, idx_def.clone())? {
+        CreateOutcome::Created(_) => {}
+        CreateOutcome::AlreadyExists(other) if ci.if_not_exists && other == ci.index_name => {
+            return Ok(None);
+        }
+        CreateOutcome::AlreadyExists(other) => {
+            return Err(collision_error("Index", &ci.index_name, &other));
+        }
+    }
+
+    idx_def.btree_root = BTree::create(pager)?.root_page_id();
+    catalog.update_index(pager, &idx_def)?;
+    catalog.put_index_build(pager, &ci.index_name, &IndexBuildProgress::default())?;
+    Ok(Some(ci.index_name.clone()))
+}
+
+/// Index up to `max_rows` more rows for the building index `name` and
+/// record how far it got. Once every row is indexed the index is marked
+/// complete and its progress record removed; returns whether that happened.
+pub(crate) fn continue_index_build(
+    name: &str,
+    max_rows: u64,
+    pager: &mut impl PageStore,
+    catalog: &mut SystemCatalog,
+) -> Result<bool> {
+    let mut idx_def = catalog
+        .get_index(pager, name)?
+        .filter(|idx| idx.building)
+        .ok_or_else(|| MuroError::Internal(format!("index '{}' is not being built", name)))?;
+    let mut progress = catalog.get_index_build(pager, name)?.unwrap_or_default();
+    let table_def = catalog
+        .get_table(pager, &idx_def.table_name)?
+        .ok_or_else(|| MuroError::Schema(format!("Table '{}' not found", idx_def.table_name)))?;
+    let parts = index_key_parts(&table_def, &idx_def)?.ok_or_else(|| {
+        MuroError::Schema(format!("Index '{}' references a missing column", name))
+    })?;
+
+    let mut idx_btree = BTree::open(idx_def.btree_root).with_fill_factor(idx_def.fill_factor);
+    let duplicate = || {
+        MuroError::UniqueViolation(format!(
+            "Duplicate value in column(s) '{}'",
+            idx_def.column_names.join(", ")
+        ))
+    };
+    let done = build_index_rows(
+        &table_def,
+        &mut idx_btree,
+        pager,
+        &mut progress,
+        max_rows,
+        |row_values| encode_index_key(&table_def, &parts, row_values),
+        idx_def
+            .is_unique
+            .then_some(&duplicate as &dyn Fn() -> MuroError),
+    )?;
+
+    idx_def.btree_root = idx_btree.root_page_id();
+    if done {
+        idx_def.building = false;
+        catalog.delete_index_build(pager, name)?;
+        catalog.bump_generation();
+    } else {
+        catalog.put_index_build(pager, name, &progress)?;
+    }
+    catalog.update_index(pager, &idx_def)?;
+    Ok(done)
+}
+
+/// Drop the building index `name` with the pages it filled so far.
+pub(crate) fn abort_index_build(
+    name: &str,
+    pager: &mut impl PageStore,
+    catalog: &mut SystemCatalog,
+) -> Result<()> {
+    if let Some(idx_def) = catalog.try_delete_index(pager, name)? {
+        free_index_pages(pager, &idx_def)?;
+    }
+    catalog.bump_generation();
+    Ok(())
 }
 
 pub(super) fn exec_create_fulltext_index(
@@ -509,6 +729,7 @@ pub(super) fn exec_create_fulltext_index(
     pager: &mut impl PageStore,
     catalog: &mut SystemCatalog,
 ) -> Result<ExecResult> {
+    Limit::IdentifierBytes.check(fi.index_name.len())?;
     let table_def = catalog
         .get_table(pager, &fi.table_name)?
         .ok_or_else(|| MuroError::Schema(format!("Table '{}' not found", fi.table_name)))?;
@@ -594,6 +815,12 @@ pub(super) fn exec_create_fulltext_index(
         stats_num_hist_bins: Vec::new(),
         fts_stop_filter: fi.stop_filter,
         fts_stop_df_ratio_ppm: fi.stop_df_ratio_ppm,
+        fts_stop_fallback: fi.stop_fallback,
+        fts_stop_fallback_max_docs: fi.stop_fallback_max_docs,
+        expressions: Vec::new(),
+        fill_factor: DEFAULT_FILL_FACTOR,
+        building: false,
+        trailing_fields: Vec::new(),
     };
     catalog.create_index(pager, idx_def)?;
 
@@ -612,11 +839,26 @@ pub(super) fn exec_analyze_table(
 
     let data_btree = BTree::open(table_def.data_btree_root);
     let mut row_count: u64 = 0;
-    data_btree.scan(pager, |_k, _v| {
+    let mut collectors: Vec<ColumnStatsCollector> = table_def
+        .columns
+        .iter()
+        .map(ColumnStatsCollector::new)
+        .collect();
+    data_btree.scan(pager, |_k, row| {
         row_count += 1;
+        let values =
+            deserialize_row_versioned(row, &table_def.columns, table_def.row_format_version)?;
+        for (collector, value) in collectors.iter_mut().zip(&values) {
+            collector.add(value);
+        }
         Ok(true)
     })?;
     table_def.stats_row_count = row_count;
+    table_def.row_count = Some(row_count);
+    table_def.column_stats = collectors
+        .into_iter()
+        .map(ColumnStatsCollector::finish)
+        .collect();
     catalog.update_table(pager, &table_def)?;
 
     let mut indexes = catalog.get_indexes_for_table(pager, table_name)?;
@@ -712,14 +954,7 @@ pub(super) fn exec_analyze_table(
                 return Ok(true);
             }
 
-            let idx_part = if k.len() >= v.len() {
-                k[..k.len() - v.len()].to_vec()
-            } else {
-                return Err(MuroError::Corruption(
-                    "invalid non-unique index entry: key shorter than value".into(),
-                ));
-            };
-            if seen_idx_parts.insert(idx_part) {
+            if seen_idx_parts.insert(non_unique_entry_index_key(&table_def, k, v)?) {
                 distinct_keys += 1;
             }
             Ok(true)
@@ -743,16 +978,6 @@ pub(super) fn exec_analyze_table(
     Ok(ExecResult::Ok)
 }
 
-fn value_as_i64_for_stats(v: &Value) -> Option<i64> {
-    match v {
-        Value::Integer(n) => Some(*n),
-        Value::Date(n) => Some(*n as i64),
-        Value::DateTime(n) => Some(*n),
-        Value::Timestamp(n) => Some(*n),
-        _ => None,
-    }
-}
-
 fn numeric_hist_bin_index(v: i64, min_v: i64, max_v: i64, bins: usize) -> Option<usize> {
     if bins == 0 || max_v < min_v || v < min_v || v > max_v {
         return None;
@@ -788,20 +1013,23 @@ pub(super) fn exec_drop_table(
     pager: &mut impl PageStore,
     catalog: &mut SystemCatalog,
 ) -> Result<ExecResult> {
-    let table_def = match catalog.get_table(pager, &dt.table_name)? {
-        Some(td) => td,
-        None => {
-            if dt.if_exists {
-                return Ok(ExecResult::Ok);
-            }
-            return Err(MuroError::Schema(format!(
-                "Table '{}' does not exist",
-                dt.table_name
-            )));
+    let temp = catalog.is_temp_table(pager, &dt.table_name)?;
+    if dt.temporary && !temp {
+        if dt.if_exists {
+            return Ok(ExecResult::Ok);
         }
+        return Err(MuroError::Schema(format!(
+            "Temporary table '{}' does not exist",
+            dt.table_name
+        )));
+    }
+    // Foreign keys never reference a temporary table.
+    let children = if temp {
+        Vec::new()
+    } else {
+        catalog.list_tables(pager)?
     };
-
-    for table_name in catalog.list_tables(pager)? {
+    for table_name in children {
         if table_name == dt.table_name {
             continue;
         }
@@ -822,6 +1050,23 @@ pub(super) fn exec_drop_table(
         }
     }
 
+    // Indexes are looked up and removed while the name still resolves to
+    // this table, which matters for a temporary table shadowing another.
+    let indexes = catalog.get_indexes_for_table(pager, &dt.table_name)?;
+    catalog.delete_indexes_for_table(pager, &dt.table_name)?;
+
+    // Existence is decided by the removal itself, so DROP ... IF EXISTS
+    // cannot race another DROP between a lookup and the delete.
+    let Some(table_def) = catalog.try_delete_table(pager, &dt.table_name)? else {
+        if dt.if_exists {
+            return Ok(ExecResult::Ok);
+        }
+        return Err(MuroError::Schema(format!(
+            "Table '{}' does not exist",
+            dt.table_name
+        )));
+    };
+
     // Free the data B-tree pages
     let data_btree = BTree::open(table_def.data_btree_root);
     let pages_to_free = data_btree.collect_all_pages(pager)?;
@@ -829,21 +1074,11 @@ pub(super) fn exec_drop_table(
         pager.free_page(page_id);
     }
 
-    // Free index B-tree pages
-    let indexes = catalog.get_indexes_for_table(pager, &dt.table_name)?;
+    // Free index pages
     for idx in &indexes {
-        let idx_btree = BTree::open(idx.btree_root);
-        let idx_pages = idx_btree.collect_all_pages(pager)?;
-        for page_id in idx_pages {
-            pager.free_page(page_id);
-        }
+        free_index_pages(pager, idx)?;
     }
 
-    // Delete all indexes for this table first
-    catalog.delete_indexes_for_table(pager, &dt.table_name)?;
-    // Delete the table
-    catalog.delete_table(pager, &dt.table_name)?;
-
     Ok(ExecResult::Ok)
 }
 
@@ -852,26 +1087,29 @@ pub(super) fn exec_drop_index(
     pager: &mut impl PageStore,
     catalog: &mut SystemCatalog,
 ) -> Result<ExecResult> {
-    let idx_def = match catalog.get_index(pager, &di.index_name)? {
-        Some(idx) => idx,
-        None => {
-            if di.if_exists {
-                return Ok(ExecResult::Ok);
-            }
-            return Err(MuroError::Schema(format!(
-                "Index '{}' does not exist",
-                di.index_name
-            )));
+    let Some(idx_def) = catalog.try_delete_index(pager, &di.index_name)? else {
+        if di.if_exists {
+            return Ok(ExecResult::Ok);
         }
+        return Err(MuroError::Schema(format!(
+            "Index '{}' does not exist",
+            di.index_name
+        )));
     };
 
-    // Free the index B-tree pages
-    let idx_btree = BTree::open(idx_def.btree_root);
-    let pages_to_free = idx_btree.collect_all_pages(pager)?;
-    for page_id in pages_to_free {
-        pager.free_page(page_id);
-    }
+    free_index_pages(pager, &idx_def)?;
 
-    catalog.delete_index(pager, &di.index_name)?;
     Ok(ExecResult::Ok)
 }
+
+/// Free every page of a dropped index. A FULLTEXT index also owns the
+/// overflow chains of its large posting segments, which are not B-tree pages.
+fn free_index_pages(pager: &mut impl PageStore, idx: &IndexDef) -> Result<()> {
+    if idx.index_type == IndexType::Fulltext {
+        return FtsIndex::open(idx.btree_root, pager.fts_term_key()?).destroy(pager);
+    }
+    for page_id in BTree::open(idx.btree_root).collect_all_pages(pager)? {
+        pager.free_page(page_id);
+    }
+    Ok(())
+}
@@ -50,7 +50,7 @@ pub(super) fn enforce_child_foreign_keys(
         })?;
 
         if !exists {
-            return Err(MuroError::Execution(format!(
+            return Err(MuroError::ConstraintViolation(format!(
                 "FOREIGN KEY constraint fails: ({}) REFERENCES {}({})",
                 fk.columns.join(", "),
                 fk.ref_table,
@@ -125,7 +125,7 @@ fn enforce_parent_restrict_on_delete_inner(
             }
 
             if fk.on_delete == crate::schema::catalog::ForeignKeyAction::Restrict {
-                return Err(MuroError::Execution(format!(
+                return Err(MuroError::ConstraintViolation(format!(
                     "Cannot delete parent row: referenced by {} via FOREIGN KEY ({})",
                     child_table.name,
                     fk.columns.join(", "),
@@ -242,7 +242,7 @@ fn enforce_parent_restrict_on_update_inner(
         }
 
         if fk.on_update == crate::schema::catalog::ForeignKeyAction::Restrict {
-            return Err(MuroError::Execution(format!(
+            return Err(MuroError::ConstraintViolation(format!(
                 "Cannot update parent key: referenced by {} via FOREIGN KEY ({})",
                 child_table.name,
                 fk.columns.join(", "),
@@ -389,11 +389,19 @@ fn cascade_delete_child_rows(
     let mut indexes = catalog.get_indexes_for_table(pager, &child_table.name)?;
     let mut data_btree = BTree::open(child_table.data_btree_root);
 
+    let mut removed = 0i64;
     for m in matches {
         delete_from_secondary_indexes(child_table, &mut indexes, &m.row_values, &m.pk_key, pager)?;
-        data_btree.delete(pager, &m.pk_key)?;
+        if data_btree.delete(pager, &m.pk_key)? {
+            removed += 1;
+        }
     }
 
+    // Nested cascades may have deleted from this table too.
+    if let Some(current) = catalog.get_table(pager, &child_table.name)? {
+        child_table.row_count = current.row_count;
+    }
+    child_table.adjust_row_count(-removed);
     child_table.data_btree_root = data_btree.root_page_id();
     catalog.update_table(pager, child_table)?;
     persist_indexes(catalog, pager, &indexes)?;
@@ -411,7 +419,7 @@ fn set_null_child_references(
     let fk_indices = fk_child_column_indices(child_table, fk)?;
     for idx in &fk_indices {
         if !child_table.columns[*idx].is_nullable {
-            return Err(MuroError::Execution(format!(
+            return Err(MuroError::ConstraintViolation(format!(
                 "Cannot SET NULL on non-nullable child column '{}.{}'",
                 child_table.name, child_table.columns[*idx].name
             )));
@@ -567,7 +575,7 @@ fn enforce_child_foreign_keys_with_skip(
         })?;
 
         if !exists {
-            return Err(MuroError::Execution(format!(
+            return Err(MuroError::ConstraintViolation(format!(
                 "FOREIGN KEY constraint fails: ({}) REFERENCES {}({})",
                 fk.columns.join(", "),
                 fk.ref_table,
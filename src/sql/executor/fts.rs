@@ -1,4 +1,5 @@
 use super::*;
+use crate::sql::session::{record_query_warning_current, ScanWarning};
 
 pub(super) const SQL_FTS_SCORE_SCALE: f64 = 1_000_000.0;
 pub(super) const SQL_FTS_NEXT_DOC_ID_KEY: &[u8] = b"__next_doc_id__";
@@ -41,15 +42,7 @@ pub(super) fn execute_fts_scan_rows(
             ))
         })?;
     let results = match mode {
-        MatchMode::NaturalLanguage => query_natural_with_config(
-            &fts,
-            pager,
-            query,
-            FtsQueryConfig {
-                stop_filter: idx.fts_stop_filter,
-                stop_df_ratio_ppm: idx.fts_stop_df_ratio_ppm,
-            },
-        )?,
+        MatchMode::NaturalLanguage => query_natural_for_index(&fts, idx, column, query, pager)?,
         MatchMode::Boolean => query_boolean(&fts, pager, query)?,
     };
 
@@ -75,6 +68,45 @@ pub(super) fn execute_fts_scan_rows(
     Ok(rows)
 }
 
+/// Run a NATURAL query with the index's stop-filter settings. A rescan is
+/// bounded by the index's document cap and the statement's timeout and
+/// cancellation; an empty fallback is reported through `SHOW WARNINGS`.
+fn query_natural_for_index(
+    fts: &FtsIndex,
+    idx: &IndexDef,
+    column: &str,
+    query: &str,
+    pager: &mut impl PageStore,
+) -> Result<Vec<FtsResult>> {
+    let outcome = query_natural_outcome(
+        fts,
+        pager,
+        query,
+        FtsQueryConfig {
+            stop_filter: idx.fts_stop_filter,
+            stop_df_ratio_ppm: idx.fts_stop_df_ratio_ppm,
+            stop_fallback: idx.fts_stop_fallback,
+            stop_fallback_max_docs: idx.fts_stop_fallback_max_docs,
+            checkpoint: Some(cancellation_point),
+        },
+    )?;
+    if outcome.all_stop_filtered {
+        record_query_warning_current(ScanWarning {
+            table: idx.table_name.clone(),
+            page_id: None,
+            key_range: String::new(),
+            message: format!(
+                "MATCH({}) AGAINST('{}'): every query ngram is stop-filtered by index '{}'; no rows matched (stop_fallback='{}')",
+                column,
+                query,
+                idx.name,
+                FtsStopFallback::EmptyWithWarning.as_str()
+            ),
+        });
+    }
+    Ok(outcome.results)
+}
+
 pub(super) fn open_fulltext_index(
     indexes: &[IndexDef],
     table_name: &str,
@@ -145,15 +177,9 @@ pub(super) fn build_fts_eval_context(
         let idx = find_fulltext_index(indexes, table_name, &key.column)?;
         let fts = open_fulltext_index(indexes, table_name, &key.column, pager)?;
         let results = match key.mode {
-            MatchMode::NaturalLanguage => query_natural_with_config(
-                &fts,
-                pager,
-                &key.query,
-                FtsQueryConfig {
-                    stop_filter: idx.fts_stop_filter,
-                    stop_df_ratio_ppm: idx.fts_stop_df_ratio_ppm,
-                },
-            )?,
+            MatchMode::NaturalLanguage => {
+                query_natural_for_index(&fts, idx, &key.column, &key.query, pager)?
+            }
             MatchMode::Boolean => query_boolean(&fts, pager, &key.query)?,
         };
         let mut scores = HashMap::new();
@@ -187,9 +213,17 @@ pub(super) fn collect_match_expr_keys(expr: &Expr, keys: &mut HashSet<MatchExprK
             collect_match_expr_keys(right, keys);
         }
         Expr::UnaryOp { operand, .. } => collect_match_expr_keys(operand, keys),
-        Expr::Like { expr, pattern, .. } => {
+        Expr::Like {
+            expr,
+            pattern,
+            escape,
+            ..
+        } => {
             collect_match_expr_keys(expr, keys);
             collect_match_expr_keys(pattern, keys);
+            if let Some(e) = escape {
+                collect_match_expr_keys(e, keys);
+            }
         }
         Expr::InList { expr, list, .. } => {
             collect_match_expr_keys(expr, keys);
@@ -227,19 +261,21 @@ pub(super) fn collect_match_expr_keys(expr: &Expr, keys: &mut HashSet<MatchExprK
                 collect_match_expr_keys(e, keys);
             }
         }
-        Expr::Cast { expr, .. } => collect_match_expr_keys(expr, keys),
+        Expr::Cast { expr, .. } | Expr::Collate { expr, .. } => collect_match_expr_keys(expr, keys),
         Expr::GreaterThanZero(inner) => collect_match_expr_keys(inner, keys),
         Expr::InSubquery { expr, .. } => collect_match_expr_keys(expr, keys),
         _ => {}
     }
 }
 
-pub(super) fn materialize_fts_expr(
+/// Value of a MATCH ... AGAINST score or an fts_snippet() call for the
+/// current row; the evaluator asks for these instead of computing them.
+pub(super) fn fts_expr_value(
     expr: &Expr,
     table_def: &TableDef,
     values: &[Value],
     fts_ctx: Option<&FtsEvalContext>,
-) -> Expr {
+) -> Value {
     match expr {
         Expr::MatchAgainst {
             column,
@@ -260,7 +296,7 @@ pub(super) fn materialize_fts_expr(
                     })
                 })
                 .unwrap_or(0);
-            Expr::IntLiteral(score)
+            Value::Integer(score)
         }
         Expr::FtsSnippet {
             column,
@@ -275,89 +311,9 @@ pub(super) fn materialize_fts_expr(
                 .and_then(value_to_fts_text)
                 .map(|text| fts_snippet(&text, query, pre_tag, post_tag, *context_chars))
                 .unwrap_or_default();
-            Expr::StringLiteral(snippet)
+            Value::Varchar(snippet)
         }
-        Expr::BinaryOp { left, op, right } => Expr::BinaryOp {
-            left: Box::new(materialize_fts_expr(left, table_def, values, fts_ctx)),
-            op: *op,
-            right: Box::new(materialize_fts_expr(right, table_def, values, fts_ctx)),
-        },
-        Expr::UnaryOp { op, operand } => Expr::UnaryOp {
-            op: *op,
-            operand: Box::new(materialize_fts_expr(operand, table_def, values, fts_ctx)),
-        },
-        Expr::Like {
-            expr,
-            pattern,
-            negated,
-        } => Expr::Like {
-            expr: Box::new(materialize_fts_expr(expr, table_def, values, fts_ctx)),
-            pattern: Box::new(materialize_fts_expr(pattern, table_def, values, fts_ctx)),
-            negated: *negated,
-        },
-        Expr::InList {
-            expr,
-            list,
-            negated,
-        } => Expr::InList {
-            expr: Box::new(materialize_fts_expr(expr, table_def, values, fts_ctx)),
-            list: list
-                .iter()
-                .map(|e| materialize_fts_expr(e, table_def, values, fts_ctx))
-                .collect(),
-            negated: *negated,
-        },
-        Expr::Between {
-            expr,
-            low,
-            high,
-            negated,
-        } => Expr::Between {
-            expr: Box::new(materialize_fts_expr(expr, table_def, values, fts_ctx)),
-            low: Box::new(materialize_fts_expr(low, table_def, values, fts_ctx)),
-            high: Box::new(materialize_fts_expr(high, table_def, values, fts_ctx)),
-            negated: *negated,
-        },
-        Expr::IsNull { expr, negated } => Expr::IsNull {
-            expr: Box::new(materialize_fts_expr(expr, table_def, values, fts_ctx)),
-            negated: *negated,
-        },
-        Expr::FunctionCall { name, args } => Expr::FunctionCall {
-            name: name.clone(),
-            args: args
-                .iter()
-                .map(|a| materialize_fts_expr(a, table_def, values, fts_ctx))
-                .collect(),
-        },
-        Expr::CaseWhen {
-            operand,
-            when_clauses,
-            else_clause,
-        } => Expr::CaseWhen {
-            operand: operand
-                .as_ref()
-                .map(|o| Box::new(materialize_fts_expr(o, table_def, values, fts_ctx))),
-            when_clauses: when_clauses
-                .iter()
-                .map(|(c, t)| {
-                    (
-                        materialize_fts_expr(c, table_def, values, fts_ctx),
-                        materialize_fts_expr(t, table_def, values, fts_ctx),
-                    )
-                })
-                .collect(),
-            else_clause: else_clause
-                .as_ref()
-                .map(|e| Box::new(materialize_fts_expr(e, table_def, values, fts_ctx))),
-        },
-        Expr::Cast { expr, target_type } => Expr::Cast {
-            expr: Box::new(materialize_fts_expr(expr, table_def, values, fts_ctx)),
-            target_type: *target_type,
-        },
-        Expr::GreaterThanZero(inner) => Expr::GreaterThanZero(Box::new(materialize_fts_expr(
-            inner, table_def, values, fts_ctx,
-        ))),
-        _ => expr.clone(),
+        _ => Value::Null,
     }
 }
 
@@ -512,75 +468,3 @@ pub(super) fn value_to_fts_text(value: &Value) -> Option<String> {
         Value::Uuid(b) => Some(crate::types::format_uuid(b)),
     }
 }
-
-/// Validate that a value fits within the constraints of the data type.
-pub(super) fn validate_value(value: &Value, data_type: &DataType) -> Result<()> {
-    match (value, data_type) {
-        (Value::Integer(n), DataType::TinyInt) if *n < -128 || *n > 127 => {
-            Err(MuroError::Execution(format!(
-                "Value {} out of range for TINYINT (-128 to 127)",
-                n
-            )))
-        }
-        (Value::Integer(n), DataType::SmallInt) if *n < -32768 || *n > 32767 => {
-            Err(MuroError::Execution(format!(
-                "Value {} out of range for SMALLINT (-32768 to 32767)",
-                n
-            )))
-        }
-        (Value::Integer(n), DataType::Int) if *n < i32::MIN as i64 || *n > i32::MAX as i64 => {
-            Err(MuroError::Execution(format!(
-                "Value {} out of range for INT ({} to {})",
-                n,
-                i32::MIN,
-                i32::MAX
-            )))
-        }
-        (Value::Float(n), DataType::Float) if !n.is_finite() => {
-            Err(MuroError::Execution("FLOAT must be a finite value".into()))
-        }
-        (Value::Float(n), DataType::Double) if !n.is_finite() => {
-            Err(MuroError::Execution("DOUBLE must be a finite value".into()))
-        }
-        (Value::Float(n), DataType::Float) if *n < f32::MIN as f64 || *n > f32::MAX as f64 => Err(
-            MuroError::Execution(format!("Value {} out of range for FLOAT", n)),
-        ),
-        (Value::Varchar(s), DataType::Varchar(Some(max))) if s.chars().count() as u32 > *max => {
-            Err(MuroError::Execution(format!(
-                "String length {} exceeds VARCHAR({})",
-                s.chars().count(),
-                max
-            )))
-        }
-        (Value::Varbinary(b), DataType::Varbinary(Some(max))) if b.len() as u32 > *max => {
-            Err(MuroError::Execution(format!(
-                "Binary length {} exceeds VARBINARY({})",
-                b.len(),
-                max
-            )))
-        }
-        (Value::Decimal(d), DataType::Decimal(p, s)) => {
-            let max_int_digits = p - s;
-            let int_part = d.trunc().abs();
-            let int_digits = if int_part.is_zero() {
-                0u32
-            } else {
-                int_part.to_string().len() as u32
-            };
-            if int_digits > max_int_digits {
-                Err(MuroError::Execution(format!(
-                    "Value '{}' out of range for DECIMAL({},{})",
-                    d, p, s
-                )))
-            } else {
-                Ok(())
-            }
-        }
-        (Value::Varchar(s), DataType::Jsonb) => {
-            serde_json::from_str::<serde_json::Value>(s)
-                .map_err(|e| MuroError::Execution(format!("Invalid JSON: {}", e)))?;
-            Ok(())
-        }
-        _ => Ok(()),
-    }
-}
@@ -1,30 +1,394 @@
 use super::*;
 
-pub(super) fn encode_index_key_from_row(
+/// How one B-tree index key part is read from a row.
+pub(super) enum IndexKeyPart {
+    Column(usize),
+    Expr(Expr),
+}
+
+/// Resolve the key parts of a B-tree index against its table. `None` if a
+/// key column no longer exists.
+pub(super) fn index_key_parts(
+    table_def: &TableDef,
+    idx: &IndexDef,
+) -> Result<Option<Vec<IndexKeyPart>>> {
+    let mut parts = Vec::with_capacity(idx.column_names.len());
+    for (i, name) in idx.column_names.iter().enumerate() {
+        match idx.expressions.get(i).and_then(Option::as_deref) {
+            Some(text) => parts.push(IndexKeyPart::Expr(parse_index_expr(text)?)),
+            None => match table_def.column_index(name) {
+                Some(ci) => parts.push(IndexKeyPart::Column(ci)),
+                None => return Ok(None),
+            },
+        }
+    }
+    Ok(Some(parts))
+}
+
+/// Key type of an expression key part, from the value it produced.
+pub(super) fn expr_key_type(value: &Value) -> DataType {
+    match value {
+        Value::Integer(_) => DataType::BigInt,
+        Value::Float(_) => DataType::Double,
+        Value::Decimal(d) => DataType::Decimal(28, d.scale()),
+        Value::Date(_) => DataType::Date,
+        Value::DateTime(_) => DataType::DateTime,
+        Value::Timestamp(_) => DataType::Timestamp,
+        Value::Varbinary(_) => DataType::Varbinary(None),
+        Value::Uuid(_) => DataType::Uuid,
+        Value::Varchar(_) | Value::Null => DataType::Text,
+    }
+}
+
+/// Encode a row's key for one B-tree index, evaluating expression key parts
+/// against the row. `None` if any key part is NULL (NULLs are not indexed).
+pub(super) fn encode_index_key(
+    table_def: &TableDef,
+    parts: &[IndexKeyPart],
     row_values: &[Value],
-    col_indices: &[usize],
-    columns: &[crate::schema::column::ColumnDef],
-    is_composite: bool,
-) -> Option<Vec<u8>> {
-    if is_composite {
-        // For composite: skip if any value is NULL
-        let mut vals = Vec::new();
-        let mut types = Vec::new();
-        for &ci in col_indices {
-            if ci >= row_values.len() || row_values[ci].is_null() {
-                return None;
+) -> Result<Option<Vec<u8>>> {
+    let mut vals: Vec<std::borrow::Cow<Value>> = Vec::with_capacity(parts.len());
+    let mut types = Vec::with_capacity(parts.len());
+    for part in parts {
+        match part {
+            IndexKeyPart::Column(ci) => match row_values.get(*ci) {
+                Some(v) if !v.is_null() => {
+                    let col = &table_def.columns[*ci];
+                    vals.push(col.collation.key_value(v));
+                    types.push(col.data_type);
+                }
+                _ => return Ok(None),
+            },
+            IndexKeyPart::Expr(expr) => {
+                let v = eval_expr(expr, &|name| {
+                    table_def
+                        .column_index(name)
+                        .and_then(|i| row_values.get(i).cloned())
+                })?;
+                if v.is_null() {
+                    return Ok(None);
+                }
+                types.push(expr_key_type(&v));
+                vals.push(std::borrow::Cow::Owned(v));
             }
-            vals.push(&row_values[ci]);
-            types.push(&columns[ci].data_type);
         }
-        Some(encode_composite_key(&vals, &types))
+    }
+    if parts.len() > 1 {
+        let val_refs: Vec<&Value> = vals.iter().map(|v| v.as_ref()).collect();
+        let type_refs: Vec<&DataType> = types.iter().collect();
+        Ok(Some(encode_composite_key(&val_refs, &type_refs)))
+    } else {
+        Ok(Some(encode_value(&vals[0], &types[0])))
+    }
+}
+
+/// Encode a row's key for `idx`. Outer `None` if the index no longer
+/// resolves against the table, inner `None` for NULL keys.
+pub(super) fn index_key_for_row(
+    table_def: &TableDef,
+    idx: &IndexDef,
+    row_values: &[Value],
+) -> Result<Option<Option<Vec<u8>>>> {
+    match index_key_parts(table_def, idx)? {
+        Some(parts) => encode_index_key(table_def, &parts, row_values).map(Some),
+        None => Ok(None),
+    }
+}
+
+/// B-tree key of a non-unique index entry of `table_def`. In the current
+/// layout the index key is byte-stuffed and terminated (as in
+/// [`encode_composite_key`]), then the primary key follows: the terminator
+/// keeps the entries of one index key apart from those of any key it is a
+/// prefix of, and entries sort by index key. Tables not yet migrated to it
+/// concatenate the two, where a variable-length key runs into the primary
+/// key and two rows can share an entry key.
+pub(super) fn non_unique_entry_key(table_def: &TableDef, idx_key: &[u8], pk_key: &[u8]) -> Vec<u8> {
+    let mut key = Vec::with_capacity(idx_key.len() + pk_key.len() + 2);
+    if table_def.index_key_format == INDEX_KEY_FORMAT_CONCAT {
+        key.extend_from_slice(idx_key);
     } else {
-        let ci = col_indices[0];
-        if ci >= row_values.len() || row_values[ci].is_null() {
-            return None;
+        encode_byte_stuffed(&mut key, idx_key);
+    }
+    key.extend_from_slice(pk_key);
+    key
+}
+
+/// Prefix of the non-unique entries of `idx` whose index key is `idx_key`,
+/// or starts with it when `idx_key` holds only the leading parts of a
+/// composite key. Composite parts are self-delimiting, so only a
+/// single-part key needs the terminator to stop at its own entries.
+fn non_unique_entry_prefix(table_def: &TableDef, idx: &IndexDef, idx_key: &[u8]) -> Vec<u8> {
+    let mut prefix = Vec::with_capacity(idx_key.len() + 2);
+    if table_def.index_key_format == INDEX_KEY_FORMAT_CONCAT {
+        prefix.extend_from_slice(idx_key);
+    } else if idx.column_names.len() > 1 {
+        push_byte_stuffed(&mut prefix, idx_key);
+    } else {
+        encode_byte_stuffed(&mut prefix, idx_key);
+    }
+    prefix
+}
+
+/// Index key of the non-unique index entry `entry_key` whose value (the
+/// primary key) is `pk_key`.
+pub(super) fn non_unique_entry_index_key(
+    table_def: &TableDef,
+    entry_key: &[u8],
+    pk_key: &[u8],
+) -> Result<Vec<u8>> {
+    if table_def.index_key_format == INDEX_KEY_FORMAT_CONCAT {
+        return match entry_key.len().checked_sub(pk_key.len()) {
+            Some(len) => Ok(entry_key[..len].to_vec()),
+            None => Err(MuroError::Corruption(
+                "invalid non-unique index entry: key shorter than value".into(),
+            )),
+        };
+    }
+    decode_byte_stuffed(entry_key)
+        .map(|(idx_key, _)| idx_key)
+        .ok_or_else(|| {
+            MuroError::Corruption("invalid non-unique index entry: unterminated index key".into())
+        })
+}
+
+/// Rebuild the B-tree of `idx` from the rows of `table_def` and free the old
+/// tree. A building index is finished. Catalog migrations use this when the
+/// entry layout changes.
+pub(crate) fn rebuild_index(
+    table_def: &TableDef,
+    idx: &mut IndexDef,
+    pager: &mut impl PageStore,
+    catalog: &mut SystemCatalog,
+) -> Result<()> {
+    let Some(parts) = index_key_parts(table_def, idx)? else {
+        return Ok(());
+    };
+    let mut idx_btree = BTree::create(pager)?.with_fill_factor(idx.fill_factor);
+    build_index_from_rows(
+        table_def,
+        &mut idx_btree,
+        idx.is_unique,
+        pager,
+        |row_values| encode_index_key(table_def, &parts, row_values),
+        || unique_violation(idx),
+    )?;
+    for page_id in BTree::open(idx.btree_root).collect_all_pages(pager)? {
+        pager.free_page(page_id);
+    }
+    idx.btree_root = idx_btree.root_page_id();
+    if idx.building {
+        idx.building = false;
+        catalog.delete_index_build(pager, &idx.name)?;
+    }
+    catalog.update_index(pager, idx)
+}
+
+/// Rows read per batch while building an index from existing data.
+const INDEX_BUILD_BATCH_ROWS: usize = 1024;
+
+/// Fill `idx_btree` from the rows of `table_def`, with `key_of` giving each
+/// row's index key (`None` skips the row, as for NULL keys). Rows are read in
+/// batches that are inserted before the scan resumes, so memory stays bounded
+/// however large the table is. A unique index checks each key against the
+/// tree it is building and fails with `duplicate()` on the first repeat;
+/// other indexes store [`non_unique_entry_key`]s.
+pub(super) fn build_index_from_rows(
+    table_def: &TableDef,
+    idx_btree: &mut BTree,
+    unique: bool,
+    pager: &mut impl PageStore,
+    key_of: impl FnMut(&[Value]) -> Result<Option<Vec<u8>>>,
+    duplicate: impl Fn() -> MuroError,
+) -> Result<()> {
+    let duplicate: &dyn Fn() -> MuroError = &duplicate;
+    build_index_rows(
+        table_def,
+        idx_btree,
+        pager,
+        &mut IndexBuildProgress::default(),
+        u64::MAX,
+        key_of,
+        unique.then_some(duplicate),
+    )?;
+    Ok(())
+}
+
+/// Index up to `max_rows` more rows of `table_def` into `idx_btree`, like
+/// [`build_index_from_rows`], starting after the row `progress.last_key`
+/// names and advancing `progress`. `duplicate` is set for unique indexes.
+/// Returns whether the scan reached the end of the table.
+pub(super) fn build_index_rows(
+    table_def: &TableDef,
+    idx_btree: &mut BTree,
+    pager: &mut impl PageStore,
+    progress: &mut IndexBuildProgress,
+    max_rows: u64,
+    mut key_of: impl FnMut(&[Value]) -> Result<Option<Vec<u8>>>,
+    duplicate: Option<&dyn Fn() -> MuroError>,
+) -> Result<bool> {
+    let data_btree = BTree::open(table_def.data_btree_root);
+    let mut remaining = max_rows;
+    while remaining > 0 {
+        cancellation_point()?;
+        let limit = remaining.min(INDEX_BUILD_BATCH_ROWS as u64);
+        let mut batch: Vec<(Vec<u8>, Vec<u8>)> = Vec::new();
+        let mut rows_read = 0u64;
+        let mut last_pk = None;
+        let resume = progress.last_key.as_deref();
+        let mut visit = |pk_key: &[u8], v: &[u8]| -> Result<bool> {
+            // `scan_from` starts at the last row of the previous batch.
+            if resume == Some(pk_key) {
+                return Ok(true);
+            }
+            let row_values =
+                deserialize_row_versioned(v, &table_def.columns, table_def.row_format_version)?;
+            if let Some(key) = key_of(&row_values)? {
+                batch.push((key, pk_key.to_vec()));
+            }
+            rows_read += 1;
+            last_pk = Some(pk_key.to_vec());
+            Ok(rows_read < limit)
+        };
+        match resume {
+            Some(start) => data_btree.scan_from(pager, start, &mut visit)?,
+            None => data_btree.scan(pager, &mut visit)?,
+        }
+
+        for (key, pk_key) in batch {
+            match duplicate {
+                // A write may already have indexed this row of a build
+                // that was interrupted; only another row's key collides.
+                Some(duplicate) => {
+                    if idx_btree
+                        .search(pager, &key)?
+                        .is_some_and(|existing| existing != pk_key)
+                    {
+                        return Err(duplicate());
+                    }
+                }
+                None => {
+                    let entry_key = non_unique_entry_key(table_def, &key, &pk_key);
+                    idx_btree.insert(pager, &entry_key, &pk_key)?;
+                    continue;
+                }
+            }
+            idx_btree.insert(pager, &key, &pk_key)?;
+        }
+        if last_pk.is_some() {
+            progress.last_key = last_pk;
+        }
+        progress.rows_done += rows_read;
+        if rows_read < limit {
+            return Ok(true);
+        }
+        remaining -= rows_read;
+    }
+    Ok(false)
+}
+
+/// Table columns an index reads, including those its key expressions read.
+pub(super) fn index_referenced_columns(idx: &IndexDef) -> Result<Vec<String>> {
+    let mut columns = Vec::new();
+    for (i, name) in idx.column_names.iter().enumerate() {
+        match idx.expressions.get(i).and_then(Option::as_deref) {
+            Some(text) => index_expr_columns(&parse_index_expr(text)?, &mut columns),
+            None => {
+                if !columns.contains(name) {
+                    columns.push(name.clone());
+                }
+            }
         }
-        Some(encode_value(&row_values[ci], &columns[ci].data_type))
     }
+    Ok(columns)
+}
+
+/// Rename a column in an index's key parts, rewriting key expressions that
+/// read it. Returns whether the index changed.
+pub(super) fn rename_index_column(idx: &mut IndexDef, old: &str, new: &str) -> Result<bool> {
+    let mut changed = false;
+    for i in 0..idx.column_names.len() {
+        match idx.expressions.get(i).and_then(Option::as_deref) {
+            Some(text) => {
+                let mut expr = parse_index_expr(text)?;
+                rename_index_expr_column(&mut expr, old, new);
+                let renamed = index_expr_text(&expr).ok_or_else(|| {
+                    MuroError::Schema(format!("Invalid index expression '{}'", text))
+                })?;
+                if renamed != text {
+                    idx.column_names[i] = renamed.clone();
+                    idx.expressions[i] = Some(renamed);
+                    changed = true;
+                }
+            }
+            None => {
+                if idx.column_names[i] == old {
+                    idx.column_names[i] = new.to_string();
+                    changed = true;
+                }
+            }
+        }
+    }
+    Ok(changed)
+}
+
+/// Reject a column type change that would silently change the keys of an
+/// expression index reading the column.
+pub(super) fn ensure_no_expression_index_on(indexes: &[IndexDef], col_name: &str) -> Result<()> {
+    for idx in indexes.iter().filter(|idx| idx.has_expressions()) {
+        if index_referenced_columns(idx)?.iter().any(|c| c == col_name) {
+            return Err(MuroError::Schema(format!(
+                "Cannot change type of column '{}': expression index '{}' depends on it",
+                col_name, idx.name
+            )));
+        }
+    }
+    Ok(())
+}
+
+/// Planner statistics for the B-tree indexes of a table.
+pub(super) fn index_plan_stats(table_def: &TableDef, indexes: &[IndexDef]) -> Vec<IndexPlanStat> {
+    indexes
+        .iter()
+        .filter(|idx| idx.index_type == IndexType::BTree && !idx.building)
+        .map(|idx| IndexPlanStat {
+            name: idx.name.clone(),
+            column_names: idx.column_names.clone(),
+            is_unique: idx.is_unique,
+            stats_distinct_keys: idx.stats_distinct_keys,
+            stats_num_min: idx.stats_num_bounds_known.then_some(idx.stats_num_min),
+            stats_num_max: idx.stats_num_bounds_known.then_some(idx.stats_num_max),
+            stats_num_hist_bins: idx.stats_num_hist_bins.clone(),
+            column_stats: idx
+                .column_names
+                .iter()
+                .map(|col| {
+                    table_def.column_stats(col).map(|cs| ColumnPlanStat {
+                        distinct_count: cs.distinct_count,
+                        null_count: cs.null_count,
+                        min: cs.min,
+                        max: cs.max,
+                    })
+                })
+                .collect(),
+        })
+        .collect()
+}
+
+/// Table-level planner statistics. The live row count is only sampled when
+/// the table has been analyzed, to tell whether those stats went stale.
+pub(super) fn table_planner_stats(
+    table_def: &TableDef,
+    pager: &mut impl PageStore,
+) -> Result<PlannerStats> {
+    let live_rows = if table_def.stats_row_count > 0 {
+        BTree::open(table_def.data_btree_root).estimate_entries(pager)?
+    } else {
+        0
+    };
+    Ok(PlannerStats {
+        table_rows: table_def.stats_row_count,
+        live_rows,
+    })
 }
 
 /// Evaluate PK seek key from planner key expressions.
@@ -40,8 +404,9 @@ pub(super) fn eval_pk_seek_key(
             let col_idx = table_def.column_index(col_name).ok_or_else(|| {
                 MuroError::Execution(format!("PK column '{}' not found", col_name))
             })?;
-            types.push(table_def.columns[col_idx].data_type);
-            vals.push(val);
+            let col = &table_def.columns[col_idx];
+            types.push(col.data_type);
+            vals.push(col.collation.key_value(&val).into_owned());
         }
         let val_refs: Vec<&Value> = vals.iter().collect();
         let type_refs: Vec<&DataType> = types.iter().collect();
@@ -52,9 +417,10 @@ pub(super) fn eval_pk_seek_key(
         let col_idx = table_def
             .column_index(col_name)
             .ok_or_else(|| MuroError::Execution(format!("PK column '{}' not found", col_name)))?;
+        let col = &table_def.columns[col_idx];
         Ok(encode_value(
-            &key_val,
-            &table_def.columns[col_idx].data_type,
+            &col.collation.key_value(&key_val),
+            &col.data_type,
         ))
     }
 }
@@ -70,103 +436,328 @@ pub(super) fn eval_index_seek_key(
         let mut types = Vec::new();
         for (col_name, expr) in column_names.iter().zip(key_exprs.iter()) {
             let val = eval_expr(expr, &|_| None)?;
-            let col_idx = table_def.column_index(col_name).ok_or_else(|| {
-                MuroError::Execution(format!("Index column '{}' not found", col_name))
-            })?;
-            types.push(table_def.columns[col_idx].data_type);
-            vals.push(val);
+            types.push(seek_key_type(table_def, col_name, &val));
+            vals.push(seek_key_value(table_def, col_name, val));
         }
         let val_refs: Vec<&Value> = vals.iter().collect();
         let type_refs: Vec<&DataType> = types.iter().collect();
         Ok(encode_composite_key(&val_refs, &type_refs))
     } else {
         let key_val = eval_expr(&key_exprs[0], &|_| None)?;
-        let col_idx = table_def.column_index(&column_names[0]).ok_or_else(|| {
-            MuroError::Execution(format!("Index column '{}' not found", column_names[0]))
-        })?;
+        let key_type = seek_key_type(table_def, &column_names[0], &key_val);
         Ok(encode_value(
-            &key_val,
-            &table_def.columns[col_idx].data_type,
+            &seek_key_value(table_def, &column_names[0], key_val),
+            &key_type,
         ))
     }
 }
 
+/// Probe value of one index key part: for a collated column, its collation
+/// key, as stored in the index.
+fn seek_key_value(table_def: &TableDef, key_name: &str, value: Value) -> Value {
+    match table_def.column_index(key_name) {
+        Some(ci) => match table_def.columns[ci].collation.key_value(&value) {
+            std::borrow::Cow::Owned(folded) => folded,
+            std::borrow::Cow::Borrowed(_) => value,
+        },
+        None => value,
+    }
+}
+
+/// Key type of one index key part: the column's type, or for an expression
+/// part (named by its expression text) the type of the probe value.
+fn seek_key_type(table_def: &TableDef, key_name: &str, value: &Value) -> DataType {
+    match table_def.column_index(key_name) {
+        Some(ci) => table_def.columns[ci].data_type,
+        None => expr_key_type(value),
+    }
+}
+
+/// Evaluate one end of an index range seek.
+///
+/// An integer bound on a FLOAT/DOUBLE column that the column type cannot
+/// represent exactly is moved to the nearest representable value inside the
+/// range, so the seek returns the same rows as the exact comparison.
+pub(super) fn eval_index_range_bound(
+    table_def: &TableDef,
+    column_names: &[String],
+    prefix_key_exprs: &[Expr],
+    bound: &(Box<Expr>, bool),
+    is_upper: bool,
+) -> Result<(Vec<u8>, bool)> {
+    let (expr, inclusive) = bound;
+    let mut key_exprs = prefix_key_exprs.to_vec();
+    key_exprs.push(*expr.clone());
+    let col_name = &column_names[prefix_key_exprs.len()];
+    let data_type = table_def
+        .column_index(col_name)
+        .map(|i| table_def.columns[i].data_type);
+    if matches!(data_type, Some(DataType::Float | DataType::Double)) {
+        if let Value::Integer(n) = eval_expr(expr, &|_| None)? {
+            let nearest = if data_type == Some(DataType::Float) {
+                let f = n as f32;
+                match (f as i128).cmp(&(n as i128)) {
+                    std::cmp::Ordering::Equal => None,
+                    std::cmp::Ordering::Less if is_upper => Some(f as f64),
+                    std::cmp::Ordering::Greater if !is_upper => Some(f as f64),
+                    _ if is_upper => Some(f.next_down() as f64),
+                    _ => Some(f.next_up() as f64),
+                }
+            } else {
+                let f = n as f64;
+                match (f as i128).cmp(&(n as i128)) {
+                    std::cmp::Ordering::Equal => None,
+                    std::cmp::Ordering::Less if is_upper => Some(f),
+                    std::cmp::Ordering::Greater if !is_upper => Some(f),
+                    _ if is_upper => Some(f.next_down()),
+                    _ => Some(f.next_up()),
+                }
+            };
+            if let Some(nearest) = nearest {
+                // No stored value equals `n`, so the moved bound is inclusive.
+                *key_exprs.last_mut().unwrap() = Expr::FloatLiteral(nearest);
+                return Ok((
+                    eval_index_seek_key(table_def, column_names, &key_exprs)?,
+                    true,
+                ));
+            }
+        }
+    }
+    Ok((
+        eval_index_seek_key(table_def, column_names, &key_exprs)?,
+        *inclusive,
+    ))
+}
+
 /// Encode the primary key for a row.
 pub(super) fn encode_pk_key(table_def: &TableDef, values: &[Value]) -> Vec<u8> {
     if table_def.is_composite_pk() {
         let pk_indices = table_def.pk_column_indices();
-        let pk_vals: Vec<&Value> = pk_indices.iter().map(|&i| &values[i]).collect();
+        let pk_vals: Vec<_> = pk_indices
+            .iter()
+            .map(|&i| table_def.columns[i].collation.key_value(&values[i]))
+            .collect();
+        let pk_vals: Vec<&Value> = pk_vals.iter().map(|v| v.as_ref()).collect();
         let pk_types: Vec<&DataType> = pk_indices
             .iter()
             .map(|&i| &table_def.columns[i].data_type)
             .collect();
         encode_composite_key(&pk_vals, &pk_types)
     } else if let Some(pk_idx) = table_def.pk_column_index() {
-        encode_value(&values[pk_idx], &table_def.columns[pk_idx].data_type)
+        let col = &table_def.columns[pk_idx];
+        encode_value(&col.collation.key_value(&values[pk_idx]), &col.data_type)
     } else {
         Vec::new()
     }
 }
 
+/// Primary keys a [`Plan::InListSeek`] reads: the distinct values of the list
+/// encoded as primary or index keys and sorted, so the seeks walk the B-tree
+/// in key order. Values that evaluate to NULL match nothing and are skipped.
+/// On the primary key the keys are returned as is; rows may not exist.
+///
+/// [`Plan::InListSeek`]: crate::sql::planner::Plan::InListSeek
+pub(super) fn in_list_seek_pk_keys(
+    table_def: &TableDef,
+    index: Option<&IndexDef>,
+    column_name: &str,
+    values: &[Expr],
+    pager: &mut impl PageStore,
+) -> Result<Vec<Vec<u8>>> {
+    let keys = in_list_seek_keys(table_def, index.is_some(), column_name, values)?;
+    let Some(idx) = index else {
+        return Ok(keys);
+    };
+    let mut pk_keys = Vec::new();
+    for key in &keys {
+        cancellation_point()?;
+        pk_keys.extend(index_seek_pk_keys(table_def, idx, key, pager)?);
+    }
+    Ok(pk_keys)
+}
+
+/// The entries of `idx` an in-list seek reads, as (index key, primary key)
+/// pairs in key order.
+pub(super) fn in_list_seek_entries(
+    table_def: &TableDef,
+    idx: &IndexDef,
+    column_name: &str,
+    values: &[Expr],
+    pager: &mut impl PageStore,
+) -> Result<Vec<(Vec<u8>, Vec<u8>)>> {
+    let mut entries = Vec::new();
+    for key in in_list_seek_keys(table_def, true, column_name, values)? {
+        cancellation_point()?;
+        entries.extend(index_seek_entries(table_def, idx, &key, pager)?);
+    }
+    Ok(entries)
+}
+
+/// The distinct non-NULL values of an in-list, encoded as primary keys or
+/// (`on_index`) index keys and sorted.
+fn in_list_seek_keys(
+    table_def: &TableDef,
+    on_index: bool,
+    column_name: &str,
+    values: &[Expr],
+) -> Result<Vec<Vec<u8>>> {
+    let mut keys = Vec::with_capacity(values.len());
+    for expr in values {
+        let value = eval_expr(expr, &|_| None)?;
+        if value.is_null() {
+            continue;
+        }
+        keys.push(if on_index {
+            let key_type = seek_key_type(table_def, column_name, &value);
+            encode_value(&seek_key_value(table_def, column_name, value), &key_type)
+        } else {
+            let col_idx = table_def.column_index(column_name).ok_or_else(|| {
+                MuroError::Execution(format!("PK column '{}' not found", column_name))
+            })?;
+            let col = &table_def.columns[col_idx];
+            encode_value(&col.collation.key_value(&value), &col.data_type)
+        });
+    }
+    keys.sort_unstable();
+    keys.dedup();
+    Ok(keys)
+}
+
 /// Look up PK keys from an index for a given index key.
 /// For unique indexes, uses exact search. For non-unique indexes,
 /// uses prefix scan to find all matching entries.
 pub(super) fn index_seek_pk_keys(
+    table_def: &TableDef,
     idx: &IndexDef,
     idx_key: &[u8],
     pager: &mut impl PageStore,
 ) -> Result<Vec<Vec<u8>>> {
+    let mut pk_keys = Vec::new();
+    scan_index_seek(table_def, idx, idx_key, pager, |_, pk_key| {
+        pk_keys.push(pk_key.to_vec());
+        Ok(())
+    })?;
+    Ok(pk_keys)
+}
+
+/// [`index_seek_pk_keys`] with the index key of each entry: (index key,
+/// primary key) pairs. A prefix of a composite key finds entries with
+/// longer keys.
+pub(super) fn index_seek_entries(
+    table_def: &TableDef,
+    idx: &IndexDef,
+    idx_key: &[u8],
+    pager: &mut impl PageStore,
+) -> Result<Vec<(Vec<u8>, Vec<u8>)>> {
+    let mut entries = Vec::new();
+    scan_index_seek(table_def, idx, idx_key, pager, |entry_key, pk_key| {
+        let idx_key = if idx.is_unique {
+            entry_key.to_vec()
+        } else {
+            non_unique_entry_index_key(table_def, entry_key, pk_key)?
+        };
+        entries.push((idx_key, pk_key.to_vec()));
+        Ok(())
+    })?;
+    Ok(entries)
+}
+
+/// Pass each entry of `idx` under `idx_key` to `f` as (entry key, primary
+/// key).
+fn scan_index_seek(
+    table_def: &TableDef,
+    idx: &IndexDef,
+    idx_key: &[u8],
+    pager: &mut impl PageStore,
+    mut f: impl FnMut(&[u8], &[u8]) -> Result<()>,
+) -> Result<()> {
     let idx_btree = BTree::open(idx.btree_root);
     if idx.is_unique {
         if let Some(pk_key) = idx_btree.search(pager, idx_key)? {
-            Ok(vec![pk_key])
-        } else {
-            Ok(vec![])
+            f(idx_key, &pk_key)?;
         }
+        Ok(())
     } else {
-        // Non-unique: scan entries whose key starts with idx_key prefix
-        let mut pk_keys = Vec::new();
-        idx_btree.scan_from(pager, idx_key, |k, v| {
-            if k.starts_with(idx_key) {
-                pk_keys.push(v.to_vec());
+        // Non-unique: scan the entries under the key's prefix
+        let prefix = non_unique_entry_prefix(table_def, idx, idx_key);
+        idx_btree.scan_from(pager, &prefix, |k, v| {
+            if k.starts_with(&prefix) {
+                f(k, v)?;
                 Ok(true)
             } else {
                 Ok(false) // past the prefix range, stop scanning
             }
-        })?;
-        Ok(pk_keys)
+        })
     }
 }
 
 /// Look up PK keys from an index for a key range on index columns.
 /// `lower`/`upper` bounds are compared against the index-key portion only
-/// (excluding appended PK suffix for non-unique indexes).
+/// (decoded from the entry key for non-unique indexes).
 pub(super) fn index_seek_pk_keys_range(
+    table_def: &TableDef,
     idx: &IndexDef,
     lower: Option<(Vec<u8>, bool)>,
     upper: Option<(Vec<u8>, bool)>,
     pager: &mut impl PageStore,
 ) -> Result<Vec<Vec<u8>>> {
-    let idx_btree = BTree::open(idx.btree_root);
     let mut pk_keys = Vec::new();
-    let start_key = lower
-        .as_ref()
-        .map(|(k, _)| k.as_slice())
-        .unwrap_or(&[] as &[u8]);
+    scan_index_range(table_def, idx, lower, upper, pager, |_, pk_key| {
+        pk_keys.push(pk_key.to_vec());
+    })?;
+    Ok(pk_keys)
+}
+
+/// [`index_seek_pk_keys_range`] with the index key of each entry: (index
+/// key, primary key) pairs.
+pub(super) fn index_range_entries(
+    table_def: &TableDef,
+    idx: &IndexDef,
+    lower: Option<(Vec<u8>, bool)>,
+    upper: Option<(Vec<u8>, bool)>,
+    pager: &mut impl PageStore,
+) -> Result<Vec<(Vec<u8>, Vec<u8>)>> {
+    let mut entries = Vec::new();
+    scan_index_range(table_def, idx, lower, upper, pager, |idx_key, pk_key| {
+        entries.push((idx_key.to_vec(), pk_key.to_vec()));
+    })?;
+    Ok(entries)
+}
 
-    idx_btree.scan_from(pager, start_key, |k, v| {
+/// Pass each entry of `idx` whose index key is within the bounds to `f` as
+/// (index key, primary key).
+fn scan_index_range(
+    table_def: &TableDef,
+    idx: &IndexDef,
+    lower: Option<(Vec<u8>, bool)>,
+    upper: Option<(Vec<u8>, bool)>,
+    pager: &mut impl PageStore,
+    mut f: impl FnMut(&[u8], &[u8]),
+) -> Result<()> {
+    let idx_btree = BTree::open(idx.btree_root);
+    // Entries of the concatenated layout do not sort by index key.
+    let ordered = idx.is_unique || table_def.index_key_format != INDEX_KEY_FORMAT_CONCAT;
+    let start_key = match &lower {
+        Some((k, _)) if !idx.is_unique && table_def.index_key_format != INDEX_KEY_FORMAT_CONCAT => {
+            let mut start = Vec::with_capacity(k.len());
+            push_byte_stuffed(&mut start, k);
+            start
+        }
+        Some((k, _)) => k.clone(),
+        None => Vec::new(),
+    };
+
+    idx_btree.scan_from(pager, &start_key, |k, v| {
+        let decoded;
         let idx_part: &[u8] = if idx.is_unique {
             k
-        } else if k.len() >= v.len() {
-            &k[..k.len() - v.len()]
         } else {
-            return Err(MuroError::Corruption(
-                "invalid non-unique index entry: key shorter than value".into(),
-            ));
+            decoded = non_unique_entry_index_key(table_def, k, v)?;
+            &decoded
         };
 
         if let Some((lower_key, inclusive)) = &lower {
-            match idx_part.cmp(lower_key) {
+            match idx_part.cmp(lower_key.as_slice()) {
                 std::cmp::Ordering::Less => return Ok(true),
                 std::cmp::Ordering::Equal if !inclusive => return Ok(true),
                 _ => {}
@@ -174,57 +765,24 @@ pub(super) fn index_seek_pk_keys_range(
         }
 
         if let Some((upper_key, inclusive)) = &upper {
-            match idx_part.cmp(upper_key) {
-                std::cmp::Ordering::Greater => {
-                    if idx.is_unique {
-                        return Ok(false);
-                    }
-                    return Ok(true);
-                }
+            match idx_part.cmp(upper_key.as_slice()) {
+                std::cmp::Ordering::Greater => return Ok(!ordered),
                 std::cmp::Ordering::Equal if !inclusive => return Ok(true),
                 _ => {}
             }
         }
 
-        pk_keys.push(v.to_vec());
+        f(idx_part, v);
         Ok(true)
-    })?;
-
-    Ok(pk_keys)
+    })
 }
 
-/// Check unique index constraints for a set of values.
-pub(super) fn check_unique_index_constraints(
-    table_def: &TableDef,
-    indexes: &[IndexDef],
-    values: &[Value],
-    pager: &mut impl PageStore,
-) -> Result<()> {
-    for idx in indexes {
-        if idx.is_unique {
-            let col_indices: Vec<usize> = idx
-                .column_names
-                .iter()
-                .filter_map(|cn| table_def.column_index(cn))
-                .collect();
-            if col_indices.len() != idx.column_names.len() {
-                continue;
-            }
-            let is_composite = idx.column_names.len() > 1;
-            let encoded =
-                encode_index_key_from_row(values, &col_indices, &table_def.columns, is_composite);
-            if let Some(idx_key) = encoded {
-                let idx_btree = BTree::open(idx.btree_root);
-                if idx_btree.search(pager, &idx_key)?.is_some() {
-                    return Err(MuroError::UniqueViolation(format!(
-                        "Duplicate value in unique column(s) '{}'",
-                        idx.column_names.join(", ")
-                    )));
-                }
-            }
-        }
-    }
-    Ok(())
+/// The error for a row whose key is already taken in unique index `idx`.
+pub(super) fn unique_violation(idx: &IndexDef) -> MuroError {
+    MuroError::UniqueViolation(format!(
+        "Duplicate value in unique column(s) '{}'",
+        idx.column_names.join(", ")
+    ))
 }
 
 /// Find the first unique index conflict for the given values.
@@ -237,17 +795,9 @@ pub(super) fn find_unique_index_conflict(
 ) -> Result<Option<Vec<u8>>> {
     for idx in indexes {
         if idx.is_unique {
-            let col_indices: Vec<usize> = idx
-                .column_names
-                .iter()
-                .filter_map(|cn| table_def.column_index(cn))
-                .collect();
-            if col_indices.len() != idx.column_names.len() {
+            let Some(encoded) = index_key_for_row(table_def, idx, values)? else {
                 continue;
-            }
-            let is_composite = idx.column_names.len() > 1;
-            let encoded =
-                encode_index_key_from_row(values, &col_indices, &table_def.columns, is_composite);
+            };
             if let Some(idx_key) = encoded {
                 let idx_btree = BTree::open(idx.btree_root);
                 if let Some(existing_pk_key) = idx_btree.search(pager, &idx_key)? {
@@ -270,26 +820,15 @@ pub(super) fn check_unique_index_constraints_excluding(
 ) -> Result<()> {
     for idx in indexes {
         if idx.is_unique {
-            let col_indices: Vec<usize> = idx
-                .column_names
-                .iter()
-                .filter_map(|cn| table_def.column_index(cn))
-                .collect();
-            if col_indices.len() != idx.column_names.len() {
+            let Some(encoded) = index_key_for_row(table_def, idx, values)? else {
                 continue;
-            }
-            let is_composite = idx.column_names.len() > 1;
-            let encoded =
-                encode_index_key_from_row(values, &col_indices, &table_def.columns, is_composite);
+            };
             if let Some(idx_key) = encoded {
                 let idx_btree = BTree::open(idx.btree_root);
                 if let Some(existing_pk_key) = idx_btree.search(pager, &idx_key)? {
                     // Skip if the conflicting entry belongs to the row we're updating
                     if existing_pk_key != excluded_pk {
-                        return Err(MuroError::UniqueViolation(format!(
-                            "Duplicate value in unique column(s) '{}'",
-                            idx.column_names.join(", ")
-                        )));
+                        return Err(unique_violation(idx));
                     }
                 }
             }
@@ -299,8 +838,8 @@ pub(super) fn check_unique_index_constraints_excluding(
 }
 
 /// Insert values into secondary indexes.
-/// For non-unique indexes, the B-tree key is `index_key + pk_key` so that
-/// duplicate indexed values each get their own B-tree entry.
+/// For non-unique indexes, the B-tree key is a [`non_unique_entry_key`] so
+/// that duplicate indexed values each get their own B-tree entry.
 pub(super) fn insert_into_secondary_indexes(
     table_def: &TableDef,
     indexes: &mut [IndexDef],
@@ -310,26 +849,16 @@ pub(super) fn insert_into_secondary_indexes(
 ) -> Result<()> {
     for idx in indexes.iter_mut() {
         if idx.index_type == IndexType::BTree {
-            let col_indices: Vec<usize> = idx
-                .column_names
-                .iter()
-                .filter_map(|cn| table_def.column_index(cn))
-                .collect();
-            if col_indices.len() != idx.column_names.len() {
+            let Some(encoded) = index_key_for_row(table_def, idx, values)? else {
                 continue;
-            }
-            let is_composite = idx.column_names.len() > 1;
-            let encoded =
-                encode_index_key_from_row(values, &col_indices, &table_def.columns, is_composite);
+            };
             if let Some(idx_key) = encoded {
-                let mut idx_btree = BTree::open(idx.btree_root);
+                let mut idx_btree = BTree::open(idx.btree_root).with_fill_factor(idx.fill_factor);
                 if idx.is_unique {
                     idx_btree.insert(pager, &idx_key, pk_key)?;
                 } else {
-                    // Append pk_key to make the B-tree key unique
-                    let mut full_key = idx_key;
-                    full_key.extend_from_slice(pk_key);
-                    idx_btree.insert(pager, &full_key, pk_key)?;
+                    let entry_key = non_unique_entry_key(table_def, &idx_key, pk_key);
+                    idx_btree.insert(pager, &entry_key, pk_key)?;
                 }
                 idx.btree_root = idx_btree.root_page_id();
             }
@@ -363,7 +892,7 @@ pub(super) fn insert_into_secondary_indexes(
 }
 
 /// Delete values from secondary indexes.
-/// For non-unique indexes, the B-tree key is `index_key + pk_key`.
+/// For non-unique indexes, the B-tree key is a [`non_unique_entry_key`].
 pub(super) fn delete_from_secondary_indexes(
     table_def: &TableDef,
     indexes: &mut [IndexDef],
@@ -373,25 +902,15 @@ pub(super) fn delete_from_secondary_indexes(
 ) -> Result<()> {
     for idx in indexes.iter_mut() {
         if idx.index_type == IndexType::BTree {
-            let col_indices: Vec<usize> = idx
-                .column_names
-                .iter()
-                .filter_map(|cn| table_def.column_index(cn))
-                .collect();
-            if col_indices.len() != idx.column_names.len() {
+            let Some(encoded) = index_key_for_row(table_def, idx, values)? else {
                 continue;
-            }
-            let is_composite = idx.column_names.len() > 1;
-            let encoded =
-                encode_index_key_from_row(values, &col_indices, &table_def.columns, is_composite);
+            };
             if let Some(idx_key) = encoded {
                 let mut idx_btree = BTree::open(idx.btree_root);
                 if idx.is_unique {
                     idx_btree.delete(pager, &idx_key)?;
                 } else {
-                    let mut full_key = idx_key;
-                    full_key.extend_from_slice(pk_key);
-                    idx_btree.delete(pager, &full_key)?;
+                    idx_btree.delete(pager, &non_unique_entry_key(table_def, &idx_key, pk_key))?;
                 }
                 idx.btree_root = idx_btree.root_page_id();
             }
@@ -1,6 +1,11 @@
 use super::*;
+use crate::sql::session::{auto_increment_high_water_current, raise_auto_increment_current};
 use std::collections::HashSet;
 
+/// Multi-row INSERTs of at least this many rows into an empty table bulk load
+/// the data tree and its B-tree indexes instead of inserting row by row.
+const BULK_LOAD_MIN_ROWS: usize = 2;
+
 pub(super) fn exec_insert(
     ins: &Insert,
     pager: &mut impl PageStore,
@@ -15,90 +20,103 @@ pub(super) fn exec_insert(
 
     let mut indexes = catalog.get_indexes_for_table(pager, &ins.table_name)?;
 
-    let mut data_btree = BTree::open(table_def.data_btree_root);
+    let mut data_btree =
+        BTree::open(table_def.data_btree_root).with_fill_factor(table_def.fill_factor);
     let mut rows_inserted = 0u64;
-
-    for value_row in &ins.values {
-        let mut values = resolve_insert_values(&table_def, &ins.columns, value_row)?;
-
-        // Apply DEFAULT values for NULL columns that have defaults
-        for (i, col) in table_def.columns.iter().enumerate() {
-            if values[i].is_null() && !col.is_hidden {
-                if let Some(default) = &col.default_value {
-                    values[i] = match default {
-                        DefaultValue::Integer(n) => Value::Integer(*n),
-                        DefaultValue::Float(n) => Value::Float(*n),
-                        DefaultValue::String(s) => Value::Varchar(s.clone()),
-                        DefaultValue::Null => Value::Null,
-                    };
-                }
-            }
-        }
-
-        // Auto-generate for AUTO_INCREMENT / hidden _rowid columns
-        let pk_indices = table_def.pk_column_indices();
-        if pk_indices.len() == 1 {
-            let pk_idx = pk_indices[0];
-            if (table_def.columns[pk_idx].auto_increment || table_def.columns[pk_idx].is_hidden)
-                && values[pk_idx].is_null()
-            {
-                table_def.next_rowid += 1;
-                values[pk_idx] = Value::Integer(table_def.next_rowid);
-            }
+    let pk_indices = table_def.pk_column_indices();
+    let auto_pk_idx = match pk_indices.as_slice() {
+        [pk_idx]
+            if table_def.columns[*pk_idx].auto_increment
+                || table_def.columns[*pk_idx].is_hidden =>
+        {
+            Some(*pk_idx)
         }
-
-        // Validate NOT NULL constraints
-        for (i, col) in table_def.columns.iter().enumerate() {
-            if !col.is_nullable && values[i].is_null() {
-                return Err(MuroError::Execution(format!(
-                    "Column '{}' cannot be NULL",
-                    col.name
-                )));
-            }
+        _ => None,
+    };
+    let mut counter_reconciled = false;
+    // Foreign keys to the table itself look its root up in the catalog.
+    let self_referencing = table_def
+        .foreign_keys
+        .iter()
+        .any(|fk| fk.ref_table == table_def.name);
+
+    if !self_referencing && bulk_load_applies(ins, &table_def, &indexes, pager)? {
+        let mut rows = Vec::with_capacity(ins.values.len());
+        for value_row in &ins.values {
+            rows.push(prepare_insert_row(
+                &mut table_def,
+                &ins.columns,
+                value_row,
+                auto_pk_idx,
+                &mut counter_reconciled,
+                pager,
+                catalog,
+            )?);
         }
+        bulk_load_rows(&mut table_def, &mut indexes, &rows, pager)?;
+        table_def.adjust_row_count(rows.len() as i64);
+        catalog.update_table(pager, &table_def)?;
+        persist_indexes(catalog, pager, &indexes)?;
+        return Ok(ExecResult::RowsAffected(rows.len() as u64));
+    }
 
-        // Coerce values to declared column types before validation/serialization.
-        for (i, col) in table_def.columns.iter().enumerate() {
-            if !values[i].is_null() {
-                values[i] = coerce_value(&values[i], col.data_type)?;
+    if !ins.is_replace && ins.on_duplicate_key_update.is_none() {
+        if self_referencing {
+            // Each row may reference the ones before it, so it is prepared
+            // after they are inserted and the catalog shows them.
+            for (i, value_row) in ins.values.iter().enumerate() {
+                if i > 0 {
+                    catalog.update_table(pager, &table_def)?;
+                    persist_indexes(catalog, pager, &indexes)?;
+                }
+                let values = prepare_insert_row(
+                    &mut table_def,
+                    &ins.columns,
+                    value_row,
+                    auto_pk_idx,
+                    &mut counter_reconciled,
+                    pager,
+                    catalog,
+                )?;
+                insert_prepared_rows(&mut table_def, &mut indexes, &[values], pager)?;
             }
-        }
-
-        // Validate all values against their column types
-        for (i, val) in values.iter().enumerate() {
-            if !val.is_null() {
-                validate_value(val, &table_def.columns[i].data_type)?;
+        } else {
+            let mut rows = Vec::with_capacity(ins.values.len());
+            for value_row in &ins.values {
+                rows.push(prepare_insert_row(
+                    &mut table_def,
+                    &ins.columns,
+                    value_row,
+                    auto_pk_idx,
+                    &mut counter_reconciled,
+                    pager,
+                    catalog,
+                )?);
             }
+            insert_prepared_rows(&mut table_def, &mut indexes, &rows, pager)?;
         }
+        catalog.update_table(pager, &table_def)?;
+        persist_indexes(catalog, pager, &indexes)?;
+        return Ok(ExecResult::RowsAffected(ins.values.len() as u64));
+    }
 
-        // Validate CHECK constraints
-        for (i, col) in table_def.columns.iter().enumerate() {
-            if let Some(check_sql) = &col.check_expr {
-                if !values[i].is_null() {
-                    let check_expr = crate::sql::parser::parse_sql(&format!(
-                        "SELECT * FROM _dummy WHERE {}",
-                        check_sql
-                    ));
-                    if let Ok(Statement::Select(sel)) = check_expr {
-                        if let Some(where_expr) = &sel.where_clause {
-                            let result = eval_expr(where_expr, &|name| {
-                                table_def
-                                    .column_index(name)
-                                    .and_then(|idx| values.get(idx).cloned())
-                            })?;
-                            if !is_truthy(&result) {
-                                return Err(MuroError::Execution(format!(
-                                    "CHECK constraint failed for column '{}'",
-                                    col.name
-                                )));
-                            }
-                        }
-                    }
-                }
-            }
+    // REPLACE and ON DUPLICATE KEY UPDATE resolve conflicts row by row.
+    // Roots and the counter are written to the catalog once per statement
+    // (and before each row that reads the table back through a foreign key).
+    for value_row in &ins.values {
+        if self_referencing && rows_inserted > 0 {
+            catalog.update_table(pager, &table_def)?;
+            persist_indexes(catalog, pager, &indexes)?;
         }
-
-        enforce_child_foreign_keys(&table_def, &values, pager, catalog)?;
+        let values = prepare_insert_row(
+            &mut table_def,
+            &ins.columns,
+            value_row,
+            auto_pk_idx,
+            &mut counter_reconciled,
+            pager,
+            catalog,
+        )?;
 
         let pk_key = encode_pk_key(&table_def, &values);
 
@@ -150,6 +168,15 @@ pub(super) fn exec_insert(
                     pager,
                     catalog,
                 )?;
+                if self_referencing {
+                    // Cascades into the table itself recorded their own deletions.
+                    if let Some(current) = catalog.get_table(pager, &table_def.name)? {
+                        table_def.row_count = current.row_count;
+                    }
+                }
+                table_def.adjust_row_count(-(conflicts.len() as i64));
+                // MySQL counts each deleted row as well as the inserted one
+                rows_inserted += conflicts.len() as u64;
                 for (pk, existing_values) in conflicts {
                     delete_from_secondary_indexes(
                         &table_def,
@@ -160,8 +187,13 @@ pub(super) fn exec_insert(
                     )?;
                     data_btree.delete(pager, &pk)?;
                 }
-                data_btree = BTree::open(data_btree.root_page_id());
-            } else if let Some(ref assignments) = ins.on_duplicate_key_update {
+                data_btree =
+                    BTree::open(data_btree.root_page_id()).with_fill_factor(table_def.fill_factor);
+            } else {
+                let assignments = ins
+                    .on_duplicate_key_update
+                    .as_ref()
+                    .expect("plain INSERTs go through insert_prepared_rows");
                 // ON DUPLICATE KEY UPDATE: read original, apply updates, write back
                 let existing_data = data_btree.search(pager, &conflict_pk)?.unwrap();
                 let original_values = deserialize_row_versioned(
@@ -171,18 +203,28 @@ pub(super) fn exec_insert(
                 )?;
                 let mut updated_values = original_values.clone();
 
-                // Apply update assignments (expressions can reference current row values)
+                // Apply update assignments (expressions can reference current
+                // row values, and VALUES(col) the values of this INSERT row)
                 for (col_name, expr) in assignments {
                     let col_idx = table_def.column_index(col_name).ok_or_else(|| {
                         MuroError::Execution(format!("Unknown column: {}", col_name))
                     })?;
-                    let val = eval_expr(expr, &|name| {
+                    let mut expr = expr.clone();
+                    substitute_insert_values(&mut expr, &table_def, &values)?;
+                    let val = eval_expr(&expr, &|name| {
                         table_def
                             .column_index(name)
                             .and_then(|idx| updated_values.get(idx).cloned())
                     })?;
                     updated_values[col_idx] = val;
                 }
+                coerce_row(&table_def, &mut updated_values)?;
+
+                // MySQL reports 0 affected rows when the row keeps its values
+                if updated_values == original_values {
+                    continue;
+                }
+                validate_row(&table_def, &updated_values)?;
 
                 // Check unique constraints on updated values (excluding self)
                 // Must be done BEFORE deleting indexes to avoid inconsistency on error
@@ -215,7 +257,8 @@ pub(super) fn exec_insert(
                 // Update the data row (delete + insert)
                 let row_data = serialize_row(&updated_values, &table_def.columns);
                 data_btree.delete(pager, &conflict_pk)?;
-                data_btree = BTree::open(data_btree.root_page_id());
+                data_btree =
+                    BTree::open(data_btree.root_page_id()).with_fill_factor(table_def.fill_factor);
                 data_btree.insert(pager, &conflict_pk, &row_data)?;
 
                 // Insert new secondary index entries with updated values
@@ -227,29 +270,14 @@ pub(super) fn exec_insert(
                     pager,
                 )?;
 
-                // Update table_def
                 table_def.data_btree_root = data_btree.root_page_id();
-                catalog.update_table(pager, &table_def)?;
-                persist_indexes(catalog, pager, &indexes)?;
 
                 // MySQL reports 2 affected rows for ON DUPLICATE KEY UPDATE
                 rows_inserted += 2;
                 continue;
-            } else if pk_duplicate {
-                return Err(MuroError::UniqueViolation(
-                    "Duplicate primary key".to_string(),
-                ));
-            } else {
-                return Err(MuroError::UniqueViolation(
-                    "Duplicate value in unique index".to_string(),
-                ));
             }
         }
 
-        if !ins.is_replace {
-            check_unique_index_constraints(&table_def, &indexes, &values, pager)?;
-        }
-
         // Serialize row and insert into data B-tree
         let row_data = serialize_row(&values, &table_def.columns);
         data_btree.insert(pager, &pk_key, &row_data)?;
@@ -257,17 +285,455 @@ pub(super) fn exec_insert(
         // Update secondary indexes
         insert_into_secondary_indexes(&table_def, &mut indexes, &values, &pk_key, pager)?;
 
-        // Update table_def if btree root changed or next_rowid changed
         table_def.data_btree_root = data_btree.root_page_id();
-        catalog.update_table(pager, &table_def)?;
-        persist_indexes(catalog, pager, &indexes)?;
+        table_def.adjust_row_count(1);
 
         rows_inserted += 1;
     }
 
+    if rows_inserted > 0 {
+        catalog.update_table(pager, &table_def)?;
+        persist_indexes(catalog, pager, &indexes)?;
+    }
     Ok(ExecResult::RowsAffected(rows_inserted))
 }
 
+/// Insert prepared `rows` into a table that may already hold data. Primary
+/// and unique keys repeated among the rows are rejected before anything is
+/// written. Conflicts with stored rows come from the inserts themselves: the
+/// data tree and unique indexes take each key with
+/// [`BTree::insert_if_absent`], so no key is searched for separately. The
+/// row that conflicts is taken back out; earlier rows stay for the
+/// statement rollback to discard.
+fn insert_prepared_rows(
+    table_def: &mut TableDef,
+    indexes: &mut [IndexDef],
+    rows: &[Vec<Value>],
+    pager: &mut impl PageStore,
+) -> Result<()> {
+    let pk_keys: Vec<Vec<u8>> = rows
+        .iter()
+        .map(|values| encode_pk_key(table_def, values))
+        .collect();
+    if rows.len() > 1 {
+        let mut seen = HashSet::with_capacity(rows.len());
+        if !pk_keys.iter().all(|key| seen.insert(key.as_slice())) {
+            return Err(duplicate_primary_key());
+        }
+    }
+
+    // Each unique B-tree index's key for every row (None for NULL keys).
+    let mut unique_keys: Vec<Option<Vec<Option<Vec<u8>>>>> = Vec::with_capacity(indexes.len());
+    for idx in indexes.iter() {
+        if !idx.is_unique || idx.index_type != IndexType::BTree {
+            unique_keys.push(None);
+            continue;
+        }
+        let keys = rows
+            .iter()
+            .map(|values| Ok(index_key_for_row(table_def, idx, values)?.flatten()))
+            .collect::<Result<Vec<_>>>()?;
+        if rows.len() > 1 {
+            let mut seen = HashSet::with_capacity(rows.len());
+            if !keys.iter().flatten().all(|key| seen.insert(key.as_slice())) {
+                return Err(unique_violation(idx));
+            }
+        }
+        unique_keys.push(Some(keys));
+    }
+
+    let mut data_btree =
+        BTree::open(table_def.data_btree_root).with_fill_factor(table_def.fill_factor);
+    for (row, (values, pk_key)) in rows.iter().zip(&pk_keys).enumerate() {
+        let row_data = serialize_row(values, &table_def.columns);
+        if !data_btree.insert_if_absent(pager, pk_key, &row_data)? {
+            return Err(duplicate_primary_key());
+        }
+        // Unique entries go in before the other indexes, so a conflict only
+        // has this row's data and earlier unique entries to take back out.
+        let mut taken: Vec<usize> = Vec::new();
+        for (i, keys) in unique_keys.iter().enumerate() {
+            let Some(key) = keys.as_ref().and_then(|keys| keys[row].as_ref()) else {
+                continue;
+            };
+            let idx = &mut indexes[i];
+            let mut idx_btree = BTree::open(idx.btree_root).with_fill_factor(idx.fill_factor);
+            let inserted = idx_btree.insert_if_absent(pager, key, pk_key)?;
+            idx.btree_root = idx_btree.root_page_id();
+            if !inserted {
+                for &j in &taken {
+                    let key = unique_keys[j].as_ref().and_then(|keys| keys[row].as_ref());
+                    let mut idx_btree = BTree::open(indexes[j].btree_root);
+                    idx_btree.delete(pager, key.expect("taken keys are present"))?;
+                    indexes[j].btree_root = idx_btree.root_page_id();
+                }
+                data_btree.delete(pager, pk_key)?;
+                table_def.data_btree_root = data_btree.root_page_id();
+                return Err(unique_violation(&indexes[i]));
+            }
+            taken.push(i);
+        }
+        table_def.data_btree_root = data_btree.root_page_id();
+        for (idx, keys) in indexes.iter_mut().zip(&unique_keys) {
+            if keys.is_none() {
+                insert_into_secondary_indexes(
+                    table_def,
+                    std::slice::from_mut(idx),
+                    values,
+                    pk_key,
+                    pager,
+                )?;
+            }
+        }
+    }
+    table_def.adjust_row_count(rows.len() as i64);
+    Ok(())
+}
+
+fn duplicate_primary_key() -> MuroError {
+    MuroError::UniqueViolation("Duplicate primary key".to_string())
+}
+
+/// Evaluate one VALUES row into a full, validated row: defaults, generated
+/// AUTO_INCREMENT ids, NOT NULL, type coercion, CHECK constraints and
+/// foreign keys to other rows.
+fn prepare_insert_row(
+    table_def: &mut TableDef,
+    columns: &Option<Vec<String>>,
+    value_row: &[Expr],
+    auto_pk_idx: Option<usize>,
+    counter_reconciled: &mut bool,
+    pager: &mut impl PageStore,
+    catalog: &mut SystemCatalog,
+) -> Result<Vec<Value>> {
+    let mut values = resolve_insert_values(table_def, columns, value_row)?;
+
+    // Apply DEFAULT values for NULL columns that have defaults
+    for (i, col) in table_def.columns.iter().enumerate() {
+        if values[i].is_null() && !col.is_hidden && col.default_value.is_some() {
+            values[i] = eval_column_default(col)?;
+        }
+    }
+
+    // Auto-generate for AUTO_INCREMENT / hidden _rowid columns
+    if let Some(pk_idx) = auto_pk_idx {
+        if values[pk_idx].is_null() {
+            if !*counter_reconciled {
+                reconcile_auto_increment(table_def, pk_idx, pager)?;
+                *counter_reconciled = true;
+            }
+            table_def.next_rowid += 1;
+            values[pk_idx] = Value::Integer(table_def.next_rowid);
+            raise_auto_increment_current(&table_def.name, table_def.next_rowid);
+        }
+    }
+
+    // Coerce values to declared column types before validation/serialization.
+    coerce_row(table_def, &mut values)?;
+
+    // An explicit id past the counter moves the counter forward.
+    if let Some(pk_idx) = auto_pk_idx {
+        if let Value::Integer(n) = values[pk_idx] {
+            table_def.next_rowid = table_def.next_rowid.max(n);
+        }
+    }
+
+    validate_row(table_def, &values)?;
+    enforce_child_foreign_keys(table_def, &values, pager, catalog)?;
+    Ok(values)
+}
+
+/// Replace each `VALUES(col)` in an ON DUPLICATE KEY UPDATE assignment with
+/// the value the INSERT row tried to write into `col`.
+fn substitute_insert_values(expr: &mut Expr, table_def: &TableDef, values: &[Value]) -> Result<()> {
+    let substitute = |e: &mut Expr| substitute_insert_values(e, table_def, values);
+    match expr {
+        Expr::FunctionCall { name, args } if name.eq_ignore_ascii_case("VALUES") => {
+            let [Expr::ColumnRef(column)] = args.as_slice() else {
+                return Err(MuroError::Execution(
+                    "VALUES() takes a single column name".into(),
+                ));
+            };
+            let idx = table_def
+                .column_index(column)
+                .ok_or_else(|| MuroError::Execution(format!("Unknown column: {}", column)))?;
+            *expr = value_to_expr(&values[idx]);
+        }
+        Expr::FunctionCall { args, .. } => {
+            for arg in args {
+                substitute(arg)?;
+            }
+        }
+        Expr::BinaryOp { left, right, .. } => {
+            substitute(left)?;
+            substitute(right)?;
+        }
+        Expr::Like {
+            expr,
+            pattern,
+            escape,
+            ..
+        } => {
+            substitute(expr)?;
+            substitute(pattern)?;
+            if let Some(escape) = escape {
+                substitute(escape)?;
+            }
+        }
+        Expr::InList { expr, list, .. } => {
+            substitute(expr)?;
+            for item in list {
+                substitute(item)?;
+            }
+        }
+        Expr::Between {
+            expr, low, high, ..
+        } => {
+            substitute(expr)?;
+            substitute(low)?;
+            substitute(high)?;
+        }
+        Expr::UnaryOp { operand: expr, .. }
+        | Expr::IsNull { expr, .. }
+        | Expr::Cast { expr, .. }
+        | Expr::Collate { expr, .. }
+        | Expr::GreaterThanZero(expr)
+        | Expr::InSubquery { expr, .. } => substitute(expr)?,
+        Expr::CaseWhen {
+            operand,
+            when_clauses,
+            else_clause,
+        } => {
+            if let Some(operand) = operand {
+                substitute(operand)?;
+            }
+            for (when, then) in when_clauses {
+                substitute(when)?;
+                substitute(then)?;
+            }
+            if let Some(else_clause) = else_clause {
+                substitute(else_clause)?;
+            }
+        }
+        Expr::AggregateFunc { .. }
+        | Expr::BindParam
+        | Expr::IntLiteral(_)
+        | Expr::FloatLiteral(_)
+        | Expr::StringLiteral(_)
+        | Expr::BlobLiteral(_)
+        | Expr::Null
+        | Expr::DefaultValue
+        | Expr::ColumnRef(_)
+        | Expr::MatchAgainst { .. }
+        | Expr::FtsSnippet { .. }
+        | Expr::Exists { .. }
+        | Expr::ScalarSubquery(_) => {}
+    }
+    Ok(())
+}
+
+/// Coerce the non-NULL values of a full row to their declared column types.
+pub(super) fn coerce_row(table_def: &TableDef, values: &mut [Value]) -> Result<()> {
+    for (value, col) in values.iter_mut().zip(&table_def.columns) {
+        if !value.is_null() {
+            *value = coerce_value(value, col.data_type)?;
+        }
+    }
+    Ok(())
+}
+
+/// Check a full, coerced row against the column constraints: NOT NULL, the
+/// range and length of its type, and CHECK expressions. Every path that
+/// writes a row (INSERT, UPDATE, ON DUPLICATE KEY UPDATE, column rewrites of
+/// ALTER TABLE) runs it before touching the table or its indexes.
+pub(super) fn validate_row(table_def: &TableDef, values: &[Value]) -> Result<()> {
+    for (col, val) in table_def.columns.iter().zip(values) {
+        if val.is_null() {
+            if !col.is_nullable {
+                return Err(MuroError::ConstraintViolation(format!(
+                    "Column '{}' cannot be NULL",
+                    col.name
+                )));
+            }
+            continue;
+        }
+        validate_value(val, &col.data_type).map_err(|e| match e {
+            MuroError::Execution(msg) => {
+                MuroError::Execution(format!("{} for column '{}'", msg, col.name))
+            }
+            other => other,
+        })?;
+    }
+
+    // CHECK constraints pass on NULL, like in SQL.
+    for (col, val) in table_def.columns.iter().zip(values) {
+        let Some(check_sql) = &col.check_expr else {
+            continue;
+        };
+        if val.is_null() {
+            continue;
+        }
+        let check_expr =
+            crate::sql::parser::parse_sql(&format!("SELECT * FROM _dummy WHERE {}", check_sql));
+        if let Ok(Statement::Select(sel)) = check_expr {
+            if let Some(where_expr) = &sel.where_clause {
+                let collated = with_column_collations(where_expr, table_def);
+                let where_expr = collated.as_ref().unwrap_or(where_expr);
+                let result = eval_expr(where_expr, &|name| {
+                    table_def
+                        .column_index(name)
+                        .and_then(|idx| values.get(idx).cloned())
+                })?;
+                if !is_truthy(&result) {
+                    return Err(MuroError::ConstraintViolation(format!(
+                        "CHECK constraint failed for column '{}': CHECK ({})",
+                        col.name, check_sql
+                    )));
+                }
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Whether a plain multi-row INSERT goes to an empty table whose B-tree
+/// indexes are empty too, so that every tree can be bulk loaded.
+fn bulk_load_applies(
+    ins: &Insert,
+    table_def: &TableDef,
+    indexes: &[IndexDef],
+    pager: &mut impl PageStore,
+) -> Result<bool> {
+    if ins.values.len() < BULK_LOAD_MIN_ROWS
+        || ins.is_replace
+        || ins.on_duplicate_key_update.is_some()
+        || !BTree::open(table_def.data_btree_root).is_empty(pager)?
+    {
+        return Ok(false);
+    }
+    for idx in indexes {
+        if idx.index_type == IndexType::BTree && !BTree::open(idx.btree_root).is_empty(pager)? {
+            return Ok(false);
+        }
+    }
+    Ok(true)
+}
+
+/// Load prepared `rows` into the empty table: sort the encoded rows and the
+/// entries of each B-tree index, reject duplicate primary and unique keys
+/// among them, and bulk load every tree in place of its empty root.
+/// Full-text indexes are filled row by row.
+fn bulk_load_rows(
+    table_def: &mut TableDef,
+    indexes: &mut [IndexDef],
+    rows: &[Vec<Value>],
+    pager: &mut impl PageStore,
+) -> Result<()> {
+    let pk_keys: Vec<Vec<u8>> = rows
+        .iter()
+        .map(|values| encode_pk_key(table_def, values))
+        .collect();
+    let mut entries: Vec<(Vec<u8>, Vec<u8>)> = pk_keys
+        .iter()
+        .zip(rows)
+        .map(|(pk_key, values)| (pk_key.clone(), serialize_row(values, &table_def.columns)))
+        .collect();
+    entries.sort_unstable_by(|a, b| a.0.cmp(&b.0));
+    if entries.windows(2).any(|w| w[0].0 == w[1].0) {
+        return Err(duplicate_primary_key());
+    }
+
+    for idx in indexes.iter_mut() {
+        if idx.index_type == IndexType::Fulltext {
+            for (values, pk_key) in rows.iter().zip(&pk_keys) {
+                insert_into_secondary_indexes(
+                    table_def,
+                    std::slice::from_mut(idx),
+                    values,
+                    pk_key,
+                    pager,
+                )?;
+            }
+            continue;
+        }
+        let mut idx_entries = Vec::with_capacity(rows.len());
+        for (values, pk_key) in rows.iter().zip(&pk_keys) {
+            let Some(Some(mut idx_key)) = index_key_for_row(table_def, idx, values)? else {
+                continue;
+            };
+            if !idx.is_unique {
+                idx_key = non_unique_entry_key(table_def, &idx_key, pk_key);
+            }
+            idx_entries.push((idx_key, pk_key.clone()));
+        }
+        idx_entries.sort_unstable_by(|a, b| a.0.cmp(&b.0));
+        if idx_entries.windows(2).any(|w| w[0].0 == w[1].0) {
+            return Err(unique_violation(idx));
+        }
+        idx.btree_root =
+            bulk_load_empty_tree(pager, idx.btree_root, &idx_entries, idx.fill_factor)?;
+    }
+
+    table_def.data_btree_root = bulk_load_empty_tree(
+        pager,
+        table_def.data_btree_root,
+        &entries,
+        table_def.fill_factor,
+    )?;
+    Ok(())
+}
+
+/// Bulk load `entries` into a new tree at `fill_factor` and free the empty
+/// root it replaces.
+fn bulk_load_empty_tree(
+    pager: &mut impl PageStore,
+    empty_root: PageId,
+    entries: &[(Vec<u8>, Vec<u8>)],
+    fill_factor: u8,
+) -> Result<PageId> {
+    let loaded = BTree::bulk_load(pager, entries, empty_root, fill_factor)?;
+    pager.free_page(empty_root);
+    Ok(loaded.root_page_id())
+}
+
+/// Move the counter of `table_def` past every value this session has handed
+/// out, including ones whose transaction rolled back, and, the first time the
+/// session allocates for the table, past the largest key already stored. A
+/// counter persisted behind its data (e.g. by an older version) then never
+/// reissues a key.
+fn reconcile_auto_increment(
+    table_def: &mut TableDef,
+    pk_idx: usize,
+    pager: &mut impl PageStore,
+) -> Result<()> {
+    let floor = match auto_increment_high_water_current(&table_def.name) {
+        Some(high_water) => high_water,
+        None => {
+            let mut last = None;
+            BTree::open(table_def.data_btree_root).scan_rev(pager, |_, v| {
+                last = Some(v.to_vec());
+                Ok(false)
+            })?;
+            match last {
+                Some(row_data) => {
+                    let values = deserialize_row_versioned(
+                        &row_data,
+                        &table_def.columns,
+                        table_def.row_format_version,
+                    )?;
+                    match values.get(pk_idx) {
+                        Some(Value::Integer(n)) => *n,
+                        _ => 0,
+                    }
+                }
+                None => 0,
+            }
+        }
+    };
+    table_def.next_rowid = table_def.next_rowid.max(floor);
+    Ok(())
+}
+
 fn collect_replace_conflicts(
     table_def: &TableDef,
     indexes: &[IndexDef],
@@ -282,17 +748,9 @@ fn collect_replace_conflicts(
         if !idx.is_unique {
             continue;
         }
-        let col_indices: Vec<usize> = idx
-            .column_names
-            .iter()
-            .filter_map(|cn| table_def.column_index(cn))
-            .collect();
-        if col_indices.len() != idx.column_names.len() {
+        let Some(encoded) = index_key_for_row(table_def, idx, new_values)? else {
             continue;
-        }
-        let is_composite = idx.column_names.len() > 1;
-        let encoded =
-            encode_index_key_from_row(new_values, &col_indices, &table_def.columns, is_composite);
+        };
         if let Some(idx_key) = encoded {
             let idx_btree = BTree::open(idx.btree_root);
             if let Some(existing_pk_key) = idx_btree.search(pager, &idx_key)? {
@@ -365,7 +823,7 @@ fn validate_replace_child_foreign_keys_after_conflict_deletes(
         })?;
 
         if !exists {
-            return Err(MuroError::Execution(format!(
+            return Err(MuroError::ConstraintViolation(format!(
                 "FOREIGN KEY constraint fails: ({}) REFERENCES {}({})",
                 fk.columns.join(", "),
                 fk.ref_table,
@@ -0,0 +1,323 @@
+use super::*;
+use crate::sql::eval::{like_escape_char, like_tokens, LikeToken};
+use crate::types::DataType;
+use std::borrow::Cow;
+
+/// Rewrite top-level WHERE conjuncts of the form `col [NOT] LIKE 'literal'`
+/// into comparisons the planner can seek on:
+///
+/// - no wildcards: `col = 'lit'` (`col != 'lit'` for NOT LIKE)
+/// - `'lit%'`: `col >= 'lit' AND col < 'lim'`, the half-open range of
+///   strings starting with `lit`
+/// - only `%`: `col IS NOT NULL`
+/// - any other pattern after a literal prefix (`'lit_'`, `'lit%x'`): the
+///   prefix range, followed by the original LIKE as a residual filter
+///
+/// Only string columns with a literal pattern and escape are rewritten;
+/// patterns starting with a wildcard and NOT LIKE with wildcards stay as
+/// written. The rewrites keep which rows pass the filter: strings compare
+/// bytewise, which for UTF-8 is code point order, the same characters LIKE
+/// compares; on a collated column both sides compare collation keys.
+/// `LIKE '%'` on NULL is NULL rather than false, which no top-level
+/// conjunct can tell apart.
+///
+/// Returns the rewritten clause and a `before -> after` note per rewrite, or
+/// `None` when nothing was rewritten.
+pub(super) fn simplify_like_predicates(
+    where_clause: &Option<Expr>,
+    table_def: &TableDef,
+) -> Option<(Expr, Vec<String>)> {
+    let mut notes = Vec::new();
+    let simplified = simplify_conjuncts(where_clause.as_ref()?, table_def, &mut notes);
+    (!notes.is_empty()).then_some((simplified, notes))
+}
+
+/// `where_clause` with [`simplify_like_predicates`] applied.
+pub(super) fn like_simplified_where<'a>(
+    where_clause: &'a Option<Expr>,
+    table_def: &TableDef,
+) -> Cow<'a, Option<Expr>> {
+    match simplify_like_predicates(where_clause, table_def) {
+        Some((simplified, _)) => Cow::Owned(Some(simplified)),
+        None => Cow::Borrowed(where_clause),
+    }
+}
+
+fn simplify_conjuncts(expr: &Expr, table_def: &TableDef, notes: &mut Vec<String>) -> Expr {
+    match expr {
+        // A prefix range with its residual LIKE is already simplified.
+        Expr::BinaryOp {
+            op: BinaryOp::And,
+            right,
+            ..
+        } if matches!(right.as_ref(), Expr::Like { .. })
+            && simplify_like(right, table_def)
+                .is_some_and(|s| describe_conjunct(&s) == describe_conjunct(expr)) =>
+        {
+            expr.clone()
+        }
+        Expr::BinaryOp {
+            left,
+            op: BinaryOp::And,
+            right,
+        } => Expr::BinaryOp {
+            left: Box::new(simplify_conjuncts(left, table_def, notes)),
+            op: BinaryOp::And,
+            right: Box::new(simplify_conjuncts(right, table_def, notes)),
+        },
+        _ => match simplify_like(expr, table_def) {
+            Some(simplified) => {
+                notes.push(format!(
+                    "{} -> {}",
+                    describe_conjunct(expr),
+                    describe_conjunct(&simplified)
+                ));
+                simplified
+            }
+            None => expr.clone(),
+        },
+    }
+}
+
+fn simplify_like(expr: &Expr, table_def: &TableDef) -> Option<Expr> {
+    let Expr::Like {
+        expr: column,
+        pattern,
+        escape,
+        negated,
+    } = expr
+    else {
+        return None;
+    };
+    let Expr::ColumnRef(name) = column.as_ref() else {
+        return None;
+    };
+    let col = &table_def.columns[table_def.column_index(name)?];
+    if !matches!(col.data_type, DataType::Varchar(_) | DataType::Text) {
+        return None;
+    }
+    let Expr::StringLiteral(pattern) = pattern.as_ref() else {
+        return None;
+    };
+    let escape = match escape.as_deref() {
+        None => None,
+        // Invalid escapes are left for evaluation to report.
+        Some(Expr::StringLiteral(e)) => like_escape_char(&Value::Varchar(e.clone())).ok()?,
+        Some(_) => return None,
+    };
+    let tokens = like_tokens(pattern, escape);
+    let literal_len = tokens
+        .iter()
+        .take_while(|t| matches!(t, LikeToken::Literal(_)))
+        .count();
+    let prefix: String = tokens[..literal_len]
+        .iter()
+        .map(|t| match t {
+            LikeToken::Literal(c) => *c,
+            _ => unreachable!("literal prefix"),
+        })
+        .collect();
+    let rest = &tokens[literal_len..];
+    let compare = |op: BinaryOp, value: String| Expr::BinaryOp {
+        left: column.clone(),
+        op,
+        right: Box::new(Expr::StringLiteral(value)),
+    };
+
+    if rest.is_empty() {
+        let op = if *negated { BinaryOp::Ne } else { BinaryOp::Eq };
+        return Some(compare(op, prefix));
+    }
+    if *negated {
+        return None;
+    }
+    let needs_matching = !rest.iter().all(|t| *t == LikeToken::AnyRun);
+    if prefix.is_empty() {
+        if needs_matching {
+            return None;
+        }
+        return Some(Expr::IsNull {
+            expr: column.clone(),
+            negated: true,
+        });
+    }
+    let and = |left: Expr, right: Expr| Expr::BinaryOp {
+        left: Box::new(left),
+        op: BinaryOp::And,
+        right: Box::new(right),
+    };
+    // Under a collation the range covers the prefix's collation key, which
+    // is what both the comparisons and the index keys use.
+    let prefix = col.collation.fold(&prefix).into_owned();
+    let lower = compare(BinaryOp::Ge, prefix.clone());
+    let range = match prefix_successor(&prefix) {
+        Some(upper) => and(lower, compare(BinaryOp::Lt, upper)),
+        None => lower,
+    };
+    Some(if needs_matching {
+        and(range, expr.clone())
+    } else {
+        range
+    })
+}
+
+/// The least string greater than every string starting with `prefix`, or
+/// `None` if there is none (every character is `char::MAX`).
+fn prefix_successor(prefix: &str) -> Option<String> {
+    let mut chars: Vec<char> = prefix.chars().collect();
+    while let Some(last) = chars.pop() {
+        let next = match last {
+            '\u{D7FF}' => Some('\u{E000}'),
+            c => char::from_u32(c as u32 + 1),
+        };
+        if let Some(next) = next {
+            chars.push(next);
+            return Some(chars.into_iter().collect());
+        }
+    }
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn table() -> TableDef {
+        TableDef {
+            name: "t".into(),
+            columns: vec![
+                ColumnDef::new("id", DataType::BigInt),
+                ColumnDef::new("s", DataType::Varchar(None)),
+                ColumnDef::new("n", DataType::Int),
+            ],
+            pk_columns: vec!["id".into()],
+            data_btree_root: 0,
+            next_rowid: 0,
+            row_format_version: 1,
+            stats_row_count: 0,
+            foreign_keys: Vec::new(),
+            column_stats: Vec::new(),
+            row_count: None,
+            fill_factor: DEFAULT_FILL_FACTOR,
+            index_key_format: INDEX_KEY_FORMAT_TERMINATED,
+            trailing_fields: Vec::new(),
+        }
+    }
+
+    fn where_expr(sql: &str) -> Expr {
+        match parse_statement(&format!("SELECT * FROM t WHERE {}", sql)).unwrap() {
+            Statement::Select(sel) => sel.where_clause.unwrap(),
+            other => panic!("unexpected statement {:?}", other),
+        }
+    }
+
+    fn passes(expr: &Expr, s: &Option<&str>) -> bool {
+        let value = s.map_or(Value::Null, |s| Value::Varchar(s.to_string()));
+        let columns = |name: &str| (name == "s").then(|| value.clone());
+        is_truthy(&eval_expr(expr, &columns).unwrap())
+    }
+
+    #[test]
+    fn test_rewrites_match_unsimplified_evaluation() {
+        let table = table();
+        let values: Vec<Option<&str>> = vec![
+            None,
+            Some(""),
+            Some("a"),
+            Some("ab"),
+            Some("abc"),
+            Some("abd"),
+            Some("ac"),
+            Some("b"),
+            Some("a%"),
+            Some("a%c"),
+            Some("a_"),
+            Some("a\\"),
+            Some("a\u{D7FF}"),
+            Some("a\u{D7FF}x"),
+            Some("a\u{E000}"),
+            Some("\u{10FFFF}"),
+            Some("\u{10FFFF}z"),
+        ];
+        let cases = [
+            ("s LIKE 'ab'", "s = 'ab'"),
+            ("s NOT LIKE 'ab'", "s != 'ab'"),
+            ("s LIKE ''", "s = ''"),
+            ("s LIKE 'a%'", "s >= 'a' AND s < 'b'"),
+            ("s LIKE 'a%%'", "s >= 'a' AND s < 'b'"),
+            ("s LIKE '%'", "s IS NOT NULL"),
+            ("s LIKE 'a!%' ESCAPE '!'", "s = 'a%'"),
+            ("s LIKE 'a!%%' ESCAPE '!'", "s >= 'a%' AND s < 'a&'"),
+            ("s LIKE 'a!_' ESCAPE '!'", "s = 'a_'"),
+            ("s LIKE 'a\\' ESCAPE '\\'", "s = 'a\\'"),
+            ("s LIKE 'a%' ESCAPE ''", "s >= 'a' AND s < 'b'"),
+            (
+                "s LIKE 'a\u{D7FF}%'",
+                "s >= 'a\u{D7FF}' AND s < 'a\u{E000}'",
+            ),
+            ("s LIKE '\u{10FFFF}%'", "s >= '\u{10FFFF}'"),
+            ("s LIKE 'a_'", "s >= 'a' AND s < 'b' AND s LIKE 'a_'"),
+            ("s LIKE 'a%c'", "s >= 'a' AND s < 'b' AND s LIKE 'a%c'"),
+            (
+                "s LIKE 'a!%_' ESCAPE '!'",
+                "s >= 'a%' AND s < 'a&' AND s LIKE 'a!%_' ESCAPE '!'",
+            ),
+            (
+                "s LIKE '\u{10FFFF}_'",
+                "s >= '\u{10FFFF}' AND s LIKE '\u{10FFFF}_'",
+            ),
+        ];
+        for (like, expected) in cases {
+            let original = where_expr(like);
+            let (simplified, notes) =
+                simplify_like_predicates(&Some(original.clone()), &table).unwrap();
+            assert_eq!(notes.len(), 1, "{like}");
+            // A second pass leaves the result alone.
+            assert!(
+                simplify_like_predicates(&Some(simplified.clone()), &table).is_none(),
+                "{like}"
+            );
+            assert_eq!(
+                describe_conjunct(&simplified),
+                describe_conjunct(&where_expr(expected)),
+                "{like}"
+            );
+            for v in &values {
+                assert_eq!(
+                    passes(&simplified, v),
+                    passes(&original, v),
+                    "{like} on {v:?}"
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn test_patterns_without_literal_prefix_are_kept() {
+        let table = table();
+        for like in [
+            "s LIKE '%a'",
+            "s LIKE '_a%'",
+            "s NOT LIKE 'a%'",
+            "s NOT LIKE 'a_'",
+            "s NOT LIKE '%'",
+            "s LIKE 'a' ESCAPE 'xy'",
+            "s LIKE 'a' ESCAPE NULL",
+            "n LIKE '1'",
+            "s LIKE s",
+            "NOT s LIKE 'a' OR id = 1",
+        ] {
+            assert!(
+                simplify_like_predicates(&Some(where_expr(like)), &table).is_none(),
+                "{like}"
+            );
+        }
+    }
+
+    #[test]
+    fn test_prefix_successor() {
+        assert_eq!(prefix_successor("ab").as_deref(), Some("ac"));
+        assert_eq!(prefix_successor("a\u{10FFFF}").as_deref(), Some("b"));
+        assert_eq!(prefix_successor("\u{10FFFF}\u{10FFFF}"), None);
+    }
+}
@@ -13,151 +13,32 @@ pub(super) fn exec_update(
     ensure_row_format_v1(&mut table_def, pager, catalog)?;
 
     let mut indexes = catalog.get_indexes_for_table(pager, &upd.table_name)?;
-    let index_stats: Vec<IndexPlanStat> = indexes
-        .iter()
-        .filter(|idx| idx.index_type == IndexType::BTree)
-        .map(|idx| IndexPlanStat {
-            name: idx.name.clone(),
-            column_names: idx.column_names.clone(),
-            is_unique: idx.is_unique,
-            stats_distinct_keys: idx.stats_distinct_keys,
-            stats_num_min: idx.stats_num_bounds_known.then_some(idx.stats_num_min),
-            stats_num_max: idx.stats_num_bounds_known.then_some(idx.stats_num_max),
-            stats_num_hist_bins: idx.stats_num_hist_bins.clone(),
-        })
-        .collect();
-    let plan = plan_select_with_hints(
-        &upd.table_name,
-        &table_def.pk_columns,
-        &index_stats,
-        &upd.where_clause,
-        PlannerStats {
-            table_rows: table_def.stats_row_count,
-        },
-        &upd.index_hints,
-    );
-
-    let data_btree = BTree::open(table_def.data_btree_root);
+    let stats = table_planner_stats(&table_def, pager)?;
+    check_where_literal_types(&upd.where_clause, &table_def)?;
+    let where_clause = like_simplified_where(&upd.where_clause, &table_def);
+    let plan = plan_mutation(&table_def, &indexes, &where_clause, &upd.index_hints, stats);
 
-    // Collect rows to update (to avoid modifying during scan)
-    let mut to_update: Vec<(Vec<u8>, Vec<Value>)> = Vec::new();
-    match plan {
-        Plan::PkSeek { key_exprs, .. }
-            if key_exprs.iter().all(|(_, e)| is_row_independent_expr(e)) =>
-        {
-            let pk_key = eval_pk_seek_key(&table_def, &key_exprs)?;
-            if let Some(data) = data_btree.search(pager, &pk_key)? {
-                let values = deserialize_row_versioned(
-                    &data,
-                    &table_def.columns,
-                    table_def.row_format_version,
-                )?;
-                if matches_where(&upd.where_clause, &table_def, &values)? {
-                    to_update.push((pk_key, values));
-                }
-            }
-        }
-        Plan::IndexSeek {
-            index_name,
-            column_names,
-            key_exprs,
-            ..
-        } if key_exprs.iter().all(is_row_independent_expr) => {
-            let idx_key = eval_index_seek_key(&table_def, &column_names, &key_exprs)?;
-            let idx = indexes
-                .iter()
-                .find(|i| i.name == index_name)
-                .ok_or_else(|| MuroError::Execution(format!("Index '{}' not found", index_name)))?;
-            let pk_keys = index_seek_pk_keys(idx, &idx_key, pager)?;
-            for pk_key in pk_keys {
-                cancellation_point()?;
-                if let Some(data) = data_btree.search(pager, &pk_key)? {
-                    let values = deserialize_row_versioned(
-                        &data,
-                        &table_def.columns,
-                        table_def.row_format_version,
-                    )?;
-                    if matches_where(&upd.where_clause, &table_def, &values)? {
-                        to_update.push((pk_key, values));
-                    }
-                }
-            }
-        }
-        Plan::IndexRangeSeek {
-            index_name,
-            column_names,
-            prefix_key_exprs,
-            lower,
-            upper,
-            ..
-        } if prefix_key_exprs.iter().all(is_row_independent_expr)
-            && lower
-                .as_ref()
-                .is_none_or(|(expr, _)| is_row_independent_expr(expr.as_ref()))
-            && upper
-                .as_ref()
-                .is_none_or(|(expr, _)| is_row_independent_expr(expr.as_ref())) =>
-        {
-            let prefix_len = prefix_key_exprs.len();
-            let bound_columns = column_names[..prefix_len + 1].to_vec();
-            let lower_key = lower
-                .as_ref()
-                .map(|(expr, inclusive)| {
-                    let mut key_exprs = prefix_key_exprs.clone();
-                    key_exprs.push(*expr.clone());
-                    eval_index_seek_key(&table_def, &bound_columns, &key_exprs)
-                        .map(|key| (key, *inclusive))
-                })
-                .transpose()?;
-            let upper_key = upper
-                .as_ref()
-                .map(|(expr, inclusive)| {
-                    let mut key_exprs = prefix_key_exprs.clone();
-                    key_exprs.push(*expr.clone());
-                    eval_index_seek_key(&table_def, &bound_columns, &key_exprs)
-                        .map(|key| (key, *inclusive))
-                })
-                .transpose()?;
-            let idx = indexes
-                .iter()
-                .find(|i| i.name == index_name)
-                .ok_or_else(|| MuroError::Execution(format!("Index '{}' not found", index_name)))?;
-            let pk_keys = index_seek_pk_keys_range(idx, lower_key, upper_key, pager)?;
-            for pk_key in pk_keys {
-                cancellation_point()?;
-                if let Some(data) = data_btree.search(pager, &pk_key)? {
-                    let values = deserialize_row_versioned(
-                        &data,
-                        &table_def.columns,
-                        table_def.row_format_version,
-                    )?;
-                    if matches_where(&upd.where_clause, &table_def, &values)? {
-                        to_update.push((pk_key, values));
-                    }
-                }
-            }
-        }
-        Plan::PkSeek { .. }
-        | Plan::IndexSeek { .. }
-        | Plan::IndexRangeSeek { .. }
-        | Plan::FullScan { .. }
-        | Plan::FtsScan { .. } => {
-            data_btree.scan(pager, |k, v| {
-                cancellation_point()?;
-                let values =
-                    deserialize_row_versioned(v, &table_def.columns, table_def.row_format_version)?;
-                if matches_where(&upd.where_clause, &table_def, &values)? {
-                    to_update.push((k.to_vec(), values));
-                }
-                Ok(true)
-            })?;
-        }
-    }
+    // Candidates are collected under the old values before any assignment is
+    // applied, so a WHERE on an updated column still sees each row once.
+    let access_stage = mutation_access_stage(&plan, &table_def, &indexes, &stats, pager);
+    let to_update = collect_mutation_candidates(
+        &plan,
+        &table_def,
+        &indexes,
+        &where_clause,
+        upd.order_by.as_deref(),
+        upd.limit,
+        pager,
+    )?;
+    finish_stage(access_stage, pager, to_update.len());
 
-    let mut data_btree = BTree::open(table_def.data_btree_root);
+    let write_stage = start_stage(pager, "Update", &table_def.name, || None);
+    let mut data_btree =
+        BTree::open(table_def.data_btree_root).with_fill_factor(table_def.fill_factor);
     let mut count = 0u64;
 
     for (pk_key, old_values) in to_update {
+        cancellation_point()?;
         let mut new_values = old_values.clone();
 
         // Apply assignments
@@ -174,21 +55,17 @@ pub(super) fn exec_update(
         }
 
         // Coerce values to declared column types before index update/serialization.
-        for (i, col) in table_def.columns.iter().enumerate() {
-            if !new_values[i].is_null() {
-                new_values[i] = coerce_value(&new_values[i], col.data_type)?;
-            }
-        }
+        coerce_row(&table_def, &mut new_values)?;
+        validate_row(&table_def, &new_values)?;
 
-        // Validate all values against their column types
-        for (i, val) in new_values.iter().enumerate() {
-            if !val.is_null() {
-                validate_value(val, &table_def.columns[i].data_type)?;
-            }
-        }
-
-        // Check unique constraints on new values
-        check_unique_index_constraints(&table_def, &indexes, &new_values, pager)?;
+        // Check unique constraints on new values; the row's own entries don't conflict.
+        check_unique_index_constraints_excluding(
+            &table_def,
+            &indexes,
+            &new_values,
+            &pk_key,
+            pager,
+        )?;
         enforce_child_foreign_keys(&table_def, &new_values, pager, catalog)?;
         enforce_parent_restrict_on_update(
             &table_def,
@@ -209,6 +86,7 @@ pub(super) fn exec_update(
     }
 
     persist_indexes(catalog, pager, &indexes)?;
+    finish_stage(write_stage, pager, count as usize);
     Ok(ExecResult::RowsAffected(count))
 }
 
@@ -222,75 +100,157 @@ pub(super) fn exec_delete(
         .ok_or_else(|| MuroError::Schema(format!("Table '{}' not found", del.table_name)))?;
 
     let mut indexes = catalog.get_indexes_for_table(pager, &del.table_name)?;
-    let index_stats: Vec<IndexPlanStat> = indexes
-        .iter()
-        .filter(|idx| idx.index_type == IndexType::BTree)
-        .map(|idx| IndexPlanStat {
-            name: idx.name.clone(),
-            column_names: idx.column_names.clone(),
-            is_unique: idx.is_unique,
-            stats_distinct_keys: idx.stats_distinct_keys,
-            stats_num_min: idx.stats_num_bounds_known.then_some(idx.stats_num_min),
-            stats_num_max: idx.stats_num_bounds_known.then_some(idx.stats_num_max),
-            stats_num_hist_bins: idx.stats_num_hist_bins.clone(),
-        })
-        .collect();
+    let stats = table_planner_stats(&table_def, pager)?;
+    check_where_literal_types(&del.where_clause, &table_def)?;
+    let where_clause = like_simplified_where(&del.where_clause, &table_def);
+    let plan = plan_mutation(&table_def, &indexes, &where_clause, &del.index_hints, stats);
+    let access_stage = mutation_access_stage(&plan, &table_def, &indexes, &stats, pager);
+    let to_delete = collect_mutation_candidates(
+        &plan,
+        &table_def,
+        &indexes,
+        &where_clause,
+        del.order_by.as_deref(),
+        del.limit,
+        pager,
+    )?;
+    finish_stage(access_stage, pager, to_delete.len());
+
+    let write_stage = start_stage(pager, "Delete", &table_def.name, || None);
+    let mut data_btree = BTree::open(table_def.data_btree_root);
+    let mut count = 0u64;
+
+    let deleting_rows: Vec<Vec<Value>> =
+        to_delete.iter().map(|(_, values)| values.clone()).collect();
+    let deleting_pk_keys: Vec<Vec<u8>> = to_delete.iter().map(|(pk, _)| pk.clone()).collect();
+    enforce_parent_restrict_on_delete(
+        &table_def,
+        &deleting_rows,
+        &deleting_pk_keys,
+        pager,
+        catalog,
+    )?;
+
+    let mut removed = 0i64;
+    for (pk_key, values) in &to_delete {
+        cancellation_point()?;
+        delete_from_secondary_indexes(&table_def, &mut indexes, values, pk_key, pager)?;
+        if data_btree.delete(pager, pk_key)? {
+            removed += 1;
+        }
+        count += 1;
+    }
+
+    if removed > 0 {
+        // Re-read the definition: cascades into the table itself already
+        // recorded their own deletions.
+        if let Some(mut current) = catalog.get_table(pager, &table_def.name)? {
+            current.adjust_row_count(-removed);
+            catalog.update_table(pager, &current)?;
+        }
+    }
+    persist_indexes(catalog, pager, &indexes)?;
+    finish_stage(write_stage, pager, count as usize);
+    Ok(ExecResult::RowsAffected(count))
+}
+
+/// Choose how an UPDATE or DELETE finds its rows.
+///
+/// Uses the SELECT planner, then falls back to a full scan for plans the
+/// write path cannot execute: FULLTEXT plans, and seeks whose key depends on
+/// the row being examined. EXPLAIN UPDATE / DELETE reports this plan.
+pub(super) fn plan_mutation(
+    table_def: &TableDef,
+    indexes: &[IndexDef],
+    where_clause: &Option<Expr>,
+    index_hints: &[IndexHint],
+    stats: PlannerStats,
+) -> Plan {
     let plan = plan_select_with_hints(
-        &del.table_name,
+        &table_def.name,
         &table_def.pk_columns,
-        &index_stats,
-        &del.where_clause,
-        PlannerStats {
-            table_rows: table_def.stats_row_count,
-        },
-        &del.index_hints,
+        &index_plan_stats(table_def, indexes),
+        where_clause,
+        stats,
+        index_hints,
     );
+    let executable = match &plan {
+        Plan::PkSeek { key_exprs, .. } => key_exprs.iter().all(|(_, e)| is_row_independent_expr(e)),
+        Plan::IndexSeek { key_exprs, .. } => key_exprs.iter().all(is_row_independent_expr),
+        Plan::IndexRangeSeek {
+            prefix_key_exprs,
+            lower,
+            upper,
+            ..
+        } => {
+            prefix_key_exprs.iter().all(is_row_independent_expr)
+                && [lower, upper].iter().all(|bound| {
+                    bound
+                        .as_ref()
+                        .is_none_or(|(expr, _)| is_row_independent_expr(expr.as_ref()))
+                })
+        }
+        Plan::InListSeek { values, .. } => values.iter().all(is_row_independent_expr),
+        Plan::FullScan { .. } => true,
+        Plan::FtsScan { .. } => false,
+    };
+    if executable {
+        plan
+    } else {
+        Plan::FullScan {
+            table_name: table_def.name.clone(),
+        }
+    }
+}
 
-    let data_btree = BTree::open(table_def.data_btree_root);
+/// EXPLAIN ANALYZE stage for collecting the rows of an UPDATE or DELETE.
+fn mutation_access_stage(
+    plan: &Plan,
+    table_def: &TableDef,
+    indexes: &[IndexDef],
+    stats: &PlannerStats,
+    pager: &impl PageStore,
+) -> Option<Stage> {
+    start_stage(pager, plan_node_name(plan), &table_def.name, || {
+        let index_stats = index_plan_stats(table_def, indexes);
+        Some(estimate_plan_rows_hint(plan, stats, &index_stats))
+    })
+}
 
-    // Collect keys and row values to delete
-    let mut to_delete: Vec<(Vec<u8>, Vec<Value>)> = Vec::new();
-    match plan {
-        Plan::PkSeek { key_exprs, .. }
-            if key_exprs.iter().all(|(_, e)| is_row_independent_expr(e)) =>
-        {
-            let pk_key = eval_pk_seek_key(&table_def, &key_exprs)?;
-            if let Some(data) = data_btree.search(pager, &pk_key)? {
-                let values = deserialize_row_versioned(
-                    &data,
-                    &table_def.columns,
-                    table_def.row_format_version,
-                )?;
-                if matches_where(&del.where_clause, &table_def, &values)? {
-                    to_delete.push((pk_key, values));
-                }
-            }
-        }
+/// Collect the `(pk_key, row)` pairs an UPDATE or DELETE applies to, in the
+/// order they will be written.
+///
+/// Seeks only narrow the candidates: every fetched row is re-checked against
+/// the full WHERE clause, which also filters the extra rows a non-unique index
+/// prefix or range seek returns.
+fn collect_mutation_candidates(
+    plan: &Plan,
+    table_def: &TableDef,
+    indexes: &[IndexDef],
+    where_clause: &Option<Expr>,
+    order_by: Option<&[OrderByItem]>,
+    limit: Option<u64>,
+    pager: &mut impl PageStore,
+) -> Result<Vec<(Vec<u8>, Vec<Value>)>> {
+    let residual = residual_filter(where_clause, table_def);
+    let where_clause: &Option<Expr> = &residual;
+    let data_btree = BTree::open(table_def.data_btree_root);
+    let find_index = |name: &str| {
+        indexes
+            .iter()
+            .find(|i| i.name == name)
+            .ok_or_else(|| MuroError::Execution(format!("Index '{}' not found", name)))
+    };
+    let pk_keys = match plan {
+        Plan::PkSeek { key_exprs, .. } => vec![eval_pk_seek_key(table_def, key_exprs)?],
         Plan::IndexSeek {
             index_name,
             column_names,
             key_exprs,
             ..
-        } if key_exprs.iter().all(is_row_independent_expr) => {
-            let idx_key = eval_index_seek_key(&table_def, &column_names, &key_exprs)?;
-            let idx = indexes
-                .iter()
-                .find(|i| i.name == index_name)
-                .ok_or_else(|| MuroError::Execution(format!("Index '{}' not found", index_name)))?;
-            let pk_keys = index_seek_pk_keys(idx, &idx_key, pager)?;
-            for pk_key in pk_keys {
-                cancellation_point()?;
-                if let Some(data) = data_btree.search(pager, &pk_key)? {
-                    let values = deserialize_row_versioned(
-                        &data,
-                        &table_def.columns,
-                        table_def.row_format_version,
-                    )?;
-                    if matches_where(&del.where_clause, &table_def, &values)? {
-                        to_delete.push((pk_key, values));
-                    }
-                }
-            }
+        } => {
+            let idx_key = eval_index_seek_key(table_def, column_names, key_exprs)?;
+            index_seek_pk_keys(table_def, find_index(index_name)?, &idx_key, pager)?
         }
         Plan::IndexRangeSeek {
             index_name,
@@ -299,90 +259,136 @@ pub(super) fn exec_delete(
             lower,
             upper,
             ..
-        } if prefix_key_exprs.iter().all(is_row_independent_expr)
-            && lower
-                .as_ref()
-                .is_none_or(|(expr, _)| is_row_independent_expr(expr.as_ref()))
-            && upper
-                .as_ref()
-                .is_none_or(|(expr, _)| is_row_independent_expr(expr.as_ref())) =>
-        {
-            let prefix_len = prefix_key_exprs.len();
-            let bound_columns = column_names[..prefix_len + 1].to_vec();
-            let lower_key = lower
-                .as_ref()
-                .map(|(expr, inclusive)| {
-                    let mut key_exprs = prefix_key_exprs.clone();
-                    key_exprs.push(*expr.clone());
-                    eval_index_seek_key(&table_def, &bound_columns, &key_exprs)
-                        .map(|key| (key, *inclusive))
-                })
-                .transpose()?;
-            let upper_key = upper
-                .as_ref()
-                .map(|(expr, inclusive)| {
-                    let mut key_exprs = prefix_key_exprs.clone();
-                    key_exprs.push(*expr.clone());
-                    eval_index_seek_key(&table_def, &bound_columns, &key_exprs)
-                        .map(|key| (key, *inclusive))
-                })
-                .transpose()?;
-            let idx = indexes
-                .iter()
-                .find(|i| i.name == index_name)
-                .ok_or_else(|| MuroError::Execution(format!("Index '{}' not found", index_name)))?;
-            let pk_keys = index_seek_pk_keys_range(idx, lower_key, upper_key, pager)?;
-            for pk_key in pk_keys {
-                cancellation_point()?;
-                if let Some(data) = data_btree.search(pager, &pk_key)? {
-                    let values = deserialize_row_versioned(
-                        &data,
-                        &table_def.columns,
-                        table_def.row_format_version,
-                    )?;
-                    if matches_where(&del.where_clause, &table_def, &values)? {
-                        to_delete.push((pk_key, values));
-                    }
-                }
-            }
+        } => {
+            let bound_columns = &column_names[..prefix_key_exprs.len() + 1];
+            let bound_key = |bound: &Option<(Box<Expr>, bool)>, is_upper: bool| {
+                bound
+                    .as_ref()
+                    .map(|bound| {
+                        eval_index_range_bound(
+                            table_def,
+                            bound_columns,
+                            prefix_key_exprs,
+                            bound,
+                            is_upper,
+                        )
+                    })
+                    .transpose()
+            };
+            let (lower_key, upper_key) = (bound_key(lower, false)?, bound_key(upper, true)?);
+            index_seek_pk_keys_range(
+                table_def,
+                find_index(index_name)?,
+                lower_key,
+                upper_key,
+                pager,
+            )?
         }
-        Plan::PkSeek { .. }
-        | Plan::IndexSeek { .. }
-        | Plan::IndexRangeSeek { .. }
-        | Plan::FullScan { .. }
-        | Plan::FtsScan { .. } => {
+        Plan::InListSeek {
+            index_name,
+            column_name,
+            values,
+            ..
+        } => {
+            let idx = index_name.as_deref().map(find_index).transpose()?;
+            in_list_seek_pk_keys(table_def, idx, column_name, values, pager)?
+        }
+        Plan::FullScan { .. } | Plan::FtsScan { .. } => {
+            let mut candidates = Vec::new();
+            // A full scan visits rows in primary-key order, so without ORDER BY
+            // it can stop as soon as LIMIT candidates are collected.
+            let scan_limit = limit.filter(|_| order_by.is_none());
+            // The memo tests long literal IN lists by hash.
+            let mut memo = ExprMemo::new(where_clause.iter(), &|name| table_def.column_index(name));
             data_btree.scan(pager, |k, v| {
                 cancellation_point()?;
                 let values =
                     deserialize_row_versioned(v, &table_def.columns, table_def.row_format_version)?;
-                if matches_where(&del.where_clause, &table_def, &values)? {
-                    to_delete.push((k.to_vec(), values));
+                if matches_where_with_fts(where_clause, table_def, &values, None, &mut memo)? {
+                    candidates.push((k.to_vec(), values));
                 }
-                Ok(true)
+                Ok(scan_limit.is_none_or(|limit| (candidates.len() as u64) < limit))
             })?;
+            order_and_limit_candidates(&mut candidates, table_def, order_by, limit)?;
+            return Ok(candidates);
         }
-    }
+    };
 
-    let mut data_btree = BTree::open(table_def.data_btree_root);
-    let mut count = 0u64;
+    let mut candidates = Vec::new();
+    for chunk in pk_keys.chunks(ROW_FETCH_CHUNK) {
+        let fetched = data_btree.get_many(pager, chunk)?;
+        for (pk_key, data) in chunk.iter().zip(fetched) {
+            cancellation_point()?;
+            if let Some(data) = data {
+                let values = deserialize_row_versioned(
+                    &data,
+                    &table_def.columns,
+                    table_def.row_format_version,
+                )?;
+                if matches_where(where_clause, table_def, &values)? {
+                    candidates.push((pk_key.clone(), values));
+                }
+            }
+        }
+    }
+    order_and_limit_candidates(&mut candidates, table_def, order_by, limit)?;
+    Ok(candidates)
+}
 
-    let deleting_rows: Vec<Vec<Value>> =
-        to_delete.iter().map(|(_, values)| values.clone()).collect();
-    let deleting_pk_keys: Vec<Vec<u8>> = to_delete.iter().map(|(pk, _)| pk.clone()).collect();
-    enforce_parent_restrict_on_delete(
-        &table_def,
-        &deleting_rows,
-        &deleting_pk_keys,
-        pager,
-        catalog,
-    )?;
+/// Apply the `ORDER BY` / `LIMIT` of an UPDATE or DELETE to its candidate rows.
+///
+/// Candidates are first put in primary-key order so the rows picked for a
+/// limited batch do not depend on which access plan collected them; ORDER BY
+/// then sorts stably on top of that, so ties also resolve by primary key.
+fn order_and_limit_candidates(
+    candidates: &mut Vec<(Vec<u8>, Vec<Value>)>,
+    table_def: &TableDef,
+    order_by: Option<&[OrderByItem]>,
+    limit: Option<u64>,
+) -> Result<()> {
+    if order_by.is_none() && limit.is_none() {
+        return Ok(());
+    }
+    candidates.sort_by(|a, b| a.0.cmp(&b.0));
 
-    for (pk_key, values) in &to_delete {
-        delete_from_secondary_indexes(&table_def, &mut indexes, values, pk_key, pager)?;
-        data_btree.delete(pager, pk_key)?;
-        count += 1;
+    if let Some(items) = order_by {
+        let items = collated_order_by(items, table_def);
+        let mut keyed = Vec::with_capacity(candidates.len());
+        for (pk_key, values) in candidates.drain(..) {
+            let sort_keys = items
+                .iter()
+                .map(|item| {
+                    let value = eval_expr(&item.expr, &|name| {
+                        table_def
+                            .column_index(name)
+                            .and_then(|i| values.get(i).cloned())
+                    })?;
+                    Ok(match &item.expr {
+                        Expr::Collate { collation, .. } => collation.key_value(&value).into_owned(),
+                        _ => value,
+                    })
+                })
+                .collect::<Result<Vec<Value>>>()?;
+            keyed.push((sort_keys, pk_key, values));
+        }
+        keyed.sort_by(|a, b| {
+            for (i, item) in items.iter().enumerate() {
+                let ord = cmp_values(Some(&a.0[i]), Some(&b.0[i]));
+                if ord != std::cmp::Ordering::Equal {
+                    return if item.descending { ord.reverse() } else { ord };
+                }
+            }
+            std::cmp::Ordering::Equal
+        });
+        candidates.extend(
+            keyed
+                .into_iter()
+                .map(|(_, pk_key, values)| (pk_key, values)),
+        );
     }
 
-    persist_indexes(catalog, pager, &indexes)?;
-    Ok(ExecResult::RowsAffected(count))
+    if let Some(limit) = limit {
+        candidates.truncate(usize::try_from(limit).unwrap_or(usize::MAX));
+    }
+    Ok(())
 }
@@ -0,0 +1,53 @@
+use super::*;
+use crate::sql::session::fts_vacuum_batch_current;
+
+/// `OPTIMIZE TABLE`: rebuild the data tree and every B-tree index by bulk
+/// loading their entries into freshly allocated contiguous pages, then free
+/// the old pages. Full-text indexes keep their own page layout; their stale
+/// segment payloads are reclaimed instead, up to `fts_vacuum_batch` per index.
+pub(super) fn exec_optimize_table(
+    table_name: &str,
+    pager: &mut impl PageStore,
+    catalog: &mut SystemCatalog,
+) -> Result<ExecResult> {
+    let mut table_def = catalog
+        .get_table(pager, table_name)?
+        .ok_or_else(|| MuroError::Schema(format!("Table '{}' not found", table_name)))?;
+
+    table_def.data_btree_root =
+        rebuild_btree(pager, table_def.data_btree_root, table_def.fill_factor)?;
+    catalog.update_table(pager, &table_def)?;
+
+    for mut idx in catalog.get_indexes_for_table(pager, table_name)? {
+        if idx.index_type == IndexType::Fulltext {
+            let mut fts = FtsIndex::open(idx.btree_root, pager.fts_term_key()?);
+            fts.vacuum_stale_segments(pager, fts_vacuum_batch_current())?;
+            if fts.root_page_id() != idx.btree_root {
+                idx.btree_root = fts.root_page_id();
+                catalog.update_index(pager, &idx)?;
+            }
+            continue;
+        }
+        idx.btree_root = rebuild_btree(pager, idx.btree_root, idx.fill_factor)?;
+        catalog.update_index(pager, &idx)?;
+    }
+    Ok(ExecResult::Ok)
+}
+
+/// Copy the tree rooted at `root` into a tree bulk loaded at `fill_factor`
+/// and free the old pages. Returns the new root.
+fn rebuild_btree(pager: &mut impl PageStore, root: PageId, fill_factor: u8) -> Result<PageId> {
+    let old = BTree::open(root);
+    let mut entries = Vec::new();
+    old.scan(pager, |k, v| {
+        cancellation_point()?;
+        entries.push((k.to_vec(), v.to_vec()));
+        Ok(true)
+    })?;
+    let old_pages = old.collect_all_pages(pager)?;
+    let rebuilt = BTree::bulk_load(pager, &entries, root, fill_factor)?;
+    for page_id in old_pages {
+        pager.free_page(page_id);
+    }
+    Ok(rebuilt.root_page_id())
+}
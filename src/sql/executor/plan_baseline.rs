@@ -0,0 +1,306 @@
+use super::*;
+use crate::schema::plan_baseline::{PlanAccess, PlanBaseline, PlanDescription};
+use crate::sql::session::{
+    plan_baseline_current, record_plan_baseline_outcome_current, record_query_warning_current,
+    ScanWarning,
+};
+
+/// Shape of a single-table SELECT for plan baselines: the table, its index
+/// hints, and the WHERE clause with every literal replaced by `?` (a list of
+/// literals in `IN (...)` becomes one `?`). These are the planner's inputs, so
+/// statements that differ only in literal values, projection, ordering, or
+/// limit share a shape.
+pub(super) fn select_shape(table_name: &str, sel: &Select) -> String {
+    let mut shape = table_name.to_string();
+    for hint in &sel.index_hints {
+        let kind = match hint.hint_type {
+            IndexHintType::Force => "FORCE",
+            IndexHintType::Use => "USE",
+            IndexHintType::Ignore => "IGNORE",
+        };
+        shape.push_str(&format!(
+            " {} INDEX ({})",
+            kind,
+            hint.index_names.join(", ")
+        ));
+    }
+    if let Some(where_clause) = &sel.where_clause {
+        let mut normalized = where_clause.clone();
+        parameterize_literals(&mut normalized);
+        shape.push_str(" WHERE ");
+        shape.push_str(&describe_conjunct(&normalized));
+    }
+    shape
+}
+
+fn parameterize_literals(expr: &mut Expr) {
+    match expr {
+        Expr::IntLiteral(_)
+        | Expr::FloatLiteral(_)
+        | Expr::StringLiteral(_)
+        | Expr::BlobLiteral(_) => *expr = Expr::BindParam,
+        Expr::UnaryOp {
+            op: UnaryOp::Neg,
+            operand,
+        } if matches!(**operand, Expr::IntLiteral(_) | Expr::FloatLiteral(_)) => {
+            *expr = Expr::BindParam
+        }
+        Expr::MatchAgainst { query, .. } | Expr::FtsSnippet { query, .. } => {
+            *query = "?".to_string()
+        }
+        Expr::BinaryOp { left, right, .. } => {
+            parameterize_literals(left);
+            parameterize_literals(right);
+        }
+        Expr::UnaryOp { operand, .. } => parameterize_literals(operand),
+        Expr::Like {
+            expr,
+            pattern,
+            escape,
+            ..
+        } => {
+            parameterize_literals(expr);
+            parameterize_literals(pattern);
+            if let Some(escape) = escape {
+                parameterize_literals(escape);
+            }
+        }
+        Expr::InList { expr, list, .. } => {
+            parameterize_literals(expr);
+            list.iter_mut().for_each(parameterize_literals);
+            if list.iter().all(|item| matches!(item, Expr::BindParam)) {
+                list.truncate(1);
+            }
+        }
+        Expr::Between {
+            expr, low, high, ..
+        } => {
+            parameterize_literals(expr);
+            parameterize_literals(low);
+            parameterize_literals(high);
+        }
+        Expr::IsNull { expr, .. } | Expr::Cast { expr, .. } | Expr::Collate { expr, .. } => {
+            parameterize_literals(expr)
+        }
+        Expr::GreaterThanZero(inner) => parameterize_literals(inner),
+        Expr::FunctionCall { args, .. } => args.iter_mut().for_each(parameterize_literals),
+        Expr::CaseWhen {
+            operand,
+            when_clauses,
+            else_clause,
+        } => {
+            if let Some(operand) = operand {
+                parameterize_literals(operand);
+            }
+            for (when, then) in when_clauses {
+                parameterize_literals(when);
+                parameterize_literals(then);
+            }
+            if let Some(else_clause) = else_clause {
+                parameterize_literals(else_clause);
+            }
+        }
+        _ => {}
+    }
+}
+
+/// EXPLAIN's `table` / `type` / `key` of a single-table plan.
+pub(super) fn describe_plan(plan: &Plan, indexes: &[IndexDef]) -> PlanDescription {
+    let (table, access, index) = match plan {
+        Plan::PkSeek { table_name, .. } => (table_name, PlanAccess::Const, None),
+        Plan::IndexSeek {
+            table_name,
+            index_name,
+            ..
+        } => (table_name, PlanAccess::Ref, Some(index_name.clone())),
+        Plan::IndexRangeSeek {
+            table_name,
+            index_name,
+            ..
+        } => (table_name, PlanAccess::Range, Some(index_name.clone())),
+        Plan::InListSeek {
+            table_name,
+            index_name,
+            ..
+        } => (table_name, PlanAccess::Range, index_name.clone()),
+        Plan::FullScan { table_name } => (table_name, PlanAccess::All, None),
+        Plan::FtsScan {
+            table_name, column, ..
+        } => {
+            let index_name = indexes
+                .iter()
+                .find(|idx| {
+                    idx.index_type == IndexType::Fulltext
+                        && idx.column_names.first().map(|c| c.as_str()) == Some(column.as_str())
+                })
+                .map(|idx| idx.name.clone())
+                .unwrap_or_else(|| format!("fts_{}", column));
+            (table_name, PlanAccess::Fulltext, Some(index_name))
+        }
+    };
+    PlanDescription {
+        table: table.clone(),
+        access,
+        index,
+    }
+}
+
+/// Record the plan the planner currently picks for `sel`, ignoring any
+/// installed baseline.
+pub(crate) fn capture_plan_baseline(
+    sel: &Select,
+    pager: &mut impl PageStore,
+    catalog: &mut SystemCatalog,
+) -> Result<PlanBaseline> {
+    let table_name =
+        match &sel.table_name {
+            Some(name)
+                if sel.joins.is_empty()
+                    && !sel
+                        .where_clause
+                        .as_ref()
+                        .is_some_and(expr_contains_subquery)
+                    && !select_columns_contain_subquery(&sel.columns)
+                    && !sel.having.as_ref().is_some_and(expr_contains_subquery) =>
+            {
+                name
+            }
+            _ => return Err(MuroError::Execution(
+                "Plan baselines cover single-table SELECT statements without joins or subqueries"
+                    .into(),
+            )),
+        };
+    let table_def = catalog
+        .get_table(pager, table_name)?
+        .ok_or_else(|| MuroError::Schema(format!("Table '{}' not found", table_name)))?;
+    let simplified;
+    let sel = match simplify_like_predicates(&sel.where_clause, &table_def) {
+        Some((where_clause, _)) => {
+            simplified = Select {
+                where_clause: Some(where_clause),
+                ..sel.clone()
+            };
+            &simplified
+        }
+        None => sel,
+    };
+    let indexes = catalog.get_indexes_for_table(pager, table_name)?;
+    let plan = plan_select_with_hints(
+        table_name,
+        &table_def.pk_columns,
+        &index_plan_stats(&table_def, &indexes),
+        &sel.where_clause,
+        table_planner_stats(&table_def, pager)?,
+        &sel.index_hints,
+    );
+    Ok(PlanBaseline {
+        shape: select_shape(table_name, sel),
+        plan: describe_plan(&plan, &indexes),
+        table_version: table_def.semantics_version(),
+    })
+}
+
+/// The plan pinned by a baseline for the shape of `sel`, or `None` when the
+/// statement should be planned normally: baselines are disabled, none
+/// matches, or the matching one no longer applies (reported as a warning).
+pub(super) fn baseline_plan(
+    sel: &Select,
+    table_def: &TableDef,
+    indexes: &[IndexDef],
+    planner_stats: PlannerStats,
+    pager: &mut impl PageStore,
+    catalog: &SystemCatalog,
+) -> Result<Option<Plan>> {
+    let Some(baseline) = plan_baseline_current(
+        catalog.generation(),
+        || catalog.list_plan_baselines(pager),
+        || select_shape(&table_def.name, sel),
+    )?
+    else {
+        return Ok(None);
+    };
+
+    let invalid = if baseline.table_version != table_def.semantics_version() {
+        Some(format!(
+            "table '{}' changed since it was captured",
+            table_def.name
+        ))
+    } else {
+        baseline
+            .plan
+            .index
+            .as_ref()
+            .filter(|name| !indexes.iter().any(|idx| &idx.name == *name))
+            .map(|name| format!("index '{}' no longer exists", name))
+    };
+    let plan = match invalid {
+        Some(_) => None,
+        None => {
+            let hints = match (baseline.plan.access, &baseline.plan.index) {
+                (PlanAccess::Ref | PlanAccess::Range, Some(index)) => vec![IndexHint {
+                    hint_type: IndexHintType::Force,
+                    index_names: vec![index.clone()],
+                }],
+                (PlanAccess::All, _) => vec![IndexHint {
+                    hint_type: IndexHintType::Ignore,
+                    index_names: indexes.iter().map(|idx| idx.name.clone()).collect(),
+                }],
+                _ => sel.index_hints.clone(),
+            };
+            let plan = plan_select_with_hints(
+                &table_def.name,
+                &table_def.pk_columns,
+                &index_plan_stats(table_def, indexes),
+                &sel.where_clause,
+                planner_stats,
+                &hints,
+            );
+            (describe_plan(&plan, indexes) == baseline.plan).then_some(plan)
+        }
+    };
+    match plan {
+        Some(plan) => {
+            record_plan_baseline_outcome_current(true);
+            Ok(Some(plan))
+        }
+        None => {
+            record_plan_baseline_outcome_current(false);
+            let reason = invalid
+                .unwrap_or_else(|| format!("the statement can no longer use {}", baseline.plan));
+            record_query_warning_current(ScanWarning {
+                table: table_def.name.clone(),
+                page_id: None,
+                key_range: String::new(),
+                message: format!(
+                    "Plan baseline for '{}' ignored: {}; planned normally",
+                    baseline.shape, reason
+                ),
+            });
+            Ok(None)
+        }
+    }
+}
+
+pub(super) fn exec_install_plan_baseline(
+    baseline: &PlanBaseline,
+    pager: &mut impl PageStore,
+    catalog: &mut SystemCatalog,
+) -> Result<ExecResult> {
+    if catalog.get_table(pager, &baseline.plan.table)?.is_none() {
+        return Err(MuroError::Schema(format!(
+            "Table '{}' not found",
+            baseline.plan.table
+        )));
+    }
+    catalog.put_plan_baseline(pager, baseline)?;
+    Ok(ExecResult::Ok)
+}
+
+pub(super) fn exec_remove_plan_baseline(
+    shape: &str,
+    pager: &mut impl PageStore,
+    catalog: &mut SystemCatalog,
+) -> Result<ExecResult> {
+    let removed = catalog.delete_plan_baseline(pager, shape)?;
+    Ok(ExecResult::RowsAffected(removed as u64))
+}
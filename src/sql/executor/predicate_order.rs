@@ -0,0 +1,463 @@
+use super::*;
+use crate::sql::session::predicate_reorder_enabled_current;
+use std::borrow::Cow;
+
+/// Rough per-row cost of evaluating `expr`. Only the relative order matters:
+/// equality < range < LIKE < function call, and wider columns cost more to
+/// read and compare than integers.
+pub(super) fn conjunct_cost(expr: &Expr, table_def: &TableDef) -> u32 {
+    let cost = |e: &Expr| conjunct_cost(e, table_def);
+    match expr {
+        Expr::BindParam
+        | Expr::IntLiteral(_)
+        | Expr::FloatLiteral(_)
+        | Expr::StringLiteral(_)
+        | Expr::BlobLiteral(_)
+        | Expr::Null
+        | Expr::DefaultValue => 0,
+        Expr::ColumnRef(name) => table_def
+            .column_index(name)
+            .map_or(1, |i| column_cost(&table_def.columns[i].data_type)),
+        Expr::BinaryOp { left, op, right } => {
+            let op_cost = match op {
+                BinaryOp::Eq | BinaryOp::Ne => 1,
+                BinaryOp::Lt | BinaryOp::Le | BinaryOp::Gt | BinaryOp::Ge => 2,
+                BinaryOp::And | BinaryOp::Or => 0,
+                BinaryOp::Add | BinaryOp::Sub | BinaryOp::Mul | BinaryOp::Div | BinaryOp::Mod => 1,
+            };
+            op_cost + cost(left) + cost(right)
+        }
+        Expr::UnaryOp { operand, .. } => cost(operand),
+        Expr::IsNull { expr, .. } => 1 + cost(expr),
+        Expr::GreaterThanZero(expr) => 1 + cost(expr),
+        Expr::Between {
+            expr, low, high, ..
+        } => 3 + cost(expr) + cost(low) + cost(high),
+        Expr::InList { expr, list, .. } => 2 + cost(expr) + list.iter().map(cost).sum::<u32>(),
+        Expr::Like {
+            expr,
+            pattern,
+            escape,
+            ..
+        } => 16 + cost(expr) + cost(pattern) + escape.as_deref().map_or(0, cost),
+        Expr::Cast { expr, .. } => 4 + cost(expr),
+        Expr::Collate { expr, .. } => cost(expr),
+        Expr::CaseWhen {
+            operand,
+            when_clauses,
+            else_clause,
+        } => {
+            8 + operand.as_deref().map_or(0, cost)
+                + when_clauses
+                    .iter()
+                    .map(|(when, then)| cost(when) + cost(then))
+                    .sum::<u32>()
+                + else_clause.as_deref().map_or(0, cost)
+        }
+        Expr::FunctionCall { args, .. } => 32 + args.iter().map(cost).sum::<u32>(),
+        Expr::AggregateFunc { arg, .. } => 32 + arg.as_deref().map_or(0, cost),
+        Expr::MatchAgainst { .. } | Expr::FtsSnippet { .. } => 64,
+        Expr::InSubquery { .. } | Expr::Exists { .. } | Expr::ScalarSubquery(_) => 256,
+    }
+}
+
+fn column_cost(data_type: &DataType) -> u32 {
+    match data_type {
+        DataType::TinyInt
+        | DataType::SmallInt
+        | DataType::Int
+        | DataType::BigInt
+        | DataType::Date
+        | DataType::DateTime
+        | DataType::Timestamp => 1,
+        DataType::Float | DataType::Double | DataType::Decimal(..) | DataType::Uuid => 2,
+        DataType::Varchar(Some(n)) | DataType::Varbinary(Some(n)) if *n <= 64 => 2,
+        DataType::Varchar(_) | DataType::Varbinary(_) => 4,
+        DataType::Text | DataType::Jsonb => 8,
+    }
+}
+
+fn flatten_and<'a>(expr: &'a Expr, out: &mut Vec<&'a Expr>) {
+    match expr {
+        Expr::BinaryOp {
+            left,
+            op: BinaryOp::And,
+            right,
+        } => {
+            flatten_and(left, out);
+            flatten_and(right, out);
+        }
+        _ => out.push(expr),
+    }
+}
+
+/// Top-level AND conjuncts of `where_expr` with their costs, in evaluation
+/// order: cheapest first, ties in written order. With
+/// `SET predicate_reorder = 'off'` the written order is kept.
+pub(super) fn ordered_conjuncts<'a>(
+    where_expr: &'a Expr,
+    table_def: &TableDef,
+) -> Vec<(&'a Expr, u32)> {
+    let mut conjuncts = Vec::new();
+    flatten_and(where_expr, &mut conjuncts);
+    let mut ordered: Vec<(&Expr, u32)> = conjuncts
+        .into_iter()
+        .map(|e| (e, conjunct_cost(e, table_def)))
+        .collect();
+    if predicate_reorder_enabled_current() {
+        ordered.sort_by_key(|(_, cost)| *cost);
+    }
+    ordered
+}
+
+/// The per-row filter for a scan: the WHERE clause with its top-level AND
+/// chain rebuilt in evaluation order.
+///
+/// Reordering cannot change which rows match, since a row passes exactly
+/// when every conjunct is true. It can change which per-row errors surface:
+/// a conjunct that would fail is not evaluated once an earlier one is false.
+/// References to collated columns compare under the column's collation.
+pub(super) fn residual_filter<'a>(
+    where_clause: &'a Option<Expr>,
+    table_def: &TableDef,
+) -> Cow<'a, Option<Expr>> {
+    let ordered = ordered_filter(where_clause, table_def);
+    match ordered.as_ref() {
+        Some(expr) => match with_column_collations(expr, table_def) {
+            Some(collated) => Cow::Owned(Some(collated)),
+            None => ordered,
+        },
+        None => ordered,
+    }
+}
+
+fn ordered_filter<'a>(
+    where_clause: &'a Option<Expr>,
+    table_def: &TableDef,
+) -> Cow<'a, Option<Expr>> {
+    let Some(where_expr) = where_clause else {
+        return Cow::Borrowed(where_clause);
+    };
+    let ordered = ordered_conjuncts(where_expr, table_def);
+    let mut written = Vec::new();
+    flatten_and(where_expr, &mut written);
+    if ordered
+        .iter()
+        .zip(&written)
+        .all(|((e, _), w)| std::ptr::eq(*e, *w))
+    {
+        return Cow::Borrowed(where_clause);
+    }
+    let mut iter = ordered.into_iter().map(|(e, _)| e.clone());
+    let first = iter.next().expect("at least two conjuncts");
+    Cow::Owned(Some(iter.fold(first, |acc, next| Expr::BinaryOp {
+        left: Box::new(acc),
+        op: BinaryOp::And,
+        right: Box::new(next),
+    })))
+}
+
+/// Evaluate `expr` as a row filter with `eval`, stopping at the first false
+/// top-level conjunct. Equivalent to `is_truthy(eval(expr))`: AND is true
+/// exactly when both sides are, and NULL is not true.
+pub(super) fn filter_matches(
+    expr: &Expr,
+    eval: &mut dyn FnMut(&Expr) -> Result<Value>,
+) -> Result<bool> {
+    match expr {
+        Expr::BinaryOp {
+            left,
+            op: BinaryOp::And,
+            right,
+        } => Ok(filter_matches(left, eval)? && filter_matches(right, eval)?),
+        _ => Ok(is_truthy(&eval(expr)?)),
+    }
+}
+
+/// SQL-like text of a conjunct for EXPLAIN.
+pub(super) fn describe_conjunct(expr: &Expr) -> String {
+    let not = |negated: &bool| if *negated { "NOT " } else { "" };
+    match expr {
+        Expr::BindParam => "?".to_string(),
+        Expr::BlobLiteral(b) => format!(
+            "X'{}'",
+            b.iter()
+                .map(|byte| format!("{:02X}", byte))
+                .collect::<String>()
+        ),
+        Expr::BinaryOp { left, op, right } => format!(
+            "{} {} {}",
+            describe_operand(left),
+            binary_op_sql(*op),
+            describe_operand(right)
+        ),
+        Expr::UnaryOp {
+            op: UnaryOp::Not,
+            operand,
+        } => format!("NOT {}", describe_operand(operand)),
+        Expr::UnaryOp {
+            op: UnaryOp::Neg,
+            operand,
+        } => format!("-{}", describe_operand(operand)),
+        Expr::Like {
+            expr,
+            pattern,
+            escape,
+            negated,
+        } => {
+            let mut s = format!(
+                "{} {}LIKE {}",
+                describe_operand(expr),
+                not(negated),
+                describe_operand(pattern)
+            );
+            if let Some(e) = escape {
+                s.push_str(&format!(" ESCAPE {}", describe_operand(e)));
+            }
+            s
+        }
+        Expr::InList {
+            expr,
+            list,
+            negated,
+        } => format!(
+            "{} {}IN ({})",
+            describe_operand(expr),
+            not(negated),
+            list.iter()
+                .map(describe_conjunct)
+                .collect::<Vec<_>>()
+                .join(", ")
+        ),
+        Expr::Between {
+            expr,
+            low,
+            high,
+            negated,
+        } => format!(
+            "{} {}BETWEEN {} AND {}",
+            describe_operand(expr),
+            not(negated),
+            describe_operand(low),
+            describe_operand(high)
+        ),
+        Expr::IsNull { expr, negated } => {
+            format!("{} IS {}NULL", describe_operand(expr), not(negated))
+        }
+        Expr::FunctionCall { name, args } => format!(
+            "{}({})",
+            name.to_ascii_uppercase(),
+            args.iter()
+                .map(describe_conjunct)
+                .collect::<Vec<_>>()
+                .join(", ")
+        ),
+        Expr::Cast { expr, target_type } => {
+            format!("CAST({} AS {})", describe_conjunct(expr), target_type)
+        }
+        Expr::Collate { expr, collation } => {
+            format!("{} COLLATE {}", describe_operand(expr), collation)
+        }
+        Expr::MatchAgainst { column, query, .. } => {
+            format!("MATCH({}) AGAINST('{}')", column, query)
+        }
+        Expr::GreaterThanZero(expr) => format!("{} > 0", describe_operand(expr)),
+        Expr::CaseWhen { .. } => "CASE ... END".to_string(),
+        Expr::InSubquery { expr, negated, .. } => {
+            format!("{} {}IN (SELECT ...)", describe_operand(expr), not(negated))
+        }
+        Expr::Exists { negated, .. } => format!("{}EXISTS (SELECT ...)", not(negated)),
+        Expr::ScalarSubquery(_) => "(SELECT ...)".to_string(),
+        _ => expr_to_string(expr),
+    }
+}
+
+fn binary_op_sql(op: BinaryOp) -> &'static str {
+    match op {
+        BinaryOp::Eq => "=",
+        BinaryOp::Ne => "!=",
+        BinaryOp::Lt => "<",
+        BinaryOp::Gt => ">",
+        BinaryOp::Le => "<=",
+        BinaryOp::Ge => ">=",
+        BinaryOp::And => "AND",
+        BinaryOp::Or => "OR",
+        BinaryOp::Add => "+",
+        BinaryOp::Sub => "-",
+        BinaryOp::Mul => "*",
+        BinaryOp::Div => "/",
+        BinaryOp::Mod => "%",
+    }
+}
+
+fn describe_operand(expr: &Expr) -> String {
+    match expr {
+        Expr::BinaryOp { .. }
+        | Expr::Like { .. }
+        | Expr::Between { .. }
+        | Expr::InList { .. }
+        | Expr::IsNull { .. } => format!("({})", describe_conjunct(expr)),
+        _ => describe_conjunct(expr),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::sql::parser::parse_sql;
+    use rand::rngs::StdRng;
+    use rand::{Rng, SeedableRng};
+
+    fn table() -> TableDef {
+        TableDef {
+            name: "t".into(),
+            columns: vec![
+                ColumnDef::new("id", DataType::BigInt),
+                ColumnDef::new("n", DataType::Int),
+                ColumnDef::new("body", DataType::Text),
+                ColumnDef::new("tag", DataType::Varchar(Some(10))),
+            ],
+            pk_columns: vec!["id".into()],
+            data_btree_root: 0,
+            next_rowid: 0,
+            row_format_version: 1,
+            stats_row_count: 0,
+            foreign_keys: Vec::new(),
+            column_stats: Vec::new(),
+            row_count: None,
+            fill_factor: DEFAULT_FILL_FACTOR,
+            index_key_format: INDEX_KEY_FORMAT_TERMINATED,
+            trailing_fields: Vec::new(),
+        }
+    }
+
+    fn where_of(sql: &str) -> Expr {
+        match parse_sql(&format!("SELECT * FROM t WHERE {}", sql)).unwrap() {
+            Statement::Select(sel) => sel.where_clause.unwrap(),
+            other => panic!("unexpected {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_cost_classes_are_ordered() {
+        let t = table();
+        let cost = |sql: &str| conjunct_cost(&where_of(sql), &t);
+        assert!(cost("id = 1") < cost("id > 1"));
+        assert!(cost("id > 1") < cost("body LIKE '%x%'"));
+        assert!(cost("body LIKE '%x%'") < cost("LOWER(body) = 'x'"));
+        assert!(cost("n = 1") < cost("body = 'x'"));
+    }
+
+    #[test]
+    fn test_residual_filter_puts_cheap_conjuncts_first() {
+        let t = table();
+        let where_clause = Some(where_of(
+            "body LIKE '%x%' AND LENGTH(tag) > 2 AND id = 5 AND n < 3",
+        ));
+        let filter = residual_filter(&where_clause, &t);
+        let order: Vec<String> = ordered_conjuncts(filter.as_ref().as_ref().unwrap(), &t)
+            .into_iter()
+            .map(|(e, _)| describe_conjunct(e))
+            .collect();
+        assert_eq!(
+            order,
+            vec!["id = 5", "n < 3", "body LIKE '%x%'", "LENGTH(tag) > 2"]
+        );
+
+        // Already in order: no rewrite.
+        let sorted = Some(where_of("id = 5 AND body LIKE '%x%'"));
+        assert!(matches!(residual_filter(&sorted, &t), Cow::Borrowed(_)));
+    }
+
+    fn random_operand(rng: &mut StdRng) -> String {
+        match rng.gen_range(0..6) {
+            0 => "n".into(),
+            1 => "id".into(),
+            2 => "tag".into(),
+            3 => "NULL".into(),
+            4 => format!("{}", rng.gen_range(-2..4)),
+            _ => format!("'{}'", ["a", "b", "ab", ""][rng.gen_range(0..4)]),
+        }
+    }
+
+    fn random_predicate(rng: &mut StdRng, depth: u32) -> String {
+        let pick = if depth == 0 {
+            rng.gen_range(0..6)
+        } else {
+            rng.gen_range(0..9)
+        };
+        match pick {
+            0 => format!(
+                "{} {} {}",
+                random_operand(rng),
+                ["=", "!=", "<", ">=", "<=", ">"][rng.gen_range(0..6)],
+                random_operand(rng)
+            ),
+            1 => format!("tag LIKE '{}'", ["a%", "%b", "%", "_"][rng.gen_range(0..4)]),
+            2 => format!("{} IS NULL", random_operand(rng)),
+            3 => format!("n IN ({}, {})", random_operand(rng), random_operand(rng)),
+            4 => format!(
+                "n BETWEEN {} AND {}",
+                random_operand(rng),
+                random_operand(rng)
+            ),
+            5 => format!("LENGTH(tag) = {}", rng.gen_range(0..3)),
+            6 => format!(
+                "({} OR {})",
+                random_predicate(rng, depth - 1),
+                random_predicate(rng, depth - 1)
+            ),
+            7 => format!("NOT ({})", random_predicate(rng, depth - 1)),
+            _ => format!(
+                "({} AND {})",
+                random_predicate(rng, depth - 1),
+                random_predicate(rng, depth - 1)
+            ),
+        }
+    }
+
+    #[test]
+    fn test_reordered_filter_matches_unordered_evaluation() {
+        let t = table();
+        let mut rng = StdRng::seed_from_u64(0x0DE5);
+        let values = |rng: &mut StdRng| -> Vec<Value> {
+            let int = |rng: &mut StdRng| match rng.gen_range(0..5) {
+                0 => Value::Null,
+                n => Value::Integer(n as i64 - 2),
+            };
+            vec![
+                int(rng),
+                int(rng),
+                Value::Null,
+                match rng.gen_range(0..4) {
+                    0 => Value::Null,
+                    1 => Value::Varchar("a".into()),
+                    2 => Value::Varchar("ab".into()),
+                    _ => Value::Varchar(String::new()),
+                },
+            ]
+        };
+        let mut checked = 0;
+        for _ in 0..500 {
+            let conjuncts: Vec<String> = (0..rng.gen_range(2..6))
+                .map(|_| random_predicate(&mut rng, 2))
+                .collect();
+            let where_clause = Some(where_of(&conjuncts.join(" AND ")));
+            let filter = residual_filter(&where_clause, &t);
+            for _ in 0..8 {
+                let row = values(&mut rng);
+                let columns = |name: &str| t.column_index(name).map(|i| row[i].clone());
+                let Ok(expected) = eval_expr(where_clause.as_ref().unwrap(), &columns) else {
+                    continue;
+                };
+                let got = filter_matches(filter.as_ref().as_ref().unwrap(), &mut |e| {
+                    eval_expr(e, &columns)
+                })
+                .unwrap();
+                assert_eq!(got, is_truthy(&expected), "{}", conjuncts.join(" AND "));
+                checked += 1;
+            }
+        }
+        assert!(checked > 1000);
+    }
+}
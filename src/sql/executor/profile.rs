@@ -0,0 +1,159 @@
+use super::*;
+use std::cell::RefCell;
+use std::time::Instant;
+
+/// One executed plan stage, as reported by EXPLAIN ANALYZE.
+struct StageProfile {
+    node: &'static str,
+    table: String,
+    estimated_rows: Option<u64>,
+    actual_rows: u64,
+    pages_read: u64,
+    elapsed_us: u64,
+}
+
+thread_local! {
+    /// Stages finished so far by the statement under EXPLAIN ANALYZE;
+    /// `None` when nothing is being profiled.
+    static ACTIVE_PROFILE: RefCell<Option<Vec<StageProfile>>> = const { RefCell::new(None) };
+}
+
+/// A stage that has started under profiling.
+pub(super) struct Stage {
+    node: &'static str,
+    table: String,
+    estimated_rows: Option<u64>,
+    started_at: Instant,
+    cache_counters: (u64, u64),
+}
+
+fn profiling() -> bool {
+    ACTIVE_PROFILE.with(|slot| slot.borrow().is_some())
+}
+
+/// Start timing a plan stage. Returns `None`, doing no work (not even the
+/// estimate), unless the statement runs under EXPLAIN ANALYZE.
+pub(super) fn start_stage(
+    pager: &impl PageStore,
+    node: &'static str,
+    table: &str,
+    estimated_rows: impl FnOnce() -> Option<u64>,
+) -> Option<Stage> {
+    if !profiling() {
+        return None;
+    }
+    Some(Stage {
+        node,
+        table: table.to_string(),
+        estimated_rows: estimated_rows(),
+        started_at: Instant::now(),
+        cache_counters: pager.cache_counters(),
+    })
+}
+
+/// Record a stage started by [`start_stage`] as having produced `actual_rows`.
+pub(super) fn finish_stage(stage: Option<Stage>, pager: &impl PageStore, actual_rows: usize) {
+    let Some(stage) = stage else {
+        return;
+    };
+    let (hits, misses) = pager.cache_counters();
+    let pages_read =
+        hits.saturating_sub(stage.cache_counters.0) + misses.saturating_sub(stage.cache_counters.1);
+    let profile = StageProfile {
+        node: stage.node,
+        table: stage.table,
+        estimated_rows: stage.estimated_rows,
+        actual_rows: actual_rows as u64,
+        pages_read,
+        elapsed_us: stage.started_at.elapsed().as_micros() as u64,
+    };
+    ACTIVE_PROFILE.with(|slot| {
+        if let Some(stages) = slot.borrow_mut().as_mut() {
+            stages.push(profile);
+        }
+    });
+}
+
+/// EXPLAIN ANALYZE node name of an access plan.
+pub(super) fn plan_node_name(plan: &Plan) -> &'static str {
+    match plan {
+        Plan::PkSeek { .. } => "PkSeek",
+        Plan::IndexSeek { .. } => "IndexSeek",
+        Plan::IndexRangeSeek { .. } => "IndexRangeSeek",
+        Plan::InListSeek { .. } => "IndexSeek (IN)",
+        Plan::FullScan { .. } => "FullScan",
+        Plan::FtsScan { .. } => "FtsScan",
+    }
+}
+
+/// Estimate for a full scan: the row count from the last ANALYZE TABLE.
+pub(super) fn stats_rows_hint(table_def: &TableDef) -> Option<u64> {
+    (table_def.stats_row_count > 0).then_some(table_def.stats_row_count)
+}
+
+/// Clears the profile slot even if the profiled statement fails.
+struct ProfileGuard;
+
+impl Drop for ProfileGuard {
+    fn drop(&mut self) {
+        ACTIVE_PROFILE.with(|slot| *slot.borrow_mut() = None);
+    }
+}
+
+/// `EXPLAIN ANALYZE <stmt>`: execute the statement and report one row per
+/// finished plan stage, innermost first.
+///
+/// Writes are really executed here; `Session` runs EXPLAIN ANALYZE of an
+/// UPDATE or DELETE in a transaction it rolls back afterwards.
+pub(super) fn exec_explain_analyze(
+    stmt: &Statement,
+    pager: &mut impl PageStore,
+    catalog: &mut SystemCatalog,
+) -> Result<ExecResult> {
+    if !matches!(
+        stmt,
+        Statement::Select(_) | Statement::Update(_) | Statement::Delete(_)
+    ) {
+        return Err(MuroError::Execution(
+            "EXPLAIN ANALYZE supports SELECT, UPDATE, and DELETE statements".into(),
+        ));
+    }
+
+    let _guard = ProfileGuard;
+    ACTIVE_PROFILE.with(|slot| *slot.borrow_mut() = Some(Vec::new()));
+    execute_statement(stmt, pager, catalog)?;
+    let stages = ACTIVE_PROFILE
+        .with(|slot| slot.borrow_mut().take())
+        .unwrap_or_default();
+
+    let rows = stages
+        .into_iter()
+        .enumerate()
+        .map(|(i, stage)| Row {
+            values: vec![
+                ("id".to_string(), Value::Integer(i as i64 + 1)),
+                ("node".to_string(), Value::Varchar(stage.node.to_string())),
+                ("table".to_string(), Value::Varchar(stage.table)),
+                (
+                    "estimated_rows".to_string(),
+                    stage
+                        .estimated_rows
+                        .map_or(Value::Null, |rows| Value::Integer(rows as i64)),
+                ),
+                (
+                    "actual_rows".to_string(),
+                    Value::Integer(stage.actual_rows as i64),
+                ),
+                (
+                    "pages_read".to_string(),
+                    Value::Integer(stage.pages_read as i64),
+                ),
+                (
+                    "elapsed_us".to_string(),
+                    Value::Integer(stage.elapsed_us as i64),
+                ),
+            ],
+        })
+        .collect();
+    Ok(ExecResult::Rows(rows))
+}
@@ -0,0 +1,160 @@
+use super::*;
+use crate::sql::eval::{cast_value, is_truthy};
+
+/// Build a value from a result row; see [`Row::map_into`].
+///
+/// ```
+/// use murodb::{Database, FromRow, Result, Row};
+///
+/// struct User {
+///     id: i64,
+///     name: Option<String>,
+/// }
+///
+/// impl FromRow for User {
+///     fn from_row(row: &Row) -> Result<Self> {
+///         Ok(User {
+///             id: row.get_i64("id")?.unwrap_or_default(),
+///             name: row.get_string("name")?,
+///         })
+///     }
+/// }
+///
+/// let dir = tempfile::TempDir::new().unwrap();
+/// let mut db = Database::create_plaintext(&dir.path().join("app.db")).unwrap();
+/// db.execute("CREATE TABLE users (id BIGINT PRIMARY KEY, name VARCHAR(20))").unwrap();
+/// db.execute("INSERT INTO users VALUES (1, 'ann'), (2, NULL)").unwrap();
+/// let users: Vec<User> = db
+///     .query("SELECT id, name FROM users ORDER BY id")
+///     .unwrap()
+///     .iter()
+///     .map(Row::map_into)
+///     .collect::<Result<_>>()
+///     .unwrap();
+/// assert_eq!(users[0].name.as_deref(), Some("ann"));
+/// assert_eq!((users[1].id, users[1].name.as_deref()), (2, None));
+/// ```
+pub trait FromRow: Sized {
+    fn from_row(row: &Row) -> Result<Self>;
+}
+
+/// Typed getters tell a column that is not in the result (an error) from
+/// one that is NULL (`Ok(None)`). Numeric and text getters convert like
+/// `CAST`, so `get_i64` reads a DECIMAL or a numeric string as
+/// `CAST(col AS BIGINT)` would, and fails where that `CAST` fails.
+impl Row {
+    /// The value of the first column named `name`, or `None` when the result
+    /// has no such column.
+    pub fn get(&self, name: &str) -> Option<&Value> {
+        self.values.iter().find(|(n, _)| n == name).map(|(_, v)| v)
+    }
+
+    /// The value at position `index`, in SELECT list order. Unlike
+    /// [`Row::get`], this reaches every column when names repeat, as in a
+    /// join selecting `a.id, b.id`.
+    pub fn get_at(&self, index: usize) -> Option<&Value> {
+        self.values.get(index).map(|(_, v)| v)
+    }
+
+    /// Column names in SELECT list order.
+    pub fn columns(&self) -> Vec<&str> {
+        self.values.iter().map(|(n, _)| n.as_str()).collect()
+    }
+
+    /// Integer value of `name`, converted like `CAST(name AS BIGINT)`:
+    /// floats and decimals are truncated, strings are parsed.
+    pub fn get_i64(&self, name: &str) -> Result<Option<i64>> {
+        match self.cast(name, &DataType::BigInt, "an integer")? {
+            Some(Value::Integer(n)) => Ok(Some(n)),
+            _ => Ok(None),
+        }
+    }
+
+    /// Float value of `name`, converted like `CAST(name AS DOUBLE)`.
+    pub fn get_f64(&self, name: &str) -> Result<Option<f64>> {
+        match self.cast(name, &DataType::Double, "a float")? {
+            Some(Value::Float(n)) => Ok(Some(n)),
+            _ => Ok(None),
+        }
+    }
+
+    /// Boolean value of `name`. Numbers are true when non-zero, as in a
+    /// `WHERE` clause; strings may be `true` / `false` in any case or an
+    /// integer.
+    pub fn get_bool(&self, name: &str) -> Result<Option<bool>> {
+        let value = self.require_column(name)?;
+        match value {
+            Value::Null => Ok(None),
+            Value::Integer(_) | Value::Float(_) | Value::Decimal(_) => Ok(Some(is_truthy(value))),
+            Value::Varchar(s) if s.trim().eq_ignore_ascii_case("true") => Ok(Some(true)),
+            Value::Varchar(s) if s.trim().eq_ignore_ascii_case("false") => Ok(Some(false)),
+            Value::Varchar(_) => Ok(self.get_i64(name)?.map(|n| n != 0)),
+            _ => Err(read_error(name, "a boolean", value)),
+        }
+    }
+
+    /// String value of `name`, borrowed from the row. Only VARCHAR, TEXT
+    /// and JSONB values are strings; use [`Row::get_string`] to format
+    /// other types.
+    pub fn get_str(&self, name: &str) -> Result<Option<&str>> {
+        match self.require_column(name)? {
+            Value::Null => Ok(None),
+            Value::Varchar(s) => Ok(Some(s)),
+            other => Err(read_error(name, "a string", other)),
+        }
+    }
+
+    /// Value of `name` as text, converted like `CAST(name AS TEXT)`: dates,
+    /// UUIDs and numbers are formatted as SQL prints them.
+    pub fn get_string(&self, name: &str) -> Result<Option<String>> {
+        match self.cast(name, &DataType::Text, "a string")? {
+            Some(Value::Varchar(s)) => Ok(Some(s)),
+            _ => Ok(None),
+        }
+    }
+
+    /// Bytes of `name`, borrowed from the row: VARBINARY as stored, strings
+    /// as UTF-8 and UUIDs as their 16 bytes, as `CAST(name AS VARBINARY)`.
+    pub fn get_bytes(&self, name: &str) -> Result<Option<&[u8]>> {
+        match self.require_column(name)? {
+            Value::Null => Ok(None),
+            Value::Varbinary(b) => Ok(Some(b)),
+            Value::Varchar(s) => Ok(Some(s.as_bytes())),
+            Value::Uuid(b) => Ok(Some(b)),
+            other => Err(read_error(name, "bytes", other)),
+        }
+    }
+
+    /// Build a `T` from this row.
+    pub fn map_into<T: FromRow>(&self) -> Result<T> {
+        T::from_row(self)
+    }
+
+    fn require_column(&self, name: &str) -> Result<&Value> {
+        self.get(name).ok_or_else(|| {
+            MuroError::Execution(format!(
+                "Column '{}' is not in the result; columns: {}",
+                name,
+                self.columns().join(", ")
+            ))
+        })
+    }
+
+    /// `CAST` the value of `name` to `target`; `None` for NULL.
+    fn cast(&self, name: &str, target: &DataType, what: &str) -> Result<Option<Value>> {
+        let value = self.require_column(name)?;
+        if value.is_null() {
+            return Ok(None);
+        }
+        cast_value(value, target)
+            .map(Some)
+            .map_err(|_| read_error(name, what, value))
+    }
+}
+
+fn read_error(name: &str, what: &str, value: &Value) -> MuroError {
+    MuroError::Execution(format!(
+        "Cannot read column '{}' as {}: {:?}",
+        name, what, value
+    ))
+}
@@ -1,4 +1,5 @@
 use super::*;
+use crate::sql::session::forget_auto_increment_current;
 
 pub(super) fn exec_rename_table(
     rt: &RenameTable,
@@ -6,6 +7,8 @@ pub(super) fn exec_rename_table(
     catalog: &mut SystemCatalog,
 ) -> Result<ExecResult> {
     catalog.rename_table(pager, &rt.old_name, &rt.new_name)?;
+    forget_auto_increment_current(&rt.old_name);
+    forget_auto_increment_current(&rt.new_name);
 
     // Rewrite all FOREIGN KEY references that point to the old table name.
     for table_name in catalog.list_tables(pager)? {
@@ -0,0 +1,94 @@
+use super::check::short_hex;
+use super::*;
+use crate::btree::salvage::SkippedRange;
+use crate::sql::session::{record_scan_warning_current, scan_skip_corruption_current, ScanWarning};
+
+/// Full scan of a table's decoded rows in primary-key order (descending when
+/// `reverse`). `visit` returns false to stop early.
+///
+/// Under `scan_corruption_policy = 'skip'`, unreadable pages and rows that
+/// fail to decode are added to the statement's warnings and skipped;
+/// otherwise the first failure aborts the scan.
+pub(super) fn scan_table_rows<F>(
+    table_def: &TableDef,
+    pager: &mut impl PageStore,
+    reverse: bool,
+    visit: F,
+) -> Result<()>
+where
+    F: FnMut(&[u8], Vec<Value>) -> Result<bool>,
+{
+    scan_table_columns(table_def, pager, reverse, None, visit)
+}
+
+/// [`scan_table_rows`] decoding only the columns at positions `wanted`
+/// (all of them for `None`); the others read as NULL.
+pub(super) fn scan_table_columns<F>(
+    table_def: &TableDef,
+    pager: &mut impl PageStore,
+    reverse: bool,
+    wanted: Option<&[usize]>,
+    mut visit: F,
+) -> Result<()>
+where
+    F: FnMut(&[u8], Vec<Value>) -> Result<bool>,
+{
+    let data_btree = BTree::open(table_def.data_btree_root);
+    let decode = |v: &[u8]| match wanted {
+        Some(wanted) => {
+            deserialize_columns(v, &table_def.columns, table_def.row_format_version, wanted)
+        }
+        None => deserialize_row_versioned(v, &table_def.columns, table_def.row_format_version),
+    };
+    if !scan_skip_corruption_current() {
+        let strict = |k: &[u8], v: &[u8]| visit(k, decode(v)?);
+        return if reverse {
+            data_btree.scan_rev(pager, strict)
+        } else {
+            data_btree.scan(pager, strict)
+        };
+    }
+
+    let mut bad_rows = Vec::new();
+    let skipped = data_btree.scan_salvage(pager, reverse, |k, v| match decode(v) {
+        Ok(values) => visit(k, values),
+        Err(e) => {
+            bad_rows.push(ScanWarning {
+                table: table_def.name.clone(),
+                page_id: None,
+                key_range: short_hex(k),
+                message: e.to_string(),
+            });
+            Ok(true)
+        }
+    })?;
+    for range in skipped {
+        record_scan_warning_current(page_warning(&table_def.name, range));
+    }
+    for warning in bad_rows {
+        record_scan_warning_current(warning);
+    }
+    Ok(())
+}
+
+fn page_warning(table: &str, range: SkippedRange) -> ScanWarning {
+    let bound = |b: &Option<Vec<u8>>, unbounded: &str| {
+        b.as_deref()
+            .map_or_else(|| unbounded.to_string(), short_hex)
+    };
+    let key_range = if range.lower.is_some() && range.lower == range.upper {
+        bound(&range.lower, "")
+    } else {
+        format!(
+            "[{}, {})",
+            bound(&range.lower, "-inf"),
+            bound(&range.upper, "+inf")
+        )
+    };
+    ScanWarning {
+        table: table.to_string(),
+        page_id: Some(range.page_id),
+        key_range,
+        message: range.error,
+    }
+}
@@ -7,12 +7,9 @@ pub(super) fn scan_table_qualified(
     pager: &mut impl PageStore,
 ) -> Result<Vec<Vec<(String, Value)>>> {
     let qualifier = alias.unwrap_or(table_name);
-    let data_btree = BTree::open(table_def.data_btree_root);
     let mut result = Vec::new();
-    data_btree.scan(pager, |_k, v| {
+    scan_table_rows(table_def, pager, false, |_k, values| {
         cancellation_point()?;
-        let values =
-            deserialize_row_versioned(v, &table_def.columns, table_def.row_format_version)?;
         let mut row: Vec<(String, Value)> = Vec::with_capacity(table_def.columns.len());
         for (i, col) in table_def.columns.iter().enumerate() {
             let val = values.get(i).cloned().unwrap_or(Value::Null);
@@ -39,23 +36,26 @@ pub(super) fn resolve_join_column<'a>(
     name: &str,
     row: &'a [(String, Value)],
 ) -> std::result::Result<Option<&'a Value>, String> {
+    Ok(resolve_join_slot(name, row)?.map(|i| &row[i].1))
+}
+
+/// Position in a joined row of the column `name` refers to.
+fn resolve_join_slot(
+    name: &str,
+    row: &[(String, Value)],
+) -> std::result::Result<Option<usize>, String> {
     // If already qualified (contains a dot, but not ".*")
     if name.contains('.') && !name.ends_with(".*") {
-        for (k, v) in row {
-            if k == name {
-                return Ok(Some(v));
-            }
-        }
-        return Ok(None);
+        return Ok(row.iter().position(|(k, _)| k == name));
     }
 
     // Unqualified: search all columns, check for ambiguity
-    let mut found: Option<&Value> = None;
+    let mut found = None;
     let mut found_count = 0;
-    for (k, v) in row {
+    for (i, (k, _)) in row.iter().enumerate() {
         let col_part = k.rsplit('.').next().unwrap_or(k);
         if col_part == name {
-            found = Some(v);
+            found = Some(i);
             found_count += 1;
         }
     }
@@ -67,9 +67,16 @@ pub(super) fn resolve_join_column<'a>(
 
 /// Evaluate a WHERE/ON expression against a joined row (Vec of qualified (name, value) pairs).
 pub(super) fn eval_join_expr(expr: &Expr, row: &[(String, Value)]) -> Result<Value> {
-    eval_expr(expr, &|name| {
-        resolve_join_column(name, row).ok().flatten().cloned()
-    })
+    eval_expr_ref(expr, &|name| resolve_join_column(name, row).ok().flatten())
+}
+
+/// Evaluate `expr` against a joined row through `memo`.
+fn eval_join_expr_memo(expr: &Expr, row: &[(String, Value)], memo: &mut ExprMemo) -> Result<Value> {
+    memo.eval(
+        expr,
+        &|name| resolve_join_column(name, row).ok().flatten(),
+        None,
+    )
 }
 
 pub(super) fn exec_select_join(
@@ -89,12 +96,16 @@ pub(super) fn exec_select_join(
         .collect();
 
     // 1. Scan the base (FROM) table
+    let scan_stage = start_stage(pager, "FullScan", base_table_name, || {
+        stats_rows_hint(base_table_def)
+    });
     let mut joined_rows = scan_table_qualified(
         base_table_name,
         sel.table_alias.as_deref(),
         base_table_def,
         pager,
     )?;
+    finish_stage(scan_stage, pager, joined_rows.len());
     let mut joined_rows_est: u64 = if base_table_def.stats_row_count > 0 {
         base_table_def.stats_row_count
     } else {
@@ -113,6 +124,14 @@ pub(super) fn exec_select_join(
             .ok_or_else(|| MuroError::Schema(format!("Table '{}' not found", join.table_name)))?;
 
         let right_qualifier = join.alias.as_deref().unwrap_or(&join.table_name);
+        if let Some(on_expr) = &join.on_condition {
+            let mut tables: Vec<(&str, &TableDef)> = left_qualifiers_and_defs
+                .iter()
+                .map(|(q, def)| (q.as_str(), def))
+                .collect();
+            tables.push((right_qualifier, &right_table_def));
+            check_join_literal_types(on_expr, &tables)?;
+        }
         hidden_columns.extend(
             right_table_def
                 .columns
@@ -120,18 +139,29 @@ pub(super) fn exec_select_join(
                 .filter(|c| c.is_hidden)
                 .map(|c| format!("{}.{}", right_qualifier, c.name)),
         );
+        let scan_stage = start_stage(pager, "FullScan", &join.table_name, || {
+            stats_rows_hint(&right_table_def)
+        });
         let right_rows = scan_table_qualified(
             &join.table_name,
             join.alias.as_deref(),
             &right_table_def,
             pager,
         )?;
+        finish_stage(scan_stage, pager, right_rows.len());
+        let join_stage = start_stage(pager, "Join", &join.table_name, || None);
         let right_rows_est: u64 = if right_table_def.stats_row_count > 0 {
             right_table_def.stats_row_count
         } else {
             right_rows.len() as u64
         };
 
+        // ON compares under the collations of the columns it names, as WHERE does.
+        let on_condition = join.on_condition.as_ref().map(|on_expr| {
+            let mut tables = left_qualifiers_and_defs.clone();
+            tables.push((right_qualifier.to_string(), right_table_def.clone()));
+            with_join_collations(on_expr, &tables).unwrap_or_else(|| on_expr.clone())
+        });
         let mut new_rows: Vec<Vec<(String, Value)>> = Vec::new();
 
         match join.join_type {
@@ -149,7 +179,7 @@ pub(super) fn exec_select_join(
                             combined.extend(left.iter().cloned());
                             combined.extend(right.iter().cloned());
 
-                            if let Some(on_expr) = &join.on_condition {
+                            if let Some(on_expr) = &on_condition {
                                 let val = eval_join_expr(on_expr, &combined)?;
                                 if is_truthy(&val) {
                                     new_rows.push(combined);
@@ -169,7 +199,7 @@ pub(super) fn exec_select_join(
                             combined.extend(left.iter().cloned());
                             combined.extend(right.iter().cloned());
 
-                            if let Some(on_expr) = &join.on_condition {
+                            if let Some(on_expr) = &on_condition {
                                 let val = eval_join_expr(on_expr, &combined)?;
                                 if is_truthy(&val) {
                                     new_rows.push(combined);
@@ -192,7 +222,7 @@ pub(super) fn exec_select_join(
                         combined.extend(left.iter().cloned());
                         combined.extend(right.iter().cloned());
 
-                        if let Some(on_expr) = &join.on_condition {
+                        if let Some(on_expr) = &on_condition {
                             let val = eval_join_expr(on_expr, &combined)?;
                             if is_truthy(&val) {
                                 new_rows.push(combined);
@@ -227,7 +257,7 @@ pub(super) fn exec_select_join(
                         combined.extend(left.iter().cloned());
                         combined.extend(right.iter().cloned());
 
-                        if let Some(on_expr) = &join.on_condition {
+                        if let Some(on_expr) = &on_condition {
                             let val = eval_join_expr(on_expr, &combined)?;
                             if is_truthy(&val) {
                                 new_rows.push(combined);
@@ -276,19 +306,49 @@ pub(super) fn exec_select_join(
             }
         }
 
+        finish_stage(join_stage, pager, new_rows.len());
         left_qualifiers_and_defs.push((right_qualifier.to_string(), right_table_def));
         joined_rows_est = new_rows.len() as u64;
         joined_rows = new_rows;
     }
 
-    // 3. Apply WHERE filter
+    // Joined rows all share the shape of the first one, so column references
+    // resolve to fixed slots and repeated sub-expressions (by slot, not by
+    // text) are computed once per row.
     if let Some(where_expr) = &sel.where_clause {
+        let tables: Vec<(&str, &TableDef)> = left_qualifiers_and_defs
+            .iter()
+            .map(|(q, def)| (q.as_str(), def))
+            .collect();
+        check_join_literal_types(where_expr, &tables)?;
+    }
+    let collated_where = sel
+        .where_clause
+        .as_ref()
+        .and_then(|w| with_join_collations(w, &left_qualifiers_and_defs));
+    let where_clause = collated_where.as_ref().or(sel.where_clause.as_ref());
+    let layout = joined_rows.first().cloned().unwrap_or_default();
+    let need_aggregation = has_aggregates(&sel.columns, &sel.having) || sel.group_by.is_some();
+    let projected: &[SelectColumn] = if need_aggregation { &[] } else { &sel.columns };
+    let mut memo = ExprMemo::new(
+        where_clause
+            .into_iter()
+            .chain(projected.iter().filter_map(|col| match col {
+                SelectColumn::Expr(expr, _) => Some(expr),
+                SelectColumn::Star => None,
+            })),
+        &|name| resolve_join_slot(name, &layout).ok().flatten(),
+    );
+
+    // 3. Apply WHERE filter
+    if let Some(where_expr) = where_clause {
         let mut filter_error: Option<MuroError> = None;
         joined_rows.retain(|row| {
             if filter_error.is_some() {
                 return false;
             }
-            match eval_join_expr(where_expr, row) {
+            memo.start_row();
+            match eval_join_expr_memo(where_expr, row, &mut memo) {
                 Ok(val) => is_truthy(&val),
                 Err(e) => {
                     filter_error = Some(e);
@@ -301,15 +361,24 @@ pub(super) fn exec_select_join(
         }
     }
 
-    let need_aggregation = has_aggregates(&sel.columns, &sel.having) || sel.group_by.is_some();
-
     if need_aggregation {
         // Aggregation path for joins
-        let mut rows = execute_aggregation_join(&joined_rows, sel, &hidden_columns)?;
+        let agg_stage = start_stage(pager, "Aggregate", base_table_name, || {
+            sel.group_by.is_none().then_some(1)
+        });
+        let mut rows = execute_aggregation_join(
+            &joined_rows,
+            sel,
+            &hidden_columns,
+            &left_qualifiers_and_defs,
+        )?;
+        finish_stage(agg_stage, pager, rows.len());
 
         // ORDER BY
         if let Some(order_items) = &sel.order_by {
+            let sort_stage = start_stage(pager, "Sort", base_table_name, || None);
             sort_rows(&mut rows, order_items);
+            finish_stage(sort_stage, pager, rows.len());
         }
 
         // OFFSET
@@ -331,12 +400,22 @@ pub(super) fn exec_select_join(
     } else {
         // 4. ORDER BY (before projection, so all columns are accessible)
         if let Some(order_items) = &sel.order_by {
+            let sort_stage = start_stage(pager, "Sort", base_table_name, || None);
             joined_rows.sort_by(|a, b| {
                 for item in order_items {
-                    if let Expr::ColumnRef(col) = &item.expr {
+                    if let Some((col, collation)) = order_by_column(&item.expr) {
+                        let collation = match &item.expr {
+                            Expr::ColumnRef(_) => {
+                                join_column_collation(&left_qualifiers_and_defs, col)
+                                    .unwrap_or(collation)
+                            }
+                            _ => collation,
+                        };
                         let va = resolve_join_column(col, a).ok().flatten();
                         let vb = resolve_join_column(col, b).ok().flatten();
-                        let ord = cmp_values(va, vb);
+                        let va = va.map(|v| collation.key_value(v));
+                        let vb = vb.map(|v| collation.key_value(v));
+                        let ord = cmp_values(va.as_deref(), vb.as_deref());
                         if ord != std::cmp::Ordering::Equal {
                             return if item.descending { ord.reverse() } else { ord };
                         }
@@ -344,6 +423,7 @@ pub(super) fn exec_select_join(
                 }
                 std::cmp::Ordering::Equal
             });
+            finish_stage(sort_stage, pager, joined_rows.len());
         }
 
         // 5. OFFSET
@@ -365,21 +445,22 @@ pub(super) fn exec_select_join(
         let mut rows: Vec<Row> = Vec::new();
         for jrow in &joined_rows {
             cancellation_point()?;
-            let row = build_join_row(jrow, &sel.columns, &hidden_columns)?;
+            memo.start_row();
+            let row = build_join_row(jrow, &sel.columns, &hidden_columns, &mut memo)?;
             rows.push(row);
         }
 
         // SELECT DISTINCT
         if sel.distinct {
-            let mut seen = HashSet::new();
-            rows.retain(|row| {
-                let key: Vec<ValueKey> = row
-                    .values
-                    .iter()
-                    .map(|(_, v)| ValueKey(v.clone()))
-                    .collect();
-                seen.insert(key)
+            let star: Vec<Collation> = left_qualifiers_and_defs
+                .iter()
+                .flat_map(|(_, def)| def.columns.iter().filter(|c| !c.is_hidden))
+                .map(|c| c.collation)
+                .collect();
+            let collations = output_collations(&sel.columns, &star, &|name| {
+                join_column_collation(&left_qualifiers_and_defs, name)
             });
+            retain_distinct(&mut rows, &collations);
         }
 
         Ok(ExecResult::Rows(rows))
@@ -390,6 +471,7 @@ pub(super) fn build_join_row(
     jrow: &[(String, Value)],
     select_columns: &[SelectColumn],
     hidden_columns: &[String],
+    memo: &mut ExprMemo,
 ) -> Result<Row> {
     let mut row_values = Vec::new();
 
@@ -430,7 +512,7 @@ pub(super) fn build_join_row(
                     }
                 }
 
-                let val = eval_join_expr(expr, jrow)?;
+                let val = eval_join_expr_memo(expr, jrow, memo)?;
                 let name = alias.clone().unwrap_or_else(|| match expr {
                     Expr::ColumnRef(n) => n.clone(),
                     _ => "?column?".to_string(),
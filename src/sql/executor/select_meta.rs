@@ -1,13 +1,5 @@
 use super::*;
-
-pub(super) fn select_col_count(sel: &Select) -> Option<usize> {
-    // Star expands to all table columns, so we can't determine the count statically
-    if sel.columns.iter().any(|c| matches!(c, SelectColumn::Star)) {
-        None
-    } else {
-        Some(sel.columns.len())
-    }
-}
+use crate::schema::plan_baseline::PlanAccess;
 
 pub(super) fn exec_explain(
     stmt: &Statement,
@@ -52,25 +44,29 @@ pub(super) fn exec_explain(
         .get_table(pager, &table_name)?
         .ok_or_else(|| MuroError::Schema(format!("Table '{}' not found", table_name)))?;
 
-    let indexes = catalog.get_indexes_for_table(pager, &table_name)?;
-    let index_stats: Vec<IndexPlanStat> = indexes
-        .iter()
-        .filter(|idx| idx.index_type == IndexType::BTree)
-        .map(|idx| IndexPlanStat {
-            name: idx.name.clone(),
-            column_names: idx.column_names.clone(),
-            is_unique: idx.is_unique,
-            stats_distinct_keys: idx.stats_distinct_keys,
-            stats_num_min: idx.stats_num_bounds_known.then_some(idx.stats_num_min),
-            stats_num_max: idx.stats_num_bounds_known.then_some(idx.stats_num_max),
-            stats_num_hist_bins: idx.stats_num_hist_bins.clone(),
-        })
-        .collect();
-    let planner_stats = PlannerStats {
-        table_rows: table_def.stats_row_count,
+    // Plan against the WHERE clause as executed, with LIKE simplified.
+    let like_rewrites = match stmt {
+        Statement::Select(sel) if !sel.joins.is_empty() => None,
+        _ => simplify_like_predicates(where_clause, &table_def),
     };
+    let simplified_where;
+    let (where_clause, like_note) = match like_rewrites {
+        Some((simplified, notes)) => {
+            simplified_where = Some(simplified);
+            (
+                &simplified_where,
+                Some(format!("Simplified LIKE: {}", notes.join(", "))),
+            )
+        }
+        None => (where_clause, None),
+    };
+
+    let indexes = catalog.get_indexes_for_table(pager, &table_name)?;
+    let index_stats = index_plan_stats(&table_def, &indexes);
+    let planner_stats = table_planner_stats(&table_def, pager)?;
 
-    let plan = plan_select_with_hints(
+    // UPDATE/DELETE plan through the same planner, so the estimates match.
+    let (plan, estimates) = plan_select_with_estimates(
         &table_name,
         &table_def.pk_columns,
         &index_stats,
@@ -78,52 +74,86 @@ pub(super) fn exec_explain(
         planner_stats,
         index_hints,
     );
+    let mut baseline_note = None;
+    let plan = match stmt {
+        Statement::Select(sel) if sel.joins.is_empty() => {
+            let sel = Select {
+                where_clause: where_clause.clone(),
+                ..(**sel).clone()
+            };
+            match baseline_plan(&sel, &table_def, &indexes, planner_stats, pager, catalog)? {
+                Some(pinned) => {
+                    baseline_note = Some("Using plan baseline");
+                    pinned
+                }
+                None => plan,
+            }
+        }
+        Statement::Select(_) => plan,
+        _ => plan_mutation(
+            &table_def,
+            &indexes,
+            where_clause,
+            index_hints,
+            planner_stats,
+        ),
+    };
     // Keep EXPLAIN row cardinality informative even before ANALYZE TABLE
     // by falling back to observed table rows for display only.
     let display_stats = PlannerStats {
         table_rows: estimate_table_rows(&table_def, pager)?,
+        ..planner_stats
     };
     let estimated_rows = estimate_plan_rows_hint(&plan, &display_stats, &index_stats);
     let estimated_cost = plan_cost_hint_with_stats(&plan, &planner_stats, &index_stats) as i64;
 
-    let (access_type, key_name, extra) = match &plan {
-        Plan::PkSeek { .. } => ("const", "PRIMARY".to_string(), "Using where".to_string()),
-        Plan::IndexSeek { index_name, .. } => (
-            "ref",
-            index_name.clone(),
-            "Using where; Using index".to_string(),
-        ),
-        Plan::IndexRangeSeek { index_name, .. } => (
-            "range",
-            index_name.clone(),
-            "Using where; Using index".to_string(),
-        ),
-        Plan::FullScan { .. } => {
-            let extra = if where_clause.is_some() {
-                "Using where"
-            } else {
-                ""
+    let description = describe_plan(&plan, &indexes);
+    let access_type = description.access.as_str();
+    let key_name = match (&plan, description.access) {
+        (_, PlanAccess::Const)
+        | (
+            Plan::InListSeek {
+                index_name: None, ..
+            },
+            _,
+        ) => "PRIMARY".to_string(),
+        _ => description.index.unwrap_or_default(),
+    };
+    let extra = match description.access {
+        PlanAccess::Const => "Using where",
+        PlanAccess::Ref | PlanAccess::Range => "Using where; Using index",
+        PlanAccess::All if where_clause.is_some() => "Using where",
+        PlanAccess::All => "",
+        PlanAccess::Fulltext => "Using where; Using fulltext",
+    }
+    .to_string();
+
+    // Join filters are evaluated per joined row as written.
+    let predicate_note = match stmt {
+        Statement::Select(sel) if !sel.joins.is_empty() => None,
+        _ => predicate_order_note(where_clause, &table_def),
+    };
+    let covering_note = match stmt {
+        Statement::Select(sel) if sel.joins.is_empty() => {
+            let sel = Select {
+                where_clause: where_clause.clone(),
+                ..(**sel).clone()
             };
-            ("ALL", String::new(), extra.to_string())
-        }
-        Plan::FtsScan { column, .. } => {
-            let key_name = indexes
-                .iter()
-                .find(|idx| {
-                    idx.index_type == IndexType::Fulltext
-                        && idx.column_names.first().map(|c| c.as_str()) == Some(column.as_str())
-                })
-                .map(|idx| idx.name.clone())
-                .unwrap_or_else(|| format!("fts_{}", column));
-            (
-                "fulltext",
-                key_name,
-                "Using where; Using fulltext".to_string(),
-            )
+            covering_index(&sel, &table_def, &plan, &indexes).map(|_| "Using covering index")
         }
+        _ => None,
     };
-
+    let in_list_note = match &plan {
+        Plan::InListSeek { values, .. } => Some(format!("IndexSeek (IN, n={})", values.len())),
+        _ => None,
+    };
+    let extra = append_extra(extra, covering_note);
+    let extra = append_extra(extra, in_list_note.as_deref());
     let extra = append_extra(extra, join_note.as_deref());
+    let extra = append_extra(extra, stats_note(&planner_stats, &estimates).as_deref());
+    let extra = append_extra(extra, predicate_note.as_deref());
+    let extra = append_extra(extra, like_note.as_deref());
+    let extra = append_extra(extra, baseline_note);
     let row = Row {
         values: vec![
             ("id".to_string(), Value::Integer(1)),
@@ -165,6 +195,19 @@ fn append_extra(base: String, extra_note: Option<&str>) -> String {
     }
 }
 
+/// Evaluation order of the WHERE conjuncts, when there is more than one.
+fn predicate_order_note(where_clause: &Option<Expr>, table_def: &TableDef) -> Option<String> {
+    let ordered = ordered_conjuncts(where_clause.as_ref()?, table_def);
+    if ordered.len() < 2 {
+        return None;
+    }
+    let conjuncts: Vec<String> = ordered
+        .iter()
+        .map(|(expr, _)| describe_conjunct(expr))
+        .collect();
+    Some(format!("Predicate order: {}", conjuncts.join(", ")))
+}
+
 fn build_join_loop_note(
     sel: &Select,
     pager: &mut impl PageStore,
@@ -213,6 +256,29 @@ fn build_join_loop_note(
     }
 }
 
+/// EXPLAIN note listing the candidate row estimates and stale stats.
+fn stats_note(planner_stats: &PlannerStats, estimates: &[PlanEstimate]) -> Option<String> {
+    let mut parts = Vec::new();
+    if planner_stats.stats_are_stale() {
+        parts.push(format!(
+            "Stale stats: analyzed {} rows, now ~{}",
+            planner_stats.table_rows, planner_stats.live_rows
+        ));
+    }
+    if !estimates.is_empty() {
+        let list: Vec<String> = estimates
+            .iter()
+            .map(|e| format!("{}={}", e.label, e.rows))
+            .collect();
+        parts.push(format!("Estimates: {}", list.join(", ")));
+    }
+    if parts.is_empty() {
+        None
+    } else {
+        Some(parts.join("; "))
+    }
+}
+
 fn estimate_table_rows(table_def: &TableDef, pager: &mut impl PageStore) -> Result<u64> {
     if table_def.stats_row_count > 0 {
         return Ok(table_def.stats_row_count);
@@ -225,107 +291,3 @@ fn estimate_table_rows(table_def: &TableDef, pager: &mut impl PageStore) -> Resu
     })?;
     Ok(cnt.max(1))
 }
-
-pub(super) fn exec_set_query(
-    sq: &SetQuery,
-    pager: &mut impl PageStore,
-    catalog: &mut SystemCatalog,
-) -> Result<ExecResult> {
-    // Determine expected column count from AST when possible
-    let mut expected_col_count: Option<usize> = select_col_count(&sq.left);
-
-    // Execute the first SELECT
-    let first_result = exec_select_returning_rows(&sq.left, pager, catalog)?;
-
-    // If we couldn't determine from AST (e.g. SELECT *), use result rows
-    if expected_col_count.is_none() {
-        if let Some(first_row) = first_result.first() {
-            expected_col_count = Some(first_row.values.len());
-        }
-    }
-
-    let col_names: Vec<String> = first_result
-        .first()
-        .map(|r| r.values.iter().map(|(n, _)| n.clone()).collect())
-        .unwrap_or_default();
-
-    let mut rows = first_result;
-
-    for (op, sel) in &sq.ops {
-        // Check column count from AST before executing
-        if let (Some(expected), Some(actual)) = (expected_col_count, select_col_count(sel)) {
-            if actual != expected {
-                return Err(MuroError::Execution(format!(
-                    "UNION queries must have the same number of columns (expected {}, got {})",
-                    expected, actual
-                )));
-            }
-        }
-
-        let sel_rows = exec_select_returning_rows(sel, pager, catalog)?;
-
-        // Validate column count from result rows (handles SELECT * cases)
-        if let Some(first_row) = sel_rows.first() {
-            match expected_col_count {
-                Some(expected) if first_row.values.len() != expected => {
-                    return Err(MuroError::Execution(format!(
-                        "UNION queries must have the same number of columns (expected {}, got {})",
-                        expected,
-                        first_row.values.len()
-                    )));
-                }
-                None => {
-                    expected_col_count = Some(first_row.values.len());
-                }
-                _ => {}
-            }
-        }
-
-        // Unify column names to match the first SELECT
-        for mut row in sel_rows {
-            if !col_names.is_empty() {
-                for (i, (name, _)) in row.values.iter_mut().enumerate() {
-                    if i < col_names.len() {
-                        *name = col_names[i].clone();
-                    }
-                }
-            }
-            rows.push(row);
-        }
-
-        // UNION (without ALL) removes duplicates
-        if *op == SetOp::Union {
-            let mut seen = HashSet::new();
-            rows.retain(|row| {
-                let key: Vec<ValueKey> = row
-                    .values
-                    .iter()
-                    .map(|(_, v)| ValueKey(v.clone()))
-                    .collect();
-                seen.insert(key)
-            });
-        }
-    }
-
-    // Apply ORDER BY
-    if let Some(order_items) = &sq.order_by {
-        sort_rows(&mut rows, order_items);
-    }
-
-    // Apply OFFSET
-    if let Some(offset) = sq.offset {
-        let offset = offset as usize;
-        if offset >= rows.len() {
-            rows.clear();
-        } else {
-            rows = rows.into_iter().skip(offset).collect();
-        }
-    }
-
-    // Apply LIMIT
-    if let Some(limit) = sq.limit {
-        rows.truncate(limit as usize);
-    }
-
-    Ok(ExecResult::Rows(rows))
-}
@@ -1,4 +1,9 @@
 use super::*;
+use crate::sql::session::{scan_skip_corruption_current, select_plan_current};
+
+/// Primary keys looked up per [`BTree::get_many`] call when rows are fetched
+/// through an index, so only one chunk of rows is held at a time.
+pub(super) const ROW_FETCH_CHUNK: usize = 256;
 
 pub(super) fn exec_select_without_table(
     sel: &Select,
@@ -67,6 +72,11 @@ pub(super) fn exec_select_without_table_inner(sel: &Select) -> Result<ExecResult
             row_format_version: 0,
             stats_row_count: 0,
             foreign_keys: Vec::new(),
+            column_stats: Vec::new(),
+            row_count: None,
+            fill_factor: DEFAULT_FILL_FACTOR,
+            index_key_format: INDEX_KEY_FORMAT_TERMINATED,
+            trailing_fields: Vec::new(),
         };
         let raw_rows = if where_passed {
             vec![vec![]]
@@ -168,32 +178,42 @@ pub(super) fn exec_select(
     if !sel.joins.is_empty() {
         return exec_select_join(sel, table_name, &table_def, pager, catalog);
     }
+    check_where_literal_types(&sel.where_clause, &table_def)?;
+
+    if let Some(row) = tracked_count_row(sel, &table_def) {
+        let skipped = sel.offset.unwrap_or(0) > 0 || sel.limit == Some(0);
+        return Ok(ExecResult::Rows(if skipped {
+            Vec::new()
+        } else {
+            vec![row]
+        }));
+    }
+
+    if let Some((where_clause, _)) = simplify_like_predicates(&sel.where_clause, &table_def) {
+        let simplified = Select {
+            where_clause: Some(where_clause),
+            ..sel.clone()
+        };
+        return exec_select(&simplified, pager, catalog);
+    }
 
     let indexes = catalog.get_indexes_for_table(pager, table_name)?;
-    let index_stats: Vec<IndexPlanStat> = indexes
-        .iter()
-        .filter(|idx| idx.index_type == IndexType::BTree)
-        .map(|idx| IndexPlanStat {
-            name: idx.name.clone(),
-            column_names: idx.column_names.clone(),
-            is_unique: idx.is_unique,
-            stats_distinct_keys: idx.stats_distinct_keys,
-            stats_num_min: idx.stats_num_bounds_known.then_some(idx.stats_num_min),
-            stats_num_max: idx.stats_num_bounds_known.then_some(idx.stats_num_max),
-            stats_num_hist_bins: idx.stats_num_hist_bins.clone(),
-        })
-        .collect();
+    let index_stats = index_plan_stats(&table_def, &indexes);
 
-    let plan = plan_select_with_hints(
-        table_name,
-        &table_def.pk_columns,
-        &index_stats,
-        &sel.where_clause,
-        PlannerStats {
-            table_rows: table_def.stats_row_count,
-        },
-        &sel.index_hints,
-    );
+    let planner_stats = table_planner_stats(&table_def, pager)?;
+    let plan = match baseline_plan(sel, &table_def, &indexes, planner_stats, pager, catalog)? {
+        Some(plan) => plan,
+        None => select_plan_current(table_name, sel, catalog.generation(), &indexes, || {
+            plan_select_with_hints(
+                table_name,
+                &table_def.pk_columns,
+                &index_stats,
+                &sel.where_clause,
+                planner_stats,
+                &sel.index_hints,
+            )
+        }),
+    };
 
     let need_aggregation = has_aggregates(&sel.columns, &sel.having) || sel.group_by.is_some();
     let mut fts_ctx = build_fts_eval_context(
@@ -204,12 +224,54 @@ pub(super) fn exec_select(
         pager,
     )?;
     let needs_fts_doc_ids = !fts_ctx.score_maps.is_empty();
+    // Per-row filter: the full WHERE, cheapest conjuncts first.
+    let residual = residual_filter(&sel.where_clause, &table_def);
+    // Sub-expressions repeated across the filter and the projected columns
+    // are computed once per row.
+    let projected: &[SelectColumn] = if need_aggregation { &[] } else { &sel.columns };
+    let mut memo = ExprMemo::new(
+        residual
+            .iter()
+            .chain(projected.iter().filter_map(|col| match col {
+                SelectColumn::Expr(expr, _) => Some(expr),
+                SelectColumn::Star => None,
+            })),
+        &|name| table_def.column_index(name),
+    );
+    let covering = covering_index(sel, &table_def, &plan, &indexes);
 
     if need_aggregation {
-        // Aggregation path: collect raw values first
-        let mut raw_rows: Vec<Vec<Value>> = Vec::new();
+        if let Some(raw_rows) = min_max_probe_rows(sel, &table_def, &indexes, pager)? {
+            let mut aggregator = GroupAggregator::new(&table_def, sel);
+            for values in raw_rows {
+                aggregator.feed(values)?;
+            }
+            return finish_aggregation(aggregator, &table_def, sel, pager);
+        }
+
+        // Aggregation path: stream matching rows into their groups
+        let mut aggregator = GroupAggregator::new(&table_def, sel);
+        let access_stage = start_stage(pager, plan_node_name(&plan), table_name, || {
+            Some(estimate_plan_rows_hint(&plan, &planner_stats, &index_stats))
+        });
+        let covered = covering
+            .map(|c| index_only_rows(&c, &table_def, &plan, pager))
+            .transpose()?;
 
         match plan {
+            _ if covered.is_some() => {
+                for values in covered.into_iter().flatten() {
+                    if matches_where_with_fts(
+                        &residual,
+                        &table_def,
+                        &values,
+                        Some(&fts_ctx),
+                        &mut memo,
+                    )? {
+                        aggregator.feed(values)?;
+                    }
+                }
+            }
             Plan::PkSeek { key_exprs, .. } => {
                 let pk_key = eval_pk_seek_key(&table_def, &key_exprs)?;
                 let data_btree = BTree::open(table_def.data_btree_root);
@@ -229,12 +291,13 @@ pub(super) fn exec_select(
                         )?;
                     }
                     if matches_where_with_fts(
-                        &sel.where_clause,
+                        &residual,
                         &table_def,
                         &values,
                         Some(&fts_ctx),
+                        &mut memo,
                     )? {
-                        raw_rows.push(values);
+                        aggregator.feed(values)?;
                     }
                 }
             }
@@ -251,32 +314,84 @@ pub(super) fn exec_select(
                     .ok_or_else(|| {
                         MuroError::Execution(format!("Index '{}' not found", index_name))
                     })?;
-                let pk_keys = index_seek_pk_keys(idx, &idx_key, pager)?;
+                let pk_keys = index_seek_pk_keys(&table_def, idx, &idx_key, pager)?;
                 let data_btree = BTree::open(table_def.data_btree_root);
-                for pk_key in &pk_keys {
-                    cancellation_point()?;
-                    if let Some(data) = data_btree.search(pager, pk_key)? {
-                        let values = deserialize_row_versioned(
-                            &data,
-                            &table_def.columns,
-                            table_def.row_format_version,
-                        )?;
-                        if needs_fts_doc_ids {
-                            populate_fts_row_doc_ids(
-                                &mut fts_ctx,
-                                pk_key,
-                                &indexes,
-                                &table_def.name,
-                                pager,
+                for chunk in pk_keys.chunks(ROW_FETCH_CHUNK) {
+                    let fetched = data_btree.get_many(pager, chunk)?;
+                    for (pk_key, data) in chunk.iter().zip(fetched) {
+                        cancellation_point()?;
+                        if let Some(data) = data {
+                            let values = deserialize_row_versioned(
+                                &data,
+                                &table_def.columns,
+                                table_def.row_format_version,
                             )?;
+                            if needs_fts_doc_ids {
+                                populate_fts_row_doc_ids(
+                                    &mut fts_ctx,
+                                    pk_key,
+                                    &indexes,
+                                    &table_def.name,
+                                    pager,
+                                )?;
+                            }
+                            if matches_where_with_fts(
+                                &residual,
+                                &table_def,
+                                &values,
+                                Some(&fts_ctx),
+                                &mut memo,
+                            )? {
+                                aggregator.feed(values)?;
+                            }
                         }
-                        if matches_where_with_fts(
-                            &sel.where_clause,
-                            &table_def,
-                            &values,
-                            Some(&fts_ctx),
-                        )? {
-                            raw_rows.push(values);
+                    }
+                }
+            }
+            Plan::InListSeek {
+                index_name,
+                column_name,
+                values,
+                ..
+            } => {
+                let idx = index_name
+                    .as_ref()
+                    .map(|name| {
+                        indexes.iter().find(|i| &i.name == name).ok_or_else(|| {
+                            MuroError::Execution(format!("Index '{}' not found", name))
+                        })
+                    })
+                    .transpose()?;
+                let pk_keys = in_list_seek_pk_keys(&table_def, idx, &column_name, &values, pager)?;
+                let data_btree = BTree::open(table_def.data_btree_root);
+                for chunk in pk_keys.chunks(ROW_FETCH_CHUNK) {
+                    let fetched = data_btree.get_many(pager, chunk)?;
+                    for (pk_key, data) in chunk.iter().zip(fetched) {
+                        cancellation_point()?;
+                        if let Some(data) = data {
+                            let values = deserialize_row_versioned(
+                                &data,
+                                &table_def.columns,
+                                table_def.row_format_version,
+                            )?;
+                            if needs_fts_doc_ids {
+                                populate_fts_row_doc_ids(
+                                    &mut fts_ctx,
+                                    pk_key,
+                                    &indexes,
+                                    &table_def.name,
+                                    pager,
+                                )?;
+                            }
+                            if matches_where_with_fts(
+                                &residual,
+                                &table_def,
+                                &values,
+                                Some(&fts_ctx),
+                                &mut memo,
+                            )? {
+                                aggregator.feed(values)?;
+                            }
                         }
                     }
                 }
@@ -293,20 +408,26 @@ pub(super) fn exec_select(
                 let bound_columns = column_names[..prefix_len + 1].to_vec();
                 let lower_key = lower
                     .as_ref()
-                    .map(|(expr, inclusive)| {
-                        let mut key_exprs = prefix_key_exprs.clone();
-                        key_exprs.push(*expr.clone());
-                        eval_index_seek_key(&table_def, &bound_columns, &key_exprs)
-                            .map(|key| (key, *inclusive))
+                    .map(|bound| {
+                        eval_index_range_bound(
+                            &table_def,
+                            &bound_columns,
+                            &prefix_key_exprs,
+                            bound,
+                            false,
+                        )
                     })
                     .transpose()?;
                 let upper_key = upper
                     .as_ref()
-                    .map(|(expr, inclusive)| {
-                        let mut key_exprs = prefix_key_exprs.clone();
-                        key_exprs.push(*expr.clone());
-                        eval_index_seek_key(&table_def, &bound_columns, &key_exprs)
-                            .map(|key| (key, *inclusive))
+                    .map(|bound| {
+                        eval_index_range_bound(
+                            &table_def,
+                            &bound_columns,
+                            &prefix_key_exprs,
+                            bound,
+                            true,
+                        )
                     })
                     .transpose()?;
                 let idx = indexes
@@ -315,7 +436,8 @@ pub(super) fn exec_select(
                     .ok_or_else(|| {
                         MuroError::Execution(format!("Index '{}' not found", index_name))
                     })?;
-                let pk_keys = index_seek_pk_keys_range(idx, lower_key, upper_key, pager)?;
+                let pk_keys =
+                    index_seek_pk_keys_range(&table_def, idx, lower_key, upper_key, pager)?;
                 let data_btree = BTree::open(table_def.data_btree_root);
                 for pk_key in &pk_keys {
                     cancellation_point()?;
@@ -335,27 +457,22 @@ pub(super) fn exec_select(
                             )?;
                         }
                         if matches_where_with_fts(
-                            &sel.where_clause,
+                            &residual,
                             &table_def,
                             &values,
                             Some(&fts_ctx),
+                            &mut memo,
                         )? {
-                            raw_rows.push(values);
+                            aggregator.feed(values)?;
                         }
                     }
                 }
             }
             Plan::FullScan { .. } => {
-                let data_btree = BTree::open(table_def.data_btree_root);
                 if needs_fts_doc_ids {
                     let mut entries: Vec<(Vec<u8>, Vec<Value>)> = Vec::new();
-                    data_btree.scan(pager, |pk_key, v| {
+                    scan_table_rows(&table_def, pager, false, |pk_key, values| {
                         cancellation_point()?;
-                        let values = deserialize_row_versioned(
-                            v,
-                            &table_def.columns,
-                            table_def.row_format_version,
-                        )?;
                         entries.push((pk_key.to_vec(), values));
                         Ok(true)
                     })?;
@@ -369,32 +486,36 @@ pub(super) fn exec_select(
                             pager,
                         )?;
                         if matches_where_with_fts(
-                            &sel.where_clause,
+                            &residual,
                             &table_def,
                             &values,
                             Some(&fts_ctx),
+                            &mut memo,
                         )? {
-                            raw_rows.push(values);
+                            aggregator.feed(values)?;
                         }
                     }
                 } else {
-                    data_btree.scan(pager, |_, v| {
-                        cancellation_point()?;
-                        let values = deserialize_row_versioned(
-                            v,
-                            &table_def.columns,
-                            table_def.row_format_version,
-                        )?;
-                        if matches_where_with_fts(
-                            &sel.where_clause,
-                            &table_def,
-                            &values,
-                            Some(&fts_ctx),
-                        )? {
-                            raw_rows.push(values);
-                        }
-                        Ok(true)
-                    })?;
+                    let wanted = scanned_column_positions(sel, &table_def);
+                    scan_table_columns(
+                        &table_def,
+                        pager,
+                        false,
+                        wanted.as_deref(),
+                        |_, values| {
+                            cancellation_point()?;
+                            if matches_where_with_fts(
+                                &residual,
+                                &table_def,
+                                &values,
+                                Some(&fts_ctx),
+                                &mut memo,
+                            )? {
+                                aggregator.feed(values)?;
+                            }
+                            Ok(true)
+                        },
+                    )?;
                 }
             }
             Plan::FtsScan {
@@ -418,40 +539,20 @@ pub(super) fn exec_select(
                         )?;
                     }
                     if matches_where_with_fts(
-                        &sel.where_clause,
+                        &residual,
                         &table_def,
                         &values,
                         Some(&fts_ctx),
+                        &mut memo,
                     )? {
-                        raw_rows.push(values);
+                        aggregator.feed(values)?;
                     }
                 }
             }
         }
+        finish_stage(access_stage, pager, aggregator.rows_fed() as usize);
 
-        let mut rows = execute_aggregation(raw_rows, &table_def, sel)?;
-
-        // ORDER BY
-        if let Some(order_items) = &sel.order_by {
-            sort_rows(&mut rows, order_items);
-        }
-
-        // OFFSET
-        if let Some(offset) = sel.offset {
-            let offset = offset as usize;
-            if offset >= rows.len() {
-                rows.clear();
-            } else {
-                rows = rows.into_iter().skip(offset).collect();
-            }
-        }
-
-        // LIMIT
-        if let Some(limit) = sel.limit {
-            rows.truncate(limit as usize);
-        }
-
-        Ok(ExecResult::Rows(rows))
+        finish_aggregation(aggregator, &table_def, sel, pager)
     } else {
         // Non-aggregation path (original)
         let mut rows: Vec<Row> = Vec::new();
@@ -459,8 +560,35 @@ pub(super) fn exec_select(
         // Collect ORDER BY columns not present in SELECT list.
         // These must be included in each Row for sorting, then stripped afterward.
         let extra_order_cols = collect_extra_order_by_columns(&sel.columns, &sel.order_by);
+        let access_stage = start_stage(pager, plan_node_name(&plan), table_name, || {
+            Some(estimate_plan_rows_hint(&plan, &planner_stats, &index_stats))
+        });
+        let covered = covering
+            .map(|c| index_only_rows(&c, &table_def, &plan, pager))
+            .transpose()?;
 
         match plan {
+            _ if covered.is_some() => {
+                for values in covered.into_iter().flatten() {
+                    if matches_where_with_fts(
+                        &residual,
+                        &table_def,
+                        &values,
+                        Some(&fts_ctx),
+                        &mut memo,
+                    )? {
+                        let row = build_row_with_fts_and_extras(
+                            &table_def,
+                            &values,
+                            &sel.columns,
+                            Some(&fts_ctx),
+                            &extra_order_cols,
+                            &mut memo,
+                        )?;
+                        rows.push(row);
+                    }
+                }
+            }
             Plan::PkSeek { key_exprs, .. } => {
                 let pk_key = eval_pk_seek_key(&table_def, &key_exprs)?;
                 let data_btree = BTree::open(table_def.data_btree_root);
@@ -480,10 +608,11 @@ pub(super) fn exec_select(
                         )?;
                     }
                     if matches_where_with_fts(
-                        &sel.where_clause,
+                        &residual,
                         &table_def,
                         &values,
                         Some(&fts_ctx),
+                        &mut memo,
                     )? {
                         let row = build_row_with_fts_and_extras(
                             &table_def,
@@ -491,6 +620,7 @@ pub(super) fn exec_select(
                             &sel.columns,
                             Some(&fts_ctx),
                             &extra_order_cols,
+                            &mut memo,
                         )?;
                         rows.push(row);
                     }
@@ -509,39 +639,100 @@ pub(super) fn exec_select(
                     .ok_or_else(|| {
                         MuroError::Execution(format!("Index '{}' not found", index_name))
                     })?;
-                let pk_keys = index_seek_pk_keys(idx, &idx_key, pager)?;
+                let pk_keys = index_seek_pk_keys(&table_def, idx, &idx_key, pager)?;
                 let data_btree = BTree::open(table_def.data_btree_root);
-                for pk_key in &pk_keys {
-                    cancellation_point()?;
-                    if let Some(data) = data_btree.search(pager, pk_key)? {
-                        let values = deserialize_row_versioned(
-                            &data,
-                            &table_def.columns,
-                            table_def.row_format_version,
-                        )?;
-                        if needs_fts_doc_ids {
-                            populate_fts_row_doc_ids(
-                                &mut fts_ctx,
-                                pk_key,
-                                &indexes,
-                                &table_def.name,
-                                pager,
+                for chunk in pk_keys.chunks(ROW_FETCH_CHUNK) {
+                    let fetched = data_btree.get_many(pager, chunk)?;
+                    for (pk_key, data) in chunk.iter().zip(fetched) {
+                        cancellation_point()?;
+                        if let Some(data) = data {
+                            let values = deserialize_row_versioned(
+                                &data,
+                                &table_def.columns,
+                                table_def.row_format_version,
                             )?;
-                        }
-                        if matches_where_with_fts(
-                            &sel.where_clause,
-                            &table_def,
-                            &values,
-                            Some(&fts_ctx),
-                        )? {
-                            let row = build_row_with_fts_and_extras(
+                            if needs_fts_doc_ids {
+                                populate_fts_row_doc_ids(
+                                    &mut fts_ctx,
+                                    pk_key,
+                                    &indexes,
+                                    &table_def.name,
+                                    pager,
+                                )?;
+                            }
+                            if matches_where_with_fts(
+                                &residual,
                                 &table_def,
                                 &values,
-                                &sel.columns,
                                 Some(&fts_ctx),
-                                &extra_order_cols,
+                                &mut memo,
+                            )? {
+                                let row = build_row_with_fts_and_extras(
+                                    &table_def,
+                                    &values,
+                                    &sel.columns,
+                                    Some(&fts_ctx),
+                                    &extra_order_cols,
+                                    &mut memo,
+                                )?;
+                                rows.push(row);
+                            }
+                        }
+                    }
+                }
+            }
+            Plan::InListSeek {
+                index_name,
+                column_name,
+                values,
+                ..
+            } => {
+                let idx = index_name
+                    .as_ref()
+                    .map(|name| {
+                        indexes.iter().find(|i| &i.name == name).ok_or_else(|| {
+                            MuroError::Execution(format!("Index '{}' not found", name))
+                        })
+                    })
+                    .transpose()?;
+                let pk_keys = in_list_seek_pk_keys(&table_def, idx, &column_name, &values, pager)?;
+                let data_btree = BTree::open(table_def.data_btree_root);
+                for chunk in pk_keys.chunks(ROW_FETCH_CHUNK) {
+                    let fetched = data_btree.get_many(pager, chunk)?;
+                    for (pk_key, data) in chunk.iter().zip(fetched) {
+                        cancellation_point()?;
+                        if let Some(data) = data {
+                            let values = deserialize_row_versioned(
+                                &data,
+                                &table_def.columns,
+                                table_def.row_format_version,
                             )?;
-                            rows.push(row);
+                            if needs_fts_doc_ids {
+                                populate_fts_row_doc_ids(
+                                    &mut fts_ctx,
+                                    pk_key,
+                                    &indexes,
+                                    &table_def.name,
+                                    pager,
+                                )?;
+                            }
+                            if matches_where_with_fts(
+                                &residual,
+                                &table_def,
+                                &values,
+                                Some(&fts_ctx),
+                                &mut memo,
+                            )? {
+                                let row = build_row_with_fts_and_extras(
+                                    &table_def,
+                                    &values,
+                                    &sel.columns,
+                                    Some(&fts_ctx),
+                                    &extra_order_cols,
+                                    &mut memo,
+                                )?;
+                                rows.push(row);
+                            }
                         }
                     }
                 }
@@ -558,20 +749,26 @@ pub(super) fn exec_select(
                 let bound_columns = column_names[..prefix_len + 1].to_vec();
                 let lower_key = lower
                     .as_ref()
-                    .map(|(expr, inclusive)| {
-                        let mut key_exprs = prefix_key_exprs.clone();
-                        key_exprs.push(*expr.clone());
-                        eval_index_seek_key(&table_def, &bound_columns, &key_exprs)
-                            .map(|key| (key, *inclusive))
+                    .map(|bound| {
+                        eval_index_range_bound(
+                            &table_def,
+                            &bound_columns,
+                            &prefix_key_exprs,
+                            bound,
+                            false,
+                        )
                     })
                     .transpose()?;
                 let upper_key = upper
                     .as_ref()
-                    .map(|(expr, inclusive)| {
-                        let mut key_exprs = prefix_key_exprs.clone();
-                        key_exprs.push(*expr.clone());
-                        eval_index_seek_key(&table_def, &bound_columns, &key_exprs)
-                            .map(|key| (key, *inclusive))
+                    .map(|bound| {
+                        eval_index_range_bound(
+                            &table_def,
+                            &bound_columns,
+                            &prefix_key_exprs,
+                            bound,
+                            true,
+                        )
                     })
                     .transpose()?;
                 let idx = indexes
@@ -580,7 +777,8 @@ pub(super) fn exec_select(
                     .ok_or_else(|| {
                         MuroError::Execution(format!("Index '{}' not found", index_name))
                     })?;
-                let pk_keys = index_seek_pk_keys_range(idx, lower_key, upper_key, pager)?;
+                let pk_keys =
+                    index_seek_pk_keys_range(&table_def, idx, lower_key, upper_key, pager)?;
                 let data_btree = BTree::open(table_def.data_btree_root);
                 for pk_key in &pk_keys {
                     cancellation_point()?;
@@ -600,10 +798,11 @@ pub(super) fn exec_select(
                             )?;
                         }
                         if matches_where_with_fts(
-                            &sel.where_clause,
+                            &residual,
                             &table_def,
                             &values,
                             Some(&fts_ctx),
+                            &mut memo,
                         )? {
                             let row = build_row_with_fts_and_extras(
                                 &table_def,
@@ -611,6 +810,7 @@ pub(super) fn exec_select(
                                 &sel.columns,
                                 Some(&fts_ctx),
                                 &extra_order_cols,
+                                &mut memo,
                             )?;
                             rows.push(row);
                         }
@@ -618,16 +818,10 @@ pub(super) fn exec_select(
                 }
             }
             Plan::FullScan { .. } => {
-                let data_btree = BTree::open(table_def.data_btree_root);
                 if needs_fts_doc_ids {
                     let mut entries: Vec<(Vec<u8>, Vec<Value>)> = Vec::new();
-                    data_btree.scan(pager, |pk_key, v| {
+                    scan_table_rows(&table_def, pager, false, |pk_key, values| {
                         cancellation_point()?;
-                        let values = deserialize_row_versioned(
-                            v,
-                            &table_def.columns,
-                            table_def.row_format_version,
-                        )?;
                         entries.push((pk_key.to_vec(), values));
                         Ok(true)
                     })?;
@@ -641,10 +835,11 @@ pub(super) fn exec_select(
                             pager,
                         )?;
                         if matches_where_with_fts(
-                            &sel.where_clause,
+                            &residual,
                             &table_def,
                             &values,
                             Some(&fts_ctx),
+                            &mut memo,
                         )? {
                             let row = build_row_with_fts_and_extras(
                                 &table_def,
@@ -652,23 +847,23 @@ pub(super) fn exec_select(
                                 &sel.columns,
                                 Some(&fts_ctx),
                                 &extra_order_cols,
+                                &mut memo,
                             )?;
                             rows.push(row);
                         }
                     }
                 } else {
-                    data_btree.scan(pager, |_, v| {
+                    // ORDER BY <pk> [DESC] LIMIT n can stream in key order and
+                    // stop as soon as enough rows have matched.
+                    let ordered_limit = pk_order_scan_limit(sel, &table_def);
+                    let visit = |_: &[u8], values: Vec<Value>| -> Result<bool> {
                         cancellation_point()?;
-                        let values = deserialize_row_versioned(
-                            v,
-                            &table_def.columns,
-                            table_def.row_format_version,
-                        )?;
                         if matches_where_with_fts(
-                            &sel.where_clause,
+                            &residual,
                             &table_def,
                             &values,
                             Some(&fts_ctx),
+                            &mut memo,
                         )? {
                             let row = build_row_with_fts_and_extras(
                                 &table_def,
@@ -676,11 +871,15 @@ pub(super) fn exec_select(
                                 &sel.columns,
                                 Some(&fts_ctx),
                                 &extra_order_cols,
+                                &mut memo,
                             )?;
                             rows.push(row);
                         }
-                        Ok(true)
-                    })?;
+                        Ok(ordered_limit.is_none_or(|(_, wanted)| rows.len() < wanted))
+                    };
+                    let reverse = matches!(ordered_limit, Some((true, _)));
+                    let wanted = scanned_column_positions(sel, &table_def);
+                    scan_table_columns(&table_def, pager, reverse, wanted.as_deref(), visit)?;
                 }
             }
             Plan::FtsScan {
@@ -704,10 +903,11 @@ pub(super) fn exec_select(
                         )?;
                     }
                     if matches_where_with_fts(
-                        &sel.where_clause,
+                        &residual,
                         &table_def,
                         &values,
                         Some(&fts_ctx),
+                        &mut memo,
                     )? {
                         let row = build_row_with_fts_and_extras(
                             &table_def,
@@ -715,29 +915,34 @@ pub(super) fn exec_select(
                             &sel.columns,
                             Some(&fts_ctx),
                             &extra_order_cols,
+                            &mut memo,
                         )?;
                         rows.push(row);
                     }
                 }
             }
         }
+        finish_stage(access_stage, pager, rows.len());
 
         // SELECT DISTINCT
         if sel.distinct {
-            let mut seen = HashSet::new();
-            rows.retain(|row| {
-                let key: Vec<ValueKey> = row
-                    .values
-                    .iter()
-                    .map(|(_, v)| ValueKey(v.clone()))
-                    .collect();
-                seen.insert(key)
+            let star: Vec<Collation> = table_def
+                .columns
+                .iter()
+                .filter(|c| !c.is_hidden)
+                .map(|c| c.collation)
+                .collect();
+            let collations = output_collations(&sel.columns, &star, &|name| {
+                column_collation_of(&table_def, name)
             });
+            retain_distinct(&mut rows, &collations);
         }
 
         // ORDER BY
         if let Some(order_items) = &sel.order_by {
-            sort_rows(&mut rows, order_items);
+            let sort_stage = start_stage(pager, "Sort", table_name, || None);
+            sort_rows(&mut rows, &collated_order_by(order_items, &table_def));
+            finish_stage(sort_stage, pager, rows.len());
         }
 
         // Strip extra ORDER BY columns that were injected for sorting
@@ -781,13 +986,354 @@ pub(super) fn exec_select_returning_rows(
     }
 }
 
+fn finish_aggregation(
+    aggregator: GroupAggregator,
+    table_def: &TableDef,
+    sel: &Select,
+    pager: &impl PageStore,
+) -> Result<ExecResult> {
+    let agg_stage = start_stage(pager, "Aggregate", &table_def.name, || {
+        sel.group_by.is_none().then_some(1)
+    });
+    let mut rows = aggregator.finish()?;
+    finish_stage(agg_stage, pager, rows.len());
+
+    // ORDER BY
+    if let Some(order_items) = &sel.order_by {
+        let sort_stage = start_stage(pager, "Sort", &table_def.name, || None);
+        sort_rows(&mut rows, &collated_order_by(order_items, table_def));
+        finish_stage(sort_stage, pager, rows.len());
+    }
+
+    // OFFSET
+    if let Some(offset) = sel.offset {
+        let offset = offset as usize;
+        if offset >= rows.len() {
+            rows.clear();
+        } else {
+            rows = rows.into_iter().skip(offset).collect();
+        }
+    }
+
+    // LIMIT
+    if let Some(limit) = sel.limit {
+        rows.truncate(limit as usize);
+    }
+
+    Ok(ExecResult::Rows(rows))
+}
+
+/// Whether byte order of encoded keys for this column equals `cmp_values`
+/// order, so the first/last B-tree entry holds the column's minimum/maximum.
+/// Keys of a collated column encode the collation key, not the value.
+fn key_order_matches_value_order(column: &ColumnDef) -> bool {
+    column.collation == Collation::Binary
+        && matches!(
+            column.data_type,
+            DataType::TinyInt
+                | DataType::SmallInt
+                | DataType::Int
+                | DataType::BigInt
+                | DataType::Decimal(_, _)
+                | DataType::Date
+                | DataType::DateTime
+                | DataType::Timestamp
+                | DataType::Varchar(_)
+                | DataType::Varbinary(_)
+                | DataType::Text
+                | DataType::Uuid
+        )
+}
+
+/// For `SELECT ... ORDER BY <single pk column> [DESC] LIMIT n`, return
+/// `(descending, offset + limit)` when rows can be produced directly in key order.
+fn pk_order_scan_limit(sel: &Select, table_def: &TableDef) -> Option<(bool, usize)> {
+    let limit = sel.limit?;
+    if sel.distinct || table_def.pk_columns.len() != 1 {
+        return None;
+    }
+    let [item] = sel.order_by.as_deref()? else {
+        return None;
+    };
+    let Expr::ColumnRef(col) = &item.expr else {
+        return None;
+    };
+    if *col != table_def.pk_columns[0] {
+        return None;
+    }
+    let col_idx = table_def.column_index(col)?;
+    if !key_order_matches_value_order(&table_def.columns[col_idx]) {
+        return None;
+    }
+    // An alias with the PK's name would make ORDER BY refer to another expression.
+    let shadowed = sel.columns.iter().any(|c| match c {
+        SelectColumn::Expr(expr, Some(alias)) => {
+            alias == col && !matches!(expr, Expr::ColumnRef(n) if n == col)
+        }
+        _ => false,
+    });
+    if shadowed {
+        return None;
+    }
+    let wanted = sel.offset.unwrap_or(0).saturating_add(limit);
+    Some((
+        item.descending,
+        usize::try_from(wanted).unwrap_or(usize::MAX),
+    ))
+}
+
+/// Positions of the columns a full scan for `sel` has to decode: those its
+/// select list, WHERE, GROUP BY, HAVING and ORDER BY read. `None` to decode
+/// every column: for `SELECT *`, when a subquery may read any of them, or
+/// when scans skip corrupt rows, which only a full decode detects.
+pub(super) fn scanned_column_positions(sel: &Select, table_def: &TableDef) -> Option<Vec<usize>> {
+    if scan_skip_corruption_current() {
+        return None;
+    }
+    let mut names = Vec::new();
+    let mut exprs: Vec<&Expr> = Vec::new();
+    for col in &sel.columns {
+        match col {
+            SelectColumn::Star => return None,
+            SelectColumn::Expr(expr, _) => exprs.push(expr),
+        }
+    }
+    exprs.extend(sel.where_clause.iter());
+    exprs.extend(sel.group_by.iter().flatten());
+    exprs.extend(sel.having.iter());
+    exprs.extend(sel.order_by.iter().flatten().map(|item| &item.expr));
+    for expr in exprs {
+        if !collect_column_refs(expr, &mut names) {
+            return None;
+        }
+    }
+    let mut positions: Vec<usize> = names
+        .iter()
+        .filter_map(|name| table_def.column_index(name))
+        .collect();
+    positions.sort_unstable();
+    positions.dedup();
+    Some(positions)
+}
+
+/// Add the column names `expr` reads to `out`. Returns false when it holds
+/// a subquery, whose column references are not collected.
+fn collect_column_refs<'a>(expr: &'a Expr, out: &mut Vec<&'a str>) -> bool {
+    match expr {
+        Expr::ColumnRef(name)
+        | Expr::MatchAgainst { column: name, .. }
+        | Expr::FtsSnippet { column: name, .. } => {
+            out.push(name);
+            true
+        }
+        Expr::InSubquery { .. } | Expr::Exists { .. } | Expr::ScalarSubquery(_) => false,
+        Expr::BinaryOp { left, right, .. } => {
+            collect_column_refs(left, out) && collect_column_refs(right, out)
+        }
+        Expr::UnaryOp { operand, .. } => collect_column_refs(operand, out),
+        Expr::Like {
+            expr,
+            pattern,
+            escape,
+            ..
+        } => {
+            collect_column_refs(expr, out)
+                && collect_column_refs(pattern, out)
+                && escape
+                    .as_deref()
+                    .is_none_or(|e| collect_column_refs(e, out))
+        }
+        Expr::InList { expr, list, .. } => {
+            collect_column_refs(expr, out) && list.iter().all(|e| collect_column_refs(e, out))
+        }
+        Expr::Between {
+            expr, low, high, ..
+        } => {
+            collect_column_refs(expr, out)
+                && collect_column_refs(low, out)
+                && collect_column_refs(high, out)
+        }
+        Expr::IsNull { expr, .. }
+        | Expr::Cast { expr, .. }
+        | Expr::Collate { expr, .. }
+        | Expr::GreaterThanZero(expr) => collect_column_refs(expr, out),
+        Expr::CaseWhen {
+            operand,
+            when_clauses,
+            else_clause,
+        } => {
+            operand
+                .as_deref()
+                .is_none_or(|e| collect_column_refs(e, out))
+                && when_clauses
+                    .iter()
+                    .all(|(c, t)| collect_column_refs(c, out) && collect_column_refs(t, out))
+                && else_clause
+                    .as_deref()
+                    .is_none_or(|e| collect_column_refs(e, out))
+        }
+        Expr::FunctionCall { args, .. } => args.iter().all(|e| collect_column_refs(e, out)),
+        Expr::AggregateFunc { arg, .. } => {
+            arg.as_deref().is_none_or(|e| collect_column_refs(e, out))
+        }
+        Expr::BindParam
+        | Expr::IntLiteral(_)
+        | Expr::FloatLiteral(_)
+        | Expr::StringLiteral(_)
+        | Expr::BlobLiteral(_)
+        | Expr::Null
+        | Expr::DefaultValue => true,
+    }
+}
+
+/// Answer an unfiltered `SELECT COUNT(*) FROM t` from the table's tracked row
+/// count, without reading its rows. `None` when the query has any other
+/// shape, the table predates row-count tracking, or scans skip corrupt pages
+/// (the count then covers only the rows that can be read).
+fn tracked_count_row(sel: &Select, table_def: &TableDef) -> Option<Row> {
+    if sel.where_clause.is_some()
+        || sel.group_by.is_some()
+        || sel.having.is_some()
+        || scan_skip_corruption_current()
+    {
+        return None;
+    }
+    let count = table_def.row_count?;
+    let mut values = Vec::with_capacity(sel.columns.len());
+    for col in &sel.columns {
+        let SelectColumn::Expr(
+            Expr::AggregateFunc {
+                name,
+                arg: None,
+                distinct: false,
+            },
+            alias,
+        ) = col
+        else {
+            return None;
+        };
+        if !name.eq_ignore_ascii_case("COUNT") {
+            return None;
+        }
+        let column = alias.clone().unwrap_or_else(|| format!("{}(*)", name));
+        values.push((column, Value::Integer(count as i64)));
+    }
+    (!values.is_empty()).then_some(Row { values })
+}
+
+/// Answer an unfiltered `SELECT MIN(col), MAX(col) FROM t` by probing the
+/// first/last entry of the PK tree or a single-column secondary index.
+///
+/// Returns the probed rows (at most one per aggregate) to feed into the regular
+/// aggregation, or `None` when the query shape is not eligible.
+fn min_max_probe_rows(
+    sel: &Select,
+    table_def: &TableDef,
+    indexes: &[IndexDef],
+    pager: &mut impl PageStore,
+) -> Result<Option<Vec<Vec<Value>>>> {
+    if sel.where_clause.is_some() || sel.group_by.is_some() || sel.having.is_some() {
+        return Ok(None);
+    }
+
+    // (btree root, is the PK tree, probe the maximum)
+    let mut probes: Vec<(PageId, bool, bool)> = Vec::new();
+    for col in &sel.columns {
+        let SelectColumn::Expr(
+            Expr::AggregateFunc {
+                name,
+                arg: Some(arg),
+                ..
+            },
+            _,
+        ) = col
+        else {
+            return Ok(None);
+        };
+        let want_max = if name.eq_ignore_ascii_case("MAX") {
+            true
+        } else if name.eq_ignore_ascii_case("MIN") {
+            false
+        } else {
+            return Ok(None);
+        };
+        let Expr::ColumnRef(col_name) = arg.as_ref() else {
+            return Ok(None);
+        };
+        let Some(col_idx) = table_def.column_index(col_name) else {
+            return Ok(None);
+        };
+        if !key_order_matches_value_order(&table_def.columns[col_idx]) {
+            return Ok(None);
+        }
+
+        if table_def.pk_columns.len() == 1 && table_def.pk_columns[0] == *col_name {
+            probes.push((table_def.data_btree_root, true, want_max));
+            continue;
+        }
+        // NULLs are never indexed, which matches MIN/MAX ignoring NULLs.
+        let index = sel
+            .index_hints
+            .is_empty()
+            .then(|| {
+                indexes.iter().find(|idx| {
+                    idx.index_type == IndexType::BTree
+                        && !idx.building
+                        && idx.column_names.len() == 1
+                        && idx.column_names[0] == *col_name
+                })
+            })
+            .flatten();
+        match index {
+            Some(idx) => probes.push((idx.btree_root, false, want_max)),
+            None => return Ok(None),
+        }
+    }
+    if probes.is_empty() {
+        return Ok(None);
+    }
+
+    let data_btree = BTree::open(table_def.data_btree_root);
+    let mut rows = Vec::with_capacity(probes.len());
+    for (root, is_pk_tree, want_max) in probes {
+        let mut edge: Option<Vec<u8>> = None;
+        let take_first = |_: &[u8], v: &[u8]| {
+            edge = Some(v.to_vec());
+            Ok(false)
+        };
+        let btree = BTree::open(root);
+        if want_max {
+            btree.scan_rev(pager, take_first)?;
+        } else {
+            btree.scan(pager, take_first)?;
+        }
+        let Some(value) = edge else {
+            // Empty tree: aggregation over no rows yields NULL.
+            continue;
+        };
+        let row_data = if is_pk_tree {
+            value
+        } else {
+            data_btree.search(pager, &value)?.ok_or_else(|| {
+                MuroError::Corruption("index entry references a missing row".into())
+            })?
+        };
+        rows.push(deserialize_row_versioned(
+            &row_data,
+            &table_def.columns,
+            table_def.row_format_version,
+        )?);
+    }
+    Ok(Some(rows))
+}
+
 pub(super) fn sort_rows(rows: &mut [Row], order_items: &[OrderByItem]) {
     rows.sort_by(|a, b| {
         for item in order_items {
-            if let Expr::ColumnRef(col) = &item.expr {
-                let va = a.get(col);
-                let vb = b.get(col);
-                let ord = cmp_values(va, vb);
+            if let Some((col, collation)) = order_by_column(&item.expr) {
+                let va = a.get(col).map(|v| collation.key_value(v));
+                let vb = b.get(col).map(|v| collation.key_value(v));
+                let ord = cmp_values(va.as_deref(), vb.as_deref());
                 if ord != std::cmp::Ordering::Equal {
                     return if item.descending { ord.reverse() } else { ord };
                 }
@@ -805,12 +1351,8 @@ pub(super) fn matches_where(
     match where_clause {
         None => Ok(true),
         Some(expr) => {
-            let result = eval_expr(expr, &|name| {
-                table_def
-                    .column_index(name)
-                    .and_then(|i| values.get(i).cloned())
-            })?;
-            Ok(is_truthy(&result))
+            let columns = |name: &str| table_def.column_index(name).and_then(|i| values.get(i));
+            filter_matches(expr, &mut |e| eval_expr_ref(e, &columns))
         }
     }
 }
@@ -823,8 +1365,15 @@ pub(super) fn is_row_independent_expr(expr: &Expr) -> bool {
             is_row_independent_expr(left) && is_row_independent_expr(right)
         }
         Expr::UnaryOp { operand, .. } => is_row_independent_expr(operand),
-        Expr::Like { expr, pattern, .. } => {
-            is_row_independent_expr(expr) && is_row_independent_expr(pattern)
+        Expr::Like {
+            expr,
+            pattern,
+            escape,
+            ..
+        } => {
+            is_row_independent_expr(expr)
+                && is_row_independent_expr(pattern)
+                && escape.as_deref().is_none_or(is_row_independent_expr)
         }
         Expr::InList { expr, list, .. } => {
             is_row_independent_expr(expr) && list.iter().all(is_row_independent_expr)
@@ -855,7 +1404,7 @@ pub(super) fn is_row_independent_expr(expr: &Expr) -> bool {
                     .map(|e| is_row_independent_expr(e))
                     .unwrap_or(true)
         }
-        Expr::Cast { expr, .. } => is_row_independent_expr(expr),
+        Expr::Cast { expr, .. } | Expr::Collate { expr, .. } => is_row_independent_expr(expr),
         Expr::AggregateFunc { arg, .. } => arg
             .as_ref()
             .map(|e| is_row_independent_expr(e))
@@ -872,22 +1421,23 @@ pub(super) fn is_row_independent_expr(expr: &Expr) -> bool {
     }
 }
 
+/// Check `values` against the WHERE clause, starting a new row in `memo`.
+/// A matching row is then projected with the same memo, so sub-expressions
+/// shared with the select list are not computed again.
 pub(super) fn matches_where_with_fts(
     where_clause: &Option<Expr>,
     table_def: &TableDef,
     values: &[Value],
     fts_ctx: Option<&FtsEvalContext>,
+    memo: &mut ExprMemo,
 ) -> Result<bool> {
+    memo.start_row();
     match where_clause {
         None => Ok(true),
         Some(expr) => {
-            let expr = materialize_fts_expr(expr, table_def, values, fts_ctx);
-            let result = eval_expr(&expr, &|name| {
-                table_def
-                    .column_index(name)
-                    .and_then(|i| values.get(i).cloned())
-            })?;
-            Ok(is_truthy(&result))
+            let columns = |name: &str| table_def.column_index(name).and_then(|i| values.get(i));
+            let fts = |e: &Expr| fts_expr_value(e, table_def, values, fts_ctx);
+            filter_matches(expr, &mut |e| memo.eval(e, &columns, Some(&fts)))
         }
     }
 }
@@ -898,8 +1448,11 @@ pub(super) fn build_row_with_fts_and_extras(
     select_columns: &[SelectColumn],
     fts_ctx: Option<&FtsEvalContext>,
     extra_columns: &[String],
+    memo: &mut ExprMemo,
 ) -> Result<Row> {
     let mut row_values = Vec::with_capacity(select_columns.len().max(table_def.columns.len()));
+    let columns = |name: &str| table_def.column_index(name).and_then(|i| values.get(i));
+    let fts = |e: &Expr| fts_expr_value(e, table_def, values, fts_ctx);
 
     for sel_col in select_columns {
         match sel_col {
@@ -913,14 +1466,9 @@ pub(super) fn build_row_with_fts_and_extras(
                 }
             }
             SelectColumn::Expr(expr, alias) => {
-                let expr = materialize_fts_expr(expr, table_def, values, fts_ctx);
-                let val = eval_expr(&expr, &|name| {
-                    table_def
-                        .column_index(name)
-                        .and_then(|i| values.get(i).cloned())
-                })?;
+                let val = memo.eval(expr, &columns, Some(&fts))?;
                 let name = alias.clone().unwrap_or_else(|| match expr {
-                    Expr::ColumnRef(ref n) => n.clone(),
+                    Expr::ColumnRef(n) => n.clone(),
                     _ => "?column?".to_string(),
                 });
                 row_values.push((name, val));
@@ -969,7 +1517,7 @@ fn collect_extra_order_by_columns(
     // Find ORDER BY columns not in SELECT
     let mut extra = Vec::new();
     for item in order_items {
-        if let Expr::ColumnRef(name) = &item.expr {
+        if let Some((name, _)) = order_by_column(&item.expr) {
             if !selected_names.contains(name) {
                 extra.push(name.clone());
             }
@@ -0,0 +1,317 @@
+//! `UNION` / `UNION ALL`.
+//!
+//! Every branch runs to completion first. Branches must return the same
+//! number of columns; the result takes its column names from the first
+//! branch. Where branches disagree on a column's type, every value in that
+//! column is converted to one common type (see [`UnionType`]) before
+//! duplicates are removed, so `1` and `'1'` count as the same row. The
+//! operators then apply left to right: `UNION ALL` appends a branch, and
+//! `UNION` appends it and drops duplicate rows from everything so far.
+//! ORDER BY, LIMIT and OFFSET apply to the combined result.
+
+use super::*;
+use rust_decimal::prelude::ToPrimitive;
+
+fn select_col_count(sel: &Select) -> Option<usize> {
+    // Star expands to all table columns, so we can't determine the count statically
+    if sel.columns.iter().any(|c| matches!(c, SelectColumn::Star)) {
+        None
+    } else {
+        Some(sel.columns.len())
+    }
+}
+
+pub(super) fn exec_set_query(
+    sq: &SetQuery,
+    pager: &mut impl PageStore,
+    catalog: &mut SystemCatalog,
+) -> Result<ExecResult> {
+    let branches: Vec<&Select> = std::iter::once(&sq.left)
+        .chain(sq.ops.iter().map(|(_, sel)| sel))
+        .collect();
+
+    // Column counts known from the AST are checked before anything runs.
+    let static_counts: Vec<Option<usize>> =
+        branches.iter().map(|sel| select_col_count(sel)).collect();
+    let mut expected_col_count = static_counts.iter().flatten().next().copied();
+    if let Some(expected) = expected_col_count {
+        check_union_col_count(expected, static_counts.iter().flatten().copied())?;
+    }
+
+    let mut results = Vec::with_capacity(branches.len());
+    for sel in &branches {
+        let rows = exec_select_returning_rows(sel, pager, catalog)?;
+        // `SELECT *` branches are checked against their rows.
+        if let Some(first_row) = rows.first() {
+            let actual = first_row.values.len();
+            match expected_col_count {
+                Some(expected) => check_union_col_count(expected, [actual])?,
+                None => expected_col_count = Some(actual),
+            }
+        }
+        results.push(rows);
+    }
+
+    let col_names = union_column_names(&sq.left, &results);
+    coerce_union_columns(&mut results);
+
+    let mut rows = Vec::new();
+    let ops = std::iter::once(SetOp::UnionAll).chain(sq.ops.iter().map(|(op, _)| *op));
+    for (op, branch_rows) in ops.zip(results) {
+        for mut row in branch_rows {
+            for ((name, _), first_name) in row.values.iter_mut().zip(&col_names) {
+                name.clone_from(first_name);
+            }
+            rows.push(row);
+        }
+        // UNION (without ALL) removes duplicates
+        if op == SetOp::Union {
+            let mut seen = HashSet::new();
+            rows.retain(|row| {
+                let key: Vec<ValueKey> = row
+                    .values
+                    .iter()
+                    .map(|(_, v)| ValueKey(v.clone()))
+                    .collect();
+                seen.insert(key)
+            });
+        }
+    }
+
+    if let (Some(order_items), false) = (&sq.order_by, rows.is_empty()) {
+        sort_union_rows(&mut rows, order_items, &col_names)?;
+    }
+
+    // Apply OFFSET
+    if let Some(offset) = sq.offset {
+        let offset = offset as usize;
+        if offset >= rows.len() {
+            rows.clear();
+        } else {
+            rows = rows.into_iter().skip(offset).collect();
+        }
+    }
+
+    // Apply LIMIT
+    if let Some(limit) = sq.limit {
+        rows.truncate(limit as usize);
+    }
+
+    Ok(ExecResult::Rows(rows))
+}
+
+fn check_union_col_count(expected: usize, actual: impl IntoIterator<Item = usize>) -> Result<()> {
+    match actual.into_iter().find(|&n| n != expected) {
+        Some(n) => Err(MuroError::Execution(format!(
+            "UNION queries must have the same number of columns (expected {}, got {})",
+            expected, n
+        ))),
+        None => Ok(()),
+    }
+}
+
+/// Result column names: the first branch's. When the first branch returns
+/// no rows they come from its select list, if every item is a plain column
+/// or has an alias; otherwise from the first branch that returned rows.
+fn union_column_names(first: &Select, results: &[Vec<Row>]) -> Vec<String> {
+    if let Some(row) = results[0].first() {
+        return row.values.iter().map(|(n, _)| n.clone()).collect();
+    }
+    let from_select_list: Option<Vec<String>> = first
+        .columns
+        .iter()
+        .map(|col| match col {
+            SelectColumn::Expr(_, Some(alias)) => Some(alias.clone()),
+            SelectColumn::Expr(Expr::ColumnRef(name), None) if !name.ends_with(".*") => {
+                Some(name.clone())
+            }
+            _ => None,
+        })
+        .collect();
+    from_select_list
+        .or_else(|| {
+            let row = results.iter().find_map(|rows| rows.first())?;
+            Some(row.values.iter().map(|(n, _)| n.clone()).collect())
+        })
+        .unwrap_or_default()
+}
+
+/// The type a UNION result column takes when its branches disagree.
+///
+/// - Integers and DECIMALs combine as DECIMAL; either with a FLOAT as FLOAT.
+/// - DATE, DATETIME and TIMESTAMP combine as DATETIME.
+/// - Any other mix is VARCHAR, holding each value's text form, unless a
+///   VARBINARY is involved, which makes the column VARBINARY.
+///
+/// NULL combines with every type and stays NULL.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum UnionType {
+    Integer,
+    Decimal,
+    Float,
+    Date,
+    DateTime,
+    Timestamp,
+    Varchar,
+    Varbinary,
+    Uuid,
+}
+
+impl UnionType {
+    fn of(value: &Value) -> Option<Self> {
+        Some(match value {
+            Value::Integer(_) => UnionType::Integer,
+            Value::Decimal(_) => UnionType::Decimal,
+            Value::Float(_) => UnionType::Float,
+            Value::Date(_) => UnionType::Date,
+            Value::DateTime(_) => UnionType::DateTime,
+            Value::Timestamp(_) => UnionType::Timestamp,
+            Value::Varchar(_) => UnionType::Varchar,
+            Value::Varbinary(_) => UnionType::Varbinary,
+            Value::Uuid(_) => UnionType::Uuid,
+            Value::Null => return None,
+        })
+    }
+
+    fn is_numeric(self) -> bool {
+        matches!(
+            self,
+            UnionType::Integer | UnionType::Decimal | UnionType::Float
+        )
+    }
+
+    fn is_temporal(self) -> bool {
+        matches!(
+            self,
+            UnionType::Date | UnionType::DateTime | UnionType::Timestamp
+        )
+    }
+
+    fn combine(self, other: Self) -> Self {
+        use UnionType::*;
+        match (self, other) {
+            (a, b) if a == b => a,
+            (a, b) if a.is_numeric() && b.is_numeric() => {
+                if a == Float || b == Float {
+                    Float
+                } else {
+                    Decimal
+                }
+            }
+            (a, b) if a.is_temporal() && b.is_temporal() => DateTime,
+            (Varbinary, _) | (_, Varbinary) => Varbinary,
+            _ => Varchar,
+        }
+    }
+
+    fn convert(self, value: Value) -> Value {
+        match (self, value) {
+            (_, Value::Null) => Value::Null,
+            (UnionType::Decimal, Value::Integer(n)) => {
+                Value::Decimal(rust_decimal::Decimal::from(n))
+            }
+            (UnionType::Float, Value::Integer(n)) => Value::Float(n as f64),
+            (UnionType::Float, Value::Decimal(d)) => Value::Float(d.to_f64().unwrap_or(f64::NAN)),
+            (UnionType::DateTime, Value::Date(d)) => Value::DateTime(i64::from(d) * 1_000_000),
+            (UnionType::DateTime, Value::Timestamp(ts)) => Value::DateTime(ts),
+            (UnionType::Varchar, v @ Value::Varchar(_)) => v,
+            (UnionType::Varchar, v) => Value::Varchar(v.to_string()),
+            (UnionType::Varbinary, v @ Value::Varbinary(_)) => v,
+            (UnionType::Varbinary, Value::Varchar(s)) => Value::Varbinary(s.into_bytes()),
+            (UnionType::Varbinary, v) => Value::Varbinary(v.to_string().into_bytes()),
+            (_, v) => v,
+        }
+    }
+}
+
+/// Convert the columns whose branches disagree on type to their common
+/// [`UnionType`]. Columns that already agree are left untouched.
+fn coerce_union_columns(results: &mut [Vec<Row>]) {
+    let width = results
+        .iter()
+        .find_map(|rows| rows.first())
+        .map_or(0, |row| row.values.len());
+    for col in 0..width {
+        let mut common: Option<UnionType> = None;
+        let mut mixed = false;
+        for (_, value) in results
+            .iter()
+            .flatten()
+            .filter_map(|row| row.values.get(col))
+        {
+            if let Some(ty) = UnionType::of(value) {
+                mixed |= common.is_some_and(|c| c != ty);
+                common = Some(common.map_or(ty, |c| c.combine(ty)));
+            }
+        }
+        let Some(target) = common.filter(|_| mixed) else {
+            continue;
+        };
+        for (_, value) in results
+            .iter_mut()
+            .flatten()
+            .filter_map(|row| row.values.get_mut(col))
+        {
+            *value = target.convert(std::mem::replace(value, Value::Null));
+        }
+    }
+}
+
+/// Sort a UNION result. Each ORDER BY item must name a result column (as
+/// the first branch names it) or give its 1-based position.
+fn sort_union_rows(
+    rows: &mut [Row],
+    order_items: &[OrderByItem],
+    col_names: &[String],
+) -> Result<()> {
+    let keys = order_items
+        .iter()
+        .map(|item| {
+            let (position, collation) = match &item.expr {
+                Expr::IntLiteral(n) => {
+                    let position = usize::try_from(*n)
+                        .ok()
+                        .filter(|&n| n >= 1 && n <= col_names.len())
+                        .ok_or_else(|| {
+                            MuroError::Execution(format!(
+                                "Unknown column '{}' in UNION ORDER BY",
+                                n
+                            ))
+                        })?;
+                    (position - 1, Collation::Binary)
+                }
+                expr => {
+                    let (name, collation) = order_by_column(expr).ok_or_else(|| {
+                        MuroError::Execution(
+                            "ORDER BY on a UNION must name a result column or its position".into(),
+                        )
+                    })?;
+                    let position = col_names
+                        .iter()
+                        .position(|n| n.eq_ignore_ascii_case(name))
+                        .ok_or_else(|| {
+                            MuroError::Execution(format!(
+                                "Unknown column '{}' in UNION ORDER BY",
+                                name
+                            ))
+                        })?;
+                    (position, collation)
+                }
+            };
+            Ok((position, collation, item.descending))
+        })
+        .collect::<Result<Vec<_>>>()?;
+
+    rows.sort_by(|a, b| {
+        for &(position, collation, descending) in &keys {
+            let va = a.get_at(position).map(|v| collation.key_value(v));
+            let vb = b.get_at(position).map(|v| collation.key_value(v));
+            let ord = cmp_values(va.as_deref(), vb.as_deref());
+            if ord != std::cmp::Ordering::Equal {
+                return if descending { ord.reverse() } else { ord };
+            }
+        }
+        std::cmp::Ordering::Equal
+    });
+    Ok(())
+}
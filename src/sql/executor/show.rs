@@ -1,4 +1,5 @@
 use super::*;
+use crate::btree::layout::TreeLayout;
 
 pub(super) fn exec_show_tables(
     pager: &mut impl PageStore,
@@ -14,6 +15,119 @@ pub(super) fn exec_show_tables(
     Ok(ExecResult::Rows(rows))
 }
 
+/// One row per table (`Tree = PRIMARY`): its row count, the node pages of
+/// its data tree and of its indexes, how many indexes it has, and the leaf
+/// clustering of its data tree. Each index follows as a row of its own with
+/// only the per-tree columns set.
+pub(super) fn exec_show_table_status(
+    pager: &mut impl PageStore,
+    catalog: &mut SystemCatalog,
+) -> Result<ExecResult> {
+    let page_size = crate::storage::page::PAGE_SIZE as u64;
+    let mut rows = Vec::new();
+    for table_name in catalog.list_tables(pager)? {
+        let Some(table_def) = catalog.get_table(pager, &table_name)? else {
+            continue;
+        };
+        let data_layout = BTree::open(table_def.data_btree_root).layout(pager)?;
+        let data_pages = tree_pages(&data_layout);
+        let indexes = catalog.get_indexes_for_table(pager, &table_name)?;
+        let mut index_layouts = Vec::with_capacity(indexes.len());
+        for idx in &indexes {
+            index_layouts.push(BTree::open(idx.btree_root).layout(pager)?);
+        }
+        let index_pages: u64 = index_layouts.iter().map(tree_pages).sum();
+
+        let mut values = vec![
+            ("Name".to_string(), Value::Varchar(table_name.clone())),
+            ("Tree".to_string(), Value::Varchar("PRIMARY".to_string())),
+            ("Engine".to_string(), Value::Varchar("MuroDB".to_string())),
+            (
+                "Rows".to_string(),
+                Value::Integer(table_def.approximate_row_count() as i64),
+            ),
+            (
+                "Rows_source".to_string(),
+                Value::Varchar(rows_source(&table_def).to_string()),
+            ),
+            ("Data_pages".to_string(), Value::Integer(data_pages as i64)),
+            (
+                "Index_pages".to_string(),
+                Value::Integer(index_pages as i64),
+            ),
+            ("Indexes".to_string(), Value::Integer(indexes.len() as i64)),
+            (
+                "Data_length".to_string(),
+                Value::Integer((data_pages * page_size) as i64),
+            ),
+            (
+                "Index_length".to_string(),
+                Value::Integer((index_pages * page_size) as i64),
+            ),
+        ];
+        values.extend(tree_status(&data_layout));
+        rows.push(Row { values });
+
+        for (idx, layout) in indexes.iter().zip(&index_layouts) {
+            let mut values = vec![
+                ("Name".to_string(), Value::Varchar(table_name.clone())),
+                ("Tree".to_string(), Value::Varchar(idx.name.clone())),
+            ];
+            for column in [
+                "Engine",
+                "Rows",
+                "Rows_source",
+                "Data_pages",
+                "Index_pages",
+                "Indexes",
+                "Data_length",
+                "Index_length",
+            ] {
+                values.push((column.to_string(), Value::Null));
+            }
+            values.extend(tree_status(layout));
+            rows.push(Row { values });
+        }
+    }
+    Ok(ExecResult::Rows(rows))
+}
+
+/// Where `Rows` of SHOW TABLE STATUS comes from: the tracked count, the last
+/// ANALYZE TABLE of a table that predates tracking, or neither.
+fn rows_source(table_def: &TableDef) -> &'static str {
+    if table_def.row_count.is_some() {
+        "exact"
+    } else if table_def.stats_row_count > 0 {
+        "analyze"
+    } else {
+        "unknown"
+    }
+}
+
+/// Node pages of a B-tree, without overflow chains.
+fn tree_pages(layout: &TreeLayout) -> u64 {
+    layout.leaf_pages.len() as u64 + layout.internal_pages
+}
+
+/// The per-tree columns of SHOW TABLE STATUS: node pages, leaf pages, and
+/// the mean gap between consecutive leaves.
+fn tree_status(layout: &TreeLayout) -> Vec<(String, Value)> {
+    vec![
+        (
+            "Pages".to_string(),
+            Value::Integer(tree_pages(layout) as i64),
+        ),
+        (
+            "Leaf_pages".to_string(),
+            Value::Integer(layout.leaf_pages.len() as i64),
+        ),
+        (
+            "Leaf_gap".to_string(),
+            layout.mean_leaf_gap().map_or(Value::Null, Value::Float),
+        ),
+    ]
+}
+
 pub(super) fn exec_show_create_table(
     table_name: &str,
     pager: &mut impl PageStore,
@@ -23,7 +137,30 @@ pub(super) fn exec_show_create_table(
         .get_table(pager, table_name)?
         .ok_or_else(|| MuroError::Schema(format!("Table '{}' not found", table_name)))?;
 
-    let mut sql = format!("CREATE TABLE {} (\n", table_name);
+    let temporary = catalog.is_temp_table(pager, table_name)?;
+    let indexes = catalog.get_indexes_for_table(pager, table_name)?;
+    let sql = create_table_sql(&table_def, &indexes, temporary);
+
+    let rows = vec![Row {
+        values: vec![
+            ("Table".to_string(), Value::Varchar(table_name.to_string())),
+            ("Create Table".to_string(), Value::Varchar(sql)),
+        ],
+    }];
+    Ok(ExecResult::Rows(rows))
+}
+
+/// The `SHOW CREATE TABLE` text of `table_def`: its columns, constraints,
+/// B-tree indexes and foreign keys. FULLTEXT indexes are left out; see
+/// [`fulltext_index_sql`].
+pub(crate) fn create_table_sql(
+    table_def: &TableDef,
+    indexes: &[IndexDef],
+    temporary: bool,
+) -> String {
+    let table_name = &table_def.name;
+    let temporary = if temporary { "TEMPORARY " } else { "" };
+    let mut sql = format!("CREATE {}TABLE {} (\n", temporary, table_name);
     let visible_columns: Vec<&ColumnDef> =
         table_def.columns.iter().filter(|c| !c.is_hidden).collect();
     let is_composite_pk = table_def.is_composite_pk();
@@ -37,27 +174,46 @@ pub(super) fn exec_show_create_table(
         ));
     }
 
-    // Collect composite UNIQUE indexes
-    let indexes = catalog.get_indexes_for_table(pager, table_name)?;
-    for idx in &indexes {
-        if idx.is_unique && idx.column_names.len() > 1 {
+    // Composite UNIQUE constraints, then indexes created with CREATE INDEX.
+    // Single-column UNIQUE constraints are rendered on their column below.
+    for idx in indexes {
+        if idx.index_type == IndexType::BTree
+            && is_constraint_index(table_def, idx)
+            && idx.column_names.len() > 1
+        {
             table_constraints.push(format!("  UNIQUE ({})", idx.column_names.join(", ")));
         }
     }
+    for idx in indexes {
+        if idx.index_type == IndexType::BTree && !is_constraint_index(table_def, idx) {
+            let parts = idx
+                .column_names
+                .iter()
+                .enumerate()
+                .map(|(i, name)| match idx.expressions.get(i) {
+                    Some(Some(expr)) => format!("({})", expr),
+                    _ => name.clone(),
+                })
+                .collect::<Vec<_>>();
+            table_constraints.push(format!(
+                "  {}KEY {} ({}){}",
+                if idx.is_unique { "UNIQUE " } else { "" },
+                idx.name,
+                parts.join(", "),
+                storage_options_sql(idx.fill_factor)
+            ));
+        }
+    }
     for fk in &table_def.foreign_keys {
-        table_constraints.push(format!(
-            "  FOREIGN KEY ({}) REFERENCES {}({}) ON DELETE {} ON UPDATE {}",
-            fk.columns.join(", "),
-            fk.ref_table,
-            fk.ref_columns.join(", "),
-            fk_action_to_sql(&fk.on_delete),
-            fk_action_to_sql(&fk.on_update),
-        ));
+        table_constraints.push(format!("  {}", foreign_key_sql(fk)));
     }
 
     let total_items = visible_columns.len() + table_constraints.len();
     for (i, col) in visible_columns.iter().enumerate() {
         sql.push_str(&format!("  {} {}", col.name, col.data_type));
+        if col.collation != Collation::Binary {
+            sql.push_str(&format!(" COLLATE {}", col.collation));
+        }
         if col.is_primary_key && !is_composite_pk {
             sql.push_str(" PRIMARY KEY");
         }
@@ -73,9 +229,18 @@ pub(super) fn exec_show_create_table(
         if let Some(default) = &col.default_value {
             match default {
                 DefaultValue::Integer(n) => sql.push_str(&format!(" DEFAULT {}", n)),
-                DefaultValue::Float(n) => sql.push_str(&format!(" DEFAULT {}", n)),
-                DefaultValue::String(s) => sql.push_str(&format!(" DEFAULT '{}'", s)),
+                DefaultValue::Float(n) => {
+                    sql.push_str(&format!(" DEFAULT {}", format_float_literal(*n)))
+                }
+                DefaultValue::String(s) => {
+                    sql.push_str(&format!(" DEFAULT '{}'", s.replace('\'', "''")))
+                }
                 DefaultValue::Null => sql.push_str(" DEFAULT NULL"),
+                // Binary operators render parenthesized already.
+                DefaultValue::Expr(e) if e == "CURRENT_TIMESTAMP" || e.starts_with('(') => {
+                    sql.push_str(&format!(" DEFAULT {}", e))
+                }
+                DefaultValue::Expr(e) => sql.push_str(&format!(" DEFAULT ({})", e)),
             }
         }
         if let Some(check) = &col.check_expr {
@@ -94,13 +259,124 @@ pub(super) fn exec_show_create_table(
         sql.push('\n');
     }
     sql.push(')');
+    // The next value the counter hands out, once it has handed one out.
+    if table_def.next_rowid > 0 && table_def.columns.iter().any(|c| c.auto_increment) {
+        sql.push_str(&format!(
+            " AUTO_INCREMENT = {}",
+            table_def.next_rowid.saturating_add(1)
+        ));
+    }
+    sql.push_str(&storage_options_sql(table_def.fill_factor));
+    sql
+}
 
-    let rows = vec![Row {
-        values: vec![
-            ("Table".to_string(), Value::Varchar(table_name.to_string())),
-            ("Create Table".to_string(), Value::Varchar(sql)),
-        ],
-    }];
+/// `FOREIGN KEY (...) REFERENCES parent(...) ON DELETE ... ON UPDATE ...`.
+pub(crate) fn foreign_key_sql(fk: &ForeignKeyDef) -> String {
+    format!(
+        "FOREIGN KEY ({}) REFERENCES {}({}) ON DELETE {} ON UPDATE {}",
+        fk.columns.join(", "),
+        fk.ref_table,
+        fk.ref_columns.join(", "),
+        fk_action_to_sql(&fk.on_delete),
+        fk_action_to_sql(&fk.on_update),
+    )
+}
+
+/// The `CREATE FULLTEXT INDEX` statement that recreates `idx` with all of
+/// its options.
+pub(crate) fn fulltext_index_sql(idx: &IndexDef) -> String {
+    format!(
+        "CREATE FULLTEXT INDEX {} ON {}({}) WITH PARSER ngram \
+         OPTIONS (n=2, normalize='nfkc', stop_filter={}, stop_df_ratio_ppm={}, \
+         stop_fallback='{}', stop_fallback_max_docs={})",
+        idx.name,
+        idx.table_name,
+        idx.column_names.join(", "),
+        if idx.fts_stop_filter { "on" } else { "off" },
+        idx.fts_stop_df_ratio_ppm,
+        idx.fts_stop_fallback.as_str(),
+        idx.fts_stop_fallback_max_docs,
+    )
+}
+
+/// ` WITH (fill_factor = N)` for a non-default fill factor.
+fn storage_options_sql(fill_factor: u8) -> String {
+    if fill_factor == DEFAULT_FILL_FACTOR {
+        String::new()
+    } else {
+        format!(" WITH (fill_factor = {})", fill_factor)
+    }
+}
+
+/// Whether `idx` backs a UNIQUE column or table constraint (named
+/// `auto_unique_*` by CREATE TABLE / ALTER TABLE) rather than coming from
+/// CREATE INDEX.
+fn is_constraint_index(table_def: &TableDef, idx: &IndexDef) -> bool {
+    if !idx.is_unique || idx.has_expressions() || !idx.name.starts_with("auto_unique_") {
+        return false;
+    }
+    match idx.column_names.as_slice() {
+        [col] => table_def
+            .columns
+            .iter()
+            .any(|c| c.name == *col && c.is_unique),
+        _ => true,
+    }
+}
+
+/// One row per key part of every index on the table, in key order.
+pub(super) fn exec_show_indexes(
+    table_name: &str,
+    pager: &mut impl PageStore,
+    catalog: &mut SystemCatalog,
+) -> Result<ExecResult> {
+    let table_def = catalog
+        .get_table(pager, table_name)?
+        .ok_or_else(|| MuroError::Schema(format!("Table '{}' not found", table_name)))?;
+    let mut rows = Vec::new();
+    for idx in catalog.get_indexes_for_table(pager, table_name)? {
+        let unique = if idx.is_unique { "YES" } else { "NO" };
+        // An interrupted build shows the rows indexed so far out of the
+        // table's estimated row count.
+        let (status, progress) = if idx.building {
+            let done = catalog
+                .get_index_build(pager, &idx.name)?
+                .unwrap_or_default()
+                .rows_done;
+            let total = BTree::open(table_def.data_btree_root).estimate_entries(pager)?;
+            (
+                "building",
+                Value::Varchar(format!("{}/{} rows", done, total.max(done))),
+            )
+        } else {
+            ("ready", Value::Null)
+        };
+        let index_type = match idx.index_type {
+            IndexType::BTree => "BTREE",
+            IndexType::Fulltext => "FULLTEXT",
+        };
+        for (seq, column) in idx.column_names.iter().enumerate() {
+            rows.push(Row {
+                values: vec![
+                    ("Table".to_string(), Value::Varchar(table_name.to_string())),
+                    ("Key_name".to_string(), Value::Varchar(idx.name.clone())),
+                    ("Seq_in_index".to_string(), Value::Integer(seq as i64 + 1)),
+                    ("Column_name".to_string(), Value::Varchar(column.clone())),
+                    ("Unique".to_string(), Value::Varchar(unique.to_string())),
+                    (
+                        "Index_type".to_string(),
+                        Value::Varchar(index_type.to_string()),
+                    ),
+                    (
+                        "Root_page".to_string(),
+                        Value::Integer(idx.btree_root as i64),
+                    ),
+                    ("Status".to_string(), Value::Varchar(status.to_string())),
+                    ("Build_progress".to_string(), progress.clone()),
+                ],
+            });
+        }
+    }
     Ok(ExecResult::Rows(rows))
 }
 
@@ -128,13 +404,16 @@ pub(super) fn exec_describe(
         };
         let default_str = match &col.default_value {
             Some(DefaultValue::Integer(n)) => n.to_string(),
-            Some(DefaultValue::Float(n)) => n.to_string(),
+            Some(DefaultValue::Float(n)) => format_float(*n),
             Some(DefaultValue::String(s)) => s.clone(),
             Some(DefaultValue::Null) => "NULL".to_string(),
+            Some(DefaultValue::Expr(e)) => e.clone(),
             None => "NULL".to_string(),
         };
         let extra_str = if col.auto_increment {
             "auto_increment"
+        } else if matches!(col.default_value, Some(DefaultValue::Expr(_))) {
+            "DEFAULT_GENERATED"
         } else {
             ""
         };
@@ -0,0 +1,142 @@
+use crate::crypto::aead::{MasterKey, PageCrypto};
+use crate::error::Result;
+use rand::RngCore;
+use std::fs::{File, OpenOptions};
+use std::io::{BufReader, BufWriter, ErrorKind, Read, Seek, SeekFrom, Write};
+use std::path::PathBuf;
+use zeroize::Zeroize;
+
+/// Plaintext bytes buffered before a chunk is sealed and written.
+const SPILL_CHUNK_BYTES: usize = 64 * 1024;
+
+/// Removes the temp file when dropped, whether the spill was read back or not.
+struct TempPath(PathBuf);
+
+impl Drop for TempPath {
+    fn drop(&mut self) {
+        let _ = std::fs::remove_file(&self.0);
+    }
+}
+
+/// Temp file for records an operator spilled after running over its memory
+/// budget.
+///
+/// Records are grouped into chunks sealed with AES-GCM-SIV under a key that
+/// exists only in this process, so spilled rows are no more readable at rest
+/// than the database pages they came from.
+pub(super) struct SpillFile {
+    path: TempPath,
+    writer: BufWriter<File>,
+    crypto: PageCrypto,
+    chunk: Vec<u8>,
+    chunks: u64,
+}
+
+impl SpillFile {
+    pub(super) fn create() -> Result<Self> {
+        let mut key = [0u8; 32];
+        rand::thread_rng().fill_bytes(&mut key);
+        let crypto = PageCrypto::new(&MasterKey::new(key));
+        key.zeroize();
+
+        let path = std::env::temp_dir().join(format!(
+            "murodb-spill-{}-{:016x}.tmp",
+            std::process::id(),
+            rand::random::<u64>()
+        ));
+        let file = OpenOptions::new()
+            .read(true)
+            .write(true)
+            .create_new(true)
+            .open(&path)?;
+        Ok(SpillFile {
+            path: TempPath(path),
+            writer: BufWriter::new(file),
+            crypto,
+            chunk: Vec::with_capacity(SPILL_CHUNK_BYTES),
+            chunks: 0,
+        })
+    }
+
+    /// Append a record: a sequence number and an opaque payload.
+    pub(super) fn push(&mut self, seq: u64, payload: &[u8]) -> Result<()> {
+        self.chunk.extend_from_slice(&seq.to_le_bytes());
+        self.chunk
+            .extend_from_slice(&(payload.len() as u32).to_le_bytes());
+        self.chunk.extend_from_slice(payload);
+        if self.chunk.len() >= SPILL_CHUNK_BYTES {
+            self.write_chunk()?;
+        }
+        Ok(())
+    }
+
+    /// Finish writing and read the records back in the order they were pushed.
+    pub(super) fn into_reader(mut self) -> Result<SpillReader> {
+        if !self.chunk.is_empty() {
+            self.write_chunk()?;
+        }
+        let mut file = self.writer.into_inner().map_err(|e| e.into_error())?;
+        file.seek(SeekFrom::Start(0))?;
+        Ok(SpillReader {
+            _path: self.path,
+            reader: BufReader::new(file),
+            crypto: self.crypto,
+            chunk: Vec::new(),
+            pos: 0,
+            chunks: 0,
+        })
+    }
+
+    fn write_chunk(&mut self) -> Result<()> {
+        // The chunk number is bound into the AAD, so chunks cannot be
+        // reordered or replayed within the file.
+        let sealed = self.crypto.encrypt(self.chunks, 0, &self.chunk)?;
+        self.writer
+            .write_all(&(sealed.len() as u32).to_le_bytes())?;
+        self.writer.write_all(&sealed)?;
+        self.chunks += 1;
+        self.chunk.clear();
+        Ok(())
+    }
+}
+
+/// Reads back the records of a [`SpillFile`].
+pub(super) struct SpillReader {
+    _path: TempPath,
+    reader: BufReader<File>,
+    crypto: PageCrypto,
+    chunk: Vec<u8>,
+    pos: usize,
+    chunks: u64,
+}
+
+impl SpillReader {
+    /// The next record, or `None` after the last one.
+    pub(super) fn next_record(&mut self) -> Result<Option<(u64, Vec<u8>)>> {
+        if self.pos >= self.chunk.len() && !self.read_chunk()? {
+            return Ok(None);
+        }
+        let header = &self.chunk[self.pos..self.pos + 12];
+        let seq = u64::from_le_bytes(header[0..8].try_into().unwrap());
+        let len = u32::from_le_bytes(header[8..12].try_into().unwrap()) as usize;
+        let start = self.pos + 12;
+        let payload = self.chunk[start..start + len].to_vec();
+        self.pos = start + len;
+        Ok(Some((seq, payload)))
+    }
+
+    fn read_chunk(&mut self) -> Result<bool> {
+        let mut len = [0u8; 4];
+        match self.reader.read_exact(&mut len) {
+            Ok(()) => {}
+            Err(e) if e.kind() == ErrorKind::UnexpectedEof => return Ok(false),
+            Err(e) => return Err(e.into()),
+        }
+        let mut sealed = vec![0u8; u32::from_le_bytes(len) as usize];
+        self.reader.read_exact(&mut sealed)?;
+        self.chunk = self.crypto.decrypt(self.chunks, 0, &sealed)?;
+        self.chunks += 1;
+        self.pos = 0;
+        Ok(true)
+    }
+}
@@ -4,7 +4,15 @@ pub(super) fn value_to_expr(v: &Value) -> Expr {
     match v {
         Value::Integer(n) => Expr::IntLiteral(*n),
         Value::Float(n) => Expr::FloatLiteral(*n),
-        Value::Decimal(d) => Expr::StringLiteral(d.to_string()),
+        // A cast keeps the value a DECIMAL, scale included, for comparisons
+        // with DECIMAL columns.
+        Value::Decimal(d) => {
+            let digits = d.to_string().chars().filter(|c| c.is_ascii_digit()).count() as u32;
+            Expr::Cast {
+                expr: Box::new(Expr::StringLiteral(d.to_string())),
+                target_type: DataType::Decimal(digits.max(d.scale()).max(1), d.scale()),
+            }
+        }
         Value::Date(n) => Expr::Cast {
             expr: Box::new(Expr::StringLiteral(format_date(*n))),
             target_type: DataType::Date,
@@ -118,13 +126,19 @@ pub(super) fn materialize_subqueries(
         Expr::Like {
             expr: e,
             pattern,
+            escape,
             negated,
         } => {
             let e2 = materialize_subqueries(e, pager, catalog)?;
             let p2 = materialize_subqueries(pattern, pager, catalog)?;
+            let esc2 = match escape {
+                Some(esc) => Some(Box::new(materialize_subqueries(esc, pager, catalog)?)),
+                None => None,
+            };
             Ok(Expr::Like {
                 expr: Box::new(e2),
                 pattern: Box::new(p2),
+                escape: esc2,
                 negated: *negated,
             })
         }
@@ -205,6 +219,10 @@ pub(super) fn materialize_subqueries(
                 target_type: *target_type,
             })
         }
+        Expr::Collate { expr, collation } => Ok(Expr::Collate {
+            expr: Box::new(materialize_subqueries(expr, pager, catalog)?),
+            collation: *collation,
+        }),
         Expr::FunctionCall { name, args } => {
             let args2: Vec<Expr> = args
                 .iter()
@@ -247,8 +265,15 @@ pub(super) fn expr_contains_subquery(expr: &Expr) -> bool {
             expr_contains_subquery(left) || expr_contains_subquery(right)
         }
         Expr::UnaryOp { operand, .. } => expr_contains_subquery(operand),
-        Expr::Like { expr, pattern, .. } => {
-            expr_contains_subquery(expr) || expr_contains_subquery(pattern)
+        Expr::Like {
+            expr,
+            pattern,
+            escape,
+            ..
+        } => {
+            expr_contains_subquery(expr)
+                || expr_contains_subquery(pattern)
+                || escape.as_deref().is_some_and(expr_contains_subquery)
         }
         Expr::InList { expr, list, .. } => {
             expr_contains_subquery(expr) || list.iter().any(expr_contains_subquery)
@@ -272,7 +297,7 @@ pub(super) fn expr_contains_subquery(expr: &Expr) -> bool {
                     .any(|(c, t)| expr_contains_subquery(c) || expr_contains_subquery(t))
                 || else_clause.as_deref().is_some_and(expr_contains_subquery)
         }
-        Expr::Cast { expr, .. } => expr_contains_subquery(expr),
+        Expr::Cast { expr, .. } | Expr::Collate { expr, .. } => expr_contains_subquery(expr),
         Expr::FunctionCall { args, .. } => args.iter().any(expr_contains_subquery),
         Expr::AggregateFunc { arg, .. } => arg.as_deref().is_some_and(expr_contains_subquery),
         Expr::GreaterThanZero(inner) => expr_contains_subquery(inner),
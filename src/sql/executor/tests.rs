@@ -639,6 +639,64 @@ fn test_having_max_date_preserves_temporal_type() {
     }
 }
 
+#[test]
+fn test_deserialize_columns_decodes_only_wanted_columns() {
+    let columns = vec![
+        ColumnDef::new("id", DataType::BigInt).primary_key(),
+        ColumnDef::new("name", DataType::Varchar(None)),
+        ColumnDef::new("tiny", DataType::TinyInt),
+        ColumnDef::new("blob", DataType::Varbinary(None)),
+        ColumnDef::new("amount", DataType::Decimal(10, 2)),
+        ColumnDef::new("gone", DataType::Text),
+        ColumnDef::new("ratio", DataType::Double),
+        ColumnDef::new("uuid", DataType::Uuid),
+    ];
+    let values = vec![
+        Value::Integer(7),
+        Value::Varchar("alice".into()),
+        Value::Integer(-3),
+        Value::Varbinary(vec![1, 2, 3]),
+        Value::Decimal(rust_decimal::Decimal::new(1234, 2)),
+        Value::Null,
+        Value::Float(0.5),
+        Value::Uuid([9; 16]),
+    ];
+    let data = serialize_row(&values, &columns);
+    let full = deserialize_row_versioned(&data, &columns, 1).unwrap();
+    assert_eq!(full, values);
+
+    for wanted in [vec![0], vec![2, 6], vec![1, 5, 7], vec![3, 4], vec![7]] {
+        let partial = deserialize_columns(&data, &columns, 1, &wanted).unwrap();
+        for (i, value) in partial.iter().enumerate() {
+            let expected = if wanted.contains(&i) {
+                &values[i]
+            } else {
+                &Value::Null
+            };
+            assert_eq!(value, expected, "column {} of {:?}", i, wanted);
+        }
+    }
+    assert_eq!(
+        deserialize_columns(&[], &columns, 1, &[]).unwrap(),
+        vec![Value::Null; columns.len()]
+    );
+
+    // A column added after the row was written reads as its default, and
+    // only when wanted.
+    let mut widened = columns.clone();
+    widened.push(ColumnDef::new("added", DataType::Int).with_default(DefaultValue::Integer(42)));
+    let partial = deserialize_columns(&data, &widened, 1, &[1, 8]).unwrap();
+    assert_eq!(partial[1], Value::Varchar("alice".into()));
+    assert_eq!(partial[8], Value::Integer(42));
+    let partial = deserialize_columns(&data, &widened, 1, &[0]).unwrap();
+    assert_eq!(partial[8], Value::Null);
+
+    // Truncated rows are still rejected when the damage precedes a wanted
+    // column.
+    let truncated = &data[..data.len() - 20];
+    assert!(deserialize_columns(truncated, &columns, 1, &[7]).is_err());
+}
+
 #[test]
 fn test_encode_value_float_to_temporal_is_non_empty_impossible_key() {
     let key_date = encode_value(&Value::Float(1.5), &DataType::Date);
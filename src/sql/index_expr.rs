@@ -0,0 +1,139 @@
+//! Expression index keys.
+//!
+//! `CREATE INDEX idx ON users ((LOWER(email)))` stores the expression in the
+//! catalog as canonical SQL text. The planner renders WHERE operands the same
+//! way and matches them against that text, so the rendering is deterministic
+//! and parses back to the same expression.
+
+use crate::error::{MuroError, Result};
+use crate::sql::ast::{BinaryOp, Expr, SelectColumn, Statement, UnaryOp};
+use crate::sql::parser::parse_sql;
+use crate::types::format_float_literal;
+
+/// Functions whose result is not a function of the row alone.
+pub(crate) const NON_DETERMINISTIC_FUNCTIONS: &[&str] =
+    &["NOW", "CURRENT_TIMESTAMP", "UUID_V4", "UUID_V7"];
+
+/// Canonical text of `expr` as an index key part, or `None` if the
+/// expression cannot be indexed (unsupported node, bind parameter,
+/// non-deterministic function).
+pub fn index_expr_text(expr: &Expr) -> Option<String> {
+    render_expr(expr, ExprContext::Index)
+}
+
+/// Canonical text of a column `DEFAULT` expression, or `None` if it cannot
+/// be stored (unsupported node, bind parameter, column reference). Unlike
+/// index keys, defaults may call non-deterministic functions; bare
+/// `CURRENT_TIMESTAMP` keeps its keyword spelling.
+pub fn default_expr_text(expr: &Expr) -> Option<String> {
+    render_expr(expr, ExprContext::Default)
+}
+
+#[derive(Clone, Copy, PartialEq)]
+enum ExprContext {
+    Index,
+    Default,
+}
+
+fn render_expr(expr: &Expr, ctx: ExprContext) -> Option<String> {
+    let render = |e: &Expr| render_expr(e, ctx);
+    Some(match expr {
+        Expr::ColumnRef(_) if ctx == ExprContext::Default => return None,
+        Expr::ColumnRef(name) => name.clone(),
+        Expr::IntLiteral(n) => n.to_string(),
+        Expr::FloatLiteral(n) => format_float_literal(*n),
+        Expr::StringLiteral(s) => format!("'{}'", s.replace('\'', "''")),
+        Expr::Null => "NULL".to_string(),
+        Expr::FunctionCall { name, args } => {
+            if ctx == ExprContext::Index && NON_DETERMINISTIC_FUNCTIONS.contains(&name.as_str()) {
+                return None;
+            }
+            if name == "CURRENT_TIMESTAMP" && args.is_empty() {
+                return Some(name.clone());
+            }
+            let args = args.iter().map(render).collect::<Option<Vec<_>>>()?;
+            format!("{}({})", name, args.join(", "))
+        }
+        Expr::BinaryOp { left, op, right } => {
+            let op = match op {
+                BinaryOp::Eq => "=",
+                BinaryOp::Ne => "!=",
+                BinaryOp::Lt => "<",
+                BinaryOp::Gt => ">",
+                BinaryOp::Le => "<=",
+                BinaryOp::Ge => ">=",
+                BinaryOp::And => "AND",
+                BinaryOp::Or => "OR",
+                BinaryOp::Add => "+",
+                BinaryOp::Sub => "-",
+                BinaryOp::Mul => "*",
+                BinaryOp::Div => "/",
+                BinaryOp::Mod => "%",
+            };
+            format!("({} {} {})", render(left)?, op, render(right)?)
+        }
+        Expr::UnaryOp { op, operand } => match op {
+            UnaryOp::Neg => format!("-({})", render(operand)?),
+            UnaryOp::Not => format!("NOT ({})", render(operand)?),
+        },
+        Expr::Cast { expr, target_type } => {
+            format!("CAST({} AS {})", render(expr)?, target_type)
+        }
+        _ => return None,
+    })
+}
+
+/// Parse expression text stored in an index definition.
+pub fn parse_index_expr(text: &str) -> Result<Expr> {
+    match parse_sql(&format!("SELECT {}", text)) {
+        Ok(Statement::Select(sel)) => match sel.columns.into_iter().next() {
+            Some(SelectColumn::Expr(expr, None)) => Ok(expr),
+            _ => Err(MuroError::Schema(format!(
+                "Invalid index expression '{}'",
+                text
+            ))),
+        },
+        _ => Err(MuroError::Schema(format!(
+            "Invalid index expression '{}'",
+            text
+        ))),
+    }
+}
+
+/// Collect the column names an index expression reads.
+pub fn index_expr_columns(expr: &Expr, out: &mut Vec<String>) {
+    match expr {
+        Expr::ColumnRef(name) if !out.contains(name) => out.push(name.clone()),
+        Expr::FunctionCall { args, .. } => {
+            for arg in args {
+                index_expr_columns(arg, out);
+            }
+        }
+        Expr::BinaryOp { left, right, .. } => {
+            index_expr_columns(left, out);
+            index_expr_columns(right, out);
+        }
+        Expr::UnaryOp { operand, .. } => index_expr_columns(operand, out),
+        Expr::Cast { expr, .. } => index_expr_columns(expr, out),
+        _ => {}
+    }
+}
+
+/// Rename a column everywhere an index expression reads it.
+pub fn rename_index_expr_column(expr: &mut Expr, old: &str, new: &str) {
+    match expr {
+        Expr::ColumnRef(name) if name == old => *name = new.to_string(),
+        Expr::FunctionCall { args, .. } => {
+            for arg in args {
+                rename_index_expr_column(arg, old, new);
+            }
+        }
+        Expr::BinaryOp { left, right, .. } => {
+            rename_index_expr_column(left, old, new);
+            rename_index_expr_column(right, old, new);
+        }
+        Expr::UnaryOp { operand, .. } => rename_index_expr_column(operand, old, new),
+        Expr::Cast { expr, .. } => rename_index_expr_column(expr, old, new),
+        _ => {}
+    }
+}
@@ -119,6 +119,7 @@ pub enum Token {
     TimestampType, // "TIMESTAMP"
     VarcharType,   // "VARCHAR"
     VarbinaryType, // "VARBINARY"
+    BinaryType,    // "BINARY"
     TextType,      // "TEXT"
     JsonbType,     // "JSONB"
     BooleanType,   // "BOOLEAN" / "BOOL"
@@ -159,8 +160,17 @@ pub enum Token {
 
 /// Tokenize a SQL string.
 pub fn tokenize(input: &str) -> Result<Vec<Token>, String> {
+    tokenize_with_offsets(input)
+        .map(|tokens| tokens.into_iter().map(|(token, _)| token).collect())
+        .map_err(|(_, message)| message)
+}
+
+/// Tokenize a SQL string, pairing each token with the byte offset in `input`
+/// where it starts. A failure carries the byte offset of the bad input.
+pub fn tokenize_with_offsets(input: &str) -> Result<Vec<(Token, usize)>, (usize, String)> {
     let mut tokens = Vec::new();
     let mut remaining = input.trim();
+    let offset_of = |rest: &str| rest.as_ptr() as usize - input.as_ptr() as usize;
 
     while !remaining.is_empty() {
         // Skip whitespace
@@ -176,24 +186,33 @@ pub fn tokenize(input: &str) -> Result<Vec<Token>, String> {
         // Try to match a token
         match lex_token(remaining) {
             Ok((rest, token)) => {
-                tokens.push(token);
+                tokens.push((token, offset_of(remaining)));
                 remaining = rest;
             }
             Err(nom::Err::Failure(e)) => {
                 if e.code == nom::error::ErrorKind::HexDigit {
                     // Determine if it's odd digits or invalid character
                     let snippet = &remaining[..remaining.len().min(30)];
-                    return Err(format!("Invalid hex literal: {}", snippet));
+                    return Err((
+                        offset_of(remaining),
+                        format!("Invalid hex literal: {}", snippet),
+                    ));
                 }
-                return Err(format!(
-                    "Unexpected character at: '{}'",
-                    &remaining[..remaining.len().min(20)]
+                return Err((
+                    offset_of(remaining),
+                    format!(
+                        "Unexpected character at: '{}'",
+                        &remaining[..remaining.len().min(20)]
+                    ),
                 ));
             }
             Err(_) => {
-                return Err(format!(
-                    "Unexpected character at: '{}'",
-                    &remaining[..remaining.len().min(20)]
+                return Err((
+                    offset_of(remaining),
+                    format!(
+                        "Unexpected character at: '{}'",
+                        &remaining[..remaining.len().min(20)]
+                    ),
                 ));
             }
         }
@@ -322,6 +341,8 @@ fn lex_number(input: &str) -> IResult<&str, Token> {
         }
     }
 
+    // Optional fraction, then optional exponent: `1.5`, `1e10`, `2.5E-3`.
+    let mut mantissa_end = int_end;
     if int_end > 0 && input[int_end..].starts_with('.') {
         let frac_start = int_end + 1;
         let mut frac_end = frac_start;
@@ -333,13 +354,26 @@ fn lex_number(input: &str) -> IResult<&str, Token> {
             }
         }
         if frac_end > frac_start {
-            let float_text = &input[..frac_end];
-            let num: f64 = float_text.parse().map_err(|_| {
-                nom::Err::Error(nom::error::Error::new(input, nom::error::ErrorKind::Float))
-            })?;
-            return Ok((&input[frac_end..], Token::Float(num)));
+            mantissa_end = frac_end;
         }
     }
+    let float_end = if int_end > 0 {
+        mantissa_end + exponent_len(&input[mantissa_end..])
+    } else {
+        0
+    };
+    if float_end > int_end {
+        let float_text = &input[..float_end];
+        let num: f64 = float_text
+            .parse()
+            .ok()
+            .filter(|n: &f64| n.is_finite())
+            .ok_or_else(|| {
+                // Out of range: fail outright rather than lex as an identifier.
+                nom::Err::Failure(nom::error::Error::new(input, nom::error::ErrorKind::Float))
+            })?;
+        return Ok((&input[float_end..], Token::Float(num)));
+    }
 
     let (input, digits) = digit1(input)?;
 
@@ -350,8 +384,31 @@ fn lex_number(input: &str) -> IResult<&str, Token> {
     Ok((input, Token::Integer(num)))
 }
 
+/// Length of an exponent suffix (`e10`, `E-5`, `e+3`) at the start of `s`,
+/// or 0 if there is none.
+fn exponent_len(s: &str) -> usize {
+    let bytes = s.as_bytes();
+    if !matches!(bytes.first(), Some(b'e' | b'E')) {
+        return 0;
+    }
+    let sign_len = usize::from(matches!(bytes.get(1), Some(b'+' | b'-')));
+    let digits = bytes[1 + sign_len..]
+        .iter()
+        .take_while(|b| b.is_ascii_digit())
+        .count();
+    if digits == 0 {
+        0
+    } else {
+        1 + sign_len + digits
+    }
+}
+
 fn lex_keyword_or_ident(input: &str) -> IResult<&str, Token> {
-    let (remaining, word) = take_while1(|c: char| c.is_alphanumeric() || c == '_')(input)?;
+    // Combining marks belong to the identifier, so decomposed spellings
+    // ("e" + U+0301) lex like their precomposed forms.
+    let (remaining, word) = take_while1(|c: char| {
+        c.is_alphanumeric() || c == '_' || unicode_normalization::char::is_combining_mark(c)
+    })(input)?;
     let upper = word.to_uppercase();
 
     let token = match upper.as_str() {
@@ -484,6 +541,7 @@ fn lex_keyword_or_ident(input: &str) -> IResult<&str, Token> {
         "TIMESTAMP" => Token::TimestampType,
         "VARCHAR" => Token::VarcharType,
         "VARBINARY" => Token::VarbinaryType,
+        "BINARY" => Token::BinaryType,
         "TEXT" => Token::TextType,
         "JSONB" => Token::JsonbType,
         "UUID" => Token::UuidType,
@@ -616,4 +674,15 @@ mod tests {
         assert!(tokens.contains(&Token::Key));
         assert!(tokens.contains(&Token::References));
     }
+
+    #[test]
+    fn test_tokenize_scientific_notation() {
+        let tokens = tokenize("SELECT 1e10, 2.5E-3, 5e+2, 1e, 1.x").unwrap();
+        assert_eq!(tokens[1], Token::Float(1e10));
+        assert_eq!(tokens[3], Token::Float(2.5e-3));
+        assert_eq!(tokens[5], Token::Float(500.0));
+        assert_eq!(tokens[7], Token::Integer(1));
+        assert_eq!(tokens[8], Token::Ident("e".to_string()));
+        assert!(tokenize("SELECT 1e400").is_err());
+    }
 }
@@ -1,6 +1,7 @@
 pub mod ast;
 pub mod eval;
 pub mod executor;
+pub mod index_expr;
 pub mod lexer;
 pub mod parser;
 pub mod planner;
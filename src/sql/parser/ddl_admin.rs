@@ -1,4 +1,6 @@
 use super::*;
+use crate::btree::ops::MIN_FILL_FACTOR;
+use crate::fts::query::{FtsStopFallback, DEFAULT_STOP_FALLBACK_MAX_DOCS};
 use crate::types::DataType;
 
 impl Parser {
@@ -9,6 +11,23 @@ impl Parser {
         Ok(Statement::AnalyzeTable(table_name))
     }
 
+    pub(super) fn parse_check_table(&mut self) -> Result<Statement, String> {
+        self.advance(); // CHECK
+        self.expect(&Token::Table)?;
+        let table_name = self.expect_ident()?;
+        Ok(Statement::CheckTable(table_name))
+    }
+
+    pub(super) fn parse_optimize_table(&mut self) -> Result<Statement, String> {
+        self.advance(); // OPTIMIZE
+        if matches!(self.peek(), None | Some(Token::Semicolon)) {
+            return Ok(Statement::Optimize);
+        }
+        self.expect(&Token::Table)?;
+        let table_name = self.expect_ident()?;
+        Ok(Statement::OptimizeTable(table_name))
+    }
+
     pub(super) fn parse_create(&mut self) -> Result<Statement, String> {
         self.advance(); // consume CREATE
 
@@ -20,6 +39,15 @@ impl Parser {
                 ct.if_not_exists = if_not_exists;
                 Ok(Statement::CreateTable(ct))
             }
+            Some(Token::Ident(name)) if name.eq_ignore_ascii_case("temporary") => {
+                self.advance(); // TEMPORARY
+                self.expect(&Token::Table)?;
+                let if_not_exists = self.parse_if_not_exists()?;
+                let mut ct = self.parse_create_table()?;
+                ct.if_not_exists = if_not_exists;
+                ct.temporary = true;
+                Ok(Statement::CreateTable(ct))
+            }
             Some(Token::Unique) => {
                 self.advance();
                 self.expect(&Token::Index)?;
@@ -42,7 +70,10 @@ impl Parser {
                     self.parse_create_fulltext_index()?,
                 ))
             }
-            _ => Err("Expected TABLE, INDEX, UNIQUE INDEX, or FULLTEXT INDEX after CREATE".into()),
+            _ => Err(
+                "Expected TABLE, TEMPORARY TABLE, INDEX, UNIQUE INDEX, or FULLTEXT INDEX after CREATE"
+                    .into(),
+            ),
         }
     }
 
@@ -70,6 +101,17 @@ impl Parser {
     pub(super) fn parse_drop(&mut self) -> Result<Statement, String> {
         self.advance(); // DROP
 
+        let temporary = matches!(
+            self.peek(),
+            Some(Token::Ident(name)) if name.eq_ignore_ascii_case("temporary")
+        );
+        if temporary {
+            self.advance(); // TEMPORARY
+            if self.peek() != Some(&Token::Table) {
+                return Err("Expected TABLE after DROP TEMPORARY".into());
+            }
+        }
+
         match self.peek() {
             Some(Token::Table) => {
                 self.advance();
@@ -78,6 +120,7 @@ impl Parser {
                 Ok(Statement::DropTable(DropTable {
                     table_name,
                     if_exists,
+                    temporary,
                 }))
             }
             Some(Token::Index) => {
@@ -101,19 +144,55 @@ impl Parser {
         self.expect(&Token::Table)?;
         let table_name = self.expect_ident()?;
 
+        let mut operations = vec![self.parse_alter_op(&table_name)?];
+        while self.peek() == Some(&Token::Comma) {
+            self.advance();
+            operations.push(self.parse_alter_op(&table_name)?);
+        }
+        if operations.len() > 1
+            && operations
+                .iter()
+                .any(|op| matches!(op, AlterTableOp::ExchangeWith(_)))
+        {
+            return Err(
+                "EXCHANGE WITH cannot be combined with other ALTER TABLE operations".into(),
+            );
+        }
+
+        Ok(Statement::AlterTable(AlterTable {
+            table_name,
+            operations,
+        }))
+    }
+
+    fn parse_alter_op(&mut self, table_name: &str) -> Result<AlterTableOp, String> {
         let operation = match self.peek() {
             Some(Token::Add) => {
                 self.advance(); // ADD
-                if self.peek() == Some(&Token::Foreign) {
-                    let fk = self.parse_foreign_key_spec()?;
-                    AlterTableOp::AddForeignKey(fk)
-                } else {
-                    // Optional COLUMN keyword
-                    if self.peek() == Some(&Token::Column) {
-                        self.advance();
+                match self.peek() {
+                    Some(Token::Foreign) => {
+                        let fk = self.parse_foreign_key_spec()?;
+                        AlterTableOp::AddForeignKey(fk)
+                    }
+                    Some(Token::Index) | Some(Token::Key) => {
+                        self.advance(); // INDEX / KEY
+                        AlterTableOp::AddIndex(self.parse_alter_index(table_name, false)?)
+                    }
+                    Some(Token::Unique) => {
+                        self.advance(); // UNIQUE
+                        if matches!(self.peek(), Some(Token::Index) | Some(Token::Key)) {
+                            self.advance();
+                        }
+                        AlterTableOp::AddIndex(self.parse_alter_index(table_name, true)?)
+                    }
+                    _ => {
+                        // Optional COLUMN keyword
+                        if self.peek() == Some(&Token::Column) {
+                            self.advance();
+                        }
+                        let col_spec = self.parse_column_spec()?;
+                        AlterTableOp::AddColumn(col_spec)
                     }
-                    let col_spec = self.parse_column_spec()?;
-                    AlterTableOp::AddColumn(col_spec)
                 }
             }
             Some(Token::Drop) => {
@@ -132,7 +211,11 @@ impl Parser {
                         self.expect(&Token::RParen)?;
                         AlterTableOp::DropForeignKey(cols)
                     }
-                    _ => return Err("Expected COLUMN or FOREIGN KEY after DROP".into()),
+                    Some(Token::Index) | Some(Token::Key) => {
+                        self.advance(); // INDEX / KEY
+                        AlterTableOp::DropIndex(self.expect_ident()?)
+                    }
+                    _ => return Err("Expected COLUMN, INDEX, KEY or FOREIGN KEY after DROP".into()),
                 }
             }
             Some(Token::Modify) => {
@@ -154,15 +237,62 @@ impl Parser {
                 let col_spec = self.parse_column_spec()?;
                 AlterTableOp::ChangeColumn(old_name, col_spec)
             }
-            _ => {
-                return Err("Expected ADD, DROP, MODIFY, or CHANGE after ALTER TABLE <name>".into())
+            Some(Token::Rename) => {
+                self.advance(); // RENAME
+                if !matches!(self.peek(), Some(Token::Index) | Some(Token::Key)) {
+                    return Err("Expected INDEX or KEY after RENAME".into());
+                }
+                self.advance(); // INDEX / KEY
+                let old_name = self.expect_ident()?;
+                self.expect(&Token::To)?;
+                let new_name = self.expect_ident()?;
+                AlterTableOp::RenameIndex(old_name, new_name)
+            }
+            Some(Token::Ident(name)) if name.eq_ignore_ascii_case("exchange") => {
+                self.advance(); // EXCHANGE
+                self.expect(&Token::With)?;
+                let other = self.expect_ident()?;
+                AlterTableOp::ExchangeWith(other)
             }
+            _ => return Err(
+                "Expected ADD, DROP, MODIFY, CHANGE, RENAME, or EXCHANGE after ALTER TABLE <name>"
+                    .into(),
+            ),
         };
+        Ok(operation)
+    }
 
-        Ok(Statement::AlterTable(AlterTable {
-            table_name,
-            operation,
-        }))
+    /// The index of `ADD [UNIQUE] INDEX|KEY [name] (...)`. Without a name,
+    /// one is made from the table and columns, as CREATE TABLE names its
+    /// UNIQUE constraints: `auto_unique_<table>_<cols>` or
+    /// `auto_index_<table>_<cols>`.
+    fn parse_alter_index(
+        &mut self,
+        table_name: &str,
+        is_unique: bool,
+    ) -> Result<CreateIndex, String> {
+        if self.peek() != Some(&Token::LParen) {
+            return self.parse_table_index(table_name, is_unique);
+        }
+        let (column_names, expressions) = self.parse_index_key_parts()?;
+        if !expressions.is_empty() {
+            return Err("An index on an expression needs a name".into());
+        }
+        let fill_factor = self.parse_storage_options()?;
+        let prefix = if is_unique {
+            "auto_unique"
+        } else {
+            "auto_index"
+        };
+        Ok(CreateIndex {
+            index_name: format!("{}_{}_{}", prefix, table_name, column_names.join("_")),
+            table_name: table_name.to_string(),
+            column_names,
+            expressions,
+            is_unique,
+            if_not_exists: false,
+            fill_factor,
+        })
     }
 
     pub(super) fn parse_rename(&mut self) -> Result<Statement, String> {
@@ -202,28 +332,66 @@ impl Parser {
                     }
                     continue;
                 }
-                Some(Token::Unique) => {
-                    // Could be UNIQUE(col1, col2) table constraint or column named "unique" (unlikely)
-                    // Peek ahead: UNIQUE followed by LParen means table constraint
-                    if self.tokens.get(self.pos + 1) == Some(&Token::LParen) {
-                        self.advance(); // UNIQUE
-                        self.expect(&Token::LParen)?;
-                        let cols = self.parse_ident_list()?;
-                        self.expect(&Token::RParen)?;
-                        constraints.push(TableConstraint::Unique(None, cols));
+                // Could be UNIQUE(col1, col2) table constraint or column named "unique" (unlikely)
+                // Peek ahead: UNIQUE followed by LParen means table constraint
+                Some(Token::Unique) if self.tokens.get(self.pos + 1) == Some(&Token::LParen) => {
+                    self.advance(); // UNIQUE
+                    self.expect(&Token::LParen)?;
+                    let cols = self.parse_ident_list()?;
+                    self.expect(&Token::RParen)?;
+                    constraints.push(TableConstraint::Unique(None, cols));
 
-                        match self.peek() {
-                            Some(Token::Comma) => {
-                                self.advance();
-                            }
-                            Some(Token::RParen) => {
-                                self.advance();
-                                break;
-                            }
-                            _ => return Err("Expected ',' or ')' after table constraint".into()),
+                    match self.peek() {
+                        Some(Token::Comma) => {
+                            self.advance();
+                        }
+                        Some(Token::RParen) => {
+                            self.advance();
+                            break;
+                        }
+                        _ => return Err("Expected ',' or ')' after table constraint".into()),
+                    }
+                    continue;
+                }
+                Some(Token::Key) | Some(Token::Index) => {
+                    self.advance(); // KEY / INDEX
+                    let ci = self.parse_table_index(&table_name, false)?;
+                    constraints.push(TableConstraint::Index(ci));
+
+                    match self.peek() {
+                        Some(Token::Comma) => {
+                            self.advance();
+                        }
+                        Some(Token::RParen) => {
+                            self.advance();
+                            break;
+                        }
+                        _ => return Err("Expected ',' or ')' after table constraint".into()),
+                    }
+                    continue;
+                }
+                Some(Token::Unique)
+                    if matches!(
+                        self.tokens.get(self.pos + 1),
+                        Some(Token::Key) | Some(Token::Index)
+                    ) =>
+                {
+                    self.advance(); // UNIQUE
+                    self.advance(); // KEY / INDEX
+                    let ci = self.parse_table_index(&table_name, true)?;
+                    constraints.push(TableConstraint::Index(ci));
+
+                    match self.peek() {
+                        Some(Token::Comma) => {
+                            self.advance();
                         }
-                        continue;
+                        Some(Token::RParen) => {
+                            self.advance();
+                            break;
+                        }
+                        _ => return Err("Expected ',' or ')' after table constraint".into()),
                     }
+                    continue;
                 }
                 Some(Token::Foreign) => {
                     let fk = self.parse_foreign_key_spec()?;
@@ -265,11 +433,81 @@ impl Parser {
             }
         }
 
+        let auto_increment = self.parse_auto_increment_option()?;
+        let fill_factor = self.parse_storage_options()?;
+
         Ok(CreateTable {
             table_name,
             columns,
             constraints,
             if_not_exists: false,
+            fill_factor,
+            auto_increment,
+            temporary: false,
+        })
+    }
+
+    /// Optional `AUTO_INCREMENT [=] N` after the CREATE TABLE column list.
+    fn parse_auto_increment_option(&mut self) -> Result<Option<i64>, String> {
+        if self.peek() != Some(&Token::AutoIncrement) {
+            return Ok(None);
+        }
+        self.advance(); // AUTO_INCREMENT
+        if self.peek() == Some(&Token::Eq) {
+            self.advance();
+        }
+        match self.advance() {
+            Some(Token::Integer(n)) if n >= 1 => Ok(Some(n)),
+            Some(Token::Integer(n)) => Err(format!("AUTO_INCREMENT must be at least 1, got {}", n)),
+            Some(tok) => Err(format!("Invalid AUTO_INCREMENT token: {:?}", tok)),
+            None => Err("Expected AUTO_INCREMENT value".into()),
+        }
+    }
+
+    /// Optional `WITH (fill_factor = N)` after CREATE TABLE / CREATE INDEX.
+    fn parse_storage_options(&mut self) -> Result<Option<u8>, String> {
+        if self.peek() != Some(&Token::With) {
+            return Ok(None);
+        }
+        self.advance(); // WITH
+        self.expect(&Token::LParen)?;
+        let key = self.expect_ident()?;
+        if !key.eq_ignore_ascii_case("fill_factor") {
+            return Err(format!("Unknown storage option: {}", key));
+        }
+        self.expect(&Token::Eq)?;
+        let fill_factor = match self.advance() {
+            Some(Token::Integer(n)) if (MIN_FILL_FACTOR as i64..=100).contains(&n) => n as u8,
+            Some(Token::Integer(n)) => {
+                return Err(format!(
+                    "fill_factor must be between {} and 100, got {}",
+                    MIN_FILL_FACTOR, n
+                ));
+            }
+            Some(tok) => return Err(format!("Invalid fill_factor token: {:?}", tok)),
+            None => return Err("Expected fill_factor value".into()),
+        };
+        self.expect(&Token::RParen)?;
+        Ok(Some(fill_factor))
+    }
+
+    /// `name (parts) [WITH (...)]` after `KEY` / `UNIQUE KEY` in CREATE TABLE.
+    fn parse_table_index(
+        &mut self,
+        table_name: &str,
+        is_unique: bool,
+    ) -> Result<CreateIndex, String> {
+        let index_name = self.expect_ident()?;
+        let (column_names, expressions) = self.parse_index_key_parts()?;
+        let fill_factor = self.parse_storage_options()?;
+        Ok(CreateIndex {
+            index_name,
+            table_name: table_name.to_string(),
+            column_names,
+            expressions,
+            is_unique,
+            if_not_exists: false,
+            fill_factor,
         })
     }
 
@@ -353,6 +591,7 @@ impl Parser {
         let mut default_value = None;
         let mut auto_increment = false;
         let mut check_expr = None;
+        let mut collation = None;
         let mut constraint_expr_order = Vec::new();
 
         loop {
@@ -389,6 +628,10 @@ impl Parser {
                     constraint_expr_order.retain(|k| *k != ColumnConstraintExprKind::Check);
                     constraint_expr_order.push(ColumnConstraintExprKind::Check);
                 }
+                Some(Token::Ident(word)) if word.eq_ignore_ascii_case("COLLATE") => {
+                    self.advance();
+                    collation = Some(self.parse_collation_name()?);
+                }
                 _ => break,
             }
         }
@@ -402,6 +645,7 @@ impl Parser {
             default_value,
             auto_increment,
             check_expr,
+            collation,
             constraint_expr_order,
         })
     }
@@ -436,6 +680,17 @@ impl Parser {
                 Some(Token::TextType) => Ok(DataType::Text),
                 Some(Token::JsonbType) => Ok(DataType::Jsonb),
                 Some(Token::UuidType) => Ok(DataType::Uuid),
+                // BINARY(16) is the only fixed-width binary type and maps to UUID.
+                Some(Token::BinaryType) => match self.parse_optional_size()? {
+                    Some(16) => Ok(DataType::Uuid),
+                    Some(n) => Err(format!(
+                        "BINARY({}) is not supported; only BINARY(16) (UUID) is",
+                        n
+                    )),
+                    None => {
+                        Err("BINARY requires a length; only BINARY(16) (UUID) is supported".into())
+                    }
+                },
                 Some(Token::DecimalType) => {
                     // Parse optional (precision, scale) with defaults (10, 0)
                     if self.peek() == Some(&Token::LParen) {
@@ -502,19 +757,49 @@ impl Parser {
         let index_name = self.expect_ident()?;
         self.expect(&Token::On)?;
         let table_name = self.expect_ident()?;
-        self.expect(&Token::LParen)?;
-        let column_names = self.parse_ident_list()?;
-        self.expect(&Token::RParen)?;
+        let (column_names, expressions) = self.parse_index_key_parts()?;
+        let fill_factor = self.parse_storage_options()?;
 
         Ok(CreateIndex {
             index_name,
             table_name,
             column_names,
+            expressions,
             is_unique,
             if_not_exists: false,
+            fill_factor,
         })
     }
 
+    /// Parse `(part, ...)` where a part is `col` or a parenthesized
+    /// expression `(LOWER(col))`.
+    fn parse_index_key_parts(&mut self) -> Result<(Vec<String>, Vec<Option<Expr>>), String> {
+        self.expect(&Token::LParen)?;
+        let mut column_names = Vec::new();
+        let mut expressions = Vec::new();
+        loop {
+            if self.peek() == Some(&Token::LParen) {
+                self.advance();
+                expressions.push(Some(self.parse_expr()?));
+                self.expect(&Token::RParen)?;
+                column_names.push(String::new());
+            } else {
+                expressions.push(None);
+                column_names.push(self.expect_ident()?);
+            }
+            if self.peek() == Some(&Token::Comma) {
+                self.advance();
+            } else {
+                break;
+            }
+        }
+        self.expect(&Token::RParen)?;
+        if expressions.iter().all(Option::is_none) {
+            expressions.clear();
+        }
+        Ok((column_names, expressions))
+    }
+
     pub(super) fn parse_create_fulltext_index(&mut self) -> Result<CreateFulltextIndex, String> {
         let index_name = self.expect_ident()?;
         self.expect(&Token::On)?;
@@ -529,6 +814,8 @@ impl Parser {
         let mut normalize = "nfkc".to_string();
         let mut stop_filter = false;
         let mut stop_df_ratio_ppm = 200_000u32;
+        let mut stop_fallback = FtsStopFallback::Rescan;
+        let mut stop_fallback_max_docs = DEFAULT_STOP_FALLBACK_MAX_DOCS;
 
         if self.peek() == Some(&Token::With) {
             self.advance();
@@ -590,6 +877,36 @@ impl Parser {
                         }
                         None => return Err("Expected stop_df_ratio_ppm value".into()),
                     },
+                    "stop_fallback" => match self.advance() {
+                        Some(Token::StringLit(s)) | Some(Token::Ident(s)) => {
+                            stop_fallback = FtsStopFallback::parse(&s).ok_or_else(|| {
+                                format!(
+                                    "Invalid stop_fallback value: {} (expected 'rescan' or 'empty_with_warning')",
+                                    s
+                                )
+                            })?;
+                        }
+                        Some(tok) => {
+                            return Err(format!("Invalid stop_fallback token: {:?}", tok));
+                        }
+                        None => return Err("Expected stop_fallback value".into()),
+                    },
+                    "stop_fallback_max_docs" => match self.advance() {
+                        Some(Token::Integer(n)) if (0..=u32::MAX as i64).contains(&n) => {
+                            stop_fallback_max_docs = n as u32;
+                        }
+                        Some(Token::Integer(n)) => {
+                            return Err(format!(
+                                "stop_fallback_max_docs is out of range: {} (0..={})",
+                                n,
+                                u32::MAX
+                            ));
+                        }
+                        Some(tok) => {
+                            return Err(format!("Invalid stop_fallback_max_docs token: {:?}", tok));
+                        }
+                        None => return Err("Expected stop_fallback_max_docs value".into()),
+                    },
                     _ => return Err(format!("Unknown option: {}", key)),
                 }
                 if self.peek() == Some(&Token::Comma) {
@@ -610,6 +927,8 @@ impl Parser {
             normalize,
             stop_filter,
             stop_df_ratio_ppm,
+            stop_fallback,
+            stop_fallback_max_docs,
         })
     }
 
@@ -627,6 +946,16 @@ impl Parser {
                 let table_name = self.expect_ident()?;
                 Ok(Statement::ShowCreateTable(table_name))
             }
+            Some(Token::Index) => {
+                self.advance(); // INDEX
+                self.expect(&Token::From)?;
+                Ok(Statement::ShowIndexes(self.expect_ident()?))
+            }
+            Some(Token::Ident(name)) if name.eq_ignore_ascii_case("indexes") => {
+                self.advance();
+                self.expect(&Token::From)?;
+                Ok(Statement::ShowIndexes(self.expect_ident()?))
+            }
             Some(Token::Checkpoint) => {
                 self.advance(); // CHECKPOINT
                 self.expect(&Token::Stats)?;
@@ -637,55 +966,62 @@ impl Parser {
                 self.expect(&Token::Stats)?;
                 Ok(Statement::ShowDatabaseStats)
             }
+            Some(Token::Ident(name)) if name.eq_ignore_ascii_case("warnings") => {
+                self.advance();
+                Ok(Statement::ShowWarnings)
+            }
+            Some(Token::Ident(name)) if name.eq_ignore_ascii_case("processlist") => {
+                self.advance();
+                Ok(Statement::ShowProcesslist)
+            }
+            Some(Token::Ident(name)) if name.eq_ignore_ascii_case("variables") => {
+                self.advance();
+                if self.peek() != Some(&Token::Like) {
+                    return Ok(Statement::ShowVariables(None));
+                }
+                self.advance(); // LIKE
+                match self.advance() {
+                    Some(Token::StringLit(pattern)) => Ok(Statement::ShowVariables(Some(pattern))),
+                    _ => Err("Expected a string pattern after SHOW VARIABLES LIKE".into()),
+                }
+            }
+            Some(Token::Table) => {
+                self.advance(); // TABLE
+                match self.advance() {
+                    Some(Token::Ident(name)) if name.eq_ignore_ascii_case("status") => {
+                        Ok(Statement::ShowTableStatus)
+                    }
+                    _ => Err("Expected STATUS after SHOW TABLE".into()),
+                }
+            }
             _ => Err(
-                "Expected TABLES, CREATE TABLE, CHECKPOINT STATS, or DATABASE STATS after SHOW"
+                "Expected TABLES, TABLE STATUS, CREATE TABLE, INDEXES FROM, CHECKPOINT STATS, DATABASE STATS, VARIABLES, WARNINGS, or PROCESSLIST after SHOW"
                     .into(),
             ),
         }
     }
 
-    pub(super) fn parse_set_runtime_option(&mut self) -> Result<Statement, String> {
+    pub(super) fn parse_set_variable(&mut self) -> Result<Statement, String> {
         self.advance(); // consume SET
-        let option_name = match self.advance() {
+        let name = match self.advance() {
             Some(Token::Ident(name)) => name.to_ascii_lowercase(),
-            Some(tok) => {
-                return Err(format!(
-                    "Expected runtime option name after SET, got {:?}",
-                    tok
-                ))
-            }
-            None => return Err("Expected runtime option name after SET".into()),
+            Some(tok) => return Err(format!("Expected setting name after SET, got {:?}", tok)),
+            None => return Err("Expected setting name after SET".into()),
         };
         self.expect(&Token::Eq)?;
         let value = match self.advance() {
-            Some(Token::Integer(v)) if v >= 0 => v as u64,
-            Some(Token::Integer(_)) => {
-                return Err("Runtime option value must be >= 0".into());
-            }
-            Some(tok) => {
-                return Err(format!(
-                    "Expected integer runtime option value, got {:?}",
-                    tok
-                ))
-            }
-            None => return Err("Expected integer runtime option value".into()),
-        };
-
-        let option = match option_name.as_str() {
-            "checkpoint_tx_threshold" => RuntimeOption::CheckpointTxThreshold,
-            "checkpoint_wal_bytes_threshold" => RuntimeOption::CheckpointWalBytesThreshold,
-            "checkpoint_interval_ms" => RuntimeOption::CheckpointIntervalMs,
-            _ => {
-                return Err(format!(
-                    "Unknown runtime option '{}'. Supported options: checkpoint_tx_threshold, checkpoint_wal_bytes_threshold, checkpoint_interval_ms",
-                    option_name
-                ))
+            Some(Token::Integer(v)) => SetValue::Integer(v),
+            Some(Token::Minus) => match self.advance() {
+                Some(Token::Integer(v)) => SetValue::Integer(-v),
+                _ => return Err(format!("Expected a number after '-' in SET {}", name)),
+            },
+            Some(Token::On) => SetValue::Word("on".to_string()),
+            Some(Token::StringLit(s)) | Some(Token::Ident(s)) => {
+                SetValue::Word(s.to_ascii_lowercase())
             }
+            Some(tok) => return Err(format!("Expected a value for SET {}, got {:?}", name, tok)),
+            None => return Err(format!("Expected a value for SET {}", name)),
         };
-
-        Ok(Statement::SetRuntimeOption(SetRuntimeOption {
-            option,
-            value,
-        }))
+        Ok(Statement::SetVariable(SetVariable { name, value }))
     }
 }
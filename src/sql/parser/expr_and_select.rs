@@ -13,6 +13,15 @@ impl Parser {
         })
     }
 
+    /// Optional `ESCAPE <expr>` after a LIKE pattern.
+    pub(super) fn parse_like_escape(&mut self) -> Result<Option<Box<Expr>>, String> {
+        if matches!(self.peek(), Some(Token::Ident(s)) if s.eq_ignore_ascii_case("ESCAPE")) {
+            self.advance();
+            return Ok(Some(Box::new(self.parse_additive()?)));
+        }
+        Ok(None)
+    }
+
     pub(super) fn parse_additive(&mut self) -> Result<Expr, String> {
         let mut left = self.parse_multiplicative()?;
         loop {
@@ -37,7 +46,7 @@ impl Parser {
     }
 
     pub(super) fn parse_multiplicative(&mut self) -> Result<Expr, String> {
-        let mut left = self.parse_unary()?;
+        let mut left = self.parse_collated()?;
         loop {
             let op = match self.peek() {
                 Some(Token::Star) => Some(BinaryOp::Mul),
@@ -47,7 +56,7 @@ impl Parser {
             };
             if let Some(op) = op {
                 self.advance();
-                let right = self.parse_unary()?;
+                let right = self.parse_collated()?;
                 left = Expr::BinaryOp {
                     left: Box::new(left),
                     op,
@@ -60,6 +69,32 @@ impl Parser {
         Ok(left)
     }
 
+    /// A unary expression with optional `COLLATE <name>` suffixes.
+    pub(super) fn parse_collated(&mut self) -> Result<Expr, String> {
+        let mut expr = self.parse_unary()?;
+        while matches!(self.peek(), Some(Token::Ident(s)) if s.eq_ignore_ascii_case("COLLATE")) {
+            self.advance();
+            expr = Expr::Collate {
+                expr: Box::new(expr),
+                collation: self.parse_collation_name()?,
+            };
+        }
+        Ok(expr)
+    }
+
+    /// Collation name after COLLATE, as an identifier or string.
+    pub(super) fn parse_collation_name(&mut self) -> Result<Collation, String> {
+        let name = match self.advance() {
+            Some(Token::Ident(name)) | Some(Token::StringLit(name)) => name,
+            // BINARY lexes as the type keyword.
+            Some(Token::BinaryType) => return Ok(Collation::Binary),
+            Some(t) => return Err(format!("Expected collation name, got {:?}", t)),
+            None => return Err("Expected collation name, got end of input".into()),
+        };
+        Collation::from_name(&name)
+            .ok_or_else(|| format!("Unknown collation '{}' (supported: binary, nocase)", name))
+    }
+
     pub(super) fn parse_unary(&mut self) -> Result<Expr, String> {
         if self.peek() == Some(&Token::Minus) {
             self.advance();
@@ -119,6 +154,18 @@ impl Parser {
                 self.advance();
                 Ok(Expr::DefaultValue)
             }
+            Some(Token::Values) => {
+                // VALUES(col) in ON DUPLICATE KEY UPDATE: the value the
+                // INSERT tried to write into `col`.
+                self.advance();
+                self.expect(&Token::LParen)?;
+                let column = self.expect_ident()?;
+                self.expect(&Token::RParen)?;
+                Ok(Expr::FunctionCall {
+                    name: "VALUES".to_string(),
+                    args: vec![Expr::ColumnRef(column)],
+                })
+            }
             Some(Token::Count) | Some(Token::Sum) | Some(Token::Avg) | Some(Token::Min)
             | Some(Token::Max) => self.parse_aggregate_func(),
             Some(Token::Match) => self.parse_match_against(),
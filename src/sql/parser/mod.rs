@@ -1,7 +1,10 @@
 /// SQL parser: converts token stream into AST.
 /// Hand-written recursive descent parser.
+use crate::error::MuroError;
+use crate::limits::Limit;
 use crate::sql::ast::*;
 use crate::sql::lexer::Token;
+use crate::types::Collation;
 
 mod ddl_admin;
 mod expr_and_select;
@@ -12,11 +15,23 @@ mod select_stmt;
 pub struct Parser {
     tokens: Vec<Token>,
     pos: usize,
+    /// Set with the parse error when the statement went past a limit.
+    limit_exceeded: Option<(Limit, usize)>,
 }
 
 impl Parser {
     pub fn new(tokens: Vec<Token>) -> Self {
-        Parser { tokens, pos: 0 }
+        Parser {
+            tokens,
+            pos: 0,
+            limit_exceeded: None,
+        }
+    }
+
+    /// Fail the parse for going past `limit` with `value`.
+    fn exceed_limit<T>(&mut self, limit: Limit, value: usize) -> Result<T, String> {
+        self.limit_exceeded = Some((limit, value));
+        Err(limit.exceeded(value).to_string())
     }
 
     fn peek(&self) -> Option<&Token> {
@@ -78,12 +93,18 @@ impl Parser {
             Some(Token::Replace) => Statement::Insert(self.parse_insert(true)?),
             Some(Token::Explain) => {
                 self.advance(); // EXPLAIN
+                if self.peek() == Some(&Token::Analyze) {
+                    self.advance(); // ANALYZE
+                    let inner = self.parse()?;
+                    return Ok(Statement::ExplainAnalyze(Box::new(inner)));
+                }
                 let inner = self.parse()?;
                 return Ok(Statement::Explain(Box::new(inner)));
             }
             Some(Token::Update) => Statement::Update(self.parse_update()?),
             Some(Token::Delete) => Statement::Delete(self.parse_delete()?),
             Some(Token::Analyze) => self.parse_analyze()?,
+            Some(Token::Check) => self.parse_check_table()?,
             Some(Token::Alter) => self.parse_alter()?,
             Some(Token::Rename) => self.parse_rename()?,
             Some(Token::Show) => self.parse_show()?,
@@ -132,7 +153,10 @@ impl Parser {
                 let name = self.expect_ident()?;
                 Statement::ReleaseSavepoint(name)
             }
-            Some(Token::Set) => self.parse_set_runtime_option()?,
+            Some(Token::Set) => self.parse_set_variable()?,
+            Some(Token::Ident(name)) if name.eq_ignore_ascii_case("optimize") => {
+                self.parse_optimize_table()?
+            }
             Some(t) => return Err(format!("Unexpected token: {:?}", t)),
             None => return Err("Empty input".into()),
         };
@@ -156,9 +180,136 @@ impl Parser {
 
 /// Parse a SQL string into a statement.
 pub fn parse_sql(sql: &str) -> Result<Statement, String> {
-    let tokens = crate::sql::lexer::tokenize(sql)?;
+    parse_statement(sql).map_err(|e| match e {
+        MuroError::Parse(message) => message,
+        other => other.to_string(),
+    })
+}
+
+/// Parse a SQL string into a statement. Going past `max_sql_bytes` or
+/// `max_in_list_items` fails with [`MuroError::LimitExceeded`], other
+/// errors with [`MuroError::Parse`].
+pub fn parse_statement(sql: &str) -> crate::error::Result<Statement> {
+    Limit::SqlBytes.check(sql.len())?;
+    let tokens = crate::sql::lexer::tokenize(sql).map_err(MuroError::Parse)?;
     let mut parser = Parser::new(tokens);
-    parser.parse()
+    parser
+        .parse()
+        .map_err(|message| match parser.limit_exceeded {
+            Some((limit, value)) => limit.exceeded(value),
+            None => MuroError::Parse(message),
+        })
+}
+
+/// A statement of a script and the character offset where it starts.
+#[derive(Debug, Clone)]
+pub struct ScriptStatement {
+    pub statement: Statement,
+    pub offset: usize,
+}
+
+/// Failure to parse a script: the 0-based index of the statement that failed
+/// and the character offset of that statement (or of the character that
+/// could not be tokenized).
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ScriptParseError {
+    pub index: usize,
+    pub offset: usize,
+    pub message: String,
+    /// The limit the statement went past, and by how much, if that is why
+    /// it failed.
+    pub limit_exceeded: Option<(Limit, usize)>,
+}
+
+impl ScriptParseError {
+    /// The error of the failing statement itself.
+    pub fn statement_error(&self) -> MuroError {
+        match self.limit_exceeded {
+            Some((limit, value)) => limit.exceeded(value),
+            None => MuroError::Parse(self.message.clone()),
+        }
+    }
+}
+
+impl std::fmt::Display for ScriptParseError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "statement {} at offset {}: {}",
+            self.index, self.offset, self.message
+        )
+    }
+}
+
+/// Parse a script of statements separated by semicolons. Semicolons inside
+/// string literals do not end a statement, and empty statements are skipped.
+pub fn parse_script(sql: &str) -> Result<Vec<Statement>, String> {
+    parse_script_with_offsets(sql)
+        .map(|stmts| stmts.into_iter().map(|s| s.statement).collect())
+        .map_err(|e| e.to_string())
+}
+
+/// Like [`parse_script`], keeping the character offset of each statement.
+pub fn parse_script_with_offsets(sql: &str) -> Result<Vec<ScriptStatement>, ScriptParseError> {
+    if let Err(e) = Limit::SqlBytes.check(sql.len()) {
+        return Err(ScriptParseError {
+            index: 0,
+            offset: 0,
+            message: e.to_string(),
+            limit_exceeded: Some((Limit::SqlBytes, sql.len())),
+        });
+    }
+    let char_offset = |byte: usize| sql[..byte].chars().count();
+    let tokens = crate::sql::lexer::tokenize_with_offsets(sql).map_err(|(byte, message)| {
+        // The script up to the bad character still tells which statement it is in.
+        let index = crate::sql::lexer::tokenize_with_offsets(&sql[..byte])
+            .map(|mut tokens| {
+                tokens.push((Token::Question, byte));
+                split_statements(tokens).len() - 1
+            })
+            .unwrap_or(0);
+        ScriptParseError {
+            index,
+            offset: char_offset(byte),
+            message,
+            limit_exceeded: None,
+        }
+    })?;
+
+    let mut statements = Vec::new();
+    for (index, chunk) in split_statements(tokens).into_iter().enumerate() {
+        let offset = char_offset(chunk[0].1);
+        let mut parser = Parser::new(chunk.into_iter().map(|(token, _)| token).collect());
+        let statement = parser.parse().map_err(|message| ScriptParseError {
+            index,
+            offset,
+            message,
+            limit_exceeded: parser.limit_exceeded,
+        })?;
+        statements.push(ScriptStatement { statement, offset });
+    }
+    Ok(statements)
+}
+
+/// Split tokens at semicolons, dropping empty statements. No statement has a
+/// semicolon inside parentheses, so an unclosed `(` stays in its own
+/// statement and fails there instead of swallowing the rest of the script.
+fn split_statements(tokens: Vec<(Token, usize)>) -> Vec<Vec<(Token, usize)>> {
+    let mut chunks = Vec::new();
+    let mut current = Vec::new();
+    for (token, offset) in tokens {
+        if token == Token::Semicolon {
+            if !current.is_empty() {
+                chunks.push(std::mem::take(&mut current));
+            }
+            continue;
+        }
+        current.push((token, offset));
+    }
+    if !current.is_empty() {
+        chunks.push(current);
+    }
+    chunks
 }
 
 #[cfg(test)]
@@ -1,53 +1,52 @@
 use super::*;
 
 impl Parser {
+    /// A SELECT, or a chain of SELECTs joined by `UNION [ALL | DISTINCT]`.
+    /// ORDER BY, LIMIT and OFFSET after the last SELECT of a chain apply to
+    /// the whole union; on any earlier SELECT they are rejected.
     pub(super) fn parse_select_or_union(&mut self) -> Result<Statement, String> {
-        let mut first = self.parse_select()?;
+        let first = self.parse_select()?;
 
         // Check for UNION
         if self.peek() != Some(&Token::Union) {
             return Ok(Statement::Select(Box::new(first)));
         }
-
-        // Move ORDER BY / LIMIT / OFFSET from first SELECT to SetQuery level
-        // (they belong to the whole UNION, not the individual SELECT)
-        let mut set_order_by = first.order_by.take();
-        let mut set_limit = first.limit.take();
-        let mut set_offset = first.offset.take();
+        reject_union_branch_clauses(&first)?;
 
         let mut ops = Vec::new();
-
         while self.peek() == Some(&Token::Union) {
             self.advance(); // consume UNION
-            let set_op = if self.peek() == Some(&Token::All) {
-                self.advance();
-                SetOp::UnionAll
-            } else {
-                SetOp::Union
+            let set_op = match self.peek() {
+                Some(Token::All) => {
+                    self.advance();
+                    SetOp::UnionAll
+                }
+                Some(Token::Distinct) => {
+                    self.advance();
+                    SetOp::Union
+                }
+                _ => SetOp::Union,
             };
-
-            let mut sel = self.parse_select()?;
-
-            // If this SELECT has ORDER BY/LIMIT/OFFSET, treat them as final SetQuery-level clauses
-            if sel.order_by.is_some() {
-                set_order_by = sel.order_by.take();
+            let sel = self.parse_select()?;
+            if self.peek() == Some(&Token::Union) {
+                reject_union_branch_clauses(&sel)?;
             }
-            if sel.limit.is_some() {
-                set_limit = sel.limit.take();
-            }
-            if sel.offset.is_some() {
-                set_offset = sel.offset.take();
-            }
-
             ops.push((set_op, sel));
         }
 
+        // The last SELECT's ORDER BY / LIMIT / OFFSET belong to the whole
+        // UNION, not to that SELECT.
+        let last = &mut ops.last_mut().expect("UNION has a second SELECT").1;
+        let order_by = last.order_by.take();
+        let limit = last.limit.take();
+        let offset = last.offset.take();
+
         Ok(Statement::SetQuery(Box::new(SetQuery {
             left: first,
             ops,
-            order_by: set_order_by,
-            limit: set_limit,
-            offset: set_offset,
+            order_by,
+            limit,
+            offset,
         })))
     }
 
@@ -74,7 +73,21 @@ impl Parser {
                     | Token::Use
                     | Token::Ignore
             )
-        )
+        ) || self.is_full_join_ahead()
+    }
+
+    /// `FULL [OUTER] JOIN` is recognised only to reject it with a clear
+    /// error; `FULL` is not a reserved word, so it must be followed by
+    /// `JOIN` or `OUTER` to count.
+    pub(super) fn is_full_join_ahead(&self) -> bool {
+        let is_full =
+            matches!(self.peek(), Some(Token::Ident(s)) if s.eq_ignore_ascii_case("FULL"));
+        is_full
+            && match self.tokens.get(self.pos + 1) {
+                Some(Token::Join) => true,
+                Some(Token::Ident(s)) => s.eq_ignore_ascii_case("OUTER"),
+                _ => false,
+            }
     }
 
     pub(super) fn parse_select_columns(&mut self) -> Result<Vec<SelectColumn>, String> {
@@ -186,11 +199,16 @@ impl Parser {
             None
         };
 
+        let order_by = self.parse_order_by_clause()?;
+        let limit = self.parse_limit_clause()?;
+
         Ok(Update {
             table_name,
             index_hints,
             assignments,
             where_clause,
+            order_by,
+            limit,
         })
     }
 
@@ -207,13 +225,69 @@ impl Parser {
             None
         };
 
+        let order_by = self.parse_order_by_clause()?;
+        let limit = self.parse_limit_clause()?;
+
         Ok(Delete {
             table_name,
             index_hints,
             where_clause,
+            order_by,
+            limit,
         })
     }
 
+    /// Parse an optional `ORDER BY expr [ASC|DESC], ...` clause.
+    pub(super) fn parse_order_by_clause(&mut self) -> Result<Option<Vec<OrderByItem>>, String> {
+        if self.peek() != Some(&Token::Order) {
+            return Ok(None);
+        }
+        self.advance();
+        self.expect(&Token::By)?;
+        let mut items = Vec::new();
+        loop {
+            let expr = self.parse_expr()?;
+            let descending = if self.peek() == Some(&Token::Desc) {
+                self.advance();
+                true
+            } else if self.peek() == Some(&Token::Asc) {
+                self.advance();
+                false
+            } else {
+                false
+            };
+            items.push(OrderByItem { expr, descending });
+            if self.peek() == Some(&Token::Comma) {
+                self.advance();
+            } else {
+                break;
+            }
+        }
+        Ok(Some(items))
+    }
+
+    /// Parse an optional `LIMIT n` clause. Like MySQL, a negative count is a
+    /// syntax error rather than "no limit".
+    pub(super) fn parse_limit_clause(&mut self) -> Result<Option<u64>, String> {
+        if self.peek() != Some(&Token::Limit) {
+            return Ok(None);
+        }
+        self.advance();
+        match self.advance() {
+            Some(Token::Integer(n)) if n >= 0 => Ok(Some(n as u64)),
+            Some(Token::Integer(n)) => {
+                Err(format!("LIMIT must be a non-negative integer, got {}", n))
+            }
+            Some(Token::Minus) => match self.peek() {
+                Some(Token::Integer(n)) => {
+                    Err(format!("LIMIT must be a non-negative integer, got -{}", n))
+                }
+                _ => Err("Expected integer after LIMIT".into()),
+            },
+            _ => Err("Expected integer after LIMIT".into()),
+        }
+    }
+
     // Expression parsing with precedence:
     // parse_expr -> parse_or_expr -> parse_and_expr -> parse_not_expr
     //   -> parse_comparison -> parse_additive -> parse_multiplicative -> parse_unary -> parse_primary
@@ -303,9 +377,11 @@ impl Parser {
                 Some(Token::Like) => {
                     self.advance();
                     let pattern = self.parse_additive()?;
+                    let escape = self.parse_like_escape()?;
                     return Ok(Expr::Like {
                         expr: Box::new(left),
                         pattern: Box::new(pattern),
+                        escape,
                         negated: true,
                     });
                 }
@@ -328,9 +404,11 @@ impl Parser {
         if self.peek() == Some(&Token::Like) {
             self.advance();
             let pattern = self.parse_additive()?;
+            let escape = self.parse_like_escape()?;
             return Ok(Expr::Like {
                 expr: Box::new(left),
                 pattern: Box::new(pattern),
+                escape,
                 negated: false,
             });
         }
@@ -400,6 +478,9 @@ impl Parser {
         // Otherwise parse as a regular value list
         let mut list = Vec::new();
         loop {
+            if list.len() == Limit::InListItems.max() {
+                return self.exceed_limit(Limit::InListItems, list.len() + 1);
+            }
             list.push(self.parse_expr()?);
             if self.peek() == Some(&Token::Comma) {
                 self.advance();
@@ -480,6 +561,9 @@ impl Parser {
                         self.expect(&Token::Join)?;
                         Some(JoinType::Cross)
                     }
+                    _ if self.is_full_join_ahead() => {
+                        return Err("FULL OUTER JOIN is not supported; combine a LEFT JOIN and a RIGHT JOIN with UNION".into());
+                    }
                     _ => None,
                 };
 
@@ -545,42 +629,9 @@ impl Parser {
             None
         };
 
-        let order_by = if self.peek() == Some(&Token::Order) {
-            self.advance();
-            self.expect(&Token::By)?;
-            let mut items = Vec::new();
-            loop {
-                let expr = self.parse_expr()?;
-                let descending = if self.peek() == Some(&Token::Desc) {
-                    self.advance();
-                    true
-                } else if self.peek() == Some(&Token::Asc) {
-                    self.advance();
-                    false
-                } else {
-                    false
-                };
-                items.push(OrderByItem { expr, descending });
-                if self.peek() == Some(&Token::Comma) {
-                    self.advance();
-                } else {
-                    break;
-                }
-            }
-            Some(items)
-        } else {
-            None
-        };
+        let order_by = self.parse_order_by_clause()?;
 
-        let limit = if self.peek() == Some(&Token::Limit) {
-            self.advance();
-            match self.advance() {
-                Some(Token::Integer(n)) => Some(n as u64),
-                _ => return Err("Expected integer after LIMIT".into()),
-            }
-        } else {
-            None
-        };
+        let limit = self.parse_limit_clause()?;
 
         let offset = if self.peek() == Some(&Token::Offset) {
             self.advance();
@@ -612,3 +663,14 @@ impl Parser {
         })
     }
 }
+
+/// ORDER BY, LIMIT and OFFSET are only allowed after the last SELECT of a
+/// UNION, where they apply to the whole result.
+fn reject_union_branch_clauses(sel: &Select) -> Result<(), String> {
+    if sel.order_by.is_some() || sel.limit.is_some() || sel.offset.is_some() {
+        return Err(
+            "ORDER BY, LIMIT and OFFSET must follow the last SELECT of a UNION".to_string(),
+        );
+    }
+    Ok(())
+}
@@ -70,6 +70,9 @@ impl Parser {
                         self.expect(&Token::Join)?;
                         Some(JoinType::Cross)
                     }
+                    _ if self.is_full_join_ahead() => {
+                        return Err("FULL OUTER JOIN is not supported; combine a LEFT JOIN and a RIGHT JOIN with UNION".into());
+                    }
                     _ => None,
                 };
 
@@ -137,42 +140,9 @@ impl Parser {
             None
         };
 
-        let order_by = if self.peek() == Some(&Token::Order) {
-            self.advance();
-            self.expect(&Token::By)?;
-            let mut items = Vec::new();
-            loop {
-                let expr = self.parse_expr()?;
-                let descending = if self.peek() == Some(&Token::Desc) {
-                    self.advance();
-                    true
-                } else if self.peek() == Some(&Token::Asc) {
-                    self.advance();
-                    false
-                } else {
-                    false
-                };
-                items.push(OrderByItem { expr, descending });
-                if self.peek() == Some(&Token::Comma) {
-                    self.advance();
-                } else {
-                    break;
-                }
-            }
-            Some(items)
-        } else {
-            None
-        };
+        let order_by = self.parse_order_by_clause()?;
 
-        let limit = if self.peek() == Some(&Token::Limit) {
-            self.advance();
-            match self.advance() {
-                Some(Token::Integer(n)) => Some(n as u64),
-                _ => return Err("Expected integer after LIMIT".into()),
-            }
-        } else {
-            None
-        };
+        let limit = self.parse_limit_clause()?;
 
         let offset = if self.peek() == Some(&Token::Offset) {
             self.advance();
@@ -1,5 +1,5 @@
 use super::*;
-use crate::types::DataType;
+use crate::types::{Collation, DataType};
 
 #[test]
 fn test_parse_create_table() {
@@ -29,6 +29,67 @@ fn test_parse_create_table_if_not_exists() {
     }
 }
 
+#[test]
+fn test_parse_auto_increment_table_option() {
+    let Statement::CreateTable(ct) = parse_sql(
+        "CREATE TABLE t (id BIGINT PRIMARY KEY AUTO_INCREMENT) AUTO_INCREMENT = 42 WITH (fill_factor = 90)",
+    )
+    .unwrap() else {
+        panic!("Expected CreateTable");
+    };
+    assert_eq!(ct.auto_increment, Some(42));
+    assert_eq!(ct.fill_factor, Some(90));
+
+    let Statement::CreateTable(ct) =
+        parse_sql("CREATE TABLE t (id BIGINT PRIMARY KEY AUTO_INCREMENT) AUTO_INCREMENT 7")
+            .unwrap()
+    else {
+        panic!("Expected CreateTable");
+    };
+    assert_eq!(ct.auto_increment, Some(7));
+    assert!(parse_sql("CREATE TABLE t (id BIGINT PRIMARY KEY) AUTO_INCREMENT = 0").is_err());
+}
+
+#[test]
+fn test_parse_fill_factor_options() {
+    let Statement::CreateTable(ct) =
+        parse_sql("CREATE TABLE t (id BIGINT PRIMARY KEY, KEY idx_v (v) WITH (fill_factor = 70), v INT) WITH (fill_factor = 90)")
+            .unwrap()
+    else {
+        panic!("Expected CreateTable");
+    };
+    assert_eq!(ct.fill_factor, Some(90));
+    assert_eq!(ct.auto_increment, None);
+    match &ct.constraints[0] {
+        TableConstraint::Index(ci) => assert_eq!(ci.fill_factor, Some(70)),
+        other => panic!("unexpected constraint {:?}", other),
+    }
+
+    let Statement::CreateIndex(ci) =
+        parse_sql("CREATE UNIQUE INDEX idx ON t (a, b) WITH (FILL_FACTOR = 10)").unwrap()
+    else {
+        panic!("Expected CreateIndex");
+    };
+    assert_eq!(ci.fill_factor, Some(10));
+    let Statement::CreateIndex(ci) = parse_sql("CREATE INDEX idx ON t (a)").unwrap() else {
+        panic!("Expected CreateIndex");
+    };
+    assert_eq!(ci.fill_factor, None);
+
+    for sql in [
+        "CREATE TABLE t (id BIGINT PRIMARY KEY) WITH (fill_factor = 9)",
+        "CREATE TABLE t (id BIGINT PRIMARY KEY) WITH (fill_factor = 101)",
+        "CREATE INDEX idx ON t (a) WITH (fill_factor = 'x')",
+        "CREATE INDEX idx ON t (a) WITH (page_size = 4096)",
+    ] {
+        assert!(parse_sql(sql).is_err(), "{}", sql);
+    }
+    assert_eq!(
+        parse_sql("CREATE TABLE t (id BIGINT PRIMARY KEY) WITH (fill_factor = 0)").unwrap_err(),
+        "fill_factor must be between 10 and 100, got 0"
+    );
+}
+
 #[test]
 fn test_parse_create_table_with_foreign_key() {
     let stmt = parse_sql(
@@ -143,6 +204,29 @@ fn test_parse_delete() {
     }
 }
 
+#[test]
+fn test_parse_update_delete_order_by_limit() {
+    let stmt = parse_sql("UPDATE t SET n = n + 1 WHERE a = 1 ORDER BY b DESC, id LIMIT 5").unwrap();
+    if let Statement::Update(upd) = stmt {
+        let order_by = upd.order_by.unwrap();
+        assert_eq!(order_by.len(), 2);
+        assert!(order_by[0].descending);
+        assert_eq!(upd.limit, Some(5));
+    } else {
+        panic!("Expected Update");
+    }
+
+    let stmt = parse_sql("DELETE FROM t LIMIT 3").unwrap();
+    if let Statement::Delete(del) = stmt {
+        assert!(del.order_by.is_none());
+        assert_eq!(del.limit, Some(3));
+    } else {
+        panic!("Expected Delete");
+    }
+
+    assert!(parse_sql("DELETE FROM t ORDER BY id LIMIT -1").is_err());
+}
+
 #[test]
 fn test_parse_savepoint() {
     let stmt = parse_sql("SAVEPOINT sp1").unwrap();
@@ -187,6 +271,29 @@ fn test_parse_create_unique_index() {
     }
 }
 
+#[test]
+fn test_parse_create_expression_index() {
+    let stmt = parse_sql("CREATE INDEX idx ON users (id, (LOWER(email)))").unwrap();
+    if let Statement::CreateIndex(ci) = stmt {
+        assert_eq!(ci.column_names, vec!["id".to_string(), String::new()]);
+        assert!(ci.expressions[0].is_none());
+        assert!(matches!(
+            &ci.expressions[1],
+            Some(Expr::FunctionCall { name, .. }) if name == "LOWER"
+        ));
+    } else {
+        panic!("Expected CreateIndex");
+    }
+
+    // Plain column lists keep `expressions` empty.
+    let stmt = parse_sql("CREATE INDEX idx ON users (id, email)").unwrap();
+    if let Statement::CreateIndex(ci) = stmt {
+        assert!(ci.expressions.is_empty());
+    } else {
+        panic!("Expected CreateIndex");
+    }
+}
+
 #[test]
 fn test_parse_create_fulltext_index() {
     let stmt = parse_sql(
@@ -238,6 +345,29 @@ fn test_parse_create_fulltext_index_rejects_too_large_stop_df_ratio_ppm() {
     assert!(err.contains("stop_df_ratio_ppm is too large"));
 }
 
+#[test]
+fn test_parse_create_fulltext_index_stop_fallback_options() {
+    let stmt = parse_sql(
+        "CREATE FULLTEXT INDEX ft_body ON t(body) WITH PARSER ngram OPTIONS (stop_fallback='empty_with_warning', stop_fallback_max_docs=500)",
+    )
+    .unwrap();
+    if let Statement::CreateFulltextIndex(fi) = stmt {
+        assert_eq!(
+            fi.stop_fallback,
+            crate::fts::query::FtsStopFallback::EmptyWithWarning
+        );
+        assert_eq!(fi.stop_fallback_max_docs, 500);
+    } else {
+        panic!("Expected CreateFulltextIndex");
+    }
+
+    let err = parse_sql(
+        "CREATE FULLTEXT INDEX ft_body ON t(body) WITH PARSER ngram OPTIONS (stop_fallback='retry')",
+    )
+    .unwrap_err();
+    assert!(err.contains("Invalid stop_fallback value"), "{}", err);
+}
+
 #[test]
 fn test_parse_match_against() {
     let stmt = parse_sql(
@@ -295,20 +425,95 @@ fn test_parse_show_create_table() {
 }
 
 #[test]
-fn test_parse_set_runtime_option() {
-    let stmt = parse_sql("SET checkpoint_tx_threshold = 8").unwrap();
-    if let Statement::SetRuntimeOption(set_stmt) = stmt {
-        assert_eq!(set_stmt.option, RuntimeOption::CheckpointTxThreshold);
-        assert_eq!(set_stmt.value, 8);
+fn test_parse_show_indexes() {
+    for sql in ["SHOW INDEXES FROM users", "SHOW INDEX FROM users"] {
+        match parse_sql(sql).unwrap() {
+            Statement::ShowIndexes(name) => assert_eq!(name, "users"),
+            _ => panic!("Expected ShowIndexes"),
+        }
+    }
+    assert!(parse_sql("SHOW INDEXES users").is_err());
+}
+
+#[test]
+fn test_parse_create_table_with_keys() {
+    let stmt = parse_sql(
+        "CREATE TABLE t (id BIGINT PRIMARY KEY, a INT, b INT, KEY k (a, b), UNIQUE KEY u (b))",
+    )
+    .unwrap();
+    if let Statement::CreateTable(ct) = stmt {
+        let keys: Vec<(String, bool, Vec<String>)> = ct
+            .constraints
+            .iter()
+            .filter_map(|c| match c {
+                TableConstraint::Index(ci) => {
+                    assert_eq!(ci.table_name, "t");
+                    Some((ci.index_name.clone(), ci.is_unique, ci.column_names.clone()))
+                }
+                _ => None,
+            })
+            .collect();
+        assert_eq!(
+            keys,
+            vec![
+                (
+                    "k".to_string(),
+                    false,
+                    vec!["a".to_string(), "b".to_string()]
+                ),
+                ("u".to_string(), true, vec!["b".to_string()]),
+            ]
+        );
     } else {
-        panic!("Expected SetRuntimeOption");
+        panic!("Expected CreateTable");
     }
 }
 
+fn parse_set_variable(sql: &str) -> SetVariable {
+    match parse_sql(sql).unwrap() {
+        Statement::SetVariable(set_stmt) => set_stmt,
+        other => panic!("Expected SetVariable, got {:?}", other),
+    }
+}
+
+#[test]
+fn test_parse_set_variable() {
+    let set_stmt = parse_set_variable("SET checkpoint_tx_threshold = 8");
+    assert_eq!(set_stmt.name, "checkpoint_tx_threshold");
+    assert_eq!(set_stmt.value, SetValue::Integer(8));
+    let set_stmt = parse_set_variable("SET Busy_Timeout = -250");
+    assert_eq!(set_stmt.name, "busy_timeout");
+    assert_eq!(set_stmt.value, SetValue::Integer(-250));
+    let set_stmt = parse_set_variable("SET scan_corruption_policy = 'SKIP'");
+    assert_eq!(set_stmt.value, SetValue::Word("skip".into()));
+    let set_stmt = parse_set_variable("SET predicate_reorder = ON");
+    assert_eq!(set_stmt.value, SetValue::Word("on".into()));
+    let set_stmt = parse_set_variable("SET read_only = off");
+    assert_eq!(set_stmt.value, SetValue::Word("off".into()));
+    // Names and values are checked when the statement runs.
+    parse_set_variable("SET unknown_setting = 1");
+    assert!(parse_sql("SET busy_timeout 1").is_err());
+    assert!(parse_sql("SET busy_timeout = 1.5").is_err());
+}
+
 #[test]
-fn test_parse_set_runtime_option_rejects_unknown_name() {
-    let err = parse_sql("SET unknown_runtime_option = 1").unwrap_err();
-    assert!(err.contains("Unknown runtime option"));
+fn test_parse_show_variables_and_warnings() {
+    assert!(matches!(
+        parse_sql("SHOW VARIABLES").unwrap(),
+        Statement::ShowVariables(None)
+    ));
+    assert!(matches!(
+        parse_sql("SHOW VARIABLES LIKE 'checkpoint%'").unwrap(),
+        Statement::ShowVariables(Some(p)) if p == "checkpoint%"
+    ));
+    assert!(matches!(
+        parse_sql("SHOW WARNINGS").unwrap(),
+        Statement::ShowWarnings
+    ));
+    assert!(matches!(
+        parse_sql("show processlist").unwrap(),
+        Statement::ShowProcesslist
+    ));
 }
 
 #[test]
@@ -347,6 +552,27 @@ fn test_parse_not_like() {
     }
 }
 
+#[test]
+fn test_parse_like_escape() {
+    let stmt =
+        parse_sql("SELECT * FROM t WHERE name NOT LIKE '50!%' escape '!' AND id = 1").unwrap();
+    if let Statement::Select(sel) = stmt {
+        let Some(Expr::BinaryOp { left, .. }) = sel.where_clause else {
+            panic!("Expected AND");
+        };
+        match *left {
+            Expr::Like {
+                escape: Some(escape),
+                negated: true,
+                ..
+            } => assert!(matches!(*escape, Expr::StringLiteral(ref e) if e == "!")),
+            other => panic!("Expected LIKE ... ESCAPE, got {:?}", other),
+        }
+    } else {
+        panic!("Expected Select");
+    }
+}
+
 #[test]
 fn test_parse_bind_parameter() {
     let stmt = parse_sql("SELECT * FROM t WHERE id = ?").unwrap();
@@ -369,7 +595,7 @@ fn test_parse_alter_table_add_foreign_key() {
     let Statement::AlterTable(at) = stmt else {
         panic!("Expected AlterTable");
     };
-    match at.operation {
+    match at.operations.into_iter().next().unwrap() {
         AlterTableOp::AddForeignKey(fk) => {
             assert_eq!(fk.columns, vec!["parent_id".to_string()]);
             assert_eq!(fk.ref_table, "parent");
@@ -387,7 +613,7 @@ fn test_parse_alter_table_drop_foreign_key() {
     let Statement::AlterTable(at) = stmt else {
         panic!("Expected AlterTable");
     };
-    match at.operation {
+    match at.operations.into_iter().next().unwrap() {
         AlterTableOp::DropForeignKey(cols) => {
             assert_eq!(cols, vec!["parent_id".to_string()]);
         }
@@ -395,6 +621,57 @@ fn test_parse_alter_table_drop_foreign_key() {
     }
 }
 
+#[test]
+fn test_parse_alter_table_exchange_with() {
+    let stmt = parse_sql("ALTER TABLE live exchange WITH staging").unwrap();
+    let Statement::AlterTable(at) = stmt else {
+        panic!("Expected AlterTable");
+    };
+    assert_eq!(at.table_name, "live");
+    match at.operations.into_iter().next().unwrap() {
+        AlterTableOp::ExchangeWith(other) => assert_eq!(other, "staging"),
+        _ => panic!("Expected ExchangeWith"),
+    }
+    assert!(parse_sql("ALTER TABLE live EXCHANGE staging").is_err());
+}
+
+#[test]
+fn test_parse_alter_table_index_ops() {
+    let stmt = parse_sql(
+        "ALTER TABLE t ADD COLUMN x INT, ADD UNIQUE KEY u (a, b), ADD INDEX (x), \
+         DROP KEY k, RENAME INDEX old TO new",
+    )
+    .unwrap();
+    let Statement::AlterTable(at) = stmt else {
+        panic!("Expected AlterTable");
+    };
+    assert_eq!(at.operations.len(), 5);
+    assert!(matches!(at.operations[0], AlterTableOp::AddColumn(_)));
+    match &at.operations[1] {
+        AlterTableOp::AddIndex(ci) => {
+            assert_eq!(ci.index_name, "u");
+            assert!(ci.is_unique);
+        }
+        other => panic!("Expected AddIndex, got {:?}", other),
+    }
+    match &at.operations[2] {
+        AlterTableOp::AddIndex(ci) => {
+            assert_eq!(ci.index_name, "auto_index_t_x");
+            assert!(!ci.is_unique);
+        }
+        other => panic!("Expected AddIndex, got {:?}", other),
+    }
+    assert!(matches!(&at.operations[3], AlterTableOp::DropIndex(name) if name == "k"));
+    assert!(matches!(
+        &at.operations[4],
+        AlterTableOp::RenameIndex(old, new) if old == "old" && new == "new"
+    ));
+
+    assert!(parse_sql("ALTER TABLE t ADD INDEX ((a + 1))").is_err());
+    assert!(parse_sql("ALTER TABLE t RENAME INDEX a b").is_err());
+    assert!(parse_sql("ALTER TABLE t DROP INDEX a, EXCHANGE WITH u").is_err());
+}
+
 #[test]
 fn test_parse_in() {
     let stmt = parse_sql("SELECT * FROM t WHERE id IN (1, 2, 3)").unwrap();
@@ -533,6 +810,36 @@ fn test_parse_check_constraint() {
     }
 }
 
+#[test]
+fn test_parse_collate() {
+    let stmt = parse_sql(
+        "CREATE TABLE t (id BIGINT PRIMARY KEY, name VARCHAR(50) COLLATE nocase NOT NULL)",
+    )
+    .unwrap();
+    if let Statement::CreateTable(ct) = stmt {
+        assert_eq!(ct.columns[1].collation, Some(Collation::Nocase));
+        assert!(!ct.columns[1].is_nullable);
+        assert_eq!(ct.columns[0].collation, None);
+    } else {
+        panic!("Expected CreateTable");
+    }
+
+    // COLLATE binds tighter than comparison.
+    let stmt = parse_sql("SELECT * FROM t WHERE name COLLATE 'NOCASE' = 'a'").unwrap();
+    if let Statement::Select(sel) = stmt {
+        assert!(matches!(
+            sel.where_clause,
+            Some(Expr::BinaryOp { ref left, op: BinaryOp::Eq, .. })
+                if matches!(left.as_ref(), Expr::Collate { collation: Collation::Nocase, .. })
+        ));
+    } else {
+        panic!("Expected Select");
+    }
+
+    let err = parse_sql("SELECT * FROM t WHERE name COLLATE utf8mb4_bin = 'a'").unwrap_err();
+    assert!(err.contains("Unknown collation 'utf8mb4_bin'"), "{}", err);
+}
+
 #[test]
 fn test_parse_boolean_type() {
     let stmt =
@@ -579,3 +886,88 @@ fn test_parse_analyze_table() {
         panic!("Expected AnalyzeTable");
     }
 }
+
+#[test]
+fn test_parse_optimize_table_and_show_table_status() {
+    let stmt = parse_sql("optimize table users;").unwrap();
+    if let Statement::OptimizeTable(name) = stmt {
+        assert_eq!(name, "users");
+    } else {
+        panic!("Expected OptimizeTable");
+    }
+    assert!(matches!(
+        parse_sql("SHOW TABLE STATUS").unwrap(),
+        Statement::ShowTableStatus
+    ));
+    assert!(parse_sql("SHOW TABLE users").is_err());
+    assert!(parse_sql("OPTIMIZE users").is_err());
+    assert!(matches!(
+        parse_sql("optimize").unwrap(),
+        Statement::Optimize
+    ));
+    assert!(matches!(
+        parse_sql("OPTIMIZE;").unwrap(),
+        Statement::Optimize
+    ));
+}
+
+#[test]
+fn test_parse_script_splits_on_top_level_semicolons() {
+    let sql = "CREATE TABLE t (id BIGINT PRIMARY KEY, s VARCHAR);\n\
+               INSERT INTO t VALUES (1, 'a;b'), (2, ';');;\n\
+               SELECT * FROM t WHERE s = ';'";
+    let stmts = parse_script_with_offsets(sql).unwrap();
+    assert_eq!(stmts.len(), 3);
+    assert!(matches!(stmts[0].statement, Statement::CreateTable(_)));
+    assert!(matches!(stmts[1].statement, Statement::Insert(_)));
+    assert!(matches!(stmts[2].statement, Statement::Select(_)));
+    assert_eq!(stmts[0].offset, 0);
+    assert_eq!(stmts[1].offset, sql.find("INSERT").unwrap());
+    assert_eq!(stmts[2].offset, sql.find("SELECT").unwrap());
+
+    assert!(parse_script("").unwrap().is_empty());
+    assert!(parse_script(" ; ;").unwrap().is_empty());
+    assert_eq!(parse_script("BEGIN; COMMIT;").unwrap().len(), 2);
+}
+
+#[test]
+fn test_parse_script_reports_failing_statement() {
+    let err = parse_script_with_offsets("SELECT 1; SELEC 2; SELECT 3").unwrap_err();
+    assert_eq!((err.index, err.offset), (1, 10));
+    assert!(err.message.contains("Unexpected token"), "{}", err.message);
+
+    // Character offsets, not bytes.
+    let err = parse_script_with_offsets("SELECT 'é'; SELECT 1 @").unwrap_err();
+    assert_eq!((err.index, err.offset), (1, 21));
+    let err = parse_script_with_offsets("SELECT 'é'; @").unwrap_err();
+    assert_eq!((err.index, err.offset), (1, 12));
+    assert_eq!(
+        parse_script("SELECT 1; SELECT").unwrap_err(),
+        "statement 1 at offset 10: Unexpected end of input in expression"
+    );
+
+    // An unclosed parenthesis fails its own statement, not the script's end.
+    let err = parse_script_with_offsets("SELECT (1; SELECT 2; SELECT 3").unwrap_err();
+    assert_eq!((err.index, err.offset), (0, 0));
+    let err = parse_script_with_offsets("SELECT 1; SELECT (2; SELECT 3)").unwrap_err();
+    assert_eq!((err.index, err.offset), (1, 10));
+}
+
+#[test]
+fn test_parse_temporary_table() {
+    let Statement::CreateTable(ct) =
+        parse_sql("CREATE TEMPORARY TABLE IF NOT EXISTS tmp (id BIGINT PRIMARY KEY)").unwrap()
+    else {
+        panic!("Expected CreateTable");
+    };
+    assert!(ct.temporary);
+    assert!(ct.if_not_exists);
+    assert_eq!(ct.table_name, "tmp");
+
+    let Statement::DropTable(dt) = parse_sql("DROP TEMPORARY TABLE IF EXISTS tmp").unwrap() else {
+        panic!("Expected DropTable");
+    };
+    assert!(dt.temporary);
+    assert!(dt.if_exists);
+    assert!(parse_sql("DROP TEMPORARY INDEX i").is_err());
+}
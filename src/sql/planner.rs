@@ -3,10 +3,22 @@
 /// Plan types:
 ///   PkSeek(key)       - Primary key lookup
 ///   IndexSeek(idx, key) - Secondary index lookup
+///   InListSeek(idx?, values) - One PK or index lookup per IN list value
 ///   FullScan          - Full table scan
 ///   FtsScan(col, query, mode) - FTS search
 use crate::sql::ast::*;
 use crate::sql::eval::eval_expr;
+use crate::sql::index_expr::index_expr_text;
+use crate::sql::session::in_list_seek_max_items_current;
+
+/// ANALYZE TABLE statistics of one indexed column.
+#[derive(Debug, Clone)]
+pub struct ColumnPlanStat {
+    pub distinct_count: u64,
+    pub null_count: u64,
+    pub min: Option<i64>,
+    pub max: Option<i64>,
+}
 
 #[derive(Debug, Clone)]
 pub struct IndexPlanStat {
@@ -17,12 +29,44 @@ pub struct IndexPlanStat {
     pub stats_num_min: Option<i64>,
     pub stats_num_max: Option<i64>,
     pub stats_num_hist_bins: Vec<u32>,
+    /// Per-column stats aligned with `column_names` (`None` = not analyzed).
+    pub column_stats: Vec<Option<ColumnPlanStat>>,
 }
 
+/// Growth or shrink factor past which ANALYZE stats are treated as stale.
+pub const STALE_STATS_FACTOR: u64 = 10;
+
+/// A secondary-index plan estimated to return more than this share of the
+/// table loses to a full scan (only with fresh stats and no FORCE INDEX).
+pub const FULL_SCAN_THRESHOLD_PCT: u64 = 50;
+
 #[derive(Debug, Clone, Copy, Default)]
 pub struct PlannerStats {
     /// 0 means unknown/not analyzed.
     pub table_rows: u64,
+    /// Current approximate row count (0 = not measured).
+    pub live_rows: u64,
+}
+
+impl PlannerStats {
+    /// Whether the table has grown or shrunk by `STALE_STATS_FACTOR` or more
+    /// since ANALYZE TABLE. Stale distribution stats are ignored.
+    pub fn stats_are_stale(&self) -> bool {
+        if self.table_rows == 0 || self.live_rows == 0 {
+            return false;
+        }
+        self.live_rows >= self.table_rows.saturating_mul(STALE_STATS_FACTOR)
+            || self.table_rows >= self.live_rows.saturating_mul(STALE_STATS_FACTOR)
+    }
+}
+
+/// One candidate access path the planner costed, for EXPLAIN.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PlanEstimate {
+    /// Index name and access type (e.g. `idx_a ref`), or `ALL`.
+    pub label: String,
+    pub rows: u64,
+    pub cost: u64,
 }
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
@@ -31,7 +75,7 @@ pub enum JoinLoopOrder {
     RightOuter,
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub enum Plan {
     PkSeek {
         table_name: String,
@@ -51,6 +95,14 @@ pub enum Plan {
         lower: Option<(Box<Expr>, bool)>, // (expr, inclusive)
         upper: Option<(Box<Expr>, bool)>, // (expr, inclusive)
     },
+    /// `column IN (values)` on a single-column primary key (`index_name`
+    /// is `None`) or index: one seek per distinct value, in key order.
+    InListSeek {
+        table_name: String,
+        index_name: Option<String>,
+        column_name: String,
+        values: Vec<Expr>,
+    },
     FullScan {
         table_name: String,
     },
@@ -100,6 +152,10 @@ pub fn plan_cost_hint_with_stats(
                 .saturating_sub(bound_terms.saturating_mul(250))
                 .saturating_add(est_rows.saturating_mul(3))
         }
+        Plan::InListSeek {
+            index_name: None, ..
+        } => 100u64.saturating_add(est_rows.saturating_mul(2)),
+        Plan::InListSeek { .. } => 1_300u64.saturating_add(est_rows.saturating_mul(3)),
         Plan::FtsScan { .. } => 2_000u64.saturating_add(est_rows.saturating_mul(2)),
         Plan::FullScan { .. } => 3_000u64.saturating_add(est_rows.saturating_mul(5)),
     }
@@ -112,6 +168,14 @@ pub fn estimate_plan_rows_hint(
     index_stats: &[IndexPlanStat],
 ) -> u64 {
     let table_rows = table_rows_hint(planner_stats);
+    // Stale stats keep only what does not drift: uniqueness and column names.
+    let stale = planner_stats.stats_are_stale();
+    let find_index = |name: &str| {
+        index_stats
+            .iter()
+            .find(|idx| idx.name == name)
+            .filter(|_| !stale)
+    };
     match plan {
         Plan::PkSeek { .. } => 1,
         Plan::IndexSeek {
@@ -119,11 +183,17 @@ pub fn estimate_plan_rows_hint(
             key_exprs,
             ..
         } => {
-            let index = index_stats.iter().find(|idx| idx.name == *index_name);
-            let full_key_equality = index
+            let full_key_equality = index_stats
+                .iter()
+                .find(|idx| idx.name == *index_name)
                 .map(|idx| idx.is_unique && key_exprs.len() == idx.column_names.len())
                 .unwrap_or(false);
-            estimate_index_seek_rows(table_rows, key_exprs.len(), index, full_key_equality)
+            estimate_index_seek_rows(
+                table_rows,
+                key_exprs,
+                find_index(index_name),
+                full_key_equality,
+            )
         }
         Plan::IndexRangeSeek {
             index_name,
@@ -132,26 +202,56 @@ pub fn estimate_plan_rows_hint(
             upper,
             ..
         } => {
-            let index = index_stats.iter().find(|idx| idx.name == *index_name);
-            let prefix_rows =
-                estimate_index_seek_rows(table_rows, prefix_key_exprs.len(), index, false);
+            let index = find_index(index_name);
+            let prefix_rows = estimate_index_seek_rows(table_rows, prefix_key_exprs, index, false);
             let ranged_rows = if prefix_key_exprs.is_empty() {
-                estimate_numeric_range_rows(prefix_rows, lower, upper, index).unwrap_or_else(|| {
-                    match (lower.is_some(), upper.is_some()) {
-                        (true, true) => div_ceil(prefix_rows, 5),
-                        (true, false) | (false, true) => div_ceil(prefix_rows, 2),
-                        (false, false) => prefix_rows,
-                    }
-                })
+                estimate_numeric_range_rows(prefix_rows, lower, upper, index)
             } else {
-                match (lower.is_some(), upper.is_some()) {
-                    (true, true) => div_ceil(prefix_rows, 5),
-                    (true, false) | (false, true) => div_ceil(prefix_rows, 2),
-                    (false, false) => prefix_rows,
-                }
-            };
+                // The range column's own min/max, when ANALYZE captured them.
+                index
+                    .and_then(|idx| idx.column_stats.get(prefix_key_exprs.len()))
+                    .and_then(Option::as_ref)
+                    .and_then(|cs| {
+                        estimate_bounded_range_rows(
+                            prefix_rows,
+                            lower,
+                            upper,
+                            cs.min?,
+                            cs.max?,
+                            &[],
+                        )
+                    })
+            }
+            .unwrap_or_else(|| match (lower.is_some(), upper.is_some()) {
+                (true, true) => div_ceil(prefix_rows, 5),
+                (true, false) | (false, true) => div_ceil(prefix_rows, 2),
+                (false, false) => prefix_rows,
+            });
             ranged_rows.max(1).min(table_rows)
         }
+        Plan::InListSeek {
+            index_name: None,
+            values,
+            ..
+        } => (values.len() as u64).min(table_rows).max(1),
+        Plan::InListSeek {
+            index_name: Some(index_name),
+            values,
+            ..
+        } => {
+            let index = index_stats.iter().find(|idx| idx.name == *index_name);
+            let unique = index.is_some_and(|idx| idx.is_unique);
+            let per_value = estimate_index_seek_rows(
+                table_rows,
+                &values[..1],
+                index.filter(|_| !stale),
+                unique,
+            );
+            per_value
+                .saturating_mul(values.len() as u64)
+                .min(table_rows)
+                .max(1)
+        }
         Plan::FullScan { .. } => table_rows,
         Plan::FtsScan { .. } => div_ceil(table_rows.saturating_mul(3), 10).max(1),
     }
@@ -184,6 +284,28 @@ pub fn plan_select_with_hints(
     planner_stats: PlannerStats,
     index_hints: &[IndexHint],
 ) -> Plan {
+    plan_select_with_estimates(
+        table_name,
+        pk_columns,
+        index_stats,
+        where_clause,
+        planner_stats,
+        index_hints,
+    )
+    .0
+}
+
+/// Like [`plan_select_with_hints`], also returning the estimate of every
+/// index candidate and of the full scan it was compared against. The list is
+/// empty when no index applies (or a PK / FULLTEXT plan wins outright).
+pub fn plan_select_with_estimates(
+    table_name: &str,
+    pk_columns: &[String],
+    index_stats: &[IndexPlanStat],
+    where_clause: &Option<Expr>,
+    planner_stats: PlannerStats,
+    index_hints: &[IndexHint],
+) -> (Plan, Vec<PlanEstimate>) {
     // Determine hint behavior
     let has_force = index_hints
         .iter()
@@ -213,8 +335,16 @@ pub fn plan_select_with_hints(
     };
 
     let mut best_candidate: Option<(u64, String, Plan)> = None;
-    let consider = |best: &mut Option<(u64, String, Plan)>, plan: Plan, tie_key: String| {
+    let mut estimates: Vec<PlanEstimate> = Vec::new();
+    let mut consider = |best: &mut Option<(u64, String, Plan)>, plan: Plan, tie_key: String| {
         let cost = plan_cost_hint_with_stats(&plan, &planner_stats, index_stats);
+        if let Some(label) = plan_label(&plan) {
+            estimates.push(PlanEstimate {
+                label,
+                rows: estimate_plan_rows_hint(&plan, &planner_stats, index_stats),
+                cost,
+            });
+        }
         match best {
             Some((best_cost, best_tie, _)) if *best_cost < cost => {}
             Some((best_cost, best_tie, _)) if *best_cost == cost && *best_tie <= tie_key => {}
@@ -225,12 +355,13 @@ pub fn plan_select_with_hints(
     if let Some(expr) = where_clause {
         // Check for FTS MATCH...AGAINST
         if let Some((column, query, mode)) = extract_fts_match(expr) {
-            return Plan::FtsScan {
+            let plan = Plan::FtsScan {
                 table_name: table_name.to_string(),
                 column,
                 query,
                 mode,
             };
+            return (plan, Vec::new());
         }
 
         // Check for PK equality (single or composite)
@@ -246,10 +377,11 @@ pub fn plan_select_with_hints(
                         None
                     }
                 }) {
-                    return Plan::PkSeek {
+                    let plan = Plan::PkSeek {
                         table_name: table_name.to_string(),
                         key_exprs: vec![(pk_columns[0].clone(), key_expr)],
                     };
+                    return (plan, Vec::new());
                 }
             } else {
                 // Composite PK: need all columns matched
@@ -264,14 +396,32 @@ pub fn plan_select_with_hints(
                     }
                 }
                 if all_found {
-                    return Plan::PkSeek {
+                    let plan = Plan::PkSeek {
                         table_name: table_name.to_string(),
                         key_exprs,
                     };
+                    return (plan, Vec::new());
                 }
             }
         }
 
+        // IN lists on a single-column primary key or index: a seek per value.
+        let in_lists = extract_in_lists(expr, in_list_seek_max_items_current());
+        if !skip_pk && pk_columns.len() == 1 {
+            if let Some((_, values)) = in_lists.iter().find(|(col, _)| col == &pk_columns[0]) {
+                consider(
+                    &mut best_candidate,
+                    Plan::InListSeek {
+                        table_name: table_name.to_string(),
+                        index_name: None,
+                        column_name: pk_columns[0].clone(),
+                        values: values.clone(),
+                    },
+                    "2:".to_string(),
+                );
+            }
+        }
+
         // Check for index equality (single or composite)
         let equalities = extract_equalities(expr);
         let ranges = extract_ranges(expr);
@@ -302,6 +452,18 @@ pub fn plan_select_with_hints(
                         );
                     }
                 }
+                if let Some((_, values)) = in_lists.iter().find(|(col, _)| col == &col_names[0]) {
+                    consider(
+                        &mut best_candidate,
+                        Plan::InListSeek {
+                            table_name: table_name.to_string(),
+                            index_name: Some(idx_name.clone()),
+                            column_name: col_names[0].clone(),
+                            values: values.clone(),
+                        },
+                        format!("2:{}", idx_name),
+                    );
+                }
                 if let Some(range) = ranges.get(&col_names[0]) {
                     consider(
                         &mut best_candidate,
@@ -371,17 +533,48 @@ pub fn plan_select_with_hints(
         }
     }
 
-    if let Some((_, _, plan)) = best_candidate {
-        return plan;
+    let full_scan = Plan::FullScan {
+        table_name: table_name.to_string(),
+    };
+    let Some((_, _, plan)) = best_candidate else {
+        return (full_scan, estimates);
+    };
+    let table_rows = table_rows_hint(&planner_stats);
+    estimates.push(PlanEstimate {
+        label: "ALL".to_string(),
+        rows: table_rows,
+        cost: plan_cost_hint_with_stats(&full_scan, &planner_stats, index_stats),
+    });
+    // Reading most of the table through a secondary index costs a PK lookup
+    // per row; a sequential scan is cheaper. Only trust that call with fresh
+    // stats, and never override FORCE INDEX.
+    let est_rows = estimate_plan_rows_hint(&plan, &planner_stats, index_stats);
+    if !has_force
+        && planner_stats.table_rows > 0
+        && !planner_stats.stats_are_stale()
+        && est_rows.saturating_mul(100) > table_rows.saturating_mul(FULL_SCAN_THRESHOLD_PCT)
+    {
+        return (full_scan, estimates);
     }
+    (plan, estimates)
+}
 
-    Plan::FullScan {
-        table_name: table_name.to_string(),
+/// EXPLAIN label of an index candidate.
+fn plan_label(plan: &Plan) -> Option<String> {
+    match plan {
+        Plan::IndexSeek { index_name, .. } => Some(format!("{} ref", index_name)),
+        Plan::IndexRangeSeek { index_name, .. } => Some(format!("{} range", index_name)),
+        Plan::InListSeek { index_name, .. } => {
+            Some(format!("{} in", index_name.as_deref().unwrap_or("PRIMARY")))
+        }
+        _ => None,
     }
 }
 
 fn table_rows_hint(planner_stats: &PlannerStats) -> u64 {
-    if planner_stats.table_rows > 0 {
+    if planner_stats.stats_are_stale() {
+        planner_stats.live_rows
+    } else if planner_stats.table_rows > 0 {
         planner_stats.table_rows
     } else {
         // Conservative fallback before ANALYZE TABLE is run.
@@ -391,10 +584,11 @@ fn table_rows_hint(planner_stats: &PlannerStats) -> u64 {
 
 fn estimate_index_seek_rows(
     table_rows: u64,
-    key_parts: usize,
+    key_exprs: &[Expr],
     index: Option<&IndexPlanStat>,
     full_key_equality: bool,
 ) -> u64 {
+    let key_parts = key_exprs.len();
     if key_parts == 0 {
         return table_rows.max(1);
     }
@@ -403,6 +597,26 @@ fn estimate_index_seek_rows(
         return 1;
     }
 
+    // Single-column equality: non-NULL rows spread over the distinct values,
+    // or next to nothing for a constant outside the analyzed min/max.
+    if key_parts == 1 {
+        let column = index
+            .and_then(|idx| idx.column_stats.first())
+            .and_then(Option::as_ref)
+            .filter(|cs| cs.distinct_count > 0);
+        if let Some(cs) = column {
+            if let (Some(min), Some(max), Some(v)) = (cs.min, cs.max, const_i64(&key_exprs[0])) {
+                if v < min || v > max {
+                    return 1;
+                }
+            }
+            let non_null = table_rows.saturating_sub(cs.null_count).max(1);
+            return div_ceil(non_null, cs.distinct_count)
+                .max(1)
+                .min(table_rows.max(1));
+        }
+    }
+
     let mut rows = if let Some(idx) = index {
         if idx.stats_distinct_keys > 0 {
             div_ceil(table_rows.max(1), idx.stats_distinct_keys)
@@ -433,8 +647,26 @@ fn estimate_numeric_range_rows(
     index: Option<&IndexPlanStat>,
 ) -> Option<u64> {
     let idx = index?;
-    let min_v = idx.stats_num_min?;
-    let max_v = idx.stats_num_max?;
+    estimate_bounded_range_rows(
+        prefix_rows,
+        lower,
+        upper,
+        idx.stats_num_min?,
+        idx.stats_num_max?,
+        &idx.stats_num_hist_bins,
+    )
+}
+
+/// Scale `prefix_rows` by how much of `[min_v, max_v]` the bounds cover,
+/// weighted by the histogram when there is one.
+fn estimate_bounded_range_rows(
+    prefix_rows: u64,
+    lower: &Option<(Box<Expr>, bool)>,
+    upper: &Option<(Box<Expr>, bool)>,
+    min_v: i64,
+    max_v: i64,
+    hist_bins: &[u32],
+) -> Option<u64> {
     if min_v > max_v {
         return None;
     }
@@ -460,14 +692,14 @@ fn estimate_numeric_range_rows(
         return Some(1);
     }
 
-    if !idx.stats_num_hist_bins.is_empty() {
+    if !hist_bins.is_empty() {
         let est = estimate_from_histogram_bins(
             prefix_rows,
             min_v,
             max_v,
             clamped_lo,
             clamped_hi,
-            &idx.stats_num_hist_bins,
+            hist_bins,
         )?;
         return Some(est.max(1));
     }
@@ -572,6 +804,7 @@ mod tests {
             stats_num_min: Some(i64::MIN),
             stats_num_max: Some(i64::MAX),
             stats_num_hist_bins: Vec::new(),
+            column_stats: Vec::new(),
         };
         let lower = Some((Box::new(Expr::IntLiteral(0)), true));
         let rows = estimate_numeric_range_rows(1000, &lower, &None, Some(&idx)).unwrap();
@@ -588,6 +821,7 @@ mod tests {
             stats_num_min: Some(0),
             stats_num_max: Some(99),
             stats_num_hist_bins: vec![1000, 0],
+            column_stats: Vec::new(),
         };
         let lower = Some((Box::new(Expr::IntLiteral(50)), true));
         let rows = estimate_numeric_range_rows(1000, &lower, &None, Some(&idx)).unwrap();
@@ -658,6 +892,10 @@ fn collect_equalities(expr: &Expr, result: &mut Vec<(String, Expr)>) {
                 result.push((name.clone(), *right.clone()));
             } else if let Expr::ColumnRef(ref name) = **right {
                 result.push((name.clone(), *left.clone()));
+            } else if let Some(key) = index_expr_key(left) {
+                result.push((key, *right.clone()));
+            } else if let Some(key) = index_expr_key(right) {
+                result.push((key, *left.clone()));
             }
         }
         Expr::BinaryOp {
@@ -672,6 +910,67 @@ fn collect_equalities(expr: &Expr, result: &mut Vec<(String, Expr)>) {
     }
 }
 
+/// `column IN (...)` conjuncts of an AND-connected expression whose list
+/// items do not depend on the row, with NULL items dropped (they never
+/// match). Lists left empty or longer than `max_items` are not included;
+/// those are filtered row by row.
+fn extract_in_lists(expr: &Expr, max_items: usize) -> Vec<(String, Vec<Expr>)> {
+    let mut result = Vec::new();
+    collect_in_lists(expr, max_items, &mut result);
+    result
+}
+
+fn collect_in_lists(expr: &Expr, max_items: usize, result: &mut Vec<(String, Vec<Expr>)>) {
+    match expr {
+        Expr::InList {
+            expr,
+            list,
+            negated: false,
+        } => {
+            let Expr::ColumnRef(ref name) = **expr else {
+                return;
+            };
+            if !list.iter().all(is_row_independent_expr) {
+                return;
+            }
+            let values: Vec<Expr> = list
+                .iter()
+                .filter(|item| !matches!(item, Expr::Null))
+                .cloned()
+                .collect();
+            if !values.is_empty() && values.len() <= max_items {
+                result.push((name.clone(), values));
+            }
+        }
+        Expr::BinaryOp {
+            left,
+            op: BinaryOp::And,
+            right,
+        } => {
+            collect_in_lists(left, max_items, result);
+            collect_in_lists(right, max_items, result);
+        }
+        _ => {}
+    }
+}
+
+/// Key under which a row-dependent, non-column operand can match an
+/// expression index part: its canonical index expression text.
+fn index_expr_key(expr: &Expr) -> Option<String> {
+    if matches!(expr, Expr::ColumnRef(_)) || is_row_independent_expr(expr) {
+        return None;
+    }
+    index_expr_text(expr)
+}
+
+/// Column name or index expression key a range predicate operand binds.
+fn range_key(expr: &Expr) -> Option<String> {
+    match expr {
+        Expr::ColumnRef(col) => Some(col.clone()),
+        _ => index_expr_key(expr),
+    }
+}
+
 #[derive(Debug, Clone, Default)]
 struct ColumnRange {
     lower: Option<(Expr, bool)>,
@@ -692,11 +991,11 @@ fn collect_ranges(expr: &Expr, result: &mut std::collections::HashMap<String, Co
                 collect_ranges(right, result);
             }
             BinaryOp::Gt | BinaryOp::Ge | BinaryOp::Lt | BinaryOp::Le => {
-                if let Expr::ColumnRef(col) = left.as_ref() {
+                if let Some(col) = range_key(left) {
                     if !is_row_independent_expr(right) {
                         return;
                     }
-                    let entry = result.entry(col.clone()).or_default();
+                    let entry = result.entry(col).or_default();
                     match op {
                         BinaryOp::Gt => merge_lower(entry, *right.clone(), false),
                         BinaryOp::Ge => merge_lower(entry, *right.clone(), true),
@@ -704,11 +1003,11 @@ fn collect_ranges(expr: &Expr, result: &mut std::collections::HashMap<String, Co
                         BinaryOp::Le => merge_upper(entry, *right.clone(), true),
                         _ => {}
                     }
-                } else if let Expr::ColumnRef(col) = right.as_ref() {
+                } else if let Some(col) = range_key(right) {
                     if !is_row_independent_expr(left) {
                         return;
                     }
-                    let entry = result.entry(col.clone()).or_default();
+                    let entry = result.entry(col).or_default();
                     match op {
                         BinaryOp::Gt => merge_upper(entry, *left.clone(), false),
                         BinaryOp::Ge => merge_upper(entry, *left.clone(), true),
@@ -726,9 +1025,9 @@ fn collect_ranges(expr: &Expr, result: &mut std::collections::HashMap<String, Co
             high,
             negated: false,
         } => {
-            if let Expr::ColumnRef(col) = expr.as_ref() {
+            if let Some(col) = range_key(expr) {
                 if is_row_independent_expr(low) && is_row_independent_expr(high) {
-                    let entry = result.entry(col.clone()).or_default();
+                    let entry = result.entry(col).or_default();
                     merge_lower(entry, *low.clone(), true);
                     merge_upper(entry, *high.clone(), true);
                 }
@@ -810,8 +1109,15 @@ fn is_row_independent_expr(expr: &Expr) -> bool {
             is_row_independent_expr(left) && is_row_independent_expr(right)
         }
         Expr::UnaryOp { operand, .. } => is_row_independent_expr(operand),
-        Expr::Like { expr, pattern, .. } => {
-            is_row_independent_expr(expr) && is_row_independent_expr(pattern)
+        Expr::Like {
+            expr,
+            pattern,
+            escape,
+            ..
+        } => {
+            is_row_independent_expr(expr)
+                && is_row_independent_expr(pattern)
+                && escape.as_deref().is_none_or(is_row_independent_expr)
         }
         Expr::InList { expr, list, .. } => {
             is_row_independent_expr(expr) && list.iter().all(is_row_independent_expr)
@@ -842,7 +1148,7 @@ fn is_row_independent_expr(expr: &Expr) -> bool {
                     .map(|e| is_row_independent_expr(e))
                     .unwrap_or(true)
         }
-        Expr::Cast { expr, .. } => is_row_independent_expr(expr),
+        Expr::Cast { expr, .. } | Expr::Collate { expr, .. } => is_row_independent_expr(expr),
         Expr::AggregateFunc { arg, .. } => arg
             .as_ref()
             .map(|e| is_row_independent_expr(e))
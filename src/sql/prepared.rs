@@ -1,6 +1,6 @@
 use crate::error::{MuroError, Result};
 use crate::sql::ast::*;
-use crate::sql::parser::parse_sql;
+use crate::sql::parser::parse_statement;
 use crate::types::{format_date, format_datetime, DataType, Value};
 
 /// Parsed SQL template with positional bind parameters (`?`).
@@ -14,7 +14,7 @@ pub struct PreparedStatement {
 impl PreparedStatement {
     /// Parse SQL once and keep the AST template for repeated execution.
     pub fn parse(sql: &str) -> Result<Self> {
-        let template = parse_sql(sql).map_err(MuroError::Parse)?;
+        let template = parse_statement(sql)?;
         let parameter_count = count_statement_bind_params(&template);
         Ok(Self {
             sql: sql.to_string(),
@@ -90,18 +90,31 @@ fn count_statement_bind_params(stmt: &Statement) -> usize {
             for (_, expr) in &upd.assignments {
                 total += count_expr_bind_params(expr);
             }
+            total += upd
+                .where_clause
+                .as_ref()
+                .map(count_expr_bind_params)
+                .unwrap_or(0);
+            if let Some(order_by) = &upd.order_by {
+                for item in order_by {
+                    total += count_expr_bind_params(&item.expr);
+                }
+            }
+            total
+        }
+        Statement::Delete(del) => {
+            let mut total = del
+                .where_clause
+                .as_ref()
+                .map(count_expr_bind_params)
+                .unwrap_or(0);
+            if let Some(order_by) = &del.order_by {
+                for item in order_by {
+                    total += count_expr_bind_params(&item.expr);
+                }
+            }
             total
-                + upd
-                    .where_clause
-                    .as_ref()
-                    .map(count_expr_bind_params)
-                    .unwrap_or(0)
         }
-        Statement::Delete(del) => del
-            .where_clause
-            .as_ref()
-            .map(count_expr_bind_params)
-            .unwrap_or(0),
         Statement::SetQuery(sq) => {
             let mut total = count_select_bind_params(&sq.left);
             for (_, sel) in &sq.ops {
@@ -114,25 +127,35 @@ fn count_statement_bind_params(stmt: &Statement) -> usize {
             }
             total
         }
-        Statement::Explain(inner) => count_statement_bind_params(inner),
-        Statement::AlterTable(at) => match &at.operation {
-            AlterTableOp::AddColumn(spec)
-            | AlterTableOp::ModifyColumn(spec)
-            | AlterTableOp::ChangeColumn(_, spec) => {
-                spec.default_value
-                    .as_ref()
-                    .map(count_expr_bind_params)
-                    .unwrap_or(0)
-                    + spec
-                        .check_expr
+        Statement::Explain(inner) | Statement::ExplainAnalyze(inner) => {
+            count_statement_bind_params(inner)
+        }
+        Statement::AlterTable(at) => at
+            .operations
+            .iter()
+            .map(|operation| match operation {
+                AlterTableOp::AddColumn(spec)
+                | AlterTableOp::ModifyColumn(spec)
+                | AlterTableOp::ChangeColumn(_, spec) => {
+                    spec.default_value
                         .as_ref()
                         .map(count_expr_bind_params)
                         .unwrap_or(0)
-            }
-            AlterTableOp::DropColumn(_)
-            | AlterTableOp::AddForeignKey(_)
-            | AlterTableOp::DropForeignKey(_) => 0,
-        },
+                        + spec
+                            .check_expr
+                            .as_ref()
+                            .map(count_expr_bind_params)
+                            .unwrap_or(0)
+                }
+                AlterTableOp::DropColumn(_)
+                | AlterTableOp::AddForeignKey(_)
+                | AlterTableOp::DropForeignKey(_)
+                | AlterTableOp::ExchangeWith(_)
+                | AlterTableOp::AddIndex(_)
+                | AlterTableOp::DropIndex(_)
+                | AlterTableOp::RenameIndex(..) => 0,
+            })
+            .sum(),
         Statement::CreateIndex(_)
         | Statement::CreateFulltextIndex(_)
         | Statement::DropTable(_)
@@ -140,6 +163,7 @@ fn count_statement_bind_params(stmt: &Statement) -> usize {
         | Statement::RenameTable(_)
         | Statement::ShowTables
         | Statement::ShowCreateTable(_)
+        | Statement::ShowIndexes(_)
         | Statement::Describe(_)
         | Statement::Begin
         | Statement::Commit
@@ -149,8 +173,17 @@ fn count_statement_bind_params(stmt: &Statement) -> usize {
         | Statement::ReleaseSavepoint(_)
         | Statement::ShowCheckpointStats
         | Statement::ShowDatabaseStats
-        | Statement::SetRuntimeOption(_)
-        | Statement::AnalyzeTable(_) => 0,
+        | Statement::SetVariable(_)
+        | Statement::ShowVariables(_)
+        | Statement::InstallPlanBaseline(_)
+        | Statement::RemovePlanBaseline(_)
+        | Statement::ShowWarnings
+        | Statement::ShowProcesslist
+        | Statement::AnalyzeTable(_)
+        | Statement::CheckTable(_)
+        | Statement::ShowTableStatus
+        | Statement::OptimizeTable(_)
+        | Statement::Optimize => 0,
     }
 }
 
@@ -192,8 +225,15 @@ fn count_expr_bind_params(expr: &Expr) -> usize {
             count_expr_bind_params(left) + count_expr_bind_params(right)
         }
         Expr::UnaryOp { operand, .. } => count_expr_bind_params(operand),
-        Expr::Like { expr, pattern, .. } => {
-            count_expr_bind_params(expr) + count_expr_bind_params(pattern)
+        Expr::Like {
+            expr,
+            pattern,
+            escape,
+            ..
+        } => {
+            count_expr_bind_params(expr)
+                + count_expr_bind_params(pattern)
+                + escape.as_deref().map_or(0, count_expr_bind_params)
         }
         Expr::InList { expr, list, .. } => {
             let mut total = count_expr_bind_params(expr);
@@ -230,7 +270,7 @@ fn count_expr_bind_params(expr: &Expr) -> usize {
                     .map(|e| count_expr_bind_params(e))
                     .unwrap_or(0)
         }
-        Expr::Cast { expr, .. } => count_expr_bind_params(expr),
+        Expr::Cast { expr, .. } | Expr::Collate { expr, .. } => count_expr_bind_params(expr),
         Expr::AggregateFunc { arg, .. } => {
             arg.as_ref().map(|e| count_expr_bind_params(e)).unwrap_or(0)
         }
@@ -284,11 +324,21 @@ fn bind_statement_in_place(stmt: &mut Statement, params: &[Value], next: &mut us
             if let Some(where_clause) = &mut upd.where_clause {
                 bind_expr_in_place(where_clause, params, next)?;
             }
+            if let Some(order_by) = &mut upd.order_by {
+                for item in order_by {
+                    bind_expr_in_place(&mut item.expr, params, next)?;
+                }
+            }
         }
         Statement::Delete(del) => {
             if let Some(where_clause) = &mut del.where_clause {
                 bind_expr_in_place(where_clause, params, next)?;
             }
+            if let Some(order_by) = &mut del.order_by {
+                for item in order_by {
+                    bind_expr_in_place(&mut item.expr, params, next)?;
+                }
+            }
         }
         Statement::SetQuery(sq) => {
             bind_select_in_place(&mut sq.left, params, next)?;
@@ -301,17 +351,27 @@ fn bind_statement_in_place(stmt: &mut Statement, params: &[Value], next: &mut us
                 }
             }
         }
-        Statement::Explain(inner) => bind_statement_in_place(inner, params, next)?,
-        Statement::AlterTable(at) => match &mut at.operation {
-            AlterTableOp::AddColumn(spec)
-            | AlterTableOp::ModifyColumn(spec)
-            | AlterTableOp::ChangeColumn(_, spec) => {
-                bind_column_spec_in_place(spec, params, next)?;
+        Statement::Explain(inner) | Statement::ExplainAnalyze(inner) => {
+            bind_statement_in_place(inner, params, next)?
+        }
+        Statement::AlterTable(at) => {
+            for operation in &mut at.operations {
+                match operation {
+                    AlterTableOp::AddColumn(spec)
+                    | AlterTableOp::ModifyColumn(spec)
+                    | AlterTableOp::ChangeColumn(_, spec) => {
+                        bind_column_spec_in_place(spec, params, next)?;
+                    }
+                    AlterTableOp::DropColumn(_)
+                    | AlterTableOp::AddForeignKey(_)
+                    | AlterTableOp::DropForeignKey(_)
+                    | AlterTableOp::ExchangeWith(_)
+                    | AlterTableOp::AddIndex(_)
+                    | AlterTableOp::DropIndex(_)
+                    | AlterTableOp::RenameIndex(..) => {}
+                }
             }
-            AlterTableOp::DropColumn(_)
-            | AlterTableOp::AddForeignKey(_)
-            | AlterTableOp::DropForeignKey(_) => {}
-        },
+        }
         Statement::CreateIndex(_)
         | Statement::CreateFulltextIndex(_)
         | Statement::DropTable(_)
@@ -319,6 +379,7 @@ fn bind_statement_in_place(stmt: &mut Statement, params: &[Value], next: &mut us
         | Statement::RenameTable(_)
         | Statement::ShowTables
         | Statement::ShowCreateTable(_)
+        | Statement::ShowIndexes(_)
         | Statement::Describe(_)
         | Statement::Begin
         | Statement::Commit
@@ -328,8 +389,17 @@ fn bind_statement_in_place(stmt: &mut Statement, params: &[Value], next: &mut us
         | Statement::ReleaseSavepoint(_)
         | Statement::ShowCheckpointStats
         | Statement::ShowDatabaseStats
-        | Statement::SetRuntimeOption(_)
-        | Statement::AnalyzeTable(_) => {}
+        | Statement::SetVariable(_)
+        | Statement::ShowVariables(_)
+        | Statement::InstallPlanBaseline(_)
+        | Statement::RemovePlanBaseline(_)
+        | Statement::ShowWarnings
+        | Statement::ShowProcesslist
+        | Statement::AnalyzeTable(_)
+        | Statement::CheckTable(_)
+        | Statement::ShowTableStatus
+        | Statement::OptimizeTable(_)
+        | Statement::Optimize => {}
     }
 
     Ok(())
@@ -415,9 +485,17 @@ fn bind_expr_in_place(expr: &mut Expr, params: &[Value], next: &mut usize) -> Re
             bind_expr_in_place(right, params, next)?;
         }
         Expr::UnaryOp { operand, .. } => bind_expr_in_place(operand, params, next)?,
-        Expr::Like { expr, pattern, .. } => {
+        Expr::Like {
+            expr,
+            pattern,
+            escape,
+            ..
+        } => {
             bind_expr_in_place(expr, params, next)?;
             bind_expr_in_place(pattern, params, next)?;
+            if let Some(e) = escape {
+                bind_expr_in_place(e, params, next)?;
+            }
         }
         Expr::InList { expr, list, .. } => {
             bind_expr_in_place(expr, params, next)?;
@@ -454,7 +532,9 @@ fn bind_expr_in_place(expr: &mut Expr, params: &[Value], next: &mut usize) -> Re
                 bind_expr_in_place(else_expr, params, next)?;
             }
         }
-        Expr::Cast { expr, .. } => bind_expr_in_place(expr, params, next)?,
+        Expr::Cast { expr, .. } | Expr::Collate { expr, .. } => {
+            bind_expr_in_place(expr, params, next)?
+        }
         Expr::AggregateFunc { arg, .. } => {
             if let Some(arg) = arg {
                 bind_expr_in_place(arg, params, next)?;
@@ -488,7 +568,7 @@ fn bind_expr_in_place(expr: &mut Expr, params: &[Value], next: &mut usize) -> Re
     Ok(())
 }
 
-fn value_to_expr(v: &Value) -> Expr {
+pub(crate) fn value_to_expr(v: &Value) -> Expr {
     match v {
         Value::Integer(n) => Expr::IntLiteral(*n),
         Value::Float(n) => Expr::FloatLiteral(*n),
@@ -591,7 +671,7 @@ mod tests {
         let Statement::AlterTable(at) = stmt else {
             panic!("expected ALTER TABLE");
         };
-        let AlterTableOp::AddColumn(col) = at.operation else {
+        let Some(AlterTableOp::AddColumn(col)) = at.operations.into_iter().next() else {
             panic!("expected ADD COLUMN");
         };
         assert!(matches!(col.check_expr, Some(Expr::IntLiteral(7))));
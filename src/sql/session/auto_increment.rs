@@ -0,0 +1,58 @@
+use super::*;
+use std::collections::HashMap;
+
+/// Highest auto-increment value each table has handed out in this session.
+///
+/// The catalog counter is rolled back with the transaction that advanced it;
+/// this high-water mark is not, so a value handed out by a rolled-back
+/// statement is never handed out again. A table is present once its counter
+/// has been checked against the largest key in its data B-tree.
+#[derive(Default)]
+pub(crate) struct AutoIncrementState {
+    high_water: HashMap<String, i64>,
+}
+
+impl Session {
+    /// Lend the auto-increment high-water marks to the running statement.
+    pub(super) fn begin_statement_auto_increment(&mut self) {
+        let state = std::mem::take(&mut self.auto_increment);
+        ACTIVE_AUTO_INCREMENT.with(|slot| *slot.borrow_mut() = Some(state));
+    }
+
+    /// Take the high-water marks back, including values handed out by a
+    /// statement that failed.
+    pub(super) fn finish_statement_auto_increment(&mut self) {
+        if let Some(state) = ACTIVE_AUTO_INCREMENT.with(|slot| slot.borrow_mut().take()) {
+            self.auto_increment = state;
+        }
+    }
+}
+
+/// High-water mark of `table`, or `None` if this session has not checked the
+/// table's counter against its data yet.
+pub(crate) fn auto_increment_high_water_current(table: &str) -> Option<i64> {
+    ACTIVE_AUTO_INCREMENT.with(|slot| {
+        slot.borrow()
+            .as_ref()
+            .and_then(|state| state.high_water.get(table).copied())
+    })
+}
+
+/// Raise the high-water mark of `table` to `value`.
+pub(crate) fn raise_auto_increment_current(table: &str, value: i64) {
+    ACTIVE_AUTO_INCREMENT.with(|slot| {
+        if let Some(state) = slot.borrow_mut().as_mut() {
+            let high_water = state.high_water.entry(table.to_string()).or_insert(value);
+            *high_water = (*high_water).max(value);
+        }
+    });
+}
+
+/// Drop the high-water mark of a table that was dropped or renamed.
+pub(crate) fn forget_auto_increment_current(table: &str) {
+    ACTIVE_AUTO_INCREMENT.with(|slot| {
+        if let Some(state) = slot.borrow_mut().as_mut() {
+            state.high_water.remove(table);
+        }
+    });
+}
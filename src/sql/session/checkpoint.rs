@@ -108,27 +108,11 @@ impl Session {
         Ok(())
     }
 
-    pub(super) fn handle_set_runtime_option(
-        &mut self,
-        stmt: &crate::sql::ast::SetRuntimeOption,
-    ) -> Result<ExecResult> {
-        let mut cfg = self.runtime_config();
-        match stmt.option {
-            crate::sql::ast::RuntimeOption::CheckpointTxThreshold => {
-                cfg.checkpoint_tx_threshold = stmt.value
-            }
-            crate::sql::ast::RuntimeOption::CheckpointWalBytesThreshold => {
-                cfg.checkpoint_wal_bytes_threshold = stmt.value
-            }
-            crate::sql::ast::RuntimeOption::CheckpointIntervalMs => {
-                cfg.checkpoint_interval_ms = stmt.value
-            }
-        }
-        self.set_runtime_config(cfg)?;
-        Ok(ExecResult::Ok)
-    }
-
     pub(super) fn post_commit_checkpoint(&mut self) {
+        let write_calls = self.wal.last_commit_write_calls();
+        self.stats.wal_commits += 1;
+        self.stats.wal_commit_write_calls += write_calls;
+        self.stats.last_commit_wal_write_calls = write_calls;
         self.post_checkpoint(CheckpointPhase::PostCommit);
     }
 
@@ -142,6 +126,10 @@ impl Session {
             self.stats.deferred_checkpoints += 1;
             return;
         }
+        self.run_checkpoint(phase);
+    }
+
+    fn run_checkpoint(&mut self, phase: CheckpointPhase) {
         self.stats.total_checkpoints += 1;
         // Best-effort: rollback leaves no committed changes to preserve in WAL.
         if let Err((attempts, e)) = self.try_checkpoint_truncate_with_retry() {
@@ -164,6 +152,10 @@ impl Session {
         if self.pending_checkpoint_ops == 0 {
             return false;
         }
+        // Checkpointing syncs the batch; let it fill up first.
+        if self.wal.commit_batch_open() && !self.wal.commit_batch_expired() {
+            return false;
+        }
         if self.checkpoint_policy.tx_threshold > 0 {
             if self.checkpoint_policy.tx_threshold <= 1 {
                 return true;
@@ -365,6 +357,42 @@ impl Session {
                 format!("{:.2}", cache_hit_rate_pct),
             ),
             stat_row("wal_file_size_bytes", wal_file_size_bytes.to_string()),
+            stat_row(
+                "pager_cache_evictions",
+                self.pager.cache_evictions().to_string(),
+            ),
+            stat_row("pager_cache_bytes", self.pager.cache_bytes().to_string()),
+            stat_row(
+                "pager_cache_capacity_pages",
+                self.pager.cache_capacity().to_string(),
+            ),
+            stat_row("scan_skipped_pages", stats.scan_skipped_pages.to_string()),
+            stat_row("scan_skipped_rows", stats.scan_skipped_rows.to_string()),
+            stat_row("plan_cache_hits", stats.plan_cache_hits.to_string()),
+            stat_row(
+                "plan_cache_fallbacks",
+                stats.plan_cache_fallbacks.to_string(),
+            ),
+            stat_row("plan_baseline_hits", stats.plan_baseline_hits.to_string()),
+            stat_row(
+                "plan_baseline_fallbacks",
+                stats.plan_baseline_fallbacks.to_string(),
+            ),
+            stat_row("wal_commits", stats.wal_commits.to_string()),
+            stat_row(
+                "wal_commit_write_calls",
+                stats.wal_commit_write_calls.to_string(),
+            ),
+            stat_row(
+                "last_commit_wal_write_calls",
+                stats.last_commit_wal_write_calls.to_string(),
+            ),
+            stat_row(
+                "pager_pages_decrypted",
+                self.pager.pages_decrypted().to_string(),
+            ),
+            stat_row("busy_timeout_ms", self.busy_timeout_ms.to_string()),
+            stat_row("read_only", self.read_only.to_string()),
         ];
         Ok(ExecResult::Rows(rows))
     }
@@ -396,11 +424,99 @@ impl Session {
                 "injected checkpoint failure",
             )));
         }
+        // Truncating drops the only copy of deferred commits.
+        self.flush_commit_batch()?;
+        self.wal.archive_segment(self.pager.catalog_root())?;
         self.wal.checkpoint_truncate()
     }
 
+    /// Directory checkpointed WAL segments are archived into, if any.
+    pub fn wal_archive_dir(&self) -> Option<&std::path::Path> {
+        self.wal.archive().map(|a| a.dir())
+    }
+
+    /// Start (`Some`) or stop (`None`) archiving checkpointed WAL segments.
+    pub fn set_wal_archive_dir(&mut self, dir: Option<&std::path::Path>) -> Result<()> {
+        self.check_poisoned()?;
+        if self.active_tx.is_some() {
+            return Err(MuroError::Execution(
+                "WAL archiving cannot be changed inside a transaction".into(),
+            ));
+        }
+        let archive = match dir {
+            Some(dir) => Some(crate::wal::archive::WalArchive::open(
+                dir,
+                self.wal.wal_path(),
+            )?),
+            None => None,
+        };
+        self.wal.set_archive(archive);
+        Ok(())
+    }
+
+    /// WAL durability mode for subsequent commits.
+    pub fn wal_durability(&self) -> WalDurability {
+        self.wal.durability()
+    }
+
+    /// Change the WAL durability mode. Commits deferred under the previous
+    /// mode are fsynced first.
+    pub fn set_wal_durability(&mut self, durability: WalDurability) -> Result<()> {
+        self.check_poisoned()?;
+        if self.active_tx.is_some() {
+            return Err(MuroError::Execution(
+                "WAL durability cannot be changed inside a transaction".into(),
+            ));
+        }
+        self.flush_commit_batch()?;
+        self.wal.set_durability(durability);
+        Ok(())
+    }
+
+    /// Bytes of WAL frames a commit buffers before writing them to the WAL
+    /// file (without fsync); see [`WalWriter::set_write_buffer_limit`].
+    pub fn set_wal_write_buffer_bytes(&mut self, bytes: usize) {
+        self.wal.set_write_buffer_limit(bytes);
+    }
+
+    pub fn wal_write_buffer_bytes(&self) -> usize {
+        self.wal.write_buffer_limit()
+    }
+
+    /// Make every deferred commit durable: fsync the WAL, then write the
+    /// held-back pages and header to the data file.
+    pub(crate) fn flush_commit_batch(&mut self) -> Result<()> {
+        if !self.wal.has_unsynced_commits() && !self.pager.has_deferred_writes() {
+            return Ok(());
+        }
+        self.wal.sync()?;
+        self.pager.write_deferred_pages()?;
+        self.pager.flush_meta()
+    }
+
+    /// Sync a group-commit batch that has outlived its `max_delay_ms`
+    /// without another commit arriving to close it.
+    pub(super) fn flush_expired_commit_batch(&mut self) -> Result<()> {
+        if self.active_tx.is_none() && self.wal.commit_batch_expired() {
+            self.flush_commit_batch()?;
+            if self.should_checkpoint_now() {
+                self.run_checkpoint(CheckpointPhase::PostCommit);
+            }
+        }
+        Ok(())
+    }
+
     pub(super) fn try_checkpoint_truncate_with_retry(
         &mut self,
+    ) -> std::result::Result<usize, (usize, MuroError)> {
+        let previous = self.status.set_phase(StatementPhase::Checkpointing);
+        let result = self.try_checkpoint_truncate_attempts();
+        self.status.set_phase(previous);
+        result
+    }
+
+    fn try_checkpoint_truncate_attempts(
+        &mut self,
     ) -> std::result::Result<usize, (usize, MuroError)> {
         let mut last_err = None;
         for attempt in 1..=CHECKPOINT_MAX_ATTEMPTS {
@@ -0,0 +1,45 @@
+use super::*;
+use crate::tx::commit_outcome::{validate_commit_tag, CommitOutcome, CommitOutcomeLog, CommitRef};
+
+impl Session {
+    /// Tag the next commit so its verdict can be looked up after a
+    /// `CommitInDoubt`.
+    ///
+    /// The tag applies to the next `COMMIT`, or to the next write statement
+    /// run in auto-commit mode, and is cleared by that commit attempt. If the
+    /// commit fails with [`MuroError::CommitInDoubt`], reopen the database
+    /// and ask [`Session::commit_outcome`] whether it reached the database
+    /// before retrying. Tags are stored unencrypted in the `<db>.commits`
+    /// sidecar file.
+    pub fn set_commit_tag(&mut self, tag: &str) -> Result<()> {
+        validate_commit_tag(tag)?;
+        self.commit_tag = Some(tag.to_string());
+        Ok(())
+    }
+
+    /// How the last open's recovery resolved an in-doubt commit, by commit
+    /// tag or txid.
+    ///
+    /// `None` for commits that never returned `CommitInDoubt`, for commits
+    /// not yet resolved (the session that saw the error stays poisoned until
+    /// the database is reopened read-write), and for entries older than the
+    /// last [`COMMIT_OUTCOME_HISTORY_LIMIT`](crate::tx::commit_outcome::COMMIT_OUTCOME_HISTORY_LIMIT)
+    /// resolutions.
+    pub fn commit_outcome<'a>(&self, key: impl Into<CommitRef<'a>>) -> Option<CommitOutcome> {
+        self.commit_outcomes.outcome(key.into())
+    }
+
+    pub(crate) fn set_commit_outcomes(&mut self, outcomes: CommitOutcomeLog) {
+        self.commit_outcomes = outcomes;
+    }
+
+    /// Durably note an in-doubt commit for resolution at the next open.
+    pub(super) fn note_commit_in_doubt(&mut self, txid: TxId, tag: Option<&str>) {
+        if let Err(e) = CommitOutcomeLog::note_in_doubt(self.pager.path(), txid, tag) {
+            eprintln!(
+                "WARNING: commit_in_doubt note failed txid={} error=\"{}\"",
+                txid, e
+            );
+        }
+    }
+}
@@ -0,0 +1,324 @@
+//! Logical dump and import.
+//!
+//! A dump is a SQL script: `CREATE TABLE` for every table in foreign-key
+//! order, with its `AUTO_INCREMENT` counter, the rows as multi-row `INSERT` statements in primary-key order,
+//! then `CREATE FULLTEXT INDEX` statements and the foreign keys that could
+//! not be declared inline (self references and cycles). The same database
+//! always dumps to the same bytes.
+
+use super::*;
+use crate::btree::ops::BTree;
+use crate::schema::catalog::{ForeignKeyDef, TableDef};
+use crate::schema::index::{IndexDef, IndexType};
+use crate::sql::executor::{
+    create_table_sql, deserialize_row_versioned, foreign_key_sql, fulltext_index_sql,
+};
+use crate::storage::page_store::PageStore;
+use crate::types::{format_date, format_datetime, format_float_literal, format_uuid};
+use std::io::{BufRead, BufReader, Read, Write};
+
+/// An `INSERT` statement is ended early once its text reaches this size, so
+/// rows with large values do not make statements near the SQL size limit.
+const MAX_INSERT_BYTES: usize = 1024 * 1024;
+
+/// [`import_script`] commits after this many statements...
+const IMPORT_COMMIT_STATEMENTS: usize = 100;
+
+/// ...or once the statements since the last commit reach this size.
+const IMPORT_COMMIT_BYTES: usize = 8 * 1024 * 1024;
+
+/// What [`Session::dump`] writes.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DumpOptions {
+    /// Tables to dump. Empty dumps every table. Temporary tables are never
+    /// dumped.
+    pub tables: Vec<String>,
+    /// Write `CREATE TABLE`, index and foreign key statements.
+    pub schema: bool,
+    /// Write the rows as `INSERT` statements.
+    pub data: bool,
+    /// Rows per `INSERT` statement (at least 1).
+    pub rows_per_insert: usize,
+}
+
+impl Default for DumpOptions {
+    fn default() -> Self {
+        Self {
+            tables: Vec::new(),
+            schema: true,
+            data: true,
+            rows_per_insert: 500,
+        }
+    }
+}
+
+/// A table to dump and the foreign keys left out of its `CREATE TABLE`.
+struct DumpTable {
+    def: TableDef,
+    indexes: Vec<IndexDef>,
+    deferred_fks: Vec<ForeignKeyDef>,
+}
+
+impl Session {
+    /// Write the committed schema and rows as a SQL script that
+    /// [`Session::import`] replays into another database.
+    ///
+    /// Tables are created parents first, so foreign keys can be declared
+    /// inline; a foreign key to the table itself or within a cycle is added
+    /// with `ALTER TABLE` after the rows are loaded. Rejected inside a
+    /// transaction.
+    pub fn dump(&mut self, out: &mut impl Write, opts: DumpOptions) -> Result<()> {
+        self.check_poisoned()?;
+        self.refresh_from_disk_if_needed()?;
+        if self.active_tx.is_some() {
+            return Err(MuroError::Execution(
+                "dump cannot be used inside a transaction".into(),
+            ));
+        }
+        let tables = self.dump_tables(&opts.tables)?;
+
+        if opts.schema {
+            for table in &tables {
+                let mut def = table.def.clone();
+                def.foreign_keys
+                    .retain(|fk| !table.deferred_fks.contains(fk));
+                writeln!(out, "{};\n", create_table_sql(&def, &table.indexes, false))?;
+            }
+        }
+        if opts.data {
+            let rows_per_insert = opts.rows_per_insert.max(1);
+            for table in &tables {
+                dump_rows(&mut self.pager, &table.def, rows_per_insert, out)?;
+            }
+        }
+        if opts.schema {
+            for table in &tables {
+                for idx in &table.indexes {
+                    if idx.index_type == IndexType::Fulltext {
+                        writeln!(out, "{};", fulltext_index_sql(idx))?;
+                    }
+                }
+                for fk in &table.deferred_fks {
+                    writeln!(
+                        out,
+                        "ALTER TABLE {} ADD {};",
+                        table.def.name,
+                        foreign_key_sql(fk)
+                    )?;
+                }
+            }
+        }
+        out.flush()?;
+        Ok(())
+    }
+
+    /// Replay a script written by [`Session::dump`], committing every
+    /// hundred or so statements. Returns the number of statements run.
+    ///
+    /// A failure is reported as [`MuroError::Script`] with the statement's
+    /// index and character offset in the whole script; the statements
+    /// committed before it stay.
+    pub fn import(&mut self, reader: impl Read) -> Result<u64> {
+        import_script(reader, |sql| self.execute_batch(sql))
+    }
+
+    /// The permanent tables to dump, parents before the tables whose
+    /// foreign keys reference them.
+    fn dump_tables(&mut self, only: &[String]) -> Result<Vec<DumpTable>> {
+        let mut pending = Vec::new();
+        for name in self.catalog.list_tables(&mut self.pager)? {
+            if !only.is_empty() && !only.iter().any(|t| t.eq_ignore_ascii_case(&name)) {
+                continue;
+            }
+            if self.catalog.is_temp_table(&mut self.pager, &name)? {
+                continue;
+            }
+            let Some(def) = self.catalog.get_table(&mut self.pager, &name)? else {
+                continue;
+            };
+            let indexes = self.catalog.get_indexes_for_table(&mut self.pager, &name)?;
+            pending.push(DumpTable {
+                def,
+                indexes,
+                deferred_fks: Vec::new(),
+            });
+        }
+        for name in only {
+            if !pending
+                .iter()
+                .any(|t| t.def.name.eq_ignore_ascii_case(name))
+            {
+                return Err(MuroError::Schema(format!("Table '{}' not found", name)));
+            }
+        }
+
+        // A parent outside the dump is expected to exist where it is loaded.
+        let dumped: Vec<String> = pending.iter().map(|t| t.def.name.clone()).collect();
+        let mut ordered: Vec<DumpTable> = Vec::with_capacity(pending.len());
+        while !pending.is_empty() {
+            let created = |parent: &str, ordered: &[DumpTable]| {
+                !dumped.iter().any(|d| d == parent) || ordered.iter().any(|t| t.def.name == parent)
+            };
+            let ready = pending.iter().position(|t| {
+                t.def
+                    .foreign_keys
+                    .iter()
+                    .all(|fk| fk.ref_table == t.def.name || created(&fk.ref_table, &ordered))
+            });
+            // Within a cycle, take the first table by name and add the keys
+            // to tables not created yet afterwards.
+            let mut table = pending.remove(ready.unwrap_or(0));
+            for fk in &table.def.foreign_keys {
+                if fk.ref_table == table.def.name || !created(&fk.ref_table, &ordered) {
+                    table.deferred_fks.push(fk.clone());
+                }
+            }
+            ordered.push(table);
+        }
+        Ok(ordered)
+    }
+}
+
+/// Write the rows of `def` as `INSERT` statements of up to
+/// `rows_per_insert` rows, in primary-key order.
+fn dump_rows(
+    pager: &mut impl PageStore,
+    def: &TableDef,
+    rows_per_insert: usize,
+    out: &mut impl Write,
+) -> Result<()> {
+    let visible: Vec<usize> = (0..def.columns.len())
+        .filter(|&i| !def.columns[i].is_hidden)
+        .collect();
+    let header = format!(
+        "INSERT INTO {} ({}) VALUES\n",
+        def.name,
+        visible
+            .iter()
+            .map(|&i| def.columns[i].name.as_str())
+            .collect::<Vec<_>>()
+            .join(", ")
+    );
+    let mut statement = String::new();
+    let mut rows = 0usize;
+    BTree::open(def.data_btree_root).scan(pager, |_, data| {
+        let values = deserialize_row_versioned(data, &def.columns, def.row_format_version)?;
+        if rows == 0 {
+            statement.push_str(&header);
+        } else {
+            statement.push_str(",\n");
+        }
+        statement.push('(');
+        for (n, &i) in visible.iter().enumerate() {
+            if n > 0 {
+                statement.push_str(", ");
+            }
+            statement.push_str(&value_sql(&values[i]));
+        }
+        statement.push(')');
+        rows += 1;
+        if rows >= rows_per_insert || statement.len() >= MAX_INSERT_BYTES {
+            writeln!(out, "{};", statement)?;
+            statement.clear();
+            rows = 0;
+        }
+        Ok(true)
+    })?;
+    if rows > 0 {
+        writeln!(out, "{};", statement)?;
+    }
+    Ok(())
+}
+
+/// `value` as a SQL literal that an `INSERT` converts back to the same
+/// value for a column of its type.
+fn value_sql(value: &Value) -> String {
+    match value {
+        Value::Null => "NULL".to_string(),
+        // The lexer reads the digits before applying the sign, and
+        // 9223372036854775808 does not fit an i64.
+        Value::Integer(i64::MIN) => "(-9223372036854775807 - 1)".to_string(),
+        Value::Integer(n) => n.to_string(),
+        Value::Float(f) => format_float_literal(*f),
+        Value::Decimal(d) => format!("'{}'", d),
+        Value::Date(d) => format!("'{}'", format_date(*d)),
+        Value::DateTime(dt) | Value::Timestamp(dt) => format!("'{}'", format_datetime(*dt)),
+        Value::Varchar(s) => format!("'{}'", s.replace('\'', "''")),
+        Value::Varbinary(bytes) => {
+            let mut hex = String::with_capacity(bytes.len() * 2 + 3);
+            hex.push_str("X'");
+            for b in bytes {
+                hex.push_str(&format!("{:02X}", b));
+            }
+            hex.push('\'');
+            hex
+        }
+        Value::Uuid(bytes) => format!("'{}'", format_uuid(bytes)),
+    }
+}
+
+/// Split `reader` into statements and pass them to `execute` a batch at a
+/// time, each batch its own transaction. Statement ends are found by `;`
+/// outside quoted strings and identifiers. Returns the statements run.
+pub(crate) fn import_script(
+    reader: impl Read,
+    mut execute: impl FnMut(&str) -> Result<Vec<ExecResult>>,
+) -> Result<u64> {
+    let mut reader = BufReader::new(reader);
+    let mut line = String::new();
+    let mut batch = String::new();
+    let mut batch_statements = 0usize;
+    let mut quote: Option<char> = None;
+    let mut done_statements = 0u64;
+    let mut done_chars = 0usize;
+
+    let mut run = |batch: &mut String, done: &mut u64, chars: &mut usize| {
+        if batch.trim().is_empty() {
+            batch.clear();
+            return Ok(());
+        }
+        let ran = execute(batch).map_err(|e| match e {
+            MuroError::Script {
+                index,
+                offset,
+                source,
+            } => MuroError::Script {
+                index: *done as usize + index,
+                offset: *chars + offset,
+                source,
+            },
+            other => other,
+        })?;
+        *done += ran.len() as u64;
+        *chars += batch.chars().count();
+        batch.clear();
+        Ok::<(), MuroError>(())
+    };
+
+    loop {
+        line.clear();
+        if reader.read_line(&mut line)? == 0 {
+            break;
+        }
+        for c in line.chars() {
+            batch.push(c);
+            match quote {
+                Some(q) if c == q => quote = None,
+                Some(_) => {}
+                None if c == '\'' || c == '"' || c == '`' => quote = Some(c),
+                None if c == ';' => batch_statements += 1,
+                None => {}
+            }
+        }
+        if quote.is_none()
+            && batch_statements > 0
+            && (batch_statements >= IMPORT_COMMIT_STATEMENTS || batch.len() >= IMPORT_COMMIT_BYTES)
+            && batch.trim_end().ends_with(';')
+        {
+            run(&mut batch, &mut done_statements, &mut done_chars)?;
+            batch_statements = 0;
+        }
+    }
+    run(&mut batch, &mut done_statements, &mut done_chars)?;
+    Ok(done_statements)
+}
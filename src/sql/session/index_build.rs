@@ -0,0 +1,99 @@
+//! `CREATE INDEX` in auto-commit mode, built in committed batches.
+//!
+//! The index is first stored marked as building, with an empty tree and a
+//! progress record (`index_build:<name>` in the catalog). Each batch then
+//! indexes the next `index_build_batch_rows` rows in its own transaction and
+//! advances the record, so a crash loses at most one batch and never leaves
+//! a half-built index the planner would trust. Opening the database
+//! read-write resumes any build that was interrupted. A build that fails
+//! drops the index and the pages it filled.
+
+use super::*;
+use crate::sql::ast::CreateIndex;
+use crate::sql::executor::{abort_index_build, begin_index_build, continue_index_build};
+
+impl Session {
+    pub(super) fn execute_create_index_batched(&mut self, ci: &CreateIndex) -> Result<ExecResult> {
+        let begun = self.run_auto_commit(
+            |_| false,
+            |store, catalog| begin_index_build(ci, store, catalog),
+        )?;
+        if let Some(name) = begun {
+            if let Err(e) = self.finish_index_build(&name) {
+                return Err(self.abort_failed_index_build(&name, e));
+            }
+        }
+        Ok(ExecResult::Ok)
+    }
+
+    /// Index the remaining rows of building index `name`, one committed
+    /// batch at a time. The commit that completes the index takes the
+    /// pending commit tag.
+    fn finish_index_build(&mut self, name: &str) -> Result<()> {
+        let batch_rows = self.index_build_batch_rows;
+        loop {
+            self.cancellation_point()?;
+            let done = self.run_auto_commit(
+                |done| *done,
+                |store, catalog| continue_index_build(name, batch_rows, store, catalog),
+            )?;
+            if done {
+                return Ok(());
+            }
+        }
+    }
+
+    /// Drop the index whose build failed with `error` and return `error`.
+    /// If the drop cannot be committed, the index stays marked as building
+    /// and the next read-write open resumes it.
+    fn abort_failed_index_build(&mut self, name: &str, error: MuroError) -> MuroError {
+        if self.poisoned.is_none() {
+            let _ = self.run_auto_commit(
+                |_| false,
+                |store, catalog| abort_index_build(name, store, catalog),
+            );
+        }
+        error
+    }
+
+    /// Finish the index builds an earlier handle left interrupted. A build
+    /// that cannot complete because the rows violate its UNIQUE constraint
+    /// is dropped instead.
+    pub(crate) fn resume_index_builds(&mut self) -> Result<()> {
+        // Fresh files and those built through Pager-only flows have no
+        // catalog B-tree to look in, as in `catalog_needs_migration`.
+        if self.pager.catalog_root() == 0 && self.pager.page_count() == 0 {
+            return Ok(());
+        }
+        let names = match self.catalog.list_index_builds(&mut self.pager) {
+            Err(MuroError::InvalidPage) if self.pager.catalog_root() == 0 => return Ok(()),
+            other => other?,
+        };
+        for name in names {
+            match self.finish_index_build(&name) {
+                Ok(()) => {}
+                Err(e @ MuroError::UniqueViolation(_)) => {
+                    self.run_auto_commit(
+                        |_| false,
+                        |store, catalog| abort_index_build(&name, store, catalog),
+                    )
+                    .map_err(|_| e)?;
+                }
+                Err(e) => return Err(e),
+            }
+        }
+        Ok(())
+    }
+
+    /// Rows an auto-commit `CREATE INDEX` indexes per committed batch.
+    ///
+    /// Same as `SET index_build_batch_rows = <n>`.
+    pub fn set_index_build_batch_rows(&mut self, rows: u64) {
+        self.index_build_batch_rows = rows.max(1);
+    }
+
+    /// Rows indexed per committed `CREATE INDEX` batch.
+    pub fn index_build_batch_rows(&self) -> u64 {
+        self.index_build_batch_rows
+    }
+}
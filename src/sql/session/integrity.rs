@@ -0,0 +1,192 @@
+use super::*;
+use crate::sql::executor::check_database;
+use crate::storage::page::PageId;
+use crate::storage::pager::{PageScanReport, UnreadablePage};
+use std::collections::HashSet;
+use std::fmt;
+
+/// The logical object a page belongs to.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum PageOwner {
+    /// The system catalog B-tree.
+    Catalog,
+    /// A table's row B-tree, including row overflow pages.
+    Table(String),
+    /// A B-tree or FULLTEXT index, including its overflow pages.
+    Index { table: String, index: String },
+    /// The B-tree of a key-value namespace (see [`crate::KvDatabase`]).
+    KvNamespace(String),
+    /// A page of the persisted freelist chain.
+    Freelist,
+    /// A page listed as free.
+    Free,
+    /// Reachable from nothing that could be read: orphaned, or below another
+    /// unreadable page.
+    Unknown,
+}
+
+impl fmt::Display for PageOwner {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            PageOwner::Catalog => write!(f, "catalog"),
+            PageOwner::Table(table) => write!(f, "{}", table),
+            PageOwner::Index { table, index } => write!(f, "{}.{}", table, index),
+            PageOwner::KvNamespace(name) => write!(f, "kv:{}", name),
+            PageOwner::Freelist => write!(f, "freelist"),
+            PageOwner::Free => write!(f, "free page"),
+            PageOwner::Unknown => write!(f, "unknown"),
+        }
+    }
+}
+
+/// A page that failed to read, with the object it belongs to.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CorruptPage {
+    pub page_id: PageId,
+    /// Whether the page failed AEAD authentication
+    /// ([`MuroError::PageDecrypt`]) rather than I/O or page format.
+    pub decrypt_failed: bool,
+    pub error: String,
+    pub owner: PageOwner,
+}
+
+/// Result of [`Session::corruption_report`].
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct CorruptionReport {
+    pub pages_scanned: u64,
+    /// In page order.
+    pub bad_pages: Vec<CorruptPage>,
+}
+
+impl CorruptionReport {
+    pub fn is_clean(&self) -> bool {
+        self.bad_pages.is_empty()
+    }
+}
+
+impl Session {
+    /// Verify every on-disk structure and return `(object, status, detail)`
+    /// rows, one `ok` row per healthy object and one `error` row per problem.
+    ///
+    /// Covers the catalog, every table and index (see `CHECK TABLE`), and the
+    /// freelist: free pages must be in range, listed once, and unreachable
+    /// from any B-tree. Pages that are neither reachable nor free are leaked
+    /// space and reported as a warning. Runs against committed state, so it
+    /// is rejected inside a transaction.
+    pub fn verify_integrity(&mut self) -> Result<Vec<Row>> {
+        self.check_poisoned()?;
+        self.refresh_from_disk_if_needed()?;
+        if self.active_tx.is_some() {
+            return Err(MuroError::Execution(
+                "integrity verification cannot be used inside a transaction".into(),
+            ));
+        }
+
+        let mut report = check_database(&mut self.pager, &self.catalog)?;
+
+        let page_count = self.pager.page_count();
+        let free_pages = self.pager.freelist_mut().pages().to_vec();
+        let mut seen = std::collections::HashSet::new();
+        let mut problems = Vec::new();
+        for &page_id in &free_pages {
+            if page_id >= page_count {
+                problems.push(format!(
+                    "free page {} is beyond page count {}",
+                    page_id, page_count
+                ));
+            }
+            if !seen.insert(page_id) {
+                problems.push(format!("free page {} is listed more than once", page_id));
+            }
+            if let Some(owner) = report.reachable.get(&page_id) {
+                problems.push(format!(
+                    "free page {} is still reachable from {}",
+                    page_id, owner
+                ));
+            }
+        }
+        report.record(
+            "freelist",
+            format!("{} free pages of {}", free_pages.len(), page_count),
+            problems,
+        );
+
+        // Every page is reachable, free, or part of the freelist chain; any
+        // other page was leaked and can never be reused.
+        let chain: HashSet<PageId> = self.pager.freelist_chain_pages().into_iter().collect();
+        let leaked: Vec<PageId> = (0..page_count)
+            .filter(|page_id| {
+                !report.reachable.contains_key(page_id)
+                    && !seen.contains(page_id)
+                    && !chain.contains(page_id)
+            })
+            .collect();
+        if !leaked.is_empty() {
+            report.warn(
+                "freelist",
+                format!(
+                    "{} pages are neither reachable nor free (first: page {})",
+                    leaked.len(),
+                    leaked[0]
+                ),
+            );
+        }
+        Ok(report.rows)
+    }
+
+    /// Read every page of the file and report the ones that fail, each
+    /// mapped to the table, index, or other structure it belongs to.
+    ///
+    /// Ownership comes from walking the catalog and every B-tree with the
+    /// same fault-tolerant reader as [`Session::verify_integrity`], so a bad
+    /// page is attributed as long as the page pointing to it is readable.
+    /// Runs against committed state, so it is rejected inside a transaction.
+    pub fn corruption_report(&mut self) -> Result<CorruptionReport> {
+        self.check_poisoned()?;
+        self.refresh_from_disk_if_needed()?;
+        if self.active_tx.is_some() {
+            return Err(MuroError::Execution(
+                "corruption report cannot be used inside a transaction".into(),
+            ));
+        }
+
+        let PageScanReport {
+            pages_scanned,
+            unreadable,
+        } = self.pager.scan_all_pages_report();
+        let mut report = CorruptionReport {
+            pages_scanned,
+            bad_pages: Vec::new(),
+        };
+        if unreadable.is_empty() {
+            return Ok(report);
+        }
+
+        let reachable = check_database(&mut self.pager, &self.catalog)?.reachable;
+        let chain: HashSet<PageId> = self.pager.freelist_chain_pages().into_iter().collect();
+        let free: HashSet<PageId> = self.pager.freelist_mut().pages().iter().copied().collect();
+        for UnreadablePage {
+            page_id,
+            decrypt_failed,
+            error,
+        } in unreadable
+        {
+            let owner = if let Some(owner) = reachable.get(&page_id) {
+                owner.clone()
+            } else if chain.contains(&page_id) {
+                PageOwner::Freelist
+            } else if free.contains(&page_id) {
+                PageOwner::Free
+            } else {
+                PageOwner::Unknown
+            };
+            report.bad_pages.push(CorruptPage {
+                page_id,
+                decrypt_failed,
+                error,
+                owner,
+            });
+        }
+        Ok(report)
+    }
+}
@@ -0,0 +1,270 @@
+use super::*;
+use crate::fts::index::FtsIndex;
+use crate::schema::index::IndexType;
+use crate::storage::page_store::PageStore;
+
+/// Limits of one idle-time maintenance call ([`Session::run_maintenance`],
+/// `Database::run_maintenance`, or `OPTIMIZE`).
+///
+/// The work is done in batches of at most `batch_size` items, each its own
+/// committed transaction. A call always runs at least one batch and starts
+/// no further batch once either limit is reached.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct MaintenanceBudget {
+    /// Time after which no new batch is started.
+    pub max_duration: Duration,
+    /// Pages written or released after which no new batch is started.
+    pub max_pages: u64,
+    /// Full-text GC tasks, or trailing free pages, handled per batch.
+    pub batch_size: usize,
+    /// Whether to shorten the data file when the pages at its end are free.
+    pub release_free_tail: bool,
+}
+
+impl Default for MaintenanceBudget {
+    fn default() -> Self {
+        Self {
+            max_duration: Duration::from_millis(50),
+            max_pages: 4096,
+            batch_size: 64,
+            release_free_tail: true,
+        }
+    }
+}
+
+/// What a maintenance call did, and what is left for the next one.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct MaintenanceReport {
+    /// Batches run, each under the write lock and in its own transaction.
+    pub batches: u64,
+    /// Full-text GC tasks processed: stale posting segments freed.
+    pub fts_gc_tasks: u64,
+    /// Pages written by the batches.
+    pub pages_written: u64,
+    /// Pages cut from the end of the data file.
+    pub pages_released: u64,
+    /// Full-text GC tasks still queued, over all full-text indexes.
+    pub fts_gc_tasks_remaining: u64,
+    /// Free pages at the end of the file still to be released; `0` when the
+    /// budget does not release them.
+    pub free_tail_pages_remaining: u64,
+    pub elapsed: Duration,
+}
+
+impl MaintenanceReport {
+    /// Whether the call left no work for another one.
+    pub fn is_complete(&self) -> bool {
+        self.fts_gc_tasks_remaining == 0 && self.free_tail_pages_remaining == 0
+    }
+}
+
+/// Progress of one maintenance call across its batches.
+pub(crate) struct MaintenanceRun {
+    budget: MaintenanceBudget,
+    started: Instant,
+    /// Whether the last batch found anything to do.
+    progressed: bool,
+    report: MaintenanceReport,
+}
+
+impl MaintenanceRun {
+    pub(crate) fn new(budget: MaintenanceBudget) -> Self {
+        Self {
+            budget,
+            started: Instant::now(),
+            progressed: true,
+            report: MaintenanceReport::default(),
+        }
+    }
+
+    /// Whether to run another batch. The first one always runs, so that the
+    /// report says how much work there is.
+    pub(crate) fn wants_batch(&self) -> bool {
+        let report = &self.report;
+        report.batches == 0
+            || (self.progressed
+                && !report.is_complete()
+                && self.started.elapsed() < self.budget.max_duration
+                && report.pages_written + report.pages_released < self.budget.max_pages)
+    }
+
+    pub(crate) fn finish(mut self) -> MaintenanceReport {
+        self.report.elapsed = self.started.elapsed();
+        self.report
+    }
+}
+
+impl Session {
+    /// Run idle-time maintenance within `budget`: vacuum the stale posting
+    /// segments of every full-text index, then release free pages at the
+    /// end of the data file. With a lock manager the write lock is taken
+    /// for each batch separately, so other handles run in between.
+    pub fn run_maintenance(&mut self, budget: MaintenanceBudget) -> Result<MaintenanceReport> {
+        let mut run = MaintenanceRun::new(budget);
+        while run.wants_batch() {
+            let lock_manager = self.lock_manager.clone();
+            let _guard = self.write_lock(lock_manager.as_deref())?;
+            self.run_maintenance_batch(&mut run)?;
+        }
+        Ok(run.finish())
+    }
+
+    /// Run one batch of `run` as a statement of its own; the caller holds
+    /// the write lock.
+    pub(crate) fn run_maintenance_batch(&mut self, run: &mut MaintenanceRun) -> Result<()> {
+        let _status = self.status.enter("OPTIMIZE");
+        let _statement_guard = self.enter_statement();
+        self.status.set_phase(StatementPhase::Executing);
+        self.check_poisoned()?;
+        self.flush_expired_commit_batch()?;
+        self.refresh_from_disk_if_needed()?;
+        self.maintenance_batch(run)
+    }
+
+    /// `OPTIMIZE`: one maintenance call with the default budget, all under
+    /// the lock of the statement.
+    pub(super) fn handle_optimize(&mut self) -> Result<ExecResult> {
+        let mut run = MaintenanceRun::new(MaintenanceBudget::default());
+        while run.wants_batch() {
+            self.maintenance_batch(&mut run)?;
+        }
+        let report = run.finish();
+        let integer = |v: u64| Value::Integer(v as i64);
+        Ok(ExecResult::Rows(vec![Row {
+            values: vec![
+                ("Batches".to_string(), integer(report.batches)),
+                ("Fts_gc_tasks".to_string(), integer(report.fts_gc_tasks)),
+                ("Pages_written".to_string(), integer(report.pages_written)),
+                ("Pages_released".to_string(), integer(report.pages_released)),
+                (
+                    "Fts_gc_tasks_remaining".to_string(),
+                    integer(report.fts_gc_tasks_remaining),
+                ),
+                (
+                    "Free_tail_pages_remaining".to_string(),
+                    integer(report.free_tail_pages_remaining),
+                ),
+                (
+                    "Elapsed_ms".to_string(),
+                    integer(report.elapsed.as_millis() as u64),
+                ),
+            ],
+        }]))
+    }
+
+    /// Full-text GC first, since it frees the pages a tail release may cut.
+    fn maintenance_batch(&mut self, run: &mut MaintenanceRun) -> Result<()> {
+        if self.read_only {
+            return Err(MuroError::ReadOnly);
+        }
+        if self.active_tx.is_some() {
+            return Err(MuroError::Transaction(
+                "maintenance cannot run inside a transaction".into(),
+            ));
+        }
+        let batch_size = run.budget.batch_size.max(1);
+        let dirtied_before = self.statement_pages_dirtied;
+        let mut vacuumed = 0;
+        if self.pending_fts_gc_tasks()? > 0 {
+            vacuumed = self.run_auto_commit(
+                |_| false,
+                |store, catalog| vacuum_fts_indexes(store, catalog, batch_size),
+            )?;
+        }
+        let mut released = 0;
+        if vacuumed == 0 && run.budget.release_free_tail {
+            released = self.release_free_tail(batch_size as u64)?;
+        }
+
+        let report = &mut run.report;
+        report.batches += 1;
+        report.fts_gc_tasks += vacuumed as u64;
+        report.pages_written += self.statement_pages_dirtied - dirtied_before;
+        report.pages_released += released;
+        report.fts_gc_tasks_remaining = self.pending_fts_gc_tasks()?;
+        report.free_tail_pages_remaining = if run.budget.release_free_tail {
+            self.pager.releasable_tail_pages()
+        } else {
+            0
+        };
+        run.progressed = vacuumed > 0 || released > 0;
+        if vacuumed > 0 {
+            self.refresh_fts_gc_backlog();
+        }
+        Ok(())
+    }
+
+    fn pending_fts_gc_tasks(&mut self) -> Result<u64> {
+        let mut pending = 0;
+        for idx in fulltext_indexes(&mut self.pager, &self.catalog)? {
+            let fts = FtsIndex::open(idx.btree_root, self.pager.fts_term_key()?);
+            pending += fts.pending_gc_tasks(&mut self.pager)?;
+        }
+        Ok(pending)
+    }
+
+    /// Cut up to `max_pages` free pages from the end of the data file.
+    /// Returns how many pages the file lost.
+    ///
+    /// The lower page count is committed like any change, and the file is
+    /// shortened only after a checkpoint has written it to the header, so
+    /// no WAL frame can be replayed past the new end.
+    fn release_free_tail(&mut self, max_pages: u64) -> Result<u64> {
+        let alloc_before = PagerAllocState::capture(&mut self.pager);
+        let page_count_before = self.pager.page_count();
+        if !self.pager.release_free_tail(max_pages) {
+            return Ok(0);
+        }
+        if let Err(e) = self.run_auto_commit(|_| false, |_, _| Ok(())) {
+            if !matches!(e, MuroError::CommitInDoubt(_)) {
+                alloc_before.restore(&mut self.pager);
+            }
+            return Err(e);
+        }
+        self.try_checkpoint_truncate_with_retry()
+            .map_err(|(_, e)| e)?;
+        self.pager.truncate_to_page_count()?;
+        Ok(page_count_before.saturating_sub(self.pager.page_count()))
+    }
+}
+
+fn fulltext_indexes(
+    pager: &mut impl PageStore,
+    catalog: &SystemCatalog,
+) -> Result<Vec<crate::schema::index::IndexDef>> {
+    let mut indexes = Vec::new();
+    for table in catalog.list_tables(pager)? {
+        indexes.extend(
+            catalog
+                .get_indexes_for_table(pager, &table)?
+                .into_iter()
+                .filter(|idx| idx.index_type == IndexType::Fulltext),
+        );
+    }
+    Ok(indexes)
+}
+
+/// Process up to `max_tasks` full-text GC tasks, index by index.
+fn vacuum_fts_indexes(
+    store: &mut TxPageStore,
+    catalog: &mut SystemCatalog,
+    max_tasks: usize,
+) -> Result<usize> {
+    let mut processed = 0;
+    for mut idx in fulltext_indexes(store, catalog)? {
+        if processed >= max_tasks {
+            break;
+        }
+        let mut fts = FtsIndex::open(idx.btree_root, store.fts_term_key()?);
+        if fts.pending_gc_tasks(store)? == 0 {
+            continue;
+        }
+        processed += fts.vacuum_stale_segments(store, max_tasks - processed)?;
+        if fts.root_page_id() != idx.btree_root {
+            idx.btree_root = fts.root_page_id();
+            catalog.update_index(store, &idx)?;
+            catalog.bump_generation();
+        }
+    }
+    Ok(processed)
+}
@@ -0,0 +1,275 @@
+use super::*;
+use sha2::{Digest, Sha256};
+use std::collections::BTreeMap;
+use std::fmt::Write as _;
+
+/// Prometheus metric kind.
+#[derive(Clone, Copy)]
+enum Kind {
+    Counter,
+    Gauge,
+}
+
+struct MetricsWriter {
+    out: String,
+    labels: String,
+}
+
+impl MetricsWriter {
+    fn metric(&mut self, name: &str, kind: Kind, help: &str, value: impl std::fmt::Display) {
+        self.header(name, kind, help);
+        let _ = writeln!(self.out, "{}{{{}}} {}", name, self.labels, value);
+    }
+
+    /// A metric with one sample per table, labelled `table`.
+    fn per_table(&mut self, name: &str, kind: Kind, help: &str, values: &BTreeMap<String, u64>) {
+        self.header(name, kind, help);
+        for (table, value) in values {
+            let _ = writeln!(
+                self.out,
+                "{}{{{},table=\"{}\"}} {}",
+                name,
+                self.labels,
+                escape_label_value(table),
+                value
+            );
+        }
+    }
+
+    fn header(&mut self, name: &str, kind: Kind, help: &str) {
+        let kind = match kind {
+            Kind::Counter => "counter",
+            Kind::Gauge => "gauge",
+        };
+        let _ = writeln!(self.out, "# HELP {} {}", name, help);
+        let _ = writeln!(self.out, "# TYPE {} {}", name, kind);
+    }
+}
+
+/// Escape a label value as the text format requires.
+fn escape_label_value(value: &str) -> String {
+    value
+        .replace('\\', "\\\\")
+        .replace('"', "\\\"")
+        .replace('\n', "\\n")
+}
+
+/// Stable, path-free identifier for the `db` label: first 16 hex digits of
+/// SHA-256 over the database file path.
+fn db_label(path: &std::path::Path) -> String {
+    let digest = Sha256::digest(path.as_os_str().as_encoded_bytes());
+    digest[..8].iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+fn ms_to_seconds(ms: u64) -> f64 {
+    ms as f64 / 1000.0
+}
+
+impl Session {
+    /// Render in-memory statistics in the Prometheus text exposition format.
+    ///
+    /// Reads the same counters as `SHOW DATABASE STATS` without touching pages,
+    /// so it is cheap enough to call on every scrape. Metric names are a stable
+    /// contract: existing names are never renamed or retyped.
+    pub fn metrics_prometheus(&self) -> String {
+        let stats = &self.stats;
+        let mut w = MetricsWriter {
+            out: String::new(),
+            labels: format!("db=\"{}\"", db_label(self.pager.path())),
+        };
+
+        w.metric(
+            "murodb_checkpoints_total",
+            Kind::Counter,
+            "Successful WAL checkpoints.",
+            stats.total_checkpoints,
+        );
+        w.metric(
+            "murodb_checkpoint_failures_total",
+            Kind::Counter,
+            "WAL checkpoints that failed after retries.",
+            stats.failed_checkpoints,
+        );
+        w.metric(
+            "murodb_deferred_checkpoints_total",
+            Kind::Counter,
+            "Commits whose checkpoint was deferred by policy.",
+            stats.deferred_checkpoints,
+        );
+        w.metric(
+            "murodb_checkpoint_pending_ops",
+            Kind::Gauge,
+            "Committed transactions not yet checkpointed.",
+            self.pending_checkpoint_ops,
+        );
+        w.metric(
+            "murodb_last_checkpoint_failure_timestamp_seconds",
+            Kind::Gauge,
+            "Unix time of the last checkpoint failure (0 if none).",
+            ms_to_seconds(stats.last_failure_timestamp_ms.unwrap_or(0)),
+        );
+        w.metric(
+            "murodb_commit_in_doubt_total",
+            Kind::Counter,
+            "Commits durable in the WAL whose data-file flush failed.",
+            stats.commit_in_doubt_count,
+        );
+        w.metric(
+            "murodb_last_commit_in_doubt_timestamp_seconds",
+            Kind::Gauge,
+            "Unix time of the last commit-in-doubt event (0 if none).",
+            ms_to_seconds(stats.last_commit_in_doubt_timestamp_ms.unwrap_or(0)),
+        );
+        w.metric(
+            "murodb_session_poisoned",
+            Kind::Gauge,
+            "1 if the session is poisoned and the database must be reopened.",
+            u8::from(self.poisoned.is_some()),
+        );
+        w.metric(
+            "murodb_freelist_sanitize_total",
+            Kind::Counter,
+            "Freelist sanitizations performed at open.",
+            stats.freelist_sanitize_count,
+        );
+        w.metric(
+            "murodb_freelist_out_of_range_entries_total",
+            Kind::Counter,
+            "Out-of-range freelist entries removed by sanitization.",
+            stats.freelist_out_of_range_total,
+        );
+        w.metric(
+            "murodb_freelist_duplicate_entries_total",
+            Kind::Counter,
+            "Duplicate freelist entries removed by sanitization.",
+            stats.freelist_duplicates_total,
+        );
+        w.metric(
+            "murodb_scan_skipped_pages_total",
+            Kind::Counter,
+            "Unreadable pages skipped by scans under scan_corruption_policy = 'skip'.",
+            stats.scan_skipped_pages,
+        );
+        w.metric(
+            "murodb_scan_skipped_rows_total",
+            Kind::Counter,
+            "Undecodable rows skipped by scans under scan_corruption_policy = 'skip'.",
+            stats.scan_skipped_rows,
+        );
+        w.metric(
+            "murodb_plan_cache_hits_total",
+            Kind::Counter,
+            "SELECT plans reused from the plan cache.",
+            stats.plan_cache_hits,
+        );
+        w.metric(
+            "murodb_plan_cache_fallbacks_total",
+            Kind::Counter,
+            "Cached plans re-planned because an index they use no longer exists.",
+            stats.plan_cache_fallbacks,
+        );
+        w.metric(
+            "murodb_plan_baseline_hits_total",
+            Kind::Counter,
+            "SELECT plans taken from an installed plan baseline.",
+            stats.plan_baseline_hits,
+        );
+        w.metric(
+            "murodb_plan_baseline_fallbacks_total",
+            Kind::Counter,
+            "Statements planned normally because their plan baseline no longer applies.",
+            stats.plan_baseline_fallbacks,
+        );
+        w.metric(
+            "murodb_wal_commits_total",
+            Kind::Counter,
+            "Transactions committed through this handle.",
+            stats.wal_commits,
+        );
+        w.metric(
+            "murodb_wal_commit_write_calls_total",
+            Kind::Counter,
+            "WAL file writes taken by commits.",
+            stats.wal_commit_write_calls,
+        );
+        w.metric(
+            "murodb_checkpoint_policy_tx_threshold",
+            Kind::Gauge,
+            "Configured commits per checkpoint.",
+            self.checkpoint_policy.tx_threshold,
+        );
+        w.metric(
+            "murodb_checkpoint_policy_wal_bytes_threshold",
+            Kind::Gauge,
+            "Configured WAL size that forces a checkpoint (0 = disabled).",
+            self.checkpoint_policy.wal_bytes_threshold,
+        );
+        w.metric(
+            "murodb_checkpoint_policy_interval_seconds",
+            Kind::Gauge,
+            "Configured maximum time between checkpoints (0 = disabled).",
+            ms_to_seconds(self.checkpoint_policy.interval_ms),
+        );
+        w.metric(
+            "murodb_pager_cache_hits_total",
+            Kind::Counter,
+            "Page reads served from the page cache.",
+            self.pager.cache_hits(),
+        );
+        w.metric(
+            "murodb_pager_cache_misses_total",
+            Kind::Counter,
+            "Page reads that had to read and decrypt from disk.",
+            self.pager.cache_misses(),
+        );
+        w.metric(
+            "murodb_pager_cache_evictions_total",
+            Kind::Counter,
+            "Pages evicted from the page cache to stay within capacity.",
+            self.pager.cache_evictions(),
+        );
+        w.metric(
+            "murodb_pager_pages_decrypted_total",
+            Kind::Counter,
+            "Page images read from disk and decrypted.",
+            self.pager.pages_decrypted(),
+        );
+        w.metric(
+            "murodb_pager_cache_bytes",
+            Kind::Gauge,
+            "Memory held by cached page images.",
+            self.pager.cache_bytes(),
+        );
+        w.metric(
+            "murodb_pager_cache_capacity_pages",
+            Kind::Gauge,
+            "Configured page cache capacity.",
+            self.pager.cache_capacity(),
+        );
+        w.metric(
+            "murodb_pages",
+            Kind::Gauge,
+            "Pages allocated in the database file.",
+            self.pager.page_count(),
+        );
+        w.metric(
+            "murodb_wal_size_bytes",
+            Kind::Gauge,
+            "Current WAL file size.",
+            self.wal.file_size_bytes().unwrap_or(0),
+        );
+        w.metric(
+            "murodb_fts_gc_backlog_tasks",
+            Kind::Gauge,
+            "FTS GC tasks queued for vacuum, as of open, this handle's last write to each table, or its last maintenance run.",
+            self.table_counters.fts_gc_backlog_total(),
+        );
+        w.per_table(
+            "murodb_table_version_total",
+            Kind::Counter,
+            "Committed transactions through this handle that changed the table's rows or definition.",
+            &self.table_counters.versions,
+        );
+        w.out
+    }
+}
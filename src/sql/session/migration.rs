@@ -0,0 +1,73 @@
+use super::*;
+use crate::schema::migrate;
+
+impl Session {
+    /// Bring the catalog up to the current format version, or fail with
+    /// [`MuroError::IncompatibleVersion`] if it is newer than this library.
+    ///
+    /// All migrations run in one transaction committed through the WAL, so
+    /// the version is bumped only together with the rewritten records.
+    /// Returns the version migrated from, or `None` when nothing was due.
+    /// Databases without a catalog yet (pager-only files) are left alone.
+    pub(crate) fn migrate_catalog(&mut self) -> Result<Option<u32>> {
+        if !self.catalog_needs_migration()? {
+            return Ok(None);
+        }
+
+        let txid = self.next_txid;
+        self.next_txid += 1;
+        let tx = Transaction::begin(txid, self.wal.current_lsn());
+        let catalog_root_before = self.catalog.root_page_id();
+        let alloc_before = PagerAllocState::capture(&mut self.pager);
+
+        let mut store = TxPageStore::new(tx, &mut self.pager);
+        let result = migrate::migrate(&mut store, &mut self.catalog);
+        let mut tx = store.into_tx();
+
+        let from = match result {
+            Ok(from) => from,
+            Err(e) => {
+                tx.rollback_no_wal();
+                alloc_before.restore(&mut self.pager);
+                self.reopen_catalog(catalog_root_before);
+                return Err(e);
+            }
+        };
+        let catalog_root = self.catalog.root_page_id();
+        self.pager.set_next_txid(self.next_txid);
+        match tx.commit(&mut self.pager, &mut self.wal, catalog_root) {
+            Err(e @ MuroError::CommitInDoubt(_)) => {
+                self.record_commit_in_doubt(&e);
+                self.poisoned = Some(e.to_string());
+                return Err(e);
+            }
+            Err(e) => {
+                alloc_before.restore(&mut self.pager);
+                self.reopen_catalog(catalog_root_before);
+                return Err(e);
+            }
+            Ok(_) => {}
+        }
+        self.catalog.bump_generation();
+        self.post_commit_checkpoint();
+        Ok(Some(from))
+    }
+
+    /// Fail with [`MuroError::IncompatibleVersion`] if the catalog is newer
+    /// than this library, without migrating an older one.
+    pub(crate) fn check_catalog_version(&mut self) -> Result<()> {
+        self.catalog_needs_migration().map(|_| ())
+    }
+
+    fn catalog_needs_migration(&mut self) -> Result<bool> {
+        // Root page 0 is a valid catalog root, except in a fresh file or one
+        // built through Pager-only flows, which have no catalog B-tree.
+        if self.pager.catalog_root() == 0 && self.pager.page_count() == 0 {
+            return Ok(false);
+        }
+        match migrate::needs_migration(&mut self.pager, &self.catalog) {
+            Err(MuroError::InvalidPage) if self.pager.catalog_root() == 0 => Ok(false),
+            other => other,
+        }
+    }
+}
@@ -1,29 +1,75 @@
+use crate::concurrency::{HandleRegistration, LockManager, ReadGuard, WriteGuard};
 use crate::crypto::kdf;
 use crate::crypto::suite::EncryptionSuite;
 use crate::error::{MuroError, Result};
 use crate::schema::catalog::SystemCatalog;
-use crate::sql::ast::Statement;
+use crate::sql::ast::{AlterTableOp, CreateIndex, Insert, ScanCorruptionPolicy, Statement};
 use crate::sql::executor::{execute_statement, ExecResult, Row};
-use crate::sql::parser::parse_sql;
-use crate::sql::prepared::{contains_bind_params, PreparedStatement};
-use crate::storage::freelist::FreeList;
+use crate::sql::parser::{parse_script_with_offsets, parse_statement};
+use crate::sql::prepared::{contains_bind_params, value_to_expr, PreparedStatement};
+use crate::storage::page::PageId;
 use crate::storage::pager::Pager;
-use crate::tx::page_store::TxPageStore;
-use crate::tx::transaction::Transaction;
+use crate::tx::commit_outcome::CommitOutcomeLog;
+use crate::tx::page_store::{PagerAllocState, TxPageStore};
+use crate::tx::transaction::{Transaction, TxState};
 use crate::types::Value;
 use crate::wal::record::TxId;
-use crate::wal::writer::WalWriter;
+use crate::wal::writer::{WalDurability, WalWriter};
 use checkpoint::CheckpointPolicy;
-use std::cell::RefCell;
-use std::sync::atomic::{AtomicU64, Ordering};
+use std::cell::{Cell, RefCell};
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
 use std::sync::Arc;
-use std::time::Instant;
+use std::time::{Duration, Instant};
 
 const CHECKPOINT_MAX_ATTEMPTS: usize = 2;
 const DEFAULT_CHECKPOINT_TX_THRESHOLD: u64 = 1;
 const DEFAULT_CHECKPOINT_WAL_BYTES_THRESHOLD: u64 = 0;
 const DEFAULT_CHECKPOINT_INTERVAL_MS: u64 = 0;
+/// Default of `aggregation_memory_budget`: 64 MiB.
+const DEFAULT_AGGREGATION_MEMORY_BUDGET: usize = 64 * 1024 * 1024;
+/// Default of `in_list_seek_max_items`.
+const DEFAULT_IN_LIST_SEEK_MAX_ITEMS: usize = 10_000;
+const DEFAULT_FTS_VACUUM_BATCH: usize = 1024;
+/// Default of `index_build_batch_rows`.
+const DEFAULT_INDEX_BUILD_BATCH_ROWS: u64 = 10_000;
+mod auto_increment;
 mod checkpoint;
+mod commit_outcome;
+mod dump;
+mod index_build;
+mod integrity;
+mod maintenance;
+mod metrics;
+mod migration;
+mod plan_baselines;
+mod plan_cache;
+mod schema_check;
+mod settings;
+mod status;
+mod table_counters;
+mod temp_tables;
+mod warnings;
+
+use auto_increment::AutoIncrementState;
+pub(crate) use auto_increment::{
+    auto_increment_high_water_current, forget_auto_increment_current, raise_auto_increment_current,
+};
+pub(crate) use dump::import_script;
+pub use dump::DumpOptions;
+pub use integrity::{CorruptPage, CorruptionReport, PageOwner};
+pub(crate) use maintenance::MaintenanceRun;
+pub use maintenance::{MaintenanceBudget, MaintenanceReport};
+use plan_baselines::PlanBaselines;
+pub(crate) use plan_baselines::{plan_baseline_current, record_plan_baseline_outcome_current};
+pub(crate) use plan_cache::select_plan_current;
+use plan_cache::PlanCache;
+pub use status::{EngineStatus, SessionStatus, StatementPhase};
+use table_counters::TableCounters;
+
+pub(crate) use warnings::{
+    record_query_warning_current, record_scan_warning_current, scan_skip_corruption_current,
+    ScanWarning,
+};
 
 /// Database operation statistics for observability.
 #[derive(Debug, Clone, Default)]
@@ -42,11 +88,71 @@ pub struct DatabaseStats {
     pub freelist_out_of_range_total: u64,
     pub freelist_duplicates_total: u64,
     pub deferred_checkpoints: u64,
+    // Corruption skipped by scans in skip mode
+    pub scan_skipped_pages: u64,
+    pub scan_skipped_rows: u64,
+    // Plan cache
+    pub plan_cache_hits: u64,
+    /// Cached plans discarded because an index they use no longer exists.
+    pub plan_cache_fallbacks: u64,
+    // Plan baselines
+    pub plan_baseline_hits: u64,
+    /// Statements whose baseline no longer applied and were planned normally.
+    pub plan_baseline_fallbacks: u64,
+    // WAL writes
+    /// Transactions committed through this handle.
+    pub wal_commits: u64,
+    /// Writes to the WAL file those commits took. A commit writes its frames
+    /// with one call unless they overflow the WAL write buffer.
+    pub wal_commit_write_calls: u64,
+    /// Writes the last commit took.
+    pub last_commit_wal_write_calls: u64,
 }
 
 /// Backward-compatible alias.
 pub type CheckpointStats = DatabaseStats;
 
+/// What the last statement wrote, for audit trails.
+///
+/// Read-only statements run through [`Session::execute_read_only_query`]
+/// always report zero writes. `execute` of a SELECT in auto-commit mode still
+/// commits an (empty) implicit transaction, which appends WAL frames.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct StatementMetrics {
+    /// Whether the statement dirtied any page or appended any WAL frame.
+    pub performed_writes: bool,
+    /// WAL frames appended, including those of the commit it triggered.
+    pub wal_frames_appended: u64,
+    /// Distinct pages the statement wrote, including writes that were later
+    /// rolled back (a failed statement, EXPLAIN ANALYZE of a write).
+    pub pages_dirtied: u64,
+    /// Page images its commit (or a batch flush it triggered) wrote to the
+    /// data file.
+    pub data_pages_flushed: u64,
+    /// Positioned writes those pages took: consecutive page ids are written
+    /// with one call.
+    pub contiguous_write_runs: u64,
+}
+
+impl StatementMetrics {
+    /// Mean pages per data-file write; `0.0` when nothing was written.
+    pub fn avg_run_length(&self) -> f64 {
+        if self.contiguous_write_runs == 0 {
+            0.0
+        } else {
+            self.data_pages_flushed as f64 / self.contiguous_write_runs as f64
+        }
+    }
+}
+
+/// Counters sampled when a statement starts, diffed for [`StatementMetrics`].
+#[derive(Clone, Copy)]
+struct StatementMetricsStart {
+    wal_frames: u64,
+    data_pages_flushed: u64,
+    data_write_runs: u64,
+}
+
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub struct RuntimeConfig {
     pub checkpoint_tx_threshold: u64,
@@ -82,6 +188,39 @@ impl QueryCancelHandle {
             .store(active_id, Ordering::SeqCst);
         true
     }
+
+    /// Whether a statement is running on the handle, without cancelling it.
+    pub fn is_running(&self) -> bool {
+        self.state.active_statement_id.load(Ordering::SeqCst) != 0
+    }
+}
+
+/// Cancellation flag owned by the caller and passed to
+/// [`Session::execute_with_cancel`] or `Database::execute_with_cancel`.
+///
+/// Unlike [`QueryCancelHandle`], which targets whatever statement is running
+/// when it is used, a token belongs to the statements it is passed to:
+/// cancelling it before one starts makes that statement fail right away.
+/// Once cancelled it stays cancelled; use a new token for the next statement.
+#[derive(Debug, Clone, Default)]
+pub struct CancellationToken {
+    cancelled: Arc<AtomicBool>,
+}
+
+impl CancellationToken {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Ask the statements running with this token to stop at their next
+    /// cancellation point.
+    pub fn cancel(&self) {
+        self.cancelled.store(true, Ordering::SeqCst);
+    }
+
+    pub fn is_cancelled(&self) -> bool {
+        self.cancelled.load(Ordering::SeqCst)
+    }
 }
 
 struct StatementExecutionGuard {
@@ -98,6 +237,26 @@ struct StatementTimeoutContext {
 thread_local! {
     static ACTIVE_CANCEL_STATE: RefCell<Option<Arc<QueryCancelState>>> = const { RefCell::new(None) };
     static ACTIVE_STATEMENT_TIMEOUT: RefCell<Option<StatementTimeoutContext>> = const { RefCell::new(None) };
+    /// Token passed to `execute_with_cancel` for the running statement.
+    static ACTIVE_CANCEL_TOKEN: RefCell<Option<CancellationToken>> = const { RefCell::new(None) };
+    /// Corruption report of the running statement; `Some` only in skip mode.
+    static ACTIVE_SCAN_WARNINGS: RefCell<Option<Vec<ScanWarning>>> = const { RefCell::new(None) };
+    /// Non-corruption warnings of the running statement (e.g. FTS fallbacks).
+    static ACTIVE_QUERY_WARNINGS: RefCell<Option<Vec<ScanWarning>>> = const { RefCell::new(None) };
+    /// Whether the running statement may reorder WHERE conjuncts.
+    static ACTIVE_PREDICATE_REORDER: Cell<bool> = const { Cell::new(true) };
+    /// Bytes GROUP BY may hold in memory before spilling, for the running statement.
+    static ACTIVE_AGGREGATION_MEMORY_BUDGET: Cell<usize> = const { Cell::new(DEFAULT_AGGREGATION_MEMORY_BUDGET) };
+    /// Longest `IN` list the running statement may plan as one seek per value.
+    static ACTIVE_IN_LIST_SEEK_MAX_ITEMS: Cell<usize> = const { Cell::new(DEFAULT_IN_LIST_SEEK_MAX_ITEMS) };
+    /// Stale FULLTEXT segments `OPTIMIZE TABLE` may reclaim per index in the running statement.
+    static ACTIVE_FTS_VACUUM_BATCH: Cell<usize> = const { Cell::new(DEFAULT_FTS_VACUUM_BATCH) };
+    /// The session's plan cache, lent to the running statement.
+    static ACTIVE_PLAN_CACHE: RefCell<Option<PlanCache>> = const { RefCell::new(None) };
+    /// The session's plan baselines, lent to the running statement when enabled.
+    static ACTIVE_PLAN_BASELINES: RefCell<Option<PlanBaselines>> = const { RefCell::new(None) };
+    /// The session's auto-increment high-water marks, lent to the running statement.
+    static ACTIVE_AUTO_INCREMENT: RefCell<Option<AutoIncrementState>> = const { RefCell::new(None) };
 }
 
 impl Drop for StatementExecutionGuard {
@@ -108,6 +267,28 @@ impl Drop for StatementExecutionGuard {
         ACTIVE_STATEMENT_TIMEOUT.with(|slot| {
             *slot.borrow_mut() = None;
         });
+        ACTIVE_CANCEL_TOKEN.with(|slot| {
+            *slot.borrow_mut() = None;
+        });
+        ACTIVE_SCAN_WARNINGS.with(|slot| {
+            *slot.borrow_mut() = None;
+        });
+        ACTIVE_QUERY_WARNINGS.with(|slot| {
+            *slot.borrow_mut() = None;
+        });
+        ACTIVE_PREDICATE_REORDER.with(|slot| slot.set(true));
+        ACTIVE_AGGREGATION_MEMORY_BUDGET.with(|slot| slot.set(DEFAULT_AGGREGATION_MEMORY_BUDGET));
+        ACTIVE_IN_LIST_SEEK_MAX_ITEMS.with(|slot| slot.set(DEFAULT_IN_LIST_SEEK_MAX_ITEMS));
+        ACTIVE_FTS_VACUUM_BATCH.with(|slot| slot.set(DEFAULT_FTS_VACUUM_BATCH));
+        ACTIVE_PLAN_CACHE.with(|slot| {
+            *slot.borrow_mut() = None;
+        });
+        ACTIVE_PLAN_BASELINES.with(|slot| {
+            *slot.borrow_mut() = None;
+        });
+        ACTIVE_AUTO_INCREMENT.with(|slot| {
+            *slot.borrow_mut() = None;
+        });
         let _ = self.state.active_statement_id.compare_exchange(
             self.statement_id,
             0,
@@ -131,6 +312,9 @@ pub struct Session {
     catalog: SystemCatalog,
     wal: WalWriter,
     active_tx: Option<Transaction>,
+    /// Allocation state at `BEGIN`, restored on `ROLLBACK` or a failed `COMMIT`.
+    tx_alloc_state: Option<PagerAllocState>,
+    tx_started_at: Instant,
     savepoints: Vec<Savepoint>,
     next_txid: TxId,
     stats: DatabaseStats,
@@ -140,20 +324,72 @@ pub struct Session {
     last_checkpoint_at: std::time::Instant,
     statement_timeout_ms: u64,
     cancel_state: Arc<QueryCancelState>,
+    /// Token of the `execute_with_cancel` call in progress.
+    cancel_token: Option<CancellationToken>,
+    scan_corruption_policy: ScanCorruptionPolicy,
+    predicate_reorder: bool,
+    aggregation_memory_budget: usize,
+    in_list_seek_max_items: usize,
+    fts_vacuum_batch: usize,
+    index_build_batch_rows: u64,
+    /// Set by `SET read_only = ON`: statements that write are rejected.
+    read_only: bool,
+    /// Set by `SET abort_on_statement_error = ON`: a failed statement
+    /// aborts the explicit transaction it ran in.
+    abort_on_statement_error: bool,
+    plan_cache: Option<PlanCache>,
+    plan_baselines_enabled: bool,
+    plan_baselines: PlanBaselines,
+    auto_increment: AutoIncrementState,
+    table_counters: TableCounters,
+    /// Tag for the next commit, set by `set_commit_tag`.
+    commit_tag: Option<String>,
+    /// In-doubt commits resolved when the database was opened.
+    commit_outcomes: CommitOutcomeLog,
+    /// Corruption report of the last statement, for `SHOW WARNINGS`.
+    warnings: Vec<ScanWarning>,
+    last_statement_metrics: StatementMetrics,
+    /// Pages dirtied so far by the running statement.
+    statement_pages_dirtied: u64,
+    /// Lock manager taken over from `Database::into_session`. When set,
+    /// every statement runs under its shared or exclusive lock.
+    lock_manager: Option<Arc<LockManager>>,
+    /// The `Database`'s place in the in-process handle registry, kept so an
+    /// exclusive claim outlives `Database::into_session`.
+    registration: Option<HandleRegistration>,
+    /// Lock wait timeout in milliseconds; `0` waits indefinitely.
+    busy_timeout_ms: u64,
+    /// Catalog namespace of this session's temporary tables, set up by the
+    /// first `CREATE TEMPORARY TABLE`.
+    temp_namespace: Option<temp_tables::TempNamespace>,
+    /// Live activity for `SHOW PROCESSLIST` and `status()`.
+    status: Arc<EngineStatus>,
     #[cfg(test)]
     inject_checkpoint_failures_remaining: usize,
     #[cfg(test)]
     inject_wal_recreate_fail_once: bool,
 }
 
+/// The explicit transaction a session has open.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TransactionInfo {
+    pub txid: TxId,
+    /// When `BEGIN` ran.
+    pub started_at: Instant,
+}
+
+impl TransactionInfo {
+    pub fn age(&self) -> std::time::Duration {
+        self.started_at.elapsed()
+    }
+}
+
 #[derive(Clone)]
 struct Savepoint {
     name: String,
     tx: Transaction,
     catalog_root: u64,
-    pager_page_count: u64,
-    pager_freelist_page_id: u64,
-    pager_freelist: FreeList,
+    alloc: PagerAllocState,
 }
 
 impl Session {
@@ -174,11 +410,14 @@ impl Session {
             );
         }
 
-        Session {
+        let status = EngineStatus::register(pager.path());
+        let mut session = Session {
             pager,
             catalog,
             wal,
             active_tx: None,
+            tx_alloc_state: None,
+            tx_started_at: Instant::now(),
             savepoints: Vec::new(),
             next_txid,
             stats,
@@ -188,11 +427,37 @@ impl Session {
             last_checkpoint_at: std::time::Instant::now(),
             statement_timeout_ms: 0,
             cancel_state: Arc::new(QueryCancelState::default()),
+            cancel_token: None,
+            scan_corruption_policy: ScanCorruptionPolicy::default(),
+            predicate_reorder: true,
+            aggregation_memory_budget: DEFAULT_AGGREGATION_MEMORY_BUDGET,
+            in_list_seek_max_items: DEFAULT_IN_LIST_SEEK_MAX_ITEMS,
+            fts_vacuum_batch: DEFAULT_FTS_VACUUM_BATCH,
+            index_build_batch_rows: DEFAULT_INDEX_BUILD_BATCH_ROWS,
+            read_only: false,
+            abort_on_statement_error: false,
+            plan_cache: None,
+            plan_baselines_enabled: true,
+            plan_baselines: PlanBaselines::default(),
+            auto_increment: AutoIncrementState::default(),
+            table_counters: TableCounters::default(),
+            commit_tag: None,
+            commit_outcomes: CommitOutcomeLog::default(),
+            warnings: Vec::new(),
+            last_statement_metrics: StatementMetrics::default(),
+            statement_pages_dirtied: 0,
+            lock_manager: None,
+            registration: None,
+            busy_timeout_ms: 0,
+            temp_namespace: None,
+            status,
             #[cfg(test)]
             inject_checkpoint_failures_remaining: 0,
             #[cfg(test)]
             inject_wal_recreate_fail_once: false,
-        }
+        };
+        session.refresh_fts_gc_backlog();
+        session
     }
 
     /// Get a handle that can request cancellation of in-flight statements.
@@ -216,6 +481,98 @@ impl Session {
         self.statement_timeout_ms
     }
 
+    /// Run every later statement under `lock_manager`, as `Database` does.
+    pub(crate) fn attach_lock_manager(
+        &mut self,
+        lock_manager: LockManager,
+        registration: HandleRegistration,
+    ) {
+        self.track_lock(&lock_manager);
+        self.lock_manager = Some(Arc::new(lock_manager));
+        self.registration = Some(registration);
+    }
+
+    /// Configure lock wait timeout in milliseconds. `Database` and
+    /// `DatabaseReader` read it from their session, and a session from
+    /// `Database::into_session` keeps it. Also set by `SET busy_timeout`.
+    ///
+    /// `0` means wait indefinitely (default).
+    pub fn set_busy_timeout_ms(&mut self, timeout_ms: u64) {
+        self.busy_timeout_ms = timeout_ms;
+    }
+
+    /// Current lock wait timeout in milliseconds.
+    ///
+    /// `0` means wait indefinitely.
+    pub fn busy_timeout_ms(&self) -> u64 {
+        self.busy_timeout_ms
+    }
+
+    fn lock_timeout(&self) -> Option<Duration> {
+        (self.busy_timeout_ms > 0).then(|| Duration::from_millis(self.busy_timeout_ms))
+    }
+
+    /// Take the exclusive lock of `lock_manager`, if the session has one.
+    fn write_lock<'a>(
+        &self,
+        lock_manager: Option<&'a LockManager>,
+    ) -> Result<Option<WriteGuard<'a>>> {
+        lock_manager
+            .map(|m| m.write_lock_with_timeout(self.lock_timeout()))
+            .transpose()
+    }
+
+    /// Take the shared lock of `lock_manager`, if the session has one.
+    fn read_lock<'a>(
+        &self,
+        lock_manager: Option<&'a LockManager>,
+    ) -> Result<Option<ReadGuard<'a>>> {
+        lock_manager
+            .map(|m| m.read_lock_with_timeout(self.lock_timeout()))
+            .transpose()
+    }
+
+    /// Enable or disable cost-based ordering of WHERE conjuncts.
+    ///
+    /// Same as `SET predicate_reorder = 'on' | 'off'`. Disabling evaluates
+    /// conjuncts in written order, which is mainly useful for debugging.
+    pub fn set_predicate_reorder(&mut self, enabled: bool) {
+        self.predicate_reorder = enabled;
+    }
+
+    /// Whether WHERE conjuncts are reordered by estimated cost.
+    pub fn predicate_reorder(&self) -> bool {
+        self.predicate_reorder
+    }
+
+    /// Bytes of group state GROUP BY may keep in memory before it spills
+    /// rows of further groups to encrypted temp files.
+    ///
+    /// Same as `SET aggregation_memory_budget = <bytes>`. Results do not
+    /// depend on the budget; only memory use and temp-file I/O do.
+    pub fn set_aggregation_memory_budget(&mut self, bytes: usize) {
+        self.aggregation_memory_budget = bytes;
+    }
+
+    /// Memory budget of GROUP BY, in bytes.
+    pub fn aggregation_memory_budget(&self) -> usize {
+        self.aggregation_memory_budget
+    }
+
+    /// Longest `column IN (...)` list planned as one primary key or index
+    /// seek per value; longer lists scan the table and test each row against
+    /// a hash set of the list. `0` never seeks.
+    ///
+    /// Same as `SET in_list_seek_max_items = <n>`.
+    pub fn set_in_list_seek_max_items(&mut self, items: usize) {
+        self.in_list_seek_max_items = items;
+    }
+
+    /// Longest `IN` list planned as one seek per value.
+    pub fn in_list_seek_max_items(&self) -> usize {
+        self.in_list_seek_max_items
+    }
+
     fn check_poisoned(&self) -> Result<()> {
         if let Some(ref msg) = self.poisoned {
             return Err(MuroError::SessionPoisoned(msg.clone()));
@@ -230,12 +587,18 @@ impl Session {
 
     /// Execute a SQL string, handling BEGIN/COMMIT/ROLLBACK at the session level.
     pub fn execute(&mut self, sql: &str) -> Result<ExecResult> {
-        let stmt = parse_sql(sql).map_err(MuroError::Parse)?;
-        if contains_bind_params(&stmt) {
-            return Err(MuroError::Execution(
-                "SQL contains bind parameters ('?'); use prepare()/execute_prepared()".into(),
-            ));
-        }
+        let _status = self.status.enter(sql);
+        let parsed = parse_statement(sql).and_then(|stmt| {
+            if contains_bind_params(&stmt) {
+                return Err(MuroError::Execution(
+                    "SQL contains bind parameters ('?'); use prepare()/execute_prepared()".into(),
+                ));
+            }
+            Ok(stmt)
+        });
+        let stmt = self.abort_tx_on_error(parsed)?;
+        let lock_manager = self.lock_manager.clone();
+        let _guard = self.write_lock(lock_manager.as_deref())?;
         self.execute_statement_with_session(&stmt)
     }
 
@@ -245,12 +608,179 @@ impl Session {
         prepared: &PreparedStatement,
         params: &[Value],
     ) -> Result<ExecResult> {
-        let stmt = prepared.bind(params)?;
+        let _status = self.status.enter(prepared.sql());
+        let stmt = self.abort_tx_on_error(prepared.bind(params))?;
+        let lock_manager = self.lock_manager.clone();
+        let _guard = self.write_lock(lock_manager.as_deref())?;
         self.execute_statement_with_session(&stmt)
     }
 
+    /// Execute a SQL string like [`Self::execute`], failing with
+    /// [`MuroError::Cancelled`] at the next cancellation point once `token`
+    /// is cancelled. The statement is then rolled back like any failed one.
+    pub fn execute_with_cancel(
+        &mut self,
+        sql: &str,
+        token: &CancellationToken,
+    ) -> Result<ExecResult> {
+        let previous = self.set_cancel_token(Some(token.clone()));
+        let result = self.execute(sql);
+        self.set_cancel_token(previous);
+        result
+    }
+
+    /// [`Self::execute_read_only_query`] with a [`CancellationToken`].
+    pub fn execute_read_only_query_with_cancel(
+        &mut self,
+        sql: &str,
+        token: &CancellationToken,
+    ) -> Result<Vec<Row>> {
+        let previous = self.set_cancel_token(Some(token.clone()));
+        let result = self.execute_read_only_query(sql);
+        self.set_cancel_token(previous);
+        result
+    }
+
+    /// Token checked by the statements run until it is replaced. Returns
+    /// the previous one.
+    pub(crate) fn set_cancel_token(
+        &mut self,
+        token: Option<CancellationToken>,
+    ) -> Option<CancellationToken> {
+        std::mem::replace(&mut self.cancel_token, token)
+    }
+
+    /// Insert `rows` into `table` as one INSERT statement, without SQL text.
+    /// Each row holds values for the visible columns in table order, as in
+    /// `INSERT INTO t VALUES (...)`. Into an empty table the rows are bulk
+    /// loaded; otherwise they are inserted one by one.
+    pub fn bulk_insert(&mut self, table: &str, rows: &[Vec<Value>]) -> Result<u64> {
+        let _status = self.status.enter(&format!(
+            "INSERT INTO {} ({} rows via bulk_insert)",
+            table,
+            rows.len()
+        ));
+        let stmt = Statement::Insert(Insert {
+            table_name: table.to_string(),
+            columns: None,
+            values: rows
+                .iter()
+                .map(|row| row.iter().map(value_to_expr).collect())
+                .collect(),
+            on_duplicate_key_update: None,
+            is_replace: false,
+        });
+        let lock_manager = self.lock_manager.clone();
+        let _guard = self.write_lock(lock_manager.as_deref())?;
+        match self.execute_statement_with_session(&stmt)? {
+            ExecResult::RowsAffected(n) => Ok(n),
+            other => Err(MuroError::Execution(format!(
+                "unexpected INSERT result: {:?}",
+                other
+            ))),
+        }
+    }
+
+    /// Execute a script of semicolon-separated statements in order and return
+    /// one result per statement.
+    ///
+    /// Without transaction control statements in the script, and outside an
+    /// explicit transaction, the statements run in one implicit transaction:
+    /// all of them commit or none do. A script with its own `BEGIN`/`COMMIT`
+    /// runs as written; if a statement fails while a transaction the script
+    /// opened is still active, that transaction is rolled back. Failures are
+    /// reported as [`MuroError::Script`] with the statement index and offset.
+    pub fn execute_batch(&mut self, sql: &str) -> Result<Vec<ExecResult>> {
+        let _status = self.status.enter(sql);
+        let statements = parse_script_with_offsets(sql).map_err(|e| MuroError::Script {
+            index: e.index,
+            offset: e.offset,
+            source: Box::new(e.statement_error()),
+        });
+        let statements = self.abort_tx_on_error(statements)?;
+        for (index, s) in statements.iter().enumerate() {
+            if contains_bind_params(&s.statement) {
+                self.abort_tx_after_statement_error();
+                return Err(MuroError::Script {
+                    index,
+                    offset: s.offset,
+                    source: Box::new(MuroError::Execution(
+                        "SQL contains bind parameters ('?'); use prepare()/execute_prepared()"
+                            .into(),
+                    )),
+                });
+            }
+        }
+        let lock_manager = self.lock_manager.clone();
+        let _guard = self.write_lock(lock_manager.as_deref())?;
+
+        let was_in_tx = self.active_tx.is_some();
+        let implicit_tx = !was_in_tx
+            && !statements
+                .iter()
+                .any(|s| Self::is_transaction_control(&s.statement));
+        if implicit_tx {
+            self.execute_statement_with_session(&Statement::Begin)?;
+        }
+        let mut results = Vec::with_capacity(statements.len());
+        for (index, s) in statements.iter().enumerate() {
+            match self.execute_statement_with_session(&s.statement) {
+                Ok(result) => results.push(result),
+                Err(e) => {
+                    if !was_in_tx && self.active_tx.is_some() {
+                        let _ = self.execute_statement_with_session(&Statement::Rollback);
+                    }
+                    return Err(MuroError::Script {
+                        index,
+                        offset: s.offset,
+                        source: Box::new(e),
+                    });
+                }
+            }
+        }
+        if implicit_tx {
+            self.execute_statement_with_session(&Statement::Commit)?;
+        }
+        Ok(results)
+    }
+
+    /// Statements that keep a script out of an implicit transaction.
+    /// `OPTIMIZE` counts: it commits transactions of its own.
+    fn is_transaction_control(stmt: &Statement) -> bool {
+        matches!(
+            stmt,
+            Statement::Begin
+                | Statement::Commit
+                | Statement::Rollback
+                | Statement::Savepoint(_)
+                | Statement::RollbackToSavepoint(_)
+                | Statement::ReleaseSavepoint(_)
+                | Statement::Optimize
+        )
+    }
+
     fn execute_statement_with_session(&mut self, stmt: &Statement) -> Result<ExecResult> {
         let _statement_guard = self.enter_statement();
+        self.begin_statement_plan_cache();
+        self.begin_statement_plan_baselines();
+        self.begin_statement_auto_increment();
+        let metrics_start = self.begin_statement_metrics();
+        self.status.set_phase(StatementPhase::Executing);
+        let result = self.dispatch_statement(stmt);
+        if result.is_err() {
+            self.abort_tx_after_statement_error();
+        }
+        self.finish_statement_metrics(metrics_start);
+        self.finish_statement_auto_increment();
+        self.finish_statement_plan_baselines();
+        self.finish_statement_plan_cache();
+        self.finish_statement_warnings(stmt);
+        self.status
+            .set_txid(self.active_tx.as_ref().map(|tx| tx.txid()));
+        result
+    }
+
+    fn dispatch_statement(&mut self, stmt: &Statement) -> Result<ExecResult> {
         self.cancellation_point()?;
 
         // Stats queries are always allowed, even on poisoned sessions,
@@ -258,23 +788,48 @@ impl Session {
         match stmt {
             Statement::ShowCheckpointStats => return self.handle_show_checkpoint_stats(),
             Statement::ShowDatabaseStats => return self.handle_show_database_stats(),
+            Statement::ShowWarnings => return self.handle_show_warnings(),
+            Statement::ShowProcesslist => return self.handle_show_processlist(),
+            Statement::ShowVariables(pattern) => {
+                return self.handle_show_variables(pattern.as_deref())
+            }
             _ => {}
         }
 
         self.check_poisoned()?;
+        self.reject_in_aborted_tx(stmt)?;
+        self.flush_expired_commit_batch()?;
         self.refresh_from_disk_if_needed()?;
 
         match stmt {
             Statement::Begin => self.handle_begin(),
             Statement::Commit => self.handle_commit(),
             Statement::Rollback => self.handle_rollback(),
-            Statement::SetRuntimeOption(set_stmt) => self.handle_set_runtime_option(set_stmt),
+            Statement::SetVariable(set_stmt) => self.handle_set_variable(set_stmt),
             Statement::Savepoint(name) => self.handle_savepoint(name),
             Statement::RollbackToSavepoint(name) => self.handle_rollback_to_savepoint(name),
             Statement::ReleaseSavepoint(name) => self.handle_release_savepoint(name),
+            Statement::Optimize => {
+                self.reject_write_when_read_only(stmt)?;
+                self.handle_optimize()
+            }
+            Statement::ExplainAnalyze(inner) if !Self::is_read_only_statement(inner) => {
+                self.reject_write_when_read_only(stmt)?;
+                self.reject_write_in_skip_mode(stmt)?;
+                self.execute_discarding_writes(stmt)
+            }
             _ => {
+                self.reject_write_when_read_only(stmt)?;
+                self.reject_write_in_skip_mode(stmt)?;
+                if let Statement::CreateTable(ct) = stmt {
+                    if ct.temporary {
+                        self.ensure_temp_namespace()?;
+                    }
+                }
                 if self.active_tx.is_some() {
                     self.execute_in_tx(stmt)
+                } else if let Some(ci) = self.batched_create_index(stmt)? {
+                    self.execute_create_index_batched(ci)
                 } else {
                     // Auto-commit: wrap in an implicit transaction with WAL
                     self.execute_auto_commit(stmt)
@@ -283,16 +838,44 @@ impl Session {
         }
     }
 
+    /// The auto-commit `CREATE INDEX`, or `ALTER TABLE` whose only operation
+    /// is `ADD INDEX`, to build in batches. An index on a temporary table is
+    /// built in one statement: batch progress is kept under permanent names,
+    /// which a later open could not resolve.
+    fn batched_create_index<'a>(&mut self, stmt: &'a Statement) -> Result<Option<&'a CreateIndex>> {
+        let ci = match stmt {
+            Statement::CreateIndex(ci) => ci,
+            Statement::AlterTable(at) => match at.operations.as_slice() {
+                [AlterTableOp::AddIndex(ci)] => ci,
+                _ => return Ok(None),
+            },
+            _ => return Ok(None),
+        };
+        if self
+            .catalog
+            .is_temp_table(&mut self.pager, &ci.table_name)?
+        {
+            return Ok(None);
+        }
+        Ok(Some(ci))
+    }
+
     /// Execute a read-only SQL query and return rows.
     ///
     /// This path avoids auto-commit WAL writes for non-transactional reads.
     pub fn execute_read_only_query(&mut self, sql: &str) -> Result<Vec<Row>> {
-        let stmt = parse_sql(sql).map_err(MuroError::Parse)?;
-        if contains_bind_params(&stmt) {
-            return Err(MuroError::Execution(
-                "SQL contains bind parameters ('?'); use prepare()/query_prepared()".into(),
-            ));
-        }
+        let _status = self.status.enter(sql);
+        let parsed = parse_statement(sql).and_then(|stmt| {
+            if contains_bind_params(&stmt) {
+                return Err(MuroError::Execution(
+                    "SQL contains bind parameters ('?'); use prepare()/query_prepared()".into(),
+                ));
+            }
+            Ok(stmt)
+        });
+        let stmt = self.abort_tx_on_error(parsed)?;
+        let lock_manager = self.lock_manager.clone();
+        let _guard = self.read_lock(lock_manager.as_deref())?;
         self.execute_read_only_query_statement(&stmt)
     }
 
@@ -302,12 +885,35 @@ impl Session {
         prepared: &PreparedStatement,
         params: &[Value],
     ) -> Result<Vec<Row>> {
-        let stmt = prepared.bind(params)?;
+        let _status = self.status.enter(prepared.sql());
+        let stmt = self.abort_tx_on_error(prepared.bind(params))?;
+        let lock_manager = self.lock_manager.clone();
+        let _guard = self.read_lock(lock_manager.as_deref())?;
         self.execute_read_only_query_statement(&stmt)
     }
 
     fn execute_read_only_query_statement(&mut self, stmt: &Statement) -> Result<Vec<Row>> {
         let _statement_guard = self.enter_statement();
+        self.begin_statement_plan_cache();
+        self.begin_statement_plan_baselines();
+        self.begin_statement_auto_increment();
+        let metrics_start = self.begin_statement_metrics();
+        self.status.set_phase(StatementPhase::Executing);
+        let result = self.dispatch_read_only_query(stmt);
+        if result.is_err() {
+            self.abort_tx_after_statement_error();
+        }
+        self.finish_statement_metrics(metrics_start);
+        self.finish_statement_auto_increment();
+        self.finish_statement_plan_baselines();
+        self.finish_statement_plan_cache();
+        self.finish_statement_warnings(stmt);
+        self.status
+            .set_txid(self.active_tx.as_ref().map(|tx| tx.txid()));
+        result
+    }
+
+    fn dispatch_read_only_query(&mut self, stmt: &Statement) -> Result<Vec<Row>> {
         self.cancellation_point()?;
 
         // Stats queries are always allowed, even on poisoned sessions.
@@ -318,10 +924,21 @@ impl Session {
             Statement::ShowDatabaseStats => {
                 return Self::rows_from_exec_result(self.handle_show_database_stats())
             }
+            Statement::ShowWarnings => {
+                return Self::rows_from_exec_result(self.handle_show_warnings())
+            }
+            Statement::ShowProcesslist => {
+                return Self::rows_from_exec_result(self.handle_show_processlist())
+            }
+            Statement::ShowVariables(pattern) => {
+                return Self::rows_from_exec_result(self.handle_show_variables(pattern.as_deref()))
+            }
             _ => {}
         }
 
         self.check_poisoned()?;
+        self.reject_in_aborted_tx(stmt)?;
+        self.flush_expired_commit_batch()?;
         self.refresh_from_disk_if_needed()?;
 
         if !Self::is_read_only_statement(stmt) {
@@ -330,11 +947,16 @@ impl Session {
             ));
         }
 
+        // Inside a transaction, reads (SHOW and DESCRIBE included) go through
+        // its page store and catalog so they see its uncommitted writes.
         if self.active_tx.is_some() {
             Self::rows_from_exec_result(self.execute_in_tx(stmt))
         } else {
             // Read directly from pager/catalog without opening an implicit WAL transaction.
-            Self::rows_from_exec_result(execute_statement(stmt, &mut self.pager, &mut self.catalog))
+            let written_before = self.pager.pages_written();
+            let result = execute_statement(stmt, &mut self.pager, &mut self.catalog);
+            self.statement_pages_dirtied += self.pager.pages_written() - written_before;
+            Self::rows_from_exec_result(result)
         }
     }
 
@@ -362,6 +984,20 @@ impl Session {
                     })
             };
         });
+        ACTIVE_CANCEL_TOKEN.with(|slot| {
+            *slot.borrow_mut() = self.cancel_token.clone();
+        });
+        ACTIVE_SCAN_WARNINGS.with(|slot| {
+            *slot.borrow_mut() =
+                (self.scan_corruption_policy == ScanCorruptionPolicy::Skip).then(Vec::new);
+        });
+        ACTIVE_QUERY_WARNINGS.with(|slot| {
+            *slot.borrow_mut() = Some(Vec::new());
+        });
+        ACTIVE_PREDICATE_REORDER.with(|slot| slot.set(self.predicate_reorder));
+        ACTIVE_AGGREGATION_MEMORY_BUDGET.with(|slot| slot.set(self.aggregation_memory_budget));
+        ACTIVE_IN_LIST_SEEK_MAX_ITEMS.with(|slot| slot.set(self.in_list_seek_max_items));
+        ACTIVE_FTS_VACUUM_BATCH.with(|slot| slot.set(self.fts_vacuum_batch));
         StatementExecutionGuard {
             state: Arc::clone(&self.cancel_state),
             statement_id,
@@ -372,6 +1008,9 @@ impl Session {
         if let Some(err) = statement_timeout_error_current() {
             return Err(err);
         }
+        if self.cancel_token.as_ref().is_some_and(|t| t.is_cancelled()) {
+            return Err(MuroError::Cancelled);
+        }
         let active_id = self.cancel_state.active_statement_id.load(Ordering::SeqCst);
         if active_id != 0
             && self
@@ -400,10 +1039,17 @@ impl Session {
             | Statement::SetQuery(_)
             | Statement::ShowTables
             | Statement::ShowCreateTable(_)
+            | Statement::ShowIndexes(_)
             | Statement::Describe(_)
             | Statement::ShowCheckpointStats
-            | Statement::ShowDatabaseStats => true,
-            Statement::Explain(inner) => Self::is_read_only_statement(inner),
+            | Statement::ShowDatabaseStats
+            | Statement::ShowWarnings
+            | Statement::ShowProcesslist
+            | Statement::ShowVariables(_)
+            | Statement::ShowTableStatus
+            | Statement::CheckTable(_)
+            | Statement::Explain(_) => true,
+            Statement::ExplainAnalyze(inner) => Self::is_read_only_statement(inner),
             Statement::CreateTable(_)
             | Statement::CreateIndex(_)
             | Statement::CreateFulltextIndex(_)
@@ -415,22 +1061,34 @@ impl Session {
             | Statement::Update(_)
             | Statement::Delete(_)
             | Statement::AnalyzeTable(_)
+            | Statement::OptimizeTable(_)
+            | Statement::Optimize
             | Statement::Begin
             | Statement::Commit
             | Statement::Rollback
             | Statement::Savepoint(_)
             | Statement::RollbackToSavepoint(_)
             | Statement::ReleaseSavepoint(_)
-            | Statement::SetRuntimeOption(_) => false,
+            | Statement::SetVariable(_)
+            | Statement::InstallPlanBaseline(_)
+            | Statement::RemovePlanBaseline(_) => false,
         }
     }
 
+    /// Reload the catalog from `catalog_root`, keeping this session's
+    /// temporary namespace.
+    fn reopen_catalog(&mut self, catalog_root: PageId) {
+        self.catalog = SystemCatalog::open(catalog_root);
+        self.catalog.set_temp_owner(self.temp_owner());
+    }
+
     fn refresh_from_disk_if_needed(&mut self) -> Result<()> {
-        if self.active_tx.is_some() {
+        // Deferred commits put this handle ahead of the file on purpose.
+        if self.active_tx.is_some() || self.wal.has_unsynced_commits() {
             return Ok(());
         }
         if self.pager.refresh_from_disk_if_changed()? {
-            self.catalog = SystemCatalog::open(self.pager.catalog_root());
+            self.reopen_catalog(self.pager.catalog_root());
             self.next_txid = self.next_txid.max(self.pager.next_txid());
         }
         Ok(())
@@ -444,27 +1102,89 @@ impl Session {
         self.next_txid += 1;
         let snapshot_lsn = self.wal.current_lsn();
         self.active_tx = Some(Transaction::begin(txid, snapshot_lsn));
+        self.tx_alloc_state = Some(PagerAllocState::capture(&mut self.pager));
+        self.tx_started_at = Instant::now();
         self.savepoints.clear();
+        self.clear_tx_writes();
         Ok(ExecResult::Ok)
     }
 
+    /// Under `abort_on_statement_error`, mark the explicit transaction a
+    /// statement just failed in as aborted.
+    fn abort_tx_after_statement_error(&mut self) {
+        if !self.abort_on_statement_error {
+            return;
+        }
+        if let Some(tx) = &mut self.active_tx {
+            tx.mark_aborted();
+        }
+    }
+
+    /// [`Self::abort_tx_after_statement_error`] for a statement that failed
+    /// to parse or bind, before it could run.
+    fn abort_tx_on_error<T>(&mut self, result: Result<T>) -> Result<T> {
+        if result.is_err() {
+            self.abort_tx_after_statement_error();
+        }
+        result
+    }
+
+    /// In an aborted transaction only ROLLBACK, COMMIT, which rolls it back,
+    /// and ROLLBACK TO SAVEPOINT, which returns it to a savepoint set before
+    /// the failure, may run.
+    fn reject_in_aborted_tx(&self, stmt: &Statement) -> Result<()> {
+        if self.in_aborted_tx()
+            && !matches!(
+                stmt,
+                Statement::Rollback | Statement::Commit | Statement::RollbackToSavepoint(_)
+            )
+        {
+            return Err(MuroError::Transaction(
+                "current transaction is aborted".into(),
+            ));
+        }
+        Ok(())
+    }
+
+    /// Whether the explicit transaction was aborted by a failed statement.
+    pub fn in_aborted_tx(&self) -> bool {
+        self.active_tx
+            .as_ref()
+            .is_some_and(|tx| tx.state() == TxState::Aborted)
+    }
+
     fn handle_commit(&mut self) -> Result<ExecResult> {
+        if self.in_aborted_tx() {
+            self.handle_rollback()?;
+            return Err(MuroError::Transaction(
+                "current transaction is aborted; COMMIT rolled it back".into(),
+            ));
+        }
         let mut tx = self
             .active_tx
             .take()
             .ok_or_else(|| MuroError::Transaction("No active transaction".into()))?;
+        let alloc_state = self.tx_alloc_state.take();
         let catalog_root = self.catalog.root_page_id();
+        let commit_tag = self.commit_tag.take();
         self.pager.set_next_txid(self.next_txid);
+        self.status.set_phase(StatementPhase::Committing);
         match tx.commit(&mut self.pager, &mut self.wal, catalog_root) {
             Err(e @ MuroError::CommitInDoubt(_)) => {
                 self.record_commit_in_doubt(&e);
+                self.note_commit_in_doubt(tx.txid(), commit_tag.as_deref());
                 self.poisoned = Some(e.to_string());
                 return Err(e);
             }
-            Err(e) => return Err(e),
+            Err(e) => {
+                self.savepoints.clear();
+                self.abort_allocations(alloc_state);
+                return Err(e);
+            }
             Ok(_) => {}
         }
         self.savepoints.clear();
+        self.commit_tx_writes();
         self.post_commit_checkpoint();
         Ok(ExecResult::Ok)
     }
@@ -476,13 +1196,23 @@ impl Session {
             .ok_or_else(|| MuroError::Transaction("No active transaction".into()))?;
         tx.rollback_no_wal();
         self.savepoints.clear();
+        self.clear_tx_writes();
+        let alloc_state = self.tx_alloc_state.take();
+        self.abort_allocations(alloc_state);
         self.post_rollback_checkpoint();
-        // Reload catalog from disk since in-memory catalog may have been modified
-        let catalog_root = self.pager.catalog_root();
-        self.catalog = SystemCatalog::open(catalog_root);
         Ok(ExecResult::Ok)
     }
 
+    /// Return pages allocated by an aborted transaction and reload the catalog
+    /// from the last committed root, discarding uncommitted DDL.
+    fn abort_allocations(&mut self, alloc_state: Option<PagerAllocState>) {
+        if let Some(state) = alloc_state {
+            state.restore(&mut self.pager);
+        }
+        let catalog_root = self.pager.catalog_root();
+        self.reopen_catalog(catalog_root);
+    }
+
     fn handle_savepoint(&mut self, name: &str) -> Result<ExecResult> {
         let tx = self
             .active_tx
@@ -490,17 +1220,13 @@ impl Session {
             .ok_or_else(|| MuroError::Transaction("No active transaction".into()))?
             .clone();
         let catalog_root = self.catalog.root_page_id();
-        let pager_page_count = self.pager.page_count();
-        let pager_freelist_page_id = self.pager.freelist_page_id();
-        let pager_freelist = self.pager.freelist_mut().clone();
+        let alloc = PagerAllocState::capture(&mut self.pager);
         self.savepoints.retain(|sp| sp.name != name);
         self.savepoints.push(Savepoint {
             name: name.to_string(),
             tx,
             catalog_root,
-            pager_page_count,
-            pager_freelist_page_id,
-            pager_freelist,
+            alloc,
         });
         Ok(ExecResult::Ok)
     }
@@ -516,12 +1242,11 @@ impl Session {
             .position(|sp| sp.name == name)
             .ok_or_else(|| MuroError::Transaction(format!("Unknown savepoint: {}", name)))?;
         let snapshot = self.savepoints[idx].clone();
+        // Savepoints are only set while the transaction is active, so this
+        // also lifts an abort.
         *tx = snapshot.tx;
-        self.catalog = SystemCatalog::open(snapshot.catalog_root);
-        self.pager.set_page_count(snapshot.pager_page_count);
-        self.pager
-            .set_freelist_page_id(snapshot.pager_freelist_page_id);
-        *self.pager.freelist_mut() = snapshot.pager_freelist;
+        self.reopen_catalog(snapshot.catalog_root);
+        snapshot.alloc.restore(&mut self.pager);
         // Savepoints created after the target are discarded.
         self.savepoints.truncate(idx + 1);
         Ok(ExecResult::Ok)
@@ -545,7 +1270,7 @@ impl Session {
     /// Re-encrypt all pages with a new password-derived key.
     ///
     /// Must not be called inside an active transaction.
-    pub fn rekey_with_password(&mut self, new_password: &str) -> Result<()> {
+    pub fn rekey_with_password(&mut self, new_password: impl AsRef<[u8]>) -> Result<()> {
         self.check_poisoned()?;
         self.refresh_from_disk_if_needed()?;
 
@@ -571,7 +1296,7 @@ impl Session {
 
         // Generate new salt and derive new key
         let new_salt = kdf::generate_salt();
-        let new_key = kdf::derive_key(new_password.as_bytes(), &new_salt)?;
+        let new_key = kdf::derive_key(new_password.as_ref(), &new_salt)?;
 
         // Re-encrypt all pages
         self.pager.rekey(&new_key, new_salt)?;
@@ -592,7 +1317,12 @@ impl Session {
             return Err(MuroError::SessionPoisoned(msg));
         }
 
-        self.wal = match WalWriter::create(&wal_path, &new_key) {
+        self.wal = match WalWriter::create_for_instance(
+            &wal_path,
+            EncryptionSuite::Aes256GcmSiv,
+            Some(&new_key),
+            new_salt,
+        ) {
             Ok(wal) => wal,
             Err(e) => {
                 let msg = format!(
@@ -609,66 +1339,139 @@ impl Session {
 
     /// Execute a statement in auto-commit mode: wrap in an implicit transaction.
     fn execute_auto_commit(&mut self, stmt: &Statement) -> Result<ExecResult> {
+        // Read-only statements leave the tag for the next write.
+        let tagged = !Self::is_read_only_statement(stmt);
+        let result = self.run_auto_commit(
+            |_| tagged,
+            |store, catalog| execute_statement(stmt, store, catalog),
+        )?;
+        self.commit_statement_writes(stmt, &result);
+        Ok(result)
+    }
+
+    /// Run `f` in an implicit transaction and commit it through the WAL, or
+    /// roll it back if `f` fails. The pending commit tag goes to the commit
+    /// when `tagged` says so for `f`'s result.
+    fn run_auto_commit<T>(
+        &mut self,
+        tagged: impl FnOnce(&T) -> bool,
+        f: impl FnOnce(&mut TxPageStore, &mut SystemCatalog) -> Result<T>,
+    ) -> Result<T> {
         let txid = self.next_txid;
         self.next_txid += 1;
         let snapshot_lsn = self.wal.current_lsn();
         let tx = Transaction::begin(txid, snapshot_lsn);
+        self.status.set_txid(Some(txid));
 
-        // Save catalog state for rollback on error
+        // Save catalog and allocation state for rollback on error
         let catalog_root_before = self.catalog.root_page_id();
+        let alloc_before = PagerAllocState::capture(&mut self.pager);
 
         let mut store = TxPageStore::new(tx, &mut self.pager);
-        let result = execute_statement(stmt, &mut store, &mut self.catalog);
+        let result = f(&mut store, &mut self.catalog);
+        self.statement_pages_dirtied += store.pages_dirtied();
         let mut tx = store.into_tx();
 
         match result {
-            Ok(exec_result) => {
+            Ok(value) => {
                 // Commit via WAL (catalog_root included in WAL MetaUpdate)
                 let catalog_root = self.catalog.root_page_id();
+                let commit_tag = if tagged(&value) {
+                    self.commit_tag.take()
+                } else {
+                    None
+                };
                 self.pager.set_next_txid(self.next_txid);
+                self.status.set_phase(StatementPhase::Committing);
                 match tx.commit(&mut self.pager, &mut self.wal, catalog_root) {
                     Err(e @ MuroError::CommitInDoubt(_)) => {
                         self.record_commit_in_doubt(&e);
+                        self.note_commit_in_doubt(txid, commit_tag.as_deref());
                         self.poisoned = Some(e.to_string());
                         return Err(e);
                     }
-                    Err(e) => return Err(e),
+                    Err(e) => {
+                        alloc_before.restore(&mut self.pager);
+                        self.reopen_catalog(catalog_root_before);
+                        return Err(e);
+                    }
                     Ok(_) => {}
                 }
                 self.post_commit_checkpoint();
-                Ok(exec_result)
+                Ok(value)
             }
             Err(e) => {
-                // Rollback: discard dirty pages, restore catalog
+                // Rollback: discard dirty pages, return allocations, restore catalog
                 tx.rollback_no_wal();
-                self.catalog = SystemCatalog::open(catalog_root_before);
+                alloc_before.restore(&mut self.pager);
+                self.reopen_catalog(catalog_root_before);
                 Err(e)
             }
         }
     }
 
     /// Execute a statement within an active transaction.
+    ///
+    /// A failing statement is undone on its own: its page writes, frees, and
+    /// allocations are discarded and the catalog is restored, while the
+    /// transaction stays open with the earlier statements' changes.
     fn execute_in_tx(&mut self, stmt: &Statement) -> Result<ExecResult> {
-        // Save catalog state so we can restore on error
+        // Save catalog and allocation state so we can restore on error
         let catalog_root_before = self.catalog.root_page_id();
+        let alloc_before = if Self::is_read_only_statement(stmt) {
+            None
+        } else {
+            Some(PagerAllocState::capture(&mut self.pager))
+        };
 
         // Take the transaction out temporarily
-        let tx = self.active_tx.take().unwrap();
+        let mut tx = self.active_tx.take().unwrap();
+        tx.begin_statement();
         let mut store = TxPageStore::new(tx, &mut self.pager);
 
         let result = execute_statement(stmt, &mut store, &mut self.catalog);
+        self.statement_pages_dirtied += store.pages_dirtied();
 
         // Put the transaction back
-        self.active_tx = Some(store.into_tx());
-
+        let mut tx = store.into_tx();
         if result.is_err() {
-            // Restore catalog to pre-statement state on error
-            self.catalog = SystemCatalog::open(catalog_root_before);
+            tx.rollback_statement();
+            if let Some(state) = alloc_before {
+                state.restore(&mut self.pager);
+            }
+            self.reopen_catalog(catalog_root_before);
+        } else {
+            tx.end_statement();
+        }
+        self.active_tx = Some(tx);
+        if let Ok(r) = &result {
+            self.note_tx_writes(stmt, r);
         }
 
         result
     }
 
+    /// Execute a statement and throw its writes away (EXPLAIN ANALYZE of an
+    /// UPDATE or DELETE). It runs on a copy of the open transaction, or on a
+    /// fresh one in auto-commit mode, which is rolled back without WAL.
+    fn execute_discarding_writes(&mut self, stmt: &Statement) -> Result<ExecResult> {
+        let catalog_root_before = self.catalog.root_page_id();
+        let alloc_before = PagerAllocState::capture(&mut self.pager);
+        let tx = match &self.active_tx {
+            Some(tx) => tx.clone(),
+            None => Transaction::begin(self.next_txid, self.wal.current_lsn()),
+        };
+
+        let mut store = TxPageStore::new(tx, &mut self.pager);
+        let result = execute_statement(stmt, &mut store, &mut self.catalog);
+        self.statement_pages_dirtied += store.pages_dirtied();
+        store.into_tx().rollback_no_wal();
+
+        alloc_before.restore(&mut self.pager);
+        self.reopen_catalog(catalog_root_before);
+        result
+    }
+
     /// Get a reference to the pager (for flush/metadata operations).
     pub fn pager(&self) -> &Pager {
         &self.pager
@@ -685,16 +1488,69 @@ impl Session {
         &mut self.wal
     }
 
+    /// Route the writes and fsyncs of the database file and the WAL through
+    /// `fault`, or stop doing so with `None`.
+    #[cfg(any(test, feature = "test-utils"))]
+    pub fn set_fault_injector(&mut self, fault: Option<crate::fault::FaultInjector>) {
+        self.pager.set_fault_injector(fault.clone());
+        self.wal.set_fault_injector(fault);
+    }
+
     /// Get a reference to the catalog.
     pub fn catalog(&self) -> &SystemCatalog {
         &self.catalog
     }
+
+    /// Writes of the last statement run through this session.
+    pub fn last_statement_metrics(&self) -> StatementMetrics {
+        self.last_statement_metrics
+    }
+
+    /// Start counting the running statement's writes; returns the counters
+    /// to diff against.
+    fn begin_statement_metrics(&mut self) -> StatementMetricsStart {
+        self.statement_pages_dirtied = 0;
+        StatementMetricsStart {
+            wal_frames: self.wal.frames_appended(),
+            data_pages_flushed: self.pager.data_pages_flushed(),
+            data_write_runs: self.pager.data_write_runs(),
+        }
+    }
+
+    fn finish_statement_metrics(&mut self, start: StatementMetricsStart) {
+        let wal_frames_appended = self.wal.frames_appended().saturating_sub(start.wal_frames);
+        let pages_dirtied = self.statement_pages_dirtied;
+        self.last_statement_metrics = StatementMetrics {
+            performed_writes: wal_frames_appended > 0 || pages_dirtied > 0,
+            wal_frames_appended,
+            pages_dirtied,
+            data_pages_flushed: self
+                .pager
+                .data_pages_flushed()
+                .saturating_sub(start.data_pages_flushed),
+            contiguous_write_runs: self
+                .pager
+                .data_write_runs()
+                .saturating_sub(start.data_write_runs),
+        };
+    }
+
+    /// The explicit transaction this session has open, if any.
+    pub fn transaction_info(&self) -> Option<TransactionInfo> {
+        self.active_tx.as_ref().map(|tx| TransactionInfo {
+            txid: tx.txid(),
+            started_at: self.tx_started_at,
+        })
+    }
 }
 
 pub(crate) fn cancellation_point_current() -> Result<()> {
     if let Some(err) = statement_timeout_error_current() {
         return Err(err);
     }
+    if ACTIVE_CANCEL_TOKEN.with(|slot| slot.borrow().as_ref().is_some_and(|t| t.is_cancelled())) {
+        return Err(MuroError::Cancelled);
+    }
     ACTIVE_CANCEL_STATE.with(|slot| {
         let borrowed = slot.borrow();
         let Some(state) = borrowed.as_ref() else {
@@ -714,6 +1570,24 @@ pub(crate) fn cancellation_point_current() -> Result<()> {
     })
 }
 
+/// Whether the running statement may reorder WHERE conjuncts. Outside a
+/// session statement (direct executor calls) reordering is on.
+pub(crate) fn predicate_reorder_enabled_current() -> bool {
+    ACTIVE_PREDICATE_REORDER.with(Cell::get)
+}
+
+pub(crate) fn aggregation_memory_budget_current() -> usize {
+    ACTIVE_AGGREGATION_MEMORY_BUDGET.with(Cell::get)
+}
+
+pub(crate) fn in_list_seek_max_items_current() -> usize {
+    ACTIVE_IN_LIST_SEEK_MAX_ITEMS.with(Cell::get)
+}
+
+pub(crate) fn fts_vacuum_batch_current() -> usize {
+    ACTIVE_FTS_VACUUM_BATCH.with(Cell::get)
+}
+
 fn statement_timeout_error_current() -> Option<MuroError> {
     ACTIVE_STATEMENT_TIMEOUT.with(|slot| {
         let timeout = *slot.borrow();
@@ -729,5 +1603,14 @@ fn statement_timeout_error_current() -> Option<MuroError> {
     })
 }
 
+impl Drop for Session {
+    /// Best-effort: a clean close leaves no temporary tables behind and no
+    /// commits pending under relaxed WAL durability.
+    fn drop(&mut self) {
+        let _ = self.drop_temp_tables();
+        let _ = self.flush_commit_batch();
+    }
+}
+
 #[cfg(test)]
 mod tests;
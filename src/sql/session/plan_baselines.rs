@@ -0,0 +1,133 @@
+use super::*;
+use crate::schema::plan_baseline::PlanBaseline;
+use crate::sql::executor::capture_plan_baseline;
+use std::collections::HashMap;
+
+/// The catalog's plan baselines by shape, loaded once per catalog generation.
+#[derive(Default)]
+pub(crate) struct PlanBaselines {
+    /// Generation the baselines were loaded at; `None` before the first load.
+    generation: Option<u64>,
+    by_shape: HashMap<String, PlanBaseline>,
+    hits: u64,
+    fallbacks: u64,
+}
+
+impl Session {
+    /// Enable or disable plan baselines for this session.
+    ///
+    /// Same as `SET plan_baselines = 'on' | 'off'`. When disabled, statements
+    /// are planned as if no baseline were installed.
+    pub fn set_plan_baselines_enabled(&mut self, enabled: bool) {
+        self.plan_baselines_enabled = enabled;
+    }
+
+    /// Whether installed plan baselines are used (the default).
+    pub fn plan_baselines_enabled(&self) -> bool {
+        self.plan_baselines_enabled
+    }
+
+    /// Record the plan the planner currently picks for a single-table
+    /// `SELECT`, keyed by the statement's shape. Installed baselines are
+    /// ignored while capturing. Nothing is stored until
+    /// [`install_baseline`](Self::install_baseline).
+    pub fn capture_baseline(&mut self, sql: &str) -> Result<PlanBaseline> {
+        let stmt = parse_statement(sql)?;
+        let Statement::Select(sel) = stmt else {
+            return Err(MuroError::Execution(
+                "Plan baselines cover single-table SELECT statements only".into(),
+            ));
+        };
+        let _statement_guard = self.enter_statement();
+        self.check_poisoned()?;
+        self.flush_expired_commit_batch()?;
+        self.refresh_from_disk_if_needed()?;
+        match &self.active_tx {
+            Some(tx) => {
+                let mut store = TxPageStore::new(tx.clone(), &mut self.pager);
+                let result = capture_plan_baseline(&sel, &mut store, &mut self.catalog);
+                store.into_tx().rollback_no_wal();
+                result
+            }
+            None => capture_plan_baseline(&sel, &mut self.pager, &mut self.catalog),
+        }
+    }
+
+    /// Persist `baseline` in the catalog, replacing any baseline of the same
+    /// shape. Statements of that shape then use its plan while the indexes
+    /// it names exist and the table definition is unchanged.
+    pub fn install_baseline(&mut self, baseline: &PlanBaseline) -> Result<()> {
+        let stmt = Statement::InstallPlanBaseline(Box::new(baseline.clone()));
+        self.execute_statement_with_session(&stmt).map(|_| ())
+    }
+
+    /// Remove the baseline installed for `shape`. Returns whether there was one.
+    pub fn remove_baseline(&mut self, shape: &str) -> Result<bool> {
+        let stmt = Statement::RemovePlanBaseline(shape.to_string());
+        match self.execute_statement_with_session(&stmt)? {
+            ExecResult::RowsAffected(n) => Ok(n > 0),
+            other => Err(MuroError::Execution(format!(
+                "unexpected baseline removal result: {:?}",
+                other
+            ))),
+        }
+    }
+
+    /// Lend the loaded baselines to the running statement, when enabled.
+    pub(super) fn begin_statement_plan_baselines(&mut self) {
+        let baselines = self
+            .plan_baselines_enabled
+            .then(|| std::mem::take(&mut self.plan_baselines));
+        ACTIVE_PLAN_BASELINES.with(|slot| *slot.borrow_mut() = baselines);
+    }
+
+    /// Take the baselines back and fold their counters into the stats.
+    pub(super) fn finish_statement_plan_baselines(&mut self) {
+        let Some(mut baselines) = ACTIVE_PLAN_BASELINES.with(|slot| slot.borrow_mut().take())
+        else {
+            return;
+        };
+        self.stats.plan_baseline_hits += std::mem::take(&mut baselines.hits);
+        self.stats.plan_baseline_fallbacks += std::mem::take(&mut baselines.fallbacks);
+        self.plan_baselines = baselines;
+    }
+}
+
+/// The baseline installed for the running statement's shape, if baselines
+/// are enabled. The baselines are (re)loaded through `load` when the catalog
+/// `generation` moved since they were last loaded; `shape` is only computed
+/// when at least one baseline exists.
+pub(crate) fn plan_baseline_current(
+    generation: u64,
+    load: impl FnOnce() -> Result<Vec<PlanBaseline>>,
+    shape: impl FnOnce() -> String,
+) -> Result<Option<PlanBaseline>> {
+    ACTIVE_PLAN_BASELINES.with(|slot| {
+        let mut slot = slot.borrow_mut();
+        let Some(baselines) = slot.as_mut() else {
+            return Ok(None);
+        };
+        if baselines.generation != Some(generation) {
+            baselines.by_shape = load()?.into_iter().map(|b| (b.shape.clone(), b)).collect();
+            baselines.generation = Some(generation);
+        }
+        if baselines.by_shape.is_empty() {
+            return Ok(None);
+        }
+        Ok(baselines.by_shape.get(&shape()).cloned())
+    })
+}
+
+/// Count a statement that used its baseline (`hit`) or fell back to normal
+/// planning because the baseline no longer applies.
+pub(crate) fn record_plan_baseline_outcome_current(hit: bool) {
+    ACTIVE_PLAN_BASELINES.with(|slot| {
+        if let Some(baselines) = slot.borrow_mut().as_mut() {
+            if hit {
+                baselines.hits += 1;
+            } else {
+                baselines.fallbacks += 1;
+            }
+        }
+    });
+}
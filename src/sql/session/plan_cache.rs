@@ -0,0 +1,234 @@
+use super::*;
+use crate::schema::index::IndexDef;
+use crate::sql::ast::Select;
+use crate::sql::planner::Plan;
+use std::collections::{HashMap, VecDeque};
+
+/// Single-table SELECT plans keyed by their planner inputs, each tagged with
+/// the catalog generation it was built against.
+pub(crate) struct PlanCache {
+    capacity: usize,
+    entries: HashMap<String, CachedPlan>,
+    /// Insertion order, oldest first, for eviction.
+    order: VecDeque<String>,
+    hits: u64,
+    fallbacks: u64,
+}
+
+struct CachedPlan {
+    generation: u64,
+    plan: Plan,
+}
+
+impl PlanCache {
+    fn new(capacity: usize) -> Self {
+        PlanCache {
+            capacity,
+            entries: HashMap::new(),
+            order: VecDeque::new(),
+            hits: 0,
+            fallbacks: 0,
+        }
+    }
+
+    fn insert(&mut self, key: String, generation: u64, plan: Plan) {
+        if self.entries.contains_key(&key) {
+            self.order.retain(|k| k != &key);
+        }
+        while self.order.len() >= self.capacity {
+            match self.order.pop_front() {
+                Some(old) => {
+                    self.entries.remove(&old);
+                }
+                None => break,
+            }
+        }
+        self.order.push_back(key.clone());
+        self.entries.insert(key, CachedPlan { generation, plan });
+    }
+
+    fn remove(&mut self, key: &str) {
+        if self.entries.remove(key).is_some() {
+            self.order.retain(|k| k != key);
+        }
+    }
+}
+
+impl Session {
+    /// Cache up to `capacity` single-table SELECT plans across statements.
+    ///
+    /// `0` (the default) disables caching. Cached plans are reused until the
+    /// next DDL or `ANALYZE` changes the catalog generation, so they do not
+    /// follow row-count changes in between.
+    pub fn set_plan_cache_capacity(&mut self, capacity: usize) {
+        self.plan_cache = (capacity > 0).then(|| PlanCache::new(capacity));
+    }
+
+    /// Configured plan cache capacity; `0` when disabled.
+    pub fn plan_cache_capacity(&self) -> usize {
+        self.plan_cache.as_ref().map_or(0, |c| c.capacity)
+    }
+
+    /// Lend the plan cache to the running statement.
+    pub(super) fn begin_statement_plan_cache(&mut self) {
+        let cache = self.plan_cache.take();
+        ACTIVE_PLAN_CACHE.with(|slot| *slot.borrow_mut() = cache);
+    }
+
+    /// Take the plan cache back and fold its counters into the stats.
+    pub(super) fn finish_statement_plan_cache(&mut self) {
+        let Some(mut cache) = ACTIVE_PLAN_CACHE.with(|slot| slot.borrow_mut().take()) else {
+            return;
+        };
+        self.stats.plan_cache_hits += std::mem::take(&mut cache.hits);
+        self.stats.plan_cache_fallbacks += std::mem::take(&mut cache.fallbacks);
+        self.plan_cache = Some(cache);
+    }
+}
+
+/// Plan for a single-table SELECT, from the session's plan cache when one is
+/// configured. A cached plan is only used if it was built against `generation`
+/// and every index it names still exists; otherwise the statement is planned
+/// again and the miss on a missing index is counted as a fallback.
+pub(crate) fn select_plan_current(
+    table_name: &str,
+    sel: &Select,
+    generation: u64,
+    indexes: &[IndexDef],
+    plan: impl FnOnce() -> Plan,
+) -> Plan {
+    ACTIVE_PLAN_CACHE.with(|slot| {
+        let mut slot = slot.borrow_mut();
+        let Some(cache) = slot.as_mut() else {
+            return plan();
+        };
+        let key = format!(
+            "{}\0{:?}\0{:?}\0{}",
+            table_name,
+            sel.where_clause,
+            sel.index_hints,
+            in_list_seek_max_items_current()
+        );
+        if let Some(cached) = cache.entries.get(&key) {
+            if cached.generation == generation {
+                if plan_indexes_exist(&cached.plan, indexes) {
+                    cache.hits += 1;
+                    return cached.plan.clone();
+                }
+                cache.fallbacks += 1;
+            }
+            cache.remove(&key);
+        }
+        let fresh = plan();
+        cache.insert(key, generation, fresh.clone());
+        fresh
+    })
+}
+
+fn plan_indexes_exist(plan: &Plan, indexes: &[IndexDef]) -> bool {
+    match plan {
+        Plan::IndexSeek { index_name, .. } | Plan::IndexRangeSeek { index_name, .. } => {
+            indexes.iter().any(|i| &i.name == index_name)
+        }
+        Plan::InListSeek { index_name, .. } => index_name
+            .as_ref()
+            .is_none_or(|name| indexes.iter().any(|i| &i.name == name)),
+        Plan::PkSeek { .. } | Plan::FullScan { .. } | Plan::FtsScan { .. } => true,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::btree::ops::DEFAULT_FILL_FACTOR;
+    use crate::schema::index::IndexType;
+
+    fn index_plan(name: &str) -> Plan {
+        Plan::IndexSeek {
+            table_name: "t".into(),
+            index_name: name.into(),
+            column_names: vec!["a".into()],
+            key_exprs: vec![],
+        }
+    }
+
+    fn select() -> Select {
+        match crate::sql::parser::parse_sql("SELECT * FROM t WHERE a = 1").unwrap() {
+            Statement::Select(sel) => *sel,
+            other => panic!("unexpected statement {:?}", other),
+        }
+    }
+
+    fn with_cache<R>(cache: PlanCache, f: impl FnOnce() -> R) -> (R, PlanCache) {
+        ACTIVE_PLAN_CACHE.with(|slot| *slot.borrow_mut() = Some(cache));
+        let result = f();
+        let cache = ACTIVE_PLAN_CACHE.with(|slot| slot.borrow_mut().take().unwrap());
+        (result, cache)
+    }
+
+    #[test]
+    fn test_cached_plan_naming_missing_index_is_replanned() {
+        let sel = select();
+        let idx = IndexDef {
+            name: "idx_a".into(),
+            table_name: "t".into(),
+            column_names: vec!["a".into()],
+            index_type: IndexType::BTree,
+            is_unique: false,
+            btree_root: 0,
+            stats_distinct_keys: 0,
+            stats_num_min: 0,
+            stats_num_max: 0,
+            stats_num_bounds_known: false,
+            stats_num_hist_bins: Vec::new(),
+            fts_stop_filter: false,
+            fts_stop_df_ratio_ppm: 0,
+            fts_stop_fallback: Default::default(),
+            fts_stop_fallback_max_docs: 0,
+            expressions: Vec::new(),
+            fill_factor: DEFAULT_FILL_FACTOR,
+            building: false,
+            trailing_fields: Vec::new(),
+        };
+        let (_, cache) = with_cache(PlanCache::new(4), || {
+            select_plan_current("t", &sel, 7, &[], || index_plan("gone"))
+        });
+        assert_eq!((cache.hits, cache.fallbacks), (0, 0));
+
+        // Same generation, but the planned index no longer exists.
+        let (plan, cache) = with_cache(cache, || {
+            select_plan_current("t", &sel, 7, std::slice::from_ref(&idx), || {
+                index_plan("idx_a")
+            })
+        });
+        assert!(matches!(plan, Plan::IndexSeek { ref index_name, .. } if index_name == "idx_a"));
+        assert_eq!((cache.hits, cache.fallbacks), (0, 1));
+
+        let (_, cache) = with_cache(cache, || {
+            select_plan_current("t", &sel, 7, std::slice::from_ref(&idx), || {
+                panic!("expected a cache hit")
+            })
+        });
+        assert_eq!((cache.hits, cache.fallbacks), (1, 1));
+
+        // A newer generation discards the entry without counting a fallback.
+        let (_, cache) = with_cache(cache, || {
+            select_plan_current("t", &sel, 8, &[], || Plan::FullScan {
+                table_name: "t".into(),
+            })
+        });
+        assert_eq!((cache.hits, cache.fallbacks), (1, 1));
+    }
+
+    #[test]
+    fn test_plan_cache_evicts_oldest() {
+        let mut cache = PlanCache::new(2);
+        for key in ["a", "b", "c"] {
+            cache.insert(key.into(), 1, index_plan(key));
+        }
+        assert!(!cache.entries.contains_key("a"));
+        assert_eq!(cache.order, ["b", "c"]);
+        cache.insert("b".into(), 2, index_plan("b"));
+        assert_eq!(cache.order, ["c", "b"]);
+    }
+}
@@ -0,0 +1,25 @@
+use super::*;
+use crate::schema::expectation::{SchemaDiff, SchemaExpectation};
+
+impl Session {
+    /// Compare the committed schema with `expected`.
+    ///
+    /// Returns the diff when every required table, column, and index is
+    /// present and compatible; it lists any tolerated extras. Otherwise fails
+    /// with [`MuroError::SchemaMismatch`] carrying the same diff. Rejected
+    /// inside a transaction.
+    pub fn assert_schema(&mut self, expected: &SchemaExpectation) -> Result<SchemaDiff> {
+        self.check_poisoned()?;
+        self.refresh_from_disk_if_needed()?;
+        if self.active_tx.is_some() {
+            return Err(MuroError::Execution(
+                "schema assertion cannot be used inside a transaction".into(),
+            ));
+        }
+        let diff = expected.compare(&mut self.pager, &self.catalog)?;
+        if !diff.is_compatible() {
+            return Err(MuroError::SchemaMismatch(Box::new(diff)));
+        }
+        Ok(diff)
+    }
+}
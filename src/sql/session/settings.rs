@@ -0,0 +1,365 @@
+//! Session settings changed by `SET name = value` and listed by
+//! `SHOW VARIABLES`.
+//!
+//! Every setting is declared once in [`SETTINGS`] with its value domain and
+//! whether it may change inside a transaction; `SET` validates against that
+//! entry before applying the value. Settings live on the [`Session`] and are
+//! not persisted: a new handle starts from the defaults again (for the
+//! checkpoint settings, the `MURODB_CHECKPOINT_*` environment variables).
+
+use super::*;
+use crate::sql::ast::{SetValue, SetVariable};
+use crate::sql::eval::like_match;
+
+/// Largest value an integer setting accepts (SQL integers are `i64`).
+const MAX_INTEGER: u64 = i64::MAX as u64;
+
+/// Values a setting accepts.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SettingKind {
+    /// An integer in `min..=max`.
+    Integer { min: u64, max: u64 },
+    /// `ON` or `OFF` (quoted or not).
+    Bool,
+    /// One of the listed words, case-insensitively.
+    Choice(&'static [&'static str]),
+}
+
+impl SettingKind {
+    fn describe(&self) -> String {
+        match self {
+            SettingKind::Integer { min, max } => format!("an integer from {} to {}", min, max),
+            SettingKind::Bool => "ON or OFF".to_string(),
+            SettingKind::Choice(words) => format!("one of '{}'", words.join("', '")),
+        }
+    }
+}
+
+/// Declaration of one session setting.
+#[derive(Debug, Clone, Copy)]
+pub struct SettingDef {
+    pub name: &'static str,
+    pub kind: SettingKind,
+    /// Whether `SET` may change it while a transaction is open.
+    pub in_transaction: bool,
+    pub description: &'static str,
+}
+
+const fn integer(min: u64) -> SettingKind {
+    SettingKind::Integer {
+        min,
+        max: MAX_INTEGER,
+    }
+}
+
+/// Every setting `SET` accepts, in `SHOW VARIABLES` order.
+pub const SETTINGS: &[SettingDef] = &[
+    SettingDef {
+        name: "abort_on_statement_error",
+        kind: SettingKind::Bool,
+        in_transaction: true,
+        description:
+            "A failed statement aborts the transaction; only ROLLBACK or ROLLBACK TO SAVEPOINT runs",
+    },
+    SettingDef {
+        name: "aggregation_memory_budget",
+        kind: integer(0),
+        in_transaction: true,
+        description: "Bytes of GROUP BY state kept in memory before spilling",
+    },
+    SettingDef {
+        name: "busy_timeout",
+        kind: integer(0),
+        in_transaction: true,
+        description: "Lock wait in milliseconds; 0 waits indefinitely",
+    },
+    SettingDef {
+        name: "checkpoint_interval_ms",
+        kind: integer(0),
+        in_transaction: false,
+        description: "Checkpoint when this long has passed since the last one; 0 disables",
+    },
+    SettingDef {
+        name: "checkpoint_tx_threshold",
+        kind: integer(0),
+        in_transaction: false,
+        description: "Checkpoint after this many commits or rollbacks; 0 disables",
+    },
+    SettingDef {
+        name: "checkpoint_wal_bytes_threshold",
+        kind: integer(0),
+        in_transaction: false,
+        description: "Checkpoint once the WAL grows past this many bytes; 0 disables",
+    },
+    SettingDef {
+        name: "fts_vacuum_batch",
+        kind: integer(0),
+        in_transaction: true,
+        description: "Stale FULLTEXT segments OPTIMIZE TABLE reclaims per index; 0 skips",
+    },
+    SettingDef {
+        name: "in_list_seek_max_items",
+        kind: integer(0),
+        in_transaction: true,
+        description: "Longest IN list planned as one seek per value",
+    },
+    SettingDef {
+        name: "index_build_batch_rows",
+        kind: integer(1),
+        in_transaction: true,
+        description: "Rows an auto-commit CREATE INDEX indexes per committed batch",
+    },
+    SettingDef {
+        name: "lock_page_cache",
+        kind: SettingKind::Bool,
+        in_transaction: false,
+        description: "Lock cached decrypted pages in memory so they are never swapped out",
+    },
+    SettingDef {
+        name: "max_execution_time_ms",
+        kind: integer(0),
+        in_transaction: true,
+        description: "Statement time limit in milliseconds; 0 disables",
+    },
+    SettingDef {
+        name: "page_cache_pages",
+        kind: integer(1),
+        in_transaction: false,
+        description: "Pages the page cache holds",
+    },
+    SettingDef {
+        name: "plan_baselines",
+        kind: SettingKind::Bool,
+        in_transaction: true,
+        description: "Use installed plan baselines",
+    },
+    SettingDef {
+        name: "predicate_reorder",
+        kind: SettingKind::Bool,
+        in_transaction: true,
+        description: "Reorder WHERE conjuncts by estimated cost",
+    },
+    SettingDef {
+        name: "read_only",
+        kind: SettingKind::Bool,
+        in_transaction: false,
+        description: "Reject statements that write",
+    },
+    SettingDef {
+        name: "scan_corruption_policy",
+        kind: SettingKind::Choice(&["error", "skip"]),
+        in_transaction: true,
+        description: "How full scans react to unreadable pages and rows",
+    },
+];
+
+/// The declaration of setting `name`, if there is one.
+pub fn setting_def(name: &str) -> Option<&'static SettingDef> {
+    SETTINGS
+        .iter()
+        .find(|def| def.name.eq_ignore_ascii_case(name))
+}
+
+/// A value checked against its [`SettingKind`].
+enum Checked {
+    Integer(u64),
+    Bool(bool),
+    Choice(&'static str),
+}
+
+fn check_value(def: &SettingDef, value: &SetValue) -> Result<Checked> {
+    let checked = match (def.kind, value) {
+        (SettingKind::Integer { min, max }, SetValue::Integer(n)) => u64::try_from(*n)
+            .ok()
+            .filter(|n| (min..=max).contains(n))
+            .map(Checked::Integer),
+        (SettingKind::Bool, SetValue::Word(w)) => match w.as_str() {
+            "on" => Some(Checked::Bool(true)),
+            "off" => Some(Checked::Bool(false)),
+            _ => None,
+        },
+        (SettingKind::Choice(words), SetValue::Word(w)) => words
+            .iter()
+            .find(|word| word.eq_ignore_ascii_case(w))
+            .map(|word| Checked::Choice(word)),
+        _ => None,
+    };
+    checked.ok_or_else(|| {
+        let shown = match value {
+            SetValue::Integer(n) => n.to_string(),
+            SetValue::Word(w) => format!("'{}'", w),
+        };
+        MuroError::Execution(format!(
+            "Invalid value {} for {}: expected {}",
+            shown,
+            def.name,
+            def.kind.describe()
+        ))
+    })
+}
+
+fn on_off(enabled: bool) -> String {
+    if enabled { "ON" } else { "OFF" }.to_string()
+}
+
+impl Session {
+    /// Change a session setting, as `SET name = value` does.
+    pub fn set_variable(&mut self, name: &str, value: &SetValue) -> Result<()> {
+        let def = setting_def(name).ok_or_else(|| {
+            let names: Vec<&str> = SETTINGS.iter().map(|def| def.name).collect();
+            MuroError::Execution(format!(
+                "Unknown setting '{}'. Supported settings: {}",
+                name,
+                names.join(", ")
+            ))
+        })?;
+        let checked = check_value(def, value)?;
+        if !def.in_transaction && self.active_tx.is_some() {
+            return Err(MuroError::Execution(format!(
+                "SET {} cannot be used inside a transaction",
+                def.name
+            )));
+        }
+        let as_usize = |n: u64| usize::try_from(n).unwrap_or(usize::MAX);
+        match (def.name, checked) {
+            ("abort_on_statement_error", Checked::Bool(b)) => self.set_abort_on_statement_error(b),
+            ("aggregation_memory_budget", Checked::Integer(n)) => {
+                self.set_aggregation_memory_budget(as_usize(n))
+            }
+            ("busy_timeout", Checked::Integer(n)) => self.set_busy_timeout_ms(n),
+            ("checkpoint_interval_ms", Checked::Integer(n)) => {
+                self.checkpoint_policy.interval_ms = n
+            }
+            ("checkpoint_tx_threshold", Checked::Integer(n)) => {
+                self.checkpoint_policy.tx_threshold = n
+            }
+            ("checkpoint_wal_bytes_threshold", Checked::Integer(n)) => {
+                self.checkpoint_policy.wal_bytes_threshold = n
+            }
+            ("fts_vacuum_batch", Checked::Integer(n)) => self.set_fts_vacuum_batch(as_usize(n)),
+            ("in_list_seek_max_items", Checked::Integer(n)) => {
+                self.set_in_list_seek_max_items(as_usize(n))
+            }
+            ("index_build_batch_rows", Checked::Integer(n)) => self.set_index_build_batch_rows(n),
+            ("lock_page_cache", Checked::Bool(b)) => self.pager.set_cache_locked(b)?,
+            ("max_execution_time_ms", Checked::Integer(n)) => self.set_statement_timeout_ms(n),
+            ("page_cache_pages", Checked::Integer(n)) => self.pager.set_cache_capacity(as_usize(n)),
+            ("plan_baselines", Checked::Bool(b)) => self.set_plan_baselines_enabled(b),
+            ("predicate_reorder", Checked::Bool(b)) => self.set_predicate_reorder(b),
+            ("read_only", Checked::Bool(b)) => self.set_read_only(b),
+            ("scan_corruption_policy", Checked::Choice(word)) => {
+                self.set_scan_corruption_policy(if word == "skip" {
+                    ScanCorruptionPolicy::Skip
+                } else {
+                    ScanCorruptionPolicy::Error
+                })
+            }
+            _ => {
+                return Err(MuroError::Internal(format!(
+                    "setting {} has no handler",
+                    def.name
+                )))
+            }
+        }
+        Ok(())
+    }
+
+    /// Current value of setting `name` as `SHOW VARIABLES` shows it.
+    pub fn variable(&self, name: &str) -> Option<String> {
+        let def = setting_def(name)?;
+        Some(match def.name {
+            "abort_on_statement_error" => on_off(self.abort_on_statement_error),
+            "aggregation_memory_budget" => self.aggregation_memory_budget.to_string(),
+            "busy_timeout" => self.busy_timeout_ms.to_string(),
+            "checkpoint_interval_ms" => self.checkpoint_policy.interval_ms.to_string(),
+            "checkpoint_tx_threshold" => self.checkpoint_policy.tx_threshold.to_string(),
+            "checkpoint_wal_bytes_threshold" => {
+                self.checkpoint_policy.wal_bytes_threshold.to_string()
+            }
+            "fts_vacuum_batch" => self.fts_vacuum_batch.to_string(),
+            "in_list_seek_max_items" => self.in_list_seek_max_items.to_string(),
+            "index_build_batch_rows" => self.index_build_batch_rows.to_string(),
+            "lock_page_cache" => on_off(self.pager.cache_locked()),
+            "max_execution_time_ms" => self.statement_timeout_ms.to_string(),
+            "page_cache_pages" => self.pager.cache_capacity().to_string(),
+            "plan_baselines" => on_off(self.plan_baselines_enabled),
+            "predicate_reorder" => on_off(self.predicate_reorder),
+            "read_only" => on_off(self.read_only),
+            "scan_corruption_policy" => match self.scan_corruption_policy {
+                ScanCorruptionPolicy::Error => "error".to_string(),
+                ScanCorruptionPolicy::Skip => "skip".to_string(),
+            },
+            _ => return None,
+        })
+    }
+
+    pub(super) fn handle_set_variable(&mut self, stmt: &SetVariable) -> Result<ExecResult> {
+        self.set_variable(&stmt.name, &stmt.value)?;
+        Ok(ExecResult::Ok)
+    }
+
+    pub(super) fn handle_show_variables(&self, pattern: Option<&str>) -> Result<ExecResult> {
+        let rows = SETTINGS
+            .iter()
+            .filter(|def| pattern.is_none_or(|p| like_match(def.name, &p.to_ascii_lowercase())))
+            .map(|def| Row {
+                values: vec![
+                    (
+                        "variable_name".to_string(),
+                        Value::Varchar(def.name.to_string()),
+                    ),
+                    (
+                        "value".to_string(),
+                        Value::Varchar(self.variable(def.name).unwrap_or_default()),
+                    ),
+                ],
+            })
+            .collect();
+        Ok(ExecResult::Rows(rows))
+    }
+
+    /// Reject every statement that writes, as `SET read_only = ON` does.
+    /// Transaction control and `SET` still run.
+    pub fn set_read_only(&mut self, read_only: bool) {
+        self.read_only = read_only;
+    }
+
+    /// Whether the session rejects writes.
+    pub fn read_only(&self) -> bool {
+        self.read_only
+    }
+
+    /// Abort an explicit transaction when one of its statements fails, as
+    /// `SET abort_on_statement_error = ON` does. Until ROLLBACK or ROLLBACK
+    /// TO SAVEPOINT, every other statement then fails with `current
+    /// transaction is aborted`, and COMMIT rolls the transaction back and
+    /// reports that it did. Off, a failed statement is undone on its own and
+    /// the transaction continues.
+    pub fn set_abort_on_statement_error(&mut self, enabled: bool) {
+        self.abort_on_statement_error = enabled;
+    }
+
+    /// Whether a failed statement aborts its transaction.
+    pub fn abort_on_statement_error(&self) -> bool {
+        self.abort_on_statement_error
+    }
+
+    /// Stale FULLTEXT segment payloads `OPTIMIZE TABLE` reclaims per index.
+    ///
+    /// Same as `SET fts_vacuum_batch = <n>`; `0` leaves them in place.
+    pub fn set_fts_vacuum_batch(&mut self, tasks: usize) {
+        self.fts_vacuum_batch = tasks;
+    }
+
+    /// Stale FULLTEXT segments reclaimed per index by `OPTIMIZE TABLE`.
+    pub fn fts_vacuum_batch(&self) -> usize {
+        self.fts_vacuum_batch
+    }
+
+    pub(super) fn reject_write_when_read_only(&self, stmt: &Statement) -> Result<()> {
+        if self.read_only && !Self::is_read_only_statement(stmt) {
+            return Err(MuroError::ReadOnly);
+        }
+        Ok(())
+    }
+}
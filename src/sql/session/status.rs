@@ -0,0 +1,283 @@
+use super::*;
+use crate::concurrency::{LockHold, LockState};
+use parking_lot::Mutex;
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::AtomicU8;
+use std::sync::{OnceLock, Weak};
+
+/// Longest statement text kept for `SHOW PROCESSLIST`, in bytes.
+const MAX_STATUS_SQL_BYTES: usize = 1024;
+
+type StatusMap = Mutex<HashMap<PathBuf, Vec<Weak<EngineStatus>>>>;
+
+/// Status of every live session in this process, by database file.
+static STATUSES: OnceLock<StatusMap> = OnceLock::new();
+
+static NEXT_STATUS_ID: AtomicU64 = AtomicU64::new(1);
+
+/// What a session is doing, as reported by `SHOW PROCESSLIST`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum StatementPhase {
+    Idle,
+    Parsing,
+    Executing,
+    /// Appending the transaction to the WAL and flushing its pages.
+    Committing,
+    /// Copying the WAL into the data file and truncating it.
+    Checkpointing,
+}
+
+impl StatementPhase {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            StatementPhase::Idle => "idle",
+            StatementPhase::Parsing => "parsing",
+            StatementPhase::Executing => "executing",
+            StatementPhase::Committing => "committing",
+            StatementPhase::Checkpointing => "checkpointing",
+        }
+    }
+
+    fn from_u8(value: u8) -> Self {
+        match value {
+            1 => StatementPhase::Parsing,
+            2 => StatementPhase::Executing,
+            3 => StatementPhase::Committing,
+            4 => StatementPhase::Checkpointing,
+            _ => StatementPhase::Idle,
+        }
+    }
+}
+
+/// Live activity of one session, shared through an `Arc` so that a watchdog
+/// thread can read it while a statement runs.
+///
+/// The session updates it when a statement starts and ends and when the
+/// statement changes phase, with atomic stores and a text buffer reused
+/// across statements. Neither side takes the database locks, so reading it
+/// never waits for the statement it describes.
+pub struct EngineStatus {
+    id: u64,
+    /// Canonical path of the database file, shared by the sessions listed
+    /// together by `SHOW PROCESSLIST`.
+    path: PathBuf,
+    /// Base of `started_nanos`.
+    epoch: Instant,
+    phase: AtomicU8,
+    /// Statements started so far; identifies the running one.
+    statement_seq: AtomicU64,
+    /// Nanoseconds from `epoch` to the start of the running statement, plus
+    /// one; `0` while idle.
+    started_nanos: AtomicU64,
+    started_unix_ms: AtomicU64,
+    /// Id of the open transaction plus one; `0` when there is none.
+    txid: AtomicU64,
+    /// Text of the running statement, cut to `MAX_STATUS_SQL_BYTES`. Its
+    /// lock also orders a statement's start and end against snapshots.
+    sql: Mutex<String>,
+    /// Lock guards of the handle that runs this session's statements.
+    lock: Mutex<Option<Arc<LockHold>>>,
+}
+
+/// A copy of an [`EngineStatus`] taken at one point in time.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SessionStatus {
+    /// Process-unique id of the session; the `Id` column of `SHOW PROCESSLIST`.
+    pub id: u64,
+    pub phase: StatementPhase,
+    /// Text of the running statement, cut to 1024 bytes; `None` while idle.
+    pub statement: Option<String>,
+    /// Statements the session has started. A watchdog compares it between
+    /// two snapshots to tell whether the same statement is still running.
+    pub statement_seq: u64,
+    /// Unix time in milliseconds when the running statement started.
+    pub started_at_unix_ms: Option<u64>,
+    /// Time since the running statement started.
+    pub elapsed: Option<Duration>,
+    /// Open transaction: an explicit one, kept between statements, or the
+    /// implicit one of a running auto-commit statement.
+    pub txid: Option<TxId>,
+    /// Process-level file lock held by the session's handle.
+    pub lock: LockState,
+}
+
+impl EngineStatus {
+    /// Create the status of a new session on `db_path` and list it for
+    /// `SHOW PROCESSLIST` until it is dropped.
+    pub(super) fn register(db_path: &Path) -> Arc<Self> {
+        let path = std::fs::canonicalize(db_path).unwrap_or_else(|_| db_path.to_path_buf());
+        let status = Arc::new(EngineStatus {
+            id: NEXT_STATUS_ID.fetch_add(1, Ordering::Relaxed),
+            path: path.clone(),
+            epoch: Instant::now(),
+            phase: AtomicU8::new(StatementPhase::Idle as u8),
+            statement_seq: AtomicU64::new(0),
+            started_nanos: AtomicU64::new(0),
+            started_unix_ms: AtomicU64::new(0),
+            txid: AtomicU64::new(0),
+            sql: Mutex::new(String::with_capacity(MAX_STATUS_SQL_BYTES)),
+            lock: Mutex::new(None),
+        });
+        let mut map = STATUSES.get_or_init(Default::default).lock();
+        map.retain(|_, sessions| {
+            sessions.retain(|s| s.strong_count() > 0);
+            !sessions.is_empty()
+        });
+        map.entry(path).or_default().push(Arc::downgrade(&status));
+        status
+    }
+
+    pub fn id(&self) -> u64 {
+        self.id
+    }
+
+    /// Copy the current status.
+    pub fn snapshot(&self) -> SessionStatus {
+        let sql = self.sql.lock();
+        let started_nanos = self.started_nanos.load(Ordering::Acquire);
+        let running = started_nanos != 0;
+        let elapsed = running.then(|| {
+            self.epoch
+                .elapsed()
+                .saturating_sub(Duration::from_nanos(started_nanos - 1))
+        });
+        SessionStatus {
+            id: self.id,
+            phase: StatementPhase::from_u8(self.phase.load(Ordering::Acquire)),
+            statement: running.then(|| sql.clone()),
+            statement_seq: self.statement_seq.load(Ordering::Acquire),
+            started_at_unix_ms: running.then(|| self.started_unix_ms.load(Ordering::Acquire)),
+            elapsed,
+            txid: self.txid.load(Ordering::Acquire).checked_sub(1),
+            lock: self
+                .lock
+                .lock()
+                .as_ref()
+                .map_or(LockState::Unlocked, |hold| hold.state()),
+        }
+    }
+
+    /// Record `sql` as the running statement, in the parsing phase, until
+    /// the returned guard is dropped.
+    pub(super) fn enter(self: &Arc<Self>, sql: &str) -> StatusGuard {
+        let mut cut = sql.len().min(MAX_STATUS_SQL_BYTES);
+        while !sql.is_char_boundary(cut) {
+            cut -= 1;
+        }
+        let unix_ms = std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_millis() as u64;
+        let mut text = self.sql.lock();
+        text.clear();
+        text.push_str(&sql[..cut]);
+        self.statement_seq.fetch_add(1, Ordering::AcqRel);
+        self.started_unix_ms.store(unix_ms, Ordering::Release);
+        self.started_nanos.store(
+            self.epoch.elapsed().as_nanos() as u64 + 1,
+            Ordering::Release,
+        );
+        self.phase
+            .store(StatementPhase::Parsing as u8, Ordering::Release);
+        StatusGuard(Arc::clone(self))
+    }
+
+    /// Move to `phase` and return the previous one.
+    pub(super) fn set_phase(&self, phase: StatementPhase) -> StatementPhase {
+        StatementPhase::from_u8(self.phase.swap(phase as u8, Ordering::AcqRel))
+    }
+
+    pub(super) fn set_txid(&self, txid: Option<TxId>) {
+        self.txid
+            .store(txid.map_or(0, |id| id + 1), Ordering::Release);
+    }
+
+    pub(super) fn attach_lock(&self, hold: Arc<LockHold>) {
+        *self.lock.lock() = Some(hold);
+    }
+
+    /// Live sessions on the same database file, this one included, by id.
+    fn peers(&self) -> Vec<Arc<EngineStatus>> {
+        let map = STATUSES.get_or_init(Default::default).lock();
+        let mut peers: Vec<Arc<EngineStatus>> = map
+            .get(&self.path)
+            .map(|sessions| sessions.iter().filter_map(Weak::upgrade).collect())
+            .unwrap_or_default();
+        peers.sort_by_key(|s| s.id);
+        peers
+    }
+}
+
+/// Marks a session idle again when its statement ends.
+pub(super) struct StatusGuard(Arc<EngineStatus>);
+
+impl Drop for StatusGuard {
+    fn drop(&mut self) {
+        let _text = self.0.sql.lock();
+        self.0.started_nanos.store(0, Ordering::Release);
+        self.0
+            .phase
+            .store(StatementPhase::Idle as u8, Ordering::Release);
+    }
+}
+
+impl Session {
+    /// What this session is doing right now; see [`EngineStatus`].
+    pub fn status(&self) -> SessionStatus {
+        self.status.snapshot()
+    }
+
+    /// The live status of this session, for reading from another thread
+    /// while a statement runs, e.g. by a watchdog that cancels statements
+    /// running too long through [`Self::cancel_handle`].
+    pub fn status_handle(&self) -> Arc<EngineStatus> {
+        Arc::clone(&self.status)
+    }
+
+    /// Report the file lock of `lock_manager` as this session's lock.
+    pub(crate) fn track_lock(&self, lock_manager: &LockManager) {
+        self.status.attach_lock(lock_manager.hold());
+    }
+
+    /// `SHOW PROCESSLIST`: one row per live session on this database file
+    /// in this process.
+    pub(super) fn handle_show_processlist(&self) -> Result<ExecResult> {
+        let rows = self
+            .status
+            .peers()
+            .iter()
+            .map(|peer| {
+                let status = peer.snapshot();
+                let integer = |v: Option<u64>| v.map_or(Value::Null, |v| Value::Integer(v as i64));
+                Row {
+                    values: vec![
+                        ("Id".to_string(), Value::Integer(status.id as i64)),
+                        (
+                            "Phase".to_string(),
+                            Value::Varchar(status.phase.as_str().to_string()),
+                        ),
+                        (
+                            "Statement".to_string(),
+                            status.statement.map_or(Value::Null, Value::Varchar),
+                        ),
+                        (
+                            "Started_at_ms".to_string(),
+                            integer(status.started_at_unix_ms),
+                        ),
+                        (
+                            "Elapsed_ms".to_string(),
+                            integer(status.elapsed.map(|d| d.as_millis() as u64)),
+                        ),
+                        ("Transaction".to_string(), integer(status.txid)),
+                        (
+                            "Lock".to_string(),
+                            Value::Varchar(status.lock.as_str().to_string()),
+                        ),
+                    ],
+                }
+            })
+            .collect();
+        Ok(ExecResult::Rows(rows))
+    }
+}
@@ -0,0 +1,152 @@
+use super::*;
+use crate::fts::index::FtsIndex;
+use crate::schema::index::IndexType;
+use std::collections::{BTreeMap, BTreeSet};
+
+/// Per-table counters for [`Session::metrics_prometheus`], kept in memory so
+/// a scrape does no page I/O.
+#[derive(Default)]
+pub(crate) struct TableCounters {
+    /// Tables written by the statements of the open explicit transaction.
+    tx_written: BTreeSet<String>,
+    /// Whether the open explicit transaction ran DDL.
+    tx_ddl: bool,
+    /// Committed transactions that wrote each table through this handle.
+    pub(super) versions: BTreeMap<String, u64>,
+    /// FTS GC tasks queued per table, over its FULLTEXT indexes, as of open,
+    /// the last commit through this handle that wrote the table, or the last
+    /// maintenance run.
+    pub(super) fts_gc_backlog: BTreeMap<String, u64>,
+}
+
+impl TableCounters {
+    pub(super) fn fts_gc_backlog_total(&self) -> u64 {
+        self.fts_gc_backlog.values().sum()
+    }
+}
+
+/// Tables whose rows or definition `stmt` changed, given its `result`.
+/// Index DDL and statements that affected no rows change none.
+fn written_tables<'a>(stmt: &'a Statement, result: &ExecResult) -> Vec<&'a str> {
+    let changed_rows = !matches!(result, ExecResult::RowsAffected(0));
+    match stmt {
+        Statement::Insert(ins) if changed_rows => vec![&ins.table_name],
+        Statement::Update(upd) if changed_rows => vec![&upd.table_name],
+        Statement::Delete(del) if changed_rows => vec![&del.table_name],
+        Statement::CreateTable(ct) => vec![&ct.table_name],
+        Statement::DropTable(dt) => vec![&dt.table_name],
+        Statement::RenameTable(rt) => vec![&rt.old_name, &rt.new_name],
+        Statement::AlterTable(at) => {
+            let mut tables = vec![at.table_name.as_str()];
+            for op in &at.operations {
+                if let AlterTableOp::ExchangeWith(other) = op {
+                    tables.push(other);
+                }
+            }
+            tables
+        }
+        _ => Vec::new(),
+    }
+}
+
+/// Whether `stmt` can add, drop or move a FULLTEXT index.
+fn is_ddl(stmt: &Statement) -> bool {
+    matches!(
+        stmt,
+        Statement::CreateTable(_)
+            | Statement::CreateIndex(_)
+            | Statement::CreateFulltextIndex(_)
+            | Statement::DropTable(_)
+            | Statement::DropIndex(_)
+            | Statement::AlterTable(_)
+            | Statement::RenameTable(_)
+    )
+}
+
+impl Session {
+    /// Record what a statement that succeeded in the explicit transaction
+    /// wrote; it counts once the transaction commits.
+    pub(super) fn note_tx_writes(&mut self, stmt: &Statement, result: &ExecResult) {
+        let counters = &mut self.table_counters;
+        counters
+            .tx_written
+            .extend(written_tables(stmt, result).into_iter().map(String::from));
+        counters.tx_ddl |= is_ddl(stmt);
+    }
+
+    /// Forget the writes of an explicit transaction that ended.
+    pub(super) fn clear_tx_writes(&mut self) {
+        self.table_counters.tx_written.clear();
+        self.table_counters.tx_ddl = false;
+    }
+
+    /// Count the writes of the explicit transaction that just committed.
+    pub(super) fn commit_tx_writes(&mut self) {
+        let tables = std::mem::take(&mut self.table_counters.tx_written);
+        let ddl = std::mem::take(&mut self.table_counters.tx_ddl);
+        self.count_committed_writes(tables, ddl);
+    }
+
+    /// Count the writes of an auto-commit statement that just committed.
+    pub(super) fn commit_statement_writes(&mut self, stmt: &Statement, result: &ExecResult) {
+        let tables = written_tables(stmt, result)
+            .into_iter()
+            .map(String::from)
+            .collect();
+        self.count_committed_writes(tables, is_ddl(stmt));
+    }
+
+    fn count_committed_writes(&mut self, tables: BTreeSet<String>, ddl: bool) {
+        for table in &tables {
+            *self
+                .table_counters
+                .versions
+                .entry(table.clone())
+                .or_insert(0) += 1;
+        }
+        if ddl {
+            self.refresh_fts_gc_backlog();
+        } else {
+            for table in &tables {
+                self.refresh_table_fts_gc_backlog(table);
+            }
+        }
+    }
+
+    /// Re-read the FTS GC backlog of every table. Best effort: a table
+    /// whose indexes cannot be read keeps no entry.
+    pub(super) fn refresh_fts_gc_backlog(&mut self) {
+        self.table_counters.fts_gc_backlog.clear();
+        let Ok(tables) = self.catalog.list_tables(&mut self.pager) else {
+            return;
+        };
+        for table in tables {
+            self.refresh_table_fts_gc_backlog(&table);
+        }
+    }
+
+    fn refresh_table_fts_gc_backlog(&mut self, table: &str) {
+        match self.table_fts_gc_backlog(table) {
+            Ok(pending) if pending > 0 => {
+                self.table_counters
+                    .fts_gc_backlog
+                    .insert(table.to_string(), pending);
+            }
+            _ => {
+                self.table_counters.fts_gc_backlog.remove(table);
+            }
+        }
+    }
+
+    fn table_fts_gc_backlog(&mut self, table: &str) -> Result<u64> {
+        let mut pending = 0;
+        for idx in self.catalog.get_indexes_for_table(&mut self.pager, table)? {
+            if idx.index_type != IndexType::Fulltext {
+                continue;
+            }
+            let fts = FtsIndex::open(idx.btree_root, self.pager.fts_term_key()?);
+            pending += fts.pending_gc_tasks(&mut self.pager)?;
+        }
+        Ok(pending)
+    }
+}
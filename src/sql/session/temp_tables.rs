@@ -0,0 +1,144 @@
+use super::*;
+use crate::sql::ast::DropTable;
+use crate::storage::page_store::PageStore;
+use std::fs::{File, OpenOptions, TryLockError};
+use std::path::{Path, PathBuf};
+
+/// The catalog namespace holding a session's temporary tables.
+///
+/// Its entries are committed like any other DDL so a crash leaves them on
+/// disk; the lock on `<db>.temp-<owner>.lock` tells a later open whether the
+/// owner is still alive or its tables can be reclaimed.
+pub(super) struct TempNamespace {
+    owner: String,
+    lock_path: PathBuf,
+    /// Held for the life of the session.
+    _lock_file: File,
+}
+
+fn temp_lock_path(db_path: &Path, owner: &str) -> PathBuf {
+    let mut os = db_path.as_os_str().to_os_string();
+    os.push(format!(".temp-{}.lock", owner));
+    PathBuf::from(os)
+}
+
+fn open_lock_file(path: &Path) -> Result<File> {
+    Ok(OpenOptions::new()
+        .create(true)
+        .truncate(false)
+        .read(true)
+        .write(true)
+        .open(path)?)
+}
+
+/// Drop every temporary table of the catalog's current temporary owner.
+fn drop_all_temp_tables(pager: &mut impl PageStore, catalog: &mut SystemCatalog) -> Result<()> {
+    for table_name in catalog.list_temp_tables(pager)? {
+        let stmt = Statement::DropTable(DropTable {
+            table_name,
+            if_exists: true,
+            temporary: true,
+        });
+        execute_statement(&stmt, pager, catalog)?;
+    }
+    Ok(())
+}
+
+impl Session {
+    /// Owner of this session's temporary tables, once it has created one.
+    pub(super) fn temp_owner(&self) -> Option<String> {
+        self.temp_namespace.as_ref().map(|ns| ns.owner.clone())
+    }
+
+    /// Set up the temporary namespace before the session's first
+    /// `CREATE TEMPORARY TABLE`. The lock is taken before any entry of the
+    /// namespace is committed, so no open ever mistakes it for a crash.
+    pub(super) fn ensure_temp_namespace(&mut self) -> Result<()> {
+        if self.temp_namespace.is_some() {
+            return Ok(());
+        }
+        let owner = uuid::Uuid::new_v4().simple().to_string();
+        let lock_path = temp_lock_path(self.pager.path(), &owner);
+        let lock_file = open_lock_file(&lock_path)?;
+        lock_file.try_lock().map_err(|e| {
+            MuroError::Lock(format!("Failed to lock temporary table namespace: {}", e))
+        })?;
+        self.catalog.set_temp_owner(Some(owner.clone()));
+        self.temp_namespace = Some(TempNamespace {
+            owner,
+            lock_path,
+            _lock_file: lock_file,
+        });
+        Ok(())
+    }
+
+    /// Drop this session's temporary tables and release their namespace.
+    /// Runs when the session is dropped; an open transaction is rolled back
+    /// first. If it fails, the namespace lock is still released with the
+    /// session and the next read-write open reclaims the tables.
+    pub(super) fn drop_temp_tables(&mut self) -> Result<()> {
+        if self.temp_namespace.is_none() {
+            return Ok(());
+        }
+        self.check_poisoned()?;
+        if self.active_tx.is_some() {
+            self.handle_rollback()?;
+        }
+        let lock_manager = match &self.lock_manager {
+            Some(manager) => Arc::clone(manager),
+            None => Arc::new(LockManager::new(self.pager.path())?),
+        };
+        let _guard = self.write_lock(Some(&lock_manager))?;
+        self.refresh_from_disk_if_needed()?;
+        self.run_auto_commit(
+            |_| false,
+            |store, catalog| drop_all_temp_tables(store, catalog),
+        )?;
+
+        if let Some(namespace) = self.temp_namespace.take() {
+            let lock_path = namespace.lock_path.clone();
+            drop(namespace);
+            let _ = std::fs::remove_file(lock_path);
+        }
+        self.catalog.set_temp_owner(None);
+        self.catalog.bump_generation();
+        Ok(())
+    }
+
+    /// Drop the temporary tables left behind by sessions that ended without
+    /// cleaning up, such as a crashed process. A namespace whose lock file
+    /// is still locked belongs to a live session and is left alone. Runs at
+    /// open under the exclusive lock.
+    pub(crate) fn reclaim_orphaned_temp_tables(&mut self) -> Result<()> {
+        // Fresh files and those built through Pager-only flows have no
+        // catalog B-tree to look in, as in `catalog_needs_migration`.
+        if self.pager.catalog_root() == 0 && self.pager.page_count() == 0 {
+            return Ok(());
+        }
+        let owners = match self.catalog.temp_owners(&mut self.pager) {
+            Err(MuroError::InvalidPage) if self.pager.catalog_root() == 0 => return Ok(()),
+            other => other?,
+        };
+        for owner in owners {
+            let lock_path = temp_lock_path(self.pager.path(), &owner);
+            let lock_file = open_lock_file(&lock_path)?;
+            match lock_file.try_lock() {
+                Ok(()) => {}
+                Err(TryLockError::WouldBlock) => continue,
+                Err(TryLockError::Error(e)) => return Err(e.into()),
+            }
+            self.run_auto_commit(
+                |_| false,
+                |store, catalog| {
+                    catalog.set_temp_owner(Some(owner.clone()));
+                    let result = drop_all_temp_tables(store, catalog);
+                    catalog.set_temp_owner(None);
+                    result
+                },
+            )?;
+            drop(lock_file);
+            let _ = std::fs::remove_file(lock_path);
+        }
+        Ok(())
+    }
+}
@@ -378,7 +378,7 @@ fn test_show_database_stats_sql() {
 
     match session.execute("SHOW DATABASE STATS").unwrap() {
         ExecResult::Rows(rows) => {
-            assert_eq!(rows.len(), 19);
+            assert_eq!(rows.len(), 34);
             // Verify checkpoint stats
             assert_eq!(
                 rows[0].get("stat"),
@@ -442,6 +442,18 @@ fn test_show_database_stats_sql() {
                 other => panic!("unexpected wal_file_size_bytes value: {:?}", other),
             };
             assert!(wal_size > 0, "expected wal_file_size_bytes > 0");
+            assert_eq!(
+                rows[19].get("stat"),
+                Some(&Value::Varchar("pager_cache_evictions".to_string()))
+            );
+            assert_eq!(
+                rows[20].get("stat"),
+                Some(&Value::Varchar("pager_cache_bytes".to_string()))
+            );
+            assert_eq!(
+                rows[21].get("stat"),
+                Some(&Value::Varchar("pager_cache_capacity_pages".to_string()))
+            );
         }
         _ => panic!("Expected rows from SHOW DATABASE STATS"),
     }
@@ -114,7 +114,7 @@ fn test_stats_readable_on_poisoned_session() {
     // SHOW DATABASE STATS must still work on poisoned session
     match session.execute("SHOW DATABASE STATS").unwrap() {
         ExecResult::Rows(rows) => {
-            assert_eq!(rows.len(), 19);
+            assert_eq!(rows.len(), 34);
             // commit_in_doubt_count should be 1
             assert_eq!(
                 rows[4].get("stat"),
@@ -176,7 +176,7 @@ fn test_rekey_wal_recreate_failure_poison_session() {
     // Stats SQL remains available for operators.
     match session.execute("SHOW DATABASE STATS").unwrap() {
         ExecResult::Rows(rows) => {
-            assert_eq!(rows.len(), 19);
+            assert_eq!(rows.len(), 34);
         }
         _ => panic!("Expected rows from SHOW DATABASE STATS"),
     }
@@ -213,13 +213,16 @@ fn test_cancel_handle_marks_in_flight_statement() {
     let session = Session::new(pager, catalog, wal);
 
     let handle = session.cancel_handle();
+    assert!(!handle.is_running());
     let statement_guard = session.enter_statement();
+    assert!(handle.is_running());
     assert!(handle.cancel());
     assert!(matches!(
         session.cancellation_point(),
         Err(MuroError::Cancelled)
     ));
     drop(statement_guard);
+    assert!(!handle.is_running());
     assert!(session.cancellation_point().is_ok());
 }
 
@@ -558,7 +561,7 @@ fn test_cancelled_update_in_explicit_tx_does_not_apply_partial_changes() {
     let handle = session.cancel_handle();
     let statement_guard = session.enter_statement();
     assert!(handle.cancel());
-    let stmt = parse_sql("UPDATE t SET name = 'x'").unwrap();
+    let stmt = parse_statement("UPDATE t SET name = 'x'").unwrap();
     let err = session
         .execute_in_tx(&stmt)
         .expect_err("update should be cancelled");
@@ -580,3 +583,36 @@ fn test_cancelled_update_in_explicit_tx_does_not_apply_partial_changes() {
 
     session.execute("ROLLBACK").unwrap();
 }
+
+#[test]
+fn test_status_truncates_statement_text_at_char_boundary() {
+    let dir = TempDir::new().unwrap();
+    let db_path = dir.path().join("test.db");
+    let wal_path = dir.path().join("test.wal");
+
+    let mut pager = Pager::create(&db_path, &test_key()).unwrap();
+    let catalog = SystemCatalog::create(&mut pager).unwrap();
+    pager.set_catalog_root(catalog.root_page_id());
+    pager.flush_meta().unwrap();
+    let wal = WalWriter::create(&wal_path, &test_key()).unwrap();
+    let session = Session::new(pager, catalog, wal);
+
+    // 'é' is two bytes; after the 9-byte prefix, byte 1024 falls inside one.
+    let sql = format!("SELECT 'x{}'", "é".repeat(2000));
+    let handle = session.status_handle();
+    let guard = handle.enter(&sql);
+    let status = session.status();
+    let text = status.statement.unwrap();
+    assert_eq!(text.len(), 1023);
+    assert!(sql.starts_with(&text));
+    assert_eq!(status.phase, StatementPhase::Parsing);
+    assert_eq!(status.statement_seq, 1);
+    assert!(status.elapsed.is_some());
+
+    drop(guard);
+    let status = session.status();
+    assert_eq!(status.phase, StatementPhase::Idle);
+    assert_eq!(status.statement, None);
+    assert_eq!(status.elapsed, None);
+    assert_eq!(status.statement_seq, 1);
+}
@@ -0,0 +1,121 @@
+use super::*;
+use crate::sql::ast::ScanCorruptionPolicy;
+use crate::storage::page::PageId;
+
+/// One entry of a statement's corruption report, shown by `SHOW WARNINGS`.
+#[derive(Debug, Clone)]
+pub(crate) struct ScanWarning {
+    pub(crate) table: String,
+    /// The unreadable page, or `None` when a single row failed to decode.
+    pub(crate) page_id: Option<PageId>,
+    /// Primary keys that may be missing from the result.
+    pub(crate) key_range: String,
+    pub(crate) message: String,
+}
+
+impl Session {
+    /// Configure how full table scans handle unreadable pages and rows.
+    ///
+    /// Same as `SET scan_corruption_policy = 'error' | 'skip'`.
+    pub fn set_scan_corruption_policy(&mut self, policy: ScanCorruptionPolicy) {
+        self.scan_corruption_policy = policy;
+    }
+
+    /// Current scan corruption policy.
+    pub fn scan_corruption_policy(&self) -> ScanCorruptionPolicy {
+        self.scan_corruption_policy
+    }
+
+    /// Skip mode is for reading out what survives; a statement that writes
+    /// could persist a result computed from an incomplete scan.
+    pub(super) fn reject_write_in_skip_mode(&self, stmt: &Statement) -> Result<()> {
+        if self.scan_corruption_policy == ScanCorruptionPolicy::Skip
+            && !Self::is_read_only_statement(stmt)
+        {
+            return Err(MuroError::Execution(
+                "Writes are not allowed while scan_corruption_policy = 'skip'; SET scan_corruption_policy = 'error' first".into(),
+            ));
+        }
+        Ok(())
+    }
+
+    /// Replace the warnings of the previous statement with this statement's
+    /// report. Diagnostic statements leave the previous report in place.
+    pub(super) fn finish_statement_warnings(&mut self, stmt: &Statement) {
+        let mut warnings = ACTIVE_SCAN_WARNINGS
+            .with(|slot| slot.borrow_mut().take())
+            .unwrap_or_default();
+        let query_warnings = ACTIVE_QUERY_WARNINGS
+            .with(|slot| slot.borrow_mut().take())
+            .unwrap_or_default();
+        if matches!(
+            stmt,
+            Statement::ShowWarnings
+                | Statement::ShowProcesslist
+                | Statement::ShowVariables(_)
+                | Statement::ShowCheckpointStats
+                | Statement::ShowDatabaseStats
+        ) {
+            return;
+        }
+        for w in &warnings {
+            if w.page_id.is_some() {
+                self.stats.scan_skipped_pages += 1;
+            } else {
+                self.stats.scan_skipped_rows += 1;
+            }
+        }
+        warnings.extend(query_warnings);
+        self.warnings = warnings;
+    }
+
+    pub(super) fn handle_show_warnings(&self) -> Result<ExecResult> {
+        let rows = self
+            .warnings
+            .iter()
+            .map(|w| Row {
+                values: vec![
+                    ("level".to_string(), Value::Varchar("Warning".to_string())),
+                    ("table".to_string(), Value::Varchar(w.table.clone())),
+                    (
+                        "page_id".to_string(),
+                        w.page_id
+                            .map_or(Value::Null, |id| Value::Integer(id as i64)),
+                    ),
+                    ("key_range".to_string(), Value::Varchar(w.key_range.clone())),
+                    ("message".to_string(), Value::Varchar(w.message.clone())),
+                ],
+            })
+            .collect();
+        Ok(ExecResult::Rows(rows))
+    }
+}
+
+/// Whether the running statement scans in skip mode.
+pub(crate) fn scan_skip_corruption_current() -> bool {
+    ACTIVE_SCAN_WARNINGS.with(|slot| slot.borrow().is_some())
+}
+
+/// Add an entry to the running statement's corruption report.
+pub(crate) fn record_scan_warning_current(warning: ScanWarning) {
+    ACTIVE_SCAN_WARNINGS.with(|slot| {
+        if let Some(warnings) = slot.borrow_mut().as_mut() {
+            warnings.push(warning);
+        }
+    });
+}
+
+/// Add a warning that is not about corruption to the running statement's
+/// report. A message already recorded by this statement is not repeated.
+pub(crate) fn record_query_warning_current(warning: ScanWarning) {
+    ACTIVE_QUERY_WARNINGS.with(|slot| {
+        if let Some(warnings) = slot.borrow_mut().as_mut() {
+            if !warnings
+                .iter()
+                .any(|w| w.table == warning.table && w.message == warning.message)
+            {
+                warnings.push(warning);
+            }
+        }
+    });
+}
@@ -67,10 +67,65 @@ impl FreeList {
         true
     }
 
-    /// Undo the most recent `free()` call. Used to speculatively compute
-    /// a freelist snapshot without permanently mutating state.
-    pub fn undo_last_free(&mut self) {
-        self.free_pages.pop();
+    /// Remove a specific page from the free list. Returns `false` if it was
+    /// not free.
+    pub fn take(&mut self, page_id: PageId) -> bool {
+        match self.free_pages.iter().position(|&p| p == page_id) {
+            Some(pos) => {
+                self.free_pages.remove(pos);
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Free page closest to `hint` within `range`. Ties go to the higher page,
+    /// so consecutive hinted allocations move forward through the file.
+    pub fn nearest(&self, hint: PageId, range: std::ops::Range<PageId>) -> Option<PageId> {
+        self.free_pages
+            .iter()
+            .copied()
+            .filter(|p| range.contains(p))
+            .min_by_key(|&p| (p.abs_diff(hint), std::cmp::Reverse(p)))
+    }
+
+    /// First page of the completely free `extent_pages`-aligned extent
+    /// closest to `hint`.
+    pub fn nearest_free_extent(&self, hint: PageId, extent_pages: u64) -> Option<PageId> {
+        let mut counts: std::collections::HashMap<u64, u64> = std::collections::HashMap::new();
+        for &p in &self.free_pages {
+            *counts.entry(p / extent_pages).or_default() += 1;
+        }
+        counts
+            .into_iter()
+            .filter(|&(_, n)| n == extent_pages)
+            .map(|(extent, _)| extent * extent_pages)
+            .min_by_key(|&start| (start.abs_diff(hint), start))
+    }
+
+    /// Start of the run of `count` consecutive free pages closest to `hint`.
+    pub fn nearest_run(&self, count: usize, hint: PageId) -> Option<PageId> {
+        if count == 0 {
+            return None;
+        }
+        let mut sorted = self.free_pages.clone();
+        sorted.sort_unstable();
+        let mut best: Option<PageId> = None;
+        let mut run_len = 0usize;
+        for (i, &p) in sorted.iter().enumerate() {
+            run_len = if i > 0 && p == sorted[i - 1] + 1 {
+                run_len + 1
+            } else {
+                1
+            };
+            if run_len >= count {
+                let start = p + 1 - count as u64;
+                if best.is_none_or(|b| start.abs_diff(hint) < b.abs_diff(hint)) {
+                    best = Some(start);
+                }
+            }
+        }
+        best
     }
 
     /// Number of free pages.
@@ -82,6 +137,11 @@ impl FreeList {
         self.free_pages.is_empty()
     }
 
+    /// Free page IDs, in allocation-stack order.
+    pub fn pages(&self) -> &[PageId] {
+        &self.free_pages
+    }
+
     /// Serialize freelist to bytes for persistence.
     pub fn serialize(&self) -> Vec<u8> {
         let mut buf = Vec::with_capacity(8 + self.free_pages.len() * 8);
@@ -94,11 +154,13 @@ impl FreeList {
 
     /// Number of pages needed to store this freelist in multi-page format.
     pub fn page_count_needed(&self) -> usize {
-        if self.free_pages.is_empty() {
-            1 // Always need at least one page for the freelist
-        } else {
-            self.free_pages.len().div_ceil(ENTRIES_PER_FREELIST_PAGE)
-        }
+        Self::page_count_needed_for(self.free_pages.len())
+    }
+
+    /// Number of pages needed to store `entries` free page IDs.
+    pub fn page_count_needed_for(entries: usize) -> usize {
+        // Always need at least one page for the freelist
+        entries.div_ceil(ENTRIES_PER_FREELIST_PAGE).max(1)
     }
 
     /// Serialize freelist into multiple page data buffers (multi-page chain format).
@@ -107,18 +169,17 @@ impl FreeList {
     ///   [next_freelist_page_id: u64] [count_in_this_page: u64] [page_id entries: u64...]
     ///
     /// `page_ids` provides the allocated page IDs for each page in the chain.
+    /// Pages beyond the ones the entries fill are written empty, so a chain
+    /// can keep a page it no longer strictly needs.
     /// Returns Vec of (page_id, page_data_bytes) pairs.
     pub fn serialize_pages(&self, page_ids: &[PageId]) -> Vec<(PageId, Vec<u8>)> {
-        let chunks: Vec<&[PageId]> = if self.free_pages.is_empty() {
-            vec![&[]]
-        } else {
-            self.free_pages.chunks(ENTRIES_PER_FREELIST_PAGE).collect()
-        };
-        assert_eq!(
-            chunks.len(),
-            page_ids.len(),
-            "must provide exactly enough page IDs"
+        let mut chunks: Vec<&[PageId]> =
+            self.free_pages.chunks(ENTRIES_PER_FREELIST_PAGE).collect();
+        assert!(
+            chunks.len() <= page_ids.len() && !page_ids.is_empty(),
+            "must provide enough page IDs"
         );
+        chunks.resize(page_ids.len(), &[]);
 
         let mut result = Vec::with_capacity(chunks.len());
         for (i, chunk) in chunks.iter().enumerate() {
@@ -223,6 +284,32 @@ mod tests {
         assert!(fl.allocate().is_none());
     }
 
+    #[test]
+    fn test_nearest_extent_and_run_lookups() {
+        let mut fl = FreeList::new();
+        for p in [3, 9, 10, 11, 12, 40, 41, 42, 43, 44, 45, 46, 47, 100] {
+            fl.free(p);
+        }
+        // Closest inside the range; ties prefer the higher page.
+        assert_eq!(fl.nearest(10, 0..16), Some(10));
+        assert_eq!(fl.nearest(6, 0..16), Some(9));
+        assert_eq!(fl.nearest(6, 0..8), Some(3));
+        assert_eq!(fl.nearest(20, 16..32), None);
+
+        // Only 40..48 is a completely free 8-page extent.
+        assert_eq!(fl.nearest_free_extent(0, 8), Some(40));
+        assert_eq!(fl.nearest_free_extent(0, 16), None);
+
+        assert_eq!(fl.nearest_run(4, 0), Some(9));
+        assert_eq!(fl.nearest_run(4, 90), Some(44));
+        assert_eq!(fl.nearest_run(9, 0), None);
+
+        assert!(fl.take(41));
+        assert!(!fl.take(41));
+        assert_eq!(fl.nearest_free_extent(0, 8), None);
+        assert_eq!(fl.len(), 13);
+    }
+
     #[test]
     fn test_serialize_roundtrip_via_pages() {
         let mut fl = FreeList::new();
@@ -284,6 +371,21 @@ mod tests {
         assert_eq!(fl2.len(), ENTRIES_PER_FREELIST_PAGE + 5);
     }
 
+    #[test]
+    fn test_serialize_pages_pads_spare_chain_pages() {
+        let mut fl = FreeList::new();
+        fl.free(7);
+        let pages = fl.serialize_pages(&[10, 11, 12]);
+        assert_eq!(pages.len(), 3);
+        let counts: Vec<u64> = pages
+            .iter()
+            .map(|(_, d)| u64::from_le_bytes(d[12..20].try_into().unwrap()))
+            .collect();
+        assert_eq!(counts, vec![1, 0, 0]);
+        let refs: Vec<&[u8]> = pages.iter().map(|(_, d)| d.as_slice()).collect();
+        assert_eq!(FreeList::deserialize_pages(&refs).pages(), &[7]);
+    }
+
     #[test]
     fn test_non_flmp_data_is_rejected_by_deserialize_pages() {
         // Data without FLMP magic should produce an empty freelist
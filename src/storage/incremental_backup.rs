@@ -0,0 +1,458 @@
+//! Incremental backups: copy only the pages written since a previous backup.
+//!
+//! Every backup returns a [`BackupCursor`] naming the write generation it
+//! closed (see `storage/pager/generations.rs`). An incremental backup taken
+//! from a cursor writes two files into its own directory:
+//!
+//! - `pages`: the on-disk images (still encrypted) of every page stamped
+//!   with a later generation, in page order;
+//! - `manifest`: the cursor it started from, the cursor it ends, the file
+//!   header, and for each page its id, generation and SHA-256, followed by a
+//!   SHA-256 over the whole manifest.
+//!
+//! [`restore_from_incrementals`] applies a chain of them onto the full
+//! backup the first one started from. Each link must start at the cursor the
+//! previous one ended with, so a missing, reordered or foreign link is
+//! refused, and every page and manifest is checked against its checksum
+//! before it is applied.
+//!
+//! Manifest lines are tab separated:
+//! `version 1`, `since <cursor>`, `cursor <cursor>`, `page_count <n>`,
+//! `frame_bytes <n>`, `header <hex>`, then one `page <id> <generation>
+//! <sha256>` per page and a final `checksum <sha256>`.
+
+use std::fmt;
+use std::fs::{File, OpenOptions};
+use std::io::{BufReader, BufWriter, Read, Seek, SeekFrom, Write};
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+
+use sha2::{Digest, Sha256};
+
+use crate::error::{MuroError, Result};
+use crate::storage::page::PageId;
+use crate::storage::pager::Pager;
+use crate::wal::record::crc32;
+
+const MANIFEST_FILE: &str = "manifest";
+const PAGES_FILE: &str = "pages";
+const MANIFEST_VERSION: u32 = 1;
+const CURSOR_VERSION: &str = "1";
+/// Size of the data file header (see `storage/pager/mod.rs`).
+const HEADER_BYTES: usize = 76;
+
+/// Where an incremental backup starts: the state of the database when a
+/// full or incremental backup was taken.
+///
+/// It prints as, and parses from, a single line of text, so it can be
+/// stored next to the backup it came from.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct BackupCursor {
+    salt: [u8; 16],
+    epoch: u64,
+    lineage: [u8; 16],
+    generation: u64,
+    header_crc: u32,
+}
+
+impl BackupCursor {
+    pub(crate) fn new(
+        salt: [u8; 16],
+        epoch: u64,
+        lineage: [u8; 16],
+        generation: u64,
+        header_crc: u32,
+    ) -> Self {
+        BackupCursor {
+            salt,
+            epoch,
+            lineage,
+            generation,
+            header_crc,
+        }
+    }
+
+    /// The write generation the backup closed: later incrementals copy the
+    /// pages stamped with a newer one.
+    pub fn generation(&self) -> u64 {
+        self.generation
+    }
+
+    pub(crate) fn salt(&self) -> [u8; 16] {
+        self.salt
+    }
+
+    pub(crate) fn epoch(&self) -> u64 {
+        self.epoch
+    }
+
+    pub(crate) fn lineage(&self) -> [u8; 16] {
+        self.lineage
+    }
+}
+
+impl fmt::Display for BackupCursor {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{}-{}-{}-{}-{}-{:08x}",
+            CURSOR_VERSION,
+            to_hex(&self.salt),
+            self.epoch,
+            to_hex(&self.lineage),
+            self.generation,
+            self.header_crc
+        )
+    }
+}
+
+impl FromStr for BackupCursor {
+    type Err = MuroError;
+
+    fn from_str(s: &str) -> Result<Self> {
+        let invalid = || MuroError::Execution(format!("invalid backup cursor: {}", s));
+        let fields: Vec<&str> = s.trim().split('-').collect();
+        let [CURSOR_VERSION, salt, epoch, lineage, generation, header_crc] = fields[..] else {
+            return Err(invalid());
+        };
+        Ok(BackupCursor {
+            salt: from_hex(salt).ok_or_else(invalid)?,
+            epoch: epoch.parse().map_err(|_| invalid())?,
+            lineage: from_hex(lineage).ok_or_else(invalid)?,
+            generation: generation.parse().map_err(|_| invalid())?,
+            header_crc: u32::from_str_radix(header_crc, 16).map_err(|_| invalid())?,
+        })
+    }
+}
+
+/// One page of an incremental backup.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ManifestPage {
+    pub page_id: PageId,
+    /// Write generation the page was stamped with.
+    pub generation: u64,
+    /// SHA-256 of the page as stored on disk.
+    pub sha256: [u8; 32],
+}
+
+/// What an incremental backup holds; see the module docs.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct IncrementalManifest {
+    /// Cursor of the backup this one continues.
+    pub since: BackupCursor,
+    /// Cursor of this backup, for the next one.
+    pub cursor: BackupCursor,
+    /// Pages in the database when the backup was taken.
+    pub page_count: u64,
+    /// Bytes of one page on disk.
+    pub frame_bytes: usize,
+    /// Pages written since `since`, in page order.
+    pub pages: Vec<ManifestPage>,
+    header: Vec<u8>,
+}
+
+impl IncrementalManifest {
+    /// Read and verify the manifest of the incremental backup in `dir`.
+    pub fn read(dir: &Path) -> Result<Self> {
+        let path = dir.join(MANIFEST_FILE);
+        let text = std::fs::read_to_string(&path)?;
+        let corrupt = |what: &str| MuroError::Corruption(format!("{}: {}", path.display(), what));
+
+        let body_end = text.trim_end_matches('\n').rfind('\n').map_or(0, |i| i + 1);
+        let (body, last) = text.split_at(body_end);
+        let checksum = last
+            .trim_end()
+            .strip_prefix("checksum\t")
+            .ok_or_else(|| corrupt("missing manifest checksum"))?;
+        if checksum != to_hex(&Sha256::digest(body.as_bytes())) {
+            return Err(corrupt("manifest checksum mismatch"));
+        }
+
+        let mut version = None;
+        let mut since = None;
+        let mut cursor = None;
+        let mut page_count = None;
+        let mut frame_bytes = None;
+        let mut header = None;
+        let mut pages = Vec::new();
+        for line in body.lines() {
+            let bad_line = || corrupt(&format!("malformed line: {}", line));
+            let fields: Vec<&str> = line.split('\t').collect();
+            match fields[..] {
+                ["version", v] => version = Some(v.parse::<u32>().map_err(|_| bad_line())?),
+                ["since", c] => since = Some(c.parse::<BackupCursor>()?),
+                ["cursor", c] => cursor = Some(c.parse::<BackupCursor>()?),
+                ["page_count", n] => page_count = Some(n.parse::<u64>().map_err(|_| bad_line())?),
+                ["frame_bytes", n] => {
+                    frame_bytes = Some(n.parse::<usize>().map_err(|_| bad_line())?)
+                }
+                ["header", h] => header = Some(from_hex::<HEADER_BYTES>(h).ok_or_else(bad_line)?),
+                ["page", id, generation, sha256] => pages.push(ManifestPage {
+                    page_id: id.parse().map_err(|_| bad_line())?,
+                    generation: generation.parse().map_err(|_| bad_line())?,
+                    sha256: from_hex(sha256).ok_or_else(bad_line)?,
+                }),
+                _ => return Err(bad_line()),
+            }
+        }
+        if version != Some(MANIFEST_VERSION) {
+            return Err(corrupt("unsupported manifest version"));
+        }
+        let (Some(since), Some(cursor), Some(page_count), Some(frame_bytes), Some(header)) =
+            (since, cursor, page_count, frame_bytes, header)
+        else {
+            return Err(corrupt("incomplete manifest"));
+        };
+        Ok(IncrementalManifest {
+            since,
+            cursor,
+            page_count,
+            frame_bytes,
+            pages,
+            header: header.to_vec(),
+        })
+    }
+
+    fn write(&self, dir: &Path) -> Result<()> {
+        let mut text = format!(
+            "version\t{}\nsince\t{}\ncursor\t{}\npage_count\t{}\nframe_bytes\t{}\nheader\t{}\n",
+            MANIFEST_VERSION,
+            self.since,
+            self.cursor,
+            self.page_count,
+            self.frame_bytes,
+            to_hex(&self.header)
+        );
+        for page in &self.pages {
+            text.push_str(&format!(
+                "page\t{}\t{}\t{}\n",
+                page.page_id,
+                page.generation,
+                to_hex(&page.sha256)
+            ));
+        }
+        let checksum = to_hex(&Sha256::digest(text.as_bytes()));
+        text.push_str(&format!("checksum\t{}\n", checksum));
+
+        // Written under a temporary name: a directory with a manifest holds
+        // a complete backup.
+        let path = dir.join(MANIFEST_FILE);
+        let tmp = dir.join(format!("{}.tmp", MANIFEST_FILE));
+        let mut file = File::create(&tmp)?;
+        file.write_all(text.as_bytes())?;
+        file.sync_all()?;
+        std::fs::rename(&tmp, &path)?;
+        crate::sync_dir(&path);
+        Ok(())
+    }
+}
+
+/// Write the pages changed since `since` into `dest_dir` and end the
+/// current write generation.
+///
+/// The caller holds the write lock and has checkpointed the WAL.
+pub(crate) fn backup_incremental(
+    pager: &mut Pager,
+    dest_dir: &Path,
+    since: &BackupCursor,
+) -> Result<(IncrementalManifest, BackupCursor)> {
+    std::fs::create_dir_all(dest_dir)?;
+    if dest_dir.join(MANIFEST_FILE).exists() {
+        return Err(MuroError::Execution(format!(
+            "{} already holds an incremental backup",
+            dest_dir.display()
+        )));
+    }
+    let changed = pager.pages_changed_since(since)?;
+    let cursor = pager.close_backup_generation()?;
+    let header = pager.read_raw_header()?;
+
+    let frame_bytes = pager.page_bytes_on_disk();
+    let mut frame = vec![0u8; frame_bytes];
+    let mut writer = BufWriter::with_capacity(64 * 1024, File::create(dest_dir.join(PAGES_FILE))?);
+    let mut pages = Vec::with_capacity(changed.len());
+    for (page_id, generation) in changed {
+        pager.read_raw_page(page_id, &mut frame)?;
+        writer.write_all(&frame)?;
+        pages.push(ManifestPage {
+            page_id,
+            generation,
+            sha256: Sha256::digest(&frame).into(),
+        });
+    }
+    writer
+        .into_inner()
+        .map_err(|e| MuroError::Io(e.into_error()))?
+        .sync_all()?;
+
+    let manifest = IncrementalManifest {
+        since: *since,
+        cursor,
+        page_count: pager.page_count(),
+        frame_bytes,
+        pages,
+        header: header.to_vec(),
+    };
+    manifest.write(dest_dir)?;
+    Ok((manifest, cursor))
+}
+
+/// Rebuild a database at `target` from the full backup `base_backup` and
+/// the incremental backups in `manifest_dirs`, oldest first.
+///
+/// `target` must not exist, and is removed again if the chain is refused.
+pub fn restore_from_incrementals(
+    base_backup: &Path,
+    manifest_dirs: &[&Path],
+    target: &Path,
+) -> Result<()> {
+    if target.exists() {
+        return Err(MuroError::Execution(format!(
+            "restore target {} already exists",
+            target.display()
+        )));
+    }
+    let manifests: Vec<IncrementalManifest> = manifest_dirs
+        .iter()
+        .map(|dir| IncrementalManifest::read(dir))
+        .collect::<Result<_>>()?;
+    check_chain(base_backup, &manifests)?;
+
+    std::fs::copy(base_backup, target)?;
+    let result = apply_manifests(target, manifest_dirs, &manifests);
+    if result.is_err() {
+        let _ = std::fs::remove_file(target);
+    }
+    result
+}
+
+/// Refuse a chain whose links do not follow each other from the base.
+fn check_chain(base_backup: &Path, manifests: &[IncrementalManifest]) -> Result<()> {
+    let mut header = [0u8; HEADER_BYTES];
+    File::open(base_backup)?.read_exact(&mut header)?;
+    let mut expected = BackupCursor::new(
+        header[12..28].try_into().unwrap(),
+        u64::from_le_bytes(header[44..52].try_into().unwrap()),
+        [0u8; 16],
+        0,
+        crc32(&header[..72]),
+    );
+    for (i, manifest) in manifests.iter().enumerate() {
+        let since = &manifest.since;
+        let follows = if i == 0 {
+            (since.salt, since.epoch, since.header_crc)
+                == (expected.salt, expected.epoch, expected.header_crc)
+        } else {
+            *since == expected
+        };
+        if !follows {
+            return Err(MuroError::Execution(format!(
+                "incremental backup {} does not continue {}: it starts at generation {}",
+                i + 1,
+                if i == 0 {
+                    "the base backup".to_string()
+                } else {
+                    format!("backup {} (generation {})", i, expected.generation)
+                },
+                since.generation
+            )));
+        }
+        let cursor = &manifest.cursor;
+        if cursor.lineage != since.lineage || cursor.generation <= since.generation {
+            return Err(MuroError::Corruption(format!(
+                "incremental backup {} ends at generation {}, not after {}",
+                i + 1,
+                cursor.generation,
+                since.generation
+            )));
+        }
+        let mut previous_page = None;
+        for page in &manifest.pages {
+            if page.generation <= since.generation
+                || page.generation > cursor.generation
+                || previous_page.is_some_and(|p| p >= page.page_id)
+                || page.page_id >= manifest.page_count
+            {
+                return Err(MuroError::Corruption(format!(
+                    "incremental backup {} lists page {} out of order or generation",
+                    i + 1,
+                    page.page_id
+                )));
+            }
+            previous_page = Some(page.page_id);
+        }
+        expected = manifest.cursor;
+    }
+    Ok(())
+}
+
+fn apply_manifests(
+    target: &Path,
+    manifest_dirs: &[&Path],
+    manifests: &[IncrementalManifest],
+) -> Result<()> {
+    let mut file = OpenOptions::new().read(true).write(true).open(target)?;
+    for (dir, manifest) in manifest_dirs.iter().zip(manifests) {
+        let pages_path: PathBuf = dir.join(PAGES_FILE);
+        let pages_file = File::open(&pages_path)?;
+        if pages_file.metadata()?.len() != (manifest.pages.len() * manifest.frame_bytes) as u64 {
+            return Err(MuroError::Corruption(format!(
+                "{} does not hold the {} pages its manifest lists",
+                pages_path.display(),
+                manifest.pages.len()
+            )));
+        }
+        let mut reader = BufReader::with_capacity(64 * 1024, pages_file);
+        let mut frame = vec![0u8; manifest.frame_bytes];
+        for page in &manifest.pages {
+            reader.read_exact(&mut frame)?;
+            if <[u8; 32]>::from(Sha256::digest(&frame)) != page.sha256 {
+                return Err(MuroError::Corruption(format!(
+                    "{}: page {} does not match its manifest checksum",
+                    pages_path.display(),
+                    page.page_id
+                )));
+            }
+            let offset = HEADER_BYTES as u64 + page.page_id * manifest.frame_bytes as u64;
+            file.seek(SeekFrom::Start(offset))?;
+            file.write_all(&frame)?;
+        }
+    }
+    if let Some(last) = manifests.last() {
+        file.set_len(HEADER_BYTES as u64 + last.page_count * last.frame_bytes as u64)?;
+        file.seek(SeekFrom::Start(0))?;
+        file.write_all(&last.header)?;
+    }
+    file.sync_all()?;
+    Ok(())
+}
+
+fn to_hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+fn from_hex<const N: usize>(s: &str) -> Option<[u8; N]> {
+    if s.len() != N * 2 {
+        return None;
+    }
+    let mut out = [0u8; N];
+    for (i, byte) in out.iter_mut().enumerate() {
+        *byte = u8::from_str_radix(s.get(i * 2..i * 2 + 2)?, 16).ok()?;
+    }
+    Some(out)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_cursor_round_trips_as_text() {
+        let cursor = BackupCursor::new([1u8; 16], 3, [0xabu8; 16], 42, 0xdead_beef);
+        let text = cursor.to_string();
+        assert!(text.starts_with("1-0101"), "{}", text);
+        assert_eq!(text.parse::<BackupCursor>().unwrap(), cursor);
+        assert!("1-00-3".parse::<BackupCursor>().is_err());
+        assert!(text.replace("-42-", "-x-").parse::<BackupCursor>().is_err());
+    }
+}
@@ -1,4 +1,5 @@
 pub mod freelist;
+pub mod incremental_backup;
 pub mod overflow;
 pub mod page;
 pub mod page_store;
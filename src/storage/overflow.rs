@@ -21,7 +21,13 @@ const OVERFLOW_HEADER_SIZE: usize = 19; // page_id(8) + marker(1) + next_page(8)
 pub const OVERFLOW_CHUNK_SIZE: usize = PAGE_SIZE - OVERFLOW_HEADER_SIZE; // 4077
 
 /// Write data into an overflow page chain. Returns the first page ID.
-pub fn write_overflow_chain(pager: &mut impl PageStore, data: &[u8]) -> Result<PageId> {
+///
+/// The chain is requested as one run near `hint`, so it reads sequentially.
+pub fn write_overflow_chain(
+    pager: &mut impl PageStore,
+    data: &[u8],
+    hint: PageId,
+) -> Result<PageId> {
     if data.is_empty() {
         return Err(MuroError::Internal(
             "cannot write empty overflow chain".into(),
@@ -30,11 +36,7 @@ pub fn write_overflow_chain(pager: &mut impl PageStore, data: &[u8]) -> Result<P
 
     // Split data into chunks and allocate pages
     let chunks: Vec<&[u8]> = data.chunks(OVERFLOW_CHUNK_SIZE).collect();
-    let mut pages: Vec<Page> = Vec::with_capacity(chunks.len());
-
-    for _ in 0..chunks.len() {
-        pages.push(pager.allocate_page()?);
-    }
+    let mut pages: Vec<Page> = pager.allocate_pages_near(chunks.len(), hint)?;
 
     let first_page_id = pages[0].page_id();
 
@@ -177,7 +179,7 @@ mod tests {
         let (mut pager, path) = setup();
         let data = vec![0xABu8; 100];
 
-        let first_page = write_overflow_chain(&mut pager, &data).unwrap();
+        let first_page = write_overflow_chain(&mut pager, &data, 0).unwrap();
         let result = read_overflow_chain(&mut pager, first_page, data.len() as u32).unwrap();
         assert_eq!(result, data);
 
@@ -190,7 +192,7 @@ mod tests {
         // 10000 bytes needs 3 pages (4077 + 4077 + 1846)
         let data = vec![0xCDu8; 10000];
 
-        let first_page = write_overflow_chain(&mut pager, &data).unwrap();
+        let first_page = write_overflow_chain(&mut pager, &data, 0).unwrap();
         let pages = collect_overflow_pages(&mut pager, first_page).unwrap();
         assert_eq!(pages.len(), 3);
 
@@ -205,7 +207,7 @@ mod tests {
         let (mut pager, path) = setup();
         let data = vec![0xABu8; 10000];
 
-        let first_page = write_overflow_chain(&mut pager, &data).unwrap();
+        let first_page = write_overflow_chain(&mut pager, &data, 0).unwrap();
         let pages = collect_overflow_pages(&mut pager, first_page).unwrap();
         assert_eq!(pages.len(), 3);
 
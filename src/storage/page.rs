@@ -1,3 +1,5 @@
+use zeroize::Zeroize;
+
 /// Slotted page implementation (4096 bytes).
 ///
 /// Layout:
@@ -18,7 +20,7 @@ pub const CELL_HEADER_SIZE: usize = 2; // u16 length prefix
 
 pub type PageId = u64;
 
-#[derive(Clone)]
+#[derive(Clone, Zeroize)]
 pub struct Page {
     pub data: [u8; PAGE_SIZE],
 }
@@ -91,6 +93,12 @@ impl Page {
         }
     }
 
+    /// Bytes between the cell pointer array and the cell data: room for new
+    /// cells without compacting the page.
+    pub fn contiguous_free_space(&self) -> usize {
+        (self.free_end() as usize).saturating_sub(self.free_start() as usize)
+    }
+
     /// Insert a cell payload into the page. Returns the cell index.
     pub fn insert_cell(&mut self, payload: &[u8]) -> crate::error::Result<u16> {
         let total_cell_size = CELL_HEADER_SIZE + payload.len();
@@ -121,6 +129,55 @@ impl Page {
         Ok(cell_idx)
     }
 
+    /// Insert a cell payload at `index`, shifting the pointers of the cells
+    /// from `index` on one slot right. The page is unchanged on error.
+    pub fn insert_cell_at(&mut self, index: u16, payload: &[u8]) -> crate::error::Result<()> {
+        let count = self.cell_count();
+        if index > count {
+            return Err(crate::error::MuroError::InvalidPage);
+        }
+        let cell_idx = self.insert_cell(payload)?;
+        if index < cell_idx {
+            let offset = self
+                .cell_pointer(cell_idx)
+                .ok_or(crate::error::MuroError::InvalidPage)?;
+            let start = PAGE_HEADER_SIZE + (index as usize) * CELL_POINTER_SIZE;
+            let end = PAGE_HEADER_SIZE + (cell_idx as usize) * CELL_POINTER_SIZE;
+            self.data.copy_within(start..end, start + CELL_POINTER_SIZE);
+            self.set_cell_pointer(index, offset);
+        }
+        Ok(())
+    }
+
+    /// Replace the payload of cell `index`.
+    ///
+    /// A payload of the same length overwrites the old bytes. Otherwise it is
+    /// written into the contiguous free space and the cell pointer moved to
+    /// it; the old bytes stay behind as dead space until the page is rebuilt.
+    /// Fails with `PageOverflow`, leaving the page unchanged, when there is
+    /// not enough contiguous room.
+    pub fn replace_cell(&mut self, index: u16, payload: &[u8]) -> crate::error::Result<()> {
+        let (start, len) = self
+            .cell_offset_and_len(index)
+            .ok_or(crate::error::MuroError::InvalidPage)?;
+        if len == payload.len() {
+            self.data[start..start + len].copy_from_slice(payload);
+            return Ok(());
+        }
+
+        let total_cell_size = CELL_HEADER_SIZE + payload.len();
+        if self.contiguous_free_space() < total_cell_size {
+            return Err(crate::error::MuroError::PageOverflow);
+        }
+        let cell_offset = self.free_end() as usize - total_cell_size;
+        let len = payload.len() as u16;
+        self.data[cell_offset..cell_offset + 2].copy_from_slice(&len.to_le_bytes());
+        self.data[cell_offset + 2..cell_offset + 2 + payload.len()].copy_from_slice(payload);
+        self.set_cell_pointer(index, cell_offset as u16);
+        self.set_free_end(cell_offset as u16);
+        Ok(())
+    }
+
     /// Get cell payload by index.
     pub fn cell(&self, index: u16) -> Option<&[u8]> {
         if index >= self.cell_count() {
@@ -251,6 +308,46 @@ mod tests {
         assert_eq!(page.cell(1), Some(b"ccc".as_slice()));
     }
 
+    #[test]
+    fn test_insert_cell_at_keeps_order() {
+        let mut page = Page::new(1);
+        page.insert_cell(b"aaa").unwrap();
+        page.insert_cell(b"ccc").unwrap();
+        page.insert_cell_at(1, b"bbb").unwrap();
+        page.insert_cell_at(0, b"_").unwrap();
+        page.insert_cell_at(4, b"dddd").unwrap();
+        let cells: Vec<&[u8]> = (0..page.cell_count())
+            .map(|i| page.cell(i).unwrap())
+            .collect();
+        assert_eq!(cells, [&b"_"[..], b"aaa", b"bbb", b"ccc", b"dddd"]);
+        assert!(page.insert_cell_at(6, b"x").is_err());
+    }
+
+    #[test]
+    fn test_replace_cell_in_place_and_relocated() {
+        let mut page = Page::new(1);
+        page.insert_cell(b"aaa").unwrap();
+        page.insert_cell(b"bbb").unwrap();
+        let free_end = page.free_end();
+
+        // Same length: written over the old bytes.
+        page.replace_cell(0, b"xyz").unwrap();
+        assert_eq!(page.cell(0), Some(b"xyz".as_slice()));
+        assert_eq!(page.free_end(), free_end);
+
+        // Different length: moved into the free space.
+        page.replace_cell(1, b"longer").unwrap();
+        assert_eq!(page.cell(1), Some(b"longer".as_slice()));
+        assert_eq!(page.cell(0), Some(b"xyz".as_slice()));
+        assert!(page.free_end() < free_end);
+
+        // No contiguous room: the page is left unchanged.
+        let big = vec![7u8; page.contiguous_free_space()];
+        let before = *page.as_bytes();
+        assert!(page.replace_cell(0, &big).is_err());
+        assert_eq!(page.as_bytes(), &before);
+    }
+
     #[test]
     fn test_page_overflow() {
         let mut page = Page::new(1);
@@ -263,11 +360,8 @@ mod tests {
         let mut page = Page::new(1);
         let cell_data = vec![0u8; 32];
         let mut count = 0u16;
-        loop {
-            match page.insert_cell(&cell_data) {
-                Ok(_) => count += 1,
-                Err(_) => break,
-            }
+        while page.insert_cell(&cell_data).is_ok() {
+            count += 1;
         }
         assert!(count > 50); // should fit many 32-byte cells
         assert_eq!(page.cell_count(), count);
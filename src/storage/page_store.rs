@@ -1,3 +1,4 @@
+use crate::crypto::hmac_util::TermKey;
 use crate::error::Result;
 use crate::storage::page::{Page, PageId};
 
@@ -8,6 +9,27 @@ pub trait PageStore {
     fn read_page(&mut self, page_id: PageId) -> Result<Page>;
     fn write_page(&mut self, page: &Page) -> Result<()>;
     fn allocate_page(&mut self) -> Result<Page>;
+    /// Allocate a page, preferring free pages numerically close to `hint`
+    /// so pages of one B-tree stay clustered. Falls back to any free page.
+    fn allocate_page_near(&mut self, hint: PageId) -> Result<Page> {
+        let _ = hint;
+        self.allocate_page()
+    }
+    /// Allocate `count` pages, as one contiguous run when the store can.
+    fn allocate_pages_near(&mut self, count: usize, hint: PageId) -> Result<Vec<Page>> {
+        let mut pages = Vec::with_capacity(count);
+        let mut hint = hint;
+        for _ in 0..count {
+            let page = self.allocate_page_near(hint)?;
+            hint = page.page_id();
+            pages.push(page);
+        }
+        Ok(pages)
+    }
     fn free_page(&mut self, page_id: PageId);
-    fn fts_term_key(&self) -> Result<[u8; 32]>;
+    fn fts_term_key(&self) -> Result<TermKey>;
+    /// Pager cache `(hits, misses)` so far; EXPLAIN ANALYZE reports deltas.
+    fn cache_counters(&self) -> (u64, u64) {
+        (0, 0)
+    }
 }
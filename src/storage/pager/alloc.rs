@@ -0,0 +1,119 @@
+//! Allocation policy that keeps each B-tree's pages clustered.
+//!
+//! Hinted allocations first look for a free page in the hint's extent (an
+//! `EXTENT_PAGES`-aligned block), then for a completely free extent. When the
+//! freelist holds nothing but the spare pages of earlier claims, a fresh
+//! extent is claimed at the end of the file: the first page is returned and
+//! the rest go on the freelist, where later allocations hinted into the same
+//! extent find them. Otherwise the free page closest to the hint is reused,
+//! so hints never grow the file while real free pages exist.
+
+use crate::error::Result;
+use crate::storage::page::{Page, PageId};
+
+use super::Pager;
+
+/// Pages per allocation extent (64 KiB at the 4 KiB page size).
+pub const EXTENT_PAGES: u64 = 16;
+
+/// Claimed-but-unused extent pages allowed on the freelist before hinted
+/// allocations start taking each other's spares.
+const MAX_EXTENT_SPARES: usize = 128;
+
+impl Pager {
+    /// Whether `allocate_page_near` / `allocate_pages_near` honor their hint.
+    pub fn allocation_hints(&self) -> bool {
+        self.allocation_hints
+    }
+
+    /// Turn allocation hints off (plain freelist-then-append allocation) or on.
+    pub fn set_allocation_hints(&mut self, enabled: bool) {
+        self.allocation_hints = enabled;
+    }
+
+    /// Allocate a page close to `hint`. Spare pages of a freshly claimed
+    /// extent are written out empty right away, since free pages must exist
+    /// in the file.
+    pub fn allocate_page_near(&mut self, hint: PageId) -> Result<Page> {
+        let (page, spares) = self.allocate_near_with_spares(hint)?;
+        for spare in spares {
+            self.write_page(&Page::new(spare))?;
+        }
+        Ok(page)
+    }
+
+    /// Like `allocate_page_near`, but leaves writing the spare pages of a
+    /// claimed extent to the caller (a transaction buffers them).
+    pub(crate) fn allocate_near_with_spares(
+        &mut self,
+        hint: PageId,
+    ) -> Result<(Page, Vec<PageId>)> {
+        if !self.allocation_hints {
+            return Ok((self.allocate_page()?, Vec::new()));
+        }
+        let extent_start = hint - hint % EXTENT_PAGES;
+        let pick = self
+            .freelist
+            .nearest(hint, extent_start..extent_start + EXTENT_PAGES)
+            .or_else(|| self.freelist.nearest_free_extent(hint, EXTENT_PAGES));
+        if let Some(page_id) = pick {
+            return Ok((self.take_free_page(page_id), Vec::new()));
+        }
+        if !self.should_claim_extent() {
+            if let Some(page_id) = self.freelist.nearest(hint, 0..PageId::MAX) {
+                return Ok((self.take_free_page(page_id), Vec::new()));
+            }
+        }
+
+        // Claim the rest of the extent at the end of the file.
+        let start = self.page_count;
+        let end = (start / EXTENT_PAGES + 1) * EXTENT_PAGES;
+        self.page_count = end;
+        let spares: Vec<PageId> = (start + 1..end).collect();
+        // Pushed in reverse so plain stack pops also hand them out in order.
+        for &spare in spares.iter().rev() {
+            self.freelist.free(spare);
+            self.extent_spares.insert(spare);
+        }
+        Ok((Page::new(start), spares))
+    }
+
+    /// Allocate `count` pages as one run: the contiguous free run closest to
+    /// `hint`, or new pages at the end of the file.
+    pub fn allocate_pages_near(&mut self, count: usize, hint: PageId) -> Result<Vec<Page>> {
+        if !self.allocation_hints {
+            return (0..count).map(|_| self.allocate_page()).collect();
+        }
+        if count == 1 {
+            return Ok(vec![self.allocate_page_near(hint)?]);
+        }
+        match self.freelist.nearest_run(count, hint) {
+            Some(start) => Ok((start..start + count as u64)
+                .map(|page_id| self.take_free_page(page_id))
+                .collect()),
+            None => {
+                let start = self.page_count;
+                self.page_count += count as u64;
+                Ok((start..self.page_count).map(Page::new).collect())
+            }
+        }
+    }
+
+    fn take_free_page(&mut self, page_id: PageId) -> Page {
+        self.freelist.take(page_id);
+        self.extent_spares.remove(&page_id);
+        Page::new(page_id)
+    }
+
+    /// A new extent is claimed only while every free page is a spare of an
+    /// earlier claim and there are not too many of them.
+    fn should_claim_extent(&self) -> bool {
+        let spares = self
+            .freelist
+            .pages()
+            .iter()
+            .filter(|p| self.extent_spares.contains(p))
+            .count();
+        spares == self.freelist.len() && spares < MAX_EXTENT_SPARES
+    }
+}
@@ -1,11 +1,13 @@
 use std::fs::OpenOptions;
-use std::io::{Read, Seek, SeekFrom, Write};
+use std::io::{BufWriter, Read, Seek, SeekFrom, Write};
 use std::path::Path;
 
 use crate::crypto::aead::MasterKey;
 use crate::crypto::suite::{EncryptionSuite, PageCipher};
 use crate::error::{MuroError, Result};
-use crate::storage::page::PAGE_SIZE;
+use crate::storage::incremental_backup::BackupCursor;
+use crate::storage::page::{PageId, PAGE_SIZE};
+use crate::wal::record::crc32;
 
 use super::rekey_marker::write_rekey_marker;
 use super::{rekey_marker_path, Pager, PLAINTEXT_HEADER_SIZE};
@@ -34,6 +36,7 @@ impl Pager {
         let new_epoch = self.epoch + 1;
         let new_crypto = PageCipher::new(self.encryption_suite, Some(new_key))?;
         let old_epoch = self.epoch;
+        let page_size_on_disk = self.page_size_on_disk();
 
         // Write .rekey marker file for crash recovery
         let marker_path = rekey_marker_path(&self.path);
@@ -41,33 +44,18 @@ impl Pager {
 
         // Re-encrypt all pages
         let page_count = self.page_count;
-        let page_size_on_disk = self.page_size_on_disk();
+        let mut new_encrypted = vec![0u8; page_size_on_disk];
         for page_id in 0..page_count {
-            // Read with current crypto/epoch
-            let offset = PLAINTEXT_HEADER_SIZE + page_id * page_size_on_disk as u64;
-            self.file.seek(SeekFrom::Start(offset))?;
-            let mut encrypted = vec![0u8; page_size_on_disk];
-            self.file.read_exact(&mut encrypted)?;
-
-            let mut plaintext = [0u8; PAGE_SIZE];
-            let plaintext_len =
-                self.crypto
-                    .decrypt_into(page_id, old_epoch, &encrypted, &mut plaintext)?;
-            if plaintext_len != PAGE_SIZE {
-                return Err(MuroError::InvalidPage);
-            }
-
-            // Re-encrypt with new crypto/epoch
-            let mut new_encrypted = vec![0u8; page_size_on_disk];
-            let written =
-                new_crypto.encrypt_into(page_id, new_epoch, &plaintext, &mut new_encrypted)?;
-            if written != page_size_on_disk {
-                return Err(MuroError::Encryption(
-                    "unexpected encrypted page size during rekey".to_string(),
-                ));
-            }
+            self.reencrypt_page(
+                page_id,
+                old_epoch,
+                &new_crypto,
+                new_epoch,
+                &mut new_encrypted,
+            )?;
 
             // Write back
+            let offset = PLAINTEXT_HEADER_SIZE + page_id * page_size_on_disk as u64;
             self.file.seek(SeekFrom::Start(offset))?;
             self.file.write_all(&new_encrypted)?;
         }
@@ -88,6 +76,10 @@ impl Pager {
         // Remove marker file
         let _ = std::fs::remove_file(&marker_path);
 
+        // The sidecar belongs to the old salt; the next write starts a new
+        // lineage, as no earlier backup cursor applies any more.
+        self.generations = None;
+
         // Clear page cache since all pages changed
         self.cache.clear();
 
@@ -104,20 +96,7 @@ impl Pager {
     /// The resulting file is a valid MuroDB database that can be opened
     /// with the same key/password.
     pub fn backup_to_file(&mut self, dest: &Path) -> Result<()> {
-        // Guard: reject backup to the same file (including symlinks/hardlinks).
-        {
-            use std::os::unix::fs::MetadataExt;
-            let src_meta = self.file.metadata()?;
-            if let Ok(dest_meta) = std::fs::metadata(dest) {
-                if src_meta.dev() == dest_meta.dev() && src_meta.ino() == dest_meta.ino() {
-                    return Err(MuroError::Io(std::io::Error::new(
-                        std::io::ErrorKind::InvalidInput,
-                        "backup destination is the same file as the source database",
-                    )));
-                }
-            }
-        }
-
+        self.check_backup_destination(dest)?;
         self.refresh_from_disk_if_changed()?;
 
         let total_bytes = PLAINTEXT_HEADER_SIZE + self.page_count * self.page_size_on_disk() as u64;
@@ -144,4 +123,181 @@ impl Pager {
 
         Ok(())
     }
+
+    /// Copy the database to `dest`, re-encrypting every page under `new_key`.
+    ///
+    /// The result is a standalone database whose header carries `new_salt`,
+    /// so it opens only with the key derived from that salt. Pages are
+    /// streamed one at a time. The header is written last: until the copy
+    /// completes, `dest` has no magic and is rejected by `open`.
+    ///
+    /// Same preconditions as [`Pager::backup_to_file`].
+    pub fn backup_to_file_with_key(
+        &mut self,
+        dest: &Path,
+        new_key: &MasterKey,
+        new_salt: [u8; 16],
+    ) -> Result<()> {
+        if self.encryption_suite == EncryptionSuite::Plaintext {
+            return Err(MuroError::Execution(
+                "re-encrypting backup is not supported for plaintext databases".to_string(),
+            ));
+        }
+        self.check_backup_destination(dest)?;
+        self.refresh_from_disk_if_changed()?;
+
+        let mut header = self.read_raw_header()?;
+
+        let new_crypto = PageCipher::new(self.encryption_suite, Some(new_key))?;
+        let epoch = self.epoch;
+        let page_size_on_disk = self.page_size_on_disk();
+
+        let dest_file = OpenOptions::new()
+            .write(true)
+            .create(true)
+            .truncate(true)
+            .open(dest)?;
+        let mut writer = BufWriter::with_capacity(64 * 1024, dest_file);
+        writer.write_all(&[0u8; PLAINTEXT_HEADER_SIZE as usize])?;
+
+        let mut encrypted = vec![0u8; page_size_on_disk];
+        for page_id in 0..self.page_count {
+            #[cfg(any(test, feature = "test-utils"))]
+            if self.inject_backup_failure_at_page == Some(page_id) {
+                writer.flush()?;
+                return Err(MuroError::Io(std::io::Error::other(
+                    "injected backup failure",
+                )));
+            }
+            self.reencrypt_page(page_id, epoch, &new_crypto, epoch, &mut encrypted)?;
+            writer.write_all(&encrypted)?;
+        }
+        let mut dest_file = writer
+            .into_inner()
+            .map_err(|e| MuroError::Io(e.into_error()))?;
+        dest_file.sync_data()?;
+
+        header[12..28].copy_from_slice(&new_salt);
+        let checksum = crc32(&header[0..72]);
+        header[72..76].copy_from_slice(&checksum.to_le_bytes());
+        dest_file.seek(SeekFrom::Start(0))?;
+        dest_file.write_all(&header)?;
+        dest_file.sync_all()?;
+
+        Ok(())
+    }
+
+    /// Read a page as stored on disk and encrypt it under `target` into `out`.
+    fn reencrypt_page(
+        &mut self,
+        page_id: PageId,
+        epoch: u64,
+        target: &PageCipher,
+        target_epoch: u64,
+        out: &mut [u8],
+    ) -> Result<()> {
+        let page_size_on_disk = self.page_size_on_disk();
+        let offset = PLAINTEXT_HEADER_SIZE + page_id * page_size_on_disk as u64;
+        self.file.seek(SeekFrom::Start(offset))?;
+        let mut encrypted = vec![0u8; page_size_on_disk];
+        self.file.read_exact(&mut encrypted)?;
+
+        let mut plaintext = [0u8; PAGE_SIZE];
+        let plaintext_len = self
+            .crypto
+            .decrypt_into(page_id, epoch, &encrypted, &mut plaintext)
+            .map_err(|e| super::page_decrypt_error(e, page_id))?;
+        if plaintext_len != PAGE_SIZE {
+            return Err(MuroError::InvalidPage);
+        }
+
+        let written = target.encrypt_into(page_id, target_epoch, &plaintext, out)?;
+        if written != out.len() {
+            return Err(MuroError::Encryption(
+                "unexpected encrypted page size during re-encryption".to_string(),
+            ));
+        }
+        Ok(())
+    }
+
+    /// End the current write generation for a backup taken now, and return
+    /// the cursor that later incremental backups start from.
+    ///
+    /// Same preconditions as [`Pager::backup_to_file`], which must copy the
+    /// file before any further write.
+    pub fn close_backup_generation(&mut self) -> Result<BackupCursor> {
+        self.refresh_from_disk_if_changed()?;
+        let header = self.read_raw_header()?;
+        let (lineage, generation) = self.page_generations()?.close_generation()?;
+        Ok(BackupCursor::new(
+            self.salt,
+            self.epoch,
+            lineage,
+            generation,
+            crc32(&header[..72]),
+        ))
+    }
+
+    /// Pages written after the backup `since` was taken, with their
+    /// generations, in page order.
+    pub fn pages_changed_since(&mut self, since: &BackupCursor) -> Result<Vec<(PageId, u64)>> {
+        self.refresh_from_disk_if_changed()?;
+        if since.salt() != self.salt || since.epoch() != self.epoch {
+            return Err(MuroError::Execution(
+                "backup cursor belongs to another database or key; take a full backup".into(),
+            ));
+        }
+        let page_count = self.page_count;
+        let generations = self.page_generations()?;
+        if since.lineage() != generations.lineage() {
+            return Err(MuroError::Execution(
+                "page generations were reset since this backup cursor; take a full backup".into(),
+            ));
+        }
+        Ok(generations
+            .page_generations(page_count)?
+            .into_iter()
+            .enumerate()
+            .filter(|&(_, generation)| generation > since.generation())
+            .map(|(page_id, generation)| (page_id as PageId, generation))
+            .collect())
+    }
+
+    /// The file header as stored on disk.
+    pub fn read_raw_header(&mut self) -> Result<[u8; PLAINTEXT_HEADER_SIZE as usize]> {
+        let mut header = [0u8; PLAINTEXT_HEADER_SIZE as usize];
+        self.file.seek(SeekFrom::Start(0))?;
+        self.file.read_exact(&mut header)?;
+        Ok(header)
+    }
+
+    /// A page as stored on disk, still encrypted, into `out` (one on-disk
+    /// page long).
+    pub fn read_raw_page(&mut self, page_id: PageId, out: &mut [u8]) -> Result<()> {
+        let offset = PLAINTEXT_HEADER_SIZE + page_id * out.len() as u64;
+        self.file.seek(SeekFrom::Start(offset))?;
+        self.file.read_exact(out)?;
+        Ok(())
+    }
+
+    /// Bytes a page takes in the data file.
+    pub fn page_bytes_on_disk(&self) -> usize {
+        self.page_size_on_disk()
+    }
+
+    /// Reject a backup destination that is the source file itself
+    /// (including symlinks/hardlinks).
+    fn check_backup_destination(&self, dest: &Path) -> Result<()> {
+        use std::os::unix::fs::MetadataExt;
+        let src_meta = self.file.metadata()?;
+        if let Ok(dest_meta) = std::fs::metadata(dest) {
+            if src_meta.dev() == dest_meta.dev() && src_meta.ino() == dest_meta.ino() {
+                return Err(MuroError::Io(std::io::Error::new(
+                    std::io::ErrorKind::InvalidInput,
+                    "backup destination is the same file as the source database",
+                )));
+            }
+        }
+        Ok(())
+    }
 }
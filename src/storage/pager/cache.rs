@@ -0,0 +1,368 @@
+use lru::LruCache;
+use std::num::NonZeroUsize;
+use zeroize::Zeroize;
+
+use crate::btree::node::{node_type, NodeType};
+use crate::error::{MuroError, Result};
+use crate::storage::page::{Page, PageId, PAGE_SIZE};
+
+/// Share of the cache capacity reserved for B-tree internal pages.
+const PINNED_FRACTION: usize = 8;
+
+/// A cached page in an allocation of its own, aligned to the page size, so
+/// that locking it in memory covers exactly this page and unlocking it does
+/// not unlock a neighbour.
+#[repr(C, align(4096))]
+struct CachedPage(Page);
+
+const _: () = assert!(std::mem::size_of::<CachedPage>() == PAGE_SIZE);
+
+/// Decrypted page cache.
+///
+/// B-tree internal pages are few but sit on nearly every lookup path, so they
+/// get their own LRU tier that leaf and overflow churn (e.g. a large scan)
+/// cannot flush. Internal pages evicted from that tier fall back to the
+/// shared tier like any other page. Capacities smaller than
+/// `PINNED_FRACTION` pages use the shared tier only.
+///
+/// Pages leaving the cache (evicted, removed, cleared or dropped with it) are
+/// zeroed, so decrypted pages do not linger in freed memory. With
+/// [`PageCache::set_locked`] every cached page is also locked in memory and
+/// never written to swap.
+pub(super) struct PageCache {
+    main: LruCache<PageId, Box<CachedPage>>,
+    pinned: Option<LruCache<PageId, Box<CachedPage>>>,
+    capacity: usize,
+    locked: bool,
+}
+
+impl PageCache {
+    pub(super) fn new(capacity: usize) -> Self {
+        let capacity = capacity.max(1);
+        let pinned_cap = capacity / PINNED_FRACTION;
+        PageCache {
+            main: LruCache::new(NonZeroUsize::new(capacity - pinned_cap).unwrap()),
+            pinned: NonZeroUsize::new(pinned_cap).map(LruCache::new),
+            capacity,
+            locked: false,
+        }
+    }
+
+    pub(super) fn get(&mut self, page_id: &PageId) -> Option<&Page> {
+        if let Some(pinned) = self.pinned.as_mut() {
+            if pinned.contains(page_id) {
+                return pinned.get(page_id).map(|cached| &cached.0);
+            }
+        }
+        self.main.get(page_id).map(|cached| &cached.0)
+    }
+
+    /// Insert or replace a page. Returns the number of pages evicted.
+    ///
+    /// While the cache is locked, a page that cannot be locked in memory is
+    /// not cached.
+    pub(super) fn insert(&mut self, page: Page) -> u64 {
+        let cached = Box::new(CachedPage(page));
+        if self.locked && !memlock::lock(&cached) {
+            self.release(cached);
+            return 0;
+        }
+        self.insert_cached(cached)
+    }
+
+    fn insert_cached(&mut self, cached: Box<CachedPage>) -> u64 {
+        let page_id = cached.0.page_id();
+        // A page id can change role (e.g. a freed leaf reused as an internal
+        // node), so never keep it in both tiers.
+        self.remove(&page_id);
+        let (page_id, cached) = match self.pinned.as_mut() {
+            Some(pinned) if node_type(&cached.0) == Some(NodeType::Internal) => {
+                match pinned.push(page_id, cached) {
+                    Some(spilled) => spilled,
+                    None => return 0,
+                }
+            }
+            _ => (page_id, cached),
+        };
+        match self.main.push(page_id, cached) {
+            Some((evicted_id, evicted)) => {
+                self.release(evicted);
+                u64::from(evicted_id != page_id)
+            }
+            None => 0,
+        }
+    }
+
+    pub(super) fn remove(&mut self, page_id: &PageId) {
+        if let Some(cached) = self.pinned.as_mut().and_then(|pinned| pinned.pop(page_id)) {
+            self.release(cached);
+        }
+        if let Some(cached) = self.main.pop(page_id) {
+            self.release(cached);
+        }
+    }
+
+    pub(super) fn clear(&mut self) {
+        while let Some(cached) = self.pop_oldest() {
+            self.release(cached);
+        }
+    }
+
+    pub(super) fn len(&self) -> usize {
+        self.main.len() + self.pinned.as_ref().map_or(0, LruCache::len)
+    }
+
+    pub(super) fn capacity(&self) -> usize {
+        self.capacity
+    }
+
+    /// Re-split the cache for a new capacity, keeping the most recently used
+    /// pages. Returns the number of pages dropped.
+    pub(super) fn resize(&mut self, capacity: usize) -> u64 {
+        let before = self.len();
+        let mut pages: Vec<Box<CachedPage>> = Vec::with_capacity(before);
+        // Oldest first, so the most recently used pages are inserted last.
+        while let Some(cached) = self.main.pop_lru() {
+            pages.push(cached.1);
+        }
+        if let Some(pinned) = self.pinned.as_mut() {
+            while let Some(cached) = pinned.pop_lru() {
+                pages.push(cached.1);
+            }
+        }
+        let locked = self.locked;
+        *self = PageCache::new(capacity);
+        self.locked = locked;
+        for cached in pages {
+            self.insert_cached(cached);
+        }
+        before.saturating_sub(self.len()) as u64
+    }
+
+    pub(super) fn is_locked(&self) -> bool {
+        self.locked
+    }
+
+    /// Lock every cached page, and each page cached from now on, in memory
+    /// (`mlock`), or unlock them again.
+    ///
+    /// Fails, leaving the cache unlocked, where the platform cannot lock
+    /// memory or the process may not lock the whole cache capacity.
+    pub(super) fn set_locked(&mut self, locked: bool) -> Result<()> {
+        if locked == self.locked {
+            return Ok(());
+        }
+        if !locked {
+            self.for_each_cached(memlock::unlock);
+            self.locked = false;
+            return Ok(());
+        }
+        memlock::check_capacity(self.capacity)?;
+        let mut failed = None;
+        self.for_each_cached(|cached| {
+            if failed.is_none() && !memlock::lock(cached) {
+                failed = Some(std::io::Error::last_os_error());
+            }
+        });
+        if let Some(e) = failed {
+            self.for_each_cached(memlock::unlock);
+            return Err(MuroError::Io(e));
+        }
+        self.locked = true;
+        Ok(())
+    }
+
+    fn for_each_cached(&self, mut f: impl FnMut(&CachedPage)) {
+        for (_, cached) in self.main.iter() {
+            f(cached);
+        }
+        if let Some(pinned) = self.pinned.as_ref() {
+            for (_, cached) in pinned.iter() {
+                f(cached);
+            }
+        }
+    }
+
+    fn pop_oldest(&mut self) -> Option<Box<CachedPage>> {
+        if let Some((_, cached)) = self.main.pop_lru() {
+            return Some(cached);
+        }
+        self.pinned
+            .as_mut()
+            .and_then(|pinned| pinned.pop_lru())
+            .map(|(_, cached)| cached)
+    }
+
+    /// Zero a page leaving the cache, then unlock it if the cache is locked.
+    fn release(&self, mut cached: Box<CachedPage>) {
+        cached.0.zeroize();
+        if self.locked {
+            memlock::unlock(&cached);
+        }
+    }
+}
+
+impl Drop for PageCache {
+    fn drop(&mut self) {
+        self.clear();
+    }
+}
+
+/// `mlock` / `munlock` of single cached pages.
+#[cfg(unix)]
+mod memlock {
+    use super::CachedPage;
+    use crate::error::{MuroError, Result};
+    use crate::storage::page::PAGE_SIZE;
+
+    pub(super) fn check_capacity(capacity: usize) -> Result<()> {
+        // SAFETY: sysconf only reads a configuration value.
+        let os_page_size = unsafe { libc::sysconf(libc::_SC_PAGESIZE) };
+        if os_page_size <= 0 || os_page_size as usize > PAGE_SIZE {
+            return Err(MuroError::Execution(format!(
+                "cannot lock the page cache with {}-byte memory pages",
+                os_page_size
+            )));
+        }
+        let mut limit = libc::rlimit {
+            rlim_cur: 0,
+            rlim_max: 0,
+        };
+        // SAFETY: getrlimit writes only into `limit`.
+        if unsafe { libc::getrlimit(libc::RLIMIT_MEMLOCK, &mut limit) } != 0 {
+            return Err(MuroError::Io(std::io::Error::last_os_error()));
+        }
+        let needed = (capacity as libc::rlim_t).saturating_mul(PAGE_SIZE as libc::rlim_t);
+        if limit.rlim_cur != libc::RLIM_INFINITY && limit.rlim_cur < needed {
+            return Err(MuroError::Execution(format!(
+                "locking {} cached pages needs {} bytes, over the memlock limit of {} bytes",
+                capacity, needed, limit.rlim_cur
+            )));
+        }
+        Ok(())
+    }
+
+    pub(super) fn lock(cached: &CachedPage) -> bool {
+        // SAFETY: the range is the live, page-aligned allocation of `cached`.
+        unsafe {
+            libc::mlock(
+                cached as *const CachedPage as *const libc::c_void,
+                PAGE_SIZE,
+            ) == 0
+        }
+    }
+
+    pub(super) fn unlock(cached: &CachedPage) {
+        // SAFETY: as in `lock`. Each cached page owns its memory pages, so
+        // no other page is unlocked with it.
+        unsafe {
+            libc::munlock(
+                cached as *const CachedPage as *const libc::c_void,
+                PAGE_SIZE,
+            );
+        }
+    }
+}
+
+#[cfg(not(unix))]
+mod memlock {
+    use super::CachedPage;
+    use crate::error::{MuroError, Result};
+
+    pub(super) fn check_capacity(_capacity: usize) -> Result<()> {
+        Err(MuroError::Execution(
+            "locking the page cache in memory is not supported on this platform".to_string(),
+        ))
+    }
+
+    pub(super) fn lock(_cached: &CachedPage) -> bool {
+        false
+    }
+
+    pub(super) fn unlock(_cached: &CachedPage) {}
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::btree::node::{init_internal, init_leaf};
+
+    fn leaf(id: PageId) -> Page {
+        let mut page = Page::new(id);
+        init_leaf(&mut page);
+        page
+    }
+
+    fn internal(id: PageId) -> Page {
+        let mut page = Page::new(id);
+        init_internal(&mut page, 0);
+        page
+    }
+
+    #[test]
+    fn test_internal_pages_survive_leaf_churn() {
+        let mut cache = PageCache::new(16);
+        cache.insert(internal(1));
+        cache.insert(internal(2));
+        for id in 100..200 {
+            cache.insert(leaf(id));
+        }
+        assert!(cache.get(&1).is_some());
+        assert!(cache.get(&2).is_some());
+        assert_eq!(cache.len(), 16);
+    }
+
+    #[test]
+    fn test_pinned_overflow_spills_into_main_tier() {
+        let mut cache = PageCache::new(16);
+        // Two pinned slots: the oldest internal page moves to the main tier.
+        for id in 1..=3 {
+            cache.insert(internal(id));
+        }
+        assert_eq!(cache.len(), 3);
+        assert!(cache.get(&1).is_some());
+    }
+
+    #[test]
+    fn test_page_changing_role_is_cached_once() {
+        let mut cache = PageCache::new(16);
+        cache.insert(leaf(7));
+        cache.insert(internal(7));
+        assert_eq!(cache.len(), 1);
+        assert_eq!(node_type(cache.get(&7).unwrap()), Some(NodeType::Internal));
+        cache.remove(&7);
+        assert!(cache.get(&7).is_none());
+    }
+
+    #[test]
+    fn test_small_capacity_has_no_pinned_tier() {
+        let mut cache = PageCache::new(4);
+        cache.insert(internal(1));
+        for id in 10..14 {
+            cache.insert(leaf(id));
+        }
+        assert!(cache.get(&1).is_none());
+        assert_eq!(cache.resize(1), 3);
+        assert_eq!(cache.len(), 1);
+        assert!(cache.get(&13).is_some());
+    }
+
+    #[test]
+    fn test_locked_cache_stays_locked_across_resize() {
+        let mut cache = PageCache::new(16);
+        cache.insert(leaf(1));
+        if cache.set_locked(true).is_err() {
+            // No mlock on this platform, or too low a memlock limit.
+            assert!(!cache.is_locked());
+            return;
+        }
+        cache.insert(internal(2));
+        assert_eq!(cache.resize(8), 0);
+        assert!(cache.is_locked());
+        assert!(cache.get(&1).is_some());
+        assert!(cache.get(&2).is_some());
+        cache.set_locked(false).unwrap();
+        assert!(!cache.is_locked());
+        assert_eq!(cache.len(), 2);
+    }
+}
@@ -0,0 +1,313 @@
+//! Write generations of pages, for incremental backups.
+//!
+//! Every page image the pager writes to the data file is stamped with the
+//! current write generation in a sidecar file next to the database
+//! (`<db>.pagegen`). A backup closes the current generation and opens the
+//! next, so the pages stamped with a generation past a backup's are exactly
+//! those written since it. The page format has no spare header field, which
+//! is why the stamps live beside the file instead of in the pages.
+//!
+//! The stamps are fsynced before the data file in [`Pager::flush_meta`], so a
+//! durable page always has a durable stamp. Pages written to the data file
+//! but never covered by a `flush_meta` are replayed from the WAL, and get
+//! stamped again, when the database is next opened.
+//!
+//! The sidecar remembers the next transaction id of the data file header it
+//! was last synced with. When the two disagree on open (the data file was
+//! replaced, or a crash came between the header write and the stamp), the
+//! floor is raised to the current generation: every page counts as written
+//! after every earlier backup, and the next incremental copies them all. A
+//! missing or unreadable sidecar starts a new lineage, which no earlier
+//! backup cursor accepts.
+//!
+//! [`Pager::flush_meta`]: super::Pager::flush_meta
+
+use std::fs::{File, OpenOptions};
+use std::io::{Read, Seek, SeekFrom, Write};
+use std::path::{Path, PathBuf};
+
+use rand::RngCore;
+
+use crate::error::Result;
+use crate::storage::page::PageId;
+use crate::wal::record::crc32;
+
+/// Sidecar layout:
+///   0..8    Magic "MUROPGEN"
+///   8..24   Database salt
+///   24..40  Lineage id (random, new for every new sidecar)
+///   40..48  Current write generation (u64 LE)
+///   48..56  Floor generation: no page counts as written before it (u64 LE)
+///   56..64  Next TxId of the data file header at the last sync (u64 LE)
+///   64..68  CRC32 of bytes 0..64
+///   68..72  Reserved
+///   72..    One u64 LE generation per page id (0 = not stamped)
+const MAGIC: &[u8; 8] = b"MUROPGEN";
+const HEADER_SIZE: u64 = 72;
+const SLOT_SIZE: u64 = 8;
+
+/// Path of the page generation sidecar of the database at `db_path`.
+pub fn page_generations_path(db_path: &Path) -> PathBuf {
+    let mut s = db_path.as_os_str().to_os_string();
+    s.push(".pagegen");
+    PathBuf::from(s)
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct Header {
+    salt: [u8; 16],
+    lineage: [u8; 16],
+    generation: u64,
+    floor: u64,
+    synced_next_txid: u64,
+}
+
+impl Header {
+    fn encode(&self) -> [u8; HEADER_SIZE as usize] {
+        let mut buf = [0u8; HEADER_SIZE as usize];
+        buf[0..8].copy_from_slice(MAGIC);
+        buf[8..24].copy_from_slice(&self.salt);
+        buf[24..40].copy_from_slice(&self.lineage);
+        buf[40..48].copy_from_slice(&self.generation.to_le_bytes());
+        buf[48..56].copy_from_slice(&self.floor.to_le_bytes());
+        buf[56..64].copy_from_slice(&self.synced_next_txid.to_le_bytes());
+        let checksum = crc32(&buf[0..64]);
+        buf[64..68].copy_from_slice(&checksum.to_le_bytes());
+        buf
+    }
+
+    fn decode(buf: &[u8; HEADER_SIZE as usize]) -> Option<Self> {
+        if &buf[0..8] != MAGIC {
+            return None;
+        }
+        if u32::from_le_bytes(buf[64..68].try_into().unwrap()) != crc32(&buf[0..64]) {
+            return None;
+        }
+        Some(Header {
+            salt: buf[8..24].try_into().unwrap(),
+            lineage: buf[24..40].try_into().unwrap(),
+            generation: u64::from_le_bytes(buf[40..48].try_into().unwrap()),
+            floor: u64::from_le_bytes(buf[48..56].try_into().unwrap()),
+            synced_next_txid: u64::from_le_bytes(buf[56..64].try_into().unwrap()),
+        })
+    }
+}
+
+/// The open page generation sidecar of a writable pager.
+pub(super) struct PageGenerations {
+    file: File,
+    header: Header,
+    /// Stamps written since the last fsync.
+    dirty: bool,
+}
+
+impl PageGenerations {
+    /// Open the sidecar of the database at `db_path`, creating or resetting
+    /// it as described in the module docs. `salt` and `next_txid` are those
+    /// of the data file header as it is on disk.
+    pub(super) fn open(db_path: &Path, salt: [u8; 16], next_txid: u64) -> Result<Self> {
+        let mut file = OpenOptions::new()
+            .read(true)
+            .write(true)
+            .create(true)
+            .truncate(false)
+            .open(page_generations_path(db_path))?;
+        let header = match read_header(&mut file)? {
+            Some(header) if header.salt == salt => {
+                if header.synced_next_txid == next_txid {
+                    header
+                } else {
+                    Header {
+                        floor: header.generation,
+                        synced_next_txid: next_txid,
+                        ..header
+                    }
+                }
+            }
+            _ => {
+                let mut lineage = [0u8; 16];
+                rand::thread_rng().fill_bytes(&mut lineage);
+                file.set_len(HEADER_SIZE)?;
+                Header {
+                    salt,
+                    lineage,
+                    generation: 1,
+                    floor: 1,
+                    synced_next_txid: next_txid,
+                }
+            }
+        };
+        let mut generations = PageGenerations {
+            file,
+            header,
+            dirty: false,
+        };
+        generations.write_header()?;
+        generations.file.sync_all()?;
+        Ok(generations)
+    }
+
+    /// Re-read the header: another handle may have closed a generation.
+    fn refresh(&mut self) -> Result<()> {
+        if let Some(header) = read_header(&mut self.file)? {
+            if header.salt == self.header.salt {
+                self.header = header;
+            }
+        }
+        Ok(())
+    }
+
+    fn write_header(&mut self) -> Result<()> {
+        self.file.seek(SeekFrom::Start(0))?;
+        self.file.write_all(&self.header.encode())?;
+        Ok(())
+    }
+
+    /// Stamp runs of consecutive page ids with the current generation.
+    pub(super) fn stamp<I>(&mut self, runs: I) -> Result<()>
+    where
+        I: IntoIterator<Item = (PageId, usize)>,
+    {
+        self.refresh()?;
+        let stamp = self.header.generation.to_le_bytes();
+        let mut buf = Vec::new();
+        for (first, len) in runs {
+            buf.clear();
+            for _ in 0..len {
+                buf.extend_from_slice(&stamp);
+            }
+            self.file
+                .seek(SeekFrom::Start(HEADER_SIZE + first * SLOT_SIZE))?;
+            self.file.write_all(&buf)?;
+            self.dirty = true;
+        }
+        Ok(())
+    }
+
+    /// Fsync stamps written since the last call; done before the data file
+    /// is synced.
+    pub(super) fn sync_stamps(&mut self) -> Result<()> {
+        if self.dirty {
+            self.file.sync_data()?;
+            self.dirty = false;
+        }
+        Ok(())
+    }
+
+    /// Record the next transaction id just synced into the data file header.
+    pub(super) fn note_header_synced(&mut self, next_txid: u64) -> Result<()> {
+        self.refresh()?;
+        if self.header.synced_next_txid != next_txid {
+            self.header.synced_next_txid = next_txid;
+            self.write_header()?;
+        }
+        Ok(())
+    }
+
+    /// Close the current generation and durably open the next one. Returns
+    /// the lineage id and the closed generation.
+    pub(super) fn close_generation(&mut self) -> Result<([u8; 16], u64)> {
+        self.refresh()?;
+        let closed = self.header.generation;
+        self.header.generation += 1;
+        self.write_header()?;
+        self.file.sync_all()?;
+        Ok((self.header.lineage, closed))
+    }
+
+    pub(super) fn lineage(&self) -> [u8; 16] {
+        self.header.lineage
+    }
+
+    /// Generation of every page below `page_count`, raised to the floor.
+    pub(super) fn page_generations(&mut self, page_count: u64) -> Result<Vec<u64>> {
+        self.refresh()?;
+        let mut slots = Vec::new();
+        self.file.seek(SeekFrom::Start(HEADER_SIZE))?;
+        (&mut self.file)
+            .take(page_count * SLOT_SIZE)
+            .read_to_end(&mut slots)?;
+        let floor = self.header.floor;
+        Ok((0..page_count as usize)
+            .map(|i| {
+                let stamp = slots
+                    .get(i * SLOT_SIZE as usize..(i + 1) * SLOT_SIZE as usize)
+                    .map_or(0, |s| u64::from_le_bytes(s.try_into().unwrap()));
+                stamp.max(floor)
+            })
+            .collect())
+    }
+}
+
+/// The header, or `None` when the file is too short or not a valid sidecar.
+fn read_header(file: &mut File) -> Result<Option<Header>> {
+    let mut buf = [0u8; HEADER_SIZE as usize];
+    file.seek(SeekFrom::Start(0))?;
+    let mut filled = 0;
+    while filled < buf.len() {
+        match file.read(&mut buf[filled..])? {
+            0 => return Ok(None),
+            n => filled += n,
+        }
+    }
+    Ok(Header::decode(&buf))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_stamps_follow_generations() {
+        let dir = TempDir::new().unwrap();
+        let db_path = dir.path().join("test.db");
+        let salt = [7u8; 16];
+        let mut gens = PageGenerations::open(&db_path, salt, 5).unwrap();
+        gens.stamp([(0, 3)]).unwrap();
+        let (lineage, closed) = gens.close_generation().unwrap();
+        assert_eq!(closed, 1);
+        gens.stamp([(1, 1), (4, 1)]).unwrap();
+        gens.sync_stamps().unwrap();
+        gens.note_header_synced(6).unwrap();
+        // Unstamped pages count from the floor.
+        assert_eq!(gens.page_generations(6).unwrap(), [1, 2, 1, 1, 2, 1]);
+
+        // Reopening against the header it was synced with keeps everything.
+        let mut gens = PageGenerations::open(&db_path, salt, 6).unwrap();
+        assert_eq!(gens.lineage(), lineage);
+        assert_eq!(gens.page_generations(5).unwrap(), [1, 2, 1, 1, 2]);
+    }
+
+    #[test]
+    fn test_header_mismatch_raises_floor_and_bad_file_resets_lineage() {
+        let dir = TempDir::new().unwrap();
+        let db_path = dir.path().join("test.db");
+        let salt = [7u8; 16];
+        let mut gens = PageGenerations::open(&db_path, salt, 5).unwrap();
+        gens.close_generation().unwrap();
+        gens.close_generation().unwrap();
+        gens.stamp([(0, 1)]).unwrap();
+        let lineage = gens.lineage();
+        drop(gens);
+
+        // A data file header the sidecar was not synced with.
+        let mut gens = PageGenerations::open(&db_path, salt, 9).unwrap();
+        assert_eq!(gens.lineage(), lineage);
+        assert_eq!(gens.page_generations(2).unwrap(), [3, 3]);
+        drop(gens);
+
+        // Another database's salt, or a torn header, starts over.
+        let mut gens = PageGenerations::open(&db_path, [8u8; 16], 9).unwrap();
+        assert_ne!(gens.lineage(), lineage);
+        assert_eq!(gens.page_generations(2).unwrap(), [1, 1]);
+        let lineage = gens.lineage();
+        drop(gens);
+        let path = page_generations_path(&db_path);
+        let mut bytes = std::fs::read(&path).unwrap();
+        bytes[41] ^= 1;
+        std::fs::write(&path, bytes).unwrap();
+        let gens = PageGenerations::open(&db_path, [8u8; 16], 9).unwrap();
+        assert_ne!(gens.lineage(), lineage);
+    }
+}
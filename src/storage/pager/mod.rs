@@ -1,28 +1,42 @@
+use std::collections::{BTreeMap, HashSet};
 use std::fs::{File, OpenOptions};
 use std::io::{Read, Seek, SeekFrom, Write};
 use std::path::{Path, PathBuf};
 
-use lru::LruCache;
-use std::num::NonZeroUsize;
-
 use crate::crypto::aead::MasterKey;
-use crate::crypto::hmac_util::{derive_fts_term_key, derive_fts_term_key_plaintext};
+use crate::crypto::hmac_util::{derive_fts_term_key, derive_fts_term_key_plaintext, TermKey};
+use crate::crypto::kdf::generate_salt;
 use crate::crypto::suite::{EncryptionSuite, PageCipher};
 use crate::error::{MuroError, Result};
 use crate::storage::freelist::{FreeList, SanitizeReport};
 use crate::storage::page::{Page, PageId, PAGE_SIZE};
 use crate::wal::record::crc32;
+use zeroize::Zeroize;
 
+mod alloc;
 mod backup_rekey;
+mod cache;
+mod generations;
 mod rekey_marker;
+mod scan;
+mod shrink;
+
+use cache::PageCache;
+use generations::PageGenerations;
 
-pub use rekey_marker::{read_rekey_marker, rekey_marker_path, unwrap_rekey_old_key};
+#[cfg(feature = "test-utils")]
+pub use generations::page_generations_path;
+pub use rekey_marker::rekey_marker_path;
+#[cfg(feature = "sql")]
+pub use rekey_marker::{read_rekey_marker, unwrap_rekey_old_key};
+#[cfg(feature = "sql")]
+pub use scan::{PageScanReport, UnreadablePage};
 
 /// Plaintext file header size (written before any encrypted pages).
 /// Layout:
 ///   0..8    Magic "MURODB01"
 ///   8..12   Format version (u32 LE) — currently 5
-///   12..28  Salt (16 bytes, for Argon2 KDF)
+///   12..28  Salt (16 bytes, for Argon2 KDF; also the instance id in WAL headers)
 ///   28..36  Catalog root page ID (u64 LE)
 ///   36..44  Page count (u64 LE)
 ///   44..52  Epoch (u64 LE)
@@ -36,8 +50,12 @@ const FORMAT_VERSION: u32 = 5;
 /// Previous format version that is read-compatible (no overflow cells in v4 databases).
 const FORMAT_VERSION_COMPAT: u32 = 4;
 
-/// Default LRU cache capacity.
-const DEFAULT_CACHE_CAPACITY: usize = 256;
+/// Default LRU cache capacity, in pages.
+pub const DEFAULT_CACHE_CAPACITY: usize = 256;
+
+/// Longest run of consecutive pages written with one call, bounding the
+/// buffer a large commit needs (1 MiB of 4 KiB pages).
+const MAX_WRITE_RUN_PAGES: usize = 256;
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 struct HeaderSnapshot {
@@ -63,7 +81,7 @@ pub struct Pager {
     path: PathBuf,
     crypto: PageCipher,
     master_key: Option<MasterKey>,
-    fts_term_key: Option<[u8; 32]>,
+    fts_term_key: Option<TermKey>,
     encryption_suite: EncryptionSuite,
     page_count: u64,
     epoch: u64,
@@ -72,15 +90,39 @@ pub struct Pager {
     freelist: FreeList,
     freelist_page_id: u64,
     next_txid: u64,
-    cache: LruCache<PageId, Page>,
+    cache: PageCache,
     cache_hits: u64,
     cache_misses: u64,
+    cache_evictions: u64,
+    pages_decrypted: u64,
+    pages_written: u64,
+    /// Positioned writes of page images to the data file; each covers a run
+    /// of consecutive page ids.
+    data_write_runs: u64,
+    /// Page images written to the data file.
+    data_pages_flushed: u64,
+    /// Committed pages whose WAL records are not fsynced yet, held back from
+    /// the data file until they are (see `WalDurability`).
+    deferred_writes: BTreeMap<PageId, Page>,
     /// Diagnostics from freelist sanitization during open.
     freelist_sanitize_report: Option<SanitizeReport>,
+    /// Whether `allocate_page_near` honors its hint (see `alloc.rs`).
+    allocation_hints: bool,
+    /// Free pages that were pre-allocated with a claimed extent, not freed.
+    extent_spares: HashSet<PageId>,
+    /// Whether the data file was opened for writing.
+    writable: bool,
+    /// Page generation sidecar, opened on the first write (see
+    /// `generations.rs`).
+    generations: Option<PageGenerations>,
     #[cfg(any(test, feature = "test-utils"))]
     inject_write_page_failure: Option<std::io::ErrorKind>,
     #[cfg(any(test, feature = "test-utils"))]
     inject_flush_meta_failure: Option<std::io::ErrorKind>,
+    #[cfg(any(test, feature = "test-utils"))]
+    inject_backup_failure_at_page: Option<PageId>,
+    #[cfg(any(test, feature = "test-utils"))]
+    fault: Option<crate::fault::FaultInjector>,
 }
 
 impl Pager {
@@ -93,9 +135,10 @@ impl Pager {
         Self::create_with_suite(path, EncryptionSuite::Aes256GcmSiv, Some(master_key), salt)
     }
 
-    /// Create a new database in plaintext mode.
+    /// Create a new database in plaintext mode. The salt is unused for key
+    /// derivation but still random, so it identifies the database instance.
     pub fn create_plaintext(path: &Path) -> Result<Self> {
-        Self::create_with_suite(path, EncryptionSuite::Plaintext, None, [0u8; 16])
+        Self::create_with_suite(path, EncryptionSuite::Plaintext, None, generate_salt())
     }
 
     /// Create a new database file with a specific encryption suite.
@@ -112,7 +155,7 @@ impl Pager {
             .open(path)?;
 
         let crypto = PageCipher::new(suite, master_key)?;
-        let cache = LruCache::new(NonZeroUsize::new(DEFAULT_CACHE_CAPACITY).unwrap());
+        let cache = PageCache::new(DEFAULT_CACHE_CAPACITY);
 
         let bootstrap_fts_term_key = if let Some(k) = master_key {
             derive_fts_term_key(k, &salt)
@@ -137,11 +180,25 @@ impl Pager {
             cache,
             cache_hits: 0,
             cache_misses: 0,
+            cache_evictions: 0,
+            pages_decrypted: 0,
+            pages_written: 0,
+            data_write_runs: 0,
+            data_pages_flushed: 0,
+            deferred_writes: BTreeMap::new(),
             freelist_sanitize_report: None,
+            allocation_hints: true,
+            extent_spares: HashSet::new(),
+            writable: true,
+            generations: None,
             #[cfg(any(test, feature = "test-utils"))]
             inject_write_page_failure: None,
             #[cfg(any(test, feature = "test-utils"))]
             inject_flush_meta_failure: None,
+            #[cfg(any(test, feature = "test-utils"))]
+            inject_backup_failure_at_page: None,
+            #[cfg(any(test, feature = "test-utils"))]
+            fault: None,
         };
 
         // Write the plaintext header
@@ -150,9 +207,9 @@ impl Pager {
         Ok(pager)
     }
 
-    /// Create a new database file (legacy API, generates a zero salt).
+    /// Create a new database file with a raw master key and a random salt.
     pub fn create(path: &Path, master_key: &MasterKey) -> Result<Self> {
-        Self::create_with_salt(path, master_key, [0u8; 16])
+        Self::create_with_salt(path, master_key, generate_salt())
     }
 
     /// Open an existing database file.
@@ -171,7 +228,55 @@ impl Pager {
         expected_suite: Option<EncryptionSuite>,
         master_key: Option<&MasterKey>,
     ) -> Result<Self> {
-        let file = OpenOptions::new().read(true).write(true).open(path)?;
+        Self::open_file(path, expected_suite, master_key, true)
+    }
+
+    /// Open an existing database file without write access. Any attempt to
+    /// write a page or the header fails with an I/O error.
+    pub fn open_read_only_with_suite(
+        path: &Path,
+        expected_suite: Option<EncryptionSuite>,
+        master_key: Option<&MasterKey>,
+    ) -> Result<Self> {
+        Self::open_file(path, expected_suite, master_key, false)
+    }
+
+    /// Open an existing database file for WAL replay: the header only. Page 0
+    /// and the freelist are not read, since a write torn by a crash may have
+    /// left them unreadable until the WAL rewrites them.
+    pub(crate) fn open_for_recovery(
+        path: &Path,
+        expected_suite: Option<EncryptionSuite>,
+        master_key: Option<&MasterKey>,
+    ) -> Result<Self> {
+        Self::open_header(path, expected_suite, master_key, true)
+    }
+
+    fn open_file(
+        path: &Path,
+        expected_suite: Option<EncryptionSuite>,
+        master_key: Option<&MasterKey>,
+        writable: bool,
+    ) -> Result<Self> {
+        let mut pager = Self::open_header(path, expected_suite, master_key, writable)?;
+
+        // Verify that decryption works by reading page 0 if there are pages
+        if pager.page_count > 0 {
+            let _page0 = pager.read_page_from_disk(0)?;
+        }
+
+        pager.reload_freelist_from_disk()?;
+
+        Ok(pager)
+    }
+
+    fn open_header(
+        path: &Path,
+        expected_suite: Option<EncryptionSuite>,
+        master_key: Option<&MasterKey>,
+        writable: bool,
+    ) -> Result<Self> {
+        let file = OpenOptions::new().read(true).write(writable).open(path)?;
         let mut probe_file = file.try_clone()?;
         let snapshot = Self::read_plaintext_header_snapshot_from_file(&mut probe_file)?;
 
@@ -186,7 +291,7 @@ impl Pager {
         }
 
         let crypto = PageCipher::new(snapshot.encryption_suite, master_key)?;
-        let cache = LruCache::new(NonZeroUsize::new(DEFAULT_CACHE_CAPACITY).unwrap());
+        let cache = PageCache::new(DEFAULT_CACHE_CAPACITY);
 
         let mut pager = Pager {
             file,
@@ -205,23 +310,29 @@ impl Pager {
             cache,
             cache_hits: 0,
             cache_misses: 0,
+            cache_evictions: 0,
+            pages_decrypted: 0,
+            pages_written: 0,
+            data_write_runs: 0,
+            data_pages_flushed: 0,
+            deferred_writes: BTreeMap::new(),
             freelist_sanitize_report: None,
+            allocation_hints: true,
+            extent_spares: HashSet::new(),
+            writable,
+            generations: None,
             #[cfg(any(test, feature = "test-utils"))]
             inject_write_page_failure: None,
             #[cfg(any(test, feature = "test-utils"))]
             inject_flush_meta_failure: None,
+            #[cfg(any(test, feature = "test-utils"))]
+            inject_backup_failure_at_page: None,
+            #[cfg(any(test, feature = "test-utils"))]
+            fault: None,
         };
 
         pager.read_plaintext_header()?;
         pager.fts_term_key = Some(pager.derive_bootstrap_fts_term_key());
-
-        // Verify that decryption works by reading page 0 if there are pages
-        if pager.page_count > 0 {
-            let _page0 = pager.read_page_from_disk(0)?;
-        }
-
-        pager.reload_freelist_from_disk()?;
-
         Ok(pager)
     }
 
@@ -265,11 +376,34 @@ impl Pager {
         let checksum = crc32(&header[0..72]);
         header[72..76].copy_from_slice(&checksum.to_le_bytes());
 
+        self.admit_write(header.len(), true)?;
         self.file.seek(SeekFrom::Start(0))?;
         self.file.write_all(&header)?;
         Ok(())
     }
 
+    /// Bytes of a `len` byte write that reach the file: all of them, unless
+    /// a fault injector crashes here. An `atomic` write fits in one sector
+    /// and is only ever dropped, not torn.
+    fn admit_write(&self, len: usize, atomic: bool) -> Result<usize> {
+        #[cfg(any(test, feature = "test-utils"))]
+        if let Some(fault) = &self.fault {
+            return fault.admit(crate::fault::FaultFile::Data, len, atomic);
+        }
+        let _ = atomic;
+        Ok(len)
+    }
+
+    /// Fsync the data file.
+    fn sync_file(&mut self) -> Result<()> {
+        #[cfg(any(test, feature = "test-utils"))]
+        if let Some(fault) = &self.fault {
+            fault.check()?;
+        }
+        self.file.sync_all()?;
+        Ok(())
+    }
+
     /// Read the plaintext file header.
     fn read_plaintext_header(&mut self) -> Result<()> {
         let snapshot = self.read_plaintext_header_snapshot()?;
@@ -415,6 +549,11 @@ impl Pager {
     ///
     /// Returns `Ok(true)` when metadata changed and local cache was invalidated.
     pub fn refresh_from_disk_if_changed(&mut self) -> Result<bool> {
+        // With deferred writes the on-disk header is behind by design; this
+        // handle is the writer that moved ahead of it.
+        if !self.deferred_writes.is_empty() {
+            return Ok(false);
+        }
         let snapshot = self.read_plaintext_header_snapshot()?;
         if snapshot.salt != self.salt {
             return Err(MuroError::Corruption(
@@ -446,6 +585,7 @@ impl Pager {
     /// Allocate a new page. Returns a fresh Page with the assigned page_id.
     pub fn allocate_page(&mut self) -> Result<Page> {
         let page_id = if let Some(free_id) = self.freelist.allocate() {
+            self.extent_spares.remove(&free_id);
             free_id
         } else {
             let id = self.page_count;
@@ -459,12 +599,16 @@ impl Pager {
 
     /// Free a page, returning it to the freelist.
     pub fn free_page(&mut self, page_id: PageId) {
-        self.cache.pop(&page_id);
+        self.cache.remove(&page_id);
         self.freelist.free(page_id);
     }
 
     /// Read a page (from cache or disk).
     pub fn read_page(&mut self, page_id: PageId) -> Result<Page> {
+        if let Some(page) = self.deferred_writes.get(&page_id) {
+            self.cache_hits = self.cache_hits.saturating_add(1);
+            return Ok(page.clone());
+        }
         if let Some(page) = self.cache.get(&page_id) {
             self.cache_hits = self.cache_hits.saturating_add(1);
             return Ok(page.clone());
@@ -472,12 +616,29 @@ impl Pager {
         self.cache_misses = self.cache_misses.saturating_add(1);
 
         let page = self.read_page_from_disk(page_id)?;
-        self.cache.put(page_id, page.clone());
+        self.cache_page(page.clone());
         Ok(page)
     }
 
+    /// Insert a page into the cache, counting capacity evictions.
+    ///
+    /// Cached pages are always committed: writes go to disk (or to the deferred
+    /// write set) before they are cached, and uncommitted transaction pages
+    /// live in `Transaction`'s dirty buffer.
+    fn cache_page(&mut self, page: Page) {
+        let evicted = self.cache.insert(page);
+        self.cache_evictions = self.cache_evictions.saturating_add(evicted);
+    }
+
     /// Write a page (to cache and disk).
     pub fn write_page(&mut self, page: &Page) -> Result<()> {
+        self.write_pages([page])
+    }
+
+    /// Write pages (to cache and disk) in ascending page order, with one
+    /// write per run of consecutive page ids. A page listed more than once
+    /// is written once, with its last image.
+    pub fn write_pages<'a>(&mut self, pages: impl IntoIterator<Item = &'a Page>) -> Result<()> {
         #[cfg(any(test, feature = "test-utils"))]
         if let Some(kind) = self.inject_write_page_failure {
             return Err(MuroError::Io(std::io::Error::new(
@@ -485,8 +646,50 @@ impl Pager {
                 "injected write_page failure",
             )));
         }
-        self.write_page_to_disk(page)?;
-        self.cache.put(page.page_id(), page.clone());
+        let by_id: BTreeMap<PageId, &Page> = pages
+            .into_iter()
+            .map(|page| (page.page_id(), page))
+            .collect();
+        let sorted: Vec<&Page> = by_id.into_values().collect();
+        self.write_sorted_pages_to_disk(&sorted)?;
+        for page in sorted {
+            self.pages_written = self.pages_written.saturating_add(1);
+            if let Some(mut deferred) = self.deferred_writes.remove(&page.page_id()) {
+                deferred.zeroize();
+            }
+            self.cache_page(page.clone());
+        }
+        Ok(())
+    }
+
+    /// Record a committed page without writing it to the data file yet.
+    ///
+    /// Reads see the page immediately; [`Pager::write_deferred_pages`] writes
+    /// it out once the WAL records covering it are durable.
+    pub fn defer_write_page(&mut self, page: &Page) {
+        if let Some(mut replaced) = self.deferred_writes.insert(page.page_id(), page.clone()) {
+            replaced.zeroize();
+        }
+        self.cache_page(page.clone());
+    }
+
+    /// Whether committed pages are waiting to be written to the data file.
+    pub fn has_deferred_writes(&self) -> bool {
+        !self.deferred_writes.is_empty()
+    }
+
+    /// Write all deferred pages to the data file, in page order, with one
+    /// write per run of consecutive page ids.
+    ///
+    /// Does not write the header; callers follow up with `flush_meta`.
+    pub fn write_deferred_pages(&mut self) -> Result<()> {
+        let mut pages = std::mem::take(&mut self.deferred_writes);
+        let sorted: Vec<&Page> = pages.values().collect();
+        if let Err(e) = self.write_sorted_pages_to_disk(&sorted) {
+            self.deferred_writes = pages;
+            return Err(e);
+        }
+        pages.values_mut().for_each(Zeroize::zeroize);
         Ok(())
     }
 
@@ -500,34 +703,66 @@ impl Pager {
         self.file.read_exact(&mut encrypted)?;
 
         let mut plaintext = [0u8; PAGE_SIZE];
-        let plaintext_len =
-            self.crypto
-                .decrypt_into(page_id, self.epoch, &encrypted, &mut plaintext)?;
+        let plaintext_len = self
+            .crypto
+            .decrypt_into(page_id, self.epoch, &encrypted, &mut plaintext)
+            .map_err(|e| page_decrypt_error(e, page_id))?;
 
         if plaintext_len != PAGE_SIZE {
             return Err(MuroError::InvalidPage);
         }
 
+        self.pages_decrypted = self.pages_decrypted.saturating_add(1);
         Ok(Page::from_bytes(plaintext))
     }
 
-    /// Encrypt a page and write it to disk.
-    fn write_page_to_disk(&mut self, page: &Page) -> Result<()> {
-        let page_id = page.page_id();
+    /// Encrypt pages sorted by page id (without duplicates) and write them to
+    /// disk, one positioned write per run of consecutive page ids.
+    fn write_sorted_pages_to_disk(&mut self, pages: &[&Page]) -> Result<()> {
         let page_size_on_disk = self.page_size_on_disk();
-        let mut encrypted = vec![0u8; page_size_on_disk];
-        let written =
-            self.crypto
-                .encrypt_into(page_id, self.epoch, page.as_bytes(), &mut encrypted)?;
-        if written != page_size_on_disk {
-            return Err(MuroError::Encryption(
-                "unexpected encrypted page size".to_string(),
-            ));
+        let page_ids: Vec<PageId> = pages.iter().map(|page| page.page_id()).collect();
+        let runs = contiguous_runs(&page_ids, MAX_WRITE_RUN_PAGES);
+        let mut allowed = match pages.len() {
+            0 => 0,
+            n => self.admit_write(n * page_size_on_disk, false)?,
+        };
+        if self.writable {
+            self.page_generations()?
+                .stamp(runs.iter().map(|run| (page_ids[run.start], run.len())))?;
         }
+        let mut buf = Vec::new();
+        for run in runs {
+            buf.clear();
+            buf.resize(run.len() * page_size_on_disk, 0);
+            for (page, encrypted) in pages[run.clone()]
+                .iter()
+                .zip(buf.chunks_exact_mut(page_size_on_disk))
+            {
+                let written = self.crypto.encrypt_into(
+                    page.page_id(),
+                    self.epoch,
+                    page.as_bytes(),
+                    encrypted,
+                )?;
+                if written != page_size_on_disk {
+                    return Err(MuroError::Encryption(
+                        "unexpected encrypted page size".to_string(),
+                    ));
+                }
+            }
 
-        let offset = PLAINTEXT_HEADER_SIZE + page_id * page_size_on_disk as u64;
-        self.file.seek(SeekFrom::Start(offset))?;
-        self.file.write_all(&encrypted)?;
+            let offset = PLAINTEXT_HEADER_SIZE + page_ids[run.start] * page_size_on_disk as u64;
+            self.file.seek(SeekFrom::Start(offset))?;
+            if buf.len() > allowed {
+                // Torn by an injected crash.
+                self.file.write_all(&buf[..allowed])?;
+                return Err(MuroError::Io(std::io::Error::other("injected crash")));
+            }
+            allowed -= buf.len();
+            self.file.write_all(&buf)?;
+            self.data_write_runs = self.data_write_runs.saturating_add(1);
+            self.data_pages_flushed = self.data_pages_flushed.saturating_add(run.len() as u64);
+        }
         Ok(())
     }
 
@@ -540,11 +775,30 @@ impl Pager {
                 "injected flush_meta failure",
             )));
         }
+        if self.writable {
+            self.page_generations()?.sync_stamps()?;
+        }
         self.write_plaintext_header()?;
-        self.file.sync_all()?;
+        self.sync_file()?;
+        if let Some(generations) = self.generations.as_mut() {
+            generations.note_header_synced(self.next_txid)?;
+        }
         Ok(())
     }
 
+    /// The page generation sidecar, opened against the header on disk.
+    fn page_generations(&mut self) -> Result<&mut PageGenerations> {
+        if self.generations.is_none() {
+            let on_disk = self.read_plaintext_header_snapshot()?;
+            self.generations = Some(PageGenerations::open(
+                &self.path,
+                on_disk.salt,
+                on_disk.next_txid,
+            )?);
+        }
+        Ok(self.generations.as_mut().unwrap())
+    }
+
     /// Get current page count.
     pub fn page_count(&self) -> u64 {
         self.page_count
@@ -560,6 +814,72 @@ impl Pager {
         self.cache_misses
     }
 
+    /// Number of pages evicted from the cache to stay within capacity.
+    pub fn cache_evictions(&self) -> u64 {
+        self.cache_evictions
+    }
+
+    /// Number of page images read from disk and decrypted since pager
+    /// open/create. Unlike `cache_misses`, this also counts reads that bypass
+    /// the cache (e.g. the freelist at open).
+    pub fn pages_decrypted(&self) -> u64 {
+        self.pages_decrypted
+    }
+
+    /// Number of pages written through `write_page` / `write_pages` since
+    /// pager open/create.
+    pub fn pages_written(&self) -> u64 {
+        self.pages_written
+    }
+
+    /// Number of positioned writes of page images to the data file since
+    /// pager open/create; consecutive pages share one write.
+    pub fn data_write_runs(&self) -> u64 {
+        self.data_write_runs
+    }
+
+    /// Number of page images written to the data file since pager
+    /// open/create, including deferred pages written at a batch flush.
+    pub fn data_pages_flushed(&self) -> u64 {
+        self.data_pages_flushed
+    }
+
+    /// Approximate memory held by cached page images.
+    pub fn cache_bytes(&self) -> u64 {
+        (self.cache.len() * PAGE_SIZE) as u64
+    }
+
+    /// Maximum number of pages kept in the cache.
+    pub fn cache_capacity(&self) -> usize {
+        self.cache.capacity()
+    }
+
+    /// Resize the page cache. Shrinking evicts least-recently-used pages.
+    /// A capacity of 0 is treated as 1.
+    ///
+    /// One eighth of the capacity is reserved for B-tree internal pages, so
+    /// large scans do not push them out.
+    pub fn set_cache_capacity(&mut self, pages: usize) {
+        let dropped = self.cache.resize(pages);
+        self.cache_evictions = self.cache_evictions.saturating_add(dropped);
+    }
+
+    /// Lock cached pages in memory (`mlock`) so decrypted pages are never
+    /// written to swap, or unlock them again.
+    ///
+    /// Fails where the platform cannot lock memory, or when the cache
+    /// capacity exceeds what the process may lock (`RLIMIT_MEMLOCK`). If a
+    /// page cannot be locked later on, for example after the cache grew, it
+    /// is read from disk again instead of being cached.
+    pub fn set_cache_locked(&mut self, locked: bool) -> Result<()> {
+        self.cache.set_locked(locked)
+    }
+
+    /// Whether cached pages are locked in memory.
+    pub fn cache_locked(&self) -> bool {
+        self.cache.is_locked()
+    }
+
     /// Get current epoch.
     pub fn epoch(&self) -> u64 {
         self.epoch
@@ -621,6 +941,17 @@ impl Pager {
         self.inject_write_page_failure = kind;
     }
 
+    #[cfg(any(test, feature = "test-utils"))]
+    pub fn set_inject_backup_failure_at_page(&mut self, page_id: Option<PageId>) {
+        self.inject_backup_failure_at_page = page_id;
+    }
+
+    /// Route data file writes and fsyncs through `fault`.
+    #[cfg(any(test, feature = "test-utils"))]
+    pub fn set_fault_injector(&mut self, fault: Option<crate::fault::FaultInjector>) {
+        self.fault = fault;
+    }
+
     #[cfg(any(test, feature = "test-utils"))]
     pub fn set_inject_flush_meta_failure(&mut self, kind: Option<std::io::ErrorKind>) {
         self.inject_flush_meta_failure = kind;
@@ -638,8 +969,10 @@ impl Pager {
 
     /// Sync file to disk.
     pub fn sync(&mut self) -> Result<()> {
-        self.file.sync_all()?;
-        Ok(())
+        if let Some(generations) = self.generations.as_mut() {
+            generations.sync_stamps()?;
+        }
+        self.sync_file()
     }
 
     /// Get the database file path.
@@ -648,8 +981,8 @@ impl Pager {
     }
 
     /// Derive the FULLTEXT term key from the current master key and database salt.
-    pub fn fts_term_key(&self) -> Result<[u8; 32]> {
-        self.fts_term_key.ok_or_else(|| {
+    pub fn fts_term_key(&self) -> Result<TermKey> {
+        self.fts_term_key.clone().ok_or_else(|| {
             MuroError::Execution(
                 "FULLTEXT term key is not initialized; open database via Database API".to_string(),
             )
@@ -657,7 +990,7 @@ impl Pager {
     }
 
     /// Compute the legacy/default FTS term key from current key material.
-    pub fn derive_bootstrap_fts_term_key(&self) -> [u8; 32] {
+    pub fn derive_bootstrap_fts_term_key(&self) -> TermKey {
         if let Some(master_key) = self.master_key.as_ref() {
             derive_fts_term_key(master_key, &self.salt)
         } else {
@@ -666,11 +999,17 @@ impl Pager {
     }
 
     /// Set the in-memory FULLTEXT term key.
-    pub fn set_fts_term_key(&mut self, key: [u8; 32]) {
+    pub fn set_fts_term_key(&mut self, key: TermKey) {
         self.fts_term_key = Some(key);
     }
 }
 
+impl Drop for Pager {
+    fn drop(&mut self) {
+        self.deferred_writes.values_mut().for_each(Zeroize::zeroize);
+    }
+}
+
 impl crate::storage::page_store::PageStore for Pager {
     fn read_page(&mut self, page_id: PageId) -> Result<Page> {
         Pager::read_page(self, page_id)
@@ -684,13 +1023,52 @@ impl crate::storage::page_store::PageStore for Pager {
         Pager::allocate_page(self)
     }
 
+    fn allocate_page_near(&mut self, hint: PageId) -> Result<Page> {
+        Pager::allocate_page_near(self, hint)
+    }
+
+    fn allocate_pages_near(&mut self, count: usize, hint: PageId) -> Result<Vec<Page>> {
+        Pager::allocate_pages_near(self, count, hint)
+    }
+
     fn free_page(&mut self, page_id: PageId) {
         Pager::free_page(self, page_id)
     }
 
-    fn fts_term_key(&self) -> Result<[u8; 32]> {
+    fn fts_term_key(&self) -> Result<TermKey> {
         Pager::fts_term_key(self)
     }
+
+    fn cache_counters(&self) -> (u64, u64) {
+        (self.cache_hits, self.cache_misses)
+    }
+}
+
+/// Attach the page id to an authentication failure.
+/// Split ascending, duplicate-free `page_ids` into index ranges of
+/// consecutive ids, each at most `max_len` long.
+fn contiguous_runs(page_ids: &[PageId], max_len: usize) -> Vec<std::ops::Range<usize>> {
+    let mut runs = Vec::new();
+    let mut start = 0;
+    for i in 1..=page_ids.len() {
+        let ends =
+            i == page_ids.len() || page_ids[i] != page_ids[i - 1] + 1 || i - start == max_len;
+        if ends {
+            runs.push(start..i);
+            start = i;
+        }
+    }
+    runs
+}
+
+fn page_decrypt_error(e: MuroError, page_id: PageId) -> MuroError {
+    match e {
+        MuroError::Decryption => MuroError::PageDecrypt {
+            page_id,
+            page_lsn: None,
+        },
+        other => other,
+    }
 }
 
 #[cfg(test)]
@@ -2,6 +2,8 @@ use std::fs::{File, OpenOptions};
 use std::io::{Read, Write};
 use std::path::{Path, PathBuf};
 
+use zeroize::Zeroizing;
+
 use crate::crypto::aead::MasterKey;
 use crate::crypto::suite::{EncryptionSuite, PageCipher};
 use crate::error::{MuroError, Result};
@@ -122,7 +124,10 @@ pub fn unwrap_rekey_old_key(
     wrapped_old_key: &[u8],
 ) -> Result<MasterKey> {
     let unwrap_cipher = PageCipher::new(EncryptionSuite::Aes256GcmSiv, Some(new_key))?;
-    let old_key_bytes =
-        unwrap_cipher.decrypt(REKEY_MARKER_WRAP_PAGE_ID, new_epoch, wrapped_old_key)?;
+    let old_key_bytes = Zeroizing::new(unwrap_cipher.decrypt(
+        REKEY_MARKER_WRAP_PAGE_ID,
+        new_epoch,
+        wrapped_old_key,
+    )?);
     MasterKey::from_slice(&old_key_bytes)
 }
@@ -0,0 +1,96 @@
+//! Releasing free pages at the end of the data file.
+//!
+//! Pages are never moved, so the file can only shrink by the run of pages at
+//! its end that hold nothing: free pages and pages of the freelist chain. The
+//! chain is relocated when part of it sits in that run; the commit that
+//! records the lower page count then writes a new chain right after the new
+//! end. Releasing happens in two steps: `release_free_tail` lowers the page
+//! count in memory for the next commit to record, and once that commit is
+//! checkpointed `truncate_to_page_count` shortens the file. A crash between
+//! the two leaves a file longer than its page count, which is harmless.
+
+use std::collections::HashSet;
+
+use crate::error::Result;
+use crate::storage::freelist::FreeList;
+use crate::storage::page::PageId;
+
+use super::{Pager, PLAINTEXT_HEADER_SIZE};
+
+/// Where the file would end after releasing its free tail.
+struct TailPlan {
+    /// First released page.
+    start: PageId,
+    /// Whether part of the freelist chain is released and must be rewritten.
+    relocate_chain: bool,
+    /// Page count once the commit has written the freelist chain.
+    end: u64,
+}
+
+impl Pager {
+    /// Pages a release of the free tail would cut from the file.
+    pub fn releasable_tail_pages(&mut self) -> u64 {
+        self.plan_tail_release(u64::MAX)
+            .map_or(0, |plan| self.page_count - plan.end)
+    }
+
+    /// Drop up to `max_pages` free pages at the end of the file from the
+    /// freelist and the page count, for the next commit to record. Returns
+    /// `false` when there is nothing to release.
+    pub fn release_free_tail(&mut self, max_pages: u64) -> bool {
+        let Some(plan) = self.plan_tail_release(max_pages) else {
+            return false;
+        };
+        if plan.relocate_chain {
+            // Chain pages below the new end become plain free pages, and the
+            // commit appends a fresh chain.
+            for page_id in self.freelist_chain_pages() {
+                if page_id < plan.start {
+                    self.freelist.free(page_id);
+                }
+            }
+            self.freelist_page_id = 0;
+        }
+        for page_id in plan.start..self.page_count {
+            self.freelist.take(page_id);
+            self.extent_spares.remove(&page_id);
+            self.cache.remove(&page_id);
+        }
+        self.page_count = plan.start;
+        true
+    }
+
+    /// Shorten the data file to the page count, once a checkpoint has made
+    /// the lower count durable in the header.
+    pub fn truncate_to_page_count(&mut self) -> Result<()> {
+        let len = PLAINTEXT_HEADER_SIZE + self.page_count * self.page_size_on_disk() as u64;
+        if self.file.metadata()?.len() > len {
+            self.file.set_len(len)?;
+            self.sync_file()?;
+        }
+        Ok(())
+    }
+
+    fn plan_tail_release(&mut self, max_pages: u64) -> Option<TailPlan> {
+        let chain = self.freelist_chain_pages();
+        let free: HashSet<PageId> = self.freelist.pages().iter().copied().collect();
+        let floor = self.page_count.saturating_sub(max_pages).max(1);
+        let mut start = self.page_count;
+        while start > floor && (free.contains(&(start - 1)) || chain.contains(&(start - 1))) {
+            start -= 1;
+        }
+        let relocate_chain = chain.iter().any(|&page_id| page_id >= start);
+        let end = if relocate_chain {
+            let entries = free.iter().filter(|&&page_id| page_id < start).count()
+                + chain.iter().filter(|&&page_id| page_id < start).count();
+            start + FreeList::page_count_needed_for(entries) as u64
+        } else {
+            start
+        };
+        (end < self.page_count).then_some(TailPlan {
+            start,
+            relocate_chain,
+            end,
+        })
+    }
+}
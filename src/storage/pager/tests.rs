@@ -170,6 +170,46 @@ fn test_cache_miss_then_hit_stats() {
     std::fs::remove_file(&path).ok();
 }
 
+#[test]
+fn test_cache_capacity_evicts_and_rereads_identical_bytes() {
+    let tmp = NamedTempFile::new().unwrap();
+    let path = tmp.path().to_path_buf();
+    drop(tmp);
+    std::fs::remove_file(&path).ok();
+
+    let mut pager = Pager::create(&path, &test_key()).unwrap();
+    pager.set_cache_capacity(4);
+    assert_eq!(pager.cache_capacity(), 4);
+
+    let mut written = Vec::new();
+    for i in 0..10u8 {
+        let mut page = pager.allocate_page().unwrap();
+        page.insert_cell(&[i; 100]).unwrap();
+        pager.write_page(&page).unwrap();
+        written.push(*page.as_bytes());
+    }
+    assert_eq!(pager.cache_evictions(), 6);
+    assert_eq!(pager.cache_bytes(), 4 * PAGE_SIZE as u64);
+
+    // Page 0 was evicted; reading it back must decrypt to the same image.
+    let misses = pager.cache_misses();
+    let page0 = pager.read_page(0).unwrap();
+    assert_eq!(pager.cache_misses(), misses + 1);
+    assert_eq!(page0.as_bytes(), &written[0]);
+    for (id, bytes) in written.iter().enumerate() {
+        assert_eq!(pager.read_page(id as u64).unwrap().as_bytes(), bytes);
+    }
+    assert!(pager.cache_bytes() <= 4 * PAGE_SIZE as u64);
+
+    // Shrinking drops LRU entries and counts them as evictions.
+    let before = pager.cache_evictions();
+    pager.set_cache_capacity(1);
+    assert_eq!(pager.cache_evictions(), before + 3);
+    assert_eq!(pager.cache_bytes(), PAGE_SIZE as u64);
+
+    std::fs::remove_file(&path).ok();
+}
+
 #[test]
 fn test_catalog_root_persistence() {
     let tmp = NamedTempFile::new().unwrap();
@@ -479,3 +519,49 @@ fn test_freelist_sanitize_report_none_when_clean() {
 
     std::fs::remove_file(&path).ok();
 }
+
+#[test]
+fn test_contiguous_runs() {
+    assert!(contiguous_runs(&[], 4).is_empty());
+    assert_eq!(contiguous_runs(&[7], 4), vec![0..1]);
+    assert_eq!(
+        contiguous_runs(&[1, 2, 3, 5, 6, 9, 10, 11, 12, 13, 14], 4),
+        vec![0..3, 3..5, 5..9, 9..11]
+    );
+    assert_eq!(contiguous_runs(&[0, 2, 4], 4), vec![0..1, 1..2, 2..3]);
+}
+
+#[test]
+fn test_write_pages_coalesces_consecutive_pages() {
+    let tmp = NamedTempFile::new().unwrap();
+    let path = tmp.path().to_path_buf();
+    drop(tmp);
+    std::fs::remove_file(&path).ok();
+
+    let mut pager = Pager::create(&path, &test_key()).unwrap();
+    let mut pages = Vec::new();
+    for _ in 0..8 {
+        pages.push(pager.allocate_page().unwrap());
+    }
+    for (i, page) in pages.iter_mut().enumerate() {
+        page.insert_cell(format!("page {}", i).as_bytes()).unwrap();
+    }
+    // Out of order, with a gap at page 4 and page 6 listed twice.
+    let mut stale = Page::new(6);
+    stale.insert_cell(b"stale").unwrap();
+    let order = [
+        &pages[7], &stale, &pages[1], &pages[0], &pages[6], &pages[2], &pages[5], &pages[3],
+    ];
+    pager.write_pages(order).unwrap();
+    assert_eq!(pager.data_write_runs(), 2);
+    assert_eq!(pager.data_pages_flushed(), 7);
+    assert_eq!(pager.pages_written(), 7);
+    pager.flush_meta().unwrap();
+    drop(pager);
+
+    let mut pager = Pager::open(&path, &test_key()).unwrap();
+    for i in [0u64, 1, 2, 3, 5, 6, 7] {
+        let page = pager.read_page(i).unwrap();
+        assert_eq!(page.cell(0), Some(format!("page {}", i).as_bytes()));
+    }
+}
@@ -0,0 +1,208 @@
+//! Verdicts of commits that failed with `CommitInDoubt`.
+//!
+//! When a commit fails after its WAL frames were synced, the session notes
+//! the txid, and the caller's commit tag if one was set, in a sidecar file
+//! next to the database (`<db>.commits`). The next read-write open resolves
+//! every noted txid against WAL recovery before the WAL is truncated:
+//! committed if recovery replayed its `Commit` record, rolled back otherwise.
+//!
+//! File format: one entry per line, oldest first,
+//! `<txid>\t<in_doubt|committed|rolled_back>\t<tag>` (the tag may be empty).
+//! Only the newest [`COMMIT_OUTCOME_HISTORY_LIMIT`] resolved entries are kept.
+
+use std::io::Write;
+use std::path::{Path, PathBuf};
+
+use crate::error::{MuroError, Result};
+use crate::wal::record::TxId;
+
+/// Resolved entries kept in the sidecar file; older ones are dropped.
+pub const COMMIT_OUTCOME_HISTORY_LIMIT: usize = 256;
+
+/// Longest accepted commit tag, in bytes.
+pub const MAX_COMMIT_TAG_LEN: usize = 255;
+
+/// How recovery resolved a commit that returned `CommitInDoubt`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CommitOutcome {
+    /// Recovery replayed the transaction; retrying would apply it twice.
+    Committed,
+    /// The transaction is not in the database; it is safe to retry.
+    RolledBack,
+}
+
+/// An in-doubt commit, by the tag set before it or by its txid.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CommitRef<'a> {
+    Tag(&'a str),
+    TxId(TxId),
+}
+
+impl<'a> From<&'a str> for CommitRef<'a> {
+    fn from(tag: &'a str) -> Self {
+        CommitRef::Tag(tag)
+    }
+}
+
+impl From<TxId> for CommitRef<'_> {
+    fn from(txid: TxId) -> Self {
+        CommitRef::TxId(txid)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum EntryState {
+    InDoubt,
+    Resolved(CommitOutcome),
+}
+
+#[derive(Debug, Clone)]
+struct Entry {
+    txid: TxId,
+    tag: Option<String>,
+    state: EntryState,
+}
+
+/// Contents of the commit-outcome sidecar file.
+#[derive(Debug, Default)]
+pub(crate) struct CommitOutcomeLog {
+    entries: Vec<Entry>,
+}
+
+/// Sidecar file holding in-doubt notes and their resolutions.
+pub(crate) fn commit_outcome_path(db_path: &Path) -> PathBuf {
+    let mut s = db_path.as_os_str().to_os_string();
+    s.push(".commits");
+    PathBuf::from(s)
+}
+
+/// Reject tags that could not be stored as one field of a sidecar line.
+pub(crate) fn validate_commit_tag(tag: &str) -> Result<()> {
+    if tag.is_empty() || tag.len() > MAX_COMMIT_TAG_LEN {
+        return Err(MuroError::Execution(format!(
+            "commit tag must be 1 to {} bytes",
+            MAX_COMMIT_TAG_LEN
+        )));
+    }
+    if tag.chars().any(char::is_control) {
+        return Err(MuroError::Execution(
+            "commit tag must not contain control characters".into(),
+        ));
+    }
+    Ok(())
+}
+
+impl CommitOutcomeLog {
+    /// Read the sidecar file; a missing file is an empty log. Lines that do
+    /// not parse (e.g. torn by a crash mid-write of an older version) are
+    /// ignored.
+    fn load(path: &Path) -> Result<Self> {
+        let text = match std::fs::read_to_string(path) {
+            Ok(text) => text,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(Self::default()),
+            Err(e) => return Err(e.into()),
+        };
+        let entries = text.lines().filter_map(parse_entry).collect();
+        Ok(CommitOutcomeLog { entries })
+    }
+
+    /// Atomically replace the sidecar file: write a temporary file, fsync it,
+    /// then rename it over the old one.
+    fn store(&self, path: &Path) -> Result<()> {
+        let mut text = String::new();
+        for entry in &self.entries {
+            let state = match entry.state {
+                EntryState::InDoubt => "in_doubt",
+                EntryState::Resolved(CommitOutcome::Committed) => "committed",
+                EntryState::Resolved(CommitOutcome::RolledBack) => "rolled_back",
+            };
+            text.push_str(&format!(
+                "{}\t{}\t{}\n",
+                entry.txid,
+                state,
+                entry.tag.as_deref().unwrap_or("")
+            ));
+        }
+        let mut tmp = path.as_os_str().to_os_string();
+        tmp.push(".tmp");
+        let tmp = PathBuf::from(tmp);
+        let mut file = std::fs::File::create(&tmp)?;
+        file.write_all(text.as_bytes())?;
+        file.sync_all()?;
+        std::fs::rename(&tmp, path)?;
+        crate::sync_dir(path);
+        Ok(())
+    }
+
+    /// Durably note that `txid` returned `CommitInDoubt`.
+    pub(crate) fn note_in_doubt(db_path: &Path, txid: TxId, tag: Option<&str>) -> Result<()> {
+        let path = commit_outcome_path(db_path);
+        let mut log = Self::load(&path)?;
+        log.entries.push(Entry {
+            txid,
+            tag: tag.map(str::to_string),
+            state: EntryState::InDoubt,
+        });
+        log.store(&path)
+    }
+
+    /// Resolve every in-doubt note against the txids WAL recovery committed.
+    ///
+    /// Must run before the WAL is truncated: a crash in between then only
+    /// repeats the resolution on the next open.
+    pub(crate) fn resolve_at_open(db_path: &Path, committed_txids: &[TxId]) -> Result<Self> {
+        let path = commit_outcome_path(db_path);
+        let mut log = Self::load(&path)?;
+        let mut changed = false;
+        for entry in &mut log.entries {
+            if entry.state == EntryState::InDoubt {
+                entry.state = EntryState::Resolved(if committed_txids.contains(&entry.txid) {
+                    CommitOutcome::Committed
+                } else {
+                    CommitOutcome::RolledBack
+                });
+                changed = true;
+            }
+        }
+        if log.entries.len() > COMMIT_OUTCOME_HISTORY_LIMIT {
+            let excess = log.entries.len() - COMMIT_OUTCOME_HISTORY_LIMIT;
+            log.entries.drain(..excess);
+            changed = true;
+        }
+        if changed {
+            log.store(&path)?;
+        }
+        Ok(log)
+    }
+
+    /// Verdict of the newest resolved entry matching `key`.
+    pub(crate) fn outcome(&self, key: CommitRef<'_>) -> Option<CommitOutcome> {
+        self.entries.iter().rev().find_map(|entry| {
+            let matches = match key {
+                CommitRef::Tag(tag) => entry.tag.as_deref() == Some(tag),
+                CommitRef::TxId(txid) => entry.txid == txid,
+            };
+            match entry.state {
+                EntryState::Resolved(outcome) if matches => Some(outcome),
+                _ => None,
+            }
+        })
+    }
+}
+
+fn parse_entry(line: &str) -> Option<Entry> {
+    let mut fields = line.splitn(3, '\t');
+    let txid = fields.next()?.parse().ok()?;
+    let state = match fields.next()? {
+        "in_doubt" => EntryState::InDoubt,
+        "committed" => EntryState::Resolved(CommitOutcome::Committed),
+        "rolled_back" => EntryState::Resolved(CommitOutcome::RolledBack),
+        _ => return None,
+    };
+    let tag = fields.next()?;
+    Some(Entry {
+        txid,
+        tag: (!tag.is_empty()).then(|| tag.to_string()),
+        state,
+    })
+}
@@ -1,3 +1,4 @@
+pub mod commit_outcome;
 pub mod lock_manager;
 pub mod page_store;
 pub mod transaction;
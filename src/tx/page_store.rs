@@ -1,4 +1,8 @@
+use std::collections::HashSet;
+
+use crate::crypto::hmac_util::TermKey;
 use crate::error::Result;
+use crate::storage::freelist::FreeList;
 use crate::storage::page::{Page, PageId};
 use crate::storage::page_store::PageStore;
 use crate::storage::pager::Pager;
@@ -11,11 +15,22 @@ use crate::tx::transaction::Transaction;
 pub struct TxPageStore<'a> {
     tx: Transaction,
     pager: &'a mut Pager,
+    /// Pages written through this store.
+    dirtied: HashSet<PageId>,
 }
 
 impl<'a> TxPageStore<'a> {
     pub fn new(tx: Transaction, pager: &'a mut Pager) -> Self {
-        TxPageStore { tx, pager }
+        TxPageStore {
+            tx,
+            pager,
+            dirtied: HashSet::new(),
+        }
+    }
+
+    /// Number of distinct pages written through this store.
+    pub fn pages_dirtied(&self) -> u64 {
+        self.dirtied.len() as u64
     }
 
     /// Consume this store and return the `Transaction` (for put-back into Session).
@@ -30,6 +45,7 @@ impl PageStore for TxPageStore<'_> {
     }
 
     fn write_page(&mut self, page: &Page) -> Result<()> {
+        self.dirtied.insert(page.page_id());
         self.tx.write_page(page.clone());
         Ok(())
     }
@@ -38,11 +54,52 @@ impl PageStore for TxPageStore<'_> {
         self.tx.allocate_page(self.pager)
     }
 
+    fn allocate_page_near(&mut self, hint: PageId) -> Result<Page> {
+        self.tx.allocate_page_near(self.pager, hint)
+    }
+
+    fn allocate_pages_near(&mut self, count: usize, hint: PageId) -> Result<Vec<Page>> {
+        if count == 1 {
+            return Ok(vec![self.allocate_page_near(hint)?]);
+        }
+        self.pager.allocate_pages_near(count, hint)
+    }
+
     fn free_page(&mut self, page_id: PageId) {
         self.tx.free_page(page_id);
     }
 
-    fn fts_term_key(&self) -> Result<[u8; 32]> {
+    fn fts_term_key(&self) -> Result<TermKey> {
         self.pager.fts_term_key()
     }
+
+    /// Pages served from the dirty buffer are not counted.
+    fn cache_counters(&self) -> (u64, u64) {
+        (self.pager.cache_hits(), self.pager.cache_misses())
+    }
+}
+
+/// Pager allocation state captured before a transaction (or savepoint) so that
+/// pages it allocated are handed back when it is rolled back.
+#[derive(Clone)]
+pub struct PagerAllocState {
+    page_count: u64,
+    freelist_page_id: u64,
+    freelist: FreeList,
+}
+
+impl PagerAllocState {
+    pub fn capture(pager: &mut Pager) -> Self {
+        PagerAllocState {
+            page_count: pager.page_count(),
+            freelist_page_id: pager.freelist_page_id(),
+            freelist: pager.freelist_mut().clone(),
+        }
+    }
+
+    pub fn restore(self, pager: &mut Pager) {
+        pager.set_page_count(self.page_count);
+        pager.set_freelist_page_id(self.freelist_page_id);
+        *pager.freelist_mut() = self.freelist;
+    }
 }
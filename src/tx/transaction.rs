@@ -11,6 +11,8 @@ use crate::wal::writer::WalWriter;
 pub enum TxState {
     Active,
     Committed,
+    /// Rolled back, or failed by a statement error under
+    /// `abort_on_statement_error` and waiting for ROLLBACK.
     Aborted,
 }
 
@@ -23,6 +25,16 @@ pub struct Transaction {
     snapshot_lsn: Lsn,
     dirty_pages: HashMap<PageId, Page>,
     freed_pages: Vec<PageId>,
+    statement: Option<StatementUndo>,
+}
+
+/// What the running statement changed in the transaction's buffers, so a
+/// failing statement can be undone without discarding the transaction.
+#[derive(Clone)]
+struct StatementUndo {
+    freed_len: usize,
+    /// Buffered version of each page before the statement first wrote it.
+    before: HashMap<PageId, Option<Page>>,
 }
 
 impl Transaction {
@@ -33,6 +45,7 @@ impl Transaction {
             snapshot_lsn,
             dirty_pages: HashMap::new(),
             freed_pages: Vec::new(),
+            statement: None,
         }
     }
 
@@ -58,7 +71,46 @@ impl Transaction {
 
     /// Write a page into the dirty buffer.
     pub fn write_page(&mut self, page: Page) {
-        self.dirty_pages.insert(page.page_id(), page);
+        let page_id = page.page_id();
+        let previous = self.dirty_pages.insert(page_id, page);
+        if let Some(undo) = &mut self.statement {
+            undo.before.entry(page_id).or_insert(previous);
+        }
+    }
+
+    /// Start tracking a statement's writes and frees so that
+    /// [`Transaction::rollback_statement`] can undo them.
+    pub fn begin_statement(&mut self) {
+        self.statement = Some(StatementUndo {
+            freed_len: self.freed_pages.len(),
+            before: HashMap::new(),
+        });
+    }
+
+    /// Keep the running statement's changes.
+    pub fn end_statement(&mut self) {
+        self.statement = None;
+    }
+
+    /// Undo the running statement: restore the pages it wrote and forget the
+    /// pages it freed, which are still reachable from the restored state.
+    pub fn rollback_statement(&mut self) {
+        let Some(undo) = self.statement.take() else {
+            return;
+        };
+        self.freed_pages.truncate(undo.freed_len);
+        for (page_id, before) in undo.before {
+            match before {
+                Some(page) => self.dirty_pages.insert(page_id, page),
+                None => self.dirty_pages.remove(&page_id),
+            };
+        }
+    }
+
+    /// Mark the transaction aborted after a failed statement. Its buffers
+    /// stay until it is rolled back; it can no longer commit.
+    pub fn mark_aborted(&mut self) {
+        self.state = TxState::Aborted;
     }
 
     /// Record a page as freed within this transaction.
@@ -73,10 +125,26 @@ impl Transaction {
         Ok(page)
     }
 
+    /// Allocate a page close to `hint` through the pager. Spare pages of a
+    /// newly claimed extent are buffered as empty pages, so they reach the
+    /// data file with this transaction (or vanish with its rollback).
+    pub fn allocate_page_near(&mut self, pager: &mut Pager, hint: PageId) -> Result<Page> {
+        let (page, spares) = pager.allocate_near_with_spares(hint)?;
+        for spare in spares {
+            self.write_page(Page::new(spare));
+        }
+        Ok(page)
+    }
+
     /// Commit: write dirty pages to WAL, then flush to pager.
     ///
     /// `catalog_root` is included in the WAL MetaUpdate record so that recovery
     /// can restore it atomically with the committed pages.
+    ///
+    /// Page frames are streamed through the WAL write buffer, so a commit of
+    /// any size holds at most the buffer limit of encrypted frames in memory.
+    /// The Commit record is written after every other frame of the
+    /// transaction and fsynced last.
     pub fn commit(
         &mut self,
         pager: &mut Pager,
@@ -90,13 +158,15 @@ impl Transaction {
         }
 
         // Write Begin record
-        wal.append(&WalRecord::Begin { txid: self.txid })?;
+        wal.append_buffered(&WalRecord::Begin { txid: self.txid })?;
 
-        // Write all dirty pages to WAL
-        for (page_id, page) in &self.dirty_pages {
-            wal.append(&WalRecord::PagePut {
+        // Write all dirty pages to WAL, in page order like the data file writes
+        let mut dirty: Vec<&Page> = self.dirty_pages.values().collect();
+        dirty.sort_unstable_by_key(|page| page.page_id());
+        for page in &dirty {
+            wal.append_buffered(&WalRecord::PagePut {
                 txid: self.txid,
-                page_id: *page_id,
+                page_id: page.page_id(),
                 data: page.data.to_vec(),
             })?;
         }
@@ -110,54 +180,35 @@ impl Transaction {
             }
         }
 
-        // Build a speculative freelist snapshot without mutating the pager's freelist.
-        // This avoids leaking freed pages into the pager if WAL commit fails below.
-        // We track how many pages were actually added (free() returns false for
-        // duplicates) so we undo exactly the right number of pushes.
+        // Build the committed freelist on a copy, so the pager's freelist is
+        // untouched if the WAL commit fails below.
+        use crate::storage::freelist::FreeList;
         use crate::storage::page::PAGE_HEADER_SIZE;
-        let pages_needed = {
-            let fl = pager.freelist_mut();
-            let mut actually_freed = 0usize;
-            for &page_id in &self.freed_pages {
-                if fl.free(page_id) {
-                    actually_freed += 1;
-                }
-            }
-            let needed = fl.page_count_needed();
-            for _ in 0..actually_freed {
-                fl.undo_last_free();
-            }
-            needed
-        };
+        let mut freelist = pager.freelist_mut().clone();
+        for &page_id in &self.freed_pages {
+            freelist.free(page_id);
+        }
 
-        // Compute freelist page IDs without mutating pager state.
-        // Reuse existing freelist page as the first one, then assign sequential
-        // page IDs starting from page_count for any additional pages needed.
-        let mut fl_page_ids = Vec::with_capacity(pages_needed);
-        let existing_fl_page_id = pager.freelist_page_id();
-        if existing_fl_page_id != 0 {
-            fl_page_ids.push(existing_fl_page_id);
+        // Rewrite the current freelist chain in place. Chain pages it no
+        // longer needs become free pages themselves (which may take one of
+        // them back), and missing ones are appended to the file, so chain
+        // pages are never orphaned as the freelist grows and shrinks.
+        let mut fl_page_ids = pager.freelist_chain_pages();
+        let mut keep = freelist.page_count_needed().min(fl_page_ids.len());
+        while keep < fl_page_ids.len()
+            && FreeList::page_count_needed_for(freelist.len() + fl_page_ids.len() - keep) > keep
+        {
+            keep += 1;
         }
-        while fl_page_ids.len() < pages_needed {
+        for &page_id in &fl_page_ids[keep..] {
+            freelist.free(page_id);
+        }
+        fl_page_ids.truncate(keep);
+        while fl_page_ids.len() < freelist.page_count_needed() {
             fl_page_ids.push(page_count);
             page_count += 1;
         }
-
-        // Build speculative freelist and serialize to pages
-        let fl_pages_data = {
-            let fl = pager.freelist_mut();
-            let mut actually_freed = 0usize;
-            for &page_id in &self.freed_pages {
-                if fl.free(page_id) {
-                    actually_freed += 1;
-                }
-            }
-            let data = fl.serialize_pages(&fl_page_ids);
-            for _ in 0..actually_freed {
-                fl.undo_last_free();
-            }
-            data
-        };
+        let fl_pages_data = freelist.serialize_pages(&fl_page_ids);
 
         // Write freelist pages to WAL
         let mut fl_disk_pages = Vec::with_capacity(fl_pages_data.len());
@@ -165,7 +216,7 @@ impl Transaction {
             let mut fl_page = Page::new(*pid);
             fl_page.data[PAGE_HEADER_SIZE..PAGE_HEADER_SIZE + data_area.len()]
                 .copy_from_slice(data_area);
-            wal.append(&WalRecord::PagePut {
+            wal.append_buffered(&WalRecord::PagePut {
                 txid: self.txid,
                 page_id: *pid,
                 data: fl_page.data.to_vec(),
@@ -177,7 +228,7 @@ impl Transaction {
         let freelist_page_id = fl_page_ids[0];
 
         // Write MetaUpdate so recovery can restore catalog_root, page_count, and freelist_page_id
-        wal.append(&WalRecord::MetaUpdate {
+        wal.append_buffered(&WalRecord::MetaUpdate {
             txid: self.txid,
             catalog_root,
             page_count,
@@ -185,30 +236,41 @@ impl Transaction {
             epoch: pager.epoch(),
         })?;
 
-        // Write Commit record
+        // Write Commit record, after the buffered frames
         let commit_lsn = wal.current_lsn();
         wal.append(&WalRecord::Commit {
             txid: self.txid,
             lsn: commit_lsn,
         })?;
 
-        // Fsync the WAL — this is the commit point.
+        // Fsync the WAL — this is the commit point. Relaxed durability modes
+        // may defer the fsync to a later commit of the same batch.
         // Only after this succeeds do we apply freed pages to the in-memory freelist.
-        wal.sync()?;
+        let synced = wal.sync_commit()?;
 
         // WAL commit succeeded: now apply freed pages to the pager's freelist
-        for &page_id in &self.freed_pages {
-            pager.freelist_mut().free(page_id);
+        *pager.freelist_mut() = freelist;
+
+        if !synced {
+            // The data file must never get ahead of the durable WAL, so the
+            // pages wait in the pager until the batch is fsynced.
+            for page in dirty.into_iter().chain(&fl_disk_pages) {
+                pager.defer_write_page(page);
+            }
+            pager.set_catalog_root(catalog_root);
+            pager.set_page_count(page_count);
+            pager.set_freelist_page_id(freelist_page_id);
+            self.state = TxState::Committed;
+            self.dirty_pages.clear();
+            self.freed_pages.clear();
+            return Ok(commit_lsn);
         }
 
         // Post-sync data flush — errors become CommitInDoubt since WAL is durable
         let flush_result: Result<()> = (|| {
-            for page in self.dirty_pages.values() {
-                pager.write_page(page)?;
-            }
-            for fl_page in &fl_disk_pages {
-                pager.write_page(fl_page)?;
-            }
+            // Earlier commits of this batch became durable with this fsync.
+            pager.write_deferred_pages()?;
+            pager.write_pages(dirty.into_iter().chain(&fl_disk_pages))?;
             pager.set_catalog_root(catalog_root);
             pager.set_page_count(page_count);
             pager.set_freelist_page_id(freelist_page_id);
@@ -220,7 +282,10 @@ impl Transaction {
             self.state = TxState::Committed; // WAL is durable
             self.dirty_pages.clear();
             self.freed_pages.clear();
-            return Err(MuroError::CommitInDoubt(format!("{}", e)));
+            return Err(MuroError::CommitInDoubt(format!(
+                "txid {}: {}",
+                self.txid, e
+            )));
         }
 
         self.state = TxState::Committed;
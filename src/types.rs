@@ -1,6 +1,10 @@
 use std::fmt;
 use std::hash::{Hash, Hasher};
 
+mod collation;
+
+pub use collation::Collation;
+
 #[derive(Debug, Clone, PartialEq)]
 pub enum Value {
     Integer(i64),
@@ -67,7 +71,7 @@ impl fmt::Display for Value {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         match self {
             Value::Integer(v) => write!(f, "{}", v),
-            Value::Float(v) => write!(f, "{}", v),
+            Value::Float(v) => write!(f, "{}", format_float(*v)),
             Value::Decimal(v) => write!(f, "{}", v),
             Value::Date(v) => write!(f, "{}", format_date(*v)),
             Value::DateTime(v) => write!(f, "{}", format_datetime(*v)),
@@ -80,7 +84,14 @@ impl fmt::Display for Value {
     }
 }
 
-/// Wrapper for Value that implements Eq + Hash, for use in HashMap/HashSet (GROUP BY, COUNT DISTINCT).
+/// Wrapper for Value that implements Eq + Hash, for use in HashMap/HashSet
+/// (GROUP BY, DISTINCT, COUNT DISTINCT, UNION/INTERSECT/EXCEPT).
+///
+/// Values of different SQL types are never equal, so `'abc'` and
+/// `X'616263'` or `1` and `'1'` are distinct keys. The exceptions are numbers,
+/// which are equal when their values are (`1`, `1.0` and `DECIMAL 1.00`), and
+/// DATE/DATETIME/TIMESTAMP, which are equal at the same instant. NULL equals
+/// NULL, so NULLs form one group.
 #[derive(Debug, Clone)]
 pub struct ValueKey(pub Value);
 
@@ -99,6 +110,8 @@ impl PartialEq for ValueKey {
             (Value::Integer(a), Value::Decimal(b)) => rust_decimal::Decimal::from(*a) == *b,
             (Value::Integer(a), Value::Float(b)) => int_float_equal(*a, *b),
             (Value::Float(a), Value::Integer(b)) => int_float_equal(*b, *a),
+            (Value::Decimal(a), Value::Float(b)) => decimal_float_equal(a, *b),
+            (Value::Float(a), Value::Decimal(b)) => decimal_float_equal(b, *a),
             (a @ Value::Date(_), b @ Value::Date(_))
             | (a @ Value::Date(_), b @ Value::DateTime(_))
             | (a @ Value::Date(_), b @ Value::Timestamp(_))
@@ -124,16 +137,7 @@ impl Eq for ValueKey {}
 impl Hash for ValueKey {
     fn hash<H: Hasher>(&self, state: &mut H) {
         match &self.0 {
-            Value::Integer(n) => {
-                if in_exact_f64_int_range(*n) {
-                    // Numeric-equivalent domain for int/float (e.g. 1 and 1.0).
-                    0u8.hash(state);
-                    n.hash(state);
-                } else {
-                    1u8.hash(state);
-                    n.hash(state);
-                }
-            }
+            Value::Integer(n) => hash_integer(*n, state),
             Value::Float(n) => {
                 if let Some(i) = float_to_exact_i64_key(*n) {
                     0u8.hash(state);
@@ -146,14 +150,18 @@ impl Hash for ValueKey {
             Value::Decimal(d) => {
                 use rust_decimal::prelude::ToPrimitive;
                 let normalized = d.normalize();
-                // If the Decimal is an exact integer, hash as Integer for cross-type consistency
+                // Hash as the Integer or Float it equals, if any.
                 if normalized.scale() == 0 {
                     if let Some(i) = normalized.to_i64() {
-                        0u8.hash(state);
-                        i.hash(state);
+                        hash_integer(i, state);
                         return;
                     }
                 }
+                if let Some(f) = decimal_as_exact_fraction(&normalized) {
+                    2u8.hash(state);
+                    canonical_f64_bits(f).hash(state);
+                    return;
+                }
                 10u8.hash(state);
                 normalized.serialize().hash(state);
             }
@@ -212,6 +220,33 @@ fn int_float_equal(i: i64, f: f64) -> bool {
     in_exact_f64_int_range(i) && float_to_exact_i64_key(f) == Some(i)
 }
 
+fn hash_integer<H: Hasher>(n: i64, state: &mut H) {
+    if in_exact_f64_int_range(n) {
+        // Numeric-equivalent domain for int/float (e.g. 1 and 1.0).
+        0u8.hash(state);
+    } else {
+        1u8.hash(state);
+    }
+    n.hash(state);
+}
+
+/// The non-integral float a decimal converts to and back from unchanged.
+fn decimal_as_exact_fraction(d: &rust_decimal::Decimal) -> Option<f64> {
+    use rust_decimal::prelude::ToPrimitive;
+    let f = d.to_f64()?;
+    (f.is_finite() && f.fract() != 0.0 && rust_decimal::Decimal::from_f64_retain(f) == Some(*d))
+        .then_some(f)
+}
+
+/// Integral floats equal a decimal like they equal an integer; the others
+/// only the decimal with exactly their value.
+fn decimal_float_equal(d: &rust_decimal::Decimal, f: f64) -> bool {
+    match float_to_exact_i64_key(f) {
+        Some(i) => *d == rust_decimal::Decimal::from(i),
+        None => decimal_as_exact_fraction(d) == Some(f),
+    }
+}
+
 fn temporal_key(v: &Value) -> i64 {
     match v {
         Value::Date(d) => (*d as i64) * 1_000_000,
@@ -220,6 +255,31 @@ fn temporal_key(v: &Value) -> i64 {
     }
 }
 
+/// Text form of a float: the shortest decimal that parses back to the same
+/// bits, independent of platform.
+///
+/// Magnitudes in `[1e-5, 1e16)` (and zero) use plain notation (`0.1`, `42`,
+/// `-0`); others use exponent notation (`1e300`, `5e-324`, `-1.5e-7`).
+pub fn format_float(n: f64) -> String {
+    let abs = n.abs();
+    if n.is_finite() && abs != 0.0 && !(1e-5..1e16).contains(&abs) {
+        format!("{:e}", n)
+    } else {
+        n.to_string()
+    }
+}
+
+/// [`format_float`] as a SQL literal that lexes back as a float (`1.0`
+/// rather than `1`).
+pub(crate) fn format_float_literal(n: f64) -> String {
+    let s = format_float(n);
+    if s.contains(['.', 'e']) || !n.is_finite() {
+        s
+    } else {
+        format!("{}.0", s)
+    }
+}
+
 /// Format a 16-byte UUID as a lowercase hyphenated string.
 pub fn format_uuid(bytes: &[u8; 16]) -> String {
     format!(
@@ -237,7 +297,7 @@ pub fn parse_uuid_string(s: &str) -> Option<[u8; 16]> {
     let s = s.trim();
     // Remove hyphens
     let hex: String = s.chars().filter(|c| *c != '-').collect();
-    if hex.len() != 32 {
+    if hex.len() != 32 || !hex.bytes().all(|b| b.is_ascii_hexdigit()) {
         return None;
     }
     let mut bytes = [0u8; 16];
@@ -271,6 +331,106 @@ mod tests {
         assert_eq!(set.len(), 1);
     }
 
+    #[test]
+    fn test_value_key_different_types_are_distinct() {
+        let pairs = [
+            (
+                Value::Varchar("abc".into()),
+                Value::Varbinary(b"abc".to_vec()),
+            ),
+            (Value::Integer(1), Value::Varchar("1".into())),
+            (Value::Varchar("".into()), Value::Varbinary(vec![])),
+            (Value::Varchar("".into()), Value::Null),
+            (Value::Integer(0), Value::Null),
+            (Value::Uuid([0x61; 16]), Value::Varbinary(vec![0x61; 16])),
+            (Value::Date(20260221), Value::Integer(20260221)),
+        ];
+        for (a, b) in pairs {
+            assert_ne!(ValueKey(a.clone()), ValueKey(b.clone()), "{:?} {:?}", a, b);
+            let set: HashSet<_> = [ValueKey(a), ValueKey(b)].into_iter().collect();
+            assert_eq!(set.len(), 2);
+        }
+        assert_eq!(ValueKey(Value::Null), ValueKey(Value::Null));
+    }
+
+    #[test]
+    fn test_value_key_numeric_equality_is_transitive() {
+        use rust_decimal::Decimal;
+        use std::str::FromStr;
+        let big = 1_i64 << 60;
+        let mut set = HashSet::new();
+        for v in [
+            Value::Integer(1),
+            Value::Float(1.0),
+            Value::Decimal(Decimal::from_str("1.00").unwrap()),
+            Value::Float(1.5),
+            Value::Decimal(Decimal::from_str("1.50").unwrap()),
+            Value::Integer(big),
+            Value::Decimal(Decimal::from(big)),
+            // Not the binary fraction 0.1 is stored as.
+            Value::Decimal(Decimal::from_str("0.1").unwrap()),
+            Value::Float(0.1),
+            Value::Float(big as f64),
+        ] {
+            set.insert(ValueKey(v));
+        }
+        // {1}, {1.5}, {2^60}, DECIMAL 0.1, DOUBLE 0.1, DOUBLE 2^60.
+        assert_eq!(set.len(), 6);
+        assert_eq!(
+            ValueKey(Value::Decimal(Decimal::from_str("-2.25").unwrap())),
+            ValueKey(Value::Float(-2.25))
+        );
+    }
+
+    /// Equal keys hash alike, and equality is transitive, across a pool of
+    /// values of every type.
+    #[test]
+    fn test_value_key_eq_implies_equal_hash() {
+        use rust_decimal::Decimal;
+        use std::collections::hash_map::RandomState;
+        use std::hash::BuildHasher;
+        use std::str::FromStr;
+        let mut pool = vec![Value::Null];
+        for n in [0_i64, 1, -1, 42, 1 << 53, (1 << 53) + 1, 1 << 60, i64::MIN] {
+            pool.push(Value::Integer(n));
+            pool.push(Value::Float(n as f64));
+            pool.push(Value::Decimal(Decimal::from(n)));
+            pool.push(Value::Varchar(n.to_string()));
+            pool.push(Value::Varbinary(n.to_string().into_bytes()));
+        }
+        for s in ["0.5", "-0.0", "0.1", "1.25", "1.250", "2.5e-3"] {
+            let f: f64 = s.parse().unwrap();
+            pool.push(Value::Float(f));
+            pool.push(Value::Float(-f));
+            if let Ok(d) = Decimal::from_str(s) {
+                pool.push(Value::Decimal(d));
+            }
+        }
+        pool.extend([
+            Value::Float(f64::NAN),
+            Value::Float(f64::INFINITY),
+            Value::Date(20260221),
+            Value::DateTime(20260221000000),
+            Value::Timestamp(20260221000001),
+            Value::Uuid([1; 16]),
+        ]);
+        let hasher = RandomState::new();
+        let keys: Vec<_> = pool.into_iter().map(ValueKey).collect();
+        for a in &keys {
+            for b in &keys {
+                if a == b {
+                    assert_eq!(hasher.hash_one(a), hasher.hash_one(b), "{:?} {:?}", a, b);
+                    assert_eq!(b, a);
+                    for c in &keys {
+                        if b == c {
+                            assert_eq!(a, c, "{:?} {:?} {:?}", a, b, c);
+                        }
+                    }
+                }
+            }
+        }
+    }
+
     #[test]
     fn test_as_i64_is_strict_integer_only() {
         assert_eq!(Value::Integer(42).as_i64(), Some(42));
@@ -309,6 +469,24 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_metadata_only_type_changes() {
+        use super::DataType;
+        assert!(DataType::Int.is_metadata_only_change_to(DataType::Int));
+        assert!(!DataType::Int.is_metadata_only_change_to(DataType::BigInt));
+        assert!(DataType::Varchar(Some(10)).is_metadata_only_change_to(DataType::Varchar(Some(20))));
+        assert!(DataType::Varchar(Some(10)).is_metadata_only_change_to(DataType::Varchar(None)));
+        assert!(
+            !DataType::Varchar(Some(20)).is_metadata_only_change_to(DataType::Varchar(Some(10)))
+        );
+        assert!(!DataType::Varchar(None).is_metadata_only_change_to(DataType::Varchar(Some(10))));
+        assert!(
+            DataType::Varbinary(Some(4)).is_metadata_only_change_to(DataType::Varbinary(Some(4)))
+        );
+        assert!(!DataType::Varchar(None).is_metadata_only_change_to(DataType::Text));
+        assert!(!DataType::Decimal(10, 2).is_metadata_only_change_to(DataType::Decimal(12, 2)));
+    }
+
     #[test]
     fn test_temporal_parsers_reject_non_ascii_without_panic() {
         assert_eq!(super::parse_date_string("123é-45-67"), None);
@@ -363,6 +541,27 @@ impl fmt::Display for DataType {
     }
 }
 
+impl DataType {
+    /// Whether changing a column from `self` to `new` keeps every stored value
+    /// valid and identically encoded, so MODIFY/CHANGE COLUMN only rewrites
+    /// metadata: the same type, or a VARCHAR/VARBINARY length limit raised or
+    /// removed.
+    pub fn is_metadata_only_change_to(self, new: DataType) -> bool {
+        fn widens(old: Option<u32>, new: Option<u32>) -> bool {
+            match (old, new) {
+                (_, None) => true,
+                (Some(old), Some(new)) => new >= old,
+                (None, Some(_)) => false,
+            }
+        }
+        match (self, new) {
+            (DataType::Varchar(old), DataType::Varchar(new)) => widens(old, new),
+            (DataType::Varbinary(old), DataType::Varbinary(new)) => widens(old, new),
+            (old, new) => old == new,
+        }
+    }
+}
+
 pub fn parse_date_string(s: &str) -> Option<i32> {
     let s = s.trim();
     let b = s.as_bytes();
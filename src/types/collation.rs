@@ -0,0 +1,127 @@
+use super::Value;
+use std::borrow::Cow;
+use std::cmp::Ordering;
+use std::fmt;
+
+/// How string values of a column compare, sort, and form index keys.
+///
+/// A collation compares strings by their collation key: the text with its
+/// collation's folding applied. Index and primary keys are encoded from that
+/// key, so seeks and UNIQUE enforcement agree with comparisons. The catalog
+/// stores a collation by name, which leaves room for locale-specific ones.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Collation {
+    /// Byte-wise comparison of the UTF-8 text.
+    #[default]
+    Binary,
+    /// Case-insensitive: each character is replaced by its simple Unicode
+    /// lowercase mapping, which covers ASCII and most other scripts.
+    Nocase,
+}
+
+impl Collation {
+    /// Look up a collation by name, ignoring case.
+    pub fn from_name(name: &str) -> Option<Self> {
+        if name.eq_ignore_ascii_case("binary") {
+            Some(Collation::Binary)
+        } else if name.eq_ignore_ascii_case("nocase") {
+            Some(Collation::Nocase)
+        } else {
+            None
+        }
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Collation::Binary => "binary",
+            Collation::Nocase => "nocase",
+        }
+    }
+
+    /// Collation key of a string.
+    pub fn fold(self, s: &str) -> Cow<'_, str> {
+        match self {
+            Collation::Binary => Cow::Borrowed(s),
+            Collation::Nocase => {
+                if !s.chars().any(|c| simple_lowercase(c) != c) {
+                    return Cow::Borrowed(s);
+                }
+                Cow::Owned(s.chars().map(simple_lowercase).collect())
+            }
+        }
+    }
+
+    /// `value` with string contents replaced by their collation key; other
+    /// values are returned as is.
+    pub fn key_value(self, value: &Value) -> Cow<'_, Value> {
+        match (self, value) {
+            (Collation::Binary, _) => Cow::Borrowed(value),
+            (_, Value::Varchar(s)) => match self.fold(s) {
+                Cow::Borrowed(_) => Cow::Borrowed(value),
+                Cow::Owned(folded) => Cow::Owned(Value::Varchar(folded)),
+            },
+            _ => Cow::Borrowed(value),
+        }
+    }
+
+    /// Compare two strings under this collation.
+    pub fn compare(self, a: &str, b: &str) -> Ordering {
+        match self {
+            Collation::Binary => a.cmp(b),
+            Collation::Nocase => a
+                .chars()
+                .map(simple_lowercase)
+                .cmp(b.chars().map(simple_lowercase)),
+        }
+    }
+}
+
+impl fmt::Display for Collation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
+/// Lowercase `c` when its lowercase form is a single character. Characters
+/// whose lowercase form expands (such as U+0130) are kept, so folding never
+/// changes the number of characters.
+fn simple_lowercase(c: char) -> char {
+    if c.is_ascii() {
+        return c.to_ascii_lowercase();
+    }
+    let mut lower = c.to_lowercase();
+    match (lower.next(), lower.next()) {
+        (Some(l), None) => l,
+        _ => c,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_nocase_folds_ascii_and_unicode() {
+        let nocase = Collation::Nocase;
+        assert_eq!(nocase.fold("Alice"), "alice");
+        assert_eq!(nocase.fold("ÀÉÎ Straße ΣΑΣ"), "àéî straße σασ");
+        assert!(matches!(nocase.fold("already lower"), Cow::Borrowed(_)));
+        // U+0130 lowercases to two characters, so it is kept.
+        assert_eq!(nocase.fold("İx"), "İx");
+        assert_eq!(nocase.compare("Zebra", "apple"), Ordering::Greater);
+        assert_eq!(Collation::Binary.compare("Zebra", "apple"), Ordering::Less);
+        assert_eq!(nocase.compare("ÉCOLE", "école"), Ordering::Equal);
+    }
+
+    #[test]
+    fn test_key_value_only_folds_strings() {
+        let nocase = Collation::Nocase;
+        assert_eq!(
+            *nocase.key_value(&Value::Varchar("MiXeD".into())),
+            Value::Varchar("mixed".into())
+        );
+        assert_eq!(*nocase.key_value(&Value::Integer(7)), Value::Integer(7));
+        assert_eq!(Collation::from_name("NoCase"), Some(Collation::Nocase));
+        assert_eq!(Collation::from_name("latin1"), None);
+    }
+}
@@ -0,0 +1,350 @@
+/// WAL archiving: keep checkpointed WAL contents instead of discarding them.
+///
+/// With an archive directory configured, every checkpoint first copies the
+/// WAL (header and frames, exactly as on disk, so encrypted frames stay
+/// encrypted) to the numbered segment `<db>.wal.NNNNNN` and appends a line to
+/// `<db>.wal.index`; only after both are fsynced is the live WAL truncated.
+/// A base backup plus the segments written after it can be replayed with
+/// [`restore_from_archive`] to reproduce the database as of the last archived
+/// checkpoint.
+///
+/// Each segment starts at LSN 0 like any WAL, so it is a complete WAL file
+/// that the regular recovery path can replay. Index lines are
+/// `segment start_frame end_frame catalog_root`, tab separated: frame
+/// numbers count frames across all segments, and `catalog_root` is the
+/// catalog root the checkpoint left in the data file.
+use std::fs::{File, OpenOptions};
+use std::io::{Read, Write};
+use std::path::{Path, PathBuf};
+
+use crate::crypto::aead::MasterKey;
+use crate::crypto::suite::EncryptionSuite;
+use crate::error::{MuroError, Result};
+use crate::storage::page::PageId;
+use crate::storage::pager::Pager;
+use crate::wal::recovery::{inspect_wal_with_suite, recover_with_mode_and_suite, RecoveryMode};
+
+const INDEX_SUFFIX: &str = ".index";
+const INDEX_HEADER: &str = "# segment\tstart_frame\tend_frame\tcatalog_root";
+
+/// One archived segment, as recorded in the index.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ArchivedSegment {
+    pub segment: u64,
+    pub path: PathBuf,
+    /// First frame of the segment, counted across all segments.
+    pub start_frame: u64,
+    /// One past the last frame of the segment.
+    pub end_frame: u64,
+    /// Catalog root in the data file after the checkpoint.
+    pub catalog_root: PageId,
+}
+
+/// Outcome of [`restore_from_archive`].
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ArchiveRestoreResult {
+    /// Segments replayed onto the base backup, in order.
+    pub segments_replayed: Vec<u64>,
+    /// Segments skipped because the base backup already contained them.
+    pub segments_skipped: Vec<u64>,
+}
+
+/// Destination for checkpointed WAL segments of one database.
+#[derive(Debug)]
+pub struct WalArchive {
+    dir: PathBuf,
+    /// WAL file name (`<db>.wal`); segments and index are named after it.
+    wal_name: String,
+    next_segment: u64,
+    next_frame: u64,
+}
+
+impl WalArchive {
+    /// Archive the WAL at `wal_path` into `dir`, creating the directory if
+    /// needed and continuing the numbering of an existing index.
+    pub fn open(dir: &Path, wal_path: &Path) -> Result<Self> {
+        let wal_name = wal_path
+            .file_name()
+            .and_then(|n| n.to_str())
+            .ok_or_else(|| MuroError::Wal(format!("invalid WAL path {}", wal_path.display())))?
+            .to_string();
+        std::fs::create_dir_all(dir)?;
+        let index_path = dir.join(format!("{}{}", wal_name, INDEX_SUFFIX));
+        let segments = read_index_file(dir, &wal_name, &index_path)?;
+        let (next_segment, next_frame) = segments
+            .last()
+            .map_or((1, 0), |s| (s.segment + 1, s.end_frame));
+        Ok(WalArchive {
+            dir: dir.to_path_buf(),
+            wal_name,
+            next_segment,
+            next_frame,
+        })
+    }
+
+    pub fn dir(&self) -> &Path {
+        &self.dir
+    }
+
+    fn index_path(&self) -> PathBuf {
+        self.dir.join(format!("{}{}", self.wal_name, INDEX_SUFFIX))
+    }
+
+    /// Copy the WAL at `wal_path`, holding `frames` frames, into the next
+    /// segment and record it in the index. Both are fsynced before this
+    /// returns, so the caller may then truncate the WAL.
+    pub fn store_segment(
+        &mut self,
+        wal_path: &Path,
+        frames: u64,
+        catalog_root: PageId,
+    ) -> Result<ArchivedSegment> {
+        let segment = self.next_segment;
+        let path = segment_path(&self.dir, &self.wal_name, segment);
+        // Copy under a temporary name so a crash never leaves a partial
+        // segment behind the final name.
+        let tmp = self
+            .dir
+            .join(format!("{}.{:06}.tmp", self.wal_name, segment));
+        {
+            let mut src = File::open(wal_path)?;
+            let mut dst = File::create(&tmp)?;
+            let mut buf = Vec::new();
+            src.read_to_end(&mut buf)?;
+            dst.write_all(&buf)?;
+            dst.sync_all()?;
+        }
+        std::fs::rename(&tmp, &path)?;
+
+        let archived = ArchivedSegment {
+            segment,
+            path,
+            start_frame: self.next_frame,
+            end_frame: self.next_frame + frames,
+            catalog_root,
+        };
+        let index_path = self.index_path();
+        let new_index = !index_path.exists();
+        let mut index = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&index_path)?;
+        if new_index {
+            writeln!(index, "{}", INDEX_HEADER)?;
+        }
+        writeln!(
+            index,
+            "{:06}\t{}\t{}\t{}",
+            archived.segment, archived.start_frame, archived.end_frame, archived.catalog_root
+        )?;
+        index.sync_all()?;
+        // Persist the segment's directory entry and the index creation.
+        if let Ok(dir) = File::open(&self.dir) {
+            let _ = dir.sync_all();
+        }
+
+        self.next_segment += 1;
+        self.next_frame = archived.end_frame;
+        Ok(archived)
+    }
+}
+
+fn segment_path(dir: &Path, wal_name: &str, segment: u64) -> PathBuf {
+    dir.join(format!("{}.{:06}", wal_name, segment))
+}
+
+fn read_index_file(dir: &Path, wal_name: &str, index_path: &Path) -> Result<Vec<ArchivedSegment>> {
+    let text = match std::fs::read_to_string(index_path) {
+        Ok(text) => text,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(Vec::new()),
+        Err(e) => return Err(e.into()),
+    };
+    let mut segments: Vec<ArchivedSegment> = Vec::new();
+    for (line_no, line) in text.lines().enumerate() {
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        let bad_line = || {
+            MuroError::Wal(format!(
+                "malformed WAL archive index {} line {}: {}",
+                index_path.display(),
+                line_no + 1,
+                line
+            ))
+        };
+        let fields: Vec<u64> = line
+            .split('\t')
+            .map(|f| f.parse::<u64>().map_err(|_| bad_line()))
+            .collect::<Result<_>>()?;
+        let [segment, start_frame, end_frame, catalog_root] = fields[..] else {
+            return Err(bad_line());
+        };
+        if let Some(prev) = segments.last() {
+            if segment != prev.segment + 1 || start_frame != prev.end_frame {
+                return Err(MuroError::Wal(format!(
+                    "WAL archive index {} has a gap before segment {}",
+                    index_path.display(),
+                    segment
+                )));
+            }
+        }
+        segments.push(ArchivedSegment {
+            segment,
+            path: segment_path(dir, wal_name, segment),
+            start_frame,
+            end_frame,
+            catalog_root,
+        });
+    }
+    Ok(segments)
+}
+
+/// Segments recorded in the index of `archive_dir`, in order.
+///
+/// The directory must hold the archive of exactly one database.
+pub fn read_archive_index(archive_dir: &Path) -> Result<Vec<ArchivedSegment>> {
+    let mut indexes = Vec::new();
+    for entry in std::fs::read_dir(archive_dir)? {
+        let name = entry?.file_name();
+        if let Some(wal_name) = name
+            .to_str()
+            .and_then(|n| n.strip_suffix(INDEX_SUFFIX))
+            .filter(|n| n.ends_with(".wal"))
+        {
+            indexes.push(wal_name.to_string());
+        }
+    }
+    match indexes.as_slice() {
+        [wal_name] => read_index_file(
+            archive_dir,
+            wal_name,
+            &archive_dir.join(format!("{}{}", wal_name, INDEX_SUFFIX)),
+        ),
+        [] => Err(MuroError::Wal(format!(
+            "no WAL archive index in {}",
+            archive_dir.display()
+        ))),
+        _ => Err(MuroError::Wal(format!(
+            "{} holds WAL archives of several databases: {}",
+            archive_dir.display(),
+            indexes.join(", ")
+        ))),
+    }
+}
+
+/// Rebuild a database at `target` from `base_backup` and the segments in
+/// `archive_dir`.
+///
+/// The base backup is copied to `target` (which must not exist), then every
+/// segment holding transactions the copy does not contain yet is replayed
+/// in order through WAL recovery. Segments whose committed transactions all
+/// predate the backup's next transaction id are skipped, so a backup taken
+/// with [`crate::Database::backup`] can be combined with an archive that
+/// started earlier. After each replay the catalog root must match the one
+/// the index recorded for that checkpoint.
+pub fn restore_from_archive(
+    base_backup: &Path,
+    archive_dir: &Path,
+    target: &Path,
+    suite: EncryptionSuite,
+    master_key: Option<&MasterKey>,
+) -> Result<ArchiveRestoreResult> {
+    if target.exists() {
+        return Err(MuroError::Execution(format!(
+            "restore target {} already exists",
+            target.display()
+        )));
+    }
+    let segments = read_archive_index(archive_dir)?;
+    std::fs::copy(base_backup, target)?;
+    File::open(target)?.sync_all()?;
+
+    let mut next_txid =
+        Pager::open_read_only_with_suite(target, Some(suite), master_key)?.next_txid();
+    let mut target_wal = target.as_os_str().to_os_string();
+    target_wal.push(".wal");
+    let target_wal = PathBuf::from(target_wal);
+
+    let mut result = ArchiveRestoreResult::default();
+    for segment in &segments {
+        let report =
+            inspect_wal_with_suite(&segment.path, suite, master_key, RecoveryMode::Strict)?;
+        let Some(&last_txid) = report.committed_txids.last() else {
+            result.segments_skipped.push(segment.segment);
+            continue;
+        };
+        if last_txid < next_txid {
+            result.segments_skipped.push(segment.segment);
+            continue;
+        }
+        std::fs::copy(&segment.path, &target_wal)?;
+        recover_with_mode_and_suite(target, &target_wal, suite, master_key, RecoveryMode::Strict)?;
+        let mut pager = Pager::open_with_suite(target, Some(suite), master_key)?;
+        if pager.catalog_root() != segment.catalog_root {
+            return Err(MuroError::Wal(format!(
+                "segment {} left catalog root {} but the archive index records {}",
+                segment.segment,
+                pager.catalog_root(),
+                segment.catalog_root
+            )));
+        }
+        // Replay does not advance the header's transaction counter.
+        next_txid = next_txid.max(last_txid + 1);
+        pager.set_next_txid(next_txid);
+        pager.flush_meta()?;
+        result.segments_replayed.push(segment.segment);
+    }
+    if target_wal.exists() {
+        std::fs::remove_file(&target_wal)?;
+    }
+    Ok(result)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::wal::record::WalRecord;
+    use crate::wal::writer::WalWriter;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_segments_and_index_continue_across_reopen() {
+        let dir = TempDir::new().unwrap();
+        let wal_path = dir.path().join("test.db.wal");
+        let archive_dir = dir.path().join("archive");
+        let mut writer = WalWriter::create_plaintext(&wal_path).unwrap();
+        writer.append(&WalRecord::Begin { txid: 1 }).unwrap();
+        writer.sync().unwrap();
+
+        let mut archive = WalArchive::open(&archive_dir, &wal_path).unwrap();
+        let first = archive.store_segment(&wal_path, 1, 7).unwrap();
+        assert_eq!(first.path, archive_dir.join("test.db.wal.000001"));
+        assert_eq!(
+            std::fs::read(&first.path).unwrap(),
+            std::fs::read(&wal_path).unwrap()
+        );
+
+        let mut archive = WalArchive::open(&archive_dir, &wal_path).unwrap();
+        let second = archive.store_segment(&wal_path, 3, 9).unwrap();
+        assert_eq!(
+            (second.segment, second.start_frame, second.end_frame),
+            (2, 1, 4)
+        );
+        assert_eq!(
+            read_archive_index(&archive_dir).unwrap(),
+            vec![first, second]
+        );
+    }
+
+    #[test]
+    fn test_index_rejects_gaps() {
+        let dir = TempDir::new().unwrap();
+        std::fs::write(
+            dir.path().join("test.db.wal.index"),
+            format!("{}\n000001\t0\t2\t1\n000003\t2\t5\t1\n", INDEX_HEADER),
+        )
+        .unwrap();
+        let err = read_archive_index(dir.path()).unwrap_err();
+        assert!(err.to_string().contains("gap before segment 3"), "{}", err);
+    }
+}
@@ -0,0 +1,257 @@
+//! WAL file header.
+//!
+//! Version 2 and later headers record which database the WAL belongs to, so
+//! recovery can refuse a WAL that sits next to the wrong data file (for
+//! example a data file restored from backup under a different key) before
+//! decrypting or applying anything:
+//!
+//! ```text
+//!   0..8    Magic "MUROWAL1"
+//!   8..12   Version (u32 LE) — currently 3
+//!   12..28  Database instance id (the data file's 16-byte header salt)
+//!   28..32  Page size (u32 LE)
+//!   32..36  Encryption suite ID (u32 LE)
+//!   36..44  Key check (first 8 bytes of an HMAC of the master key)
+//! ```
+//!
+//! Version 1 headers stop after the version field and carry no identity.
+//! Version 3 has the same header as version 2; its frames end with a CRC32
+//! of the encrypted payload (see [`crate::wal::writer::WalWriter`]).
+
+use std::fs::File;
+use std::io::{Read, Seek, SeekFrom};
+
+use crate::crypto::aead::MasterKey;
+use crate::crypto::hmac_util::derive_wal_key_check;
+use crate::crypto::suite::EncryptionSuite;
+use crate::error::{MuroError, Result};
+use crate::storage::page::PAGE_SIZE;
+use crate::wal::{WAL_HEADER_SIZE, WAL_HEADER_SIZE_V1, WAL_MAGIC, WAL_VERSION};
+
+/// The database configuration a WAL was written for.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct WalIdentity {
+    /// All zeros when unknown (WAL writers not bound to a database, and
+    /// databases created with a zero salt); such ids are never compared.
+    pub instance_id: [u8; 16],
+    pub page_size: u32,
+    pub suite_id: u32,
+    /// All zeros for plaintext databases.
+    pub key_check: [u8; 8],
+}
+
+impl WalIdentity {
+    pub fn new(
+        instance_id: [u8; 16],
+        suite: EncryptionSuite,
+        master_key: Option<&MasterKey>,
+    ) -> Self {
+        let key_check = match master_key {
+            Some(key) if suite.requires_master_key() => derive_wal_key_check(key),
+            _ => [0u8; 8],
+        };
+        WalIdentity {
+            instance_id,
+            page_size: PAGE_SIZE as u32,
+            suite_id: suite.id(),
+            key_check,
+        }
+    }
+
+    pub fn encode_header(&self) -> [u8; WAL_HEADER_SIZE] {
+        let mut header = [0u8; WAL_HEADER_SIZE];
+        header[0..8].copy_from_slice(WAL_MAGIC);
+        header[8..12].copy_from_slice(&WAL_VERSION.to_le_bytes());
+        header[12..28].copy_from_slice(&self.instance_id);
+        header[28..32].copy_from_slice(&self.page_size.to_le_bytes());
+        header[32..36].copy_from_slice(&self.suite_id.to_le_bytes());
+        header[36..44].copy_from_slice(&self.key_check);
+        header
+    }
+
+    /// Compare against the identity of the data file the WAL is about to be
+    /// replayed into, returning the first field that differs.
+    pub fn check_matches(&self, db: &WalIdentity) -> Result<()> {
+        let hex = |bytes: &[u8]| bytes.iter().map(|b| format!("{:02x}", b)).collect();
+        let suite_name = |id: u32| match EncryptionSuite::from_id(id) {
+            Ok(suite) => suite.as_str().to_string(),
+            Err(_) => format!("unknown suite {}", id),
+        };
+        let mismatch = |field: &'static str, wal_value: String, db_value: String| {
+            Err(MuroError::WalMismatch {
+                field,
+                wal_value,
+                db_value,
+            })
+        };
+        let unknown = [0u8; 16];
+        if self.instance_id != unknown
+            && db.instance_id != unknown
+            && self.instance_id != db.instance_id
+        {
+            return mismatch("instance_id", hex(&self.instance_id), hex(&db.instance_id));
+        }
+        if self.page_size != db.page_size {
+            return mismatch(
+                "page_size",
+                self.page_size.to_string(),
+                db.page_size.to_string(),
+            );
+        }
+        if self.suite_id != db.suite_id {
+            return mismatch("cipher", suite_name(self.suite_id), suite_name(db.suite_id));
+        }
+        if self.key_check != db.key_check {
+            return mismatch("key_check", hex(&self.key_check), hex(&db.key_check));
+        }
+        Ok(())
+    }
+}
+
+/// What the start of a WAL file holds.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum WalHeader {
+    /// No magic: a pre-header WAL whose frames start at offset 0.
+    Missing,
+    /// A version 1 header without identity fields.
+    V1,
+    V2(WalIdentity),
+    /// Same fields as version 2; frames carry a CRC32.
+    V3(WalIdentity),
+}
+
+impl WalHeader {
+    /// Offset of the first frame.
+    pub fn frames_offset(&self) -> usize {
+        match self {
+            WalHeader::Missing => 0,
+            WalHeader::V1 => WAL_HEADER_SIZE_V1,
+            WalHeader::V2(_) | WalHeader::V3(_) => WAL_HEADER_SIZE,
+        }
+    }
+
+    pub fn identity(&self) -> Option<&WalIdentity> {
+        match self {
+            WalHeader::V2(identity) | WalHeader::V3(identity) => Some(identity),
+            _ => None,
+        }
+    }
+
+    /// Whether each frame ends with a CRC32 of its encrypted payload.
+    pub fn has_frame_crc(&self) -> bool {
+        matches!(self, WalHeader::V3(_))
+    }
+}
+
+/// Read and validate the header at the start of `file`, leaving the file
+/// position at the first frame.
+pub fn read_wal_header(file: &mut File, file_len: u64) -> Result<WalHeader> {
+    file.seek(SeekFrom::Start(0))?;
+    if file_len < WAL_HEADER_SIZE_V1 as u64 {
+        return Ok(WalHeader::Missing);
+    }
+    let mut header = [0u8; WAL_HEADER_SIZE];
+    file.read_exact(&mut header[..WAL_HEADER_SIZE_V1])?;
+    if &header[0..8] != WAL_MAGIC {
+        file.seek(SeekFrom::Start(0))?;
+        return Ok(WalHeader::Missing);
+    }
+    let version = u32::from_le_bytes(header[8..12].try_into().unwrap());
+    match version {
+        0 | 1 => Ok(WalHeader::V1),
+        2 | WAL_VERSION => {
+            if file_len < WAL_HEADER_SIZE as u64 {
+                return Err(MuroError::Wal(format!(
+                    "WAL file is corrupt: size {} is smaller than the required header size {}",
+                    file_len, WAL_HEADER_SIZE
+                )));
+            }
+            file.read_exact(&mut header[WAL_HEADER_SIZE_V1..])?;
+            let mut instance_id = [0u8; 16];
+            instance_id.copy_from_slice(&header[12..28]);
+            let mut key_check = [0u8; 8];
+            key_check.copy_from_slice(&header[36..44]);
+            let identity = WalIdentity {
+                instance_id,
+                page_size: u32::from_le_bytes(header[28..32].try_into().unwrap()),
+                suite_id: u32::from_le_bytes(header[32..36].try_into().unwrap()),
+                key_check,
+            };
+            Ok(if version == 2 {
+                WalHeader::V2(identity)
+            } else {
+                WalHeader::V3(identity)
+            })
+        }
+        _ => Err(MuroError::Wal(format!(
+            "unsupported WAL format version {}",
+            version
+        ))),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Write;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_header_roundtrip() {
+        let dir = TempDir::new().unwrap();
+        let path = dir.path().join("test.wal");
+        let key = MasterKey::new([0x42u8; 32]);
+        let identity = WalIdentity::new([7u8; 16], EncryptionSuite::Aes256GcmSiv, Some(&key));
+        let mut file = File::create(&path).unwrap();
+        file.write_all(&identity.encode_header()).unwrap();
+        drop(file);
+
+        let mut file = File::open(&path).unwrap();
+        let header = read_wal_header(&mut file, WAL_HEADER_SIZE as u64).unwrap();
+        assert_eq!(header, WalHeader::V3(identity));
+        assert!(header.has_frame_crc());
+        assert_eq!(header.frames_offset(), WAL_HEADER_SIZE);
+    }
+
+    #[test]
+    fn test_check_matches_reports_first_differing_field() {
+        let key = MasterKey::new([0x42u8; 32]);
+        let other_key = MasterKey::new([0x43u8; 32]);
+        let db = WalIdentity::new([7u8; 16], EncryptionSuite::Aes256GcmSiv, Some(&key));
+        assert!(db.check_matches(&db).is_ok());
+
+        let foreign = WalIdentity::new([8u8; 16], EncryptionSuite::Aes256GcmSiv, Some(&key));
+        assert!(matches!(
+            foreign.check_matches(&db),
+            Err(MuroError::WalMismatch {
+                field: "instance_id",
+                ..
+            })
+        ));
+        let rekeyed = WalIdentity::new([7u8; 16], EncryptionSuite::Aes256GcmSiv, Some(&other_key));
+        assert!(matches!(
+            rekeyed.check_matches(&db),
+            Err(MuroError::WalMismatch {
+                field: "key_check",
+                ..
+            })
+        ));
+        let plaintext = WalIdentity::new([7u8; 16], EncryptionSuite::Plaintext, None);
+        match plaintext.check_matches(&db) {
+            Err(MuroError::WalMismatch {
+                field,
+                wal_value,
+                db_value,
+            }) => {
+                assert_eq!(field, "cipher");
+                assert_eq!(wal_value, "plaintext");
+                assert_eq!(db_value, "aes256-gcm-siv");
+            }
+            other => panic!("expected WalMismatch, got {:?}", other),
+        }
+
+        // A zero instance id is unknown and matches any database.
+        let unbound = WalIdentity::new([0u8; 16], EncryptionSuite::Aes256GcmSiv, Some(&key));
+        assert!(unbound.check_matches(&db).is_ok());
+    }
+}
@@ -1,5 +1,7 @@
 use crate::storage::page::PAGE_SIZE;
 
+pub mod archive;
+pub mod header;
 pub mod reader;
 pub mod record;
 pub mod recovery;
@@ -12,8 +14,13 @@ pub const MAX_WAL_FRAME_LEN: usize = PAGE_SIZE + 1024;
 /// WAL file magic bytes: "MUROWAL1" (8 bytes).
 pub const WAL_MAGIC: &[u8; 8] = b"MUROWAL1";
 
-/// WAL header size: magic (8) + version (4) = 12 bytes.
-pub const WAL_HEADER_SIZE: usize = 12;
+/// WAL header size: magic (8) + version (4) + instance id (16) +
+/// page size (4) + suite id (4) + key check (8) = 44 bytes.
+pub const WAL_HEADER_SIZE: usize = 44;
 
-/// WAL format version.
-pub const WAL_VERSION: u32 = 1;
+/// Header size of version 1 WAL files: magic (8) + version (4).
+pub const WAL_HEADER_SIZE_V1: usize = 12;
+
+/// WAL format version. Version 3 added a CRC32 to every frame; versions 1
+/// and 2 are still read.
+pub const WAL_VERSION: u32 = 3;
@@ -5,8 +5,86 @@ use std::path::Path;
 use crate::crypto::aead::MasterKey;
 use crate::crypto::suite::{EncryptionSuite, PageCipher};
 use crate::error::{MuroError, Result};
+use crate::wal::header::{read_wal_header, WalHeader};
 use crate::wal::record::{crc32, Lsn, WalRecord};
-use crate::wal::{MAX_WAL_FRAME_LEN, WAL_HEADER_SIZE, WAL_MAGIC, WAL_VERSION};
+use crate::wal::MAX_WAL_FRAME_LEN;
+use zeroize::Zeroizing;
+
+/// How the frames of a WAL end.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum WalTail {
+    /// The last frame is complete and valid.
+    Clean,
+    /// The log ends in a frame that was cut short or zero-filled, as a crash
+    /// in the middle of a write leaves it.
+    TornWrite,
+    /// The log ends in bytes that are neither valid frames nor a torn write.
+    Corrupt,
+}
+
+impl WalTail {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            WalTail::Clean => "clean",
+            WalTail::TornWrite => "torn_write",
+            WalTail::Corrupt => "corrupt",
+        }
+    }
+}
+
+/// A frame in the middle of the log that could not be read.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct UnreadableFrame {
+    /// The LSN the frame was written at.
+    pub lsn: Lsn,
+    /// Byte offset of the frame (its length field) in the WAL file.
+    pub offset: u64,
+}
+
+/// Everything [`WalReader::scan`] found in a WAL.
+#[derive(Debug)]
+pub struct WalScan {
+    pub records: Vec<(Lsn, WalRecord)>,
+    /// Frames skipped in the middle of the log, in file order. Always empty
+    /// when the scan does not skip unreadable frames.
+    pub unreadable: Vec<UnreadableFrame>,
+    /// Offset of the first frame that could not be read, mid-log or at the
+    /// tail. `None` when every frame was read.
+    pub first_corruption_offset: Option<u64>,
+    pub tail: WalTail,
+}
+
+/// One step through the log.
+enum Frame {
+    Record(Lsn, WalRecord),
+    /// No frames follow. `offset` is where unreadable tail bytes begin.
+    End {
+        tail: WalTail,
+        offset: Option<u64>,
+    },
+    /// A frame that failed validation with valid frames after it. When
+    /// `resumable`, the reader is positioned at the next frame; otherwise
+    /// the frame's length cannot be trusted and the next frame is unknown.
+    Unreadable {
+        frame: UnreadableFrame,
+        resumable: bool,
+        error: MuroError,
+    },
+}
+
+/// Smallest serialized record: a Begin or Abort, a tag and a txid.
+const MIN_RECORD_LEN: usize = 1 + 8;
+
+/// LSNs guessed after resuming past a damaged region of a plaintext WAL,
+/// whose frames do not authenticate their LSN.
+struct ProvisionalLsns {
+    /// Index in the scan of the first record read after the region.
+    first_record: usize,
+    /// Index in the scan of the first frame skipped after the region.
+    first_unreadable: usize,
+    /// Offset where the damaged region starts.
+    offset: u64,
+}
 
 /// WAL reader: iterate through WAL records for recovery/snapshot.
 pub struct WalReader {
@@ -14,6 +92,7 @@ pub struct WalReader {
     crypto: PageCipher,
     current_lsn: Lsn,
     file_len: u64,
+    header: WalHeader,
 }
 
 impl WalReader {
@@ -33,35 +112,24 @@ impl WalReader {
         let mut file = File::open(path)?;
         let file_len = file.metadata()?.len();
 
-        // Validate and skip WAL header if present
-        if file_len >= WAL_HEADER_SIZE as u64 {
-            let mut header = [0u8; WAL_HEADER_SIZE];
-            file.read_exact(&mut header)?;
-            if &header[0..8] == WAL_MAGIC {
-                // Check version — reject WAL files from a future format
-                let version = u32::from_le_bytes(header[8..12].try_into().unwrap());
-                if version > WAL_VERSION {
-                    return Err(MuroError::Wal(format!(
-                        "unsupported WAL format version {}",
-                        version
-                    )));
-                }
-                // Valid header - file position is now past the header
-            } else {
-                // Legacy WAL without header - seek back to start
-                file.seek(SeekFrom::Start(0))?;
-            }
-        }
-        // If file is smaller than header size, it's either empty or legacy
+        // Validate and skip the WAL header if present; a file without the
+        // magic is a legacy WAL whose frames start at offset 0.
+        let header = read_wal_header(&mut file, file_len)?;
 
         Ok(WalReader {
             file,
             crypto: PageCipher::new(suite, master_key)?,
             current_lsn: 0,
             file_len,
+            header,
         })
     }
 
+    /// The header found at open.
+    pub fn header(&self) -> &WalHeader {
+        &self.header
+    }
+
     /// Check whether the current file position is at or near the end of the WAL.
     /// "At tail" means there are no more complete, structurally plausible frames
     /// after the current position.
@@ -135,21 +203,12 @@ impl WalReader {
                 break false;
             }
 
-            let mut encrypted = vec![0u8; frame_len];
-            if self.file.read_exact(&mut encrypted).is_err() {
+            let mut body = vec![0u8; frame_len];
+            if self.file.read_exact(&mut body).is_err() {
                 break false;
             }
-
-            // Try to decrypt and validate CRC
-            if let Ok(payload) = self.crypto.decrypt(probe_lsn, 0, &encrypted) {
-                if payload.len() >= 4 {
-                    let record_bytes = &payload[..payload.len() - 4];
-                    let stored_crc =
-                        u32::from_le_bytes(payload[payload.len() - 4..].try_into().unwrap());
-                    if crc32(record_bytes) == stored_crc {
-                        break true;
-                    }
-                }
+            if self.decode_frame(probe_lsn, &body).is_ok() {
+                break true;
             }
 
             // This frame was invalid; keep scanning
@@ -162,42 +221,128 @@ impl WalReader {
         found
     }
 
-    /// Read the next WAL record. Returns None at end-of-file.
+    /// Validate, decrypt and parse one frame body written at `lsn`. The error
+    /// says what failed.
     ///
-    /// Tolerates partial/corrupt frames at the WAL tail (no valid frames follow).
-    /// Mid-log corruption (a corrupt frame followed by valid frames) is returned
-    /// as a hard error to avoid silently dropping committed records.
+    /// Version 3 frames end with a CRC32 of the encrypted payload, checked
+    /// before decrypting; the payload itself ends with a CRC32 of the record
+    /// bytes in every version.
+    fn decode_frame(&self, lsn: Lsn, body: &[u8]) -> std::result::Result<WalRecord, String> {
+        let encrypted = if self.header.has_frame_crc() {
+            if body.len() < 4 {
+                return Err(format!("WAL frame too short at LSN {}", lsn));
+            }
+            let (encrypted, stored) = body.split_at(body.len() - 4);
+            if crc32(encrypted) != u32::from_le_bytes(stored.try_into().unwrap()) {
+                return Err(format!("WAL frame CRC mismatch at LSN {}", lsn));
+            }
+            encrypted
+        } else {
+            body
+        };
+
+        let payload = match self.crypto.decrypt(lsn, 0, encrypted) {
+            Ok(p) => Zeroizing::new(p),
+            Err(_) => return Err(format!("Failed to decrypt WAL record at LSN {}", lsn)),
+        };
+        if payload.len() < 4 {
+            return Err("WAL record too short".into());
+        }
+
+        let record_bytes = &payload[..payload.len() - 4];
+        let stored_crc = u32::from_le_bytes(payload[payload.len() - 4..].try_into().unwrap());
+        if crc32(record_bytes) != stored_crc {
+            return Err(format!("CRC mismatch at LSN {}", lsn));
+        }
+        WalRecord::deserialize(record_bytes).ok_or_else(|| format!("Invalid record at LSN {}", lsn))
+    }
+
+    /// The log ends in unreadable bytes starting at `offset`, where a frame
+    /// ending at `frame_end` failed. It is a torn write when the file is zero
+    /// from the last bytes of that frame on, as when a crash leaves space the
+    /// filesystem allocated but never wrote; otherwise it is corrupt.
+    fn unreadable_tail(&mut self, offset: u64, frame_end: u64) -> Result<Frame> {
+        let start = frame_end.saturating_sub(4).max(offset);
+        self.file.seek(SeekFrom::Start(start))?;
+        let mut zeros = true;
+        let mut buf = [0u8; 4096];
+        loop {
+            let n = self.file.read(&mut buf)?;
+            if n == 0 {
+                break;
+            }
+            if buf[..n].iter().any(|&b| b != 0) {
+                zeros = false;
+                break;
+            }
+        }
+        self.file.seek(SeekFrom::End(0))?;
+        Ok(Frame::End {
+            tail: if zeros {
+                WalTail::TornWrite
+            } else {
+                WalTail::Corrupt
+            },
+            offset: Some(offset),
+        })
+    }
+
+    /// Read the next frame.
+    ///
+    /// Partial and corrupt frames at the tail (no valid frame follows) end
+    /// the log. A corrupt frame followed by valid frames is mid-log
+    /// corruption, returned as [`Frame::Unreadable`].
     ///
     /// The tail heuristic uses two layers:
     /// 1. **Structural check** (`is_at_tail`): no structurally plausible next frame.
     /// 2. **Content probe** (`has_valid_frame_ahead`): even if the next chunk looks
     ///    frame-shaped, if it (and everything after) fails decryption/CRC, there
     ///    are no valid records to protect and the corruption is treated as tail.
-    #[allow(clippy::should_implement_trait)]
-    pub fn next(&mut self) -> Result<Option<(Lsn, WalRecord)>> {
+    fn next_frame(&mut self) -> Result<Frame> {
+        let offset = self.file.stream_position()?;
+        let torn = Frame::End {
+            tail: WalTail::TornWrite,
+            offset: Some(offset),
+        };
+
         // Read frame length
         let mut len_buf = [0u8; 4];
         match self.file.read_exact(&mut len_buf) {
             Ok(()) => {}
-            Err(e) if e.kind() == std::io::ErrorKind::UnexpectedEof => return Ok(None),
+            Err(e) if e.kind() == std::io::ErrorKind::UnexpectedEof => {
+                // A partial length header is a write cut short.
+                if offset < self.file_len {
+                    return Ok(torn);
+                }
+                return Ok(Frame::End {
+                    tail: WalTail::Clean,
+                    offset: None,
+                });
+            }
             Err(e) => return Err(e.into()),
         }
 
         let frame_len = u32::from_le_bytes(len_buf) as usize;
-        let payload_pos = self.file.stream_position()?;
+        let payload_pos = offset + 4;
         let remaining_payload_bytes = self.file_len.saturating_sub(payload_pos);
+        let lsn = self.current_lsn;
+        let unreadable = |resumable: bool, error: String| Frame::Unreadable {
+            frame: UnreadableFrame { lsn, offset },
+            resumable,
+            error: MuroError::Wal(error),
+        };
 
         // Truncated tail frame: header is present but payload isn't fully written.
         if frame_len as u64 > remaining_payload_bytes {
-            return Ok(None);
+            return Ok(torn);
         }
         if frame_len == 0 {
             // Zero-length frame is never valid. If nothing valid follows, treat
             // as tail; otherwise it's genuine mid-log corruption.
             if !self.has_valid_frame_ahead() {
-                return Ok(None);
+                return self.unreadable_tail(offset, payload_pos);
             }
-            return Err(MuroError::Wal("WAL frame length is zero".into()));
+            return Ok(unreadable(false, "WAL frame length is zero".into()));
         }
         if frame_len > MAX_WAL_FRAME_LEN {
             // Oversized length — the length header itself may be corrupted, so we
@@ -206,103 +351,257 @@ impl WalReader {
             // Tolerate only when the claimed payload occupies the exact file tail
             // (original heuristic); otherwise report as corruption.
             if frame_len as u64 == remaining_payload_bytes {
-                return Ok(None);
+                return self.unreadable_tail(offset, self.file_len);
             }
-            return Err(MuroError::Wal(format!(
-                "WAL frame length {} exceeds max {} at LSN {}",
-                frame_len, MAX_WAL_FRAME_LEN, self.current_lsn
-            )));
+            return Ok(unreadable(
+                false,
+                format!(
+                    "WAL frame length {} exceeds max {} at LSN {}",
+                    frame_len, MAX_WAL_FRAME_LEN, lsn
+                ),
+            ));
         }
 
-        let mut encrypted = vec![0u8; frame_len];
-        match self.file.read_exact(&mut encrypted) {
+        let mut body = vec![0u8; frame_len];
+        match self.file.read_exact(&mut body) {
             Ok(()) => {}
             Err(e) if e.kind() == std::io::ErrorKind::UnexpectedEof => {
                 // Payload was truncated — this can only happen at the WAL tail
                 // (crash during frame write). Safe to treat as end-of-log.
-                return Ok(None);
+                return Ok(torn);
             }
             Err(e) => return Err(e.into()),
         }
 
-        let lsn = self.current_lsn;
-        // Two-layer tail check: structural heuristic first, then content probe
-        // as fallback. The probe is only called when validation fails, so the
-        // happy path pays no extra I/O cost.
-        let effectively_at_tail =
-            |this: &mut Self| -> bool { this.is_at_tail() || !this.has_valid_frame_ahead() };
-
-        let payload = match self.crypto.decrypt(lsn, 0, &encrypted) {
-            Ok(p) => p,
-            Err(_) if effectively_at_tail(self) => {
-                return Ok(None);
+        match self.decode_frame(lsn, &body) {
+            Ok(record) => {
+                self.current_lsn += 1;
+                Ok(Frame::Record(lsn, record))
             }
-            Err(_) => {
-                return Err(MuroError::Wal(format!(
-                    "Failed to decrypt WAL record at LSN {} (mid-log corruption)",
-                    lsn
-                )));
+            // Two-layer tail check: structural heuristic first, then content
+            // probe as fallback. The probe is only called when validation
+            // fails, so the happy path pays no extra I/O cost.
+            Err(_) if self.is_at_tail() || !self.has_valid_frame_ahead() => {
+                self.unreadable_tail(offset, payload_pos + frame_len as u64)
             }
-        };
-
-        if payload.len() < 4 {
-            if effectively_at_tail(self) {
-                return Ok(None);
+            Err(reason) => {
+                self.current_lsn += 1;
+                Ok(unreadable(true, format!("{} (mid-log corruption)", reason)))
             }
-            return Err(MuroError::Wal("WAL record too short".into()));
         }
+    }
 
-        let record_bytes = &payload[..payload.len() - 4];
-        let stored_crc = u32::from_le_bytes(payload[payload.len() - 4..].try_into().unwrap());
-
-        if crc32(record_bytes) != stored_crc {
-            if effectively_at_tail(self) {
-                return Ok(None);
-            }
-            return Err(MuroError::Wal(format!(
-                "CRC mismatch at LSN {} (mid-log corruption)",
-                lsn
-            )));
+    /// Read the next WAL record. Returns None at end-of-file.
+    ///
+    /// Tolerates partial/corrupt frames at the WAL tail (no valid frames follow).
+    /// Mid-log corruption (a corrupt frame followed by valid frames) is returned
+    /// as a hard error to avoid silently dropping committed records.
+    #[allow(clippy::should_implement_trait)]
+    pub fn next(&mut self) -> Result<Option<(Lsn, WalRecord)>> {
+        match self.next_frame()? {
+            Frame::Record(lsn, record) => Ok(Some((lsn, record))),
+            Frame::End { .. } => Ok(None),
+            Frame::Unreadable { error, .. } => Err(error),
         }
-
-        let record = match WalRecord::deserialize(record_bytes) {
-            Some(r) => r,
-            None => {
-                if effectively_at_tail(self) {
-                    return Ok(None);
-                }
-                return Err(MuroError::Wal(format!(
-                    "Invalid record at LSN {} (mid-log corruption)",
-                    lsn
-                )));
-            }
-        };
-
-        self.current_lsn += 1;
-        Ok(Some((lsn, record)))
     }
 
     /// Read all records into a vector.
     pub fn read_all(&mut self) -> Result<Vec<(Lsn, WalRecord)>> {
-        // Seek to start and skip header if present
-        self.file.seek(SeekFrom::Start(0))?;
+        Ok(self.scan(false)?.records)
+    }
+
+    /// Read every record from the start of the log, recording where reading
+    /// failed and how the log ends.
+    ///
+    /// Mid-log corruption is an error unless `skip_unreadable`, in which case
+    /// each unreadable frame is recorded and skipped. A frame whose length
+    /// cannot be trusted ends the scan, with a corrupt tail.
+    pub fn scan(&mut self, skip_unreadable: bool) -> Result<WalScan> {
+        self.scan_frames(skip_unreadable, false)
+    }
+
+    /// Read every record like `scan(true)`, but resynchronize after damage
+    /// that hides where the next frame starts instead of ending the log.
+    ///
+    /// Reading resumes at the first later offset holding a frame that
+    /// validates at an LSN the damaged bytes leave room for. Every LSN in
+    /// between is recorded as an unreadable frame at the offset where the
+    /// damage starts. Plaintext frames do not authenticate their LSN: the
+    /// frames after the damage are numbered from the first Commit record
+    /// read there, which states its own LSN.
+    pub fn salvage(&mut self) -> Result<WalScan> {
+        self.scan_frames(true, true)
+    }
+
+    fn scan_frames(&mut self, skip_unreadable: bool, resync: bool) -> Result<WalScan> {
+        // Skip the header validated at open
+        self.file
+            .seek(SeekFrom::Start(self.header.frames_offset() as u64))?;
         self.current_lsn = 0;
 
-        if self.file_len >= WAL_HEADER_SIZE as u64 {
-            let mut header = [0u8; WAL_HEADER_SIZE];
-            if self.file.read_exact(&mut header).is_ok() && &header[0..8] == WAL_MAGIC {
-                // Valid header - continue reading from after header
+        let mut scan = WalScan {
+            records: Vec::new(),
+            unreadable: Vec::new(),
+            first_corruption_offset: None,
+            tail: WalTail::Clean,
+        };
+        let mut provisional = None;
+        loop {
+            match self.next_frame()? {
+                Frame::Record(lsn, record) => {
+                    let lsn = match (&provisional, &record) {
+                        (Some(p), WalRecord::Commit { lsn: stated, .. }) => {
+                            let shift = stated.saturating_sub(lsn);
+                            self.renumber(&mut scan, p, lsn, shift);
+                            provisional = None;
+                            lsn + shift
+                        }
+                        _ => lsn,
+                    };
+                    scan.records.push((lsn, record));
+                }
+                Frame::End { tail, offset } => {
+                    if let Some(offset) = offset.filter(|_| resync) {
+                        if let Some(p) = self.resume_after(offset, &mut scan)? {
+                            provisional = p;
+                            continue;
+                        }
+                    }
+                    scan.tail = tail;
+                    scan.first_corruption_offset = scan.first_corruption_offset.or(offset);
+                    return Ok(scan);
+                }
+                Frame::Unreadable {
+                    frame,
+                    resumable,
+                    error,
+                } => {
+                    if !skip_unreadable {
+                        return Err(error);
+                    }
+                    if resync && !resumable {
+                        if let Some(p) = self.resume_after(frame.offset, &mut scan)? {
+                            provisional = p;
+                            continue;
+                        }
+                    }
+                    scan.first_corruption_offset.get_or_insert(frame.offset);
+                    scan.unreadable.push(frame);
+                    if !resumable {
+                        scan.tail = WalTail::Corrupt;
+                        return Ok(scan);
+                    }
+                }
+            }
+        }
+    }
+
+    /// Resume reading after the frame at `offset`, written at the current
+    /// LSN, whose length cannot be trusted. Records the frames lost in
+    /// between and returns whether the LSNs read next are provisional, or
+    /// `None` when no valid frame follows.
+    fn resume_after(
+        &mut self,
+        offset: u64,
+        scan: &mut WalScan,
+    ) -> Result<Option<Option<ProvisionalLsns>>> {
+        let damaged_lsn = self.current_lsn;
+        let Some((resume_offset, resume_lsn)) = self.find_next_frame(offset, damaged_lsn)? else {
+            return Ok(None);
+        };
+        scan.first_corruption_offset.get_or_insert(offset);
+        scan.unreadable
+            .extend((damaged_lsn..resume_lsn).map(|lsn| UnreadableFrame { lsn, offset }));
+        self.file.seek(SeekFrom::Start(resume_offset))?;
+        self.current_lsn = resume_lsn;
+        Ok(Some(
+            (!self.crypto.suite().requires_master_key()).then_some(ProvisionalLsns {
+                first_record: scan.records.len(),
+                first_unreadable: scan.unreadable.len(),
+                offset,
+            }),
+        ))
+    }
+
+    /// The offset and LSN of the first frame after `offset` that validates.
+    ///
+    /// Each frame lost in between takes at least the bytes of the smallest
+    /// record, which bounds the LSNs tried at each offset. A plaintext frame
+    /// validates at any LSN and is given the first one.
+    fn find_next_frame(&mut self, offset: u64, damaged_lsn: Lsn) -> Result<Option<(u64, Lsn)>> {
+        let frame_crc_len = if self.header.has_frame_crc() { 4 } else { 0 };
+        let min_frame_len =
+            (4 + self.crypto.overhead() + MIN_RECORD_LEN + 4 + frame_crc_len) as u64;
+        let lsn_known = self.crypto.suite().requires_master_key();
+
+        let start = offset + 1;
+        if start >= self.file_len {
+            return Ok(None);
+        }
+        self.file.seek(SeekFrom::Start(start))?;
+        let mut bytes = Vec::with_capacity((self.file_len - start) as usize);
+        self.file.read_to_end(&mut bytes)?;
+
+        for pos in 0..bytes.len().saturating_sub(4) {
+            let frame_len = u32::from_le_bytes(bytes[pos..pos + 4].try_into().unwrap()) as usize;
+            if frame_len == 0 || frame_len > MAX_WAL_FRAME_LEN || pos + 4 + frame_len > bytes.len()
+            {
+                continue;
+            }
+            let body = &bytes[pos + 4..pos + 4 + frame_len];
+            if frame_crc_len > 0 {
+                let (encrypted, stored) = body.split_at(body.len().saturating_sub(4));
+                if stored.len() < 4
+                    || crc32(encrypted) != u32::from_le_bytes(stored.try_into().unwrap())
+                {
+                    continue;
+                }
+            }
+            let frame_offset = start + pos as u64;
+            let max_lost = ((frame_offset - offset) / min_frame_len).max(1);
+            let last_lsn = if lsn_known {
+                damaged_lsn + max_lost
             } else {
-                // Legacy format or read failure - seek back to start
-                self.file.seek(SeekFrom::Start(0))?;
+                damaged_lsn + 1
+            };
+            for lsn in damaged_lsn + 1..=last_lsn {
+                if self.decode_frame(lsn, body).is_ok() {
+                    return Ok(Some((frame_offset, lsn)));
+                }
             }
         }
+        Ok(None)
+    }
 
-        let mut records = Vec::new();
-        while let Some(record) = self.next()? {
-            records.push(record);
+    /// Shift the provisional LSNs of the records read since `provisional`,
+    /// up to the Commit read at `commit_lsn`, by `shift`, adding as many
+    /// frames to those lost before them.
+    fn renumber(
+        &mut self,
+        scan: &mut WalScan,
+        provisional: &ProvisionalLsns,
+        commit_lsn: Lsn,
+        shift: Lsn,
+    ) {
+        let first_lsn = scan
+            .records
+            .get(provisional.first_record)
+            .map_or(commit_lsn, |(lsn, _)| *lsn);
+        for (lsn, _) in &mut scan.records[provisional.first_record..] {
+            *lsn += shift;
         }
-        Ok(records)
+        let skipped = scan.unreadable.split_off(provisional.first_unreadable);
+        scan.unreadable
+            .extend((first_lsn..first_lsn + shift).map(|lsn| UnreadableFrame {
+                lsn,
+                offset: provisional.offset,
+            }));
+        scan.unreadable
+            .extend(skipped.into_iter().map(|frame| UnreadableFrame {
+                lsn: frame.lsn + shift,
+                ..frame
+            }));
+        self.current_lsn += shift;
     }
 }
 
@@ -310,6 +609,7 @@ impl WalReader {
 mod tests {
     use super::*;
     use crate::wal::writer::WalWriter;
+    use crate::wal::WAL_HEADER_SIZE;
     use std::io::Write;
     use tempfile::NamedTempFile;
 
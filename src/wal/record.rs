@@ -10,6 +10,7 @@
 ///   Commit(txid, lsn)
 ///   Abort(txid)
 use crate::storage::page::PageId;
+use zeroize::Zeroize;
 
 pub type TxId = u64;
 pub type Lsn = u64;
@@ -40,6 +41,15 @@ pub enum WalRecord {
     },
 }
 
+/// A `PagePut` carries a plaintext page image; it is zeroed on drop.
+impl Drop for WalRecord {
+    fn drop(&mut self) {
+        if let WalRecord::PagePut { data, .. } = self {
+            data.zeroize();
+        }
+    }
+}
+
 const TAG_BEGIN: u8 = 1;
 const TAG_PAGE_PUT: u8 = 2;
 const TAG_COMMIT: u8 = 3;
@@ -6,13 +6,26 @@ use crate::crypto::suite::EncryptionSuite;
 use crate::error::{MuroError, Result};
 use crate::storage::page::{Page, PageId, PAGE_SIZE};
 use crate::storage::pager::Pager;
-use crate::wal::reader::WalReader;
-use crate::wal::record::{TxId, WalRecord};
+use crate::wal::header::{read_wal_header, WalIdentity};
+use crate::wal::reader::{UnreadableFrame, WalReader, WalTail};
+use crate::wal::record::{Lsn, TxId, WalRecord};
+use zeroize::Zeroizing;
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum RecoveryMode {
     Strict,
     Permissive,
+    Salvage,
+}
+
+impl RecoveryMode {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            RecoveryMode::Strict => "strict",
+            RecoveryMode::Permissive => "permissive",
+            RecoveryMode::Salvage => "salvage",
+        }
+    }
 }
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
@@ -59,6 +72,9 @@ struct TxValidationState {
     seen_begin: bool,
     seen_meta_update: bool,
     terminal: Option<TxTerminalState>,
+    begin_lsn: Option<Lsn>,
+    /// LSN of the first Commit record, valid or not.
+    commit_lsn: Option<Lsn>,
 }
 
 impl TxValidationState {
@@ -67,8 +83,27 @@ impl TxValidationState {
             seen_begin: false,
             seen_meta_update: false,
             terminal: None,
+            begin_lsn: None,
+            commit_lsn: None,
         }
     }
+
+    /// Whether an unreadable frame at `lsn` may have belonged to this
+    /// transaction: it lies before the Commit record and after the Begin
+    /// record, or anywhere before the Commit when the Begin was not read.
+    fn may_own(&self, lsn: Lsn) -> bool {
+        self.commit_lsn.is_some_and(|commit| lsn < commit)
+            && self.begin_lsn.is_none_or(|begin| lsn > begin)
+    }
+
+    /// Whether an unreadable frame at `lsn` may have belonged to this
+    /// transaction, its Commit record included: it lies after the Begin
+    /// record and before any Commit, in a transaction that did not abort.
+    fn may_have_lost(&self, lsn: Lsn) -> bool {
+        self.terminal != Some(TxTerminalState::Aborted)
+            && self.begin_lsn.is_none_or(|begin| lsn > begin)
+            && self.commit_lsn.is_none_or(|commit| lsn < commit)
+    }
 }
 
 /// Recover the database from WAL.
@@ -82,6 +117,8 @@ pub fn recover(db_path: &Path, wal_path: &Path, master_key: &MasterKey) -> Resul
 ///
 /// - `Strict`: reject any WAL protocol inconsistency.
 /// - `Permissive`: ignore malformed transactions and recover valid committed ones.
+/// - `Salvage`: as `Permissive`, and also read past damage that hides where
+///   the next frame starts, resuming at the next frame that validates.
 pub fn recover_with_mode(
     db_path: &Path,
     wal_path: &Path,
@@ -141,25 +178,29 @@ fn recover_with_mode_internal(
     apply_to_db: bool,
 ) -> Result<RecoveryResult> {
     if !wal_path.exists() {
-        return Ok(RecoveryResult {
-            committed_txids: Vec::new(),
-            aborted_txids: Vec::new(),
-            pages_replayed: 0,
-            skipped: Vec::new(),
-            wal_quarantine_path: None,
-        });
+        return Ok(RecoveryResult::empty());
+    }
+
+    if let Some(db_path) = db_path {
+        check_wal_belongs_to_db(db_path, wal_path, master_key)?;
     }
 
+    // Permissive recovery skips unreadable frames mid-log; the transactions
+    // they may have belonged to are reported instead of replayed.
     let mut reader = WalReader::open_with_suite(wal_path, suite, master_key)?;
-    let records = reader.read_all()?;
+    let scan = match mode {
+        RecoveryMode::Strict => reader.scan(false)?,
+        RecoveryMode::Permissive => reader.scan(true)?,
+        RecoveryMode::Salvage => reader.salvage()?,
+    };
+    let records = scan.records;
 
     if records.is_empty() {
         return Ok(RecoveryResult {
-            committed_txids: Vec::new(),
-            aborted_txids: Vec::new(),
-            pages_replayed: 0,
-            skipped: Vec::new(),
-            wal_quarantine_path: None,
+            unreadable_frames: scan.unreadable,
+            first_corruption_offset: scan.first_corruption_offset,
+            wal_tail: scan.tail,
+            ..RecoveryResult::empty()
         });
     }
 
@@ -173,7 +214,7 @@ fn recover_with_mode_internal(
     let mut invalidate_or_err = |txid: TxId, code: RecoverySkipCode, msg: String| -> Result<()> {
         match mode {
             RecoveryMode::Strict => Err(MuroError::Wal(msg)),
-            RecoveryMode::Permissive => {
+            RecoveryMode::Permissive | RecoveryMode::Salvage => {
                 invalid_txs.entry(txid).or_insert(RecoverySkippedTx {
                     txid,
                     code,
@@ -210,6 +251,7 @@ fn recover_with_mode_internal(
                     continue;
                 }
                 state.seen_begin = true;
+                state.begin_lsn = Some(*lsn);
             }
             WalRecord::PagePut { txid, .. } => {
                 let state = tx_states
@@ -267,6 +309,7 @@ fn recover_with_mode_internal(
                 let state = tx_states
                     .entry(*txid)
                     .or_insert_with(TxValidationState::new);
+                state.commit_lsn.get_or_insert(*lsn);
                 if !state.seen_begin {
                     invalidate_or_err(
                         *txid,
@@ -329,10 +372,31 @@ fn recover_with_mode_internal(
         }
     }
 
+    // A transaction whose Commit was read but which may have lost a frame
+    // is incomplete, not malformed: none of it is replayed.
+    let mut incomplete_txids = tx_states
+        .iter()
+        .filter(|(_, state)| scan.unreadable.iter().any(|f| state.may_own(f.lsn)))
+        .map(|(txid, _)| *txid)
+        .collect::<Vec<_>>();
+    incomplete_txids.sort_unstable();
+
+    // Transactions that may have lost any frame, their Commit included, are
+    // abandoned: what was read of them is not malformed, only cut short.
+    let mut abandoned_txids = tx_states
+        .iter()
+        .filter(|(_, state)| scan.unreadable.iter().any(|f| state.may_have_lost(f.lsn)))
+        .map(|(txid, _)| *txid)
+        .collect::<Vec<_>>();
+    abandoned_txids.sort_unstable();
+    for txid in &abandoned_txids {
+        invalid_txs.remove(txid);
+    }
+
     let terminal: HashMap<TxId, TxTerminalState> = tx_states
         .iter()
         .filter_map(|(txid, state)| {
-            if invalid_txs.contains_key(txid) {
+            if invalid_txs.contains_key(txid) || abandoned_txids.binary_search(txid).is_ok() {
                 None
             } else {
                 state.terminal.map(|t| (*txid, t))
@@ -341,7 +405,7 @@ fn recover_with_mode_internal(
         .collect();
 
     // Phase 2: Collect the latest page data and metadata from committed transactions
-    let mut page_updates: HashMap<PageId, Vec<u8>> = HashMap::new();
+    let mut page_updates: HashMap<PageId, Zeroizing<Vec<u8>>> = HashMap::new();
     let mut latest_catalog_root: Option<u64> = None;
     let mut latest_page_count: Option<u64> = None;
     let mut latest_freelist_page_id: Option<u64> = None;
@@ -355,7 +419,7 @@ fn recover_with_mode_internal(
                 data,
             } => {
                 if matches!(terminal.get(txid), Some(TxTerminalState::Committed)) {
-                    page_updates.insert(*page_id, data.clone());
+                    page_updates.insert(*page_id, Zeroizing::new(data.clone()));
                 }
             }
             WalRecord::MetaUpdate {
@@ -378,7 +442,7 @@ fn recover_with_mode_internal(
 
     // Phase 3: Validate/collect replayable page updates and optionally apply to DB.
     let mut pager = if apply_to_db {
-        Some(Pager::open_with_suite(
+        Some(Pager::open_for_recovery(
             db_path.expect("db path required"),
             Some(suite),
             master_key,
@@ -387,6 +451,7 @@ fn recover_with_mode_internal(
         None
     };
     let mut pages_replayed = 0;
+    let mut replayed_pages = Zeroizing::new(Vec::with_capacity(page_updates.len()));
 
     for (&page_id, data) in &page_updates {
         if data.len() != PAGE_SIZE {
@@ -399,7 +464,7 @@ fn recover_with_mode_internal(
                         PAGE_SIZE
                     )));
                 }
-                RecoveryMode::Permissive => continue,
+                RecoveryMode::Permissive | RecoveryMode::Salvage => continue,
             }
         }
         let mut page_data = [0u8; PAGE_SIZE];
@@ -414,14 +479,18 @@ fn recover_with_mode_internal(
                         page_id, embedded_page_id
                     )));
                 }
-                RecoveryMode::Permissive => continue,
+                RecoveryMode::Permissive | RecoveryMode::Salvage => continue,
             }
         }
-        if let Some(p) = pager.as_mut() {
-            p.write_page(&page)?;
+        if pager.is_some() {
+            replayed_pages.push(page);
         }
         pages_replayed += 1;
     }
+    // Applied in page order, one write per run of consecutive pages.
+    if let Some(p) = pager.as_mut() {
+        p.write_pages(replayed_pages.iter())?;
+    }
 
     // Phase 4: Restore metadata from WAL MetaUpdate records (DB apply mode only)
     if let Some(p) = pager.as_mut() {
@@ -485,10 +554,60 @@ fn recover_with_mode_internal(
             skipped.sort_by_key(|x| x.txid);
             skipped
         },
+        incomplete_txids,
+        abandoned_txids,
+        unreadable_frames: scan.unreadable,
+        first_corruption_offset: scan.first_corruption_offset,
+        wal_tail: scan.tail,
         wal_quarantine_path: None,
     })
 }
 
+/// Refuse a WAL that was written for another database or configuration.
+///
+/// Compares the identity in the WAL header (instance id, page size, cipher,
+/// key check) with the data file before any frame is decrypted. This applies
+/// in every recovery mode: quarantining a foreign WAL would silently drop
+/// committed data that belongs to some other file.
+///
+/// Nothing is compared for WALs without frames (there is nothing to replay)
+/// or with a version 1 header, which records no identity. A zero instance id
+/// on either side is unknown and skipped; the other fields are still checked.
+pub fn check_wal_belongs_to_db(
+    db_path: &Path,
+    wal_path: &Path,
+    master_key: Option<&MasterKey>,
+) -> Result<()> {
+    let mut file = std::fs::File::open(wal_path)?;
+    let file_len = file.metadata()?.len();
+    let header = read_wal_header(&mut file, file_len)?;
+    let Some(wal) = header.identity() else {
+        return Ok(());
+    };
+    if file_len <= header.frames_offset() as u64 {
+        return Ok(());
+    }
+
+    let info = Pager::read_encryption_info_from_file(db_path)?;
+    let db = WalIdentity::new(info.salt, info.suite, master_key);
+    match wal.check_matches(&db) {
+        Err(MuroError::WalMismatch {
+            field: "key_check", ..
+        }) if info.suite.requires_master_key() => {
+            // A key that cannot read the data file either is a wrong key, not
+            // a foreign WAL: report it the way opening the data file would.
+            let mut pager =
+                Pager::open_read_only_with_suite(db_path, Some(info.suite), master_key)?;
+            let root = pager.catalog_root();
+            if root < pager.page_count() {
+                pager.read_page(root)?;
+            }
+            wal.check_matches(&db)
+        }
+        result => result,
+    }
+}
+
 /// Recover the database from WAL in permissive mode.
 pub fn recover_permissive(
     db_path: &Path,
@@ -500,15 +619,60 @@ pub fn recover_permissive(
 
 #[derive(Debug)]
 pub struct RecoveryResult {
-    /// Txids that reached `Commit`, sorted in ascending order.
+    /// Txids that reached `Commit` with every frame readable, sorted in
+    /// ascending order. These are the transactions replayed in full.
     pub committed_txids: Vec<TxId>,
     /// Txids that reached `Abort`, sorted in ascending order.
     pub aborted_txids: Vec<TxId>,
     pub pages_replayed: usize,
     pub skipped: Vec<RecoverySkippedTx>,
+    /// Txids whose `Commit` record was read but which may have lost frames
+    /// to unreadable WAL bytes, sorted in ascending order. None of their
+    /// pages are replayed. Only permissive and salvage recovery read past
+    /// such frames.
+    pub incomplete_txids: Vec<TxId>,
+    /// Txids that may have lost any frame to unreadable WAL bytes, their
+    /// `Commit` included, sorted in ascending order. Includes
+    /// `incomplete_txids`; none of their pages are replayed.
+    pub abandoned_txids: Vec<TxId>,
+    /// Frames skipped in the middle of the WAL (permissive mode), in file
+    /// order.
+    pub unreadable_frames: Vec<UnreadableFrame>,
+    /// Byte offset in the WAL file of the first frame that could not be
+    /// read, mid-log or at the tail.
+    pub first_corruption_offset: Option<u64>,
+    /// How the WAL ended: cleanly, in a torn write, or in corrupt bytes.
+    pub wal_tail: WalTail,
     pub wal_quarantine_path: Option<String>,
 }
 
+impl RecoveryResult {
+    fn empty() -> Self {
+        RecoveryResult {
+            committed_txids: Vec::new(),
+            aborted_txids: Vec::new(),
+            pages_replayed: 0,
+            skipped: Vec::new(),
+            incomplete_txids: Vec::new(),
+            abandoned_txids: Vec::new(),
+            unreadable_frames: Vec::new(),
+            first_corruption_offset: None,
+            wal_tail: WalTail::Clean,
+            wal_quarantine_path: None,
+        }
+    }
+
+    /// Whether recovery left WAL contents behind that may hold committed
+    /// work: malformed or incomplete transactions, or frames it could not
+    /// read mid-log. An unreadable tail is dropped in every mode, as a crash
+    /// during a write leaves one, and does not count.
+    pub fn lost_committed_data(&self) -> bool {
+        !self.skipped.is_empty()
+            || !self.incomplete_txids.is_empty()
+            || !self.unreadable_frames.is_empty()
+    }
+}
+
 #[derive(Debug)]
 pub struct RecoverySkippedTx {
     pub txid: TxId,
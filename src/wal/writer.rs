@@ -1,30 +1,86 @@
 use std::fs::{File, OpenOptions};
 use std::io::{Read, Seek, SeekFrom, Write};
 use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant};
 
 use crate::crypto::aead::MasterKey;
 use crate::crypto::suite::{EncryptionSuite, PageCipher};
 use crate::error::{MuroError, Result};
+use crate::storage::page::PageId;
+use crate::wal::archive::WalArchive;
+use crate::wal::header::{read_wal_header, WalHeader, WalIdentity};
 use crate::wal::record::{crc32, Lsn, WalRecord};
-use crate::wal::{MAX_WAL_FRAME_LEN, WAL_HEADER_SIZE, WAL_MAGIC, WAL_VERSION};
+use crate::wal::{MAX_WAL_FRAME_LEN, WAL_HEADER_SIZE, WAL_HEADER_SIZE_V1};
+use zeroize::Zeroizing;
+
+/// Default bytes of encrypted frames a commit buffers before writing them out.
+pub const DEFAULT_WAL_WRITE_BUFFER_BYTES: usize = 1024 * 1024;
+
+/// When commit records are fsynced.
+///
+/// Relaxed modes may lose the most recent commits on power failure, but never
+/// corrupt the database: data pages of a commit whose WAL records are not yet
+/// fsynced are held in memory instead of being written to the data file.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum WalDurability {
+    /// Fsync on every commit.
+    #[default]
+    Full,
+    /// Consecutive commits share one fsync. The batch is synced by the commit
+    /// that makes it `max_batch` commits long or that arrives `max_delay_ms`
+    /// after the batch's first commit; an idle batch is synced by the next
+    /// statement, checkpoint or flush.
+    GroupCommit { max_delay_ms: u64, max_batch: u32 },
+    /// Fsync only at checkpoint (or flush).
+    Async,
+}
+
 /// WAL writer: append-only log with encryption.
 ///
 /// Framing on disk:
-///   [frame_len: u32 (of encrypted payload)] [encrypted payload]
+///   [frame_len: u32] [encrypted payload] [crc32 of encrypted payload: u32]
+///
+/// `frame_len` counts the encrypted payload and its CRC. Frames of version 1
+/// and 2 WALs have no CRC; writers appending to such a file keep its format.
 ///
 /// Encrypted payload contains:
 ///   [record bytes] [crc32: u4]
 pub struct WalWriter {
-    file: File,
+    /// `None` for a read-only handle, which never touches the WAL file.
+    file: Option<File>,
     path: PathBuf,
     crypto: PageCipher,
     current_lsn: Lsn,
+    /// Offset of the first frame; checkpoints truncate back to it.
+    header_len: u64,
+    /// Whether frames end with a CRC32 (version 3 and later files).
+    frame_crc: bool,
+    /// Frames appended since this writer was created; never reset.
+    frames_appended: u64,
+    durability: WalDurability,
+    /// Commits appended since the last fsync.
+    unsynced_commits: u32,
+    batch_started_at: Option<Instant>,
+    /// Where checkpoints copy the WAL before truncating it.
+    archive: Option<WalArchive>,
+    /// Frames from [`WalWriter::append_buffered`] not yet written to the file.
+    write_buffer: Vec<u8>,
+    write_buffer_limit: usize,
+    write_buffer_high_water: usize,
+    /// Writes of frames to the file; never reset.
+    write_calls: u64,
+    /// `write_calls` when the current commit's Begin frame was appended.
+    commit_write_calls_start: u64,
+    /// Writes the last commit took, from its Begin frame to its Commit frame.
+    last_commit_write_calls: u64,
     #[cfg(test)]
     inject_write_failure: Option<std::io::ErrorKind>,
     #[cfg(test)]
     inject_sync_failure: Option<std::io::ErrorKind>,
     #[cfg(any(test, feature = "test-utils"))]
     inject_checkpoint_truncate_failure: Option<std::io::ErrorKind>,
+    #[cfg(any(test, feature = "test-utils"))]
+    fault: Option<crate::fault::FaultInjector>,
 }
 
 impl WalWriter {
@@ -36,10 +92,22 @@ impl WalWriter {
         Self::create_with_suite(path, EncryptionSuite::Plaintext, None)
     }
 
+    /// Create a WAL that is not bound to a database instance: recovery
+    /// checks its page size, cipher and key but not its instance id.
     pub fn create_with_suite(
         path: &Path,
         suite: EncryptionSuite,
         master_key: Option<&MasterKey>,
+    ) -> Result<Self> {
+        Self::create_for_instance(path, suite, master_key, [0u8; 16])
+    }
+
+    /// Create a WAL for the database whose header salt is `instance_id`.
+    pub fn create_for_instance(
+        path: &Path,
+        suite: EncryptionSuite,
+        master_key: Option<&MasterKey>,
+        instance_id: [u8; 16],
     ) -> Result<Self> {
         let mut file = OpenOptions::new()
             .create(true)
@@ -49,19 +117,35 @@ impl WalWriter {
             .open(path)?;
 
         // Write WAL header
-        Self::write_wal_header(&mut file)?;
+        let identity = WalIdentity::new(instance_id, suite, master_key);
+        file.write_all(&identity.encode_header())?;
 
         Ok(WalWriter {
-            file,
+            file: Some(file),
             path: path.to_path_buf(),
             crypto: PageCipher::new(suite, master_key)?,
             current_lsn: 0,
+            header_len: WAL_HEADER_SIZE as u64,
+            frame_crc: true,
+            frames_appended: 0,
+            durability: WalDurability::Full,
+            unsynced_commits: 0,
+            batch_started_at: None,
+            archive: None,
+            write_buffer: Vec::new(),
+            write_buffer_limit: DEFAULT_WAL_WRITE_BUFFER_BYTES,
+            write_buffer_high_water: 0,
+            write_calls: 0,
+            commit_write_calls_start: 0,
+            last_commit_write_calls: 0,
             #[cfg(test)]
             inject_write_failure: None,
             #[cfg(test)]
             inject_sync_failure: None,
             #[cfg(any(test, feature = "test-utils"))]
             inject_checkpoint_truncate_failure: None,
+            #[cfg(any(test, feature = "test-utils"))]
+            fault: None,
         })
     }
 
@@ -92,74 +176,139 @@ impl WalWriter {
             .open(path)?;
 
         let file_len = file.metadata()?.len();
-        if file_len == 0 {
+        let (header_len, frame_crc) = if file_len == 0 {
             // Empty file: write header
-            Self::write_wal_header(&mut file)?;
-        } else if file_len >= WAL_HEADER_SIZE as u64 {
-            // Validate existing header
-            Self::validate_wal_header(&mut file)?;
+            let identity = WalIdentity::new([0u8; 16], suite, master_key);
+            file.write_all(&identity.encode_header())?;
+            (WAL_HEADER_SIZE as u64, true)
+        } else if file_len >= WAL_HEADER_SIZE_V1 as u64 {
+            // Validate existing header; older files keep their header and
+            // frame format.
+            let header = read_wal_header(&mut file, file_len)?;
+            if header == WalHeader::Missing {
+                return Err(MuroError::Wal(
+                    "WAL file magic mismatch: not a valid MuroDB WAL file".into(),
+                ));
+            }
             file.seek(SeekFrom::End(0))?;
+            (header.frames_offset() as u64, header.has_frame_crc())
         } else {
             // Non-empty but shorter than the WAL header — the file is corrupt.
             return Err(MuroError::Wal(format!(
                 "WAL file is corrupt: size {} is smaller than the required header size {}",
-                file_len, WAL_HEADER_SIZE
+                file_len, WAL_HEADER_SIZE_V1
             )));
-        }
+        };
 
         Ok(WalWriter {
-            file,
+            file: Some(file),
             path: path.to_path_buf(),
             crypto: PageCipher::new(suite, master_key)?,
             current_lsn: start_lsn,
+            header_len,
+            frame_crc,
+            frames_appended: 0,
+            durability: WalDurability::Full,
+            unsynced_commits: 0,
+            batch_started_at: None,
+            archive: None,
+            write_buffer: Vec::new(),
+            write_buffer_limit: DEFAULT_WAL_WRITE_BUFFER_BYTES,
+            write_buffer_high_water: 0,
+            write_calls: 0,
+            commit_write_calls_start: 0,
+            last_commit_write_calls: 0,
             #[cfg(test)]
             inject_write_failure: None,
             #[cfg(test)]
             inject_sync_failure: None,
             #[cfg(any(test, feature = "test-utils"))]
             inject_checkpoint_truncate_failure: None,
+            #[cfg(any(test, feature = "test-utils"))]
+            fault: None,
         })
     }
 
-    fn write_wal_header(file: &mut File) -> Result<()> {
-        let mut header = [0u8; WAL_HEADER_SIZE];
-        header[0..8].copy_from_slice(WAL_MAGIC);
-        header[8..12].copy_from_slice(&WAL_VERSION.to_le_bytes());
-        file.write_all(&header)?;
-        Ok(())
+    /// A writer for a read-only database handle: it opens no file, and
+    /// appending or truncating fails with [`MuroError::ReadOnly`].
+    pub fn read_only(
+        path: &Path,
+        suite: EncryptionSuite,
+        master_key: Option<&MasterKey>,
+    ) -> Result<Self> {
+        Ok(WalWriter {
+            file: None,
+            path: path.to_path_buf(),
+            crypto: PageCipher::new(suite, master_key)?,
+            current_lsn: 0,
+            header_len: WAL_HEADER_SIZE as u64,
+            frame_crc: true,
+            frames_appended: 0,
+            durability: WalDurability::Full,
+            unsynced_commits: 0,
+            batch_started_at: None,
+            archive: None,
+            write_buffer: Vec::new(),
+            write_buffer_limit: DEFAULT_WAL_WRITE_BUFFER_BYTES,
+            write_buffer_high_water: 0,
+            write_calls: 0,
+            commit_write_calls_start: 0,
+            last_commit_write_calls: 0,
+            #[cfg(test)]
+            inject_write_failure: None,
+            #[cfg(test)]
+            inject_sync_failure: None,
+            #[cfg(any(test, feature = "test-utils"))]
+            inject_checkpoint_truncate_failure: None,
+            #[cfg(any(test, feature = "test-utils"))]
+            fault: None,
+        })
     }
 
-    fn validate_wal_header(file: &mut File) -> Result<()> {
-        file.seek(SeekFrom::Start(0))?;
-        let mut header = [0u8; WAL_HEADER_SIZE];
-        file.read_exact(&mut header)?;
-        if &header[0..8] != WAL_MAGIC {
-            return Err(MuroError::Wal(
-                "WAL file magic mismatch: not a valid MuroDB WAL file".into(),
-            ));
-        }
-        let version = u32::from_le_bytes(header[8..12].try_into().unwrap());
-        if version > WAL_VERSION {
-            return Err(MuroError::Wal(format!(
-                "unsupported WAL format version {}",
-                version
-            )));
-        }
-        Ok(())
+    fn file_mut(&mut self) -> Result<&mut File> {
+        self.file.as_mut().ok_or(MuroError::ReadOnly)
     }
 
-    /// Append a WAL record. Returns the LSN assigned.
+    /// Append a frame and write it, with any frames buffered before it, to
+    /// the file. Not fsynced; see [`WalWriter::sync`].
     pub fn append(&mut self, record: &WalRecord) -> Result<Lsn> {
+        let lsn = self.append_buffered(record)?;
+        self.flush_buffer()?;
+        Ok(lsn)
+    }
+
+    /// Append a frame to the write buffer; the buffer is written out (not
+    /// fsynced) whenever it reaches the write buffer limit, and by the next
+    /// [`WalWriter::append`] or [`WalWriter::sync`].
+    ///
+    /// A commit streams its page frames through here, so the memory it uses
+    /// for frames is bounded however many pages it writes. Frames that reach
+    /// the file before the commit record are ignored by recovery if the
+    /// commit record never follows.
+    pub fn append_buffered(&mut self, record: &WalRecord) -> Result<Lsn> {
+        if self.write_buffer.is_empty() {
+            self.follow_file()?;
+        }
         let lsn = self.current_lsn;
+        if matches!(record, WalRecord::Begin { .. }) {
+            self.commit_write_calls_start = self.write_calls;
+        }
 
-        let record_bytes = record.serialize();
+        // Both buffers hold plaintext and are zeroed when dropped; the
+        // payload is sized up front so appending the CRC never reallocates.
+        let record_bytes = Zeroizing::new(record.serialize());
         let crc = crc32(&record_bytes);
 
-        let mut payload = record_bytes;
+        let mut payload = Zeroizing::new(Vec::with_capacity(record_bytes.len() + 4));
+        payload.extend_from_slice(&record_bytes);
         payload.extend_from_slice(&crc.to_le_bytes());
 
         // Encrypt with LSN as "page_id" and 0 as epoch
-        let encrypted = self.crypto.encrypt(lsn, 0, &payload)?;
+        let mut encrypted = self.crypto.encrypt(lsn, 0, &payload)?;
+        if self.frame_crc {
+            let frame_crc = crc32(&encrypted);
+            encrypted.extend_from_slice(&frame_crc.to_le_bytes());
+        }
         if encrypted.len() > MAX_WAL_FRAME_LEN {
             return Err(MuroError::Wal(format!(
                 "WAL frame length {} exceeds max {}",
@@ -177,15 +326,97 @@ impl WalWriter {
         }
 
         let frame_len = encrypted.len() as u32;
-        self.file.write_all(&frame_len.to_le_bytes())?;
-        self.file.write_all(&encrypted)?;
+        self.write_buffer
+            .extend_from_slice(&frame_len.to_le_bytes());
+        self.write_buffer.extend_from_slice(&encrypted);
+        self.write_buffer_high_water = self.write_buffer_high_water.max(self.write_buffer.len());
 
         self.current_lsn += 1;
+        self.frames_appended += 1;
+        if self.write_buffer.len() >= self.write_buffer_limit {
+            self.flush_buffer()?;
+        }
         Ok(lsn)
     }
 
+    /// Pick up where the WAL file now ends. Another handle on the same
+    /// database may have opened it (recovering and truncating the WAL) or
+    /// committed and checkpointed since this writer last wrote; each did so
+    /// under the exclusive lock this writer's caller now holds. Frames are
+    /// appended after the last complete frame, with the LSN that follows it,
+    /// instead of at this writer's stale offset.
+    fn follow_file(&mut self) -> Result<()> {
+        let header_len = self.header_len;
+        let file = self.file_mut()?;
+        let file_len = file.metadata()?.len();
+        if file.stream_position()? == file_len {
+            return Ok(());
+        }
+        if file_len < header_len {
+            return Err(MuroError::Wal(format!(
+                "WAL file shrank to {} bytes, below its {} byte header",
+                file_len, header_len
+            )));
+        }
+        // The other handle may have rewritten the header in the current
+        // format while recovering.
+        let header = read_wal_header(file, file_len)?;
+        let header_len = header.frames_offset() as u64;
+        self.frame_crc = header.has_frame_crc();
+        self.header_len = header_len;
+        let file = self.file_mut()?;
+        let mut offset = header_len;
+        let mut frames = 0;
+        let mut len_buf = [0u8; 4];
+        while offset + 4 <= file_len {
+            file.seek(SeekFrom::Start(offset))?;
+            file.read_exact(&mut len_buf)?;
+            let frame_len = u32::from_le_bytes(len_buf) as u64;
+            if frame_len == 0
+                || frame_len > MAX_WAL_FRAME_LEN as u64
+                || offset + 4 + frame_len > file_len
+            {
+                break;
+            }
+            offset += 4 + frame_len;
+            frames += 1;
+        }
+        file.seek(SeekFrom::Start(offset))?;
+        self.current_lsn = frames;
+        Ok(())
+    }
+
+    /// Write buffered frames to the file, without fsync.
+    fn flush_buffer(&mut self) -> Result<()> {
+        if self.write_buffer.is_empty() {
+            return Ok(());
+        }
+        let mut buffer = std::mem::take(&mut self.write_buffer);
+        let result = self.write_frames(&buffer);
+        // Keep the allocation for the next commit; the frames are either in
+        // the file or lost with the failed write.
+        buffer.clear();
+        self.write_buffer = buffer;
+        result
+    }
+
+    fn write_frames(&mut self, bytes: &[u8]) -> Result<()> {
+        self.write_calls += 1;
+        #[cfg(any(test, feature = "test-utils"))]
+        if let Some(fault) = self.fault.clone() {
+            return fault.write_all(crate::fault::FaultFile::Wal, self.file_mut()?, bytes, false);
+        }
+        self.file_mut()?.write_all(bytes)?;
+        Ok(())
+    }
+
     /// Sync the WAL file to disk (fsync).
     pub fn sync(&mut self) -> Result<()> {
+        self.flush_buffer()?;
+        #[cfg(any(test, feature = "test-utils"))]
+        if let Some(fault) = &self.fault {
+            fault.check()?;
+        }
         #[cfg(test)]
         if let Some(kind) = self.inject_sync_failure {
             return Err(MuroError::Io(std::io::Error::new(
@@ -193,7 +424,119 @@ impl WalWriter {
                 "injected sync failure",
             )));
         }
-        self.file.sync_all()?;
+        if let Some(file) = self.file.as_mut() {
+            file.sync_all()?;
+        }
+        self.unsynced_commits = 0;
+        self.batch_started_at = None;
+        Ok(())
+    }
+
+    /// Fsync a just-appended commit as the durability mode requires.
+    ///
+    /// Returns `false` when the fsync was deferred; the commit then becomes
+    /// durable with a later [`WalWriter::sync`].
+    pub fn sync_commit(&mut self) -> Result<bool> {
+        self.last_commit_write_calls = self.write_calls - self.commit_write_calls_start;
+        match self.durability {
+            WalDurability::Full => {}
+            WalDurability::GroupCommit { max_batch, .. } => {
+                self.unsynced_commits += 1;
+                self.batch_started_at.get_or_insert_with(Instant::now);
+                if self.unsynced_commits < max_batch && !self.commit_batch_expired() {
+                    return Ok(false);
+                }
+            }
+            WalDurability::Async => {
+                self.unsynced_commits += 1;
+                return Ok(false);
+            }
+        }
+        self.sync()?;
+        Ok(true)
+    }
+
+    /// Writes of frames to the WAL file since this writer was created.
+    pub fn write_calls(&self) -> u64 {
+        self.write_calls
+    }
+
+    /// Writes the last commit took: one unless its frames overflowed the
+    /// write buffer limit.
+    pub fn last_commit_write_calls(&self) -> u64 {
+        self.last_commit_write_calls
+    }
+
+    /// Bytes of frames [`WalWriter::append_buffered`] may hold before writing
+    /// them out; `0` writes every frame as it is appended.
+    pub fn set_write_buffer_limit(&mut self, bytes: usize) {
+        self.write_buffer_limit = bytes;
+    }
+
+    pub fn write_buffer_limit(&self) -> usize {
+        self.write_buffer_limit
+    }
+
+    /// Most bytes the write buffer has held at once since this writer was
+    /// created.
+    pub fn write_buffer_high_water(&self) -> usize {
+        self.write_buffer_high_water
+    }
+
+    /// Whether some appended commits have not been fsynced yet.
+    pub fn has_unsynced_commits(&self) -> bool {
+        self.unsynced_commits > 0
+    }
+
+    /// Whether an open group-commit batch is waiting for more commits.
+    pub fn commit_batch_open(&self) -> bool {
+        matches!(self.durability, WalDurability::GroupCommit { .. }) && self.unsynced_commits > 0
+    }
+
+    /// Whether the open group-commit batch has reached its `max_delay_ms`.
+    pub fn commit_batch_expired(&self) -> bool {
+        match (self.durability, self.batch_started_at) {
+            (WalDurability::GroupCommit { max_delay_ms, .. }, Some(started)) => {
+                started.elapsed() >= Duration::from_millis(max_delay_ms)
+            }
+            _ => false,
+        }
+    }
+
+    pub fn durability(&self) -> WalDurability {
+        self.durability
+    }
+
+    /// Change the durability mode. Commits already deferred stay pending
+    /// until the next [`WalWriter::sync`].
+    pub fn set_durability(&mut self, durability: WalDurability) {
+        self.durability = durability;
+    }
+
+    /// Archive checkpointed WAL contents into `archive` instead of dropping
+    /// them; `None` turns archiving off.
+    pub fn set_archive(&mut self, archive: Option<WalArchive>) {
+        self.archive = archive;
+    }
+
+    pub fn archive(&self) -> Option<&WalArchive> {
+        self.archive.as_ref()
+    }
+
+    /// Copy the frames a checkpoint is about to truncate into the archive,
+    /// recording `catalog_root` as the root the checkpoint left in the data
+    /// file. Does nothing without an archive or when the WAL has no frames.
+    /// The segment is durable when this returns.
+    pub fn archive_segment(&mut self, catalog_root: PageId) -> Result<()> {
+        if self.archive.is_none() || self.current_lsn == 0 {
+            return Ok(());
+        }
+        self.flush_buffer()?;
+        self.file_mut()?.sync_data()?;
+        let frames = self.current_lsn;
+        if let Some(archive) = self.archive.as_mut() {
+            archive.store_segment(&self.path, frames, catalog_root)?;
+        }
         Ok(())
     }
 
@@ -217,9 +560,16 @@ impl WalWriter {
                 "injected checkpoint_truncate failure",
             )));
         }
-        self.file.set_len(WAL_HEADER_SIZE as u64)?;
-        self.file.seek(SeekFrom::Start(WAL_HEADER_SIZE as u64))?;
-        self.file.sync_all()?;
+        #[cfg(any(test, feature = "test-utils"))]
+        if let Some(fault) = &self.fault {
+            fault.admit_atomic(crate::fault::FaultFile::Wal)?;
+        }
+        let header_len = self.header_len;
+        self.write_buffer.clear();
+        let file = self.file_mut()?;
+        file.set_len(header_len)?;
+        file.seek(SeekFrom::Start(header_len))?;
+        file.sync_all()?;
         // Best-effort parent directory fsync to harden metadata persistence.
         if let Some(parent) = self.path.parent() {
             if let Ok(dir) = File::open(parent) {
@@ -227,12 +577,21 @@ impl WalWriter {
             }
         }
         self.current_lsn = 0;
+        self.unsynced_commits = 0;
+        self.batch_started_at = None;
         Ok(())
     }
 
     /// Current WAL file size in bytes.
     pub fn file_size_bytes(&self) -> Result<u64> {
-        Ok(self.file.metadata()?.len())
+        match &self.file {
+            Some(file) => Ok(file.metadata()?.len()),
+            None => match std::fs::metadata(&self.path) {
+                Ok(meta) => Ok(meta.len()),
+                Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(0),
+                Err(e) => Err(e.into()),
+            },
+        }
     }
 
     pub fn wal_path(&self) -> &Path {
@@ -243,6 +602,12 @@ impl WalWriter {
         self.current_lsn
     }
 
+    /// Frames appended by this writer. Unlike the LSN, this is not reset by
+    /// a checkpoint, so deltas count frames across truncations.
+    pub fn frames_appended(&self) -> u64 {
+        self.frames_appended
+    }
+
     #[cfg(test)]
     pub fn set_inject_write_failure(&mut self, kind: Option<std::io::ErrorKind>) {
         self.inject_write_failure = kind;
@@ -253,6 +618,12 @@ impl WalWriter {
         self.inject_sync_failure = kind;
     }
 
+    /// Route frame writes, truncations and fsyncs through `fault`.
+    #[cfg(any(test, feature = "test-utils"))]
+    pub fn set_fault_injector(&mut self, fault: Option<crate::fault::FaultInjector>) {
+        self.fault = fault;
+    }
+
     #[cfg(any(test, feature = "test-utils"))]
     pub fn set_inject_checkpoint_truncate_failure(&mut self, kind: Option<std::io::ErrorKind>) {
         self.inject_checkpoint_truncate_failure = kind;
@@ -331,16 +702,182 @@ mod tests {
         assert_eq!(writer.file_size_bytes().unwrap(), WAL_HEADER_SIZE as u64);
     }
 
+    #[test]
+    fn test_buffered_frames_are_written_at_limit_or_next_append() {
+        let tmp = NamedTempFile::new().unwrap();
+        let key = MasterKey::new([0x42u8; 32]);
+        let mut writer = WalWriter::create(tmp.path(), &key).unwrap();
+        writer.set_write_buffer_limit(2 * PAGE_SIZE);
+        let page_put = |page_id| WalRecord::PagePut {
+            txid: 1,
+            page_id,
+            data: vec![0xAB; PAGE_SIZE],
+        };
+
+        writer.append_buffered(&page_put(0)).unwrap();
+        assert_eq!(writer.file_size_bytes().unwrap(), WAL_HEADER_SIZE as u64);
+        writer.append_buffered(&page_put(1)).unwrap();
+        let flushed = writer.file_size_bytes().unwrap();
+        assert!(flushed > (WAL_HEADER_SIZE + 2 * PAGE_SIZE) as u64);
+
+        writer.append_buffered(&page_put(2)).unwrap();
+        assert_eq!(writer.file_size_bytes().unwrap(), flushed);
+        writer
+            .append(&WalRecord::Commit { txid: 1, lsn: 3 })
+            .unwrap();
+        assert!(writer.file_size_bytes().unwrap() > flushed + PAGE_SIZE as u64);
+        assert_eq!(writer.current_lsn(), 4);
+    }
+
+    #[test]
+    fn test_commit_is_one_write_with_the_same_bytes_as_frame_by_frame() {
+        let commit = |limit: usize| {
+            let tmp = NamedTempFile::new().unwrap();
+            let mut writer = WalWriter::create_plaintext(tmp.path()).unwrap();
+            writer.set_write_buffer_limit(limit);
+            writer
+                .append_buffered(&WalRecord::Begin { txid: 1 })
+                .unwrap();
+            for page_id in 0..40 {
+                writer
+                    .append_buffered(&WalRecord::PagePut {
+                        txid: 1,
+                        page_id,
+                        data: vec![page_id as u8; PAGE_SIZE],
+                    })
+                    .unwrap();
+            }
+            writer
+                .append(&WalRecord::Commit { txid: 1, lsn: 41 })
+                .unwrap();
+            writer.sync_commit().unwrap();
+            let calls = writer.last_commit_write_calls();
+            (calls, std::fs::read(tmp.path()).unwrap())
+        };
+
+        let (batched_calls, batched) = commit(DEFAULT_WAL_WRITE_BUFFER_BYTES);
+        let (unbatched_calls, unbatched) = commit(0);
+        assert_eq!(batched_calls, 1);
+        assert_eq!(unbatched_calls, 42);
+        assert_eq!(batched, unbatched);
+
+        // A commit larger than the limit spills in limit-sized chunks.
+        let (chunked_calls, chunked) = commit(8 * PAGE_SIZE);
+        assert!(chunked_calls > 1 && chunked_calls < 10, "{}", chunked_calls);
+        assert_eq!(chunked, unbatched);
+    }
+
+    #[test]
+    fn test_writers_on_one_file_follow_each_other() {
+        let tmp = NamedTempFile::new().unwrap();
+        let key = MasterKey::new([0x42u8; 32]);
+        let mut a = WalWriter::create(tmp.path(), &key).unwrap();
+        let mut b = WalWriter::open(tmp.path(), &key, 0).unwrap();
+
+        assert_eq!(a.append(&WalRecord::Begin { txid: 1 }).unwrap(), 0);
+        assert_eq!(b.append(&WalRecord::Begin { txid: 2 }).unwrap(), 1);
+        assert_eq!(a.append(&WalRecord::Abort { txid: 1 }).unwrap(), 2);
+
+        // After the other handle truncates, LSNs start over at the header.
+        b.checkpoint_truncate().unwrap();
+        assert_eq!(a.append(&WalRecord::Begin { txid: 3 }).unwrap(), 0);
+        assert_eq!(b.append(&WalRecord::Abort { txid: 3 }).unwrap(), 1);
+        a.sync().unwrap();
+
+        let mut reader = crate::wal::reader::WalReader::open(tmp.path(), &key).unwrap();
+        let records = reader.read_all().unwrap();
+        assert_eq!(records.len(), 2);
+        assert!(matches!(records[0], (0, WalRecord::Begin { txid: 3 })));
+        assert!(matches!(records[1], (1, WalRecord::Abort { txid: 3 })));
+    }
+
+    #[test]
+    fn test_injected_crash_cuts_the_write_and_fails_later_ones() {
+        use crate::fault::{FaultFile, FaultInjector, FaultKind, FaultPoint};
+
+        let tmp = NamedTempFile::new().unwrap();
+        let key = MasterKey::new([0x42u8; 32]);
+        let mut writer = WalWriter::create(tmp.path(), &key).unwrap();
+        let frame_len = {
+            let scratch_file = NamedTempFile::new().unwrap();
+            let mut scratch = WalWriter::create(scratch_file.path(), &key).unwrap();
+            scratch.append(&WalRecord::Begin { txid: 1 }).unwrap();
+            scratch.file_size_bytes().unwrap() - WAL_HEADER_SIZE as u64
+        };
+        writer.set_fault_injector(Some(FaultInjector::at(FaultPoint {
+            file: FaultFile::Wal,
+            write: 1,
+            kind: FaultKind::TornWrite,
+        })));
+
+        assert!(writer.append(&WalRecord::Begin { txid: 1 }).is_err());
+        let crash_at = WAL_HEADER_SIZE as u64 + frame_len / 2;
+        assert_eq!(writer.file_size_bytes().unwrap(), crash_at);
+        assert!(writer.append(&WalRecord::Abort { txid: 1 }).is_err());
+        assert!(writer.sync().is_err());
+        assert_eq!(writer.file_size_bytes().unwrap(), crash_at);
+    }
+
     #[test]
     fn test_open_rejects_truncated_header() {
         let tmp = NamedTempFile::new().unwrap();
         let path = tmp.path().to_path_buf();
 
-        // Write a few bytes — less than WAL_HEADER_SIZE (12 bytes)
-        std::fs::write(&path, &[0xAA; 5]).unwrap();
+        // Write a few bytes — less than even a version 1 header (12 bytes)
+        std::fs::write(&path, [0xAA; 5]).unwrap();
 
         let key = MasterKey::new([0x42u8; 32]);
         let res = WalWriter::open(&path, &key, 0);
         assert!(matches!(res, Err(MuroError::Wal(ref msg)) if msg.contains("corrupt")));
     }
+
+    #[test]
+    fn test_group_commit_syncs_every_max_batch_commits() {
+        let tmp = NamedTempFile::new().unwrap();
+        let key = MasterKey::new([0x42u8; 32]);
+        let mut writer = WalWriter::create(tmp.path(), &key).unwrap();
+        writer.set_durability(WalDurability::GroupCommit {
+            max_delay_ms: 60_000,
+            max_batch: 3,
+        });
+
+        assert!(!writer.sync_commit().unwrap());
+        assert!(!writer.sync_commit().unwrap());
+        assert!(writer.commit_batch_open());
+        assert!(!writer.commit_batch_expired());
+        assert!(writer.sync_commit().unwrap());
+        assert!(!writer.has_unsynced_commits());
+        assert!(!writer.commit_batch_open());
+    }
+
+    #[test]
+    fn test_group_commit_syncs_expired_batch() {
+        let tmp = NamedTempFile::new().unwrap();
+        let key = MasterKey::new([0x42u8; 32]);
+        let mut writer = WalWriter::create(tmp.path(), &key).unwrap();
+        writer.set_durability(WalDurability::GroupCommit {
+            max_delay_ms: 0,
+            max_batch: 100,
+        });
+        assert!(writer.sync_commit().unwrap());
+        assert!(!writer.has_unsynced_commits());
+    }
+
+    #[test]
+    fn test_async_defers_sync_until_explicit() {
+        let tmp = NamedTempFile::new().unwrap();
+        let key = MasterKey::new([0x42u8; 32]);
+        let mut writer = WalWriter::create(tmp.path(), &key).unwrap();
+        assert_eq!(writer.durability(), WalDurability::Full);
+        assert!(writer.sync_commit().unwrap());
+
+        writer.set_durability(WalDurability::Async);
+        for _ in 0..10 {
+            assert!(!writer.sync_commit().unwrap());
+        }
+        assert!(writer.has_unsynced_commits());
+        assert!(!writer.commit_batch_open());
+        writer.sync().unwrap();
+        assert!(!writer.has_unsynced_commits());
+    }
 }
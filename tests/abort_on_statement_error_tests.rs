@@ -0,0 +1,185 @@
+#![cfg(feature = "test-utils")]
+/// `abort_on_statement_error`: off, a failed statement inside a transaction
+/// is undone on its own and the transaction goes on; on, the failure (parse
+/// and bind errors included) aborts the transaction, every statement but
+/// ROLLBACK and ROLLBACK TO SAVEPOINT fails until it ends or returns to a
+/// savepoint, and COMMIT rolls it back and says so.
+use murodb::{Database, MuroError, Value};
+use tempfile::TempDir;
+
+fn setup() -> (Database, TempDir) {
+    let dir = TempDir::new().unwrap();
+    let mut db = Database::create_plaintext(&dir.path().join("test.db")).unwrap();
+    db.execute("CREATE TABLE t (id BIGINT PRIMARY KEY, v VARCHAR)")
+        .unwrap();
+    db.execute("INSERT INTO t VALUES (1, 'a')").unwrap();
+    (db, dir)
+}
+
+fn count(db: &mut Database) -> i64 {
+    match db.query("SELECT COUNT(*) FROM t").unwrap()[0].values[0].1 {
+        Value::Integer(n) => n,
+        ref other => panic!("unexpected value {:?}", other),
+    }
+}
+
+fn assert_aborted(result: Result<impl std::fmt::Debug, MuroError>) {
+    match result {
+        Err(MuroError::Transaction(msg)) => {
+            assert_eq!(msg, "current transaction is aborted")
+        }
+        other => panic!("expected an aborted transaction, got {:?}", other),
+    }
+}
+
+#[test]
+fn test_default_off_keeps_transaction_after_error() {
+    let (mut db, _dir) = setup();
+    assert!(!db.abort_on_statement_error());
+
+    db.execute("BEGIN").unwrap();
+    db.execute("INSERT INTO t VALUES (2, 'b')").unwrap();
+    assert!(db.execute("INSERT INTO t VALUES (1, 'dup')").is_err());
+    db.execute("INSERT INTO t VALUES (3, 'c')").unwrap();
+    db.execute("COMMIT").unwrap();
+
+    assert_eq!(count(&mut db), 3);
+}
+
+#[test]
+fn test_error_aborts_transaction_until_rollback() {
+    let (mut db, _dir) = setup();
+    db.execute("SET abort_on_statement_error = ON").unwrap();
+
+    db.execute("BEGIN").unwrap();
+    db.execute("INSERT INTO t VALUES (2, 'b')").unwrap();
+    assert!(matches!(
+        db.execute("INSERT INTO t VALUES (1, 'dup')"),
+        Err(MuroError::UniqueViolation(_))
+    ));
+
+    assert_aborted(db.execute("INSERT INTO t VALUES (3, 'c')"));
+    assert_aborted(db.execute("SELECT * FROM t"));
+    assert_aborted(db.query("SELECT * FROM t"));
+    assert_aborted(db.execute("SET predicate_reorder = OFF"));
+    assert_aborted(db.execute("SAVEPOINT sp"));
+
+    db.execute("ROLLBACK").unwrap();
+    assert_eq!(count(&mut db), 1);
+
+    // The next transaction starts clean.
+    db.execute("BEGIN").unwrap();
+    db.execute("INSERT INTO t VALUES (4, 'd')").unwrap();
+    db.execute("COMMIT").unwrap();
+    assert_eq!(count(&mut db), 2);
+}
+
+#[test]
+fn test_commit_of_aborted_transaction_rolls_back() {
+    let (mut db, _dir) = setup();
+    db.set_abort_on_statement_error(true);
+
+    db.execute("BEGIN").unwrap();
+    db.execute("INSERT INTO t VALUES (2, 'b')").unwrap();
+    assert!(db.execute("SELECT * FROM missing").is_err());
+    match db.execute("COMMIT") {
+        Err(MuroError::Transaction(msg)) => {
+            assert!(msg.contains("rolled it back"), "{}", msg)
+        }
+        other => panic!("expected COMMIT to report a rollback, got {:?}", other),
+    }
+
+    // Nothing was kept and no transaction is left open.
+    assert_eq!(count(&mut db), 1);
+    assert!(db.execute("ROLLBACK").is_err());
+    db.execute("INSERT INTO t VALUES (2, 'b')").unwrap();
+    assert_eq!(count(&mut db), 2);
+}
+
+#[test]
+fn test_errors_outside_transaction_do_not_abort() {
+    let (mut db, _dir) = setup();
+    db.execute("SET abort_on_statement_error = ON").unwrap();
+
+    assert!(db.execute("INSERT INTO t VALUES (1, 'dup')").is_err());
+    db.execute("INSERT INTO t VALUES (2, 'b')").unwrap();
+    assert_eq!(count(&mut db), 2);
+}
+
+#[test]
+fn test_show_and_set_inside_transaction() {
+    let (mut db, _dir) = setup();
+    let value = |db: &mut Database| {
+        db.query("SHOW VARIABLES LIKE 'abort_on_statement_error'")
+            .unwrap()[0]
+            .get("value")
+            .cloned()
+    };
+    assert_eq!(value(&mut db), Some(Value::Varchar("OFF".into())));
+
+    // It may be switched inside a transaction; turning it on does not
+    // reach back to a statement that already failed.
+    db.execute("BEGIN").unwrap();
+    assert!(db.execute("INSERT INTO t VALUES (1, 'dup')").is_err());
+    db.execute("SET abort_on_statement_error = 'on'").unwrap();
+    assert_eq!(value(&mut db), Some(Value::Varchar("ON".into())));
+    db.execute("INSERT INTO t VALUES (2, 'b')").unwrap();
+    db.execute("COMMIT").unwrap();
+    assert_eq!(count(&mut db), 2);
+}
+
+#[test]
+fn test_rollback_to_savepoint_resumes_aborted_transaction() {
+    let (mut db, _dir) = setup();
+    db.execute("SET abort_on_statement_error = ON").unwrap();
+
+    db.execute("BEGIN").unwrap();
+    db.execute("INSERT INTO t VALUES (2, 'b')").unwrap();
+    db.execute("SAVEPOINT sp").unwrap();
+    db.execute("INSERT INTO t VALUES (3, 'c')").unwrap();
+    assert!(db.execute("INSERT INTO t VALUES (1, 'dup')").is_err());
+    assert_aborted(db.execute("INSERT INTO t VALUES (4, 'd')"));
+    assert_aborted(db.execute("RELEASE SAVEPOINT sp"));
+
+    // An unknown savepoint leaves the transaction aborted.
+    assert!(db.execute("ROLLBACK TO SAVEPOINT missing").is_err());
+    assert_aborted(db.execute("INSERT INTO t VALUES (4, 'd')"));
+
+    // Back at the savepoint the transaction is active again, with the work
+    // done before it.
+    db.execute("ROLLBACK TO SAVEPOINT sp").unwrap();
+    db.execute("INSERT INTO t VALUES (5, 'e')").unwrap();
+    db.execute("COMMIT").unwrap();
+
+    let ids: Vec<i64> = db
+        .query("SELECT id FROM t ORDER BY id")
+        .unwrap()
+        .iter()
+        .map(|row| row.get_i64("id").unwrap().unwrap())
+        .collect();
+    assert_eq!(ids, vec![1, 2, 5]);
+}
+
+#[test]
+fn test_parse_and_bind_errors_abort_transaction() {
+    let (mut db, _dir) = setup();
+    db.execute("SET abort_on_statement_error = ON").unwrap();
+
+    db.execute("BEGIN").unwrap();
+    db.execute("INSERT INTO t VALUES (2, 'b')").unwrap();
+    assert!(matches!(
+        db.execute("INSERT INTO t VALUES ("),
+        Err(MuroError::Parse(_))
+    ));
+    assert_aborted(db.execute("INSERT INTO t VALUES (3, 'c')"));
+    db.execute("ROLLBACK").unwrap();
+
+    let insert = db.prepare("INSERT INTO t VALUES (?, ?)").unwrap();
+    db.execute("BEGIN").unwrap();
+    db.execute("INSERT INTO t VALUES (2, 'b')").unwrap();
+    assert!(db.execute_prepared(&insert, &[Value::Integer(3)]).is_err());
+    assert_aborted(db.execute_prepared(&insert, &[Value::Integer(3), Value::Varchar("c".into())]));
+    db.execute("ROLLBACK").unwrap();
+
+    assert_eq!(count(&mut db), 1);
+}
@@ -0,0 +1,114 @@
+#![cfg(feature = "test-utils")]
+/// GROUP BY streams rows into per-group state and spills groups beyond
+/// `aggregation_memory_budget` to encrypted temp files; the results, including
+/// group order, must not depend on the budget.
+use murodb::crypto::aead::MasterKey;
+use murodb::{Database, MuroError, Value};
+use tempfile::TempDir;
+
+fn test_key() -> MasterKey {
+    MasterKey::new([0x42u8; 32])
+}
+
+fn setup(db: &mut Database) {
+    db.execute(
+        "CREATE TABLE events (id BIGINT PRIMARY KEY, user_id BIGINT, kind VARCHAR(20), amount DOUBLE, price DECIMAL(10,2))",
+    )
+    .unwrap();
+    db.execute("BEGIN").unwrap();
+    for i in 0..3000i64 {
+        let user = (i * 7919) % 997;
+        let kind = match i % 3 {
+            0 => "'click'".to_string(),
+            1 => "'view'".to_string(),
+            _ => "NULL".to_string(),
+        };
+        db.execute(&format!(
+            "INSERT INTO events VALUES ({}, {}, {}, {}, {}.{:02})",
+            i,
+            user,
+            kind,
+            (i as f64) / 7.0,
+            i % 50,
+            i % 100
+        ))
+        .unwrap();
+    }
+    db.execute("COMMIT").unwrap();
+}
+
+const QUERIES: &[&str] = &[
+    "SELECT user_id, COUNT(*), SUM(amount), AVG(amount), MIN(kind), MAX(price), AVG(price) FROM events GROUP BY user_id",
+    "SELECT user_id, COUNT(DISTINCT kind) FROM events GROUP BY user_id HAVING COUNT(*) > 3",
+    "SELECT kind, COUNT(*), AVG(user_id) FROM events GROUP BY kind",
+    "SELECT user_id % 10, kind, SUM(id) FROM events WHERE id > 100 GROUP BY user_id % 10, kind",
+    "SELECT id, kind, amount, COUNT(*) FROM events GROUP BY user_id",
+    "SELECT COUNT(*), SUM(amount), COUNT(DISTINCT user_id) FROM events",
+    "SELECT user_id, COUNT(*) AS n FROM events GROUP BY user_id ORDER BY n DESC, user_id LIMIT 5",
+    "SELECT user_id FROM events WHERE id < 0 GROUP BY user_id",
+    "SELECT COUNT(*), MAX(amount) FROM events WHERE id < 0",
+];
+
+fn results(db: &mut Database) -> Vec<String> {
+    QUERIES
+        .iter()
+        .map(|q| format!("{:?}", db.query(q).unwrap()))
+        .collect()
+}
+
+#[test]
+fn test_results_do_not_depend_on_memory_budget() {
+    let dir = TempDir::new().unwrap();
+    let mut db = Database::create(&dir.path().join("test.db"), &test_key()).unwrap();
+    setup(&mut db);
+    assert_eq!(db.aggregation_memory_budget(), 64 * 1024 * 1024);
+    let in_memory = results(&mut db);
+
+    // A few groups fit, the rest spill.
+    db.execute("SET aggregation_memory_budget = 4096").unwrap();
+    assert_eq!(db.aggregation_memory_budget(), 4096);
+    let spilled = results(&mut db);
+    for ((query, a), b) in QUERIES.iter().zip(&in_memory).zip(&spilled) {
+        assert_eq!(a, b, "{}", query);
+    }
+
+    // Every group spills, and partitions spill again until the last round.
+    db.set_aggregation_memory_budget(0);
+    let all_spilled = results(&mut db);
+    for ((query, a), b) in QUERIES.iter().zip(&in_memory).zip(&all_spilled) {
+        assert_eq!(a, b, "{}", query);
+    }
+}
+
+#[test]
+fn test_spilled_groups_keep_first_appearance_order() {
+    let dir = TempDir::new().unwrap();
+    let mut db = Database::create(&dir.path().join("test.db"), &test_key()).unwrap();
+    setup(&mut db);
+    db.set_aggregation_memory_budget(0);
+    let rows = db
+        .query("SELECT user_id, MIN(id) AS first_id FROM events GROUP BY user_id")
+        .unwrap();
+    assert_eq!(rows.len(), 997);
+    let first_ids: Vec<i64> = rows
+        .iter()
+        .map(|r| match r.get("first_id") {
+            Some(Value::Integer(n)) => *n,
+            other => panic!("unexpected first_id {:?}", other),
+        })
+        .collect();
+    assert!(first_ids.windows(2).all(|w| w[0] < w[1]), "{:?}", first_ids);
+}
+
+#[test]
+fn test_aggregation_memory_budget_option() {
+    let dir = TempDir::new().unwrap();
+    let mut db = Database::create(&dir.path().join("test.db"), &test_key()).unwrap();
+    let err = db
+        .execute("SET aggregation_memory_budget = 'lots'")
+        .unwrap_err();
+    assert!(matches!(err, MuroError::Execution(_)), "{}", err);
+    db.execute("SET aggregation_memory_budget = 1048576")
+        .unwrap();
+    assert_eq!(db.aggregation_memory_budget(), 1048576);
+}
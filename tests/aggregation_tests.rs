@@ -503,3 +503,137 @@ fn test_group_by_with_limit() {
         Some(&Value::Varchar("A".to_string()))
     );
 }
+
+// --- MIN/MAX via B-tree edge probes ---
+
+fn setup_wide_table() -> (Pager, SystemCatalog, TempDir) {
+    let (mut pager, mut catalog, dir) = setup();
+    exec(
+        &mut pager,
+        &mut catalog,
+        "CREATE TABLE t (id BIGINT PRIMARY KEY, score INT, note VARCHAR)",
+    );
+    exec(
+        &mut pager,
+        &mut catalog,
+        "CREATE INDEX idx_score ON t (score)",
+    );
+    for i in -150..350i64 {
+        let score = if i % 7 == 0 {
+            "NULL".to_string()
+        } else {
+            (1000 - i * 3).to_string()
+        };
+        exec(
+            &mut pager,
+            &mut catalog,
+            &format!("INSERT INTO t VALUES ({}, {}, 'row{}')", i, score, i),
+        );
+    }
+    (pager, catalog, dir)
+}
+
+#[test]
+fn test_min_max_pk_and_index_probe() {
+    let (mut pager, mut catalog, _dir) = setup_wide_table();
+    let rows = query_rows(
+        &mut pager,
+        &mut catalog,
+        "SELECT MIN(id), MAX(id), MIN(score), MAX(score) FROM t",
+    );
+    assert_eq!(rows.len(), 1);
+    let values: Vec<Value> = rows[0].values.iter().map(|(_, v)| v.clone()).collect();
+    // score = 1000 - 3 * id, with NULL for multiples of 7.
+    assert_eq!(
+        values,
+        vec![
+            Value::Integer(-150),
+            Value::Integer(349),
+            Value::Integer(1000 - 349 * 3),
+            Value::Integer(1000 + 150 * 3),
+        ]
+    );
+
+    exec(&mut pager, &mut catalog, "DELETE FROM t WHERE id >= 340");
+    let val = query_one(&mut pager, &mut catalog, "SELECT MAX(id) FROM t");
+    assert_eq!(val, Value::Integer(339));
+    let val = query_one(&mut pager, &mut catalog, "SELECT MIN(score) FROM t");
+    assert_eq!(val, Value::Integer(1000 - 339 * 3));
+}
+
+#[test]
+fn test_min_max_probe_empty_table() {
+    let (mut pager, mut catalog, _dir) = setup();
+    exec(
+        &mut pager,
+        &mut catalog,
+        "CREATE TABLE t (id BIGINT PRIMARY KEY, score INT)",
+    );
+    exec(
+        &mut pager,
+        &mut catalog,
+        "CREATE INDEX idx_score ON t (score)",
+    );
+    let rows = query_rows(
+        &mut pager,
+        &mut catalog,
+        "SELECT MIN(id), MAX(score) FROM t",
+    );
+    assert_eq!(rows.len(), 1);
+    assert_eq!(rows[0].values[0].1, Value::Null);
+    assert_eq!(rows[0].values[1].1, Value::Null);
+}
+
+#[test]
+fn test_min_max_unindexed_column_falls_back_to_scan() {
+    let (mut pager, mut catalog, _dir) = setup_wide_table();
+    let rows = query_rows(&mut pager, &mut catalog, "SELECT MIN(note), MAX(id) FROM t");
+    assert_eq!(rows[0].values[0].1, Value::Varchar("row-1".to_string()));
+    assert_eq!(rows[0].values[1].1, Value::Integer(349));
+}
+
+// --- ORDER BY primary key with LIMIT ---
+
+#[test]
+fn test_order_by_pk_desc_limit_streams() {
+    let (mut pager, mut catalog, _dir) = setup_wide_table();
+    let ids = |rows: Vec<Row>| -> Vec<Value> {
+        rows.iter().map(|r| r.get("id").unwrap().clone()).collect()
+    };
+
+    let rows = query_rows(
+        &mut pager,
+        &mut catalog,
+        "SELECT id FROM t ORDER BY id DESC LIMIT 3",
+    );
+    assert_eq!(
+        ids(rows),
+        vec![
+            Value::Integer(349),
+            Value::Integer(348),
+            Value::Integer(347)
+        ]
+    );
+
+    let rows = query_rows(
+        &mut pager,
+        &mut catalog,
+        "SELECT id FROM t WHERE score IS NULL ORDER BY id DESC LIMIT 2 OFFSET 1",
+    );
+    assert_eq!(ids(rows), vec![Value::Integer(336), Value::Integer(329)]);
+
+    let rows = query_rows(
+        &mut pager,
+        &mut catalog,
+        "SELECT id FROM t ORDER BY id LIMIT 2",
+    );
+    assert_eq!(ids(rows), vec![Value::Integer(-150), Value::Integer(-149)]);
+
+    // An alias that shadows the PK name must still sort by the aliased expression.
+    let rows = query_rows(
+        &mut pager,
+        &mut catalog,
+        "SELECT score AS id FROM t WHERE id < 3 AND id > 0 ORDER BY id DESC LIMIT 1",
+    );
+    assert_eq!(ids(rows), vec![Value::Integer(997)]);
+}
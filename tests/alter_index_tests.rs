@@ -0,0 +1,203 @@
+#![cfg(feature = "test-utils")]
+/// ALTER TABLE ... ADD [UNIQUE] INDEX|KEY, DROP INDEX|KEY and RENAME INDEX,
+/// alone and combined with other operations in one statement, as MySQL
+/// dumps and ORM migrations write them.
+use murodb::crypto::aead::MasterKey;
+use murodb::{Database, MuroError, Value};
+use tempfile::TempDir;
+
+fn test_key() -> MasterKey {
+    MasterKey::new([0x42u8; 32])
+}
+
+fn setup(dir: &TempDir) -> Database {
+    let mut db = Database::create(&dir.path().join("test.db"), &test_key()).unwrap();
+    db.execute("CREATE TABLE t (id BIGINT PRIMARY KEY, a INT, b INT, c VARCHAR(20))")
+        .unwrap();
+    for i in 0..20 {
+        db.execute(&format!(
+            "INSERT INTO t VALUES ({}, {}, {}, 'c{}')",
+            i,
+            i % 5,
+            i,
+            i % 3
+        ))
+        .unwrap();
+    }
+    db
+}
+
+/// Index names of `t`, one per index, in SHOW INDEXES order.
+fn index_names(db: &mut Database) -> Vec<String> {
+    let mut names: Vec<String> = Vec::new();
+    for row in db.query("SHOW INDEXES FROM t").unwrap() {
+        let Some(Value::Varchar(name)) = row.get("Key_name") else {
+            panic!("{:?}", row);
+        };
+        if !names.contains(name) {
+            names.push(name.clone());
+        }
+    }
+    names
+}
+
+fn explain(db: &mut Database, sql: &str) -> String {
+    format!("{:?}", db.query(&format!("EXPLAIN {}", sql)).unwrap())
+}
+
+#[test]
+fn test_add_index_and_key_build_usable_indexes() {
+    let dir = TempDir::new().unwrap();
+    let mut db = setup(&dir);
+    db.execute("ALTER TABLE t ADD INDEX idx_a (a)").unwrap();
+    db.execute("ALTER TABLE t ADD KEY idx_bc (b, c)").unwrap();
+    assert_eq!(index_names(&mut db), ["idx_a", "idx_bc"]);
+    assert!(explain(&mut db, "SELECT id FROM t WHERE a = 3").contains("idx_a"));
+    assert_eq!(db.query("SELECT id FROM t WHERE a = 3").unwrap().len(), 4);
+
+    // Rows written after the ALTER are indexed too.
+    db.execute("INSERT INTO t VALUES (100, 3, 100, 'x')")
+        .unwrap();
+    assert_eq!(db.query("SELECT id FROM t WHERE a = 3").unwrap().len(), 5);
+}
+
+#[test]
+fn test_add_unique_key_enforces_and_rejects_duplicates() {
+    let dir = TempDir::new().unwrap();
+    let mut db = setup(&dir);
+    db.execute("ALTER TABLE t ADD UNIQUE KEY u_b (b)").unwrap();
+    assert!(matches!(
+        db.execute("INSERT INTO t VALUES (100, 0, 7, 'x')"),
+        Err(MuroError::UniqueViolation(_))
+    ));
+
+    // Existing duplicates fail the backfill and leave no index behind.
+    assert!(db
+        .execute("ALTER TABLE t ADD UNIQUE INDEX u_a (a)")
+        .is_err());
+    assert_eq!(index_names(&mut db), ["u_b"]);
+}
+
+#[test]
+fn test_unnamed_indexes_get_generated_names() {
+    let dir = TempDir::new().unwrap();
+    let mut db = setup(&dir);
+    db.execute("ALTER TABLE t ADD INDEX (a)").unwrap();
+    db.execute("ALTER TABLE t ADD UNIQUE (b, c)").unwrap();
+    assert_eq!(
+        index_names(&mut db),
+        ["auto_index_t_a", "auto_unique_t_b_c"]
+    );
+    // A second unnamed index on the same columns collides with the first.
+    assert!(db.execute("ALTER TABLE t ADD INDEX (a)").is_err());
+    assert!(db.execute("ALTER TABLE t ADD INDEX ((a + 1))").is_err());
+}
+
+#[test]
+fn test_drop_index_and_key() {
+    let dir = TempDir::new().unwrap();
+    let mut db = setup(&dir);
+    db.execute("CREATE TABLE other (id BIGINT PRIMARY KEY, a INT)")
+        .unwrap();
+    db.execute("CREATE INDEX idx_other ON other (a)").unwrap();
+    db.execute("ALTER TABLE t ADD INDEX idx_a (a), ADD KEY idx_b (b)")
+        .unwrap();
+
+    db.execute("ALTER TABLE t DROP INDEX idx_a").unwrap();
+    db.execute("ALTER TABLE t DROP KEY idx_b").unwrap();
+    assert!(index_names(&mut db).is_empty());
+    assert!(!explain(&mut db, "SELECT id FROM t WHERE a = 3").contains("idx_a"));
+
+    let err = db
+        .execute("ALTER TABLE t DROP INDEX idx_other")
+        .unwrap_err();
+    assert!(
+        err.to_string().contains("belongs to table 'other'"),
+        "{}",
+        err
+    );
+    let err = db.execute("ALTER TABLE t DROP INDEX missing").unwrap_err();
+    assert!(err.to_string().contains("does not exist"), "{}", err);
+    assert!(explain(&mut db, "SELECT id FROM other WHERE a = 1").contains("idx_other"));
+}
+
+#[test]
+fn test_rename_index_keeps_tree_and_survives_reopen() {
+    let dir = TempDir::new().unwrap();
+    let path = dir.path().join("test.db");
+    {
+        let mut db = setup(&dir);
+        db.execute("ALTER TABLE t ADD INDEX idx_a (a), ADD INDEX idx_b (b)")
+            .unwrap();
+        db.execute("ALTER TABLE t RENAME INDEX idx_a TO idx_renamed")
+            .unwrap();
+        assert_eq!(index_names(&mut db), ["idx_b", "idx_renamed"]);
+        assert!(explain(&mut db, "SELECT id FROM t WHERE a = 3").contains("idx_renamed"));
+        assert_eq!(db.query("SELECT id FROM t WHERE a = 3").unwrap().len(), 4);
+
+        let err = db
+            .execute("ALTER TABLE t RENAME KEY idx_renamed TO idx_b")
+            .unwrap_err();
+        assert!(matches!(err, MuroError::Schema(_)), "{}", err);
+        assert!(db
+            .execute("ALTER TABLE t RENAME INDEX idx_a TO idx_c")
+            .is_err());
+        // DROP under the old name no longer finds it.
+        assert!(db.execute("DROP INDEX idx_a").is_err());
+    }
+
+    let mut db = Database::open(&path, &test_key()).unwrap();
+    assert_eq!(index_names(&mut db), ["idx_b", "idx_renamed"]);
+    db.execute("INSERT INTO t VALUES (100, 3, 100, 'x')")
+        .unwrap();
+    assert_eq!(db.query("SELECT id FROM t WHERE a = 3").unwrap().len(), 5);
+    db.execute("ALTER TABLE t DROP INDEX idx_renamed").unwrap();
+    assert_eq!(index_names(&mut db), ["idx_b"]);
+}
+
+#[test]
+fn test_multiple_operations_in_one_statement() {
+    let dir = TempDir::new().unwrap();
+    let mut db = setup(&dir);
+    db.execute("ALTER TABLE t ADD INDEX idx_a (a)").unwrap();
+    db.execute(
+        "ALTER TABLE t ADD COLUMN d INT DEFAULT 7, ADD INDEX idx_d (d), \
+         DROP INDEX idx_a, ADD UNIQUE KEY u_b (b), RENAME INDEX idx_d TO idx_dd",
+    )
+    .unwrap();
+    assert_eq!(index_names(&mut db), ["idx_dd", "u_b"]);
+    assert_eq!(db.query("SELECT id FROM t WHERE d = 7").unwrap().len(), 20);
+    assert!(explain(&mut db, "SELECT id FROM t WHERE d = 7").contains("idx_dd"));
+}
+
+#[test]
+fn test_failing_operation_undoes_the_whole_statement() {
+    let dir = TempDir::new().unwrap();
+    let mut db = setup(&dir);
+    db.execute("ALTER TABLE t ADD INDEX idx_a (a)").unwrap();
+    for in_tx in [false, true] {
+        if in_tx {
+            db.execute("BEGIN").unwrap();
+        }
+        // u_a fails its backfill on duplicate `a` values.
+        assert!(db
+            .execute("ALTER TABLE t ADD COLUMN d INT, DROP INDEX idx_a, ADD UNIQUE KEY u_a (a)")
+            .is_err());
+        if in_tx {
+            db.execute("COMMIT").unwrap();
+        }
+        assert_eq!(index_names(&mut db), ["idx_a"]);
+        let rows = db.query("SELECT * FROM t WHERE id = 1").unwrap();
+        assert_eq!(rows[0].values.len(), 4);
+    }
+}
+
+#[test]
+fn test_exchange_cannot_be_combined() {
+    let dir = TempDir::new().unwrap();
+    let mut db = setup(&dir);
+    assert!(matches!(
+        db.execute("ALTER TABLE t ADD INDEX idx_a (a), EXCHANGE WITH other"),
+        Err(MuroError::Parse(_))
+    ));
+}
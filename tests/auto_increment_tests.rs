@@ -0,0 +1,142 @@
+#![cfg(feature = "test-utils")]
+/// AUTO_INCREMENT counters only move forward: not back on rollback, not back
+/// on recovery, and forward past explicitly inserted ids.
+use murodb::crypto::aead::MasterKey;
+use murodb::{Database, Value};
+use std::path::Path;
+use tempfile::TempDir;
+
+fn test_key() -> MasterKey {
+    MasterKey::new([0x42u8; 32])
+}
+
+fn setup(db_path: &Path) -> Database {
+    let mut db = Database::create(db_path, &test_key()).unwrap();
+    db.execute("CREATE TABLE t (id BIGINT PRIMARY KEY AUTO_INCREMENT, name VARCHAR)")
+        .unwrap();
+    db
+}
+
+fn ids(db: &mut Database) -> Vec<i64> {
+    db.query("SELECT id FROM t ORDER BY id")
+        .unwrap()
+        .iter()
+        .map(|row| match row.get("id") {
+            Some(Value::Integer(n)) => *n,
+            other => panic!("unexpected id {:?}", other),
+        })
+        .collect()
+}
+
+#[test]
+fn test_rolled_back_ids_are_not_reused() {
+    let dir = TempDir::new().unwrap();
+    let mut db = setup(&dir.path().join("test.db"));
+    db.execute("INSERT INTO t (name) VALUES ('a')").unwrap();
+
+    db.execute("BEGIN").unwrap();
+    db.execute("INSERT INTO t (name) VALUES ('b'), ('c')")
+        .unwrap();
+    db.execute("ROLLBACK").unwrap();
+
+    db.execute("INSERT INTO t (name) VALUES ('d')").unwrap();
+    assert_eq!(ids(&mut db), vec![1, 4]);
+}
+
+#[test]
+fn test_failed_statement_ids_are_not_reused() {
+    let dir = TempDir::new().unwrap();
+    let mut db = setup(&dir.path().join("test.db"));
+    db.execute("INSERT INTO t VALUES (2, 'x')").unwrap();
+    db.execute("DELETE FROM t").unwrap();
+
+    // The second row collides with an id the first row just took.
+    assert!(db
+        .execute("INSERT INTO t (id, name) VALUES (NULL, 'a'), (3, 'b')")
+        .is_err());
+    db.execute("INSERT INTO t (name) VALUES ('c')").unwrap();
+    assert_eq!(ids(&mut db), vec![4]);
+}
+
+#[test]
+fn test_explicit_larger_id_advances_counter() {
+    let dir = TempDir::new().unwrap();
+    let mut db = setup(&dir.path().join("test.db"));
+    db.execute("INSERT INTO t (name) VALUES ('a')").unwrap();
+    db.execute("INSERT INTO t VALUES (100, 'b')").unwrap();
+    db.execute("INSERT INTO t (name) VALUES ('c')").unwrap();
+    // A smaller explicit id leaves the counter alone.
+    db.execute("INSERT INTO t VALUES (50, 'd')").unwrap();
+    db.execute("INSERT INTO t (name) VALUES ('e')").unwrap();
+    assert_eq!(ids(&mut db), vec![1, 50, 100, 101, 102]);
+}
+
+#[test]
+fn test_replace_with_explicit_id_advances_counter() {
+    let dir = TempDir::new().unwrap();
+    let mut db = setup(&dir.path().join("test.db"));
+    db.execute("INSERT INTO t (name) VALUES ('a')").unwrap();
+    db.execute("REPLACE INTO t VALUES (10, 'b')").unwrap();
+    db.execute("REPLACE INTO t (name) VALUES ('c')").unwrap();
+    assert_eq!(ids(&mut db), vec![1, 10, 11]);
+}
+
+#[test]
+fn test_table_option_sets_counter() {
+    let dir = TempDir::new().unwrap();
+    let mut db = setup(&dir.path().join("test.db"));
+    db.execute("DROP TABLE t").unwrap();
+    db.execute(
+        "CREATE TABLE t (id BIGINT PRIMARY KEY AUTO_INCREMENT, name VARCHAR) AUTO_INCREMENT = 100",
+    )
+    .unwrap();
+    db.execute("INSERT INTO t (name) VALUES ('a'), ('b')")
+        .unwrap();
+    assert_eq!(ids(&mut db), vec![100, 101]);
+
+    // SHOW CREATE TABLE gives the next value the counter hands out.
+    let rows = db.query("SHOW CREATE TABLE t").unwrap();
+    match rows[0].get("Create Table") {
+        Some(Value::Varchar(sql)) => {
+            assert!(sql.ends_with(") AUTO_INCREMENT = 102"), "{}", sql)
+        }
+        other => panic!("unexpected value {:?}", other),
+    }
+}
+
+#[test]
+fn test_counter_survives_reopen() {
+    let dir = TempDir::new().unwrap();
+    let db_path = dir.path().join("test.db");
+    let mut db = setup(&db_path);
+    db.execute("INSERT INTO t (name) VALUES ('a'), ('b')")
+        .unwrap();
+    db.execute("INSERT INTO t VALUES (20, 'c')").unwrap();
+    db.execute("DELETE FROM t WHERE id = 20").unwrap();
+    drop(db);
+
+    let mut db = Database::open(&db_path, &test_key()).unwrap();
+    db.execute("INSERT INTO t (name) VALUES ('d')").unwrap();
+    assert_eq!(ids(&mut db), vec![1, 2, 21]);
+}
+
+#[test]
+fn test_counter_recovered_from_wal_after_crash() {
+    let dir = TempDir::new().unwrap();
+    let db_path = dir.path().join("test.db");
+    let mut db = setup(&db_path);
+    db.execute("INSERT INTO t (name) VALUES ('a')").unwrap();
+    db.execute("SET checkpoint_tx_threshold = 0").unwrap();
+    let before_commit = std::fs::read(&db_path).unwrap();
+
+    db.execute("INSERT INTO t (name) VALUES ('b'), ('c')")
+        .unwrap();
+    drop(db);
+
+    // Crash before checkpoint: only the WAL has the second insert.
+    std::fs::write(&db_path, before_commit).unwrap();
+
+    let mut db = Database::open(&db_path, &test_key()).unwrap();
+    db.execute("INSERT INTO t (name) VALUES ('d')").unwrap();
+    assert_eq!(ids(&mut db), vec![1, 2, 3, 4]);
+}
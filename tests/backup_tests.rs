@@ -275,3 +275,149 @@ fn test_rekey_on_backup_does_not_affect_source() {
         Value::Varchar("source_data".to_string())
     );
 }
+
+#[test]
+fn test_backup_with_new_password_is_independent() {
+    let dir = TempDir::new().unwrap();
+    let db_path = dir.path().join("source.db");
+    let backup_path = dir.path().join("backup.db");
+
+    let mut db = murodb::Database::create_with_password(&db_path, "live-pw").unwrap();
+    db.execute("CREATE TABLE t (id BIGINT PRIMARY KEY, v VARCHAR)")
+        .unwrap();
+    db.execute("CREATE INDEX idx_v ON t (v)").unwrap();
+    for i in 0..200 {
+        db.execute(&format!("INSERT INTO t VALUES ({}, 'row{}')", i, i))
+            .unwrap();
+    }
+    db.backup_with_new_password(&backup_path, "backup-pw")
+        .unwrap();
+
+    let live_salt = murodb::storage::pager::Pager::read_salt_from_file(&db_path).unwrap();
+    let backup_salt = murodb::storage::pager::Pager::read_salt_from_file(&backup_path).unwrap();
+    assert_ne!(live_salt, backup_salt);
+
+    // The source is unaffected and still writable.
+    db.execute("INSERT INTO t VALUES (1000, 'after')").unwrap();
+    drop(db);
+    let mut db = murodb::Database::open_with_password(&db_path, "live-pw").unwrap();
+    assert_eq!(query_rows(&mut db, "SELECT id FROM t").len(), 201);
+    drop(db);
+
+    assert!(murodb::Database::open_with_password(&backup_path, "live-pw").is_err());
+    let mut backup = murodb::Database::open_with_password(&backup_path, "backup-pw").unwrap();
+    assert_eq!(query_rows(&mut backup, "SELECT id FROM t").len(), 200);
+    let rows = query_rows(&mut backup, "SELECT id FROM t WHERE v = 'row7'");
+    assert_eq!(rows[0].values[0].1, Value::Integer(7));
+    assert!(backup
+        .verify_integrity()
+        .unwrap()
+        .iter()
+        .all(|row| { row.get("status") == Some(&Value::Varchar("ok".into())) }));
+    // The restored copy is a normal database.
+    backup
+        .execute("INSERT INTO t VALUES (500, 'restored')")
+        .unwrap();
+}
+
+#[test]
+fn test_backup_with_new_password_rejects_plaintext_and_same_file() {
+    let dir = TempDir::new().unwrap();
+    let plain_path = dir.path().join("plain.db");
+    let mut plain = murodb::Database::create_plaintext(&plain_path).unwrap();
+    assert!(plain
+        .backup_with_new_password(dir.path().join("b.db"), "pw")
+        .is_err());
+
+    let db_path = dir.path().join("source.db");
+    let mut db = murodb::Database::create_with_password(&db_path, "pw").unwrap();
+    let err = db.backup_with_new_password(&db_path, "other").unwrap_err();
+    assert!(err.to_string().contains("same file"), "{}", err);
+    db.execute("CREATE TABLE t (id BIGINT PRIMARY KEY)")
+        .unwrap();
+}
+
+#[test]
+fn test_interrupted_backup_with_new_password_is_not_openable() {
+    let dir = TempDir::new().unwrap();
+    let db_path = dir.path().join("source.db");
+    let backup_path = dir.path().join("backup.db");
+
+    let mut session = murodb::Database::create_with_password(&db_path, "live-pw")
+        .unwrap()
+        .into_session();
+    session
+        .execute("CREATE TABLE t (id BIGINT PRIMARY KEY, v VARCHAR)")
+        .unwrap();
+    for i in 0..100 {
+        session
+            .execute(&format!(
+                "INSERT INTO t VALUES ({}, '{}')",
+                i,
+                "x".repeat(200)
+            ))
+            .unwrap();
+    }
+    session
+        .pager_mut()
+        .set_inject_backup_failure_at_page(Some(3));
+    let key = murodb::crypto::kdf::derive_key(b"backup-pw", &[7u8; 16]).unwrap();
+    assert!(session
+        .pager_mut()
+        .backup_to_file_with_key(&backup_path, &key, [7u8; 16])
+        .is_err());
+
+    // Pages were streamed but the header was never finalized.
+    assert!(std::fs::metadata(&backup_path).unwrap().len() > 76);
+    assert!(murodb::storage::pager::Pager::read_encryption_info_from_file(&backup_path).is_err());
+    assert!(murodb::Database::open_with_password(&backup_path, "backup-pw").is_err());
+
+    // Retrying over the partial file succeeds.
+    session.pager_mut().set_inject_backup_failure_at_page(None);
+    session
+        .pager_mut()
+        .backup_to_file_with_key(&backup_path, &key, [7u8; 16])
+        .unwrap();
+    drop(session);
+    let mut backup = murodb::Database::open_with_password(&backup_path, "backup-pw").unwrap();
+    assert_eq!(query_rows(&mut backup, "SELECT id FROM t").len(), 100);
+}
+
+#[test]
+fn test_reencrypting_backup_of_many_pages() {
+    let dir = TempDir::new().unwrap();
+    let db_path = dir.path().join("source.db");
+    let mut session = murodb::Database::create(&db_path, &test_key())
+        .unwrap()
+        .into_session();
+    session
+        .execute("CREATE TABLE t (id BIGINT PRIMARY KEY, v VARCHAR)")
+        .unwrap();
+    session.execute("BEGIN").unwrap();
+    for i in 0..2000 {
+        session
+            .execute(&format!(
+                "INSERT INTO t VALUES ({}, '{}')",
+                i,
+                "y".repeat(300)
+            ))
+            .unwrap();
+    }
+    session.execute("COMMIT").unwrap();
+
+    // Timing against the byte copy lives in murodb_bench (`backup_*`).
+    let same_path = dir.path().join("same.db");
+    let rekeyed_path = dir.path().join("rekeyed.db");
+    session.pager_mut().backup_to_file(&same_path).unwrap();
+    session
+        .pager_mut()
+        .backup_to_file_with_key(&rekeyed_path, &MasterKey::new([0x17u8; 32]), [9u8; 16])
+        .unwrap();
+    assert_eq!(
+        std::fs::metadata(&same_path).unwrap().len(),
+        std::fs::metadata(&rekeyed_path).unwrap().len()
+    );
+    drop(session);
+    let mut backup = murodb::Database::open(&rekeyed_path, &MasterKey::new([0x17u8; 32])).unwrap();
+    assert_eq!(query_rows(&mut backup, "SELECT id FROM t").len(), 2000);
+}
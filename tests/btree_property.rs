@@ -119,6 +119,7 @@ fn assert_no_duplicate_pages(btree: &BTree, pager: &mut Pager) {
 /// - Internal node keys are sorted
 /// - Leaf node keys are sorted
 /// - All paths from root to leaf have the same depth
+///
 /// Returns the depth of the subtree.
 fn verify_tree_structure(pager: &mut Pager, page_id: PageId, depth: usize) -> usize {
     assert!(depth <= 64, "tree depth exceeds 64, possible corruption");
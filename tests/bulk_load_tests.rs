@@ -0,0 +1,308 @@
+#![cfg(feature = "test-utils")]
+/// Multi-row INSERT into an empty table and `Database::bulk_insert` build the
+/// data tree and its indexes bottom-up while enforcing the same constraints
+/// as row-by-row inserts.
+use murodb::crypto::aead::MasterKey;
+use murodb::error::MuroError;
+use murodb::sql::executor::ExecResult;
+use murodb::{Database, Value};
+use tempfile::TempDir;
+
+fn test_key() -> MasterKey {
+    MasterKey::new([0x42u8; 32])
+}
+
+fn int(db: &mut Database, sql: &str) -> i64 {
+    match db.query(sql).unwrap()[0].values[0].1 {
+        Value::Integer(n) => n,
+        ref other => panic!("unexpected value {:?}", other),
+    }
+}
+
+fn ids(db: &mut Database, sql: &str) -> Vec<i64> {
+    db.query(sql)
+        .unwrap()
+        .iter()
+        .map(|row| match row.get("id") {
+            Some(Value::Integer(n)) => *n,
+            other => panic!("unexpected id {:?}", other),
+        })
+        .collect()
+}
+
+fn assert_table_ok(db: &mut Database, table: &str) {
+    match db.execute(&format!("CHECK TABLE {}", table)).unwrap() {
+        ExecResult::Rows(rows) => {
+            for row in rows {
+                assert_eq!(
+                    row.get("status"),
+                    Some(&Value::Varchar("ok".into())),
+                    "{:?}",
+                    row
+                );
+            }
+        }
+        other => panic!("expected rows, got {:?}", other),
+    }
+}
+
+/// `(Leaf_pages, Leaf_gap)` of one tree from SHOW TABLE STATUS.
+fn leaf_stats(db: &mut Database, table: &str, tree: &str) -> (i64, Option<f64>) {
+    let rows = db.query("SHOW TABLE STATUS").unwrap();
+    let row = rows
+        .iter()
+        .find(|r| {
+            r.get("Name") == Some(&Value::Varchar(table.into()))
+                && r.get("Tree") == Some(&Value::Varchar(tree.into()))
+        })
+        .unwrap();
+    let leaves = match row.get("Leaf_pages") {
+        Some(Value::Integer(n)) => *n,
+        other => panic!("unexpected Leaf_pages {:?}", other),
+    };
+    let gap = match row.get("Leaf_gap") {
+        Some(Value::Float(g)) => Some(*g),
+        _ => None,
+    };
+    (leaves, gap)
+}
+
+fn setup_items(db: &mut Database, table: &str) {
+    db.execute(&format!(
+        "CREATE TABLE {} (id BIGINT PRIMARY KEY, sku VARCHAR UNIQUE, cat INT NOT NULL, \
+         qty INT CHECK (qty >= 0), note TEXT)",
+        table
+    ))
+    .unwrap();
+    db.execute(&format!("CREATE INDEX {0}_cat ON {0} (cat, qty)", table))
+        .unwrap();
+}
+
+fn item(i: i64) -> Vec<Value> {
+    vec![
+        Value::Integer(i),
+        Value::Varchar(format!("sku-{:06}", i)),
+        Value::Integer(i % 7),
+        Value::Integer(i % 100),
+        Value::Varchar(format!("note {}", i)),
+    ]
+}
+
+#[test]
+fn test_multi_row_insert_into_empty_table_bulk_loads() {
+    let dir = TempDir::new().unwrap();
+    let mut db = Database::create(&dir.path().join("test.db"), &test_key()).unwrap();
+    setup_items(&mut db, "items");
+
+    // Rows arrive out of key order; NULL sku values are not indexed.
+    let values: Vec<String> = (0..2000i64)
+        .map(|n| {
+            let i = (n * 7919) % 2000 + 1;
+            let sku = if i % 500 == 0 {
+                "NULL".to_string()
+            } else {
+                format!("'sku-{:06}'", i)
+            };
+            format!("({}, {}, {}, {}, 'note {}')", i, sku, i % 7, i % 100, i)
+        })
+        .collect();
+    let result = db
+        .execute(&format!("INSERT INTO items VALUES {}", values.join(", ")))
+        .unwrap();
+    assert!(matches!(result, ExecResult::RowsAffected(2000)));
+
+    assert_eq!(int(&mut db, "SELECT COUNT(*) FROM items"), 2000);
+    assert_eq!(
+        ids(&mut db, "SELECT id FROM items WHERE sku = 'sku-001234'"),
+        vec![1234]
+    );
+    assert_eq!(
+        int(
+            &mut db,
+            "SELECT COUNT(*) FROM items WHERE cat = 3 AND qty = 10"
+        ),
+        (1..=2000).filter(|i| i % 7 == 3 && i % 100 == 10).count() as i64
+    );
+    assert_table_ok(&mut db, "items");
+
+    // Leaves are packed and laid out sequentially.
+    let (leaves, gap) = leaf_stats(&mut db, "items", "PRIMARY");
+    assert!(leaves > 10, "{}", leaves);
+    assert_eq!(gap, Some(1.0));
+
+    // The loaded trees stay writable and keep enforcing uniqueness.
+    db.execute("INSERT INTO items VALUES (5000, 'sku-005000', 1, 1, 'x')")
+        .unwrap();
+    assert!(db
+        .execute("INSERT INTO items VALUES (5001, 'sku-000010', 1, 1, 'x')")
+        .is_err());
+    db.execute("DELETE FROM items WHERE id <= 1000").unwrap();
+    assert_eq!(int(&mut db, "SELECT COUNT(*) FROM items"), 1001);
+    assert_table_ok(&mut db, "items");
+}
+
+#[test]
+fn test_bulk_load_fills_fulltext_index() {
+    let dir = TempDir::new().unwrap();
+    let mut db = Database::create(&dir.path().join("test.db"), &test_key()).unwrap();
+    db.execute("CREATE TABLE docs (id BIGINT PRIMARY KEY, body TEXT)")
+        .unwrap();
+    db.execute("CREATE FULLTEXT INDEX docs_ft ON docs (body) WITH PARSER ngram")
+        .unwrap();
+    let rows: Vec<Vec<Value>> = (1..=100)
+        .map(|i| vec![Value::Integer(i), Value::Varchar(format!("doc {}", i))])
+        .collect();
+    db.bulk_insert("docs", &rows).unwrap();
+    assert_eq!(
+        ids(
+            &mut db,
+            "SELECT id FROM docs WHERE MATCH(body) AGAINST('doc 42' IN BOOLEAN MODE) > 0 \
+             AND id = 42"
+        ),
+        vec![42]
+    );
+    assert_table_ok(&mut db, "docs");
+}
+
+#[test]
+fn test_bulk_insert_packs_pages_compared_to_row_inserts() {
+    let dir = TempDir::new().unwrap();
+    let db_path = dir.path().join("test.db");
+    let mut db = Database::create(&db_path, &test_key()).unwrap();
+    setup_items(&mut db, "bulk");
+    setup_items(&mut db, "rowwise");
+
+    let rows: Vec<Vec<Value>> = (1..=3000).map(item).collect();
+    assert_eq!(db.bulk_insert("bulk", &rows).unwrap(), 3000);
+    // Descending keys: ascending ones would fill the rightmost leaf as full
+    // as a bulk load does.
+    db.execute("BEGIN").unwrap();
+    for i in (1..=3000).rev() {
+        db.execute(&format!(
+            "INSERT INTO rowwise VALUES ({}, 'sku-{:06}', {}, {}, 'note {}')",
+            i,
+            i,
+            i % 7,
+            i % 100,
+            i
+        ))
+        .unwrap();
+    }
+    db.execute("COMMIT").unwrap();
+
+    let (bulk_leaves, _) = leaf_stats(&mut db, "bulk", "PRIMARY");
+    let (rowwise_leaves, _) = leaf_stats(&mut db, "rowwise", "PRIMARY");
+    assert!(
+        bulk_leaves * 10 < rowwise_leaves * 7,
+        "bulk {} vs row-by-row {}",
+        bulk_leaves,
+        rowwise_leaves
+    );
+    let (bulk_index_leaves, _) = leaf_stats(&mut db, "bulk", "bulk_cat");
+    let (rowwise_index_leaves, _) = leaf_stats(&mut db, "rowwise", "rowwise_cat");
+    assert!(bulk_index_leaves < rowwise_index_leaves);
+    drop(db);
+
+    let mut db = Database::open(&db_path, &test_key()).unwrap();
+    assert_eq!(int(&mut db, "SELECT COUNT(*) FROM bulk"), 3000);
+    let values = |db: &mut Database, sql: &str| -> Vec<Vec<Value>> {
+        db.query(sql)
+            .unwrap()
+            .into_iter()
+            .map(|row| row.values.into_iter().map(|(_, v)| v).collect())
+            .collect()
+    };
+    assert_eq!(
+        values(&mut db, "SELECT * FROM bulk ORDER BY id"),
+        values(&mut db, "SELECT * FROM rowwise ORDER BY id")
+    );
+    assert_table_ok(&mut db, "bulk");
+}
+
+#[test]
+fn test_bulk_load_enforces_constraints() {
+    let dir = TempDir::new().unwrap();
+    let mut db = Database::create(&dir.path().join("test.db"), &test_key()).unwrap();
+    setup_items(&mut db, "items");
+
+    let mut rows: Vec<Vec<Value>> = (1..=100).map(item).collect();
+    rows.push(item(50));
+    assert!(matches!(
+        db.bulk_insert("items", &rows),
+        Err(MuroError::UniqueViolation(_))
+    ));
+
+    let mut rows: Vec<Vec<Value>> = (1..=100).map(item).collect();
+    rows[99][1] = Value::Varchar("sku-000001".into());
+    assert!(matches!(
+        db.bulk_insert("items", &rows),
+        Err(MuroError::UniqueViolation(_))
+    ));
+
+    let mut rows: Vec<Vec<Value>> = (1..=100).map(item).collect();
+    rows[10][2] = Value::Null;
+    assert!(db.bulk_insert("items", &rows).is_err());
+
+    assert!(db
+        .execute("INSERT INTO items VALUES (1, 'a', 1, 1, ''), (2, 'b', 1, -1, '')")
+        .is_err());
+
+    // Nothing from the failed batches is left behind.
+    assert_eq!(int(&mut db, "SELECT COUNT(*) FROM items"), 0);
+    assert_table_ok(&mut db, "items");
+
+    // Inside a transaction, a rolled-back bulk load leaves the table empty.
+    db.execute("BEGIN").unwrap();
+    db.bulk_insert("items", &(1..=500).map(item).collect::<Vec<_>>())
+        .unwrap();
+    db.execute("ROLLBACK").unwrap();
+    assert_eq!(int(&mut db, "SELECT COUNT(*) FROM items"), 0);
+    assert_table_ok(&mut db, "items");
+}
+
+#[test]
+fn test_bulk_insert_assigns_auto_increment_and_appends() {
+    let dir = TempDir::new().unwrap();
+    let mut db = Database::create(&dir.path().join("test.db"), &test_key()).unwrap();
+    db.execute("CREATE TABLE t (id BIGINT PRIMARY KEY AUTO_INCREMENT, v VARCHAR)")
+        .unwrap();
+
+    let rows: Vec<Vec<Value>> = (0..300)
+        .map(|i| vec![Value::Varchar(format!("v{}", i))])
+        .collect();
+    assert_eq!(db.bulk_insert("t", &rows).unwrap(), 300);
+    assert_eq!(int(&mut db, "SELECT MAX(id) FROM t"), 300);
+
+    // Into a non-empty table the rows are inserted one by one.
+    assert_eq!(db.bulk_insert("t", &rows[..10]).unwrap(), 10);
+    db.execute("INSERT INTO t (v) VALUES ('last')").unwrap();
+    assert_eq!(int(&mut db, "SELECT MAX(id) FROM t"), 311);
+    assert_table_ok(&mut db, "t");
+
+    assert!(db.bulk_insert("missing", &rows).is_err());
+}
+
+#[test]
+fn test_self_referencing_rows_in_one_statement() {
+    let dir = TempDir::new().unwrap();
+    let mut db = Database::create(&dir.path().join("test.db"), &test_key()).unwrap();
+    db.execute(
+        "CREATE TABLE emp (id BIGINT PRIMARY KEY, boss BIGINT, \
+         FOREIGN KEY (boss) REFERENCES emp(id))",
+    )
+    .unwrap();
+    let values: Vec<String> = (1..=500)
+        .map(|i| {
+            if i == 1 {
+                "(1, NULL)".to_string()
+            } else {
+                format!("({}, {})", i, i - 1)
+            }
+        })
+        .collect();
+    db.execute(&format!("INSERT INTO emp VALUES {}", values.join(", ")))
+        .unwrap();
+    assert_eq!(int(&mut db, "SELECT COUNT(*) FROM emp"), 500);
+    assert!(db.execute("INSERT INTO emp VALUES (1000, 999)").is_err());
+    assert_table_ok(&mut db, "emp");
+}
@@ -0,0 +1,161 @@
+#![cfg(feature = "test-utils")]
+/// Lock waits across processes honour the busy timeout. A child process holds
+/// the database's shared or exclusive file lock; opens and statements in this
+/// process, with the timeout set through `OpenOptions`, the setter or
+/// `SET busy_timeout`, must fail with `LockTimeout` once it expires and go
+/// through once the child lets go.
+///
+/// The test re-runs its own binary as the lock holder; the holder test is a
+/// no-op unless the holder environment variable is set.
+use murodb::concurrency::LockManager;
+use murodb::{Database, MuroError, OpenOptions};
+use std::path::Path;
+use std::process::{Child, Command, Stdio};
+use std::time::{Duration, Instant};
+use tempfile::TempDir;
+
+const HOLDER_ENV: &str = "MURODB_BUSY_TIMEOUT_HOLDER";
+
+fn wait_for(path: &Path) {
+    let deadline = Instant::now() + Duration::from_secs(30);
+    while !path.exists() {
+        assert!(Instant::now() < deadline, "signal {:?} never came", path);
+        std::thread::sleep(Duration::from_millis(1));
+    }
+}
+
+#[test]
+fn busy_timeout_lock_holder() {
+    let Ok(spec) = std::env::var(HOLDER_ENV) else {
+        return;
+    };
+    let (dir, mode) = spec.rsplit_once('|').unwrap();
+    let dir = Path::new(dir);
+    let lock_manager = LockManager::new(&dir.join("busy.db")).unwrap();
+    match mode {
+        "exclusive" => {
+            let _guard = lock_manager.write_lock().unwrap();
+            std::fs::write(dir.join("held"), b"").unwrap();
+            wait_for(&dir.join("release"));
+        }
+        "shared" => {
+            let _guard = lock_manager.read_lock().unwrap();
+            std::fs::write(dir.join("held"), b"").unwrap();
+            wait_for(&dir.join("release"));
+        }
+        other => panic!("unknown lock mode {}", other),
+    }
+}
+
+/// Spawn a child process holding the lock in `mode`, returning once it does.
+fn spawn_holder(dir: &Path, mode: &str) -> Child {
+    let _ = std::fs::remove_file(dir.join("held"));
+    let _ = std::fs::remove_file(dir.join("release"));
+    let child = Command::new(std::env::current_exe().unwrap())
+        .args(["busy_timeout_lock_holder", "--exact", "--test-threads=1"])
+        .env(HOLDER_ENV, format!("{}|{}", dir.display(), mode))
+        .stdout(Stdio::null())
+        .spawn()
+        .unwrap();
+    wait_for(&dir.join("held"));
+    child
+}
+
+fn release_holder(dir: &Path, mut child: Child) {
+    std::fs::write(dir.join("release"), b"").unwrap();
+    let status = child.wait().unwrap();
+    assert!(status.success(), "lock holder failed: {}", status);
+}
+
+fn assert_lock_timeout<T>(result: murodb::error::Result<T>, mode: &str) {
+    match result.err() {
+        Some(MuroError::LockTimeout { mode: got, .. }) => assert_eq!(got, mode),
+        other => panic!("expected {} LockTimeout, got {:?}", mode, other),
+    }
+}
+
+fn create_db(dir: &TempDir) -> std::path::PathBuf {
+    let path = dir.path().join("busy.db");
+    let mut db = Database::create_plaintext(&path).unwrap();
+    db.execute("CREATE TABLE t (id BIGINT PRIMARY KEY)")
+        .unwrap();
+    db.execute("INSERT INTO t VALUES (1)").unwrap();
+    path
+}
+
+#[test]
+fn test_writer_in_another_process_times_out_statements_and_open() {
+    let dir = TempDir::new().unwrap();
+    let path = create_db(&dir);
+    let mut db = Database::open_plaintext(&path).unwrap();
+    db.execute("SET busy_timeout = 100").unwrap();
+    assert_eq!(db.busy_timeout_ms(), 100);
+
+    let holder = spawn_holder(dir.path(), "exclusive");
+
+    let started = Instant::now();
+    assert_lock_timeout(db.execute("INSERT INTO t VALUES (2)"), "exclusive");
+    let waited = started.elapsed();
+    assert!(
+        waited >= Duration::from_millis(100),
+        "gave up after {:?}",
+        waited
+    );
+    assert!(waited < Duration::from_secs(10), "waited {:?}", waited);
+    assert_lock_timeout(db.query("SELECT * FROM t"), "shared");
+
+    let options = OpenOptions {
+        busy_timeout: Duration::from_millis(50),
+        ..OpenOptions::default()
+    };
+    assert_lock_timeout(
+        Database::open_plaintext_with_options(&path, options),
+        "exclusive",
+    );
+
+    release_holder(dir.path(), holder);
+
+    db.execute("INSERT INTO t VALUES (2)").unwrap();
+    let mut reopened = Database::open_plaintext_with_options(&path, options).unwrap();
+    assert_eq!(reopened.busy_timeout_ms(), 50);
+    assert_eq!(reopened.query("SELECT * FROM t").unwrap().len(), 2);
+}
+
+#[test]
+fn test_reader_in_another_process_blocks_only_writers() {
+    let dir = TempDir::new().unwrap();
+    let path = create_db(&dir);
+    let mut db = Database::open_plaintext(&path).unwrap();
+    db.set_busy_timeout_ms(100);
+
+    let holder = spawn_holder(dir.path(), "shared");
+
+    assert_eq!(db.query("SELECT * FROM t").unwrap().len(), 1);
+    assert_lock_timeout(db.execute("INSERT INTO t VALUES (2)"), "exclusive");
+
+    release_holder(dir.path(), holder);
+    db.execute("INSERT INTO t VALUES (2)").unwrap();
+}
+
+#[test]
+fn test_writer_waits_for_another_process_within_busy_timeout() {
+    let dir = TempDir::new().unwrap();
+    let path = create_db(&dir);
+    let mut db = Database::open_plaintext(&path).unwrap();
+    db.set_busy_timeout_ms(30_000);
+
+    let holder = spawn_holder(dir.path(), "exclusive");
+    let releaser = {
+        let dir = dir.path().to_path_buf();
+        std::thread::spawn(move || {
+            std::thread::sleep(Duration::from_millis(100));
+            release_holder(&dir, holder);
+        })
+    };
+
+    let started = Instant::now();
+    db.execute("INSERT INTO t VALUES (2)").unwrap();
+    assert!(started.elapsed() >= Duration::from_millis(50));
+    releaser.join().unwrap();
+    assert_eq!(db.query("SELECT * FROM t").unwrap().len(), 2);
+}
@@ -0,0 +1,148 @@
+#![cfg(feature = "test-utils")]
+/// Cooperative cancellation through a `CancellationToken` and the
+/// `max_execution_time_ms` deadline. A cancelled statement fails with
+/// `MuroError::Cancelled`, is rolled back like any failed statement, and
+/// leaves the handle usable.
+use murodb::{CancellationToken, Database, MuroError, QueryCancelHandle, Value};
+use std::thread;
+use std::time::{Duration, Instant};
+use tempfile::TempDir;
+
+/// Runs until cancelled: a billion-row cross join.
+const LONG_QUERY: &str = "SELECT COUNT(*) FROM t a CROSS JOIN t b CROSS JOIN t c";
+
+fn setup(dir: &TempDir) -> Database {
+    let mut db = Database::create_plaintext(&dir.path().join("test.db")).unwrap();
+    db.execute("CREATE TABLE t (id BIGINT PRIMARY KEY, v INT)")
+        .unwrap();
+    db.execute("CREATE INDEX idx_t_v ON t (v)").unwrap();
+    let values: Vec<String> = (0..1000).map(|i| format!("({}, {})", i, i)).collect();
+    db.execute(&format!("INSERT INTO t VALUES {}", values.join(", ")))
+        .unwrap();
+    db
+}
+
+fn wait_running(handle: &QueryCancelHandle) {
+    let deadline = Instant::now() + Duration::from_secs(30);
+    while !handle.is_running() {
+        assert!(Instant::now() < deadline, "statement never started");
+        thread::sleep(Duration::from_millis(1));
+    }
+}
+
+fn sum_v(db: &mut Database) -> Value {
+    db.query("SELECT SUM(v) FROM t").unwrap()[0]
+        .get_at(0)
+        .cloned()
+        .unwrap()
+}
+
+#[test]
+fn test_cancel_cross_join_from_another_thread() {
+    let dir = TempDir::new().unwrap();
+    let mut db = setup(&dir);
+    let handle = db.cancel_handle();
+    let token = CancellationToken::new();
+
+    let worker = thread::spawn({
+        let token = token.clone();
+        move || {
+            let result = db.query_with_cancel(LONG_QUERY, &token).map(|_| ());
+            (db, result)
+        }
+    });
+    wait_running(&handle);
+    token.cancel();
+    let (mut db, result) = worker.join().unwrap();
+    assert!(matches!(result, Err(MuroError::Cancelled)), "{:?}", result);
+
+    // The token is not left attached to the handle.
+    let rows = db.query("SELECT COUNT(*) FROM t").unwrap();
+    assert_eq!(rows[0].get_at(0), Some(&Value::Integer(1000)));
+    db.execute("INSERT INTO t VALUES (1000, 0)").unwrap();
+}
+
+#[test]
+fn test_cancelled_write_leaves_no_partial_rows() {
+    let dir = TempDir::new().unwrap();
+    let mut db = setup(&dir);
+    // Large enough that the UPDATE is still rewriting rows when cancelled.
+    let values: Vec<String> = (1000..20000).map(|i| format!("({}, {})", i, i)).collect();
+    db.execute(&format!("INSERT INTO t VALUES {}", values.join(", ")))
+        .unwrap();
+    let before = sum_v(&mut db);
+    db.execute("BEGIN").unwrap();
+    db.execute("INSERT INTO t VALUES (50000, 0)").unwrap();
+
+    let handle = db.cancel_handle();
+    let token = CancellationToken::new();
+    let worker = thread::spawn({
+        let token = token.clone();
+        move || {
+            let result = db.execute_with_cancel("UPDATE t SET v = v + 1", &token);
+            (db, result)
+        }
+    });
+    wait_running(&handle);
+    thread::sleep(Duration::from_millis(50));
+    token.cancel();
+    let (mut db, result) = worker.join().unwrap();
+    assert!(matches!(result, Err(MuroError::Cancelled)), "{:?}", result);
+
+    // The transaction keeps its earlier statement and nothing of the
+    // cancelled one.
+    db.execute("COMMIT").unwrap();
+    assert_eq!(sum_v(&mut db), before);
+    let rows = db.query("SELECT id FROM t WHERE id = 50000").unwrap();
+    assert_eq!(rows.len(), 1);
+    let rows = db
+        .query("SELECT COUNT(*) FROM t FORCE INDEX (idx_t_v) WHERE v >= 0")
+        .unwrap();
+    assert_eq!(rows[0].get_at(0), Some(&Value::Integer(20001)));
+    for row in db.query("CHECK TABLE t").unwrap() {
+        assert_eq!(row.get("status"), Some(&Value::Varchar("ok".into())));
+    }
+}
+
+#[test]
+fn test_cancelled_token_fails_statement_before_it_runs() {
+    let dir = TempDir::new().unwrap();
+    let mut db = setup(&dir);
+    let token = CancellationToken::new();
+    token.cancel();
+    assert!(token.is_cancelled());
+    assert!(matches!(
+        db.execute_with_cancel("DELETE FROM t", &token),
+        Err(MuroError::Cancelled)
+    ));
+    assert!(matches!(
+        db.query_with_cancel("SELECT * FROM t", &token),
+        Err(MuroError::Cancelled)
+    ));
+
+    let fresh = CancellationToken::new();
+    let rows = db.query_with_cancel("SELECT * FROM t", &fresh).unwrap();
+    assert_eq!(rows.len(), 1000);
+}
+
+#[test]
+fn test_max_execution_time_ms_interrupts_long_query() {
+    let dir = TempDir::new().unwrap();
+    let mut db = setup(&dir);
+    db.execute("SET max_execution_time_ms = 20").unwrap();
+    let rows = db
+        .query("SHOW VARIABLES LIKE 'max_execution_time_ms'")
+        .unwrap();
+    assert_eq!(rows[0].get("value"), Some(&Value::Varchar("20".into())));
+
+    let started = Instant::now();
+    assert!(matches!(
+        db.query(LONG_QUERY),
+        Err(MuroError::StatementTimeout { timeout_ms: 20 })
+    ));
+    assert!(started.elapsed() < Duration::from_secs(30));
+
+    db.execute("SET max_execution_time_ms = 0").unwrap();
+    let rows = db.query("SELECT COUNT(*) FROM t").unwrap();
+    assert_eq!(rows[0].get_at(0), Some(&Value::Integer(1000)));
+}
@@ -0,0 +1,196 @@
+#![cfg(feature = "test-utils")]
+/// `CAST(expr AS type)` for every column type, and its agreement with the
+/// coercion ALTER TABLE ... MODIFY applies: both go through one conversion,
+/// so a value converts the same way (or fails the same way) in either.
+use murodb::{Database, MuroError, Value};
+use tempfile::TempDir;
+
+fn open(dir: &TempDir) -> Database {
+    Database::create_plaintext(&dir.path().join("test.db")).unwrap()
+}
+
+fn scalar(db: &mut Database, sql: &str) -> Value {
+    let rows = db.query(sql).unwrap_or_else(|e| panic!("{}: {}", sql, e));
+    rows[0].values[0].1.clone()
+}
+
+fn cast_error(db: &mut Database, sql: &str) -> String {
+    match db.query(sql) {
+        Err(e @ MuroError::Execution(_)) => e.to_string(),
+        other => panic!("{}: expected execution error, got {:?}", sql, other),
+    }
+}
+
+#[test]
+fn test_cast_between_numbers_and_strings() {
+    let dir = TempDir::new().unwrap();
+    let mut db = open(&dir);
+    let cases: &[(&str, Value)] = &[
+        ("SELECT CAST('42' AS INT)", Value::Integer(42)),
+        ("SELECT CAST('  -42 ' AS BIGINT)", Value::Integer(-42)),
+        ("SELECT CAST(42 AS VARCHAR)", Value::Varchar("42".into())),
+        (
+            "SELECT CAST(42 AS VARCHAR(10))",
+            Value::Varchar("42".into()),
+        ),
+        ("SELECT CAST(3.99 AS BIGINT)", Value::Integer(3)),
+        ("SELECT CAST(-3.99 AS INT)", Value::Integer(-3)),
+        ("SELECT CAST(7 AS DOUBLE)", Value::Float(7.0)),
+        ("SELECT CAST('1.5' AS FLOAT)", Value::Float(1.5)),
+        ("SELECT CAST(2.5 AS TEXT)", Value::Varchar("2.5".into())),
+        ("SELECT CAST(NULL AS INT)", Value::Null),
+    ];
+    for (sql, expected) in cases {
+        assert_eq!(&scalar(&mut db, sql), expected, "{}", sql);
+    }
+    assert_eq!(
+        scalar(
+            &mut db,
+            "SELECT CAST(CAST('1.256' AS DECIMAL(5,2)) AS VARCHAR)"
+        ),
+        Value::Varchar("1.26".into())
+    );
+}
+
+#[test]
+fn test_cast_requires_the_whole_string_to_parse() {
+    let dir = TempDir::new().unwrap();
+    let mut db = open(&dir);
+    for sql in [
+        "SELECT CAST('12abc' AS INT)",
+        "SELECT CAST('' AS BIGINT)",
+        "SELECT CAST('1.5x' AS DOUBLE)",
+        "SELECT CAST('abc' AS DECIMAL(5,2))",
+        "SELECT CAST('2024-13-01' AS DATE)",
+    ] {
+        let err = cast_error(&mut db, sql);
+        assert!(err.contains("Cannot cast '"), "{}: {}", sql, err);
+    }
+}
+
+#[test]
+fn test_narrowing_cast_names_the_value() {
+    let dir = TempDir::new().unwrap();
+    let mut db = open(&dir);
+    let err = cast_error(&mut db, "SELECT CAST(300 AS TINYINT)");
+    assert!(err.contains("Cannot cast 300 to TINYINT"), "{}", err);
+    let err = cast_error(&mut db, "SELECT CAST('abcdef' AS VARCHAR(3))");
+    assert!(
+        err.contains("Cannot cast 'abcdef' to VARCHAR(3)"),
+        "{}",
+        err
+    );
+    let err = cast_error(&mut db, "SELECT CAST(12345.6 AS DECIMAL(4,1))");
+    assert!(err.contains("12345.6"), "{}", err);
+    assert_eq!(
+        scalar(&mut db, "SELECT CAST(127 AS TINYINT)"),
+        Value::Integer(127)
+    );
+}
+
+#[test]
+fn test_cast_dates_and_binary() {
+    let dir = TempDir::new().unwrap();
+    let mut db = open(&dir);
+    assert_eq!(
+        scalar(
+            &mut db,
+            "SELECT CAST(CAST('2024-02-29 13:45:00' AS DATETIME) AS DATE)"
+        ),
+        scalar(&mut db, "SELECT CAST('2024-02-29' AS DATE)")
+    );
+    assert_eq!(
+        scalar(
+            &mut db,
+            "SELECT CAST(CAST('2024-02-29' AS DATE) AS VARCHAR)"
+        ),
+        Value::Varchar("2024-02-29".into())
+    );
+    assert!(cast_error(&mut db, "SELECT CAST(20240229 AS DATE)").contains("Cannot cast"));
+
+    assert_eq!(
+        scalar(&mut db, "SELECT CAST('héllo' AS VARBINARY)"),
+        Value::Varbinary("héllo".as_bytes().to_vec())
+    );
+    assert_eq!(
+        scalar(
+            &mut db,
+            "SELECT CAST(CAST('héllo' AS VARBINARY) AS VARCHAR)"
+        ),
+        Value::Varchar("héllo".into())
+    );
+    let err = cast_error(&mut db, "SELECT CAST(X'FF00' AS VARCHAR)");
+    assert!(err.contains("not valid UTF-8"), "{}", err);
+}
+
+#[test]
+fn test_cast_and_alter_modify_agree() {
+    let dir = TempDir::new().unwrap();
+    let mut db = open(&dir);
+    db.execute("CREATE TABLE t (id BIGINT PRIMARY KEY, v VARCHAR(20))")
+        .unwrap();
+    db.execute("INSERT INTO t VALUES (1, ' 17 '), (2, '-3')")
+        .unwrap();
+    let cast: Vec<Value> = db
+        .query("SELECT CAST(v AS INT) FROM t ORDER BY id")
+        .unwrap()
+        .iter()
+        .map(|row| row.values[0].1.clone())
+        .collect();
+    db.execute("ALTER TABLE t MODIFY v INT").unwrap();
+    let altered: Vec<Value> = db
+        .query("SELECT v FROM t ORDER BY id")
+        .unwrap()
+        .iter()
+        .map(|row| row.values[0].1.clone())
+        .collect();
+    assert_eq!(cast, altered);
+    assert_eq!(altered, vec![Value::Integer(17), Value::Integer(-3)]);
+
+    // A value CAST rejects is rejected by MODIFY as well, leaving the table
+    // unchanged.
+    db.execute("CREATE TABLE u (id BIGINT PRIMARY KEY, v VARCHAR(20))")
+        .unwrap();
+    db.execute("INSERT INTO u VALUES (1, '12abc')").unwrap();
+    cast_error(&mut db, "SELECT CAST(v AS INT) FROM u");
+    assert!(db.execute("ALTER TABLE u MODIFY v INT").is_err());
+    assert_eq!(
+        scalar(&mut db, "SELECT v FROM u"),
+        Value::Varchar("12abc".into())
+    );
+}
+
+#[test]
+fn test_cast_around_indexed_column_is_not_a_seek() {
+    let dir = TempDir::new().unwrap();
+    let mut db = open(&dir);
+    db.execute("CREATE TABLE t (id BIGINT PRIMARY KEY, code VARCHAR(10))")
+        .unwrap();
+    db.execute("CREATE INDEX idx_code ON t (code)").unwrap();
+    db.execute("INSERT INTO t VALUES (1, '7'), (2, '007'), (3, '8')")
+        .unwrap();
+
+    for sql in [
+        "SELECT id FROM t WHERE CAST(code AS BIGINT) = 7",
+        "SELECT id FROM t WHERE CAST(id AS VARCHAR(10)) = '2'",
+    ] {
+        let plan = format!("{:?}", db.query(&format!("EXPLAIN {}", sql)).unwrap());
+        assert!(!plan.contains("idx_code"), "{}: {}", sql, plan);
+        assert!(!plan.contains("\"const\""), "{}: {}", sql, plan);
+    }
+    let mut ids: Vec<Value> = db
+        .query("SELECT id FROM t WHERE CAST(code AS BIGINT) = 7")
+        .unwrap()
+        .iter()
+        .map(|row| row.values[0].1.clone())
+        .collect();
+    ids.sort_by_key(|v| format!("{:?}", v));
+    assert_eq!(ids, vec![Value::Integer(1), Value::Integer(2)]);
+    assert_eq!(
+        scalar(
+            &mut db,
+            "SELECT id FROM t WHERE CAST(id AS VARCHAR(10)) = '2'"
+        ),
+        Value::Integer(2)
+    );
+}
@@ -0,0 +1,315 @@
+#![cfg(feature = "test-utils")]
+/// Catalog format versioning. The fixtures are built in the layout older
+/// releases wrote: no `meta:catalog_version` key and tables on row format 0
+/// (rows without a column count prefix). A read-write open migrates them in
+/// one transaction; a read-only open leaves them as they are; a catalog from
+/// a newer library is refused. A version 2 fixture keeps a non-unique index
+/// in the layout that concatenated index key and primary key.
+use murodb::btree::key_encoding::decode_byte_stuffed;
+use murodb::btree::ops::BTree;
+use murodb::schema::catalog::{
+    SystemCatalog, INDEX_KEY_FORMAT_CONCAT, INDEX_KEY_FORMAT_TERMINATED,
+};
+use murodb::schema::migrate::{CATALOG_VERSION, UNVERSIONED_CATALOG_VERSION};
+use murodb::storage::pager::Pager;
+use murodb::{Database, MuroError, Value};
+use std::path::Path;
+use tempfile::TempDir;
+
+/// Create a database with tables `a` and `b` (each with a secondary index)
+/// and rewrite it in the pre-versioning layout.
+fn create_legacy_fixture(path: &Path) {
+    {
+        let mut db = Database::create_plaintext(path).unwrap();
+        for table in ["a", "b"] {
+            db.execute(&format!(
+                "CREATE TABLE {} (id BIGINT PRIMARY KEY, name VARCHAR, score INT)",
+                table
+            ))
+            .unwrap();
+            db.execute(&format!("CREATE INDEX idx_{0}_name ON {0} (name)", table))
+                .unwrap();
+            for i in 0..300 {
+                db.execute(&format!(
+                    "INSERT INTO {} VALUES ({}, 'name{}', {})",
+                    table,
+                    i,
+                    i,
+                    if i % 7 == 0 {
+                        "NULL".to_string()
+                    } else {
+                        i.to_string()
+                    }
+                ))
+                .unwrap();
+            }
+        }
+    }
+
+    let mut pager = Pager::open_plaintext(path).unwrap();
+    let mut catalog = SystemCatalog::open(pager.catalog_root());
+    for table in ["a", "b"] {
+        let mut def = catalog.get_table(&mut pager, table).unwrap().unwrap();
+        rewrite_rows(&mut pager, def.data_btree_root, |row| row[2..].to_vec());
+        def.row_format_version = 0;
+        catalog.update_table(&mut pager, &def).unwrap();
+    }
+    catalog.clear_catalog_version(&mut pager).unwrap();
+    pager.set_catalog_root(catalog.root_page_id());
+    pager.flush_meta().unwrap();
+}
+
+/// Replace every row of the B-tree at `root` with `f(row)`.
+fn rewrite_rows(pager: &mut Pager, root: u64, f: impl Fn(&[u8]) -> Vec<u8>) {
+    let mut btree = BTree::open(root);
+    let mut rows = Vec::new();
+    btree
+        .scan(pager, |k, v| {
+            rows.push((k.to_vec(), f(v)));
+            Ok(true)
+        })
+        .unwrap();
+    for (k, v) in rows {
+        btree.insert(pager, &k, &v).unwrap();
+    }
+    assert_eq!(btree.root_page_id(), root, "fixture rewrite moved the root");
+}
+
+/// Catalog version and per-table row format versions, read from the file.
+fn stored_versions(path: &Path) -> (u32, Vec<u8>) {
+    let mut pager = Pager::open_plaintext(path).unwrap();
+    let catalog = SystemCatalog::open(pager.catalog_root());
+    let version = catalog.catalog_version(&mut pager).unwrap();
+    let formats = ["a", "b"]
+        .iter()
+        .map(|t| {
+            catalog
+                .get_table(&mut pager, t)
+                .unwrap()
+                .unwrap()
+                .row_format_version
+        })
+        .collect();
+    (version, formats)
+}
+
+fn stored_catalog_version(path: &Path) -> u32 {
+    let mut pager = Pager::open_plaintext(path).unwrap();
+    let catalog = SystemCatalog::open(pager.catalog_root());
+    catalog.catalog_version(&mut pager).unwrap()
+}
+
+fn set_catalog_version(path: &Path, version: u32) {
+    let mut pager = Pager::open_plaintext(path).unwrap();
+    let mut catalog = SystemCatalog::open(pager.catalog_root());
+    catalog.set_catalog_version(&mut pager, version).unwrap();
+    pager.set_catalog_root(catalog.root_page_id());
+    pager.flush_meta().unwrap();
+}
+
+fn count(db: &mut Database, sql: &str) -> usize {
+    db.query(sql).unwrap().len()
+}
+
+#[test]
+fn test_open_migrates_legacy_catalog_and_rows() {
+    let dir = TempDir::new().unwrap();
+    let path = dir.path().join("legacy.db");
+    create_legacy_fixture(&path);
+    assert_eq!(
+        stored_versions(&path),
+        (UNVERSIONED_CATALOG_VERSION, vec![0, 0])
+    );
+
+    {
+        let mut db = Database::open_plaintext(&path).unwrap();
+        assert_eq!(count(&mut db, "SELECT * FROM a"), 300);
+        assert_eq!(count(&mut db, "SELECT * FROM b WHERE score IS NULL"), 43);
+        let rows = db
+            .query("SELECT id, score FROM a WHERE name = 'name8'")
+            .unwrap();
+        assert_eq!(rows[0].get_at(0), Some(&Value::Integer(8)));
+        assert_eq!(rows[0].get_at(1), Some(&Value::Integer(8)));
+
+        // Rows now carry their column count, so short rows after ADD COLUMN
+        // read the default.
+        db.execute("ALTER TABLE a ADD COLUMN flag INT DEFAULT 5")
+            .unwrap();
+        let rows = db.query("SELECT flag FROM a WHERE id = 1").unwrap();
+        assert_eq!(rows[0].get_at(0), Some(&Value::Integer(5)));
+        for row in db.verify_integrity().unwrap() {
+            assert_ne!(
+                row.get("status"),
+                Some(&Value::Varchar("error".into())),
+                "{:?}",
+                row
+            );
+        }
+    }
+    assert_eq!(stored_versions(&path).0, CATALOG_VERSION);
+    assert_eq!(stored_versions(&path).1[1], 1);
+
+    // A second open has nothing left to do.
+    let mut db = Database::open_plaintext(&path).unwrap();
+    assert_eq!(count(&mut db, "SELECT * FROM b"), 300);
+}
+
+#[test]
+fn test_read_only_open_reads_legacy_catalog_without_migrating() {
+    let dir = TempDir::new().unwrap();
+    let path = dir.path().join("legacy.db");
+    create_legacy_fixture(&path);
+
+    let mut db = Database::open_plaintext_read_only(&path).unwrap();
+    assert_eq!(count(&mut db, "SELECT * FROM a WHERE name = 'name42'"), 1);
+    assert_eq!(count(&mut db, "SELECT * FROM b"), 300);
+    drop(db);
+    assert_eq!(
+        stored_versions(&path),
+        (UNVERSIONED_CATALOG_VERSION, vec![0, 0])
+    );
+}
+
+#[test]
+fn test_failed_migration_leaves_database_at_old_version() {
+    let dir = TempDir::new().unwrap();
+    let path = dir.path().join("legacy.db");
+    create_legacy_fixture(&path);
+
+    // Cut the rows of `b` down to their null bitmap. `a` is migrated first
+    // and succeeds, then `b` fails; the whole migration must roll back.
+    {
+        let mut pager = Pager::open_plaintext(&path).unwrap();
+        let catalog = SystemCatalog::open(pager.catalog_root());
+        let def = catalog.get_table(&mut pager, "b").unwrap().unwrap();
+        rewrite_rows(&mut pager, def.data_btree_root, |row| row[..1].to_vec());
+    }
+
+    assert!(Database::open_plaintext(&path).is_err());
+    assert_eq!(
+        stored_versions(&path),
+        (UNVERSIONED_CATALOG_VERSION, vec![0, 0])
+    );
+}
+
+#[test]
+fn test_newer_catalog_version_is_refused() {
+    let dir = TempDir::new().unwrap();
+    let path = dir.path().join("newer.db");
+    {
+        let mut db = Database::create_plaintext(&path).unwrap();
+        db.execute("CREATE TABLE t (id BIGINT PRIMARY KEY)")
+            .unwrap();
+    }
+    set_catalog_version(&path, CATALOG_VERSION + 1);
+
+    let expect_refused = |result: murodb::Result<Database>| match result {
+        Err(MuroError::IncompatibleVersion { found, supported }) => {
+            assert_eq!(found, CATALOG_VERSION + 1);
+            assert_eq!(supported, CATALOG_VERSION);
+        }
+        Err(other) => panic!("expected IncompatibleVersion, got {:?}", other),
+        Ok(_) => panic!("opened a catalog from a newer version"),
+    };
+    expect_refused(Database::open_plaintext(&path));
+    expect_refused(Database::open_plaintext_read_only(&path));
+
+    set_catalog_version(&path, CATALOG_VERSION);
+    let mut db = Database::open_plaintext(&path).unwrap();
+    db.execute("INSERT INTO t VALUES (1)").unwrap();
+}
+
+/// Create a version 2 database whose table `c` still stores its non-unique
+/// index in the concatenated layout: index key, then primary key.
+fn create_concat_index_fixture(path: &Path) {
+    {
+        let mut db = Database::create_plaintext(path).unwrap();
+        db.execute("CREATE TABLE c (k VARCHAR PRIMARY KEY, v VARCHAR)")
+            .unwrap();
+        db.execute("CREATE INDEX idx_c_v ON c (v)").unwrap();
+        for i in 0..200 {
+            db.execute(&format!("INSERT INTO c VALUES ('k{}', 'v{}')", i, i % 20))
+                .unwrap();
+        }
+    }
+
+    let mut pager = Pager::open_plaintext(path).unwrap();
+    let mut catalog = SystemCatalog::open(pager.catalog_root());
+    let mut def = catalog.get_table(&mut pager, "c").unwrap().unwrap();
+    let idx = catalog.get_index(&mut pager, "idx_c_v").unwrap().unwrap();
+    let mut btree = BTree::open(idx.btree_root);
+    let mut entries = Vec::new();
+    btree
+        .scan(&mut pager, |k, v| {
+            entries.push((k.to_vec(), v.to_vec()));
+            Ok(true)
+        })
+        .unwrap();
+    for (k, pk) in entries {
+        let (idx_key, _) = decode_byte_stuffed(&k).unwrap();
+        btree.delete(&mut pager, &k).unwrap();
+        btree
+            .insert(&mut pager, &[idx_key, pk.clone()].concat(), &pk)
+            .unwrap();
+    }
+    assert_eq!(
+        btree.root_page_id(),
+        idx.btree_root,
+        "fixture moved the root"
+    );
+    def.index_key_format = INDEX_KEY_FORMAT_CONCAT;
+    catalog.update_table(&mut pager, &def).unwrap();
+    catalog.set_catalog_version(&mut pager, 2).unwrap();
+    pager.set_catalog_root(catalog.root_page_id());
+    pager.flush_meta().unwrap();
+}
+
+fn index_key_format(path: &Path) -> u8 {
+    let mut pager = Pager::open_plaintext(path).unwrap();
+    let catalog = SystemCatalog::open(pager.catalog_root());
+    catalog
+        .get_table(&mut pager, "c")
+        .unwrap()
+        .unwrap()
+        .index_key_format
+}
+
+#[test]
+fn test_open_rebuilds_concatenated_non_unique_index() {
+    let dir = TempDir::new().unwrap();
+    let path = dir.path().join("concat.db");
+    create_concat_index_fixture(&path);
+
+    // A read-only open reads the old layout as it is.
+    {
+        let mut db = Database::open_plaintext_read_only(&path).unwrap();
+        assert_eq!(count(&mut db, "SELECT * FROM c WHERE v = 'v1'"), 10);
+        assert_eq!(
+            count(&mut db, "SELECT * FROM c WHERE v > 'v1' AND v < 'v2'"),
+            100
+        );
+    }
+    assert_eq!(index_key_format(&path), INDEX_KEY_FORMAT_CONCAT);
+
+    let mut db = Database::open_plaintext(&path).unwrap();
+    assert_eq!(index_key_format(&path), INDEX_KEY_FORMAT_TERMINATED);
+    assert_eq!(stored_catalog_version(&path), CATALOG_VERSION);
+    assert_eq!(count(&mut db, "SELECT * FROM c WHERE v = 'v1'"), 10);
+    assert_eq!(
+        count(&mut db, "SELECT * FROM c WHERE v > 'v1' AND v < 'v2'"),
+        100
+    );
+    for row in db.verify_integrity().unwrap() {
+        assert_eq!(
+            row.get("status"),
+            Some(&Value::Varchar("ok".into())),
+            "{:?}",
+            row
+        );
+    }
+    // ('a', 'bc') and ('ab', 'c') shared an entry key in the old layout.
+    db.execute("INSERT INTO c VALUES ('bc', 'a'), ('c', 'ab')")
+        .unwrap();
+    assert_eq!(count(&mut db, "SELECT * FROM c WHERE v = 'a'"), 1);
+    assert_eq!(count(&mut db, "SELECT * FROM c WHERE v = 'ab'"), 1);
+}
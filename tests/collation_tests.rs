@@ -0,0 +1,395 @@
+#![cfg(feature = "test-utils")]
+/// Column collations: `VARCHAR ... COLLATE nocase` compares, sorts, and keys
+/// its values case-insensitively, so filters, index seeks, and UNIQUE or
+/// PRIMARY KEY enforcement all agree; `expr COLLATE name` overrides it.
+use murodb::crypto::aead::MasterKey;
+use murodb::{Database, MuroError, Value};
+use tempfile::TempDir;
+
+fn test_key() -> MasterKey {
+    MasterKey::new([0x42u8; 32])
+}
+
+fn ids(db: &mut Database, sql: &str) -> Vec<i64> {
+    db.query(sql)
+        .unwrap()
+        .iter()
+        .map(|r| match r.get("id") {
+            Some(Value::Integer(n)) => *n,
+            other => panic!("unexpected id {:?}", other),
+        })
+        .collect()
+}
+
+fn names(db: &mut Database, sql: &str) -> Vec<String> {
+    db.query(sql)
+        .unwrap()
+        .iter()
+        .map(|r| match r.get("name") {
+            Some(Value::Varchar(s)) => s.clone(),
+            other => panic!("unexpected name {:?}", other),
+        })
+        .collect()
+}
+
+fn setup(db: &mut Database) {
+    db.execute_batch(
+        "CREATE TABLE users (id BIGINT PRIMARY KEY, name VARCHAR(50) COLLATE nocase, tag VARCHAR(20));
+         INSERT INTO users VALUES (1, 'alice', 'x'), (2, 'Bob', 'X'), (3, 'ÉMILE', 'y'), (4, 'carol', NULL);",
+    )
+    .unwrap();
+}
+
+#[test]
+fn test_nocase_filters_and_sorts() {
+    let dir = TempDir::new().unwrap();
+    let mut db = Database::create(&dir.path().join("test.db"), &test_key()).unwrap();
+    setup(&mut db);
+
+    assert_eq!(
+        ids(&mut db, "SELECT id FROM users WHERE name = 'ALICE'"),
+        [1]
+    );
+    assert_eq!(ids(&mut db, "SELECT id FROM users WHERE 'bob' = name"), [2]);
+    assert_eq!(
+        ids(&mut db, "SELECT id FROM users WHERE name = 'émile'"),
+        [3]
+    );
+    assert_eq!(
+        ids(
+            &mut db,
+            "SELECT id FROM users WHERE name IN ('BOB', 'Carol') ORDER BY id"
+        ),
+        [2, 4]
+    );
+    assert_eq!(
+        ids(
+            &mut db,
+            "SELECT id FROM users WHERE name BETWEEN 'B' AND 'c' ORDER BY id"
+        ),
+        [2]
+    );
+    assert_eq!(
+        ids(&mut db, "SELECT id FROM users WHERE name LIKE 'AL%'"),
+        [1]
+    );
+    assert_eq!(
+        ids(&mut db, "SELECT id FROM users WHERE name LIKE 'b_B'"),
+        [2]
+    );
+    // A binary column is unchanged.
+    assert_eq!(ids(&mut db, "SELECT id FROM users WHERE tag = 'x'"), [1]);
+
+    assert_eq!(
+        names(&mut db, "SELECT name FROM users ORDER BY name"),
+        ["alice", "Bob", "carol", "ÉMILE"]
+    );
+    assert_eq!(
+        ids(&mut db, "SELECT id FROM users ORDER BY name DESC LIMIT 2"),
+        [3, 4]
+    );
+    // An explicit collation wins over the column's.
+    assert_eq!(
+        names(
+            &mut db,
+            "SELECT name FROM users ORDER BY name COLLATE binary"
+        ),
+        ["Bob", "alice", "carol", "ÉMILE"]
+    );
+    assert!(ids(
+        &mut db,
+        "SELECT id FROM users WHERE name COLLATE binary = 'ALICE'"
+    )
+    .is_empty());
+    assert_eq!(
+        ids(
+            &mut db,
+            "SELECT id FROM users WHERE tag COLLATE nocase = 'x' ORDER BY id"
+        ),
+        [1, 2]
+    );
+
+    // UPDATE and DELETE find their rows the same way.
+    db.execute("UPDATE users SET tag = 'z' WHERE name = 'CAROL'")
+        .unwrap();
+    assert_eq!(ids(&mut db, "SELECT id FROM users WHERE tag = 'z'"), [4]);
+    db.execute("DELETE FROM users WHERE name LIKE 'É%'")
+        .unwrap();
+    assert_eq!(ids(&mut db, "SELECT id FROM users ORDER BY id"), [1, 2, 4]);
+}
+
+#[test]
+fn test_index_seek_agrees_with_scan() {
+    let dir = TempDir::new().unwrap();
+    let mut db = Database::create(&dir.path().join("test.db"), &test_key()).unwrap();
+    setup(&mut db);
+    let queries = [
+        "SELECT id FROM users WHERE name = 'BOB'",
+        "SELECT id FROM users WHERE name >= 'B' AND name < 'D' ORDER BY id",
+        "SELECT id FROM users WHERE name LIKE 'C%'",
+        "SELECT id FROM users WHERE name LIKE 'AZ%'",
+    ];
+    let scanned: Vec<Vec<i64>> = queries.iter().map(|q| ids(&mut db, q)).collect();
+    assert_eq!(scanned, [vec![2], vec![2, 4], vec![4], vec![]]);
+
+    db.execute("CREATE INDEX idx_users_name ON users (name)")
+        .unwrap();
+    let plan = db
+        .query("EXPLAIN SELECT id FROM users WHERE name = 'BOB'")
+        .unwrap();
+    assert!(
+        format!("{:?}", plan).contains("idx_users_name"),
+        "{:?}",
+        plan
+    );
+    for (query, expected) in queries.iter().zip(&scanned) {
+        assert_eq!(&ids(&mut db, query), expected, "{}", query);
+    }
+}
+
+#[test]
+fn test_unique_and_primary_key_ignore_case() {
+    let dir = TempDir::new().unwrap();
+    let mut db = Database::create(&dir.path().join("test.db"), &test_key()).unwrap();
+    db.execute_batch(
+        "CREATE TABLE accounts (email VARCHAR(100) COLLATE nocase PRIMARY KEY, handle TEXT COLLATE nocase UNIQUE);
+         INSERT INTO accounts VALUES ('Ann@Example.com', 'ann');",
+    )
+    .unwrap();
+
+    let err = db
+        .execute("INSERT INTO accounts VALUES ('ann@example.COM', 'other')")
+        .unwrap_err();
+    assert!(matches!(err, MuroError::UniqueViolation(_)), "{}", err);
+    let err = db
+        .execute("INSERT INTO accounts VALUES ('bo@example.com', 'ANN')")
+        .unwrap_err();
+    assert!(matches!(err, MuroError::UniqueViolation(_)), "{}", err);
+
+    // The stored value keeps its case; lookups by key ignore it.
+    let rows = db
+        .query("SELECT email FROM accounts WHERE email = 'ANN@EXAMPLE.COM'")
+        .unwrap();
+    assert_eq!(
+        rows[0].get("email"),
+        Some(&Value::Varchar("Ann@Example.com".into()))
+    );
+    db.execute("INSERT INTO accounts VALUES ('bo@example.com', 'bo')")
+        .unwrap();
+    let err = db
+        .execute("UPDATE accounts SET handle = 'Ann' WHERE email = 'BO@EXAMPLE.COM'")
+        .unwrap_err();
+    assert!(matches!(err, MuroError::UniqueViolation(_)), "{}", err);
+}
+
+#[test]
+fn test_alter_changes_collation_and_rekeys() {
+    let dir = TempDir::new().unwrap();
+    let mut db = Database::create(&dir.path().join("test.db"), &test_key()).unwrap();
+    db.execute_batch(
+        "CREATE TABLE words (w VARCHAR(20) PRIMARY KEY, note VARCHAR(20));
+         CREATE INDEX idx_words_note ON words (note);
+         INSERT INTO words VALUES ('apple', 'Red'), ('Apple', 'green'), ('pear', 'GREEN');",
+    )
+    .unwrap();
+
+    // Two keys would collide under nocase.
+    let err = db
+        .execute("ALTER TABLE words MODIFY COLUMN w VARCHAR(20) COLLATE nocase")
+        .unwrap_err();
+    assert!(matches!(err, MuroError::UniqueViolation(_)), "{}", err);
+    let rows = db.query("SELECT COUNT(*) FROM words").unwrap();
+    assert_eq!(rows[0].get("COUNT(*)"), Some(&Value::Integer(3)));
+
+    db.execute("ALTER TABLE words MODIFY COLUMN note VARCHAR(20) COLLATE nocase")
+        .unwrap();
+    let rows = db
+        .query("SELECT w FROM words WHERE note = 'green' ORDER BY w")
+        .unwrap();
+    assert_eq!(rows.len(), 2, "{:?}", rows);
+
+    db.execute("DELETE FROM words WHERE w = 'Apple'").unwrap();
+    db.execute("ALTER TABLE words MODIFY COLUMN w VARCHAR(20) COLLATE nocase")
+        .unwrap();
+    let rows = db
+        .query("SELECT note FROM words WHERE w = 'APPLE'")
+        .unwrap();
+    assert_eq!(rows[0].get("note"), Some(&Value::Varchar("Red".into())));
+    let rows = db.query("SELECT w FROM words WHERE note = 'red'").unwrap();
+    assert_eq!(rows[0].get("w"), Some(&Value::Varchar("apple".into())));
+
+    // Dropping the collation goes back to exact matches.
+    db.execute("ALTER TABLE words MODIFY COLUMN note VARCHAR(20)")
+        .unwrap();
+    assert!(db
+        .query("SELECT w FROM words WHERE note = 'red'")
+        .unwrap()
+        .is_empty());
+    for row in db.verify_integrity().unwrap() {
+        assert_ne!(
+            row.get("status"),
+            Some(&Value::Varchar("error".into())),
+            "{:?}",
+            row
+        );
+    }
+}
+
+#[test]
+fn test_collation_persists_and_is_shown() {
+    let dir = TempDir::new().unwrap();
+    let path = dir.path().join("test.db");
+    let mut db = Database::create(&path, &test_key()).unwrap();
+    setup(&mut db);
+    drop(db);
+
+    let mut db = Database::open(&path, &test_key()).unwrap();
+    assert_eq!(
+        ids(&mut db, "SELECT id FROM users WHERE name = 'ALICE'"),
+        [1]
+    );
+    let rows = db.query("SHOW CREATE TABLE users").unwrap();
+    let sql = format!("{:?}", rows[0]);
+    assert!(sql.contains("name VARCHAR(50) COLLATE nocase"), "{}", sql);
+    assert!(!sql.contains("tag VARCHAR(20) COLLATE"), "{}", sql);
+}
+
+#[test]
+fn test_collate_rejected_on_non_string_columns() {
+    let dir = TempDir::new().unwrap();
+    let mut db = Database::create(&dir.path().join("test.db"), &test_key()).unwrap();
+    let err = db
+        .execute("CREATE TABLE t (id BIGINT PRIMARY KEY, n INT COLLATE nocase)")
+        .unwrap_err();
+    assert!(matches!(err, MuroError::Schema(_)), "{}", err);
+    assert!(err.to_string().contains("only VARCHAR and TEXT"), "{}", err);
+    let err = db
+        .execute("CREATE TABLE t (id BIGINT PRIMARY KEY, s VARCHAR(5) COLLATE latin1_general)")
+        .unwrap_err();
+    assert!(err.to_string().contains("Unknown collation"), "{}", err);
+}
+
+#[test]
+fn test_join_uses_column_collation() {
+    let dir = TempDir::new().unwrap();
+    let mut db = Database::create(&dir.path().join("test.db"), &test_key()).unwrap();
+    setup(&mut db);
+    db.execute_batch(
+        "CREATE TABLE logins (id BIGINT PRIMARY KEY, user_id BIGINT);
+         INSERT INTO logins VALUES (10, 1), (11, 2), (12, 2);",
+    )
+    .unwrap();
+    let rows = db
+        .query(
+            "SELECT l.id FROM logins l JOIN users u ON l.user_id = u.id
+             WHERE u.name = 'BOB' ORDER BY l.id",
+        )
+        .unwrap();
+    assert_eq!(rows.len(), 2, "{:?}", rows);
+}
+
+#[test]
+fn test_join_on_uses_column_collation() {
+    let dir = TempDir::new().unwrap();
+    let mut db = Database::create(&dir.path().join("test.db"), &test_key()).unwrap();
+    setup(&mut db);
+    db.execute_batch(
+        "CREATE TABLE badges (id BIGINT PRIMARY KEY, holder VARCHAR(50));
+         INSERT INTO badges VALUES (20, 'ALICE'), (21, 'bob'), (22, 'dave');",
+    )
+    .unwrap();
+    // `u.name` is nocase, so it matches any spelling; `b.holder` is binary.
+    for sql in [
+        "SELECT b.id AS id FROM badges b JOIN users u ON u.name = b.holder ORDER BY b.id",
+        "SELECT b.id AS id FROM users u JOIN badges b ON u.name = b.holder ORDER BY b.id",
+        "SELECT b.id AS id FROM badges b LEFT JOIN users u ON u.name = b.holder
+         WHERE u.id IS NOT NULL ORDER BY b.id",
+        "SELECT b.id AS id FROM users u RIGHT JOIN badges b ON u.name = b.holder
+         WHERE u.id IS NOT NULL ORDER BY b.id",
+    ] {
+        assert_eq!(ids(&mut db, sql), [20, 21], "{}", sql);
+    }
+    assert!(ids(
+        &mut db,
+        "SELECT b.id AS id FROM badges b JOIN users u ON b.holder = u.name COLLATE binary"
+    )
+    .is_empty());
+}
+
+#[test]
+fn test_grouping_and_distinct_use_column_collation() {
+    let dir = TempDir::new().unwrap();
+    let mut db = Database::create(&dir.path().join("test.db"), &test_key()).unwrap();
+    db.execute_batch(
+        "CREATE TABLE tags (id BIGINT PRIMARY KEY, name VARCHAR(20) COLLATE nocase, raw VARCHAR(20));
+         INSERT INTO tags VALUES (1, 'Rust', 'Rust'), (2, 'rust', 'rust'), (3, 'RUST', 'RUST'),
+                                 (4, 'go', 'go'), (5, 'Zig', 'Zig'), (6, 'apple', 'apple');",
+    )
+    .unwrap();
+    let count = |db: &mut Database, sql: &str| match db.query(sql).unwrap()[0].get("n") {
+        Some(Value::Integer(n)) => *n,
+        other => panic!("unexpected count {:?}", other),
+    };
+
+    let rows = db
+        .query("SELECT name, COUNT(*) AS n FROM tags GROUP BY name")
+        .unwrap();
+    assert_eq!(rows.len(), 4, "{:?}", rows);
+    assert_eq!(rows[0].get("n"), Some(&Value::Integer(3)));
+    assert_eq!(
+        db.query("SELECT raw FROM tags GROUP BY raw").unwrap().len(),
+        6
+    );
+    assert_eq!(
+        db.query("SELECT name FROM tags GROUP BY name COLLATE binary")
+            .unwrap()
+            .len(),
+        6
+    );
+
+    assert_eq!(names(&mut db, "SELECT DISTINCT name FROM tags").len(), 4);
+    assert_eq!(db.query("SELECT DISTINCT raw FROM tags").unwrap().len(), 6);
+    assert_eq!(
+        count(&mut db, "SELECT COUNT(DISTINCT name) AS n FROM tags"),
+        4
+    );
+    assert_eq!(
+        count(&mut db, "SELECT COUNT(DISTINCT raw) AS n FROM tags"),
+        6
+    );
+
+    // Binary order puts 'RUST' and 'Zig' before 'apple'.
+    let rows = db
+        .query("SELECT MIN(name) AS lo, MAX(name) AS hi, MIN(raw) AS raw_lo FROM tags")
+        .unwrap();
+    assert_eq!(rows[0].get("lo"), Some(&Value::Varchar("apple".into())));
+    assert_eq!(rows[0].get("hi"), Some(&Value::Varchar("Zig".into())));
+    assert_eq!(rows[0].get("raw_lo"), Some(&Value::Varchar("RUST".into())));
+
+    // The same holds across a join.
+    db.execute_batch(
+        "CREATE TABLE posts (id BIGINT PRIMARY KEY, tag_id BIGINT);
+         INSERT INTO posts VALUES (10, 1), (11, 2), (12, 3), (13, 4);",
+    )
+    .unwrap();
+    let rows = db
+        .query(
+            "SELECT t.name, COUNT(*) AS n FROM posts p JOIN tags t ON p.tag_id = t.id
+             GROUP BY t.name",
+        )
+        .unwrap();
+    assert_eq!(rows.len(), 2, "{:?}", rows);
+    assert_eq!(
+        db.query("SELECT DISTINCT t.name FROM posts p JOIN tags t ON p.tag_id = t.id")
+            .unwrap()
+            .len(),
+        2
+    );
+    assert_eq!(
+        count(
+            &mut db,
+            "SELECT COUNT(DISTINCT t.name) AS n FROM posts p JOIN tags t ON p.tag_id = t.id"
+        ),
+        2
+    );
+}
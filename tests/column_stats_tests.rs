@@ -0,0 +1,187 @@
+#![cfg(feature = "test-utils")]
+/// Per-column ANALYZE statistics and the selectivity-based plan choice.
+use murodb::crypto::aead::MasterKey;
+use murodb::schema::catalog::SystemCatalog;
+use murodb::{Database, ExecResult, Session, Value};
+use std::path::Path;
+use tempfile::TempDir;
+
+fn test_key() -> MasterKey {
+    MasterKey::new([0x42u8; 32])
+}
+
+/// `a` has two values, `b` one value per row, `c` is one value or NULL.
+fn setup(db_path: &Path, rows: i64) -> Session {
+    let mut session = Database::create(db_path, &test_key())
+        .unwrap()
+        .into_session();
+    session
+        .execute("CREATE TABLE t (id BIGINT PRIMARY KEY, a INT, b INT, c INT)")
+        .unwrap();
+    for idx in ["idx_a ON t(a)", "idx_b ON t(b)", "idx_c ON t(c)"] {
+        session.execute(&format!("CREATE INDEX {}", idx)).unwrap();
+    }
+    insert_rows(&mut session, 1..=rows);
+    session
+}
+
+fn insert_rows(session: &mut Session, ids: std::ops::RangeInclusive<i64>) {
+    session.execute("BEGIN").unwrap();
+    for i in ids {
+        let c = if i % 4 == 0 { "NULL" } else { "7" };
+        session
+            .execute(&format!(
+                "INSERT INTO t VALUES ({}, {}, {}, {})",
+                i,
+                i % 2,
+                i,
+                c
+            ))
+            .unwrap();
+    }
+    session.execute("COMMIT").unwrap();
+}
+
+/// (type, key, rows, Extra) of the EXPLAIN row.
+fn explain(session: &mut Session, sql: &str) -> (String, Option<String>, i64, String) {
+    match session.execute(&format!("EXPLAIN {}", sql)).unwrap() {
+        ExecResult::Rows(rows) => {
+            let row = &rows[0];
+            let text = |name: &str| match row.get(name) {
+                Some(Value::Varchar(s)) => Some(s.clone()),
+                _ => None,
+            };
+            let est = match row.get("rows") {
+                Some(Value::Integer(n)) => *n,
+                other => panic!("unexpected rows {:?}", other),
+            };
+            (
+                text("type").unwrap(),
+                text("key"),
+                est,
+                text("Extra").unwrap_or_default(),
+            )
+        }
+        other => panic!("expected rows, got {:?}", other),
+    }
+}
+
+#[test]
+fn test_analyze_persists_column_stats() {
+    let dir = TempDir::new().unwrap();
+    let db_path = dir.path().join("test.db");
+    let mut session = setup(&db_path, 100);
+    session.execute("ANALYZE TABLE t").unwrap();
+    drop(session);
+
+    let mut session = Database::open(&db_path, &test_key())
+        .unwrap()
+        .into_session();
+    let catalog = SystemCatalog::open(session.catalog().root_page_id());
+    let table = catalog
+        .get_table(session.pager_mut(), "t")
+        .unwrap()
+        .unwrap();
+    let a = table.column_stats("a").unwrap();
+    assert_eq!((a.distinct_count, a.null_count), (2, 0));
+    assert_eq!((a.min, a.max), (Some(0), Some(1)));
+    let b = table.column_stats("b").unwrap();
+    assert_eq!(b.distinct_count, 100);
+    assert_eq!((b.min, b.max), (Some(1), Some(100)));
+    let c = table.column_stats("c").unwrap();
+    assert_eq!((c.distinct_count, c.null_count), (1, 25));
+
+    // Changing a column's definition forgets its stats.
+    session
+        .execute("ALTER TABLE t MODIFY COLUMN b BIGINT")
+        .unwrap();
+    let table = catalog
+        .get_table(session.pager_mut(), "t")
+        .unwrap()
+        .unwrap();
+    assert!(table.column_stats("b").is_none());
+    assert!(table.column_stats("a").is_some());
+}
+
+#[test]
+fn test_planner_ranks_indexes_by_selectivity() {
+    let dir = TempDir::new().unwrap();
+    let mut session = setup(&dir.path().join("test.db"), 200);
+    session.execute("ANALYZE TABLE t").unwrap();
+
+    let (access, key, rows, extra) =
+        explain(&mut session, "SELECT id FROM t WHERE a = 1 AND b = 9");
+    assert_eq!(access, "ref");
+    assert_eq!(key.as_deref(), Some("idx_b"));
+    assert_eq!(rows, 1);
+    assert!(
+        extra.contains("Estimates: idx_a ref=100, idx_b ref=1, ALL=200"),
+        "{}",
+        extra
+    );
+
+    // A literal outside the analyzed min/max matches next to nothing.
+    let (access, key, rows, _) = explain(&mut session, "SELECT id FROM t WHERE a = 5");
+    assert_eq!(
+        (access.as_str(), key.as_deref(), rows),
+        ("ref", Some("idx_a"), 1)
+    );
+}
+
+#[test]
+fn test_low_selectivity_predicate_falls_back_to_full_scan() {
+    let dir = TempDir::new().unwrap();
+    let mut session = setup(&dir.path().join("test.db"), 200);
+
+    // Without stats the index is still preferred.
+    let (access, ..) = explain(&mut session, "SELECT id FROM t WHERE c = 7");
+    assert_eq!(access, "ref");
+
+    // NULLs do not count toward the equality estimate: 150 of 200 rows.
+    session.execute("ANALYZE TABLE t").unwrap();
+    let (access, key, _, extra) = explain(&mut session, "SELECT id FROM t WHERE c = 7");
+    assert_eq!((access.as_str(), key), ("ALL", None));
+    assert!(extra.contains("idx_c ref=150, ALL=200"), "{}", extra);
+    let (access, ..) = explain(&mut session, "SELECT id FROM t WHERE b > 10");
+    assert_eq!(access, "ALL");
+    let (access, ..) = explain(&mut session, "SELECT id FROM t WHERE b > 190");
+    assert_eq!(access, "range");
+
+    // FORCE INDEX still wins, and both plans return the same rows.
+    let forced = "SELECT COUNT(*) AS n FROM t FORCE INDEX (idx_c) WHERE c = 7";
+    let (access, ..) = explain(&mut session, forced);
+    assert_eq!(access, "ref");
+    for sql in [forced, "SELECT COUNT(*) AS n FROM t WHERE c = 7"] {
+        match session.execute(sql).unwrap() {
+            ExecResult::Rows(rows) => assert_eq!(rows[0].get("n"), Some(&Value::Integer(150))),
+            other => panic!("expected rows, got {:?}", other),
+        }
+    }
+
+    // UPDATE and DELETE plan the same way.
+    let (access, ..) = explain(&mut session, "DELETE FROM t WHERE c = 7");
+    assert_eq!(access, "ALL");
+}
+
+#[test]
+fn test_stale_stats_are_capped() {
+    let dir = TempDir::new().unwrap();
+    let mut session = setup(&dir.path().join("test.db"), 40);
+    session.execute("ANALYZE TABLE t").unwrap();
+    let (access, _, _, extra) = explain(&mut session, "SELECT id FROM t WHERE b > 2");
+    assert_eq!(access, "ALL");
+    assert!(!extra.contains("Stale stats"), "{}", extra);
+
+    // After 25x growth the analyzed distribution no longer describes the
+    // table: the live row count is used and the index plan is kept.
+    insert_rows(&mut session, 41..=1000);
+    let (access, key, rows, extra) = explain(&mut session, "SELECT id FROM t WHERE b > 2");
+    assert_eq!((access.as_str(), key.as_deref()), ("range", Some("idx_b")));
+    assert!(rows > 40, "{}", rows);
+    assert!(extra.contains("Stale stats: analyzed 40 rows"), "{}", extra);
+
+    session.execute("ANALYZE TABLE t").unwrap();
+    let (access, _, _, extra) = explain(&mut session, "SELECT id FROM t WHERE b > 2");
+    assert_eq!(access, "ALL");
+    assert!(!extra.contains("Stale stats"), "{}", extra);
+}
@@ -0,0 +1,153 @@
+#![cfg(feature = "test-utils")]
+/// A commit that fails with CommitInDoubt is resolved by recovery at the next
+/// open; the application looks the verdict up by commit tag or txid.
+use murodb::crypto::aead::MasterKey;
+use murodb::error::MuroError;
+use murodb::sql::session::Session;
+use murodb::{CommitOutcome, Database, Value};
+use std::path::Path;
+use tempfile::TempDir;
+
+fn test_key() -> MasterKey {
+    MasterKey::new([0x42u8; 32])
+}
+
+fn setup(db_path: &Path) -> Session {
+    let mut session = Database::create(db_path, &test_key())
+        .unwrap()
+        .into_session();
+    session
+        .execute("CREATE TABLE t (id BIGINT PRIMARY KEY)")
+        .unwrap();
+    session
+}
+
+/// Run `sql` with `tag` so that its commit reaches the WAL but not the data
+/// file, then drop the session like a crashed process would.
+fn commit_in_doubt(mut session: Session, tag: &str, sql: &str) -> u64 {
+    session.set_commit_tag(tag).unwrap();
+    session
+        .pager_mut()
+        .set_inject_write_page_failure(Some(std::io::ErrorKind::Other));
+    let err = match session.execute(sql) {
+        Err(e @ MuroError::CommitInDoubt(_)) => e.to_string(),
+        other => panic!("expected CommitInDoubt, got {:?}", other),
+    };
+    drop(session);
+    let txid = err
+        .split("txid ")
+        .nth(1)
+        .and_then(|rest| rest.split(':').next())
+        .expect("error names the txid");
+    txid.parse().unwrap()
+}
+
+fn count(db: &mut Database) -> i64 {
+    match db.query("SELECT COUNT(*) FROM t").unwrap()[0].values[0].1 {
+        Value::Integer(n) => n,
+        ref other => panic!("unexpected value {:?}", other),
+    }
+}
+
+#[test]
+fn test_in_doubt_commit_resolved_as_committed() {
+    let dir = TempDir::new().unwrap();
+    let db_path = dir.path().join("test.db");
+    let session = setup(&db_path);
+    let txid = commit_in_doubt(session, "order-1", "INSERT INTO t VALUES (1)");
+
+    let mut db = Database::open(&db_path, &test_key()).unwrap();
+    assert_eq!(count(&mut db), 1);
+    assert_eq!(db.commit_outcome("order-1"), Some(CommitOutcome::Committed));
+    assert_eq!(db.commit_outcome(txid), Some(CommitOutcome::Committed));
+}
+
+#[test]
+fn test_in_doubt_commit_resolved_as_rolled_back() {
+    let dir = TempDir::new().unwrap();
+    let db_path = dir.path().join("test.db");
+    let mut session = setup(&db_path);
+    session.execute("BEGIN").unwrap();
+    session.execute("INSERT INTO t VALUES (1)").unwrap();
+    let txid = commit_in_doubt(session, "order-1", "COMMIT");
+
+    // The WAL never made it to stable storage after all.
+    std::fs::remove_file(dir.path().join("test.db.wal")).unwrap();
+
+    let mut db = Database::open(&db_path, &test_key()).unwrap();
+    assert_eq!(count(&mut db), 0);
+    assert_eq!(
+        db.commit_outcome("order-1"),
+        Some(CommitOutcome::RolledBack)
+    );
+    assert_eq!(db.commit_outcome(txid), Some(CommitOutcome::RolledBack));
+}
+
+#[test]
+fn test_outcomes_survive_later_opens() {
+    let dir = TempDir::new().unwrap();
+    let db_path = dir.path().join("test.db");
+    let session = setup(&db_path);
+    commit_in_doubt(session, "order-1", "INSERT INTO t VALUES (1)");
+    drop(Database::open(&db_path, &test_key()).unwrap());
+
+    let session = Database::open(&db_path, &test_key())
+        .unwrap()
+        .into_session();
+    commit_in_doubt(session, "order-2", "INSERT INTO t VALUES (2)");
+
+    let mut db = Database::open(&db_path, &test_key()).unwrap();
+    assert_eq!(count(&mut db), 2);
+    assert_eq!(db.commit_outcome("order-1"), Some(CommitOutcome::Committed));
+    assert_eq!(db.commit_outcome("order-2"), Some(CommitOutcome::Committed));
+}
+
+#[test]
+fn test_unknown_and_successful_commits_have_no_outcome() {
+    let dir = TempDir::new().unwrap();
+    let db_path = dir.path().join("test.db");
+    let mut db = Database::create(&db_path, &test_key()).unwrap();
+    db.execute("CREATE TABLE t (id BIGINT PRIMARY KEY)")
+        .unwrap();
+    db.set_commit_tag("ok-1").unwrap();
+    db.execute("INSERT INTO t VALUES (1)").unwrap();
+    drop(db);
+
+    let db = Database::open(&db_path, &test_key()).unwrap();
+    assert_eq!(db.commit_outcome("ok-1"), None);
+    assert_eq!(db.commit_outcome("never-used"), None);
+    assert_eq!(db.commit_outcome(12345u64), None);
+}
+
+#[test]
+fn test_tag_is_kept_across_reads_and_cleared_by_commit() {
+    let dir = TempDir::new().unwrap();
+    let db_path = dir.path().join("test.db");
+    let mut session = setup(&db_path);
+    session.set_commit_tag("order-1").unwrap();
+    session.execute("SELECT * FROM t").unwrap();
+    session.execute("INSERT INTO t VALUES (1)").unwrap();
+
+    // The tag went with the INSERT; this in-doubt commit is untagged.
+    session
+        .pager_mut()
+        .set_inject_flush_meta_failure(Some(std::io::ErrorKind::Other));
+    assert!(matches!(
+        session.execute("INSERT INTO t VALUES (2)"),
+        Err(MuroError::CommitInDoubt(_))
+    ));
+    drop(session);
+
+    let db = Database::open(&db_path, &test_key()).unwrap();
+    assert_eq!(db.commit_outcome("order-1"), None);
+}
+
+#[test]
+fn test_invalid_tags_rejected() {
+    let dir = TempDir::new().unwrap();
+    let mut db = Database::create(&dir.path().join("test.db"), &test_key()).unwrap();
+    assert!(db.set_commit_tag("").is_err());
+    assert!(db.set_commit_tag("a\tb").is_err());
+    assert!(db.set_commit_tag(&"x".repeat(256)).is_err());
+    assert!(db.set_commit_tag("order-1").is_ok());
+}
@@ -1042,3 +1042,171 @@ fn test_create_index_on_existing_data_with_duplicates() {
     ));
     assert_eq!(rows.len(), 2, "Should find both rows with a=10 via index");
 }
+
+// --- Keys crafted to collide under plain concatenation ---
+
+fn column(rows: Vec<Vec<(String, Value)>>) -> Vec<Value> {
+    rows.into_iter().map(|r| r[0].1.clone()).collect()
+}
+
+#[test]
+fn test_non_unique_index_entries_do_not_collide_across_primary_keys() {
+    let (mut pager, mut catalog, _dir) = setup();
+
+    // ('a', 'bc') and ('ab', 'c') concatenate to the same bytes.
+    for table in ["t", "u"] {
+        exec(
+            &format!("CREATE TABLE {} (k VARCHAR PRIMARY KEY, v VARCHAR)", table),
+            &mut pager,
+            &mut catalog,
+        );
+        exec(
+            &format!("CREATE INDEX idx_{0}_v ON {0} (v)", table),
+            &mut pager,
+            &mut catalog,
+        );
+    }
+    exec("INSERT INTO t VALUES ('bc', 'a')", &mut pager, &mut catalog);
+    exec("INSERT INTO t VALUES ('c', 'ab')", &mut pager, &mut catalog);
+    // Bulk load into an empty table.
+    exec(
+        "INSERT INTO u VALUES ('bc', 'a'), ('c', 'ab')",
+        &mut pager,
+        &mut catalog,
+    );
+
+    for table in ["t", "u"] {
+        let select = |pager: &mut Pager, catalog: &mut SystemCatalog, cond: &str| {
+            column(get_rows(exec(
+                &format!("SELECT k FROM {} WHERE {} ORDER BY k", table, cond),
+                pager,
+                catalog,
+            )))
+        };
+        assert_eq!(
+            select(&mut pager, &mut catalog, "v = 'a'"),
+            vec![Value::Varchar("bc".into())]
+        );
+        assert_eq!(
+            select(&mut pager, &mut catalog, "v = 'ab'"),
+            vec![Value::Varchar("c".into())]
+        );
+        assert_eq!(select(&mut pager, &mut catalog, "v >= 'a'").len(), 2);
+
+        exec(
+            &format!("DELETE FROM {} WHERE k = 'c'", table),
+            &mut pager,
+            &mut catalog,
+        );
+        assert_eq!(
+            select(&mut pager, &mut catalog, "v = 'a'"),
+            vec![Value::Varchar("bc".into())]
+        );
+        assert!(select(&mut pager, &mut catalog, "v = 'ab'").is_empty());
+    }
+}
+
+#[test]
+fn test_min_max_over_non_unique_varchar_index() {
+    let (mut pager, mut catalog, _dir) = setup();
+    exec(
+        "CREATE TABLE t (id BIGINT PRIMARY KEY, v VARCHAR)",
+        &mut pager,
+        &mut catalog,
+    );
+    exec("CREATE INDEX idx_v ON t (v)", &mut pager, &mut catalog);
+    // The encoded primary key 2 starts with 0x80, above 'b': appended
+    // straight to the value it would sort ('a', 2) after ('ab', 1).
+    exec(
+        "INSERT INTO t VALUES (1, 'ab'), (2, 'a')",
+        &mut pager,
+        &mut catalog,
+    );
+
+    // One aggregate per query: each reads only the row its probe found.
+    for (sql, expected) in [
+        ("SELECT MIN(v) FROM t", "a"),
+        ("SELECT MAX(v) FROM t", "ab"),
+    ] {
+        let rows = get_rows(exec(sql, &mut pager, &mut catalog));
+        assert_eq!(rows[0][0].1, Value::Varchar(expected.into()), "{}", sql);
+    }
+    let rows = get_rows(exec(
+        "SELECT id FROM t WHERE v > 'a' AND v <= 'ab'",
+        &mut pager,
+        &mut catalog,
+    ));
+    assert_eq!(column(rows), vec![Value::Integer(1)]);
+}
+
+#[test]
+fn test_composite_pk_with_escape_bytes_in_variable_length_part() {
+    let (mut pager, mut catalog, _dir) = setup();
+    exec(
+        "CREATE TABLE t (tenant VARBINARY, id INT, v VARCHAR, PRIMARY KEY (tenant, id))",
+        &mut pager,
+        &mut catalog,
+    );
+    exec(
+        "CREATE INDEX idx_tv ON t (tenant, v)",
+        &mut pager,
+        &mut catalog,
+    );
+
+    // Tenants holding the escape (00 01) and terminator (00 00) bytes, and
+    // prefixes of each other. Listed in key order.
+    let tenants = ["", "61", "6100", "610000", "610001", "6101", "6162", "62"];
+    for (i, tenant) in tenants.iter().enumerate().rev() {
+        for id in [1, 2] {
+            exec(
+                &format!("INSERT INTO t VALUES (X'{}', {}, 'v{}')", tenant, id, i),
+                &mut pager,
+                &mut catalog,
+            );
+        }
+    }
+
+    let rows = get_rows(exec(
+        "SELECT tenant, id FROM t ORDER BY tenant, id",
+        &mut pager,
+        &mut catalog,
+    ));
+    assert_eq!(rows.len(), tenants.len() * 2);
+    for (i, tenant) in tenants.iter().enumerate() {
+        let bytes: Vec<u8> = (0..tenant.len())
+            .step_by(2)
+            .map(|j| u8::from_str_radix(&tenant[j..j + 2], 16).unwrap())
+            .collect();
+        assert_eq!(
+            rows[2 * i][0].1,
+            Value::Varbinary(bytes),
+            "tenant {}",
+            tenant
+        );
+    }
+
+    for (i, tenant) in tenants.iter().enumerate() {
+        let rows = get_rows(exec(
+            &format!("SELECT v FROM t WHERE tenant = X'{}' AND id = 2", tenant),
+            &mut pager,
+            &mut catalog,
+        ));
+        assert_eq!(column(rows), vec![Value::Varchar(format!("v{}", i))]);
+        let rows = get_rows(exec(
+            &format!(
+                "SELECT id FROM t WHERE tenant = X'{}' AND v = 'v{}' ORDER BY id",
+                tenant, i
+            ),
+            &mut pager,
+            &mut catalog,
+        ));
+        assert_eq!(column(rows), vec![Value::Integer(1), Value::Integer(2)]);
+    }
+
+    let rows = get_rows(exec(
+        "SELECT COUNT(*) FROM t WHERE tenant > X'61' AND tenant < X'62'",
+        &mut pager,
+        &mut catalog,
+    ));
+    assert_eq!(rows[0][0].1, Value::Integer(10));
+}
@@ -0,0 +1,102 @@
+#![cfg(feature = "test-utils")]
+/// Queries take the shared lock: a `Database` and readers from
+/// `open_reader`, each on its own thread, run SELECTs at the same time, while
+/// a write waits for them.
+use murodb::crypto::aead::MasterKey;
+use murodb::{Database, MuroError, QueryCancelHandle, Value};
+use std::sync::{Arc, Barrier};
+use std::thread;
+use std::time::{Duration, Instant};
+use tempfile::TempDir;
+
+const READERS: usize = 4;
+/// Runs until cancelled: a billion-row cross join.
+const LONG_QUERY: &str = "SELECT COUNT(*) FROM t a CROSS JOIN t b CROSS JOIN t c";
+
+fn test_key() -> MasterKey {
+    MasterKey::new([0x42u8; 32])
+}
+
+fn setup(dir: &TempDir) -> Database {
+    let mut db = Database::create(&dir.path().join("test.db"), &test_key()).unwrap();
+    db.execute("CREATE TABLE t (id BIGINT PRIMARY KEY)")
+        .unwrap();
+    let values: Vec<String> = (0..1000).map(|i| format!("({})", i)).collect();
+    db.execute(&format!("INSERT INTO t VALUES {}", values.join(", ")))
+        .unwrap();
+    db
+}
+
+/// Wait until every handle is running a statement at the same moment.
+fn wait_all_running(handles: &[QueryCancelHandle]) {
+    let deadline = Instant::now() + Duration::from_secs(30);
+    while !handles.iter().all(QueryCancelHandle::is_running) {
+        assert!(
+            Instant::now() < deadline,
+            "queries never ran at the same time"
+        );
+        thread::sleep(Duration::from_millis(1));
+    }
+}
+
+#[test]
+fn test_queries_on_separate_handles_overlap() {
+    let dir = TempDir::new().unwrap();
+    let db = setup(&dir);
+    let mut readers: Vec<_> = (0..READERS).map(|_| db.open_reader().unwrap()).collect();
+    let mut cancel_handles: Vec<_> = readers.iter().map(|r| r.cancel_handle()).collect();
+    cancel_handles.push(db.cancel_handle());
+
+    let start = Arc::new(Barrier::new(READERS + 1));
+    let mut threads: Vec<_> = readers
+        .drain(..)
+        .map(|mut reader| {
+            let start = Arc::clone(&start);
+            thread::spawn(move || {
+                start.wait();
+                reader.query(LONG_QUERY).map(|_| ())
+            })
+        })
+        .collect();
+    threads.push(thread::spawn({
+        let mut db = db;
+        move || {
+            start.wait();
+            db.query(LONG_QUERY).map(|_| ())
+        }
+    }));
+
+    // Every handle holds the shared lock for the whole statement, so all of
+    // them running at once means none waited for another.
+    wait_all_running(&cancel_handles);
+    for handle in &cancel_handles {
+        handle.cancel();
+    }
+    for t in threads {
+        assert!(matches!(t.join().unwrap(), Err(MuroError::Cancelled)));
+    }
+}
+
+#[test]
+fn test_write_waits_for_running_query() {
+    let dir = TempDir::new().unwrap();
+    let mut db = setup(&dir);
+    let mut reader = db.open_reader().unwrap();
+    let reader_cancel = reader.cancel_handle();
+
+    let query = thread::spawn(move || reader.query(LONG_QUERY).map(|_| ()));
+    wait_all_running(std::slice::from_ref(&reader_cancel));
+
+    db.set_busy_timeout_ms(50);
+    assert!(matches!(
+        db.execute("INSERT INTO t VALUES (1000)"),
+        Err(MuroError::LockTimeout { .. })
+    ));
+    // A query on the writing handle still runs beside the reader.
+    let rows = db.query("SELECT COUNT(*) FROM t").unwrap();
+    assert_eq!(rows[0].get_at(0), Some(&Value::Integer(1000)));
+
+    reader_cancel.cancel();
+    assert!(matches!(query.join().unwrap(), Err(MuroError::Cancelled)));
+    db.execute("INSERT INTO t VALUES (1000)").unwrap();
+}
@@ -0,0 +1,158 @@
+#![cfg(feature = "test-utils")]
+/// Column constraints (NOT NULL, type ranges, CHECK) on every write path,
+/// not just INSERT. A statement that violates one fails as a whole: the
+/// rows it already rewrote and their index entries are restored.
+use murodb::{Database, Value};
+use tempfile::TempDir;
+
+fn setup() -> (Database, TempDir) {
+    let dir = TempDir::new().unwrap();
+    let mut db = Database::create_plaintext(&dir.path().join("test.db")).unwrap();
+    db.execute(
+        "CREATE TABLE users (
+            id BIGINT PRIMARY KEY,
+            name VARCHAR NOT NULL,
+            age INT CHECK (age >= 0),
+            level TINYINT
+        )",
+    )
+    .unwrap();
+    db.execute("CREATE INDEX idx_users_age ON users (age)")
+        .unwrap();
+    db.execute("CREATE INDEX idx_users_name ON users (name)")
+        .unwrap();
+    for i in 1..=5 {
+        db.execute(&format!(
+            "INSERT INTO users VALUES ({0}, 'user{0}', {1}, 1)",
+            i,
+            i * 10
+        ))
+        .unwrap();
+    }
+    (db, dir)
+}
+
+fn err(db: &mut Database, sql: &str) -> String {
+    db.execute(sql).unwrap_err().to_string()
+}
+
+/// Every row's `(id, name, age)`, then the ids found through each index.
+fn snapshot(db: &mut Database) -> Vec<Vec<Value>> {
+    let mut rows = Vec::new();
+    for sql in [
+        "SELECT id, name, age FROM users ORDER BY id",
+        "SELECT id FROM users FORCE INDEX (idx_users_age) WHERE age >= 0 ORDER BY id",
+        "SELECT id FROM users FORCE INDEX (idx_users_name) WHERE name >= 'user' ORDER BY id",
+    ] {
+        for row in db.query(sql).unwrap() {
+            rows.push(row.values.into_iter().map(|(_, v)| v).collect());
+        }
+    }
+    rows
+}
+
+#[test]
+fn test_update_violating_check_fails_and_names_the_constraint() {
+    let (mut db, _dir) = setup();
+    let before = snapshot(&mut db);
+
+    let msg = err(&mut db, "UPDATE users SET age = -5 WHERE id = 3");
+    assert!(msg.contains("CHECK constraint failed"), "{}", msg);
+    assert!(msg.contains("'age'"), "{}", msg);
+    assert!(msg.contains("age >= 0"), "{}", msg);
+    assert_eq!(snapshot(&mut db), before);
+
+    // NULL passes CHECK.
+    db.execute("UPDATE users SET age = NULL WHERE id = 3")
+        .unwrap();
+}
+
+#[test]
+fn test_update_to_null_in_not_null_column_fails() {
+    let (mut db, _dir) = setup();
+    let before = snapshot(&mut db);
+
+    let msg = err(&mut db, "UPDATE users SET name = NULL WHERE id = 2");
+    assert!(msg.contains("Column 'name' cannot be NULL"), "{}", msg);
+    assert_eq!(snapshot(&mut db), before);
+}
+
+#[test]
+fn test_update_out_of_range_names_the_column() {
+    let (mut db, _dir) = setup();
+    let msg = err(&mut db, "UPDATE users SET level = level + 200");
+    assert!(msg.contains("out of range for TINYINT"), "{}", msg);
+    assert!(msg.contains("column 'level'"), "{}", msg);
+    assert_eq!(
+        db.query("SELECT * FROM users WHERE level = 1")
+            .unwrap()
+            .len(),
+        5
+    );
+}
+
+#[test]
+fn test_multi_row_update_is_atomic_inside_a_transaction() {
+    let (mut db, _dir) = setup();
+    db.execute("BEGIN").unwrap();
+    db.execute("UPDATE users SET name = 'renamed' WHERE id = 1")
+        .unwrap();
+    let before = snapshot(&mut db);
+
+    // Rows 1..=2 pass, row 3 fails: the statement must not leave 1 and 2
+    // rewritten or their index entries moved.
+    let msg = err(&mut db, "UPDATE users SET age = 25 - age ORDER BY id");
+    assert!(msg.contains("CHECK constraint failed"), "{}", msg);
+    assert_eq!(snapshot(&mut db), before);
+
+    // The transaction stays usable with the earlier statement's change.
+    db.execute("COMMIT").unwrap();
+    let rows = db.query("SELECT name FROM users WHERE id = 1").unwrap();
+    assert_eq!(rows[0].get("name"), Some(&Value::Varchar("renamed".into())));
+    for row in db.verify_integrity().unwrap() {
+        assert_ne!(
+            row.get("status"),
+            Some(&Value::Varchar("error".into())),
+            "{:?}",
+            row
+        );
+    }
+}
+
+#[test]
+fn test_on_duplicate_key_update_checks_constraints() {
+    let (mut db, _dir) = setup();
+    let before = snapshot(&mut db);
+
+    let msg = err(
+        &mut db,
+        "INSERT INTO users VALUES (1, 'x', 1, 1) ON DUPLICATE KEY UPDATE age = -1",
+    );
+    assert!(msg.contains("CHECK constraint failed"), "{}", msg);
+    let msg = err(
+        &mut db,
+        "INSERT INTO users VALUES (1, 'x', 1, 1) ON DUPLICATE KEY UPDATE name = NULL",
+    );
+    assert!(msg.contains("Column 'name' cannot be NULL"), "{}", msg);
+    assert_eq!(snapshot(&mut db), before);
+}
+
+#[test]
+fn test_alter_rewrite_checks_coerced_values() {
+    let (mut db, _dir) = setup();
+    db.execute("UPDATE users SET age = 300 WHERE id = 5")
+        .unwrap();
+    let before = snapshot(&mut db);
+
+    let msg = err(&mut db, "ALTER TABLE users MODIFY COLUMN age TINYINT");
+    assert!(msg.contains("out of range for TINYINT"), "{}", msg);
+    assert!(msg.contains("column 'age'"), "{}", msg);
+    assert_eq!(snapshot(&mut db), before);
+
+    db.execute("UPDATE users SET age = 100 WHERE id = 5")
+        .unwrap();
+    db.execute("ALTER TABLE users MODIFY COLUMN age TINYINT")
+        .unwrap();
+    let rows = db.query("SELECT age FROM users WHERE id = 5").unwrap();
+    assert_eq!(rows[0].get("age"), Some(&Value::Integer(100)));
+}
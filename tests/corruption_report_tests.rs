@@ -0,0 +1,192 @@
+#![cfg(feature = "test-utils")]
+/// Corruption report: every unreadable page, mapped back to the object that
+/// owns it.
+use murodb::btree::node::internal_left_child;
+use murodb::crypto::aead::MasterKey;
+use murodb::schema::catalog::SystemCatalog;
+use murodb::{Database, MuroError, PageOwner, Session};
+use std::io::{Seek, SeekFrom, Write};
+use std::path::Path;
+use tempfile::TempDir;
+
+fn test_key() -> MasterKey {
+    MasterKey::new([0x42u8; 32])
+}
+
+fn create_db(db_path: &Path) -> Session {
+    let mut session = Database::create(db_path, &test_key())
+        .unwrap()
+        .into_session();
+    session
+        .execute("CREATE TABLE t (id BIGINT PRIMARY KEY, body VARCHAR)")
+        .unwrap();
+    session.execute("CREATE INDEX idx_body ON t(body)").unwrap();
+    session.execute("BEGIN").unwrap();
+    for i in 0..300 {
+        session
+            .execute(&format!(
+                "INSERT INTO t VALUES ({}, '{}{}')",
+                i,
+                "x".repeat(100),
+                i
+            ))
+            .unwrap();
+    }
+    session.execute("COMMIT").unwrap();
+    session
+}
+
+/// `(table root, index root, catalog root)`.
+fn roots(session: &mut Session) -> (u64, u64, u64) {
+    let catalog_root = session.catalog().root_page_id();
+    let catalog = SystemCatalog::open(catalog_root);
+    let table = catalog
+        .get_table(session.pager_mut(), "t")
+        .unwrap()
+        .unwrap();
+    let indexes = catalog
+        .get_indexes_for_table(session.pager_mut(), "t")
+        .unwrap();
+    (table.data_btree_root, indexes[0].btree_root, catalog_root)
+}
+
+/// Overwrite part of a page on disk so it no longer decrypts.
+fn corrupt_pages_on_disk(db_path: &Path, page_ids: &[u64], page_count: u64) {
+    let file_len = std::fs::metadata(db_path).unwrap().len();
+    let page_size_on_disk = (file_len - 76) / page_count;
+    let mut file = std::fs::OpenOptions::new()
+        .write(true)
+        .open(db_path)
+        .unwrap();
+    for page_id in page_ids {
+        file.seek(SeekFrom::Start(76 + page_id * page_size_on_disk + 40))
+            .unwrap();
+        file.write_all(&[0xAA; 64]).unwrap();
+    }
+}
+
+#[test]
+fn test_clean_database_reports_nothing() {
+    let dir = TempDir::new().unwrap();
+    let db_path = dir.path().join("test.db");
+    let session = create_db(&db_path);
+    let page_count = session.pager().page_count();
+    drop(session);
+
+    let mut db = Database::open(&db_path, &test_key()).unwrap();
+    let report = db.corruption_report().unwrap();
+    assert!(report.is_clean(), "{:?}", report);
+    assert_eq!(report.pages_scanned, page_count);
+}
+
+#[test]
+fn test_bad_pages_are_attributed_to_their_objects() {
+    let dir = TempDir::new().unwrap();
+    let db_path = dir.path().join("test.db");
+    let mut session = create_db(&db_path);
+    let (table_root, index_root, _) = roots(&mut session);
+    let page_count = session.pager().page_count();
+    drop(session);
+    corrupt_pages_on_disk(&db_path, &[table_root, index_root], page_count);
+
+    let mut db = Database::open(&db_path, &test_key()).unwrap();
+    match db.execute("SELECT * FROM t") {
+        Err(MuroError::PageDecrypt { page_id, page_lsn }) => {
+            assert_eq!(page_id, table_root);
+            assert_eq!(page_lsn, None);
+        }
+        other => panic!("expected PageDecrypt, got {:?}", other),
+    }
+
+    let report = db.corruption_report().unwrap();
+    assert_eq!(report.pages_scanned, page_count);
+    let mut bad: Vec<(u64, PageOwner)> = report
+        .bad_pages
+        .iter()
+        .map(|p| (p.page_id, p.owner.clone()))
+        .collect();
+    bad.sort_by_key(|(id, _)| *id);
+    let mut expected = vec![
+        (table_root, PageOwner::Table("t".into())),
+        (
+            index_root,
+            PageOwner::Index {
+                table: "t".into(),
+                index: "idx_body".into(),
+            },
+        ),
+    ];
+    expected.sort_by_key(|(id, _)| *id);
+    assert_eq!(bad, expected);
+    assert!(report.bad_pages.iter().all(|p| p.decrypt_failed));
+    assert!(report.bad_pages[0]
+        .error
+        .contains(&format!("page {}", report.bad_pages[0].page_id)));
+}
+
+#[test]
+fn test_pages_below_an_unreadable_page_are_unknown() {
+    let dir = TempDir::new().unwrap();
+    let db_path = dir.path().join("test.db");
+    let mut session = create_db(&db_path);
+    let (table_root, _, _) = roots(&mut session);
+    let root = session.pager_mut().read_page(table_root).unwrap();
+    let leaf = internal_left_child(&root, 0).unwrap();
+    let page_count = session.pager().page_count();
+    drop(session);
+    corrupt_pages_on_disk(&db_path, &[table_root, leaf], page_count);
+
+    let mut db = Database::open(&db_path, &test_key()).unwrap();
+    let report = db.corruption_report().unwrap();
+    let owner_of = |page_id: u64| {
+        report
+            .bad_pages
+            .iter()
+            .find(|p| p.page_id == page_id)
+            .map(|p| p.owner.clone())
+    };
+    assert_eq!(owner_of(table_root), Some(PageOwner::Table("t".into())));
+    // Nothing readable points at the leaf any more.
+    assert_eq!(owner_of(leaf), Some(PageOwner::Unknown));
+}
+
+#[test]
+fn test_open_names_unreadable_catalog_page() {
+    let dir = TempDir::new().unwrap();
+    let db_path = dir.path().join("test.db");
+    let mut session = create_db(&db_path);
+    let (_, _, catalog_root) = roots(&mut session);
+    let page_count = session.pager().page_count();
+    drop(session);
+    corrupt_pages_on_disk(&db_path, &[catalog_root], page_count);
+
+    match Database::open(&db_path, &test_key()) {
+        Err(MuroError::PageDecrypt { page_id, .. }) => assert_eq!(page_id, catalog_root),
+        Err(e) => panic!("expected PageDecrypt, got {:?}", e),
+        Ok(_) => panic!("opened with an unreadable catalog page"),
+    }
+}
+
+#[test]
+fn test_corrupt_free_page_is_reported_as_free() {
+    let dir = TempDir::new().unwrap();
+    let db_path = dir.path().join("test.db");
+    let mut session = create_db(&db_path);
+    let (_, index_root, _) = roots(&mut session);
+    session.execute("DROP INDEX idx_body").unwrap();
+    assert!(session
+        .pager_mut()
+        .freelist_mut()
+        .pages()
+        .contains(&index_root));
+    let page_count = session.pager().page_count();
+    drop(session);
+    corrupt_pages_on_disk(&db_path, &[index_root], page_count);
+
+    let mut db = Database::open(&db_path, &test_key()).unwrap();
+    db.execute("SELECT * FROM t").unwrap();
+    let report = db.corruption_report().unwrap();
+    assert_eq!(report.bad_pages.len(), 1, "{:?}", report);
+    assert_eq!(report.bad_pages[0].page_id, index_root);
+    assert_eq!(report.bad_pages[0].owner, PageOwner::Free);
+}
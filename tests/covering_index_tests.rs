@@ -0,0 +1,273 @@
+#![cfg(feature = "test-utils")]
+/// Index-only scans: a seek whose index and primary key hold every column
+/// the query reads decodes the rows from the index entries and returns the
+/// same results as reading the rows.
+use murodb::sql::executor::{ExecResult, Row};
+use murodb::{Database, Value};
+use tempfile::TempDir;
+
+fn setup(dir: &TempDir) -> Database {
+    let mut db = Database::create_plaintext(&dir.path().join("test.db")).unwrap();
+    db.execute(
+        "CREATE TABLE events (id BIGINT PRIMARY KEY, user_id INT, kind VARCHAR(10), \
+         tag VARCHAR(10) COLLATE NOCASE, at DATETIME, day DATE, amount DECIMAL(8,2), \
+         score DOUBLE, blob VARBINARY(8), ref UUID, note VARCHAR(200))",
+    )
+    .unwrap();
+    db.execute("CREATE INDEX idx_user ON events (user_id)")
+        .unwrap();
+    db.execute("CREATE INDEX idx_user_kind ON events (user_id, kind)")
+        .unwrap();
+    db.execute("CREATE INDEX idx_values ON events (day, at, amount, blob, ref)")
+        .unwrap();
+    db.execute("CREATE INDEX idx_tag ON events (tag)").unwrap();
+    db.execute("CREATE INDEX idx_score ON events (score)")
+        .unwrap();
+    db.execute("BEGIN").unwrap();
+    for i in 0..300i64 {
+        let user = if i % 25 == 0 {
+            "NULL".to_string()
+        } else {
+            (i % 10 - 3).to_string()
+        };
+        let kind = if i % 7 == 0 {
+            "NULL".to_string()
+        } else {
+            format!("'k{}'", i % 3)
+        };
+        db.execute(&format!(
+            "INSERT INTO events VALUES ({}, {}, {}, 'Tag{}', '2024-03-{:02} 10:{:02}:00', \
+             '2024-03-{:02}', {}.25, {}.5, X'00{:02X}00', \
+             '00000000-0000-0000-0000-{:012}', '{}')",
+            i,
+            user,
+            kind,
+            i % 4,
+            i % 28 + 1,
+            i % 60,
+            i % 5 + 1,
+            i - 150,
+            i % 6,
+            i % 256,
+            i % 9,
+            "x".repeat(150)
+        ))
+        .unwrap();
+    }
+    db.execute("COMMIT").unwrap();
+    db.execute("ANALYZE TABLE events").unwrap();
+    db
+}
+
+fn extra(db: &mut Database, sql: &str) -> String {
+    match db.query(&format!("EXPLAIN {}", sql)).unwrap()[0].get("Extra") {
+        Some(Value::Varchar(s)) => s.clone(),
+        other => panic!("unexpected Extra {:?}", other),
+    }
+}
+
+fn rows(db: &mut Database, sql: &str) -> Vec<Vec<Value>> {
+    db.query(sql)
+        .unwrap()
+        .into_iter()
+        .map(|r| r.values.into_iter().map(|(_, v)| v).collect())
+        .collect()
+}
+
+/// `sql` with `IGNORE INDEX (...)` for every index, which reads the rows.
+fn without_indexes(sql: &str) -> String {
+    sql.replacen(
+        "FROM events",
+        "FROM events IGNORE INDEX (idx_user, idx_user_kind, idx_values, idx_tag, idx_score)",
+        1,
+    )
+}
+
+fn assert_covered(db: &mut Database, sql: &str) {
+    assert!(
+        extra(db, sql).contains("Using covering index"),
+        "{}: {}",
+        sql,
+        extra(db, sql)
+    );
+    let covered = rows(db, sql);
+    assert!(!covered.is_empty(), "{}", sql);
+    assert_eq!(covered, rows(db, &without_indexes(sql)), "{}", sql);
+}
+
+fn assert_not_covered(db: &mut Database, sql: &str) {
+    assert!(!extra(db, sql).contains("covering"), "{}", sql);
+    assert_eq!(rows(db, sql), rows(db, &without_indexes(sql)), "{}", sql);
+}
+
+#[test]
+fn test_covered_queries_match_row_reads() {
+    let dir = TempDir::new().unwrap();
+    let mut db = setup(&dir);
+    for sql in [
+        "SELECT user_id FROM events WHERE user_id = 4",
+        // Negative keys and the primary key from the entry.
+        "SELECT id, user_id FROM events WHERE user_id = -2 ORDER BY id",
+        "SELECT id, kind FROM events WHERE user_id = 4 AND kind = 'k1' ORDER BY id",
+        "SELECT user_id, kind, id FROM events WHERE user_id = 3 AND kind > 'k0' ORDER BY id",
+        "SELECT id FROM events WHERE user_id IN (1, -3, 5) ORDER BY id",
+        "SELECT id, user_id FROM events WHERE user_id > 4 ORDER BY id LIMIT 7",
+        "SELECT DISTINCT user_id FROM events WHERE user_id >= 5 ORDER BY user_id",
+        "SELECT day, at, amount, blob, ref, id FROM events \
+         WHERE day = CAST('2024-03-02' AS DATE) \
+         AND at = CAST('2024-03-02 10:01:00' AS DATETIME) \
+         AND amount = CAST('-149.25' AS DECIMAL(8,2)) AND blob = X'000100' \
+         AND ref = '00000000-0000-0000-0000-000000000001'",
+        "SELECT user_id * 2 + id FROM events WHERE user_id = 6 AND id % 3 = 0 ORDER BY id",
+    ] {
+        assert_covered(&mut db, sql);
+    }
+}
+
+#[test]
+fn test_covered_aggregates() {
+    let dir = TempDir::new().unwrap();
+    let mut db = setup(&dir);
+    for sql in [
+        "SELECT COUNT(*) FROM events WHERE user_id = 2",
+        "SELECT COUNT(*), SUM(id), MAX(id) FROM events WHERE user_id IN (1, 2)",
+        "SELECT kind, COUNT(*) FROM events WHERE user_id = 5 AND kind >= 'k0' \
+         GROUP BY kind ORDER BY kind",
+    ] {
+        assert_covered(&mut db, sql);
+    }
+}
+
+#[test]
+fn test_null_predicates_are_not_covered() {
+    let dir = TempDir::new().unwrap();
+    let mut db = setup(&dir);
+    // Rows with a NULL key are not in the index; NULL tests read the rows.
+    for sql in [
+        "SELECT id FROM events WHERE user_id = 4 AND kind IS NULL ORDER BY id",
+        "SELECT id FROM events WHERE user_id = 4 AND kind IS NOT NULL ORDER BY id",
+        "SELECT id FROM events WHERE user_id IS NULL ORDER BY id",
+    ] {
+        assert_not_covered(&mut db, sql);
+    }
+    assert_eq!(
+        rows(
+            &mut db,
+            "SELECT COUNT(*) FROM events WHERE user_id = 4 AND kind IS NULL"
+        ),
+        vec![vec![Value::Integer(5)]]
+    );
+    // Without a NULL test, rows with a NULL in another key part of a
+    // composite index are still found through the single-column index.
+    assert_covered(
+        &mut db,
+        "SELECT id FROM events WHERE user_id = 4 ORDER BY id",
+    );
+    assert_eq!(
+        rows(&mut db, "SELECT COUNT(*) FROM events WHERE user_id = 4")[0],
+        vec![Value::Integer(30)]
+    );
+}
+
+#[test]
+fn test_uncovered_columns_and_lossy_keys_read_rows() {
+    let dir = TempDir::new().unwrap();
+    let mut db = setup(&dir);
+    for sql in [
+        "SELECT * FROM events WHERE user_id = 4",
+        "SELECT note FROM events WHERE user_id = 4",
+        "SELECT id FROM events WHERE user_id = 4 AND note = 'x'",
+        "SELECT id FROM events WHERE user_id = 4 ORDER BY note",
+        // NOCASE keys hold the folded text, DOUBLE keys fold -0.0.
+        "SELECT tag FROM events WHERE tag = 'tag1'",
+        "SELECT score FROM events WHERE score > 100",
+    ] {
+        assert_not_covered(&mut db, sql);
+    }
+    assert_eq!(
+        rows(
+            &mut db,
+            "SELECT DISTINCT tag FROM events WHERE tag = 'TAG1'"
+        ),
+        vec![vec![Value::Varchar("Tag1".into())]]
+    );
+}
+
+#[test]
+fn test_covered_scan_skips_row_reads() {
+    let dir = TempDir::new().unwrap();
+    let mut db = setup(&dir);
+    let pages_read = |db: &mut Database, sql: &str| -> i64 {
+        let ExecResult::Rows(stages) = db.execute(&format!("EXPLAIN ANALYZE {}", sql)).unwrap()
+        else {
+            panic!("expected rows");
+        };
+        let stage: &Row = &stages[0];
+        match stage.get("pages_read") {
+            Some(Value::Integer(n)) => *n,
+            other => panic!("unexpected pages_read {:?}", other),
+        }
+    };
+    let covered = pages_read(&mut db, "SELECT id FROM events WHERE user_id > 4");
+    let uncovered = pages_read(&mut db, "SELECT note FROM events WHERE user_id > 4");
+    assert!(covered * 4 < uncovered, "{} vs {}", covered, uncovered);
+}
+
+#[test]
+fn test_covered_scan_sees_uncommitted_writes() {
+    let dir = TempDir::new().unwrap();
+    let mut db = setup(&dir);
+    let sql = "SELECT id FROM events WHERE user_id = 4 ORDER BY id";
+    let before = rows(&mut db, sql).len();
+    db.execute("BEGIN").unwrap();
+    db.execute("INSERT INTO events (id, user_id) VALUES (1000, 4)")
+        .unwrap();
+    db.execute("UPDATE events SET user_id = 5 WHERE id = 7")
+        .unwrap();
+    db.execute("DELETE FROM events WHERE id = 17").unwrap();
+    assert_covered(&mut db, sql);
+    assert_eq!(rows(&mut db, sql).len(), before - 1);
+    db.execute("ROLLBACK").unwrap();
+    assert_eq!(rows(&mut db, sql).len(), before);
+}
+
+#[test]
+fn test_composite_primary_key_from_entries() {
+    let dir = TempDir::new().unwrap();
+    let mut db = Database::create_plaintext(&dir.path().join("test.db")).unwrap();
+    db.execute(
+        "CREATE TABLE m (tenant VARCHAR(10), seq BIGINT, status SMALLINT, body VARCHAR(100), \
+         PRIMARY KEY (tenant, seq))",
+    )
+    .unwrap();
+    db.execute("CREATE UNIQUE INDEX u_status ON m (status, seq)")
+        .unwrap();
+    db.execute("CREATE INDEX idx_status ON m (status)").unwrap();
+    for i in 0..60 {
+        db.execute(&format!(
+            "INSERT INTO m VALUES ('t{}', {}, {}, 'b')",
+            i % 3,
+            i,
+            i % 4 - 1
+        ))
+        .unwrap();
+    }
+    for sql in [
+        "SELECT tenant, seq FROM m WHERE status = -1 ORDER BY seq",
+        "SELECT status, seq, tenant FROM m WHERE status = 2 AND seq = 7",
+        "SELECT tenant, COUNT(*) FROM m WHERE status IN (0, 1) GROUP BY tenant ORDER BY tenant",
+    ] {
+        assert!(
+            extra(&mut db, sql).contains("Using covering index"),
+            "{}",
+            sql
+        );
+        let covered = rows(&mut db, sql);
+        let read = rows(
+            &mut db,
+            &sql.replacen("FROM m", "FROM m IGNORE INDEX (u_status, idx_status)", 1),
+        );
+        assert!(!covered.is_empty(), "{}", sql);
+        assert_eq!(covered, read, "{}", sql);
+    }
+}
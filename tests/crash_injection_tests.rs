@@ -0,0 +1,163 @@
+#![cfg(feature = "test-utils")]
+/// Crash-point sweep: a workload of DDL, inserts, updates, deletes and
+/// FULLTEXT changes is crashed at every write of the WAL and of the database
+/// file, failed and torn, and each crash must recover in strict and
+/// permissive mode to the state after the acknowledged steps (or one more),
+/// with consistent indexes and freelist.
+use murodb::crypto::aead::MasterKey;
+use murodb::fault::harness::{CrashHarness, CrashWorkload};
+use murodb::fault::{
+    FaultFile, FaultInjector, FaultKind, FaultPoint, FaultyPager, FaultyWalWriter,
+};
+use murodb::storage::pager::Pager;
+use murodb::tx::transaction::Transaction;
+use murodb::wal::recovery::recover;
+use murodb::wal::writer::WalWriter;
+use tempfile::TempDir;
+
+fn test_key() -> MasterKey {
+    MasterKey::new([0x42u8; 32])
+}
+
+fn workload() -> CrashWorkload {
+    let mut steps = vec![
+        "CREATE TABLE docs (id BIGINT PRIMARY KEY, tag VARCHAR(20), n INT, body TEXT, pad TEXT)".to_string(),
+        "CREATE INDEX idx_tag ON docs (tag)".to_string(),
+        "CREATE FULLTEXT INDEX ft_body ON docs (body) WITH PARSER ngram OPTIONS (n=2, normalize='nfkc')"
+            .to_string(),
+    ];
+    for batch in 0..3 {
+        let values: Vec<String> = (0..8)
+            .map(|i| {
+                let id = batch * 8 + i;
+                format!(
+                    "({}, 'tag{}', {}, 'doc {} {}', '{}')",
+                    id,
+                    id % 4,
+                    id,
+                    id,
+                    ["red apple", "blue sky", "green tea"][id % 3],
+                    // Long enough to spill some rows to overflow pages.
+                    "x".repeat((id % 5) * 900)
+                )
+            })
+            .collect();
+        steps.push(format!("INSERT INTO docs VALUES {}", values.join(", ")));
+    }
+    steps.extend(
+        [
+            "UPDATE docs SET n = n * 10, tag = 'hot' WHERE id % 5 = 0",
+            "DELETE FROM docs WHERE id BETWEEN 4 AND 9",
+            "UPDATE docs SET body = 'rewritten blue tea', pad = '' WHERE tag = 'tag1'",
+            "CREATE TABLE kv (k VARCHAR(20) PRIMARY KEY, v INT); INSERT INTO kv VALUES ('a', 1), ('b', 2)",
+            "ALTER TABLE docs ADD COLUMN extra INT DEFAULT 7",
+            "INSERT INTO docs (id, tag, n, body, pad) VALUES (1000, 'late', 1, 'late body', ''); UPDATE kv SET v = v + 1",
+            "DROP INDEX idx_tag",
+            "DELETE FROM docs WHERE id < 12",
+            "DROP TABLE kv",
+        ]
+        .map(String::from),
+    );
+    CrashWorkload::new(steps)
+}
+
+/// Crash points a sweep skips between two it runs, to keep the sweep short.
+/// `MURODB_CRASH_SWEEP_FULL=1` runs every point.
+const STRIDE: usize = 6;
+
+/// Crash at every `STRIDE`-th write of the workload, starting at `offset`,
+/// with `kind` faults.
+fn sweep(kind: FaultKind, offset: usize) {
+    let dir = TempDir::new().unwrap();
+    let harness = CrashHarness::prepare(dir.path(), &test_key(), workload()).unwrap();
+    let counts = harness.counts();
+    assert!(counts.wal_writes > 20, "{:?}", counts);
+    assert!(counts.data_writes > 20, "{:?}", counts);
+
+    let (offset, stride) = match std::env::var("MURODB_CRASH_SWEEP_FULL") {
+        Ok(_) => (0, 1),
+        Err(_) => (offset, STRIDE),
+    };
+    let mut outcomes = [0usize; 2];
+    for point in harness
+        .points(kind)
+        .into_iter()
+        .skip(offset)
+        .step_by(stride)
+    {
+        let report = harness.run(point).unwrap();
+        assert!(report.crashed, "{:?}", report);
+        assert_eq!(report.strict_steps, report.permissive_steps, "{:?}", report);
+        // Whether the step in flight survived depends on where it crashed.
+        outcomes[report.strict_steps - report.acknowledged_steps] += 1;
+    }
+    assert!(outcomes[0] > 0 && outcomes[1] > 0, "{:?}", outcomes);
+}
+
+#[test]
+fn test_failed_writes_recover_to_a_step_boundary() {
+    sweep(FaultKind::Fail, 0);
+}
+
+#[test]
+fn test_torn_writes_recover_to_a_step_boundary() {
+    sweep(FaultKind::TornWrite, STRIDE / 2);
+}
+
+#[test]
+fn test_point_past_the_workload_never_crashes() {
+    let dir = TempDir::new().unwrap();
+    let harness = CrashHarness::prepare(
+        dir.path(),
+        &test_key(),
+        CrashWorkload::new([
+            "CREATE TABLE t (id BIGINT PRIMARY KEY)",
+            "INSERT INTO t VALUES (1)",
+        ]),
+    )
+    .unwrap();
+    let report = harness
+        .run(FaultPoint {
+            file: FaultFile::Wal,
+            write: harness.counts().wal_writes + 1,
+            kind: FaultKind::Fail,
+        })
+        .unwrap();
+    assert!(!report.crashed);
+    assert_eq!(report.acknowledged_steps, 2);
+    assert_eq!(report.strict_steps, 2);
+}
+
+#[test]
+fn test_faulty_pager_and_wal_writer_crash_a_direct_commit() {
+    let dir = TempDir::new().unwrap();
+    let db_path = dir.path().join("test.db");
+    let wal_path = dir.path().join("test.db.wal");
+    let injector = FaultInjector::at(FaultPoint {
+        file: FaultFile::Data,
+        write: 1,
+        kind: FaultKind::TornWrite,
+    });
+    let mut pager = FaultyPager::new(Pager::create(&db_path, &test_key()).unwrap(), &injector);
+    let mut wal = FaultyWalWriter::new(
+        WalWriter::create(&wal_path, &test_key()).unwrap(),
+        &injector,
+    );
+
+    // The WAL commit is durable; writing its page to the data file tears.
+    let mut tx = Transaction::begin(1, 0);
+    let mut page = tx.allocate_page(&mut pager).unwrap();
+    page.insert_cell(b"committed").unwrap();
+    let page_id = page.page_id();
+    tx.write_page(page);
+    assert!(tx.commit(&mut pager, &mut wal, 0).is_err());
+    assert!(injector.has_crashed());
+    assert!(injector.counts().wal_writes >= 1);
+    drop(pager);
+    drop(wal);
+
+    recover(&db_path, &wal_path, &test_key()).unwrap();
+    let mut pager = Pager::open(&db_path, &test_key()).unwrap();
+    let page = pager.read_page(page_id).unwrap();
+    assert_eq!(page.cell(0), Some(&b"committed"[..]));
+}
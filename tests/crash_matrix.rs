@@ -16,6 +16,7 @@ fn test_key() -> MasterKey {
 
 /// Points in the commit pipeline where a crash can occur.
 #[derive(Debug, Clone, Copy)]
+#[allow(clippy::enum_variant_names)]
 enum CrashPoint {
     /// After Begin record written
     AfterBegin,
@@ -268,7 +269,7 @@ fn run_crash_test(crash_point: CrashPoint, freed: FreedPages, prior: PriorTx) {
 
         // Verify page_count was updated
         assert!(
-            pager.page_count() >= fl_pid + 1,
+            pager.page_count() > fl_pid,
             "crash at {:?}: page_count should be at least {}, got {}",
             crash_point,
             fl_pid + 1,
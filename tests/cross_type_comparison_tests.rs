@@ -0,0 +1,221 @@
+#![cfg(feature = "test-utils")]
+/// Comparing a VARCHAR/TEXT column with a numeric literal, or a numeric
+/// column with a string literal, is a type error rather than a filter that
+/// silently matches nothing. Strings are compared as strings and numbers as
+/// numbers, so with and without an index the same query gives the same rows.
+use murodb::{Database, MuroError, Value};
+use tempfile::TempDir;
+
+fn setup(dir: &TempDir, indexed: bool) -> Database {
+    let mut db = Database::create_plaintext(&dir.path().join("test.db")).unwrap();
+    db.execute("CREATE TABLE t (id BIGINT PRIMARY KEY, code VARCHAR(10), n INT, note TEXT)")
+        .unwrap();
+    db.execute("CREATE TABLE p (code VARCHAR(10) PRIMARY KEY, n INT)")
+        .unwrap();
+    if indexed {
+        db.execute("CREATE INDEX idx_code ON t (code)").unwrap();
+        db.execute("CREATE INDEX idx_n ON t (n)").unwrap();
+    }
+    for (i, code) in ["7", "007", "8", "70", "10", " 7"].iter().enumerate() {
+        db.execute(&format!(
+            "INSERT INTO t VALUES ({}, '{}', {}, 'note {}')",
+            i, code, i, i
+        ))
+        .unwrap();
+        db.execute(&format!("INSERT INTO p VALUES ('{}', {})", code, i))
+            .unwrap();
+    }
+    db
+}
+
+fn ids(db: &mut Database, sql: &str) -> Result<Vec<i64>, MuroError> {
+    let mut ids: Vec<i64> = db
+        .query(sql)?
+        .iter()
+        .map(|row| match row.values[0].1 {
+            Value::Integer(n) => n,
+            ref other => panic!("{}: {:?}", sql, other),
+        })
+        .collect();
+    ids.sort_unstable();
+    Ok(ids)
+}
+
+fn assert_type_error(result: Result<Vec<i64>, MuroError>, sql: &str) {
+    assert!(
+        matches!(result, Err(MuroError::Type(_))),
+        "{}: {:?}",
+        sql,
+        result
+    );
+}
+
+const MISMATCHED: &[&str] = &[
+    // int vs varchar
+    "SELECT id FROM t WHERE code = 7",
+    "SELECT id FROM t WHERE 7 = code",
+    "SELECT id FROM t WHERE code > 7",
+    "SELECT id FROM t WHERE code <> -7",
+    "SELECT id FROM t WHERE code IN (7, 8)",
+    "SELECT id FROM t WHERE code BETWEEN 5 AND 9",
+    "SELECT id FROM t WHERE note = 1.5",
+    "SELECT n FROM p WHERE code = 7",
+    "SELECT n FROM p WHERE code >= 7",
+    // varchar vs int
+    "SELECT id FROM t WHERE n = '3'",
+    "SELECT id FROM t WHERE n > '3'",
+    "SELECT id FROM t WHERE n IN ('3', '4')",
+    "SELECT id FROM t WHERE n NOT BETWEEN '1' AND '2'",
+    "SELECT id FROM t WHERE id = '1' OR n = 2",
+];
+
+#[test]
+fn test_mismatched_literal_comparisons_fail_with_and_without_index() {
+    for indexed in [false, true] {
+        let dir = TempDir::new().unwrap();
+        let mut db = setup(&dir, indexed);
+        for sql in MISMATCHED {
+            assert_type_error(ids(&mut db, sql), sql);
+        }
+    }
+}
+
+#[test]
+fn test_type_error_names_column_and_literal() {
+    let dir = TempDir::new().unwrap();
+    let mut db = setup(&dir, true);
+    let err = db
+        .query("SELECT id FROM t WHERE code = 7")
+        .unwrap_err()
+        .to_string();
+    assert!(err.contains("VARCHAR(10) column 'code'"), "{}", err);
+    assert!(err.contains("numeric literal 7"), "{}", err);
+    let err = db
+        .query("SELECT id FROM t WHERE n = '007'")
+        .unwrap_err()
+        .to_string();
+    assert!(err.contains("INT column 'n'"), "{}", err);
+    assert!(err.contains("string literal '007'"), "{}", err);
+}
+
+#[test]
+fn test_matching_comparisons_agree_with_and_without_index() {
+    let queries = [
+        // Padded zeros: strings compare as strings, numbers as numbers.
+        "SELECT id FROM t WHERE code = '7'",
+        "SELECT id FROM t WHERE code = '007'",
+        "SELECT id FROM t WHERE code IN ('7', '007', ' 7')",
+        "SELECT id FROM t WHERE code > '10'",
+        "SELECT id FROM t WHERE code BETWEEN '0' AND '8'",
+        "SELECT id FROM t WHERE n = 3",
+        "SELECT id FROM t WHERE n = 3.0",
+        "SELECT id FROM t WHERE n IN (1, 2, 007)",
+        // An explicit CAST compares by number.
+        "SELECT id FROM t WHERE CAST(code AS BIGINT) = 7",
+        "SELECT id FROM t WHERE CAST(n AS VARCHAR) = '3'",
+    ];
+    let dir = TempDir::new().unwrap();
+    let mut plain = setup(&dir, false);
+    let dir = TempDir::new().unwrap();
+    let mut indexed = setup(&dir, true);
+    for sql in queries {
+        let expected = ids(&mut plain, sql).unwrap();
+        assert_eq!(ids(&mut indexed, sql).unwrap(), expected, "{}", sql);
+    }
+    assert_eq!(ids(&mut indexed, queries[0]).unwrap(), vec![0]);
+    assert_eq!(ids(&mut indexed, queries[1]).unwrap(), vec![1]);
+    assert_eq!(ids(&mut indexed, queries[8]).unwrap(), vec![0, 1, 5]);
+
+    let rows = indexed.query("SELECT n FROM p WHERE code = '007'").unwrap();
+    assert_eq!(rows.len(), 1);
+    assert_eq!(rows[0].values[0].1, Value::Integer(1));
+}
+
+#[test]
+fn test_update_and_delete_reject_mismatched_where() {
+    let dir = TempDir::new().unwrap();
+    let mut db = setup(&dir, true);
+    for sql in [
+        "UPDATE t SET n = 100 WHERE code = 7",
+        "DELETE FROM t WHERE n IN ('1', '2')",
+    ] {
+        assert!(
+            matches!(db.execute(sql), Err(MuroError::Type(_))),
+            "{}",
+            sql
+        );
+    }
+    assert_eq!(ids(&mut db, "SELECT id FROM t").unwrap().len(), 6);
+    assert!(ids(&mut db, "SELECT id FROM t WHERE n = 100")
+        .unwrap()
+        .is_empty());
+}
+
+#[test]
+fn test_join_conditions_are_checked() {
+    let dir = TempDir::new().unwrap();
+    let mut db = setup(&dir, true);
+    assert_type_error(
+        ids(
+            &mut db,
+            "SELECT t.id FROM t JOIN p ON p.code = t.code WHERE p.code = 7",
+        ),
+        "join WHERE",
+    );
+    assert_type_error(
+        ids(
+            &mut db,
+            "SELECT t.id FROM t JOIN p ON p.code = t.code AND t.n = '1'",
+        ),
+        "join ON",
+    );
+    assert_eq!(
+        ids(
+            &mut db,
+            "SELECT t.id FROM t JOIN p ON p.code = t.code WHERE p.code = '007'",
+        )
+        .unwrap(),
+        vec![1]
+    );
+}
+
+#[test]
+fn test_bound_parameters_are_checked() {
+    let dir = TempDir::new().unwrap();
+    let mut db = setup(&dir, true);
+    let stmt = db.prepare("SELECT id FROM t WHERE code = ?").unwrap();
+    assert!(matches!(
+        db.query_prepared(&stmt, &[Value::Integer(7)]),
+        Err(MuroError::Type(_))
+    ));
+    let rows = db
+        .query_prepared(&stmt, &[Value::Varchar("007".into())])
+        .unwrap();
+    assert_eq!(rows.len(), 1);
+}
+
+#[test]
+fn test_decimal_subquery_values_compare_as_decimals() {
+    let dir = TempDir::new().unwrap();
+    let mut db = Database::create_plaintext(&dir.path().join("test.db")).unwrap();
+    db.execute("CREATE TABLE d (id BIGINT PRIMARY KEY, amount DECIMAL(10,2))")
+        .unwrap();
+    db.execute("INSERT INTO d VALUES (1, 1.50), (2, 2.25)")
+        .unwrap();
+    assert_eq!(
+        ids(
+            &mut db,
+            "SELECT id FROM d WHERE amount IN (SELECT amount FROM d WHERE id = 2)"
+        )
+        .unwrap(),
+        vec![2]
+    );
+    assert_eq!(
+        ids(
+            &mut db,
+            "SELECT id FROM d WHERE amount = (SELECT MAX(amount) FROM d)"
+        )
+        .unwrap(),
+        vec![2]
+    );
+}
@@ -0,0 +1,121 @@
+#![cfg(feature = "test-utils")]
+/// `CREATE ... IF NOT EXISTS` and `DROP ... IF EXISTS` decide existence in the
+/// same catalog call that creates or removes the entry. Two processes running
+/// the same DDL against one file must both succeed and leave exactly one
+/// table or index behind.
+///
+/// The test re-runs its own binary as the worker processes; the worker test
+/// is a no-op unless the worker environment variable is set.
+use murodb::crypto::aead::MasterKey;
+use murodb::{Database, Value};
+use std::path::Path;
+use std::process::{Command, Stdio};
+use std::time::{Duration, Instant};
+use tempfile::TempDir;
+
+const WORKER_ENV: &str = "MURODB_DDL_RACE_WORKER";
+const WORKERS: i64 = 2;
+const ROUNDS: usize = 15;
+
+fn test_key() -> MasterKey {
+    MasterKey::new([0x42u8; 32])
+}
+
+#[test]
+fn ddl_race_worker() {
+    let Ok(spec) = std::env::var(WORKER_ENV) else {
+        return;
+    };
+    let (dir, worker) = spec.rsplit_once('|').unwrap();
+    let dir = Path::new(dir);
+    let worker: i64 = worker.parse().unwrap();
+    let mut db = Database::open(&dir.join("race.db"), &test_key()).unwrap();
+
+    // Start together so the statements overlap.
+    let deadline = Instant::now() + Duration::from_secs(30);
+    while !dir.join("go").exists() {
+        assert!(Instant::now() < deadline, "start signal never came");
+        std::thread::sleep(Duration::from_millis(1));
+    }
+
+    for round in 0..ROUNDS {
+        db.execute(&format!(
+            "CREATE TABLE IF NOT EXISTS t{} (id BIGINT PRIMARY KEY, worker BIGINT)",
+            round
+        ))
+        .unwrap();
+        db.execute(&format!(
+            "CREATE INDEX IF NOT EXISTS idx_t{}_worker ON t{} (worker)",
+            round, round
+        ))
+        .unwrap();
+        db.execute(&format!(
+            "INSERT INTO t{} VALUES ({}, {})",
+            round, worker, worker
+        ))
+        .unwrap();
+        db.execute(&format!(
+            "CREATE TABLE IF NOT EXISTS scratch{} (id BIGINT PRIMARY KEY)",
+            round
+        ))
+        .unwrap();
+        db.execute(&format!("DROP TABLE IF EXISTS scratch{}", round))
+            .unwrap();
+        db.execute(&format!("DROP INDEX IF EXISTS idx_t{}_worker", round))
+            .unwrap();
+    }
+}
+
+#[test]
+fn test_concurrent_if_not_exists_ddl_across_processes() {
+    let dir = TempDir::new().unwrap();
+    let path = dir.path().join("race.db");
+    drop(Database::create(&path, &test_key()).unwrap());
+
+    let exe = std::env::current_exe().unwrap();
+    let children: Vec<_> = (0..WORKERS)
+        .map(|worker| {
+            Command::new(&exe)
+                .args(["ddl_race_worker", "--exact", "--test-threads=1"])
+                .env(WORKER_ENV, format!("{}|{}", dir.path().display(), worker))
+                .stdout(Stdio::null())
+                .spawn()
+                .unwrap()
+        })
+        .collect();
+    std::fs::write(dir.path().join("go"), b"").unwrap();
+    for mut child in children {
+        let status = child.wait().unwrap();
+        assert!(status.success(), "worker failed: {}", status);
+    }
+
+    let mut db = Database::open(&path, &test_key()).unwrap();
+    let tables: Vec<Value> = db
+        .query("SHOW TABLES")
+        .unwrap()
+        .iter()
+        .map(|r| r.values[0].1.clone())
+        .collect();
+    assert_eq!(tables.len(), ROUNDS, "{:?}", tables);
+    for round in 0..ROUNDS {
+        // A second creation would have replaced the table and lost a row.
+        let rows = db
+            .query(&format!("SELECT worker FROM t{} ORDER BY worker", round))
+            .unwrap();
+        let workers: Vec<Value> = rows.iter().map(|r| r.values[0].1.clone()).collect();
+        assert_eq!(
+            workers,
+            (0..WORKERS).map(Value::Integer).collect::<Vec<_>>(),
+            "t{}",
+            round
+        );
+    }
+    for row in db.verify_integrity().unwrap() {
+        assert_ne!(
+            row.get("status"),
+            Some(&Value::Varchar("error".into())),
+            "{:?}",
+            row
+        );
+    }
+}
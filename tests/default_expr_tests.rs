@@ -0,0 +1,180 @@
+#![cfg(feature = "test-utils")]
+/// Column DEFAULT expressions: `DEFAULT CURRENT_TIMESTAMP` and
+/// `DEFAULT (expr)` evaluated per inserted row, rendered by SHOW CREATE TABLE
+/// and DESCRIBE, and materialized once by ALTER TABLE.
+use murodb::crypto::aead::MasterKey;
+use murodb::{Database, Value};
+use tempfile::TempDir;
+
+fn test_key() -> MasterKey {
+    MasterKey::new([0x42u8; 32])
+}
+
+fn column(db: &mut Database, sql: &str, name: &str) -> Vec<Value> {
+    db.query(sql)
+        .unwrap()
+        .iter()
+        .map(|row| row.get(name).cloned().unwrap_or(Value::Null))
+        .collect()
+}
+
+fn text(value: &Value) -> String {
+    match value {
+        Value::Varchar(s) => s.clone(),
+        other => panic!("expected text, got {:?}", other),
+    }
+}
+
+fn show_create(db: &mut Database, table: &str) -> String {
+    text(&column(db, &format!("SHOW CREATE TABLE {}", table), "Create Table")[0])
+}
+
+#[test]
+fn test_expression_defaults_are_evaluated_per_row() {
+    let dir = TempDir::new().unwrap();
+    let path = dir.path().join("test.db");
+    let mut db = Database::create(&path, &test_key()).unwrap();
+    db.execute(
+        "CREATE TABLE t (id BIGINT PRIMARY KEY, created DATETIME DEFAULT CURRENT_TIMESTAMP, \
+         n INT DEFAULT (2 * 21), tag VARCHAR DEFAULT (CONCAT('v', '1')), \
+         token VARCHAR DEFAULT (UUID_V4()), plain INT DEFAULT 7)",
+    )
+    .unwrap();
+    db.execute("INSERT INTO t (id) VALUES (1)").unwrap();
+    db.execute("INSERT INTO t VALUES (2, DEFAULT, DEFAULT, 'x', DEFAULT, DEFAULT)")
+        .unwrap();
+    db.execute("INSERT INTO t (id, n) VALUES (3, 5)").unwrap();
+
+    let created = column(&mut db, "SELECT created FROM t ORDER BY id", "created");
+    assert!(created.iter().all(|v| matches!(v, Value::DateTime(_))));
+    assert_eq!(
+        column(&mut db, "SELECT n FROM t ORDER BY id", "n"),
+        vec![Value::Integer(42), Value::Integer(42), Value::Integer(5)]
+    );
+    assert_eq!(
+        column(&mut db, "SELECT tag FROM t ORDER BY id", "tag"),
+        vec![
+            Value::Varchar("v1".into()),
+            Value::Varchar("x".into()),
+            Value::Varchar("v1".into())
+        ]
+    );
+    // Each row evaluates the default on its own.
+    let tokens = column(&mut db, "SELECT token FROM t ORDER BY id", "token");
+    assert_ne!(tokens[0], tokens[1]);
+    assert_ne!(tokens[1], tokens[2]);
+
+    // The definition survives a reopen.
+    drop(db);
+    let mut db = Database::open(&path, &test_key()).unwrap();
+    db.execute("INSERT INTO t (id) VALUES (4)").unwrap();
+    assert_eq!(
+        column(&mut db, "SELECT n FROM t WHERE id = 4", "n"),
+        vec![Value::Integer(42)]
+    );
+}
+
+#[test]
+fn test_show_create_and_describe_render_expression_defaults() {
+    let dir = TempDir::new().unwrap();
+    let mut db = Database::create(&dir.path().join("test.db"), &test_key()).unwrap();
+    db.execute(
+        "CREATE TABLE t (id BIGINT PRIMARY KEY, created DATETIME DEFAULT CURRENT_TIMESTAMP, \
+         n INT DEFAULT (1 + 2), tag VARCHAR DEFAULT (UPPER('a')), plain INT DEFAULT 7)",
+    )
+    .unwrap();
+
+    let ddl = show_create(&mut db, "t");
+    assert!(
+        ddl.contains("created DATETIME DEFAULT CURRENT_TIMESTAMP"),
+        "{}",
+        ddl
+    );
+    assert!(ddl.contains("n INT DEFAULT (1 + 2)"), "{}", ddl);
+    assert!(ddl.contains("tag VARCHAR DEFAULT (UPPER('a'))"), "{}", ddl);
+    assert!(ddl.contains("plain INT DEFAULT 7"), "{}", ddl);
+
+    // SHOW CREATE TABLE output recreates the same table.
+    db.execute(&ddl.replacen("CREATE TABLE t", "CREATE TABLE u", 1))
+        .unwrap();
+    assert_eq!(
+        show_create(&mut db, "u"),
+        ddl.replacen("CREATE TABLE t", "CREATE TABLE u", 1)
+    );
+
+    let rows = db.query("DESCRIBE t").unwrap();
+    let described: Vec<(String, String)> = rows
+        .iter()
+        .map(|row| {
+            (
+                text(row.get("Default").unwrap()),
+                text(row.get("Extra").unwrap()),
+            )
+        })
+        .collect();
+    assert_eq!(
+        described[1],
+        ("CURRENT_TIMESTAMP".into(), "DEFAULT_GENERATED".into())
+    );
+    assert_eq!(described[2], ("(1 + 2)".into(), "DEFAULT_GENERATED".into()));
+    assert_eq!(described[4], ("7".into(), String::new()));
+}
+
+#[test]
+fn test_alter_materializes_expression_default_once() {
+    let dir = TempDir::new().unwrap();
+    let mut db = Database::create(&dir.path().join("test.db"), &test_key()).unwrap();
+    db.execute("CREATE TABLE t (id BIGINT PRIMARY KEY)")
+        .unwrap();
+    db.execute("INSERT INTO t VALUES (1), (2), (3)").unwrap();
+
+    // Existing rows get one value, stored rather than re-evaluated on read.
+    db.execute("ALTER TABLE t ADD COLUMN token VARCHAR DEFAULT (UUID_V4())")
+        .unwrap();
+    let tokens = column(&mut db, "SELECT token FROM t ORDER BY id", "token");
+    assert!(tokens.iter().all(|t| *t == tokens[0] && !t.is_null()));
+    assert_eq!(
+        column(&mut db, "SELECT token FROM t ORDER BY id", "token"),
+        tokens
+    );
+    db.execute("INSERT INTO t (id) VALUES (4)").unwrap();
+    let after = column(&mut db, "SELECT token FROM t ORDER BY id", "token");
+    assert_eq!(after[..3], tokens[..]);
+    assert_ne!(after[3], tokens[0]);
+
+    // Rows that read a literal default keep it when it becomes an expression.
+    db.execute("ALTER TABLE t ADD COLUMN n INT DEFAULT 5")
+        .unwrap();
+    db.execute("ALTER TABLE t MODIFY COLUMN n INT DEFAULT (6 + 1)")
+        .unwrap();
+    db.execute("INSERT INTO t (id) VALUES (5)").unwrap();
+    assert_eq!(
+        column(&mut db, "SELECT n FROM t ORDER BY id", "n"),
+        vec![
+            Value::Integer(5),
+            Value::Integer(5),
+            Value::Integer(5),
+            Value::Integer(5),
+            Value::Integer(7)
+        ]
+    );
+}
+
+#[test]
+fn test_invalid_default_expressions_are_rejected() {
+    let dir = TempDir::new().unwrap();
+    let mut db = Database::create(&dir.path().join("test.db"), &test_key()).unwrap();
+    for sql in [
+        "CREATE TABLE t (id BIGINT PRIMARY KEY, n INT DEFAULT (id + 1))",
+        "CREATE TABLE t (id BIGINT PRIMARY KEY, n INT DEFAULT (NO_SUCH_FUNCTION()))",
+        "CREATE TABLE t (id BIGINT PRIMARY KEY, n INT DEFAULT (CURRENT_TIMESTAMP))",
+        "CREATE TABLE t (id BIGINT PRIMARY KEY, n INT DEFAULT ((SELECT 1)))",
+    ] {
+        assert!(db.execute(sql).is_err(), "{}", sql);
+    }
+    db.execute("CREATE TABLE t (id BIGINT PRIMARY KEY)")
+        .unwrap();
+    assert!(db
+        .execute("ALTER TABLE t ADD COLUMN n INT DEFAULT (id * 2)")
+        .is_err());
+}
@@ -0,0 +1,307 @@
+#![cfg(feature = "test-utils")]
+/// `Database::dump` / `Database::import`: a dump replayed into a fresh file
+/// gives back the same schema, indexes and rows, and dumping that copy gives
+/// the same bytes.
+use murodb::{Database, DumpOptions, MuroError, Value};
+use tempfile::TempDir;
+
+const SCHEMA: &str = "
+CREATE TABLE parent (
+  id BIGINT PRIMARY KEY AUTO_INCREMENT,
+  name VARCHAR(50) COLLATE nocase UNIQUE NOT NULL,
+  note VARCHAR(100) DEFAULT 'it''s; here',
+  created DATETIME DEFAULT CURRENT_TIMESTAMP,
+  score INT DEFAULT (1 + 2) CHECK (score >= 0)
+) WITH (fill_factor = 80);
+CREATE TABLE every_type (
+  id BIGINT PRIMARY KEY,
+  t TINYINT, s SMALLINT, i INT, b BIGINT,
+  f FLOAT, d DOUBLE, dec DECIMAL(20,6),
+  dt DATE, dtm DATETIME, ts TIMESTAMP,
+  v VARCHAR(200), tx TEXT, vb VARBINARY(64), j JSONB, u UUID
+);
+CREATE TABLE child (
+  a INT, b INT, parent_id BIGINT, manager_id INT, body TEXT,
+  PRIMARY KEY (a, b),
+  UNIQUE (parent_id, manager_id),
+  FOREIGN KEY (parent_id) REFERENCES parent(id) ON DELETE CASCADE ON UPDATE RESTRICT
+);
+CREATE TABLE ring_a (id INT PRIMARY KEY, b_id INT);
+CREATE TABLE ring_b (id INT PRIMARY KEY, a_id INT,
+  FOREIGN KEY (a_id) REFERENCES ring_a(id));
+ALTER TABLE ring_a ADD FOREIGN KEY (b_id) REFERENCES ring_b(id) ON DELETE SET NULL;
+CREATE TABLE node (id INT PRIMARY KEY, up INT, FOREIGN KEY (up) REFERENCES node(id));
+CREATE INDEX idx_child_body ON child (body) WITH (fill_factor = 70);
+CREATE UNIQUE INDEX uq_type_v ON every_type (v, i);
+CREATE INDEX idx_lower_name ON parent ((LOWER(note)));
+CREATE FULLTEXT INDEX child_body_fts ON child(body)
+  WITH PARSER ngram OPTIONS (n=2, normalize='nfkc', stop_filter=on, stop_df_ratio_ppm=150000,
+  stop_fallback='empty_with_warning', stop_fallback_max_docs=9);
+";
+
+fn populate(db: &mut Database) {
+    db.execute_batch(SCHEMA).unwrap();
+    db.execute_batch(
+        "INSERT INTO parent (name, note, created, score) VALUES
+           ('alice', 'a''b', '2024-02-29 23:59:59', 7),
+           ('Bob', NULL, '1999-01-01 00:00:00', 0);
+         INSERT INTO parent (name) VALUES ('carol');
+         INSERT INTO node VALUES (3, NULL), (1, 3), (2, 1);
+         INSERT INTO ring_a VALUES (1, NULL);
+         INSERT INTO ring_b VALUES (10, 1);
+         UPDATE ring_a SET b_id = 10 WHERE id = 1;",
+    )
+    .unwrap();
+    for i in 0..40 {
+        db.execute(&format!(
+            "INSERT INTO child VALUES ({}, {}, {}, {}, 'line {} ; it''s\n全文検索 {}')",
+            i / 4,
+            i % 4,
+            1 + i % 3,
+            i,
+            i,
+            i % 5
+        ))
+        .unwrap();
+    }
+    db.execute_params(
+        "INSERT INTO every_type VALUES (?, ?, ?, ?, ?, ?, ?, CAST(? AS DECIMAL(20,6)), \
+         CAST(? AS DATE), CAST(? AS DATETIME), CAST(? AS TIMESTAMP), ?, ?, ?, ?, CAST(? AS UUID))",
+        &[
+            Value::Integer(1),
+            Value::Integer(-128),
+            Value::Integer(32767),
+            Value::Integer(i32::MIN as i64),
+            Value::Integer(i64::MIN),
+            Value::Float(1.5),
+            Value::Float(-1e-300),
+            Value::Varchar("-12345678901234.000001".into()),
+            Value::Varchar("0001-01-01".into()),
+            Value::Varchar("9999-12-31 23:59:59".into()),
+            Value::Varchar("2024-06-01 12:00:00".into()),
+            Value::Varchar("quote ' and \\ backslash; \"double\" `tick`".into()),
+            Value::Varchar("multi\nline\r\n\ttext ☃".into()),
+            Value::Varbinary(vec![0, 39, 59, 255, 10]),
+            Value::Varchar(r#"{"k": [1, "x'y"]}"#.into()),
+            Value::Varchar("0190a5b4-8e2f-7c3a-9b1d-2f4e6a8c0d1e".into()),
+        ],
+    )
+    .unwrap();
+    db.execute(
+        "INSERT INTO every_type VALUES (2, NULL, NULL, NULL, i64_max(), NULL, NULL, NULL, \
+         NULL, NULL, NULL, '', NULL, X'', NULL, NULL)"
+            .replace("i64_max()", &i64::MAX.to_string())
+            .as_str(),
+    )
+    .unwrap();
+    db.execute("INSERT INTO every_type (id, f, d) VALUES (3, 1.25e30, 0.1)")
+        .unwrap();
+}
+
+fn dump(db: &mut Database, opts: DumpOptions) -> String {
+    let mut out = Vec::new();
+    db.dump(&mut out, opts).unwrap();
+    String::from_utf8(out).unwrap()
+}
+
+fn table_names(db: &mut Database) -> Vec<String> {
+    db.query("SHOW TABLES")
+        .unwrap()
+        .iter()
+        .map(|r| match &r.values[0].1 {
+            Value::Varchar(s) => s.clone(),
+            other => panic!("{:?}", other),
+        })
+        .collect()
+}
+
+/// SHOW CREATE TABLE, SHOW INDEXES (without root pages) and every row of
+/// every table.
+fn snapshot(db: &mut Database) -> Vec<String> {
+    let mut out = Vec::new();
+    for table in table_names(db) {
+        out.push(format!(
+            "{:?}",
+            db.query(&format!("SHOW CREATE TABLE {}", table)).unwrap()
+        ));
+        for row in db.query(&format!("SHOW INDEXES FROM {}", table)).unwrap() {
+            let values: Vec<_> = row
+                .values
+                .iter()
+                .filter(|(name, _)| name != "Root_page")
+                .collect();
+            out.push(format!("{:?}", values));
+        }
+        for row in db.query(&format!("SELECT * FROM {}", table)).unwrap() {
+            out.push(format!("{:?}", row.values));
+        }
+    }
+    out
+}
+
+#[test]
+fn test_dump_import_round_trip() {
+    let dir = TempDir::new().unwrap();
+    let mut src = Database::create_plaintext(&dir.path().join("src.db")).unwrap();
+    populate(&mut src);
+    let script = dump(&mut src, DumpOptions::default());
+
+    let mut dst = Database::create_plaintext(&dir.path().join("dst.db")).unwrap();
+    let statements = dst.import(script.as_bytes()).unwrap();
+    assert!(statements > 10, "{}", statements);
+    assert_eq!(snapshot(&mut src), snapshot(&mut dst));
+    assert_eq!(dump(&mut dst, DumpOptions::default()), script);
+
+    // Parents come before children; the self reference and the cycle are
+    // added once the rows are in.
+    let pos = |s: &str| script.find(s).unwrap_or_else(|| panic!("{}", s));
+    assert!(pos("CREATE TABLE parent") < pos("CREATE TABLE child"));
+    assert!(pos("CREATE TABLE ring_a") < pos("CREATE TABLE ring_b"));
+    assert!(pos("INSERT INTO node") < pos("ALTER TABLE node ADD FOREIGN KEY (up)"));
+    assert!(pos("INSERT INTO ring_a") < pos("ALTER TABLE ring_a ADD FOREIGN KEY (b_id)"));
+    assert!(script.contains("X'00273BFF0A'"));
+    assert!(script.contains("(-9223372036854775807 - 1)"));
+
+    // AUTO_INCREMENT continues after the largest key loaded.
+    dst.execute("INSERT INTO parent (name) VALUES ('dave')")
+        .unwrap();
+    let rows = dst
+        .query("SELECT id FROM parent WHERE name = 'dave'")
+        .unwrap();
+    assert_eq!(rows[0].values[0].1, Value::Integer(4));
+
+    // The copy enforces what the original did.
+    let err = dst.execute("INSERT INTO parent (name) VALUES ('ALICE')");
+    assert!(
+        matches!(err, Err(MuroError::UniqueViolation(_))),
+        "{:?}",
+        err
+    );
+    assert!(dst.execute("INSERT INTO node VALUES (9, 99)").is_err());
+    assert!(dst
+        .execute("INSERT INTO parent (name, score) VALUES ('x', -1)")
+        .is_err());
+    let hits = dst
+        .query("SELECT a FROM child WHERE MATCH(body) AGAINST('全文' IN BOOLEAN MODE)")
+        .unwrap();
+    assert_eq!(hits.len(), 40);
+    dst.execute("DELETE FROM parent WHERE name = 'alice'")
+        .unwrap();
+    let left = dst
+        .query("SELECT a FROM child WHERE parent_id = 1")
+        .unwrap();
+    assert!(left.is_empty());
+}
+
+#[test]
+fn test_dump_keeps_auto_increment_counter() {
+    let dir = TempDir::new().unwrap();
+    let mut src = Database::create_plaintext(&dir.path().join("src.db")).unwrap();
+    src.execute("CREATE TABLE t (id BIGINT PRIMARY KEY AUTO_INCREMENT, name VARCHAR)")
+        .unwrap();
+    src.execute("INSERT INTO t (name) VALUES ('a'), ('b'), ('c'), ('d'), ('e')")
+        .unwrap();
+    // The rows holding the highest ids are gone, but their ids are used.
+    src.execute("DELETE FROM t WHERE id >= 4").unwrap();
+    let script = dump(&mut src, DumpOptions::default());
+    assert!(script.contains(") AUTO_INCREMENT = 6;"), "{}", script);
+
+    let mut dst = Database::create_plaintext(&dir.path().join("dst.db")).unwrap();
+    dst.import(script.as_bytes()).unwrap();
+    for db in [&mut src, &mut dst] {
+        db.execute("INSERT INTO t (name) VALUES ('f')").unwrap();
+        let rows = db.query("SELECT id FROM t WHERE name = 'f'").unwrap();
+        assert_eq!(rows[0].values[0].1, Value::Integer(6));
+    }
+}
+
+#[test]
+fn test_dump_is_deterministic_and_batches_rows() {
+    let dir = TempDir::new().unwrap();
+    let mut db = Database::create_plaintext(&dir.path().join("test.db")).unwrap();
+    populate(&mut db);
+    let opts = DumpOptions {
+        rows_per_insert: 7,
+        ..DumpOptions::default()
+    };
+    let first = dump(&mut db, opts.clone());
+    assert_eq!(dump(&mut db, opts), first);
+    // 40 child rows in INSERTs of at most 7.
+    assert_eq!(first.matches("INSERT INTO child ").count(), 6);
+}
+
+#[test]
+fn test_dump_options_filter_tables_and_sections() {
+    let dir = TempDir::new().unwrap();
+    let mut db = Database::create_plaintext(&dir.path().join("test.db")).unwrap();
+    populate(&mut db);
+
+    let schema = dump(
+        &mut db,
+        DumpOptions {
+            data: false,
+            ..DumpOptions::default()
+        },
+    );
+    assert!(!schema.contains("INSERT"));
+    assert!(schema.contains("CREATE FULLTEXT INDEX child_body_fts"));
+
+    let data = dump(
+        &mut db,
+        DumpOptions {
+            schema: false,
+            tables: vec!["parent".into(), "child".into()],
+            ..DumpOptions::default()
+        },
+    );
+    assert!(!data.contains("CREATE"));
+    assert!(data.contains("INSERT INTO parent "));
+    assert!(!data.contains("INSERT INTO node"));
+    assert!(data.find("INSERT INTO parent").unwrap() < data.find("INSERT INTO child").unwrap());
+
+    // Schema and data dumped separately load like one dump.
+    let mut copy = Database::create_plaintext(&dir.path().join("copy.db")).unwrap();
+    copy.import(schema.as_bytes()).unwrap();
+    copy.import(data.as_bytes()).unwrap();
+    let count = |db: &mut Database, t: &str| {
+        db.query(&format!("SELECT COUNT(*) FROM {}", t)).unwrap()[0].values[0]
+            .1
+            .clone()
+    };
+    assert_eq!(count(&mut copy, "child"), Value::Integer(40));
+    assert_eq!(count(&mut copy, "node"), Value::Integer(0));
+
+    let mut out = Vec::new();
+    let err = db
+        .dump(
+            &mut out,
+            DumpOptions {
+                tables: vec!["missing".into()],
+                ..DumpOptions::default()
+            },
+        )
+        .unwrap_err();
+    assert!(matches!(err, MuroError::Schema(_)), "{}", err);
+}
+
+#[test]
+fn test_import_commits_periodically_and_reports_failing_statement() {
+    let dir = TempDir::new().unwrap();
+    let mut db = Database::create_plaintext(&dir.path().join("test.db")).unwrap();
+    let mut script = String::from("CREATE TABLE t (id BIGINT PRIMARY KEY, v VARCHAR(20));\n");
+    for i in 0..250 {
+        script.push_str(&format!("INSERT INTO t VALUES ({}, 'a;''{}');\n", i, i));
+    }
+    let commits = db.database_stats().wal_commits;
+    assert_eq!(db.import(script.as_bytes()).unwrap(), 251);
+    assert!(db.database_stats().wal_commits - commits >= 3);
+
+    // Statement 1 of the second script is the duplicate key.
+    let bad = "INSERT INTO t VALUES (1000, 'x');\nINSERT INTO t VALUES (0, 'dup');\n";
+    match db.import(bad.as_bytes()) {
+        Err(MuroError::Script { index, .. }) => assert_eq!(index, 1),
+        other => panic!("{:?}", other),
+    }
+    let rows = db.query("SELECT v FROM t WHERE id = 7").unwrap();
+    assert_eq!(rows[0].values[0].1, Value::Varchar("a;'7".into()));
+}
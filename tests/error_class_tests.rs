@@ -0,0 +1,121 @@
+#![cfg(feature = "test-utils")]
+/// Error classification of failures raised by real execution paths.
+use murodb::crypto::aead::MasterKey;
+use murodb::schema::catalog::SystemCatalog;
+use murodb::sql::executor::execute;
+use murodb::storage::pager::Pager;
+use murodb::{ErrorClass, MuroError};
+use tempfile::TempDir;
+
+fn test_key() -> MasterKey {
+    MasterKey::new([0x42u8; 32])
+}
+
+fn setup() -> (Pager, SystemCatalog, TempDir) {
+    let dir = TempDir::new().unwrap();
+    let db_path = dir.path().join("test.db");
+    let mut pager = Pager::create(&db_path, &test_key()).unwrap();
+    let catalog = SystemCatalog::create(&mut pager).unwrap();
+    (pager, catalog, dir)
+}
+
+fn exec_err(pager: &mut Pager, catalog: &mut SystemCatalog, sql: &str) -> MuroError {
+    execute(sql, pager, catalog).unwrap_err()
+}
+
+#[test]
+fn test_unique_violation_is_constraint_violation() {
+    let (mut pager, mut catalog, _dir) = setup();
+    execute(
+        "CREATE TABLE t (id BIGINT PRIMARY KEY, email VARCHAR UNIQUE)",
+        &mut pager,
+        &mut catalog,
+    )
+    .unwrap();
+    execute("INSERT INTO t VALUES (1, 'a')", &mut pager, &mut catalog).unwrap();
+
+    let err = exec_err(&mut pager, &mut catalog, "INSERT INTO t VALUES (2, 'a')");
+    assert_eq!(err.error_class(), ErrorClass::ConstraintViolation);
+    assert!(!err.is_retryable());
+}
+
+#[test]
+fn test_column_and_foreign_key_violations_are_constraint_violations() {
+    let (mut pager, mut catalog, _dir) = setup();
+    for sql in [
+        "CREATE TABLE p (id BIGINT PRIMARY KEY)",
+        "CREATE TABLE c (id BIGINT PRIMARY KEY, name VARCHAR NOT NULL, age INT CHECK (age >= 0), p_id BIGINT, FOREIGN KEY (p_id) REFERENCES p(id))",
+        "INSERT INTO p VALUES (1)",
+        "INSERT INTO c VALUES (1, 'a', 1, 1)",
+    ] {
+        execute(sql, &mut pager, &mut catalog).unwrap();
+    }
+
+    for sql in [
+        "INSERT INTO c VALUES (2, NULL, 1, 1)",
+        "INSERT INTO c VALUES (2, 'b', -1, 1)",
+        "INSERT INTO c VALUES (2, 'b', 1, 99)",
+        "UPDATE c SET name = NULL WHERE id = 1",
+        "DELETE FROM p WHERE id = 1",
+    ] {
+        let err = exec_err(&mut pager, &mut catalog, sql);
+        assert!(
+            matches!(err, MuroError::ConstraintViolation(_)),
+            "{sql}: {err:?}"
+        );
+        assert_eq!(err.error_class(), ErrorClass::ConstraintViolation, "{sql}");
+        assert!(!err.is_retryable());
+    }
+}
+
+#[test]
+fn test_parse_and_schema_errors_are_user_errors() {
+    let (mut pager, mut catalog, _dir) = setup();
+    let err = exec_err(&mut pager, &mut catalog, "SELEC * FROM t");
+    assert!(matches!(err, MuroError::Parse(_)));
+    assert_eq!(err.error_class(), ErrorClass::UserError);
+
+    let err = exec_err(&mut pager, &mut catalog, "SELECT * FROM missing");
+    assert_eq!(err.error_class(), ErrorClass::UserError);
+}
+
+#[test]
+fn test_injected_io_failures() {
+    let (mut pager, mut catalog, _dir) = setup();
+
+    pager.set_inject_write_page_failure(Some(std::io::ErrorKind::Other));
+    let err = exec_err(
+        &mut pager,
+        &mut catalog,
+        "CREATE TABLE t (id BIGINT PRIMARY KEY)",
+    );
+    assert_eq!(err.error_class(), ErrorClass::Transient);
+    assert!(err.is_retryable());
+
+    pager.set_inject_write_page_failure(Some(std::io::ErrorKind::StorageFull));
+    let err = exec_err(
+        &mut pager,
+        &mut catalog,
+        "CREATE TABLE t (id BIGINT PRIMARY KEY)",
+    );
+    assert_eq!(err.error_class(), ErrorClass::ResourceExhausted);
+    assert!(!err.is_retryable());
+}
+
+#[test]
+fn test_decrypt_failure_is_corruption() {
+    let dir = TempDir::new().unwrap();
+    let db_path = dir.path().join("test.db");
+    {
+        let mut pager = Pager::create(&db_path, &test_key()).unwrap();
+        SystemCatalog::create(&mut pager).unwrap();
+        pager.flush_meta().unwrap();
+    }
+
+    let err = match Pager::open(&db_path, &MasterKey::new([0x99u8; 32])) {
+        Ok(_) => panic!("opening with the wrong key must fail"),
+        Err(e) => e,
+    };
+    assert!(err.is_data_corruption(), "{err:?}");
+    assert_eq!(err.error_class(), ErrorClass::Corruption);
+}
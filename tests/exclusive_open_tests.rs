@@ -0,0 +1,148 @@
+#![cfg(feature = "test-utils")]
+/// `OpenOptions::exclusive_in_process`: a handle that refuses to share its
+/// file with other handles in this process, whatever path they name it by.
+use murodb::crypto::aead::MasterKey;
+use murodb::{Database, DatabasePool, MuroError, OpenOptions, PoolConfig};
+use std::path::{Component, Path, PathBuf};
+use tempfile::TempDir;
+
+fn test_key() -> MasterKey {
+    MasterKey::new([0x42u8; 32])
+}
+
+fn exclusive() -> OpenOptions {
+    OpenOptions {
+        exclusive_in_process: true,
+        ..OpenOptions::default()
+    }
+}
+
+fn create(dir: &TempDir) -> PathBuf {
+    let path = dir.path().join("test.db");
+    let mut db = Database::create(&path, &test_key()).unwrap();
+    db.execute("CREATE TABLE t (id BIGINT PRIMARY KEY)")
+        .unwrap();
+    path
+}
+
+fn assert_already_open<T>(result: murodb::Result<T>) {
+    match result {
+        Err(MuroError::AlreadyOpen(_)) => {}
+        Err(e) => panic!("expected AlreadyOpen, got {}", e),
+        Ok(_) => panic!("expected AlreadyOpen, got a handle"),
+    }
+}
+
+#[test]
+fn test_second_open_of_exclusive_file_fails() {
+    let dir = TempDir::new().unwrap();
+    let path = create(&dir);
+    let mut db = Database::open_with_options(&path, &test_key(), exclusive()).unwrap();
+
+    assert_already_open(Database::open(&path, &test_key()));
+    assert_already_open(Database::open_read_only(&path, &test_key()));
+    assert_already_open(Database::open_with_options(&path, &test_key(), exclusive()));
+    assert_already_open(Database::create(&path, &test_key()));
+
+    // The refused opens touched nothing.
+    db.execute("INSERT INTO t VALUES (1)").unwrap();
+    assert_eq!(db.query("SELECT * FROM t").unwrap().len(), 1);
+}
+
+#[test]
+fn test_exclusive_open_fails_while_another_handle_is_open() {
+    let dir = TempDir::new().unwrap();
+    let path = create(&dir);
+    let shared = Database::open(&path, &test_key()).unwrap();
+    assert_already_open(Database::open_with_options(&path, &test_key(), exclusive()));
+
+    // Handles without the option still share the file.
+    let mut other = Database::open(&path, &test_key()).unwrap();
+    other.execute("INSERT INTO t VALUES (1)").unwrap();
+    drop(shared);
+    drop(other);
+    Database::open_with_options(&path, &test_key(), exclusive()).unwrap();
+}
+
+#[test]
+fn test_path_is_freed_after_drop() {
+    let dir = TempDir::new().unwrap();
+    let path = create(&dir);
+    for _ in 0..3 {
+        let db = Database::open_with_options(&path, &test_key(), exclusive()).unwrap();
+        assert_already_open(Database::open(&path, &test_key()));
+        drop(db);
+        drop(Database::open(&path, &test_key()).unwrap());
+    }
+
+    // A session from into_session keeps the claim until it is dropped.
+    let session = Database::open_with_options(&path, &test_key(), exclusive())
+        .unwrap()
+        .into_session();
+    assert_already_open(Database::open(&path, &test_key()));
+    drop(session);
+    Database::open(&path, &test_key()).unwrap();
+}
+
+#[test]
+fn test_readers_of_the_exclusive_handle_are_allowed() {
+    let dir = TempDir::new().unwrap();
+    let path = create(&dir);
+    let mut db = Database::open_with_options(&path, &test_key(), exclusive()).unwrap();
+    db.execute("INSERT INTO t VALUES (1)").unwrap();
+    let mut reader = db.open_reader().unwrap();
+    assert_eq!(reader.query("SELECT * FROM t").unwrap().len(), 1);
+    assert_already_open(Database::open(&path, &test_key()));
+    drop(db);
+    drop(reader);
+
+    let pool = DatabasePool::open(
+        &path,
+        &test_key(),
+        PoolConfig {
+            open_options: exclusive(),
+            ..PoolConfig::default()
+        },
+    )
+    .unwrap();
+    assert_eq!(
+        pool.read().unwrap().query("SELECT * FROM t").unwrap().len(),
+        1
+    );
+    assert_already_open(Database::open(&path, &test_key()));
+}
+
+#[cfg(unix)]
+#[test]
+fn test_symlinks_and_relative_paths_name_the_same_file() {
+    let dir = TempDir::new().unwrap();
+    let path = create(&dir);
+    let link = dir.path().join("link.db");
+    std::os::unix::fs::symlink(&path, &link).unwrap();
+    let dotted = dir.path().join("sub").join("..").join("test.db");
+    std::fs::create_dir(dir.path().join("sub")).unwrap();
+    let relative = relative_to_cwd(&path);
+
+    for alias in [&link, &dotted, &relative] {
+        let db = Database::open_with_options(alias, &test_key(), exclusive()).unwrap();
+        assert_already_open(Database::open(&path, &test_key()));
+        drop(db);
+        let db = Database::open_with_options(&path, &test_key(), exclusive()).unwrap();
+        assert_already_open(Database::open(alias, &test_key()));
+        drop(db);
+    }
+}
+
+/// `path` spelled relative to the current directory, without changing it.
+fn relative_to_cwd(path: &Path) -> PathBuf {
+    let cwd = std::env::current_dir().unwrap();
+    let mut relative = PathBuf::new();
+    for component in cwd.components() {
+        if let Component::Normal(_) = component {
+            relative.push("..");
+        }
+    }
+    relative.push(path.strip_prefix("/").unwrap());
+    assert!(relative.is_relative());
+    relative
+}
@@ -0,0 +1,188 @@
+#![cfg(feature = "test-utils")]
+/// Multi-statement scripts: `Database::execute_batch` splits on semicolons
+/// outside string literals, runs the statements in one implicit transaction unless the
+/// script has its own BEGIN/COMMIT, and reports which statement failed.
+use murodb::crypto::aead::MasterKey;
+use murodb::{Database, ExecResult, MuroError, Value};
+use tempfile::TempDir;
+
+fn test_key() -> MasterKey {
+    MasterKey::new([0x42u8; 32])
+}
+
+const MIGRATION: &str = "
+CREATE TABLE users (
+    id BIGINT PRIMARY KEY,
+    email VARCHAR(255) NOT NULL UNIQUE,
+    bio TEXT DEFAULT 'none; yet'
+);
+CREATE INDEX idx_users_email ON users (email);
+CREATE TABLE notes (id BIGINT PRIMARY KEY, user_id BIGINT, body TEXT);
+CREATE INDEX idx_notes_user ON notes (user_id);
+
+INSERT INTO users (id, email) VALUES (1, 'a@example.com');
+INSERT INTO users VALUES (2, 'b@example.com', 'likes semicolons; and quotes '';''');
+INSERT INTO notes VALUES (10, 1, 'step 1; step 2;'), (11, 2, ';');
+";
+
+fn table_names(db: &mut Database) -> Vec<String> {
+    db.query("SHOW TABLES")
+        .unwrap()
+        .iter()
+        .map(|r| match r.values[0].1 {
+            Value::Varchar(ref s) => s.clone(),
+            ref other => panic!("unexpected {:?}", other),
+        })
+        .collect()
+}
+
+#[test]
+fn test_migration_script_runs_in_order() {
+    let dir = TempDir::new().unwrap();
+    let mut db = Database::create(&dir.path().join("test.db"), &test_key()).unwrap();
+    let results = db.execute_batch(MIGRATION).unwrap();
+    assert_eq!(results.len(), 7);
+    assert!(matches!(results[4], ExecResult::RowsAffected(1)));
+    assert!(matches!(results[6], ExecResult::RowsAffected(2)));
+
+    let rows = db.query("SELECT bio FROM users ORDER BY id").unwrap();
+    assert_eq!(
+        rows[0].get("bio"),
+        Some(&Value::Varchar("none; yet".into()))
+    );
+    assert_eq!(
+        rows[1].get("bio"),
+        Some(&Value::Varchar("likes semicolons; and quotes ';'".into()))
+    );
+    let rows = db
+        .query("SELECT body FROM notes WHERE user_id = 1")
+        .unwrap();
+    assert_eq!(
+        rows[0].get("body"),
+        Some(&Value::Varchar("step 1; step 2;".into()))
+    );
+    let rows = db
+        .query("SELECT id FROM users WHERE email = 'b@example.com'")
+        .unwrap();
+    assert_eq!(rows[0].get("id"), Some(&Value::Integer(2)));
+}
+
+#[test]
+fn test_failing_statement_rolls_back_the_whole_script() {
+    let dir = TempDir::new().unwrap();
+    let mut db = Database::create(&dir.path().join("test.db"), &test_key()).unwrap();
+    db.execute("CREATE TABLE existing (id BIGINT PRIMARY KEY)")
+        .unwrap();
+
+    let script = format!(
+        "{}INSERT INTO users VALUES (3, 'a@example.com', 'dup');",
+        MIGRATION
+    );
+    let err = db.execute_batch(&script).unwrap_err();
+    match &err {
+        MuroError::Script {
+            index,
+            offset,
+            source,
+        } => {
+            assert_eq!(*index, 7);
+            assert_eq!(*offset, script.rfind("INSERT").unwrap());
+            assert!(
+                matches!(**source, MuroError::UniqueViolation(_)),
+                "{}",
+                source
+            );
+        }
+        other => panic!("unexpected error {:?}", other),
+    }
+    assert!(
+        err.to_string().starts_with("Statement 7 at offset"),
+        "{}",
+        err
+    );
+    assert_eq!(table_names(&mut db), vec!["existing".to_string()]);
+
+    // Parse errors name the statement before anything runs.
+    let err = db
+        .execute_batch("INSERT INTO existing VALUES (1);\nINSERT INTO existing VALUS (2)")
+        .unwrap_err();
+    match err {
+        MuroError::Script {
+            index,
+            offset,
+            source,
+        } => {
+            assert_eq!((index, offset), (1, 33));
+            assert!(matches!(*source, MuroError::Parse(_)), "{}", source);
+        }
+        other => panic!("unexpected error {:?}", other),
+    }
+    assert!(db.query("SELECT * FROM existing").unwrap().is_empty());
+}
+
+#[test]
+fn test_explicit_transactions_in_script_are_respected() {
+    let dir = TempDir::new().unwrap();
+    let mut db = Database::create(&dir.path().join("test.db"), &test_key()).unwrap();
+    let results = db
+        .execute_batch(
+            "CREATE TABLE t (id BIGINT PRIMARY KEY);
+             BEGIN; INSERT INTO t VALUES (1); ROLLBACK;
+             BEGIN; INSERT INTO t VALUES (2); COMMIT;
+             INSERT INTO t VALUES (3);",
+        )
+        .unwrap();
+    assert_eq!(results.len(), 8);
+    let ids: Vec<_> = db
+        .query("SELECT id FROM t ORDER BY id")
+        .unwrap()
+        .iter()
+        .map(|r| r.get("id").cloned())
+        .collect();
+    assert_eq!(ids, vec![Some(Value::Integer(2)), Some(Value::Integer(3))]);
+
+    // Statements before the failing transaction keep their own commits, and
+    // the transaction the script opened is rolled back.
+    let err = db
+        .execute_batch(
+            "INSERT INTO t VALUES (4);
+             BEGIN; INSERT INTO t VALUES (5); INSERT INTO t VALUES (2); COMMIT;",
+        )
+        .unwrap_err();
+    assert!(
+        matches!(err, MuroError::Script { index: 3, .. }),
+        "{:?}",
+        err
+    );
+    let rows = db.query("SELECT COUNT(*) FROM t").unwrap();
+    assert_eq!(rows[0].get("COUNT(*)"), Some(&Value::Integer(3)));
+    db.execute("BEGIN").unwrap();
+
+    // Inside a caller's transaction the script joins it.
+    db.execute_batch("INSERT INTO t VALUES (6); INSERT INTO t VALUES (7)")
+        .unwrap();
+    db.execute("ROLLBACK").unwrap();
+    let rows = db.query("SELECT COUNT(*) FROM t").unwrap();
+    assert_eq!(rows[0].get("COUNT(*)"), Some(&Value::Integer(3)));
+}
+
+#[test]
+fn test_unclosed_parenthesis_fails_its_own_statement() {
+    let dir = TempDir::new().unwrap();
+    let mut db = Database::create(&dir.path().join("test.db"), &test_key()).unwrap();
+    db.execute("CREATE TABLE t (id BIGINT PRIMARY KEY)")
+        .unwrap();
+
+    let script = "INSERT INTO t VALUES (1;\nINSERT INTO t VALUES (2);\nINSERT INTO t VALUES (3)";
+    match db.execute_batch(script).unwrap_err() {
+        MuroError::Script { index, offset, .. } => assert_eq!((index, offset), (0, 0)),
+        other => panic!("unexpected error {:?}", other),
+    }
+    assert!(db.query("SELECT * FROM t").unwrap().is_empty());
+
+    // The statements after it parse and run once it is fixed.
+    let results = db
+        .execute_batch(&script.replacen("(1;", "(1);", 1))
+        .unwrap();
+    assert_eq!(results.len(), 3);
+}
@@ -0,0 +1,186 @@
+#![cfg(feature = "test-utils")]
+/// EXPLAIN ANALYZE executes the statement and reports, per plan stage, the
+/// estimated rows next to the rows actually produced; writes are rolled back.
+use murodb::crypto::aead::MasterKey;
+use murodb::sql::executor::{ExecResult, Row};
+use murodb::{Database, Value};
+use tempfile::TempDir;
+
+fn test_key() -> MasterKey {
+    MasterKey::new([0x42u8; 32])
+}
+
+fn setup(dir: &TempDir) -> Database {
+    let mut db = Database::create(&dir.path().join("test.db"), &test_key()).unwrap();
+    db.execute("CREATE TABLE t (id BIGINT PRIMARY KEY, grp INT, name VARCHAR)")
+        .unwrap();
+    db.execute("CREATE INDEX idx_grp ON t(grp)").unwrap();
+    for i in 1..=20 {
+        db.execute(&format!(
+            "INSERT INTO t VALUES ({}, {}, 'n{}')",
+            i,
+            i % 4,
+            i
+        ))
+        .unwrap();
+    }
+    db
+}
+
+fn analyze(db: &mut Database, sql: &str) -> Vec<Row> {
+    match db.execute(&format!("EXPLAIN ANALYZE {}", sql)).unwrap() {
+        ExecResult::Rows(rows) => rows,
+        other => panic!("Expected Rows, got {:?}", other),
+    }
+}
+
+fn text(row: &Row, col: &str) -> String {
+    match row.get(col) {
+        Some(Value::Varchar(s)) => s.clone(),
+        other => panic!("unexpected {} {:?}", col, other),
+    }
+}
+
+fn int(row: &Row, col: &str) -> i64 {
+    match row.get(col) {
+        Some(Value::Integer(n)) => *n,
+        other => panic!("unexpected {} {:?}", col, other),
+    }
+}
+
+fn nodes(rows: &[Row]) -> Vec<String> {
+    rows.iter().map(|r| text(r, "node")).collect()
+}
+
+fn count(db: &mut Database) -> i64 {
+    match db.query("SELECT COUNT(*) FROM t").unwrap()[0].values[0].1 {
+        Value::Integer(n) => n,
+        ref other => panic!("unexpected value {:?}", other),
+    }
+}
+
+#[test]
+fn test_explain_analyze_output_columns() {
+    let dir = TempDir::new().unwrap();
+    let mut db = setup(&dir);
+    let rows = analyze(&mut db, "SELECT * FROM t");
+    assert_eq!(nodes(&rows), vec!["FullScan"]);
+    let names: Vec<&str> = rows[0].values.iter().map(|(n, _)| n.as_str()).collect();
+    assert_eq!(
+        names,
+        vec![
+            "id",
+            "node",
+            "table",
+            "estimated_rows",
+            "actual_rows",
+            "pages_read",
+            "elapsed_us"
+        ]
+    );
+    assert_eq!(int(&rows[0], "id"), 1);
+    assert_eq!(text(&rows[0], "table"), "t");
+    assert_eq!(int(&rows[0], "actual_rows"), 20);
+    assert!(int(&rows[0], "pages_read") > 0);
+}
+
+#[test]
+fn test_explain_analyze_seeks_report_actual_rows() {
+    let dir = TempDir::new().unwrap();
+    let mut db = setup(&dir);
+
+    let rows = analyze(&mut db, "SELECT * FROM t WHERE id = 7");
+    assert_eq!(nodes(&rows), vec!["PkSeek"]);
+    assert_eq!(int(&rows[0], "estimated_rows"), 1);
+    assert_eq!(int(&rows[0], "actual_rows"), 1);
+
+    let rows = analyze(&mut db, "SELECT * FROM t WHERE grp = 2");
+    assert_eq!(nodes(&rows), vec!["IndexSeek"]);
+    assert_eq!(int(&rows[0], "actual_rows"), 5);
+}
+
+#[test]
+fn test_explain_analyze_estimates_follow_analyze_table() {
+    let dir = TempDir::new().unwrap();
+    let mut db = setup(&dir);
+    db.execute("ANALYZE TABLE t").unwrap();
+    let rows = analyze(&mut db, "SELECT * FROM t WHERE name = 'n3'");
+    assert_eq!(nodes(&rows), vec!["FullScan"]);
+    assert_eq!(int(&rows[0], "estimated_rows"), 20);
+    assert_eq!(int(&rows[0], "actual_rows"), 1);
+}
+
+#[test]
+fn test_explain_analyze_sort_and_aggregate_stages() {
+    let dir = TempDir::new().unwrap();
+    let mut db = setup(&dir);
+
+    let rows = analyze(&mut db, "SELECT * FROM t WHERE grp = 1 ORDER BY name");
+    assert_eq!(nodes(&rows), vec!["IndexSeek", "Sort"]);
+    assert_eq!(int(&rows[1], "actual_rows"), 5);
+
+    let rows = analyze(
+        &mut db,
+        "SELECT grp, COUNT(*) FROM t GROUP BY grp ORDER BY grp",
+    );
+    assert_eq!(nodes(&rows), vec!["FullScan", "Aggregate", "Sort"]);
+    assert_eq!(int(&rows[0], "actual_rows"), 20);
+    assert_eq!(int(&rows[1], "actual_rows"), 4);
+    assert_eq!(rows[1].get("estimated_rows"), Some(&Value::Null));
+}
+
+#[test]
+fn test_explain_analyze_join_stages() {
+    let dir = TempDir::new().unwrap();
+    let mut db = setup(&dir);
+    db.execute("CREATE TABLE g (grp INT PRIMARY KEY, label VARCHAR)")
+        .unwrap();
+    db.execute("INSERT INTO g VALUES (1, 'one')").unwrap();
+    db.execute("INSERT INTO g VALUES (2, 'two')").unwrap();
+
+    let rows = analyze(&mut db, "SELECT t.id FROM t JOIN g ON t.grp = g.grp");
+    assert_eq!(nodes(&rows), vec!["FullScan", "FullScan", "Join"]);
+    assert_eq!(text(&rows[0], "table"), "t");
+    assert_eq!(text(&rows[1], "table"), "g");
+    assert_eq!(int(&rows[2], "actual_rows"), 10);
+}
+
+#[test]
+fn test_explain_analyze_write_is_rolled_back() {
+    let dir = TempDir::new().unwrap();
+    let mut db = setup(&dir);
+
+    let rows = analyze(&mut db, "DELETE FROM t WHERE grp = 3");
+    assert_eq!(nodes(&rows), vec!["IndexSeek", "Delete"]);
+    assert_eq!(int(&rows[1], "actual_rows"), 5);
+    assert_eq!(count(&mut db), 20);
+
+    let rows = analyze(&mut db, "UPDATE t SET name = 'x' WHERE id = 1");
+    assert_eq!(nodes(&rows), vec!["PkSeek", "Update"]);
+    assert_eq!(int(&rows[1], "actual_rows"), 1);
+    let name = db.query("SELECT name FROM t WHERE id = 1").unwrap();
+    assert_eq!(name[0].get("name"), Some(&Value::Varchar("n1".into())));
+}
+
+#[test]
+fn test_explain_analyze_write_inside_transaction_keeps_earlier_writes() {
+    let dir = TempDir::new().unwrap();
+    let mut db = setup(&dir);
+
+    db.execute("BEGIN").unwrap();
+    db.execute("DELETE FROM t WHERE id = 1").unwrap();
+    analyze(&mut db, "DELETE FROM t");
+    assert_eq!(count(&mut db), 19);
+    db.execute("COMMIT").unwrap();
+    assert_eq!(count(&mut db), 19);
+}
+
+#[test]
+fn test_explain_analyze_rejects_other_statements() {
+    let dir = TempDir::new().unwrap();
+    let mut db = setup(&dir);
+    assert!(db
+        .execute("EXPLAIN ANALYZE INSERT INTO t VALUES (99, 0, 'z')")
+        .is_err());
+    assert_eq!(count(&mut db), 20);
+}
@@ -186,6 +186,79 @@ fn test_explain_delete_index_seek() {
     assert_eq!(row[4].1, Value::Varchar("idx_name".to_string()));
 }
 
+#[test]
+fn test_explain_update_range_seek_on_updated_column() {
+    let (mut pager, mut catalog, _dir) = setup();
+
+    execute(
+        "CREATE TABLE t (id BIGINT PRIMARY KEY, a INT)",
+        &mut pager,
+        &mut catalog,
+    )
+    .unwrap();
+    execute("CREATE INDEX idx_a ON t(a)", &mut pager, &mut catalog).unwrap();
+    for i in 1..=5 {
+        execute(
+            &format!("INSERT INTO t VALUES ({}, {})", i, i),
+            &mut pager,
+            &mut catalog,
+        )
+        .unwrap();
+    }
+
+    let sql = "UPDATE t SET a = a + 1 WHERE a >= 2 AND a <= 4";
+    let rows = query_rows(&format!("EXPLAIN {}", sql), &mut pager, &mut catalog);
+    assert_eq!(rows[0][3].1, Value::Varchar("range".to_string()));
+    assert_eq!(rows[0][4].1, Value::Varchar("idx_a".to_string()));
+
+    // Rows are chosen by their old values: each matching row moves once, even
+    // though the new values still fall inside the seek range.
+    match execute(sql, &mut pager, &mut catalog).unwrap() {
+        ExecResult::RowsAffected(n) => assert_eq!(n, 3),
+        other => panic!("Expected RowsAffected, got {:?}", other),
+    }
+    let rows = query_rows("SELECT a FROM t ORDER BY id", &mut pager, &mut catalog);
+    let values: Vec<Value> = rows.into_iter().map(|r| r[0].1.clone()).collect();
+    assert_eq!(values, [1, 3, 4, 5, 5].map(Value::Integer).to_vec());
+}
+
+#[test]
+fn test_explain_delete_fulltext_reports_full_scan() {
+    let (mut pager, mut catalog, _dir) = setup();
+
+    execute(
+        "CREATE TABLE t (id BIGINT PRIMARY KEY, body TEXT)",
+        &mut pager,
+        &mut catalog,
+    )
+    .unwrap();
+    execute(
+        "CREATE FULLTEXT INDEX ft_body ON t(body) WITH PARSER ngram OPTIONS (n=2, normalize='nfkc')",
+        &mut pager,
+        &mut catalog,
+    )
+    .unwrap();
+    execute(
+        "INSERT INTO t VALUES (1, 'hello world'), (2, 'goodbye')",
+        &mut pager,
+        &mut catalog,
+    )
+    .unwrap();
+
+    // The write path does not execute FULLTEXT plans; EXPLAIN shows the scan
+    // it actually runs.
+    let sql = "DELETE FROM t WHERE MATCH(body) AGAINST('hello' IN NATURAL LANGUAGE MODE) > 0";
+    let rows = query_rows(&format!("EXPLAIN {}", sql), &mut pager, &mut catalog);
+    assert_eq!(rows[0][3].1, Value::Varchar("ALL".to_string()));
+    assert_eq!(rows[0][4].1, Value::Null);
+    let rows = query_rows(
+        &format!("EXPLAIN {}", sql.replacen("DELETE", "SELECT *", 1)),
+        &mut pager,
+        &mut catalog,
+    );
+    assert_eq!(rows[0][3].1, Value::Varchar("fulltext".to_string()));
+}
+
 #[test]
 fn test_explain_composite_index_range_seek() {
     let (mut pager, mut catalog, _dir) = setup();
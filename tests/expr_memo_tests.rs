@@ -0,0 +1,185 @@
+#![cfg(feature = "test-utils")]
+/// Sub-expressions shared by WHERE and the select list are computed once per
+/// row; results match evaluating every expression on its own, and equal text
+/// over different tables is never shared.
+use murodb::crypto::aead::MasterKey;
+use murodb::sql::ast::{SelectColumn, Statement};
+use murodb::sql::eval::{eval_expr, is_truthy};
+use murodb::sql::parser::parse_sql;
+use murodb::{Database, Value};
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+use tempfile::TempDir;
+
+fn test_key() -> MasterKey {
+    MasterKey::new([0x42u8; 32])
+}
+
+fn rows(db: &mut Database, sql: &str) -> Vec<Vec<Value>> {
+    db.query(sql)
+        .unwrap()
+        .into_iter()
+        .map(|row| row.values.into_iter().map(|(_, v)| v).collect())
+        .collect()
+}
+
+const COLUMNS: [&str; 4] = ["id", "a", "b", "s"];
+
+/// Sub-expressions the corpus repeats between WHERE and the select list.
+const SHARED: &[&str] = &[
+    "a * b",
+    "a + b * 2",
+    "(a - b) * (a + b)",
+    "COALESCE(a, b, 0) + 1",
+    "ABS(a - 7)",
+    "LENGTH(CONCAT(s, s))",
+    "UPPER(s)",
+    "CASE WHEN a > b THEN a ELSE b END",
+    "CAST(a AS VARCHAR)",
+    "a % 5",
+];
+
+#[test]
+fn test_results_match_unshared_evaluation() {
+    let dir = TempDir::new().unwrap();
+    let mut db = Database::create(&dir.path().join("test.db"), &test_key()).unwrap();
+    db.execute("CREATE TABLE t (id BIGINT PRIMARY KEY, a INT, b INT, s VARCHAR)")
+        .unwrap();
+    let mut rng = StdRng::seed_from_u64(0x3E30);
+    let mut values = Vec::new();
+    for id in 1..=60 {
+        let mut int = || match rng.gen_range(0..6) {
+            0 => "NULL".to_string(),
+            _ => rng.gen_range(-20..20).to_string(),
+        };
+        let (a, b) = (int(), int());
+        let s = match id % 4 {
+            0 => "NULL".to_string(),
+            n => format!("'{}'", "xy".repeat(n)),
+        };
+        values.push(format!("({}, {}, {}, {})", id, a, b, s));
+    }
+    db.execute(&format!("INSERT INTO t VALUES {}", values.join(", ")))
+        .unwrap();
+    let table = rows(&mut db, "SELECT * FROM t ORDER BY id");
+
+    let mut checked = 0;
+    for _ in 0..300 {
+        let first = SHARED[rng.gen_range(0..SHARED.len())];
+        let second = SHARED[rng.gen_range(0..SHARED.len())];
+        let filter = match rng.gen_range(0..4) {
+            0 => format!("{} > {}", first, rng.gen_range(-10..10)),
+            1 => format!("{} IS NOT NULL AND {} IS NOT NULL", first, second),
+            2 => format!("{} = {} OR {} < 3", first, first, second),
+            _ => format!("NOT ({} IS NULL) AND id > {}", second, rng.gen_range(0..30)),
+        };
+        let sql = format!(
+            "SELECT id, {0} AS c1, {1} AS c2, {0} AS c3 FROM t WHERE {2} ORDER BY id",
+            first, second, filter
+        );
+
+        // Reference: every expression evaluated independently.
+        let Ok(Statement::Select(sel)) = parse_sql(&sql) else {
+            panic!("{}", sql);
+        };
+        let mut expected = Vec::new();
+        for row in &table {
+            let lookup = |name: &str| {
+                COLUMNS
+                    .iter()
+                    .position(|c| *c == name)
+                    .map(|i| row[i].clone())
+            };
+            if !is_truthy(&eval_expr(sel.where_clause.as_ref().unwrap(), &lookup).unwrap()) {
+                continue;
+            }
+            expected.push(
+                sel.columns
+                    .iter()
+                    .map(|col| match col {
+                        SelectColumn::Expr(expr, _) => eval_expr(expr, &lookup).unwrap(),
+                        SelectColumn::Star => unreachable!(),
+                    })
+                    .collect::<Vec<_>>(),
+            );
+        }
+        assert_eq!(rows(&mut db, &sql), expected, "{}", sql);
+        checked += expected.len();
+    }
+    assert!(checked > 1000, "{}", checked);
+}
+
+#[test]
+fn test_same_text_over_different_tables_is_not_shared() {
+    let dir = TempDir::new().unwrap();
+    let mut db = Database::create(&dir.path().join("test.db"), &test_key()).unwrap();
+    db.execute("CREATE TABLE a (id BIGINT PRIMARY KEY, v INT, w INT)")
+        .unwrap();
+    db.execute("CREATE TABLE b (id BIGINT PRIMARY KEY, v INT)")
+        .unwrap();
+    db.execute("INSERT INTO a VALUES (1, 1, 10), (2, 2, 20), (3, 3, 30)")
+        .unwrap();
+    db.execute("INSERT INTO b VALUES (1, 100), (2, 200), (3, 300)")
+        .unwrap();
+
+    // Qualified references to different tables stay distinct...
+    assert_eq!(
+        rows(
+            &mut db,
+            "SELECT a.v * 2 AS x, b.v * 2 AS y FROM a JOIN b ON a.id = b.id \
+             WHERE a.v * 2 > 2 AND b.v * 2 < 600 ORDER BY a.id"
+        ),
+        vec![vec![Value::Integer(4), Value::Integer(400)]]
+    );
+    // ...while an unqualified reference matches its qualified spelling.
+    assert_eq!(
+        rows(
+            &mut db,
+            "SELECT w + 1 AS x, a.w + 1 AS y, b.v + 1 AS z FROM a JOIN b ON a.id = b.id \
+             WHERE a.w + 1 = 21"
+        ),
+        vec![vec![
+            Value::Integer(21),
+            Value::Integer(21),
+            Value::Integer(201)
+        ]]
+    );
+    // The outer query and its subquery use the same text over different tables.
+    db.execute("INSERT INTO b VALUES (4, 3)").unwrap();
+    assert_eq!(
+        rows(
+            &mut db,
+            "SELECT v * 2 AS d FROM a WHERE v * 2 IN (SELECT v * 2 FROM b WHERE v * 2 < 10)"
+        ),
+        vec![vec![Value::Integer(6)]]
+    );
+}
+
+#[test]
+fn test_shared_fulltext_expressions() {
+    let dir = TempDir::new().unwrap();
+    let mut db = Database::create(&dir.path().join("test.db"), &test_key()).unwrap();
+    db.execute("CREATE TABLE docs (id BIGINT PRIMARY KEY, body TEXT)")
+        .unwrap();
+    db.execute("CREATE FULLTEXT INDEX docs_ft ON docs (body) WITH PARSER ngram")
+        .unwrap();
+    db.execute(
+        "INSERT INTO docs VALUES (1, 'rust database engine'), (2, 'python web'), \
+         (3, 'embedded rust storage')",
+    )
+    .unwrap();
+
+    let scored = rows(
+        &mut db,
+        "SELECT id, MATCH(body) AGAINST('rust' IN NATURAL LANGUAGE MODE) > 0 AS hit, \
+         LENGTH(fts_snippet(body, 'rust', '[', ']', 20)) AS len FROM docs \
+         WHERE MATCH(body) AGAINST('rust' IN NATURAL LANGUAGE MODE) > 0 \
+         AND LENGTH(fts_snippet(body, 'rust', '[', ']', 20)) > 0 ORDER BY id",
+    );
+    let ids: Vec<&Value> = scored.iter().map(|row| &row[0]).collect();
+    assert_eq!(ids, vec![&Value::Integer(1), &Value::Integer(3)]);
+    for row in &scored {
+        assert_eq!(row[1], Value::Integer(1));
+        assert!(matches!(row[2], Value::Integer(n) if n > 0));
+    }
+}
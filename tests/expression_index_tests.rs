@@ -0,0 +1,198 @@
+#![cfg(feature = "test-utils")]
+/// Indexes on expressions such as `LOWER(email)`: maintained on writes,
+/// enforced when UNIQUE, and used by the planner for matching predicates.
+use murodb::crypto::aead::MasterKey;
+use murodb::sql::executor::ExecResult;
+use murodb::{Database, Value};
+use std::path::Path;
+use tempfile::TempDir;
+
+fn test_key() -> MasterKey {
+    MasterKey::new([0x42u8; 32])
+}
+
+fn setup(db_path: &Path) -> Database {
+    let mut db = Database::create(db_path, &test_key()).unwrap();
+    db.execute("CREATE TABLE users (id BIGINT PRIMARY KEY, email VARCHAR)")
+        .unwrap();
+    db
+}
+
+fn ids(db: &mut Database, sql: &str) -> Vec<i64> {
+    db.query(sql)
+        .unwrap()
+        .iter()
+        .map(|row| match row.get("id") {
+            Some(Value::Integer(n)) => *n,
+            other => panic!("unexpected id {:?}", other),
+        })
+        .collect()
+}
+
+fn explain(db: &mut Database, sql: &str) -> (String, String) {
+    let rows = db.query(&format!("EXPLAIN {}", sql)).unwrap();
+    let text = |name: &str| match rows[0].get(name) {
+        Some(Value::Varchar(s)) => s.clone(),
+        other => panic!("unexpected {} {:?}", name, other),
+    };
+    (text("type"), text("key"))
+}
+
+#[test]
+fn test_lower_lookup_uses_expression_index() {
+    let dir = TempDir::new().unwrap();
+    let mut db = setup(&dir.path().join("test.db"));
+    db.execute(
+        "INSERT INTO users VALUES (1, 'Alice@Example.com'), (2, 'bob@example.com'), (3, NULL)",
+    )
+    .unwrap();
+    db.execute("CREATE INDEX idx_email_ci ON users ((LOWER(email)))")
+        .unwrap();
+
+    let sql = "SELECT id FROM users WHERE lower(email) = 'alice@example.com'";
+    assert_eq!(
+        explain(&mut db, sql),
+        ("ref".to_string(), "idx_email_ci".to_string())
+    );
+    assert_eq!(ids(&mut db, sql), vec![1]);
+
+    // Writes keep the index in step.
+    db.execute("INSERT INTO users VALUES (4, 'CAROL@example.com')")
+        .unwrap();
+    db.execute("UPDATE users SET email = 'Bobby@Example.com' WHERE id = 2")
+        .unwrap();
+    db.execute("DELETE FROM users WHERE id = 1").unwrap();
+    assert_eq!(ids(&mut db, sql), Vec::<i64>::new());
+    assert_eq!(
+        ids(
+            &mut db,
+            "SELECT id FROM users WHERE LOWER(email) = 'carol@example.com'"
+        ),
+        vec![4]
+    );
+    assert_eq!(
+        ids(
+            &mut db,
+            "SELECT id FROM users WHERE LOWER(email) = 'bobby@example.com'"
+        ),
+        vec![2]
+    );
+    assert!(ids(
+        &mut db,
+        "SELECT id FROM users WHERE LOWER(email) = 'bob@example.com'"
+    )
+    .is_empty());
+}
+
+#[test]
+fn test_unique_expression_index_enforced() {
+    let dir = TempDir::new().unwrap();
+    let mut db = setup(&dir.path().join("test.db"));
+    db.execute("INSERT INTO users VALUES (1, 'a@x.com'), (2, 'A@X.COM')")
+        .unwrap();
+    // Backfill finds the existing case-insensitive duplicate.
+    assert!(db
+        .execute("CREATE UNIQUE INDEX uq_email ON users ((LOWER(email)))")
+        .is_err());
+
+    db.execute("DELETE FROM users WHERE id = 2").unwrap();
+    db.execute("CREATE UNIQUE INDEX uq_email ON users ((LOWER(email)))")
+        .unwrap();
+    assert!(db
+        .execute("INSERT INTO users VALUES (3, 'A@x.com')")
+        .is_err());
+    db.execute("INSERT INTO users VALUES (3, 'b@x.com')")
+        .unwrap();
+    assert!(db
+        .execute("UPDATE users SET email = 'B@X.com' WHERE id = 1")
+        .is_err());
+    // A row may keep its own key.
+    db.execute("UPDATE users SET email = 'B@x.COM' WHERE id = 3")
+        .unwrap();
+    // NULL keys are not indexed, so they never collide.
+    db.execute("INSERT INTO users VALUES (4, NULL), (5, NULL)")
+        .unwrap();
+}
+
+#[test]
+fn test_expression_index_survives_reopen_and_check() {
+    let dir = TempDir::new().unwrap();
+    let db_path = dir.path().join("test.db");
+    let mut db = setup(&db_path);
+    db.execute("INSERT INTO users VALUES (1, 'Alice@Example.com')")
+        .unwrap();
+    db.execute("CREATE INDEX idx_mixed ON users (id, (LOWER(email)))")
+        .unwrap();
+    db.execute("CREATE INDEX idx_len ON users ((CHAR_LENGTH(email) + 1))")
+        .unwrap();
+    drop(db);
+
+    let mut db = Database::open(&db_path, &test_key()).unwrap();
+    db.execute("INSERT INTO users VALUES (2, 'Dave@Example.com')")
+        .unwrap();
+    let sql = "SELECT id FROM users WHERE id = 2 AND LOWER(email) = 'dave@example.com'";
+    assert_eq!(ids(&mut db, sql), vec![2]);
+    let sql = "SELECT id FROM users WHERE CHAR_LENGTH(email) + 1 = 18";
+    assert_eq!(
+        explain(&mut db, sql),
+        ("ref".to_string(), "idx_len".to_string())
+    );
+    assert_eq!(ids(&mut db, sql), vec![1]);
+
+    match db.execute("CHECK TABLE users").unwrap() {
+        ExecResult::Rows(rows) => {
+            for row in rows {
+                assert_eq!(
+                    row.get("status"),
+                    Some(&Value::Varchar("ok".into())),
+                    "{:?}",
+                    row
+                );
+            }
+        }
+        other => panic!("expected rows, got {:?}", other),
+    }
+}
+
+#[test]
+fn test_alter_table_respects_expression_indexes() {
+    let dir = TempDir::new().unwrap();
+    let mut db = setup(&dir.path().join("test.db"));
+    db.execute("INSERT INTO users VALUES (1, 'Alice@Example.com')")
+        .unwrap();
+    db.execute("CREATE INDEX idx_email_ci ON users ((LOWER(email)))")
+        .unwrap();
+
+    let err = db
+        .execute("ALTER TABLE users DROP COLUMN email")
+        .unwrap_err()
+        .to_string();
+    assert!(err.contains("idx_email_ci"), "{}", err);
+    assert!(db
+        .execute("ALTER TABLE users MODIFY COLUMN email TEXT")
+        .is_err());
+
+    // Renaming the column rewrites the indexed expression.
+    db.execute("ALTER TABLE users CHANGE COLUMN email mail VARCHAR")
+        .unwrap();
+    let sql = "SELECT id FROM users WHERE LOWER(mail) = 'alice@example.com'";
+    assert_eq!(
+        explain(&mut db, sql),
+        ("ref".to_string(), "idx_email_ci".to_string())
+    );
+    assert_eq!(ids(&mut db, sql), vec![1]);
+}
+
+#[test]
+fn test_invalid_index_expressions_rejected() {
+    let dir = TempDir::new().unwrap();
+    let mut db = setup(&dir.path().join("test.db"));
+    assert!(db
+        .execute("CREATE INDEX i1 ON users ((LOWER(missing)))")
+        .is_err());
+    assert!(db.execute("CREATE INDEX i2 ON users ((NOW()))").is_err());
+    assert!(db
+        .execute("CREATE INDEX i3 ON users ((CONCAT(email, UUID_V4())))")
+        .is_err());
+    assert!(db.execute("CREATE INDEX i4 ON users ((1 + 2))").is_err());
+}
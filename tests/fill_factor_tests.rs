@@ -0,0 +1,221 @@
+#![cfg(feature = "test-utils")]
+/// Leaf fill factor: ascending inserts fill the rightmost leaf before
+/// splitting, `WITH (fill_factor = N)` leaves room in bulk-loaded and
+/// appended leaves, and the option is stored with the table or index.
+use murodb::crypto::aead::MasterKey;
+use murodb::{Database, Value};
+use tempfile::TempDir;
+
+fn test_key() -> MasterKey {
+    MasterKey::new([0x42u8; 32])
+}
+
+fn integer(value: Option<&Value>) -> i64 {
+    match value {
+        Some(Value::Integer(n)) => *n,
+        other => panic!("expected integer, got {:?}", other),
+    }
+}
+
+/// `(Data_pages, Index_pages)` of `table` from SHOW TABLE STATUS.
+fn pages(db: &mut Database, table: &str) -> (i64, i64) {
+    let rows = db.query("SHOW TABLE STATUS").unwrap();
+    let row = rows
+        .iter()
+        .find(|r| r.get("Name") == Some(&Value::Varchar(table.to_string())))
+        .unwrap_or_else(|| panic!("no status row for {}", table));
+    (
+        integer(row.get("Data_pages")),
+        integer(row.get("Index_pages")),
+    )
+}
+
+fn show_create(db: &mut Database, table: &str) -> String {
+    let rows = db.query(&format!("SHOW CREATE TABLE {}", table)).unwrap();
+    match rows[0].get("Create Table") {
+        Some(Value::Varchar(sql)) => sql.clone(),
+        other => panic!("unexpected SHOW CREATE TABLE output {:?}", other),
+    }
+}
+
+/// Insert rows `ids` one statement at a time in a single transaction.
+fn insert_each(db: &mut Database, table: &str, ids: impl Iterator<Item = i64>) {
+    let body = "b".repeat(100);
+    db.execute("BEGIN").unwrap();
+    for id in ids {
+        db.execute(&format!(
+            "INSERT INTO {} VALUES ({}, {}, '{}')",
+            table, id, id, body
+        ))
+        .unwrap();
+    }
+    db.execute("COMMIT").unwrap();
+}
+
+#[test]
+fn test_ascending_inserts_fill_data_and_index_pages() {
+    let dir = TempDir::new().unwrap();
+    let mut db = Database::create(&dir.path().join("test.db"), &test_key()).unwrap();
+    for table in ["ascending", "descending", "shuffled"] {
+        db.execute(&format!(
+            "CREATE TABLE {} (id BIGINT PRIMARY KEY, k BIGINT, body VARCHAR)",
+            table
+        ))
+        .unwrap();
+        db.execute(&format!("CREATE INDEX idx_{}_k ON {} (k)", table, table))
+            .unwrap();
+    }
+    insert_each(&mut db, "ascending", 0..2000);
+    insert_each(&mut db, "descending", (0..2000).rev());
+    insert_each(&mut db, "shuffled", (0..2000).map(|i| (i * 7919) % 2000));
+
+    let (asc_data, asc_index) = pages(&mut db, "ascending");
+    let (desc_data, desc_index) = pages(&mut db, "descending");
+    let (shuffled_data, _) = pages(&mut db, "shuffled");
+    // Splits below the right edge still go down the middle.
+    assert!(
+        asc_data * 10 <= desc_data * 6,
+        "{} vs {}",
+        asc_data,
+        desc_data
+    );
+    assert!(
+        asc_index * 10 <= desc_index * 7,
+        "{} vs {}",
+        asc_index,
+        desc_index
+    );
+    assert!(shuffled_data < desc_data * 11 / 10);
+
+    // Appended pages are as full as bulk-loaded ones.
+    db.execute("OPTIMIZE TABLE ascending").unwrap();
+    assert!(pages(&mut db, "ascending").0 >= asc_data - 1);
+    for table in ["ascending", "descending", "shuffled"] {
+        let rows = db
+            .query(&format!("SELECT COUNT(*) FROM {} WHERE k >= 0", table))
+            .unwrap();
+        assert_eq!(integer(rows[0].get("COUNT(*)")), 2000);
+    }
+    let check = db.query("CHECK TABLE shuffled").unwrap();
+    assert!(check
+        .iter()
+        .all(|r| r.get("Msg_type") != Some(&Value::Varchar("error".into()))));
+}
+
+#[test]
+fn test_fill_factor_leaves_room_in_leaves() {
+    let dir = TempDir::new().unwrap();
+    let mut db = Database::create(&dir.path().join("test.db"), &test_key()).unwrap();
+    db.execute("CREATE TABLE full (id BIGINT PRIMARY KEY, k BIGINT, body VARCHAR)")
+        .unwrap();
+    db.execute(
+        "CREATE TABLE spaced (id BIGINT PRIMARY KEY, k BIGINT, body VARCHAR) \
+         WITH (fill_factor = 50)",
+    )
+    .unwrap();
+    insert_each(&mut db, "full", 0..2000);
+    insert_each(&mut db, "spaced", 0..2000);
+    let full = pages(&mut db, "full").0;
+    let spaced = pages(&mut db, "spaced").0;
+    assert!(
+        spaced >= full * 18 / 10 && spaced <= full * 22 / 10,
+        "{} vs {}",
+        spaced,
+        full
+    );
+
+    // Bulk loads and OPTIMIZE TABLE use the table's fill factor too.
+    db.execute("OPTIMIZE TABLE spaced").unwrap();
+    assert!(pages(&mut db, "spaced").0 >= full * 18 / 10);
+    db.execute(
+        "CREATE TABLE loaded (id BIGINT PRIMARY KEY, k BIGINT, body VARCHAR) \
+         WITH (fill_factor = 50)",
+    )
+    .unwrap();
+    let body = "b".repeat(100);
+    let values: Vec<String> = (0..2000)
+        .map(|i| format!("({}, {}, '{}')", i, i, body))
+        .collect();
+    db.execute(&format!("INSERT INTO loaded VALUES {}", values.join(", ")))
+        .unwrap();
+    assert!(pages(&mut db, "loaded").0 >= full * 18 / 10);
+
+    // An index keeps its own fill factor.
+    db.execute("CREATE INDEX idx_full_k ON full (k)").unwrap();
+    let packed_index = pages(&mut db, "full").1;
+    db.execute("DROP INDEX idx_full_k").unwrap();
+    db.execute("CREATE INDEX idx_full_k ON full (k) WITH (fill_factor = 50)")
+        .unwrap();
+    let spaced_index = pages(&mut db, "full").1;
+    assert!(
+        spaced_index > packed_index * 3 / 2,
+        "{} vs {}",
+        spaced_index,
+        packed_index
+    );
+}
+
+#[test]
+fn test_fill_factor_is_stored_and_rendered() {
+    let dir = TempDir::new().unwrap();
+    let path = dir.path().join("test.db");
+    {
+        let mut db = Database::create(&path, &test_key()).unwrap();
+        db.execute(
+            "CREATE TABLE t (id BIGINT PRIMARY KEY, k BIGINT, \
+             KEY idx_k (k) WITH (fill_factor = 60)) WITH (fill_factor = 90)",
+        )
+        .unwrap();
+        db.execute("CREATE UNIQUE INDEX idx_id_k ON t (id, k) WITH (fill_factor = 75)")
+            .unwrap();
+        db.execute("CREATE TABLE plain (id BIGINT PRIMARY KEY)")
+            .unwrap();
+    }
+
+    let mut db = Database::open(&path, &test_key()).unwrap();
+    let ddl = show_create(&mut db, "t");
+    assert!(ddl.ends_with(") WITH (fill_factor = 90)"), "{}", ddl);
+    assert!(
+        ddl.contains("KEY idx_k (k) WITH (fill_factor = 60)"),
+        "{}",
+        ddl
+    );
+    assert!(
+        ddl.contains("UNIQUE KEY idx_id_k (id, k) WITH (fill_factor = 75)"),
+        "{}",
+        ddl
+    );
+    assert!(!show_create(&mut db, "plain").contains("WITH"));
+
+    // SHOW CREATE TABLE output recreates the same options.
+    db.execute("DROP TABLE t").unwrap();
+    db.execute(&ddl).unwrap();
+    assert_eq!(show_create(&mut db, "t"), ddl);
+}
+
+#[test]
+fn test_fill_factor_bounds_are_validated() {
+    let dir = TempDir::new().unwrap();
+    let mut db = Database::create(&dir.path().join("test.db"), &test_key()).unwrap();
+    db.execute("CREATE TABLE t (id BIGINT PRIMARY KEY, k BIGINT)")
+        .unwrap();
+    for sql in [
+        "CREATE TABLE u (id BIGINT PRIMARY KEY) WITH (fill_factor = 0)",
+        "CREATE TABLE u (id BIGINT PRIMARY KEY) WITH (fill_factor = 101)",
+        "CREATE INDEX idx_k ON t (k) WITH (fill_factor = 5)",
+        "CREATE INDEX idx_k ON t (k) WITH (fillfactor = 50)",
+    ] {
+        let err = db.execute(sql).unwrap_err().to_string();
+        assert!(
+            err.contains("fill_factor must be between 10 and 100")
+                || err.contains("Unknown storage option"),
+            "{}: {}",
+            sql,
+            err
+        );
+    }
+    db.execute("CREATE INDEX idx_k ON t (k) WITH (fill_factor = 10)")
+        .unwrap();
+    db.execute("CREATE TABLE u (id BIGINT PRIMARY KEY) WITH (fill_factor = 100)")
+        .unwrap();
+}
@@ -0,0 +1,229 @@
+#![cfg(feature = "test-utils")]
+/// DOUBLE values survive a text round trip bit for bit, non-finite values are
+/// rejected, and comparisons agree with and without an index.
+use murodb::crypto::aead::MasterKey;
+use murodb::{format_float, Database, Value};
+use std::path::Path;
+use tempfile::TempDir;
+
+fn test_key() -> MasterKey {
+    MasterKey::new([0x42u8; 32])
+}
+
+fn setup(db_path: &Path) -> Database {
+    let mut db = Database::create(db_path, &test_key()).unwrap();
+    db.execute("CREATE TABLE t (id BIGINT PRIMARY KEY, d DOUBLE)")
+        .unwrap();
+    db
+}
+
+fn doubles(db: &mut Database, sql: &str) -> Vec<f64> {
+    db.query(sql)
+        .unwrap()
+        .iter()
+        .map(|row| match &row.values[0].1 {
+            Value::Float(n) => *n,
+            other => panic!("unexpected value {:?}", other),
+        })
+        .collect()
+}
+
+fn ids(db: &mut Database, sql: &str) -> Vec<i64> {
+    db.query(sql)
+        .unwrap()
+        .iter()
+        .map(|row| match row.get("id") {
+            Some(Value::Integer(n)) => *n,
+            other => panic!("unexpected id {:?}", other),
+        })
+        .collect()
+}
+
+const SAMPLES: &[f64] = &[
+    0.0,
+    -0.0,
+    0.1,
+    1.0 / 3.0,
+    -2.5,
+    123456789.125,
+    9007199254740993.0,
+    1e16,
+    1e300,
+    -1.5e-7,
+    f64::MAX,
+    f64::MIN,
+    f64::MIN_POSITIVE,
+    5e-324,
+    f64::from_bits(0x000f_ffff_ffff_ffff),
+];
+
+#[test]
+fn test_format_float_parses_back_exactly() {
+    for &n in SAMPLES {
+        let text = format_float(n);
+        let parsed: f64 = text.parse().unwrap();
+        assert_eq!(parsed.to_bits(), n.to_bits(), "{}", text);
+    }
+    assert_eq!(format_float(0.1), "0.1");
+    assert_eq!(format_float(2.0), "2");
+    assert_eq!(format_float(1e300), "1e300");
+    assert_eq!(format_float(-1.5e-7), "-1.5e-7");
+}
+
+#[test]
+fn test_dump_and_restore_is_bit_exact() {
+    let dir = TempDir::new().unwrap();
+    let mut db = setup(&dir.path().join("a.db"));
+    for (i, &n) in SAMPLES.iter().enumerate() {
+        db.execute(&format!(
+            "INSERT INTO t VALUES ({}, {})",
+            i,
+            Value::Float(n)
+        ))
+        .unwrap();
+    }
+    let stored = doubles(&mut db, "SELECT d FROM t ORDER BY id");
+
+    // Dump as SQL text, restore into a fresh database.
+    let mut restored_db = setup(&dir.path().join("b.db"));
+    for (i, n) in stored.iter().enumerate() {
+        restored_db
+            .execute(&format!(
+                "INSERT INTO t VALUES ({}, {})",
+                i,
+                Value::Float(*n)
+            ))
+            .unwrap();
+    }
+    let restored = doubles(&mut restored_db, "SELECT d FROM t ORDER BY id");
+
+    assert_eq!(stored.len(), SAMPLES.len());
+    for ((s, r), n) in stored.iter().zip(&restored).zip(SAMPLES) {
+        assert_eq!(s.to_bits(), r.to_bits(), "{:e}", n);
+        // -0.0 is stored as 0.0; everything else is unchanged.
+        assert_eq!(*s, *n);
+    }
+}
+
+#[test]
+fn test_show_create_table_default_round_trips() {
+    let dir = TempDir::new().unwrap();
+    let mut db = Database::create(&dir.path().join("a.db"), &test_key()).unwrap();
+    db.execute(
+        "CREATE TABLE t (id BIGINT PRIMARY KEY, \
+         a DOUBLE DEFAULT 1e300, b DOUBLE DEFAULT 0.1, c DOUBLE DEFAULT 2.0)",
+    )
+    .unwrap();
+    let show = |db: &mut Database| match &db.query("SHOW CREATE TABLE t").unwrap()[0].values[1].1 {
+        Value::Varchar(s) => s.clone(),
+        other => panic!("unexpected value {:?}", other),
+    };
+    let ddl = show(&mut db);
+
+    let mut copy = Database::create(&dir.path().join("b.db"), &test_key()).unwrap();
+    copy.execute(&ddl).unwrap();
+    assert_eq!(show(&mut copy), ddl);
+    copy.execute("INSERT INTO t (id) VALUES (1)").unwrap();
+    assert_eq!(doubles(&mut copy, "SELECT a FROM t"), vec![1e300]);
+    assert_eq!(doubles(&mut copy, "SELECT b FROM t"), vec![0.1]);
+    assert_eq!(doubles(&mut copy, "SELECT c FROM t"), vec![2.0]);
+}
+
+#[test]
+fn test_scientific_notation_literals_and_strings() {
+    let dir = TempDir::new().unwrap();
+    let mut db = setup(&dir.path().join("test.db"));
+    db.execute("INSERT INTO t VALUES (1, 1.5e3), (2, 2E-3), (3, '-4.25e+2')")
+        .unwrap();
+    assert_eq!(
+        doubles(&mut db, "SELECT d FROM t ORDER BY id"),
+        vec![1500.0, 0.002, -425.0]
+    );
+    assert_eq!(
+        doubles(&mut db, "SELECT CAST('1e-5' AS DOUBLE)"),
+        vec![1e-5]
+    );
+}
+
+#[test]
+fn test_non_finite_values_rejected() {
+    let dir = TempDir::new().unwrap();
+    let mut db = setup(&dir.path().join("test.db"));
+    for s in ["nan", "NaN", "inf", "-infinity"] {
+        assert!(
+            db.execute(&format!("INSERT INTO t VALUES (1, '{}')", s))
+                .is_err(),
+            "{}",
+            s
+        );
+        assert!(db
+            .query(&format!("SELECT CAST('{}' AS DOUBLE)", s))
+            .is_err());
+    }
+    assert!(db.query("SELECT 1e308 * 10").is_err());
+    assert!(db.query("SELECT POWER(10.0, 400)").is_err());
+    assert!(db.execute("INSERT INTO t VALUES (1, 1e400)").is_err());
+    assert!(db.query("SELECT * FROM t").unwrap().is_empty());
+}
+
+#[test]
+fn test_order_by_matches_numeric_order() {
+    let dir = TempDir::new().unwrap();
+    let mut db = Database::create(&dir.path().join("test.db"), &test_key()).unwrap();
+    db.execute("CREATE TABLE k (d DOUBLE PRIMARY KEY)").unwrap();
+    for &n in SAMPLES
+        .iter()
+        .filter(|n| n.to_bits() != (-0.0f64).to_bits())
+    {
+        db.execute(&format!("INSERT INTO k VALUES ({})", Value::Float(n)))
+            .unwrap();
+    }
+    let mut expected: Vec<f64> = SAMPLES
+        .iter()
+        .copied()
+        .filter(|n| n.to_bits() != (-0.0f64).to_bits())
+        .collect();
+    expected.sort_by(|a, b| a.partial_cmp(b).unwrap());
+    assert_eq!(doubles(&mut db, "SELECT d FROM k ORDER BY d"), expected);
+
+    // -0.0 and 0.0 are the same key.
+    assert!(db.execute("INSERT INTO k VALUES (-0.0)").is_err());
+}
+
+#[test]
+fn test_integer_comparison_is_exact_with_and_without_index() {
+    let dir = TempDir::new().unwrap();
+    let mut db = setup(&dir.path().join("test.db"));
+    // 2^53 and 2^53 + 2 are the doubles on either side of 2^53 + 1.
+    db.execute("INSERT INTO t VALUES (1, 9007199254740992.0), (2, 9007199254740994.0)")
+        .unwrap();
+    let queries = [
+        ("d = 9007199254740993", vec![]),
+        ("d < 9007199254740993", vec![1]),
+        ("d > 9007199254740993", vec![2]),
+        ("d = 9007199254740992", vec![1]),
+    ];
+    for (cond, want) in &queries {
+        assert_eq!(
+            &ids(
+                &mut db,
+                &format!("SELECT id FROM t WHERE {} ORDER BY id", cond)
+            ),
+            want,
+            "{}",
+            cond
+        );
+    }
+    db.execute("CREATE INDEX idx_d ON t (d)").unwrap();
+    for (cond, want) in &queries {
+        assert_eq!(
+            &ids(
+                &mut db,
+                &format!("SELECT id FROM t WHERE {} ORDER BY id", cond)
+            ),
+            want,
+            "indexed: {}",
+            cond
+        );
+    }
+}
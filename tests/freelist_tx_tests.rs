@@ -0,0 +1,216 @@
+#![cfg(feature = "test-utils")]
+/// Transactional page freeing: pages freed by a transaction reach the
+/// freelist only when it commits, a failing statement inside a transaction is
+/// undone without leaking or freeing pages, and the freelist chain itself is
+/// rewritten in place so commits never orphan pages.
+use murodb::crypto::aead::MasterKey;
+use murodb::{Database, Row, Value};
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+use tempfile::TempDir;
+
+fn test_key() -> MasterKey {
+    MasterKey::new([0x42u8; 32])
+}
+
+fn text(row: &Row, column: &str) -> String {
+    match row.get(column) {
+        Some(Value::Varchar(s)) => s.clone(),
+        other => panic!("unexpected {} {:?}", column, other),
+    }
+}
+
+/// Run `verify_integrity`, fail on any error or leak warning, and return the
+/// file's page count.
+fn checked_page_count(db: &mut Database) -> u64 {
+    let rows = db.verify_integrity().unwrap();
+    for row in &rows {
+        assert_eq!(text(row, "status"), "ok", "{:?}", row);
+    }
+    let summary = rows
+        .iter()
+        .rev()
+        .find(|r| text(r, "object") == "freelist")
+        .map(|r| text(r, "detail"))
+        .unwrap();
+    summary.rsplit(' ').next().unwrap().parse().unwrap()
+}
+
+#[test]
+fn test_random_create_drop_with_rollbacks_does_not_leak() {
+    let dir = TempDir::new().unwrap();
+    let path = dir.path().join("test.db");
+    let mut db = Database::create(&path, &test_key()).unwrap();
+    let mut rng = StdRng::seed_from_u64(0xF4EE);
+    let mut committed: Vec<u32> = Vec::new();
+    let mut page_counts = Vec::new();
+    let mut next_name = 0u32;
+
+    for round in 0..120u32 {
+        db.execute("BEGIN").unwrap();
+        let mut tables = committed.clone();
+        for _ in 0..rng.gen_range(1..4) {
+            if tables.len() < 4 && rng.gen_bool(0.6) {
+                let name = next_name;
+                next_name += 1;
+                db.execute(&format!(
+                    "CREATE TABLE t{} (id BIGINT PRIMARY KEY, tag VARCHAR(20), body TEXT)",
+                    name
+                ))
+                .unwrap();
+                db.execute(&format!(
+                    "CREATE INDEX idx_t{}_tag ON t{} (tag)",
+                    name, name
+                ))
+                .unwrap();
+                for id in 0..rng.gen_range(20..200) {
+                    // Some bodies spill into overflow pages.
+                    let len = if rng.gen_bool(0.1) { 6000 } else { 300 };
+                    db.execute(&format!(
+                        "INSERT INTO t{} VALUES ({}, 'tag{}', '{}')",
+                        name,
+                        id,
+                        id % 7,
+                        "x".repeat(len)
+                    ))
+                    .unwrap();
+                }
+                tables.push(name);
+            } else if !tables.is_empty() {
+                let victim = tables.remove(rng.gen_range(0..tables.len()));
+                db.execute(&format!("DROP TABLE t{}", victim)).unwrap();
+            }
+            if let Some(&name) = tables.first() {
+                // A statement that fails partway through is undone on its own.
+                db.execute(&format!(
+                    "INSERT INTO t{} VALUES (100000, 'a', 'b'), (100001, 'a', 'b'), (0, 'dup', 'dup')",
+                    name
+                ))
+                .unwrap_err();
+            }
+        }
+        if rng.gen_bool(0.5) {
+            db.execute("COMMIT").unwrap();
+            committed = tables;
+        } else {
+            db.execute("ROLLBACK").unwrap();
+        }
+
+        if round % 20 == 19 {
+            page_counts.push(checked_page_count(&mut db));
+            drop(db);
+            db = Database::open(&path, &test_key()).unwrap();
+            checked_page_count(&mut db);
+        }
+    }
+
+    // At most four tables of bounded size are live, so once the freelist has
+    // absorbed the churn the file stops growing.
+    let early = page_counts[..3].iter().max().unwrap();
+    let late = page_counts[3..].iter().max().unwrap();
+    assert!(late <= &(early * 3 / 2), "{:?}", page_counts);
+    for name in committed {
+        let rows = db
+            .query(&format!(
+                "SELECT COUNT(*) FROM t{} WHERE id >= 100000",
+                name
+            ))
+            .unwrap();
+        assert_eq!(rows[0].get("COUNT(*)"), Some(&Value::Integer(0)));
+    }
+}
+
+#[test]
+fn test_failed_statement_in_transaction_keeps_earlier_work() {
+    let dir = TempDir::new().unwrap();
+    let path = dir.path().join("test.db");
+    let mut db = Database::create(&path, &test_key()).unwrap();
+    db.execute("CREATE TABLE t (id BIGINT PRIMARY KEY, body TEXT)")
+        .unwrap();
+    db.execute("CREATE TABLE big (id BIGINT PRIMARY KEY, body TEXT)")
+        .unwrap();
+    db.execute("INSERT INTO t VALUES (5, 'five')").unwrap();
+
+    db.execute("BEGIN").unwrap();
+    db.execute("INSERT INTO t VALUES (6, 'six')").unwrap();
+    for id in 0..50 {
+        db.execute(&format!(
+            "INSERT INTO big VALUES ({}, '{}')",
+            id,
+            "y".repeat(3000)
+        ))
+        .unwrap();
+    }
+    db.execute("INSERT INTO t VALUES (1, 'one'), (2, 'two'), (5, 'dup')")
+        .unwrap_err();
+    db.execute("DROP TABLE missing").unwrap_err();
+    db.execute("COMMIT").unwrap();
+
+    let ids: Vec<_> = db
+        .query("SELECT id FROM t ORDER BY id")
+        .unwrap()
+        .iter()
+        .map(|r| r.get("id").cloned())
+        .collect();
+    assert_eq!(ids, vec![Some(Value::Integer(5)), Some(Value::Integer(6))]);
+    let rows = db.query("SELECT COUNT(*) FROM big").unwrap();
+    assert_eq!(rows[0].get("COUNT(*)"), Some(&Value::Integer(50)));
+    checked_page_count(&mut db);
+
+    // Dropping the table in a rolled-back transaction frees nothing.
+    let before = checked_page_count(&mut db);
+    db.execute("BEGIN").unwrap();
+    db.execute("DROP TABLE big").unwrap();
+    db.execute("ROLLBACK").unwrap();
+    assert_eq!(checked_page_count(&mut db), before);
+    let rows = db.query("SELECT COUNT(*) FROM big").unwrap();
+    assert_eq!(rows[0].get("COUNT(*)"), Some(&Value::Integer(50)));
+}
+
+#[test]
+fn test_large_freelist_chain_is_reused_across_commits() {
+    let dir = TempDir::new().unwrap();
+    let path = dir.path().join("test.db");
+    let mut db = Database::create(&path, &test_key()).unwrap();
+    db.execute("CREATE TABLE big (id BIGINT PRIMARY KEY, body VARCHAR(2000))")
+        .unwrap();
+    db.execute("BEGIN").unwrap();
+    for id in 0..3000 {
+        db.execute(&format!(
+            "INSERT INTO big VALUES ({}, '{}')",
+            id,
+            "z".repeat(1500)
+        ))
+        .unwrap();
+    }
+    db.execute("COMMIT").unwrap();
+    // Freeing well over one freelist page's worth of pages.
+    db.execute("DROP TABLE big").unwrap();
+    let after_drop = checked_page_count(&mut db);
+
+    db.execute("CREATE TABLE small (id BIGINT PRIMARY KEY)")
+        .unwrap();
+    for id in 0..50 {
+        db.execute(&format!("INSERT INTO small VALUES ({})", id))
+            .unwrap();
+    }
+    assert_eq!(checked_page_count(&mut db), after_drop);
+
+    // Reusing the free pages shrinks the chain; its spare pages become free.
+    db.execute("CREATE TABLE refill (id BIGINT PRIMARY KEY, body VARCHAR(2000))")
+        .unwrap();
+    db.execute("BEGIN").unwrap();
+    for id in 0..2500 {
+        db.execute(&format!(
+            "INSERT INTO refill VALUES ({}, '{}')",
+            id,
+            "w".repeat(1500)
+        ))
+        .unwrap();
+    }
+    db.execute("COMMIT").unwrap();
+    assert_eq!(checked_page_count(&mut db), after_drop);
+    drop(db);
+    let mut db = Database::open(&path, &test_key()).unwrap();
+    assert_eq!(checked_page_count(&mut db), after_drop);
+}
@@ -1,5 +1,6 @@
 #![cfg(feature = "test-utils")]
 use murodb::crypto::aead::MasterKey;
+use murodb::crypto::hmac_util::TermKey;
 #[cfg(feature = "test-utils")]
 use murodb::error::MuroError;
 use murodb::fts::index::{FtsIndex, FtsPendingOp};
@@ -16,8 +17,8 @@ fn test_key() -> MasterKey {
     MasterKey::new([0x42u8; 32])
 }
 
-fn term_key() -> [u8; 32] {
-    [0x55u8; 32]
+fn term_key() -> TermKey {
+    TermKey::new([0x55u8; 32])
 }
 
 fn assert_overflow_doc_searchable(db_path: &std::path::Path, fts_root: u64) {
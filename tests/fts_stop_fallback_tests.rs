@@ -0,0 +1,146 @@
+#![cfg(feature = "test-utils")]
+/// Stop-filter fallback for NATURAL queries whose every ngram is filtered.
+use murodb::crypto::aead::MasterKey;
+use murodb::{Database, ExecResult, Session, Value};
+use std::path::Path;
+use tempfile::TempDir;
+
+fn test_key() -> MasterKey {
+    MasterKey::new([0x42u8; 32])
+}
+
+/// Every document contains 東京, so at a 50% threshold it is a stop ngram.
+fn setup(db_path: &Path, docs: usize, options: &str) -> Session {
+    let mut session = Database::create(db_path, &test_key())
+        .unwrap()
+        .into_session();
+    session
+        .execute("CREATE TABLE t (id BIGINT PRIMARY KEY, body TEXT)")
+        .unwrap();
+    session.execute("BEGIN").unwrap();
+    let tails = ["タワー", "駅", "大学", "ドーム"];
+    for i in 1..=docs {
+        session
+            .execute(&format!(
+                "INSERT INTO t VALUES ({}, '東京{}')",
+                i,
+                tails[(i - 1) % tails.len()]
+            ))
+            .unwrap();
+    }
+    session.execute("COMMIT").unwrap();
+    session
+        .execute(&format!(
+            "CREATE FULLTEXT INDEX ft_body ON t(body) WITH PARSER ngram OPTIONS (n=2, normalize='nfkc', stop_filter=on, stop_df_ratio_ppm=500000{})",
+            options
+        ))
+        .unwrap();
+    session
+}
+
+fn natural_sql(term: &str) -> String {
+    format!(
+        "SELECT id FROM t WHERE MATCH(body) AGAINST('{}' IN NATURAL LANGUAGE MODE) > 0 ORDER BY id",
+        term
+    )
+}
+
+fn ids(session: &mut Session, sql: &str) -> Vec<i64> {
+    match session.execute(sql).unwrap() {
+        ExecResult::Rows(rows) => rows
+            .iter()
+            .map(|row| match row.get("id") {
+                Some(Value::Integer(id)) => *id,
+                other => panic!("unexpected id {:?}", other),
+            })
+            .collect(),
+        other => panic!("expected rows, got {:?}", other),
+    }
+}
+
+fn warning_messages(session: &mut Session) -> Vec<String> {
+    match session.execute("SHOW WARNINGS").unwrap() {
+        ExecResult::Rows(rows) => rows
+            .iter()
+            .map(|row| match row.get("message") {
+                Some(Value::Varchar(m)) => m.clone(),
+                other => panic!("unexpected message {:?}", other),
+            })
+            .collect(),
+        other => panic!("expected rows, got {:?}", other),
+    }
+}
+
+#[test]
+fn test_all_stop_filtered_query_rescans_by_default() {
+    let dir = TempDir::new().unwrap();
+    let mut session = setup(&dir.path().join("test.db"), 4, "");
+    assert_eq!(ids(&mut session, &natural_sql("東京")), vec![1, 2, 3, 4]);
+    assert!(warning_messages(&mut session).is_empty());
+}
+
+#[test]
+fn test_all_stop_filtered_query_empty_with_warning() {
+    let dir = TempDir::new().unwrap();
+    let db_path = dir.path().join("test.db");
+    let session = setup(&db_path, 4, ", stop_fallback='empty_with_warning'");
+    drop(session);
+
+    // The mode is part of the index definition and survives a reopen.
+    let mut session = Database::open(&db_path, &test_key())
+        .unwrap()
+        .into_session();
+    assert!(ids(&mut session, &natural_sql("東京")).is_empty());
+    let warnings = warning_messages(&mut session);
+    assert_eq!(warnings.len(), 1, "{:?}", warnings);
+    assert!(
+        warnings[0].contains("every query ngram is stop-filtered by index 'ft_body'"),
+        "{}",
+        warnings[0]
+    );
+
+    // A statement without the fallback clears the report.
+    assert_eq!(ids(&mut session, &natural_sql("東京駅")), vec![2]);
+    assert!(warning_messages(&mut session).is_empty());
+}
+
+#[test]
+fn test_rescan_cap_aborts_on_large_corpus() {
+    let dir = TempDir::new().unwrap();
+    let mut session = setup(
+        &dir.path().join("test.db"),
+        200,
+        ", stop_fallback_max_docs=40",
+    );
+    let err = session.execute(&natural_sql("東京")).unwrap_err();
+    assert!(
+        err.to_string().contains("stop_fallback_max_docs=40"),
+        "{}",
+        err
+    );
+
+    // The aborted statement leaves the session usable.
+    assert_eq!(ids(&mut session, &natural_sql("東京タワー")).len(), 50);
+    session
+        .execute("INSERT INTO t VALUES (1000, '大阪城')")
+        .unwrap();
+    assert_eq!(ids(&mut session, &natural_sql("大阪城")), vec![1000]);
+}
+
+#[test]
+fn test_partially_stop_filtered_query_is_unaffected() {
+    for options in ["", ", stop_fallback='empty_with_warning'"] {
+        let dir = TempDir::new().unwrap();
+        let mut session = setup(
+            &dir.path().join("test.db"),
+            200,
+            &format!("{}, stop_fallback_max_docs=10", options),
+        );
+        // 東京 is filtered; ドーム still selects its quarter of the corpus,
+        // and that is not a fallback, so the cap does not apply.
+        let hits = ids(&mut session, &natural_sql("東京ドーム"));
+        assert_eq!(hits.len(), 50, "{}", options);
+        assert!(hits.iter().all(|id| id % 4 == 0));
+        assert!(warning_messages(&mut session).is_empty());
+    }
+}
@@ -1,5 +1,6 @@
 #![cfg(feature = "test-utils")]
 use murodb::crypto::aead::MasterKey;
+use murodb::crypto::hmac_util::TermKey;
 use murodb::fts::index::{FtsIndex, FtsPendingOp};
 use murodb::fts::query::{query_boolean, query_natural};
 use murodb::fts::snippet::fts_snippet;
@@ -11,8 +12,8 @@ fn test_key() -> MasterKey {
     MasterKey::new([0x42u8; 32])
 }
 
-fn term_key() -> [u8; 32] {
-    [0x55u8; 32]
+fn term_key() -> TermKey {
+    TermKey::new([0x55u8; 32])
 }
 
 fn setup_fts(docs: &[(u64, &str)]) -> (Pager, FtsIndex, TempDir) {
@@ -0,0 +1,133 @@
+#![cfg(feature = "test-utils")]
+/// FTS maintenance is transactional: posting-page writes go through the
+/// transaction's page store, so they are discarded with ROLLBACK and
+/// replayed from the WAL with the rest of the commit.
+use murodb::crypto::aead::MasterKey;
+use murodb::fts::index::FtsIndex;
+use murodb::schema::catalog::SystemCatalog;
+use murodb::{Database, ExecResult, Session, Value};
+use std::path::Path;
+use tempfile::TempDir;
+
+fn test_key() -> MasterKey {
+    MasterKey::new([0x42u8; 32])
+}
+
+fn setup(db_path: &Path) -> Session {
+    let mut session = Database::create(db_path, &test_key())
+        .unwrap()
+        .into_session();
+    session
+        .execute("CREATE TABLE docs (id BIGINT PRIMARY KEY, body TEXT)")
+        .unwrap();
+    session
+        .execute(
+            "CREATE FULLTEXT INDEX ft_body ON docs(body) WITH PARSER ngram OPTIONS (n=2, normalize='nfkc')",
+        )
+        .unwrap();
+    session
+        .execute("INSERT INTO docs VALUES (1, '東京タワーの夜景')")
+        .unwrap();
+    session
+}
+
+fn matching_ids(session: &mut Session, term: &str) -> Vec<i64> {
+    let sql = format!(
+        "SELECT id FROM docs WHERE MATCH(body) AGAINST('{}' IN BOOLEAN MODE) > 0 ORDER BY id",
+        term
+    );
+    match session.execute(&sql).unwrap() {
+        ExecResult::Rows(rows) => rows
+            .iter()
+            .map(|row| match row.get("id") {
+                Some(Value::Integer(id)) => *id,
+                other => panic!("unexpected id {:?}", other),
+            })
+            .collect(),
+        other => panic!("expected rows, got {:?}", other),
+    }
+}
+
+fn total_docs(session: &mut Session) -> u64 {
+    let catalog = SystemCatalog::open(session.catalog().root_page_id());
+    let idx = catalog
+        .get_index(session.pager_mut(), "ft_body")
+        .unwrap()
+        .expect("index not found");
+    let term_key = session.pager().fts_term_key().unwrap();
+    FtsIndex::open(idx.btree_root, term_key)
+        .get_stats(session.pager_mut())
+        .unwrap()
+        .total_docs
+}
+
+#[test]
+fn test_rollback_discards_fts_postings() {
+    let dir = TempDir::new().unwrap();
+    let mut session = setup(&dir.path().join("test.db"));
+    assert_eq!(total_docs(&mut session), 1);
+
+    session.execute("BEGIN").unwrap();
+    session
+        .execute("INSERT INTO docs VALUES (2, '京都の金閣寺')")
+        .unwrap();
+    // Visible inside the transaction that wrote it, while the committed
+    // stats page is untouched.
+    assert_eq!(matching_ids(&mut session, "金閣寺"), vec![2]);
+    assert_eq!(total_docs(&mut session), 1);
+    session.execute("ROLLBACK").unwrap();
+
+    assert!(matching_ids(&mut session, "金閣寺").is_empty());
+    assert_eq!(total_docs(&mut session), 1);
+    assert_eq!(matching_ids(&mut session, "東京"), vec![1]);
+}
+
+#[test]
+fn test_rollback_to_savepoint_discards_later_fts_postings() {
+    let dir = TempDir::new().unwrap();
+    let mut session = setup(&dir.path().join("test.db"));
+
+    session.execute("BEGIN").unwrap();
+    session
+        .execute("INSERT INTO docs VALUES (2, '京都の金閣寺')")
+        .unwrap();
+    session.execute("SAVEPOINT sp").unwrap();
+    session
+        .execute("INSERT INTO docs VALUES (3, '奈良の大仏')")
+        .unwrap();
+    session.execute("DELETE FROM docs WHERE id = 1").unwrap();
+    session.execute("ROLLBACK TO SAVEPOINT sp").unwrap();
+    session.execute("COMMIT").unwrap();
+
+    assert_eq!(matching_ids(&mut session, "金閣寺"), vec![2]);
+    assert!(matching_ids(&mut session, "大仏").is_empty());
+    assert_eq!(matching_ids(&mut session, "東京"), vec![1]);
+    assert_eq!(total_docs(&mut session), 2);
+}
+
+#[test]
+fn test_committed_fts_postings_replay_from_wal() {
+    let dir = TempDir::new().unwrap();
+    let db_path = dir.path().join("test.db");
+    let mut session = setup(&db_path);
+    session.execute("SET checkpoint_tx_threshold = 0").unwrap();
+    let before_commit = std::fs::read(&db_path).unwrap();
+
+    session.execute("BEGIN").unwrap();
+    session
+        .execute("INSERT INTO docs VALUES (2, '京都の金閣寺')")
+        .unwrap();
+    session.execute("COMMIT").unwrap();
+    drop(session);
+
+    // Crash before checkpoint: the WAL holds the commit, but none of its
+    // page writes reached the data file.
+    std::fs::write(&db_path, before_commit).unwrap();
+
+    let mut session = Database::open(&db_path, &test_key())
+        .unwrap()
+        .into_session();
+    assert_eq!(matching_ids(&mut session, "金閣寺"), vec![2]);
+    assert_eq!(matching_ids(&mut session, "東京"), vec![1]);
+    assert_eq!(total_docs(&mut session), 2);
+}
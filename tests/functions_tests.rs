@@ -723,3 +723,153 @@ fn test_nested_functions() {
         Value::Varchar("HELLO WORLD".into())
     );
 }
+
+// ── Table-driven builtin coverage ──
+
+#[test]
+fn test_builtin_functions_table() {
+    let (mut p, mut c, _d) = setup();
+    // (expression, expected string form; None = NULL)
+    let cases: &[(&str, Option<&str>)] = &[
+        ("LENGTH('héllo')", Some("6")),
+        ("LENGTH('')", Some("0")),
+        ("LENGTH(12345)", Some("5")),
+        ("LENGTH(X'00FF')", Some("2")),
+        ("LENGTH(NULL)", None),
+        ("CHAR_LENGTH('héllo')", Some("5")),
+        ("CHAR_LENGTH(-12)", Some("3")),
+        ("CHAR_LENGTH(NULL)", None),
+        ("SUBSTR('hello', 2)", Some("ello")),
+        ("SUBSTR('hello', 2, 3)", Some("ell")),
+        ("SUBSTR('hello', -3)", Some("llo")),
+        ("SUBSTR('hello', -3, 2)", Some("ll")),
+        ("SUBSTR('hello', -6)", Some("")),
+        ("SUBSTR('hello', 0)", Some("")),
+        ("SUBSTR('hello', 9)", Some("")),
+        ("SUBSTR('hello', 2, -1)", Some("")),
+        ("SUBSTRING('héllo', 2, 1)", Some("é")),
+        ("SUBSTR(NULL, 1)", None),
+        ("SUBSTR('hello', NULL)", None),
+        ("CONCAT('a', 1, 'b')", Some("a1b")),
+        ("CONCAT('a', NULL)", None),
+        ("CONCAT_WS(',', 'a', NULL, 'b')", Some("a,b")),
+        ("CONCAT_WS(',', NULL, NULL)", Some("")),
+        ("CONCAT_WS(NULL, 'a', 'b')", None),
+        ("CONCAT_WS('-', 1, 2.5)", Some("1-2.5")),
+        ("UPPER('héllo')", Some("HÉLLO")),
+        ("LOWER('ABC')", Some("abc")),
+        ("UPPER(NULL)", None),
+        ("TRIM('  x  ')", Some("x")),
+        ("LTRIM('  x  ')", Some("x  ")),
+        ("RTRIM('  x  ')", Some("  x")),
+        ("TRIM(NULL)", None),
+        ("REPLACE('aaa', 'a', 'bb')", Some("bbbbbb")),
+        ("REPLACE('abc', '', 'x')", Some("abc")),
+        ("REPLACE('abc', NULL, 'x')", None),
+        ("HEX('abc')", Some("616263")),
+        ("HEX('')", Some("")),
+        ("HEX(255)", Some("FF")),
+        ("HEX(-1)", Some("FFFFFFFFFFFFFFFF")),
+        ("HEX(X'00ff')", Some("00FF")),
+        ("HEX(NULL)", None),
+        ("ABS(-5)", Some("5")),
+        ("ABS(-2.5)", Some("2.5")),
+        ("ABS(NULL)", None),
+        ("ROUND(2.5)", Some("3")),
+        ("ROUND(-2.5)", Some("-3")),
+        ("ROUND(1.2345, 2)", Some("1.23")),
+        ("ROUND(1250, -2)", Some("1300")),
+        ("ROUND(-1250, -2)", Some("-1300")),
+        ("ROUND(1234.5, -2)", Some("1200")),
+        ("ROUND(7, 2)", Some("7")),
+        ("ROUND(NULL)", None),
+        ("ROUND(1.5, NULL)", None),
+        ("FLOOR(-1.5)", Some("-2")),
+        ("FLOOR(7)", Some("7")),
+        ("CEIL(-1.5)", Some("-1")),
+        ("CEILING(1.1)", Some("2")),
+        ("FLOOR(NULL)", None),
+        ("MOD(10, 3)", Some("1")),
+        ("MOD(-10, 3)", Some("-1")),
+        ("MOD(-9223372036854775807 - 1, -1)", Some("0")),
+        ("MOD(NULL, 3)", None),
+        ("COALESCE(NULL, NULL, 'x')", Some("x")),
+        ("COALESCE(NULL, NULL)", None),
+        ("IFNULL(NULL, 0)", Some("0")),
+        ("IFNULL('a', 0)", Some("a")),
+        ("NULLIF(1, 1)", None),
+        ("NULLIF(1, 2)", Some("1")),
+        ("NULLIF(NULL, 1)", None),
+    ];
+    for (expr, expected) in cases {
+        let got = query_one(&mut p, &mut c, &format!("SELECT {}", expr));
+        match expected {
+            None => assert!(got.is_null(), "{} = {:?}, expected NULL", expr, got),
+            Some(s) => assert_eq!(got.to_string(), *s, "{} = {:?}", expr, got),
+        }
+    }
+
+    // Type mismatches are errors rather than silent coercions.
+    for expr in [
+        "ABS('x')",
+        "MOD('a', 2)",
+        "ROUND(1.5, 'x')",
+        "SUBSTR('a', 'b')",
+    ] {
+        assert!(
+            execute(&format!("SELECT {}", expr), &mut p, &mut c).is_err(),
+            "{} should fail",
+            expr
+        );
+    }
+    assert!(execute("SELECT ABS(-9223372036854775807 - 1)", &mut p, &mut c).is_err());
+}
+
+#[test]
+fn test_builtin_functions_in_group_by_and_check() {
+    let dir = TempDir::new().unwrap();
+    let db_path = dir.path().join("test.db");
+    let catalog_root;
+    {
+        let mut p = Pager::create(&db_path, &test_key()).unwrap();
+        let mut c = SystemCatalog::create(&mut p).unwrap();
+        exec(
+            &mut p,
+            &mut c,
+            "CREATE TABLE t (id BIGINT PRIMARY KEY CHECK (ABS(id) < 100), \
+             name VARCHAR CHECK (CHAR_LENGTH(TRIM(name)) > 0))",
+        );
+        exec(&mut p, &mut c, "INSERT INTO t VALUES (1, 'apple')");
+        exec(&mut p, &mut c, "INSERT INTO t VALUES (2, 'Avocado')");
+        exec(&mut p, &mut c, "INSERT INTO t VALUES (3, 'banana')");
+        assert!(execute("INSERT INTO t VALUES (4, '   ')", &mut p, &mut c).is_err());
+
+        let rows = query_rows(
+            &mut p,
+            &mut c,
+            "SELECT UPPER(SUBSTR(name, 1, 1)), COUNT(*) FROM t \
+             GROUP BY UPPER(SUBSTR(name, 1, 1)) ORDER BY 1",
+        );
+        assert_eq!(rows.len(), 2);
+        assert_eq!(rows[0].values[0].1, Value::Varchar("A".into()));
+        assert_eq!(rows[0].values[1].1, Value::Integer(2));
+        assert_eq!(rows[1].values[0].1, Value::Varchar("B".into()));
+
+        let rows = query_rows(
+            &mut p,
+            &mut c,
+            "SELECT id FROM t WHERE CONCAT_WS('-', HEX(id), LOWER(name)) = '3-banana'",
+        );
+        assert_eq!(rows.len(), 1);
+        assert_eq!(rows[0].values[0].1, Value::Integer(3));
+        catalog_root = c.root_page_id();
+        p.flush_meta().unwrap();
+    }
+
+    // After reopening, the CHECK expression is parsed again from its SQL text.
+    let mut p = Pager::open(&db_path, &test_key()).unwrap();
+    let mut c = SystemCatalog::open(catalog_root);
+    assert!(execute("INSERT INTO t VALUES (5, '')", &mut p, &mut c).is_err());
+    assert!(execute("INSERT INTO t VALUES (-200, 'x')", &mut p, &mut c).is_err());
+    exec(&mut p, &mut c, "INSERT INTO t VALUES (-99, 'x')");
+}
@@ -0,0 +1,248 @@
+#![cfg(feature = "test-utils")]
+/// GROUP BY, DISTINCT and COUNT(DISTINCT) keep values of different types
+/// apart even when they look alike (`'abc'` and `X'616263'`, `1` and `'1'`),
+/// group numbers by value, and put all NULLs in one group. Grouping never
+/// loses or duplicates rows: the group counts add up to the row count.
+use murodb::crypto::aead::MasterKey;
+use murodb::{Database, Value};
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+use std::collections::HashSet;
+use tempfile::TempDir;
+
+fn test_key() -> MasterKey {
+    MasterKey::new([0x42u8; 32])
+}
+
+/// `t.kind` picks which column `v` yields: 0 the VARCHAR, 1 the VARBINARY,
+/// 2 the INT, 3 the DOUBLE, 4 the DECIMAL, 5 NULL.
+const V: &str = "CASE kind WHEN 0 THEN s WHEN 1 THEN b WHEN 2 THEN n \
+                 WHEN 3 THEN f WHEN 4 THEN d ELSE NULL END";
+
+fn setup(dir: &TempDir) -> Database {
+    let mut db = Database::create(&dir.path().join("test.db"), &test_key()).unwrap();
+    db.execute(
+        "CREATE TABLE t (id BIGINT PRIMARY KEY, kind INT, s VARCHAR(10), b VARBINARY(10), \
+         n INT, f DOUBLE, d DECIMAL(6,2))",
+    )
+    .unwrap();
+    db
+}
+
+fn insert(db: &mut Database, id: i64, kind: i64, text: &str, number: &str) {
+    let hex: String = text.bytes().map(|b| format!("{:02X}", b)).collect();
+    db.execute(&format!(
+        "INSERT INTO t VALUES ({}, {}, '{}', X'{}', {}, {}, {})",
+        id,
+        kind,
+        text,
+        hex,
+        number.split('.').next().unwrap(),
+        number,
+        number
+    ))
+    .unwrap();
+}
+
+fn rows(db: &mut Database, sql: &str) -> Vec<Vec<Value>> {
+    db.query(sql)
+        .unwrap()
+        .into_iter()
+        .map(|r| r.values.into_iter().map(|(_, v)| v).collect())
+        .collect()
+}
+
+fn count(db: &mut Database, sql: &str) -> i64 {
+    match rows(db, sql)[..] {
+        [ref row] => match row[..] {
+            [Value::Integer(n)] => n,
+            _ => panic!("{}: {:?}", sql, row),
+        },
+        ref other => panic!("{}: {:?}", sql, other),
+    }
+}
+
+#[test]
+fn test_lookalike_values_of_different_types_stay_apart() {
+    let dir = TempDir::new().unwrap();
+    let mut db = setup(&dir);
+    for (id, kind) in (0..3).enumerate() {
+        // 'abc' and X'616263'; '1', X'31' and 1.
+        insert(&mut db, 2 * id as i64, kind, "abc", "1");
+        insert(&mut db, 2 * id as i64 + 1, kind, "1", "1");
+    }
+    let groups = rows(
+        &mut db,
+        &format!(
+            "SELECT {v} AS v, COUNT(*) FROM t GROUP BY {v} ORDER BY MIN(id)",
+            v = V
+        ),
+    );
+    assert_eq!(
+        groups,
+        vec![
+            vec![Value::Varchar("abc".into()), Value::Integer(1)],
+            vec![Value::Varchar("1".into()), Value::Integer(1)],
+            vec![Value::Varbinary(b"abc".to_vec()), Value::Integer(1)],
+            vec![Value::Varbinary(b"1".to_vec()), Value::Integer(1)],
+            vec![Value::Integer(1), Value::Integer(2)],
+        ]
+    );
+    assert_eq!(
+        rows(&mut db, &format!("SELECT DISTINCT {} FROM t", V)).len(),
+        5
+    );
+    assert_eq!(
+        count(&mut db, &format!("SELECT COUNT(DISTINCT {}) FROM t", V)),
+        5
+    );
+}
+
+#[test]
+fn test_numbers_group_by_value() {
+    let dir = TempDir::new().unwrap();
+    let mut db = setup(&dir);
+    let mut id = 0;
+    for number in ["1", "1.5", "0.1"] {
+        for kind in 2..5 {
+            insert(&mut db, id, kind, "x", number);
+            id += 1;
+        }
+    }
+    // INT 1 (twice, from 1 and 1.5), DOUBLE 1 and DECIMAL 1.00 are one
+    // group; so are DOUBLE 1.5 and DECIMAL 1.50. DOUBLE 0.1 is not exactly
+    // DECIMAL 0.10, and INT 0 is neither.
+    let groups = rows(
+        &mut db,
+        &format!("SELECT COUNT(*) FROM t GROUP BY {} ORDER BY MIN(id)", V),
+    );
+    assert_eq!(
+        groups,
+        [4, 2, 1, 1, 1]
+            .into_iter()
+            .map(|n| vec![Value::Integer(n)])
+            .collect::<Vec<_>>()
+    );
+    assert_eq!(
+        count(&mut db, &format!("SELECT COUNT(DISTINCT {}) FROM t", V)),
+        5
+    );
+}
+
+#[test]
+fn test_nulls_form_one_group() {
+    let dir = TempDir::new().unwrap();
+    let mut db = setup(&dir);
+    for id in 0..6 {
+        db.execute(&format!(
+            "INSERT INTO t (id, kind, s) VALUES ({}, {}, {})",
+            id,
+            if id % 2 == 0 { 5 } else { 0 },
+            if id < 3 { "NULL" } else { "'a'" }
+        ))
+        .unwrap();
+    }
+    assert_eq!(
+        rows(
+            &mut db,
+            "SELECT s, COUNT(*) FROM t GROUP BY s ORDER BY MIN(id)"
+        ),
+        vec![
+            vec![Value::Null, Value::Integer(3)],
+            vec![Value::Varchar("a".into()), Value::Integer(3)],
+        ]
+    );
+    assert_eq!(
+        rows(&mut db, "SELECT DISTINCT s, kind FROM t ORDER BY s, kind"),
+        vec![
+            vec![Value::Null, Value::Integer(0)],
+            vec![Value::Null, Value::Integer(5)],
+            vec![Value::Varchar("a".into()), Value::Integer(0)],
+            vec![Value::Varchar("a".into()), Value::Integer(5)],
+        ]
+    );
+    // NULL from a NULL column and from the CASE are the same key.
+    assert_eq!(
+        rows(&mut db, &format!("SELECT DISTINCT {} FROM t", V)),
+        vec![vec![Value::Null], vec![Value::Varchar("a".into())]]
+    );
+    assert_eq!(count(&mut db, "SELECT COUNT(DISTINCT s) FROM t"), 1);
+}
+
+#[test]
+fn test_union_converts_types_before_removing_duplicates() {
+    let dir = TempDir::new().unwrap();
+    let mut db = setup(&dir);
+    insert(&mut db, 1, 0, "abc", "1");
+    // The UNION column is VARBINARY, so both values are X'616263'.
+    assert_eq!(
+        rows(&mut db, "SELECT s FROM t UNION SELECT b FROM t"),
+        vec![vec![Value::Varbinary(b"abc".to_vec())]]
+    );
+    assert_eq!(
+        rows(&mut db, "SELECT s FROM t UNION ALL SELECT b FROM t").len(),
+        2
+    );
+}
+
+/// Random rows of every kind from small pools of look-alike values: however
+/// they are grouped, the groups partition the rows, and the number of groups
+/// is the number of distinct (type, value) pairs.
+#[test]
+fn test_property_grouping_preserves_row_counts() {
+    let mut rng = StdRng::seed_from_u64(0x6B0_u64);
+    for (round, budget) in [None, Some(4096), None, Some(4096)].into_iter().enumerate() {
+        let dir = TempDir::new().unwrap();
+        let mut db = setup(&dir);
+        if let Some(budget) = budget {
+            db.execute(&format!("SET aggregation_memory_budget = {}", budget))
+                .unwrap();
+        }
+        let texts = ["1", "2", "abc", "ab", ""];
+        let numbers = ["1", "2", "1.5", "0.25", "-2", "0"];
+        let mut expected = HashSet::new();
+        let total = rng.gen_range(200..600);
+        db.execute("BEGIN").unwrap();
+        for id in 0..total {
+            let kind = rng.gen_range(0..6);
+            let text = texts[rng.gen_range(0..texts.len())];
+            let number = numbers[rng.gen_range(0..numbers.len())];
+            insert(&mut db, id, kind, text, number);
+            expected.insert(match kind {
+                0 => format!("s{}", text),
+                1 => format!("b{}", text),
+                2 => format!("#{}", number.split('.').next().unwrap()),
+                3 | 4 => format!("#{}", number),
+                _ => "null".to_string(),
+            });
+        }
+        db.execute("COMMIT").unwrap();
+        let context = format!("round {}", round);
+
+        for group_by in [V.to_string(), format!("kind, {}", V), format!("{}, s", V)] {
+            let groups = rows(
+                &mut db,
+                &format!("SELECT COUNT(*) FROM t GROUP BY {}", group_by),
+            );
+            let sum: i64 = groups
+                .iter()
+                .map(|g| match g[..] {
+                    [Value::Integer(n)] => n,
+                    _ => panic!("{}: {:?}", context, g),
+                })
+                .sum();
+            assert_eq!(sum, total, "{}: GROUP BY {}", context, group_by);
+            let distinct = rows(&mut db, &format!("SELECT DISTINCT {} FROM t", group_by));
+            assert_eq!(distinct.len(), groups.len(), "{}: {}", context, group_by);
+        }
+        let groups = rows(&mut db, &format!("SELECT COUNT(*) FROM t GROUP BY {}", V));
+        assert_eq!(groups.len(), expected.len(), "{}", context);
+        let non_null = expected.len() as i64 - i64::from(expected.contains("null"));
+        assert_eq!(
+            count(&mut db, &format!("SELECT COUNT(DISTINCT {}) FROM t", V)),
+            non_null,
+            "{}",
+            context
+        );
+    }
+}
@@ -0,0 +1,245 @@
+#![cfg(feature = "test-utils")]
+/// Identifiers that differ only by letter case or Unicode normalization form
+/// are rejected by DDL; databases that already hold such names still open,
+/// resolve them by exact name, and get an integrity warning.
+use murodb::crypto::aead::MasterKey;
+use murodb::schema::catalog::SystemCatalog;
+use murodb::storage::pager::Pager;
+use murodb::{Database, Value};
+use std::path::Path;
+use tempfile::TempDir;
+
+fn test_key() -> MasterKey {
+    MasterKey::new([0x42u8; 32])
+}
+
+/// "café" spelled with U+00E9 and with "e" + U+0301.
+const PRECOMPOSED: &str = "caf\u{e9}";
+const DECOMPOSED: &str = "cafe\u{301}";
+
+fn setup(dir: &TempDir) -> Database {
+    let mut db = Database::create(&dir.path().join("test.db"), &test_key()).unwrap();
+    db.execute("CREATE TABLE Users (id BIGINT PRIMARY KEY, Email VARCHAR, body TEXT)")
+        .unwrap();
+    db.execute("CREATE INDEX idx_Email ON Users (Email)")
+        .unwrap();
+    db
+}
+
+fn expect_collision(db: &mut Database, sql: &str, name: &str, other: &str) {
+    let err = db.execute(sql).unwrap_err().to_string();
+    assert!(
+        err.contains(&format!("'{}' collides with existing", name))
+            && err.contains(&format!("'{}'", other)),
+        "{}: {}",
+        sql,
+        err
+    );
+}
+
+#[test]
+fn test_ddl_rejects_case_folded_collisions() {
+    let dir = TempDir::new().unwrap();
+    let mut db = setup(&dir);
+
+    expect_collision(
+        &mut db,
+        "CREATE TABLE t (id BIGINT PRIMARY KEY, Name VARCHAR, NAME VARCHAR)",
+        "NAME",
+        "Name",
+    );
+    expect_collision(
+        &mut db,
+        "CREATE TABLE users (id BIGINT PRIMARY KEY)",
+        "users",
+        "Users",
+    );
+    expect_collision(
+        &mut db,
+        "ALTER TABLE Users ADD COLUMN email VARCHAR",
+        "email",
+        "Email",
+    );
+    expect_collision(
+        &mut db,
+        "ALTER TABLE Users CHANGE COLUMN body EMAIL VARCHAR",
+        "EMAIL",
+        "Email",
+    );
+    expect_collision(
+        &mut db,
+        "CREATE INDEX IDX_EMAIL ON Users (body)",
+        "IDX_EMAIL",
+        "idx_Email",
+    );
+    expect_collision(
+        &mut db,
+        "CREATE FULLTEXT INDEX idx_email ON Users (body) WITH PARSER ngram",
+        "idx_email",
+        "idx_Email",
+    );
+    expect_collision(
+        &mut db,
+        "CREATE TABLE t (id BIGINT PRIMARY KEY, v INT, KEY IDX_email (v))",
+        "IDX_email",
+        "idx_Email",
+    );
+    db.execute("CREATE TABLE other (id BIGINT PRIMARY KEY)")
+        .unwrap();
+    expect_collision(&mut db, "RENAME TABLE other TO USERS", "USERS", "Users");
+
+    // Failed statements leave nothing behind.
+    assert!(db.query("SELECT * FROM t").is_err());
+
+    // An object may change the spelling of its own name.
+    db.execute("ALTER TABLE Users CHANGE COLUMN Email email VARCHAR")
+        .unwrap();
+    db.execute("RENAME TABLE Users TO users").unwrap();
+    db.execute("INSERT INTO users (id, email) VALUES (1, 'a@example.com')")
+        .unwrap();
+    assert_eq!(
+        db.query("SELECT email FROM users WHERE email = 'a@example.com'")
+            .unwrap()
+            .len(),
+        1
+    );
+
+    // Exact duplicates keep their usual error.
+    let err = db.execute("CREATE TABLE users (id INT)").unwrap_err();
+    assert!(
+        err.to_string().contains("Table 'users' already exists"),
+        "{}",
+        err
+    );
+}
+
+#[test]
+fn test_ddl_rejects_unicode_normalization_collisions() {
+    let dir = TempDir::new().unwrap();
+    let mut db = setup(&dir);
+    db.execute(&format!(
+        "CREATE TABLE {} (id BIGINT PRIMARY KEY, na\u{ef}ve INT)",
+        PRECOMPOSED
+    ))
+    .unwrap();
+
+    expect_collision(
+        &mut db,
+        &format!("CREATE TABLE {} (id BIGINT PRIMARY KEY)", DECOMPOSED),
+        DECOMPOSED,
+        PRECOMPOSED,
+    );
+    // Decomposed and upper case at once.
+    expect_collision(
+        &mut db,
+        &format!("ALTER TABLE {} ADD COLUMN NAI\u{308}VE INT", PRECOMPOSED),
+        "NAI\u{308}VE",
+        "na\u{ef}ve",
+    );
+    expect_collision(
+        &mut db,
+        &format!(
+            "CREATE TABLE t (id BIGINT PRIMARY KEY, {} INT, {} INT)",
+            PRECOMPOSED, DECOMPOSED
+        ),
+        DECOMPOSED,
+        PRECOMPOSED,
+    );
+}
+
+/// Write a catalog the current DDL would refuse: tables `Dup` / `dup`,
+/// columns `Email` / `email` in `Dup`, and indexes `ix` / `IX`.
+fn write_legacy_collisions(path: &Path) {
+    let mut pager = Pager::open(path, &test_key()).unwrap();
+    let mut catalog = SystemCatalog::open(pager.catalog_root());
+
+    let mut dup = catalog.get_table(&mut pager, "Dup").unwrap().unwrap();
+    dup.columns[2].name = "email".into();
+    catalog.update_table(&mut pager, &dup).unwrap();
+
+    let mut lower = catalog.get_table(&mut pager, "tmp").unwrap().unwrap();
+    catalog.delete_table(&mut pager, "tmp").unwrap();
+    lower.name = "dup".into();
+    catalog.update_table(&mut pager, &lower).unwrap();
+    let mut ix = catalog.get_index(&mut pager, "tmp_ix").unwrap().unwrap();
+    catalog.delete_index(&mut pager, "tmp_ix").unwrap();
+    ix.name = "IX".into();
+    ix.table_name = "dup".into();
+    catalog.update_index(&mut pager, &ix).unwrap();
+
+    pager.set_catalog_root(catalog.root_page_id());
+    pager.flush_meta().unwrap();
+}
+
+#[test]
+fn test_legacy_collisions_stay_readable_by_exact_name() {
+    let dir = TempDir::new().unwrap();
+    let path = dir.path().join("test.db");
+    {
+        let mut db = Database::create(&path, &test_key()).unwrap();
+        db.execute("CREATE TABLE Dup (id BIGINT PRIMARY KEY, Email VARCHAR, other VARCHAR)")
+            .unwrap();
+        db.execute("CREATE INDEX ix ON Dup (Email)").unwrap();
+        db.execute("INSERT INTO Dup VALUES (1, 'upper', 'lower')")
+            .unwrap();
+        db.execute("CREATE TABLE tmp (id BIGINT PRIMARY KEY, v INT)")
+            .unwrap();
+        db.execute("CREATE INDEX tmp_ix ON tmp (v)").unwrap();
+        db.execute("INSERT INTO tmp VALUES (7, 70)").unwrap();
+    }
+    write_legacy_collisions(&path);
+
+    let mut db = Database::open(&path, &test_key()).unwrap();
+    let value = |db: &mut Database, sql: &str, col: &str| -> Value {
+        db.query(sql).unwrap()[0].get(col).cloned().unwrap()
+    };
+    assert_eq!(
+        value(&mut db, "SELECT Email FROM Dup", "Email"),
+        Value::Varchar("upper".into())
+    );
+    assert_eq!(
+        value(&mut db, "SELECT email FROM Dup", "email"),
+        Value::Varchar("lower".into())
+    );
+    assert_eq!(
+        value(&mut db, "SELECT v FROM dup WHERE v = 70", "v"),
+        Value::Integer(70)
+    );
+
+    // New names still may not join the collision.
+    expect_collision(
+        &mut db,
+        "ALTER TABLE Dup ADD COLUMN EMAIL INT",
+        "EMAIL",
+        "Email",
+    );
+
+    let rows = db.verify_integrity().unwrap();
+    let warnings: Vec<(String, String)> = rows
+        .iter()
+        .filter(|row| row.get("status") == Some(&Value::Varchar("warning".into())))
+        .map(|row| match (row.get("object"), row.get("detail")) {
+            (Some(Value::Varchar(o)), Some(Value::Varchar(d))) => (o.clone(), d.clone()),
+            other => panic!("unexpected row {:?}", other),
+        })
+        .collect();
+    let has = |object: &str, detail: &str| {
+        warnings
+            .iter()
+            .any(|(o, d)| o == object && d.starts_with(detail))
+    };
+    assert!(
+        has("catalog", "tables 'Dup' and 'dup' differ only"),
+        "{:?}",
+        warnings
+    );
+    assert!(has("catalog", "indexes "), "{:?}", warnings);
+    assert!(
+        has("Dup", "columns 'Email' and 'email' differ only"),
+        "{:?}",
+        warnings
+    );
+    assert!(rows
+        .iter()
+        .all(|row| row.get("status") != Some(&Value::Varchar("error".into()))));
+}
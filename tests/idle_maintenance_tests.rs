@@ -0,0 +1,276 @@
+#![cfg(feature = "test-utils")]
+/// Idle-time maintenance: `Database::run_maintenance` and `OPTIMIZE` drain
+/// full-text GC tasks and release free pages at the end of the file, in
+/// budgeted batches that each commit on their own.
+use murodb::crypto::aead::MasterKey;
+use murodb::fault::harness::{CrashHarness, CrashWorkload};
+use murodb::fault::FaultKind;
+use murodb::{Database, MaintenanceBudget, MuroError, Value};
+use std::path::Path;
+use std::time::Duration;
+use tempfile::TempDir;
+
+fn test_key() -> MasterKey {
+    MasterKey::new([0x42u8; 32])
+}
+
+/// A table with a FULLTEXT index whose inserts queued GC tasks.
+fn create_docs(path: &Path) -> Database {
+    let mut db = Database::create_plaintext(path).unwrap();
+    db.execute("CREATE TABLE docs (id BIGINT PRIMARY KEY, body TEXT)")
+        .unwrap();
+    db.execute("CREATE FULLTEXT INDEX idx_body ON docs (body) WITH PARSER ngram")
+        .unwrap();
+    for i in 0..20 {
+        db.execute(&format!(
+            "INSERT INTO docs VALUES ({}, 'shared words {}')",
+            i, i
+        ))
+        .unwrap();
+    }
+    db
+}
+
+/// Fill and drop a table, leaving free pages at the end of the file.
+fn leave_free_tail(db: &mut Database) {
+    db.execute("CREATE TABLE scratch (id BIGINT PRIMARY KEY, pad TEXT)")
+        .unwrap();
+    for i in 0..200 {
+        db.execute(&format!(
+            "INSERT INTO scratch VALUES ({}, '{}')",
+            i,
+            "x".repeat(1500)
+        ))
+        .unwrap();
+    }
+    db.execute("DROP TABLE scratch").unwrap();
+}
+
+fn assert_integrity_clean(db: &mut Database) {
+    for row in db.verify_integrity().unwrap() {
+        assert_eq!(
+            row.get("status"),
+            Some(&Value::Varchar("ok".to_string())),
+            "{:?}",
+            row.values
+        );
+    }
+}
+
+fn matching_docs(db: &mut Database) -> usize {
+    db.query("SELECT id FROM docs WHERE MATCH(body) AGAINST('shared' IN BOOLEAN MODE) > 0")
+        .unwrap()
+        .len()
+}
+
+#[test]
+fn test_run_maintenance_drains_fulltext_gc_tasks() {
+    let dir = TempDir::new().unwrap();
+    let mut db = create_docs(&dir.path().join("test.db"));
+
+    let report = db
+        .run_maintenance(MaintenanceBudget {
+            max_duration: Duration::from_secs(60),
+            ..MaintenanceBudget::default()
+        })
+        .unwrap();
+    assert!(report.fts_gc_tasks > 0, "{:?}", report);
+    assert!(report.pages_written > 0, "{:?}", report);
+    assert_eq!(report.fts_gc_tasks_remaining, 0, "{:?}", report);
+    assert!(report.is_complete());
+    assert_eq!(matching_docs(&mut db), 20);
+
+    // Nothing left: one batch that finds no work and commits nothing.
+    let report = db.run_maintenance(MaintenanceBudget::default()).unwrap();
+    assert_eq!(report.batches, 1);
+    assert_eq!(report.fts_gc_tasks, 0);
+    assert_eq!(report.pages_written, 0);
+    assert!(report.is_complete());
+}
+
+#[test]
+fn test_run_maintenance_stops_at_budget_and_reports_remaining_work() {
+    let dir = TempDir::new().unwrap();
+    let mut db = create_docs(&dir.path().join("test.db"));
+    let budget = MaintenanceBudget {
+        max_duration: Duration::ZERO,
+        batch_size: 1,
+        ..MaintenanceBudget::default()
+    };
+
+    let report = db.run_maintenance(budget).unwrap();
+    assert_eq!(report.batches, 1);
+    assert_eq!(report.fts_gc_tasks, 1);
+    assert!(report.fts_gc_tasks_remaining > 0, "{:?}", report);
+    assert!(!report.is_complete());
+
+    let mut remaining = report.fts_gc_tasks_remaining;
+    while remaining > 0 {
+        let report = db.run_maintenance(budget).unwrap();
+        assert_eq!(report.fts_gc_tasks_remaining, remaining - 1);
+        remaining = report.fts_gc_tasks_remaining;
+    }
+    assert_eq!(matching_docs(&mut db), 20);
+}
+
+#[test]
+fn test_run_maintenance_releases_free_tail_pages() {
+    let dir = TempDir::new().unwrap();
+    let path = dir.path().join("test.db");
+    let mut db = create_docs(&path);
+    leave_free_tail(&mut db);
+    let len_before = std::fs::metadata(&path).unwrap().len();
+
+    let kept = db
+        .run_maintenance(MaintenanceBudget {
+            release_free_tail: false,
+            ..MaintenanceBudget::default()
+        })
+        .unwrap();
+    assert_eq!(kept.pages_released, 0);
+    assert_eq!(std::fs::metadata(&path).unwrap().len(), len_before);
+
+    let report = db
+        .run_maintenance(MaintenanceBudget {
+            max_duration: Duration::from_secs(60),
+            max_pages: u64::MAX,
+            ..MaintenanceBudget::default()
+        })
+        .unwrap();
+    assert!(report.pages_released > 50, "{:?}", report);
+    assert_eq!(report.free_tail_pages_remaining, 0, "{:?}", report);
+    let len_after = std::fs::metadata(&path).unwrap().len();
+    assert!(
+        len_after + 50 * 4096 < len_before,
+        "{} -> {}",
+        len_before,
+        len_after
+    );
+    assert_integrity_clean(&mut db);
+
+    // Pages past the new end are handed out again as the file grows.
+    db.execute("CREATE TABLE regrown (id BIGINT PRIMARY KEY, pad TEXT)")
+        .unwrap();
+    for i in 0..100 {
+        db.execute(&format!(
+            "INSERT INTO regrown VALUES ({}, '{}')",
+            i,
+            "y".repeat(1500)
+        ))
+        .unwrap();
+    }
+    db.execute("INSERT INTO docs VALUES (100, 'shared again')")
+        .unwrap();
+    drop(db);
+    let mut db = Database::open_plaintext(&path).unwrap();
+    assert_eq!(matching_docs(&mut db), 21);
+    assert_integrity_clean(&mut db);
+}
+
+#[test]
+fn test_file_longer_than_its_pages_reopens() {
+    // A crash after the release commit but before the file is shortened.
+    let dir = TempDir::new().unwrap();
+    let path = dir.path().join("test.db");
+    let mut db = create_docs(&path);
+    leave_free_tail(&mut db);
+    db.run_maintenance(MaintenanceBudget {
+        max_duration: Duration::from_secs(60),
+        max_pages: u64::MAX,
+        ..MaintenanceBudget::default()
+    })
+    .unwrap();
+    drop(db);
+    let len = std::fs::metadata(&path).unwrap().len();
+    std::fs::OpenOptions::new()
+        .write(true)
+        .open(&path)
+        .unwrap()
+        .set_len(len + 64 * 4096)
+        .unwrap();
+
+    let mut db = Database::open_plaintext(&path).unwrap();
+    assert_integrity_clean(&mut db);
+    db.execute("INSERT INTO docs VALUES (500, 'shared after reopen')")
+        .unwrap();
+    assert_eq!(matching_docs(&mut db), 21);
+}
+
+#[test]
+fn test_optimize_statement_runs_one_pass() {
+    let dir = TempDir::new().unwrap();
+    let mut db = create_docs(&dir.path().join("test.db"));
+    leave_free_tail(&mut db);
+
+    let result = db.execute("OPTIMIZE").unwrap();
+    let murodb::ExecResult::Rows(rows) = result else {
+        panic!("expected rows, got {:?}", result);
+    };
+    assert_eq!(rows.len(), 1);
+    let integer = |name: &str| match rows[0].get(name) {
+        Some(Value::Integer(n)) => *n,
+        other => panic!("{}: {:?}", name, other),
+    };
+    assert!(integer("Batches") >= 1);
+    assert!(integer("Fts_gc_tasks") > 0);
+    assert!(integer("Fts_gc_tasks_remaining") >= 0);
+    assert!(integer("Free_tail_pages_remaining") >= 0);
+
+    db.execute("BEGIN").unwrap();
+    let err = db.execute("OPTIMIZE").unwrap_err();
+    assert!(matches!(err, MuroError::Transaction(_)), "{:?}", err);
+    db.execute("ROLLBACK").unwrap();
+
+    // In a script it runs outside the implicit transaction.
+    db.execute_batch("INSERT INTO docs VALUES (900, 'shared'); OPTIMIZE")
+        .unwrap();
+    assert_eq!(matching_docs(&mut db), 21);
+}
+
+#[test]
+fn test_maintenance_is_rejected_on_read_only_handles() {
+    let dir = TempDir::new().unwrap();
+    let path = dir.path().join("test.db");
+    {
+        let mut db = Database::create(&path, &test_key()).unwrap();
+        db.execute("CREATE TABLE t (id BIGINT PRIMARY KEY)")
+            .unwrap();
+    }
+    let mut db = Database::open_read_only(&path, &test_key()).unwrap();
+    assert!(matches!(
+        db.run_maintenance(MaintenanceBudget::default()),
+        Err(MuroError::ReadOnly)
+    ));
+    assert!(matches!(db.execute("OPTIMIZE"), Err(MuroError::ReadOnly)));
+}
+
+#[test]
+fn test_crash_during_optimize_recovers_to_a_step_boundary() {
+    let mut steps = vec![
+        "CREATE TABLE docs (id BIGINT PRIMARY KEY, body TEXT)".to_string(),
+        "CREATE FULLTEXT INDEX ft_body ON docs (body) WITH PARSER ngram".to_string(),
+        "CREATE TABLE scratch (id BIGINT PRIMARY KEY, pad TEXT)".to_string(),
+    ];
+    for i in 0..12 {
+        steps.push(format!(
+            "INSERT INTO docs VALUES ({}, 'shared words {}'); INSERT INTO scratch VALUES ({}, '{}')",
+            i,
+            i,
+            i,
+            "x".repeat(3000)
+        ));
+    }
+    steps.push("DROP TABLE scratch".to_string());
+    steps.push("OPTIMIZE".to_string());
+    steps.push("INSERT INTO docs VALUES (100, 'shared after optimize')".to_string());
+
+    let dir = TempDir::new().unwrap();
+    let harness =
+        CrashHarness::prepare(dir.path(), &test_key(), CrashWorkload::new(steps)).unwrap();
+    for kind in [FaultKind::Fail, FaultKind::TornWrite] {
+        for point in harness.points(kind).into_iter().step_by(3) {
+            let report = harness.run(point).unwrap();
+            assert_eq!(report.strict_steps, report.permissive_steps, "{:?}", report);
+        }
+    }
+}
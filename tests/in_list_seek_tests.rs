@@ -0,0 +1,223 @@
+#![cfg(feature = "test-utils")]
+/// `column IN (...)` on a single-column primary key or index is planned as one
+/// seek per distinct list value. NOT IN and lists past
+/// `in_list_seek_max_items` scan the table; either way the rows match a scan
+/// with every index ignored.
+use murodb::crypto::aead::MasterKey;
+use murodb::{Database, Value};
+use tempfile::TempDir;
+
+fn test_key() -> MasterKey {
+    MasterKey::new([0x42u8; 32])
+}
+
+fn setup(dir: &TempDir) -> Database {
+    let mut db = Database::create(&dir.path().join("test.db"), &test_key()).unwrap();
+    db.execute("CREATE TABLE t (id BIGINT PRIMARY KEY, grp INT, name VARCHAR(20))")
+        .unwrap();
+    db.execute("CREATE INDEX idx_grp ON t (grp)").unwrap();
+    db.execute("BEGIN").unwrap();
+    for i in 0..500 {
+        db.execute(&format!(
+            "INSERT INTO t VALUES ({}, {}, 'n{}')",
+            i,
+            i % 50,
+            i
+        ))
+        .unwrap();
+    }
+    db.execute("COMMIT").unwrap();
+    db
+}
+
+/// EXPLAIN `type`, `key` and `Extra` of `sql`.
+fn explain(db: &mut Database, sql: &str) -> (String, Option<String>, String) {
+    let rows = db.query(&format!("EXPLAIN {}", sql)).unwrap();
+    let text = |name: &str| match rows[0].get(name) {
+        Some(Value::Varchar(s)) => Some(s.clone()),
+        _ => None,
+    };
+    (
+        text("type").unwrap(),
+        text("key"),
+        text("Extra").unwrap_or_default(),
+    )
+}
+
+fn ids(db: &mut Database, sql: &str) -> Vec<i64> {
+    db.query(sql)
+        .unwrap()
+        .iter()
+        .map(|row| row.get_i64("id").unwrap().unwrap())
+        .collect()
+}
+
+/// Ids of `SELECT id FROM t <where>` through the planned access path and
+/// through a scan of the table.
+fn planned_and_scanned(db: &mut Database, where_clause: &str) -> (Vec<i64>, Vec<i64>) {
+    let planned = ids(
+        db,
+        &format!("SELECT id FROM t WHERE {} ORDER BY id", where_clause),
+    );
+    let max_items = db.in_list_seek_max_items();
+    db.set_in_list_seek_max_items(0);
+    let scanned = ids(
+        db,
+        &format!(
+            "SELECT id FROM t IGNORE INDEX (idx_grp) WHERE {} ORDER BY id",
+            where_clause
+        ),
+    );
+    db.set_in_list_seek_max_items(max_items);
+    (planned, scanned)
+}
+
+#[test]
+fn test_pk_in_list_seeks_each_distinct_value() {
+    let dir = TempDir::new().unwrap();
+    let mut db = setup(&dir);
+    let sql = "SELECT id FROM t WHERE id IN (200, 7, 9999, 7, NULL, 42)";
+
+    let (access, key, extra) = explain(&mut db, sql);
+    assert_eq!(access, "range");
+    assert_eq!(key.as_deref(), Some("PRIMARY"));
+    assert!(extra.contains("IndexSeek (IN, n=5)"), "{}", extra);
+
+    // Duplicates collapse, NULL and missing keys match nothing, and the seeks
+    // run in key order.
+    assert_eq!(ids(&mut db, sql), vec![7, 42, 200]);
+    let (planned, scanned) = planned_and_scanned(&mut db, "id IN (200, 7, 9999, 7, NULL, 42)");
+    assert_eq!(planned, scanned);
+
+    // Other conjuncts still filter the rows the seeks return.
+    assert_eq!(
+        ids(
+            &mut db,
+            "SELECT id FROM t WHERE id IN (1, 2, 3, 4) AND grp > 2"
+        ),
+        vec![3, 4]
+    );
+    // A list of only NULLs matches nothing.
+    assert!(ids(&mut db, "SELECT id FROM t WHERE id IN (NULL)").is_empty());
+}
+
+#[test]
+fn test_index_in_list_seeks_each_value() {
+    let dir = TempDir::new().unwrap();
+    let mut db = setup(&dir);
+    let sql = "SELECT id FROM t WHERE grp IN (3, 48, 3, 77)";
+
+    let (access, key, extra) = explain(&mut db, sql);
+    assert_eq!(access, "range");
+    assert_eq!(key.as_deref(), Some("idx_grp"));
+    assert!(extra.contains("IndexSeek (IN, n=4)"), "{}", extra);
+
+    let (planned, scanned) = planned_and_scanned(&mut db, "grp IN (3, 48, 3, 77)");
+    assert_eq!(planned.len(), 20);
+    assert_eq!(planned, scanned);
+
+    // Aggregates read the same rows.
+    let rows = db
+        .query("SELECT COUNT(*), SUM(grp) FROM t WHERE grp IN (3, 48)")
+        .unwrap();
+    assert_eq!(rows[0].get_at(0), Some(&Value::Integer(20)));
+    assert_eq!(rows[0].get_at(1), Some(&Value::Integer(10 * 3 + 10 * 48)));
+}
+
+#[test]
+fn test_index_seek_over_more_rows_than_a_fetch_chunk() {
+    let dir = TempDir::new().unwrap();
+    let mut db = setup(&dir);
+    let list = (0..30)
+        .map(|g| g.to_string())
+        .collect::<Vec<_>>()
+        .join(", ");
+    let where_clause = format!("grp IN ({})", list);
+
+    // 300 matching rows are fetched in more than one chunk of primary keys.
+    let (planned, scanned) = planned_and_scanned(&mut db, &where_clause);
+    assert_eq!(planned.len(), 300);
+    assert_eq!(planned, scanned);
+    let rows = db
+        .query(&format!("SELECT COUNT(*) FROM t WHERE {}", where_clause))
+        .unwrap();
+    assert_eq!(rows[0].get_at(0), Some(&Value::Integer(300)));
+
+    db.execute(&format!("DELETE FROM t WHERE {}", where_clause))
+        .unwrap();
+    let rows = db.query("SELECT COUNT(*) FROM t").unwrap();
+    assert_eq!(rows[0].get_at(0), Some(&Value::Integer(200)));
+}
+
+#[test]
+fn test_not_in_and_long_lists_scan() {
+    let dir = TempDir::new().unwrap();
+    let mut db = setup(&dir);
+
+    let (access, _, extra) = explain(&mut db, "SELECT id FROM t WHERE id NOT IN (1, 2)");
+    assert_eq!(access, "ALL");
+    assert!(!extra.contains("IndexSeek"), "{}", extra);
+    assert_eq!(
+        ids(&mut db, "SELECT id FROM t WHERE id NOT IN (1, 2)").len(),
+        498
+    );
+
+    // Past the configured length the list is tested per row instead.
+    assert_eq!(db.in_list_seek_max_items(), 10_000);
+    db.execute("SET in_list_seek_max_items = 3").unwrap();
+    assert_eq!(db.in_list_seek_max_items(), 3);
+    let (access, _, _) = explain(&mut db, "SELECT id FROM t WHERE id IN (1, 2, 3, 4)");
+    assert_eq!(access, "ALL");
+    let (access, _, _) = explain(&mut db, "SELECT id FROM t WHERE id IN (1, 2, 3)");
+    assert_eq!(access, "range");
+    assert_eq!(
+        ids(&mut db, "SELECT id FROM t WHERE id IN (1, 2, 3, 4)"),
+        vec![1, 2, 3, 4]
+    );
+    db.set_in_list_seek_max_items(0);
+    let (access, _, _) = explain(&mut db, "SELECT id FROM t WHERE id IN (1)");
+    assert_eq!(access, "ALL");
+    db.set_in_list_seek_max_items(10_000);
+
+    // A list over the default limit scans and matches through a hash set,
+    // with the same results, including NOT IN.
+    let long: Vec<String> = (0..10_001).map(|i| (i * 7).to_string()).collect();
+    let where_clause = format!("id IN ({})", long.join(", "));
+    let (access, _, _) = explain(&mut db, &format!("SELECT id FROM t WHERE {}", where_clause));
+    assert_eq!(access, "ALL");
+    let (planned, scanned) = planned_and_scanned(&mut db, &where_clause);
+    assert_eq!(planned, (0..500).filter(|i| i % 7 == 0).collect::<Vec<_>>());
+    assert_eq!(planned, scanned);
+    let not_in = ids(
+        &mut db,
+        &format!("SELECT id FROM t WHERE id NOT IN ({})", long.join(", ")),
+    );
+    assert_eq!(not_in.len(), 500 - planned.len());
+
+    let names: Vec<String> = (0..40).map(|i| format!("'n{}'", i * 3)).collect();
+    let rows = db
+        .query(&format!(
+            "SELECT COUNT(*) FROM t WHERE name IN ({})",
+            names.join(", ")
+        ))
+        .unwrap();
+    assert_eq!(rows[0].get_at(0), Some(&Value::Integer(40)));
+}
+
+#[test]
+fn test_update_and_delete_with_in_lists() {
+    let dir = TempDir::new().unwrap();
+    let mut db = setup(&dir);
+
+    db.execute("UPDATE t SET name = 'hit' WHERE id IN (5, 10, 10, 600)")
+        .unwrap();
+    assert_eq!(
+        ids(&mut db, "SELECT id FROM t WHERE name = 'hit' ORDER BY id"),
+        vec![5, 10]
+    );
+
+    db.execute("DELETE FROM t WHERE grp IN (1, 2)").unwrap();
+    let rows = db.query("SELECT COUNT(*) FROM t").unwrap();
+    assert_eq!(rows[0].get_at(0), Some(&Value::Integer(480)));
+    assert!(ids(&mut db, "SELECT id FROM t WHERE grp IN (1, 2)").is_empty());
+}
@@ -0,0 +1,254 @@
+#![cfg(feature = "test-utils")]
+/// Incremental backups copy only pages written since the previous backup,
+/// and a full backup plus a chain of them restores the live database. Gaps,
+/// reordering and tampered pages or manifests are refused.
+use murodb::crypto::aead::MasterKey;
+use murodb::storage::pager::page_generations_path;
+use murodb::{BackupCursor, Database, IncrementalManifest, MuroError, Value};
+use std::path::{Path, PathBuf};
+use tempfile::TempDir;
+
+fn test_key() -> MasterKey {
+    MasterKey::new([0x42u8; 32])
+}
+
+/// Every table's rows, in primary key order.
+fn dump(db: &mut Database) -> Vec<String> {
+    let tables: Vec<String> = db
+        .query("SHOW TABLES")
+        .unwrap()
+        .iter()
+        .map(|row| match row.get_at(0) {
+            Some(Value::Varchar(name)) => name.clone(),
+            other => panic!("unexpected table name {:?}", other),
+        })
+        .collect();
+    let mut out = Vec::new();
+    for table in tables {
+        for row in db
+            .query(&format!("SELECT * FROM {} ORDER BY 1", table))
+            .unwrap()
+        {
+            out.push(format!("{}: {:?}", table, row.values));
+        }
+    }
+    out
+}
+
+/// A database with enough rows that one change touches few of its pages.
+fn populated(dir: &Path) -> (Database, PathBuf) {
+    let path = dir.join("live.db");
+    let mut db = Database::create(&path, &test_key()).unwrap();
+    db.execute("CREATE TABLE t (id BIGINT PRIMARY KEY, v VARCHAR(200))")
+        .unwrap();
+    db.execute("BEGIN").unwrap();
+    for i in 0..2000 {
+        db.execute(&format!(
+            "INSERT INTO t VALUES ({}, '{}')",
+            i,
+            "x".repeat(150)
+        ))
+        .unwrap();
+    }
+    db.execute("COMMIT").unwrap();
+    (db, path)
+}
+
+/// Base backup plus three incrementals, each after a different change.
+fn chain(dir: &Path, db: &mut Database) -> (PathBuf, Vec<(PathBuf, IncrementalManifest)>) {
+    let base = dir.join("base.db");
+    let mut cursor = db.backup(&base).unwrap();
+    let changes = [
+        "UPDATE t SET v = 'changed' WHERE id = 1500",
+        "CREATE TABLE u (id BIGINT PRIMARY KEY, n INT); INSERT INTO u VALUES (1, 10), (2, 20)",
+        "DELETE FROM t WHERE id < 100; INSERT INTO t VALUES (5000, 'late'); UPDATE u SET n = 21 WHERE id = 2",
+    ];
+    let mut links = Vec::new();
+    for (i, sql) in changes.iter().enumerate() {
+        db.execute_batch(sql).unwrap();
+        let inc_dir = dir.join(format!("inc{}", i + 1));
+        let (manifest, next) = db.backup_incremental(&inc_dir, cursor).unwrap();
+        assert_eq!(manifest.since, cursor);
+        assert_eq!(manifest.cursor, next);
+        assert!(next.generation() > cursor.generation());
+        cursor = next;
+        links.push((inc_dir, manifest));
+    }
+    (base, links)
+}
+
+fn restore(base: &Path, links: &[&Path], target: &Path) -> murodb::Result<()> {
+    Database::restore_from_incrementals(base, links, target)
+}
+
+#[test]
+fn test_full_plus_three_incrementals_restore_the_live_database() {
+    let dir = TempDir::new().unwrap();
+    let (mut db, _) = populated(dir.path());
+    let (base, links) = chain(dir.path(), &mut db);
+
+    let base_pages = std::fs::metadata(&base).unwrap().len() / 4096;
+    for (_, manifest) in &links {
+        assert!(!manifest.pages.is_empty());
+        assert!(
+            (manifest.pages.len() as u64) < base_pages / 4,
+            "{} of {} pages",
+            manifest.pages.len(),
+            base_pages
+        );
+    }
+
+    let target = dir.path().join("restored.db");
+    let dirs: Vec<&Path> = links.iter().map(|(d, _)| d.as_path()).collect();
+    restore(&base, &dirs, &target).unwrap();
+    let mut restored = Database::open(&target, &test_key()).unwrap();
+    assert_eq!(dump(&mut restored), dump(&mut db));
+    assert_eq!(
+        restored.query("SELECT v FROM t WHERE id = 1500").unwrap()[0]
+            .get_str("v")
+            .unwrap(),
+        Some("changed")
+    );
+
+    // A prefix of the chain restores the state of its last link.
+    let partial = dir.path().join("partial.db");
+    restore(&base, &dirs[..1], &partial).unwrap();
+    let mut partial = Database::open(&partial, &test_key()).unwrap();
+    assert!(partial.query("SELECT * FROM u").is_err());
+    let rows = partial.query("SELECT COUNT(*) FROM t").unwrap();
+    assert_eq!(rows[0].get_i64("COUNT(*)").unwrap(), Some(2000));
+
+    // The restored database keeps working and backs up in turn.
+    restored.execute("INSERT INTO u VALUES (3, 30)").unwrap();
+    let cursor = restored.backup(dir.path().join("again.db")).unwrap();
+    restored.execute("INSERT INTO u VALUES (4, 40)").unwrap();
+    restored
+        .backup_incremental(dir.path().join("again1"), cursor)
+        .unwrap();
+}
+
+#[test]
+fn test_incremental_without_changes_and_cursor_text() {
+    let dir = TempDir::new().unwrap();
+    let (mut db, _) = populated(dir.path());
+    let base = dir.path().join("base.db");
+    let cursor = db.backup(&base).unwrap();
+
+    // Cursors survive being stored as text.
+    let cursor: BackupCursor = cursor.to_string().parse().unwrap();
+    let (manifest, next) = db
+        .backup_incremental(dir.path().join("inc1"), cursor)
+        .unwrap();
+    assert!(manifest.pages.is_empty());
+
+    // The destination of an earlier incremental is not reused.
+    assert!(db
+        .backup_incremental(dir.path().join("inc1"), next)
+        .is_err());
+
+    let target = dir.path().join("restored.db");
+    restore(&base, &[&dir.path().join("inc1")], &target).unwrap();
+    let mut restored = Database::open(&target, &test_key()).unwrap();
+    assert_eq!(dump(&mut restored), dump(&mut db));
+}
+
+#[test]
+fn test_gaps_and_reordering_are_refused() {
+    let dir = TempDir::new().unwrap();
+    let (mut db, _) = populated(dir.path());
+    let (base, links) = chain(dir.path(), &mut db);
+    let [inc1, inc2, inc3] = [0, 1, 2].map(|i| links[i].0.as_path());
+    let target = dir.path().join("restored.db");
+
+    for chain in [
+        vec![inc1, inc3],
+        vec![inc2, inc3],
+        vec![inc1, inc3, inc2],
+        vec![inc1, inc1],
+    ] {
+        match restore(&base, &chain, &target) {
+            Err(MuroError::Execution(msg)) => {
+                assert!(msg.contains("does not continue"), "{}", msg)
+            }
+            other => panic!("expected a gap error for {:?}, got {:?}", chain, other),
+        }
+        assert!(!target.exists());
+    }
+
+    // Another full backup is not the base of this chain.
+    let other_base = dir.path().join("other.db");
+    db.backup(&other_base).unwrap();
+    assert!(restore(&other_base, &[inc1, inc2, inc3], &target).is_err());
+    assert!(!target.exists());
+}
+
+#[test]
+fn test_tampered_pages_and_manifests_are_refused() {
+    let dir = TempDir::new().unwrap();
+    let (mut db, _) = populated(dir.path());
+    let (base, links) = chain(dir.path(), &mut db);
+    let dirs: Vec<&Path> = links.iter().map(|(d, _)| d.as_path()).collect();
+    let target = dir.path().join("restored.db");
+
+    let expect_corruption = |what: &str| match restore(&base, &dirs, &target) {
+        Err(MuroError::Corruption(msg)) => {
+            assert!(!target.exists(), "{}", what);
+            msg
+        }
+        other => panic!("{}: expected corruption, got {:?}", what, other),
+    };
+
+    // A flipped bit in a copied page.
+    let pages = links[1].0.join("pages");
+    let original = std::fs::read(&pages).unwrap();
+    let mut flipped = original.clone();
+    flipped[100] ^= 1;
+    std::fs::write(&pages, &flipped).unwrap();
+    let msg = expect_corruption("page bit flip");
+    assert!(
+        msg.contains("does not match its manifest checksum"),
+        "{}",
+        msg
+    );
+
+    // A truncated page file.
+    std::fs::write(&pages, &original[..original.len() - 1]).unwrap();
+    expect_corruption("truncated pages");
+    std::fs::write(&pages, &original).unwrap();
+
+    // An edited manifest line.
+    let manifest = links[2].0.join("manifest");
+    let text = std::fs::read_to_string(&manifest).unwrap();
+    let edited = text.replacen("page\t", "page\t9", 1);
+    std::fs::write(&manifest, edited).unwrap();
+    let msg = expect_corruption("edited manifest");
+    assert!(msg.contains("manifest checksum mismatch"), "{}", msg);
+    std::fs::write(&manifest, &text).unwrap();
+
+    restore(&base, &dirs, &target).unwrap();
+}
+
+#[test]
+fn test_foreign_or_reset_cursors_are_refused() {
+    let dir = TempDir::new().unwrap();
+    let (mut db, path) = populated(dir.path());
+    let cursor = db.backup(dir.path().join("base.db")).unwrap();
+
+    let other_dir = TempDir::new().unwrap();
+    let (mut other, _) = populated(other_dir.path());
+    let other_cursor = other.backup(other_dir.path().join("base.db")).unwrap();
+    let err = db
+        .backup_incremental(dir.path().join("foreign"), other_cursor)
+        .unwrap_err();
+    assert!(err.to_string().contains("another database"), "{}", err);
+
+    // Losing the page generation file invalidates earlier cursors.
+    drop(db);
+    std::fs::remove_file(page_generations_path(&path)).unwrap();
+    let mut db = Database::open(&path, &test_key()).unwrap();
+    db.execute("UPDATE t SET v = 'after' WHERE id = 1").unwrap();
+    let err = db
+        .backup_incremental(dir.path().join("reset"), cursor)
+        .unwrap_err();
+    assert!(err.to_string().contains("take a full backup"), "{}", err);
+}
@@ -0,0 +1,271 @@
+#![cfg(feature = "test-utils")]
+/// Building an index over existing rows: `CREATE [UNIQUE] INDEX` and
+/// `ALTER TABLE ... MODIFY ... UNIQUE` stream the table in batches, find
+/// duplicates by probing the index being built, and leave a complete index.
+/// In auto-commit mode each batch is its own commit: an interrupted build is
+/// ignored by the planner and resumed by the next read-write open.
+use murodb::crypto::aead::MasterKey;
+use murodb::fault::{FaultFile, FaultInjector, FaultKind, FaultPoint};
+use murodb::wal::recovery::recover;
+use murodb::{Database, MuroError, Value};
+use std::time::{Duration, Instant};
+use tempfile::TempDir;
+
+const ROWS: i64 = 30_000;
+
+fn test_key() -> MasterKey {
+    MasterKey::new([0x42u8; 32])
+}
+
+/// A table of `ROWS` rows whose `email` values are distinct and whose `grp`
+/// values repeat every 100 rows.
+fn large_table(dir: &TempDir) -> Database {
+    let mut db = Database::create(&dir.path().join("test.db"), &test_key()).unwrap();
+    db.execute("CREATE TABLE users (id BIGINT PRIMARY KEY, email VARCHAR(64), grp INT)")
+        .unwrap();
+    db.execute("BEGIN").unwrap();
+    for chunk in 0..ROWS / 500 {
+        let values: Vec<String> = (chunk * 500..(chunk + 1) * 500)
+            .map(|id| format!("({}, 'user{}@example.com', {})", id, id, id % 100))
+            .collect();
+        db.execute(&format!("INSERT INTO users VALUES {}", values.join(", ")))
+            .unwrap();
+    }
+    db.execute("COMMIT").unwrap();
+    db
+}
+
+fn id_for_email(db: &mut Database, email: &str) -> Option<Value> {
+    let rows = db
+        .query(&format!("SELECT id FROM users WHERE email = '{}'", email))
+        .unwrap();
+    rows.first().and_then(|r| r.get("id").cloned())
+}
+
+fn assert_integrity(db: &mut Database) {
+    for row in db.verify_integrity().unwrap() {
+        assert_ne!(
+            row.get("status"),
+            Some(&Value::Varchar("error".into())),
+            "{:?}",
+            row
+        );
+    }
+}
+
+#[test]
+fn test_unique_index_build_on_large_table() {
+    let dir = TempDir::new().unwrap();
+    let mut db = large_table(&dir);
+
+    // Comparing every key with every earlier one took minutes at this size.
+    let started = Instant::now();
+    db.execute("CREATE UNIQUE INDEX idx_users_email ON users (email)")
+        .unwrap();
+    db.execute("CREATE INDEX idx_users_grp ON users (grp)")
+        .unwrap();
+    assert!(
+        started.elapsed() < Duration::from_secs(60),
+        "{:?}",
+        started.elapsed()
+    );
+
+    for id in [0, 1023, 1024, 1025, ROWS - 1] {
+        assert_eq!(
+            id_for_email(&mut db, &format!("user{}@example.com", id)),
+            Some(Value::Integer(id))
+        );
+    }
+    let rows = db
+        .query("SELECT COUNT(*) FROM users WHERE grp = 7")
+        .unwrap();
+    assert_eq!(rows[0].get("COUNT(*)"), Some(&Value::Integer(ROWS / 100)));
+    let err = db
+        .execute("INSERT INTO users VALUES (-1, 'user5@example.com', 0)")
+        .unwrap_err();
+    assert!(matches!(err, MuroError::UniqueViolation(_)), "{}", err);
+    assert_integrity(&mut db);
+}
+
+#[test]
+fn test_duplicate_found_late_in_large_table() {
+    let dir = TempDir::new().unwrap();
+    let mut db = large_table(&dir);
+    // The repeat sits in the last batch the build reads.
+    db.execute(&format!(
+        "UPDATE users SET email = 'user3@example.com' WHERE id = {}",
+        ROWS - 1
+    ))
+    .unwrap();
+
+    let err = db
+        .execute("CREATE UNIQUE INDEX idx_users_email ON users (email)")
+        .unwrap_err();
+    assert!(matches!(err, MuroError::UniqueViolation(_)), "{}", err);
+    assert!(err.to_string().contains("'email'"), "{}", err);
+    let err = db
+        .execute("ALTER TABLE users MODIFY COLUMN email VARCHAR(64) UNIQUE")
+        .unwrap_err();
+    assert!(
+        err.to_string().contains("cannot add UNIQUE constraint"),
+        "{}",
+        err
+    );
+    // A failed build leaves neither an index nor leaked pages behind.
+    assert!(db.query("SHOW INDEXES FROM users").unwrap().is_empty());
+    assert_integrity(&mut db);
+
+    db.execute(&format!(
+        "UPDATE users SET email = 'user{}@example.com' WHERE id = {}",
+        ROWS - 1,
+        ROWS - 1
+    ))
+    .unwrap();
+    db.execute("ALTER TABLE users MODIFY COLUMN email VARCHAR(64) UNIQUE")
+        .unwrap();
+    assert_eq!(
+        id_for_email(&mut db, &format!("user{}@example.com", ROWS - 1)),
+        Some(Value::Integer(ROWS - 1))
+    );
+    assert_integrity(&mut db);
+}
+
+#[test]
+fn test_unique_build_skips_nulls_across_batches() {
+    let dir = TempDir::new().unwrap();
+    let mut db = large_table(&dir);
+    db.execute("UPDATE users SET email = NULL WHERE grp < 10")
+        .unwrap();
+    db.execute("CREATE UNIQUE INDEX idx_users_email ON users (email)")
+        .unwrap();
+    let rows = db
+        .query("SELECT COUNT(*) FROM users WHERE email IS NULL")
+        .unwrap();
+    assert_eq!(rows[0].get("COUNT(*)"), Some(&Value::Integer(ROWS / 10)));
+    assert_eq!(
+        id_for_email(&mut db, "user10@example.com"),
+        Some(Value::Integer(10))
+    );
+    assert_eq!(id_for_email(&mut db, "user9@example.com"), None);
+}
+
+/// `(Status, Build_progress)` of index `name` in SHOW INDEXES.
+fn index_status(db: &mut Database, name: &str) -> (Value, Value) {
+    let rows = db.query("SHOW INDEXES FROM users").unwrap();
+    let row = rows
+        .iter()
+        .find(|r| r.get("Key_name") == Some(&Value::Varchar(name.into())))
+        .unwrap_or_else(|| panic!("no index {}: {:?}", name, rows));
+    (
+        row.get("Status").cloned().unwrap(),
+        row.get("Build_progress").cloned().unwrap(),
+    )
+}
+
+fn plan(db: &mut Database, sql: &str) -> String {
+    format!("{:?}", db.query(&format!("EXPLAIN {}", sql)).unwrap())
+}
+
+#[test]
+fn test_batched_build_leaves_a_ready_index() {
+    let dir = TempDir::new().unwrap();
+    let mut db = large_table(&dir);
+    db.execute("SET index_build_batch_rows = 700").unwrap();
+    let rows = db
+        .query("SHOW VARIABLES LIKE 'index_build_batch_rows'")
+        .unwrap();
+    assert_eq!(rows[0].get("value"), Some(&Value::Varchar("700".into())));
+
+    db.execute("CREATE UNIQUE INDEX idx_users_email ON users (email)")
+        .unwrap();
+    assert_eq!(
+        index_status(&mut db, "idx_users_email"),
+        (Value::Varchar("ready".into()), Value::Null)
+    );
+    for id in [0, 699, 700, 701, ROWS - 1] {
+        assert_eq!(
+            id_for_email(&mut db, &format!("user{}@example.com", id)),
+            Some(Value::Integer(id))
+        );
+    }
+    assert_integrity(&mut db);
+}
+
+#[test]
+fn test_interrupted_build_is_ignored_then_resumed_at_open() {
+    let dir = TempDir::new().unwrap();
+    let create = "CREATE INDEX idx_users_grp ON users (grp)";
+
+    // Count the WAL writes of the whole build on a twin database.
+    let counted = {
+        let twin = TempDir::new().unwrap();
+        let mut db = large_table(&twin);
+        db.execute("SET index_build_batch_rows = 2000").unwrap();
+        let injector = FaultInjector::counting();
+        db.set_fault_injector(Some(injector.clone()));
+        db.execute(create).unwrap();
+        injector.counts().wal_writes
+    };
+
+    let db_path = dir.path().join("test.db");
+    {
+        let mut db = large_table(&dir);
+        db.execute("SET index_build_batch_rows = 2000").unwrap();
+        let injector = FaultInjector::at(FaultPoint {
+            file: FaultFile::Wal,
+            write: counted / 2,
+            kind: FaultKind::Fail,
+        });
+        db.set_fault_injector(Some(injector.clone()));
+        assert!(db.execute(create).is_err());
+        assert!(injector.has_crashed());
+    }
+
+    // Without resuming, the batches committed so far are visible as a
+    // building index the planner does not use.
+    recover(&db_path, &dir.path().join("test.db.wal"), &test_key()).unwrap();
+    {
+        let mut db = Database::open_read_only(&db_path, &test_key()).unwrap();
+        let (status, progress) = index_status(&mut db, "idx_users_grp");
+        assert_eq!(status, Value::Varchar("building".into()));
+        let Value::Varchar(progress) = progress else {
+            panic!("{:?}", progress)
+        };
+        let done: i64 = progress.split('/').next().unwrap().parse().unwrap();
+        assert!(done > 0 && done < ROWS, "{}", progress);
+        let sql = "SELECT COUNT(*) FROM users WHERE grp = 7";
+        assert!(!plan(&mut db, sql).contains("idx_users_grp"));
+        let rows = db.query(sql).unwrap();
+        assert_eq!(rows[0].get("COUNT(*)"), Some(&Value::Integer(ROWS / 100)));
+    }
+
+    // A read-write open finishes the build.
+    let mut db = Database::open(&db_path, &test_key()).unwrap();
+    assert_eq!(
+        index_status(&mut db, "idx_users_grp"),
+        (Value::Varchar("ready".into()), Value::Null)
+    );
+    let sql = "SELECT COUNT(*) FROM users WHERE grp = 7";
+    assert!(plan(&mut db, sql).contains("idx_users_grp"));
+    let rows = db.query(sql).unwrap();
+    assert_eq!(rows[0].get("COUNT(*)"), Some(&Value::Integer(ROWS / 100)));
+    assert_integrity(&mut db);
+}
+
+#[test]
+fn test_cancelled_build_drops_the_index() {
+    let dir = TempDir::new().unwrap();
+    let mut db = large_table(&dir);
+    db.execute("SET index_build_batch_rows = 100").unwrap();
+    db.execute("SET max_execution_time_ms = 1").unwrap();
+    let err = db
+        .execute("CREATE INDEX idx_users_grp ON users (grp)")
+        .unwrap_err();
+    assert!(matches!(err, MuroError::StatementTimeout { .. }), "{}", err);
+    db.execute("SET max_execution_time_ms = 0").unwrap();
+    assert!(db.query("SHOW INDEXES FROM users").unwrap().is_empty());
+    assert_integrity(&mut db);
+    db.execute("SET index_build_batch_rows = 10000").unwrap();
+    db.execute("CREATE INDEX idx_users_grp ON users (grp)")
+        .unwrap();
+}
@@ -0,0 +1,298 @@
+#![cfg(feature = "test-utils")]
+use murodb::btree::key_encoding::{encode_byte_stuffed, encode_i64};
+use murodb::btree::ops::BTree;
+use murodb::crypto::aead::MasterKey;
+use murodb::schema::catalog::SystemCatalog;
+use murodb::sql::executor::encode_value;
+use murodb::storage::page::Page;
+use murodb::types::{DataType, Value};
+use murodb::{Database, ExecResult, Row, Session};
+use std::io::{Seek, SeekFrom, Write};
+use tempfile::TempDir;
+
+fn test_key() -> MasterKey {
+    MasterKey::new([0x42u8; 32])
+}
+
+fn setup() -> (Session, TempDir) {
+    let dir = TempDir::new().unwrap();
+    let db_path = dir.path().join("test.db");
+    let db = Database::create(&db_path, &test_key()).unwrap();
+    let mut session = db.into_session();
+    session
+        .execute(
+            "CREATE TABLE t (id BIGINT PRIMARY KEY, name VARCHAR, email VARCHAR UNIQUE, body TEXT)",
+        )
+        .unwrap();
+    session.execute("CREATE INDEX idx_name ON t(name)").unwrap();
+    session
+        .execute("CREATE FULLTEXT INDEX ft_body ON t(body) WITH PARSER ngram OPTIONS (n=2, normalize='nfkc')")
+        .unwrap();
+    for i in 0..30 {
+        session
+            .execute(&format!(
+                "INSERT INTO t VALUES ({}, 'n{}', 'u{}@example.com', 'hello world {}')",
+                i,
+                i % 5,
+                i,
+                i
+            ))
+            .unwrap();
+    }
+    (session, dir)
+}
+
+fn report(rows: Vec<Row>) -> Vec<(String, String, String)> {
+    rows.into_iter()
+        .map(|row| {
+            let text = |name: &str| match row.get(name) {
+                Some(Value::Varchar(s)) => s.clone(),
+                other => panic!("unexpected {} value: {:?}", name, other),
+            };
+            (text("object"), text("status"), text("detail"))
+        })
+        .collect()
+}
+
+fn check_table(session: &mut Session, table: &str) -> Vec<(String, String, String)> {
+    match session.execute(&format!("CHECK TABLE {}", table)).unwrap() {
+        ExecResult::Rows(rows) => report(rows),
+        other => panic!("expected rows, got {:?}", other),
+    }
+}
+
+fn errors_for<'a>(
+    report: &'a [(String, String, String)],
+    object: &str,
+) -> Vec<&'a (String, String, String)> {
+    report
+        .iter()
+        .filter(|(o, status, _)| o == object && status == "error")
+        .collect()
+}
+
+fn index_root(session: &mut Session, name: &str) -> u64 {
+    let catalog = SystemCatalog::open(session.catalog().root_page_id());
+    catalog
+        .get_index(session.pager_mut(), name)
+        .unwrap()
+        .unwrap()
+        .btree_root
+}
+
+fn table_root(session: &mut Session, name: &str) -> u64 {
+    let catalog = SystemCatalog::open(session.catalog().root_page_id());
+    catalog
+        .get_table(session.pager_mut(), name)
+        .unwrap()
+        .unwrap()
+        .data_btree_root
+}
+
+/// Entry key of `idx_name` (non-unique): the stuffed name, then the PK.
+fn name_entry_key(name: &str, pk: &[u8]) -> Vec<u8> {
+    let mut key = Vec::new();
+    encode_byte_stuffed(
+        &mut key,
+        &encode_value(&Value::Varchar(name.into()), &DataType::Varchar(None)),
+    );
+    key.extend_from_slice(pk);
+    key
+}
+
+#[test]
+fn test_check_table_reports_ok_for_healthy_table() {
+    let (mut session, _dir) = setup();
+    session
+        .execute(&format!(
+            "INSERT INTO t VALUES (100, 'big', 'big@example.com', '{}')",
+            "overflow ".repeat(1000)
+        ))
+        .unwrap();
+
+    let rows = check_table(&mut session, "t");
+    let objects: Vec<&str> = rows.iter().map(|(o, _, _)| o.as_str()).collect();
+    assert_eq!(
+        objects,
+        vec!["t", "t.auto_unique_t_email", "t.idx_name", "t.ft_body"]
+    );
+    assert!(
+        rows.iter().all(|(_, status, _)| status == "ok"),
+        "{:?}",
+        rows
+    );
+    assert!(rows[0].2.starts_with("31 rows"), "{:?}", rows[0]);
+
+    let full = report(session.verify_integrity().unwrap());
+    assert!(
+        full.iter().all(|(_, status, _)| status == "ok"),
+        "{:?}",
+        full
+    );
+    assert_eq!(full.first().unwrap().0, "catalog");
+    assert_eq!(full.last().unwrap().0, "freelist");
+}
+
+#[test]
+fn test_check_table_unknown_table_errors() {
+    let (mut session, _dir) = setup();
+    assert!(session.execute("CHECK TABLE missing").is_err());
+}
+
+#[test]
+fn test_check_table_detects_dangling_and_missing_index_entries() {
+    let (mut session, _dir) = setup();
+    let root = index_root(&mut session, "idx_name");
+    let mut idx = BTree::open(root);
+
+    // Entry for a row that does not exist.
+    let ghost_pk = encode_i64(999).to_vec();
+    let ghost_key = name_entry_key("n1", &ghost_pk);
+    idx.insert(session.pager_mut(), &ghost_key, &ghost_pk)
+        .unwrap();
+
+    // Entry removed for an existing row.
+    let pk = encode_i64(7).to_vec();
+    let key = name_entry_key("n2", &pk);
+    assert!(idx.delete(session.pager_mut(), &key).unwrap());
+    assert_eq!(idx.root_page_id(), root);
+
+    let rows = check_table(&mut session, "t");
+    let errors = errors_for(&rows, "t.idx_name");
+    assert_eq!(errors.len(), 2, "{:?}", rows);
+    assert!(errors[0].2.starts_with("dangling entry"), "{:?}", errors);
+    assert!(errors[1].2.starts_with("missing entry"), "{:?}", errors);
+    // Other objects are still checked and reported.
+    assert!(errors_for(&rows, "t").is_empty());
+    assert!(errors_for(&rows, "t.auto_unique_t_email").is_empty());
+}
+
+#[test]
+fn test_check_table_cross_checks_tables_larger_than_a_chunk() {
+    let (mut session, _dir) = setup();
+    session.execute("BEGIN").unwrap();
+    for i in 30..2500 {
+        session
+            .execute(&format!(
+                "INSERT INTO t VALUES ({}, 'n{}', 'u{}@example.com', NULL)",
+                i,
+                i % 5,
+                i
+            ))
+            .unwrap();
+    }
+    session.execute("COMMIT").unwrap();
+    let rows = check_table(&mut session, "t");
+    assert!(
+        rows.iter().all(|(_, status, _)| status == "ok"),
+        "{:?}",
+        rows
+    );
+
+    // A row far past the first chunk lost its entry.
+    let root = index_root(&mut session, "idx_name");
+    let mut idx = BTree::open(root);
+    let pk = encode_i64(2400).to_vec();
+    assert!(idx
+        .delete(session.pager_mut(), &name_entry_key("n0", &pk))
+        .unwrap());
+    assert_eq!(idx.root_page_id(), root);
+
+    let rows = check_table(&mut session, "t");
+    let errors = errors_for(&rows, "t.idx_name");
+    assert_eq!(errors.len(), 1, "{:?}", errors);
+    assert!(errors[0].2.starts_with("missing entry"), "{:?}", errors);
+}
+
+#[test]
+fn test_check_table_detects_unique_duplicates() {
+    let (mut session, _dir) = setup();
+    let mut data = BTree::open(table_root(&mut session, "t"));
+    let row1 = data
+        .search(session.pager_mut(), &encode_i64(1))
+        .unwrap()
+        .unwrap();
+    // Row 2 now carries row 1's email (and name/body).
+    data.insert(session.pager_mut(), &encode_i64(2), &row1)
+        .unwrap();
+
+    let rows = check_table(&mut session, "t");
+    let errors = errors_for(&rows, "t.auto_unique_t_email");
+    assert!(
+        errors
+            .iter()
+            .any(|(_, _, d)| d.starts_with("duplicate key")),
+        "{:?}",
+        rows
+    );
+}
+
+#[test]
+fn test_verify_integrity_detects_reachable_free_page() {
+    let (mut session, _dir) = setup();
+    let root = index_root(&mut session, "idx_name");
+    session.pager_mut().free_page(root);
+
+    let rows = report(session.verify_integrity().unwrap());
+    let errors = errors_for(&rows, "freelist");
+    assert_eq!(errors.len(), 1, "{:?}", rows);
+    assert!(
+        errors[0].2.contains("reachable from t.idx_name"),
+        "{:?}",
+        errors
+    );
+}
+
+#[test]
+fn test_verify_integrity_reports_all_damaged_objects() {
+    let (mut session, dir) = setup();
+    session
+        .execute("CREATE TABLE other (id BIGINT PRIMARY KEY, v INT)")
+        .unwrap();
+    session.execute("INSERT INTO other VALUES (1, 1)").unwrap();
+
+    // A page with no node header: invalid node type.
+    let other_root = table_root(&mut session, "other");
+    session
+        .pager_mut()
+        .write_page(&Page::new(other_root))
+        .unwrap();
+
+    // Garbage on disk for the FULLTEXT root: fails to decrypt.
+    let ft_root = index_root(&mut session, "ft_body");
+    let page_count = session.pager().page_count();
+    drop(session);
+    let db_path = dir.path().join("test.db");
+    let file_len = std::fs::metadata(&db_path).unwrap().len();
+    let page_size_on_disk = (file_len - 76) / page_count;
+    let mut file = std::fs::OpenOptions::new()
+        .write(true)
+        .open(&db_path)
+        .unwrap();
+    file.seek(SeekFrom::Start(76 + ft_root * page_size_on_disk + 40))
+        .unwrap();
+    file.write_all(&[0xAA; 64]).unwrap();
+    drop(file);
+
+    let mut db = Database::open(&db_path, &test_key()).unwrap();
+    let rows = report(db.verify_integrity().unwrap());
+    let other = errors_for(&rows, "other");
+    assert_eq!(other.len(), 1, "{:?}", rows);
+    assert!(
+        other[0].2.contains("invalid B-tree node type"),
+        "{:?}",
+        other
+    );
+    let fts = errors_for(&rows, "t.ft_body");
+    assert!(!fts.is_empty(), "{:?}", rows);
+    assert!(fts[0].2.contains("unreadable"), "{:?}", fts);
+    // Undamaged objects still report ok.
+    for object in ["catalog", "t", "t.idx_name", "t.auto_unique_t_email"] {
+        assert!(
+            rows.iter().any(|(o, s, _)| o == object && s == "ok"),
+            "{} not ok: {:?}",
+            object,
+            rows
+        );
+    }
+}
@@ -618,3 +618,158 @@ fn test_right_outer_join() {
         panic!("Expected rows");
     }
 }
+
+#[test]
+fn test_right_join_keeps_written_column_order_and_both_side_on_condition() {
+    let (mut pager, mut catalog, _dir) = setup();
+    setup_users_orders(&mut pager, &mut catalog);
+
+    // The ON condition reads both tables; Charlie has no orders and the
+    // Doohickey order fails the product filter, so it keeps a NULL user.
+    let result = execute(
+        "SELECT * FROM users RIGHT JOIN orders \
+         ON users.id = orders.user_id AND orders.product != 'Doohickey' ORDER BY orders.id",
+        &mut pager,
+        &mut catalog,
+    )
+    .unwrap();
+
+    if let ExecResult::Rows(rows) = result {
+        assert_eq!(rows.len(), 3);
+        let names: Vec<&str> = rows[0].values.iter().map(|(n, _)| n.as_str()).collect();
+        assert_eq!(names, vec!["id", "name", "id", "user_id", "product"]);
+        assert_eq!(rows[0].values[1].1, Value::Varchar("Alice".into()));
+        assert_eq!(rows[1].values[1].1, Value::Varchar("Alice".into()));
+        assert_eq!(rows[2].values[0].1, Value::Null);
+        assert_eq!(rows[2].values[1].1, Value::Null);
+        assert_eq!(rows[2].values[2].1, Value::Integer(12));
+    } else {
+        panic!("Expected rows");
+    }
+}
+
+fn setup_chain(pager: &mut Pager, catalog: &mut SystemCatalog) {
+    setup_users_orders(pager, catalog);
+    execute(
+        "CREATE TABLE reviews (id BIGINT PRIMARY KEY, order_id BIGINT, stars BIGINT)",
+        pager,
+        catalog,
+    )
+    .unwrap();
+    // One review of an existing order, one of an order that does not exist.
+    execute("INSERT INTO reviews VALUES (100, 10, 5)", pager, catalog).unwrap();
+    execute("INSERT INTO reviews VALUES (101, 99, 1)", pager, catalog).unwrap();
+}
+
+fn chain_rows(pager: &mut Pager, catalog: &mut SystemCatalog, sql: &str) -> Vec<Vec<Value>> {
+    match execute(sql, pager, catalog).unwrap() {
+        ExecResult::Rows(rows) => rows
+            .into_iter()
+            .map(|r| r.values.into_iter().map(|(_, v)| v).collect())
+            .collect(),
+        other => panic!("Expected rows, got {:?}", other),
+    }
+}
+
+#[test]
+fn test_left_then_right_join_chain_evaluates_in_order() {
+    let (mut pager, mut catalog, _dir) = setup();
+    setup_chain(&mut pager, &mut catalog);
+
+    // (users LEFT JOIN orders) RIGHT JOIN reviews: only reviews are kept,
+    // and the orphan review gets NULLs for both users and orders.
+    let rows = chain_rows(
+        &mut pager,
+        &mut catalog,
+        "SELECT users.name, orders.product, reviews.id FROM users \
+         LEFT JOIN orders ON users.id = orders.user_id \
+         RIGHT JOIN reviews ON orders.id = reviews.order_id ORDER BY reviews.id",
+    );
+    assert_eq!(
+        rows,
+        vec![
+            vec![
+                Value::Varchar("Alice".into()),
+                Value::Varchar("Widget".into()),
+                Value::Integer(100)
+            ],
+            vec![Value::Null, Value::Null, Value::Integer(101)],
+        ]
+    );
+}
+
+#[test]
+fn test_right_then_left_join_chain_evaluates_in_order() {
+    let (mut pager, mut catalog, _dir) = setup();
+    setup_chain(&mut pager, &mut catalog);
+    execute(
+        "INSERT INTO orders VALUES (13, 7, 'Orphan')",
+        &mut pager,
+        &mut catalog,
+    )
+    .unwrap();
+
+    // (users RIGHT JOIN orders) keeps every order, including the one with
+    // no user; the LEFT JOIN then keeps all of those rows, reviewed or not.
+    let rows = chain_rows(
+        &mut pager,
+        &mut catalog,
+        "SELECT users.name, orders.id, reviews.stars FROM users \
+         RIGHT JOIN orders ON users.id = orders.user_id \
+         LEFT JOIN reviews ON orders.id = reviews.order_id ORDER BY orders.id",
+    );
+    assert_eq!(
+        rows,
+        vec![
+            vec![
+                Value::Varchar("Alice".into()),
+                Value::Integer(10),
+                Value::Integer(5)
+            ],
+            vec![
+                Value::Varchar("Alice".into()),
+                Value::Integer(11),
+                Value::Null
+            ],
+            vec![
+                Value::Varchar("Bob".into()),
+                Value::Integer(12),
+                Value::Null
+            ],
+            vec![Value::Null, Value::Integer(13), Value::Null],
+        ]
+    );
+}
+
+#[test]
+fn test_full_outer_join_is_rejected() {
+    let (mut pager, mut catalog, _dir) = setup();
+    setup_users_orders(&mut pager, &mut catalog);
+
+    for sql in [
+        "SELECT * FROM users FULL OUTER JOIN orders ON users.id = orders.user_id",
+        "SELECT * FROM users FULL JOIN orders ON users.id = orders.user_id",
+        "SELECT * FROM users u full outer join orders o ON u.id = o.user_id",
+    ] {
+        let err = execute(sql, &mut pager, &mut catalog).unwrap_err();
+        assert!(
+            err.to_string().contains("FULL OUTER JOIN is not supported"),
+            "{}: {}",
+            sql,
+            err
+        );
+    }
+
+    // FULL is still usable as an alias when introduced with AS.
+    let result = execute(
+        "SELECT full.name FROM users AS full JOIN orders ON full.id = orders.user_id",
+        &mut pager,
+        &mut catalog,
+    )
+    .unwrap();
+    if let ExecResult::Rows(rows) = result {
+        assert_eq!(rows.len(), 3);
+    } else {
+        panic!("Expected rows");
+    }
+}
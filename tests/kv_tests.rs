@@ -0,0 +1,323 @@
+/// `KvDatabase`: byte keys and values in a catalog-registered B-tree, with
+/// single-operation and closure transactions, persistence across reopen,
+/// encryption, and recovery after the process dies mid-transaction.
+///
+/// Unlike the other suites this one needs neither `test-utils` nor `sql`:
+/// `cargo test --no-default-features --test kv_tests` runs it against the
+/// key-value-only build. The SQL interoperability test is skipped there.
+use murodb::{KvDatabase, MasterKey, MuroError, RetryPolicy};
+use std::ops::Bound;
+use std::path::Path;
+use std::process::{Command, Stdio};
+use tempfile::TempDir;
+
+const CRASHER_ENV: &str = "MURODB_KV_CRASHER";
+
+fn key() -> MasterKey {
+    MasterKey::new([42u8; 32])
+}
+
+fn all(db: &mut KvDatabase) -> Vec<(Vec<u8>, Vec<u8>)> {
+    db.scan_range(Bound::Unbounded, Bound::Unbounded).unwrap()
+}
+
+fn keys(entries: &[(Vec<u8>, Vec<u8>)]) -> Vec<&[u8]> {
+    entries.iter().map(|(k, _)| k.as_slice()).collect()
+}
+
+#[test]
+fn test_put_get_delete_and_reopen() {
+    let dir = TempDir::new().unwrap();
+    let path = dir.path().join("kv.db");
+    let mut db = KvDatabase::create(&path, &key()).unwrap();
+
+    assert_eq!(db.get(b"missing").unwrap(), None);
+    assert!(!db.delete(b"missing").unwrap());
+    db.put(b"alpha", b"1").unwrap();
+    db.put(b"beta", b"2").unwrap();
+    db.put(b"alpha", b"one").unwrap();
+    assert_eq!(db.get(b"alpha").unwrap(), Some(b"one".to_vec()));
+    assert!(db.delete(b"beta").unwrap());
+    assert_eq!(db.get(b"beta").unwrap(), None);
+    // Keys and values are arbitrary bytes, including empty ones.
+    db.put(&[0u8, 255, 0], b"").unwrap();
+    assert_eq!(db.get(&[0u8, 255, 0]).unwrap(), Some(Vec::new()));
+    drop(db);
+
+    let mut db = KvDatabase::open(&path, &key()).unwrap();
+    assert_eq!(db.get(b"alpha").unwrap(), Some(b"one".to_vec()));
+    assert_eq!(db.get(b"beta").unwrap(), None);
+    assert_eq!(all(&mut db).len(), 2);
+}
+
+#[test]
+fn test_wrong_key_and_wrong_mode_fail_to_open() {
+    let dir = TempDir::new().unwrap();
+    let path = dir.path().join("kv.db");
+    KvDatabase::create(&path, &key())
+        .unwrap()
+        .put(b"k", b"v")
+        .unwrap();
+
+    assert!(KvDatabase::open(&path, &MasterKey::new([1u8; 32])).is_err());
+    assert!(KvDatabase::open_plaintext(&path).is_err());
+
+    let plain = dir.path().join("plain.db");
+    KvDatabase::create_plaintext(&plain)
+        .unwrap()
+        .put(b"k", b"v")
+        .unwrap();
+    assert!(KvDatabase::open(&plain, &key()).is_err());
+    let mut db = KvDatabase::open_plaintext(&plain).unwrap();
+    assert_eq!(db.get(b"k").unwrap(), Some(b"v".to_vec()));
+}
+
+#[test]
+fn test_scan_range_bounds() {
+    let dir = TempDir::new().unwrap();
+    let mut db = KvDatabase::create_plaintext(&dir.path().join("kv.db")).unwrap();
+    db.transaction(|tx| {
+        for k in ["a", "b", "ba", "c", "d"] {
+            tx.put(k.as_bytes(), k.to_uppercase().as_bytes())?;
+        }
+        Ok(())
+    })
+    .unwrap();
+
+    let range = |db: &mut KvDatabase, start: Bound<&[u8]>, end: Bound<&[u8]>| {
+        let entries = db.scan_range(start, end).unwrap();
+        keys(&entries)
+            .iter()
+            .map(|k| String::from_utf8(k.to_vec()).unwrap())
+            .collect::<Vec<_>>()
+    };
+    assert_eq!(
+        range(&mut db, Bound::Included(b"b"), Bound::Excluded(b"c")),
+        ["b", "ba"]
+    );
+    assert_eq!(
+        range(&mut db, Bound::Excluded(b"b"), Bound::Included(b"c")),
+        ["ba", "c"]
+    );
+    assert_eq!(
+        range(&mut db, Bound::Unbounded, Bound::Excluded(b"b")),
+        ["a"]
+    );
+    assert_eq!(
+        range(&mut db, Bound::Included(b"bb"), Bound::Unbounded),
+        ["c", "d"]
+    );
+    assert!(range(&mut db, Bound::Included(b"x"), Bound::Unbounded).is_empty());
+    assert_eq!(
+        db.scan_range(Bound::Included(b"d"), Bound::Included(b"d"))
+            .unwrap(),
+        vec![(b"d".to_vec(), b"D".to_vec())]
+    );
+}
+
+#[test]
+fn test_transaction_commits_or_rolls_back_as_a_whole() {
+    let dir = TempDir::new().unwrap();
+    let path = dir.path().join("kv.db");
+    let mut db = KvDatabase::create_plaintext(&path).unwrap();
+    db.put(b"kept", b"1").unwrap();
+
+    let seen = db
+        .transaction(|tx| {
+            tx.put(b"x", b"1")?;
+            tx.put(b"y", b"2")?;
+            tx.delete(b"kept")?;
+            // Reads see the transaction's own writes.
+            assert_eq!(tx.get(b"x")?, Some(b"1".to_vec()));
+            assert_eq!(tx.get(b"kept")?, None);
+            Ok(tx.scan_range(Bound::Unbounded, Bound::Unbounded)?.len())
+        })
+        .unwrap();
+    assert_eq!(seen, 2);
+    assert_eq!(keys(&all(&mut db)), [b"x".as_slice(), b"y".as_slice()]);
+
+    let err = db
+        .transaction(|tx| {
+            tx.put(b"z", b"3")?;
+            tx.delete(b"x")?;
+            Err::<(), _>(MuroError::Execution("abandon".into()))
+        })
+        .unwrap_err();
+    assert!(matches!(err, MuroError::Execution(_)));
+    assert_eq!(keys(&all(&mut db)), [b"x".as_slice(), b"y".as_slice()]);
+
+    let panicked = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| {
+        let _ = db.transaction(|tx| {
+            tx.put(b"p", b"panic")?;
+            panic!("boom");
+            #[allow(unreachable_code)]
+            Ok(())
+        });
+    }));
+    assert!(panicked.is_err());
+    assert_eq!(db.get(b"p").unwrap(), None);
+
+    // Rolled-back writes never reached the file.
+    drop(db);
+    let mut db = KvDatabase::open_plaintext(&path).unwrap();
+    assert_eq!(keys(&all(&mut db)), [b"x".as_slice(), b"y".as_slice()]);
+}
+
+#[test]
+fn test_large_values_and_many_keys() {
+    let dir = TempDir::new().unwrap();
+    let path = dir.path().join("kv.db");
+    let mut db = KvDatabase::create(&path, &key()).unwrap();
+    let big: Vec<u8> = (0..100_000u32).map(|i| (i % 251) as u8).collect();
+    db.put(b"big", &big).unwrap();
+    db.transaction(|tx| {
+        for i in 0..3000u32 {
+            tx.put(&i.to_be_bytes(), &i.to_le_bytes())?;
+        }
+        Ok(())
+    })
+    .unwrap();
+    db.transaction(|tx| {
+        for i in (0..3000u32).filter(|i| i % 3 != 0) {
+            assert!(tx.delete(&i.to_be_bytes())?);
+        }
+        Ok(())
+    })
+    .unwrap();
+
+    // Keys beyond the B-tree limit are refused.
+    assert!(db.put(&vec![b'k'; 10_000], b"v").is_err());
+    drop(db);
+
+    let mut db = KvDatabase::open(&path, &key()).unwrap();
+    assert_eq!(db.get(b"big").unwrap(), Some(big));
+    let numbered = db
+        .scan_range(
+            Bound::Included(&0u32.to_be_bytes()),
+            Bound::Included(&u32::MAX.to_be_bytes()),
+        )
+        .unwrap()
+        .into_iter()
+        .filter(|(k, _)| k != b"big")
+        .collect::<Vec<_>>();
+    assert_eq!(numbered.len(), 1000);
+    assert!(numbered
+        .iter()
+        .all(|(k, v)| u32::from_be_bytes(k[..].try_into().unwrap())
+            == u32::from_le_bytes(v[..].try_into().unwrap())));
+}
+
+#[test]
+fn test_handles_on_one_file_see_each_others_commits() {
+    let dir = TempDir::new().unwrap();
+    let path = dir.path().join("kv.db");
+    let mut a = KvDatabase::create_plaintext(&path).unwrap();
+    let mut b = KvDatabase::open_plaintext(&path).unwrap();
+
+    a.put(b"from_a", b"1").unwrap();
+    assert_eq!(b.get(b"from_a").unwrap(), Some(b"1".to_vec()));
+    b.transaction_with_retry(RetryPolicy::default(), |tx| tx.put(b"from_b", b"2"))
+        .unwrap();
+    assert_eq!(a.get(b"from_b").unwrap(), Some(b"2".to_vec()));
+    assert_eq!(all(&mut a), all(&mut b));
+}
+
+/// Runs in a child process: commits `crash_committed_*`, then dies inside a
+/// transaction that has already written `crash_pending_*`.
+#[test]
+fn kv_crasher() {
+    let Ok(path) = std::env::var(CRASHER_ENV) else {
+        return;
+    };
+    let mut db = KvDatabase::open(Path::new(&path), &key()).unwrap();
+    for i in 0..20u32 {
+        db.put(format!("crash_committed_{:02}", i).as_bytes(), b"v")
+            .unwrap();
+    }
+    let _: murodb::Result<()> = db.transaction(|tx| {
+        for i in 0..500u32 {
+            tx.put(format!("crash_pending_{:03}", i).as_bytes(), &[1u8; 200])?;
+        }
+        std::process::abort();
+    });
+}
+
+#[test]
+fn test_process_crash_mid_transaction_recovers_committed_state() {
+    let dir = TempDir::new().unwrap();
+    let path = dir.path().join("crash.db");
+    KvDatabase::create(&path, &key())
+        .unwrap()
+        .put(b"before", b"0")
+        .unwrap();
+
+    let status = Command::new(std::env::current_exe().unwrap())
+        .args(["kv_crasher", "--exact", "--test-threads=1"])
+        .env(CRASHER_ENV, &path)
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .status()
+        .unwrap();
+    assert!(!status.success(), "crasher was expected to abort");
+
+    let (mut db, _report) = KvDatabase::open_with_recovery_mode_and_report(
+        &path,
+        Some(&key()),
+        murodb::RecoveryMode::Strict,
+    )
+    .unwrap();
+    let entries = all(&mut db);
+    assert_eq!(entries.len(), 21);
+    assert_eq!(db.get(b"before").unwrap(), Some(b"0".to_vec()));
+    assert!(entries
+        .iter()
+        .all(|(k, _)| !k.starts_with(b"crash_pending_")));
+    db.put(b"after", b"1").unwrap();
+    assert_eq!(all(&mut db).len(), 22);
+}
+
+/// Key-value data lives next to SQL tables in one file, and integrity checks
+/// account for its pages.
+#[cfg(feature = "sql")]
+#[test]
+fn test_kv_namespace_coexists_with_sql_tables() {
+    use murodb::{Database, Value};
+
+    let dir = TempDir::new().unwrap();
+    let path = dir.path().join("mixed.db");
+    let mut kv = KvDatabase::create_plaintext(&path).unwrap();
+    kv.transaction(|tx| {
+        for i in 0..500u32 {
+            tx.put(&i.to_be_bytes(), &[3u8; 100])?;
+        }
+        Ok(())
+    })
+    .unwrap();
+    drop(kv);
+
+    let mut db = Database::open_plaintext(&path).unwrap();
+    assert!(db.query("SHOW TABLES").unwrap().is_empty());
+    db.execute("CREATE TABLE t (id BIGINT PRIMARY KEY)")
+        .unwrap();
+    db.execute("INSERT INTO t VALUES (1)").unwrap();
+    let rows = db.verify_integrity().unwrap();
+    assert!(rows
+        .iter()
+        .any(|r| r.get("object") == Some(&Value::Varchar("kv:default".into()))));
+    for row in rows {
+        assert_eq!(
+            row.get("status"),
+            Some(&Value::Varchar("ok".into())),
+            "{:?}",
+            row
+        );
+    }
+    drop(db);
+
+    let mut kv = KvDatabase::open_plaintext(&path).unwrap();
+    assert_eq!(all(&mut kv).len(), 500);
+    kv.put(b"more", b"1").unwrap();
+    drop(kv);
+    let mut db = Database::open_plaintext(&path).unwrap();
+    assert_eq!(db.query("SELECT * FROM t").unwrap().len(), 1);
+}
@@ -0,0 +1,232 @@
+#![cfg(feature = "test-utils")]
+/// LIKE patterns are planned as equality, prefix ranges, or IS NOT NULL,
+/// with the same results as matching them; patterns that still need
+/// matching after a literal prefix keep the LIKE as a residual filter.
+use murodb::crypto::aead::MasterKey;
+use murodb::schema::catalog::SystemCatalog;
+use murodb::sql::executor::{execute, ExecResult};
+use murodb::storage::pager::Pager;
+use murodb::types::Value;
+use tempfile::TempDir;
+
+fn test_key() -> MasterKey {
+    MasterKey::new([0x42u8; 32])
+}
+
+fn setup() -> (Pager, SystemCatalog, TempDir) {
+    let dir = TempDir::new().unwrap();
+    let db_path = dir.path().join("test.db");
+    let mut pager = Pager::create(&db_path, &test_key()).unwrap();
+    let mut catalog = SystemCatalog::create(&mut pager).unwrap();
+    for sql in [
+        "CREATE TABLE items (sku VARCHAR PRIMARY KEY, name VARCHAR, qty INT)",
+        "CREATE INDEX idx_name ON items(name)",
+        "INSERT INTO items VALUES ('ABC-123', 'ab', 1)",
+        "INSERT INTO items VALUES ('ABC-124', 'abc', 2)",
+        "INSERT INTO items VALUES ('ABD-1', 'a%', 3)",
+        "INSERT INTO items VALUES ('AB_-1', 'a_c', 4)",
+        "INSERT INTO items VALUES ('X', '', 5)",
+        "INSERT INTO items VALUES ('Y', NULL, 6)",
+        "INSERT INTO items VALUES ('Z', 'b', 7)",
+    ] {
+        execute(sql, &mut pager, &mut catalog).unwrap();
+    }
+    (pager, catalog, dir)
+}
+
+fn skus(sql: &str, pager: &mut Pager, catalog: &mut SystemCatalog) -> Vec<String> {
+    match execute(sql, pager, catalog).unwrap() {
+        ExecResult::Rows(rows) => rows
+            .iter()
+            .map(|r| match r.get("sku") {
+                Some(Value::Varchar(s)) => s.clone(),
+                other => panic!("unexpected sku {:?}", other),
+            })
+            .collect(),
+        other => panic!("Expected Rows, got {:?}", other),
+    }
+}
+
+fn explain(sql: &str, pager: &mut Pager, catalog: &mut SystemCatalog) -> (Value, Value, String) {
+    match execute(&format!("EXPLAIN {}", sql), pager, catalog).unwrap() {
+        ExecResult::Rows(rows) => {
+            let extra = match rows[0].get("Extra") {
+                Some(Value::Varchar(s)) => s.clone(),
+                _ => String::new(),
+            };
+            (
+                rows[0].get("type").cloned().unwrap(),
+                rows[0].get("key").cloned().unwrap(),
+                extra,
+            )
+        }
+        other => panic!("Expected Rows, got {:?}", other),
+    }
+}
+
+#[test]
+fn test_simplified_like_matches_pattern_evaluation() {
+    let (mut pager, mut catalog, _dir) = setup();
+    for predicate in [
+        "sku LIKE 'ABC-123'",
+        "sku NOT LIKE 'ABC-123'",
+        "sku LIKE 'ABC%'",
+        "sku LIKE 'AB!_%' ESCAPE '!'",
+        "name LIKE ''",
+        "name LIKE 'a!%' ESCAPE '!'",
+        "name LIKE 'a%'",
+        "name LIKE '%'",
+        "name LIKE '%%'",
+        "name NOT LIKE 'ab'",
+        "sku LIKE 'ABC-12_'",
+        "sku LIKE 'AB_-%'",
+        "name LIKE 'a_c'",
+        "name LIKE 'a%c'",
+        "name LIKE 'a!%%' ESCAPE '!'",
+    ] {
+        // An OR with a false disjunct is not simplified, so it evaluates the
+        // pattern row by row.
+        let matched = skus(
+            &format!(
+                "SELECT sku FROM items WHERE {} OR 1 = 0 ORDER BY sku",
+                predicate
+            ),
+            &mut pager,
+            &mut catalog,
+        );
+        let simplified = skus(
+            &format!("SELECT sku FROM items WHERE {} ORDER BY sku", predicate),
+            &mut pager,
+            &mut catalog,
+        );
+        assert_eq!(simplified, matched, "{predicate}");
+    }
+    assert_eq!(
+        skus(
+            "SELECT sku FROM items WHERE name LIKE '%' ORDER BY sku",
+            &mut pager,
+            &mut catalog
+        )
+        .len(),
+        6
+    );
+}
+
+#[test]
+fn test_explain_shows_seek_for_simplified_like() {
+    let (mut pager, mut catalog, _dir) = setup();
+    let (access, key, extra) = explain(
+        "SELECT * FROM items WHERE sku LIKE 'ABC-123'",
+        &mut pager,
+        &mut catalog,
+    );
+    assert_eq!(access, Value::Varchar("const".into()));
+    assert_eq!(key, Value::Varchar("PRIMARY".into()));
+    assert!(
+        extra.contains("Simplified LIKE: sku LIKE 'ABC-123' -> sku = 'ABC-123'"),
+        "{extra}"
+    );
+
+    let (access, key, extra) = explain(
+        "SELECT * FROM items WHERE name LIKE 'a!%' ESCAPE '!'",
+        &mut pager,
+        &mut catalog,
+    );
+    assert_eq!(access, Value::Varchar("ref".into()));
+    assert_eq!(key, Value::Varchar("idx_name".into()));
+    assert!(extra.contains("-> name = 'a%'"), "{extra}");
+
+    let (access, key, extra) = explain(
+        "SELECT * FROM items WHERE name LIKE 'ab%'",
+        &mut pager,
+        &mut catalog,
+    );
+    assert_eq!(access, Value::Varchar("range".into()));
+    assert_eq!(key, Value::Varchar("idx_name".into()));
+    assert!(
+        extra.contains("-> (name >= 'ab') AND (name < 'ac')"),
+        "{extra}"
+    );
+
+    let (_, _, extra) = explain(
+        "SELECT * FROM items WHERE name LIKE '%'",
+        &mut pager,
+        &mut catalog,
+    );
+    assert!(extra.contains("-> name IS NOT NULL"), "{extra}");
+
+    // A pattern that still needs matching scans the prefix range and keeps
+    // the LIKE as a filter.
+    let (access, key, extra) = explain(
+        "SELECT * FROM items WHERE name LIKE 'a_c'",
+        &mut pager,
+        &mut catalog,
+    );
+    assert_eq!(access, Value::Varchar("range".into()));
+    assert_eq!(key, Value::Varchar("idx_name".into()));
+    assert!(
+        extra.contains(
+            "Simplified LIKE: name LIKE 'a_c' -> ((name >= 'a') AND (name < 'b')) AND (name LIKE 'a_c')"
+        ),
+        "{extra}"
+    );
+    assert_eq!(
+        skus(
+            "SELECT sku FROM items WHERE name LIKE 'a_c'",
+            &mut pager,
+            &mut catalog
+        ),
+        vec!["AB_-1".to_string(), "ABC-124".to_string()]
+    );
+
+    // Patterns starting with a wildcard are planned as written.
+    for predicate in ["name LIKE '_b'", "name LIKE '%c'", "name NOT LIKE 'a_'"] {
+        let (access, _, extra) = explain(
+            &format!("SELECT * FROM items WHERE {}", predicate),
+            &mut pager,
+            &mut catalog,
+        );
+        assert_eq!(access, Value::Varchar("ALL".into()), "{predicate}");
+        assert!(!extra.contains("Simplified LIKE"), "{extra}");
+    }
+}
+
+#[test]
+fn test_simplified_like_in_update_and_delete() {
+    let (mut pager, mut catalog, _dir) = setup();
+    let (access, _, _) = explain(
+        "DELETE FROM items WHERE sku LIKE 'ABC-124'",
+        &mut pager,
+        &mut catalog,
+    );
+    assert_eq!(access, Value::Varchar("const".into()));
+
+    match execute(
+        "UPDATE items SET qty = 10 WHERE sku LIKE 'ABC%'",
+        &mut pager,
+        &mut catalog,
+    )
+    .unwrap()
+    {
+        ExecResult::RowsAffected(n) => assert_eq!(n, 2),
+        other => panic!("Expected RowsAffected, got {:?}", other),
+    }
+    match execute(
+        "DELETE FROM items WHERE sku LIKE 'ABC-124'",
+        &mut pager,
+        &mut catalog,
+    )
+    .unwrap()
+    {
+        ExecResult::RowsAffected(n) => assert_eq!(n, 1),
+        other => panic!("Expected RowsAffected, got {:?}", other),
+    }
+    assert_eq!(
+        skus(
+            "SELECT sku FROM items WHERE qty = 10",
+            &mut pager,
+            &mut catalog
+        ),
+        vec!["ABC-123".to_string()]
+    );
+}
@@ -0,0 +1,250 @@
+#![cfg(feature = "test-utils")]
+/// Every limit reported by `Database::limits` holds at its maximum and fails
+/// one past it with `MuroError::LimitExceeded`, leaving the database as it was.
+use murodb::crypto::aead::MasterKey;
+use murodb::{Database, Limit, Limits, MuroError, Value};
+use tempfile::TempDir;
+
+fn test_key() -> MasterKey {
+    MasterKey::new([0x42u8; 32])
+}
+
+fn create_db(dir: &TempDir) -> Database {
+    Database::create(&dir.path().join("test.db"), &test_key()).unwrap()
+}
+
+fn assert_limit<T: std::fmt::Debug>(result: murodb::error::Result<T>, expected: Limit) {
+    match result {
+        Err(MuroError::LimitExceeded { limit, value, max }) => {
+            assert_eq!(limit, expected);
+            assert_eq!(max, expected.max() as u64);
+            assert!(value > max, "{} > {}", value, max);
+        }
+        other => panic!("expected {} to be exceeded, got {:?}", expected, other),
+    }
+}
+
+fn count(db: &mut Database, table: &str) -> Value {
+    let rows = db
+        .query(&format!("SELECT COUNT(*) FROM {}", table))
+        .unwrap();
+    rows[0].get_at(0).unwrap().clone()
+}
+
+#[test]
+fn test_limits_api_reports_enforced_values() {
+    let dir = TempDir::new().unwrap();
+    let db = create_db(&dir);
+    let limits = db.limits();
+    assert_eq!(limits, Limits::current());
+    assert_eq!(limits.page_size, 4096);
+    assert_eq!(limits.max_key_bytes, Limit::KeyBytes.max());
+    assert_eq!(limits.max_identifier_bytes, 255);
+    assert_eq!(limits.max_in_list_items, 100_000);
+    assert_eq!(limits.max_sql_bytes, 16 * 1024 * 1024);
+    assert_eq!(limits.max_columns_per_table, Limit::ColumnsPerTable.max());
+    assert!(limits.max_row_inline_bytes < limits.page_size);
+}
+
+#[test]
+fn test_identifier_bytes() {
+    let dir = TempDir::new().unwrap();
+    let mut db = create_db(&dir);
+    let max = db.limits().max_identifier_bytes;
+    let ok = "t".repeat(max);
+    let long = "t".repeat(max + 1);
+
+    db.execute(&format!("CREATE TABLE {} (id BIGINT PRIMARY KEY)", ok))
+        .unwrap();
+    assert_limit(
+        db.execute(&format!("CREATE TABLE {} (id BIGINT PRIMARY KEY)", long)),
+        Limit::IdentifierBytes,
+    );
+    // Multi-byte names count UTF-8 bytes: 85 three-byte characters fit, 86 do not.
+    db.execute(&format!(
+        "CREATE TABLE {} (id BIGINT PRIMARY KEY)",
+        "表".repeat(85)
+    ))
+    .unwrap();
+    assert_limit(
+        db.execute(&format!(
+            "CREATE TABLE {} (id BIGINT PRIMARY KEY)",
+            "表".repeat(86)
+        )),
+        Limit::IdentifierBytes,
+    );
+
+    db.execute(&format!(
+        "CREATE TABLE c (id BIGINT PRIMARY KEY, {} INT)",
+        ok
+    ))
+    .unwrap();
+    assert_limit(
+        db.execute(&format!(
+            "CREATE TABLE d (id BIGINT PRIMARY KEY, {} INT)",
+            long
+        )),
+        Limit::IdentifierBytes,
+    );
+    assert_limit(
+        db.execute(&format!("ALTER TABLE c ADD COLUMN {} INT", long)),
+        Limit::IdentifierBytes,
+    );
+    assert_limit(
+        db.execute(&format!("ALTER TABLE c CHANGE COLUMN {} {} INT", ok, long)),
+        Limit::IdentifierBytes,
+    );
+    assert_limit(
+        db.execute(&format!("RENAME TABLE c TO {}", long)),
+        Limit::IdentifierBytes,
+    );
+
+    db.execute(&format!("CREATE INDEX {} ON c ({})", ok, ok))
+        .unwrap();
+    assert_limit(
+        db.execute(&format!("CREATE INDEX {} ON c (id)", long)),
+        Limit::IdentifierBytes,
+    );
+
+    let rows = db.query("SHOW TABLES").unwrap();
+    assert_eq!(rows.len(), 3);
+    assert!(db.query("SELECT * FROM d").is_err());
+}
+
+#[test]
+fn test_columns_per_table() {
+    let dir = TempDir::new().unwrap();
+    let mut db = create_db(&dir);
+    let max = db.limits().max_columns_per_table;
+    let columns = |n: usize| {
+        (1..n)
+            .map(|i| format!("c{} TINYINT", i))
+            .collect::<Vec<_>>()
+            .join(", ")
+    };
+
+    db.execute(&format!(
+        "CREATE TABLE wide (id BIGINT PRIMARY KEY, {})",
+        columns(max)
+    ))
+    .unwrap();
+    db.execute("INSERT INTO wide (id, c1) VALUES (1, 7)")
+        .unwrap();
+    let rows = db.query("SELECT c1 FROM wide WHERE id = 1").unwrap();
+    assert_eq!(rows[0].get_at(0), Some(&Value::Integer(7)));
+    assert_limit(
+        db.execute("ALTER TABLE wide ADD COLUMN extra INT"),
+        Limit::ColumnsPerTable,
+    );
+
+    assert_limit(
+        db.execute(&format!(
+            "CREATE TABLE wider (id BIGINT PRIMARY KEY, {})",
+            columns(max + 1)
+        )),
+        Limit::ColumnsPerTable,
+    );
+    // Without a primary key the hidden _rowid column counts too.
+    assert_limit(
+        db.execute(&format!("CREATE TABLE no_pk (c0 INT, {})", columns(max))),
+        Limit::ColumnsPerTable,
+    );
+}
+
+#[test]
+fn test_key_bytes() {
+    let dir = TempDir::new().unwrap();
+    let mut db = create_db(&dir);
+    let max = db.limits().max_key_bytes;
+    db.execute("CREATE TABLE k (id VARBINARY(4000) PRIMARY KEY, v VARCHAR(4000))")
+        .unwrap();
+
+    db.execute(&format!(
+        "INSERT INTO k VALUES (X'{}', 'a')",
+        "ab".repeat(max)
+    ))
+    .unwrap();
+    assert_limit(
+        db.execute(&format!(
+            "INSERT INTO k VALUES (X'{}', 'b')",
+            "ab".repeat(max + 1)
+        )),
+        Limit::KeyBytes,
+    );
+    assert_eq!(count(&mut db, "k"), Value::Integer(1));
+
+    // A non-unique index key holds the indexed value, its 2-byte
+    // terminator, and the primary key.
+    db.execute("CREATE TABLE ix (id BIGINT PRIMARY KEY, v VARCHAR(4000))")
+        .unwrap();
+    db.execute("CREATE INDEX idx_v ON ix (v)").unwrap();
+    db.execute(&format!(
+        "INSERT INTO ix VALUES (1, '{}')",
+        "v".repeat(max - 10)
+    ))
+    .unwrap();
+    assert_limit(
+        db.execute(&format!(
+            "INSERT INTO ix VALUES (2, '{}')",
+            "v".repeat(max - 9)
+        )),
+        Limit::KeyBytes,
+    );
+    assert_eq!(count(&mut db, "ix"), Value::Integer(1));
+    assert_limit(db.execute("CREATE INDEX idx_k ON k (v)"), Limit::KeyBytes);
+    // The failed index left nothing behind under its name.
+    db.execute("CREATE INDEX idx_k ON ix (id)").unwrap();
+}
+
+#[test]
+fn test_in_list_items() {
+    let dir = TempDir::new().unwrap();
+    let mut db = create_db(&dir);
+    let max = db.limits().max_in_list_items;
+    db.execute("CREATE TABLE t (id BIGINT PRIMARY KEY)")
+        .unwrap();
+    db.execute("INSERT INTO t VALUES (1), (2)").unwrap();
+    let in_list = |n: usize| (0..n).map(|i| i.to_string()).collect::<Vec<_>>().join(",");
+
+    let rows = db
+        .query(&format!("SELECT id FROM t WHERE id IN ({})", in_list(max)))
+        .unwrap();
+    assert_eq!(rows.len(), 2);
+    assert_limit(
+        db.query(&format!(
+            "SELECT id FROM t WHERE id IN ({})",
+            in_list(max + 1)
+        )),
+        Limit::InListItems,
+    );
+    // Scripts report the limit of the failing statement.
+    match db.execute_batch(&format!(
+        "SELECT 1; DELETE FROM t WHERE id IN ({})",
+        in_list(max + 1)
+    )) {
+        Err(MuroError::Script { index, source, .. }) => {
+            assert_eq!(index, 1);
+            assert_limit::<()>(Err(*source), Limit::InListItems);
+        }
+        other => panic!("expected a script error, got {:?}", other),
+    }
+    assert_eq!(count(&mut db, "t"), Value::Integer(2));
+}
+
+#[test]
+fn test_sql_bytes() {
+    let dir = TempDir::new().unwrap();
+    let mut db = create_db(&dir);
+    let max = db.limits().max_sql_bytes;
+    let select = "SELECT 1";
+    let padded = |n: usize| format!("{}{}", select, " ".repeat(n - select.len()));
+
+    let rows = db.query(&padded(max)).unwrap();
+    assert_eq!(rows[0].get_at(0), Some(&Value::Integer(1)));
+    assert_limit(db.query(&padded(max + 1)), Limit::SqlBytes);
+    assert_limit(db.prepare(&padded(max + 1)), Limit::SqlBytes);
+    match db.execute_batch(&padded(max + 1)) {
+        Err(MuroError::Script { source, .. }) => assert_limit::<()>(Err(*source), Limit::SqlBytes),
+        other => panic!("expected a script error, got {:?}", other),
+    }
+}
@@ -0,0 +1,140 @@
+#![cfg(feature = "test-utils")]
+/// Maintenance mode: one handle takes exclusive use of a database while the
+/// other handles of the same file in this process are turned away.
+use murodb::crypto::aead::MasterKey;
+use murodb::{Database, ErrorClass, MuroError, Value};
+use std::path::Path;
+use std::time::{Duration, Instant};
+use tempfile::TempDir;
+
+fn test_key() -> MasterKey {
+    MasterKey::new([0x42u8; 32])
+}
+
+fn setup(db_path: &Path) -> Database {
+    let mut db = Database::create(db_path, &test_key()).unwrap();
+    db.execute("CREATE TABLE t (id BIGINT PRIMARY KEY, v INT)")
+        .unwrap();
+    db.execute("INSERT INTO t VALUES (1, 10)").unwrap();
+    db
+}
+
+fn count(db: &mut Database) -> i64 {
+    match db.query("SELECT COUNT(*) FROM t").unwrap()[0].values[0].1 {
+        Value::Integer(n) => n,
+        ref other => panic!("unexpected value {:?}", other),
+    }
+}
+
+#[test]
+fn test_maintenance_turns_other_handles_away() {
+    let dir = TempDir::new().unwrap();
+    let db_path = dir.path().join("test.db");
+    let mut db = setup(&db_path);
+    let mut reader = db.open_reader().unwrap();
+    let mut other = Database::open(&db_path, &test_key()).unwrap();
+
+    let mut guard = db.begin_maintenance(Duration::from_secs(1)).unwrap();
+    let started = Instant::now();
+    let err = reader.query("SELECT COUNT(*) FROM t").unwrap_err();
+    assert!(matches!(err, MuroError::MaintenanceInProgress), "{err}");
+    assert_eq!(err.error_class(), ErrorClass::Transient);
+    assert!(matches!(
+        other.execute("INSERT INTO t VALUES (2, 20)"),
+        Err(MuroError::MaintenanceInProgress)
+    ));
+    assert!(started.elapsed() < Duration::from_millis(500));
+
+    // The owner keeps full access; maintenance does not nest.
+    guard.execute("INSERT INTO t VALUES (3, 30)").unwrap();
+    guard.execute("CREATE INDEX idx_v ON t(v)").unwrap();
+    assert!(guard.verify_integrity().is_ok());
+    assert!(matches!(
+        guard.begin_maintenance(Duration::ZERO),
+        Err(MuroError::MaintenanceInProgress)
+    ));
+    drop(guard);
+
+    assert_eq!(count(&mut other), 2);
+    other.execute("INSERT INTO t VALUES (2, 20)").unwrap();
+    match reader.query("SELECT COUNT(*) FROM t").unwrap()[0].values[0].1 {
+        Value::Integer(n) => assert_eq!(n, 3),
+        ref v => panic!("unexpected value {:?}", v),
+    }
+}
+
+#[test]
+fn test_maintenance_waits_for_open_transaction() {
+    let dir = TempDir::new().unwrap();
+    let db_path = dir.path().join("test.db");
+    let mut db = setup(&db_path);
+    let mut other = Database::open(&db_path, &test_key()).unwrap();
+    let mut bystander = db.open_reader().unwrap();
+    other.execute("BEGIN").unwrap();
+    other.execute("INSERT INTO t VALUES (2, 20)").unwrap();
+
+    let committed_at = std::thread::scope(|s| {
+        let waiter = s.spawn(|| {
+            let mut guard = db.begin_maintenance(Duration::from_secs(10)).unwrap();
+            let acquired_at = Instant::now();
+            assert_eq!(count(&mut guard), 2);
+            acquired_at
+        });
+        std::thread::sleep(Duration::from_millis(200));
+        // Pending maintenance lets the open transaction finish, and only it.
+        other.execute("INSERT INTO t VALUES (3, 30)").unwrap();
+        assert!(matches!(
+            bystander.query("SELECT COUNT(*) FROM t"),
+            Err(MuroError::MaintenanceInProgress)
+        ));
+        other.execute("DELETE FROM t WHERE id = 3").unwrap();
+        let committed_at = Instant::now();
+        other.execute("COMMIT").unwrap();
+        let acquired_at = waiter.join().unwrap();
+        assert!(acquired_at >= committed_at);
+        committed_at
+    });
+    assert!(committed_at.elapsed() < Duration::from_secs(10));
+    assert_eq!(count(&mut other), 2);
+}
+
+#[test]
+fn test_maintenance_times_out_with_blockers() {
+    let dir = TempDir::new().unwrap();
+    let db_path = dir.path().join("test.db");
+    let mut db = setup(&db_path);
+    let mut other = Database::open(&db_path, &test_key()).unwrap();
+    let mut reader = db.open_reader().unwrap();
+    other.execute("BEGIN").unwrap();
+    other.execute("UPDATE t SET v = 11 WHERE id = 1").unwrap();
+
+    // Holding a transaction of its own is refused outright.
+    db.execute("BEGIN").unwrap();
+    assert!(matches!(
+        db.begin_maintenance(Duration::from_secs(1)),
+        Err(MuroError::Transaction(_))
+    ));
+    db.execute("ROLLBACK").unwrap();
+
+    let started = Instant::now();
+    let err = match db.begin_maintenance(Duration::from_millis(100)) {
+        Err(e) => e,
+        Ok(_) => panic!("maintenance acquired past an open transaction"),
+    };
+    assert!(started.elapsed() >= Duration::from_millis(100));
+    match &err {
+        MuroError::Busy(detail) => {
+            assert!(detail.contains("transaction"), "{detail}");
+            assert!(detail.contains("open for"), "{detail}");
+        }
+        other => panic!("expected Busy, got {other:?}"),
+    }
+    assert!(err.is_retryable());
+
+    // Normal service resumes after the timeout.
+    assert_eq!(count(&mut db), 1);
+    assert!(reader.query("SELECT COUNT(*) FROM t").is_ok());
+    other.execute("COMMIT").unwrap();
+    let guard = db.begin_maintenance(Duration::from_secs(1)).unwrap();
+    drop(guard);
+}
@@ -0,0 +1,164 @@
+#![cfg(feature = "test-utils")]
+/// Key material and decrypted page images are zeroed before their memory is
+/// freed. A global allocator scans every block freed while a test watches
+/// for a canary byte pattern; finding it in freed memory fails the test.
+use murodb::crypto::aead::MasterKey;
+use murodb::crypto::hmac_util::TermKey;
+use murodb::storage::page::PAGE_HEADER_SIZE;
+use murodb::storage::pager::Pager;
+use murodb::wal::reader::WalReader;
+use murodb::wal::record::WalRecord;
+use murodb::wal::writer::WalWriter;
+use murodb::{Database, MuroError, Value};
+use std::alloc::{GlobalAlloc, Layout, System};
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::sync::Mutex;
+use tempfile::TempDir;
+
+const CANARY: [u8; 32] = *b"zeroize-canary-0123456789abcdef!";
+
+static WATCHING: AtomicBool = AtomicBool::new(false);
+static CANARIES_FREED: AtomicUsize = AtomicUsize::new(0);
+/// Tests run one at a time so a watch only sees its own test's frees.
+static SERIAL: Mutex<()> = Mutex::new(());
+
+struct CanaryAlloc;
+
+// SAFETY: every call is forwarded to `System`; `dealloc` only reads the
+// block it is handed before freeing it.
+unsafe impl GlobalAlloc for CanaryAlloc {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        System.alloc(layout)
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        if WATCHING.load(Ordering::Relaxed) && layout.size() >= CANARY.len() {
+            let block = std::slice::from_raw_parts(ptr, layout.size());
+            if block.windows(CANARY.len()).any(|w| w == CANARY) {
+                CANARIES_FREED.fetch_add(1, Ordering::Relaxed);
+            }
+        }
+        System.dealloc(ptr, layout)
+    }
+}
+
+#[global_allocator]
+static GLOBAL: CanaryAlloc = CanaryAlloc;
+
+/// Run `f` while watching freed memory; returns how many freed blocks
+/// still held the canary.
+fn canaries_freed_during(f: impl FnOnce()) -> usize {
+    let _serial = SERIAL.lock().unwrap_or_else(|e| e.into_inner());
+    CANARIES_FREED.store(0, Ordering::Relaxed);
+    WATCHING.store(true, Ordering::Relaxed);
+    f();
+    WATCHING.store(false, Ordering::Relaxed);
+    CANARIES_FREED.load(Ordering::Relaxed)
+}
+
+fn test_key() -> MasterKey {
+    MasterKey::new([0x42u8; 32])
+}
+
+#[test]
+fn test_allocator_detects_canary() {
+    let freed = canaries_freed_during(|| drop(Box::new(CANARY)));
+    assert_eq!(freed, 1);
+}
+
+#[test]
+fn test_keys_are_zeroed_on_drop() {
+    let freed = canaries_freed_during(|| {
+        let master = Box::new(MasterKey::new(CANARY));
+        let copy = master.clone();
+        drop(master);
+        drop(copy);
+        drop(Box::new(TermKey::new(CANARY)));
+        drop(Box::new(MasterKey::from_slice(&CANARY).unwrap()));
+    });
+    assert_eq!(freed, 0);
+}
+
+#[test]
+fn test_cached_pages_are_zeroed_on_eviction_and_drop() {
+    let dir = TempDir::new().unwrap();
+    let freed = canaries_freed_during(|| {
+        let mut pager = Pager::create(&dir.path().join("test.db"), &test_key()).unwrap();
+        pager.set_cache_capacity(4);
+        let mut page = pager.allocate_page().unwrap();
+        let id = page.page_id();
+        page.data[PAGE_HEADER_SIZE..PAGE_HEADER_SIZE + CANARY.len()].copy_from_slice(&CANARY);
+        pager.write_page(&page).unwrap();
+        // Push the page out of the cache, then read it back from disk.
+        for _ in 0..8 {
+            let other = pager.allocate_page().unwrap();
+            pager.write_page(&other).unwrap();
+        }
+        let read = pager.read_page(id).unwrap();
+        assert_eq!(&read.data[PAGE_HEADER_SIZE..][..CANARY.len()], &CANARY);
+        pager.set_cache_capacity(1);
+        drop(pager);
+    });
+    assert_eq!(freed, 0);
+}
+
+#[test]
+fn test_wal_frame_plaintext_is_zeroed() {
+    let dir = TempDir::new().unwrap();
+    let wal_path = dir.path().join("test.wal");
+    let freed = canaries_freed_during(|| {
+        let mut data = vec![0u8; 256];
+        data[..CANARY.len()].copy_from_slice(&CANARY);
+        let mut writer = WalWriter::create(&wal_path, &test_key()).unwrap();
+        writer.append(&WalRecord::Begin { txid: 1 }).unwrap();
+        writer
+            .append(&WalRecord::PagePut {
+                txid: 1,
+                page_id: 7,
+                data,
+            })
+            .unwrap();
+        writer.sync().unwrap();
+        drop(writer);
+
+        let mut reader = WalReader::open(&wal_path, &test_key()).unwrap();
+        let records = reader.read_all().unwrap();
+        assert!(matches!(
+            &records[1].1,
+            WalRecord::PagePut { data, .. } if data[..CANARY.len()] == CANARY
+        ));
+    });
+    assert_eq!(freed, 0);
+}
+
+#[test]
+fn test_lock_page_cache_setting() {
+    let dir = TempDir::new().unwrap();
+    let mut db = Database::create_with_password(&dir.path().join("test.db"), b"pw").unwrap();
+    db.execute("CREATE TABLE t (id BIGINT PRIMARY KEY, v VARCHAR)")
+        .unwrap();
+    db.execute("INSERT INTO t VALUES (1, 'a'), (2, 'b')")
+        .unwrap();
+
+    db.execute("BEGIN").unwrap();
+    let e = db.execute("SET lock_page_cache = ON").unwrap_err();
+    assert!(e.to_string().contains("inside a transaction"), "{}", e);
+    db.execute("ROLLBACK").unwrap();
+
+    match db.execute("SET lock_page_cache = ON") {
+        Ok(_) => {}
+        // Platforms without mlock, or a memlock limit below the cache size.
+        Err(MuroError::Execution(_) | MuroError::Io(_)) => return,
+        Err(e) => panic!("{}", e),
+    }
+    let show = db.query("SHOW VARIABLES LIKE 'lock_page_cache'").unwrap();
+    assert_eq!(show[0].get("value"), Some(&Value::Varchar("ON".into())));
+    db.execute("SET page_cache_pages = 16").unwrap();
+    db.execute("INSERT INTO t VALUES (3, 'c')").unwrap();
+    let rows = db.query("SELECT v FROM t ORDER BY id").unwrap();
+    assert_eq!(rows.len(), 3);
+    assert_eq!(rows[2].get("v"), Some(&Value::Varchar("c".into())));
+    db.execute("SET lock_page_cache = OFF").unwrap();
+    let show = db.query("SHOW VARIABLES LIKE 'lock_page_cache'").unwrap();
+    assert_eq!(show[0].get("value"), Some(&Value::Varchar("OFF".into())));
+}
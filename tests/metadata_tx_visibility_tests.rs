@@ -0,0 +1,170 @@
+#![cfg(feature = "test-utils")]
+/// Read-your-writes for metadata: inside a transaction, SHOW TABLES,
+/// DESCRIBE, SHOW CREATE TABLE, and SHOW INDEXES see the transaction's own
+/// uncommitted DDL, ROLLBACK reverts them, and other handles see the DDL only
+/// after COMMIT.
+use murodb::crypto::aead::MasterKey;
+use murodb::{Database, Value};
+use tempfile::TempDir;
+
+fn test_key() -> MasterKey {
+    MasterKey::new([0x42u8; 32])
+}
+
+fn column(db: &mut Database, sql: &str, name: &str) -> Vec<String> {
+    let mut values: Vec<String> = db
+        .query(sql)
+        .unwrap()
+        .iter()
+        .map(|r| match r.get(name) {
+            Some(Value::Varchar(s)) => s.clone(),
+            other => panic!("unexpected {} {:?}", name, other),
+        })
+        .collect();
+    values.sort();
+    values.dedup();
+    values
+}
+
+fn tables(db: &mut Database) -> Vec<String> {
+    let rows = db.query("SHOW TABLES").unwrap();
+    let mut names: Vec<String> = rows
+        .iter()
+        .map(|r| match r.values[0].1 {
+            Value::Varchar(ref s) => s.clone(),
+            ref other => panic!("unexpected {:?}", other),
+        })
+        .collect();
+    names.sort();
+    names
+}
+
+fn columns(db: &mut Database, table: &str) -> Vec<String> {
+    column(db, &format!("DESCRIBE {}", table), "Field")
+}
+
+fn indexes(db: &mut Database, table: &str) -> Vec<String> {
+    column(db, &format!("SHOW INDEXES FROM {}", table), "Key_name")
+}
+
+fn create_table_sql(db: &mut Database, table: &str) -> String {
+    let rows = db.query(&format!("SHOW CREATE TABLE {}", table)).unwrap();
+    match &rows[0].values[1].1 {
+        Value::Varchar(s) => s.clone(),
+        other => panic!("unexpected {:?}", other),
+    }
+}
+
+fn setup(dir: &TempDir) -> Database {
+    let mut db = Database::create(&dir.path().join("test.db"), &test_key()).unwrap();
+    db.execute_batch(
+        "CREATE TABLE users (id BIGINT PRIMARY KEY, name VARCHAR(50));
+         CREATE TABLE old_logs (id BIGINT PRIMARY KEY);
+         CREATE INDEX idx_users_name ON users (name);",
+    )
+    .unwrap();
+    db
+}
+
+fn apply_ddl(db: &mut Database) {
+    db.execute("BEGIN").unwrap();
+    db.execute_batch(
+        "CREATE TABLE notes (id BIGINT PRIMARY KEY, body TEXT);
+         ALTER TABLE users ADD COLUMN email VARCHAR(100);
+         CREATE INDEX idx_users_email ON users (email);
+         DROP INDEX idx_users_name;
+         DROP TABLE old_logs;",
+    )
+    .unwrap();
+}
+
+fn assert_ddl_visible(db: &mut Database) {
+    assert_eq!(tables(db), vec!["notes", "users"]);
+    assert_eq!(columns(db, "users"), vec!["email", "id", "name"]);
+    assert_eq!(columns(db, "notes"), vec!["body", "id"]);
+    assert_eq!(indexes(db, "users"), vec!["idx_users_email"]);
+    let create = create_table_sql(db, "users");
+    assert!(create.contains("email"), "{}", create);
+    assert!(db.query("DESCRIBE old_logs").is_err());
+}
+
+fn assert_ddl_invisible(db: &mut Database) {
+    assert_eq!(tables(db), vec!["old_logs", "users"]);
+    assert_eq!(columns(db, "users"), vec!["id", "name"]);
+    assert_eq!(indexes(db, "users"), vec!["idx_users_name"]);
+    let create = create_table_sql(db, "users");
+    assert!(!create.contains("email"), "{}", create);
+    assert!(db.query("DESCRIBE notes").is_err());
+}
+
+#[test]
+fn test_uncommitted_ddl_is_visible_to_metadata_statements() {
+    let dir = TempDir::new().unwrap();
+    let mut db = setup(&dir);
+    apply_ddl(&mut db);
+    assert_ddl_visible(&mut db);
+
+    // Through execute() as well as query(), and through prepared statements.
+    let rows = match db.execute("SHOW TABLES").unwrap() {
+        murodb::ExecResult::Rows(rows) => rows,
+        other => panic!("unexpected {:?}", other),
+    };
+    assert_eq!(rows.len(), 2);
+    let describe = db.prepare("DESCRIBE notes").unwrap();
+    assert_eq!(db.query_prepared(&describe, &[]).unwrap().len(), 2);
+    db.execute("COMMIT").unwrap();
+    assert_ddl_visible(&mut db);
+}
+
+#[test]
+fn test_rollback_reverts_metadata_view() {
+    let dir = TempDir::new().unwrap();
+    let mut db = setup(&dir);
+    apply_ddl(&mut db);
+    db.execute("ROLLBACK").unwrap();
+    assert_ddl_invisible(&mut db);
+
+    // Savepoints revert the metadata view to the savepoint.
+    db.execute("BEGIN").unwrap();
+    db.execute("CREATE TABLE kept (id BIGINT PRIMARY KEY)")
+        .unwrap();
+    db.execute("SAVEPOINT sp").unwrap();
+    db.execute("CREATE TABLE discarded (id BIGINT PRIMARY KEY)")
+        .unwrap();
+    assert_eq!(
+        tables(&mut db),
+        vec!["discarded", "kept", "old_logs", "users"]
+    );
+    db.execute("ROLLBACK TO SAVEPOINT sp").unwrap();
+    assert_eq!(tables(&mut db), vec!["kept", "old_logs", "users"]);
+    db.execute("ROLLBACK").unwrap();
+    assert_eq!(tables(&mut db), vec!["old_logs", "users"]);
+}
+
+#[test]
+fn test_other_handles_see_ddl_only_after_commit() {
+    let dir = TempDir::new().unwrap();
+    let mut db = setup(&dir);
+    let mut other = Database::open(&dir.path().join("test.db"), &test_key()).unwrap();
+    apply_ddl(&mut db);
+    assert_ddl_invisible(&mut other);
+    db.execute("COMMIT").unwrap();
+    assert_ddl_visible(&mut other);
+}
+
+#[test]
+fn test_session_read_only_queries_join_the_transaction() {
+    let dir = TempDir::new().unwrap();
+    let mut session = setup(&dir).into_session();
+    session.execute("BEGIN").unwrap();
+    session
+        .execute("CREATE TABLE notes (id BIGINT PRIMARY KEY)")
+        .unwrap();
+    let rows = session.execute_read_only_query("SHOW TABLES").unwrap();
+    assert_eq!(rows.len(), 3);
+    let rows = session.execute_read_only_query("DESCRIBE notes").unwrap();
+    assert_eq!(rows.len(), 1);
+    session.execute("ROLLBACK").unwrap();
+    let rows = session.execute_read_only_query("SHOW TABLES").unwrap();
+    assert_eq!(rows.len(), 2);
+}
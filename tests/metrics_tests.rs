@@ -0,0 +1,238 @@
+#![cfg(feature = "test-utils")]
+/// Prometheus text exposition from `Database::metrics_prometheus`.
+use murodb::crypto::aead::MasterKey;
+use murodb::Database;
+use prometheus_parse::{LineInfo, Scrape, Value as PromValue};
+use std::collections::BTreeMap;
+use tempfile::TempDir;
+
+fn test_key() -> MasterKey {
+    MasterKey::new([0x42u8; 32])
+}
+
+#[derive(Debug)]
+struct Family {
+    kind: &'static str,
+    /// Value of each sample, by its `table` label ("" when it has none).
+    samples: BTreeMap<String, f64>,
+}
+
+impl Family {
+    fn value(&self) -> f64 {
+        self.samples[""]
+    }
+}
+
+/// Parse `text` with the `prometheus-parse` crate. Every line must be a
+/// HELP, TYPE or sample line the parser recognises, and every sample must
+/// have the HELP and a counter or gauge TYPE of its metric and the hashed
+/// `db` label.
+fn parse_exposition(text: &str) -> BTreeMap<String, Family> {
+    let mut sample_lines = 0;
+    for line in text.lines() {
+        match LineInfo::parse(line) {
+            LineInfo::Doc { .. } | LineInfo::Type { .. } => {}
+            LineInfo::Sample { .. } => sample_lines += 1,
+            _ => panic!("unrecognised line {:?}", line),
+        }
+    }
+    let scrape = Scrape::parse(text.lines().map(|l| Ok(l.to_string()))).unwrap();
+    assert_eq!(scrape.samples.len(), sample_lines, "unparsed samples");
+
+    let mut families: BTreeMap<String, Family> = BTreeMap::new();
+    for sample in &scrape.samples {
+        let name = &sample.metric;
+        let (kind, value) = match sample.value {
+            PromValue::Counter(v) => ("counter", v),
+            PromValue::Gauge(v) => ("gauge", v),
+            ref other => panic!("{} has no counter or gauge TYPE: {:?}", name, other),
+        };
+        assert!(
+            scrape.docs.get(name).is_some_and(|help| !help.is_empty()),
+            "{} has no HELP",
+            name
+        );
+        if kind == "counter" {
+            assert!(
+                name.ends_with("_total"),
+                "counter {name} must end in _total"
+            );
+        }
+        assert!(value.is_finite());
+
+        let hash = sample
+            .labels
+            .get("db")
+            .unwrap_or_else(|| panic!("{} has no db label", name));
+        assert_eq!(hash.len(), 16);
+        assert!(hash.chars().all(|c| c.is_ascii_hexdigit()));
+        let table = sample.labels.get("table").unwrap_or("");
+        let expected_labels = if table.is_empty() { 1 } else { 2 };
+        assert_eq!(sample.labels.len(), expected_labels, "{} labels", name);
+
+        let family = families.entry(name.clone()).or_insert(Family {
+            kind,
+            samples: BTreeMap::new(),
+        });
+        assert_eq!(family.kind, kind);
+        let prev = family.samples.insert(table.to_string(), value);
+        assert!(prev.is_none(), "duplicate sample of {}", name);
+    }
+    families
+}
+
+#[test]
+fn test_metrics_prometheus_format_and_golden_names() {
+    let dir = TempDir::new().unwrap();
+    let db_path = dir.path().join("test.db");
+    let mut db = Database::create(&db_path, &test_key()).unwrap();
+    // Give the per-table metric a sample so its TYPE can be read back.
+    db.execute("CREATE TABLE t (id BIGINT PRIMARY KEY)")
+        .unwrap();
+
+    let text = db.metrics_prometheus();
+    let families = parse_exposition(&text);
+
+    let actual: String = families
+        .iter()
+        .map(|(name, f)| format!("{} {}\n", name, f.kind))
+        .collect();
+    let golden = include_str!("golden/metrics_prometheus_names.txt");
+    assert_eq!(
+        actual, golden,
+        "metric names/types changed; names are a stable contract"
+    );
+    assert!(!text.contains(dir.path().to_str().unwrap()));
+}
+
+#[test]
+fn test_metrics_counters_move() {
+    let dir = TempDir::new().unwrap();
+    let db_path = dir.path().join("test.db");
+    let mut db = Database::create(&db_path, &test_key()).unwrap();
+
+    let before = parse_exposition(&db.metrics_prometheus());
+    db.execute("CREATE TABLE t (id BIGINT PRIMARY KEY, v VARCHAR)")
+        .unwrap();
+    for i in 0..20 {
+        db.execute(&format!("INSERT INTO t VALUES ({}, 'v')", i))
+            .unwrap();
+    }
+    db.query("SELECT * FROM t").unwrap();
+    let after = parse_exposition(&db.metrics_prometheus());
+
+    let delta = |name: &str| after[name].value() - before[name].value();
+    assert!(delta("murodb_checkpoints_total") >= 21.0);
+    assert!(delta("murodb_pager_cache_hits_total") > 0.0);
+    assert!(after["murodb_pages"].value() > before["murodb_pages"].value());
+    for (name, f) in &after {
+        if f.kind == "counter" {
+            for (table, value) in &f.samples {
+                let prev = before
+                    .get(name)
+                    .and_then(|f| f.samples.get(table).copied())
+                    .unwrap_or(0.0);
+                assert!(*value >= prev, "{} {} decreased", name, table);
+            }
+        }
+    }
+
+    // The same database path yields the same label from a reader handle.
+    let reader = db.open_reader().unwrap();
+    let text = db.metrics_prometheus();
+    let label = |text: &str| {
+        let line = text
+            .lines()
+            .find(|l| l.starts_with("murodb_pages{"))
+            .unwrap();
+        line.split_whitespace().next().unwrap().to_string()
+    };
+    assert_eq!(label(&reader.metrics_prometheus()), label(&text));
+}
+
+#[test]
+fn test_metrics_table_versions_count_committed_writes() {
+    let dir = TempDir::new().unwrap();
+    let db_path = dir.path().join("test.db");
+    let mut db = Database::create(&db_path, &test_key()).unwrap();
+    let version = |db: &Database, table: &str| {
+        parse_exposition(&db.metrics_prometheus())["murodb_table_version_total"]
+            .samples
+            .get(table)
+            .copied()
+            .unwrap_or(0.0)
+    };
+
+    db.execute("CREATE TABLE a (id BIGINT PRIMARY KEY)")
+        .unwrap();
+    db.execute("CREATE TABLE b (id BIGINT PRIMARY KEY)")
+        .unwrap();
+    assert_eq!(version(&db, "a"), 1.0);
+
+    db.execute("INSERT INTO a VALUES (1)").unwrap();
+    db.execute("INSERT INTO a VALUES (2)").unwrap();
+    assert_eq!(version(&db, "a"), 3.0);
+
+    // Statements that change no rows, and failed ones, do not count.
+    db.execute("UPDATE a SET id = 5 WHERE id = 99").unwrap();
+    assert!(db.execute("INSERT INTO a VALUES (1)").is_err());
+    assert_eq!(version(&db, "a"), 3.0);
+
+    // One explicit transaction counts once per table, at COMMIT.
+    db.execute("BEGIN").unwrap();
+    db.execute("INSERT INTO a VALUES (3)").unwrap();
+    db.execute("INSERT INTO a VALUES (4)").unwrap();
+    db.execute("INSERT INTO b VALUES (1)").unwrap();
+    assert_eq!(version(&db, "a"), 3.0);
+    db.execute("COMMIT").unwrap();
+    assert_eq!(version(&db, "a"), 4.0);
+    assert_eq!(version(&db, "b"), 2.0);
+
+    db.execute("BEGIN").unwrap();
+    db.execute("DELETE FROM a").unwrap();
+    db.execute("ROLLBACK").unwrap();
+    assert_eq!(version(&db, "a"), 4.0);
+}
+
+#[test]
+fn test_metrics_fts_gc_backlog() {
+    let dir = TempDir::new().unwrap();
+    let db_path = dir.path().join("test.db");
+    let mut db = Database::create(&db_path, &test_key()).unwrap();
+    let backlog = |db: &Database| {
+        parse_exposition(&db.metrics_prometheus())["murodb_fts_gc_backlog_tasks"].value()
+    };
+
+    db.execute("CREATE TABLE docs (id BIGINT PRIMARY KEY, body TEXT)")
+        .unwrap();
+    db.execute("CREATE FULLTEXT INDEX ft ON docs(body) WITH PARSER ngram")
+        .unwrap();
+    assert_eq!(backlog(&db), 0.0);
+    for i in 0..40 {
+        db.execute(&format!(
+            "INSERT INTO docs VALUES ({}, 'document number {} about databases')",
+            i, i
+        ))
+        .unwrap();
+    }
+    for i in 0..40 {
+        db.execute(&format!("DELETE FROM docs WHERE id = {}", i))
+            .unwrap();
+    }
+    let queued = backlog(&db);
+    assert!(queued > 0.0, "no FTS GC tasks queued");
+
+    // A reopened handle reads the backlog at open.
+    drop(db);
+    let mut db = Database::open(&db_path, &test_key()).unwrap();
+    assert_eq!(backlog(&db), queued);
+
+    // Each OPTIMIZE vacuums a budget's worth of tasks.
+    let mut remaining = queued;
+    while remaining > 0.0 {
+        db.execute("OPTIMIZE").unwrap();
+        let now = backlog(&db);
+        assert!(now < remaining, "backlog did not shrink: {}", now);
+        remaining = now;
+    }
+}
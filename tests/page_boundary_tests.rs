@@ -289,3 +289,84 @@ fn test_gradual_size_increase_with_overflow() {
         assert_eq!(rows[0].get("data"), Some(&Value::Varchar(expected)));
     }
 }
+
+/// Rewriting and deleting large values frees their overflow chains, so the
+/// file stops growing once the freed pages are reused.
+#[test]
+fn test_superseded_overflow_chains_are_reused() {
+    let (mut pager, mut catalog, _dir) = setup();
+    exec(
+        &mut pager,
+        &mut catalog,
+        "CREATE TABLE t (id BIGINT PRIMARY KEY, body TEXT)",
+    );
+    let sql = format!("INSERT INTO t VALUES (1, '{}')", "a".repeat(20_000));
+    exec(&mut pager, &mut catalog, &sql);
+    let pages_after_insert = pager.page_count();
+
+    for round in 0..20 {
+        let fill = char::from(b'b' + (round % 20) as u8);
+        let sql = format!(
+            "UPDATE t SET body = '{}' WHERE id = 1",
+            fill.to_string().repeat(20_000)
+        );
+        exec(&mut pager, &mut catalog, &sql);
+    }
+    assert!(
+        pager.page_count() <= pages_after_insert + 6,
+        "updates leaked overflow pages: {} -> {}",
+        pages_after_insert,
+        pager.page_count()
+    );
+
+    exec(&mut pager, &mut catalog, "DELETE FROM t WHERE id = 1");
+    let sql = format!("INSERT INTO t VALUES (2, '{}')", "z".repeat(20_000));
+    exec(&mut pager, &mut catalog, &sql);
+    assert!(pager.page_count() <= pages_after_insert + 6);
+    let rows = query_rows(&mut pager, &mut catalog, "SELECT body FROM t WHERE id = 2");
+    assert_eq!(
+        rows[0].get("body"),
+        Some(&Value::Varchar("z".repeat(20_000)))
+    );
+}
+
+/// TEXT and VARBINARY values larger than a page survive ALTER TABLE
+/// rewrites of their rows.
+#[test]
+fn test_alter_table_keeps_overflow_values() {
+    let (mut pager, mut catalog, _dir) = setup();
+    exec(
+        &mut pager,
+        &mut catalog,
+        "CREATE TABLE docs (id BIGINT PRIMARY KEY, body TEXT, blob VARBINARY)",
+    );
+    let text = "document ".repeat(2_000);
+    let hex = "ab".repeat(12_000);
+    for id in 0..5 {
+        let sql = format!("INSERT INTO docs VALUES ({}, '{}', X'{}')", id, text, hex);
+        exec(&mut pager, &mut catalog, &sql);
+    }
+
+    exec(
+        &mut pager,
+        &mut catalog,
+        "ALTER TABLE docs ADD COLUMN title VARCHAR(100) DEFAULT 'untitled'",
+    );
+    exec(
+        &mut pager,
+        &mut catalog,
+        "ALTER TABLE docs MODIFY COLUMN body TEXT NOT NULL",
+    );
+    exec(
+        &mut pager,
+        &mut catalog,
+        "ALTER TABLE docs DROP COLUMN title",
+    );
+
+    let rows = query_rows(&mut pager, &mut catalog, "SELECT * FROM docs ORDER BY id");
+    assert_eq!(rows.len(), 5);
+    for row in &rows {
+        assert_eq!(row.get("body"), Some(&Value::Varchar(text.clone())));
+        assert_eq!(row.get("blob"), Some(&Value::Varbinary(vec![0xab; 12_000])));
+    }
+}
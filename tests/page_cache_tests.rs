@@ -0,0 +1,199 @@
+#![cfg(feature = "test-utils")]
+/// Page cache sizing, eviction, and observability.
+use murodb::btree::ops::BTree;
+use murodb::crypto::aead::MasterKey;
+use murodb::sql::executor::ExecResult;
+use murodb::storage::page::PAGE_SIZE;
+use murodb::storage::pager::Pager;
+use murodb::types::Value;
+use murodb::{Database, OpenOptions};
+use tempfile::TempDir;
+
+fn test_key() -> MasterKey {
+    MasterKey::new([0x42u8; 32])
+}
+
+fn stat(db: &mut Database, name: &str) -> u64 {
+    let rows = match db.execute("SHOW DATABASE STATS").unwrap() {
+        ExecResult::Rows(rows) => rows,
+        _ => panic!("Expected rows"),
+    };
+    let row = rows
+        .iter()
+        .find(|r| r.get("stat") == Some(&Value::Varchar(name.to_string())))
+        .unwrap_or_else(|| panic!("missing stat {}", name));
+    match row.get("value") {
+        Some(Value::Varchar(v)) => v.parse().unwrap(),
+        other => panic!("unexpected value for {}: {:?}", name, other),
+    }
+}
+
+fn count(db: &mut Database, sql: &str) -> i64 {
+    match db.execute(sql).unwrap() {
+        ExecResult::Rows(rows) => match rows[0].values[0].1 {
+            Value::Integer(n) => n,
+            ref other => panic!("Expected integer, got {:?}", other),
+        },
+        _ => panic!("Expected rows"),
+    }
+}
+
+fn populate(db: &mut Database, rows: usize) {
+    db.execute("CREATE TABLE t (id BIGINT PRIMARY KEY, body VARCHAR)")
+        .unwrap();
+    let body = "b".repeat(500);
+    for i in 0..rows {
+        db.execute(&format!("INSERT INTO t VALUES ({}, '{}')", i, body))
+            .unwrap();
+    }
+}
+
+#[test]
+fn test_open_with_options_bounds_cache() {
+    let dir = TempDir::new().unwrap();
+    let db_path = dir.path().join("test.db");
+    {
+        let mut db = Database::create(&db_path, &test_key()).unwrap();
+        populate(&mut db, 400);
+    }
+
+    let mut db = Database::open_with_options(
+        &db_path,
+        &test_key(),
+        OpenOptions {
+            page_cache_pages: 8,
+            ..OpenOptions::default()
+        },
+    )
+    .unwrap();
+    assert_eq!(stat(&mut db, "pager_cache_capacity_pages"), 8);
+
+    assert_eq!(count(&mut db, "SELECT COUNT(body) FROM t"), 400);
+    assert_eq!(count(&mut db, "SELECT COUNT(body) FROM t"), 400);
+    assert!(stat(&mut db, "pager_cache_evictions") > 0);
+    assert!(stat(&mut db, "pager_cache_bytes") <= 8 * PAGE_SIZE as u64);
+
+    // Readers inherit the handle's cache capacity.
+    let mut reader = db.open_reader().unwrap();
+    let rows = reader.query("SELECT id FROM t WHERE id = 399").unwrap();
+    assert_eq!(rows.len(), 1);
+}
+
+#[test]
+fn test_uncommitted_pages_survive_cache_pressure() {
+    let dir = TempDir::new().unwrap();
+    let db_path = dir.path().join("test.db");
+    drop(Database::create(&db_path, &test_key()).unwrap());
+
+    let mut db = Database::open_with_options(
+        &db_path,
+        &test_key(),
+        OpenOptions {
+            page_cache_pages: 2,
+            ..OpenOptions::default()
+        },
+    )
+    .unwrap();
+    db.execute("CREATE TABLE t (id BIGINT PRIMARY KEY, body VARCHAR)")
+        .unwrap();
+    db.execute("BEGIN").unwrap();
+    let body = "x".repeat(500);
+    for i in 0..200 {
+        db.execute(&format!("INSERT INTO t VALUES ({}, '{}')", i, body))
+            .unwrap();
+    }
+    // The transaction spans far more pages than the cache holds.
+    assert_eq!(count(&mut db, "SELECT COUNT(*) FROM t"), 200);
+    db.execute("COMMIT").unwrap();
+    assert_eq!(count(&mut db, "SELECT COUNT(*) FROM t"), 200);
+}
+
+#[cfg(target_os = "linux")]
+fn rss_bytes() -> u64 {
+    let status = std::fs::read_to_string("/proc/self/status").unwrap();
+    let line = status
+        .lines()
+        .find(|l| l.starts_with("VmRSS:"))
+        .expect("VmRSS in /proc/self/status");
+    let kb: u64 = line.split_whitespace().nth(1).unwrap().parse().unwrap();
+    kb * 1024
+}
+
+#[cfg(target_os = "linux")]
+#[test]
+fn test_scan_larger_than_cache_keeps_rss_bounded() {
+    let dir = TempDir::new().unwrap();
+    let db_path = dir.path().join("test.db");
+    let value = vec![0xABu8; 1000];
+    let entries = 8_000i64; // ~8 MiB of leaf data
+    let root = {
+        let mut pager = Pager::create_plaintext(&db_path).unwrap();
+        let mut btree = BTree::create(&mut pager).unwrap();
+        for i in 0..entries {
+            btree.insert(&mut pager, &i.to_be_bytes(), &value).unwrap();
+        }
+        pager.flush_meta().unwrap();
+        btree.root_page_id()
+    };
+
+    let mut pager = Pager::open_plaintext(&db_path).unwrap();
+    pager.set_cache_capacity(32);
+    let btree = BTree::open(root);
+    let before = rss_bytes();
+    let mut seen = 0i64;
+    btree
+        .scan(&mut pager, |_, v| {
+            assert_eq!(v.len(), value.len());
+            seen += 1;
+            Ok(true)
+        })
+        .unwrap();
+    let grown = rss_bytes().saturating_sub(before);
+
+    assert_eq!(seen, entries);
+    assert!(pager.cache_bytes() <= 32 * PAGE_SIZE as u64);
+    assert!(pager.cache_evictions() > 0);
+    assert!(
+        grown < 4 * 1024 * 1024,
+        "RSS grew by {} bytes while scanning ~8 MiB with a 32-page cache",
+        grown
+    );
+}
+
+#[test]
+fn test_internal_pages_stay_cached_across_large_scans() {
+    let dir = TempDir::new().unwrap();
+    let db_path = dir.path().join("test.db");
+    {
+        let mut db = Database::create(&db_path, &test_key()).unwrap();
+        db.execute("CREATE TABLE t (id BIGINT PRIMARY KEY, k BIGINT, body VARCHAR)")
+            .unwrap();
+        db.execute("CREATE UNIQUE INDEX idx_k ON t(k)").unwrap();
+        let body = "b".repeat(500);
+        db.execute("BEGIN").unwrap();
+        for i in 0..2000 {
+            db.execute(&format!("INSERT INTO t VALUES ({}, {}, '{}')", i, i, body))
+                .unwrap();
+        }
+        db.execute("COMMIT").unwrap();
+    }
+
+    // The full scan reads far more pages than the default cache holds.
+    let mut db = Database::open(&db_path, &test_key()).unwrap();
+    let narrow = "SELECT COUNT(*) FROM t WHERE k >= 1000 AND k < 1003";
+    let decrypted_by = |db: &mut Database, sql: &str, expected: i64| {
+        let before = stat(db, "pager_pages_decrypted");
+        assert_eq!(count(db, sql), expected);
+        stat(db, "pager_pages_decrypted") - before
+    };
+    let cold = decrypted_by(&mut db, narrow, 3);
+    assert_eq!(decrypted_by(&mut db, narrow, 3), 0);
+    assert!(decrypted_by(&mut db, "SELECT COUNT(body) FROM t", 2000) > 256);
+    assert!(stat(&mut db, "pager_cache_evictions") > 0);
+
+    // The index and table root-to-leaf paths stayed cached: only leaves
+    // are decrypted again.
+    let after_scan = decrypted_by(&mut db, narrow, 3);
+    assert!(after_scan < cold, "after_scan {} cold {}", after_scan, cold);
+    assert!(after_scan <= 3, "{}", after_scan);
+}
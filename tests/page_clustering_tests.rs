@@ -0,0 +1,144 @@
+#![cfg(feature = "test-utils")]
+/// Locality-aware page allocation, SHOW TABLE STATUS, and OPTIMIZE TABLE.
+use murodb::crypto::aead::MasterKey;
+use murodb::{Database, ExecResult, Session, Value};
+use std::path::Path;
+use tempfile::TempDir;
+
+fn test_key() -> MasterKey {
+    MasterKey::new([0x42u8; 32])
+}
+
+/// Two tables filled in lockstep, so their splits compete for pages.
+fn interleaved(db_path: &Path, hints: bool, rows: i64) -> Session {
+    let mut session = Database::create(db_path, &test_key())
+        .unwrap()
+        .into_session();
+    session.pager_mut().set_allocation_hints(hints);
+    for t in ["a", "b"] {
+        session
+            .execute(&format!(
+                "CREATE TABLE {} (id BIGINT PRIMARY KEY, body VARCHAR)",
+                t
+            ))
+            .unwrap();
+    }
+    let body = "x".repeat(200);
+    for i in 0..rows {
+        for t in ["a", "b"] {
+            session
+                .execute(&format!("INSERT INTO {} VALUES ({}, '{}')", t, i, body))
+                .unwrap();
+        }
+    }
+    session
+}
+
+/// (Name, Tree) -> (Leaf_pages, Leaf_gap) from SHOW TABLE STATUS.
+fn status(session: &mut Session, table: &str, tree: &str) -> (i64, f64) {
+    let rows = match session.execute("SHOW TABLE STATUS").unwrap() {
+        ExecResult::Rows(rows) => rows,
+        other => panic!("expected rows, got {:?}", other),
+    };
+    let row = rows
+        .iter()
+        .find(|r| {
+            r.get("Name") == Some(&Value::Varchar(table.into()))
+                && r.get("Tree") == Some(&Value::Varchar(tree.into()))
+        })
+        .unwrap_or_else(|| panic!("no status row for {}.{}", table, tree));
+    let leaves = match row.get("Leaf_pages") {
+        Some(Value::Integer(n)) => *n,
+        other => panic!("unexpected Leaf_pages {:?}", other),
+    };
+    let gap = match row.get("Leaf_gap") {
+        Some(Value::Float(g)) => *g,
+        other => panic!("unexpected Leaf_gap {:?}", other),
+    };
+    (leaves, gap)
+}
+
+fn scan(session: &mut Session, table: &str) -> Vec<i64> {
+    match session
+        .execute(&format!("SELECT id FROM {} ORDER BY id", table))
+        .unwrap()
+    {
+        ExecResult::Rows(rows) => rows
+            .iter()
+            .map(|row| match row.get("id") {
+                Some(Value::Integer(id)) => *id,
+                other => panic!("unexpected id {:?}", other),
+            })
+            .collect(),
+        other => panic!("expected rows, got {:?}", other),
+    }
+}
+
+#[test]
+fn test_hints_cluster_interleaved_tables() {
+    let dir = TempDir::new().unwrap();
+    let mut plain = interleaved(&dir.path().join("plain.db"), false, 600);
+    let mut hinted = interleaved(&dir.path().join("hinted.db"), true, 600);
+
+    for t in ["a", "b"] {
+        let (plain_leaves, plain_gap) = status(&mut plain, t, "PRIMARY");
+        let (hinted_leaves, hinted_gap) = status(&mut hinted, t, "PRIMARY");
+        assert!(plain_leaves > 20 && hinted_leaves > 20);
+        assert!(plain_gap >= 1.9, "{} plain gap {}", t, plain_gap);
+        assert!(
+            hinted_gap < plain_gap * 0.75,
+            "{} hinted gap {} vs plain {}",
+            t,
+            hinted_gap,
+            plain_gap
+        );
+        let expected: Vec<i64> = (0..600).collect();
+        assert_eq!(scan(&mut plain, t), expected);
+        assert_eq!(scan(&mut hinted, t), expected);
+    }
+}
+
+#[test]
+fn test_optimize_table_restores_clustering() {
+    let dir = TempDir::new().unwrap();
+    let db_path = dir.path().join("test.db");
+    let mut session = interleaved(&db_path, false, 400);
+    session.execute("CREATE INDEX idx_body ON a(body)").unwrap();
+    session.execute("DELETE FROM a WHERE id % 3 = 0").unwrap();
+    let expected = scan(&mut session, "a");
+    let (_, before) = status(&mut session, "a", "PRIMARY");
+    assert!(before >= 1.9, "{}", before);
+
+    session.execute("OPTIMIZE TABLE a").unwrap();
+    let (_, after) = status(&mut session, "a", "PRIMARY");
+    assert!(after < 1.1, "{}", after);
+    assert_eq!(scan(&mut session, "a"), expected);
+    let n = match session
+        .execute("SELECT COUNT(*) AS n FROM a FORCE INDEX (idx_body) WHERE body > ''")
+        .unwrap()
+    {
+        ExecResult::Rows(rows) => rows[0].get("n").cloned(),
+        other => panic!("expected rows, got {:?}", other),
+    };
+    assert_eq!(n, Some(Value::Integer(expected.len() as i64)));
+    match session.execute("CHECK TABLE a").unwrap() {
+        ExecResult::Rows(rows) => {
+            for row in &rows {
+                assert_eq!(row.get("status"), Some(&Value::Varchar("ok".into())));
+            }
+        }
+        other => panic!("expected rows, got {:?}", other),
+    }
+    drop(session);
+
+    // The rebuilt trees are durable and stay writable.
+    let mut session = Database::open(&db_path, &test_key())
+        .unwrap()
+        .into_session();
+    assert_eq!(scan(&mut session, "a"), expected);
+    session
+        .execute("INSERT INTO a VALUES (1000, 'after')")
+        .unwrap();
+    assert_eq!(scan(&mut session, "a").last(), Some(&1000));
+    assert_eq!(scan(&mut session, "b").len(), 400);
+}
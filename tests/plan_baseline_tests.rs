@@ -0,0 +1,216 @@
+#![cfg(feature = "test-utils")]
+/// Plan baselines: capture, install, and reuse of a pinned plan per
+/// statement shape, invalidation, and the session switch.
+use murodb::crypto::aead::MasterKey;
+use murodb::{Database, PlanAccess, Value};
+use tempfile::TempDir;
+
+fn test_key() -> MasterKey {
+    MasterKey::new([0x42u8; 32])
+}
+
+/// `t(a, b)` with an index on `a` only; `a` has two values, `b` is unique.
+fn setup(dir: &TempDir) -> Database {
+    let mut db = Database::create(&dir.path().join("test.db"), &test_key()).unwrap();
+    db.execute("CREATE TABLE t (id BIGINT PRIMARY KEY, a INT, b INT)")
+        .unwrap();
+    db.execute("CREATE INDEX idx_a ON t (a)").unwrap();
+    let values: Vec<String> = (1..=400)
+        .map(|i| format!("({}, {}, {})", i, i % 2, i))
+        .collect();
+    db.execute(&format!("INSERT INTO t VALUES {}", values.join(", ")))
+        .unwrap();
+    db.execute("ANALYZE TABLE t").unwrap();
+    db
+}
+
+/// `(key, Extra)` of EXPLAIN for `sql`.
+fn explain(db: &mut Database, sql: &str) -> (String, String) {
+    let rows = db.query(&format!("EXPLAIN {}", sql)).unwrap();
+    let text = |name: &str| match rows[0].get(name) {
+        Some(Value::Varchar(s)) => s.clone(),
+        _ => String::new(),
+    };
+    (text("key"), text("Extra"))
+}
+
+fn ids(db: &mut Database, sql: &str) -> Vec<i64> {
+    db.query(sql)
+        .unwrap()
+        .iter()
+        .map(|row| match row.get("id") {
+            Some(Value::Integer(n)) => *n,
+            other => panic!("unexpected id {:?}", other),
+        })
+        .collect()
+}
+
+fn stat(db: &mut Database, name: &str) -> u64 {
+    db.query("SHOW DATABASE STATS")
+        .unwrap()
+        .iter()
+        .find(|row| row.get("stat") == Some(&Value::Varchar(name.into())))
+        .and_then(|row| match row.get("value") {
+            Some(Value::Varchar(v)) => v.parse().ok(),
+            _ => None,
+        })
+        .unwrap_or_else(|| panic!("missing stat {}", name))
+}
+
+fn warnings(db: &mut Database) -> Vec<String> {
+    db.query("SHOW WARNINGS")
+        .unwrap()
+        .iter()
+        .filter_map(|row| match row.get("message") {
+            Some(Value::Varchar(m)) => Some(m.clone()),
+            _ => None,
+        })
+        .collect()
+}
+
+const QUERY: &str = "SELECT id FROM t WHERE a = 1 AND b = 7";
+
+#[test]
+fn test_capture_install_and_use_round_trip() {
+    let dir = TempDir::new().unwrap();
+    let mut db = setup(&dir);
+
+    let baseline = db.capture_baseline(QUERY).unwrap();
+    assert_eq!(baseline.shape, "t WHERE (a = ?) AND (b = ?)");
+    assert_eq!(baseline.plan.access, PlanAccess::Ref);
+    assert_eq!(baseline.plan.index.as_deref(), Some("idx_a"));
+    db.install_baseline(&baseline).unwrap();
+
+    // A better index appears; the baseline keeps the captured plan.
+    db.execute("CREATE INDEX idx_b ON t (b)").unwrap();
+    let (key, extra) = explain(&mut db, QUERY);
+    assert_eq!(key, "idx_a");
+    assert!(extra.contains("Using plan baseline"), "{}", extra);
+    assert_eq!(ids(&mut db, QUERY), vec![7]);
+    assert!(stat(&mut db, "plan_baseline_hits") >= 2);
+
+    // Capturing again records the planner's current choice, not the baseline.
+    let recaptured = db.capture_baseline(QUERY).unwrap();
+    assert_eq!(recaptured.plan.index.as_deref(), Some("idx_b"));
+
+    // Baselines are stored in the catalog and survive a reopen.
+    drop(db);
+    let mut db = Database::open(&dir.path().join("test.db"), &test_key()).unwrap();
+    assert_eq!(explain(&mut db, QUERY).0, "idx_a");
+    assert_eq!(stat(&mut db, "plan_baseline_fallbacks"), 0);
+
+    assert!(db.remove_baseline(&baseline.shape).unwrap());
+    assert!(!db.remove_baseline(&baseline.shape).unwrap());
+    assert_eq!(explain(&mut db, QUERY).0, "idx_b");
+}
+
+#[test]
+fn test_drop_index_invalidates_baseline() {
+    let dir = TempDir::new().unwrap();
+    let mut db = setup(&dir);
+    db.execute("CREATE INDEX idx_b ON t (b)").unwrap();
+    let baseline = db.capture_baseline(QUERY).unwrap();
+    assert_eq!(baseline.plan.index.as_deref(), Some("idx_b"));
+    db.install_baseline(&baseline).unwrap();
+    assert_eq!(explain(&mut db, QUERY).0, "idx_b");
+
+    db.execute("DROP INDEX idx_b").unwrap();
+    assert_eq!(ids(&mut db, QUERY), vec![7]);
+    let messages = warnings(&mut db);
+    assert!(
+        messages
+            .iter()
+            .any(|m| m.contains("index 'idx_b' no longer exists")),
+        "{:?}",
+        messages
+    );
+    assert_eq!(explain(&mut db, QUERY).0, "idx_a");
+    assert_eq!(stat(&mut db, "plan_baseline_hits"), 1);
+    assert_eq!(stat(&mut db, "plan_baseline_fallbacks"), 2);
+
+    // Recreating the index makes the baseline usable again.
+    db.execute("CREATE INDEX idx_b ON t (b)").unwrap();
+    db.execute("DROP INDEX idx_a").unwrap();
+    db.execute("CREATE INDEX idx_a ON t (a)").unwrap();
+    assert_eq!(explain(&mut db, QUERY).0, "idx_b");
+
+    // A changed table definition invalidates it as well.
+    db.execute("ALTER TABLE t ADD COLUMN c INT").unwrap();
+    assert_eq!(ids(&mut db, QUERY), vec![7]);
+    let messages = warnings(&mut db);
+    assert!(
+        messages
+            .iter()
+            .any(|m| m.contains("table 't' changed since it was captured")),
+        "{:?}",
+        messages
+    );
+}
+
+#[test]
+fn test_disable_switch_plans_normally() {
+    let dir = TempDir::new().unwrap();
+    let mut db = setup(&dir);
+    let baseline = db.capture_baseline(QUERY).unwrap();
+    db.install_baseline(&baseline).unwrap();
+    db.execute("CREATE INDEX idx_b ON t (b)").unwrap();
+    assert_eq!(explain(&mut db, QUERY).0, "idx_a");
+
+    db.execute("SET plan_baselines = 'off'").unwrap();
+    assert!(!db.plan_baselines_enabled());
+    let hits = stat(&mut db, "plan_baseline_hits");
+    let (key, extra) = explain(&mut db, QUERY);
+    assert_eq!(key, "idx_b");
+    assert!(!extra.contains("plan baseline"), "{}", extra);
+    assert_eq!(ids(&mut db, QUERY), vec![7]);
+    assert_eq!(stat(&mut db, "plan_baseline_hits"), hits);
+
+    db.set_plan_baselines_enabled(true);
+    assert_eq!(explain(&mut db, QUERY).0, "idx_a");
+}
+
+#[test]
+fn test_shape_ignores_literal_values() {
+    let dir = TempDir::new().unwrap();
+    let mut db = setup(&dir);
+    let baseline = db.capture_baseline(QUERY).unwrap();
+    db.install_baseline(&baseline).unwrap();
+    db.execute("CREATE INDEX idx_b ON t (b)").unwrap();
+
+    // Same shape with other literals, projection, and ordering.
+    for sql in [
+        "SELECT id FROM t WHERE a = 0 AND b = 120",
+        "SELECT * FROM t WHERE a = -1 AND b = 'x' ORDER BY id LIMIT 3",
+    ] {
+        assert_eq!(explain(&mut db, sql).0, "idx_a", "{}", sql);
+    }
+    let shape = |db: &mut Database, sql: &str| db.capture_baseline(sql).unwrap().shape;
+    assert_eq!(
+        shape(&mut db, "SELECT id FROM t WHERE a IN (1, 2, 3) AND b > 1.5"),
+        shape(&mut db, "SELECT id FROM t WHERE a IN (9) AND b > 42")
+    );
+    assert_eq!(
+        shape(&mut db, "SELECT id FROM t WHERE a = ? AND b = ?"),
+        baseline.shape
+    );
+
+    // Prepared statements bound to values share the shape too.
+    let rows = db
+        .query_params(
+            "SELECT id FROM t WHERE a = ? AND b = ?",
+            &[Value::Integer(1), Value::Integer(9)],
+        )
+        .unwrap();
+    assert_eq!(rows.len(), 1);
+    let hits = stat(&mut db, "plan_baseline_hits");
+    assert!(hits >= 3, "{}", hits);
+
+    // A different operator is a different shape.
+    assert_eq!(
+        explain(&mut db, "SELECT id FROM t WHERE a = 1 AND b >= 7").0,
+        "idx_b"
+    );
+    assert!(db
+        .capture_baseline("SELECT t.id FROM t JOIN t AS u ON t.id = u.id")
+        .is_err());
+}
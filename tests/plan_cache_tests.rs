@@ -0,0 +1,87 @@
+#![cfg(feature = "test-utils")]
+/// Plan cache: reuse across statements and invalidation by catalog generation.
+use murodb::crypto::aead::MasterKey;
+use murodb::{Database, ExecResult, Session, Value};
+use tempfile::TempDir;
+
+fn test_key() -> MasterKey {
+    MasterKey::new([0x42u8; 32])
+}
+
+fn setup(dir: &TempDir) -> Session {
+    let mut session = Database::create(&dir.path().join("test.db"), &test_key())
+        .unwrap()
+        .into_session();
+    session
+        .execute("CREATE TABLE t (id BIGINT PRIMARY KEY, a INT)")
+        .unwrap();
+    session.execute("CREATE INDEX idx_a ON t(a)").unwrap();
+    session.execute("BEGIN").unwrap();
+    for i in 0..50 {
+        session
+            .execute(&format!("INSERT INTO t VALUES ({}, {})", i, i % 5))
+            .unwrap();
+    }
+    session.execute("COMMIT").unwrap();
+    session
+}
+
+fn ids(session: &mut Session, sql: &str) -> Vec<i64> {
+    match session.execute(sql).unwrap() {
+        ExecResult::Rows(rows) => rows
+            .iter()
+            .map(|r| match r.get("id") {
+                Some(Value::Integer(n)) => *n,
+                other => panic!("unexpected id {:?}", other),
+            })
+            .collect(),
+        other => panic!("expected rows, got {:?}", other),
+    }
+}
+
+const QUERY: &str = "SELECT id FROM t WHERE a = 3 ORDER BY id";
+
+#[test]
+fn test_drop_index_between_cached_executions_replans() {
+    let dir = TempDir::new().unwrap();
+    let mut session = setup(&dir);
+    session.set_plan_cache_capacity(16);
+    let expected = ids(&mut session, QUERY);
+    assert_eq!(expected.len(), 10);
+    assert_eq!(ids(&mut session, QUERY), expected);
+    assert_eq!(session.database_stats().plan_cache_hits, 1);
+
+    session.execute("DROP INDEX idx_a").unwrap();
+    assert_eq!(ids(&mut session, QUERY), expected);
+    assert_eq!(ids(&mut session, QUERY), expected);
+    let stats = session.database_stats();
+    assert_eq!((stats.plan_cache_hits, stats.plan_cache_fallbacks), (2, 0));
+
+    // A rolled-back CREATE INDEX leaves no plan naming the index behind.
+    session.execute("BEGIN").unwrap();
+    session.execute("CREATE INDEX idx_a2 ON t(a)").unwrap();
+    assert_eq!(ids(&mut session, QUERY), expected);
+    session.execute("ROLLBACK").unwrap();
+    assert_eq!(ids(&mut session, QUERY), expected);
+    assert_eq!(session.database_stats().plan_cache_fallbacks, 0);
+}
+
+#[test]
+fn test_no_plan_cache_by_default() {
+    let dir = TempDir::new().unwrap();
+    let mut session = setup(&dir);
+    assert_eq!(session.plan_cache_capacity(), 0);
+    let expected = ids(&mut session, QUERY);
+    assert_eq!(ids(&mut session, QUERY), expected);
+    session.execute("DROP INDEX idx_a").unwrap();
+    assert_eq!(ids(&mut session, QUERY), expected);
+    let stats = session.database_stats();
+    assert_eq!((stats.plan_cache_hits, stats.plan_cache_fallbacks), (0, 0));
+
+    // Disabling drops what was cached.
+    session.set_plan_cache_capacity(4);
+    ids(&mut session, QUERY);
+    session.set_plan_cache_capacity(0);
+    ids(&mut session, QUERY);
+    assert_eq!(session.database_stats().plan_cache_hits, 0);
+}
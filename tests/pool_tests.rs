@@ -0,0 +1,238 @@
+#![cfg(feature = "test-utils")]
+/// `DatabasePool`: one writer and a set of readers over the same file,
+/// checked out as owned guards that move between threads. Readers see every
+/// commit that finished before their statement and never part of one.
+use murodb::crypto::aead::MasterKey;
+use murodb::{Database, DatabasePool, MuroError, PoolConfig, ReadHandle, Value, WriteHandle};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::thread;
+use std::time::Duration;
+use tempfile::TempDir;
+
+const ACCOUNTS: i64 = 10;
+const BALANCE: i64 = 100;
+
+fn test_key() -> MasterKey {
+    MasterKey::new([0x42u8; 32])
+}
+
+fn integer(rows: &[murodb::Row], column: usize) -> i64 {
+    match rows[0].values[column].1 {
+        Value::Integer(n) => n,
+        ref other => panic!("expected integer, got {:?}", other),
+    }
+}
+
+fn setup_accounts(pool: &DatabasePool) {
+    let mut db = pool.write().unwrap();
+    db.execute("CREATE TABLE accounts (id BIGINT PRIMARY KEY, balance BIGINT)")
+        .unwrap();
+    db.execute("CREATE TABLE transfers (id BIGINT PRIMARY KEY, amount BIGINT)")
+        .unwrap();
+    for id in 0..ACCOUNTS {
+        db.execute(&format!(
+            "INSERT INTO accounts VALUES ({}, {})",
+            id, BALANCE
+        ))
+        .unwrap();
+    }
+}
+
+#[test]
+fn test_pool_handles_are_send() {
+    fn assert_send<T: Send>() {}
+    fn assert_send_sync<T: Send + Sync>() {}
+    assert_send::<ReadHandle>();
+    assert_send::<WriteHandle>();
+    assert_send_sync::<DatabasePool>();
+}
+
+#[test]
+fn test_readers_see_consistent_snapshots_under_write_load() {
+    let dir = TempDir::new().unwrap();
+    let pool = DatabasePool::create(
+        &dir.path().join("test.db"),
+        &test_key(),
+        PoolConfig {
+            readers: 3,
+            ..PoolConfig::default()
+        },
+    )
+    .unwrap();
+    setup_accounts(&pool);
+    let done = Arc::new(AtomicBool::new(false));
+
+    let readers: Vec<_> = (0..6)
+        .map(|_| {
+            let pool = pool.clone();
+            let done = Arc::clone(&done);
+            thread::spawn(move || {
+                let mut last_transfers = 0;
+                let mut checks = 0;
+                while !done.load(Ordering::Acquire) || checks == 0 {
+                    let mut reader = pool.read().unwrap();
+                    let rows = reader
+                        .query(
+                            "SELECT SUM(balance), COUNT(*), \
+                             (SELECT COUNT(*) FROM transfers) FROM accounts",
+                        )
+                        .unwrap();
+                    assert_eq!(integer(&rows, 0), ACCOUNTS * BALANCE);
+                    assert_eq!(integer(&rows, 1), ACCOUNTS);
+                    let transfers = integer(&rows, 2);
+                    assert!(
+                        transfers >= last_transfers,
+                        "{} < {}",
+                        transfers,
+                        last_transfers
+                    );
+                    last_transfers = transfers;
+                    checks += 1;
+                }
+                checks
+            })
+        })
+        .collect();
+
+    let writer = {
+        let pool = pool.clone();
+        thread::spawn(move || {
+            for i in 0..200i64 {
+                let mut db = pool.write().unwrap();
+                let (from, to) = (i % ACCOUNTS, (i * 7 + 3) % ACCOUNTS);
+                db.execute("BEGIN").unwrap();
+                db.execute(&format!(
+                    "UPDATE accounts SET balance = balance - {} WHERE id = {}",
+                    i % 13,
+                    from
+                ))
+                .unwrap();
+                db.execute(&format!(
+                    "UPDATE accounts SET balance = balance + {} WHERE id = {}",
+                    i % 13,
+                    to
+                ))
+                .unwrap();
+                db.execute(&format!("INSERT INTO transfers VALUES ({}, {})", i, i % 13))
+                    .unwrap();
+                db.execute("COMMIT").unwrap();
+            }
+        })
+    };
+    writer.join().unwrap();
+    done.store(true, Ordering::Release);
+    for reader in readers {
+        assert!(reader.join().unwrap() > 0);
+    }
+
+    // Every reader has caught up with the last commit.
+    for _ in 0..3 {
+        let mut reader = pool.read().unwrap();
+        let rows = reader.query("SELECT COUNT(*) FROM transfers").unwrap();
+        assert_eq!(integer(&rows, 0), 200);
+    }
+}
+
+#[test]
+fn test_guards_move_between_threads() {
+    let dir = TempDir::new().unwrap();
+    let pool =
+        DatabasePool::create_plaintext(&dir.path().join("test.db"), PoolConfig::default()).unwrap();
+    setup_accounts(&pool);
+
+    let mut writer = pool.write().unwrap();
+    let writer = thread::spawn(move || {
+        writer
+            .execute("INSERT INTO transfers VALUES (1, 5)")
+            .unwrap();
+        writer
+    })
+    .join()
+    .unwrap();
+    drop(writer);
+
+    let reader = pool.read().unwrap();
+    let count = thread::spawn(move || {
+        let mut reader = reader;
+        integer(&reader.query("SELECT COUNT(*) FROM transfers").unwrap(), 0)
+    })
+    .join()
+    .unwrap();
+    assert_eq!(count, 1);
+    assert_eq!(pool.idle_readers(), 4);
+}
+
+#[test]
+fn test_checkout_times_out_while_all_handles_are_out() {
+    let dir = TempDir::new().unwrap();
+    let pool = DatabasePool::create_plaintext(
+        &dir.path().join("test.db"),
+        PoolConfig {
+            readers: 1,
+            checkout_timeout: Some(Duration::from_millis(20)),
+            ..PoolConfig::default()
+        },
+    )
+    .unwrap();
+
+    let held_reader = pool.read().unwrap();
+    assert_eq!(pool.idle_readers(), 0);
+    assert!(matches!(
+        pool.read(),
+        Err(MuroError::LockTimeout {
+            mode: "pool reader",
+            ..
+        })
+    ));
+    let held_writer = pool.write().unwrap();
+    assert!(matches!(
+        pool.write(),
+        Err(MuroError::LockTimeout {
+            mode: "pool writer",
+            ..
+        })
+    ));
+
+    // Returned handles are handed out again.
+    drop(held_reader);
+    pool.read().unwrap();
+    drop(held_writer);
+    pool.write().unwrap();
+}
+
+#[test]
+fn test_open_transaction_is_rolled_back_when_writer_returns() {
+    let dir = TempDir::new().unwrap();
+    let pool =
+        DatabasePool::create_plaintext(&dir.path().join("test.db"), PoolConfig::default()).unwrap();
+    setup_accounts(&pool);
+    {
+        let mut db = pool.write().unwrap();
+        db.execute("BEGIN").unwrap();
+        db.execute("INSERT INTO transfers VALUES (1, 5)").unwrap();
+    }
+    let mut db = pool.write().unwrap();
+    assert!(db.status().txid.is_none());
+    db.execute("INSERT INTO transfers VALUES (2, 5)").unwrap();
+    drop(db);
+    let rows = pool
+        .read()
+        .unwrap()
+        .query("SELECT id FROM transfers")
+        .unwrap();
+    assert_eq!(rows.len(), 1);
+    assert_eq!(rows[0].values[0].1, Value::Integer(2));
+}
+
+#[test]
+fn test_pool_needs_a_writable_database() {
+    let dir = TempDir::new().unwrap();
+    let path = dir.path().join("test.db");
+    Database::create(&path, &test_key()).unwrap();
+    let read_only = Database::open_read_only(&path, &test_key()).unwrap();
+    assert!(matches!(
+        DatabasePool::with_writer(read_only, PoolConfig::default()),
+        Err(MuroError::ReadOnly)
+    ));
+}
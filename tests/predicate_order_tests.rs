@@ -0,0 +1,139 @@
+#![cfg(feature = "test-utils")]
+/// Cost-based ordering of WHERE conjuncts.
+use murodb::crypto::aead::MasterKey;
+use murodb::{Database, ExecResult, Session, Value};
+use tempfile::TempDir;
+
+fn test_key() -> MasterKey {
+    MasterKey::new([0x42u8; 32])
+}
+
+fn setup(dir: &TempDir) -> Session {
+    let mut session = Database::create(&dir.path().join("test.db"), &test_key())
+        .unwrap()
+        .into_session();
+    session
+        .execute("CREATE TABLE t (id BIGINT PRIMARY KEY, n INT, s VARCHAR, body TEXT)")
+        .unwrap();
+    session.execute("BEGIN").unwrap();
+    for i in 1..=200 {
+        let n = if i % 7 == 0 {
+            "NULL".to_string()
+        } else {
+            (i % 5).to_string()
+        };
+        let s = if i == 2 {
+            "2".to_string()
+        } else {
+            format!("s{}", i % 3)
+        };
+        session
+            .execute(&format!(
+                "INSERT INTO t VALUES ({}, {}, '{}', '{}')",
+                i,
+                n,
+                s,
+                "lorem ipsum ".repeat(i % 4)
+            ))
+            .unwrap();
+    }
+    session.execute("COMMIT").unwrap();
+    session
+}
+
+fn ids(session: &mut Session, sql: &str) -> Vec<i64> {
+    match session.execute(sql).unwrap() {
+        ExecResult::Rows(rows) => rows
+            .iter()
+            .map(|row| match row.get("id") {
+                Some(Value::Integer(id)) => *id,
+                other => panic!("unexpected id {:?}", other),
+            })
+            .collect(),
+        other => panic!("expected rows, got {:?}", other),
+    }
+}
+
+fn explain_extra(session: &mut Session, sql: &str) -> String {
+    match session.execute(&format!("EXPLAIN {}", sql)).unwrap() {
+        ExecResult::Rows(rows) => match rows[0].get("Extra") {
+            Some(Value::Varchar(extra)) => extra.clone(),
+            other => panic!("unexpected Extra {:?}", other),
+        },
+        other => panic!("expected rows, got {:?}", other),
+    }
+}
+
+#[test]
+fn test_explain_lists_conjunct_order() {
+    let dir = TempDir::new().unwrap();
+    let mut session = setup(&dir);
+    let sql = "SELECT id FROM t WHERE body LIKE '%ipsum%' AND n = 3 AND id > 10";
+    let extra = explain_extra(&mut session, sql);
+    assert!(
+        extra.contains("Predicate order: n = 3, id > 10, body LIKE '%ipsum%'"),
+        "{}",
+        extra
+    );
+
+    session.execute("SET predicate_reorder = 'off'").unwrap();
+    assert!(!session.predicate_reorder());
+    let extra = explain_extra(&mut session, sql);
+    assert!(
+        extra.contains("Predicate order: body LIKE '%ipsum%', n = 3, id > 10"),
+        "{}",
+        extra
+    );
+
+    // A single conjunct has nothing to order.
+    let extra = explain_extra(&mut session, "SELECT id FROM t WHERE n = 3");
+    assert!(!extra.contains("Predicate order"), "{}", extra);
+}
+
+#[test]
+fn test_reordering_preserves_results() {
+    let dir = TempDir::new().unwrap();
+    let mut session = setup(&dir);
+    let queries = [
+        "SELECT id FROM t WHERE body LIKE '%ipsum%' AND n = 3",
+        "SELECT id FROM t WHERE LENGTH(body) > 12 AND n IS NULL AND id < 150",
+        "SELECT id FROM t WHERE (n = 1 OR n IS NULL) AND s LIKE 's%' AND id BETWEEN 20 AND 120",
+        "SELECT id FROM t WHERE NOT (n = 2) AND body LIKE '%lorem%' AND n >= 0",
+        "SELECT id FROM t WHERE id = 14 AND n = 4",
+        "SELECT COUNT(*) AS id FROM t WHERE body LIKE '%ipsum%' AND n IN (1, 2)",
+    ];
+    for sql in queries {
+        session.set_predicate_reorder(true);
+        let reordered = ids(&mut session, sql);
+        session.set_predicate_reorder(false);
+        let written = ids(&mut session, sql);
+        assert_eq!(reordered, written, "{}", sql);
+    }
+
+    // UPDATE and DELETE filter the same way.
+    session.set_predicate_reorder(true);
+    session
+        .execute("UPDATE t SET s = 'x' WHERE body LIKE '%ipsum%' AND n = 1")
+        .unwrap();
+    session.set_predicate_reorder(false);
+    let updated = ids(&mut session, "SELECT id FROM t WHERE s = 'x'");
+    let expected = ids(
+        &mut session,
+        "SELECT id FROM t WHERE body LIKE '%ipsum%' AND n = 1",
+    );
+    assert_eq!(updated, expected);
+}
+
+#[test]
+fn test_cheap_conjunct_runs_first() {
+    let dir = TempDir::new().unwrap();
+    let mut session = setup(&dir);
+    // The CAST fails on every row but id 2, so the query only succeeds when
+    // the cheaper checks filter first.
+    let sql = "SELECT id FROM t WHERE CAST(s AS BIGINT) = 2 AND n = 2 AND id < 5";
+    assert_eq!(ids(&mut session, sql), vec![2]);
+
+    session.execute("SET predicate_reorder = off").unwrap();
+    let err = session.execute(sql).unwrap_err();
+    assert!(err.to_string().contains("Cannot cast"), "{}", err);
+}
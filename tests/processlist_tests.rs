@@ -0,0 +1,159 @@
+#![cfg(feature = "test-utils")]
+/// `Database::status()` and `SHOW PROCESSLIST`: the running statement, its
+/// phase and elapsed time, the open transaction, and the file lock of every
+/// handle on the same database file in this process.
+use murodb::{Database, ExecResult, LockState, MuroError, Row, StatementPhase, Value};
+use std::thread;
+use std::time::{Duration, Instant};
+use tempfile::TempDir;
+
+/// Runs until cancelled: a billion-row cross join.
+const LONG_QUERY: &str = "SELECT COUNT(*) FROM t a CROSS JOIN t b CROSS JOIN t c";
+
+fn setup(dir: &TempDir) -> Database {
+    let mut db = Database::create_plaintext(&dir.path().join("test.db")).unwrap();
+    db.execute("CREATE TABLE t (id BIGINT PRIMARY KEY, v INT)")
+        .unwrap();
+    let values: Vec<String> = (0..1000).map(|i| format!("({}, {})", i, i)).collect();
+    db.execute(&format!("INSERT INTO t VALUES {}", values.join(", ")))
+        .unwrap();
+    db
+}
+
+fn processlist(result: ExecResult) -> Vec<Row> {
+    match result {
+        ExecResult::Rows(rows) => rows,
+        other => panic!("expected rows, got {:?}", other),
+    }
+}
+
+fn row_of(rows: &[Row], id: u64) -> &Row {
+    rows.iter()
+        .find(|r| r.get_i64("Id").unwrap() == Some(id as i64))
+        .unwrap_or_else(|| panic!("session {} not listed", id))
+}
+
+#[test]
+fn test_status_tracks_explicit_transaction() {
+    let dir = TempDir::new().unwrap();
+    let mut db = setup(&dir);
+
+    let status = db.status();
+    assert_eq!(status.phase, StatementPhase::Idle);
+    assert_eq!(status.statement, None);
+    assert_eq!(status.elapsed, None);
+    assert_eq!(status.txid, None);
+    assert_eq!(status.lock, LockState::Unlocked);
+
+    db.execute("BEGIN").unwrap();
+    // The transaction stays open between statements; the lock does not.
+    let status = db.status();
+    let txid = status.txid.expect("BEGIN should leave a transaction open");
+    assert_eq!(status.phase, StatementPhase::Idle);
+    assert_eq!(status.lock, LockState::Unlocked);
+
+    db.execute("INSERT INTO t VALUES (1000, 0)").unwrap();
+    assert_eq!(db.status().txid, Some(txid));
+    db.execute("COMMIT").unwrap();
+    assert_eq!(db.status().txid, None);
+}
+
+#[test]
+fn test_show_processlist_lists_handles_of_the_same_file() {
+    let dir = TempDir::new().unwrap();
+    let mut db = setup(&dir);
+    let mut reader = db.open_reader().unwrap();
+    let other_dir = TempDir::new().unwrap();
+    let other = setup(&other_dir);
+    let db_id = db.status().id;
+    let reader_id = reader.status_handle().id();
+
+    let rows = processlist(db.execute("SHOW PROCESSLIST").unwrap());
+    assert_eq!(rows.len(), 2, "{:?}", rows);
+    assert!(rows
+        .iter()
+        .all(|r| r.get_i64("Id").unwrap() != Some(other.status().id as i64)));
+    let own = row_of(&rows, db_id);
+    assert_eq!(own.get_str("Phase").unwrap(), Some("executing"));
+    assert_eq!(own.get_str("Statement").unwrap(), Some("SHOW PROCESSLIST"));
+    assert_eq!(own.get_str("Lock").unwrap(), Some("exclusive"));
+    assert!(own.get_i64("Started_at_ms").unwrap().unwrap() > 0);
+    assert!(own.get_i64("Elapsed_ms").unwrap().is_some());
+    assert_eq!(own.get("Transaction"), Some(&Value::Null));
+    let idle = row_of(&rows, reader_id);
+    assert_eq!(idle.get_str("Phase").unwrap(), Some("idle"));
+    assert_eq!(idle.get("Statement"), Some(&Value::Null));
+    assert_eq!(idle.get("Elapsed_ms"), Some(&Value::Null));
+    assert_eq!(idle.get_str("Lock").unwrap(), Some("none"));
+
+    // The read-only path runs under the shared lock.
+    let rows = db.query("SHOW PROCESSLIST").unwrap();
+    assert_eq!(
+        row_of(&rows, db_id).get_str("Lock").unwrap(),
+        Some("shared")
+    );
+
+    // Another handle sees the transaction left open between statements.
+    db.execute("BEGIN").unwrap();
+    let txid = db.status().txid.unwrap();
+    let rows = reader.query("SHOW PROCESSLIST").unwrap();
+    let writer = row_of(&rows, db_id);
+    assert_eq!(writer.get_str("Phase").unwrap(), Some("idle"));
+    assert_eq!(writer.get_i64("Transaction").unwrap(), Some(txid as i64));
+    assert_eq!(
+        row_of(&rows, reader_id).get_str("Statement").unwrap(),
+        Some("SHOW PROCESSLIST")
+    );
+    db.execute("ROLLBACK").unwrap();
+
+    drop(reader);
+    let rows = processlist(db.execute("SHOW PROCESSLIST").unwrap());
+    assert_eq!(rows.len(), 1);
+}
+
+#[test]
+fn test_show_processlist_on_read_only_handle() {
+    let dir = TempDir::new().unwrap();
+    let path = dir.path().join("test.db");
+    drop(setup(&dir));
+    let mut db = Database::open_plaintext_read_only(&path).unwrap();
+    let rows = processlist(db.execute("SHOW PROCESSLIST").unwrap());
+    let own = row_of(&rows, db.status().id);
+    assert_eq!(own.get_str("Lock").unwrap(), Some("shared"));
+}
+
+#[test]
+fn test_watchdog_cancels_statement_running_too_long() {
+    let dir = TempDir::new().unwrap();
+    let mut db = setup(&dir);
+    let status = db.status_handle();
+    let cancel = db.cancel_handle();
+
+    let worker = thread::spawn(move || {
+        let result = db.query(LONG_QUERY).map(|_| ());
+        (db, result)
+    });
+
+    let deadline = Instant::now() + Duration::from_secs(30);
+    let seen = loop {
+        let snapshot = status.snapshot();
+        if snapshot.phase == StatementPhase::Executing
+            && snapshot.elapsed.unwrap() >= Duration::from_millis(20)
+        {
+            break snapshot;
+        }
+        assert!(Instant::now() < deadline, "statement never ran long enough");
+        thread::sleep(Duration::from_millis(1));
+    };
+    assert_eq!(seen.statement.as_deref(), Some(LONG_QUERY));
+    assert_eq!(seen.lock, LockState::Shared);
+    assert!(seen.started_at_unix_ms.is_some());
+    assert!(cancel.cancel());
+
+    let (db, result) = worker.join().unwrap();
+    assert!(matches!(result, Err(MuroError::Cancelled)), "{:?}", result);
+    let after = db.status();
+    assert_eq!(after.phase, StatementPhase::Idle);
+    assert_eq!(after.statement_seq, seen.statement_seq);
+    assert_eq!(after.lock, LockState::Unlocked);
+}
@@ -0,0 +1,182 @@
+#![cfg(feature = "test-utils")]
+/// Read-only handles: no writes to the data file or WAL, and consistent
+/// snapshots next to a live writer.
+use murodb::crypto::aead::MasterKey;
+use murodb::{Database, ExecResult, MuroError, Value};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use tempfile::TempDir;
+
+fn test_key() -> MasterKey {
+    MasterKey::new([0x42u8; 32])
+}
+
+fn wal_path(db_path: &Path) -> PathBuf {
+    let mut s = db_path.as_os_str().to_os_string();
+    s.push(".wal");
+    PathBuf::from(s)
+}
+
+fn int(db: &mut Database, sql: &str) -> i64 {
+    match db.query(sql).unwrap()[0].values[0].1 {
+        Value::Integer(n) => n,
+        ref other => panic!("unexpected value {:?}", other),
+    }
+}
+
+#[test]
+fn test_read_only_handle_never_writes() {
+    let dir = TempDir::new().unwrap();
+    let db_path = dir.path().join("test.db");
+    let mut db = Database::create(&db_path, &test_key()).unwrap();
+    db.execute("CREATE TABLE t (id BIGINT PRIMARY KEY, name VARCHAR)")
+        .unwrap();
+    db.execute("INSERT INTO t VALUES (1, 'one')").unwrap();
+    drop(db);
+    let db_bytes = std::fs::read(&db_path).unwrap();
+    let wal_bytes = std::fs::read(wal_path(&db_path)).unwrap();
+
+    let mut ro = Database::open_read_only(&db_path, &test_key()).unwrap();
+    assert!(ro.is_read_only());
+    match ro.execute("SELECT name FROM t WHERE id = 1").unwrap() {
+        ExecResult::Rows(rows) => {
+            assert_eq!(rows[0].get("name"), Some(&Value::Varchar("one".into())))
+        }
+        other => panic!("expected rows, got {:?}", other),
+    }
+    for sql in [
+        "INSERT INTO t VALUES (2, 'two')",
+        "DELETE FROM t",
+        "CREATE TABLE u (id BIGINT PRIMARY KEY)",
+        "BEGIN",
+        "ANALYZE TABLE t",
+    ] {
+        assert!(
+            matches!(ro.execute(sql), Err(MuroError::ReadOnly)),
+            "{}",
+            sql
+        );
+    }
+    let insert = ro.prepare("INSERT INTO t VALUES (?, 'x')").unwrap();
+    assert!(matches!(
+        ro.execute_prepared(&insert, &[Value::Integer(3)]),
+        Err(MuroError::ReadOnly)
+    ));
+    assert!(matches!(
+        ro.backup(dir.path().join("copy.db")),
+        Err(MuroError::ReadOnly)
+    ));
+    ro.flush().unwrap();
+    assert_eq!(int(&mut ro, "SELECT COUNT(*) FROM t"), 1);
+    drop(ro);
+
+    assert_eq!(std::fs::read(&db_path).unwrap(), db_bytes);
+    assert_eq!(std::fs::read(wal_path(&db_path)).unwrap(), wal_bytes);
+}
+
+#[test]
+fn test_read_only_open_refuses_unrecovered_wal() {
+    let dir = TempDir::new().unwrap();
+    let db_path = dir.path().join("test.db");
+    let mut session = Database::create(&db_path, &test_key())
+        .unwrap()
+        .into_session();
+    session
+        .execute("CREATE TABLE t (id BIGINT PRIMARY KEY)")
+        .unwrap();
+    // The commit reaches the WAL but the header never does: a crash.
+    session
+        .pager_mut()
+        .set_inject_flush_meta_failure(Some(std::io::ErrorKind::Other));
+    assert!(matches!(
+        session.execute("INSERT INTO t VALUES (1)"),
+        Err(MuroError::CommitInDoubt(_))
+    ));
+    drop(session);
+
+    let err = Database::open_read_only(&db_path, &test_key())
+        .err()
+        .expect("open must refuse");
+    assert!(
+        err.to_string().contains("open the database read-write"),
+        "{}",
+        err
+    );
+
+    // A read-write open recovers; afterwards read-only opens succeed.
+    drop(Database::open(&db_path, &test_key()).unwrap());
+    let mut ro = Database::open_read_only(&db_path, &test_key()).unwrap();
+    assert_eq!(int(&mut ro, "SELECT COUNT(*) FROM t"), 1);
+}
+
+#[test]
+fn test_read_only_reader_sees_committed_snapshots_next_to_writer() {
+    const ACCOUNTS: i64 = 20;
+    const TOTAL: i64 = ACCOUNTS * 100;
+    let dir = TempDir::new().unwrap();
+    let db_path = dir.path().join("test.db");
+    let mut writer = Database::create_plaintext(&db_path).unwrap();
+    writer
+        .execute("CREATE TABLE accounts (id BIGINT PRIMARY KEY, balance BIGINT)")
+        .unwrap();
+    writer
+        .execute("CREATE TABLE ledger (id BIGINT PRIMARY KEY, amount BIGINT)")
+        .unwrap();
+    writer.execute("BEGIN").unwrap();
+    for id in 0..ACCOUNTS {
+        writer
+            .execute(&format!("INSERT INTO accounts VALUES ({}, 100)", id))
+            .unwrap();
+    }
+    writer.execute("COMMIT").unwrap();
+
+    let done = Arc::new(AtomicBool::new(false));
+    let writer_done = Arc::clone(&done);
+    let writer_thread = std::thread::spawn(move || {
+        // Each transfer moves money and records it; both or neither are seen.
+        for i in 0..300i64 {
+            let (from, to) = (i % ACCOUNTS, (i * 7 + 3) % ACCOUNTS);
+            writer.execute("BEGIN").unwrap();
+            writer
+                .execute(&format!(
+                    "UPDATE accounts SET balance = balance - 5 WHERE id = {}",
+                    from
+                ))
+                .unwrap();
+            writer
+                .execute(&format!(
+                    "UPDATE accounts SET balance = balance + 5 WHERE id = {}",
+                    to
+                ))
+                .unwrap();
+            writer
+                .execute(&format!("INSERT INTO ledger VALUES ({}, 5)", i))
+                .unwrap();
+            writer.execute("COMMIT").unwrap();
+        }
+        writer_done.store(true, Ordering::SeqCst);
+    });
+
+    let mut ro = Database::open_plaintext_read_only(&db_path).unwrap();
+    let mut last_ledger = 0;
+    let mut snapshots = 0;
+    loop {
+        let finished = done.load(Ordering::SeqCst);
+        let rows = ro
+            .query("SELECT SUM(balance) AS total, COUNT(*) AS n FROM accounts")
+            .unwrap();
+        assert_eq!(rows[0].get("total"), Some(&Value::Integer(TOTAL)));
+        assert_eq!(rows[0].get("n"), Some(&Value::Integer(ACCOUNTS)));
+        let ledger = int(&mut ro, "SELECT COUNT(*) FROM ledger");
+        assert!(ledger >= last_ledger, "{} < {}", ledger, last_ledger);
+        last_ledger = ledger;
+        snapshots += 1;
+        if finished {
+            break;
+        }
+    }
+    writer_thread.join().unwrap();
+    assert_eq!(last_ledger, 300);
+    assert!(snapshots > 1);
+}
@@ -0,0 +1,191 @@
+#![cfg(feature = "test-utils")]
+/// Typed `Row` getters: a column missing from the result is an error, a NULL
+/// is `Ok(None)`, and conversions follow `CAST`.
+use murodb::crypto::aead::MasterKey;
+use murodb::{Database, FromRow, MuroError, Result, Row, Value};
+use tempfile::TempDir;
+
+fn test_key() -> MasterKey {
+    MasterKey::new([0x42u8; 32])
+}
+
+fn sample_row(db: &mut Database) -> Row {
+    db.execute_batch(
+        "CREATE TABLE t (
+            id BIGINT PRIMARY KEY, n INT, f DOUBLE, d DECIMAL(8,2), s VARCHAR(20),
+            num_s VARCHAR(20), flag TINYINT, b VARBINARY(8), dt DATE, u UUID, missing INT
+         );
+         INSERT INTO t VALUES (
+            1, -7, 2.75, 12.50, 'hello', ' 42 ', 0, X'00ff', '2024-02-29',
+            '123e4567-e89b-12d3-a456-426614174000', NULL
+         );",
+    )
+    .unwrap();
+    db.query("SELECT * FROM t").unwrap().remove(0)
+}
+
+fn assert_read_error<T: std::fmt::Debug>(result: Result<T>, needle: &str) {
+    match result {
+        Err(MuroError::Execution(msg)) => assert!(msg.contains(needle), "{}", msg),
+        other => panic!("expected an execution error, got {:?}", other),
+    }
+}
+
+#[test]
+fn test_missing_column_is_an_error_and_null_is_none() {
+    let dir = TempDir::new().unwrap();
+    let mut db = Database::create(&dir.path().join("test.db"), &test_key()).unwrap();
+    let row = sample_row(&mut db);
+
+    assert_read_error(row.get_i64("nope"), "'nope' is not in the result");
+    assert_read_error(row.get_str("nope"), "columns: id, n, f");
+    assert_read_error(row.get_bytes("nope"), "not in the result");
+    assert_read_error(row.get_bool("nope"), "not in the result");
+
+    assert_eq!(row.get_i64("missing").unwrap(), None);
+    assert_eq!(row.get_f64("missing").unwrap(), None);
+    assert_eq!(row.get_bool("missing").unwrap(), None);
+    assert_eq!(row.get_str("missing").unwrap(), None);
+    assert_eq!(row.get_string("missing").unwrap(), None);
+    assert_eq!(row.get_bytes("missing").unwrap(), None);
+}
+
+#[test]
+fn test_numeric_getters_convert_like_cast() {
+    let dir = TempDir::new().unwrap();
+    let mut db = Database::create(&dir.path().join("test.db"), &test_key()).unwrap();
+    let row = sample_row(&mut db);
+
+    assert_eq!(row.get_i64("n").unwrap(), Some(-7));
+    assert_eq!(row.get_i64("f").unwrap(), Some(2));
+    assert_eq!(row.get_i64("d").unwrap(), Some(12));
+    assert_eq!(row.get_i64("num_s").unwrap(), Some(42));
+    assert_read_error(row.get_i64("s"), "Cannot read column 's' as an integer");
+    assert_read_error(row.get_i64("dt"), "as an integer");
+
+    assert_eq!(row.get_f64("n").unwrap(), Some(-7.0));
+    assert_eq!(row.get_f64("f").unwrap(), Some(2.75));
+    assert_eq!(row.get_f64("d").unwrap(), Some(12.5));
+    assert_eq!(row.get_f64("num_s").unwrap(), Some(42.0));
+    assert_read_error(row.get_f64("b"), "as a float");
+
+    assert_eq!(row.get_bool("flag").unwrap(), Some(false));
+    assert_eq!(row.get_bool("n").unwrap(), Some(true));
+    assert_eq!(row.get_bool("f").unwrap(), Some(true));
+    assert_eq!(row.get_bool("num_s").unwrap(), Some(true));
+    assert_read_error(row.get_bool("s"), "as an integer");
+    assert_read_error(row.get_bool("dt"), "as a boolean");
+
+    let rows = db
+        .query("SELECT 'TRUE' AS a, ' false ' AS b, '0' AS c")
+        .unwrap();
+    assert_eq!(rows[0].get_bool("a").unwrap(), Some(true));
+    assert_eq!(rows[0].get_bool("b").unwrap(), Some(false));
+    assert_eq!(rows[0].get_bool("c").unwrap(), Some(false));
+}
+
+#[test]
+fn test_text_and_byte_getters() {
+    let dir = TempDir::new().unwrap();
+    let mut db = Database::create(&dir.path().join("test.db"), &test_key()).unwrap();
+    let row = sample_row(&mut db);
+
+    assert_eq!(row.get_str("s").unwrap(), Some("hello"));
+    assert_read_error(row.get_str("n"), "as a string");
+    assert_eq!(row.get_string("n").unwrap().as_deref(), Some("-7"));
+    assert_eq!(row.get_string("d").unwrap().as_deref(), Some("12.50"));
+    assert_eq!(row.get_string("dt").unwrap().as_deref(), Some("2024-02-29"));
+    assert_eq!(
+        row.get_string("u").unwrap().as_deref(),
+        Some("123e4567-e89b-12d3-a456-426614174000")
+    );
+
+    assert_eq!(row.get_bytes("b").unwrap(), Some(&[0x00, 0xff][..]));
+    assert_eq!(row.get_bytes("s").unwrap(), Some(&b"hello"[..]));
+    assert_eq!(row.get_bytes("u").unwrap().map(<[u8]>::len), Some(16));
+    assert_read_error(row.get_bytes("n"), "as bytes");
+}
+
+#[test]
+fn test_positional_access_and_column_names() {
+    let dir = TempDir::new().unwrap();
+    let mut db = Database::create(&dir.path().join("test.db"), &test_key()).unwrap();
+    db.execute_batch(
+        "CREATE TABLE a (id BIGINT PRIMARY KEY, v INT);
+         CREATE TABLE b (id BIGINT PRIMARY KEY, a_id BIGINT);
+         INSERT INTO a VALUES (1, 10);
+         INSERT INTO b VALUES (5, 1);",
+    )
+    .unwrap();
+    let rows = db
+        .query("SELECT a.id AS id, b.id AS id, v FROM a JOIN b ON b.a_id = a.id")
+        .unwrap();
+    let row = &rows[0];
+    assert_eq!(row.columns(), ["id", "id", "v"]);
+    // By name the first `id` wins; by position both are reachable.
+    assert_eq!(row.get_i64("id").unwrap(), Some(1));
+    assert_eq!(row.get_at(0), Some(&Value::Integer(1)));
+    assert_eq!(row.get_at(1), Some(&Value::Integer(5)));
+    assert_eq!(row.get_at(3), None);
+}
+
+#[derive(Debug, PartialEq)]
+struct Item {
+    id: i64,
+    label: Option<String>,
+    price: f64,
+}
+
+impl FromRow for Item {
+    fn from_row(row: &Row) -> Result<Self> {
+        Ok(Item {
+            id: row
+                .get_i64("id")?
+                .ok_or_else(|| MuroError::Execution("id is NULL".into()))?,
+            label: row.get_string("label")?,
+            price: row.get_f64("price")?.unwrap_or(0.0),
+        })
+    }
+}
+
+#[test]
+fn test_map_into_builds_user_types() {
+    let dir = TempDir::new().unwrap();
+    let mut db = Database::create(&dir.path().join("test.db"), &test_key()).unwrap();
+    db.execute_batch(
+        "CREATE TABLE items (id BIGINT PRIMARY KEY, label VARCHAR(20), price DECIMAL(6,2));
+         INSERT INTO items VALUES (1, 'pen', 1.25), (2, NULL, NULL);",
+    )
+    .unwrap();
+    let items: Vec<Item> = db
+        .query("SELECT id, label, price FROM items ORDER BY id")
+        .unwrap()
+        .iter()
+        .map(Row::map_into)
+        .collect::<Result<_>>()
+        .unwrap();
+    assert_eq!(
+        items,
+        [
+            Item {
+                id: 1,
+                label: Some("pen".into()),
+                price: 1.25
+            },
+            Item {
+                id: 2,
+                label: None,
+                price: 0.0
+            },
+        ]
+    );
+
+    // A query without a required column fails instead of defaulting.
+    let rows = db.query("SELECT id, label FROM items").unwrap();
+    let err = rows[0].map_into::<Item>().unwrap_err();
+    assert!(
+        err.to_string().contains("'price' is not in the result"),
+        "{}",
+        err
+    );
+}
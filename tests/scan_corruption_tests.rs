@@ -0,0 +1,213 @@
+#![cfg(feature = "test-utils")]
+use murodb::btree::key_encoding::encode_i64;
+use murodb::btree::node::{internal_left_child, num_entries};
+use murodb::btree::ops::BTree;
+use murodb::crypto::aead::MasterKey;
+use murodb::schema::catalog::SystemCatalog;
+use murodb::{Database, ExecResult, Row, ScanCorruptionPolicy, Session, Value};
+use std::io::{Seek, SeekFrom, Write};
+use std::path::Path;
+use tempfile::TempDir;
+
+const ROWS: i64 = 300;
+
+fn test_key() -> MasterKey {
+    MasterKey::new([0x42u8; 32])
+}
+
+fn create_table(dir: &TempDir) -> Session {
+    let db_path = dir.path().join("test.db");
+    let mut session = Database::create(&db_path, &test_key())
+        .unwrap()
+        .into_session();
+    session
+        .execute("CREATE TABLE t (id BIGINT PRIMARY KEY, body VARCHAR)")
+        .unwrap();
+    for i in 0..ROWS {
+        session
+            .execute(&format!(
+                "INSERT INTO t VALUES ({}, '{}')",
+                i,
+                "x".repeat(100)
+            ))
+            .unwrap();
+    }
+    session
+}
+
+fn table_root(session: &mut Session) -> u64 {
+    let catalog = SystemCatalog::open(session.catalog().root_page_id());
+    catalog
+        .get_table(session.pager_mut(), "t")
+        .unwrap()
+        .unwrap()
+        .data_btree_root
+}
+
+/// Overwrite part of a page on disk so it no longer decrypts.
+fn corrupt_page_on_disk(db_path: &Path, page_id: u64, page_count: u64) {
+    let file_len = std::fs::metadata(db_path).unwrap().len();
+    let page_size_on_disk = (file_len - 76) / page_count;
+    let mut file = std::fs::OpenOptions::new()
+        .write(true)
+        .open(db_path)
+        .unwrap();
+    file.seek(SeekFrom::Start(76 + page_id * page_size_on_disk + 40))
+        .unwrap();
+    file.write_all(&[0xAA; 64]).unwrap();
+}
+
+/// Corrupt the second leaf of `t` and reopen. Returns the session, the leaf's
+/// page id and the number of rows it held.
+fn setup_corrupt_leaf(dir: &TempDir) -> (Session, u64, u64) {
+    let mut session = create_table(dir);
+    let root_id = table_root(&mut session);
+    let root = session.pager_mut().read_page(root_id).unwrap();
+    let leaf = internal_left_child(&root, 1).unwrap();
+    let lost = num_entries(&session.pager_mut().read_page(leaf).unwrap()) as u64;
+    let page_count = session.pager().page_count();
+    drop(session);
+
+    let db_path = dir.path().join("test.db");
+    corrupt_page_on_disk(&db_path, leaf, page_count);
+    let session = Database::open(&db_path, &test_key())
+        .unwrap()
+        .into_session();
+    (session, leaf, lost)
+}
+
+fn rows(result: ExecResult) -> Vec<Row> {
+    match result {
+        ExecResult::Rows(rows) => rows,
+        other => panic!("expected rows, got {:?}", other),
+    }
+}
+
+fn ids(rows: &[Row]) -> Vec<i64> {
+    rows.iter()
+        .map(|row| match row.get("id") {
+            Some(Value::Integer(id)) => *id,
+            other => panic!("unexpected id {:?}", other),
+        })
+        .collect()
+}
+
+#[test]
+fn test_strict_mode_fails_on_corrupt_page() {
+    let dir = TempDir::new().unwrap();
+    let (mut session, _, _) = setup_corrupt_leaf(&dir);
+    assert!(session.execute("SELECT * FROM t").is_err());
+    // Seeks that avoid the damaged page still work.
+    let found = rows(session.execute("SELECT id FROM t WHERE id = 0").unwrap());
+    assert_eq!(ids(&found), vec![0]);
+}
+
+#[test]
+fn test_skip_mode_returns_readable_rows_with_report() {
+    let dir = TempDir::new().unwrap();
+    let (mut session, leaf, lost) = setup_corrupt_leaf(&dir);
+    session
+        .execute("SET scan_corruption_policy = 'skip'")
+        .unwrap();
+
+    let found = ids(&rows(session.execute("SELECT id FROM t").unwrap()));
+    assert_eq!(found.len() as u64, ROWS as u64 - lost);
+    let missing: Vec<i64> = (0..ROWS).filter(|id| !found.contains(id)).collect();
+    assert_eq!(missing.len() as u64, lost);
+    // The lost rows are one contiguous key range.
+    assert_eq!(
+        missing.last().unwrap() - missing.first().unwrap() + 1,
+        lost as i64
+    );
+
+    let warnings = session.execute_read_only_query("SHOW WARNINGS").unwrap();
+    assert_eq!(warnings.len(), 1, "{:?}", warnings);
+    let w = &warnings[0];
+    assert_eq!(w.get("level"), Some(&Value::Varchar("Warning".into())));
+    assert_eq!(w.get("table"), Some(&Value::Varchar("t".into())));
+    assert_eq!(w.get("page_id"), Some(&Value::Integer(leaf as i64)));
+    let hex = |id: i64| {
+        encode_i64(id)
+            .iter()
+            .map(|b| format!("{:02x}", b))
+            .collect::<String>()
+    };
+    assert_eq!(
+        w.get("key_range"),
+        Some(&Value::Varchar(format!(
+            "[{}, {})",
+            hex(missing[0]),
+            hex(missing.last().unwrap() + 1)
+        )))
+    );
+
+    // DESC scans and aggregates skip the same page.
+    let desc = ids(&rows(
+        session
+            .execute("SELECT id FROM t ORDER BY id DESC LIMIT 1000")
+            .unwrap(),
+    ));
+    assert_eq!(desc.len(), found.len());
+    let count = rows(session.execute("SELECT COUNT(*) AS n FROM t").unwrap());
+    assert_eq!(count[0].get("n"), Some(&Value::Integer(ROWS - lost as i64)));
+
+    assert_eq!(session.database_stats().scan_skipped_pages, 3);
+    assert!(session
+        .metrics_prometheus()
+        .contains("murodb_scan_skipped_pages_total{"));
+
+    // A clean statement clears the report; SHOW WARNINGS itself does not.
+    session.execute("SELECT id FROM t WHERE id = 0").unwrap();
+    assert!(rows(session.execute("SHOW WARNINGS").unwrap()).is_empty());
+}
+
+#[test]
+fn test_skip_mode_skips_undecodable_rows() {
+    let dir = TempDir::new().unwrap();
+    let mut session = create_table(&dir);
+    let mut data = BTree::open(table_root(&mut session));
+    data.insert(session.pager_mut(), &encode_i64(7), &[0xFF; 3])
+        .unwrap();
+
+    assert!(session.execute("SELECT * FROM t").is_err());
+    session.set_scan_corruption_policy(ScanCorruptionPolicy::Skip);
+    let found = ids(&rows(session.execute("SELECT * FROM t").unwrap()));
+    assert_eq!(found.len() as u64, ROWS as u64 - 1);
+    assert!(!found.contains(&7));
+
+    let warnings = rows(session.execute("SHOW WARNINGS").unwrap());
+    assert_eq!(warnings.len(), 1);
+    assert_eq!(warnings[0].get("page_id"), Some(&Value::Null));
+    assert_eq!(session.database_stats().scan_skipped_rows, 1);
+}
+
+#[test]
+fn test_skip_mode_refuses_writes() {
+    let dir = TempDir::new().unwrap();
+    let (mut session, _, _) = setup_corrupt_leaf(&dir);
+    session
+        .execute("SET scan_corruption_policy = 'skip'")
+        .unwrap();
+
+    let err = session
+        .execute("UPDATE t SET body = 'y' WHERE id = 0")
+        .unwrap_err();
+    assert!(
+        err.to_string().contains("scan_corruption_policy"),
+        "{}",
+        err
+    );
+    assert!(session.execute("DELETE FROM t").is_err());
+    assert!(session.execute("INSERT INTO t VALUES (1000, 'z')").is_err());
+
+    session
+        .execute("SET scan_corruption_policy = 'error'")
+        .unwrap();
+    assert_eq!(
+        session.scan_corruption_policy(),
+        ScanCorruptionPolicy::Error
+    );
+    session
+        .execute("UPDATE t SET body = 'y' WHERE id = 0")
+        .unwrap();
+}
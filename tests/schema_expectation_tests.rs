@@ -0,0 +1,292 @@
+#![cfg(feature = "test-utils")]
+/// Startup schema assertions: `Database::assert_schema` compares the expected
+/// tables, columns, and indexes with the live catalog, tolerating extras unless
+/// told otherwise and failing with the structured diff when something the
+/// application needs is missing or incompatible.
+use murodb::crypto::aead::MasterKey;
+use murodb::types::DataType;
+use murodb::{
+    Database, ExpectedIndex, ExpectedTable, MuroError, SchemaDiff, SchemaDifference,
+    SchemaExpectation,
+};
+use tempfile::TempDir;
+
+fn test_key() -> MasterKey {
+    MasterKey::new([0x42u8; 32])
+}
+
+const SCHEMA: &str = "
+CREATE TABLE users (
+    id BIGINT PRIMARY KEY,
+    email VARCHAR(100) NOT NULL UNIQUE,
+    name VARCHAR(50),
+    bio TEXT
+);
+CREATE INDEX idx_users_name ON users (name);
+CREATE TABLE notes (id BIGINT, user_id BIGINT NOT NULL, body TEXT, PRIMARY KEY (id));
+CREATE FULLTEXT INDEX ft_notes_body ON notes (body) WITH PARSER ngram OPTIONS (n=2, normalize='nfkc');
+";
+
+fn create(dir: &TempDir) -> Database {
+    let mut db = Database::create(&dir.path().join("test.db"), &test_key()).unwrap();
+    db.execute_batch(SCHEMA).unwrap();
+    db
+}
+
+fn mismatch(result: murodb::Result<SchemaDiff>) -> SchemaDiff {
+    match result {
+        Err(MuroError::SchemaMismatch(diff)) => *diff,
+        other => panic!("expected a schema mismatch, got {:?}", other),
+    }
+}
+
+#[test]
+fn test_matching_schema_has_empty_diff() {
+    let dir = TempDir::new().unwrap();
+    let mut db = create(&dir);
+    let expected = SchemaExpectation::from_sql(SCHEMA).unwrap();
+    assert!(db.assert_schema(&expected).unwrap().is_empty());
+
+    // The same schema built programmatically.
+    let expected = SchemaExpectation::new()
+        .table(
+            ExpectedTable::new("users")
+                .column("id", DataType::BigInt, false)
+                .column("email", DataType::Varchar(Some(100)), false)
+                .column("name", DataType::Varchar(Some(50)), true)
+                .primary_key(&["id"])
+                .index(ExpectedIndex::unique(&["email"]))
+                .index(ExpectedIndex::new(&["name"])),
+        )
+        .table(
+            ExpectedTable::new("notes")
+                .column("user_id", DataType::BigInt, false)
+                .index(ExpectedIndex::fulltext("body")),
+        );
+    let diff = db.assert_schema(&expected).unwrap();
+    assert!(diff.is_compatible());
+    assert_eq!(
+        diff.tolerated,
+        vec![
+            SchemaDifference::ExtraColumn {
+                table: "users".into(),
+                column: "bio".into()
+            },
+            SchemaDifference::ExtraColumn {
+                table: "notes".into(),
+                column: "id".into()
+            },
+            SchemaDifference::ExtraColumn {
+                table: "notes".into(),
+                column: "body".into()
+            },
+        ]
+    );
+}
+
+#[test]
+fn test_missing_and_incompatible_elements_fail() {
+    let dir = TempDir::new().unwrap();
+    let mut db = create(&dir);
+    let expected = SchemaExpectation::from_sql(
+        "CREATE TABLE users (
+             id BIGINT PRIMARY KEY,
+             email VARCHAR(100) NOT NULL UNIQUE,
+             name INT,
+             age INT
+         );
+         CREATE UNIQUE INDEX idx_users_name ON users (name);
+         CREATE TABLE notes (id BIGINT, user_id BIGINT NOT NULL, PRIMARY KEY (id, user_id));
+         CREATE TABLE tags (id BIGINT PRIMARY KEY);",
+    )
+    .unwrap();
+    let diff = mismatch(db.assert_schema(&expected));
+    assert_eq!(
+        diff.required,
+        vec![
+            SchemaDifference::IncompatibleColumn {
+                table: "users".into(),
+                column: "name".into(),
+                expected: "INT".into(),
+                actual: "VARCHAR(50)".into(),
+            },
+            SchemaDifference::MissingColumn {
+                table: "users".into(),
+                column: "age".into()
+            },
+            SchemaDifference::MissingIndex {
+                table: "users".into(),
+                index: ExpectedIndex::unique(&["name"]),
+            },
+            SchemaDifference::PrimaryKeyMismatch {
+                table: "notes".into(),
+                expected: vec!["id".into(), "user_id".into()],
+                actual: vec!["id".into()],
+            },
+            SchemaDifference::MissingTable("tags".into()),
+        ]
+    );
+    // The non-unique live index is not the UNIQUE one required, so it is extra.
+    assert!(diff.tolerated.contains(&SchemaDifference::ExtraIndex {
+        table: "users".into(),
+        index: "idx_users_name".into()
+    }));
+    let message = MuroError::SchemaMismatch(Box::new(diff)).to_string();
+    assert!(
+        message.contains("missing column users.age; missing UNIQUE INDEX (name) on users"),
+        "{}",
+        message
+    );
+}
+
+#[test]
+fn test_extras_fail_when_not_tolerated() {
+    let dir = TempDir::new().unwrap();
+    let mut db = create(&dir);
+    let users_only = "CREATE TABLE users (
+        id BIGINT PRIMARY KEY,
+        email VARCHAR(100) NOT NULL UNIQUE,
+        name VARCHAR(50),
+        bio TEXT
+    );";
+
+    let expected = SchemaExpectation::from_sql(users_only).unwrap();
+    let diff = db.assert_schema(&expected).unwrap();
+    assert_eq!(
+        diff.tolerated,
+        vec![
+            SchemaDifference::ExtraIndex {
+                table: "users".into(),
+                index: "idx_users_name".into()
+            },
+            SchemaDifference::ExtraTable("notes".into()),
+        ]
+    );
+
+    let diff = mismatch(db.assert_schema(&expected.clone().allow_extra_tables(false)));
+    assert_eq!(
+        diff.required,
+        vec![SchemaDifference::ExtraTable("notes".into())]
+    );
+    assert_eq!(diff.tolerated.len(), 1);
+
+    let diff = mismatch(db.assert_schema(&expected.clone().allow_extra_indexes(false)));
+    assert_eq!(diff.required.len(), 1);
+    assert!(matches!(
+        diff.required[0],
+        SchemaDifference::ExtraIndex { .. }
+    ));
+
+    let narrow = SchemaExpectation::new()
+        .table(ExpectedTable::new("users").column("id", DataType::BigInt, false))
+        .allow_extra_columns(false);
+    let diff = mismatch(db.assert_schema(&narrow));
+    assert_eq!(diff.required.len(), 3);
+    assert!(diff
+        .required
+        .iter()
+        .all(|d| matches!(d, SchemaDifference::ExtraColumn { .. })));
+}
+
+#[test]
+fn test_type_compatibility_follows_metadata_only_alter() {
+    let dir = TempDir::new().unwrap();
+    let mut db = create(&dir);
+    let users = |email: DataType, name: DataType, name_nullable: bool| {
+        SchemaExpectation::new().table(
+            ExpectedTable::new("users")
+                .column("email", email, false)
+                .column("name", name, name_nullable),
+        )
+    };
+
+    // A live column at least as wide as expected is compatible.
+    for (email, name) in [
+        (DataType::Varchar(Some(100)), DataType::Varchar(Some(50))),
+        (DataType::Varchar(Some(20)), DataType::Varchar(Some(1))),
+    ] {
+        assert!(db.assert_schema(&users(email, name, true)).is_ok());
+    }
+    // A live NOT NULL column is fine where the expectation is NOT NULL too...
+    assert!(db
+        .assert_schema(&users(
+            DataType::Varchar(Some(100)),
+            DataType::Varchar(Some(50)),
+            false
+        ))
+        .is_ok());
+    // ...but a narrower, unbounded, or differently typed expectation is not.
+    for email in [
+        DataType::Varchar(Some(101)),
+        DataType::Varchar(None),
+        DataType::Text,
+    ] {
+        let diff = mismatch(db.assert_schema(&users(email, DataType::Varchar(Some(50)), true)));
+        assert_eq!(diff.required.len(), 1, "{:?}", email);
+    }
+
+    // Expecting NULL where the live column is NOT NULL is incompatible.
+    let expected = SchemaExpectation::new().table(ExpectedTable::new("notes").column(
+        "user_id",
+        DataType::BigInt,
+        true,
+    ));
+    let diff = mismatch(db.assert_schema(&expected));
+    assert_eq!(
+        diff.required,
+        vec![SchemaDifference::IncompatibleColumn {
+            table: "notes".into(),
+            column: "user_id".into(),
+            expected: "BIGINT".into(),
+            actual: "BIGINT NOT NULL".into(),
+        }]
+    );
+
+    // Widening the live column is metadata-only, so an old expectation still holds.
+    db.execute("INSERT INTO users VALUES (1, 'a@example.com', 'al', NULL)")
+        .unwrap();
+    db.execute("ALTER TABLE users MODIFY COLUMN name VARCHAR(200)")
+        .unwrap();
+    let rows = db.query("SELECT name FROM users").unwrap();
+    assert_eq!(
+        rows[0].get("name"),
+        Some(&murodb::Value::Varchar("al".into()))
+    );
+    let expected = SchemaExpectation::from_sql(SCHEMA).unwrap();
+    assert!(db.assert_schema(&expected).unwrap().is_empty());
+    db.execute("ALTER TABLE users MODIFY COLUMN name VARCHAR(10)")
+        .unwrap();
+    assert!(db.assert_schema(&expected).is_err());
+}
+
+#[test]
+fn test_expectation_sql_is_validated() {
+    for (sql, message) in [
+        ("CREATE INDEX idx ON t (a)", "must follow its CREATE TABLE"),
+        ("INSERT INTO t VALUES (1)", "accepts only CREATE TABLE"),
+        (
+            "CREATE TABLE t (a INT); CREATE INDEX idx ON t ((a + 1))",
+            "expression indexes",
+        ),
+        ("CREATE TABLE t (a INT", "SQL parse error"),
+    ] {
+        let err = SchemaExpectation::from_sql(sql).unwrap_err().to_string();
+        assert!(err.contains(message), "{}: {}", sql, err);
+    }
+
+    let expected = SchemaExpectation::from_sql(SCHEMA).unwrap();
+    let notes = &expected.tables[1];
+    assert_eq!(notes.primary_key, vec!["id".to_string()]);
+    assert!(!notes.columns[0].nullable);
+    assert!(expected.allow_extra_tables);
+}
+
+#[test]
+fn test_read_only_handle_can_assert() {
+    let dir = TempDir::new().unwrap();
+    drop(create(&dir));
+    let mut db = Database::open_read_only(&dir.path().join("test.db"), &test_key()).unwrap();
+    let expected = SchemaExpectation::from_sql(SCHEMA).unwrap();
+    assert!(db.assert_schema(&expected).unwrap().is_empty());
+    db.execute("BEGIN").unwrap_err();
+}
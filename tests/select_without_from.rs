@@ -21,13 +21,13 @@ fn setup() -> (Pager, SystemCatalog, TempDir) {
 #[test]
 fn test_select_literals_without_from() {
     let (mut pager, mut catalog, _dir) = setup();
-    let rows = match execute("SELECT 3.14, 1563", &mut pager, &mut catalog).unwrap() {
+    let rows = match execute("SELECT 2.5, 1563", &mut pager, &mut catalog).unwrap() {
         ExecResult::Rows(rows) => rows,
         other => panic!("Expected rows, got {:?}", other),
     };
     assert_eq!(rows.len(), 1);
     assert_eq!(rows[0].values.len(), 2);
-    assert_eq!(rows[0].values[0].1, Value::Float(3.14));
+    assert_eq!(rows[0].values[0].1, Value::Float(2.5));
     assert_eq!(rows[0].values[1].1, Value::Integer(1563));
 }
 
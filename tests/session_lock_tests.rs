@@ -0,0 +1,91 @@
+#![cfg(feature = "test-utils")]
+/// A `Session` from `Database::into_session` keeps the database's lock
+/// manager: its statements run under the file lock, so it cannot write at the
+/// same time as another handle on the same file.
+use murodb::concurrency::LockManager;
+use murodb::crypto::aead::MasterKey;
+use murodb::{Database, ExecResult, MuroError, Value};
+use std::thread;
+use tempfile::TempDir;
+
+fn test_key() -> MasterKey {
+    MasterKey::new([0x42u8; 32])
+}
+
+#[test]
+fn test_session_and_database_writers_do_not_interleave_commits() {
+    let dir = TempDir::new().unwrap();
+    let path = dir.path().join("test.db");
+    {
+        let mut db = Database::create(&path, &test_key()).unwrap();
+        db.execute("CREATE TABLE c (id BIGINT PRIMARY KEY, n BIGINT)")
+            .unwrap();
+        db.execute("INSERT INTO c VALUES (1, 0)").unwrap();
+    }
+
+    let session_path = path.clone();
+    let session_writer = thread::spawn(move || {
+        let mut session = Database::open(&session_path, &test_key())
+            .unwrap()
+            .into_session();
+        for _ in 0..100 {
+            session
+                .execute("UPDATE c SET n = n + 1 WHERE id = 1")
+                .unwrap();
+        }
+    });
+    let database_path = path.clone();
+    let database_writer = thread::spawn(move || {
+        let mut db = Database::open(&database_path, &test_key()).unwrap();
+        for _ in 0..100 {
+            db.execute("UPDATE c SET n = n + 1 WHERE id = 1").unwrap();
+        }
+    });
+    session_writer.join().unwrap();
+    database_writer.join().unwrap();
+
+    let mut db = Database::open(&path, &test_key()).unwrap();
+    let rows = db.query("SELECT n FROM c WHERE id = 1").unwrap();
+    assert_eq!(rows[0].get("n"), Some(&Value::Integer(200)));
+}
+
+#[test]
+fn test_session_waits_for_lock_held_by_another_handle() {
+    let dir = TempDir::new().unwrap();
+    let path = dir.path().join("test.db");
+    let mut db = Database::create(&path, &test_key()).unwrap();
+    db.set_busy_timeout_ms(50);
+    let mut session = db.into_session();
+    assert_eq!(session.busy_timeout_ms(), 50);
+    session
+        .execute("CREATE TABLE t (id BIGINT PRIMARY KEY)")
+        .unwrap();
+    session.execute("BEGIN").unwrap();
+    session.execute("INSERT INTO t VALUES (1)").unwrap();
+
+    {
+        let other = LockManager::new(&path).unwrap();
+        let _held = other.write_lock().unwrap();
+        for result in [
+            session.execute("COMMIT").map(|_| ()),
+            session
+                .execute_read_only_query("SELECT * FROM t")
+                .map(|_| ()),
+            session
+                .bulk_insert("t", &[vec![Value::Integer(2)]])
+                .map(|_| ()),
+        ] {
+            assert!(
+                matches!(result, Err(MuroError::LockTimeout { .. })),
+                "{:?}",
+                result
+            );
+        }
+    }
+
+    // The transaction is still open once the lock is released.
+    assert!(session.transaction_info().is_some());
+    assert!(matches!(session.execute("COMMIT").unwrap(), ExecResult::Ok));
+    let mut other = Database::open(&path, &test_key()).unwrap();
+    assert_eq!(other.query("SELECT * FROM t").unwrap().len(), 1);
+}
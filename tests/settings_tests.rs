@@ -0,0 +1,241 @@
+#![cfg(feature = "test-utils")]
+/// Session settings: `SET name = value` validated against the settings
+/// registry, `SHOW VARIABLES`, and the settings' effects (live values in
+/// `SHOW DATABASE STATS`, `read_only`, `fts_vacuum_batch` in OPTIMIZE TABLE).
+use murodb::fts::index::FtsIndex;
+use murodb::schema::catalog::SystemCatalog;
+use murodb::storage::pager::Pager;
+use murodb::{Database, MuroError, Value};
+use std::path::Path;
+use tempfile::TempDir;
+
+fn setup() -> (Database, TempDir) {
+    let dir = TempDir::new().unwrap();
+    let db = Database::create_plaintext(&dir.path().join("test.db")).unwrap();
+    (db, dir)
+}
+
+fn err(db: &mut Database, sql: &str) -> String {
+    db.execute(sql).unwrap_err().to_string()
+}
+
+fn variables(db: &mut Database, pattern: &str) -> Vec<(String, String)> {
+    db.query(&format!("SHOW VARIABLES LIKE '{}'", pattern))
+        .unwrap()
+        .into_iter()
+        .map(|row| match (row.get("variable_name"), row.get("value")) {
+            (Some(Value::Varchar(name)), Some(Value::Varchar(value))) => {
+                (name.clone(), value.clone())
+            }
+            other => panic!("unexpected SHOW VARIABLES row: {:?}", other),
+        })
+        .collect()
+}
+
+fn variable(db: &mut Database, name: &str) -> String {
+    let rows = variables(db, name);
+    assert_eq!(rows.len(), 1, "{:?}", rows);
+    rows[0].1.clone()
+}
+
+fn stat(db: &mut Database, name: &str) -> String {
+    db.query("SHOW DATABASE STATS")
+        .unwrap()
+        .into_iter()
+        .find(|r| r.get("stat") == Some(&Value::Varchar(name.to_string())))
+        .and_then(|r| match r.get("value") {
+            Some(Value::Varchar(v)) => Some(v.clone()),
+            _ => None,
+        })
+        .unwrap_or_else(|| panic!("missing stat {}", name))
+}
+
+#[test]
+fn test_set_and_show_variables() {
+    let (mut db, _dir) = setup();
+    let names: Vec<String> = variables(&mut db, "%")
+        .into_iter()
+        .map(|(name, _)| name)
+        .collect();
+    for expected in [
+        "busy_timeout",
+        "checkpoint_interval_ms",
+        "checkpoint_tx_threshold",
+        "checkpoint_wal_bytes_threshold",
+        "fts_vacuum_batch",
+        "page_cache_pages",
+        "read_only",
+    ] {
+        assert!(names.iter().any(|n| n == expected), "{:?}", names);
+    }
+
+    db.execute("SET checkpoint_tx_threshold = 8").unwrap();
+    db.execute("SET busy_timeout = 250").unwrap();
+    db.execute("SET scan_corruption_policy = 'SKIP'").unwrap();
+    db.execute("SET predicate_reorder = off").unwrap();
+    assert_eq!(variable(&mut db, "checkpoint_tx_threshold"), "8");
+    assert_eq!(variable(&mut db, "busy_timeout"), "250");
+    assert_eq!(variable(&mut db, "scan_corruption_policy"), "skip");
+    assert_eq!(variable(&mut db, "predicate_reorder"), "OFF");
+
+    let checkpoint: Vec<String> = variables(&mut db, "CHECKPOINT%")
+        .into_iter()
+        .map(|(name, _)| name)
+        .collect();
+    assert_eq!(
+        checkpoint,
+        [
+            "checkpoint_interval_ms",
+            "checkpoint_tx_threshold",
+            "checkpoint_wal_bytes_threshold"
+        ]
+    );
+}
+
+#[test]
+fn test_invalid_values_name_the_valid_range() {
+    let (mut db, _dir) = setup();
+    let msg = err(&mut db, "SET page_cache_pages = 0");
+    assert!(msg.contains("page_cache_pages"), "{}", msg);
+    assert!(msg.contains("from 1 to 9223372036854775807"), "{}", msg);
+
+    let msg = err(&mut db, "SET checkpoint_tx_threshold = -1");
+    assert!(msg.contains("from 0 to"), "{}", msg);
+    let msg = err(&mut db, "SET read_only = 1");
+    assert!(msg.contains("ON or OFF"), "{}", msg);
+    let msg = err(&mut db, "SET scan_corruption_policy = 'ignore'");
+    assert!(msg.contains("'error', 'skip'"), "{}", msg);
+    let msg = err(&mut db, "SET busy_timeout = 'fast'");
+    assert!(msg.contains("an integer"), "{}", msg);
+
+    let msg = err(&mut db, "SET no_such_setting = 1");
+    assert!(msg.contains("Unknown setting 'no_such_setting'"), "{}", msg);
+    assert!(msg.contains("page_cache_pages"), "{}", msg);
+
+    // Nothing was changed by the failed statements.
+    assert_eq!(variable(&mut db, "page_cache_pages"), "256");
+    assert_eq!(variable(&mut db, "read_only"), "OFF");
+}
+
+#[test]
+fn test_transaction_rules() {
+    let (mut db, _dir) = setup();
+    db.execute("BEGIN").unwrap();
+    for sql in [
+        "SET checkpoint_interval_ms = 10",
+        "SET page_cache_pages = 64",
+        "SET read_only = ON",
+    ] {
+        let msg = err(&mut db, sql);
+        assert!(
+            msg.contains("cannot be used inside a transaction"),
+            "{}: {}",
+            sql,
+            msg
+        );
+    }
+    db.execute("SET busy_timeout = 100").unwrap();
+    db.execute("SET fts_vacuum_batch = 5").unwrap();
+    db.execute("COMMIT").unwrap();
+    assert_eq!(variable(&mut db, "checkpoint_interval_ms"), "0");
+    assert_eq!(variable(&mut db, "busy_timeout"), "100");
+    assert_eq!(variable(&mut db, "fts_vacuum_batch"), "5");
+}
+
+#[test]
+fn test_database_stats_show_live_values() {
+    let (mut db, _dir) = setup();
+    db.execute("SET page_cache_pages = 16").unwrap();
+    db.execute("SET checkpoint_wal_bytes_threshold = 4096")
+        .unwrap();
+    db.execute("SET busy_timeout = 75").unwrap();
+    assert_eq!(stat(&mut db, "pager_cache_capacity_pages"), "16");
+    assert_eq!(
+        stat(&mut db, "checkpoint_policy_wal_bytes_threshold"),
+        "4096"
+    );
+    assert_eq!(stat(&mut db, "busy_timeout_ms"), "75");
+    assert_eq!(stat(&mut db, "read_only"), "false");
+}
+
+#[test]
+fn test_read_only_rejects_writes() {
+    let (mut db, _dir) = setup();
+    db.execute("CREATE TABLE t (id BIGINT PRIMARY KEY, v INT)")
+        .unwrap();
+    db.execute("INSERT INTO t VALUES (1, 10)").unwrap();
+
+    db.execute("SET read_only = ON").unwrap();
+    for sql in [
+        "INSERT INTO t VALUES (2, 20)",
+        "UPDATE t SET v = 11",
+        "DELETE FROM t",
+        "CREATE TABLE u (id BIGINT PRIMARY KEY)",
+        "OPTIMIZE TABLE t",
+        "EXPLAIN ANALYZE DELETE FROM t",
+    ] {
+        assert!(
+            matches!(db.execute(sql), Err(MuroError::ReadOnly)),
+            "{}",
+            sql
+        );
+    }
+    // Reads and transaction control still run.
+    db.execute("BEGIN").unwrap();
+    assert_eq!(db.query("SELECT * FROM t").unwrap().len(), 1);
+    db.execute("COMMIT").unwrap();
+    assert_eq!(stat(&mut db, "read_only"), "true");
+
+    db.execute("SET read_only = OFF").unwrap();
+    db.execute("INSERT INTO t VALUES (2, 20)").unwrap();
+    assert_eq!(db.query("SELECT * FROM t").unwrap().len(), 2);
+}
+
+/// GC tasks still queued on the FULLTEXT index `idx_body`, drained by
+/// running them all.
+fn pending_fts_gc_tasks(path: &Path) -> usize {
+    let mut pager = Pager::open_plaintext(path).unwrap();
+    let catalog = SystemCatalog::open(pager.catalog_root());
+    let idx = catalog.get_index(&mut pager, "idx_body").unwrap().unwrap();
+    let mut fts = FtsIndex::open(idx.btree_root, pager.fts_term_key().unwrap());
+    fts.vacuum_stale_segments(&mut pager, usize::MAX).unwrap()
+}
+
+#[test]
+fn test_optimize_table_vacuums_fulltext_segments_up_to_batch() {
+    let dir = TempDir::new().unwrap();
+    let build = |path: &Path| {
+        let mut db = Database::create_plaintext(path).unwrap();
+        db.execute("CREATE TABLE docs (id BIGINT PRIMARY KEY, body TEXT)")
+            .unwrap();
+        db.execute("CREATE FULLTEXT INDEX idx_body ON docs (body) WITH PARSER ngram")
+            .unwrap();
+        for i in 0..20 {
+            db.execute(&format!(
+                "INSERT INTO docs VALUES ({}, 'shared words {}')",
+                i, i
+            ))
+            .unwrap();
+        }
+        db
+    };
+
+    let skipped = dir.path().join("skipped.db");
+    {
+        let mut db = build(&skipped);
+        db.execute("SET fts_vacuum_batch = 0").unwrap();
+        db.execute("OPTIMIZE TABLE docs").unwrap();
+    }
+    assert!(pending_fts_gc_tasks(&skipped) > 0);
+
+    let path = dir.path().join("vacuumed.db");
+    {
+        let mut db = build(&path);
+        db.execute("OPTIMIZE TABLE docs").unwrap();
+        let rows = db
+            .query("SELECT id FROM docs WHERE MATCH(body) AGAINST('shared' IN BOOLEAN MODE) > 0")
+            .unwrap();
+        assert_eq!(rows.len(), 20);
+    }
+    assert_eq!(pending_fts_gc_tasks(&path), 0);
+}
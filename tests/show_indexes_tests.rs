@@ -0,0 +1,161 @@
+#![cfg(feature = "test-utils")]
+/// SHOW INDEXES lists every index key part, and SHOW CREATE TABLE emits
+/// CREATE INDEX indexes as KEY lines that recreate them.
+use murodb::crypto::aead::MasterKey;
+use murodb::{Database, Value};
+use tempfile::TempDir;
+
+fn test_key() -> MasterKey {
+    MasterKey::new([0x42u8; 32])
+}
+
+fn text(row: &murodb::Row, name: &str) -> String {
+    match row.get(name) {
+        Some(Value::Varchar(s)) => s.clone(),
+        Some(Value::Integer(n)) => n.to_string(),
+        other => panic!("unexpected {} {:?}", name, other),
+    }
+}
+
+/// `(Key_name, Seq_in_index, Column_name, Unique, Index_type)` per row.
+fn index_rows(db: &mut Database, sql: &str) -> Vec<(String, String, String, String, String)> {
+    db.query(sql)
+        .unwrap()
+        .iter()
+        .map(|row| {
+            assert_eq!(text(row, "Table"), "t");
+            assert!(matches!(row.get("Root_page"), Some(Value::Integer(n)) if *n > 0));
+            (
+                text(row, "Key_name"),
+                text(row, "Seq_in_index"),
+                text(row, "Column_name"),
+                text(row, "Unique"),
+                text(row, "Index_type"),
+            )
+        })
+        .collect()
+}
+
+fn show_create(db: &mut Database) -> String {
+    text(&db.query("SHOW CREATE TABLE t").unwrap()[0], "Create Table")
+}
+
+fn row(
+    name: &str,
+    seq: &str,
+    column: &str,
+    unique: &str,
+    kind: &str,
+) -> (String, String, String, String, String) {
+    (
+        name.to_string(),
+        seq.to_string(),
+        column.to_string(),
+        unique.to_string(),
+        kind.to_string(),
+    )
+}
+
+#[test]
+fn test_show_indexes_lists_constraint_and_composite_indexes() {
+    let dir = TempDir::new().unwrap();
+    let mut db = Database::create(&dir.path().join("test.db"), &test_key()).unwrap();
+    db.execute(
+        "CREATE TABLE t (id BIGINT PRIMARY KEY, a INT UNIQUE, b INT, c VARCHAR, UNIQUE (a, b))",
+    )
+    .unwrap();
+    db.execute("CREATE INDEX idx_cb ON t (c, b)").unwrap();
+    db.execute("CREATE FULLTEXT INDEX ft_c ON t (c) WITH PARSER ngram")
+        .unwrap();
+
+    let expected = vec![
+        row("auto_unique_t_a", "1", "a", "YES", "BTREE"),
+        row("auto_unique_t_a_b", "1", "a", "YES", "BTREE"),
+        row("auto_unique_t_a_b", "2", "b", "YES", "BTREE"),
+        row("ft_c", "1", "c", "NO", "FULLTEXT"),
+        row("idx_cb", "1", "c", "NO", "BTREE"),
+        row("idx_cb", "2", "b", "NO", "BTREE"),
+    ];
+    assert_eq!(index_rows(&mut db, "SHOW INDEXES FROM t"), expected);
+    assert_eq!(index_rows(&mut db, "SHOW INDEX FROM t"), expected);
+
+    db.execute("CREATE TABLE empty (id BIGINT PRIMARY KEY)")
+        .unwrap();
+    assert!(db.query("SHOW INDEXES FROM empty").unwrap().is_empty());
+    assert!(db.query("SHOW INDEXES FROM missing").is_err());
+}
+
+#[test]
+fn test_show_create_table_recreates_indexes() {
+    let dir = TempDir::new().unwrap();
+    let mut db = Database::create(&dir.path().join("a.db"), &test_key()).unwrap();
+    db.execute(
+        "CREATE TABLE t (id BIGINT PRIMARY KEY, a INT UNIQUE, b INT, c VARCHAR, UNIQUE (a, b))",
+    )
+    .unwrap();
+    db.execute("CREATE INDEX idx_cb ON t (c, b)").unwrap();
+    db.execute("CREATE UNIQUE INDEX uq_c ON t (c)").unwrap();
+    db.execute("CREATE INDEX idx_lower ON t (b, (LOWER(c)))")
+        .unwrap();
+
+    let ddl = show_create(&mut db);
+    assert!(ddl.contains("  a INT UNIQUE,"), "{}", ddl);
+    assert!(ddl.contains("  UNIQUE (a, b),"), "{}", ddl);
+    assert!(ddl.contains("  KEY idx_cb (c, b),"), "{}", ddl);
+    assert!(ddl.contains("  KEY idx_lower (b, (LOWER(c))),"), "{}", ddl);
+    assert!(ddl.contains("  UNIQUE KEY uq_c (c)\n"), "{}", ddl);
+
+    let mut copy = Database::create(&dir.path().join("b.db"), &test_key()).unwrap();
+    copy.execute(&ddl).unwrap();
+    assert_eq!(show_create(&mut copy), ddl);
+    assert_eq!(
+        index_rows(&mut copy, "SHOW INDEXES FROM t"),
+        index_rows(&mut db, "SHOW INDEXES FROM t")
+    );
+
+    // The recreated indexes are enforced and used like the originals.
+    copy.execute("INSERT INTO t VALUES (1, 1, 1, 'x')").unwrap();
+    assert!(copy.execute("INSERT INTO t VALUES (2, 2, 2, 'x')").is_err());
+    let plan = copy
+        .query("EXPLAIN SELECT id FROM t WHERE b = 1 AND LOWER(c) = 'x'")
+        .unwrap();
+    assert_eq!(text(&plan[0], "key"), "idx_lower");
+}
+
+#[test]
+fn test_create_table_key_definitions() {
+    let dir = TempDir::new().unwrap();
+    let mut db = Database::create(&dir.path().join("test.db"), &test_key()).unwrap();
+    db.execute(
+        "CREATE TABLE t (id BIGINT PRIMARY KEY, a INT, b INT, \
+         KEY idx_a (a), INDEX idx_ab (a, b), UNIQUE INDEX uq_b (b))",
+    )
+    .unwrap();
+    assert_eq!(
+        index_rows(&mut db, "SHOW INDEXES FROM t"),
+        vec![
+            row("idx_a", "1", "a", "NO", "BTREE"),
+            row("idx_ab", "1", "a", "NO", "BTREE"),
+            row("idx_ab", "2", "b", "NO", "BTREE"),
+            row("uq_b", "1", "b", "YES", "BTREE"),
+        ]
+    );
+
+    // Bad key definitions fail the whole CREATE TABLE.
+    assert!(db
+        .execute("CREATE TABLE t2 (id BIGINT PRIMARY KEY, KEY k (missing))")
+        .is_err());
+    assert!(db
+        .execute("CREATE TABLE t3 (id BIGINT PRIMARY KEY, a INT, KEY k (a), KEY k (a))")
+        .is_err());
+    assert!(db
+        .execute("CREATE TABLE t4 (id BIGINT PRIMARY KEY, a INT, KEY idx_a (a))")
+        .is_err());
+    let tables: Vec<String> = db
+        .query("SHOW TABLES")
+        .unwrap()
+        .iter()
+        .map(|r| text(r, "Table"))
+        .collect();
+    assert_eq!(tables, vec!["t".to_string()]);
+}
@@ -1,6 +1,8 @@
 #![cfg(feature = "test-utils")]
 use murodb::btree::ops::BTree;
 use murodb::crypto::aead::MasterKey;
+use murodb::crypto::hmac_util::TermKey;
+use murodb::fts::index::FtsIndex;
 use murodb::schema::catalog::SystemCatalog;
 use murodb::sql::executor::{execute, ExecResult, Row};
 use murodb::storage::pager::Pager;
@@ -94,6 +96,47 @@ fn test_sql_fulltext_create_and_natural_query() {
     assert!(matches!(rows[0].get("score"), Some(Value::Integer(n)) if *n > 0));
 }
 
+#[test]
+fn test_sql_fulltext_natural_quoted_phrase() {
+    let (mut pager, mut catalog, _dir) = setup();
+
+    exec(
+        &mut pager,
+        &mut catalog,
+        "CREATE TABLE t (id BIGINT PRIMARY KEY, body TEXT)",
+    );
+    exec(
+        &mut pager,
+        &mut catalog,
+        "CREATE FULLTEXT INDEX ft_body ON t(body) WITH PARSER ngram OPTIONS (n=2, normalize='nfkc')",
+    );
+    exec(
+        &mut pager,
+        &mut catalog,
+        "INSERT INTO t VALUES (1, '東京都庁'), (2, '東京から京都へ'), (3, '京都')",
+    );
+
+    let ids = |rows: Vec<Row>| -> Vec<Value> {
+        rows.iter().map(|r| r.get("id").unwrap().clone()).collect()
+    };
+    // Both bigrams of 東京都 occur in doc 2, but not adjacently.
+    let rows = query_rows(
+        &mut pager,
+        &mut catalog,
+        "SELECT id FROM t WHERE MATCH(body) AGAINST('\"東京都\"') > 0 ORDER BY id",
+    );
+    assert_eq!(ids(rows), vec![Value::Integer(1)]);
+    let rows = query_rows(
+        &mut pager,
+        &mut catalog,
+        "SELECT id FROM t WHERE MATCH(body) AGAINST('東京都' IN NATURAL LANGUAGE MODE) > 0 ORDER BY id",
+    );
+    assert_eq!(
+        ids(rows),
+        vec![Value::Integer(1), Value::Integer(2), Value::Integer(3)]
+    );
+}
+
 #[test]
 fn test_sql_fulltext_boolean_and_snippet() {
     let (mut pager, mut catalog, _dir) = setup();
@@ -471,6 +514,84 @@ fn test_sql_fulltext_stop_ngram_filter_toggle() {
     assert_eq!(rows_on[0].get("id"), Some(&Value::Integer(1)));
 }
 
+#[test]
+fn test_sql_fulltext_stop_filter_tracks_current_df() {
+    let (mut pager, mut catalog, _dir) = setup();
+    exec(
+        &mut pager,
+        &mut catalog,
+        "CREATE TABLE t (id BIGINT PRIMARY KEY, body TEXT)",
+    );
+    exec(
+        &mut pager,
+        &mut catalog,
+        "CREATE FULLTEXT INDEX ft_body ON t(body) WITH PARSER ngram OPTIONS (n=2, normalize='nfkc', stop_filter='on', stop_df_ratio_ppm=500000)",
+    );
+    for (id, body) in [(1, "東京タワー"), (2, "タワー東京"), (3, "タワー大阪")] {
+        exec(
+            &mut pager,
+            &mut catalog,
+            &format!("INSERT INTO t VALUES ({}, '{}')", id, body),
+        );
+    }
+    for id in 4..=10 {
+        exec(
+            &mut pager,
+            &mut catalog,
+            &format!("INSERT INTO t VALUES ({}, '大阪城{}')", id, id),
+        );
+    }
+    let ranked = |pager: &mut Pager, catalog: &mut SystemCatalog| -> Vec<i64> {
+        query_rows(
+            pager,
+            catalog,
+            "SELECT id, MATCH(body) AGAINST('東京タワー' IN NATURAL LANGUAGE MODE) AS score FROM t WHERE MATCH(body) AGAINST('東京タワー' IN NATURAL LANGUAGE MODE) > 0 ORDER BY score DESC, id",
+        )
+        .iter()
+        .map(|r| match r.get("id") {
+            Some(Value::Integer(n)) => *n,
+            other => panic!("unexpected id {:?}", other),
+        })
+        .collect()
+    };
+    // 東京 is in 20% of documents: an ordinary ngram.
+    assert_eq!(ranked(&mut pager, &mut catalog), vec![1, 2, 3]);
+
+    // Growing to 90% makes it a stop ngram: it no longer pulls in the bulk
+    // of the corpus, but still ranks 2 above 3.
+    for id in 11..=80 {
+        exec(
+            &mut pager,
+            &mut catalog,
+            &format!("INSERT INTO t VALUES ({}, '東京駅{}')", id, id),
+        );
+    }
+    assert_eq!(ranked(&mut pager, &mut catalog), vec![1, 2, 3]);
+    let rescan = query_rows(
+        &mut pager,
+        &mut catalog,
+        "SELECT id FROM t WHERE MATCH(body) AGAINST('東京駅' IN NATURAL LANGUAGE MODE) > 0",
+    );
+    // Both of its ngrams are stop ngrams, so this is the fallback rescan.
+    assert_eq!(rescan.len(), 72);
+    // The postings themselves are intact.
+    let all = query_rows(
+        &mut pager,
+        &mut catalog,
+        "SELECT id FROM t WHERE MATCH(body) AGAINST('+東京' IN BOOLEAN MODE) > 0",
+    );
+    assert_eq!(all.len(), 72);
+
+    // Back under the threshold, 東京 selects documents again.
+    exec(&mut pager, &mut catalog, "DELETE FROM t WHERE id > 10");
+    exec(
+        &mut pager,
+        &mut catalog,
+        "INSERT INTO t VALUES (11, '東京駅')",
+    );
+    assert_eq!(ranked(&mut pager, &mut catalog), vec![1, 2, 3, 11]);
+}
+
 #[test]
 fn test_sql_fulltext_stop_df_ratio_validation() {
     let (mut pager, mut catalog, _dir) = setup();
@@ -526,7 +647,7 @@ fn test_sql_fulltext_legacy_fixed_key_metadata_backfill() {
     // Simulate a pre-metadata legacy DB that indexed terms with the historical fixed key.
     {
         let mut pager = Pager::create(&db_path, &test_key()).unwrap();
-        pager.set_fts_term_key([0x55u8; 32]);
+        pager.set_fts_term_key(TermKey::new([0x55u8; 32]));
         let mut catalog = SystemCatalog::create(&mut pager).unwrap();
         pager.set_catalog_root(catalog.root_page_id());
         pager.flush_meta().unwrap();
@@ -568,7 +689,7 @@ fn test_sql_fulltext_legacy_fixed_key_metadata_backfill() {
     {
         let mut pager = Pager::open(&db_path, &test_key()).unwrap();
         let catalog = SystemCatalog::open(pager.catalog_root());
-        assert_eq!(catalog.get_fts_term_key(&mut pager).unwrap(), None);
+        assert!(catalog.get_fts_term_key(&mut pager).unwrap().is_none());
     }
 }
 
@@ -619,3 +740,77 @@ fn test_sql_fulltext_bootstrap_key_metadata_backfill() {
         assert_eq!(rows[0].get("id"), Some(&Value::Integer(1)));
     }
 }
+
+/// Pages in use: allocated minus those on the freelist.
+fn used_pages(pager: &mut Pager) -> u64 {
+    pager.page_count() - pager.freelist_mut().len() as u64
+}
+
+/// Create the FULLTEXT index `fts_body` over documents large enough that
+/// their posting lists spill into overflow chains, and check that they did.
+fn create_overflowing_fts(pager: &mut Pager, catalog: &mut SystemCatalog) {
+    exec(
+        pager,
+        catalog,
+        "CREATE FULLTEXT INDEX fts_body ON docs (body) WITH PARSER ngram",
+    );
+    let idx = catalog.get_index(pager, "fts_body").unwrap().unwrap();
+    let fts = FtsIndex::open(idx.btree_root, pager.fts_term_key().unwrap());
+    let check = fts.verify(pager, |_, _| {});
+    assert!(check.problems.is_empty(), "{:?}", check.problems);
+    let btree_pages = BTree::open(idx.btree_root)
+        .collect_all_pages(pager)
+        .unwrap();
+    assert!(
+        check.pages.len() > btree_pages.len(),
+        "expected posting lists stored in overflow chains"
+    );
+}
+
+fn setup_large_docs(pager: &mut Pager, catalog: &mut SystemCatalog) {
+    exec(
+        pager,
+        catalog,
+        "CREATE TABLE docs (id BIGINT PRIMARY KEY, body TEXT)",
+    );
+    for (i, unit) in ["ab", "cd", "ef"].iter().enumerate() {
+        exec(
+            pager,
+            catalog,
+            &format!("INSERT INTO docs VALUES ({}, '{}')", i, unit.repeat(6000)),
+        );
+    }
+}
+
+#[test]
+fn test_drop_fulltext_index_frees_overflow_pages() {
+    let (mut pager, mut catalog, _dir) = setup();
+    setup_large_docs(&mut pager, &mut catalog);
+    let before = used_pages(&mut pager);
+
+    create_overflowing_fts(&mut pager, &mut catalog);
+    exec(&mut pager, &mut catalog, "DROP INDEX fts_body");
+    assert_eq!(used_pages(&mut pager), before);
+
+    // The freed pages are reused by a rebuild.
+    let high_water = pager.page_count();
+    create_overflowing_fts(&mut pager, &mut catalog);
+    assert_eq!(pager.page_count(), high_water);
+    let rows = query_rows(
+        &mut pager,
+        &mut catalog,
+        "SELECT id FROM docs WHERE MATCH(body) AGAINST('cd' IN BOOLEAN MODE) > 0",
+    );
+    assert_eq!(rows.len(), 1);
+}
+
+#[test]
+fn test_drop_table_frees_fulltext_overflow_pages() {
+    let (mut pager, mut catalog, _dir) = setup();
+    let before = used_pages(&mut pager);
+
+    setup_large_docs(&mut pager, &mut catalog);
+    create_overflowing_fts(&mut pager, &mut catalog);
+    exec(&mut pager, &mut catalog, "DROP TABLE docs");
+    assert_eq!(used_pages(&mut pager), before);
+}
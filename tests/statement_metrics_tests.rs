@@ -0,0 +1,162 @@
+#![cfg(feature = "test-utils")]
+/// Per-statement write metrics: which statements dirtied pages or appended
+/// WAL frames. Read-only paths must always report zero.
+use murodb::crypto::aead::MasterKey;
+use murodb::{Database, StatementMetrics};
+use tempfile::TempDir;
+
+fn test_key() -> MasterKey {
+    MasterKey::new([0x42u8; 32])
+}
+
+fn setup(dir: &TempDir) -> Database {
+    let mut db = Database::create(&dir.path().join("test.db"), &test_key()).unwrap();
+    db.execute("CREATE TABLE t (id BIGINT PRIMARY KEY, name VARCHAR)")
+        .unwrap();
+    db.execute("INSERT INTO t VALUES (1, 'a')").unwrap();
+    db.execute("INSERT INTO t VALUES (2, 'b')").unwrap();
+    db
+}
+
+fn assert_no_writes(db: &Database, what: &str) {
+    assert_eq!(
+        db.last_statement_metrics(),
+        StatementMetrics::default(),
+        "{what}"
+    );
+}
+
+#[test]
+fn test_query_reports_no_writes() {
+    let dir = TempDir::new().unwrap();
+    let mut db = setup(&dir);
+
+    for sql in [
+        "SELECT * FROM t",
+        "SELECT COUNT(*) FROM t WHERE id = 1",
+        "SHOW TABLES",
+        "DESCRIBE t",
+        "EXPLAIN SELECT * FROM t",
+        "EXPLAIN UPDATE t SET name = 'x' WHERE id = 1",
+        "EXPLAIN DELETE FROM t",
+        "EXPLAIN ANALYZE SELECT * FROM t",
+    ] {
+        db.query(sql).unwrap();
+        assert_no_writes(&db, sql);
+    }
+}
+
+#[test]
+fn test_reader_query_reports_no_writes() {
+    let dir = TempDir::new().unwrap();
+    let db = setup(&dir);
+    let mut reader = db.open_reader().unwrap();
+    reader.query("SELECT * FROM t").unwrap();
+    assert_eq!(reader.last_statement_metrics(), StatementMetrics::default());
+}
+
+#[test]
+fn test_rejected_write_through_query_reports_no_writes() {
+    let dir = TempDir::new().unwrap();
+    let mut db = setup(&dir);
+    db.execute("INSERT INTO t VALUES (3, 'c')").unwrap();
+    assert!(db.query("DELETE FROM t").is_err());
+    assert_no_writes(&db, "rejected DELETE");
+}
+
+#[test]
+fn test_auto_commit_write_reports_pages_and_frames() {
+    let dir = TempDir::new().unwrap();
+    let mut db = setup(&dir);
+    db.execute("INSERT INTO t VALUES (3, 'c')").unwrap();
+    let m = db.last_statement_metrics();
+    assert!(m.performed_writes);
+    assert!(m.pages_dirtied > 0);
+    assert!(m.wal_frames_appended > m.pages_dirtied);
+}
+
+/// `execute` of a SELECT outside a transaction commits an empty implicit
+/// transaction. This pins that behavior: WAL frames, but no dirtied pages.
+#[test]
+fn test_auto_commit_select_through_execute_appends_wal_frames() {
+    let dir = TempDir::new().unwrap();
+    let mut db = setup(&dir);
+    db.execute("SELECT * FROM t").unwrap();
+    let m = db.last_statement_metrics();
+    assert_eq!(m.pages_dirtied, 0);
+    assert!(m.wal_frames_appended > 0);
+    assert!(m.performed_writes);
+
+    db.execute("EXPLAIN UPDATE t SET name = 'x' WHERE id = 1")
+        .unwrap();
+    assert_eq!(db.last_statement_metrics().pages_dirtied, 0);
+}
+
+#[test]
+fn test_explicit_transaction_defers_frames_to_commit() {
+    let dir = TempDir::new().unwrap();
+    let mut db = setup(&dir);
+
+    db.execute("BEGIN").unwrap();
+    assert_no_writes(&db, "BEGIN");
+
+    db.execute("SELECT * FROM t").unwrap();
+    assert_no_writes(&db, "SELECT in transaction");
+
+    db.execute("EXPLAIN UPDATE t SET name = 'x'").unwrap();
+    assert_no_writes(&db, "EXPLAIN UPDATE in transaction");
+
+    db.execute("UPDATE t SET name = 'x' WHERE id = 1").unwrap();
+    let m = db.last_statement_metrics();
+    assert!(m.performed_writes);
+    assert!(m.pages_dirtied > 0);
+    assert_eq!(m.wal_frames_appended, 0);
+
+    db.execute("COMMIT").unwrap();
+    let m = db.last_statement_metrics();
+    assert!(m.performed_writes);
+    assert_eq!(m.pages_dirtied, 0);
+    assert!(m.wal_frames_appended > 0);
+}
+
+#[test]
+fn test_explain_analyze_write_dirties_pages_without_wal() {
+    let dir = TempDir::new().unwrap();
+    let mut db = setup(&dir);
+    db.execute("EXPLAIN ANALYZE DELETE FROM t").unwrap();
+    let m = db.last_statement_metrics();
+    assert!(m.pages_dirtied > 0);
+    assert_eq!(m.wal_frames_appended, 0);
+}
+
+#[test]
+fn test_commit_coalesces_consecutive_page_writes() {
+    let dir = TempDir::new().unwrap();
+    let mut db = setup(&dir);
+    let body = "b".repeat(1000);
+
+    db.execute("BEGIN").unwrap();
+    for i in 10..2010 {
+        db.execute(&format!("INSERT INTO t VALUES ({}, '{}')", i, body))
+            .unwrap();
+    }
+    assert_eq!(db.last_statement_metrics().data_pages_flushed, 0);
+    db.execute("COMMIT").unwrap();
+    let m = db.last_statement_metrics();
+    // The new leaves come from claimed extents, so most of them are written
+    // in a few long runs instead of one write per page.
+    assert!(m.data_pages_flushed > 500, "{:?}", m);
+    assert!(
+        m.contiguous_write_runs * 8 < m.data_pages_flushed,
+        "{:?}",
+        m
+    );
+    assert!(m.avg_run_length() > 8.0, "{:?}", m);
+
+    // Reopening reads every committed page back.
+    let path = dir.path().join("test.db");
+    drop(db);
+    let mut db = Database::open(&path, &test_key()).unwrap();
+    let rows = db.query("SELECT name FROM t WHERE id >= 10").unwrap();
+    assert_eq!(rows.len(), 2000);
+}
@@ -0,0 +1,204 @@
+#![cfg(feature = "test-utils")]
+/// `ALTER TABLE a EXCHANGE WITH b` swaps the contents of two identically
+/// shaped tables in one catalog change: readers see one mapping or the other,
+/// indexes move with the rows, and mismatched schemas are rejected.
+use murodb::crypto::aead::MasterKey;
+use murodb::{Database, MuroError, Value};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::thread;
+use tempfile::TempDir;
+
+fn test_key() -> MasterKey {
+    MasterKey::new([0x42u8; 32])
+}
+
+fn setup(db: &mut Database) {
+    db.execute_batch(
+        "CREATE TABLE live (id BIGINT PRIMARY KEY, email VARCHAR(100) UNIQUE, body TEXT);
+         CREATE INDEX idx_live_body ON live (body);
+         CREATE TABLE staging (id BIGINT PRIMARY KEY, email VARCHAR(100) UNIQUE, body TEXT);
+         CREATE INDEX idx_staging_body ON staging (body);
+         INSERT INTO live VALUES (1, 'old@example.com', 'old');
+         INSERT INTO staging VALUES (1, 'new@example.com', 'new'), (2, 'two@example.com', 'new');",
+    )
+    .unwrap();
+}
+
+fn count(db: &mut Database, table: &str) -> i64 {
+    let rows = db
+        .query(&format!("SELECT COUNT(*) FROM {}", table))
+        .unwrap();
+    match rows[0].get("COUNT(*)") {
+        Some(Value::Integer(n)) => *n,
+        other => panic!("unexpected count {:?}", other),
+    }
+}
+
+fn index_names(db: &mut Database, table: &str) -> Vec<String> {
+    let mut names: Vec<String> = db
+        .query(&format!("SHOW INDEXES FROM {}", table))
+        .unwrap()
+        .iter()
+        .filter_map(|r| match r.get("Key_name") {
+            Some(Value::Varchar(s)) => Some(s.clone()),
+            _ => None,
+        })
+        .collect();
+    names.sort();
+    names.dedup();
+    names
+}
+
+#[test]
+fn test_exchange_swaps_rows_and_indexes() {
+    let dir = TempDir::new().unwrap();
+    let mut db = Database::create(&dir.path().join("test.db"), &test_key()).unwrap();
+    setup(&mut db);
+    let live_indexes = index_names(&mut db, "live");
+    let staging_indexes = index_names(&mut db, "staging");
+    assert!(live_indexes.contains(&"idx_live_body".to_string()));
+
+    db.execute("ALTER TABLE live EXCHANGE WITH staging")
+        .unwrap();
+    assert_eq!(count(&mut db, "live"), 2);
+    assert_eq!(count(&mut db, "staging"), 1);
+
+    // Index names stay with their tables; the indexed rows move.
+    assert_eq!(index_names(&mut db, "live"), live_indexes);
+    assert_eq!(index_names(&mut db, "staging"), staging_indexes);
+    let rows = db
+        .query("SELECT id FROM live WHERE email = 'two@example.com'")
+        .unwrap();
+    assert_eq!(rows[0].get("id"), Some(&Value::Integer(2)));
+    let rows = db.query("SELECT id FROM live WHERE body = 'new'").unwrap();
+    assert_eq!(rows.len(), 2);
+    assert!(db
+        .query("SELECT id FROM staging WHERE email = 'two@example.com'")
+        .unwrap()
+        .is_empty());
+    let err = db
+        .execute("INSERT INTO live VALUES (3, 'new@example.com', 'dup')")
+        .unwrap_err();
+    assert!(matches!(err, MuroError::UniqueViolation(_)), "{}", err);
+    db.execute("INSERT INTO staging VALUES (3, 'new@example.com', 'ok')")
+        .unwrap();
+
+    let check = db.verify_integrity().unwrap();
+    assert!(check
+        .iter()
+        .all(|r| r.get("status") != Some(&Value::Varchar("error".into()))));
+}
+
+#[test]
+fn test_exchange_rejects_schema_mismatch() {
+    let dir = TempDir::new().unwrap();
+    let mut db = Database::create(&dir.path().join("test.db"), &test_key()).unwrap();
+    setup(&mut db);
+    db.execute_batch(
+        "CREATE TABLE wider (id BIGINT PRIMARY KEY, email VARCHAR(200) UNIQUE, body TEXT);
+         CREATE INDEX idx_wider_body ON wider (body);
+         CREATE TABLE fewer_indexes (id BIGINT PRIMARY KEY, email VARCHAR(100) UNIQUE, body TEXT);
+         CREATE TABLE reordered (id BIGINT PRIMARY KEY, body TEXT, email VARCHAR(100) UNIQUE);
+         CREATE INDEX idx_reordered_body ON reordered (body);",
+    )
+    .unwrap();
+
+    for (other, message) in [
+        (
+            "wider",
+            "column live.email is VARCHAR(100), expected VARCHAR(200)",
+        ),
+        ("fewer_indexes", "missing INDEX (body) on fewer_indexes"),
+        ("reordered", "column order differs"),
+        ("missing", "not found"),
+        ("live", "with itself"),
+    ] {
+        let err = db
+            .execute(&format!("ALTER TABLE live EXCHANGE WITH {}", other))
+            .unwrap_err();
+        assert!(matches!(err, MuroError::Schema(_)), "{}", err);
+        assert!(err.to_string().contains(message), "{}: {}", other, err);
+    }
+    assert_eq!(count(&mut db, "live"), 1);
+    assert_eq!(count(&mut db, "staging"), 2);
+}
+
+#[test]
+fn test_rolled_back_exchange_restores_mapping() {
+    let dir = TempDir::new().unwrap();
+    let path = dir.path().join("test.db");
+    let mut db = Database::create(&path, &test_key()).unwrap();
+    setup(&mut db);
+
+    db.execute("BEGIN").unwrap();
+    db.execute("ALTER TABLE live EXCHANGE WITH staging")
+        .unwrap();
+    assert_eq!(count(&mut db, "live"), 2);
+    db.execute("ROLLBACK").unwrap();
+    assert_eq!(count(&mut db, "live"), 1);
+    assert_eq!(count(&mut db, "staging"), 2);
+
+    // A failing script rolls the exchange back with the rest of it.
+    db.execute_batch(
+        "ALTER TABLE live EXCHANGE WITH staging;
+         INSERT INTO live VALUES (1, 'dup@example.com', 'dup');",
+    )
+    .unwrap_err();
+    drop(db);
+    let mut db = Database::open(&path, &test_key()).unwrap();
+    assert_eq!(count(&mut db, "live"), 1);
+    let rows = db.query("SELECT email FROM live").unwrap();
+    assert_eq!(
+        rows[0].get("email"),
+        Some(&Value::Varchar("old@example.com".into()))
+    );
+}
+
+#[test]
+fn test_concurrent_reader_never_sees_missing_table() {
+    let dir = TempDir::new().unwrap();
+    let path = dir.path().join("test.db");
+    let mut db = Database::create(&path, &test_key()).unwrap();
+    setup(&mut db);
+
+    let done = Arc::new(AtomicBool::new(false));
+    let reader_done = Arc::clone(&done);
+    let reader_path = path.clone();
+    let reader = thread::spawn(move || {
+        let mut reader = Database::open(&reader_path, &test_key()).unwrap();
+        let mut seen = Vec::new();
+        while !reader_done.load(Ordering::SeqCst) || seen.is_empty() {
+            seen.push(count(&mut reader, "live"));
+        }
+        seen
+    });
+    for _ in 0..50 {
+        db.execute("ALTER TABLE live EXCHANGE WITH staging")
+            .unwrap();
+    }
+    done.store(true, Ordering::SeqCst);
+    let seen = reader.join().unwrap();
+    assert!(seen.iter().all(|n| *n == 1 || *n == 2), "{:?}", seen);
+    assert_eq!(count(&mut db, "live"), 1);
+}
+
+#[test]
+fn test_exchange_refuses_foreign_keys() {
+    let dir = TempDir::new().unwrap();
+    let mut db = Database::create(&dir.path().join("test.db"), &test_key()).unwrap();
+    setup(&mut db);
+    db.execute(
+        "CREATE TABLE notes (id BIGINT PRIMARY KEY, live_id BIGINT,
+         FOREIGN KEY (live_id) REFERENCES live (id))",
+    )
+    .unwrap();
+    let err = db
+        .execute("ALTER TABLE live EXCHANGE WITH staging")
+        .unwrap_err();
+    assert!(
+        err.to_string().contains("table 'notes' references it"),
+        "{}",
+        err
+    );
+}
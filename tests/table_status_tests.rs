@@ -0,0 +1,260 @@
+#![cfg(feature = "test-utils")]
+/// Per-table row counts kept current by every write, reported by SHOW TABLE
+/// STATUS and used to answer an unfiltered `SELECT COUNT(*)` without a scan.
+use murodb::crypto::aead::MasterKey;
+use murodb::schema::catalog::SystemCatalog;
+use murodb::storage::pager::Pager;
+use murodb::{Database, Value};
+use std::path::Path;
+use tempfile::TempDir;
+
+fn test_key() -> MasterKey {
+    MasterKey::new([0x42u8; 32])
+}
+
+fn integer(value: Option<&Value>) -> i64 {
+    match value {
+        Some(Value::Integer(n)) => *n,
+        other => panic!("expected integer, got {:?}", other),
+    }
+}
+
+/// `(Rows, Rows_source)` of `table` from SHOW TABLE STATUS.
+fn status(db: &mut Database, table: &str) -> (i64, String) {
+    let rows = db.query("SHOW TABLE STATUS").unwrap();
+    let row = rows
+        .iter()
+        .find(|r| r.get("Name") == Some(&Value::Varchar(table.to_string())))
+        .unwrap_or_else(|| panic!("no status row for {}", table));
+    match row.get("Rows_source") {
+        Some(Value::Varchar(source)) => (integer(row.get("Rows")), source.clone()),
+        other => panic!("unexpected Rows_source {:?}", other),
+    }
+}
+
+fn count(db: &mut Database, table: &str) -> i64 {
+    let rows = db
+        .query(&format!("SELECT COUNT(*) FROM {}", table))
+        .unwrap();
+    integer(rows[0].get("COUNT(*)"))
+}
+
+/// The tracked count, the scanned count and SHOW TABLE STATUS agree.
+fn assert_rows(db: &mut Database, table: &str, expected: i64) {
+    let scanned = db
+        .query(&format!("SELECT COUNT(*) FROM {} WHERE 1 = 1", table))
+        .unwrap();
+    assert_eq!(integer(scanned[0].get("COUNT(*)")), expected, "{}", table);
+    assert_eq!(count(db, table), expected, "{}", table);
+    assert_eq!(
+        status(db, table),
+        (expected, "exact".to_string()),
+        "{}",
+        table
+    );
+}
+
+#[test]
+fn test_row_count_follows_every_write() {
+    let dir = TempDir::new().unwrap();
+    let path = dir.path().join("test.db");
+    let mut db = Database::create(&path, &test_key()).unwrap();
+    db.execute("CREATE TABLE p (id BIGINT PRIMARY KEY, code VARCHAR UNIQUE, n INT)")
+        .unwrap();
+    db.execute(
+        "CREATE TABLE c (id BIGINT PRIMARY KEY, p_id BIGINT, \
+         FOREIGN KEY (p_id) REFERENCES p(id) ON DELETE CASCADE)",
+    )
+    .unwrap();
+    assert_rows(&mut db, "p", 0);
+
+    // Bulk load into the empty table, then row by row.
+    db.execute("INSERT INTO p VALUES (1, 'a', 0), (2, 'b', 0), (3, 'c', 0)")
+        .unwrap();
+    db.execute("INSERT INTO p VALUES (4, 'd', 0)").unwrap();
+    assert_rows(&mut db, "p", 4);
+
+    // REPLACE removing two conflicting rows for one new row.
+    db.execute("REPLACE INTO p VALUES (1, 'b', 1)").unwrap();
+    assert_rows(&mut db, "p", 3);
+    db.execute("INSERT INTO p VALUES (3, 'x', 0) ON DUPLICATE KEY UPDATE n = n + 1")
+        .unwrap();
+    assert_rows(&mut db, "p", 3);
+    assert!(db.execute("INSERT INTO p VALUES (5, 'c', 0)").is_err());
+    assert_rows(&mut db, "p", 3);
+
+    db.execute("INSERT INTO c VALUES (10, 1), (11, 1), (12, 3)")
+        .unwrap();
+    db.execute("DELETE FROM p WHERE id = 1").unwrap();
+    assert_rows(&mut db, "p", 2);
+    assert_rows(&mut db, "c", 1);
+
+    // A rolled-back transaction leaves the counts as they were.
+    db.execute("BEGIN").unwrap();
+    db.execute("INSERT INTO p VALUES (6, 'f', 0)").unwrap();
+    assert_eq!(count(&mut db, "p"), 3);
+    db.execute("DELETE FROM c").unwrap();
+    assert_eq!(count(&mut db, "c"), 0);
+    db.execute("ROLLBACK").unwrap();
+    assert_rows(&mut db, "p", 2);
+    assert_rows(&mut db, "c", 1);
+
+    drop(db);
+    let mut db = Database::open(&path, &test_key()).unwrap();
+    assert_rows(&mut db, "p", 2);
+    assert_rows(&mut db, "c", 1);
+}
+
+#[test]
+fn test_self_referencing_cascade_counts_each_row_once() {
+    let dir = TempDir::new().unwrap();
+    let mut db = Database::create(&dir.path().join("test.db"), &test_key()).unwrap();
+    db.execute(
+        "CREATE TABLE node (id BIGINT PRIMARY KEY, parent BIGINT, \
+         FOREIGN KEY (parent) REFERENCES node(id) ON DELETE CASCADE)",
+    )
+    .unwrap();
+    db.execute("INSERT INTO node VALUES (1, NULL)").unwrap();
+    db.execute("INSERT INTO node VALUES (2, 1)").unwrap();
+    db.execute("INSERT INTO node VALUES (3, 2)").unwrap();
+    db.execute("INSERT INTO node VALUES (4, NULL)").unwrap();
+    db.execute("DELETE FROM node WHERE id = 1").unwrap();
+    assert_rows(&mut db, "node", 1);
+    db.execute("INSERT INTO node VALUES (5, 4)").unwrap();
+    db.execute("REPLACE INTO node VALUES (4, NULL)").unwrap();
+    assert_rows(&mut db, "node", 1);
+}
+
+#[test]
+fn test_show_table_status_reports_pages_and_indexes() {
+    let dir = TempDir::new().unwrap();
+    let mut db = Database::create(&dir.path().join("test.db"), &test_key()).unwrap();
+    db.execute("CREATE TABLE t (id BIGINT PRIMARY KEY, k BIGINT, body VARCHAR)")
+        .unwrap();
+    db.execute("CREATE INDEX idx_k ON t (k)").unwrap();
+    db.execute("CREATE TABLE empty (id BIGINT PRIMARY KEY)")
+        .unwrap();
+    let body = "b".repeat(500);
+    for i in 0..100 {
+        db.execute(&format!("INSERT INTO t VALUES ({}, {}, '{}')", i, i, body))
+            .unwrap();
+    }
+
+    // A row per table, plus one per index.
+    let rows = db.query("SHOW TABLE STATUS").unwrap();
+    assert_eq!(rows.len(), 3);
+    let t = rows
+        .iter()
+        .find(|r| r.get("Name") == Some(&Value::Varchar("t".into())))
+        .unwrap();
+    assert_eq!(t.get("Tree"), Some(&Value::Varchar("PRIMARY".into())));
+    assert_eq!(t.get("Engine"), Some(&Value::Varchar("MuroDB".into())));
+    assert_eq!(integer(t.get("Rows")), 100);
+    assert_eq!(integer(t.get("Indexes")), 1);
+    let data_pages = integer(t.get("Data_pages"));
+    assert!(data_pages > 10, "{}", data_pages);
+    assert!(integer(t.get("Index_pages")) >= 1);
+    assert_eq!(integer(t.get("Data_length")), data_pages * 4096);
+    assert_eq!(status(&mut db, "empty"), (0, "exact".to_string()));
+
+    // The table row carries its data tree's layout; the index row only its
+    // own tree's.
+    assert_eq!(integer(t.get("Pages")), data_pages);
+    assert!(matches!(t.get("Leaf_gap"), Some(Value::Float(_))));
+    let idx = rows
+        .iter()
+        .find(|r| r.get("Tree") == Some(&Value::Varchar("idx_k".into())))
+        .unwrap();
+    assert_eq!(idx.get("Name"), Some(&Value::Varchar("t".into())));
+    assert_eq!(integer(idx.get("Pages")), integer(t.get("Index_pages")));
+    assert_eq!(idx.get("Rows"), Some(&Value::Null));
+}
+
+#[test]
+fn test_count_star_reads_no_data_pages() {
+    let dir = TempDir::new().unwrap();
+    let path = dir.path().join("test.db");
+    {
+        let mut db = Database::create(&path, &test_key()).unwrap();
+        db.execute("CREATE TABLE t (id BIGINT PRIMARY KEY, body VARCHAR)")
+            .unwrap();
+        let body = "b".repeat(500);
+        for i in 0..500 {
+            db.execute(&format!("INSERT INTO t VALUES ({}, '{}')", i, body))
+                .unwrap();
+        }
+    }
+    let mut db = Database::open(&path, &test_key()).unwrap();
+    let decrypted = |db: &mut Database| -> i64 {
+        let rows = db.query("SHOW DATABASE STATS").unwrap();
+        let row = rows
+            .iter()
+            .find(|r| r.get("stat") == Some(&Value::Varchar("pager_pages_decrypted".into())))
+            .unwrap();
+        match row.get("value") {
+            Some(Value::Varchar(v)) => v.parse().unwrap(),
+            other => panic!("unexpected value {:?}", other),
+        }
+    };
+    let before = decrypted(&mut db);
+    assert_eq!(count(&mut db, "t"), 500);
+    assert!(decrypted(&mut db) - before < 10);
+
+    // Same columns as the scanning aggregation.
+    let rows = db.query("SELECT COUNT(*) AS n, count(*) FROM t").unwrap();
+    let scanned = db
+        .query("SELECT COUNT(*) AS n, count(*) FROM t WHERE 1 = 1")
+        .unwrap();
+    assert_eq!(rows[0].values, scanned[0].values);
+    assert_eq!(integer(rows[0].get("n")), 500);
+    assert!(db
+        .query("SELECT COUNT(*) FROM t LIMIT 0")
+        .unwrap()
+        .is_empty());
+    assert!(db
+        .query("SELECT COUNT(*) FROM t LIMIT 1 OFFSET 1")
+        .unwrap()
+        .is_empty());
+}
+
+/// Forget the tracked count of `table`, as in a database written before
+/// counts were tracked.
+fn drop_tracked_count(path: &Path, table: &str) {
+    let mut pager = Pager::open(path, &test_key()).unwrap();
+    let mut catalog = SystemCatalog::open(pager.catalog_root());
+    let mut def = catalog.get_table(&mut pager, table).unwrap().unwrap();
+    def.row_count = None;
+    catalog.update_table(&mut pager, &def).unwrap();
+    pager.set_catalog_root(catalog.root_page_id());
+    pager.flush_meta().unwrap();
+}
+
+#[test]
+fn test_legacy_tables_fall_back_to_analyze() {
+    let dir = TempDir::new().unwrap();
+    let path = dir.path().join("test.db");
+    {
+        let mut db = Database::create(&path, &test_key()).unwrap();
+        db.execute("CREATE TABLE t (id BIGINT PRIMARY KEY)")
+            .unwrap();
+        db.execute("INSERT INTO t VALUES (1), (2), (3)").unwrap();
+        db.execute("ANALYZE TABLE t").unwrap();
+        db.execute("CREATE TABLE u (id BIGINT PRIMARY KEY)")
+            .unwrap();
+    }
+    drop_tracked_count(&path, "t");
+    drop_tracked_count(&path, "u");
+
+    let mut db = Database::open(&path, &test_key()).unwrap();
+    assert_eq!(status(&mut db, "t"), (3, "analyze".to_string()));
+    assert_eq!(status(&mut db, "u"), (0, "unknown".to_string()));
+    // Untracked tables are counted by scanning, and writes keep no count.
+    db.execute("INSERT INTO t VALUES (4)").unwrap();
+    assert_eq!(count(&mut db, "t"), 4);
+    assert_eq!(status(&mut db, "t"), (3, "analyze".to_string()));
+
+    db.execute("ANALYZE TABLE t").unwrap();
+    assert_rows(&mut db, "t", 4);
+    db.execute("DELETE FROM t WHERE id = 2").unwrap();
+    assert_rows(&mut db, "t", 3);
+}
@@ -0,0 +1,295 @@
+#![cfg(feature = "test-utils")]
+/// `CREATE TEMPORARY TABLE`: session-only tables that shadow permanent ones,
+/// stay invisible to other handles, are dropped with the session, and are
+/// reclaimed on the next open when their session died without cleaning up.
+use murodb::crypto::aead::MasterKey;
+use murodb::error::MuroError;
+use murodb::sql::executor::ExecResult;
+use murodb::{Database, Value};
+use std::path::{Path, PathBuf};
+use tempfile::TempDir;
+
+fn test_key() -> MasterKey {
+    MasterKey::new([0x42u8; 32])
+}
+
+fn db_path(dir: &TempDir) -> PathBuf {
+    dir.path().join("test.db")
+}
+
+fn count(db: &mut Database, table: &str) -> i64 {
+    match db
+        .query(&format!("SELECT COUNT(*) FROM {}", table))
+        .unwrap()[0]
+        .values[0]
+        .1
+    {
+        Value::Integer(n) => n,
+        ref other => panic!("unexpected value {:?}", other),
+    }
+}
+
+fn show_tables(db: &mut Database) -> Vec<Value> {
+    db.query("SHOW TABLES")
+        .unwrap()
+        .into_iter()
+        .map(|row| row.values[0].1.clone())
+        .collect()
+}
+
+fn fill_temp(db: &mut Database, rows: i64) {
+    db.execute("CREATE TEMPORARY TABLE tmp (id BIGINT PRIMARY KEY, body VARCHAR UNIQUE)")
+        .unwrap();
+    db.execute("CREATE INDEX tmp_body ON tmp (body)").unwrap();
+    for i in 0..rows {
+        db.execute(&format!(
+            "INSERT INTO tmp VALUES ({}, '{}')",
+            i,
+            format!("row-{:04}-", i).repeat(20)
+        ))
+        .unwrap();
+    }
+}
+
+/// Integrity of the file, with no errors and no leaked pages.
+fn assert_clean(db: &mut Database) {
+    for row in db.verify_integrity().unwrap() {
+        assert_eq!(
+            row.get("status"),
+            Some(&Value::Varchar("ok".into())),
+            "{:?}",
+            row
+        );
+    }
+}
+
+fn temp_lock_files(path: &Path) -> Vec<PathBuf> {
+    std::fs::read_dir(path.parent().unwrap())
+        .unwrap()
+        .map(|e| e.unwrap().path())
+        .filter(|p| p.to_string_lossy().contains(".temp-"))
+        .collect()
+}
+
+#[test]
+fn test_dml_and_ddl_on_temporary_table() {
+    let dir = TempDir::new().unwrap();
+    let mut db = Database::create(&db_path(&dir), &test_key()).unwrap();
+    fill_temp(&mut db, 10);
+
+    db.execute("UPDATE tmp SET body = 'changed' WHERE id = 3")
+        .unwrap();
+    db.execute("DELETE FROM tmp WHERE id >= 8").unwrap();
+    assert!(matches!(
+        db.execute("INSERT INTO tmp VALUES (20, 'changed')"),
+        Err(MuroError::UniqueViolation(_))
+    ));
+    assert_eq!(count(&mut db, "tmp"), 8);
+    let rows = db
+        .query("SELECT id FROM tmp WHERE body = 'changed'")
+        .unwrap();
+    assert_eq!(rows[0].get("id"), Some(&Value::Integer(3)));
+
+    db.execute("ALTER TABLE tmp ADD COLUMN n BIGINT").unwrap();
+    db.execute("UPDATE tmp SET n = id * 2").unwrap();
+    assert_eq!(count(&mut db, "tmp WHERE n = 6"), 1);
+
+    // Not listed by SHOW TABLES, but described as temporary.
+    assert!(show_tables(&mut db).is_empty());
+    match db.execute("SHOW CREATE TABLE tmp").unwrap() {
+        ExecResult::Rows(rows) => match rows[0].values[1].1 {
+            Value::Varchar(ref sql) => {
+                assert!(sql.starts_with("CREATE TEMPORARY TABLE tmp"), "{}", sql)
+            }
+            ref other => panic!("unexpected value {:?}", other),
+        },
+        other => panic!("expected rows, got {:?}", other),
+    }
+    assert_eq!(
+        db.query("CHECK TABLE tmp").unwrap()[0].get("status"),
+        Some(&Value::Varchar("ok".into()))
+    );
+    assert_clean(&mut db);
+
+    db.execute("DROP TEMPORARY TABLE tmp").unwrap();
+    assert!(db.query("SELECT * FROM tmp").is_err());
+    assert!(db.execute("DROP TEMPORARY TABLE tmp").is_err());
+    db.execute("DROP TEMPORARY TABLE IF EXISTS tmp").unwrap();
+    assert_clean(&mut db);
+}
+
+#[test]
+fn test_temporary_table_shadows_permanent_table() {
+    let dir = TempDir::new().unwrap();
+    let mut db = Database::create(&db_path(&dir), &test_key()).unwrap();
+    db.execute("CREATE TABLE t (id BIGINT PRIMARY KEY, v VARCHAR UNIQUE)")
+        .unwrap();
+    db.execute("INSERT INTO t VALUES (1, 'permanent')").unwrap();
+
+    // Same name, and the same generated UNIQUE index name.
+    db.execute("CREATE TEMPORARY TABLE t (id BIGINT PRIMARY KEY, v VARCHAR UNIQUE)")
+        .unwrap();
+    assert_eq!(count(&mut db, "t"), 0);
+    db.execute("INSERT INTO t VALUES (1, 'temp'), (2, 'temp2')")
+        .unwrap();
+    assert_eq!(count(&mut db, "t"), 2);
+    assert!(db
+        .execute("CREATE TEMPORARY TABLE t (id BIGINT PRIMARY KEY)")
+        .is_err());
+    db.execute("CREATE TEMPORARY TABLE IF NOT EXISTS t (id BIGINT PRIMARY KEY)")
+        .unwrap();
+    // Within the session the name is taken by the temporary table.
+    assert!(db
+        .execute("CREATE TABLE t (id BIGINT PRIMARY KEY)")
+        .is_err());
+    assert_eq!(
+        show_tables(&mut db),
+        vec![Value::Varchar("t".into())],
+        "only the permanent table is listed"
+    );
+    assert_clean(&mut db);
+
+    // DROP TABLE takes the temporary table first.
+    db.execute("DROP TABLE t").unwrap();
+    let rows = db.query("SELECT v FROM t").unwrap();
+    assert_eq!(rows.len(), 1);
+    assert_eq!(rows[0].get("v"), Some(&Value::Varchar("permanent".into())));
+    assert!(db.execute("DROP TEMPORARY TABLE t").is_err());
+    assert_eq!(count(&mut db, "t"), 1);
+    assert_clean(&mut db);
+}
+
+#[test]
+fn test_temporary_table_is_invisible_to_other_handles() {
+    let dir = TempDir::new().unwrap();
+    let path = db_path(&dir);
+    let mut db = Database::create(&path, &test_key()).unwrap();
+    db.execute("CREATE TABLE t (id BIGINT PRIMARY KEY)")
+        .unwrap();
+    fill_temp(&mut db, 50);
+
+    // Opening another handle does not mistake the live session's tables
+    // for a crashed session's.
+    let mut other = Database::open(&path, &test_key()).unwrap();
+    assert!(other.query("SELECT * FROM tmp").is_err());
+    assert_eq!(show_tables(&mut other), vec![Value::Varchar("t".into())]);
+    other
+        .execute("CREATE TEMPORARY TABLE tmp (id BIGINT PRIMARY KEY)")
+        .unwrap();
+    other.execute("INSERT INTO tmp VALUES (-1)").unwrap();
+    assert_eq!(count(&mut other, "tmp"), 1);
+    assert_clean(&mut other);
+    drop(other);
+
+    assert_eq!(count(&mut db, "tmp"), 50);
+    let mut reader = db.open_reader().unwrap();
+    assert!(reader.query("SELECT * FROM tmp").is_err());
+}
+
+#[test]
+fn test_session_end_drops_temporary_tables() {
+    let dir = TempDir::new().unwrap();
+    let path = db_path(&dir);
+    let mut db = Database::create(&path, &test_key()).unwrap();
+    db.execute("CREATE TABLE t (id BIGINT PRIMARY KEY)")
+        .unwrap();
+    fill_temp(&mut db, 100);
+    assert_eq!(temp_lock_files(&path).len(), 1);
+    // An open transaction is rolled back before the tables are dropped.
+    db.execute("BEGIN").unwrap();
+    db.execute("INSERT INTO t VALUES (1)").unwrap();
+    drop(db);
+    assert!(temp_lock_files(&path).is_empty());
+
+    let mut db = Database::open(&path, &test_key()).unwrap();
+    assert!(db.query("SELECT * FROM tmp").is_err());
+    assert_eq!(count(&mut db, "t"), 0);
+    assert_clean(&mut db);
+}
+
+#[test]
+fn test_temporary_table_created_in_rolled_back_transaction() {
+    let dir = TempDir::new().unwrap();
+    let mut db = Database::create(&db_path(&dir), &test_key()).unwrap();
+    db.execute("BEGIN").unwrap();
+    db.execute("CREATE TEMPORARY TABLE tmp (id BIGINT PRIMARY KEY)")
+        .unwrap();
+    db.execute("INSERT INTO tmp VALUES (1)").unwrap();
+    db.execute("ROLLBACK").unwrap();
+    assert!(db.query("SELECT * FROM tmp").is_err());
+    assert_clean(&mut db);
+}
+
+#[test]
+fn test_temporary_tables_take_no_part_in_foreign_keys() {
+    let dir = TempDir::new().unwrap();
+    let mut db = Database::create(&db_path(&dir), &test_key()).unwrap();
+    db.execute("CREATE TABLE parent (id BIGINT PRIMARY KEY)")
+        .unwrap();
+    db.execute("CREATE TEMPORARY TABLE tparent (id BIGINT PRIMARY KEY)")
+        .unwrap();
+
+    let err = db
+        .execute(
+            "CREATE TEMPORARY TABLE c1 (id BIGINT PRIMARY KEY, p BIGINT, FOREIGN KEY (p) REFERENCES parent(id))",
+        )
+        .unwrap_err();
+    assert!(err.to_string().contains("FOREIGN KEY"), "{}", err);
+    assert!(db
+        .execute(
+            "CREATE TABLE c2 (id BIGINT PRIMARY KEY, p BIGINT, FOREIGN KEY (p) REFERENCES tparent(id))",
+        )
+        .is_err());
+    db.execute("CREATE TABLE c3 (id BIGINT PRIMARY KEY, p BIGINT)")
+        .unwrap();
+    assert!(db
+        .execute("ALTER TABLE c3 ADD FOREIGN KEY (p) REFERENCES tparent(id)")
+        .is_err());
+    assert!(db.query("SELECT * FROM c1").is_err());
+    assert!(db.query("SELECT * FROM c2").is_err());
+}
+
+#[test]
+fn test_crashed_session_temporary_tables_are_reclaimed_on_open() {
+    let dir = TempDir::new().unwrap();
+    let path = db_path(&dir);
+    let mut db = Database::create(&path, &test_key()).unwrap();
+    db.execute("CREATE TABLE t (id BIGINT PRIMARY KEY)")
+        .unwrap();
+    db.execute("INSERT INTO t VALUES (1)").unwrap();
+    fill_temp(&mut db, 200);
+
+    // A commit failure poisons the session, which then cannot drop its
+    // temporary tables: they stay committed, like after a crash.
+    let mut session = db.into_session();
+    session
+        .pager_mut()
+        .set_inject_write_page_failure(Some(std::io::ErrorKind::Other));
+    assert!(matches!(
+        session.execute("INSERT INTO tmp VALUES (1000, 'x')"),
+        Err(MuroError::CommitInDoubt(_))
+    ));
+    drop(session);
+    assert_eq!(temp_lock_files(&path).len(), 1);
+
+    let mut db = Database::open(&path, &test_key()).unwrap();
+    assert!(temp_lock_files(&path).is_empty());
+    assert!(db.query("SELECT * FROM tmp").is_err());
+    assert_eq!(count(&mut db, "t"), 1);
+    // The reclaimed pages are free again, not leaked.
+    assert_clean(&mut db);
+    let free = db
+        .verify_integrity()
+        .unwrap()
+        .into_iter()
+        .find(|row| row.get("object") == Some(&Value::Varchar("freelist".into())))
+        .and_then(|row| match row.get("detail") {
+            Some(Value::Varchar(detail)) => detail
+                .split_whitespace()
+                .next()
+                .and_then(|n| n.parse::<u64>().ok()),
+            _ => None,
+        })
+        .unwrap();
+    assert!(free > 10, "{} free pages", free);
+}
@@ -0,0 +1,233 @@
+#![cfg(feature = "test-utils")]
+/// `Database::transaction` commits when the closure returns `Ok` and rolls
+/// back on `Err` or panic; `transaction_with_retry` re-runs the closure on
+/// `Busy` and lock timeouts. Nested use fails instead of joining the open
+/// transaction.
+use murodb::concurrency::LockManager;
+use murodb::{Database, MuroError, RetryPolicy, Value};
+use std::cell::Cell;
+use std::time::Duration;
+use tempfile::TempDir;
+
+fn setup(dir: &TempDir) -> Database {
+    let mut db = Database::create_plaintext(&dir.path().join("tx.db")).unwrap();
+    db.execute("CREATE TABLE t (id BIGINT PRIMARY KEY, v VARCHAR(20))")
+        .unwrap();
+    db
+}
+
+fn ids(db: &mut Database) -> Vec<i64> {
+    db.query("SELECT id FROM t ORDER BY id")
+        .unwrap()
+        .iter()
+        .map(|r| match r.get_at(0) {
+            Some(Value::Integer(id)) => *id,
+            other => panic!("unexpected id {:?}", other),
+        })
+        .collect()
+}
+
+fn quick_retries(max_retries: u32) -> RetryPolicy {
+    RetryPolicy {
+        max_retries,
+        initial_backoff: Duration::from_millis(1),
+        max_backoff: Duration::from_millis(5),
+    }
+}
+
+#[test]
+fn test_transaction_commits_on_ok() {
+    let dir = TempDir::new().unwrap();
+    let mut db = setup(&dir);
+
+    let seen = db
+        .transaction(|tx| {
+            tx.execute("INSERT INTO t VALUES (1, 'a')")?;
+            tx.execute_params(
+                "INSERT INTO t VALUES (?, ?)",
+                &[Value::Integer(2), Value::Varchar("b".into())],
+            )?;
+            // Reads inside the closure see the transaction's own writes.
+            Ok(tx.query("SELECT * FROM t")?.len())
+        })
+        .unwrap();
+    assert_eq!(seen, 2);
+    assert_eq!(ids(&mut db), vec![1, 2]);
+}
+
+#[test]
+fn test_transaction_rolls_back_on_err() {
+    let dir = TempDir::new().unwrap();
+    let mut db = setup(&dir);
+    db.execute("INSERT INTO t VALUES (1, 'kept')").unwrap();
+
+    let err = db
+        .transaction(|tx| {
+            tx.execute("INSERT INTO t VALUES (2, 'gone')")?;
+            tx.execute("UPDATE t SET v = 'changed' WHERE id = 1")?;
+            // Duplicate key: the closure propagates the error with `?`.
+            tx.execute("INSERT INTO t VALUES (1, 'dup')")?;
+            Ok(())
+        })
+        .unwrap_err();
+    assert!(matches!(err, MuroError::UniqueViolation(_)), "{:?}", err);
+
+    assert_eq!(ids(&mut db), vec![1]);
+    let rows = db.query("SELECT v FROM t").unwrap();
+    assert_eq!(rows[0].get_at(0), Some(&Value::Varchar("kept".into())));
+    // The handle is back to autocommit.
+    db.execute("INSERT INTO t VALUES (3, 'c')").unwrap();
+    assert_eq!(ids(&mut db), vec![1, 3]);
+}
+
+#[test]
+fn test_transaction_rolls_back_on_panic_and_reraises() {
+    let dir = TempDir::new().unwrap();
+    let mut db = setup(&dir);
+
+    let panicked = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| {
+        let _: murodb::Result<()> = db.transaction(|tx| {
+            tx.execute("INSERT INTO t VALUES (1, 'a')")?;
+            panic!("boom");
+        });
+    }));
+    let payload = panicked.unwrap_err();
+    assert_eq!(payload.downcast_ref::<&str>(), Some(&"boom"));
+
+    assert!(ids(&mut db).is_empty());
+    db.transaction(|tx| tx.execute("INSERT INTO t VALUES (2, 'b')").map(|_| ()))
+        .unwrap();
+    assert_eq!(ids(&mut db), vec![2]);
+
+    // The rolled-back row never reached the file either.
+    drop(db);
+    let mut reopened = Database::open_plaintext(&dir.path().join("tx.db")).unwrap();
+    assert_eq!(ids(&mut reopened), vec![2]);
+}
+
+#[test]
+fn test_transaction_with_retry_reruns_closure_on_busy() {
+    let dir = TempDir::new().unwrap();
+    let mut db = setup(&dir);
+
+    let attempts = Cell::new(0);
+    let value = db
+        .transaction_with_retry(quick_retries(3), |tx| {
+            attempts.set(attempts.get() + 1);
+            tx.execute(&format!("INSERT INTO t VALUES ({}, 'try')", attempts.get()))?;
+            if attempts.get() < 3 {
+                return Err(MuroError::Busy("injected".into()));
+            }
+            Ok("done")
+        })
+        .unwrap();
+    assert_eq!(value, "done");
+    assert_eq!(attempts.get(), 3);
+    // Only the last attempt's insert survives.
+    assert_eq!(ids(&mut db), vec![3]);
+}
+
+#[test]
+fn test_transaction_with_retry_gives_up_after_max_retries() {
+    let dir = TempDir::new().unwrap();
+    let mut db = setup(&dir);
+
+    let attempts = Cell::new(0);
+    let err = db
+        .transaction_with_retry(quick_retries(2), |tx| {
+            attempts.set(attempts.get() + 1);
+            tx.execute("INSERT INTO t VALUES (1, 'a')")?;
+            Err::<(), _>(MuroError::Busy(format!("attempt {}", attempts.get())))
+        })
+        .unwrap_err();
+    assert!(
+        matches!(err, MuroError::Busy(ref m) if m == "attempt 3"),
+        "{:?}",
+        err
+    );
+    assert_eq!(attempts.get(), 3);
+    assert!(ids(&mut db).is_empty());
+
+    // Other errors are not retried.
+    attempts.set(0);
+    let err = db
+        .transaction_with_retry(quick_retries(5), |_| {
+            attempts.set(attempts.get() + 1);
+            Err::<(), _>(MuroError::Execution("no".into()))
+        })
+        .unwrap_err();
+    assert!(matches!(err, MuroError::Execution(_)));
+    assert_eq!(attempts.get(), 1);
+}
+
+#[test]
+fn test_transaction_with_retry_waits_out_lock_held_elsewhere() {
+    let dir = TempDir::new().unwrap();
+    let mut db = setup(&dir);
+    db.set_busy_timeout_ms(5);
+
+    // Another handle on the file holds the exclusive lock for a while.
+    let path = dir.path().join("tx.db");
+    let (held_tx, held_rx) = std::sync::mpsc::channel();
+    let holder = std::thread::spawn(move || {
+        let lock_manager = LockManager::new(&path).unwrap();
+        let _guard = lock_manager.write_lock().unwrap();
+        held_tx.send(()).unwrap();
+        std::thread::sleep(Duration::from_millis(100));
+    });
+    held_rx.recv().unwrap();
+
+    let attempts = Cell::new(0);
+    db.transaction_with_retry(
+        RetryPolicy {
+            max_retries: 100,
+            initial_backoff: Duration::from_millis(5),
+            max_backoff: Duration::from_millis(20),
+        },
+        |tx| {
+            attempts.set(attempts.get() + 1);
+            tx.execute("INSERT INTO t VALUES (1, 'a')").map(|_| ())
+        },
+    )
+    .unwrap();
+    holder.join().unwrap();
+    assert!(attempts.get() >= 1);
+    assert_eq!(ids(&mut db), vec![1]);
+}
+
+#[test]
+fn test_nested_transaction_is_rejected() {
+    let dir = TempDir::new().unwrap();
+    let mut db = setup(&dir);
+
+    // Transaction control inside the closure is refused...
+    let err = db
+        .transaction(|tx| {
+            tx.execute("INSERT INTO t VALUES (1, 'a')")?;
+            tx.execute("BEGIN")?;
+            Ok(())
+        })
+        .unwrap_err();
+    assert!(matches!(err, MuroError::Transaction(_)), "{:?}", err);
+    let err = db
+        .transaction(|tx| tx.execute("COMMIT").map(|_| ()))
+        .unwrap_err();
+    assert!(matches!(err, MuroError::Transaction(_)), "{:?}", err);
+    assert!(ids(&mut db).is_empty());
+
+    // ...and so is a closure transaction inside one opened by hand.
+    db.execute("BEGIN").unwrap();
+    db.execute("INSERT INTO t VALUES (2, 'b')").unwrap();
+    let ran = Cell::new(false);
+    let err = db
+        .transaction(|_| {
+            ran.set(true);
+            Ok(())
+        })
+        .unwrap_err();
+    assert!(matches!(err, MuroError::Transaction(_)), "{:?}", err);
+    assert!(!ran.get());
+    // The outer transaction is untouched.
+    db.execute("COMMIT").unwrap();
+    assert_eq!(ids(&mut db), vec![2]);
+}
@@ -333,3 +333,183 @@ fn test_wal_session_commit_rollback() {
         }
     }
 }
+
+fn query_int(session: &mut Session, sql: &str) -> i64 {
+    match session.execute(sql).unwrap() {
+        ExecResult::Rows(rows) => match rows[0].values[0].1 {
+            murodb::types::Value::Integer(n) => n,
+            ref other => panic!("Expected integer, got {:?}", other),
+        },
+        _ => panic!("Expected rows"),
+    }
+}
+
+fn rows_affected(session: &mut Session, sql: &str) -> u64 {
+    match session.execute(sql).unwrap() {
+        ExecResult::RowsAffected(n) => n,
+        _ => panic!("Expected RowsAffected"),
+    }
+}
+
+#[test]
+fn test_statement_reads_are_stable_within_transaction() {
+    let (mut session, _dir) = setup_session();
+
+    session
+        .execute("CREATE TABLE t (id BIGINT PRIMARY KEY, v BIGINT, pad VARCHAR)")
+        .unwrap();
+    for i in 0..300 {
+        session
+            .execute(&format!("INSERT INTO t VALUES ({}, {}, 'x')", i, i))
+            .unwrap();
+    }
+
+    session.execute("BEGIN").unwrap();
+
+    // Moving every row ahead of the scan position must touch each row once.
+    assert_eq!(
+        rows_affected(&mut session, "UPDATE t SET id = id + 1000"),
+        300
+    );
+    assert_eq!(query_int(&mut session, "SELECT COUNT(*) FROM t"), 300);
+    assert_eq!(query_int(&mut session, "SELECT MIN(id) FROM t"), 1000);
+
+    // Growing rows splits leaves behind and ahead of already-visited rows.
+    let pad = "p".repeat(400);
+    assert_eq!(
+        rows_affected(&mut session, &format!("UPDATE t SET pad = '{}'", pad)),
+        300
+    );
+    assert_eq!(rows_affected(&mut session, "UPDATE t SET v = v + 1"), 300);
+    assert_eq!(
+        query_int(&mut session, "SELECT SUM(v) FROM t"),
+        (0..300).map(|i| i + 1).sum::<i64>()
+    );
+    assert_eq!(
+        rows_affected(&mut session, "DELETE FROM t WHERE id >= 1100"),
+        200
+    );
+    assert_eq!(query_int(&mut session, "SELECT COUNT(*) FROM t"), 100);
+
+    session.execute("ROLLBACK").unwrap();
+    assert_eq!(query_int(&mut session, "SELECT COUNT(*) FROM t"), 300);
+    assert_eq!(query_int(&mut session, "SELECT MAX(id) FROM t"), 299);
+}
+
+fn alloc_state(session: &mut Session) -> (u64, usize) {
+    let pager = session.pager_mut();
+    (pager.page_count(), pager.freelist_mut().len())
+}
+
+#[test]
+fn test_rollback_of_ddl_and_dml_leaks_no_pages() {
+    let (mut session, _dir) = setup_session();
+    // Populate the freelist so the transaction allocates from it as well as
+    // from the end of the file.
+    session
+        .execute("CREATE TABLE scratch (id BIGINT PRIMARY KEY, body VARCHAR)")
+        .unwrap();
+    for i in 0..40 {
+        session
+            .execute(&format!(
+                "INSERT INTO scratch VALUES ({}, '{}')",
+                i,
+                "s".repeat(500)
+            ))
+            .unwrap();
+    }
+    session.execute("DROP TABLE scratch").unwrap();
+    let before = alloc_state(&mut session);
+    assert!(before.1 > 0, "expected a non-empty freelist");
+
+    session.execute("BEGIN").unwrap();
+    session
+        .execute("CREATE TABLE t (id BIGINT PRIMARY KEY, email VARCHAR UNIQUE, body TEXT)")
+        .unwrap();
+    session
+        .execute("CREATE FULLTEXT INDEX ft_body ON t(body) WITH PARSER ngram OPTIONS (n=2, normalize='nfkc')")
+        .unwrap();
+    for i in 0..100 {
+        session
+            .execute(&format!(
+                "INSERT INTO t VALUES ({}, 'u{}@example.com', '本文 {} hello')",
+                i, i, i
+            ))
+            .unwrap();
+    }
+    assert_eq!(count_rows(&mut session, "SELECT * FROM t"), 100);
+    session.execute("ROLLBACK").unwrap();
+
+    assert_eq!(alloc_state(&mut session), before);
+    assert!(session.execute("SELECT * FROM t").is_err());
+    match session.execute("SHOW TABLES").unwrap() {
+        ExecResult::Rows(rows) => assert!(rows.is_empty(), "unexpected tables: {:?}", rows),
+        _ => panic!("Expected rows"),
+    }
+
+    // The returned pages are reused by the next transaction.
+    session
+        .execute("CREATE TABLE t (id BIGINT PRIMARY KEY)")
+        .unwrap();
+    assert_eq!(alloc_state(&mut session).0, before.0);
+}
+
+#[test]
+fn test_committed_mixed_ddl_dml_visible_after_reopen() {
+    let dir = TempDir::new().unwrap();
+    let db_path = dir.path().join("test.db");
+    {
+        let mut db = murodb::Database::create(&db_path, &test_key()).unwrap();
+        db.execute("BEGIN").unwrap();
+        db.execute("CREATE TABLE t (id BIGINT PRIMARY KEY, name VARCHAR, body TEXT)")
+            .unwrap();
+        db.execute("CREATE INDEX idx_name ON t(name)").unwrap();
+        db.execute("CREATE FULLTEXT INDEX ft_body ON t(body) WITH PARSER ngram OPTIONS (n=2, normalize='nfkc')")
+            .unwrap();
+        db.execute("INSERT INTO t VALUES (1, 'alice', 'hello world')")
+            .unwrap();
+        db.execute("INSERT INTO t VALUES (2, 'bob', 'goodbye world')")
+            .unwrap();
+        db.execute("COMMIT").unwrap();
+    }
+
+    let mut db = murodb::Database::open(&db_path, &test_key()).unwrap();
+    assert_eq!(db.query("SELECT * FROM t").unwrap().len(), 2);
+    assert_eq!(
+        db.query("SELECT id FROM t WHERE name = 'bob'")
+            .unwrap()
+            .len(),
+        1
+    );
+    let rows = db
+        .query("SELECT id FROM t WHERE MATCH(body) AGAINST('hello' IN NATURAL LANGUAGE MODE) > 0")
+        .unwrap();
+    assert_eq!(rows.len(), 1);
+}
+
+#[test]
+fn test_drop_table_in_rolled_back_transaction_keeps_table() {
+    let (mut session, _dir) = setup_session();
+    session
+        .execute("CREATE TABLE t (id BIGINT PRIMARY KEY, name VARCHAR)")
+        .unwrap();
+    session.execute("CREATE INDEX idx_name ON t(name)").unwrap();
+    for i in 0..50 {
+        session
+            .execute(&format!("INSERT INTO t VALUES ({}, 'n{}')", i, i))
+            .unwrap();
+    }
+    let before = alloc_state(&mut session);
+
+    session.execute("BEGIN").unwrap();
+    session.execute("DROP TABLE t").unwrap();
+    assert!(session.execute("SELECT * FROM t").is_err());
+    session.execute("ROLLBACK").unwrap();
+
+    assert_eq!(alloc_state(&mut session), before);
+    assert_eq!(count_rows(&mut session, "SELECT * FROM t"), 50);
+    assert_eq!(
+        count_rows(&mut session, "SELECT * FROM t WHERE name = 'n7'"),
+        1
+    );
+}
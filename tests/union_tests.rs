@@ -264,3 +264,163 @@ fn test_union_column_names_from_first_select() {
         panic!("Expected Rows");
     }
 }
+
+#[test]
+fn test_union_order_by_position_and_first_branch_name() {
+    let (mut pager, mut catalog, _dir) = setup();
+    setup_tables(&mut pager, &mut catalog);
+
+    let rows = get_rows(
+        execute(
+            "SELECT id AS n, name FROM t1 UNION SELECT id, name FROM t2 ORDER BY 2 DESC, 1",
+            &mut pager,
+            &mut catalog,
+        )
+        .unwrap(),
+    );
+    let names: Vec<Value> = rows.into_iter().map(|mut r| r.remove(1)).collect();
+    assert_eq!(
+        names,
+        ["dave", "charlie", "bob", "alice"]
+            .map(|s| Value::Varchar(s.into()))
+            .to_vec()
+    );
+
+    let rows = get_rows(
+        execute(
+            "SELECT id AS n FROM t1 UNION ALL SELECT id FROM t2 ORDER BY N DESC LIMIT 1",
+            &mut pager,
+            &mut catalog,
+        )
+        .unwrap(),
+    );
+    assert_eq!(rows, vec![vec![Value::Integer(4)]]);
+
+    // Only first-branch names and in-range positions are accepted.
+    for sql in [
+        "SELECT id AS n FROM t1 UNION SELECT id FROM t2 ORDER BY id",
+        "SELECT id FROM t1 UNION SELECT id FROM t2 ORDER BY 2",
+        "SELECT id FROM t1 UNION SELECT id FROM t2 ORDER BY id + 1",
+    ] {
+        assert!(execute(sql, &mut pager, &mut catalog).is_err(), "{}", sql);
+    }
+}
+
+#[test]
+fn test_union_clauses_only_after_last_select() {
+    let (mut pager, mut catalog, _dir) = setup();
+    setup_tables(&mut pager, &mut catalog);
+
+    for sql in [
+        "SELECT id FROM t1 ORDER BY id UNION SELECT id FROM t2",
+        "SELECT id FROM t1 LIMIT 1 UNION SELECT id FROM t2",
+        "SELECT id FROM t1 UNION SELECT id FROM t2 LIMIT 1 UNION SELECT id FROM t2",
+    ] {
+        let err = execute(sql, &mut pager, &mut catalog).unwrap_err();
+        assert!(
+            err.to_string().contains("last SELECT of a UNION"),
+            "{}: {}",
+            sql,
+            err
+        );
+    }
+}
+
+#[test]
+fn test_union_distinct_and_mixed_chain() {
+    let (mut pager, mut catalog, _dir) = setup();
+    setup_tables(&mut pager, &mut catalog);
+
+    let rows = get_rows(
+        execute(
+            "SELECT id FROM t1 UNION DISTINCT SELECT id FROM t2",
+            &mut pager,
+            &mut catalog,
+        )
+        .unwrap(),
+    );
+    assert_eq!(rows.len(), 4);
+
+    // Operators apply left to right: the trailing UNION ALL keeps the
+    // duplicates it adds, while the UNION before it removed earlier ones.
+    let rows = get_rows(
+        execute(
+            "SELECT id FROM t1 UNION SELECT id FROM t2 UNION ALL SELECT id FROM t2 ORDER BY id",
+            &mut pager,
+            &mut catalog,
+        )
+        .unwrap(),
+    );
+    let ids: Vec<Value> = rows.into_iter().map(|mut r| r.remove(0)).collect();
+    assert_eq!(ids, [1, 2, 2, 3, 3, 4, 4].map(Value::Integer).to_vec());
+}
+
+#[test]
+fn test_union_coerces_mismatched_column_types() {
+    let (mut pager, mut catalog, _dir) = setup();
+    setup_tables(&mut pager, &mut catalog);
+
+    // INT and VARCHAR in one position become VARCHAR, so 2 and '2' are
+    // duplicates.
+    let rows = get_rows(
+        execute(
+            "SELECT id FROM t1 UNION SELECT '2' UNION SELECT 'x' ORDER BY 1",
+            &mut pager,
+            &mut catalog,
+        )
+        .unwrap(),
+    );
+    assert_eq!(
+        rows,
+        ["1", "2", "3", "x"]
+            .map(|s| vec![Value::Varchar(s.into())])
+            .to_vec()
+    );
+
+    // Integers mixed with a float become floats; NULL stays NULL.
+    let rows = get_rows(
+        execute(
+            "SELECT id FROM t1 WHERE id = 1 UNION ALL SELECT 2.5 UNION ALL SELECT NULL",
+            &mut pager,
+            &mut catalog,
+        )
+        .unwrap(),
+    );
+    assert_eq!(
+        rows,
+        vec![
+            vec![Value::Float(1.0)],
+            vec![Value::Float(2.5)],
+            vec![Value::Null]
+        ]
+    );
+
+    // Columns whose branches agree keep their type.
+    let rows = get_rows(
+        execute(
+            "SELECT id, name FROM t1 WHERE id = 1 UNION SELECT 7, 'z'",
+            &mut pager,
+            &mut catalog,
+        )
+        .unwrap(),
+    );
+    assert_eq!(rows[1], vec![Value::Integer(7), Value::Varchar("z".into())]);
+}
+
+#[test]
+fn test_union_column_names_when_first_branch_is_empty() {
+    let (mut pager, mut catalog, _dir) = setup();
+    setup_tables(&mut pager, &mut catalog);
+
+    let result = execute(
+        "SELECT id AS user_id, name FROM t1 WHERE id = 999 UNION SELECT id, name AS other FROM t2 ORDER BY user_id",
+        &mut pager,
+        &mut catalog,
+    )
+    .unwrap();
+    let ExecResult::Rows(rows) = result else {
+        panic!("Expected Rows");
+    };
+    assert_eq!(rows.len(), 3);
+    assert_eq!(rows[0].columns(), vec!["user_id", "name"]);
+}
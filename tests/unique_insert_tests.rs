@@ -0,0 +1,144 @@
+#![cfg(feature = "test-utils")]
+/// Multi-row INSERT into a table that already holds rows checks primary and
+/// unique keys repeated within the statement before writing, and finds
+/// conflicts with stored rows through the index inserts themselves.
+use murodb::crypto::aead::MasterKey;
+use murodb::error::MuroError;
+use murodb::sql::executor::ExecResult;
+use murodb::{Database, Value};
+use tempfile::TempDir;
+
+fn test_key() -> MasterKey {
+    MasterKey::new([0x42u8; 32])
+}
+
+/// `t` with three unique indexes, one of them composite, and one row.
+fn setup(dir: &TempDir) -> Database {
+    let mut db = Database::create(&dir.path().join("test.db"), &test_key()).unwrap();
+    db.execute(
+        "CREATE TABLE t (id BIGINT PRIMARY KEY, email VARCHAR, code BIGINT, a BIGINT, b BIGINT, note VARCHAR)",
+    )
+    .unwrap();
+    db.execute("CREATE UNIQUE INDEX t_email ON t (email)")
+        .unwrap();
+    db.execute("CREATE UNIQUE INDEX t_code ON t (code)")
+        .unwrap();
+    db.execute("CREATE UNIQUE INDEX t_ab ON t (a, b)").unwrap();
+    db.execute("CREATE INDEX t_note ON t (note)").unwrap();
+    db.execute("INSERT INTO t VALUES (1, 'one@x', 10, 1, 1, 'n')")
+        .unwrap();
+    db
+}
+
+fn count(db: &mut Database) -> i64 {
+    match db.query("SELECT COUNT(*) FROM t").unwrap()[0].values[0].1 {
+        Value::Integer(n) => n,
+        ref other => panic!("unexpected value {:?}", other),
+    }
+}
+
+fn assert_table_ok(db: &mut Database) {
+    match db.execute("CHECK TABLE t").unwrap() {
+        ExecResult::Rows(rows) => {
+            for row in rows {
+                assert_eq!(
+                    row.get("status"),
+                    Some(&Value::Varchar("ok".into())),
+                    "{:?}",
+                    row
+                );
+            }
+        }
+        other => panic!("expected rows, got {:?}", other),
+    }
+}
+
+fn unique_error(db: &mut Database, sql: &str) -> String {
+    match db.execute(sql) {
+        Err(MuroError::UniqueViolation(msg)) => msg,
+        other => panic!("expected a unique violation, got {:?}", other),
+    }
+}
+
+#[test]
+fn test_duplicates_within_statement_name_the_index() {
+    let dir = TempDir::new().unwrap();
+    let mut db = setup(&dir);
+
+    let msg = unique_error(
+        &mut db,
+        "INSERT INTO t VALUES (2, 'a@x', 20, 2, 1, 'n'), (3, 'b@x', 20, 3, 1, 'n')",
+    );
+    assert!(msg.contains("'code'"), "{}", msg);
+    let msg = unique_error(
+        &mut db,
+        "INSERT INTO t VALUES (2, 'a@x', 20, 2, 1, 'n'), (3, 'b@x', 30, 2, 1, 'n')",
+    );
+    assert!(msg.contains("'a, b'"), "{}", msg);
+    let msg = unique_error(
+        &mut db,
+        "INSERT INTO t VALUES (2, 'a@x', 20, 2, 1, 'n'), (2, 'b@x', 30, 3, 1, 'n')",
+    );
+    assert_eq!(msg, "Duplicate primary key");
+
+    assert_eq!(count(&mut db), 1);
+    assert_table_ok(&mut db);
+}
+
+#[test]
+fn test_conflict_with_stored_row_names_the_index() {
+    let dir = TempDir::new().unwrap();
+    let mut db = setup(&dir);
+
+    // The third unique index conflicts after the first two took the row.
+    let msg = unique_error(
+        &mut db,
+        "INSERT INTO t VALUES (2, 'a@x', 20, 2, 2, 'n'), (3, 'b@x', 30, 1, 1, 'n')",
+    );
+    assert!(msg.contains("'a, b'"), "{}", msg);
+    let msg = unique_error(&mut db, "INSERT INTO t VALUES (2, 'one@x', 20, 2, 2, 'n')");
+    assert!(msg.contains("'email'"), "{}", msg);
+    let msg = unique_error(&mut db, "INSERT INTO t VALUES (1, 'c@x', 40, 4, 4, 'n')");
+    assert_eq!(msg, "Duplicate primary key");
+
+    assert_eq!(count(&mut db), 1);
+    assert_table_ok(&mut db);
+    // The keys the failed statements took are free again.
+    db.execute("INSERT INTO t VALUES (2, 'a@x', 20, 2, 2, 'n'), (3, 'b@x', 30, 3, 3, 'n')")
+        .unwrap();
+    assert_eq!(count(&mut db), 3);
+    assert_table_ok(&mut db);
+}
+
+#[test]
+fn test_failed_statement_in_transaction_keeps_earlier_ones() {
+    let dir = TempDir::new().unwrap();
+    let mut db = setup(&dir);
+
+    db.execute("BEGIN").unwrap();
+    db.execute("INSERT INTO t VALUES (2, 'a@x', 20, 2, 2, 'n')")
+        .unwrap();
+    unique_error(
+        &mut db,
+        "INSERT INTO t VALUES (3, 'b@x', 30, 3, 3, 'n'), (4, 'c@x', 20, 4, 4, 'n')",
+    );
+    db.execute("COMMIT").unwrap();
+
+    assert_eq!(count(&mut db), 2);
+    assert_table_ok(&mut db);
+}
+
+#[test]
+fn test_null_unique_keys_do_not_conflict() {
+    let dir = TempDir::new().unwrap();
+    let mut db = setup(&dir);
+
+    db.execute(
+        "INSERT INTO t VALUES (2, NULL, NULL, 5, NULL, 'n'), (3, NULL, NULL, 5, NULL, 'n'), (4, NULL, 40, NULL, NULL, 'm')",
+    )
+    .unwrap();
+    assert_eq!(count(&mut db), 4);
+    let rows = db.query("SELECT id FROM t WHERE note = 'n'").unwrap();
+    assert_eq!(rows.len(), 3);
+    assert_table_ok(&mut db);
+}
@@ -0,0 +1,145 @@
+#![cfg(feature = "test-utils")]
+use murodb::sql::executor::ExecResult;
+use murodb::types::Value;
+use murodb::Database;
+use tempfile::TempDir;
+
+fn setup_db() -> (Database, TempDir) {
+    let dir = TempDir::new().unwrap();
+    let db_path = dir.path().join("limit.db");
+    let mut db = Database::create_plaintext(&db_path).unwrap();
+    db.execute("CREATE TABLE jobs (id BIGINT PRIMARY KEY, state VARCHAR, created INT, tag INT)")
+        .unwrap();
+    db.execute("CREATE INDEX idx_state ON jobs(state)").unwrap();
+    // created runs opposite to id so ORDER BY differs from primary-key order.
+    for id in 1..=10 {
+        let state = if id % 2 == 0 { "done" } else { "queued" };
+        db.execute(&format!(
+            "INSERT INTO jobs VALUES ({}, '{}', {}, 0)",
+            id,
+            state,
+            100 - id
+        ))
+        .unwrap();
+    }
+    (db, dir)
+}
+
+fn ids(db: &mut Database, sql: &str) -> Vec<i64> {
+    db.query(sql)
+        .unwrap()
+        .iter()
+        .map(|row| match row.get("id") {
+            Some(Value::Integer(id)) => *id,
+            other => panic!("unexpected id: {:?}", other),
+        })
+        .collect()
+}
+
+fn affected(result: ExecResult) -> u64 {
+    match result {
+        ExecResult::RowsAffected(n) => n,
+        other => panic!("expected RowsAffected, got {:?}", other),
+    }
+}
+
+#[test]
+fn test_delete_order_by_limit() {
+    let (mut db, _dir) = setup_db();
+    let n = affected(
+        db.execute("DELETE FROM jobs WHERE state = 'queued' ORDER BY created LIMIT 2")
+            .unwrap(),
+    );
+    assert_eq!(n, 2);
+    // Oldest queued jobs (smallest created) are the largest odd ids.
+    assert_eq!(
+        ids(
+            &mut db,
+            "SELECT id FROM jobs WHERE state = 'queued' ORDER BY id"
+        ),
+        vec![1, 3, 5]
+    );
+    assert_eq!(ids(&mut db, "SELECT id FROM jobs").len(), 8);
+}
+
+#[test]
+fn test_update_order_by_desc_limit() {
+    let (mut db, _dir) = setup_db();
+    let n = affected(
+        db.execute("UPDATE jobs SET tag = 1 ORDER BY created DESC, id LIMIT 3")
+            .unwrap(),
+    );
+    assert_eq!(n, 3);
+    assert_eq!(
+        ids(&mut db, "SELECT id FROM jobs WHERE tag = 1 ORDER BY id"),
+        vec![1, 2, 3]
+    );
+}
+
+#[test]
+fn test_limit_without_order_by_uses_primary_key_order() {
+    let (mut db, _dir) = setup_db();
+    // Full scan.
+    assert_eq!(
+        affected(db.execute("UPDATE jobs SET tag = 1 LIMIT 4").unwrap()),
+        4
+    );
+    assert_eq!(
+        ids(&mut db, "SELECT id FROM jobs WHERE tag = 1 ORDER BY id"),
+        vec![1, 2, 3, 4]
+    );
+    // Secondary index plan.
+    assert_eq!(
+        affected(
+            db.execute("DELETE FROM jobs WHERE state = 'done' LIMIT 2")
+                .unwrap()
+        ),
+        2
+    );
+    assert_eq!(
+        ids(
+            &mut db,
+            "SELECT id FROM jobs WHERE state = 'done' ORDER BY id"
+        ),
+        vec![6, 8, 10]
+    );
+}
+
+#[test]
+fn test_limit_zero_and_limit_larger_than_matches() {
+    let (mut db, _dir) = setup_db();
+    assert_eq!(affected(db.execute("DELETE FROM jobs LIMIT 0").unwrap()), 0);
+    assert_eq!(
+        affected(
+            db.execute("DELETE FROM jobs WHERE state = 'done' ORDER BY id LIMIT 100")
+                .unwrap()
+        ),
+        5
+    );
+    assert_eq!(ids(&mut db, "SELECT id FROM jobs").len(), 5);
+}
+
+#[test]
+fn test_negative_limit_is_rejected() {
+    let (mut db, _dir) = setup_db();
+    let err = db.execute("DELETE FROM jobs LIMIT -1").unwrap_err();
+    assert!(err.to_string().contains("LIMIT"), "{}", err);
+    assert!(db.execute("UPDATE jobs SET tag = 1 LIMIT -5").is_err());
+    assert_eq!(ids(&mut db, "SELECT id FROM jobs").len(), 10);
+}
+
+#[test]
+fn test_prepared_delete_with_order_by_limit() {
+    let (mut db, _dir) = setup_db();
+    let stmt = db
+        .prepare("DELETE FROM jobs WHERE created < ? ORDER BY created LIMIT 1")
+        .unwrap();
+    for _ in 0..2 {
+        let n = affected(db.execute_prepared(&stmt, &[Value::Integer(95)]).unwrap());
+        assert_eq!(n, 1);
+    }
+    assert_eq!(
+        ids(&mut db, "SELECT id FROM jobs ORDER BY id DESC LIMIT 2"),
+        vec![8, 7]
+    );
+}
@@ -191,7 +191,7 @@ fn test_replace_into_with_conflict() {
         &mut pager,
         &mut catalog,
     );
-    assert_eq!(n, 1);
+    assert_eq!(n, 2);
 
     let rows = query_rows("SELECT id, name, val FROM t", &mut pager, &mut catalog);
     assert_eq!(rows.len(), 1);
@@ -223,7 +223,8 @@ fn test_replace_into_multiple_rows() {
         &mut pager,
         &mut catalog,
     );
-    assert_eq!(n, 3);
+    // 2 per replaced row, 1 for the new one
+    assert_eq!(n, 5);
 
     let rows = query_rows(
         "SELECT id, name FROM t ORDER BY id",
@@ -268,7 +269,7 @@ fn test_replace_into_unique_index_conflict() {
         &mut pager,
         &mut catalog,
     );
-    assert_eq!(n, 1);
+    assert_eq!(n, 2);
 
     let rows = query_rows("SELECT id, email FROM t", &mut pager, &mut catalog);
     assert_eq!(rows.len(), 1);
@@ -276,6 +277,43 @@ fn test_replace_into_unique_index_conflict() {
     assert_eq!(rows[0][1], Value::Varchar("alice@example.com".to_string()));
 }
 
+#[test]
+fn test_replace_into_pk_and_unique_conflicts_count_each_deleted_row() {
+    let (mut pager, mut catalog, _dir) = setup();
+
+    execute(
+        "CREATE TABLE t (id BIGINT PRIMARY KEY, email VARCHAR)",
+        &mut pager,
+        &mut catalog,
+    )
+    .unwrap();
+    execute(
+        "CREATE UNIQUE INDEX idx_email ON t(email)",
+        &mut pager,
+        &mut catalog,
+    )
+    .unwrap();
+    execute(
+        "INSERT INTO t (id, email) VALUES (1, 'a@example.com'), (2, 'b@example.com')",
+        &mut pager,
+        &mut catalog,
+    )
+    .unwrap();
+
+    // Conflicts with row 1 on the PK and row 2 on email: both are deleted.
+    let n = affected_rows(
+        "REPLACE INTO t (id, email) VALUES (1, 'b@example.com')",
+        &mut pager,
+        &mut catalog,
+    );
+    assert_eq!(n, 3);
+
+    let rows = query_rows("SELECT id, email FROM t", &mut pager, &mut catalog);
+    assert_eq!(rows.len(), 1);
+    assert_eq!(rows[0][0], Value::Integer(1));
+    assert_eq!(rows[0][1], Value::Varchar("b@example.com".to_string()));
+}
+
 // ---- ON DUPLICATE KEY UPDATE with unique index conflict ----
 
 #[test]
@@ -410,3 +448,175 @@ fn test_on_duplicate_key_update_same_unique_value_ok() {
     assert_eq!(rows.len(), 1);
     assert_eq!(rows[0][2], Value::Varchar("Alicia".to_string()));
 }
+
+#[test]
+fn test_on_duplicate_key_update_values_reads_the_insert_row() {
+    let (mut pager, mut catalog, _dir) = setup();
+
+    execute(
+        "CREATE TABLE counters (id BIGINT PRIMARY KEY, cnt INT, label VARCHAR)",
+        &mut pager,
+        &mut catalog,
+    )
+    .unwrap();
+    execute(
+        "INSERT INTO counters VALUES (1, 10, 'a'), (2, 20, 'b')",
+        &mut pager,
+        &mut catalog,
+    )
+    .unwrap();
+
+    // One conflicting row per id, one new row: 2 + 2 + 1.
+    let n = affected_rows(
+        "INSERT INTO counters VALUES (1, 5, 'x'), (2, 7, 'y'), (3, 1, 'z') \
+         ON DUPLICATE KEY UPDATE cnt = cnt + VALUES(cnt), label = CONCAT(label, VALUES(label))",
+        &mut pager,
+        &mut catalog,
+    );
+    assert_eq!(n, 5);
+
+    let rows = query_rows(
+        "SELECT id, cnt, label FROM counters ORDER BY id",
+        &mut pager,
+        &mut catalog,
+    );
+    assert_eq!(
+        rows,
+        vec![
+            vec![
+                Value::Integer(1),
+                Value::Integer(15),
+                Value::Varchar("ax".into())
+            ],
+            vec![
+                Value::Integer(2),
+                Value::Integer(27),
+                Value::Varchar("by".into())
+            ],
+            vec![
+                Value::Integer(3),
+                Value::Integer(1),
+                Value::Varchar("z".into())
+            ],
+        ]
+    );
+
+    let err = execute("SELECT VALUES(cnt) FROM counters", &mut pager, &mut catalog).unwrap_err();
+    assert!(
+        err.to_string()
+            .contains("only allowed in ON DUPLICATE KEY UPDATE"),
+        "{}",
+        err
+    );
+}
+
+#[test]
+fn test_on_duplicate_key_update_unchanged_row_affects_nothing() {
+    let (mut pager, mut catalog, _dir) = setup();
+
+    execute(
+        "CREATE TABLE t (id BIGINT PRIMARY KEY, name VARCHAR)",
+        &mut pager,
+        &mut catalog,
+    )
+    .unwrap();
+    execute(
+        "INSERT INTO t VALUES (1, 'Alice')",
+        &mut pager,
+        &mut catalog,
+    )
+    .unwrap();
+
+    let n = affected_rows(
+        "INSERT INTO t VALUES (1, 'Alice') ON DUPLICATE KEY UPDATE name = VALUES(name)",
+        &mut pager,
+        &mut catalog,
+    );
+    assert_eq!(n, 0);
+}
+
+#[test]
+fn test_upsert_moves_secondary_index_entries_of_the_displaced_row() {
+    let (mut pager, mut catalog, _dir) = setup();
+
+    execute(
+        "CREATE TABLE t (id BIGINT PRIMARY KEY, email VARCHAR, city VARCHAR, bio TEXT)",
+        &mut pager,
+        &mut catalog,
+    )
+    .unwrap();
+    for sql in [
+        "CREATE UNIQUE INDEX idx_email ON t(email)",
+        "CREATE INDEX idx_city ON t(city)",
+        "CREATE FULLTEXT INDEX ft_bio ON t(bio) WITH PARSER ngram OPTIONS (n=2, normalize='nfkc')",
+        "INSERT INTO t VALUES (1, 'a@x', 'Tokyo', 'old text'), (2, 'b@x', 'Osaka', 'other')",
+    ] {
+        execute(sql, &mut pager, &mut catalog).unwrap();
+    }
+
+    // REPLACE hits row 1 through the unique email and replaces it with id 3.
+    let n = affected_rows(
+        "REPLACE INTO t VALUES (3, 'a@x', 'Kyoto', 'new text')",
+        &mut pager,
+        &mut catalog,
+    );
+    assert_eq!(n, 2);
+    // ON DUPLICATE KEY UPDATE moves row 2 to another city.
+    let n = affected_rows(
+        "INSERT INTO t VALUES (2, 'b@x', 'Nagoya', 'other') \
+         ON DUPLICATE KEY UPDATE city = VALUES(city)",
+        &mut pager,
+        &mut catalog,
+    );
+    assert_eq!(n, 2);
+
+    let ids = |sql: &str, pager: &mut Pager, catalog: &mut SystemCatalog| -> Vec<Value> {
+        query_rows(sql, pager, catalog)
+            .into_iter()
+            .map(|r| r[0].clone())
+            .collect()
+    };
+    assert_eq!(
+        ids(
+            "SELECT id FROM t WHERE email = 'a@x'",
+            &mut pager,
+            &mut catalog
+        ),
+        vec![Value::Integer(3)]
+    );
+    for city in ["Tokyo", "Osaka"] {
+        assert!(ids(
+            &format!("SELECT id FROM t WHERE city = '{}'", city),
+            &mut pager,
+            &mut catalog
+        )
+        .is_empty());
+    }
+    assert_eq!(
+        ids(
+            "SELECT id FROM t WHERE city = 'Nagoya'",
+            &mut pager,
+            &mut catalog
+        ),
+        vec![Value::Integer(2)]
+    );
+    assert!(ids(
+        "SELECT id FROM t WHERE MATCH(bio) AGAINST('old' IN NATURAL LANGUAGE MODE) > 0",
+        &mut pager,
+        &mut catalog
+    )
+    .is_empty());
+    assert_eq!(
+        ids(
+            "SELECT id FROM t WHERE MATCH(bio) AGAINST('new' IN NATURAL LANGUAGE MODE) > 0",
+            &mut pager,
+            &mut catalog
+        ),
+        vec![Value::Integer(3)]
+    );
+
+    let report = query_rows("CHECK TABLE t", &mut pager, &mut catalog);
+    for row in report {
+        assert_ne!(row[1], Value::Varchar("error".into()), "{:?}", row);
+    }
+}
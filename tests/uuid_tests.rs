@@ -139,9 +139,9 @@ fn test_uuid_v7_ordering() {
     let rows = get_rows(result);
     assert_eq!(rows.len(), 5);
 
-    for i in 0..5 {
+    for (i, row) in rows.iter().enumerate() {
         assert_eq!(
-            rows[i].get("seq"),
+            row.get("seq"),
             Some(&Value::Integer(i as i64)),
             "Row {} should have seq={}",
             i,
@@ -472,3 +472,235 @@ fn test_uuid_column_serialization_roundtrip() {
     assert_eq!(col2.data_type, DataType::Uuid);
     assert!(col2.is_primary_key);
 }
+
+#[test]
+fn test_uuid_blob_literal_input_and_pk_seek() {
+    let (mut pager, mut catalog, _dir) = setup();
+
+    execute(
+        "CREATE TABLE t (id UUID PRIMARY KEY, name VARCHAR)",
+        &mut pager,
+        &mut catalog,
+    )
+    .unwrap();
+    execute(
+        "INSERT INTO t VALUES (X'550e8400e29b41d4a716446655440000', 'alice')",
+        &mut pager,
+        &mut catalog,
+    )
+    .unwrap();
+    execute(
+        "INSERT INTO t VALUES ('6ba7b810-9dad-11d1-80b4-00c04fd430c8', 'bob')",
+        &mut pager,
+        &mut catalog,
+    )
+    .unwrap();
+
+    // Both input forms address the same 16-byte key.
+    for literal in [
+        "'550e8400-e29b-41d4-a716-446655440000'",
+        "'550E8400-E29B-41D4-A716-446655440000'",
+    ] {
+        let rows = get_rows(
+            execute(
+                &format!("SELECT name FROM t WHERE id = {}", literal),
+                &mut pager,
+                &mut catalog,
+            )
+            .unwrap(),
+        );
+        assert_eq!(rows.len(), 1, "{}", literal);
+        assert_eq!(
+            rows[0].get("name"),
+            Some(&Value::Varchar("alice".to_string()))
+        );
+    }
+
+    let rows = get_rows(
+        execute(
+            "SELECT id FROM t WHERE name = 'alice'",
+            &mut pager,
+            &mut catalog,
+        )
+        .unwrap(),
+    );
+    assert_eq!(
+        rows[0].get("id").unwrap().to_string(),
+        "550e8400-e29b-41d4-a716-446655440000"
+    );
+
+    // The PK is the raw bytes, so a duplicate in text form is rejected.
+    assert!(execute(
+        "INSERT INTO t VALUES ('550e8400-e29b-41d4-a716-446655440000', 'dup')",
+        &mut pager,
+        &mut catalog,
+    )
+    .is_err());
+}
+
+#[test]
+fn test_uuid_order_matches_byte_order() {
+    let (mut pager, mut catalog, _dir) = setup();
+
+    execute(
+        "CREATE TABLE t (id UUID PRIMARY KEY)",
+        &mut pager,
+        &mut catalog,
+    )
+    .unwrap();
+    let mut expected: Vec<[u8; 16]> = Vec::new();
+    for seed in [0x00u8, 0x7f, 0x80, 0xff, 0x09, 0xa0] {
+        let mut bytes = [seed; 16];
+        bytes[15] = seed.wrapping_add(1);
+        expected.push(bytes);
+        let hex: String = bytes.iter().map(|b| format!("{:02x}", b)).collect();
+        execute(
+            &format!("INSERT INTO t VALUES (X'{}')", hex),
+            &mut pager,
+            &mut catalog,
+        )
+        .unwrap();
+    }
+    expected.sort();
+
+    let rows = get_rows(execute("SELECT id FROM t ORDER BY id", &mut pager, &mut catalog).unwrap());
+    let actual: Vec<[u8; 16]> = rows
+        .iter()
+        .map(|r| match r.get("id") {
+            Some(Value::Uuid(b)) => *b,
+            other => panic!("expected UUID, got {:?}", other),
+        })
+        .collect();
+    assert_eq!(actual, expected);
+
+    // Full scans walk the PK B-tree, so they agree with ORDER BY.
+    let scanned = get_rows(execute("SELECT id FROM t", &mut pager, &mut catalog).unwrap());
+    let scanned: Vec<Option<&Value>> = scanned.iter().map(|r| r.get("id")).collect();
+    let ordered: Vec<Option<&Value>> = rows.iter().map(|r| r.get("id")).collect();
+    assert_eq!(scanned, ordered);
+}
+
+#[test]
+fn test_uuid_conversion_errors() {
+    let (mut pager, mut catalog, _dir) = setup();
+
+    execute(
+        "CREATE TABLE t (id UUID PRIMARY KEY)",
+        &mut pager,
+        &mut catalog,
+    )
+    .unwrap();
+    for bad in [
+        "X'550e8400'",
+        "X'550e8400e29b41d4a716446655440000ff'",
+        "'550e8400-e29b-41d4-a716-44665544000'",
+        "'550e8400-e29b-41d4-a716-44665544000g'",
+        "'+50e8400-e29b-41d4-a716-446655440000'",
+        "'550e8400-e29b-41d4-a716-4466554400é'",
+        "42",
+    ] {
+        assert!(
+            execute(
+                &format!("INSERT INTO t VALUES ({})", bad),
+                &mut pager,
+                &mut catalog,
+            )
+            .is_err(),
+            "{} should be rejected",
+            bad
+        );
+    }
+    let rows = get_rows(execute("SELECT id FROM t", &mut pager, &mut catalog).unwrap());
+    assert!(rows.is_empty());
+}
+
+#[test]
+fn test_binary_16_alias_round_trips_as_uuid() {
+    let (mut pager, mut catalog, _dir) = setup();
+
+    execute(
+        "CREATE TABLE t (id BINARY(16) PRIMARY KEY, v VARCHAR)",
+        &mut pager,
+        &mut catalog,
+    )
+    .unwrap();
+    let rows = get_rows(execute("DESCRIBE t", &mut pager, &mut catalog).unwrap());
+    assert_eq!(
+        rows[0].get("Type"),
+        Some(&Value::Varchar("UUID".to_string()))
+    );
+
+    let rows = get_rows(execute("SHOW CREATE TABLE t", &mut pager, &mut catalog).unwrap());
+    let ddl = rows[0].values[1].1.to_string();
+    assert!(ddl.contains("id UUID"), "{}", ddl);
+
+    // The dumped DDL recreates the same type.
+    let recreated = ddl.replacen("CREATE TABLE t", "CREATE TABLE t2", 1);
+    execute(&recreated, &mut pager, &mut catalog).unwrap();
+    let rows = get_rows(execute("DESCRIBE t2", &mut pager, &mut catalog).unwrap());
+    assert_eq!(
+        rows[0].get("Type"),
+        Some(&Value::Varchar("UUID".to_string()))
+    );
+
+    for bad in ["BINARY(8)", "BINARY"] {
+        assert!(execute(
+            &format!("CREATE TABLE bad (id {} PRIMARY KEY)", bad),
+            &mut pager,
+            &mut catalog,
+        )
+        .is_err());
+    }
+}
+
+#[test]
+fn test_uuid_storage_is_sixteen_bytes() {
+    use murodb::btree::ops::BTree;
+    use murodb::schema::column::ColumnDef;
+    use murodb::sql::executor::serialize_row;
+    use murodb::types::DataType;
+
+    let uuid = [0xabu8; 16];
+    let text = murodb::types::format_uuid(&uuid);
+    let as_uuid = serialize_row(
+        &[Value::Uuid(uuid)],
+        &[ColumnDef::new("id", DataType::Uuid)],
+    );
+    let as_varchar = serialize_row(
+        &[Value::Varchar(text.clone())],
+        &[ColumnDef::new("id", DataType::Varchar(Some(36)))],
+    );
+    // No length prefix: the UUID column is exactly 16 bytes more than an
+    // empty row, and 20+ bytes smaller than the VARCHAR(36) form.
+    let empty = serialize_row(&[Value::Null], &[ColumnDef::new("id", DataType::Uuid)]);
+    assert_eq!(as_uuid.len() - empty.len(), 16);
+    assert!(as_varchar.len() >= as_uuid.len() + 20);
+
+    // Primary keys are stored as the 16 raw bytes.
+    let (mut pager, mut catalog, _dir) = setup();
+    execute(
+        "CREATE TABLE t (id UUID PRIMARY KEY)",
+        &mut pager,
+        &mut catalog,
+    )
+    .unwrap();
+    execute(
+        &format!("INSERT INTO t VALUES ('{}')", text),
+        &mut pager,
+        &mut catalog,
+    )
+    .unwrap();
+    let root = catalog
+        .get_table(&mut pager, "t")
+        .unwrap()
+        .unwrap()
+        .data_btree_root;
+    let mut keys = Vec::new();
+    BTree::open(root)
+        .scan(&mut pager, |k, _| {
+            keys.push(k.to_vec());
+            Ok(true)
+        })
+        .unwrap();
+    assert_eq!(keys, vec![uuid.to_vec()]);
+}
@@ -0,0 +1,143 @@
+#![cfg(feature = "test-utils")]
+/// WAL archiving: checkpoints copy the WAL into numbered segments before
+/// truncating it, and a base backup plus the segments archived after it
+/// restore to the same table contents.
+use murodb::crypto::aead::MasterKey;
+use murodb::{Database, Value};
+use std::path::Path;
+use tempfile::TempDir;
+
+fn test_key() -> MasterKey {
+    MasterKey::new([0x42u8; 32])
+}
+
+fn contents(db: &mut Database, table: &str) -> Vec<Vec<(String, Value)>> {
+    db.query(&format!("SELECT * FROM {} ORDER BY id", table))
+        .unwrap()
+        .into_iter()
+        .map(|row| row.values)
+        .collect()
+}
+
+fn segment_files(dir: &Path) -> Vec<String> {
+    let mut names: Vec<String> = std::fs::read_dir(dir)
+        .unwrap()
+        .map(|e| e.unwrap().file_name().into_string().unwrap())
+        .filter(|n| !n.ends_with(".index"))
+        .collect();
+    names.sort();
+    names
+}
+
+#[test]
+fn test_restore_base_backup_with_archived_segments() {
+    let dir = TempDir::new().unwrap();
+    let path = dir.path().join("test.db");
+    let archive = dir.path().join("archive");
+    let base = dir.path().join("base.db");
+    let restored = dir.path().join("restored.db");
+
+    let mut db = Database::create(&path, &test_key()).unwrap();
+    db.set_wal_archive_dir(Some(&archive)).unwrap();
+    assert_eq!(db.wal_archive_dir(), Some(archive.as_path()));
+    db.execute("CREATE TABLE t (id BIGINT PRIMARY KEY, name VARCHAR, n INT)")
+        .unwrap();
+    for i in 0..20 {
+        db.execute(&format!("INSERT INTO t VALUES ({}, 'row{}', {})", i, i, i))
+            .unwrap();
+    }
+    db.backup(&base).unwrap();
+    let archived_before_backup = segment_files(&archive).len();
+    assert!(archived_before_backup > 0);
+
+    // Writes spanning several checkpoints after the base backup.
+    db.execute("UPDATE t SET n = n * 10 WHERE id < 5").unwrap();
+    db.execute("DELETE FROM t WHERE id >= 15").unwrap();
+    db.execute("CREATE TABLE u (id BIGINT PRIMARY KEY, t_id BIGINT)")
+        .unwrap();
+    db.execute("BEGIN").unwrap();
+    for i in 0..50 {
+        db.execute(&format!("INSERT INTO u VALUES ({}, {})", i, i % 15))
+            .unwrap();
+    }
+    db.execute("COMMIT").unwrap();
+    db.execute("ALTER TABLE t ADD COLUMN note VARCHAR DEFAULT 'x'")
+        .unwrap();
+    db.flush().unwrap();
+    let names = segment_files(&archive);
+    assert!(names.len() >= archived_before_backup + 5, "{:?}", names);
+    assert_eq!(names[0], "test.db.wal.000001");
+
+    let result = Database::restore_from_archive(&base, &archive, &restored, &test_key()).unwrap();
+    assert_eq!(result.segments_skipped.len(), archived_before_backup);
+    assert_eq!(
+        result.segments_replayed.len(),
+        names.len() - archived_before_backup
+    );
+
+    assert!(!dir.path().join("restored.db.wal").exists());
+
+    let mut copy = Database::open(&restored, &test_key()).unwrap();
+    assert_eq!(contents(&mut copy, "t"), contents(&mut db, "t"));
+    assert_eq!(contents(&mut copy, "u"), contents(&mut db, "u"));
+    copy.execute("INSERT INTO u VALUES (100, 1)").unwrap();
+    let check = copy.verify_integrity().unwrap();
+    assert!(check
+        .iter()
+        .all(|r| r.get("status") != Some(&Value::Varchar("error".into()))));
+}
+
+#[test]
+fn test_archiving_continues_numbering_and_stops_when_disabled() {
+    let dir = TempDir::new().unwrap();
+    let path = dir.path().join("test.db");
+    let archive = dir.path().join("archive");
+    {
+        let mut db = Database::create_plaintext(&path).unwrap();
+        db.set_wal_archive_dir(Some(&archive)).unwrap();
+        db.execute("CREATE TABLE t (id BIGINT PRIMARY KEY)")
+            .unwrap();
+        db.execute("INSERT INTO t VALUES (1)").unwrap();
+    }
+    let first = segment_files(&archive);
+    assert!(!first.is_empty());
+
+    let mut db = Database::open_plaintext(&path).unwrap();
+    assert_eq!(db.wal_archive_dir(), None);
+    db.execute("INSERT INTO t VALUES (2)").unwrap();
+    assert_eq!(segment_files(&archive), first);
+
+    db.set_wal_archive_dir(Some(&archive)).unwrap();
+    db.execute("INSERT INTO t VALUES (3)").unwrap();
+    let second = segment_files(&archive);
+    assert_eq!(second.len(), first.len() + 1);
+    assert_eq!(
+        second.last().unwrap(),
+        &format!("test.db.wal.{:06}", first.len() + 1)
+    );
+
+    db.set_wal_archive_dir(None).unwrap();
+    db.execute("INSERT INTO t VALUES (4)").unwrap();
+    assert_eq!(segment_files(&archive), second);
+}
+
+#[test]
+fn test_restore_refuses_existing_target_and_missing_index() {
+    let dir = TempDir::new().unwrap();
+    let path = dir.path().join("test.db");
+    let archive = dir.path().join("archive");
+    let base = dir.path().join("base.db");
+    let mut db = Database::create_plaintext(&path).unwrap();
+    db.backup(&base).unwrap();
+
+    std::fs::create_dir(&archive).unwrap();
+    let err = Database::restore_plaintext_from_archive(&base, &archive, &dir.path().join("r.db"))
+        .unwrap_err();
+    assert!(err.to_string().contains("no WAL archive index"), "{}", err);
+
+    db.set_wal_archive_dir(Some(&archive)).unwrap();
+    db.execute("CREATE TABLE t (id BIGINT PRIMARY KEY)")
+        .unwrap();
+    let err = Database::restore_plaintext_from_archive(&base, &archive, &path).unwrap_err();
+    assert!(err.to_string().contains("already exists"), "{}", err);
+}
@@ -0,0 +1,258 @@
+#![cfg(feature = "test-utils")]
+/// Recovery reports which transactions a damaged WAL cost: the ones replayed
+/// in full, the ones whose Commit was read but whose frames were not, where
+/// the first unreadable frame starts, and whether the log ends in a torn
+/// write or in corrupt bytes.
+use murodb::crypto::aead::MasterKey;
+use murodb::storage::page::Page;
+use murodb::storage::pager::Pager;
+use murodb::wal::reader::{UnreadableFrame, WalTail};
+use murodb::wal::record::WalRecord;
+use murodb::wal::recovery::{recover_with_mode, RecoveryMode};
+use murodb::wal::writer::WalWriter;
+use murodb::Database;
+use std::path::{Path, PathBuf};
+use tempfile::TempDir;
+
+const TXS: u64 = 3;
+/// Frames per transaction: Begin, PagePut, MetaUpdate, Commit.
+const FRAMES_PER_TX: usize = 4;
+
+fn test_key() -> MasterKey {
+    MasterKey::new([0x42u8; 32])
+}
+
+/// A database and a WAL of three committed transactions; transaction `n`
+/// writes page `n - 1`. Returns the offset of every frame, by LSN.
+fn setup(dir: &Path) -> (PathBuf, PathBuf, Vec<u64>) {
+    let db_path = dir.join("test.db");
+    let wal_path = dir.join("test.db.wal");
+    drop(Pager::create(&db_path, &test_key()).unwrap());
+
+    let mut writer = WalWriter::create(&wal_path, &test_key()).unwrap();
+    let mut offsets = Vec::new();
+    let mut append = |writer: &mut WalWriter, record: WalRecord| {
+        offsets.push(writer.file_size_bytes().unwrap());
+        writer.append(&record).unwrap()
+    };
+    for txid in 1..=TXS {
+        append(&mut writer, WalRecord::Begin { txid });
+        let mut page = Page::new(txid - 1);
+        page.insert_cell(format!("tx{}", txid).as_bytes()).unwrap();
+        append(
+            &mut writer,
+            WalRecord::PagePut {
+                txid,
+                page_id: txid - 1,
+                data: page.data.to_vec(),
+            },
+        );
+        append(
+            &mut writer,
+            WalRecord::MetaUpdate {
+                txid,
+                catalog_root: 0,
+                page_count: txid,
+                freelist_page_id: 0,
+                epoch: 0,
+            },
+        );
+        let lsn = writer.current_lsn();
+        append(&mut writer, WalRecord::Commit { txid, lsn });
+    }
+    writer.sync().unwrap();
+    (db_path, wal_path, offsets)
+}
+
+/// LSN of frame `frame` (0 = Begin) of transaction `txid`.
+fn lsn_of(txid: u64, frame: usize) -> usize {
+    (txid as usize - 1) * FRAMES_PER_TX + frame
+}
+
+fn edit_wal(wal_path: &Path, edit: impl FnOnce(&mut Vec<u8>)) {
+    let mut bytes = std::fs::read(wal_path).unwrap();
+    edit(&mut bytes);
+    std::fs::write(wal_path, bytes).unwrap();
+}
+
+fn page_cell(db_path: &Path, page_id: u64) -> Option<Vec<u8>> {
+    let mut pager = Pager::open(db_path, &test_key()).unwrap();
+    if page_id >= pager.page_count() {
+        return None;
+    }
+    let page = pager.read_page(page_id).ok()?;
+    page.cell(0).map(|c| c.to_vec())
+}
+
+#[test]
+fn test_clean_wal_report() {
+    let dir = TempDir::new().unwrap();
+    let (db_path, wal_path, _) = setup(dir.path());
+
+    let report = recover_with_mode(&db_path, &wal_path, &test_key(), RecoveryMode::Strict).unwrap();
+    assert_eq!(report.committed_txids, vec![1, 2, 3]);
+    assert!(report.incomplete_txids.is_empty());
+    assert!(report.unreadable_frames.is_empty());
+    assert_eq!(report.first_corruption_offset, None);
+    assert_eq!(report.wal_tail, WalTail::Clean);
+    assert!(!report.lost_committed_data());
+}
+
+#[test]
+fn test_flipped_page_frame_mid_log() {
+    let dir = TempDir::new().unwrap();
+    let (db_path, wal_path, offsets) = setup(dir.path());
+    let lsn = lsn_of(2, 1);
+    edit_wal(&wal_path, |bytes| bytes[offsets[lsn] as usize + 40] ^= 0x01);
+
+    // Strict recovery refuses to skip committed frames.
+    assert!(recover_with_mode(&db_path, &wal_path, &test_key(), RecoveryMode::Strict).is_err());
+
+    let report =
+        recover_with_mode(&db_path, &wal_path, &test_key(), RecoveryMode::Permissive).unwrap();
+    assert_eq!(report.committed_txids, vec![1, 3]);
+    assert_eq!(report.incomplete_txids, vec![2]);
+    assert!(report.skipped.is_empty());
+    assert_eq!(
+        report.unreadable_frames,
+        vec![UnreadableFrame {
+            lsn: lsn as u64,
+            offset: offsets[lsn],
+        }]
+    );
+    assert_eq!(report.first_corruption_offset, Some(offsets[lsn]));
+    assert_eq!(report.wal_tail, WalTail::Clean);
+    assert!(report.lost_committed_data());
+
+    assert_eq!(page_cell(&db_path, 0), Some(b"tx1".to_vec()));
+    assert_ne!(page_cell(&db_path, 1), Some(b"tx2".to_vec()));
+    assert_eq!(page_cell(&db_path, 2), Some(b"tx3".to_vec()));
+}
+
+#[test]
+fn test_flipped_begin_frame_makes_tx_incomplete() {
+    let dir = TempDir::new().unwrap();
+    let (db_path, wal_path, offsets) = setup(dir.path());
+    let lsn = lsn_of(2, 0);
+    edit_wal(&wal_path, |bytes| bytes[offsets[lsn] as usize + 6] ^= 0x80);
+
+    let report =
+        recover_with_mode(&db_path, &wal_path, &test_key(), RecoveryMode::Permissive).unwrap();
+    // Without its Begin, transaction 2 is reported as incomplete rather
+    // than as malformed.
+    assert_eq!(report.committed_txids, vec![1, 3]);
+    assert_eq!(report.incomplete_txids, vec![2]);
+    assert!(report.skipped.is_empty());
+    assert_eq!(report.first_corruption_offset, Some(offsets[lsn]));
+}
+
+#[test]
+fn test_truncation_mid_frame_is_torn_write() {
+    let dir = TempDir::new().unwrap();
+    let (db_path, wal_path, offsets) = setup(dir.path());
+    let lsn = lsn_of(3, 1);
+    edit_wal(&wal_path, |bytes| {
+        bytes.truncate(offsets[lsn] as usize + 100)
+    });
+
+    for mode in [RecoveryMode::Strict, RecoveryMode::Permissive] {
+        let report = recover_with_mode(&db_path, &wal_path, &test_key(), mode).unwrap();
+        assert_eq!(report.committed_txids, vec![1, 2]);
+        assert!(report.incomplete_txids.is_empty());
+        assert!(report.unreadable_frames.is_empty());
+        assert_eq!(report.first_corruption_offset, Some(offsets[lsn]));
+        assert_eq!(report.wal_tail, WalTail::TornWrite);
+        assert!(!report.lost_committed_data());
+    }
+
+    // A length field cut short is torn too.
+    edit_wal(&wal_path, |bytes| bytes.truncate(offsets[lsn] as usize + 2));
+    let report = recover_with_mode(&db_path, &wal_path, &test_key(), RecoveryMode::Strict).unwrap();
+    assert_eq!(report.wal_tail, WalTail::TornWrite);
+    assert_eq!(report.first_corruption_offset, Some(offsets[lsn]));
+}
+
+#[test]
+fn test_zero_filled_tail_is_torn_write() {
+    let dir = TempDir::new().unwrap();
+    let (db_path, wal_path, offsets) = setup(dir.path());
+    let lsn = lsn_of(3, 3);
+    // The Commit frame's length made it to disk, its contents did not.
+    edit_wal(&wal_path, |bytes| {
+        for b in &mut bytes[offsets[lsn] as usize + 4..] {
+            *b = 0;
+        }
+    });
+
+    let report = recover_with_mode(&db_path, &wal_path, &test_key(), RecoveryMode::Strict).unwrap();
+    assert_eq!(report.committed_txids, vec![1, 2]);
+    assert!(report.incomplete_txids.is_empty());
+    assert_eq!(report.first_corruption_offset, Some(offsets[lsn]));
+    assert_eq!(report.wal_tail, WalTail::TornWrite);
+}
+
+#[test]
+fn test_flipped_last_frame_is_corrupt_tail() {
+    let dir = TempDir::new().unwrap();
+    let (db_path, wal_path, offsets) = setup(dir.path());
+    let lsn = lsn_of(3, 3);
+    edit_wal(&wal_path, |bytes| bytes[offsets[lsn] as usize + 10] ^= 0x10);
+
+    let report = recover_with_mode(&db_path, &wal_path, &test_key(), RecoveryMode::Strict).unwrap();
+    // The Commit record was never read, so transaction 3 is uncommitted.
+    assert_eq!(report.committed_txids, vec![1, 2]);
+    assert!(report.incomplete_txids.is_empty());
+    assert_eq!(report.first_corruption_offset, Some(offsets[lsn]));
+    assert_eq!(report.wal_tail, WalTail::Corrupt);
+    assert_eq!(page_cell(&db_path, 2), None);
+}
+
+#[test]
+fn test_permissive_open_quarantines_wal_with_unreadable_frames() {
+    let dir = TempDir::new().unwrap();
+    let (db_path, wal_path, offsets) = setup(dir.path());
+    let lsn = lsn_of(2, 2);
+    edit_wal(&wal_path, |bytes| bytes[offsets[lsn] as usize + 20] ^= 0xFF);
+
+    assert!(Database::open(&db_path, &test_key()).is_err());
+    let (_db, report) = Database::open_with_recovery_mode_and_report(
+        &db_path,
+        &test_key(),
+        RecoveryMode::Permissive,
+    )
+    .unwrap();
+    let report = report.expect("expected recovery report");
+    assert_eq!(report.committed_txids, vec![1, 3]);
+    assert_eq!(report.incomplete_txids, vec![2]);
+    let quarantine = PathBuf::from(report.wal_quarantine_path.expect("WAL quarantined"));
+    assert!(quarantine.exists());
+    assert_eq!(
+        std::fs::metadata(&wal_path).unwrap().len(),
+        murodb::wal::WAL_HEADER_SIZE as u64
+    );
+}
+
+#[test]
+fn test_version_2_wal_without_frame_crc_is_read() {
+    let dir = TempDir::new().unwrap();
+    let (db_path, wal_path, _) = setup(dir.path());
+    // Rewrite as version 2: same header fields, frames without the CRC.
+    edit_wal(&wal_path, |bytes| {
+        let header = murodb::wal::WAL_HEADER_SIZE;
+        let mut v2 = bytes[..header].to_vec();
+        v2[8..12].copy_from_slice(&2u32.to_le_bytes());
+        let mut frames = &bytes[header..];
+        while !frames.is_empty() {
+            let len = u32::from_le_bytes(frames[..4].try_into().unwrap()) as usize;
+            v2.extend_from_slice(&(len as u32 - 4).to_le_bytes());
+            v2.extend_from_slice(&frames[4..len]);
+            frames = &frames[4 + len..];
+        }
+        *bytes = v2;
+    });
+
+    let report = recover_with_mode(&db_path, &wal_path, &test_key(), RecoveryMode::Strict).unwrap();
+    assert_eq!(report.committed_txids, vec![1, 2, 3]);
+    assert_eq!(report.wal_tail, WalTail::Clean);
+    assert_eq!(page_cell(&db_path, 1), Some(b"tx2".to_vec()));
+}
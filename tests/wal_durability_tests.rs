@@ -0,0 +1,210 @@
+#![cfg(feature = "test-utils")]
+/// WAL durability modes: group commit and async fsync.
+use murodb::crypto::aead::MasterKey;
+use murodb::sql::session::RuntimeConfig;
+use murodb::types::Value;
+use murodb::{Database, OpenOptions, WalDurability};
+use std::path::Path;
+use std::process::Command;
+use tempfile::TempDir;
+
+const CHILD_ENV: &str = "MURODB_DURABILITY_CHILD_DB";
+
+fn test_key() -> MasterKey {
+    MasterKey::new([0x42u8; 32])
+}
+
+fn open_with(path: &Path, durability: WalDurability) -> Database {
+    Database::open_with_options(
+        path,
+        &test_key(),
+        OpenOptions {
+            wal_durability: durability,
+            ..OpenOptions::default()
+        },
+    )
+    .unwrap()
+}
+
+fn create_table(path: &Path) {
+    let mut db = Database::create(path, &test_key()).unwrap();
+    db.execute("CREATE TABLE t (id BIGINT PRIMARY KEY, v VARCHAR)")
+        .unwrap();
+}
+
+fn ids(db: &mut Database) -> Vec<i64> {
+    db.query("SELECT id FROM t ORDER BY id")
+        .unwrap()
+        .iter()
+        .map(|row| match row.get("id") {
+            Some(Value::Integer(id)) => *id,
+            other => panic!("unexpected id {:?}", other),
+        })
+        .collect()
+}
+
+fn assert_integrity(db: &mut Database) {
+    for row in db.verify_integrity().unwrap() {
+        assert_eq!(
+            row.get("status"),
+            Some(&Value::Varchar("ok".into())),
+            "{:?}",
+            row
+        );
+    }
+}
+
+fn wal_path(db_path: &Path) -> std::path::PathBuf {
+    let mut s = db_path.as_os_str().to_os_string();
+    s.push(".wal");
+    s.into()
+}
+
+#[test]
+fn test_group_commit_defers_data_file_writes_until_batch_closes() {
+    let dir = TempDir::new().unwrap();
+    let db_path = dir.path().join("test.db");
+    create_table(&db_path);
+
+    let mut db = open_with(
+        &db_path,
+        WalDurability::GroupCommit {
+            max_delay_ms: 60_000,
+            max_batch: 4,
+        },
+    );
+    let data_file = || std::fs::read(&db_path).unwrap();
+    let before = data_file();
+    for i in 1..=3 {
+        db.execute(&format!("INSERT INTO t VALUES ({}, 'x')", i))
+            .unwrap();
+    }
+    // The open batch is visible to this handle but not yet in the data file.
+    assert_eq!(ids(&mut db), vec![1, 2, 3]);
+    assert!(data_file() == before);
+    assert!(std::fs::metadata(wal_path(&db_path)).unwrap().len() > 16);
+
+    // The fourth commit closes the batch: one fsync, then the data file and
+    // the WAL checkpoint catch up.
+    db.execute("INSERT INTO t VALUES (4, 'x')").unwrap();
+    assert!(data_file() != before);
+    drop(db);
+
+    let mut db = Database::open(&db_path, &test_key()).unwrap();
+    assert_eq!(ids(&mut db), vec![1, 2, 3, 4]);
+    assert_integrity(&mut db);
+}
+
+#[test]
+fn test_group_commit_flushes_expired_batch_on_next_statement() {
+    let dir = TempDir::new().unwrap();
+    let db_path = dir.path().join("test.db");
+    create_table(&db_path);
+
+    let mut db = open_with(
+        &db_path,
+        WalDurability::GroupCommit {
+            max_delay_ms: 20,
+            max_batch: 1000,
+        },
+    );
+    let before = std::fs::read(&db_path).unwrap();
+    db.execute("INSERT INTO t VALUES (1, 'x')").unwrap();
+    assert!(std::fs::read(&db_path).unwrap() == before);
+    std::thread::sleep(std::time::Duration::from_millis(40));
+    assert_eq!(ids(&mut db), vec![1]);
+    assert!(std::fs::read(&db_path).unwrap() != before);
+}
+
+#[test]
+fn test_async_syncs_only_at_checkpoint_or_flush() {
+    let dir = TempDir::new().unwrap();
+    let db_path = dir.path().join("test.db");
+    create_table(&db_path);
+
+    let mut db = open_with(&db_path, WalDurability::Async);
+    assert_eq!(db.wal_durability(), WalDurability::Async);
+    db.set_runtime_config(RuntimeConfig {
+        checkpoint_tx_threshold: 0,
+        checkpoint_wal_bytes_threshold: 0,
+        checkpoint_interval_ms: 0,
+    })
+    .unwrap();
+    let before = std::fs::read(&db_path).unwrap();
+    db.execute("BEGIN").unwrap();
+    assert!(db.set_wal_durability(WalDurability::Full).is_err());
+    db.execute("INSERT INTO t VALUES (1, 'x')").unwrap();
+    db.execute("COMMIT").unwrap();
+    for i in 2..=20 {
+        db.execute(&format!("INSERT INTO t VALUES ({}, 'x')", i))
+            .unwrap();
+    }
+    assert!(std::fs::read(&db_path).unwrap() == before);
+
+    // Switching back to Full syncs what is pending.
+    db.set_wal_durability(WalDurability::Full).unwrap();
+    assert!(std::fs::read(&db_path).unwrap() != before);
+    db.flush().unwrap();
+    drop(db);
+
+    let mut db = Database::open(&db_path, &test_key()).unwrap();
+    assert_eq!(ids(&mut db), (1..=20).collect::<Vec<_>>());
+    assert_integrity(&mut db);
+}
+
+fn run_child_until_abort(db_path: &Path) {
+    let mut db = open_with(
+        db_path,
+        WalDurability::GroupCommit {
+            max_delay_ms: 60_000,
+            max_batch: 4,
+        },
+    );
+    // Batches close at ids 4 and 8; 9 and 10 are still pending.
+    for i in 1..=10 {
+        db.execute(&format!(
+            "INSERT INTO t VALUES ({}, '{}')",
+            i,
+            "v".repeat(50)
+        ))
+        .unwrap();
+    }
+    // No destructors, no final flush: like a crash.
+    std::process::abort();
+}
+
+#[test]
+fn test_killed_mid_batch_keeps_earlier_commits() {
+    if let Ok(path) = std::env::var(CHILD_ENV) {
+        run_child_until_abort(Path::new(&path));
+    }
+
+    let dir = TempDir::new().unwrap();
+    let db_path = dir.path().join("test.db");
+    create_table(&db_path);
+
+    let status = Command::new(std::env::current_exe().unwrap())
+        .args([
+            "test_killed_mid_batch_keeps_earlier_commits",
+            "--exact",
+            "--nocapture",
+        ])
+        .env(CHILD_ENV, &db_path)
+        .status()
+        .unwrap();
+    assert!(!status.success(), "child should have aborted");
+
+    // The last batch never reached the data file; only the WAL has it.
+    // Tear its tail like a power cut in the middle of the last record.
+    let wal = wal_path(&db_path);
+    let wal_len = std::fs::metadata(&wal).unwrap().len();
+    let file = std::fs::OpenOptions::new().write(true).open(&wal).unwrap();
+    file.set_len(wal_len - 7).unwrap();
+    drop(file);
+
+    let mut db = Database::open(&db_path, &test_key()).unwrap();
+    let recovered = ids(&mut db);
+    assert_eq!(recovered, (1..=9).collect::<Vec<_>>());
+    assert_integrity(&mut db);
+    db.execute("INSERT INTO t VALUES (100, 'after')").unwrap();
+}
@@ -0,0 +1,211 @@
+#![cfg(feature = "test-utils")]
+/// Recovery refuses a WAL written for another database or configuration
+/// before replaying anything, in strict and permissive mode alike.
+use murodb::crypto::aead::MasterKey;
+use murodb::error::MuroError;
+use murodb::{Database, RecoveryMode, Value};
+use std::path::{Path, PathBuf};
+use tempfile::TempDir;
+
+fn test_key() -> MasterKey {
+    MasterKey::new([0x42u8; 32])
+}
+
+fn wal_of(db_path: &Path) -> PathBuf {
+    PathBuf::from(format!("{}.wal", db_path.display()))
+}
+
+/// Leave a committed INSERT in the WAL but not in the data file, as a crash
+/// right after the WAL fsync would.
+fn crash_with_committed_wal(mut db: Database) {
+    db.execute("CREATE TABLE t (id BIGINT PRIMARY KEY)")
+        .unwrap();
+    let mut session = db.into_session();
+    session
+        .pager_mut()
+        .set_inject_write_page_failure(Some(std::io::ErrorKind::Other));
+    assert!(matches!(
+        session.execute("INSERT INTO t VALUES (1)"),
+        Err(MuroError::CommitInDoubt(_))
+    ));
+}
+
+fn count(db: &mut Database) -> i64 {
+    match db.query("SELECT COUNT(*) FROM t").unwrap()[0].values[0].1 {
+        Value::Integer(n) => n,
+        ref other => panic!("unexpected value {:?}", other),
+    }
+}
+
+fn expect_mismatch(result: murodb::error::Result<Database>, want_field: &str) {
+    match result {
+        Err(MuroError::WalMismatch { field, .. }) => assert_eq!(field, want_field),
+        Err(other) => panic!("expected WalMismatch on {}, got {}", want_field, other),
+        Ok(_) => panic!("expected WalMismatch on {}, open succeeded", want_field),
+    }
+}
+
+/// Rewrite bytes of the WAL header in place.
+fn patch_wal(db_path: &Path, range: std::ops::Range<usize>, bytes: &[u8]) {
+    let mut wal = std::fs::read(wal_of(db_path)).unwrap();
+    wal[range].copy_from_slice(bytes);
+    std::fs::write(wal_of(db_path), wal).unwrap();
+}
+
+#[test]
+fn test_matching_wal_is_replayed() {
+    let dir = TempDir::new().unwrap();
+    let db_path = dir.path().join("a.db");
+    crash_with_committed_wal(Database::create(&db_path, &test_key()).unwrap());
+
+    let mut db = Database::open(&db_path, &test_key()).unwrap();
+    assert_eq!(count(&mut db), 1);
+}
+
+#[test]
+fn test_wal_of_another_database_refused_in_every_mode() {
+    let dir = TempDir::new().unwrap();
+    let a = dir.path().join("a.db");
+    let b = dir.path().join("b.db");
+    crash_with_committed_wal(Database::create(&a, &test_key()).unwrap());
+    let mut db = Database::create(&b, &test_key()).unwrap();
+    db.execute("CREATE TABLE t (id BIGINT PRIMARY KEY)")
+        .unwrap();
+    drop(db);
+    std::fs::copy(wal_of(&a), wal_of(&b)).unwrap();
+    let foreign_wal = std::fs::read(wal_of(&b)).unwrap();
+
+    expect_mismatch(Database::open(&b, &test_key()), "instance_id");
+    expect_mismatch(
+        Database::open_with_recovery_mode(&b, &test_key(), RecoveryMode::Permissive),
+        "instance_id",
+    );
+    // Neither mode truncated or quarantined the WAL.
+    assert_eq!(std::fs::read(wal_of(&b)).unwrap(), foreign_wal);
+
+    let err = Database::open(&b, &test_key()).err().unwrap().to_string();
+    assert!(err.contains("move the WAL file aside"), "{}", err);
+
+    // Moving it aside as advised opens the database untouched.
+    std::fs::rename(wal_of(&b), dir.path().join("a.wal.saved")).unwrap();
+    let mut db = Database::open(&b, &test_key()).unwrap();
+    assert_eq!(count(&mut db), 0);
+}
+
+#[test]
+fn test_page_size_mismatch_refused() {
+    let dir = TempDir::new().unwrap();
+    let db_path = dir.path().join("a.db");
+    crash_with_committed_wal(Database::create(&db_path, &test_key()).unwrap());
+    patch_wal(&db_path, 28..32, &8192u32.to_le_bytes());
+
+    match Database::open(&db_path, &test_key()) {
+        Err(MuroError::WalMismatch {
+            field,
+            wal_value,
+            db_value,
+        }) => {
+            assert_eq!(field, "page_size");
+            assert_eq!(wal_value, "8192");
+            assert_eq!(db_value, "4096");
+        }
+        other => panic!("expected WalMismatch, got {:?}", other.err()),
+    }
+}
+
+#[test]
+fn test_cipher_mismatch_refused() {
+    let dir = TempDir::new().unwrap();
+    let a = dir.path().join("a.db");
+    let b = dir.path().join("b.db");
+    crash_with_committed_wal(Database::create(&a, &test_key()).unwrap());
+    drop(Database::create_plaintext(&b).unwrap());
+    std::fs::copy(wal_of(&a), wal_of(&b)).unwrap();
+    // Give the WAL the plaintext database's instance id so that only the
+    // cipher differs.
+    let salt = Database::read_encryption_info(&b).unwrap().salt;
+    patch_wal(&b, 12..28, &salt);
+
+    expect_mismatch(Database::open_plaintext(&b), "cipher");
+    expect_mismatch(
+        Database::open_plaintext_with_recovery_mode(&b, RecoveryMode::Permissive),
+        "cipher",
+    );
+}
+
+#[test]
+fn test_key_check_mismatch_refused() {
+    let dir = TempDir::new().unwrap();
+    let db_path = dir.path().join("a.db");
+    crash_with_committed_wal(Database::create(&db_path, &test_key()).unwrap());
+    // The WAL claims another key, while the data file accepts this one.
+    patch_wal(&db_path, 36..44, &[0xEE; 8]);
+
+    expect_mismatch(Database::open(&db_path, &test_key()), "key_check");
+    expect_mismatch(
+        Database::open_with_recovery_mode(&db_path, &test_key(), RecoveryMode::Permissive),
+        "key_check",
+    );
+}
+
+#[test]
+fn test_wrong_key_reported_as_decryption_failure() {
+    let dir = TempDir::new().unwrap();
+    let db_path = dir.path().join("a.db");
+    crash_with_committed_wal(Database::create(&db_path, &test_key()).unwrap());
+
+    let wrong = MasterKey::new([0x43u8; 32]);
+    match Database::open(&db_path, &wrong) {
+        Err(MuroError::WalMismatch { .. }) => panic!("wrong key reported as WAL mismatch"),
+        Err(_) => {}
+        Ok(_) => panic!("open with a wrong key succeeded"),
+    }
+    let mut db = Database::open(&db_path, &test_key()).unwrap();
+    assert_eq!(count(&mut db), 1);
+}
+
+#[test]
+fn test_version_1_wal_is_replayed_unverified() {
+    let dir = TempDir::new().unwrap();
+    let db_path = dir.path().join("a.db");
+    crash_with_committed_wal(Database::create(&db_path, &test_key()).unwrap());
+
+    // Rewrite the WAL as version 1: the short header, then the same frames
+    // without the frame CRC version 3 added.
+    let wal = std::fs::read(wal_of(&db_path)).unwrap();
+    let mut v1 = Vec::new();
+    v1.extend_from_slice(b"MUROWAL1");
+    v1.extend_from_slice(&1u32.to_le_bytes());
+    let mut frames = &wal[murodb::wal::WAL_HEADER_SIZE..];
+    while !frames.is_empty() {
+        let len = u32::from_le_bytes(frames[..4].try_into().unwrap()) as usize;
+        v1.extend_from_slice(&(len as u32 - 4).to_le_bytes());
+        v1.extend_from_slice(&frames[4..len]);
+        frames = &frames[4 + len..];
+    }
+    std::fs::write(wal_of(&db_path), v1).unwrap();
+
+    let mut db = Database::open(&db_path, &test_key()).unwrap();
+    assert_eq!(count(&mut db), 1);
+    drop(db);
+    // The WAL is recreated with the current header.
+    let wal = std::fs::read(wal_of(&db_path)).unwrap();
+    assert_eq!(&wal[8..12], &murodb::wal::WAL_VERSION.to_le_bytes());
+}
+
+#[test]
+fn test_empty_foreign_wal_is_ignored() {
+    let dir = TempDir::new().unwrap();
+    let a = dir.path().join("a.db");
+    let b = dir.path().join("b.db");
+    drop(Database::create(&a, &test_key()).unwrap());
+    let mut db = Database::create(&b, &test_key()).unwrap();
+    db.execute("CREATE TABLE t (id BIGINT PRIMARY KEY)")
+        .unwrap();
+    drop(db);
+    // A header-only WAL has nothing to replay.
+    std::fs::copy(wal_of(&a), wal_of(&b)).unwrap();
+
+    let mut db = Database::open(&b, &test_key()).unwrap();
+    assert_eq!(count(&mut db), 0);
+}
@@ -0,0 +1,311 @@
+#![cfg(feature = "test-utils")]
+/// Salvage recovery reads past damage that hides where the next WAL frame
+/// starts: transactions entirely before and entirely after the damaged bytes
+/// are replayed, and the ones they touch are reported as abandoned.
+use murodb::crypto::aead::MasterKey;
+use murodb::crypto::suite::EncryptionSuite;
+use murodb::storage::page::Page;
+use murodb::storage::pager::Pager;
+use murodb::wal::reader::WalTail;
+use murodb::wal::record::{TxId, WalRecord};
+use murodb::wal::recovery::{recover_with_mode_and_suite, RecoveryMode, RecoveryResult};
+use murodb::wal::writer::WalWriter;
+use murodb::Database;
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+use std::ops::Range;
+use std::path::{Path, PathBuf};
+use tempfile::TempDir;
+
+const TXS: u64 = 8;
+
+fn test_key() -> MasterKey {
+    MasterKey::new([0x42u8; 32])
+}
+
+fn master_key(suite: EncryptionSuite) -> Option<MasterKey> {
+    suite.requires_master_key().then(test_key)
+}
+
+/// A database and a WAL of committed transactions; transaction `n` writes
+/// page `n - 1`, and even ones also write a page past the others. Returns
+/// the byte range of every frame of every transaction, by txid - 1.
+fn setup(dir: &Path, suite: EncryptionSuite) -> (PathBuf, PathBuf, Vec<Vec<Range<u64>>>) {
+    let db_path = dir.join("test.db");
+    let wal_path = dir.join("test.db.wal");
+    let mut writer = match suite {
+        EncryptionSuite::Plaintext => {
+            drop(Pager::create_plaintext(&db_path).unwrap());
+            WalWriter::create_plaintext(&wal_path).unwrap()
+        }
+        EncryptionSuite::Aes256GcmSiv => {
+            drop(Pager::create(&db_path, &test_key()).unwrap());
+            WalWriter::create(&wal_path, &test_key()).unwrap()
+        }
+    };
+    let mut txs = Vec::new();
+    for txid in 1..=TXS {
+        let mut records = vec![WalRecord::Begin { txid }];
+        let mut pages = vec![txid - 1];
+        if txid % 2 == 0 {
+            pages.push(TXS + txid);
+        }
+        for page_id in pages {
+            let mut page = Page::new(page_id);
+            page.insert_cell(format!("tx{}", txid).as_bytes()).unwrap();
+            records.push(WalRecord::PagePut {
+                txid,
+                page_id,
+                data: page.data.to_vec(),
+            });
+        }
+        records.push(WalRecord::MetaUpdate {
+            txid,
+            catalog_root: 0,
+            page_count: 2 * TXS + 1,
+            freelist_page_id: 0,
+            epoch: 0,
+        });
+        let mut frames = Vec::new();
+        for record in records {
+            let start = writer.file_size_bytes().unwrap();
+            writer.append(&record).unwrap();
+            frames.push(start..writer.file_size_bytes().unwrap());
+        }
+        let start = writer.file_size_bytes().unwrap();
+        let lsn = writer.current_lsn();
+        writer.append(&WalRecord::Commit { txid, lsn }).unwrap();
+        frames.push(start..writer.file_size_bytes().unwrap());
+        txs.push(frames);
+    }
+    writer.sync().unwrap();
+    (db_path, wal_path, txs)
+}
+
+fn recover(
+    db_path: &Path,
+    wal_path: &Path,
+    suite: EncryptionSuite,
+    mode: RecoveryMode,
+) -> RecoveryResult {
+    let key = master_key(suite);
+    recover_with_mode_and_suite(db_path, wal_path, suite, key.as_ref(), mode).unwrap()
+}
+
+fn edit_wal(wal_path: &Path, edit: impl FnOnce(&mut Vec<u8>)) {
+    let mut bytes = std::fs::read(wal_path).unwrap();
+    edit(&mut bytes);
+    std::fs::write(wal_path, bytes).unwrap();
+}
+
+fn page_cell(db_path: &Path, suite: EncryptionSuite, page_id: u64) -> Option<Vec<u8>> {
+    let key = master_key(suite);
+    let mut pager = Pager::open_with_suite(db_path, Some(suite), key.as_ref()).unwrap();
+    if page_id >= pager.page_count() {
+        return None;
+    }
+    let page = pager.read_page(page_id).ok()?;
+    page.cell(0).map(|c| c.to_vec())
+}
+
+/// The bytes of all frames of a transaction.
+fn span(frames: &[Range<u64>]) -> Range<u64> {
+    frames[0].start..frames[frames.len() - 1].end
+}
+
+fn overlaps(a: &Range<u64>, b: &Range<u64>) -> bool {
+    a.start < b.end && b.start < a.end
+}
+
+/// Txids whose frames overlap `damage`, and all the others.
+fn split_by_damage(txs: &[Vec<Range<u64>>], damage: &Range<u64>) -> (Vec<TxId>, Vec<TxId>) {
+    (1..=TXS).partition(|txid| overlaps(&span(&txs[*txid as usize - 1]), damage))
+}
+
+/// Txids with a frame that `damage` overlaps and one it leaves intact.
+fn partly_damaged(txs: &[Vec<Range<u64>>], damage: &Range<u64>) -> Vec<TxId> {
+    (1..=TXS)
+        .filter(|txid| {
+            let frames = &txs[*txid as usize - 1];
+            frames.iter().any(|f| overlaps(f, damage))
+                && frames.iter().any(|f| !overlaps(f, damage))
+        })
+        .collect()
+}
+
+fn assert_salvaged(
+    db_path: &Path,
+    suite: EncryptionSuite,
+    report: &RecoveryResult,
+    damaged: &[TxId],
+    intact: &[TxId],
+    context: &str,
+) {
+    assert_eq!(report.committed_txids, intact, "{}", context);
+    // A transaction with no frame left to read cannot be named.
+    for txid in &report.abandoned_txids {
+        assert!(damaged.contains(txid), "{}: txid {}", context, txid);
+    }
+    assert!(
+        report.skipped.is_empty(),
+        "{}: {:?}",
+        context,
+        report.skipped
+    );
+    assert!(report.lost_committed_data(), "{}", context);
+    for txid in 1..=TXS {
+        let cell = page_cell(db_path, suite, txid - 1);
+        let expected = format!("tx{}", txid).into_bytes();
+        if intact.contains(&txid) {
+            assert_eq!(cell, Some(expected), "{}: txid {}", context, txid);
+        } else {
+            assert_ne!(cell, Some(expected), "{}: txid {}", context, txid);
+        }
+    }
+    // No page of an abandoned transaction is written, its second one
+    // included.
+    for txid in damaged.iter().filter(|txid| *txid % 2 == 0) {
+        assert_eq!(page_cell(db_path, suite, TXS + txid), None, "{}", context);
+    }
+}
+
+#[test]
+fn test_salvage_reads_past_a_corrupted_frame_length() {
+    let dir = TempDir::new().unwrap();
+    let suite = EncryptionSuite::Aes256GcmSiv;
+    let (db_path, wal_path, txs) = setup(dir.path(), suite);
+    // An oversized length in the Begin frame of transaction 3 hides where
+    // every later frame starts.
+    let at = txs[2][0].start as usize;
+    edit_wal(&wal_path, |bytes| {
+        bytes[at..at + 4].copy_from_slice(&u32::MAX.to_le_bytes())
+    });
+
+    // Permissive recovery takes the length for a write cut short and drops
+    // the rest of the log.
+    let report = recover(&db_path, &wal_path, suite, RecoveryMode::Permissive);
+    assert_eq!(report.committed_txids, vec![1, 2]);
+    assert_eq!(report.wal_tail, WalTail::TornWrite);
+
+    let report = recover(&db_path, &wal_path, suite, RecoveryMode::Salvage);
+    assert_salvaged(
+        &db_path,
+        suite,
+        &report,
+        &[3],
+        &[1, 2, 4, 5, 6, 7, 8],
+        "length",
+    );
+    assert_eq!(report.abandoned_txids, vec![3]);
+    assert_eq!(report.incomplete_txids, vec![3]);
+    assert_eq!(report.first_corruption_offset, Some(txs[2][0].start));
+    assert_eq!(report.wal_tail, WalTail::Clean);
+    assert_eq!(report.unreadable_frames.len(), 1);
+}
+
+#[test]
+fn test_salvage_drops_a_transaction_whose_commit_is_lost() {
+    let dir = TempDir::new().unwrap();
+    let suite = EncryptionSuite::Aes256GcmSiv;
+    let (db_path, wal_path, txs) = setup(dir.path(), suite);
+    // Zero the end of transaction 4, its Commit included, and the start of
+    // transaction 5.
+    let damage = span(&txs[3]).end - 200..txs[4][0].start + 10;
+    edit_wal(&wal_path, |bytes| {
+        bytes[damage.start as usize..damage.end as usize].fill(0)
+    });
+
+    let report = recover(&db_path, &wal_path, suite, RecoveryMode::Salvage);
+    assert_salvaged(
+        &db_path,
+        suite,
+        &report,
+        &[4, 5],
+        &[1, 2, 3, 6, 7, 8],
+        "commit",
+    );
+    // Transaction 4 never reached a readable Commit, so it is abandoned
+    // without being incomplete.
+    assert_eq!(report.abandoned_txids, vec![4, 5]);
+    assert_eq!(report.incomplete_txids, vec![5]);
+}
+
+#[test]
+fn test_salvage_open_quarantines_the_wal() {
+    let dir = TempDir::new().unwrap();
+    let suite = EncryptionSuite::Aes256GcmSiv;
+    let (db_path, wal_path, txs) = setup(dir.path(), suite);
+    let at = txs[1][0].start as usize;
+    edit_wal(&wal_path, |bytes| bytes[at..at + 4].fill(0));
+
+    let (_db, report) =
+        Database::open_with_recovery_mode_and_report(&db_path, &test_key(), RecoveryMode::Salvage)
+            .unwrap();
+    let report = report.expect("expected recovery report");
+    assert_eq!(report.committed_txids, vec![1, 3, 4, 5, 6, 7, 8]);
+    assert_eq!(report.abandoned_txids, vec![2]);
+    let quarantine = PathBuf::from(report.wal_quarantine_path.expect("WAL quarantined"));
+    assert!(quarantine.exists());
+}
+
+/// Overwrite a random region of the log between the first transaction and
+/// the last one with random bytes, or zeros, and salvage.
+fn fuzz_salvage(suite: EncryptionSuite, seed: u64, iterations: usize) {
+    let mut rng = StdRng::seed_from_u64(seed);
+    for iteration in 0..iterations {
+        let dir = TempDir::new().unwrap();
+        let (db_path, wal_path, txs) = setup(dir.path(), suite);
+
+        let lo = span(&txs[0]).end;
+        let hi = txs[TXS as usize - 1][0].start;
+        let start = rng.gen_range(lo..hi);
+        let len = rng.gen_range(1..=(hi - start).min(12_000));
+        let damage = start..start + len;
+        let zeros = rng.gen_bool(0.25);
+        let (damaged, intact) = split_by_damage(&txs, &damage);
+        edit_wal(&wal_path, |bytes| {
+            for b in &mut bytes[damage.start as usize..damage.end as usize] {
+                let new = if zeros { 0 } else { rng.gen() };
+                // Make sure every byte in the region changes.
+                *b = if new == *b { !new } else { new };
+            }
+        });
+
+        let report = recover(&db_path, &wal_path, suite, RecoveryMode::Salvage);
+        let context = format!(
+            "{} seed {} iteration {}: damage {:?} ({})",
+            suite.as_str(),
+            seed,
+            iteration,
+            damage,
+            if zeros { "zeros" } else { "random" }
+        );
+        assert_salvaged(&db_path, suite, &report, &damaged, &intact, &context);
+        assert_eq!(
+            report.abandoned_txids,
+            partly_damaged(&txs, &damage),
+            "{}",
+            context
+        );
+        assert_eq!(report.wal_tail, WalTail::Clean, "{}", context);
+        let first_damaged = txs[damaged[0] as usize - 1][0].start;
+        assert!(
+            report
+                .first_corruption_offset
+                .is_some_and(|o| (first_damaged..=damage.start).contains(&o)),
+            "{}: {:?}",
+            context,
+            report.first_corruption_offset
+        );
+    }
+}
+
+#[test]
+fn test_fuzz_salvage_encrypted() {
+    fuzz_salvage(EncryptionSuite::Aes256GcmSiv, 0x5A1_7A6E, 40);
+}
+
+#[test]
+fn test_fuzz_salvage_plaintext() {
+    fuzz_salvage(EncryptionSuite::Plaintext, 0x9_1A1E, 40);
+}
@@ -0,0 +1,116 @@
+#![cfg(feature = "test-utils")]
+/// Opening a database recovers and recreates its WAL under the exclusive
+/// lock, so an open in one process never truncates or appends to the WAL in
+/// the middle of another process's commit. One worker commits row after row
+/// without checkpointing while another keeps opening the same database; the
+/// WAL left behind must read back in strict mode with no frame out of place.
+///
+/// The test re-runs its own binary as the worker processes; the worker test
+/// is a no-op unless the worker environment variable is set.
+use murodb::crypto::aead::MasterKey;
+use murodb::wal::recovery::inspect_wal;
+use murodb::{Database, RecoveryMode, Value};
+use std::path::Path;
+use std::process::{Command, Stdio};
+use std::time::{Duration, Instant};
+use tempfile::TempDir;
+
+const WORKER_ENV: &str = "MURODB_WAL_SINGLE_WRITER_WORKER";
+const ROUNDS: i64 = 200;
+
+fn test_key() -> MasterKey {
+    MasterKey::new([0x42u8; 32])
+}
+
+fn wait_for(path: &Path) {
+    let deadline = Instant::now() + Duration::from_secs(30);
+    while !path.exists() {
+        assert!(Instant::now() < deadline, "start signal never came");
+        std::thread::sleep(Duration::from_millis(1));
+    }
+}
+
+#[test]
+fn wal_single_writer_worker() {
+    let Ok(spec) = std::env::var(WORKER_ENV) else {
+        return;
+    };
+    let (dir, role) = spec.rsplit_once('|').unwrap();
+    let dir = Path::new(dir);
+    let path = dir.join("race.db");
+
+    match role {
+        "writer" => {
+            let mut db = Database::open(&path, &test_key()).unwrap();
+            // Keep every commit in the WAL for the opener to recover.
+            db.execute("SET checkpoint_tx_threshold = 1000000").unwrap();
+            wait_for(&dir.join("go"));
+            for i in 0..ROUNDS {
+                db.execute(&format!("INSERT INTO t VALUES ({}, 'row {}')", i, i))
+                    .unwrap();
+            }
+            let rows = db.query("SELECT COUNT(*) FROM t").unwrap();
+            assert_eq!(rows[0].get_at(0), Some(&Value::Integer(ROUNDS)));
+            std::fs::write(dir.join("writer-done"), b"").unwrap();
+        }
+        "opener" => {
+            wait_for(&dir.join("go"));
+            let mut opens = 0;
+            while !dir.join("writer-done").exists() || opens == 0 {
+                let (db, report) = Database::open_with_recovery_mode_and_report(
+                    &path,
+                    &test_key(),
+                    RecoveryMode::Strict,
+                )
+                .unwrap();
+                if let Some(report) = report {
+                    assert!(report.skipped.is_empty(), "{:?}", report.skipped);
+                    assert!(report.wal_quarantine_path.is_none());
+                }
+                drop(db);
+                opens += 1;
+            }
+        }
+        other => panic!("unknown worker role {}", other),
+    }
+}
+
+#[test]
+fn test_open_races_open_plus_write_without_interleaving_wal_frames() {
+    let dir = TempDir::new().unwrap();
+    let path = dir.path().join("race.db");
+    let mut db = Database::create(&path, &test_key()).unwrap();
+    db.execute("CREATE TABLE t (id BIGINT PRIMARY KEY, v VARCHAR(50))")
+        .unwrap();
+    drop(db);
+
+    let exe = std::env::current_exe().unwrap();
+    let children: Vec<_> = ["writer", "opener"]
+        .iter()
+        .map(|role| {
+            Command::new(&exe)
+                .args(["wal_single_writer_worker", "--exact", "--test-threads=1"])
+                .env(WORKER_ENV, format!("{}|{}", dir.path().display(), role))
+                .stdout(Stdio::null())
+                .spawn()
+                .unwrap()
+        })
+        .collect();
+    std::fs::write(dir.path().join("go"), b"").unwrap();
+    for mut child in children {
+        let status = child.wait().unwrap();
+        assert!(status.success(), "worker failed: {}", status);
+    }
+
+    // The writer's last commits are still in the WAL, one after another.
+    let mut wal_path = path.as_os_str().to_os_string();
+    wal_path.push(".wal");
+    let inspected = inspect_wal(Path::new(&wal_path), &test_key(), RecoveryMode::Strict).unwrap();
+    assert!(inspected.skipped.is_empty(), "{:?}", inspected.skipped);
+    assert!(inspected.aborted_txids.is_empty());
+
+    let mut db = Database::open(&path, &test_key()).unwrap();
+    let rows = db.query("SELECT id FROM t ORDER BY id").unwrap();
+    let ids: Vec<Value> = rows.iter().map(|r| r.values[0].1.clone()).collect();
+    assert_eq!(ids, (0..ROUNDS).map(Value::Integer).collect::<Vec<_>>());
+}
@@ -0,0 +1,243 @@
+#![cfg(feature = "test-utils")]
+/// Commits stream their WAL frames through a bounded write buffer. A crash
+/// anywhere in the streamed write, including mid-frame and just before the
+/// Commit record, leaves a commit-less frame prefix that recovery discards.
+use murodb::crypto::aead::MasterKey;
+use murodb::fault::{FaultFile, FaultInjector, FaultKind, FaultPoint, FaultyWalWriter};
+use murodb::storage::page::{Page, PAGE_SIZE};
+use murodb::storage::pager::Pager;
+use murodb::tx::transaction::Transaction;
+use murodb::wal::recovery::recover;
+use murodb::wal::writer::WalWriter;
+use murodb::wal::WAL_HEADER_SIZE;
+use murodb::{Database, OpenOptions, Value};
+use std::path::{Path, PathBuf};
+use tempfile::TempDir;
+
+const PAGES: usize = 2000;
+const BUFFER_LIMIT: usize = 16 * 1024;
+
+fn test_key() -> MasterKey {
+    MasterKey::new([0x42u8; 32])
+}
+
+/// A data file with one committed page (page 0) and an empty WAL.
+fn setup_prior(dir: &Path) -> (PathBuf, PathBuf, u64) {
+    let db_path = dir.join("test.db");
+    let wal_path = dir.join("test.db.wal");
+    let mut pager = Pager::create(&db_path, &test_key()).unwrap();
+    let mut wal = WalWriter::create(&wal_path, &test_key()).unwrap();
+    let mut tx = Transaction::begin(1, 0);
+    let mut page = tx.allocate_page(&mut pager).unwrap();
+    page.insert_cell(b"prior_data").unwrap();
+    tx.write_page(page);
+    tx.commit(&mut pager, &mut wal, 0).unwrap();
+    wal.checkpoint_truncate().unwrap();
+    (db_path, wal_path, pager.page_count())
+}
+
+/// Commit `PAGES` new pages plus a rewrite of page 0 as txid 2.
+fn large_commit(pager: &mut Pager, wal: &mut WalWriter) -> murodb::error::Result<u64> {
+    let mut tx = Transaction::begin(2, 0);
+    let mut overwritten = Page::new(0);
+    overwritten.insert_cell(b"overwritten").unwrap();
+    tx.write_page(overwritten);
+    for i in 0..PAGES {
+        let mut page = tx.allocate_page(pager).unwrap();
+        page.insert_cell(format!("big-{}", i).as_bytes()).unwrap();
+        tx.write_page(page);
+    }
+    tx.commit(pager, wal, 0)
+}
+
+#[test]
+fn test_large_commit_buffers_at_most_the_limit() {
+    let dir = TempDir::new().unwrap();
+    let (db_path, wal_path, prior_page_count) = setup_prior(dir.path());
+    {
+        let mut pager = Pager::open(&db_path, &test_key()).unwrap();
+        let mut wal = WalWriter::open(&wal_path, &test_key(), 0).unwrap();
+        wal.set_write_buffer_limit(BUFFER_LIMIT);
+        large_commit(&mut pager, &mut wal).unwrap();
+
+        let high_water = wal.write_buffer_high_water();
+        assert!(high_water >= BUFFER_LIMIT, "{}", high_water);
+        assert!(high_water < BUFFER_LIMIT + 2 * PAGE_SIZE, "{}", high_water);
+        assert!(wal.file_size_bytes().unwrap() > (PAGES * PAGE_SIZE) as u64);
+    }
+
+    // Replaying the WAL yields the same pages the commit wrote.
+    let rr = recover(&db_path, &wal_path, &test_key()).unwrap();
+    assert_eq!(rr.committed_txids, vec![2]);
+    let mut pager = Pager::open(&db_path, &test_key()).unwrap();
+    assert_eq!(
+        pager.read_page(0).unwrap().cell(0),
+        Some(b"overwritten".as_slice())
+    );
+    let last = pager
+        .read_page(prior_page_count + PAGES as u64 - 1)
+        .unwrap();
+    assert_eq!(last.cell(0), Some(format!("big-{}", PAGES - 1).as_bytes()));
+}
+
+#[test]
+fn test_crash_during_streamed_commit_is_discarded_by_recovery() {
+    // Size of the complete commit's WAL, and the writes it took.
+    let (full_len, wal_writes) = {
+        let dir = TempDir::new().unwrap();
+        let (db_path, wal_path, _) = setup_prior(dir.path());
+        let mut pager = Pager::open(&db_path, &test_key()).unwrap();
+        let injector = FaultInjector::counting();
+        let mut wal = FaultyWalWriter::new(
+            WalWriter::open(&wal_path, &test_key(), 0).unwrap(),
+            &injector,
+        );
+        wal.set_write_buffer_limit(BUFFER_LIMIT);
+        large_commit(&mut pager, &mut wal).unwrap();
+        (wal.file_size_bytes().unwrap(), injector.counts().wal_writes)
+    };
+    assert!(wal_writes > 4, "commit was not streamed: {}", wal_writes);
+
+    // The first writes, the page frames, and the last write, which carries
+    // the Commit frame; each lost whole or torn mid-frame.
+    let writes = [1, 2, wal_writes / 2, wal_writes - 1, wal_writes];
+    for write in writes {
+        for kind in [FaultKind::Fail, FaultKind::TornWrite] {
+            let point = FaultPoint {
+                file: FaultFile::Wal,
+                write,
+                kind,
+            };
+            let dir = TempDir::new().unwrap();
+            let (db_path, wal_path, prior_page_count) = setup_prior(dir.path());
+            {
+                let mut pager = Pager::open(&db_path, &test_key()).unwrap();
+                let injector = FaultInjector::at(point);
+                let mut wal = FaultyWalWriter::new(
+                    WalWriter::open(&wal_path, &test_key(), 0).unwrap(),
+                    &injector,
+                );
+                wal.set_write_buffer_limit(BUFFER_LIMIT);
+                assert!(large_commit(&mut pager, &mut wal).is_err(), "{:?}", point);
+                assert!(injector.has_crashed(), "{:?}", point);
+            }
+            // Frames before the crash point did reach the file.
+            let len = std::fs::metadata(&wal_path).unwrap().len();
+            assert!(len >= WAL_HEADER_SIZE as u64, "{:?}", point);
+            assert!(len < full_len, "{:?}", point);
+
+            let rr = recover(&db_path, &wal_path, &test_key())
+                .unwrap_or_else(|e| panic!("{:?}: {}", point, e));
+            assert!(rr.committed_txids.is_empty(), "{:?}", point);
+            assert_eq!(rr.pages_replayed, 0, "{:?}", point);
+
+            let mut pager = Pager::open(&db_path, &test_key()).unwrap();
+            assert_eq!(pager.page_count(), prior_page_count, "{:?}", point);
+            assert_eq!(
+                pager.read_page(0).unwrap().cell(0),
+                Some(b"prior_data".as_slice()),
+                "{:?}",
+                point
+            );
+        }
+    }
+}
+
+#[test]
+fn test_wal_write_buffer_option() {
+    let dir = TempDir::new().unwrap();
+    let path = dir.path().join("test.db");
+    drop(Database::create(&path, &test_key()).unwrap());
+
+    let mut db = Database::open_with_options(
+        &path,
+        &test_key(),
+        OpenOptions {
+            wal_write_buffer_bytes: 0,
+            ..OpenOptions::default()
+        },
+    )
+    .unwrap();
+    assert_eq!(db.wal_write_buffer_bytes(), 0);
+    db.execute("CREATE TABLE t (id BIGINT PRIMARY KEY, body VARCHAR(2000))")
+        .unwrap();
+    db.execute("BEGIN").unwrap();
+    for i in 0..300 {
+        db.execute(&format!(
+            "INSERT INTO t VALUES ({}, '{}')",
+            i,
+            "x".repeat(1500)
+        ))
+        .unwrap();
+    }
+    db.execute("COMMIT").unwrap();
+    db.set_wal_write_buffer_bytes(4096);
+    assert_eq!(db.wal_write_buffer_bytes(), 4096);
+    db.execute("DELETE FROM t WHERE id >= 100").unwrap();
+    drop(db);
+
+    let mut db = Database::open(&path, &test_key()).unwrap();
+    let rows = db.query("SELECT COUNT(*) FROM t").unwrap();
+    assert_eq!(rows[0].get_at(0), Some(&Value::Integer(100)));
+}
+
+#[test]
+fn test_multi_page_commit_takes_one_wal_write() {
+    let dir = TempDir::new().unwrap();
+    let mut db = Database::create(&dir.path().join("test.db"), &test_key()).unwrap();
+    db.execute("CREATE TABLE t (id BIGINT PRIMARY KEY, body VARCHAR(4000))")
+        .unwrap();
+    db.execute("BEGIN").unwrap();
+    for i in 0..40 {
+        db.execute(&format!(
+            "INSERT INTO t VALUES ({}, '{}')",
+            i,
+            "a".repeat(3000)
+        ))
+        .unwrap();
+    }
+    db.execute("COMMIT").unwrap();
+    // Rewrites every row, so at least 40 pages.
+    let rewrite = |db: &mut Database, fill: &str| {
+        db.execute("BEGIN").unwrap();
+        for i in 0..40 {
+            db.execute(&format!(
+                "UPDATE t SET body = '{}' WHERE id = {}",
+                fill.repeat(3000),
+                i
+            ))
+            .unwrap();
+        }
+        db.execute("COMMIT").unwrap();
+    };
+
+    let before = db.database_stats().clone();
+    rewrite(&mut db, "b");
+    let stats = db.database_stats();
+    assert_eq!(stats.last_commit_wal_write_calls, 1);
+    assert_eq!(stats.wal_commits, before.wal_commits + 1);
+    assert_eq!(
+        stats.wal_commit_write_calls,
+        before.wal_commit_write_calls + 1
+    );
+
+    // Without a write buffer every frame is its own write.
+    db.set_wal_write_buffer_bytes(0);
+    rewrite(&mut db, "c");
+    let last = db
+        .query("SHOW DATABASE STATS")
+        .unwrap()
+        .into_iter()
+        .find(|r| r.get("stat") == Some(&Value::Varchar("last_commit_wal_write_calls".into())))
+        .and_then(|r| match r.get("value") {
+            Some(Value::Varchar(v)) => v.parse::<u64>().ok(),
+            _ => None,
+        })
+        .unwrap();
+    assert!(last > 40, "{}", last);
+
+    let rows = db
+        .query("SELECT COUNT(*) FROM t WHERE body LIKE 'c%'")
+        .unwrap();
+    assert_eq!(rows[0].get_at(0), Some(&Value::Integer(40)));
+}